@@ -19,15 +19,30 @@
 //! Stores ancient block headers, bodies, receipts, and total difficulties.
 //! Furthermore, stores a "gas price corpus" of relative recency, which is a sorted
 //! vector of all gas prices from a recent range of blocks.
+//!
+//! Contract code and account proofs are cached as well; they are immutable for a given
+//! code hash and state root respectively. The cache may be saved to and restored from a
+//! database so it survives restarts.
 
 use ethcore::encoded;
 use ethcore::header::BlockNumber;
 use ethcore::receipt::Receipt;
 
+use rlp::{RlpStream, UntrustedRlp, DecoderError};
 use stats::Corpus;
 use time::{SteadyTime, Duration};
-use util::{U256, H256};
+use util::{Address, Bytes, U256, H256};
 use util::cache::MemoryLruCache;
+use util::kvdb::{DBTransaction, KeyValueDB};
+
+// database keys of the saved cache sections.
+const HEADERS_KEY: &'static [u8] = &*b"cache_headers";
+const CANON_HASHES_KEY: &'static [u8] = &*b"cache_canon_hashes";
+const BODIES_KEY: &'static [u8] = &*b"cache_bodies";
+const RECEIPTS_KEY: &'static [u8] = &*b"cache_receipts";
+const CHAIN_SCORE_KEY: &'static [u8] = &*b"cache_chain_score";
+const CODE_KEY: &'static [u8] = &*b"cache_code";
+const ACCOUNT_PROOFS_KEY: &'static [u8] = &*b"cache_account_proofs";
 
 /// Configuration for how much data to cache.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -42,6 +57,10 @@ pub struct CacheSizes {
 	pub receipts: usize,
 	/// Maximum size, in bytes, of cached chain score for the block.
 	pub chain_score: usize,
+	/// Maximum size, in bytes, of cached contract code.
+	pub code: usize,
+	/// Maximum size, in bytes, of cached account proofs.
+	pub account_proofs: usize,
 }
 
 impl Default for CacheSizes {
@@ -53,6 +72,8 @@ impl Default for CacheSizes {
 			bodies: 20 * MB,
 			receipts: 10 * MB,
 			chain_score: 7 * MB,
+			code: 5 * MB,
+			account_proofs: 5 * MB,
 		}
 	}
 }
@@ -68,6 +89,8 @@ pub struct Cache {
 	bodies: MemoryLruCache<H256, encoded::Body>,
 	receipts: MemoryLruCache<H256, Vec<Receipt>>,
 	chain_score: MemoryLruCache<H256, U256>,
+	code: MemoryLruCache<H256, Bytes>,
+	account_proofs: MemoryLruCache<(H256, Address), Vec<Bytes>>,
 	corpus: Option<(Corpus<U256>, SteadyTime)>,
 	corpus_expiration: Duration,
 }
@@ -81,6 +104,8 @@ impl Cache {
 			bodies: MemoryLruCache::new(sizes.bodies),
 			receipts: MemoryLruCache::new(sizes.receipts),
 			chain_score: MemoryLruCache::new(sizes.chain_score),
+			code: MemoryLruCache::new(sizes.code),
+			account_proofs: MemoryLruCache::new(sizes.account_proofs),
 			corpus: None,
 			corpus_expiration: corpus_expiration,
 		}
@@ -111,6 +136,16 @@ impl Cache {
 		self.chain_score.get_mut(hash).map(|x| x.clone())
 	}
 
+	/// Query contract code by code hash.
+	pub fn code(&mut self, code_hash: &H256) -> Option<Bytes> {
+		self.code.get_mut(code_hash).map(|x| x.clone())
+	}
+
+	/// Query the proof of an account under the given state root.
+	pub fn account_proof(&mut self, state_root: &H256, address: &Address) -> Option<Vec<Bytes>> {
+		self.account_proofs.get_mut(&(*state_root, *address)).map(|x| x.clone())
+	}
+
 	/// Cache the given header.
 	pub fn insert_block_header(&mut self, hash: H256, hdr: encoded::Header) {
 		self.headers.insert(hash, hdr);
@@ -136,6 +171,16 @@ impl Cache {
 		self.chain_score.insert(hash, score);
 	}
 
+	/// Cache the given contract code. The code must match the hash.
+	pub fn insert_code(&mut self, code_hash: H256, code: Bytes) {
+		self.code.insert(code_hash, code);
+	}
+
+	/// Cache the given verified account proof.
+	pub fn insert_account_proof(&mut self, state_root: H256, address: Address, proof: Vec<Bytes>) {
+		self.account_proofs.insert((state_root, address), proof);
+	}
+
 	/// Get gas price corpus, if recent enough.
 	pub fn gas_price_corpus(&self) -> Option<Corpus<U256>> {
 		let now = SteadyTime::now();
@@ -153,6 +198,118 @@ impl Cache {
 	pub fn set_gas_price_corpus(&mut self, corpus: Corpus<U256>) {
 		self.corpus = Some((corpus, SteadyTime::now()))
 	}
+
+	/// Save the contents of the cache to the database, replacing any previously saved contents.
+	/// Entries are stored from least to most recently used, so restoring them keeps their order.
+	/// The gas price corpus is not saved.
+	pub fn save(&self, db: &KeyValueDB, col: Option<u32>) -> Result<(), String> {
+		let mut batch = DBTransaction::new();
+
+		save_section(&mut batch, col, HEADERS_KEY, &self.headers, |s, hash, hdr| {
+			s.begin_list(2).append(hash).append_raw(hdr.rlp().as_raw(), 1);
+		});
+		save_section(&mut batch, col, CANON_HASHES_KEY, &self.canon_hashes, |s, num, hash| {
+			s.begin_list(2).append(num).append(hash);
+		});
+		save_section(&mut batch, col, BODIES_KEY, &self.bodies, |s, hash, body| {
+			s.begin_list(2).append(hash).append_raw(body.rlp().as_raw(), 1);
+		});
+		save_section(&mut batch, col, RECEIPTS_KEY, &self.receipts, |s, hash, receipts| {
+			s.begin_list(2).append(hash).append_list(receipts);
+		});
+		save_section(&mut batch, col, CHAIN_SCORE_KEY, &self.chain_score, |s, hash, score| {
+			s.begin_list(2).append(hash).append(score);
+		});
+		save_section(&mut batch, col, CODE_KEY, &self.code, |s, hash, code| {
+			s.begin_list(2).append(hash).append(code);
+		});
+		save_section(&mut batch, col, ACCOUNT_PROOFS_KEY, &self.account_proofs, |s, &(ref root, ref address), proof| {
+			s.begin_list(3).append(root).append(address).append_list::<Bytes, _>(proof);
+		});
+
+		db.write(batch)
+	}
+
+	/// Restore cache contents previously saved to the database.
+	/// Sections which fail to decode are skipped.
+	pub fn restore(&mut self, db: &KeyValueDB, col: Option<u32>) -> Result<(), String> {
+		let headers = &mut self.headers;
+		load_section(db, col, HEADERS_KEY, |entry| {
+			headers.insert(entry.val_at(0)?, encoded::Header::new(entry.at(1)?.as_raw().to_vec()));
+			Ok(())
+		})?;
+
+		let canon_hashes = &mut self.canon_hashes;
+		load_section(db, col, CANON_HASHES_KEY, |entry| {
+			canon_hashes.insert(entry.val_at(0)?, entry.val_at(1)?);
+			Ok(())
+		})?;
+
+		let bodies = &mut self.bodies;
+		load_section(db, col, BODIES_KEY, |entry| {
+			bodies.insert(entry.val_at(0)?, encoded::Body::new(entry.at(1)?.as_raw().to_vec()));
+			Ok(())
+		})?;
+
+		let receipts = &mut self.receipts;
+		load_section(db, col, RECEIPTS_KEY, |entry| {
+			receipts.insert(entry.val_at(0)?, entry.list_at(1)?);
+			Ok(())
+		})?;
+
+		let chain_score = &mut self.chain_score;
+		load_section(db, col, CHAIN_SCORE_KEY, |entry| {
+			chain_score.insert(entry.val_at(0)?, entry.val_at(1)?);
+			Ok(())
+		})?;
+
+		let code = &mut self.code;
+		load_section(db, col, CODE_KEY, |entry| {
+			code.insert(entry.val_at(0)?, entry.val_at(1)?);
+			Ok(())
+		})?;
+
+		let account_proofs = &mut self.account_proofs;
+		load_section(db, col, ACCOUNT_PROOFS_KEY, |entry| {
+			account_proofs.insert((entry.val_at(0)?, entry.val_at(1)?), entry.list_at(2)?);
+			Ok(())
+		})?;
+
+		Ok(())
+	}
+}
+
+// write all entries of an LRU-cache under the given key.
+fn save_section<K, V, F>(batch: &mut DBTransaction, col: Option<u32>, key: &[u8], cache: &MemoryLruCache<K, V>, f: F)
+	where K: Eq + ::std::hash::Hash, V: ::util::HeapSizeOf, F: Fn(&mut RlpStream, &K, &V)
+{
+	let entries: Vec<_> = cache.iter().collect();
+	let mut stream = RlpStream::new_list(entries.len());
+	for (k, v) in entries {
+		f(&mut stream, k, v);
+	}
+
+	batch.put(col, key, &stream.out());
+}
+
+// feed all entries saved under the given key to a closure.
+fn load_section<F>(db: &KeyValueDB, col: Option<u32>, key: &[u8], mut f: F) -> Result<(), String>
+	where F: FnMut(UntrustedRlp) -> Result<(), DecoderError>
+{
+	let saved = match db.get(col, key)? {
+		Some(saved) => saved,
+		None => return Ok(()),
+	};
+
+	let rlp = UntrustedRlp::new(&saved);
+	let res = rlp.item_count().and_then(|count| {
+		(0..count).map(|i| rlp.at(i).and_then(&mut f)).collect::<Result<Vec<_>, _>>()
+	});
+	if let Err(e) = res {
+		warn!(target: "light_cache", "Skipping corrupted cache section {}: {}", String::from_utf8_lossy(key), e);
+	}
+
+	Ok(())
 }
 
 #[cfg(test)]
@@ -173,4 +330,40 @@ mod tests {
 		}
 		assert!(cache.gas_price_corpus().is_none());
 	}
+
+	#[test]
+	fn saves_and_restores() {
+		use util::kvdb::{in_memory, KeyValueDB};
+		use util::{Address, H256};
+
+		let db = in_memory(0);
+		let mut cache = Cache::new(Default::default(), Duration::hours(5));
+
+		cache.insert_block_hash(5, H256::from(1));
+		cache.insert_chain_score(H256::from(1), 100.into());
+		cache.insert_block_receipts(H256::from(1), Vec::new());
+		cache.insert_code(H256::from(2), vec![1, 2, 3]);
+		cache.insert_account_proof(H256::from(3), Address::from(4), vec![vec![5, 6]]);
+		cache.save(&db, None).unwrap();
+
+		let mut restored = Cache::new(Default::default(), Duration::hours(5));
+		restored.restore(&db, None).unwrap();
+
+		assert_eq!(restored.block_hash(&5), Some(H256::from(1)));
+		assert_eq!(restored.chain_score(&H256::from(1)), Some(100.into()));
+		assert_eq!(restored.block_receipts(&H256::from(1)), Some(Vec::new()));
+		assert_eq!(restored.code(&H256::from(2)), Some(vec![1, 2, 3]));
+		assert_eq!(restored.account_proof(&H256::from(3), &Address::from(4)), Some(vec![vec![5, 6]]));
+		assert!(restored.block_body(&H256::from(1)).is_none());
+
+		// corrupted sections are skipped.
+		let mut batch = db.transaction();
+		batch.put(None, super::CODE_KEY, &[0xff]);
+		db.write(batch).unwrap();
+
+		let mut restored = Cache::new(Default::default(), Duration::hours(5));
+		restored.restore(&db, None).unwrap();
+		assert_eq!(restored.block_hash(&5), Some(H256::from(1)));
+		assert!(restored.code(&H256::from(2)).is_none());
+	}
 }
@@ -34,6 +34,7 @@ use ethcore::error::BlockError;
 use ethcore::encoded;
 use ethcore::header::Header;
 use ethcore::ids::BlockId;
+use ethcore::spec::LightCheckpoint;
 
 use rlp::{Encodable, Decodable, DecoderError, RlpStream, Rlp, UntrustedRlp};
 use util::{H256, U256, HeapSizeOf, RwLock};
@@ -147,6 +148,18 @@ pub struct HeaderChain {
 impl HeaderChain {
 	/// Create a new header chain given this genesis block and database to read from.
 	pub fn new(db: Arc<KeyValueDB>, col: Option<u32>, genesis: &[u8], cache: Arc<Mutex<Cache>>) -> Result<Self, String> {
+		HeaderChain::with_checkpoint(db, col, genesis, cache, None)
+	}
+
+	/// Create a new header chain, starting from the given trusted checkpoint
+	/// instead of the genesis block if the database is empty.
+	pub fn with_checkpoint(
+		db: Arc<KeyValueDB>,
+		col: Option<u32>,
+		genesis: &[u8],
+		cache: Arc<Mutex<Cache>>,
+		checkpoint: Option<&LightCheckpoint>,
+	) -> Result<Self, String> {
 		use ethcore::views::HeaderView;
 
 		let chain = if let Some(current) = db.get(col, CURRENT_KEY)? {
@@ -194,7 +207,7 @@ impl HeaderChain {
 			}
 		} else {
 			let g_view = HeaderView::new(genesis);
-			HeaderChain {
+			let chain = HeaderChain {
 				genesis_header: encoded::Header::new(genesis.to_owned()),
 				best_block: RwLock::new(BlockDescriptor {
 					hash: g_view.hash(),
@@ -205,12 +218,60 @@ impl HeaderChain {
 				db: db,
 				col: col,
 				cache: cache,
+			};
+
+			if let Some(checkpoint) = checkpoint {
+				chain.import_checkpoint(checkpoint)?;
 			}
+
+			chain
 		};
 
 		Ok(chain)
 	}
 
+	// write the checkpoint's CHT roots and header to the database and make
+	// the header the best block. only valid for an empty chain.
+	fn import_checkpoint(&self, checkpoint: &LightCheckpoint) -> Result<(), String> {
+		let number = checkpoint.header.number();
+		let expected = checkpoint.chts.len() as u64 * cht::SIZE + 1;
+		if number != expected {
+			return Err(format!("Invalid light checkpoint: header #{} given for {} CHTs, expected #{}",
+				number, checkpoint.chts.len(), expected));
+		}
+
+		let hash = checkpoint.header.hash();
+		let mut entry = Entry { candidates: SmallVec::new(), canonical_hash: hash };
+		entry.candidates.push(Candidate {
+			hash: hash,
+			parent_hash: checkpoint.header.parent_hash(),
+			total_difficulty: checkpoint.total_difficulty,
+		});
+
+		let mut transaction = self.db.transaction();
+		for (i, root) in checkpoint.chts.iter().enumerate() {
+			transaction.put(self.col, cht_key(i as u64).as_bytes(), &::rlp::encode(root));
+		}
+		transaction.put(self.col, era_key(number).as_bytes(), &::rlp::encode(&entry));
+		transaction.put(self.col, &hash[..], &checkpoint.header.clone().into_inner());
+		{
+			let mut stream = RlpStream::new_list(2);
+			stream.append(&number).append(&number);
+			transaction.put(self.col, CURRENT_KEY, &stream.out());
+		}
+		self.db.write(transaction)?;
+
+		info!(target: "chain", "Starting light chain from checkpoint #{} ({}) with {} CHTs", number, hash, checkpoint.chts.len());
+		self.candidates.write().insert(number, entry);
+		*self.best_block.write() = BlockDescriptor {
+			hash: hash,
+			number: number,
+			total_difficulty: checkpoint.total_difficulty,
+		};
+
+		Ok(())
+	}
+
 	/// Insert a pre-verified header.
 	///
 	/// This blindly trusts that the data given to it is sensible.
@@ -549,11 +610,12 @@ mod tests {
 
 	use ethcore::ids::BlockId;
 	use ethcore::header::Header;
-	use ethcore::spec::Spec;
+	use ethcore::spec::{Spec, LightCheckpoint};
+	use ethcore::encoded;
   	use cache::Cache;
 
 	use time::Duration;
-	use util::Mutex;
+	use util::{H256, Mutex};
 
 	fn make_db() -> Arc<::util::KeyValueDB> {
 		Arc::new(::util::kvdb::in_memory(0))
@@ -781,4 +843,64 @@ mod tests {
 		assert_eq!(chain.block_header(BlockId::Latest).unwrap().number(), 10);
 		assert!(chain.candidates.read().get(&100).is_some())
 	}
+
+	fn make_checkpoint(number: u64, chts: usize, genesis_header: &Header) -> LightCheckpoint {
+		let mut header = Header::new();
+		header.set_parent_hash(H256::from(number));
+		header.set_number(number);
+		header.set_timestamp(genesis_header.timestamp() + number * 10);
+		header.set_difficulty(*genesis_header.difficulty());
+
+		LightCheckpoint {
+			header: encoded::Header::new(::rlp::encode(&header).to_vec()),
+			total_difficulty: *genesis_header.difficulty() * number.into(),
+			chts: (0..chts).map(|i| H256::from(i as u64 + 1)).collect(),
+		}
+	}
+
+	#[test]
+	fn starts_from_checkpoint() {
+		let spec = Spec::new_test();
+		let genesis_header = spec.genesis_header();
+		let db = make_db();
+		let cache = Arc::new(Mutex::new(Cache::new(Default::default(), Duration::hours(6))));
+
+		let checkpoint = make_checkpoint(2 * ::cht::SIZE + 1, 2, &genesis_header);
+		let chain = HeaderChain::with_checkpoint(db.clone(), None, &::rlp::encode(&genesis_header), cache.clone(), Some(&checkpoint)).unwrap();
+
+		assert_eq!(chain.best_block().number, 2 * ::cht::SIZE + 1);
+		assert_eq!(chain.best_block().total_difficulty, checkpoint.total_difficulty);
+		assert_eq!(chain.best_header(), checkpoint.header);
+		assert_eq!(chain.cht_root(0), Some(H256::from(1)));
+		assert_eq!(chain.cht_root(1), Some(H256::from(2)));
+		assert!(chain.cht_root(2).is_none());
+
+		// headers following the checkpoint import normally.
+		let mut header = Header::new();
+		header.set_parent_hash(checkpoint.header.hash());
+		header.set_number(checkpoint.header.number() + 1);
+		header.set_timestamp(checkpoint.header.timestamp() + 10);
+		header.set_difficulty(*genesis_header.difficulty());
+
+		let mut tx = db.transaction();
+		let pending = chain.insert(&mut tx, header.clone()).unwrap();
+		db.write(tx).unwrap();
+		chain.apply_pending(pending);
+		assert_eq!(chain.best_block().hash, header.hash());
+
+		// an existing database takes precedence over the checkpoint.
+		let other = make_checkpoint(::cht::SIZE + 1, 1, &genesis_header);
+		let chain = HeaderChain::with_checkpoint(db.clone(), None, &::rlp::encode(&genesis_header), cache, Some(&other)).unwrap();
+		assert_eq!(chain.best_block().hash, header.hash());
+	}
+
+	#[test]
+	fn rejects_misaligned_checkpoint() {
+		let spec = Spec::new_test();
+		let genesis_header = spec.genesis_header();
+		let cache = Arc::new(Mutex::new(Cache::new(Default::default(), Duration::hours(6))));
+
+		let checkpoint = make_checkpoint(2 * ::cht::SIZE, 2, &genesis_header);
+		assert!(HeaderChain::with_checkpoint(make_db(), None, &::rlp::encode(&genesis_header), cache, Some(&checkpoint)).is_err());
+	}
 }
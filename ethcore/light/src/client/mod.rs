@@ -26,7 +26,7 @@ use ethcore::ids::BlockId;
 use ethcore::header::Header;
 use ethcore::verification::queue::{self, HeaderQueue};
 use ethcore::blockchain_info::BlockChainInfo;
-use ethcore::spec::Spec;
+use ethcore::spec::{Spec, LightCheckpoint};
 use ethcore::service::ClientIoMessage;
 use ethcore::encoded;
 use io::IoChannel;
@@ -56,6 +56,10 @@ pub struct Config {
 	pub db_compaction: CompactionProfile,
 	/// Should db have WAL enabled?
 	pub db_wal: bool,
+	/// Trusted checkpoint to start syncing from when the database is empty.
+	pub checkpoint: Option<LightCheckpoint>,
+	/// Whether to save the data cache to the database and restore it on startup.
+	pub persist_cache: bool,
 }
 
 /// Trait for interacting with the header chain abstractly.
@@ -144,7 +148,7 @@ impl Client {
 		Ok(Client {
 			queue: HeaderQueue::new(config.queue, spec.engine.clone(), io_channel, true),
 			engine: spec.engine.clone(),
-			chain: HeaderChain::new(db.clone(), chain_col, &gh, cache)?,
+			chain: HeaderChain::with_checkpoint(db.clone(), chain_col, &gh, cache, config.checkpoint.as_ref())?,
 			report: RwLock::new(ClientReport::default()),
 			import_lock: Mutex::new(()),
 			db: db,
@@ -24,14 +24,18 @@ use std::sync::Arc;
 use ethcore::db;
 use ethcore::service::ClientIoMessage;
 use ethcore::spec::Spec;
-use io::{IoContext, IoError, IoHandler, IoService};
-use util::kvdb::{Database, DatabaseConfig};
+use io::{IoContext, IoError, IoHandler, IoService, TimerToken};
+use util::kvdb::{Database, DatabaseConfig, KeyValueDB};
 
 use cache::Cache;
 use util::Mutex;
 
 use super::{Client, Config as ClientConfig};
 
+const SAVE_CACHE_TIMER: TimerToken = 0;
+// save the data cache every 5 minutes so an unclean shutdown loses little.
+const SAVE_CACHE_MS: u64 = 5 * 60 * 1000;
+
 /// Errors on service initialization.
 #[derive(Debug)]
 pub enum Error {
@@ -54,6 +58,7 @@ impl fmt::Display for Error {
 pub struct Service {
 	client: Arc<Client>,
 	io_service: IoService<ClientIoMessage>,
+	save_cache: Option<Arc<SaveCache>>,
 }
 
 impl Service {
@@ -76,6 +81,20 @@ impl Service {
 			&path.to_str().expect("DB path could not be converted to string.")
 		).map_err(Error::Database)?);
 
+		let save_cache = match config.persist_cache {
+			true => {
+				if let Err(e) = cache.lock().restore(&*db, db::COL_LIGHT_CHAIN) {
+					warn!(target: "light_cache", "Failed to restore data cache: {}", e);
+				}
+
+				Some(Arc::new(SaveCache {
+					db: db.clone(),
+					cache: cache.clone(),
+				}))
+			}
+			false => None,
+		};
+
 		let io_service = IoService::<ClientIoMessage>::start().map_err(Error::Io)?;
 		let client = Arc::new(Client::new(config,
 			db,
@@ -85,9 +104,14 @@ impl Service {
 			cache,
 		).map_err(Error::Database)?);
 		io_service.register_handler(Arc::new(ImportBlocks(client.clone()))).map_err(Error::Io)?;
+		if let Some(ref save_cache) = save_cache {
+			io_service.register_handler(save_cache.clone()).map_err(Error::Io)?;
+		}
+
 		Ok(Service {
 			client: client,
 			io_service: io_service,
+			save_cache: save_cache,
 		})
 	}
 
@@ -102,6 +126,14 @@ impl Service {
 	}
 }
 
+impl Drop for Service {
+	fn drop(&mut self) {
+		if let Some(ref save_cache) = self.save_cache {
+			save_cache.save();
+		}
+	}
+}
+
 struct ImportBlocks(Arc<Client>);
 
 impl IoHandler<ClientIoMessage> for ImportBlocks {
@@ -112,6 +144,32 @@ impl IoHandler<ClientIoMessage> for ImportBlocks {
 	}
 }
 
+// periodically saves the data cache to the database.
+struct SaveCache {
+	db: Arc<KeyValueDB>,
+	cache: Arc<Mutex<Cache>>,
+}
+
+impl SaveCache {
+	fn save(&self) {
+		if let Err(e) = self.cache.lock().save(&*self.db, db::COL_LIGHT_CHAIN) {
+			warn!(target: "light_cache", "Failed to save data cache: {}", e);
+		}
+	}
+}
+
+impl IoHandler<ClientIoMessage> for SaveCache {
+	fn initialize(&self, io: &IoContext<ClientIoMessage>) {
+		io.register_timer(SAVE_CACHE_TIMER, SAVE_CACHE_MS).expect("Error registering cache timer");
+	}
+
+	fn timeout(&self, _io: &IoContext<ClientIoMessage>, timer: TimerToken) {
+		if timer == SAVE_CACHE_TIMER {
+			self.save();
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::Service;
@@ -79,6 +79,88 @@ impl<'a> IoContext for NetworkContext<'a> {
 	}
 }
 
+/// A transport carrying the protocol to peers outside of the devp2p network,
+/// e.g. over WebSockets. Ids of its peers must not clash with those of devp2p
+/// sessions.
+pub trait Transport: Send + Sync {
+	/// Whether the peer is connected through this transport.
+	fn has_peer(&self, peer: PeerId) -> bool;
+
+	/// Send a packet to a peer of this transport.
+	fn send(&self, peer: PeerId, packet_id: u8, packet_body: Vec<u8>);
+
+	/// Disconnect a peer of this transport.
+	fn disconnect_peer(&self, peer: PeerId);
+
+	/// Get the protocol version spoken with a peer of this transport.
+	fn protocol_version(&self, peer: PeerId) -> Option<u8>;
+
+	/// Persistent id of a peer of this transport.
+	fn persistent_peer_id(&self, peer: PeerId) -> Option<NodeId>;
+}
+
+/// I/O context routing packets of a transport's peers through that transport
+/// and packets of all other peers through the devp2p network context, if any.
+pub struct TransportIo<'a> {
+	/// Devp2p network context.
+	pub network: Option<&'a IoContext>,
+	/// The additional transport.
+	pub transport: &'a Transport,
+	/// Peer of the transport whose packet is being handled, if any.
+	pub sender: Option<PeerId>,
+}
+
+impl<'a> IoContext for TransportIo<'a> {
+	fn send(&self, peer: PeerId, packet_id: u8, packet_body: Vec<u8>) {
+		if self.transport.has_peer(peer) {
+			self.transport.send(peer, packet_id, packet_body);
+		} else if let Some(network) = self.network {
+			network.send(peer, packet_id, packet_body);
+		}
+	}
+
+	fn respond(&self, packet_id: u8, packet_body: Vec<u8>) {
+		match (self.sender, self.network) {
+			(Some(peer), _) => self.transport.send(peer, packet_id, packet_body),
+			(None, Some(network)) => network.respond(packet_id, packet_body),
+			(None, None) => debug!(target: "pip", "Attempted to respond outside of a packet handler"),
+		}
+	}
+
+	fn disconnect_peer(&self, peer: PeerId) {
+		if self.transport.has_peer(peer) {
+			self.transport.disconnect_peer(peer);
+		} else if let Some(network) = self.network {
+			network.disconnect_peer(peer);
+		}
+	}
+
+	fn disable_peer(&self, peer: PeerId) {
+		// transport peers have no reputation to time out, disconnecting is all we can do.
+		if self.transport.has_peer(peer) {
+			self.transport.disconnect_peer(peer);
+		} else if let Some(network) = self.network {
+			network.disable_peer(peer);
+		}
+	}
+
+	fn protocol_version(&self, peer: PeerId) -> Option<u8> {
+		if self.transport.has_peer(peer) {
+			self.transport.protocol_version(peer)
+		} else {
+			self.network.and_then(|network| network.protocol_version(peer))
+		}
+	}
+
+	fn persistent_peer_id(&self, peer: PeerId) -> Option<NodeId> {
+		if self.transport.has_peer(peer) {
+			self.transport.persistent_peer_id(peer)
+		} else {
+			self.network.and_then(|network| network.persistent_peer_id(peer))
+		}
+	}
+}
+
 /// Basic context for the protocol.
 pub trait BasicContext {
 	/// Returns the relevant's peer persistent Id (aka NodeId).
@@ -21,13 +21,13 @@
 use ethcore::transaction::UnverifiedTransaction;
 
 use io::TimerToken;
-use network::{NetworkProtocolHandler, NetworkContext, PeerId};
+use network::{NetworkProtocolHandler, NetworkContext, PeerId, NodeId};
 use rlp::{RlpStream, UntrustedRlp};
 use util::hash::H256;
 use util::{DBValue, Mutex, RwLock, U256};
 use time::{Duration, SteadyTime};
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -52,7 +52,7 @@ mod tests;
 pub mod request_credits;
 
 pub use self::error::Error;
-pub use self::context::{BasicContext, EventContext, IoContext};
+pub use self::context::{BasicContext, EventContext, IoContext, Transport, TransportIo};
 pub use self::status::{Status, Capabilities, Announcement};
 
 const TIMEOUT: TimerToken = 0;
@@ -138,6 +138,28 @@ pub struct Peer {
 	propagated_transactions: HashSet<H256>,
 }
 
+/// Peers a pending transaction has been relayed to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionRelay {
+	/// Best block number when the transaction was first relayed.
+	pub first_seen: u64,
+	/// Node ids of the peers the transaction was sent to, with the number of times
+	/// it was sent to each of them.
+	pub relayed_to: BTreeMap<NodeId, usize>,
+	// connected peers the transaction was sent to.
+	connected: HashSet<PeerId>,
+}
+
+impl TransactionRelay {
+	fn new(first_seen: u64) -> Self {
+		TransactionRelay {
+			first_seen: first_seen,
+			relayed_to: BTreeMap::new(),
+			connected: HashSet::new(),
+		}
+	}
+}
+
 /// A light protocol event handler.
 ///
 /// Each handler function takes a context which describes the relevant peer
@@ -179,6 +201,11 @@ pub struct Params {
 	pub flow_params: FlowParams,
 	/// Initial capabilities.
 	pub capabilities: Capabilities,
+	/// Maximum number of connected peers each pending transaction is relayed to.
+	/// `None` relays to all peers capable of it.
+	pub tx_relay_peers: Option<usize>,
+	/// Maximum number of peers connected at the same time. `None` for no limit.
+	pub max_peers: Option<usize>,
 }
 
 /// Type alias for convenience.
@@ -249,8 +276,12 @@ pub struct LightProtocol {
 	pending_peers: RwLock<HashMap<PeerId, PendingPeer>>,
 	peers: RwLock<PeerMap>,
 	capabilities: RwLock<Capabilities>,
+	relayed_transactions: RwLock<HashMap<H256, TransactionRelay>>,
 	flow_params: FlowParams, // assumed static and same for every peer.
+	tx_relay_peers: Option<usize>,
+	max_peers: Option<usize>,
 	handlers: Vec<Arc<Handler>>,
+	transport: Option<Arc<Transport>>,
 	req_id: AtomicUsize,
 }
 
@@ -267,8 +298,12 @@ impl LightProtocol {
 			pending_peers: RwLock::new(HashMap::new()),
 			peers: RwLock::new(HashMap::new()),
 			capabilities: RwLock::new(params.capabilities),
+			relayed_transactions: RwLock::new(HashMap::new()),
 			flow_params: params.flow_params,
+			tx_relay_peers: params.tx_relay_peers,
+			max_peers: params.max_peers,
 			handlers: Vec::new(),
+			transport: None,
 			req_id: AtomicUsize::new(0),
 		}
 	}
@@ -279,6 +314,11 @@ impl LightProtocol {
 			.map(|peer| peer.lock().status.clone())
 	}
 
+	/// Get the peers each pending transaction has been relayed to.
+	pub fn transaction_relays(&self) -> HashMap<H256, TransactionRelay> {
+		self.relayed_transactions.read().clone()
+	}
+
 	/// Get number of (connected, active) peers.
 	pub fn peer_count(&self) -> (usize, usize) {
 		let num_pending = self.pending_peers.read().len();
@@ -387,6 +427,15 @@ impl LightProtocol {
 		self.handlers.push(handler);
 	}
 
+	/// Attach a transport reaching peers outside of the devp2p network.
+	///
+	/// Packets to its peers are routed through it from every devp2p event and
+	/// timer; the transport itself feeds its peers' events into `on_connect`,
+	/// `handle_packet` and `on_disconnect`.
+	pub fn set_transport(&mut self, transport: Arc<Transport>) {
+		self.transport = Some(transport);
+	}
+
 	/// Signal to handlers that network activity is being aborted
 	/// and clear peer data.
 	pub fn abort(&self) {
@@ -397,9 +446,11 @@ impl LightProtocol {
 		// acquire in order and hold.
 		let mut pending_peers = self.pending_peers.write();
 		let mut peers = self.peers.write();
+		let mut relayed_transactions = self.relayed_transactions.write();
 
 		pending_peers.clear();
 		peers.clear();
+		relayed_transactions.clear();
 	}
 
 	// Does the common pre-verification of responses before the response itself
@@ -504,7 +555,8 @@ impl LightProtocol {
 	}
 
 	// propagate transactions to relay peers.
-	// if we aren't on the mainnet, we just propagate to all relay peers
+	// if we aren't on the mainnet, we just propagate to all relay peers,
+	// or as many of them as configured. peers which disconnect are replaced.
 	fn propagate_transactions(&self, io: &IoContext) {
 		if self.capabilities.read().tx_relay { return }
 
@@ -514,9 +566,13 @@ impl LightProtocol {
 		trace!(target: "pip", "propagate transactions: {} ready", ready_transactions.len());
 
 		let all_transaction_hashes: HashSet<_> = ready_transactions.iter().map(|tx| tx.hash()).collect();
+		let best_block = self.provider.chain_info().best_block_number;
 		let mut buf = Vec::new();
 
 		let peers = self.peers.read();
+		let mut relayed = self.relayed_transactions.write();
+		relayed.retain(|hash, _| all_transaction_hashes.contains(hash));
+
 		for (peer_id, peer_info) in peers.iter() {
 			let mut peer_info = peer_info.lock();
 			if !peer_info.capabilities.tx_relay { continue }
@@ -524,12 +580,24 @@ impl LightProtocol {
 			let prop_filter = &mut peer_info.propagated_transactions;
 			*prop_filter = &*prop_filter & &all_transaction_hashes;
 
-			// fill the buffer with all non-propagated transactions.
-			let to_propagate = ready_transactions.iter()
-				.filter(|tx| prop_filter.insert(tx.hash()))
-				.map(|tx| &tx.transaction);
+			// fill the buffer with all non-propagated transactions which
+			// haven't reached enough peers yet.
+			let node_id = io.persistent_peer_id(*peer_id);
+			for tx in &ready_transactions {
+				let hash = tx.hash();
+				if prop_filter.contains(&hash) { continue }
+
+				let relay = relayed.entry(hash).or_insert_with(|| TransactionRelay::new(best_block));
+				if self.tx_relay_peers.map_or(false, |max| relay.connected.len() >= max) { continue }
 
-			buf.extend(to_propagate);
+				prop_filter.insert(hash);
+				relay.connected.insert(*peer_id);
+				if let Some(node_id) = node_id {
+					*relay.relayed_to.entry(node_id).or_insert(0) += 1;
+				}
+
+				buf.push(&tx.transaction);
+			}
 
 			// propagate to the given peer.
 			if buf.is_empty() { continue }
@@ -556,6 +624,14 @@ impl LightProtocol {
 			return;
 		}
 
+		if let Some(max_peers) = self.max_peers {
+			if self.pending_peers.read().len() + self.peers.read().len() >= max_peers {
+				debug!(target: "pip", "Too many peers, rejecting peer {}", peer);
+				io.disconnect_peer(*peer);
+				return;
+			}
+		}
+
 		let chain_info = self.provider.chain_info();
 
 		let status = Status {
@@ -596,6 +672,11 @@ impl LightProtocol {
 			}
 		};
 
+		// let other peers take over relaying transactions sent to this one.
+		for relay in self.relayed_transactions.write().values_mut() {
+			relay.connected.remove(&peer);
+		}
+
 		for handler in &self.handlers {
 			handler.on_disconnect(&Ctx {
 				peer: peer,
@@ -609,10 +690,25 @@ impl LightProtocol {
 	pub fn with_context<F, T>(&self, io: &IoContext, f: F) -> T
 		where F: FnOnce(&BasicContext) -> T
 	{
-		f(&TickCtx {
+		self.with_transport(io, move |io| f(&TickCtx {
 			io: io,
 			proto: self,
-		})
+		}))
+	}
+
+	// run a closure with an I/O context which also reaches the peers of the
+	// attached transport, if any.
+	fn with_transport<F, T>(&self, io: &IoContext, f: F) -> T
+		where F: FnOnce(&IoContext) -> T
+	{
+		match self.transport {
+			Some(ref transport) => f(&TransportIo {
+				network: Some(io),
+				transport: &**transport,
+				sender: None,
+			}),
+			None => f(io),
+		}
 	}
 
 	fn tick_handlers(&self, io: &IoContext) {
@@ -848,23 +944,23 @@ impl NetworkProtocolHandler for LightProtocol {
 	}
 
 	fn read(&self, io: &NetworkContext, peer: &PeerId, packet_id: u8, data: &[u8]) {
-		self.handle_packet(io, peer, packet_id, data);
+		self.with_transport(io, |io| self.handle_packet(io, peer, packet_id, data));
 	}
 
 	fn connected(&self, io: &NetworkContext, peer: &PeerId) {
-		self.on_connect(peer, io);
+		self.with_transport(io, |io| self.on_connect(peer, io));
 	}
 
 	fn disconnected(&self, io: &NetworkContext, peer: &PeerId) {
-		self.on_disconnect(*peer, io);
+		self.with_transport(io, |io| self.on_disconnect(*peer, io));
 	}
 
 	fn timeout(&self, io: &NetworkContext, timer: TimerToken) {
-		match timer {
+		self.with_transport(io, |io| match timer {
 			TIMEOUT => self.timeout_check(io),
 			TICK_TIMEOUT => self.tick_handlers(io),
 			PROPAGATE_TIMEOUT => self.propagate_transactions(io),
 			_ => warn!(target: "pip", "received timeout on unknown token {}", timer),
-		}
+		});
 	}
 }
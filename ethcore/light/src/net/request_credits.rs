@@ -74,7 +74,7 @@ impl Credits {
 }
 
 /// A cost table, mapping requests to base and per-request costs.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CostTable {
 	base: U256, // cost per packet.
 	headers: U256, // cost per header
@@ -104,6 +104,28 @@ impl Default for CostTable {
 	}
 }
 
+impl CostTable {
+	/// Set the base cost of every request packet.
+	pub fn set_base(&mut self, cost: U256) {
+		self.base = cost;
+	}
+
+	/// Set the cost of a kind of request. Header costs are per header
+	/// and transaction proof costs are per gas.
+	pub fn set_cost(&mut self, kind: request::Kind, cost: U256) {
+		match kind {
+			request::Kind::Headers => self.headers = cost,
+			request::Kind::Body => self.body = cost,
+			request::Kind::Receipts => self.receipts = cost,
+			request::Kind::Account => self.account = cost,
+			request::Kind::Storage => self.storage = cost,
+			request::Kind::Code => self.code = cost,
+			request::Kind::HeaderProof => self.header_proof = cost,
+			request::Kind::Execution => self.transaction_proof = cost,
+		}
+	}
+}
+
 impl Encodable for CostTable {
 	fn rlp_append(&self, s: &mut RlpStream) {
 		fn append_cost(s: &mut RlpStream, cost: &U256, kind: request::Kind) {
@@ -170,7 +192,7 @@ impl Decodable for CostTable {
 }
 
 /// Handles costs, recharge, limits of request credits.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct FlowParams {
 	costs: CostTable,
 	limit: U256,
@@ -299,6 +321,17 @@ mod tests {
 		assert_eq!(costs, new_costs);
 	}
 
+	#[test]
+	fn should_set_costs() {
+		let mut costs = CostTable::default();
+		costs.set_base(1.into());
+		costs.set_cost(request::Kind::Execution, 5.into());
+
+		assert_eq!(costs.base, 1.into());
+		assert_eq!(costs.transaction_proof, 5.into());
+		assert_eq!(costs.headers, CostTable::default().headers);
+	}
+
 	#[test]
 	fn credits_mechanism() {
 		use std::thread;
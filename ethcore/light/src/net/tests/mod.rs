@@ -25,7 +25,7 @@ use ethcore::encoded;
 use network::{PeerId, NodeId};
 
 use net::request_credits::FlowParams;
-use net::context::IoContext;
+use net::context::{IoContext, Transport, TransportIo};
 use net::status::{Capabilities, Status, write_handshake};
 use net::{LightProtocol, Params, packet, Peer};
 use provider::Provider;
@@ -91,6 +91,31 @@ impl IoContext for Expect {
 	}
 }
 
+// transport with a single peer, checking what is sent to it.
+struct TestTransport(PeerId, Expect);
+
+impl Transport for TestTransport {
+	fn has_peer(&self, peer: PeerId) -> bool {
+		peer == self.0
+	}
+
+	fn send(&self, peer: PeerId, packet_id: u8, packet_body: Vec<u8>) {
+		IoContext::send(&self.1, peer, packet_id, packet_body)
+	}
+
+	fn disconnect_peer(&self, peer: PeerId) {
+		IoContext::disconnect_peer(&self.1, peer)
+	}
+
+	fn protocol_version(&self, _peer: PeerId) -> Option<u8> {
+		Some(super::MAX_PROTOCOL_VERSION)
+	}
+
+	fn persistent_peer_id(&self, _peer: PeerId) -> Option<NodeId> {
+		None
+	}
+}
+
 // can't implement directly for Arc due to cross-crate orphan rules.
 struct TestProvider(Arc<TestProviderInner>);
 
@@ -185,6 +210,8 @@ fn setup(flow_params: FlowParams, capabilities: Capabilities) -> (Arc<TestProvid
 		network_id: 2,
 		flow_params: flow_params,
 		capabilities: capabilities,
+		tx_relay_peers: None,
+		max_peers: None,
 	});
 
 	(provider, proto)
@@ -655,3 +682,112 @@ fn id_guard() {
 		assert_eq!(peer_info.failed_requests, &[req_id_1]);
 	}
 }
+
+#[test]
+fn relays_transactions_to_limited_peers() {
+	use std::cell::RefCell;
+
+	// records the peers packets are sent to.
+	struct RecordSends(RefCell<Vec<PeerId>>);
+
+	impl IoContext for RecordSends {
+		fn send(&self, peer: PeerId, packet_id: u8, _packet_body: Vec<u8>) {
+			assert_eq!(packet_id, packet::SEND_TRANSACTIONS);
+			self.0.borrow_mut().push(peer);
+		}
+		fn respond(&self, _packet_id: u8, _packet_body: Vec<u8>) { }
+		fn disconnect_peer(&self, _peer: PeerId) { }
+		fn disable_peer(&self, _peer: PeerId) { }
+		fn protocol_version(&self, _peer: PeerId) -> Option<u8> { Some(super::MAX_PROTOCOL_VERSION) }
+		fn persistent_peer_id(&self, peer: PeerId) -> Option<NodeId> { Some((peer as u64).into()) }
+	}
+
+	let flow_params = make_flow_params();
+	let capabilities = capabilities();
+	let provider = Arc::new(TestProviderInner {
+		client: TestBlockChainClient::new(),
+	});
+	let proto = LightProtocol::new(Arc::new(TestProvider(provider.clone())), Params {
+		network_id: 2,
+		flow_params: flow_params.clone(),
+		capabilities: Capabilities { tx_relay: false, ..capabilities.clone() },
+		tx_relay_peers: Some(1),
+		max_peers: None,
+	});
+
+	for peer_id in 1..3 {
+		proto.peers.write().insert(peer_id, ::util::Mutex::new(Peer {
+			local_credits: flow_params.create_credits(),
+			status: status(provider.client.chain_info()),
+			capabilities: capabilities.clone(),
+			remote_flow: Some((flow_params.create_credits(), flow_params.clone())),
+			sent_head: provider.client.chain_info().best_block_hash,
+			last_update: ::time::SteadyTime::now(),
+			pending_requests: Default::default(),
+			failed_requests: Vec::new(),
+			propagated_transactions: Default::default(),
+		}));
+	}
+
+	let hash = provider.client.insert_transaction_to_queue();
+
+	let io = RecordSends(RefCell::new(Vec::new()));
+	proto.propagate_transactions(&io);
+	proto.propagate_transactions(&io);
+	let first = io.0.borrow().clone();
+	assert_eq!(first.len(), 1);
+
+	// a disconnected peer is replaced by the other one.
+	proto.on_disconnect(first[0], &io);
+	proto.propagate_transactions(&io);
+	let sent = io.0.borrow().clone();
+	assert_eq!(sent.len(), 2);
+	assert!(sent[1] != first[0]);
+
+	let relays = proto.transaction_relays();
+	assert_eq!(relays[&hash].relayed_to.len(), 2);
+	assert_eq!(relays[&hash].relayed_to[&NodeId::from(first[0] as u64)], 1);
+}
+
+#[test]
+fn rejects_peers_over_limit() {
+	let flow_params = make_flow_params();
+	let capabilities = capabilities();
+	let provider = Arc::new(TestProviderInner {
+		client: TestBlockChainClient::new(),
+	});
+	let proto = LightProtocol::new(Arc::new(TestProvider(provider.clone())), Params {
+		network_id: 2,
+		flow_params: flow_params.clone(),
+		capabilities: capabilities.clone(),
+		tx_relay_peers: None,
+		max_peers: Some(1),
+	});
+
+	let status = status(provider.client.chain_info());
+	let packet_body = write_handshake(&status, &capabilities, Some(&flow_params));
+
+	proto.on_connect(&1, &Expect::Send(1, packet::STATUS, packet_body));
+	proto.on_connect(&2, &Expect::Punish(2));
+}
+
+#[test]
+fn routes_packets_of_transport_peers() {
+	let flow_params = make_flow_params();
+	let capabilities = capabilities();
+	let (provider, proto) = setup(flow_params.clone(), capabilities.clone());
+
+	let status = status(provider.client.chain_info());
+	let packet_body = write_handshake(&status, &capabilities, Some(&flow_params));
+
+	let network = Expect::Send(1, packet::STATUS, packet_body.clone());
+	let transport = TestTransport(1000, Expect::Send(1000, packet::STATUS, packet_body));
+	let io = TransportIo {
+		network: Some(&network),
+		transport: &transport,
+		sender: None,
+	};
+
+	proto.on_connect(&1000, &io);
+	proto.on_connect(&1, &io);
+}
@@ -21,7 +21,8 @@
 // TODO [ToDr] Suppressing deprecation warnings. Rob will fix the API anyway.
 #![allow(deprecated)]
 
-use std::collections::HashMap;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use ethcore::basic_account::BasicAccount;
@@ -32,8 +33,10 @@ use ethcore::executed::{Executed, ExecutionError};
 
 use futures::{Async, Poll, Future};
 use futures::sync::oneshot::{self, Sender, Receiver};
-use network::PeerId;
+use network::{NodeId, PeerId};
+use rand::{self, Rng};
 use rlp::RlpStream;
+use time::{Duration, SteadyTime};
 use util::{Bytes, RwLock, Mutex, U256, H256};
 use util::sha3::{SHA3_NULL_RLP, SHA3_EMPTY, SHA3_EMPTY_LIST_RLP};
 
@@ -43,10 +46,132 @@ use request::{self as basic_request, Request as NetworkRequest, Response as Netw
 
 pub mod request;
 
+/// Strategy for choosing which capable peer a request is assigned to.
+/// Peers which already failed to answer the request are only chosen when
+/// no other peer can handle it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PeerSelection {
+	/// The first capable peer found.
+	First,
+	/// A random capable peer.
+	Random,
+	/// The capable peer with the fewest on-demand requests in flight.
+	LeastLoaded,
+}
+
+/// How long each kind of request may wait for an answer before it's retried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestTimeouts {
+	/// Header proofs by number.
+	pub header_proof: Duration,
+	/// Headers by hash.
+	pub header_by_hash: Duration,
+	/// Block bodies.
+	pub body: Duration,
+	/// Block receipts.
+	pub receipts: Duration,
+	/// Account proofs.
+	pub account: Duration,
+	/// Contract code.
+	pub code: Duration,
+	/// Transaction execution proofs.
+	pub transaction_proof: Duration,
+}
+
+impl RequestTimeouts {
+	/// Use the same timeout for all kinds of requests.
+	pub fn uniform(timeout: Duration) -> Self {
+		RequestTimeouts {
+			header_proof: timeout,
+			header_by_hash: timeout,
+			body: timeout,
+			receipts: timeout,
+			account: timeout,
+			code: timeout,
+			transaction_proof: timeout,
+		}
+	}
+}
+
+impl Default for RequestTimeouts {
+	fn default() -> Self {
+		RequestTimeouts {
+			transaction_proof: Duration::seconds(30),
+			..RequestTimeouts::uniform(Duration::seconds(15))
+		}
+	}
+}
+
+/// On-demand request service configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+	/// How long a request may wait for a peer's answer, or for a capable peer
+	/// to become available, before it's retried. Checked on every tick.
+	pub timeouts: RequestTimeouts,
+	/// How many times a request is retried after timing out or receiving
+	/// a bad response before it's cancelled.
+	pub max_retries: usize,
+	/// How to choose the peer a request is assigned to.
+	pub peer_selection: PeerSelection,
+	/// Node ids of peers to assign requests to before any other, e.g. the
+	/// operator's own serving nodes. Preferred peers failing requests are
+	/// failed over from until their health recovers.
+	pub preferred_peers: Vec<NodeId>,
+	/// Never assign requests to peers which are not preferred.
+	pub preferred_peers_only: bool,
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Config {
+			timeouts: Default::default(),
+			max_retries: 5,
+			peer_selection: PeerSelection::First,
+			preferred_peers: Vec::new(),
+			preferred_peers_only: false,
+		}
+	}
+}
+
+// penalty points a preferred peer gets for each failed request or disconnect.
+const FAILURE_PENALTY: u64 = 10;
+// seconds it takes for a single penalty point to wear off.
+const PENALTY_DECAY_SECS: i64 = 6;
+// preferred peers with at least this many penalty points are unhealthy.
+const UNHEALTHY_PENALTY: u64 = 2 * FAILURE_PENALTY;
+
+// health of a preferred peer, kept across reconnects.
+#[derive(Debug, Clone, Copy)]
+struct Health {
+	penalty: u64,
+	updated: SteadyTime,
+}
+
+impl Health {
+	fn new() -> Self {
+		Health {
+			penalty: 0,
+			updated: SteadyTime::now(),
+		}
+	}
+
+	// penalty points left at the given time.
+	fn penalty(&self, now: SteadyTime) -> u64 {
+		let decayed = (now - self.updated).num_seconds() / PENALTY_DECAY_SECS;
+		self.penalty.saturating_sub(cmp::max(decayed, 0) as u64)
+	}
+
+	fn record_failure(&mut self, now: SteadyTime) {
+		self.penalty = self.penalty(now) + FAILURE_PENALTY;
+		self.updated = now;
+	}
+}
+
 // relevant peer info.
 struct Peer {
 	status: Status,
 	capabilities: Capabilities,
+	node_id: Option<NodeId>,
 }
 
 impl Peer {
@@ -89,6 +214,19 @@ enum Pending {
 }
 
 impl Pending {
+	// How long the request may wait for an answer.
+	fn timeout(&self, timeouts: &RequestTimeouts) -> Duration {
+		match *self {
+			Pending::HeaderProof(_, _) => timeouts.header_proof,
+			Pending::HeaderByHash(_, _) => timeouts.header_by_hash,
+			Pending::Block(_, _) => timeouts.body,
+			Pending::BlockReceipts(_, _) => timeouts.receipts,
+			Pending::Account(_, _) => timeouts.account,
+			Pending::Code(_, _) => timeouts.code,
+			Pending::TxProof(_, _) => timeouts.transaction_proof,
+		}
+	}
+
 	// Create a network request.
 	fn make_request(&self) -> NetworkRequest {
 		match *self {
@@ -128,15 +266,67 @@ impl Pending {
 	}
 }
 
+// Retry state of a request.
+struct Attempts {
+	count: usize,
+	since: SteadyTime,
+	failed_peers: HashSet<PeerId>,
+}
+
+impl Attempts {
+	fn new() -> Self {
+		Attempts {
+			count: 0,
+			since: SteadyTime::now(),
+			failed_peers: HashSet::new(),
+		}
+	}
+}
+
+// Order the peers able to handle a request according to the selection strategy.
+// Healthy preferred peers come first, the least penalized ones ahead, then other
+// peers and then unhealthy preferred peers. Peers which already failed the request
+// are put last. `preferred` holds the penalty points of connected preferred peers.
+fn select_peers(
+	mut capable: Vec<PeerId>,
+	failed_peers: &HashSet<PeerId>,
+	selection: PeerSelection,
+	load: &HashMap<PeerId, usize>,
+	preferred: &HashMap<PeerId, u64>,
+	preferred_only: bool,
+) -> Vec<PeerId> {
+	if preferred_only {
+		capable.retain(|id| preferred.contains_key(id));
+	}
+
+	match selection {
+		PeerSelection::First => capable.sort(),
+		PeerSelection::Random => rand::thread_rng().shuffle(&mut capable),
+		PeerSelection::LeastLoaded => capable.sort_by_key(|id| (load.get(id).cloned().unwrap_or(0), *id)),
+	}
+
+	// stable, so the selection strategy still orders peers of the same tier.
+	capable.sort_by_key(|id| match preferred.get(id) {
+		Some(&penalty) if penalty < UNHEALTHY_PENALTY => (0, penalty),
+		None => (1, 0),
+		Some(&penalty) => (2, penalty),
+	});
+
+	let (fresh, failed): (Vec<_>, Vec<_>) = capable.into_iter().partition(|id| !failed_peers.contains(id));
+	fresh.into_iter().chain(failed).collect()
+}
+
 /// On demand request service. See module docs for more details.
 /// Accumulates info about all peers' capabilities and dispatches
 /// requests to them accordingly.
 pub struct OnDemand {
 	peers: RwLock<HashMap<PeerId, Peer>>,
-	pending_requests: RwLock<HashMap<ReqId, Pending>>,
+	pending_requests: RwLock<HashMap<ReqId, (PeerId, Pending, Attempts)>>,
 	cache: Arc<Mutex<Cache>>,
-	orphaned_requests: RwLock<Vec<Pending>>,
+	orphaned_requests: RwLock<Vec<(Pending, Attempts)>>,
+	health: RwLock<HashMap<NodeId, Health>>,
 	start_nonce: U256,
+	config: Config,
 }
 
 const RECEIVER_IN_SCOPE: &'static str = "Receiver is still in scope, so it's not dropped; qed";
@@ -144,12 +334,19 @@ const RECEIVER_IN_SCOPE: &'static str = "Receiver is still in scope, so it's not
 impl OnDemand {
 	/// Create a new `OnDemand` service with the given cache.
 	pub fn new(cache: Arc<Mutex<Cache>>, account_start_nonce: U256) -> Self {
+		OnDemand::with_config(cache, account_start_nonce, Default::default())
+	}
+
+	/// Create a new `OnDemand` service with the given cache and configuration.
+	pub fn with_config(cache: Arc<Mutex<Cache>>, account_start_nonce: U256, config: Config) -> Self {
 		OnDemand {
 			peers: RwLock::new(HashMap::new()),
 			pending_requests: RwLock::new(HashMap::new()),
 			cache: cache,
 			orphaned_requests: RwLock::new(Vec::new()),
+			health: RwLock::new(config.preferred_peers.iter().map(|id| (*id, Health::new())).collect()),
 			start_nonce: account_start_nonce,
+			config: config,
 		}
 	}
 
@@ -272,7 +469,11 @@ impl OnDemand {
 	/// to verify against.
 	pub fn account(&self, ctx: &BasicContext, req: request::Account) -> Receiver<BasicAccount> {
 		let (sender, receiver) = oneshot::channel();
-		self.dispatch(ctx, Pending::Account(req, sender));
+		let cached = { self.cache.lock().account_proof(&req.header.state_root(), &req.address) };
+		match cached.and_then(|proof| req.check_response(&proof).ok()) {
+			Some(account) => sender.send(self.account_or_blank(account)).expect(RECEIVER_IN_SCOPE),
+			None => self.dispatch(ctx, Pending::Account(req, sender)),
+		}
 		receiver
 	}
 
@@ -284,7 +485,10 @@ impl OnDemand {
 		if req.code_hash == SHA3_EMPTY {
 			sender.send(Vec::new()).expect(RECEIVER_IN_SCOPE)
 		} else {
-			self.dispatch(ctx, Pending::Code(req, sender));
+			match { self.cache.lock().code(&req.code_hash) } {
+				Some(code) => sender.send(code).expect(RECEIVER_IN_SCOPE),
+				None => self.dispatch(ctx, Pending::Code(req, sender)),
+			}
 		}
 
 		receiver
@@ -299,8 +503,23 @@ impl OnDemand {
 		receiver
 	}
 
-	// dispatch the request, with a "suitability" function to filter acceptable peers.
+	// accounts missing from the state are blank.
+	fn account_or_blank(&self, account: Option<BasicAccount>) -> BasicAccount {
+		account.unwrap_or_else(|| BasicAccount {
+			balance: 0.into(),
+			nonce: self.start_nonce,
+			code_hash: SHA3_EMPTY,
+			storage_root: SHA3_NULL_RLP
+		})
+	}
+
+	// dispatch a new request.
 	fn dispatch(&self, ctx: &BasicContext, pending: Pending) {
+		self.assign(ctx, pending, Attempts::new())
+	}
+
+	// assign the request to a capable peer, or orphan it if there is none.
+	fn assign(&self, ctx: &BasicContext, pending: Pending, mut attempts: Attempts) {
 		let mut builder = basic_request::RequestBuilder::default();
 		builder.push(pending.make_request())
 			.expect("make_request always returns fully complete request; qed");
@@ -308,16 +527,46 @@ impl OnDemand {
 		let complete = builder.build();
 
 		let kind = complete.requests()[0].kind();
-		for (id, peer) in self.peers.read().iter() {
-			if !peer.can_handle(&pending) { continue }
-			match ctx.request_from(*id, complete.clone()) {
+		let (capable, preferred) = {
+			let peers = self.peers.read();
+			let capable: Vec<_> = peers.iter()
+				.filter(|&(_, peer)| peer.can_handle(&pending))
+				.map(|(id, _)| *id)
+				.collect();
+
+			(capable, self.preferred_penalties(&*peers))
+		};
+
+		let load = match self.config.peer_selection {
+			PeerSelection::LeastLoaded => {
+				let mut load = HashMap::new();
+				for &(ref peer, _, _) in self.pending_requests.read().values() {
+					*load.entry(*peer).or_insert(0) += 1;
+				}
+				load
+			}
+			_ => HashMap::new(),
+		};
+
+		let selected = select_peers(
+			capable,
+			&attempts.failed_peers,
+			self.config.peer_selection,
+			&load,
+			&preferred,
+			self.config.preferred_peers_only,
+		);
+
+		for id in selected {
+			match ctx.request_from(id, complete.clone()) {
 				Ok(req_id) => {
 					trace!(target: "on_demand", "{}: Assigned {:?} to peer {}",
 						req_id, kind, id);
 
+					attempts.since = SteadyTime::now();
 					self.pending_requests.write().insert(
 						req_id,
-						pending,
+						(id, pending, attempts),
 					);
 					return
 				}
@@ -327,12 +576,81 @@ impl OnDemand {
 			}
 		}
 
-		self.orphaned_requests.write().push(pending);
+		self.orphaned_requests.write().push((pending, attempts));
+	}
+
+	// retry a request which timed out or got a bad response, giving up after
+	// the configured number of retries. dropping the request cancels it.
+	fn retry(&self, ctx: &BasicContext, pending: Pending, mut attempts: Attempts, failed_peer: Option<PeerId>) {
+		if let Some(peer) = failed_peer {
+			attempts.failed_peers.insert(peer);
+
+			let node_id = self.peers.read().get(&peer).and_then(|peer| peer.node_id);
+			if let Some(node_id) = node_id {
+				self.record_failure(node_id);
+			}
+		}
+
+		attempts.count += 1;
+		if attempts.count > self.config.max_retries {
+			debug!(target: "on_demand", "Cancelling {:?} request after {} failed attempts",
+				pending.make_request().kind(), attempts.count);
+			return
+		}
+
+		attempts.since = SteadyTime::now();
+		self.assign(ctx, pending, attempts)
+	}
+
+	// penalty points of the connected preferred peers.
+	fn preferred_penalties(&self, peers: &HashMap<PeerId, Peer>) -> HashMap<PeerId, u64> {
+		let health = self.health.read();
+		if health.is_empty() { return HashMap::new() }
+
+		let now = SteadyTime::now();
+		peers.iter()
+			.filter_map(|(id, peer)| peer.node_id.as_ref()
+				.and_then(|node_id| health.get(node_id))
+				.map(|health| (*id, health.penalty(now))))
+			.collect()
+	}
+
+	// penalize a preferred peer for a failure, failing over from it once unhealthy.
+	fn record_failure(&self, node_id: NodeId) {
+		let mut health = self.health.write();
+		if let Some(health) = health.get_mut(&node_id) {
+			let now = SteadyTime::now();
+			let was_healthy = health.penalty(now) < UNHEALTHY_PENALTY;
+			health.record_failure(now);
+
+			if was_healthy && health.penalty(now) >= UNHEALTHY_PENALTY {
+				info!(target: "on_demand", "Preferred peer {} is failing requests, failing over to other peers", node_id);
+			}
+		}
+	}
+
+	// retry requests which have been waiting for an answer for too long.
+	fn check_timeouts(&self, ctx: &BasicContext) {
+		let now = SteadyTime::now();
+		let timed_out: Vec<_> = {
+			let mut pending_requests = self.pending_requests.write();
+			let expired: Vec<_> = pending_requests.iter()
+				.filter(|&(_, &(_, ref pending, ref attempts))| attempts.since + pending.timeout(&self.config.timeouts) <= now)
+				.map(|(req_id, _)| *req_id)
+				.collect();
+
+			expired.into_iter().filter_map(|req_id| pending_requests.remove(&req_id)).collect()
+		};
+
+		for (peer, pending, attempts) in timed_out {
+			debug!(target: "on_demand", "Peer {} timed out answering {:?} request", peer, pending.make_request().kind());
+			self.retry(ctx, pending, attempts, Some(peer));
+		}
 	}
 
 
-	// dispatch orphaned requests, and discard those for which the corresponding
-	// receiver has been dropped.
+	// dispatch orphaned requests, retry those which have been waiting for a peer
+	// for too long, and discard those for which the corresponding receiver has been dropped.
 	fn dispatch_orphaned(&self, ctx: &BasicContext) {
 		// wrapper future for calling `poll_cancel` on our `Senders` to preserve
 		// the invariant that it's always within a task.
@@ -361,7 +679,8 @@ impl OnDemand {
 		let to_dispatch = ::std::mem::replace(&mut *self.orphaned_requests.write(), Vec::new());
 
 		trace!(target: "on_demand", "Attempting to dispatch {} orphaned requests.", to_dispatch.len());
-		for mut orphaned in to_dispatch {
+		let now = SteadyTime::now();
+		for (mut orphaned, attempts) in to_dispatch {
 			let hung_up = match orphaned {
 				Pending::HeaderProof(_, ref mut sender) => match *sender {
 						ChtProofSender::Both(ref mut s) => check_hangup(s),
@@ -376,27 +695,43 @@ impl OnDemand {
 				Pending::TxProof(_, ref mut sender) => check_hangup(sender),
 			};
 
-			if !hung_up { self.dispatch(ctx, orphaned) }
+			if hung_up { continue }
+
+			if attempts.since + orphaned.timeout(&self.config.timeouts) <= now {
+				trace!(target: "on_demand", "No peer answered orphaned request in time");
+				self.retry(ctx, orphaned, attempts, None);
+			} else {
+				self.assign(ctx, orphaned, attempts);
+			}
 		}
 	}
 }
 
 impl Handler for OnDemand {
 	fn on_connect(&self, ctx: &EventContext, status: &Status, capabilities: &Capabilities) {
-		self.peers.write().insert(ctx.peer(), Peer { status: status.clone(), capabilities: capabilities.clone() });
+		self.peers.write().insert(ctx.peer(), Peer {
+			status: status.clone(),
+			capabilities: capabilities.clone(),
+			node_id: ctx.persistent_peer_id(ctx.peer()),
+		});
 		self.dispatch_orphaned(ctx.as_basic());
 	}
 
 	fn on_disconnect(&self, ctx: &EventContext, unfulfilled: &[ReqId]) {
-		self.peers.write().remove(&ctx.peer());
+		let node_id = self.peers.write().remove(&ctx.peer()).and_then(|peer| peer.node_id);
+		if let Some(node_id) = node_id {
+			self.record_failure(node_id);
+		}
+
 		let ctx = ctx.as_basic();
 
 		{
 			let mut orphaned = self.orphaned_requests.write();
 			for unfulfilled in unfulfilled {
-				if let Some(pending) = self.pending_requests.write().remove(unfulfilled) {
+				if let Some((peer, pending, mut attempts)) = self.pending_requests.write().remove(unfulfilled) {
 					trace!(target: "on_demand", "Attempting to reassign dropped request");
-					orphaned.push(pending);
+					attempts.failed_peers.insert(peer);
+					orphaned.push((pending, attempts));
 				}
 			}
 		}
@@ -418,8 +753,8 @@ impl Handler for OnDemand {
 
 	fn on_responses(&self, ctx: &EventContext, req_id: ReqId, responses: &[basic_request::Response]) {
 		let peer = ctx.peer();
-		let req = match self.pending_requests.write().remove(&req_id) {
-			Some(req) => req,
+		let (req, attempts) = match self.pending_requests.write().remove(&req_id) {
+			Some((_, req, attempts)) => (req, attempts),
 			None => return,
 		};
 
@@ -427,7 +762,7 @@ impl Handler for OnDemand {
 			Some(response) => response,
 			None => {
 				trace!(target: "on_demand", "Ignoring empty response for request {}", req_id);
-				self.dispatch(ctx.as_basic(), req);
+				self.retry(ctx.as_basic(), req, attempts, Some(peer));
 				return;
 			}
 		};
@@ -436,8 +771,8 @@ impl Handler for OnDemand {
 
 		// handle the response appropriately for the request.
 		// all branches which do not return early lead to disabling of the peer
-		// due to misbehavior.
-		match req {
+		// due to misbehavior and retrying the request elsewhere.
+		let req = match req {
 			Pending::HeaderProof(req, sender) => {
 				if let NetworkResponse::HeaderProof(ref response) = *response {
 					match req.check_response(&response.proof) {
@@ -456,6 +791,7 @@ impl Handler for OnDemand {
 						Err(e) => warn!(target: "on_demand", "Error handling response for header request: {:?}", e),
 					}
 				}
+				Pending::HeaderProof(req, sender)
 			}
 			Pending::HeaderByHash(req, sender) => {
 				if let NetworkResponse::Headers(ref response) = *response {
@@ -470,6 +806,7 @@ impl Handler for OnDemand {
 						}
 					}
 				}
+				Pending::HeaderByHash(req, sender)
 			}
 			Pending::Block(req, sender) => {
 				if let NetworkResponse::Body(ref response) = *response {
@@ -482,6 +819,7 @@ impl Handler for OnDemand {
 						Err(e) => warn!(target: "on_demand", "Error handling response for block request: {:?}", e),
 					}
 				}
+				Pending::Block(req, sender)
 			}
 			Pending::BlockReceipts(req, sender) => {
 				if let NetworkResponse::Receipts(ref response) = *response {
@@ -495,19 +833,14 @@ impl Handler for OnDemand {
 						Err(e) => warn!(target: "on_demand", "Error handling response for receipts request: {:?}", e),
 					}
 				}
+				Pending::BlockReceipts(req, sender)
 			}
 			Pending::Account(req, sender) => {
 				if let NetworkResponse::Account(ref response) = *response {
 					match req.check_response(&response.proof) {
 						Ok(account) => {
-							let account = account.unwrap_or_else(|| {
-								BasicAccount {
-									balance: 0.into(),
-									nonce: self.start_nonce,
-									code_hash: SHA3_EMPTY,
-									storage_root: SHA3_NULL_RLP
-								}
-							});
+							self.cache.lock().insert_account_proof(req.header.state_root(), req.address, response.proof.clone());
+							let account = self.account_or_blank(account);
 
 							// TODO: validate against request outputs.
 							// needs engine + env info as part of request.
@@ -517,17 +850,20 @@ impl Handler for OnDemand {
 						Err(e) => warn!(target: "on_demand", "Error handling response for state request: {:?}", e),
 					}
 				}
+				Pending::Account(req, sender)
 			}
 			Pending::Code(req, sender) => {
 				if let NetworkResponse::Code(ref response) = *response {
 					match req.check_response(response.code.as_slice()) {
 						Ok(()) => {
+							self.cache.lock().insert_code(req.code_hash, response.code.clone());
 							let _ = sender.send(response.code.clone());
 							return
 						}
 						Err(e) => warn!(target: "on_demand", "Error handling response for code request: {:?}", e),
 					}
 				}
+				Pending::Code(req, sender)
 			}
 			Pending::TxProof(req, sender) => {
 				if let NetworkResponse::Execution(ref response) = *response {
@@ -543,13 +879,16 @@ impl Handler for OnDemand {
 						ProvedExecution::BadProof => warn!(target: "on_demand", "Error handling response for transaction proof request"),
 					}
 				}
+				Pending::TxProof(req, sender)
 			}
-		}
+		};
 
 		ctx.disable_peer(peer);
+		self.retry(ctx.as_basic(), req, attempts, Some(peer));
 	}
 
 	fn tick(&self, ctx: &BasicContext) {
+		self.check_timeouts(ctx);
 		self.dispatch_orphaned(ctx)
 	}
 }
@@ -592,4 +931,70 @@ mod tests {
 		on_demand.dispatch_orphaned(&FakeContext);
 		assert!(on_demand.orphaned_requests.read().is_empty());
 	}
+
+	#[test]
+	fn cancels_after_max_retries() {
+		let cache = Arc::new(Mutex::new(Cache::new(Default::default(), Duration::hours(6))));
+		let on_demand = OnDemand::with_config(cache, 0.into(), Config {
+			timeouts: RequestTimeouts::uniform(Duration::zero()),
+			max_retries: 2,
+			peer_selection: PeerSelection::First,
+			preferred_peers: Vec::new(),
+			preferred_peers_only: false,
+		});
+		let result = on_demand.header_by_hash(&FakeContext, request::HeaderByHash(H256::default()));
+
+		for attempt in 1..3 {
+			on_demand.dispatch_orphaned(&FakeContext);
+			assert_eq!(on_demand.orphaned_requests.read()[0].1.count, attempt);
+		}
+
+		on_demand.dispatch_orphaned(&FakeContext);
+		assert!(on_demand.orphaned_requests.read().is_empty());
+		assert!(result.wait().is_err());
+	}
+
+	#[test]
+	fn waits_for_timeout_before_retrying() {
+		let cache = Arc::new(Mutex::new(Cache::new(Default::default(), Duration::hours(6))));
+		let on_demand = OnDemand::new(cache, 0.into());
+		let _result = on_demand.header_by_hash(&FakeContext, request::HeaderByHash(H256::default()));
+
+		on_demand.dispatch_orphaned(&FakeContext);
+		assert_eq!(on_demand.orphaned_requests.read()[0].1.count, 0);
+	}
+
+	#[test]
+	fn selects_untried_peers_first() {
+		let failed = vec![1].into_iter().collect();
+		let load = vec![(2, 3), (3, 1)].into_iter().collect();
+		let preferred = HashMap::new();
+
+		assert_eq!(select_peers(vec![3, 1, 2], &failed, PeerSelection::First, &load, &preferred, false), vec![2, 3, 1]);
+		assert_eq!(select_peers(vec![1, 2, 3], &failed, PeerSelection::LeastLoaded, &load, &preferred, false), vec![3, 2, 1]);
+		assert_eq!(select_peers(vec![1, 2, 3], &failed, PeerSelection::Random, &load, &preferred, false)[2], 1);
+	}
+
+	#[test]
+	fn selects_healthy_preferred_peers_first() {
+		let failed = vec![5].into_iter().collect();
+		let load = HashMap::new();
+		let preferred = vec![(2, UNHEALTHY_PENALTY), (3, FAILURE_PENALTY), (4, 0), (5, 0)].into_iter().collect();
+		let capable = vec![1, 2, 3, 4, 5, 6];
+
+		assert_eq!(select_peers(capable.clone(), &failed, PeerSelection::First, &load, &preferred, false), vec![4, 3, 1, 6, 2, 5]);
+		assert_eq!(select_peers(capable, &failed, PeerSelection::First, &load, &preferred, true), vec![4, 3, 2, 5]);
+	}
+
+	#[test]
+	fn preferred_peer_health_recovers() {
+		let mut health = Health::new();
+		let start = health.updated;
+
+		health.record_failure(start);
+		health.record_failure(start);
+		assert_eq!(health.penalty(start), UNHEALTHY_PENALTY);
+		assert_eq!(health.penalty(start + Duration::seconds(PENALTY_DECAY_SECS)), UNHEALTHY_PENALTY - 1);
+		assert_eq!(health.penalty(start + Duration::hours(1)), 0);
+	}
 }
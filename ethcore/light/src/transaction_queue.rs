@@ -208,6 +208,11 @@ impl TransactionQueue {
 		Ok(res)
 	}
 
+	/// Get the hashes of all queued transactions.
+	pub fn transaction_hashes(&self) -> Vec<H256> {
+		self.by_hash.keys().cloned().collect()
+	}
+
 	/// Get pending transaction by hash.
 	pub fn transaction(&self, hash: &H256) -> Option<SignedTransaction> {
 		self.by_hash.get(hash).map(|tx| (&**tx).clone())
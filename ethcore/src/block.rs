@@ -474,10 +474,10 @@ impl LockedBlock {
 	/// NOTE: This does not check the validity of `seal` with the engine.
 	pub fn seal(self, engine: &Engine, seal: Vec<Bytes>) -> Result<SealedBlock, BlockError> {
 		let mut s = self;
-		if seal.len() != engine.seal_fields() {
-			return Err(BlockError::InvalidSealArity(Mismatch{expected: engine.seal_fields(), found: seal.len()}));
+		engine.apply_seal(&mut s.block.header, seal)?;
+		if s.block.header.seal().len() != engine.seal_fields() {
+			return Err(BlockError::InvalidSealArity(Mismatch{expected: engine.seal_fields(), found: s.block.header.seal().len()}));
 		}
-		s.block.header.set_seal(seal);
 		Ok(SealedBlock { block: s.block, uncle_bytes: s.uncle_bytes })
 	}
 
@@ -490,7 +490,9 @@ impl LockedBlock {
 		seal: Vec<Bytes>,
 	) -> Result<SealedBlock, (Error, LockedBlock)> {
 		let mut s = self;
-		s.block.header.set_seal(seal);
+		if let Err(e) = engine.apply_seal(&mut s.block.header, seal) {
+			return Err((e.into(), s));
+		}
 		match engine.verify_block_seal(&s.block.header) {
 			Err(e) => Err((e, s)),
 			_ => Ok(SealedBlock { block: s.block, uncle_bytes: s.uncle_bytes }),
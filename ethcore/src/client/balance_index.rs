@@ -0,0 +1,156 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! History of account balances.
+//!
+//! Balances of the accounts modified by every imported block, including blocks
+//! which are not canonical, are stored under `address ++ block number ++ block hash`.
+//! Queries skip entries of non-canonical blocks, so reorganisations need no cleanup.
+//!
+//! An account modified without a change of its balance (nonce, storage) is stored too,
+//! such entries are filtered out when the history is read.
+//! Only blocks imported while the index is enabled are recorded.
+
+use std::sync::Arc;
+use byteorder::{ByteOrder, BigEndian};
+use rlp::{self, UntrustedRlp};
+use util::{H256, U256, Address, KeyValueDB, DBTransaction};
+use db::COL_BALANCE_HISTORY;
+use header::BlockNumber;
+use types::balance_change::BalanceChange;
+
+/// Length of the address prefix of entry keys.
+const PREFIX_LEN: usize = 20;
+
+/// Length of entry keys.
+const KEY_LEN: usize = PREFIX_LEN + 8 + 32;
+
+fn entry_key(address: &Address, number: BlockNumber, hash: &H256) -> Vec<u8> {
+	let mut key = Vec::with_capacity(KEY_LEN);
+	key.extend_from_slice(&**address);
+	let mut buf = [0u8; 8];
+	BigEndian::write_u64(&mut buf, number);
+	key.extend_from_slice(&buf);
+	key.extend_from_slice(&**hash);
+	key
+}
+
+/// Block number, block hash and balance of an entry.
+fn decode_entry(key: &[u8], value: &[u8]) -> Option<(BlockNumber, H256, U256)> {
+	if key.len() != KEY_LEN {
+		return None;
+	}
+	let balance = match UntrustedRlp::new(value).as_val() {
+		Ok(balance) => balance,
+		Err(_) => return None,
+	};
+	Some((BigEndian::read_u64(&key[PREFIX_LEN..PREFIX_LEN + 8]), H256::from_slice(&key[PREFIX_LEN + 8..]), balance))
+}
+
+/// Account balance history.
+pub struct BalanceIndex {
+	db: Arc<KeyValueDB>,
+}
+
+impl BalanceIndex {
+	/// Create the index stored in the given database.
+	pub fn new(db: Arc<KeyValueDB>) -> Self {
+		BalanceIndex {
+			db: db,
+		}
+	}
+
+	/// Record balances of the accounts modified by the block.
+	pub fn insert(&self, batch: &mut DBTransaction, number: BlockNumber, hash: &H256, balances: &[(Address, U256)]) {
+		for &(ref address, ref balance) in balances {
+			batch.put(COL_BALANCE_HISTORY, &entry_key(address, number, hash), &rlp::encode(balance));
+		}
+	}
+
+	/// Changes of the account balance in canonical blocks `from` to `to`, at most `limit` of them.
+	/// `is_canon` tells whether the block of the given number and hash is canonical.
+	pub fn history<F>(&self, address: &Address, from: BlockNumber, to: BlockNumber, limit: usize, is_canon: F) -> Vec<BalanceChange>
+		where F: Fn(BlockNumber, &H256) -> bool
+	{
+		let prefix: &[u8] = &**address;
+		let mut previous_balance = U256::zero();
+		let mut changes = Vec::new();
+
+		let entries = self.db.iter_from_prefix(COL_BALANCE_HISTORY, prefix)
+			.take_while(|&(ref key, _)| key.starts_with(prefix))
+			.filter_map(|(key, value)| decode_entry(&key, &value))
+			.take_while(|&(number, _, _)| number <= to)
+			.filter(|&(number, ref hash, _)| is_canon(number, hash));
+
+		for (number, hash, balance) in entries {
+			if balance != previous_balance && number >= from {
+				if changes.len() == limit {
+					break;
+				}
+				changes.push(BalanceChange {
+					block_number: number,
+					block_hash: hash,
+					previous_balance: previous_balance,
+					balance: balance,
+				});
+			}
+			previous_balance = balance;
+		}
+		changes
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use util::{H256, U256, Address, DBTransaction};
+	use util::kvdb::in_memory;
+	use types::balance_change::BalanceChange;
+	use super::BalanceIndex;
+
+	fn change(number: u64, previous_balance: u64, balance: u64) -> BalanceChange {
+		BalanceChange {
+			block_number: number,
+			block_hash: H256::from(number),
+			previous_balance: previous_balance.into(),
+			balance: balance.into(),
+		}
+	}
+
+	#[test]
+	fn should_query_canonical_balance_changes() {
+		let index = BalanceIndex::new(Arc::new(in_memory(::db::NUM_COLUMNS.unwrap_or(0))));
+		let address = Address::from(10);
+		let other = Address::from(11);
+
+		let mut batch = DBTransaction::new();
+		index.insert(&mut batch, 1, &H256::from(1), &[(address, U256::from(100)), (other, U256::from(5))]);
+		// only the nonce changed
+		index.insert(&mut batch, 2, &H256::from(2), &[(address, U256::from(100))]);
+		index.insert(&mut batch, 3, &H256::from(3), &[(address, U256::from(70))]);
+		// retracted block
+		index.insert(&mut batch, 3, &H256::from(33), &[(address, U256::from(1000))]);
+		index.insert(&mut batch, 4, &H256::from(4), &[(address, U256::from(0))]);
+		index.db.write(batch).unwrap();
+
+		let is_canon = |number: u64, hash: &H256| *hash == H256::from(number);
+		assert_eq!(index.history(&address, 0, 10, 100, &is_canon), vec![change(1, 0, 100), change(3, 100, 70), change(4, 70, 0)]);
+		assert_eq!(index.history(&address, 2, 3, 100, &is_canon), vec![change(3, 100, 70)]);
+		assert_eq!(index.history(&address, 0, 10, 1, &is_canon), vec![change(1, 0, 100)]);
+		assert_eq!(index.history(&other, 0, 10, 100, &is_canon), vec![change(1, 0, 5)]);
+		assert_eq!(index.history(&Address::from(12), 0, 10, 100, &is_canon), vec![]);
+	}
+}
@@ -19,13 +19,14 @@ use std::str::FromStr;
 use std::sync::{Arc, Weak};
 use std::fmt;
 use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering as AtomicOrdering};
-use std::time::{Instant};
+use std::cmp;
+use std::time::{Instant, Duration};
 use time::precise_time_ns;
 
 // util
 use util::{Bytes, PerfTimer, Itertools, Mutex, RwLock, MutexGuard, Hashable};
 use util::{journaldb, DBValue, TrieFactory, Trie};
-use util::{U256, H256, Address, H2048, Uint};
+use util::{U256, H256, Address, H2048, Uint, Mismatch};
 use util::trie::TrieSpec;
 use util::kvdb::*;
 
@@ -35,6 +36,13 @@ use block::*;
 use blockchain::{BlockChain, BlockProvider, EpochTransition, TreeRoute, ImportRoute};
 use blockchain::extras::TransactionAddress;
 use client::Error as ClientError;
+use client::reorg::AlertPoster;
+use client::spans::{BlockSpansBuilder, SpanExporter};
+use client::import_stats::ImportStats;
+use client::token_index::TokenIndex;
+use client::balance_index::BalanceIndex;
+use client::log_index::LogIndex;
+use client::disk_guard::DiskGuard;
 use client::{
 	BlockId, TransactionId, UncleId, TraceId, ClientConfig, BlockChainClient,
 	MiningBlockChainClient, EngineClient, TraceFilter, CallAnalytics, BlockImportError, Mode,
@@ -52,7 +60,7 @@ use futures::{future, Future};
 use header::{BlockNumber, Header};
 use io::*;
 use log_entry::LocalizedLogEntry;
-use miner::{Miner, MinerService, TransactionImportResult};
+use miner::{Miner, MinerService, TransactionImportResult, LocalTransactionStatus};
 use native_contracts::Registry;
 use rand::OsRng;
 use receipt::{Receipt, LocalizedReceipt};
@@ -68,6 +76,12 @@ use trace::FlatTransactionTraces;
 use transaction::{LocalizedTransaction, UnverifiedTransaction, SignedTransaction, Transaction, PendingTransaction, Action};
 use types::filter::Filter;
 use types::mode::Mode as IpcMode;
+use types::reorg_alert::ReorgAlert;
+use types::disk_space::DiskSpace;
+use types::import_statistics::ImportStatistics;
+use types::token_transfer::TokenTransfer;
+use types::balance_change::BalanceChange;
+use types::author_statistics::AuthorStatistics;
 use verification;
 use verification::{PreverifiedBlock, Verifier};
 use verification::queue::BlockQueue;
@@ -82,6 +96,8 @@ pub use verification::queue::QueueInfo as BlockQueueInfo;
 const MAX_TX_QUEUE_SIZE: usize = 4096;
 const MAX_QUEUE_SIZE_TO_SLEEP_ON: usize = 2;
 const MIN_HISTORY_SIZE: u64 = 8;
+const TOKEN_INDEX_BATCH: u64 = 1000;
+const LOG_INDEX_BATCH: u64 = 1000;
 
 impl fmt::Display for BlockChainInfo {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -114,6 +130,8 @@ impl ClientReport {
 struct SleepState {
 	last_activity: Option<Instant>,
 	last_autosleep: Option<Instant>,
+	/// Kept awake until then regardless of the mode timeout.
+	awake_until: Option<Instant>,
 }
 
 impl SleepState {
@@ -121,8 +139,20 @@ impl SleepState {
 		SleepState {
 			last_activity: match awake { false => None, true => Some(Instant::now()) },
 			last_autosleep: match awake { false => Some(Instant::now()), true => None },
+			awake_until: None,
 		}
 	}
+
+	/// Whether the client was idle for the mode timeout and is not kept awake.
+	fn is_idle(&self, now: Instant, timeout: Duration) -> bool {
+		let deadline = match (self.last_activity, self.awake_until) {
+			(Some(t), Some(until)) => cmp::max(t + timeout, until),
+			(Some(t), None) => t + timeout,
+			(None, Some(until)) => until,
+			(None, None) => return false,
+		};
+		now > deadline
+	}
 }
 
 /// Blockchain database client backed by a persistent database. Owns and manages a blockchain and a block queue.
@@ -155,6 +185,19 @@ pub struct Client {
 	on_user_defaults_change: Mutex<Option<Box<FnMut(Option<Mode>) + 'static + Send>>>,
 	registrar: Mutex<Option<Registry>>,
 	exit_handler: Mutex<Option<Box<Fn(bool, Option<String>) + 'static + Send>>>,
+	/// Raised by a too deep reorganisation, block import is paused while set.
+	reorg_alert: RwLock<Option<ReorgAlert>>,
+	/// Blocks drained from the queue, but not imported because of the pause.
+	paused_blocks: Mutex<Vec<PreverifiedBlock>>,
+	/// Block allowed to cause a deep reorganisation (acknowledged alert).
+	allowed_reorg: Mutex<Option<H256>>,
+	reorg_poster: Option<AlertPoster>,
+	span_exporter: Option<SpanExporter>,
+	import_stats: ImportStats,
+	token_index: Option<TokenIndex>,
+	balance_index: Option<BalanceIndex>,
+	log_index: Option<LogIndex>,
+	disk_guard: DiskGuard,
 }
 
 impl Client {
@@ -216,6 +259,25 @@ impl Client {
 		panic_handler.forward_from(&block_queue);
 
 		let awake = match config.mode { Mode::Dark(..) | Mode::Off => false, _ => true };
+		let reorg_poster = match config.reorg_alert_urls.is_empty() {
+			true => None,
+			false => Some(AlertPoster::new(&config.reorg_alert_urls)),
+		};
+
+		let span_exporter = config.span_export.as_ref().map(SpanExporter::new);
+		let token_index = match config.token_index {
+			true => Some(TokenIndex::new(db.clone())),
+			false => None,
+		};
+		let balance_index = match config.balance_history {
+			true => Some(BalanceIndex::new(db.clone())),
+			false => None,
+		};
+		let log_index = match config.log_index {
+			true => Some(LogIndex::new(db.clone())),
+			false => None,
+		};
+		let disk_guard = DiskGuard::new(config.disk_guard.clone());
 
 		let client = Arc::new(Client {
 			enabled: AtomicBool::new(true),
@@ -245,6 +307,16 @@ impl Client {
 			on_user_defaults_change: Mutex::new(None),
 			registrar: Mutex::new(None),
 			exit_handler: Mutex::new(None),
+			reorg_alert: RwLock::new(None),
+			paused_blocks: Mutex::new(Vec::new()),
+			allowed_reorg: Mutex::new(None),
+			reorg_poster: reorg_poster,
+			span_exporter: span_exporter,
+			import_stats: ImportStats::new(),
+			token_index: token_index,
+			balance_index: balance_index,
+			log_index: log_index,
+			disk_guard: disk_guard,
 		});
 
 		// prune old states.
@@ -283,6 +355,21 @@ impl Client {
 		}
 	}
 
+	/// Wakes up client if it's asleep and keeps it awake for at least `idle`,
+	/// instead of the mode timeout. Returns `true` if the client was asleep.
+	pub fn keep_alive_for(&self, idle: Duration) -> bool {
+		match *self.mode.lock() {
+			Mode::Dark(..) | Mode::Passive(..) => {},
+			_ => return false,
+		}
+		let was_asleep = !self.liveness.load(AtomicOrdering::Relaxed);
+		self.wake_up();
+		let until = Instant::now() + idle;
+		let mut ss = self.sleep_state.lock();
+		ss.awake_until = Some(ss.awake_until.map_or(until, |current| cmp::max(current, until)));
+		was_asleep
+	}
+
 	/// Adds an actor to be notified on certain events
 	pub fn add_notify(&self, target: Arc<ChainNotify>) {
 		self.notify.write().push(Arc::downgrade(&target));
@@ -316,10 +403,16 @@ impl Client {
 		*self.on_user_defaults_change.lock() = Some(Box::new(f));
 	}
 
+	/// Stop importing blocks and wait for the import in progress to finish. Used on shutdown.
+	pub fn stop_import(&self) {
+		self.enabled.store(false, AtomicOrdering::Relaxed);
+		let _import_lock = self.import_lock.lock();
+	}
+
 	/// Flush the block import queue.
 	pub fn flush_queue(&self) {
 		self.block_queue.flush();
-		while !self.block_queue.queue_info().is_empty() {
+		while !self.block_queue.queue_info().is_empty() && self.reorg_alert.read().is_none() {
 			self.import_verified_blocks();
 		}
 	}
@@ -371,7 +464,7 @@ impl Client {
 		Arc::new(last_hashes)
 	}
 
-	fn check_and_close_block(&self, block: &PreverifiedBlock) -> Result<LockedBlock, ()> {
+	fn check_and_close_block(&self, block: &PreverifiedBlock, spans: &mut BlockSpansBuilder) -> Result<LockedBlock, ()> {
 		let engine = &*self.engine;
 		let header = &block.header;
 
@@ -384,13 +477,13 @@ impl Client {
 		}
 
 		// Verify Block Family
-		let verify_family_result = self.verifier.verify_block_family(header, &block.bytes, engine, &**chain);
+		let verify_family_result = spans.stage("verify_family", || self.verifier.verify_block_family(header, &block.bytes, engine, &**chain));
 		if let Err(e) = verify_family_result {
 			warn!(target: "client", "Stage 3 block verification failed for #{} ({})\nError: {:?}", header.number(), header.hash(), e);
 			return Err(());
 		};
 
-		let verify_external_result = self.verifier.verify_block_external(header, &block.bytes, engine);
+		let verify_external_result = spans.stage("verify_external", || self.verifier.verify_block_external(header, &block.bytes, engine));
 		if let Err(e) = verify_external_result {
 			warn!(target: "client", "Stage 4 block verification failed for #{} ({})\nError: {:?}", header.number(), header.hash(), e);
 			return Err(());
@@ -403,7 +496,8 @@ impl Client {
 			let last_hashes = self.build_last_hashes(header.parent_hash().clone());
 			let db = self.state_db.lock().boxed_clone_canon(header.parent_hash());
 
-			let enact_result = enact_verified(block, engine, self.tracedb.read().tracing_enabled(), db, &parent, last_hashes, self.factories.clone());
+			let tracing = self.tracedb.read().tracing_enabled();
+			let enact_result = spans.stage("execute", || enact_verified(block, engine, tracing, db, &parent, last_hashes, self.factories.clone()));
 			let mut locked_block = enact_result.map_err(|e| {
 				warn!(target: "client", "Block import failed for #{} ({})\nError: {:?}", header.number(), header.hash(), e);
 			})?;
@@ -413,7 +507,7 @@ impl Client {
 			}
 
 			// Final Verification
-			if let Err(e) = self.verifier.verify_block_final(header, locked_block.block().header()) {
+			if let Err(e) = spans.stage("verify_final", || self.verifier.verify_block_final(header, locked_block.block().header())) {
 				warn!(target: "client", "Stage 5 block verification failed for #{} ({})\nError: {:?}", header.number(), header.hash(), e);
 				return Err(());
 			}
@@ -425,6 +519,52 @@ impl Client {
 		}
 	}
 
+	/// Check if importing the block would retract more canonical blocks than allowed.
+	fn check_reorg_depth(&self, header: &Header) -> Option<ReorgAlert> {
+		let max_depth = match self.config.max_reorg_depth {
+			Some(max_depth) => max_depth,
+			None => return None,
+		};
+
+		let chain = self.chain.read();
+		let parent_td = match chain.block_details(header.parent_hash()) {
+			Some(details) => details.total_difficulty,
+			None => return None,
+		};
+
+		// the block doesn't become the new best block.
+		if parent_td + *header.difficulty() <= chain.best_block_total_difficulty() {
+			return None;
+		}
+
+		let best_block_hash = chain.best_block_hash();
+		chain.tree_route(best_block_hash, header.parent_hash().clone())
+			.and_then(|route| match route.index as u64 > max_depth {
+				true => Some(ReorgAlert {
+					block_hash: header.hash(),
+					block_number: header.number(),
+					best_block_hash: best_block_hash,
+					ancestor: route.ancestor,
+					depth: route.index as u64,
+					max_depth: max_depth,
+				}),
+				false => None,
+			})
+	}
+
+	fn raise_reorg_alert(&self, alert: ReorgAlert) {
+		error!(
+			target: "client",
+			"Block #{} ({}) would retract {} canonical blocks (maximum is {}), pausing block import until the reorganisation is acknowledged.",
+			alert.block_number, alert.block_hash, alert.depth, alert.max_depth
+		);
+
+		if let Some(ref poster) = self.reorg_poster {
+			poster.notify(&alert);
+		}
+		*self.reorg_alert.write() = Some(alert);
+	}
+
 	fn calculate_enacted_retracted(&self, import_results: &[ImportRoute]) -> (Vec<H256>, Vec<H256>) {
 		fn map_to_vec(map: Vec<(H256, bool)>) -> Vec<H256> {
 			map.into_iter().map(|(k, _v)| k).collect()
@@ -459,6 +599,16 @@ impl Client {
 			return 0;
 		}
 
+		// Import is paused until the deep reorganisation is acknowledged.
+		if self.reorg_alert.read().is_some() {
+			return 0;
+		}
+
+		// Halt before writing to a full disk, the blocks stay queued.
+		if self.disk_guard.check() == DiskSpace::Exhausted {
+			return 0;
+		}
+
 		let max_blocks_to_import = 4;
 		let (imported_blocks, import_results, invalid_blocks, imported, proposed_blocks, duration, is_empty) = {
 			let mut imported_blocks = Vec::with_capacity(max_blocks_to_import);
@@ -467,37 +617,64 @@ impl Client {
 			let mut import_results = Vec::with_capacity(max_blocks_to_import);
 
 			let _import_lock = self.import_lock.lock();
-			let blocks = self.block_queue.drain(max_blocks_to_import);
+			let mut blocks = ::std::mem::replace(&mut *self.paused_blocks.lock(), Vec::new());
+			blocks.extend(self.block_queue.drain(max_blocks_to_import));
 			if blocks.is_empty() {
 				return 0;
 			}
 			let _timer = PerfTimer::new("import_verified_blocks");
 			let start = precise_time_ns();
 
-			for block in blocks {
-				let header = &block.header;
-				let is_invalid = invalid_blocks.contains(header.parent_hash());
+			let mut blocks = blocks.into_iter();
+			while let Some(block) = blocks.next() {
+				let is_invalid = invalid_blocks.contains(block.header.parent_hash());
 				if is_invalid {
-					invalid_blocks.insert(header.hash());
+					invalid_blocks.insert(block.header.hash());
 					continue;
 				}
-				if let Ok(closed_block) = self.check_and_close_block(&block) {
+
+				if let Some(alert) = self.check_reorg_depth(&block.header) {
+					let mut allowed_reorg = self.allowed_reorg.lock();
+					if *allowed_reorg == Some(alert.block_hash) {
+						*allowed_reorg = None;
+					} else {
+						self.raise_reorg_alert(alert);
+						*self.paused_blocks.lock() = Some(block).into_iter().chain(blocks).collect();
+						break;
+					}
+				}
+
+				let header = &block.header;
+				let mut spans = BlockSpansBuilder::new(header.hash(), header.number());
+				spans.queued(block.span.clone());
+				let error = if let Ok(closed_block) = self.check_and_close_block(&block, &mut spans) {
 					if self.engine.is_proposal(&block.header) {
 						self.block_queue.mark_as_good(&[header.hash()]);
 						proposed_blocks.push(block.bytes);
 					} else {
 						imported_blocks.push(header.hash());
 
-						let route = self.commit_block(closed_block, &header.hash(), &block.bytes);
+						let route = spans.stage("commit", || self.commit_block(closed_block, &header.hash(), &block.bytes));
 						import_results.push(route);
 
 						self.report.write().accrue_block(&block);
 					}
+					None
 				} else {
 					invalid_blocks.insert(header.hash());
+					Some("Invalid block".to_owned())
+				};
+				let spans = spans.finish(error);
+				self.import_stats.record(&spans, header.gas_used());
+				if let Some(ref exporter) = self.span_exporter {
+					exporter.record(spans);
 				}
 			}
 
+			if let Some(ref exporter) = self.span_exporter {
+				exporter.flush();
+			}
+
 			let imported = imported_blocks.len();
 			let invalid_blocks = invalid_blocks.into_iter().collect::<Vec<H256>>();
 
@@ -531,6 +708,8 @@ impl Client {
 			}
 		}
 
+		self.update_token_index();
+		self.update_log_index();
 		self.db.read().flush().expect("DB flush failed.");
 		imported
 	}
@@ -548,6 +727,12 @@ impl Client {
 			let mut rng = self.rng.lock();
 			let chain = self.chain.read();
 
+			if let Some(checkpoint) = self.config.queue.checkpoint {
+				if header.number() == checkpoint.number && hash != checkpoint.hash {
+					return Err(BlockError::CheckpointMismatch(Mismatch { expected: checkpoint.hash, found: hash }).into());
+				}
+			}
+
 			// verify block.
 			::snapshot::verify_old_block(
 				&mut *rng,
@@ -607,6 +792,9 @@ impl Client {
 		let mut state = block.drain();
 
 		state.journal_under(&mut batch, number, hash).expect("DB commit failed");
+		if let Some(ref index) = self.balance_index {
+			index.insert(&mut batch, number, hash, &state.modified_balances());
+		}
 		let route = chain.insert_block(&mut batch, block_data, receipts);
 
 		self.tracedb.read().import(&mut batch, TraceImportRequest {
@@ -823,6 +1011,53 @@ impl Client {
 	pub fn tick(&self) {
 		self.check_garbage();
 		self.check_snooze();
+		self.check_disk_space();
+		self.update_token_index();
+		self.update_log_index();
+	}
+
+	/// Resume block import halted by lack of disk space once space is freed.
+	fn check_disk_space(&self) {
+		let previous = self.disk_guard.level();
+		if self.disk_guard.check() < DiskSpace::Exhausted && previous == DiskSpace::Exhausted {
+			if let Err(e) = self.io_channel.lock().send(ClientIoMessage::BlockVerified) {
+				debug!(target: "client", "Unable to resume block import: {}", e);
+			}
+		}
+	}
+
+	/// Index token transfers of the newly imported blocks, a batch at a time.
+	fn update_token_index(&self) {
+		if let Some(ref index) = self.token_index {
+			index.update(&self.chain.read(), TOKEN_INDEX_BATCH);
+		}
+	}
+
+	/// Index log addresses and topics of the newly imported blocks, a batch at a time.
+	fn update_log_index(&self) {
+		if let Some(ref index) = self.log_index {
+			index.update(&self.chain.read(), LOG_INDEX_BATCH);
+		}
+	}
+
+	/// Blocks which may contain logs matching the filter, according to the log index.
+	/// `None` if the index is disabled, does not cover the blocks or the filter has no constraints.
+	fn indexed_log_blocks(&self, filter: &Filter) -> Option<Vec<BlockNumber>> {
+		let index = match self.log_index {
+			Some(ref index) => index,
+			None => return None,
+		};
+		let head = match index.head() {
+			Some((number, hash)) => match self.chain.read().block_hash(number) == Some(hash) {
+				true => number,
+				false => return None,
+			},
+			None => return None,
+		};
+		match (self.block_number(filter.from_block.clone()), self.block_number(filter.to_block.clone())) {
+			(Some(from), Some(to)) if to <= head => index.blocks(filter, from, to),
+			_ => None,
+		}
 	}
 
 	fn check_garbage(&self) {
@@ -836,22 +1071,20 @@ impl Client {
 		match mode {
 			Mode::Dark(timeout) => {
 				let mut ss = self.sleep_state.lock();
-				if let Some(t) = ss.last_activity {
-					if Instant::now() > t + timeout {
-						self.sleep();
-						ss.last_activity = None;
-					}
+				if ss.is_idle(Instant::now(), timeout) {
+					self.sleep();
+					ss.last_activity = None;
+					ss.awake_until = None;
 				}
 			}
 			Mode::Passive(timeout, wakeup_after) => {
 				let mut ss = self.sleep_state.lock();
 				let now = Instant::now();
-				if let Some(t) = ss.last_activity {
-					if now > t + timeout {
-						self.sleep();
-						ss.last_activity = None;
-						ss.last_autosleep = Some(now);
-					}
+				if ss.is_idle(now, timeout) {
+					self.sleep();
+					ss.last_activity = None;
+					ss.awake_until = None;
+					ss.last_autosleep = Some(now);
 				}
 				if let Some(t) = ss.last_autosleep {
 					if now > t + wakeup_after {
@@ -868,6 +1101,16 @@ impl Client {
 	/// Take a snapshot at the given block.
 	/// If the ID given is "latest", this will default to 1000 blocks behind.
 	pub fn take_snapshot<W: snapshot_io::SnapshotWriter + Send>(&self, writer: W, at: BlockId, p: &snapshot::Progress) -> Result<(), EthcoreError> {
+		self.snapshot_with_base(writer, at, None, p)
+	}
+
+	/// Take a delta snapshot containing only the state changed since the `base` snapshot.
+	/// State at the block of the base snapshot must still be available.
+	pub fn take_delta_snapshot<W: snapshot_io::SnapshotWriter + Send>(&self, writer: W, at: BlockId, base: &snapshot::ManifestData, p: &snapshot::Progress) -> Result<(), EthcoreError> {
+		self.snapshot_with_base(writer, at, Some(base), p)
+	}
+
+	fn snapshot_with_base<W: snapshot_io::SnapshotWriter + Send>(&self, writer: W, at: BlockId, base: Option<&snapshot::ManifestData>, p: &snapshot::Progress) -> Result<(), EthcoreError> {
 		let db = self.state_db.lock().journal_db().boxed_clone();
 		let best_block_number = self.chain_info().best_block_number;
 		let block_number = self.block_number(at).ok_or(snapshot::Error::InvalidStartingBlock(at))?;
@@ -896,7 +1139,9 @@ impl Client {
 			},
 		};
 
-		snapshot::take_snapshot(&*self.engine, &self.chain.read(), start_hash, db.as_hashdb(), writer, p)?;
+		let busy = || !self.block_queue.queue_info().is_empty();
+		let writer = snapshot::ThrottledWriter::new(writer, &self.config.snapshot_throttle, &busy);
+		snapshot::take_snapshot(&*self.engine, &self.chain.read(), start_hash, db.as_hashdb(), base, self.config.snapshot_threads, writer, p)?;
 
 		Ok(())
 	}
@@ -915,6 +1160,36 @@ impl Client {
 		}
 	}
 
+	/// Resolves block id to hash, skipping blocks outside of the configured block range.
+	fn block_hash_in_range(&self, chain: &BlockChain, id: BlockId) -> Option<H256> {
+		let range = match self.config.block_range {
+			Some(range) => range,
+			None => return Self::block_hash(chain, id),
+		};
+		let best_block = chain.best_block_number();
+		Self::block_hash(chain, id).and_then(|hash| match chain.block_number(&hash) {
+			// best block is always available
+			Some(number) if number == best_block || range.contains(number, best_block) => Some(hash),
+			_ => None,
+		})
+	}
+
+	/// Checks the number of a block about to be imported against the configured block range.
+	/// Blocks below the range are rejected only when imported as ancient ones,
+	/// recent blocks below it are needed to reach the range.
+	fn check_block_range(&self, number: BlockNumber, ancient: bool) -> Result<(), ImportError> {
+		let range = match self.config.block_range {
+			Some(range) => range,
+			None => return Ok(()),
+		};
+		let above = range.last().map_or(false, |last| number > last);
+		let below = ancient && number < range.first(self.chain.read().best_block_number());
+		match above || below {
+			true => Err(ImportError::OutOfRange),
+			false => Ok(()),
+		}
+	}
+
 	fn transaction_address(&self, id: TransactionId) -> Option<TransactionAddress> {
 		match id {
 			TransactionId::Hash(ref hash) => self.chain.read().transaction_address(hash),
@@ -1170,7 +1445,7 @@ impl BlockChainClient for Client {
 
 	fn block_header(&self, id: BlockId) -> Option<::encoded::Header> {
 		let chain = self.chain.read();
-		Self::block_hash(&chain, id).and_then(|hash| chain.block_header_data(&hash))
+		self.block_hash_in_range(&chain, id).and_then(|hash| chain.block_header_data(&hash))
 	}
 
 	fn block_number(&self, id: BlockId) -> Option<BlockNumber> {
@@ -1184,7 +1459,7 @@ impl BlockChainClient for Client {
 
 	fn block_body(&self, id: BlockId) -> Option<encoded::Body> {
 		let chain = self.chain.read();
-		Self::block_hash(&chain, id).and_then(|hash| chain.block_body(&hash))
+		self.block_hash_in_range(&chain, id).and_then(|hash| chain.block_body(&hash))
 	}
 
 	fn block(&self, id: BlockId) -> Option<encoded::Block> {
@@ -1194,7 +1469,7 @@ impl BlockChainClient for Client {
 			}
 		}
 		let chain = self.chain.read();
-		Self::block_hash(&chain, id).and_then(|hash| {
+		self.block_hash_in_range(&chain, id).and_then(|hash| {
 			chain.block(&hash)
 		})
 	}
@@ -1363,6 +1638,30 @@ impl BlockChainClient for Client {
 			}))
 	}
 
+	fn localized_block_receipts(&self, id: BlockId) -> Option<Vec<LocalizedReceipt>> {
+		let chain = self.chain.read();
+		let hash = match Self::block_hash(&chain, id) {
+			Some(hash) => hash,
+			None => return None,
+		};
+		let (number, body, receipts) = match (chain.block_number(&hash), chain.block_body(&hash), chain.block_receipts(&hash)) {
+			(Some(number), Some(body), Some(receipts)) => (number, body, receipts),
+			_ => return None,
+		};
+
+		let schedule = self.engine().schedule(number);
+		let mut prior_gas_used = U256::zero();
+		let mut no_of_logs = 0;
+		Some(body.view().localized_transactions(&hash, number).into_iter().zip(receipts.receipts).map(|(tx, receipt)| {
+			let gas_used = receipt.gas_used;
+			let logs = receipt.logs.len();
+			let localized = localize_receipt(&schedule, tx, receipt, prior_gas_used, no_of_logs);
+			prior_gas_used = gas_used;
+			no_of_logs += logs;
+			localized
+		}).collect())
+	}
+
 	fn tree_route(&self, from: &H256, to: &H256) -> Option<TreeRoute> {
 		let chain = self.chain.read();
 		match chain.is_known(from) && chain.is_known(to) {
@@ -1380,13 +1679,18 @@ impl BlockChainClient for Client {
 	}
 
 	fn block_receipts(&self, hash: &H256) -> Option<Bytes> {
-		self.chain.read().block_receipts(hash).map(|receipts| ::rlp::encode(&receipts).to_vec())
+		let chain = self.chain.read();
+		self.block_hash_in_range(&chain, BlockId::Hash(*hash))
+			.and_then(|hash| chain.block_receipts(&hash))
+			.map(|receipts| ::rlp::encode(&receipts).to_vec())
 	}
 
 	fn import_block(&self, bytes: Bytes) -> Result<H256, BlockImportError> {
 		use verification::queue::kind::BlockLike;
 		use verification::queue::kind::blocks::Unverified;
 
+		self.check_block_range(BlockView::new(&bytes).header_view().number(), false).map_err(BlockImportError::Import)?;
+
 		// create unverified block here so the `sha3` calculation can be cached.
 		let unverified = Unverified::new(bytes);
 
@@ -1411,6 +1715,7 @@ impl BlockChainClient for Client {
 			if self.block_status(BlockId::Hash(header.parent_hash())) == BlockStatus::Unknown {
 				return Err(BlockImportError::Block(BlockError::UnknownParent(header.parent_hash())));
 			}
+			self.check_block_range(header.number(), true).map_err(BlockImportError::Import)?;
 		}
 		self.import_old_block(block_bytes, receipts_bytes).map_err(Into::into)
 	}
@@ -1441,13 +1746,16 @@ impl BlockChainClient for Client {
 	}
 
 	fn logs(&self, filter: Filter) -> Vec<LocalizedLogEntry> {
-		let blocks = filter.bloom_possibilities().iter()
-			.filter_map(|bloom| self.blocks_with_bloom(bloom, filter.from_block.clone(), filter.to_block.clone()))
-			.flat_map(|m| m)
-			// remove duplicate elements
-			.collect::<HashSet<u64>>()
-			.into_iter()
-			.collect::<Vec<u64>>();
+		let blocks = match self.indexed_log_blocks(&filter) {
+			Some(blocks) => blocks,
+			None => filter.bloom_possibilities().iter()
+				.filter_map(|bloom| self.blocks_with_bloom(bloom, filter.from_block.clone(), filter.to_block.clone()))
+				.flat_map(|m| m)
+				// remove duplicate elements
+				.collect::<HashSet<u64>>()
+				.into_iter()
+				.collect::<Vec<u64>>(),
+		};
 
 		self.chain.read().logs(blocks, |entry| filter.matches(entry), filter.limit)
 	}
@@ -1523,6 +1831,20 @@ impl BlockChainClient for Client {
 		self.miner.ready_transactions(number, timestamp)
 	}
 
+	fn pending_transaction(&self, hash: H256) -> Option<PendingTransaction> {
+		self.miner.transaction(self.chain.read().best_block_number(), &hash)
+	}
+
+	fn local_transaction_hashes(&self) -> Vec<H256> {
+		self.miner.local_transactions().into_iter()
+			.filter(|&(_, ref status)| match *status {
+				LocalTransactionStatus::Pending | LocalTransactionStatus::Future => true,
+				_ => false,
+			})
+			.map(|(hash, _)| hash)
+			.collect()
+	}
+
 	fn queue_consensus_message(&self, message: Bytes) {
 		let channel = self.io_channel.lock().clone();
 		if let Err(e) = channel.send(ClientIoMessage::NewMessage(message)) {
@@ -1544,6 +1866,11 @@ impl BlockChainClient for Client {
 			.map(|header| self.engine.extra_info(&header.decode()))
 	}
 
+	fn is_out_of_range(&self, id: BlockId) -> bool {
+		let chain = self.chain.read();
+		Self::block_hash(&chain, id).map_or(false, |hash| chain.is_known(&hash)) && self.block_hash_in_range(&chain, id).is_none()
+	}
+
 	fn pruning_info(&self) -> PruningInfo {
 		PruningInfo {
 			earliest_chain: self.chain.read().first_block_number().unwrap_or(1),
@@ -1551,6 +1878,101 @@ impl BlockChainClient for Client {
 		}
 	}
 
+	fn reorg_alert(&self) -> Option<ReorgAlert> {
+		self.reorg_alert.read().clone()
+	}
+
+	fn disk_space(&self) -> DiskSpace {
+		self.disk_guard.level()
+	}
+
+	fn token_index_head(&self) -> Option<BlockNumber> {
+		self.token_index.as_ref().map(|index| index.head().map_or(0, |(number, _)| number))
+	}
+
+	fn token_transfers(&self, token: &Address, holder: &Address, from: BlockId, to: BlockId, limit: usize) -> Option<Vec<TokenTransfer>> {
+		let index = match self.token_index {
+			Some(ref index) => index,
+			None => return None,
+		};
+		match (self.block_number(from), self.block_number(to)) {
+			(Some(from), Some(to)) => Some(index.transfers(token, holder, from, to, limit)),
+			_ => None,
+		}
+	}
+
+	fn token_balance(&self, token: &Address, holder: &Address, id: BlockId) -> Option<U256> {
+		let index = match self.token_index {
+			Some(ref index) => index,
+			None => return None,
+		};
+		self.block_number(id).map(|number| index.balance(token, holder, number))
+	}
+
+	fn balance_history(&self, address: &Address, from: BlockId, to: BlockId, limit: usize) -> Option<Vec<BalanceChange>> {
+		let index = match self.balance_index {
+			Some(ref index) => index,
+			None => return None,
+		};
+		let (from, to) = match (self.block_number(from), self.block_number(to)) {
+			(Some(from), Some(to)) => (from, to),
+			_ => return None,
+		};
+		let chain = self.chain.read();
+		Some(index.history(address, from, to, limit, |number, hash| chain.block_hash(number).map_or(false, |h| &h == hash)))
+	}
+
+	fn author_statistics(&self, from: BlockId, to: BlockId) -> Option<Vec<AuthorStatistics>> {
+		let (from, to) = match (self.block_number(from), self.block_number(to)) {
+			(Some(from), Some(to)) => (from, to),
+			_ => return None,
+		};
+		let chain = self.chain.read();
+		let header_at = |number| chain.block_hash(number).and_then(|hash| chain.block_header(&hash));
+
+		let mut statistics = BTreeMap::new();
+		let mut parent = if from == 0 { None } else { header_at(from - 1) };
+		for number in from..to + 1 {
+			let header = match header_at(number) {
+				Some(header) => header,
+				None => return None,
+			};
+			if let Some(ref parent) = parent {
+				for (author, missed) in self.engine.missed_turns(parent, &header) {
+					statistics.entry(author).or_insert_with(|| AuthorStatistics::new(author)).missed_steps += missed;
+				}
+			}
+			{
+				let author = statistics.entry(*header.author()).or_insert_with(|| AuthorStatistics::new(*header.author()));
+				author.sealed_blocks += 1;
+				author.last_block = Some(number);
+				author.last_seen = Some(header.timestamp());
+			}
+			parent = Some(header);
+		}
+		Some(statistics.into_iter().map(|(_, s)| s).collect())
+	}
+
+	fn import_statistics(&self) -> ImportStatistics {
+		let mut statistics = self.import_stats.statistics();
+		statistics.queued_blocks = self.block_queue.queue_info().total_queue_size() as u64;
+		statistics.gas_backlog = self.block_queue.total_gas();
+		statistics
+	}
+
+	fn acknowledge_reorg(&self) {
+		let alert = match self.reorg_alert.write().take() {
+			Some(alert) => alert,
+			None => return,
+		};
+
+		info!(target: "client", "Deep reorganisation to #{} ({}) acknowledged, resuming block import.", alert.block_number, alert.block_hash);
+		*self.allowed_reorg.lock() = Some(alert.block_hash);
+		if let Err(e) = self.io_channel.lock().send(ClientIoMessage::BlockVerified) {
+			debug!(target: "client", "Unable to resume block import: {}", e);
+		}
+	}
+
 	fn call_contract(&self, block_id: BlockId, address: Address, data: Bytes) -> Result<Bytes, String> {
 		let transaction = self.contract_call_tx(block_id, address, data);
 
@@ -1654,6 +2076,7 @@ impl MiningBlockChainClient for Client {
 	}
 
 	fn import_sealed_block(&self, block: SealedBlock) -> ImportResult {
+		self.check_block_range(block.header().number(), false)?;
 		let h = block.header().hash();
 		let start = precise_time_ns();
 		let route = {
@@ -1749,16 +2172,21 @@ impl Drop for Client {
 
 /// Returns `LocalizedReceipt` given `LocalizedTransaction`
 /// and a vector of receipts from given block up to transaction index.
-fn transaction_receipt(schedule: &Schedule, mut tx: LocalizedTransaction, mut receipts: Vec<Receipt>) -> LocalizedReceipt {
+fn transaction_receipt(schedule: &Schedule, tx: LocalizedTransaction, mut receipts: Vec<Receipt>) -> LocalizedReceipt {
 	assert_eq!(receipts.len(), tx.transaction_index + 1, "All previous receipts are provided.");
 
-	let sender = tx.sender();
 	let receipt = receipts.pop().expect("Current receipt is provided; qed");
 	let prior_gas_used = match tx.transaction_index {
 		0 => 0.into(),
 		i => receipts.get(i - 1).expect("All previous receipts are provided; qed").gas_used,
 	};
 	let no_of_logs = receipts.into_iter().map(|receipt| receipt.logs.len()).sum::<usize>();
+	localize_receipt(schedule, tx, receipt, prior_gas_used, no_of_logs)
+}
+
+/// Localize receipt of the transaction, given gas used and number of logs of the previous transactions in the block.
+fn localize_receipt(schedule: &Schedule, mut tx: LocalizedTransaction, receipt: Receipt, prior_gas_used: U256, no_of_logs: usize) -> LocalizedReceipt {
+	let sender = tx.sender();
 	let transaction_hash = tx.hash();
 	let block_hash = tx.block_hash;
 	let block_number = tx.block_number;
@@ -1825,6 +2253,25 @@ mod tests {
 		assert!(client.tree_route(&genesis, &new_hash).is_none());
 	}
 
+	#[test]
+	fn should_stay_awake_until_requested() {
+		use std::time::{Instant, Duration};
+		use super::SleepState;
+
+		let now = Instant::now();
+		let timeout = Duration::from_secs(60);
+		let mut ss = SleepState::new(false);
+		assert!(!ss.is_idle(now, timeout));
+
+		ss.awake_until = Some(now + Duration::from_secs(300));
+		assert!(!ss.is_idle(now + Duration::from_secs(200), timeout));
+		assert!(ss.is_idle(now + Duration::from_secs(301), timeout));
+
+		ss.last_activity = Some(now + Duration::from_secs(280));
+		assert!(!ss.is_idle(now + Duration::from_secs(320), timeout));
+		assert!(ss.is_idle(now + Duration::from_secs(341), timeout));
+	}
+
 	#[test]
 	fn should_return_correct_log_index() {
 		use super::transaction_receipt;
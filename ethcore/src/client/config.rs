@@ -24,6 +24,9 @@ pub use evm::VMType;
 
 use verification::{VerifierType, QueueConfig};
 use util::{journaldb, CompactionProfile};
+use header::BlockNumber;
+use client::{SpanExportConfig, DiskGuardConfig};
+use snapshot::{ThrottleConfig, RetentionPolicy};
 
 /// Client state db compaction profile
 #[derive(Debug, PartialEq, Clone)]
@@ -98,6 +101,59 @@ impl Display for Mode {
 	}
 }
 
+/// Range of blocks imported and served by the client.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BlockRange {
+	/// Blocks between given numbers, inclusive.
+	Fixed(BlockNumber, BlockNumber),
+	/// Given number of most recent blocks.
+	Recent(u64),
+}
+
+impl BlockRange {
+	/// First block in range for given best block.
+	pub fn first(&self, best_block: BlockNumber) -> BlockNumber {
+		match *self {
+			BlockRange::Fixed(first, _) => first,
+			BlockRange::Recent(count) => best_block.saturating_sub(count.saturating_sub(1)),
+		}
+	}
+
+	/// Last block in range, if bounded.
+	pub fn last(&self) -> Option<BlockNumber> {
+		match *self {
+			BlockRange::Fixed(_, last) => Some(last),
+			BlockRange::Recent(_) => None,
+		}
+	}
+
+	/// Checks if block with given number is within the range.
+	pub fn contains(&self, number: BlockNumber, best_block: BlockNumber) -> bool {
+		number >= self.first(best_block) && self.last().map_or(true, |last| number <= last)
+	}
+}
+
+impl FromStr for BlockRange {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let parse = |n: &str| n.trim().parse::<u64>().map_err(|_| format!("Invalid block number given: {}", n));
+		match s.find("..") {
+			Some(pos) => {
+				let (first, last) = (parse(&s[..pos])?, parse(&s[pos + 2..])?);
+				if first > last {
+					return Err(format!("Invalid block range given: {}. First block is above the last one.", s));
+				}
+				Ok(BlockRange::Fixed(first, last))
+			},
+			None => match parse(s)? {
+				0 => Err("Invalid block range given. Expected a positive number of recent blocks.".into()),
+				count => Ok(BlockRange::Recent(count)),
+			},
+		}
+	}
+}
+
 /// Client configuration. Includes configs for all sub-systems.
 #[derive(Debug, PartialEq, Default)]
 pub struct ClientConfig {
@@ -137,11 +193,33 @@ pub struct ClientConfig {
 	pub history_mem: usize,
 	/// Check seal valididity on block import
 	pub check_seal: bool,
+	/// Maximal accepted chain reorganisation depth. Deeper reorganisations pause block import.
+	pub max_reorg_depth: Option<u64>,
+	/// URLs notified about reorganisations deeper than `max_reorg_depth`.
+	pub reorg_alert_urls: Vec<String>,
+	/// Blocks outside of this range are not imported nor served.
+	pub block_range: Option<BlockRange>,
+	/// Maintain the index of ERC-20 token transfers.
+	pub token_index: bool,
+	/// Record balances of accounts modified by imported blocks.
+	pub balance_history: bool,
+	/// Maintain the index of blocks by log addresses and topics.
+	pub log_index: bool,
+	/// Export trace spans of block imports to a Zipkin compatible collector.
+	pub span_export: Option<SpanExportConfig>,
+	/// Number of threads chunking the state when taking a snapshot.
+	pub snapshot_threads: usize,
+	/// Limits of the snapshot creation rate.
+	pub snapshot_throttle: ThrottleConfig,
+	/// Which of the previous snapshots to keep on disk.
+	pub snapshot_retention: RetentionPolicy,
+	/// Free space thresholds of the database volume.
+	pub disk_guard: DiskGuardConfig,
 }
 
 #[cfg(test)]
 mod test {
-	use super::{DatabaseCompactionProfile, Mode};
+	use super::{DatabaseCompactionProfile, Mode, BlockRange};
 
 	#[test]
 	fn test_default_compaction_profile() {
@@ -159,4 +237,29 @@ mod test {
 	fn test_mode_default() {
 		assert_eq!(Mode::default(), Mode::Active);
 	}
+
+	#[test]
+	fn test_parsing_block_range() {
+		assert_eq!(BlockRange::Fixed(1000, 2000), "1000..2000".parse().unwrap());
+		assert_eq!(BlockRange::Recent(1_000_000), "1000000".parse().unwrap());
+		assert!("2000..1000".parse::<BlockRange>().is_err());
+		assert!("0".parse::<BlockRange>().is_err());
+		assert!("abc..10".parse::<BlockRange>().is_err());
+	}
+
+	#[test]
+	fn test_block_range_contains() {
+		let fixed = BlockRange::Fixed(10, 20);
+		assert!(fixed.contains(10, 100));
+		assert!(fixed.contains(20, 100));
+		assert!(!fixed.contains(21, 100));
+		assert!(!fixed.contains(9, 100));
+
+		let recent = BlockRange::Recent(10);
+		assert_eq!(recent.first(100), 91);
+		assert_eq!(recent.first(5), 0);
+		assert!(recent.contains(91, 100));
+		assert!(!recent.contains(90, 100));
+		assert!(recent.contains(150, 100));
+	}
 }
@@ -0,0 +1,155 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Guard against running out of space on the database volume.
+//!
+//! Free space is checked before every import and on each client tick.
+//! Below the configured thresholds the client progressively warns, stops
+//! taking and serving snapshots, pauses ancient blocks download and finally halts
+//! block import, so that the database is never left half-written by a full disk.
+
+use std::path::PathBuf;
+use util::RwLock;
+use util::disk::available_space;
+use types::disk_space::DiskSpace;
+
+const MB: u64 = 1024 * 1024;
+
+/// Free space thresholds in bytes, `0` disables the action.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DiskGuardConfig {
+	/// Warn below this threshold.
+	pub warn: u64,
+	/// Stop taking and serving snapshots below this threshold.
+	pub stop_snapshots: u64,
+	/// Pause ancient blocks download below this threshold.
+	pub pause_ancient_blocks: u64,
+	/// Halt block import below this threshold.
+	pub halt_import: u64,
+	/// Path on the watched volume, set by the client service.
+	pub path: Option<PathBuf>,
+}
+
+impl DiskGuardConfig {
+	/// Level of given available space.
+	pub fn level(&self, available: u64) -> DiskSpace {
+		let below = |threshold: u64| available < threshold;
+		if below(self.halt_import) {
+			DiskSpace::Exhausted
+		} else if below(self.pause_ancient_blocks) {
+			DiskSpace::NoAncientBlocks
+		} else if below(self.stop_snapshots) {
+			DiskSpace::NoSnapshots
+		} else if below(self.warn) {
+			DiskSpace::Low
+		} else {
+			DiskSpace::Sufficient
+		}
+	}
+
+	fn is_enabled(&self) -> bool {
+		self.path.is_some() && (self.warn > 0 || self.stop_snapshots > 0 || self.pause_ancient_blocks > 0 || self.halt_import > 0)
+	}
+}
+
+/// Tracks the disk space level of the database volume.
+pub struct DiskGuard {
+	config: DiskGuardConfig,
+	level: RwLock<DiskSpace>,
+}
+
+impl DiskGuard {
+	/// Create new guard.
+	pub fn new(config: DiskGuardConfig) -> Self {
+		DiskGuard {
+			config: config,
+			level: RwLock::new(DiskSpace::Sufficient),
+		}
+	}
+
+	/// Level determined by the last check.
+	pub fn level(&self) -> DiskSpace {
+		*self.level.read()
+	}
+
+	/// Check the available space and update the level.
+	/// The level is kept if the space can't be determined.
+	pub fn check(&self) -> DiskSpace {
+		if !self.config.is_enabled() {
+			return DiskSpace::Sufficient;
+		}
+
+		let path = self.config.path.as_ref().expect("checked by is_enabled; qed");
+		let available = match available_space(path) {
+			Ok(available) => available,
+			Err(e) => {
+				debug!(target: "client", "Unable to determine free space of {}: {}", path.display(), e);
+				return self.level();
+			},
+		};
+
+		let level = self.config.level(available);
+		let previous = ::std::mem::replace(&mut *self.level.write(), level);
+		if level != previous {
+			let free = available / MB;
+			match level {
+				DiskSpace::Sufficient => info!(target: "client", "Disk space recovered ({} MB free), resuming normal operation.", free),
+				DiskSpace::Low => warn!(target: "client", "Low disk space: {} MB free.", free),
+				DiskSpace::NoSnapshots => warn!(target: "client", "Low disk space: {} MB free. Snapshots are not taken nor served.", free),
+				DiskSpace::NoAncientBlocks => warn!(target: "client", "Low disk space: {} MB free. Ancient blocks download is paused.", free),
+				DiskSpace::Exhausted => warn!(target: "client", "Disk space exhausted: {} MB free. Block import is halted until space is freed.", free),
+			}
+		}
+		level
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use types::disk_space::DiskSpace;
+	use super::DiskGuardConfig;
+
+	#[test]
+	fn should_determine_level() {
+		let config = DiskGuardConfig {
+			warn: 400,
+			stop_snapshots: 300,
+			pause_ancient_blocks: 200,
+			halt_import: 100,
+			path: None,
+		};
+
+		assert_eq!(config.level(1000), DiskSpace::Sufficient);
+		assert_eq!(config.level(400), DiskSpace::Sufficient);
+		assert_eq!(config.level(399), DiskSpace::Low);
+		assert_eq!(config.level(250), DiskSpace::NoSnapshots);
+		assert_eq!(config.level(150), DiskSpace::NoAncientBlocks);
+		assert_eq!(config.level(50), DiskSpace::Exhausted);
+	}
+
+	#[test]
+	fn should_skip_disabled_thresholds() {
+		let config = DiskGuardConfig {
+			halt_import: 100,
+			..Default::default()
+		};
+
+		assert_eq!(DiskGuardConfig::default().level(0), DiskSpace::Sufficient);
+		assert_eq!(config.level(150), DiskSpace::Sufficient);
+		assert_eq!(config.level(50), DiskSpace::Exhausted);
+		assert!(DiskSpace::Exhausted > DiskSpace::NoAncientBlocks);
+	}
+}
@@ -0,0 +1,134 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Rolling statistics of block import stages.
+
+use std::collections::VecDeque;
+use stats::Corpus;
+use util::{U256, Uint, Mutex};
+use types::import_statistics::{ImportStatistics, Percentiles};
+use super::spans::{BlockSpans, QUEUED_VERIFICATION};
+
+/// Number of recently imported blocks kept for the statistics.
+const MAX_SAMPLES: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+	verification: u64,
+	execution: u64,
+	commit: u64,
+	gas_per_sec: u64,
+}
+
+/// Collects timings of imported blocks.
+pub struct ImportStats {
+	samples: Mutex<VecDeque<Sample>>,
+}
+
+impl ImportStats {
+	/// Create a new collector.
+	pub fn new() -> Self {
+		ImportStats {
+			samples: Mutex::new(VecDeque::with_capacity(MAX_SAMPLES)),
+		}
+	}
+
+	/// Record a successfully committed block. Rejected blocks and proposals are ignored.
+	pub fn record(&self, spans: &BlockSpans, gas_used: &U256) {
+		if spans.error.is_some() || !spans.stages.iter().any(|s| s.name == "commit") {
+			return;
+		}
+		// the root span also covers verification in the queue and waiting for the import.
+		let duration = |prefix: &str| spans.stages.iter()
+			.filter(|s| s.name != QUEUED_VERIFICATION && s.name.starts_with(prefix))
+			.fold(0, |acc, s| acc + s.duration);
+		let sample = Sample {
+			verification: duration("verify_"),
+			execution: duration("execute"),
+			commit: duration("commit"),
+			gas_per_sec: match duration("") {
+				0 => 0,
+				d => (*gas_used * U256::from(1_000_000) / U256::from(d)).low_u64(),
+			},
+		};
+
+		let mut samples = self.samples.lock();
+		if samples.len() == MAX_SAMPLES {
+			samples.pop_front();
+		}
+		samples.push_back(sample);
+	}
+
+	/// Percentiles of the recorded samples. Queue related fields are left empty.
+	pub fn statistics(&self) -> ImportStatistics {
+		let samples = self.samples.lock();
+		ImportStatistics {
+			samples: samples.len() as u64,
+			verification_time: percentiles(&samples, |s| s.verification),
+			execution_time: percentiles(&samples, |s| s.execution),
+			commit_time: percentiles(&samples, |s| s.commit),
+			gas_per_sec: percentiles(&samples, |s| s.gas_per_sec),
+			..Default::default()
+		}
+	}
+}
+
+fn percentiles<F>(samples: &VecDeque<Sample>, f: F) -> Percentiles where F: Fn(&Sample) -> u64 {
+	let corpus: Corpus<u64> = samples.iter().map(f).collect();
+	Percentiles {
+		p50: corpus.percentile(50).cloned().unwrap_or(0),
+		p90: corpus.percentile(90).cloned().unwrap_or(0),
+		p99: corpus.percentile(99).cloned().unwrap_or(0),
+		max: corpus.last().cloned().unwrap_or(0),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::{H256, U256};
+	use client::spans::{BlockSpans, Span};
+	use super::ImportStats;
+
+	fn spans(execution: u64, error: Option<String>) -> BlockSpans {
+		let span = |name, duration| Span { name: name, timestamp: 0, duration: duration };
+		BlockSpans {
+			hash: H256::from(1),
+			number: 1,
+			root: span("import_block", 100 + execution),
+			stages: vec![span("verify_unordered", 500), span("verify_family", 20), span("verify_external", 10), span("execute", execution), span("commit", 50)],
+			error: error,
+		}
+	}
+
+	#[test]
+	fn should_compute_percentiles() {
+		let stats = ImportStats::new();
+		for i in 1..101 {
+			stats.record(&spans(i * 100, None), &U256::from(1_000_000));
+		}
+		stats.record(&spans(1_000_000, Some("Invalid block".into())), &U256::from(1_000_000));
+
+		let statistics = stats.statistics();
+		assert_eq!(statistics.samples, 100);
+		assert_eq!(statistics.verification_time.p50, 30);
+		assert_eq!(statistics.execution_time.p50, 5_100);
+		assert_eq!(statistics.execution_time.p90, 9_100);
+		assert_eq!(statistics.execution_time.max, 10_000);
+		assert_eq!(statistics.commit_time.p99, 50);
+		// 1 Mgas in 0.18ms
+		assert_eq!(statistics.gas_per_sec.max, 5_555_555_555);
+	}
+}
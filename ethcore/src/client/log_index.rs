@@ -0,0 +1,251 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Index of blocks by log addresses and topics.
+//!
+//! For every block of the canonical chain an empty entry is stored under
+//! `kind ++ address or topic ++ block number` for each distinct log address
+//! (kind 0) and each distinct topic at position `i` (kind `i + 1`).
+//! Blocks possibly matching a filter are the intersection over the filter's
+//! constraints of the union of blocks of their alternatives. Unlike blooms
+//! this gives no false positives on block level and reads only the matching blocks.
+//!
+//! The index follows the canonical chain behind the import, a limited number
+//! of blocks at a time, so it can be enabled on an existing database.
+
+use std::cmp;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use byteorder::{ByteOrder, BigEndian};
+use rlp::{RlpStream, UntrustedRlp};
+use util::{H256, KeyValueDB, DBTransaction, Mutex};
+use blockchain::{BlockChain, BlockProvider};
+use db::COL_LOG_INDEX;
+use filter::Filter;
+use header::BlockNumber;
+
+/// Key of the last indexed block.
+const HEAD_KEY: &'static [u8] = b"head";
+
+/// Kind of entries of log addresses. Entries of topics at position `i` are of kind `i + 1`.
+const ADDRESS_KIND: u8 = 0;
+
+fn prefix(kind: u8, item: &[u8]) -> Vec<u8> {
+	let mut key = Vec::with_capacity(1 + item.len() + 8);
+	key.push(kind);
+	key.extend_from_slice(item);
+	key
+}
+
+fn entry_key(kind: u8, item: &[u8], number: BlockNumber) -> Vec<u8> {
+	let mut key = prefix(kind, item);
+	let mut buf = [0u8; 8];
+	BigEndian::write_u64(&mut buf, number);
+	key.extend_from_slice(&buf);
+	key
+}
+
+/// Keys of the entries of a block.
+fn block_keys(chain: &BlockChain, hash: &H256) -> Vec<Vec<u8>> {
+	let (number, receipts) = match (chain.block_number(hash), chain.block_receipts(hash)) {
+		(Some(number), Some(receipts)) => (number, receipts.receipts),
+		_ => {
+			warn!(target: "client", "Receipts of block {} not found, its logs are not indexed", hash);
+			return Vec::new();
+		},
+	};
+
+	let keys: BTreeSet<_> = receipts.iter()
+		.flat_map(|receipt| receipt.logs.iter())
+		.flat_map(|log| {
+			let address = entry_key(ADDRESS_KIND, &*log.address, number);
+			let topics = log.topics.iter().enumerate().map(move |(i, topic)| entry_key(i as u8 + 1, &**topic, number));
+			Some(address).into_iter().chain(topics)
+		})
+		.collect();
+	keys.into_iter().collect()
+}
+
+/// Log index.
+pub struct LogIndex {
+	db: Arc<KeyValueDB>,
+	update_lock: Mutex<()>,
+}
+
+impl LogIndex {
+	/// Create the index stored in the given database.
+	pub fn new(db: Arc<KeyValueDB>) -> Self {
+		LogIndex {
+			db: db,
+			update_lock: Mutex::new(()),
+		}
+	}
+
+	/// Number and hash of the last indexed block.
+	pub fn head(&self) -> Option<(BlockNumber, H256)> {
+		self.db.get(COL_LOG_INDEX, HEAD_KEY).expect("Low-level database error. Some issue with your hard disk?")
+			.and_then(|value| {
+				let rlp = UntrustedRlp::new(&value);
+				match (rlp.val_at(0), rlp.val_at(1)) {
+					(Ok(number), Ok(hash)) => Some((number, hash)),
+					_ => None,
+				}
+			})
+	}
+
+	/// Follow the canonical chain: remove entries of retracted blocks
+	/// and index at most `max_blocks` new ones. Does nothing if an update is already running.
+	pub fn update(&self, chain: &BlockChain, max_blocks: u64) {
+		let _lock = match self.update_lock.try_lock() {
+			Some(lock) => lock,
+			None => return,
+		};
+
+		let best_hash = chain.best_block_hash();
+		let best_number = chain.best_block_number();
+		let mut batch = DBTransaction::new();
+		let (mut number, mut hash) = match self.head() {
+			Some((_, ref hash)) if *hash == best_hash => return,
+			Some((number, hash)) if chain.block_hash(number) == Some(hash) => (number, hash),
+			Some((number, hash)) => match chain.tree_route(hash, best_hash) {
+				Some(route) => {
+					for retracted in &route.blocks[..route.index] {
+						for key in block_keys(chain, retracted) {
+							batch.delete(COL_LOG_INDEX, &key);
+						}
+					}
+					(number - route.index as u64, route.ancestor)
+				},
+				None => {
+					warn!(target: "client", "Last block of the log index {} is unknown, the index is not updated", hash);
+					return;
+				},
+			},
+			// genesis has no transactions
+			None => (0, chain.genesis_hash()),
+		};
+
+		let last = cmp::min(best_number, number + max_blocks);
+		while number < last {
+			let next = match chain.block_hash(number + 1) {
+				Some(next) => next,
+				None => break,
+			};
+			// reorganised in the meantime, continue with the next update
+			if chain.block_header(&next).map_or(true, |header| *header.parent_hash() != hash) {
+				break;
+			}
+			for key in block_keys(chain, &next) {
+				batch.put(COL_LOG_INDEX, &key, &[]);
+			}
+			number += 1;
+			hash = next;
+		}
+
+		let mut head = RlpStream::new_list(2);
+		head.append(&number);
+		head.append(&hash);
+		batch.put(COL_LOG_INDEX, HEAD_KEY, &head.out());
+		self.db.write(batch).expect("Low-level database error. Some issue with your hard disk?");
+	}
+
+	/// Blocks `from` to `to` with a log of the address or with the topic at the position.
+	fn item_blocks(&self, kind: u8, item: &[u8], from: BlockNumber, to: BlockNumber) -> BTreeSet<BlockNumber> {
+		let prefix = prefix(kind, item);
+		let key_len = prefix.len() + 8;
+		self.db.iter_from_prefix(COL_LOG_INDEX, &prefix)
+			.take_while(|&(ref key, _)| key.starts_with(&prefix))
+			.filter(|&(ref key, _)| key.len() == key_len)
+			.map(|(key, _)| BigEndian::read_u64(&key[key_len - 8..]))
+			.skip_while(|number| *number < from)
+			.take_while(|number| *number <= to)
+			.collect()
+	}
+
+	/// Blocks `from` to `to` which may contain logs matching the filter, in ascending order.
+	/// `None` if the filter has no address nor topic constraint, so every block may match.
+	pub fn blocks(&self, filter: &Filter, from: BlockNumber, to: BlockNumber) -> Option<Vec<BlockNumber>> {
+		let addresses = filter.address.iter()
+			.filter(|addresses| !addresses.is_empty())
+			.map(|addresses| addresses.iter().map(|address| (ADDRESS_KIND, address.to_vec())).collect::<Vec<_>>());
+		let topics = filter.topics.iter().enumerate()
+			.filter_map(|(i, topics)| topics.as_ref().map(|topics| (i, topics)))
+			.filter(|&(_, topics)| !topics.is_empty())
+			.map(|(i, topics)| topics.iter().map(|topic| (i as u8 + 1, topic.to_vec())).collect::<Vec<_>>());
+
+		let mut blocks: Option<BTreeSet<BlockNumber>> = None;
+		for alternatives in addresses.chain(topics) {
+			let matching = alternatives.iter()
+				.flat_map(|&(kind, ref item)| self.item_blocks(kind, item, from, to))
+				.collect::<BTreeSet<_>>();
+			blocks = Some(match blocks {
+				Some(blocks) => blocks.intersection(&matching).cloned().collect(),
+				None => matching,
+			});
+		}
+		blocks.map(|blocks| blocks.into_iter().collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use util::{H256, Address, DBTransaction};
+	use util::kvdb::in_memory;
+	use client::BlockId;
+	use db::COL_LOG_INDEX;
+	use filter::Filter;
+	use super::{LogIndex, ADDRESS_KIND, entry_key};
+
+	fn filter(address: Option<Vec<Address>>, topics: Vec<Option<Vec<H256>>>) -> Filter {
+		Filter {
+			from_block: BlockId::Earliest,
+			to_block: BlockId::Latest,
+			address: address,
+			topics: topics,
+			limit: None,
+		}
+	}
+
+	#[test]
+	fn should_find_blocks_matching_filter() {
+		let index = LogIndex::new(Arc::new(in_memory(::db::NUM_COLUMNS.unwrap_or(0))));
+		let mut batch = DBTransaction::new();
+		// block 1: log of address 1 with topics [10, 20]
+		// block 2: log of address 2 with topics [10]
+		// block 3: logs of address 1 with topics [20, 10] and address 2 with topics [30]
+		let entries: Vec<(u8, Vec<u8>, u64)> = vec![
+			(ADDRESS_KIND, Address::from(1).to_vec(), 1), (1, H256::from(10).to_vec(), 1), (2, H256::from(20).to_vec(), 1),
+			(ADDRESS_KIND, Address::from(2).to_vec(), 2), (1, H256::from(10).to_vec(), 2),
+			(ADDRESS_KIND, Address::from(1).to_vec(), 3), (1, H256::from(20).to_vec(), 3), (2, H256::from(10).to_vec(), 3),
+			(ADDRESS_KIND, Address::from(2).to_vec(), 3), (1, H256::from(30).to_vec(), 3),
+		];
+		for (kind, item, number) in entries {
+			batch.put(COL_LOG_INDEX, &entry_key(kind, &item, number), &[]);
+		}
+		index.db.write(batch).unwrap();
+
+		assert_eq!(index.blocks(&filter(None, vec![None, None, None, None]), 0, 10), None);
+		assert_eq!(index.blocks(&filter(Some(vec![]), vec![None, None, None, None]), 0, 10), None);
+		assert_eq!(index.blocks(&filter(Some(vec![Address::from(1)]), vec![None, None, None, None]), 0, 10), Some(vec![1, 3]));
+		assert_eq!(index.blocks(&filter(Some(vec![Address::from(1), Address::from(2)]), vec![None, None, None, None]), 0, 10), Some(vec![1, 2, 3]));
+		assert_eq!(index.blocks(&filter(Some(vec![Address::from(1)]), vec![Some(vec![H256::from(10)]), None, None, None]), 0, 10), Some(vec![1]));
+		assert_eq!(index.blocks(&filter(None, vec![None, Some(vec![H256::from(10)]), None, None]), 0, 10), Some(vec![3]));
+		assert_eq!(index.blocks(&filter(None, vec![Some(vec![H256::from(10), H256::from(30)]), None, None, None]), 2, 10), Some(vec![2, 3]));
+		assert_eq!(index.blocks(&filter(Some(vec![Address::from(3)]), vec![None, None, None, None]), 0, 10), Some(vec![]));
+		assert_eq!(index.blocks(&filter(Some(vec![Address::from(1)]), vec![None, None, None, None]), 2, 2), Some(vec![]));
+	}
+}
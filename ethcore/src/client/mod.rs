@@ -21,10 +21,20 @@ mod error;
 mod test_client;
 mod trace;
 mod client;
+mod reorg;
+mod spans;
+mod import_stats;
+mod token_index;
+mod balance_index;
+mod log_index;
+mod disk_guard;
 
 pub use self::client::*;
-pub use self::config::{Mode, ClientConfig, DatabaseCompactionProfile, BlockChainConfig, VMType};
+pub use self::config::{Mode, ClientConfig, DatabaseCompactionProfile, BlockChainConfig, VMType, BlockRange};
 pub use self::error::Error;
+pub use self::spans::{SpanExportConfig, Span, QUEUED_VERIFICATION};
+pub use self::disk_guard::DiskGuardConfig;
+pub use self::reorg::AlertPoster;
 pub use self::test_client::{TestBlockChainClient, EachBlockWith};
 pub use self::chain_notify::ChainNotify;
 pub use self::traits::{BlockChainClient, MiningBlockChainClient, EngineClient};
@@ -34,6 +44,12 @@ pub use self::traits::ProvingBlockChainClient;
 pub use types::ids::*;
 pub use types::trace_filter::Filter as TraceFilter;
 pub use types::pruning_info::PruningInfo;
+pub use types::reorg_alert::ReorgAlert;
+pub use types::import_statistics::{ImportStatistics, Percentiles as ImportPercentiles};
+pub use types::token_transfer::TokenTransfer;
+pub use types::balance_change::BalanceChange;
+pub use types::author_statistics::AuthorStatistics;
+pub use types::disk_space::DiskSpace;
 pub use types::call_analytics::CallAnalytics;
 
 pub use executive::{Executed, Executive, TransactOptions};
@@ -0,0 +1,143 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Deep reorganisation alert notifications.
+
+use std::io::Write;
+use hyper::{self, Next, Url};
+use hyper::header::ContentType;
+use hyper::method::Method;
+use hyper::client::{Request, Response, Client};
+use hyper::net::HttpStream;
+use util::Mutex;
+use types::reorg_alert::ReorgAlert;
+
+/// Posts alerts, such as deep reorganisations, to a list of webhook URLs.
+pub struct AlertPoster {
+	urls: Vec<Url>,
+	client: Mutex<Client<PostHandler>>,
+}
+
+impl AlertPoster {
+	/// Create a new poster. Invalid URLs are skipped.
+	pub fn new(urls: &[String]) -> Self {
+		let urls = urls.into_iter().filter_map(|u| {
+			match Url::parse(u) {
+				Ok(url) => Some(url),
+				Err(e) => {
+					warn!("Error parsing alert URL {} : {}", u, e);
+					None
+				}
+			}
+		}).collect();
+
+		AlertPoster {
+			urls: urls,
+			client: Mutex::new(AlertPoster::create_client()),
+		}
+	}
+
+	fn create_client() -> Client<PostHandler> {
+		Client::<PostHandler>::configure()
+			.keep_alive(true)
+			.build()
+			.expect("Error creating HTTP client")
+	}
+
+	/// Post the reorganisation alert to all URLs.
+	pub fn notify(&self, alert: &ReorgAlert) {
+		self.post(&alert_json(alert));
+	}
+
+	/// Post a JSON body to all URLs.
+	pub fn post(&self, body: &str) {
+		let mut client = self.client.lock();
+		for u in &self.urls {
+			if let Err(e) = client.request(u.clone(), PostHandler { body: body.to_owned() }) {
+				warn!("Error sending alert to {} : {}, retrying", u, e);
+				*client = AlertPoster::create_client();
+				if let Err(e) = client.request(u.clone(), PostHandler { body: body.to_owned() }) {
+					warn!("Error sending alert to {} : {}", u, e);
+				}
+			}
+		}
+	}
+}
+
+fn alert_json(alert: &ReorgAlert) -> String {
+	format!(
+		r#"{{ "reorgAlert": {{ "blockHash": "0x{}", "blockNumber": "0x{:x}", "bestBlockHash": "0x{}", "ancestor": "0x{}", "depth": "0x{:x}", "maxDepth": "0x{:x}" }} }}"#,
+		alert.block_hash.hex(), alert.block_number, alert.best_block_hash.hex(), alert.ancestor.hex(), alert.depth, alert.max_depth
+	)
+}
+
+/// Posts a JSON body and ignores the response.
+pub struct PostHandler {
+	pub body: String,
+}
+
+impl hyper::client::Handler<HttpStream> for PostHandler {
+	fn on_request(&mut self, request: &mut Request) -> Next {
+		request.set_method(Method::Post);
+		request.headers_mut().set(ContentType::json());
+		Next::write()
+	}
+
+	fn on_request_writable(&mut self, encoder: &mut hyper::Encoder<HttpStream>) -> Next {
+		if let Err(e) = encoder.write_all(self.body.as_bytes()) {
+			trace!("Error posting JSON: {}", e);
+		}
+		encoder.close();
+		Next::read()
+	}
+
+	fn on_response(&mut self, _response: Response) -> Next {
+		Next::end()
+	}
+
+	fn on_response_readable(&mut self, _decoder: &mut hyper::Decoder<HttpStream>) -> Next {
+		Next::end()
+	}
+
+	fn on_error(&mut self, err: hyper::Error) -> Next {
+		trace!("Error posting JSON: {}", err);
+		Next::end()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::H256;
+	use types::reorg_alert::ReorgAlert;
+	use super::alert_json;
+
+	#[test]
+	fn should_format_alert() {
+		let alert = ReorgAlert {
+			block_hash: H256::from(1),
+			block_number: 20,
+			best_block_hash: H256::from(2),
+			ancestor: H256::from(3),
+			depth: 10,
+			max_depth: 5,
+		};
+
+		let json = alert_json(&alert);
+		assert!(json.contains(r#""blockNumber": "0x14""#));
+		assert!(json.contains(r#""depth": "0xa""#));
+		assert!(json.contains(&format!("\"ancestor\": \"0x{}\"", H256::from(3).hex())));
+	}
+}
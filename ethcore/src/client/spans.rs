@@ -0,0 +1,325 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Trace spans of the block import pipeline, exported to Zipkin compatible collectors.
+
+use std::time::{Duration, Instant};
+use hyper::Url;
+use hyper::client::Client;
+use serde_json::{Map, Value};
+use time;
+use util::{H128, H256, Mutex};
+use header::BlockNumber;
+use super::reorg::PostHandler;
+
+/// Blocks buffered between exports, the oldest are dropped if the collector can't keep up.
+const MAX_BUFFERED_BLOCKS: usize = 1024;
+
+/// Name of the seal and signatures verification stage, run in the verification queue.
+pub const QUEUED_VERIFICATION: &'static str = "verify_unordered";
+
+/// Span export configuration.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpanExportConfig {
+	/// Zipkin v2 JSON endpoint, e.g. `http://localhost:9411/api/v2/spans`.
+	/// Jaeger accepts the same format on its Zipkin collector port.
+	pub url: String,
+	/// Service name attached to all spans.
+	pub service_name: String,
+}
+
+/// Timed stage of a block import.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Span {
+	/// Stage name.
+	pub name: &'static str,
+	/// Start in microseconds since the epoch.
+	pub timestamp: u64,
+	/// Duration in microseconds.
+	pub duration: u64,
+}
+
+impl Span {
+	/// Run `f` as a span called `name`, outside of any block import.
+	pub fn measure<T, F>(name: &'static str, f: F) -> (Span, T) where F: FnOnce() -> T {
+		let timestamp = now_micros();
+		let started = Instant::now();
+		let result = f();
+		let span = Span {
+			name: name,
+			timestamp: timestamp,
+			duration: micros(started.elapsed()),
+		};
+		(span, result)
+	}
+}
+
+/// Spans of a single block import. The trace is keyed by the block hash.
+/// The root starts with the first stage, which may have run in the verification queue.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlockSpans {
+	/// Hash of the imported block.
+	pub hash: H256,
+	/// Number of the imported block.
+	pub number: BlockNumber,
+	/// The whole import, parent of all stages.
+	pub root: Span,
+	/// Stages in order of execution.
+	pub stages: Vec<Span>,
+	/// Reason of the import failure.
+	pub error: Option<String>,
+}
+
+/// Measures stages of a block import.
+pub struct BlockSpansBuilder {
+	hash: H256,
+	number: BlockNumber,
+	timestamp: u64,
+	started: Instant,
+	stages: Vec<Span>,
+}
+
+impl BlockSpansBuilder {
+	/// Start measuring import of the block.
+	pub fn new(hash: H256, number: BlockNumber) -> Self {
+		BlockSpansBuilder {
+			hash: hash,
+			number: number,
+			timestamp: now_micros(),
+			started: Instant::now(),
+			stages: Vec::new(),
+		}
+	}
+
+	/// Add a stage measured before the import started, e.g. in the verification queue.
+	pub fn queued(&mut self, span: Span) {
+		let position = self.stages.iter().position(|s| s.timestamp > span.timestamp).unwrap_or(self.stages.len());
+		self.stages.insert(position, span);
+	}
+
+	/// Run `f` as a stage called `name`.
+	pub fn stage<T, F>(&mut self, name: &'static str, f: F) -> T where F: FnOnce() -> T {
+		let offset = micros(self.started.elapsed());
+		let started = Instant::now();
+		let result = f();
+		self.stages.push(Span {
+			name: name,
+			timestamp: self.timestamp + offset,
+			duration: micros(started.elapsed()),
+		});
+		result
+	}
+
+	/// Finish the import, `error` is set if the block was rejected.
+	pub fn finish(self, error: Option<String>) -> BlockSpans {
+		let end = self.timestamp + micros(self.started.elapsed());
+		let timestamp = self.stages.iter().map(|s| s.timestamp).min().map_or(self.timestamp, |t| ::std::cmp::min(t, self.timestamp));
+		BlockSpans {
+			hash: self.hash,
+			number: self.number,
+			root: Span {
+				name: "import_block",
+				timestamp: timestamp,
+				duration: end - timestamp,
+			},
+			stages: self.stages,
+			error: error,
+		}
+	}
+}
+
+fn micros(duration: Duration) -> u64 {
+	duration.as_secs() * 1_000_000 + duration.subsec_nanos() as u64 / 1000
+}
+
+fn now_micros() -> u64 {
+	let now = time::get_time();
+	now.sec as u64 * 1_000_000 + now.nsec as u64 / 1000
+}
+
+/// Buffers block spans and posts them to the collector.
+pub struct SpanExporter {
+	url: Option<Url>,
+	service_name: String,
+	buffer: Mutex<Vec<BlockSpans>>,
+	client: Mutex<Option<Client<PostHandler>>>,
+}
+
+impl SpanExporter {
+	/// Create a new exporter. Spans are dropped if the URL is invalid.
+	pub fn new(config: &SpanExportConfig) -> Self {
+		let url = match Url::parse(&config.url) {
+			Ok(url) => Some(url),
+			Err(e) => {
+				warn!("Error parsing span collector URL {} : {}", config.url, e);
+				None
+			}
+		};
+
+		SpanExporter {
+			url: url,
+			service_name: config.service_name.clone(),
+			buffer: Mutex::new(Vec::new()),
+			client: Mutex::new(None),
+		}
+	}
+
+	/// Queue spans of an imported block.
+	pub fn record(&self, spans: BlockSpans) {
+		if self.url.is_none() {
+			return;
+		}
+		let mut buffer = self.buffer.lock();
+		if buffer.len() >= MAX_BUFFERED_BLOCKS {
+			buffer.remove(0);
+		}
+		buffer.push(spans);
+	}
+
+	/// Post all buffered spans in a single request.
+	pub fn flush(&self) {
+		let url = match self.url {
+			Some(ref url) => url,
+			None => return,
+		};
+		let blocks = ::std::mem::replace(&mut *self.buffer.lock(), Vec::new());
+		if blocks.is_empty() {
+			return;
+		}
+
+		let body = zipkin_json(&self.service_name, &blocks);
+		let mut client = self.client.lock();
+		if client.is_none() {
+			*client = Client::<PostHandler>::configure()
+				.keep_alive(true)
+				.build()
+				.map_err(|e| warn!("Error creating span collector client: {}", e))
+				.ok();
+		}
+		let failed = match *client {
+			Some(ref mut client) => client.request(url.clone(), PostHandler { body: body }).is_err(),
+			None => false,
+		};
+		if failed {
+			// the client is recreated with the next batch.
+			debug!(target: "client", "Error sending spans to {}", url);
+			*client = None;
+		}
+	}
+}
+
+/// Zipkin v2 span ids are 64-bit, taken from the block hash. The lowest byte numbers the spans of the block.
+fn span_id(hash: &H256, index: usize) -> u64 {
+	let base = hash[24..].iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+	(base & !0xff) | (index as u64 + 1)
+}
+
+/// Encode spans in the Zipkin v2 JSON format.
+fn zipkin_json(service_name: &str, blocks: &[BlockSpans]) -> String {
+	let mut spans = Vec::new();
+	for block in blocks {
+		// 128-bit trace id taken from the block hash.
+		let trace_id = H128::from_slice(&block.hash[..16]).hex();
+		let root_id = format!("{:016x}", span_id(&block.hash, 0));
+		let mut tags = Map::new();
+		tags.insert("block.number".into(), Value::String(block.number.to_string()));
+		tags.insert("block.hash".into(), Value::String(format!("0x{}", block.hash.hex())));
+
+		let mut root_tags = tags.clone();
+		if let Some(ref error) = block.error {
+			root_tags.insert("error".into(), Value::String(error.clone()));
+		}
+		spans.push(json!({
+			"traceId": trace_id,
+			"id": root_id,
+			"name": block.root.name,
+			"timestamp": block.root.timestamp,
+			"duration": block.root.duration,
+			"localEndpoint": { "serviceName": service_name },
+			"tags": root_tags,
+		}));
+
+		for (index, stage) in block.stages.iter().enumerate() {
+			spans.push(json!({
+				"traceId": trace_id,
+				"id": format!("{:016x}", span_id(&block.hash, index + 1)),
+				"parentId": root_id,
+				"name": stage.name,
+				"timestamp": stage.timestamp,
+				"duration": stage.duration,
+				"localEndpoint": { "serviceName": service_name },
+				"tags": tags,
+			}));
+		}
+	}
+	Value::Array(spans).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use serde_json::{self, Value};
+	use util::H256;
+	use super::{BlockSpans, BlockSpansBuilder, Span, zipkin_json};
+
+	#[test]
+	fn should_measure_stages() {
+		let mut builder = BlockSpansBuilder::new(H256::from(5), 10);
+		let result = builder.stage("execute", || 42);
+		builder.stage("commit", || ());
+		let spans = builder.finish(None);
+
+		assert_eq!(result, 42);
+		assert_eq!(spans.stages.iter().map(|s| s.name).collect::<Vec<_>>(), vec!["execute", "commit"]);
+		assert!(spans.stages[0].timestamp >= spans.root.timestamp);
+		assert!(spans.stages[1].timestamp >= spans.stages[0].timestamp);
+	}
+
+	#[test]
+	fn should_start_root_with_queued_stage() {
+		let (seal, _) = Span::measure("verify_unordered", || ());
+		let mut builder = BlockSpansBuilder::new(H256::from(5), 10);
+		builder.queued(seal.clone());
+		builder.stage("execute", || ());
+		let spans = builder.finish(None);
+
+		assert_eq!(spans.stages.iter().map(|s| s.name).collect::<Vec<_>>(), vec!["verify_unordered", "execute"]);
+		assert_eq!(spans.root.timestamp, seal.timestamp);
+		assert!(spans.root.timestamp + spans.root.duration >= spans.stages[1].timestamp + spans.stages[1].duration);
+	}
+
+	#[test]
+	fn should_encode_zipkin_spans() {
+		let span = |name, timestamp, duration| Span { name: name, timestamp: timestamp, duration: duration };
+		let block = BlockSpans {
+			hash: H256::from_str("0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20").unwrap(),
+			number: 16,
+			root: span("import_block", 1_000_000, 300),
+			stages: vec![span("execute", 1_000_010, 200)],
+			error: Some("Invalid \"seal\"".into()),
+		};
+
+		let json: Value = serde_json::from_str(&zipkin_json("parity \"main\"", &[block])).unwrap();
+		let expected: Value = serde_json::from_str(&format!(
+			"[{}]",
+			[
+				r#"{"traceId":"0102030405060708090a0b0c0d0e0f10","id":"191a1b1c1d1e1f01","name":"import_block","timestamp":1000000,"duration":300,"localEndpoint":{"serviceName":"parity \"main\""},"tags":{"block.number":"16","block.hash":"0x0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20","error":"Invalid \"seal\""}}"#,
+				r#"{"traceId":"0102030405060708090a0b0c0d0e0f10","id":"191a1b1c1d1e1f02","parentId":"191a1b1c1d1e1f01","name":"execute","timestamp":1000010,"duration":200,"localEndpoint":{"serviceName":"parity \"main\""},"tags":{"block.number":"16","block.hash":"0x0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20"}}"#,
+			].join(",")
+		)).unwrap();
+		assert_eq!(json, expected);
+	}
+}
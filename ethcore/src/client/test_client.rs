@@ -36,11 +36,17 @@ use receipt::{Receipt, LocalizedReceipt};
 use blockchain::extras::BlockReceipts;
 use error::{ImportResult, Error as EthcoreError};
 use evm::{Factory as EvmFactory, VMType, Schedule};
-use miner::{Miner, MinerService, TransactionImportResult};
+use miner::{Miner, MinerService, TransactionImportResult, LocalTransactionStatus};
 use spec::Spec;
 use types::basic_account::BasicAccount;
 use types::mode::Mode;
 use types::pruning_info::PruningInfo;
+use types::reorg_alert::ReorgAlert;
+use types::disk_space::DiskSpace;
+use types::import_statistics::ImportStatistics;
+use types::token_transfer::TokenTransfer;
+use types::balance_change::BalanceChange;
+use types::author_statistics::AuthorStatistics;
 
 use verification::queue::QueueInfo;
 use block::{OpenBlock, SealedBlock};
@@ -96,6 +102,12 @@ pub struct TestBlockChainClient {
 	pub traces: RwLock<Option<Vec<LocalizedTrace>>>,
 	/// Pruning history size to report.
 	pub history: RwLock<Option<u64>>,
+	/// Deep reorganisation alert to report.
+	pub reorg_alert: RwLock<Option<ReorgAlert>>,
+	/// Disk space level to report.
+	pub disk_space: RwLock<DiskSpace>,
+	/// Import statistics to report.
+	pub import_statistics: RwLock<ImportStatistics>,
 }
 
 /// Used for generating test client blocks.
@@ -162,6 +174,9 @@ impl TestBlockChainClient {
 			first_block: RwLock::new(None),
 			traces: RwLock::new(None),
 			history: RwLock::new(None),
+			reorg_alert: RwLock::new(None),
+			disk_space: RwLock::new(DiskSpace::Sufficient),
+			import_statistics: RwLock::new(Default::default()),
 		};
 
 		// insert genesis hash.
@@ -480,6 +495,10 @@ impl BlockChainClient for TestBlockChainClient {
 		self.receipts.read().get(&id).cloned()
 	}
 
+	fn localized_block_receipts(&self, _id: BlockId) -> Option<Vec<LocalizedReceipt>> {
+		None
+	}
+
 	fn blocks_with_bloom(&self, _bloom: &H2048, _from_block: BlockId, _to_block: BlockId) -> Option<Vec<BlockNumber>> {
 		unimplemented!();
 	}
@@ -508,8 +527,10 @@ impl BlockChainClient for TestBlockChainClient {
 			.map(encoded::Header::new)
 	}
 
-	fn block_number(&self, _id: BlockId) -> Option<BlockNumber> {
-		unimplemented!()
+	fn block_number(&self, id: BlockId) -> Option<BlockNumber> {
+		self.block_hash(id).and_then(|hash| self.numbers.read().iter()
+			.find(|&(_, h)| *h == hash)
+			.map(|(number, _)| *number as BlockNumber))
 	}
 
 	fn block_body(&self, id: BlockId) -> Option<encoded::Body> {
@@ -716,6 +737,20 @@ impl BlockChainClient for TestBlockChainClient {
 		self.miner.ready_transactions(info.best_block_number, info.best_block_timestamp)
 	}
 
+	fn pending_transaction(&self, hash: H256) -> Option<PendingTransaction> {
+		self.miner.transaction(self.chain_info().best_block_number, &hash)
+	}
+
+	fn local_transaction_hashes(&self) -> Vec<H256> {
+		self.miner.local_transactions().into_iter()
+			.filter(|&(_, ref status)| match *status {
+				LocalTransactionStatus::Pending | LocalTransactionStatus::Future => true,
+				_ => false,
+			})
+			.map(|(hash, _)| hash)
+			.collect()
+	}
+
 	fn signing_network_id(&self) -> Option<u64> { None }
 
 	fn mode(&self) -> Mode { Mode::Active }
@@ -728,6 +763,8 @@ impl BlockChainClient for TestBlockChainClient {
 
 	fn disable(&self) { unimplemented!(); }
 
+	fn is_out_of_range(&self, _id: BlockId) -> bool { false }
+
 	fn pruning_info(&self) -> PruningInfo {
 		let best_num = self.chain_info().best_block_number;
 		PruningInfo {
@@ -736,6 +773,32 @@ impl BlockChainClient for TestBlockChainClient {
 		}
 	}
 
+	fn reorg_alert(&self) -> Option<ReorgAlert> {
+		self.reorg_alert.read().clone()
+	}
+
+	fn acknowledge_reorg(&self) {
+		*self.reorg_alert.write() = None;
+	}
+
+	fn import_statistics(&self) -> ImportStatistics {
+		self.import_statistics.read().clone()
+	}
+
+	fn disk_space(&self) -> DiskSpace {
+		*self.disk_space.read()
+	}
+
+	fn token_index_head(&self) -> Option<BlockNumber> { None }
+
+	fn token_transfers(&self, _token: &Address, _holder: &Address, _from: BlockId, _to: BlockId, _limit: usize) -> Option<Vec<TokenTransfer>> { None }
+
+	fn token_balance(&self, _token: &Address, _holder: &Address, _id: BlockId) -> Option<U256> { None }
+
+	fn balance_history(&self, _address: &Address, _from: BlockId, _to: BlockId, _limit: usize) -> Option<Vec<BalanceChange>> { None }
+
+	fn author_statistics(&self, _from: BlockId, _to: BlockId) -> Option<Vec<AuthorStatistics>> { None }
+
 	fn call_contract(&self, _id: BlockId, _address: Address, _data: Bytes) -> Result<Bytes, String> { Ok(vec![]) }
 
 	fn transact_contract(&self, address: Address, data: Bytes) -> Result<TransactionImportResult, EthcoreError> {
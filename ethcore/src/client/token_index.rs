@@ -0,0 +1,357 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Index of ERC-20 token transfers by token and holder.
+//!
+//! Every `Transfer(address,address,uint256)` log of the canonical chain is stored twice,
+//! under the sender and under the recipient, keyed by
+//! `token ++ holder ++ block number ++ log index ++ direction`, so transfers of a holder
+//! are read in chain order and its balance is the sum of its transfers up to a block.
+//!
+//! The index follows the canonical chain behind the import, a limited number
+//! of blocks at a time, so it can be enabled on an existing database.
+
+use std::cmp;
+use std::sync::Arc;
+use byteorder::{ByteOrder, BigEndian};
+use rlp::{RlpStream, UntrustedRlp};
+use util::{H256, U256, Address, Uint, KeyValueDB, DBTransaction, Mutex};
+use blockchain::{BlockChain, BlockProvider};
+use db::COL_TOKEN_TRANSFERS;
+use header::BlockNumber;
+use log_entry::LogEntry;
+use types::token_transfer::TokenTransfer;
+
+/// Key of the last indexed block.
+const HEAD_KEY: &'static [u8] = b"head";
+
+/// Length of the token and holder prefix of entry keys.
+const PREFIX_LEN: usize = 40;
+
+/// Topic of `Transfer(address,address,uint256)` events.
+fn transfer_topic() -> H256 {
+	"ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef".into()
+}
+
+/// Sender, recipient and value of a transfer event.
+/// ERC-721 transfers have the same signature, but the value is indexed, so they are skipped.
+fn parse_transfer(log: &LogEntry) -> Option<(Address, Address, U256)> {
+	if log.topics.len() != 3 || log.topics[0] != transfer_topic() || log.data.len() != 32 {
+		return None;
+	}
+	Some((log.topics[1].into(), log.topics[2].into(), U256::from(&log.data[..])))
+}
+
+fn prefix(token: &Address, holder: &Address) -> Vec<u8> {
+	let mut key = Vec::with_capacity(PREFIX_LEN + 13);
+	key.extend_from_slice(&**token);
+	key.extend_from_slice(&**holder);
+	key
+}
+
+fn entry_key(token: &Address, holder: &Address, block_number: BlockNumber, log_index: usize, incoming: bool) -> Vec<u8> {
+	let mut key = prefix(token, holder);
+	let mut buf = [0u8; 12];
+	BigEndian::write_u64(&mut buf[..8], block_number);
+	BigEndian::write_u32(&mut buf[8..], log_index as u32);
+	key.extend_from_slice(&buf);
+	key.push(incoming as u8);
+	key
+}
+
+fn entry_value(counterparty: &Address, transfer: &TokenTransfer) -> Vec<u8> {
+	let mut stream = RlpStream::new_list(5);
+	stream.append(counterparty);
+	stream.append(&transfer.value);
+	stream.append(&transfer.transaction_hash);
+	stream.append(&transfer.transaction_index);
+	stream.append(&transfer.block_hash);
+	stream.out()
+}
+
+/// Decode an entry of the holder. Returns whether the transfer is incoming and the transfer.
+fn decode_entry(token: &Address, holder: &Address, key: &[u8], value: &[u8]) -> Option<(bool, TokenTransfer)> {
+	if key.len() != PREFIX_LEN + 13 {
+		return None;
+	}
+	let incoming = key[PREFIX_LEN + 12] == 1;
+	let rlp = UntrustedRlp::new(value);
+	let counterparty: Address = match rlp.val_at(0) {
+		Ok(counterparty) => counterparty,
+		Err(_) => return None,
+	};
+	let (from, to) = match incoming {
+		true => (counterparty, *holder),
+		false => (*holder, counterparty),
+	};
+	match (rlp.val_at(1), rlp.val_at(2), rlp.val_at(3), rlp.val_at(4)) {
+		(Ok(value), Ok(transaction_hash), Ok(transaction_index), Ok(block_hash)) => Some((incoming, TokenTransfer {
+			token: *token,
+			from: from,
+			to: to,
+			value: value,
+			block_number: BigEndian::read_u64(&key[PREFIX_LEN..PREFIX_LEN + 8]),
+			block_hash: block_hash,
+			transaction_hash: transaction_hash,
+			transaction_index: transaction_index,
+			log_index: BigEndian::read_u32(&key[PREFIX_LEN + 8..PREFIX_LEN + 12]) as usize,
+		})),
+		_ => None,
+	}
+}
+
+/// Transfer events of a block.
+fn block_transfers(chain: &BlockChain, hash: &H256) -> Vec<TokenTransfer> {
+	let (number, receipts, body) = match (chain.block_number(hash), chain.block_receipts(hash), chain.block_body(hash)) {
+		(Some(number), Some(receipts), Some(body)) => (number, receipts.receipts, body),
+		_ => {
+			warn!(target: "client", "Receipts of block {} not found, its token transfers are not indexed", hash);
+			return Vec::new();
+		},
+	};
+
+	let mut transfers = Vec::new();
+	let mut log_index = 0;
+	for (transaction_index, (receipt, transaction_hash)) in receipts.iter().zip(body.transaction_hashes()).enumerate() {
+		for log in &receipt.logs {
+			if let Some((from, to, value)) = parse_transfer(log) {
+				transfers.push(TokenTransfer {
+					token: log.address,
+					from: from,
+					to: to,
+					value: value,
+					block_number: number,
+					block_hash: *hash,
+					transaction_hash: transaction_hash,
+					transaction_index: transaction_index,
+					log_index: log_index,
+				});
+			}
+			log_index += 1;
+		}
+	}
+	transfers
+}
+
+/// Token transfer index.
+pub struct TokenIndex {
+	db: Arc<KeyValueDB>,
+	update_lock: Mutex<()>,
+}
+
+impl TokenIndex {
+	/// Create the index stored in the given database.
+	pub fn new(db: Arc<KeyValueDB>) -> Self {
+		TokenIndex {
+			db: db,
+			update_lock: Mutex::new(()),
+		}
+	}
+
+	/// Number and hash of the last indexed block.
+	pub fn head(&self) -> Option<(BlockNumber, H256)> {
+		self.db.get(COL_TOKEN_TRANSFERS, HEAD_KEY).expect("Low-level database error. Some issue with your hard disk?")
+			.and_then(|value| {
+				let rlp = UntrustedRlp::new(&value);
+				match (rlp.val_at(0), rlp.val_at(1)) {
+					(Ok(number), Ok(hash)) => Some((number, hash)),
+					_ => None,
+				}
+			})
+	}
+
+	fn insert(&self, batch: &mut DBTransaction, transfer: &TokenTransfer) {
+		batch.put(COL_TOKEN_TRANSFERS, &entry_key(&transfer.token, &transfer.from, transfer.block_number, transfer.log_index, false), &entry_value(&transfer.to, transfer));
+		batch.put(COL_TOKEN_TRANSFERS, &entry_key(&transfer.token, &transfer.to, transfer.block_number, transfer.log_index, true), &entry_value(&transfer.from, transfer));
+	}
+
+	fn remove(&self, batch: &mut DBTransaction, transfer: &TokenTransfer) {
+		batch.delete(COL_TOKEN_TRANSFERS, &entry_key(&transfer.token, &transfer.from, transfer.block_number, transfer.log_index, false));
+		batch.delete(COL_TOKEN_TRANSFERS, &entry_key(&transfer.token, &transfer.to, transfer.block_number, transfer.log_index, true));
+	}
+
+	/// Follow the canonical chain: remove transfers of retracted blocks
+	/// and index at most `max_blocks` new ones. Does nothing if an update is already running.
+	pub fn update(&self, chain: &BlockChain, max_blocks: u64) {
+		let _lock = match self.update_lock.try_lock() {
+			Some(lock) => lock,
+			None => return,
+		};
+
+		let best_hash = chain.best_block_hash();
+		let best_number = chain.best_block_number();
+		let mut batch = DBTransaction::new();
+		let (mut number, mut hash) = match self.head() {
+			Some((_, ref hash)) if *hash == best_hash => return,
+			Some((number, hash)) if chain.block_hash(number) == Some(hash) => (number, hash),
+			Some((number, hash)) => match chain.tree_route(hash, best_hash) {
+				Some(route) => {
+					for retracted in &route.blocks[..route.index] {
+						for transfer in block_transfers(chain, retracted) {
+							self.remove(&mut batch, &transfer);
+						}
+					}
+					(number - route.index as u64, route.ancestor)
+				},
+				None => {
+					warn!(target: "client", "Last block of the token index {} is unknown, the index is not updated", hash);
+					return;
+				},
+			},
+			// genesis has no transactions
+			None => (0, chain.genesis_hash()),
+		};
+
+		let last = cmp::min(best_number, number + max_blocks);
+		while number < last {
+			let next = match chain.block_hash(number + 1) {
+				Some(next) => next,
+				None => break,
+			};
+			// reorganised in the meantime, continue with the next update
+			if chain.block_header(&next).map_or(true, |header| *header.parent_hash() != hash) {
+				break;
+			}
+			for transfer in block_transfers(chain, &next) {
+				self.insert(&mut batch, &transfer);
+			}
+			number += 1;
+			hash = next;
+		}
+
+		let mut head = RlpStream::new_list(2);
+		head.append(&number);
+		head.append(&hash);
+		batch.put(COL_TOKEN_TRANSFERS, HEAD_KEY, &head.out());
+		self.db.write(batch).expect("Low-level database error. Some issue with your hard disk?");
+	}
+
+	fn entries<'a>(&'a self, token: &'a Address, holder: &'a Address, prefix: &'a [u8]) -> Box<Iterator<Item = (bool, TokenTransfer)> + 'a> {
+		Box::new(self.db.iter_from_prefix(COL_TOKEN_TRANSFERS, prefix)
+			.take_while(move |&(ref key, _)| key.starts_with(prefix))
+			.filter_map(move |(key, value)| decode_entry(token, holder, &key, &value)))
+	}
+
+	/// Transfers of the holder in blocks `from` to `to`, at most `limit` of them.
+	pub fn transfers(&self, token: &Address, holder: &Address, from: BlockNumber, to: BlockNumber, limit: usize) -> Vec<TokenTransfer> {
+		let prefix = prefix(token, holder);
+		let transfers = self.entries(token, holder, &prefix)
+			// a transfer to self is stored twice
+			.filter(|&(incoming, ref transfer)| !incoming || transfer.from != transfer.to)
+			.map(|(_, transfer)| transfer)
+			.skip_while(|transfer| transfer.block_number < from)
+			.take_while(|transfer| transfer.block_number <= to)
+			.take(limit)
+			.collect();
+		transfers
+	}
+
+	/// Balance of the holder after the block, computed from its transfers.
+	/// Tokens credited without a transfer event are not counted.
+	pub fn balance(&self, token: &Address, holder: &Address, block: BlockNumber) -> U256 {
+		let prefix = prefix(token, holder);
+		let (incoming, outgoing) = self.entries(token, holder, &prefix)
+			.take_while(|&(_, ref transfer)| transfer.block_number <= block)
+			.fold((U256::zero(), U256::zero()), |(incoming, outgoing), (is_incoming, transfer)| match is_incoming {
+				true => (saturating_add(incoming, transfer.value), outgoing),
+				false => (incoming, saturating_add(outgoing, transfer.value)),
+			});
+		match incoming > outgoing {
+			true => incoming - outgoing,
+			false => U256::zero(),
+		}
+	}
+}
+
+fn saturating_add(a: U256, b: U256) -> U256 {
+	match a.overflowing_add(b) {
+		(_, true) => U256::max_value(),
+		(sum, false) => sum,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use util::{H256, U256, Address, DBTransaction};
+	use util::kvdb::in_memory;
+	use log_entry::LogEntry;
+	use types::token_transfer::TokenTransfer;
+	use super::{TokenIndex, parse_transfer, transfer_topic};
+
+	fn transfer(from: u64, to: u64, value: u64, block_number: u64, log_index: usize) -> TokenTransfer {
+		TokenTransfer {
+			token: Address::from(1),
+			from: Address::from(from),
+			to: Address::from(to),
+			value: value.into(),
+			block_number: block_number,
+			block_hash: H256::from(block_number),
+			transaction_hash: H256::from(100 + log_index as u64),
+			transaction_index: 0,
+			log_index: log_index,
+		}
+	}
+
+	#[test]
+	fn should_parse_transfer_event() {
+		let mut log = LogEntry {
+			address: Address::from(1),
+			topics: vec![transfer_topic(), H256::from(2), H256::from(3)],
+			data: H256::from(500).to_vec(),
+		};
+		assert_eq!(parse_transfer(&log), Some((Address::from(2), Address::from(3), U256::from(500))));
+
+		// ERC-721 transfer with indexed token id
+		log.topics.push(H256::from(4));
+		log.data = vec![];
+		assert_eq!(parse_transfer(&log), None);
+	}
+
+	#[test]
+	fn should_query_transfers_and_balances() {
+		let index = TokenIndex::new(Arc::new(in_memory(::db::NUM_COLUMNS.unwrap_or(0))));
+		let transfers = vec![
+			transfer(0, 10, 1000, 1, 0),
+			transfer(10, 11, 300, 2, 0),
+			transfer(10, 10, 50, 2, 1),
+			transfer(11, 10, 100, 3, 0),
+		];
+		let mut batch = DBTransaction::new();
+		for t in &transfers {
+			index.insert(&mut batch, t);
+		}
+		index.db.write(batch).unwrap();
+
+		let holder = Address::from(10);
+		let token = Address::from(1);
+		assert_eq!(index.transfers(&token, &holder, 0, 10, 100), transfers);
+		assert_eq!(index.transfers(&token, &holder, 2, 2, 100), vec![transfers[1].clone(), transfers[2].clone()]);
+		assert_eq!(index.transfers(&token, &holder, 0, 10, 1), vec![transfers[0].clone()]);
+		assert_eq!(index.transfers(&token, &Address::from(11), 0, 10, 100), vec![transfers[1].clone(), transfers[3].clone()]);
+
+		assert_eq!(index.balance(&token, &holder, 0), 0.into());
+		assert_eq!(index.balance(&token, &holder, 1), 1000.into());
+		assert_eq!(index.balance(&token, &holder, 2), 700.into());
+		assert_eq!(index.balance(&token, &holder, 3), 800.into());
+		assert_eq!(index.balance(&Address::from(2), &holder, 3), 0.into());
+
+		let mut batch = DBTransaction::new();
+		index.remove(&mut batch, &transfers[3]);
+		index.db.write(batch).unwrap();
+		assert_eq!(index.balance(&token, &holder, 3), 700.into());
+	}
+}
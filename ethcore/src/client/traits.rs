@@ -41,6 +41,12 @@ use types::blockchain_info::BlockChainInfo;
 use types::block_status::BlockStatus;
 use types::mode::Mode;
 use types::pruning_info::PruningInfo;
+use types::reorg_alert::ReorgAlert;
+use types::disk_space::DiskSpace;
+use types::import_statistics::ImportStatistics;
+use types::token_transfer::TokenTransfer;
+use types::balance_change::BalanceChange;
+use types::author_statistics::AuthorStatistics;
 use encoded;
 
 #[ipc(client_ident="RemoteClient")]
@@ -139,6 +145,9 @@ pub trait BlockChainClient : Sync + Send {
 	/// Get transaction receipt with given hash.
 	fn transaction_receipt(&self, id: TransactionId) -> Option<LocalizedReceipt>;
 
+	/// Get receipts of all transactions in the block.
+	fn localized_block_receipts(&self, id: BlockId) -> Option<Vec<LocalizedReceipt>>;
+
 	/// Get a tree route between `from` and `to`.
 	/// See `BlockChain::tree_route`.
 	fn tree_route(&self, from: &H256, to: &H256) -> Option<TreeRoute>;
@@ -212,6 +221,12 @@ pub trait BlockChainClient : Sync + Send {
 	/// List all transactions that are allowed into the next block.
 	fn ready_transactions(&self) -> Vec<PendingTransaction>;
 
+	/// Get a transaction from the queue by hash.
+	fn pending_transaction(&self, hash: H256) -> Option<PendingTransaction>;
+
+	/// Hashes of locally submitted transactions which are in the queue.
+	fn local_transaction_hashes(&self) -> Vec<H256>;
+
 	/// Sorted list of transaction gas prices from at least last sample_size blocks.
 	fn gas_price_corpus(&self, sample_size: usize) -> ::stats::Corpus<U256> {
 		let mut h = self.chain_info().best_block_hash;
@@ -258,9 +273,44 @@ pub trait BlockChainClient : Sync + Send {
 	/// Returns engine-related extra info for `UncleId`.
 	fn uncle_extra_info(&self, id: UncleId) -> Option<BTreeMap<String, String>>;
 
+	/// Returns true if the block is known but not served, being outside of the configured block range.
+	fn is_out_of_range(&self, id: BlockId) -> bool;
+
 	/// Returns information about pruning/data availability.
 	fn pruning_info(&self) -> PruningInfo;
 
+	/// Returns the alert raised by a chain reorganisation deeper than allowed.
+	/// Block import is paused while there is an unacknowledged alert.
+	fn reorg_alert(&self) -> Option<ReorgAlert>;
+
+	/// Acknowledge the deep reorganisation and resume block import, allowing it to happen.
+	fn acknowledge_reorg(&self);
+
+	/// Returns rolling statistics of recently imported blocks and the size of the import backlog.
+	fn import_statistics(&self) -> ImportStatistics;
+
+	/// Returns the free space level of the database volume as of the last check.
+	fn disk_space(&self) -> DiskSpace;
+
+	/// Returns the last block of the token transfer index or `None` if the index is disabled.
+	fn token_index_head(&self) -> Option<BlockNumber>;
+
+	/// Returns ERC-20 transfers of the holder between the blocks, at most `limit` of them.
+	/// `None` if the index is disabled or any of the blocks is unknown.
+	fn token_transfers(&self, token: &Address, holder: &Address, from: BlockId, to: BlockId, limit: usize) -> Option<Vec<TokenTransfer>>;
+
+	/// Returns the ERC-20 balance of the holder computed from the indexed transfers.
+	/// `None` if the index is disabled or the block is unknown.
+	fn token_balance(&self, token: &Address, holder: &Address, id: BlockId) -> Option<U256>;
+
+	/// Returns changes of the account balance between the blocks, at most `limit` of them.
+	/// `None` if the balance history is disabled or any of the blocks is unknown.
+	fn balance_history(&self, address: &Address, from: BlockId, to: BlockId, limit: usize) -> Option<Vec<BalanceChange>>;
+
+	/// Returns blocks sealed and turns missed by each author between the blocks, ordered by author.
+	/// `None` if any of the blocks is unknown.
+	fn author_statistics(&self, from: BlockId, to: BlockId) -> Option<Vec<AuthorStatistics>>;
+
 	/// Like `call`, but with various defaults. Designed to be used for calling contracts.
 	fn call_contract(&self, id: BlockId, address: Address, data: Bytes) -> Result<Bytes, String>;
 
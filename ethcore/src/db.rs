@@ -40,8 +40,14 @@ pub const COL_ACCOUNT_BLOOM: Option<u32> = Some(5);
 pub const COL_NODE_INFO: Option<u32> = Some(6);
 /// Column for the light client chain.
 pub const COL_LIGHT_CHAIN: Option<u32> = Some(7);
+/// Column for the ERC-20 token transfer index.
+pub const COL_TOKEN_TRANSFERS: Option<u32> = Some(8);
+/// Column for the account balance history.
+pub const COL_BALANCE_HISTORY: Option<u32> = Some(9);
+/// Column for the index of blocks by log addresses and topics.
+pub const COL_LOG_INDEX: Option<u32> = Some(10);
 /// Number of columns in DB
-pub const NUM_COLUMNS: Option<u32> = Some(8);
+pub const NUM_COLUMNS: Option<u32> = Some(11);
 
 /// Modes for updating caches.
 #[derive(Clone, Copy)]
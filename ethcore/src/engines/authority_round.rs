@@ -33,7 +33,7 @@ use ethjson;
 use io::{IoContext, IoHandler, TimerToken, IoService};
 use builtin::Builtin;
 use transaction::UnverifiedTransaction;
-use client::{Client, EngineClient};
+use client::{BlockChainClient, BlockId, Client, EngineClient};
 use state::CleanupMode;
 use super::signer::EngineSigner;
 use super::validator_set::{ValidatorSet, SimpleList, new_validator_set};
@@ -45,6 +45,8 @@ pub struct AuthorityRoundParams {
 	pub gas_limit_bound_divisor: U256,
 	/// Time to wait before next block or authority switching.
 	pub step_duration: Duration,
+	/// Step durations taking effect from the given block onwards.
+	pub step_duration_transitions: BTreeMap<BlockNumber, Duration>,
 	/// Block reward.
 	pub block_reward: U256,
 	/// Namereg contract address.
@@ -61,9 +63,18 @@ pub struct AuthorityRoundParams {
 
 impl From<ethjson::spec::AuthorityRoundParams> for AuthorityRoundParams {
 	fn from(p: ethjson::spec::AuthorityRoundParams) -> Self {
+		let mut step_duration_transitions: BTreeMap<BlockNumber, Duration> = p.step_duration_transitions
+			.map_or_else(BTreeMap::new, |transitions| transitions.into_iter()
+				.map(|(block, duration)| (block.into(), Duration::from_secs(duration.into())))
+				.collect()
+			);
+		// A transition at genesis simply overrides the initial duration.
+		let step_duration = step_duration_transitions.remove(&0)
+			.unwrap_or_else(|| Duration::from_secs(p.step_duration.into()));
 		AuthorityRoundParams {
 			gas_limit_bound_divisor: p.gas_limit_bound_divisor.into(),
-			step_duration: Duration::from_secs(p.step_duration.into()),
+			step_duration: step_duration,
+			step_duration_transitions: step_duration_transitions,
 			validators: p.validators,
 			block_reward: p.block_reward.map_or_else(U256::zero, Into::into),
 			registrar: p.registrar.map_or_else(Address::new, Into::into),
@@ -74,19 +85,69 @@ impl From<ethjson::spec::AuthorityRoundParams> for AuthorityRoundParams {
 	}
 }
 
+// Step duration in force from `start_step` onwards.
+#[derive(Debug, Clone, PartialEq)]
+struct StepTiming {
+	block: BlockNumber, // block which introduced the duration.
+	start_step: usize,
+	start_time: Duration, // time since epoch at which `start_step` begins.
+	duration: Duration,
+}
+
+impl StepTiming {
+	fn step_at(&self, time: Duration) -> usize {
+		let elapsed = if time > self.start_time { time - self.start_time } else { Duration::from_secs(0) };
+		self.start_step + (elapsed.as_secs() / self.duration.as_secs()) as usize
+	}
+
+	fn step_start(&self, step: usize) -> Duration {
+		self.start_time + self.duration * (step - self.start_step) as u32
+	}
+}
+
 // Helper for managing the step.
 #[derive(Debug)]
 struct Step {
 	calibrate: bool, // whether calibration is enabled.
 	inner: AtomicUsize,
-	duration: Duration,
+	timings: RwLock<Vec<StepTiming>>, // ordered by `start_step`, never empty.
 }
 
 impl Step {
+	fn new(initial_step: usize, calibrate: bool, duration: Duration) -> Self {
+		Step {
+			calibrate: calibrate,
+			inner: AtomicUsize::new(initial_step),
+			timings: RwLock::new(vec![StepTiming {
+				block: 0,
+				start_step: 0,
+				start_time: Duration::from_secs(0),
+				duration: duration,
+			}]),
+		}
+	}
 	fn load(&self) -> usize { self.inner.load(AtomicOrdering::SeqCst) }
+	fn timing(&self, step: usize) -> StepTiming {
+		let timings = self.timings.read();
+		timings.iter().rev()
+			.find(|t| t.start_step <= step)
+			.unwrap_or(&timings[0])
+			.clone()
+	}
+	fn duration(&self, step: usize) -> Duration {
+		self.timing(step).duration
+	}
+	fn step_at(&self, time: Duration) -> usize {
+		let timings = self.timings.read();
+		timings.iter().rev()
+			.find(|t| t.start_time <= time)
+			.unwrap_or(&timings[0])
+			.step_at(time)
+	}
 	fn duration_remaining(&self) -> Duration {
 		let now = unix_now();
-		let step_end = self.duration * (self.load() as u32 + 1);
+		let step = self.load();
+		let step_end = self.timing(step).step_start(step + 1);
 		if step_end > now {
 			step_end - now
 		} else {
@@ -98,9 +159,29 @@ impl Step {
 	}
 	fn calibrate(&self) {
 		if self.calibrate {
-			let new_step = unix_now().as_secs() / self.duration.as_secs();
-			self.inner.store(new_step as usize, AtomicOrdering::SeqCst);
+			let new_step = self.step_at(unix_now());
+			self.inner.store(new_step, AtomicOrdering::SeqCst);
+		}
+	}
+	// Whether the transition introduced at `block` is already in force.
+	fn has_transition(&self, block: BlockNumber) -> bool {
+		self.timings.read().iter().any(|t| t.block == block)
+	}
+	// Switch to `duration` from the step following `parent_step`, the step of block `block - 1`.
+	fn add_transition(&self, block: BlockNumber, parent_step: usize, duration: Duration) {
+		{
+			let mut timings = self.timings.write();
+			let last = timings.last().expect("timings are never empty; qed").clone();
+			if block <= last.block { return; }
+			let start_step = cmp::max(parent_step + 1, last.start_step);
+			timings.push(StepTiming {
+				block: block,
+				start_step: start_step,
+				start_time: last.step_start(start_step),
+				duration: duration,
+			});
 		}
+		self.calibrate();
 	}
 	fn is_future(&self, given: usize) -> bool {
 		if given > self.load() + 1 {
@@ -122,6 +203,7 @@ pub struct AuthorityRound {
 	builtins: BTreeMap<Address, Builtin>,
 	transition_service: IoService<()>,
 	step: Arc<Step>,
+	step_duration_transitions: BTreeMap<BlockNumber, Duration>,
 	proposed: AtomicBool,
 	client: RwLock<Option<Weak<EngineClient>>>,
 	signer: EngineSigner,
@@ -197,11 +279,8 @@ impl AuthorityRound {
 				registrar: our_params.registrar,
 				builtins: builtins,
 				transition_service: IoService::<()>::start()?,
-				step: Arc::new(Step {
-					inner: AtomicUsize::new(initial_step),
-					calibrate: our_params.start_step.is_none(),
-					duration: our_params.step_duration,
-				}),
+				step: Arc::new(Step::new(initial_step, our_params.start_step.is_none(), our_params.step_duration)),
+				step_duration_transitions: our_params.step_duration_transitions,
 				proposed: AtomicBool::new(false),
 				client: RwLock::new(None),
 				signer: Default::default(),
@@ -224,6 +303,28 @@ impl AuthorityRound {
 	fn is_step_proposer(&self, bh: &H256, step: usize, address: &Address) -> bool {
 		self.step_proposer(bh, step) == *address
 	}
+
+	/// Bring step durations in force for all transitions up to block `number`, given the `parent` of that block if known.
+	fn apply_step_transitions(&self, number: BlockNumber, parent: Option<&Header>) {
+		for (&block, &duration) in self.step_duration_transitions.iter().take_while(|&(b, _)| *b <= number) {
+			if self.step.has_transition(block) { continue; }
+			let parent_step = match parent {
+				Some(parent) if parent.number() + 1 == block => header_step(parent).ok(),
+				_ => self.client.read().as_ref()
+					.and_then(Weak::upgrade)
+					.and_then(|c| c.block_header(BlockId::Number(block - 1)))
+					.and_then(|h| header_step(&h.decode()).ok()),
+			};
+			match parent_step {
+				Some(parent_step) => {
+					debug!(target: "engine", "Step duration changes to {}s from block {}.", duration.as_secs(), block);
+					self.step.add_transition(block, parent_step, duration);
+				},
+				// Later transitions depend on this one.
+				None => return,
+			}
+		}
+	}
 }
 
 fn unix_now() -> Duration {
@@ -281,6 +382,29 @@ impl Engine for AuthorityRound {
 		}
 	}
 
+	fn expected_proposer(&self, parent_hash: &H256) -> Option<(usize, Address)> {
+		let step = self.step.load();
+		Some((step, self.step_proposer(parent_hash, step)))
+	}
+
+	fn missed_turns(&self, parent: &Header, header: &Header) -> Vec<(Address, u64)> {
+		// The steps between genesis and the first block were nobody's turn.
+		if parent.number() == 0 {
+			return Vec::new();
+		}
+		let (parent_step, step) = match (header_step(parent), header_step(header)) {
+			(Ok(parent_step), Ok(step)) if step > parent_step + 1 => (parent_step, step),
+			_ => return Vec::new(),
+		};
+		let parent_hash = parent.hash();
+		let missed = (step - parent_step - 1) as u64;
+		let validators = self.validators.count(&parent_hash) as u64;
+		// Proposers repeat every `validators` steps, so only the first round of missed steps is looked up.
+		(0..min(missed, validators))
+			.map(|i| (self.step_proposer(&parent_hash, parent_step + 1 + i as usize), (missed - i + validators - 1) / validators))
+			.collect()
+	}
+
 	/// Additional engine-specific information for the user/developer concerning `header`.
 	fn extra_info(&self, header: &Header) -> BTreeMap<String, String> {
 		map![
@@ -295,6 +419,7 @@ impl Engine for AuthorityRound {
 	}
 
 	fn populate_from_parent(&self, header: &mut Header, parent: &Header, gas_floor_target: U256, _gas_ceil_target: U256) {
+		self.apply_step_transitions(parent.number() + 1, Some(parent));
 		// Chain scoring: total weight is sqrt(U256::max_value())*height - step
 		let new_difficulty = U256::from(U128::max_value()) + header_step(parent).expect("Header has been verified; qed").into() - self.step.load().into();
 		header.set_difficulty(new_difficulty);
@@ -377,6 +502,8 @@ impl Engine for AuthorityRound {
 			return Err(From::from(BlockError::RidiculousNumber(OutOfBounds { min: Some(1), max: None, found: header.number() })));
 		}
 
+		self.apply_step_transitions(header.number(), Some(parent));
+
 		// Ensure header is from the step after parent.
 		let parent_step = header_step(parent)?;
 		if step <= parent_step {
@@ -401,8 +528,12 @@ impl Engine for AuthorityRound {
 
 	// the proofs we need just allow us to get the full validator set.
 	fn epoch_proof(&self, header: &Header, caller: &Call) -> Result<Bytes, Error> {
-		self.validators.epoch_proof(header, caller)
-			.map_err(|e| EngineError::InsufficientProof(e).into())
+		let proof = self.validators.epoch_proof(header, caller)
+			.map_err(EngineError::InsufficientProof)?;
+		if let Ok((epoch, list)) = self.validators.epoch_set(header, &proof) {
+			info!(target: "engine", "Validator set for epoch {} from block #{}: {:?}", epoch, header.number() + 1, list.into_inner());
+		}
+		Ok(proof)
 	}
 
 	fn is_epoch_end(&self, header: &Header, block: Option<&[u8]>, receipts: Option<&[::receipt::Receipt]>)
@@ -436,6 +567,9 @@ impl Engine for AuthorityRound {
 
 	fn register_client(&self, client: Weak<Client>) {
 		*self.client.write() = Some(client.clone());
+		if let Some(c) = client.upgrade() {
+			self.apply_step_transitions(c.chain_info().best_block_number + 1, None);
+		}
 		self.validators.register_contract(client);
 	}
 
@@ -460,6 +594,9 @@ mod tests {
 	use account_provider::AccountProvider;
 	use spec::Spec;
 	use engines::Seal;
+	use ethjson;
+	use std::time::Duration;
+	use super::{AuthorityRound, AuthorityRoundParams, Step};
 
 	#[test]
 	fn has_valid_metadata() {
@@ -606,4 +743,88 @@ mod tests {
 		header.set_seal(vec![encode(&3usize).to_vec(), encode(&(&*signature as &[u8])).to_vec()]);
 		assert!(engine.verify_block_family(&header, &parent_header, None).is_err());
 	}
+
+	#[test]
+	fn reports_expected_proposer() {
+		let spec = Spec::new_test_round();
+		let genesis_hash = spec.genesis_header().hash();
+		// Spec starts with step 2.
+		let expected = Address::from_str("7d577a597b2742b498cb5cf0c26cdcd726d39e6e").unwrap();
+		assert_eq!(spec.engine.expected_proposer(&genesis_hash), Some((2, expected)));
+	}
+
+	#[test]
+	fn reports_missed_turns() {
+		let engine = Spec::new_test_round().engine;
+		let mut parent = Header::default();
+		parent.set_number(1);
+		parent.set_seal(vec![encode(&3usize).to_vec()]);
+		let mut header = Header::default();
+		header.set_number(2);
+		header.set_seal(vec![encode(&9usize).to_vec()]);
+
+		// Steps 4, 6 and 8 belong to the first validator, 5 and 7 to the second.
+		let first = Address::from_str("7d577a597b2742b498cb5cf0c26cdcd726d39e6e").unwrap();
+		let second = Address::from_str("82a978b3f5962a5b0957d9ee9eef472ee55b42f1").unwrap();
+		assert_eq!(engine.missed_turns(&parent, &header), vec![(first, 3), (second, 2)]);
+
+		header.set_seal(vec![encode(&4usize).to_vec()]);
+		assert!(engine.missed_turns(&parent, &header).is_empty());
+	}
+
+	#[test]
+	fn step_timing_follows_duration_transitions() {
+		let step = Step::new(0, false, Duration::from_secs(5));
+		// Block 10 is the first one after step 3.
+		step.add_transition(10, 3, Duration::from_secs(2));
+		assert_eq!(step.duration(3), Duration::from_secs(5));
+		assert_eq!(step.duration(4), Duration::from_secs(2));
+		assert_eq!(step.step_at(Duration::from_secs(19)), 3);
+		assert_eq!(step.step_at(Duration::from_secs(20)), 4);
+		assert_eq!(step.step_at(Duration::from_secs(25)), 6);
+
+		// Transitions are applied once.
+		step.add_transition(10, 5, Duration::from_secs(1));
+		assert_eq!(step.duration(6), Duration::from_secs(2));
+
+		step.add_transition(20, 7, Duration::from_secs(10));
+		assert_eq!(step.step_at(Duration::from_secs(27)), 7);
+		assert_eq!(step.step_at(Duration::from_secs(28)), 8);
+		assert_eq!(step.step_at(Duration::from_secs(37)), 8);
+		assert_eq!(step.step_at(Duration::from_secs(38)), 9);
+	}
+
+	#[test]
+	fn applies_step_duration_transition_from_parent() {
+		let mut transitions = BTreeMap::new();
+		transitions.insert(2, Duration::from_secs(3));
+		let params = AuthorityRoundParams {
+			gas_limit_bound_divisor: 0x0400.into(),
+			step_duration: Duration::from_secs(1),
+			step_duration_transitions: transitions,
+			block_reward: U256::zero(),
+			registrar: Address::default(),
+			start_step: Some(1),
+			validators: ethjson::spec::ValidatorSet::List(vec![Address::default().into()]),
+			validate_score_transition: 0,
+			eip155_transition: 0,
+		};
+		let engine = AuthorityRound::new(Default::default(), params, Default::default()).unwrap();
+
+		let mut parent_header: Header = Header::default();
+		parent_header.set_number(1);
+		parent_header.set_seal(vec![encode(&6usize).to_vec()]);
+		parent_header.set_gas_limit(U256::from_str("222222").unwrap());
+		let mut header: Header = Header::default();
+		header.set_number(2);
+		header.set_gas_limit(U256::from_str("222222").unwrap());
+		header.set_seal(vec![encode(&7usize).to_vec(), encode(&H520::default()).to_vec()]);
+
+		assert_eq!(engine.step.duration(7), Duration::from_secs(1));
+		assert!(engine.verify_block_family(&header, &parent_header, None).is_ok());
+		assert_eq!(engine.step.duration(6), Duration::from_secs(1));
+		assert_eq!(engine.step.duration(7), Duration::from_secs(3));
+		assert_eq!(engine.step.step_at(Duration::from_secs(9)), 7);
+		assert_eq!(engine.step.step_at(Duration::from_secs(10)), 8);
+	}
 }
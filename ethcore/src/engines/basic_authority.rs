@@ -185,8 +185,12 @@ impl Engine for BasicAuthority {
 
 	// the proofs we need just allow us to get the full validator set.
 	fn epoch_proof(&self, header: &Header, caller: &Call) -> Result<Bytes, Error> {
-		self.validators.epoch_proof(header, caller)
-			.map_err(|e| EngineError::InsufficientProof(e).into())
+		let proof = self.validators.epoch_proof(header, caller)
+			.map_err(EngineError::InsufficientProof)?;
+		if let Ok((epoch, list)) = self.validators.epoch_set(header, &proof) {
+			info!(target: "engine", "Validator set for epoch {} from block #{}: {:?}", epoch, header.number() + 1, list.into_inner());
+		}
+		Ok(proof)
 	}
 
 	fn is_epoch_end(&self, header: &Header, block: Option<&[u8]>, receipts: Option<&[::receipt::Receipt]>)
@@ -0,0 +1,894 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Clique (EIP-225) proof-of-authority engine.
+//!
+//! Blocks are signed by one of the authorized signers, the signature takes the last
+//! 65 bytes of the extra data. A signer may sign only one of any `signers / 2 + 1`
+//! consecutive blocks. Signers vote on adding or removing a signer by setting the block
+//! author to the candidate and the nonce to `NONCE_AUTH` or `NONCE_DROP`; the change
+//! takes effect once more than half of the signers agree. Every `epoch` blocks a
+//! checkpoint lists the signers in its extra data and pending votes are discarded.
+
+use std::sync::Weak;
+use std::time::{UNIX_EPOCH, Duration};
+use lru_cache::LruCache;
+use rand::{self, Rng};
+use util::*;
+use ethkey::{recover, public_to_address, Signature};
+use rlp::{UntrustedRlp, RlpStream, encode};
+use account_provider::AccountProvider;
+use block::*;
+use builtin::Builtin;
+use client::{BlockChainClient, BlockId, Client, EngineClient};
+use spec::CommonParams;
+use engines::{Engine, EngineError, Seal};
+use error::{BlockError, Error};
+use evm::Schedule;
+use ethjson;
+use header::{Header, BlockNumber};
+use io::{IoContext, IoHandler, IoService, TimerToken};
+use super::signer::EngineSigner;
+
+/// Number of extra data bytes reserved for the signer vanity.
+const VANITY_LENGTH: usize = 32;
+/// Number of extra data bytes reserved for the signature.
+const SIGNATURE_LENGTH: usize = 65;
+/// Default number of blocks after which votes are reset.
+const DEFAULT_EPOCH: u64 = 30000;
+/// Default minimal number of seconds between blocks.
+const DEFAULT_PERIOD: u64 = 15;
+/// Nonce of a vote to add the candidate to the signers.
+const NONCE_AUTH: u64 = 0xffffffffffffffff;
+/// Nonce of a vote to remove the candidate from the signers.
+const NONCE_DROP: u64 = 0;
+/// Difficulty of a block signed by the in-turn signer.
+const DIFF_IN_TURN: u64 = 2;
+/// Difficulty of a block signed by an out-of-turn signer.
+const DIFF_NO_TURN: u64 = 1;
+/// Maximal delay of out-of-turn signing per allowed signer.
+const WIGGLE_TIME_MS: u64 = 500;
+/// Gas limit bound divisor.
+const GAS_LIMIT_BOUND_DIVISOR: u64 = 1024;
+/// Number of snapshots and recovered signers kept in memory.
+const CACHE_SIZE: usize = 128;
+
+/// `Clique` params.
+#[derive(Debug, PartialEq)]
+pub struct CliqueParams {
+	/// Minimal number of seconds between blocks.
+	pub period: u64,
+	/// Number of blocks after which votes are reset and the signers are checkpointed.
+	pub epoch: u64,
+}
+
+impl From<ethjson::spec::CliqueParams> for CliqueParams {
+	fn from(p: ethjson::spec::CliqueParams) -> Self {
+		CliqueParams {
+			period: p.period.map_or(DEFAULT_PERIOD, Into::into),
+			epoch: p.epoch.map_or(DEFAULT_EPOCH, Into::into),
+		}
+	}
+}
+
+/// Vote of a signer on the candidate.
+#[derive(Debug, Clone, PartialEq)]
+struct Vote {
+	signer: Address,
+	candidate: Address,
+	authorize: bool,
+}
+
+/// Signers and pending votes after a block.
+#[derive(Debug, Clone, PartialEq)]
+struct Snapshot {
+	hash: H256,
+	/// Authorized signers, the order determines the in-turn signer.
+	signers: BTreeSet<Address>,
+	/// Signers of the recent blocks by block number.
+	recents: BTreeMap<BlockNumber, Address>,
+	/// Votes in the order they were cast.
+	votes: Vec<Vote>,
+}
+
+impl Snapshot {
+	/// Snapshot after a checkpoint block (or genesis) listing the signers.
+	/// `signer_of` gives the signer of the checkpoint or one of its ancestors by number; the signers
+	/// of the blocks within the signer limit are recovered, exactly those a snapshot applied block
+	/// by block from genesis keeps in `recents`. Signers don't change at a checkpoint, so the limit
+	/// is the one of the checkpoint signers.
+	fn from_checkpoint<F>(header: &Header, mut signer_of: F) -> Result<Self, Error>
+		where F: FnMut(BlockNumber) -> Result<Address, Error>
+	{
+		let mut snapshot = Snapshot {
+			hash: header.hash(),
+			signers: checkpoint_signers(header)?.into_iter().collect(),
+			recents: BTreeMap::new(),
+			votes: Vec::new(),
+		};
+
+		let limit = snapshot.signer_limit();
+		let mut number = header.number();
+		while number > 0 && number + limit > header.number() {
+			snapshot.recents.insert(number, signer_of(number)?);
+			number -= 1;
+		}
+		Ok(snapshot)
+	}
+
+	/// A signer may sign one of any this many consecutive blocks.
+	fn signer_limit(&self) -> u64 {
+		self.signers.len() as u64 / 2 + 1
+	}
+
+	/// Returns true if it's the turn of the signer to sign the block of given number.
+	fn is_in_turn(&self, number: BlockNumber, signer: &Address) -> bool {
+		match self.signers.iter().position(|s| s == signer) {
+			Some(position) => number % self.signers.len() as u64 == position as u64,
+			None => false,
+		}
+	}
+
+	/// Returns true if the signer is not allowed to sign the block of given number yet.
+	fn recently_signed(&self, number: BlockNumber, signer: &Address) -> bool {
+		let limit = self.signer_limit();
+		self.recents.iter().any(|(seen, recent)| recent == signer && *seen + limit > number)
+	}
+
+	/// Snapshot after the block signed by the given signer.
+	fn apply(&self, header: &Header, signer: Address, epoch: u64) -> Result<Self, Error> {
+		let number = header.number();
+		let mut snapshot = self.clone();
+
+		let limit = snapshot.signer_limit();
+		if number >= limit {
+			snapshot.recents.remove(&(number - limit));
+		}
+		if !snapshot.signers.contains(&signer) {
+			return Err(EngineError::NotAuthorized(signer).into());
+		}
+		if snapshot.recently_signed(number, &signer) {
+			return Err(EngineError::DoubleVote(signer).into());
+		}
+		snapshot.recents.insert(number, signer);
+
+		if number % epoch == 0 {
+			snapshot.votes.clear();
+		} else {
+			let candidate = *header.author();
+			let authorize = header_vote(header)?;
+
+			// a new vote replaces the previous vote of the signer on the candidate
+			snapshot.votes.retain(|vote| vote.signer != signer || vote.candidate != candidate);
+			if authorize != snapshot.signers.contains(&candidate) {
+				snapshot.votes.push(Vote { signer: signer, candidate: candidate, authorize: authorize });
+			}
+
+			let votes = snapshot.votes.iter().filter(|vote| vote.candidate == candidate && vote.authorize == authorize).count();
+			if votes > snapshot.signers.len() / 2 {
+				if authorize {
+					snapshot.signers.insert(candidate);
+				} else {
+					snapshot.signers.remove(&candidate);
+					// the limit shrunk, the oldest signer may sign again
+					let limit = snapshot.signer_limit();
+					if number >= limit {
+						snapshot.recents.remove(&(number - limit));
+					}
+					snapshot.votes.retain(|vote| vote.signer != candidate);
+				}
+				snapshot.votes.retain(|vote| vote.candidate != candidate);
+			}
+		}
+
+		snapshot.hash = header.hash();
+		Ok(snapshot)
+	}
+}
+
+fn header_nonce(header: &Header) -> Result<H64, Error> {
+	let nonce = header.seal().get(1).ok_or(BlockError::InvalidSeal)?;
+	Ok(UntrustedRlp::new(nonce).as_val::<H64>()?)
+}
+
+/// Direction of the vote of the block signer.
+fn header_vote(header: &Header) -> Result<bool, Error> {
+	let nonce = header_nonce(header)?;
+	if nonce == H64::from(NONCE_AUTH) {
+		Ok(true)
+	} else if nonce == H64::from(NONCE_DROP) {
+		Ok(false)
+	} else {
+		Err(BlockError::InvalidSeal.into())
+	}
+}
+
+fn check_extra_data_length(header: &Header) -> Result<(), Error> {
+	let length = header.extra_data().len();
+	if length < VANITY_LENGTH + SIGNATURE_LENGTH {
+		return Err(BlockError::ExtraDataOutOfBounds(OutOfBounds { min: Some(VANITY_LENGTH + SIGNATURE_LENGTH), max: None, found: length }).into());
+	}
+	Ok(())
+}
+
+/// Signers listed in the extra data of a checkpoint block.
+fn checkpoint_signers(header: &Header) -> Result<Vec<Address>, Error> {
+	check_extra_data_length(header)?;
+	let extra_data = header.extra_data();
+	let signers = &extra_data[VANITY_LENGTH..extra_data.len() - SIGNATURE_LENGTH];
+	if signers.len() % 20 != 0 {
+		return Err(BlockError::InvalidSeal.into());
+	}
+	Ok(signers.chunks(20).map(Address::from_slice).collect())
+}
+
+/// Hash signed by the block signer: hash of the header with the signature stripped from the extra data.
+fn seal_hash(header: &Header) -> Result<H256, Error> {
+	check_extra_data_length(header)?;
+	let extra_data = header.extra_data();
+	let mut s = RlpStream::new_list(13 + header.seal().len());
+	s.append(header.parent_hash());
+	s.append(header.uncles_hash());
+	s.append(header.author());
+	s.append(header.state_root());
+	s.append(header.transactions_root());
+	s.append(header.receipts_root());
+	s.append(header.log_bloom());
+	s.append(header.difficulty());
+	s.append(&header.number());
+	s.append(header.gas_limit());
+	s.append(header.gas_used());
+	s.append(&header.timestamp());
+	s.append(&&extra_data[..extra_data.len() - SIGNATURE_LENGTH]);
+	for field in header.seal() {
+		s.append_raw(field, 1);
+	}
+	Ok(s.out().sha3())
+}
+
+fn recover_signer(header: &Header) -> Result<Address, Error> {
+	let hash = seal_hash(header)?;
+	let extra_data = header.extra_data();
+	let signature = H520::from_slice(&extra_data[extra_data.len() - SIGNATURE_LENGTH..]);
+	Ok(public_to_address(&recover(&signature.into(), &hash)?))
+}
+
+fn unix_now() -> Duration {
+	UNIX_EPOCH.elapsed().expect("Valid time has to be set in your system.")
+}
+
+struct TransitionHandler {
+	engine: Weak<Clique>,
+}
+
+const ENGINE_TIMEOUT_TOKEN: TimerToken = 23;
+
+/// Sealing is retried this often, so blocks are sealed once their time comes.
+const SEALING_RETRY_MS: u64 = 1000;
+
+impl IoHandler<()> for TransitionHandler {
+	fn initialize(&self, io: &IoContext<()>) {
+		io.register_timer(ENGINE_TIMEOUT_TOKEN, SEALING_RETRY_MS)
+			.unwrap_or_else(|e| warn!(target: "engine", "Failed to start sealing timer: {}.", e))
+	}
+
+	fn timeout(&self, _io: &IoContext<()>, timer: TimerToken) {
+		if timer == ENGINE_TIMEOUT_TOKEN {
+			if let Some(engine) = self.engine.upgrade() {
+				engine.step();
+			}
+		}
+	}
+}
+
+/// Engine using `Clique` proof-of-authority consensus.
+pub struct Clique {
+	params: CommonParams,
+	period: u64,
+	epoch: u64,
+	builtins: BTreeMap<Address, Builtin>,
+	transition_service: IoService<()>,
+	client: RwLock<Option<Weak<EngineClient>>>,
+	signer: EngineSigner,
+	/// Votes to cast by our signer: candidate and whether to authorize it.
+	proposals: RwLock<BTreeMap<Address, bool>>,
+	/// Snapshots by block hash.
+	snapshots: Mutex<LruCache<H256, Snapshot>>,
+	/// Recovered block signers by block hash.
+	signers: Mutex<LruCache<H256, Address>>,
+	/// Random delay of out-of-turn sealing on top of the given parent block.
+	out_of_turn_delay: Mutex<Option<(H256, u64)>>,
+}
+
+impl Clique {
+	/// Create a new instance of Clique engine.
+	pub fn new(params: CommonParams, our_params: CliqueParams, builtins: BTreeMap<Address, Builtin>) -> Result<Arc<Self>, Error> {
+		let engine = Arc::new(Clique {
+			params: params,
+			period: our_params.period,
+			epoch: cmp::max(our_params.epoch, 1),
+			builtins: builtins,
+			transition_service: IoService::<()>::start()?,
+			client: RwLock::new(None),
+			signer: Default::default(),
+			proposals: RwLock::new(BTreeMap::new()),
+			snapshots: Mutex::new(LruCache::new(CACHE_SIZE)),
+			signers: Mutex::new(LruCache::new(CACHE_SIZE)),
+			out_of_turn_delay: Mutex::new(None),
+		});
+		let handler = TransitionHandler { engine: Arc::downgrade(&engine) };
+		engine.transition_service.register_handler(Arc::new(handler))?;
+		Ok(engine)
+	}
+
+	/// Vote on adding (`authorize`) or removing the candidate in the blocks we seal.
+	pub fn propose(&self, candidate: Address, authorize: bool) {
+		self.proposals.write().insert(candidate, authorize);
+	}
+
+	/// Stop voting on the candidate.
+	pub fn discard(&self, candidate: &Address) {
+		self.proposals.write().remove(candidate);
+	}
+
+	/// Current proposals: candidates and whether they are to be authorized.
+	pub fn proposals(&self) -> BTreeMap<Address, bool> {
+		self.proposals.read().clone()
+	}
+
+	fn header(&self, hash: &H256) -> Result<Header, Error> {
+		let client = match self.client.read().as_ref().and_then(Weak::upgrade) {
+			Some(client) => client,
+			None => return Err(EngineError::RequiresClient.into()),
+		};
+		client.block_header(BlockId::Hash(*hash))
+			.map(|header| header.decode())
+			.ok_or_else(|| BlockError::UnknownParent(*hash).into())
+	}
+
+	fn signer_of(&self, header: &Header) -> Result<Address, Error> {
+		let hash = header.hash();
+		if let Some(signer) = self.signers.lock().get_mut(&hash).cloned() {
+			return Ok(signer);
+		}
+		let signer = recover_signer(header)?;
+		self.signers.lock().insert(hash, signer);
+		Ok(signer)
+	}
+
+	fn cached_snapshot(&self, hash: &H256) -> Option<Snapshot> {
+		self.snapshots.lock().get_mut(hash).cloned()
+	}
+
+	/// Signers and votes after the given block.
+	/// Ancestors back to the last checkpoint (and the recent signers before it) are looked up
+	/// unless a snapshot is cached.
+	fn snapshot(&self, header: &Header) -> Result<Snapshot, Error> {
+		let mut headers = Vec::new();
+		let mut current = header.clone();
+		let mut snapshot = self.cached_snapshot(&current.hash());
+		while snapshot.is_none() {
+			if current.number() % self.epoch == 0 {
+				let mut ancestor = current.clone();
+				let checkpoint = Snapshot::from_checkpoint(&current, |number| {
+					while ancestor.number() > number {
+						ancestor = self.header(ancestor.parent_hash())?;
+					}
+					self.signer_of(&ancestor)
+				})?;
+				self.snapshots.lock().insert(checkpoint.hash, checkpoint.clone());
+				snapshot = Some(checkpoint);
+			} else {
+				let parent = self.header(current.parent_hash())?;
+				headers.push(current);
+				current = parent;
+				snapshot = self.cached_snapshot(&current.hash());
+			}
+		}
+
+		let mut snapshot = snapshot.expect("loop ends only once the snapshot is found; qed");
+		for header in headers.into_iter().rev() {
+			snapshot = snapshot.apply(&header, self.signer_of(&header)?, self.epoch)?;
+			self.snapshots.lock().insert(snapshot.hash, snapshot.clone());
+		}
+		Ok(snapshot)
+	}
+
+	/// Snapshot after the block with given hash.
+	fn snapshot_by_hash(&self, hash: &H256) -> Result<Snapshot, Error> {
+		match self.cached_snapshot(hash) {
+			Some(snapshot) => Ok(snapshot),
+			None => self.snapshot(&self.header(hash)?),
+		}
+	}
+
+	/// Candidate to vote on in a block on top of the snapshot.
+	fn pick_candidate(&self, snapshot: &Snapshot) -> Option<Address> {
+		let candidates: Vec<_> = self.proposals.read().iter()
+			.filter(|&(candidate, authorize)| *authorize != snapshot.signers.contains(candidate))
+			.map(|(candidate, _)| *candidate)
+			.collect();
+		rand::thread_rng().choose(&candidates).cloned()
+	}
+
+	/// Random delay of out-of-turn sealing on top of the parent, the same for all attempts.
+	fn out_of_turn_delay(&self, parent_hash: &H256, snapshot: &Snapshot) -> u64 {
+		let mut delay = self.out_of_turn_delay.lock();
+		let cached = match *delay {
+			Some((hash, ms)) if hash == *parent_hash => Some(ms),
+			_ => None,
+		};
+		match cached {
+			Some(ms) => ms,
+			None => {
+				let ms = rand::thread_rng().gen_range(0, snapshot.signer_limit() * WIGGLE_TIME_MS + 1);
+				*delay = Some((*parent_hash, ms));
+				ms
+			},
+		}
+	}
+}
+
+impl Engine for Clique {
+	fn name(&self) -> &str { "Clique" }
+	fn version(&self) -> SemanticVersion { SemanticVersion::new(1, 0, 0) }
+	/// Two fields - mix hash (always zero) and nonce (the vote), the signature is in the extra data.
+	fn seal_fields(&self) -> usize { 2 }
+
+	fn params(&self) -> &CommonParams { &self.params }
+	fn builtins(&self) -> &BTreeMap<Address, Builtin> { &self.builtins }
+
+	/// Vanity, signers and signature are checked by `verify_block_basic`.
+	fn maximum_extra_data_size(&self) -> usize { usize::max_value() }
+	fn maximum_uncle_count(&self) -> usize { 0 }
+
+	fn additional_params(&self) -> HashMap<String, String> {
+		hash_map![
+			"period".to_owned() => format!("{}", self.period),
+			"epoch".to_owned() => format!("{}", self.epoch)
+		]
+	}
+
+	/// Additional engine-specific information for the user/developer concerning `header`.
+	fn extra_info(&self, header: &Header) -> BTreeMap<String, String> {
+		map![
+			"signer".into() => self.signer_of(header).map(|signer| signer.hex()).unwrap_or_default(),
+			"vote".into() => header_vote(header).map(|authorize| (if authorize { "auth" } else { "drop" }).to_owned()).unwrap_or_default()
+		]
+	}
+
+	fn schedule(&self, block_number: BlockNumber) -> Schedule {
+		let eip86 = block_number >= self.params.eip86_transition;
+		Schedule::new_post_eip150(usize::max_value(), true, true, true, eip86)
+	}
+
+	fn step(&self) {
+		if self.signer.address() == Address::default() {
+			return;
+		}
+		if let Some(ref weak) = *self.client.read() {
+			if let Some(c) = weak.upgrade() {
+				c.update_sealing();
+			}
+		}
+	}
+
+	fn populate_from_parent(&self, header: &mut Header, parent: &Header, gas_floor_target: U256, _gas_ceil_target: U256) {
+		header.set_gas_limit({
+			let gas_limit = parent.gas_limit().clone();
+			let bound_divisor = U256::from(GAS_LIMIT_BOUND_DIVISOR);
+			if gas_limit < gas_floor_target {
+				min(gas_floor_target, gas_limit + gas_limit / bound_divisor - 1.into())
+			} else {
+				max(gas_floor_target, gas_limit - gas_limit / bound_divisor + 1.into())
+			}
+		});
+		let timestamp = max(header.timestamp(), parent.timestamp() + self.period);
+		header.set_timestamp(timestamp);
+
+		let snapshot = match self.snapshot(parent) {
+			Ok(snapshot) => Some(snapshot),
+			Err(e) => {
+				trace!(target: "engine", "populate_from_parent: no snapshot of parent {}: {}", parent.hash(), e);
+				None
+			},
+		};
+		let number = header.number();
+		let in_turn = snapshot.as_ref().map_or(false, |snapshot| snapshot.is_in_turn(number, &self.signer.address()));
+		header.set_difficulty(if in_turn { DIFF_IN_TURN } else { DIFF_NO_TURN }.into());
+
+		let mut extra_data = header.extra_data().clone();
+		extra_data.resize(VANITY_LENGTH, 0);
+		if number % self.epoch == 0 {
+			header.set_author(Address::default());
+			if let Some(ref snapshot) = snapshot {
+				for signer in &snapshot.signers {
+					extra_data.extend_from_slice(&**signer);
+				}
+			}
+		} else {
+			let candidate = snapshot.as_ref().and_then(|snapshot| self.pick_candidate(snapshot));
+			header.set_author(candidate.unwrap_or_default());
+		}
+		extra_data.resize(extra_data.len() + SIGNATURE_LENGTH, 0);
+		header.set_extra_data(extra_data);
+	}
+
+	fn seals_internally(&self) -> Option<bool> {
+		Some(self.signer.address() != Address::default())
+	}
+
+	/// Attempt to seal the block internally.
+	/// The third seal field is the signature, moved to the extra data by `apply_seal`.
+	fn generate_seal(&self, block: &ExecutedBlock) -> Seal {
+		let header = block.header();
+		let number = header.number();
+		let signer = self.signer.address();
+
+		let snapshot = match self.snapshot_by_hash(header.parent_hash()) {
+			Ok(snapshot) => snapshot,
+			Err(e) => {
+				warn!(target: "engine", "generate_seal: no snapshot of parent {}: {}", header.parent_hash(), e);
+				return Seal::None;
+			},
+		};
+		if !snapshot.signers.contains(&signer) {
+			trace!(target: "engine", "generate_seal: {} is not an authorized signer.", signer);
+			return Seal::None;
+		}
+		if snapshot.recently_signed(number, &signer) {
+			trace!(target: "engine", "generate_seal: {} signed recently, waiting for others.", signer);
+			return Seal::None;
+		}
+		let in_turn = snapshot.is_in_turn(number, &signer);
+		if *header.difficulty() != U256::from(if in_turn { DIFF_IN_TURN } else { DIFF_NO_TURN }) {
+			trace!(target: "engine", "generate_seal: block was prepared for a different signer.");
+			return Seal::None;
+		}
+
+		let delay = if in_turn { 0 } else { self.out_of_turn_delay(header.parent_hash(), &snapshot) };
+		let now = unix_now();
+		let now_ms = now.as_secs() * 1000 + (now.subsec_nanos() / 1_000_000) as u64;
+		if now_ms < header.timestamp() * 1000 + delay {
+			trace!(target: "engine", "generate_seal: waiting for the time of block {}.", number);
+			return Seal::None;
+		}
+
+		let authorize = number % self.epoch != 0 && self.proposals.read().get(header.author()) == Some(&true);
+		let nonce = H64::from(if authorize { NONCE_AUTH } else { NONCE_DROP });
+		let seal = vec![encode(&H256::default()).to_vec(), encode(&nonce).to_vec()];
+
+		let mut sealed = header.clone();
+		sealed.set_seal(seal.clone());
+		let signature = match seal_hash(&sealed).and_then(|hash| self.signer.sign(hash).map_err(Into::into)) {
+			Ok(signature) => signature,
+			Err(e) => {
+				warn!(target: "engine", "generate_seal: FAIL: {}", e);
+				return Seal::None;
+			},
+		};
+		trace!(target: "engine", "generate_seal: signing block {} {}.", number, if in_turn { "in turn" } else { "out of turn" });
+		Seal::Regular(seal.into_iter().chain(Some(H520::from(signature).to_vec())).collect())
+	}
+
+	fn apply_seal(&self, header: &mut Header, mut seal: Vec<Bytes>) -> Result<(), BlockError> {
+		if seal.len() == self.seal_fields() + 1 {
+			let signature = seal.pop().expect("seal has at least one field; qed");
+			let length = header.extra_data().len();
+			if length < VANITY_LENGTH + SIGNATURE_LENGTH || signature.len() != SIGNATURE_LENGTH {
+				return Err(BlockError::InvalidSeal);
+			}
+			header.extra_data_mut()[length - SIGNATURE_LENGTH..].copy_from_slice(&signature);
+		}
+		header.set_seal(seal);
+		Ok(())
+	}
+
+	fn verify_block_basic(&self, header: &Header, _block: Option<&[u8]>) -> Result<(), Error> {
+		if header.seal().len() != self.seal_fields() {
+			return Err(From::from(BlockError::InvalidSealArity(
+				Mismatch { expected: self.seal_fields(), found: header.seal().len() }
+			)));
+		}
+		let number = header.number();
+		let checkpoint = number % self.epoch == 0;
+
+		let signers = checkpoint_signers(header)?;
+		if checkpoint == signers.is_empty() {
+			trace!(target: "engine", "verify_block_basic: signers listed in a block which is not a checkpoint or missing in one");
+			return Err(BlockError::InvalidSeal.into());
+		}
+		let authorize = header_vote(header)?;
+		if checkpoint && (authorize || *header.author() != Address::default()) {
+			trace!(target: "engine", "verify_block_basic: vote in a checkpoint");
+			return Err(BlockError::InvalidSeal.into());
+		}
+		let mix_hash = UntrustedRlp::new(&header.seal()[0]).as_val::<H256>()?;
+		if !mix_hash.is_zero() {
+			return Err(BlockError::MismatchedH256SealElement(Mismatch { expected: H256::default(), found: mix_hash }).into());
+		}
+		if *header.uncles_hash() != SHA3_EMPTY_LIST_RLP {
+			return Err(BlockError::InvalidUnclesHash(Mismatch { expected: SHA3_EMPTY_LIST_RLP, found: *header.uncles_hash() }).into());
+		}
+		if number > 0 && *header.difficulty() != DIFF_IN_TURN.into() && *header.difficulty() != DIFF_NO_TURN.into() {
+			return Err(BlockError::DifficultyOutOfBounds(OutOfBounds { min: Some(DIFF_NO_TURN.into()), max: Some(DIFF_IN_TURN.into()), found: *header.difficulty() }).into());
+		}
+		Ok(())
+	}
+
+	fn verify_block_unordered(&self, header: &Header, _block: Option<&[u8]>) -> Result<(), Error> {
+		self.signer_of(header).map(|_| ())
+	}
+
+	fn verify_block_family(&self, header: &Header, parent: &Header, _block: Option<&[u8]>) -> Result<(), Error> {
+		// Do not calculate difficulty for genesis blocks.
+		if header.number() == 0 {
+			return Err(From::from(BlockError::RidiculousNumber(OutOfBounds { min: Some(1), max: None, found: header.number() })));
+		}
+
+		let min_timestamp = parent.timestamp() + self.period;
+		if header.timestamp() < min_timestamp {
+			return Err(From::from(BlockError::InvalidTimestamp(OutOfBounds { min: Some(min_timestamp), max: None, found: header.timestamp() })));
+		}
+
+		let gas_limit_divisor = U256::from(GAS_LIMIT_BOUND_DIVISOR);
+		let min_gas = parent.gas_limit().clone() - parent.gas_limit().clone() / gas_limit_divisor;
+		let max_gas = parent.gas_limit().clone() + parent.gas_limit().clone() / gas_limit_divisor;
+		if header.gas_limit() <= &min_gas || header.gas_limit() >= &max_gas {
+			return Err(From::from(BlockError::InvalidGasLimit(OutOfBounds { min: Some(min_gas), max: Some(max_gas), found: header.gas_limit().clone() })));
+		}
+
+		let snapshot = self.snapshot(parent)?;
+		let signer = self.signer_of(header)?;
+		let expected_difficulty = U256::from(if snapshot.is_in_turn(header.number(), &signer) { DIFF_IN_TURN } else { DIFF_NO_TURN });
+		if *header.difficulty() != expected_difficulty {
+			return Err(From::from(BlockError::InvalidDifficulty(Mismatch { expected: expected_difficulty, found: *header.difficulty() })));
+		}
+		if header.number() % self.epoch == 0 {
+			let signers: Vec<_> = snapshot.signers.iter().cloned().collect();
+			if checkpoint_signers(header)? != signers {
+				trace!(target: "engine", "verify_block_family: checkpoint signers differ from the voted ones");
+				return Err(BlockError::InvalidSeal.into());
+			}
+		}
+
+		let snapshot = snapshot.apply(header, signer, self.epoch)?;
+		self.snapshots.lock().insert(snapshot.hash, snapshot);
+		Ok(())
+	}
+
+	fn register_client(&self, client: Weak<Client>) {
+		*self.client.write() = Some(client);
+	}
+
+	fn set_signer(&self, ap: Arc<AccountProvider>, address: Address, password: String) {
+		self.signer.set(ap, address, password);
+	}
+
+	fn sign(&self, hash: H256) -> Result<Signature, Error> {
+		self.signer.sign(hash).map_err(Into::into)
+	}
+
+	fn propose_signer(&self, candidate: Address, authorize: bool) -> bool {
+		self.propose(candidate, authorize);
+		true
+	}
+
+	fn discard_signer(&self, candidate: &Address) -> bool {
+		self.discard(candidate);
+		true
+	}
+
+	fn signer_proposals(&self) -> Option<BTreeMap<Address, bool>> {
+		Some(self.proposals())
+	}
+
+	fn stop(&self) {
+		self.transition_service.stop()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::*;
+	use rlp::encode;
+	use block::*;
+	use error::{BlockError, Error};
+	use tests::helpers::*;
+	use account_provider::AccountProvider;
+	use ethkey::Secret;
+	use header::Header;
+	use spec::Spec;
+	use engines::{Engine, Seal, EngineError};
+	use super::{Clique, CliqueParams, Snapshot, NONCE_AUTH, NONCE_DROP};
+
+	fn vote_header(number: u64, candidate: Address, authorize: bool) -> Header {
+		let mut header = Header::default();
+		header.set_number(number);
+		header.set_author(candidate);
+		let nonce = H64::from(if authorize { NONCE_AUTH } else { NONCE_DROP });
+		header.set_seal(vec![encode(&H256::default()).to_vec(), encode(&nonce).to_vec()]);
+		header
+	}
+
+	fn seal_block(engine: &Engine, spec: &Spec, author: Address) -> SealedBlock {
+		let genesis_header = spec.genesis_header();
+		let db = spec.ensure_db_good(get_temp_state_db(), &Default::default()).unwrap();
+		let last_hashes = Arc::new(vec![genesis_header.hash()]);
+		let b = OpenBlock::new(engine, Default::default(), false, db, &genesis_header, last_hashes, author, (3141562.into(), 31415620.into()), vec![]).unwrap();
+		let b = b.close_and_lock();
+		match engine.generate_seal(b.block()) {
+			Seal::Regular(seal) => b.seal(engine, seal).unwrap(),
+			other => panic!("Expected a regular seal, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn has_valid_metadata() {
+		let engine = Spec::new_test_clique().engine;
+		assert!(!engine.name().is_empty());
+		assert!(engine.version().major >= 1);
+		assert_eq!(engine.seal_fields(), 2);
+	}
+
+	#[test]
+	fn should_count_votes_and_recent_signers() {
+		let (a, b, c, d) = (Address::from(1), Address::from(2), Address::from(3), Address::from(4));
+		let snapshot = Snapshot {
+			hash: H256::default(),
+			signers: vec![a, b, c].into_iter().collect(),
+			recents: BTreeMap::new(),
+			votes: Vec::new(),
+		};
+
+		// a single vote of three signers is not enough
+		let snapshot = snapshot.apply(&vote_header(1, d, true), a, 30000).unwrap();
+		assert!(!snapshot.signers.contains(&d));
+		// signers may not sign consecutive blocks
+		match snapshot.apply(&vote_header(2, d, true), a, 30000) {
+			Err(Error::Engine(EngineError::DoubleVote(signer))) => assert_eq!(signer, a),
+			other => panic!("Expected a recently signed error, got {:?}", other),
+		}
+		match snapshot.apply(&vote_header(2, d, true), d, 30000) {
+			Err(Error::Engine(EngineError::NotAuthorized(signer))) => assert_eq!(signer, d),
+			other => panic!("Expected an unauthorized signer error, got {:?}", other),
+		}
+
+		let snapshot = snapshot.apply(&vote_header(2, d, true), b, 30000).unwrap();
+		assert!(snapshot.signers.contains(&d));
+		assert!(snapshot.votes.is_empty());
+
+		let snapshot = snapshot.apply(&vote_header(3, a, false), c, 30000).unwrap();
+		let snapshot = snapshot.apply(&vote_header(4, a, false), d, 30000).unwrap();
+		assert!(snapshot.signers.contains(&a));
+		let snapshot = snapshot.apply(&vote_header(5, a, false), b, 30000).unwrap();
+		assert_eq!(snapshot.signers, vec![b, c, d].into_iter().collect());
+		assert!(snapshot.votes.is_empty());
+		assert!(snapshot.is_in_turn(6, &b));
+	}
+
+	#[test]
+	fn should_reset_votes_at_checkpoint() {
+		let (a, b, c) = (Address::from(1), Address::from(2), Address::from(3));
+		let snapshot = Snapshot {
+			hash: H256::default(),
+			signers: vec![a, b].into_iter().collect(),
+			recents: BTreeMap::new(),
+			votes: Vec::new(),
+		};
+
+		let snapshot = snapshot.apply(&vote_header(9, c, true), a, 10).unwrap();
+		assert_eq!(snapshot.votes.len(), 1);
+		let snapshot = snapshot.apply(&vote_header(10, Address::default(), false), b, 10).unwrap();
+		assert!(snapshot.votes.is_empty());
+		assert!(!snapshot.signers.contains(&c));
+	}
+
+	#[test]
+	fn should_rebuild_recent_signers_at_checkpoint() {
+		let signers: Vec<_> = (1..6).map(Address::from).collect();
+		let signer_of = |number: u64| signers[(number as usize - 1) % signers.len()];
+		let mut snapshot = Snapshot {
+			hash: H256::default(),
+			signers: signers.iter().cloned().collect(),
+			recents: BTreeMap::new(),
+			votes: Vec::new(),
+		};
+		for number in 1..10 {
+			snapshot = snapshot.apply(&vote_header(number, Address::default(), false), signer_of(number), 10).unwrap();
+		}
+		let mut checkpoint = vote_header(10, Address::default(), false);
+		let mut extra_data = vec![0; 32];
+		for signer in &signers {
+			extra_data.extend_from_slice(&**signer);
+		}
+		extra_data.extend_from_slice(&[0; 65]);
+		checkpoint.set_extra_data(extra_data);
+		let snapshot = snapshot.apply(&checkpoint, signer_of(10), 10).unwrap();
+
+		let rebuilt = Snapshot::from_checkpoint(&checkpoint, |number| Ok(signer_of(number))).unwrap();
+		assert_eq!(rebuilt, snapshot);
+		// the signer of block 9 is still within the limit of 3 blocks
+		match rebuilt.apply(&vote_header(11, Address::default(), false), signer_of(9), 10) {
+			Err(Error::Engine(EngineError::DoubleVote(signer))) => assert_eq!(signer, signer_of(9)),
+			other => panic!("Expected a recently signed error, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn can_generate_and_verify_seal() {
+		let tap = AccountProvider::transient_provider();
+		let addr = tap.insert_account(Secret::from_slice(&"".sha3()).unwrap(), "").unwrap();
+
+		let spec = Spec::new_test_clique();
+		let engine = &*spec.engine;
+		engine.set_signer(Arc::new(tap), addr, "".into());
+		assert!(engine.seals_internally().unwrap());
+
+		let sealed = seal_block(engine, &spec, addr);
+		let header = sealed.header();
+		assert_eq!(*header.difficulty(), 2.into());
+		assert_eq!(*header.author(), Address::default());
+		assert_eq!(engine.extra_info(header)["signer"], addr.hex());
+		assert!(engine.verify_block_basic(header, None).is_ok());
+		assert!(engine.verify_block_unordered(header, None).is_ok());
+		assert!(engine.verify_block_family(header, &spec.genesis_header(), None).is_ok());
+	}
+
+	#[test]
+	fn should_vote_for_proposals() {
+		let tap = AccountProvider::transient_provider();
+		let addr = tap.insert_account(Secret::from_slice(&"".sha3()).unwrap(), "").unwrap();
+		let candidate = Address::from(5);
+
+		let spec = Spec::new_test_clique();
+		let engine = Clique::new(Default::default(), CliqueParams { period: 1, epoch: 30000 }, BTreeMap::new()).unwrap();
+		engine.set_signer(Arc::new(tap), addr, "".into());
+		engine.propose(candidate, true);
+
+		let sealed = seal_block(&*engine, &spec, addr);
+		assert_eq!(*sealed.header().author(), candidate);
+		assert_eq!(engine.extra_info(sealed.header())["vote"], "auth");
+		assert!(engine.verify_block_family(sealed.header(), &spec.genesis_header(), None).is_ok());
+
+		engine.discard(&candidate);
+		assert!(engine.proposals().is_empty());
+	}
+
+	#[test]
+	fn should_reject_invalid_extra_data() {
+		let engine = Spec::new_test_clique().engine;
+		let mut header = vote_header(1, Address::default(), false);
+
+		match engine.verify_block_basic(&header, None) {
+			Err(Error::Block(BlockError::ExtraDataOutOfBounds(_))) => {},
+			other => panic!("Expected extra data error, got {:?}", other),
+		}
+
+		// signers listed outside of a checkpoint
+		header.set_extra_data(vec![0; 32 + 20 + 65]);
+		match engine.verify_block_basic(&header, None) {
+			Err(Error::Block(BlockError::InvalidSeal)) => {},
+			other => panic!("Expected invalid seal error, got {:?}", other),
+		}
+
+		header.set_seal(vec![]);
+		match engine.verify_block_basic(&header, None) {
+			Err(Error::Block(BlockError::InvalidSealArity(_))) => {},
+			other => panic!("Expected seal arity error, got {:?}", other),
+		}
+	}
+}
@@ -15,28 +15,116 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::BTreeMap;
-use util::{Address, HashMap};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use util::{Address, HashMap, Mutex, RwLock};
 use builtin::Builtin;
+use client::{Client, EngineClient};
 use engines::{Engine, Seal};
+use error::Error;
 use spec::CommonParams;
 use evm::Schedule;
-use block::ExecutedBlock;
+use block::{ExecutedBlock, IsBlock};
 use header::BlockNumber;
+use io::{IoContext, IoHandler, IoService, TimerToken};
+use ethjson;
+
+/// `InstantSeal` params.
+#[derive(Debug, PartialEq)]
+pub struct InstantSealParams {
+	/// Address of the registrar contract.
+	pub registrar: Address,
+	/// Minimal time between blocks, every block is sealed right away if not set.
+	pub block_time: Option<Duration>,
+	/// Whether blocks with transactions are sealed without waiting for the block time.
+	pub seal_on_transactions: bool,
+}
+
+impl From<ethjson::spec::InstantSealParams> for InstantSealParams {
+	fn from(p: ethjson::spec::InstantSealParams) -> Self {
+		InstantSealParams {
+			registrar: p.registrar.map_or_else(Address::new, Into::into),
+			block_time: p.block_time.map(|t| Duration::from_secs(t.into())),
+			seal_on_transactions: p.seal_on_transactions.unwrap_or(false),
+		}
+	}
+}
+
+struct TransitionHandler {
+	engine: Weak<InstantSeal>,
+}
+
+const ENGINE_TIMEOUT_TOKEN: TimerToken = 23;
+
+/// Sealing is retried this often when waiting for the block time.
+const SEALING_RETRY_MS: u64 = 1000;
+
+impl IoHandler<()> for TransitionHandler {
+	fn initialize(&self, io: &IoContext<()>) {
+		io.register_timer(ENGINE_TIMEOUT_TOKEN, SEALING_RETRY_MS)
+			.unwrap_or_else(|e| warn!(target: "engine", "Failed to start sealing timer: {}.", e))
+	}
+
+	fn timeout(&self, _io: &IoContext<()>, timer: TimerToken) {
+		if timer == ENGINE_TIMEOUT_TOKEN {
+			if let Some(engine) = self.engine.upgrade() {
+				engine.step();
+			}
+		}
+	}
+}
 
 /// An engine which does not provide any consensus mechanism, just seals blocks internally.
 pub struct InstantSeal {
 	params: CommonParams,
 	registrar: Address,
+	block_time: Option<Duration>,
+	seal_on_transactions: bool,
 	builtins: BTreeMap<Address, Builtin>,
+	/// When the last block was sealed.
+	last_sealed: Mutex<Instant>,
+	/// Retries sealing while waiting for the block time, only started if there is one.
+	transition_service: Option<IoService<()>>,
+	client: RwLock<Option<Weak<EngineClient>>>,
 }
 
 impl InstantSeal {
 	/// Returns new instance of InstantSeal with default VM Factory
-	pub fn new(params: CommonParams, registrar: Address, builtins: BTreeMap<Address, Builtin>) -> Self {
-		InstantSeal {
+	pub fn new(params: CommonParams, our_params: InstantSealParams, builtins: BTreeMap<Address, Builtin>) -> Result<Arc<Self>, Error> {
+		let transition_service = match our_params.block_time {
+			Some(_) => Some(IoService::<()>::start()?),
+			None => None,
+		};
+		let engine = Arc::new(InstantSeal {
 			params: params,
-			registrar: registrar,
+			registrar: our_params.registrar,
+			block_time: our_params.block_time,
+			seal_on_transactions: our_params.seal_on_transactions,
 			builtins: builtins,
+			last_sealed: Mutex::new(Instant::now()),
+			transition_service: transition_service,
+			client: RwLock::new(None),
+		});
+		if let Some(ref service) = engine.transition_service {
+			let handler = TransitionHandler { engine: Arc::downgrade(&engine) };
+			service.register_handler(Arc::new(handler))?;
+		}
+		Ok(engine)
+	}
+
+	fn step(&self) {
+		if let Some(ref weak) = *self.client.read() {
+			if let Some(c) = weak.upgrade() {
+				c.update_sealing();
+			}
+		}
+	}
+
+	/// Whether a block is due to be sealed now.
+	fn is_sealing_due(&self, has_transactions: bool) -> bool {
+		match self.block_time {
+			Some(block_time) => (self.seal_on_transactions && has_transactions) || self.last_sealed.lock().elapsed() >= block_time,
+			None => true,
 		}
 	}
 }
@@ -65,9 +153,23 @@ impl Engine for InstantSeal {
 
 	fn seals_internally(&self) -> Option<bool> { Some(true) }
 
-	fn generate_seal(&self, _block: &ExecutedBlock) -> Seal {
+	fn generate_seal(&self, block: &ExecutedBlock) -> Seal {
+		if !self.is_sealing_due(!block.transactions().is_empty()) {
+			return Seal::None;
+		}
+		*self.last_sealed.lock() = Instant::now();
 		Seal::Regular(Vec::new())
 	}
+
+	fn register_client(&self, client: Weak<Client>) {
+		*self.client.write() = Some(client);
+	}
+
+	fn stop(&self) {
+		if let Some(ref service) = self.transition_service {
+			service.stop()
+		}
+	}
 }
 
 #[cfg(test)]
@@ -93,6 +195,26 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn instant_waits_for_block_time() {
+		let spec = Spec::new_instant_with_block_time(3600, false);
+		let engine = &*spec.engine;
+		let db = spec.ensure_db_good(get_temp_state_db(), &Default::default()).unwrap();
+		let genesis_header = spec.genesis_header();
+		let last_hashes = Arc::new(vec![genesis_header.hash()]);
+		let b = OpenBlock::new(engine, Default::default(), false, db, &genesis_header, last_hashes, Address::default(), (3141562.into(), 31415620.into()), vec![]).unwrap();
+		let b = b.close_and_lock();
+		assert_eq!(engine.generate_seal(b.block()), Seal::None);
+
+		let spec = Spec::new_instant_with_block_time(0, false);
+		let engine = &*spec.engine;
+		let db = spec.ensure_db_good(get_temp_state_db(), &Default::default()).unwrap();
+		let last_hashes = Arc::new(vec![genesis_header.hash()]);
+		let b = OpenBlock::new(engine, Default::default(), false, db, &genesis_header, last_hashes, Address::default(), (3141562.into(), 31415620.into()), vec![]).unwrap();
+		let b = b.close_and_lock();
+		assert_eq!(engine.generate_seal(b.block()), Seal::Regular(Vec::new()));
+	}
+
 	#[test]
 	fn instant_cant_verify() {
 		let engine = Spec::new_instant().engine;
@@ -18,6 +18,7 @@
 
 mod authority_round;
 mod basic_authority;
+mod clique;
 mod epoch_verifier;
 mod instant_seal;
 mod null_engine;
@@ -29,6 +30,7 @@ mod vote_collector;
 
 pub use self::authority_round::AuthorityRound;
 pub use self::basic_authority::BasicAuthority;
+pub use self::clique::Clique;
 pub use self::epoch_verifier::EpochVerifier;
 pub use self::instant_seal::InstantSeal;
 pub use self::null_engine::NullEngine;
@@ -41,7 +43,7 @@ use block::ExecutedBlock;
 use builtin::Builtin;
 use client::Client;
 use env_info::EnvInfo;
-use error::Error;
+use error::{Error, BlockError};
 use evm::Schedule;
 use header::{Header, BlockNumber};
 use receipt::Receipt;
@@ -67,6 +69,10 @@ pub enum EngineError {
 	BadSealFieldSize(OutOfBounds<usize>),
 	/// Validation proof insufficient.
 	InsufficientProof(String),
+	/// The engine needs the client to look up ancestors, but none was registered.
+	RequiresClient,
+	/// Engine parameters of the chain spec are invalid.
+	InvalidParams(String),
 }
 
 impl fmt::Display for EngineError {
@@ -79,6 +85,8 @@ impl fmt::Display for EngineError {
 			UnexpectedMessage => "This Engine should not be fed messages.".into(),
 			BadSealFieldSize(ref oob) => format!("Seal field has an unexpected length: {}", oob),
 			InsufficientProof(ref msg) => format!("Insufficient validation proof: {}", msg),
+			RequiresClient => "Call requires client but none registered.".into(),
+			InvalidParams(ref msg) => format!("Invalid engine params: {}", msg),
 		};
 
 		f.write_fmt(format_args!("Engine error ({})", msg))
@@ -148,6 +156,11 @@ pub trait Engine : Sync + Send {
 	/// (In principle these are just hints for the engine since that has the last word on them.)
 	fn builtins(&self) -> &BTreeMap<Address, Builtin>;
 
+	/// Block numbers at which the consensus rules change. Used to compute the fork identifier.
+	fn fork_transitions(&self) -> Vec<BlockNumber> {
+		vec![self.params().eip98_transition, self.params().eip86_transition]
+	}
+
 	/// Some intrinsic operation parameters; by default they take their value from the `spec()`'s `engine_params`.
 	fn maximum_extra_data_size(&self) -> usize { self.params().maximum_extra_data_size }
 	/// Maximum number of uncles a block is allowed to declare.
@@ -174,6 +187,13 @@ pub trait Engine : Sync + Send {
 	/// be returned.
 	fn generate_seal(&self, _block: &ExecutedBlock) -> Seal { Seal::None }
 
+	/// Put the seal generated by `generate_seal` into the header.
+	/// Engines keeping a part of the seal outside of the seal fields (e.g. in the extra data) override it.
+	fn apply_seal(&self, header: &mut Header, seal: Vec<Bytes>) -> Result<(), BlockError> {
+		header.set_seal(seal);
+		Ok(())
+	}
+
 	/// Phase 1 quick block verification. Only does checks that are cheap. `block` (the header's full block)
 	/// may be provided for additional checks. Returns either a null `Ok` or a general error detailing the problem with import.
 	fn verify_block_basic(&self, _header: &Header,  _block: Option<&[u8]>) -> Result<(), Error> { Ok(()) }
@@ -286,6 +306,25 @@ pub trait Engine : Sync + Send {
 	/// Trigger next step of the consensus engine.
 	fn step(&self) {}
 
+	/// For engines sealing in turns: the current step and the address expected to seal
+	/// a block in it on top of the given parent.
+	fn expected_proposer(&self, _parent_hash: &H256) -> Option<(usize, Address)> { None }
+
+	/// For engines sealing in turns: the addresses which missed their turns to seal a block
+	/// between the parent and the header, with the number of turns missed.
+	fn missed_turns(&self, _parent: &Header, _header: &Header) -> Vec<(Address, u64)> { Vec::new() }
+
+	/// For engines voting on their signers: vote on adding (`authorize`) or removing the candidate
+	/// in the blocks we seal. Returns `false` if the engine doesn't vote on signers.
+	fn propose_signer(&self, _candidate: Address, _authorize: bool) -> bool { false }
+
+	/// For engines voting on their signers: stop voting on the candidate.
+	/// Returns `false` if the engine doesn't vote on signers.
+	fn discard_signer(&self, _candidate: &Address) -> bool { false }
+
+	/// For engines voting on their signers: candidates we vote on and whether to authorize them.
+	fn signer_proposals(&self) -> Option<BTreeMap<Address, bool>> { None }
+
 	/// Stops any services that the may hold the Engine and makes it safe to drop.
 	fn stop(&self) {}
 
@@ -16,23 +16,30 @@
 
 //! A signer used by Engines which need to sign messages.
 
-use util::{Arc, Mutex, RwLock, H256, Address};
+use util::{Arc, RwLock, H256, Address};
 use ethkey::Signature;
 use account_provider::{self, AccountProvider};
 
+// Kept under a single lock so that the key can be swapped atomically.
+struct Credentials {
+	account_provider: Arc<AccountProvider>,
+	address: Address,
+	password: Option<String>,
+}
+
 /// Everything that an Engine needs to sign messages.
 pub struct EngineSigner {
-	account_provider: Mutex<Arc<AccountProvider>>,
-	address: RwLock<Address>,
-	password: RwLock<Option<String>>,
+	credentials: RwLock<Credentials>,
 }
 
 impl Default for EngineSigner {
 	fn default() -> Self {
 		EngineSigner {
-			account_provider: Mutex::new(Arc::new(AccountProvider::transient_provider())),
-			address: Default::default(),
-			password: Default::default(),
+			credentials: RwLock::new(Credentials {
+				account_provider: Arc::new(AccountProvider::transient_provider()),
+				address: Default::default(),
+				password: Default::default(),
+			}),
 		}
 	}
 }
@@ -40,24 +47,27 @@ impl Default for EngineSigner {
 impl EngineSigner {
 	/// Set up the signer to sign with given address and password.
 	pub fn set(&self, ap: Arc<AccountProvider>, address: Address, password: String) {
-		*self.account_provider.lock() = ap;
-		*self.address.write()	= address;
-		*self.password.write() = Some(password);
+		*self.credentials.write() = Credentials {
+			account_provider: ap,
+			address: address,
+			password: Some(password),
+		};
 		debug!(target: "poa", "Setting Engine signer to {}", address);
 	}
 
 	/// Sign a consensus message hash.
 	pub fn sign(&self, hash: H256) -> Result<Signature, account_provider::SignError> {
-		self.account_provider.lock().sign(*self.address.read(), self.password.read().clone(), hash)
+		let credentials = self.credentials.read();
+		credentials.account_provider.sign(credentials.address, credentials.password.clone(), hash)
 	}
 
 	/// Signing address.
 	pub fn address(&self) -> Address {
-		self.address.read().clone()
+		self.credentials.read().address.clone()
 	}
 
 	/// Check if the given address is the signing address.
 	pub fn is_address(&self, address: &Address) -> bool {
-		*self.address.read() == *address
+		self.credentials.read().address == *address
 	}
 }
@@ -46,7 +46,7 @@ use super::validator_set::{ValidatorSet, new_validator_set};
 use super::transition::TransitionHandler;
 use super::vote_collector::VoteCollector;
 use self::message::*;
-use self::params::TendermintParams;
+use self::params::{TendermintParams, TendermintTimeouts, RoundTimeouts};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Step {
@@ -80,8 +80,8 @@ pub struct Tendermint {
 	registrar: Address,
 	/// Blockchain height.
 	height: AtomicUsize,
-	/// Consensus view.
-	view: AtomicUsize,
+	/// Consensus view, shared with the step timeouts growing with it.
+	view: Arc<AtomicUsize>,
 	/// Consensus step.
 	step: RwLock<Step>,
 	/// Vote accumulator.
@@ -100,11 +100,14 @@ pub struct Tendermint {
 	last_proposed: RwLock<H256>,
 	/// Set used to determine the current validators.
 	validators: Box<ValidatorSet>,
+	/// Step timeouts, reported with the engine params.
+	timeouts: TendermintTimeouts,
 }
 
 impl Tendermint {
 	/// Create a new instance of Tendermint engine
 	pub fn new(params: CommonParams, our_params: TendermintParams, builtins: BTreeMap<Address, Builtin>) -> Result<Arc<Self>, Error> {
+		our_params.timeouts.validate().map_err(EngineError::InvalidParams)?;
 		let engine = Arc::new(
 			Tendermint {
 				params: params,
@@ -115,7 +118,7 @@ impl Tendermint {
 				block_reward: our_params.block_reward,
 				registrar: our_params.registrar,
 				height: AtomicUsize::new(1),
-				view: AtomicUsize::new(0),
+				view: Arc::new(AtomicUsize::new(0)),
 				step: RwLock::new(Step::Propose),
 				votes: Default::default(),
 				signer: Default::default(),
@@ -125,8 +128,10 @@ impl Tendermint {
 				proposal_parent: Default::default(),
 				last_proposed: Default::default(),
 				validators: new_validator_set(our_params.validators),
+				timeouts: our_params.timeouts.clone(),
 			});
-		let handler = TransitionHandler::new(Arc::downgrade(&engine) as Weak<Engine>, Box::new(our_params.timeouts));
+		let timeouts = RoundTimeouts::new(our_params.timeouts, engine.view.clone());
+		let handler = TransitionHandler::new(Arc::downgrade(&engine) as Weak<Engine>, Box::new(timeouts));
 		engine.step_service.register_handler(Arc::new(handler))?;
 		Ok(engine)
 	}
@@ -385,7 +390,16 @@ impl Engine for Tendermint {
 
 	fn params(&self) -> &CommonParams { &self.params }
 
-	fn additional_params(&self) -> HashMap<String, String> { hash_map!["registrar".to_owned() => self.registrar.hex()] }
+	fn additional_params(&self) -> HashMap<String, String> {
+		hash_map![
+			"registrar".to_owned() => self.registrar.hex(),
+			"timeoutPropose".to_owned() => format!("{}", self.timeouts.propose.num_milliseconds()),
+			"timeoutPrevote".to_owned() => format!("{}", self.timeouts.prevote.num_milliseconds()),
+			"timeoutPrecommit".to_owned() => format!("{}", self.timeouts.precommit.num_milliseconds()),
+			"timeoutCommit".to_owned() => format!("{}", self.timeouts.commit.num_milliseconds()),
+			"timeoutRoundIncrease".to_owned() => format!("{}", self.timeouts.round_increase.num_milliseconds())
+		]
+	}
 
 	fn builtins(&self) -> &BTreeMap<Address, Builtin> { &self.builtins }
 
@@ -901,6 +915,28 @@ mod tests {
 		assert!(notify.messages.read().contains(&prevote_future));
 	}
 
+	#[test]
+	fn timeouts_grow_with_rounds() {
+		use time::Duration;
+		let mut timeouts = TendermintTimeouts::default();
+		timeouts.round_increase = Duration::milliseconds(500);
+		assert!(timeouts.validate().is_ok());
+		assert_eq!(timeouts.round_timeout(&Step::Prevote, 0), Duration::milliseconds(1000));
+		assert_eq!(timeouts.round_timeout(&Step::Prevote, 2), Duration::milliseconds(2000));
+		assert_eq!(timeouts.round_timeout(&Step::Commit, 2), Duration::milliseconds(1000));
+
+		timeouts.precommit = Duration::zero();
+		assert_eq!(timeouts.validate(), Err("timeoutPrecommit has to be positive.".into()));
+	}
+
+	#[test]
+	fn reports_timeouts_in_params() {
+		let (spec, _) = setup();
+		let params = spec.engine.additional_params();
+		assert_eq!(params.get("timeoutPropose"), Some(&"10000".to_owned()));
+		assert_eq!(params.get("timeoutRoundIncrease"), Some(&"0".to_owned()));
+	}
+
 	#[test]
 	fn seal_submission() {
 		use ethkey::{Generator, Random};
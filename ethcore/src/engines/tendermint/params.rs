@@ -16,6 +16,8 @@
 
 //! Tendermint specific parameters.
 
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use ethjson;
 use util::{U256, Uint, Address};
 use time::Duration;
@@ -44,6 +46,54 @@ pub struct TendermintTimeouts {
 	pub prevote: Duration,
 	pub precommit: Duration,
 	pub commit: Duration,
+	/// Added to the propose, prevote and precommit timeouts in every further round.
+	pub round_increase: Duration,
+}
+
+impl TendermintTimeouts {
+	/// Timeout of the step in the given round (view) of a height.
+	pub fn round_timeout(&self, step: &Step, round: usize) -> Duration {
+		match *step {
+			Step::Commit => self.commit,
+			_ => self.timeout(step) + self.round_increase * round as i32,
+		}
+	}
+
+	/// Check that every step can time out, rounds would never end otherwise.
+	pub fn validate(&self) -> Result<(), String> {
+		let steps = [("timeoutPropose", self.propose), ("timeoutPrevote", self.prevote), ("timeoutPrecommit", self.precommit), ("timeoutCommit", self.commit)];
+		for &(name, timeout) in steps.iter() {
+			if timeout <= Duration::zero() {
+				return Err(format!("{} has to be positive.", name));
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Step timeouts growing with the current round, so that validators eventually have enough time to agree.
+pub struct RoundTimeouts {
+	timeouts: TendermintTimeouts,
+	round: Arc<AtomicUsize>,
+}
+
+impl RoundTimeouts {
+	pub fn new(timeouts: TendermintTimeouts, round: Arc<AtomicUsize>) -> Self {
+		RoundTimeouts {
+			timeouts: timeouts,
+			round: round,
+		}
+	}
+}
+
+impl Timeouts<Step> for RoundTimeouts {
+	fn initial(&self) -> Duration {
+		self.timeouts.initial()
+	}
+
+	fn timeout(&self, step: &Step) -> Duration {
+		self.timeouts.round_timeout(step, self.round.load(AtomicOrdering::SeqCst))
+	}
 }
 
 impl Default for TendermintTimeouts {
@@ -53,6 +103,7 @@ impl Default for TendermintTimeouts {
 			prevote: Duration::milliseconds(1000),
 			precommit: Duration::milliseconds(1000),
 			commit: Duration::milliseconds(1000),
+			round_increase: Duration::zero(),
 		}
 	}
 }
@@ -88,6 +139,7 @@ impl From<ethjson::spec::TendermintParams> for TendermintParams {
 				prevote: p.timeout_prevote.map_or(dt.prevote, to_duration),
 				precommit: p.timeout_precommit.map_or(dt.precommit, to_duration),
 				commit: p.timeout_commit.map_or(dt.commit, to_duration),
+				round_increase: p.timeout_round_increase.map_or(dt.round_increase, to_duration),
 			},
 			block_reward: p.block_reward.map_or_else(U256::zero, Into::into),
 			registrar: p.registrar.map_or_else(Address::new, Into::into),
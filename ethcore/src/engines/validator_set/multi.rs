@@ -64,6 +64,12 @@ impl Multi {
 		trace!(target: "engine", "Multi ValidatorSet retrieved for block {}.", block);
 		(*block, &**set)
 	}
+
+	// whether a different set takes over in the block after the given one.
+	// the genesis epoch proof already covers a set starting at block 1.
+	fn is_transition_boundary(&self, number: BlockNumber) -> bool {
+		number != 0 && self.sets.contains_key(&(number + 1))
+	}
 }
 
 impl ValidatorSet for Multi {
@@ -77,6 +83,13 @@ impl ValidatorSet for Multi {
 	{
 		let (set_block, set) = self.correct_set_by_number(header.number());
 
+		// switching sets ends the epoch so that a proof for the new set is generated
+		// and it is picked up without restarting.
+		if self.is_transition_boundary(header.number()) {
+			info!(target: "engine", "Switching to the validator set specified for block {}.", set_block);
+			return EpochChange::Yes(set_block, Vec::new());
+		}
+
 		match set.is_epoch_end(header, block, receipts) {
 			EpochChange::Yes(num, proof) => EpochChange::Yes(set_block + num, proof),
 			other => other,
@@ -92,7 +105,13 @@ impl ValidatorSet for Multi {
 		// ensures epoch increases monotonically.
 		let (set_block, set) = self.correct_set_by_number(header.number());
 		let (inner_epoch, list) = set.epoch_set(header, proof)?;
-		Ok((set_block + inner_epoch, list))
+
+		// the first epoch of a set is the transition block itself, whatever the inner epoch.
+		if self.is_transition_boundary(header.number()) {
+			Ok((set_block, list))
+		} else {
+			Ok((set_block + inner_epoch, list))
+		}
 	}
 
 	fn contains_with_caller(&self, bh: &H256, address: &Address, caller: &Call) -> bool {
@@ -140,9 +159,13 @@ mod tests {
 	use spec::Spec;
 	use account_provider::AccountProvider;
 	use client::{BlockChainClient, EngineClient};
+	use engines::EpochChange;
+	use engines::validator_set::{ValidatorSet, SimpleList};
+	use header::Header;
 	use ethkey::Secret;
 	use miner::MinerService;
 	use tests::helpers::{generate_dummy_client_with_spec_and_accounts, generate_dummy_client_with_spec_and_data};
+	use super::Multi;
 
 	#[test]
 	fn uses_current_set() {
@@ -187,4 +210,28 @@ mod tests {
 		sync_client.flush_queue();
 		assert_eq!(sync_client.chain_info().best_block_number, 3);
 	}
+
+	#[test]
+	fn transition_ends_epoch() {
+		let v0 = Address::from(1);
+		let v1 = Address::from(2);
+		let mut sets: BTreeMap<_, Box<ValidatorSet>> = BTreeMap::new();
+		sets.insert(0, Box::new(SimpleList::new(vec![v0])));
+		sets.insert(3, Box::new(SimpleList::new(vec![v1])));
+		let multi = Multi::new(sets);
+
+		let mut header = Header::default();
+		header.set_number(1);
+		assert_eq!(multi.is_epoch_end(&header, None, None), EpochChange::No);
+
+		// block 2 is the last one validated by the first set.
+		header.set_number(2);
+		assert_eq!(multi.is_epoch_end(&header, None, None), EpochChange::Yes(3, Vec::new()));
+		let (epoch, list) = multi.epoch_set(&header, &[]).unwrap();
+		assert_eq!(epoch, 3);
+		assert_eq!(list.into_inner(), vec![v1]);
+
+		header.set_number(3);
+		assert_eq!(multi.is_epoch_end(&header, None, None), EpochChange::No);
+	}
 }
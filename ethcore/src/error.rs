@@ -42,6 +42,25 @@ pub enum TransactionError {
 	TooCheapToReplace,
 	/// Transaction was not imported to the queue because limit has been reached.
 	LimitReached,
+	/// Transaction was not imported to the future queue because its limit has been reached.
+	FutureLimitReached,
+	/// Transaction was not imported because the sender has too many pending transactions in the queue.
+	SenderLimitReached {
+		/// Maximal number of pending transactions per sender
+		limit: usize,
+	},
+	/// Transaction was not imported because the sender has too many future transactions in the queue.
+	SenderFutureLimitReached {
+		/// Maximal number of future transactions per sender
+		limit: usize,
+	},
+	/// Transaction nonce is too far ahead of the next nonce expected from the sender.
+	NonceGapTooLarge {
+		/// Maximal accepted distance from the next nonce
+		limit: U256,
+		/// Distance of the transaction nonce from the next nonce
+		got: U256,
+	},
 	/// Transaction's gas price is below threshold.
 	InsufficientGasPrice {
 		/// Minimal expected gas price
@@ -90,6 +109,13 @@ impl fmt::Display for TransactionError {
 			Old => "No longer valid".into(),
 			TooCheapToReplace => "Gas price too low to replace".into(),
 			LimitReached => "Transaction limit reached".into(),
+			FutureLimitReached => "Future transaction limit reached".into(),
+			SenderLimitReached { limit } =>
+				format!("Limit of {} pending transactions per sender reached", limit),
+			SenderFutureLimitReached { limit } =>
+				format!("Limit of {} future transactions per sender reached", limit),
+			NonceGapTooLarge { limit, got } =>
+				format!("Nonce gap too large. Max={}, Given={}", limit, got),
 			InsufficientGasPrice { minimal, got } =>
 				format!("Insufficient gas price. Min={}, Given={}", minimal, got),
 			InsufficientGas { minimal, got } =>
@@ -169,6 +195,8 @@ pub enum BlockError {
 	UnknownParent(H256),
 	/// Uncle parent given is unknown.
 	UnknownUncleParent(H256),
+	/// Block hash differs from the trusted checkpoint.
+	CheckpointMismatch(Mismatch<H256>),
 }
 
 impl fmt::Display for BlockError {
@@ -202,6 +230,7 @@ impl fmt::Display for BlockError {
 			RidiculousNumber(ref oob) => format!("Implausible block number. {}", oob),
 			UnknownParent(ref hash) => format!("Unknown parent: {}", hash),
 			UnknownUncleParent(ref hash) => format!("Unknown uncle parent: {}", hash),
+			CheckpointMismatch(ref mis) => format!("Block does not match trusted checkpoint: {}", mis),
 		};
 
 		f.write_fmt(format_args!("Block error ({})", msg))
@@ -217,6 +246,8 @@ pub enum ImportError {
 	AlreadyQueued,
 	/// Already marked as bad from a previous import (could mean parent is bad).
 	KnownBad,
+	/// Outside of the configured block range.
+	OutOfRange,
 }
 
 impl fmt::Display for ImportError {
@@ -225,6 +256,7 @@ impl fmt::Display for ImportError {
 			ImportError::AlreadyInChain => "block already in chain",
 			ImportError::AlreadyQueued => "block already in the block queue",
 			ImportError::KnownBad => "block known to be bad",
+			ImportError::OutOfRange => "block outside of the configured range",
 		};
 
 		f.write_fmt(format_args!("Block import error ({})", msg))
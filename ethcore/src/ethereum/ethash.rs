@@ -182,6 +182,26 @@ impl Engine for Arc<Ethash> {
 		&self.builtins
 	}
 
+	fn fork_transitions(&self) -> Vec<BlockNumber> {
+		let p = &self.ethash_params;
+		vec![
+			p.homestead_transition,
+			p.dao_hardfork_transition,
+			p.difficulty_hardfork_transition,
+			p.bomb_defuse_transition,
+			p.eip100b_transition,
+			p.eip150_transition,
+			p.eip155_transition,
+			p.eip160_transition,
+			p.eip161abc_transition,
+			p.eip161d_transition,
+			p.ecip1010_pause_transition,
+			p.ecip1010_continue_transition,
+			self.params.eip98_transition,
+			self.params.eip86_transition,
+		]
+	}
+
 	/// Additional engine-specific information for the user/developer concerning `header`.
 	fn extra_info(&self, header: &Header) -> BTreeMap<String, String> {
 		map!["nonce".to_owned() => format!("0x{}", header.nonce().hex()), "mixHash".to_owned() => format!("0x{}", header.mix_hash().hex())]
@@ -78,6 +78,12 @@ pub fn new_mainnet_like() -> Spec { load(include_bytes!("../../res/ethereum/fron
 /// Create a new Foundation Ropsten chain spec.
 pub fn new_ropsten() -> Spec { load(include_bytes!("../../res/ethereum/ropsten.json")) }
 
+/// Create a new Rinkeby (Clique) chain spec.
+pub fn new_rinkeby() -> Spec { load(include_bytes!("../../res/ethereum/rinkeby.json")) }
+
+/// Create a new Görli (Clique) chain spec.
+pub fn new_goerli() -> Spec { load(include_bytes!("../../res/ethereum/goerli.json")) }
+
 /// Create a new Morden chain spec.
 pub fn new_morden() -> Spec { load(include_bytes!("../../res/ethereum/morden.json")) }
 
@@ -125,4 +131,22 @@ mod tests {
 
 		let _ = frontier.engine;
 	}
+
+	#[test]
+	fn rinkeby() {
+		let rinkeby = new_rinkeby();
+
+		let genesis = rinkeby.genesis_block();
+		assert_eq!(BlockView::new(&genesis).header_view().sha3(), "6341fd3daf94b748c72ced5a5b26028f2474f5f00d824504e4fa37a75767e177".into());
+		assert_eq!(rinkeby.engine.name(), "Clique");
+	}
+
+	#[test]
+	fn goerli() {
+		let goerli = new_goerli();
+
+		let genesis = goerli.genesis_block();
+		assert_eq!(BlockView::new(&genesis).header_view().sha3(), "bf7e331f7f7c1dd2e05159666b3bf8bc7a8a3a9eb1d518969eab529dd9b88c1a".into());
+		assert_eq!(goerli.engine.name(), "Clique");
+	}
 }
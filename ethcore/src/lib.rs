@@ -120,6 +120,8 @@ extern crate ethcore_util as util;
 #[macro_use]
 extern crate lazy_static;
 #[macro_use]
+extern crate serde_json;
+#[macro_use]
 extern crate ethcore_ipc as ipc;
 
 #[cfg(feature = "jit" )]
@@ -44,3 +44,27 @@ pub const TO_V12: ChangeColumns = ChangeColumns {
 	post_columns: Some(8),
 	version: 12,
 };
+
+/// The migration from v12 to v13.
+/// Adds a column for the token transfer index.
+pub const TO_V13: ChangeColumns = ChangeColumns {
+	pre_columns: Some(8),
+	post_columns: Some(9),
+	version: 13,
+};
+
+/// The migration from v13 to v14.
+/// Adds a column for the account balance history.
+pub const TO_V14: ChangeColumns = ChangeColumns {
+	pre_columns: Some(9),
+	post_columns: Some(10),
+	version: 14,
+};
+
+/// The migration from v14 to v15.
+/// Adds a column for the log index.
+pub const TO_V15: ChangeColumns = ChangeColumns {
+	pre_columns: Some(10),
+	post_columns: Some(11),
+	version: 15,
+};
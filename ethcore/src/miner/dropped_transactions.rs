@@ -0,0 +1,141 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reasons of transactions leaving or not entering the queue.
+
+use linked_hash_map::LinkedHashMap;
+use error::TransactionError;
+use util::{U256, H256};
+
+/// Reason why a transaction is not in the queue.
+#[derive(Debug, PartialEq, Clone)]
+pub enum DropReason {
+	/// Transaction was never accepted to the queue.
+	Rejected(TransactionError),
+	/// Transaction was dropped because of limit.
+	LimitReached,
+	/// Replaced by transaction with the given gas price and hash.
+	Replaced(U256, H256),
+	/// Transaction is invalid.
+	Invalid,
+	/// Transaction was canceled.
+	Canceled,
+	/// Transaction occupied the queue too long.
+	Evicted,
+	/// Transaction nonce is already used by another mined transaction.
+	Stale,
+	/// Transaction was mined.
+	Mined,
+}
+
+/// Keeps reasons of a limited number of transactions which recently left the queue or were rejected.
+#[derive(Debug)]
+pub struct DroppedTransactions {
+	max: usize,
+	reasons: LinkedHashMap<H256, DropReason>,
+}
+
+impl Default for DroppedTransactions {
+	fn default() -> Self {
+		Self::new(4096)
+	}
+}
+
+impl DroppedTransactions {
+	/// Creates a list keeping at most `max` reasons.
+	pub fn new(max: usize) -> Self {
+		DroppedTransactions {
+			max: max,
+			reasons: Default::default(),
+		}
+	}
+
+	/// Remember why the transaction is not in the queue.
+	pub fn mark(&mut self, hash: H256, reason: DropReason) {
+		trace!(target: "txqueue", "Transaction {:?} dropped: {:?}", hash, reason);
+		self.reasons.insert(hash, reason);
+		while self.reasons.len() > self.max {
+			self.reasons.pop_front();
+		}
+	}
+
+	/// Record that a transaction removed for its nonce was included in a block.
+	pub fn mark_mined(&mut self, hash: &H256) {
+		if let Some(reason) = self.reasons.get_mut(hash) {
+			if *reason == DropReason::Stale {
+				*reason = DropReason::Mined;
+			}
+		}
+	}
+
+	/// Forget the reason after the transaction was imported.
+	pub fn remove(&mut self, hash: &H256) {
+		self.reasons.remove(hash);
+	}
+
+	/// Returns the reason why the transaction is not in the queue.
+	pub fn get(&self, hash: &H256) -> Option<&DropReason> {
+		self.reasons.get(hash)
+	}
+
+	/// Number of remembered reasons.
+	pub fn len(&self) -> usize {
+		self.reasons.len()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use error::TransactionError;
+	use super::{DroppedTransactions, DropReason};
+
+	#[test]
+	fn should_keep_limited_number_of_reasons() {
+		// given
+		let mut dropped = DroppedTransactions::new(2);
+
+		// when
+		dropped.mark(1.into(), DropReason::Invalid);
+		dropped.mark(2.into(), DropReason::Rejected(TransactionError::LimitReached));
+		dropped.mark(3.into(), DropReason::Evicted);
+		dropped.remove(&3.into());
+
+		// then
+		assert_eq!(dropped.len(), 1);
+		assert_eq!(dropped.get(&1.into()), None);
+		assert_eq!(dropped.get(&2.into()), Some(&DropReason::Rejected(TransactionError::LimitReached)));
+	}
+
+	#[test]
+	fn should_mark_only_stale_transactions_as_mined() {
+		// given
+		let mut dropped = DroppedTransactions::new(4);
+		dropped.mark(1.into(), DropReason::Stale);
+		dropped.mark(2.into(), DropReason::Stale);
+		dropped.mark(3.into(), DropReason::Evicted);
+
+		// when
+		dropped.mark_mined(&1.into());
+		dropped.mark_mined(&3.into());
+		dropped.mark_mined(&4.into());
+
+		// then
+		assert_eq!(dropped.get(&1.into()), Some(&DropReason::Mined));
+		assert_eq!(dropped.get(&2.into()), Some(&DropReason::Stale));
+		assert_eq!(dropped.get(&3.into()), Some(&DropReason::Evicted));
+		assert_eq!(dropped.get(&4.into()), None);
+	}
+}
@@ -30,12 +30,15 @@ use receipt::{Receipt, RichReceipt};
 use spec::Spec;
 use engines::{Engine, Seal};
 use miner::{MinerService, MinerStatus, TransactionQueue, RemovalReason, TransactionQueueDetailsProvider, PrioritizationStrategy,
-	AccountDetails, TransactionOrigin};
+	AccountDetails, TransactionOrigin, TransactionInfo, PoolStatus, LocalsPolicy};
 use miner::banning_queue::{BanningTransactionQueue, Threshold};
+use miner::transaction_queue::DEFAULT_QUEUING_PERIOD;
 use miner::work_notify::{WorkPoster, NotifyWork};
 use miner::price_info::PriceInfo;
 use miner::local_transactions::{Status as LocalTransactionStatus};
 use miner::service_transaction_checker::ServiceTransactionChecker;
+use miner::transaction_filter::{TransactionFilter, FilterList};
+use miner::standby::{SealingLock, StandbyOptions};
 use header::BlockNumber;
 
 /// Different possible definitions for pending transaction set.
@@ -96,8 +99,28 @@ pub struct MinerOptions {
 	pub tx_gas_limit: U256,
 	/// Maximum size of the transaction queue.
 	pub tx_queue_size: usize,
+	/// Maximum number of future transactions in the queue, `None` for `tx_queue_size`.
+	pub tx_queue_future_size: Option<usize>,
+	/// Maximum number of pending transactions of a single sender, `None` for no limit.
+	pub tx_queue_per_sender: Option<usize>,
+	/// Maximum number of future transactions of a single sender, `None` for no limit.
+	pub tx_queue_future_per_sender: Option<usize>,
+	/// Maximum number of missing nonces before a future transaction, `None` for no limit.
+	pub tx_queue_max_nonce_gap: Option<usize>,
+	/// Maximum memory in bytes used by future transactions in the queue, `None` for no limit.
+	pub tx_queue_future_memory: Option<usize>,
+	/// Percentage by which the gas price has to be higher to replace a transaction with the same nonce.
+	pub tx_queue_replacement_bump: u32,
+	/// Number of blocks a non-local pending transaction may stay in the queue.
+	pub tx_queue_pending_lifetime: BlockNumber,
+	/// Number of blocks a non-local future transaction may stay in the queue.
+	pub tx_queue_future_lifetime: BlockNumber,
+	/// Whether hashes of evicted transactions are remembered to avoid importing them again.
+	pub tx_queue_remember_evicted: bool,
 	/// Strategy to use for prioritizing transactions in the queue.
 	pub tx_queue_strategy: PrioritizationStrategy,
+	/// Treatment of local transactions when the queue is full.
+	pub tx_queue_locals: LocalsPolicy,
 	/// Whether we should fallback to providing all the queue's transactions or just pending.
 	pub pending_set: PendingSet,
 	/// How many historical work packages can we store before running out?
@@ -110,6 +133,18 @@ pub struct MinerOptions {
 	pub tx_queue_banning: Banning,
 	/// Do we refuse to accept service transactions even if sender is certified.
 	pub refuse_service_transactions: bool,
+	/// Senders allowed to send zero gas price transactions. If set (or the certifier is set)
+	/// zero gas price transactions of other senders are neither queued nor included in blocks.
+	pub zero_gas_price_senders: Vec<Address>,
+	/// Contract certifying senders allowed to send zero gas price transactions,
+	/// `None` to use the service transaction checker from the registry.
+	pub zero_gas_price_certifier: Option<Address>,
+	/// Banned senders, recipients and contract code hashes.
+	pub tx_filter_list: FilterList,
+	/// Contract deciding which senders, recipients and contract code hashes are banned.
+	pub tx_filter_contract: Option<Address>,
+	/// Lock shared with other nodes sealing with the same engine signer, `None` to seal unconditionally.
+	pub standby: Option<StandbyOptions>,
 }
 
 impl Default for MinerOptions {
@@ -121,8 +156,18 @@ impl Default for MinerOptions {
 			reseal_on_own_tx: true,
 			tx_gas_limit: !U256::zero(),
 			tx_queue_size: 1024,
+			tx_queue_future_size: None,
+			tx_queue_per_sender: None,
+			tx_queue_future_per_sender: None,
+			tx_queue_max_nonce_gap: None,
+			tx_queue_future_memory: None,
+			tx_queue_replacement_bump: 0,
+			tx_queue_pending_lifetime: DEFAULT_QUEUING_PERIOD,
+			tx_queue_future_lifetime: DEFAULT_QUEUING_PERIOD,
+			tx_queue_remember_evicted: false,
 			tx_queue_gas_limit: GasLimit::Auto,
 			tx_queue_strategy: PrioritizationStrategy::GasPriceOnly,
+			tx_queue_locals: LocalsPolicy::Prioritize,
 			pending_set: PendingSet::AlwaysQueue,
 			reseal_min_period: Duration::from_secs(2),
 			reseal_max_period: Duration::from_secs(120),
@@ -130,6 +175,11 @@ impl Default for MinerOptions {
 			enable_resubmission: true,
 			tx_queue_banning: Banning::Disabled,
 			refuse_service_transactions: false,
+			zero_gas_price_senders: Vec::new(),
+			zero_gas_price_certifier: None,
+			tx_filter_list: FilterList::default(),
+			tx_filter_contract: None,
+			standby: None,
 		}
 	}
 }
@@ -217,6 +267,9 @@ pub struct Miner {
 	next_allowed_reseal: Mutex<Instant>,
 	next_mandatory_reseal: RwLock<Instant>,
 	sealing_block_last_request: Mutex<u64>,
+	// held while generating internal seals and while rotating the engine signer.
+	signer_rotation: Mutex<()>,
+	sealing_lock: Option<SealingLock>,
 	// for sealing...
 	options: MinerOptions,
 
@@ -227,8 +280,10 @@ pub struct Miner {
 
 	accounts: Option<Arc<AccountProvider>>,
 	notifiers: RwLock<Vec<Box<NotifyWork>>>,
+	eviction_listeners: RwLock<Vec<Box<Fn(&[H256]) + Send + Sync>>>,
 	gas_pricer: Mutex<GasPricer>,
 	service_transaction_action: ServiceTransactionAction,
+	transaction_filter: TransactionFilter,
 }
 
 impl Miner {
@@ -238,6 +293,11 @@ impl Miner {
 		self.sealing_work.lock().enabled = true;
 	}
 
+	/// Add a listener notified with hashes of transactions evicted from the queue for staying in it too long.
+	pub fn add_eviction_listener(&self, listener: Box<Fn(&[H256]) + Send + Sync>) {
+		self.eviction_listeners.write().push(listener);
+	}
+
 	/// Creates new instance of miner Arc.
 	pub fn new(options: MinerOptions, gas_pricer: GasPricer, spec: &Spec, accounts: Option<Arc<AccountProvider>>) -> Arc<Miner> {
 		Arc::new(Miner::new_raw(options, gas_pricer, spec, accounts))
@@ -250,7 +310,15 @@ impl Miner {
 			_ => !U256::zero(),
 		};
 
-		let txq = TransactionQueue::with_limits(options.tx_queue_strategy, options.tx_queue_size, gas_limit, options.tx_gas_limit);
+		let mut txq = TransactionQueue::with_limits(options.tx_queue_strategy, options.tx_queue_size, gas_limit, options.tx_gas_limit);
+		txq.set_future_limit(options.tx_queue_future_size);
+		txq.set_sender_limits(options.tx_queue_per_sender, options.tx_queue_future_per_sender);
+		txq.set_max_nonce_gap(options.tx_queue_max_nonce_gap);
+		txq.set_locals_policy(options.tx_queue_locals);
+		txq.set_future_memory_limit(options.tx_queue_future_memory);
+		txq.set_replacement_price_bump(options.tx_queue_replacement_bump);
+		txq.set_max_time_in_queue(options.tx_queue_pending_lifetime, options.tx_queue_future_lifetime);
+		txq.set_remember_evicted(options.tx_queue_remember_evicted);
 		let txq = match options.tx_queue_banning {
 			Banning::Disabled => BanningTransactionQueue::new(txq, Threshold::NeverBan, Duration::from_secs(180)),
 			Banning::Enabled { ban_duration, min_offends, .. } => BanningTransactionQueue::new(
@@ -265,9 +333,14 @@ impl Miner {
 			false => vec![Box::new(WorkPoster::new(&options.new_work_notify))],
 		};
 
+		let transaction_filter = TransactionFilter::new(options.tx_filter_list.clone(), options.tx_filter_contract);
+
 		let service_transaction_action = match options.refuse_service_transactions {
 			true => ServiceTransactionAction::Refuse,
-			false => ServiceTransactionAction::Check(ServiceTransactionChecker::default()),
+			false => ServiceTransactionAction::Check(ServiceTransactionChecker::new(
+				options.zero_gas_price_senders.iter().cloned().collect(),
+				options.zero_gas_price_certifier,
+			)),
 		};
 
 		Miner {
@@ -275,6 +348,8 @@ impl Miner {
 			next_allowed_reseal: Mutex::new(Instant::now()),
 			next_mandatory_reseal: RwLock::new(Instant::now() + options.reseal_max_period),
 			sealing_block_last_request: Mutex::new(0),
+			signer_rotation: Mutex::new(()),
+			sealing_lock: options.standby.clone().map(SealingLock::new),
 			sealing_work: Mutex::new(SealingWork{
 				queue: UsingQueue::new(options.work_queue_size),
 				enabled: options.force_sealing
@@ -288,8 +363,10 @@ impl Miner {
 			accounts: accounts,
 			engine: spec.engine.clone(),
 			notifiers: RwLock::new(notifiers),
+			eviction_listeners: RwLock::new(Vec::new()),
 			gas_pricer: Mutex::new(gas_pricer),
 			service_transaction_action: service_transaction_action,
+			transaction_filter: transaction_filter,
 		}
 	}
 
@@ -366,8 +443,21 @@ impl Miner {
 		// TODO Push new uncles too.
 		let mut tx_count: usize = 0;
 		let tx_total = transactions.len();
+		self.service_transaction_action.update_from_chain_client(chain);
 		for tx in transactions {
 			let hash = tx.hash();
+			if tx.gas_price.is_zero() && !self.service_transaction_action.is_zero_gas_price_allowed(chain, &tx) {
+				debug!(target: "miner", "Skipping zero gas price transaction of a sender not allowed to send them: {:?}", hash);
+				invalid_transactions.insert(hash);
+				continue;
+			}
+
+			if let Err(e) = self.transaction_filter.check(chain, &tx) {
+				debug!(target: "miner", "Skipping filtered transaction {:?}: {:?}", hash, e);
+				invalid_transactions.insert(hash);
+				continue;
+			}
+
 			let start = Instant::now();
 			let result = open_block.push_transaction(tx, None);
 			let took = start.elapsed();
@@ -482,9 +572,29 @@ impl Miner {
 
 	/// Attempts to perform internal sealing (one that does not require work) and handles the result depending on the type of Seal.
 	fn seal_and_import_block_internally(&self, chain: &MiningBlockChainClient, block: ClosedBlock) -> bool {
+		if let Some(ref lock) = self.sealing_lock {
+			let author = self.author.read().clone();
+			if !lock.may_seal(chain, &author) {
+				return false;
+			}
+		}
 		if !block.transactions().is_empty() || self.forced_sealing() || Instant::now() > *self.next_mandatory_reseal.read() {
 			trace!(target: "miner", "seal_block_internally: attempting internal seal.");
-			match self.engine.generate_seal(block.block()) {
+			let seal = match self.signer_rotation.try_lock() {
+				Some(_guard) => self.engine.generate_seal(block.block()),
+				None => {
+					trace!(target: "miner", "seal_block_internally: engine signer is being rotated.");
+					Seal::None
+				},
+			};
+			// Fencing: another node may have taken the lock over while sealing.
+			if let Some(ref lock) = self.sealing_lock {
+				if seal != Seal::None && !lock.is_held() {
+					warn!(target: "miner", "Sealing lock lost while sealing, dropping the block.");
+					return false;
+				}
+			}
+			match seal {
 				// Save proposal for later seal submission and broadcast it.
 				Seal::Proposal(seal) => {
 					trace!(target: "miner", "Received a Proposal seal.");
@@ -612,8 +722,9 @@ impl Miner {
 
 		let best_block_header = client.best_block_header().decode();
 		let insertion_time = client.chain_info().best_block_number;
+		let hashes = transactions.iter().map(|tx| tx.hash()).collect::<Vec<_>>();
 
-		transactions.into_iter()
+		let results = transactions.into_iter()
 			.map(|tx| {
 				let hash = tx.hash();
 				if client.transaction_block(TransactionId::Hash(hash)).is_some() {
@@ -635,9 +746,24 @@ impl Miner {
 							}
 						}).unwrap_or(default_origin);
 
+						if let Err(e) = self.transaction_filter.check(client, &transaction) {
+							return Err(Error::Transaction(e));
+						}
+
 						// try to install service transaction checker before appending transactions
 						self.service_transaction_action.update_from_chain_client(client);
 
+						// the queue checks external service transactions only if they are below the minimal gas price
+						let checked_by_queue = origin == TransactionOrigin::External && !transaction_queue.minimal_gas_price().is_zero();
+						if transaction.gas_price.is_zero() && !checked_by_queue
+							&& !self.service_transaction_action.is_zero_gas_price_allowed(client, &transaction) {
+							debug!(target: "miner", "Rejected tx {:?}: sender not allowed to send zero gas price transactions", hash);
+							return Err(Error::Transaction(TransactionError::InsufficientGasPrice {
+								minimal: cmp::max(*transaction_queue.minimal_gas_price(), U256::from(1)),
+								got: transaction.gas_price,
+							}));
+						}
+
 						let details_provider = TransactionDetailsProvider::new(client, &self.service_transaction_action);
 						match origin {
 							TransactionOrigin::Local | TransactionOrigin::RetractedBlock => {
@@ -650,7 +776,14 @@ impl Miner {
 					},
 				}
 			})
-			.collect()
+			.collect::<Vec<_>>();
+
+		for (hash, result) in hashes.into_iter().zip(&results) {
+			if let Err(Error::Transaction(ref e)) = *result {
+				transaction_queue.mark_rejected(hash, e.clone());
+			}
+		}
+		results
 	}
 
 	/// Are we allowed to do a non-mandatory reseal?
@@ -804,6 +937,38 @@ impl MinerService for Miner {
 		Ok(())
 	}
 
+	fn set_engine_signer_secure(&self, address: Address, password: String) -> Result<(), AccountError> {
+		if self.engine.seals_internally().is_some() {
+			if let Some(ref ap) = self.accounts {
+				ap.sign(address.clone(), Some(password.clone()), Default::default())?;
+				// No seal is generated until both the author and the engine signer are swapped.
+				// Sealing attempts triggered by the engine meanwhile are skipped instead of blocking.
+				let _guard = self.signer_rotation.lock();
+				{
+					let mut sealing_work = self.sealing_work.lock();
+					sealing_work.enabled = true;
+					sealing_work.queue.reset();
+					*self.author.write() = address;
+				}
+				self.engine.set_signer(ap.clone(), address, password);
+				info!(target: "miner", "Engine signer rotated to {}.", address);
+			}
+		}
+		Ok(())
+	}
+
+	fn propose_signer(&self, candidate: Address, authorize: bool) -> bool {
+		self.engine.propose_signer(candidate, authorize)
+	}
+
+	fn discard_signer(&self, candidate: &Address) -> bool {
+		self.engine.discard_signer(candidate)
+	}
+
+	fn signer_proposals(&self) -> Option<BTreeMap<Address, bool>> {
+		self.engine.signer_proposals()
+	}
+
 	fn set_extra_data(&self, extra_data: Bytes) {
 		*self.extra_data.write() = extra_data;
 	}
@@ -842,6 +1007,14 @@ impl MinerService for Miner {
 		self.transaction_queue.write().set_limit(limit)
 	}
 
+	fn replacement_price_bump(&self) -> u32 {
+		self.transaction_queue.read().replacement_price_bump()
+	}
+
+	fn set_replacement_price_bump(&self, percent: u32) {
+		self.transaction_queue.write().set_replacement_price_bump(percent)
+	}
+
 	fn set_tx_gas_limit(&self, limit: U256) {
 		self.transaction_queue.write().set_tx_gas_limit(limit)
 	}
@@ -949,6 +1122,14 @@ impl MinerService for Miner {
 			.collect()
 	}
 
+	fn pool_status(&self) -> PoolStatus {
+		self.transaction_queue.read().pool_status()
+	}
+
+	fn pool_transaction_info(&self, hash: &H256) -> Option<TransactionInfo> {
+		self.transaction_queue.read().transaction_info(hash)
+	}
+
 	fn future_transactions(&self) -> Vec<PendingTransaction> {
 		self.transaction_queue.read().future_transactions()
 	}
@@ -1178,15 +1359,27 @@ impl MinerService for Miner {
 			}
 		}
 
-		// ...and at the end remove the old ones
-		{
+		// ...and at the end remove the old ones, telling mined transactions apart
+		let evicted = {
 			let fetch_account = |a: &Address| AccountDetails {
 				nonce: chain.latest_nonce(a),
 				balance: chain.latest_balance(a),
 			};
 			let time = chain.chain_info().best_block_number;
+			let mined = enacted.iter()
+				.filter_map(|hash| chain.block_body(BlockId::Hash(*hash)))
+				.flat_map(|body| body.transaction_hashes())
+				.collect::<Vec<_>>();
 			let mut transaction_queue = self.transaction_queue.write();
-			transaction_queue.remove_old(&fetch_account, time);
+			let evicted = transaction_queue.remove_old(&fetch_account, time);
+			transaction_queue.mark_mined(&mined);
+			evicted
+		};
+
+		if !evicted.is_empty() {
+			for listener in self.eviction_listeners.read().iter() {
+				listener(&evicted);
+			}
 		}
 
 		if enacted.len() > 0 {
@@ -1220,6 +1413,15 @@ impl ServiceTransactionAction {
 			ServiceTransactionAction::Check(ref checker) => checker.check(client, tx),
 		}
 	}
+
+	/// Checks if a zero gas price transaction may be queued or included in a block
+	/// when only some senders are allowed to send them.
+	pub fn is_zero_gas_price_allowed(&self, client: &MiningBlockChainClient, tx: &SignedTransaction) -> bool {
+		match *self {
+			ServiceTransactionAction::Check(ref checker) if checker.is_restricted() => checker.check(client, tx).unwrap_or(false),
+			_ => true,
+		}
+	}
 }
 
 struct TransactionDetailsProvider<'a> {
@@ -1312,13 +1514,28 @@ mod tests {
 				reseal_max_period: Duration::from_secs(120),
 				tx_gas_limit: !U256::zero(),
 				tx_queue_size: 1024,
+				tx_queue_future_size: None,
+				tx_queue_per_sender: None,
+				tx_queue_future_per_sender: None,
+				tx_queue_max_nonce_gap: None,
+				tx_queue_future_memory: None,
+				tx_queue_replacement_bump: 0,
+				tx_queue_pending_lifetime: 128,
+				tx_queue_future_lifetime: 128,
+				tx_queue_remember_evicted: false,
 				tx_queue_gas_limit: GasLimit::None,
 				tx_queue_strategy: PrioritizationStrategy::GasFactorAndGasPrice,
+				tx_queue_locals: LocalsPolicy::Prioritize,
 				pending_set: PendingSet::AlwaysSealing,
 				work_queue_size: 5,
 				enable_resubmission: true,
 				tx_queue_banning: Banning::Disabled,
 				refuse_service_transactions: false,
+				zero_gas_price_senders: Vec::new(),
+				zero_gas_price_certifier: None,
+				tx_filter_list: FilterList::default(),
+				tx_filter_contract: None,
+				standby: None,
 			},
 			GasPricer::new_fixed(0u64.into()),
 			&Spec::new_test(),
@@ -1400,6 +1617,39 @@ mod tests {
 		assert!(miner.prepare_work_sealing(&client));
 	}
 
+	#[test]
+	fn should_accept_zero_gas_price_transactions_only_from_allowed_senders() {
+		// given
+		let client = TestBlockChainClient::default();
+		let allowed = Random.generate().unwrap();
+		let miner = Miner::new_raw(
+			MinerOptions { zero_gas_price_senders: vec![allowed.address()], ..MinerOptions::default() },
+			GasPricer::new_fixed(0u64.into()),
+			&Spec::new_test(),
+			None,
+		);
+		let allowed_transaction = Transaction {
+			action: Action::Create,
+			value: U256::zero(),
+			data: "3331600055".from_hex().unwrap(),
+			gas: U256::from(100_000),
+			gas_price: U256::zero(),
+			nonce: U256::zero(),
+		}.sign(allowed.secret(), Some(2));
+
+		// when
+		let rejected = miner.import_own_transaction(&client, PendingTransaction::new(transaction(), None));
+		let accepted = miner.import_external_transactions(&client, vec![allowed_transaction.into()]).pop().unwrap();
+
+		// then
+		match rejected {
+			Err(Error::Transaction(TransactionError::InsufficientGasPrice { .. })) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert_eq!(accepted.unwrap(), TransactionImportResult::Current);
+		assert_eq!(miner.pending_transactions().len(), 1);
+	}
+
 	#[test]
 	fn should_not_seal_unless_enabled() {
 		let miner = miner();
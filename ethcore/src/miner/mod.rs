@@ -42,11 +42,14 @@
 //! ```
 
 mod banning_queue;
+mod dropped_transactions;
 mod external;
 mod local_transactions;
 mod miner;
 mod price_info;
 mod service_transaction_checker;
+mod standby;
+mod transaction_filter;
 mod transaction_queue;
 mod work_notify;
 mod stratum;
@@ -55,8 +58,11 @@ pub use self::external::{ExternalMiner, ExternalMinerService};
 
 pub use self::miner::{Miner, MinerOptions, Banning, PendingSet, GasPricer, GasPriceCalibratorOptions, GasLimit};
 pub use self::transaction_queue::{TransactionQueue, RemovalReason, TransactionDetailsProvider as TransactionQueueDetailsProvider,
-	PrioritizationStrategy, AccountDetails, TransactionOrigin};
+	PrioritizationStrategy, LocalsPolicy, AccountDetails, TransactionOrigin, TransactionInfo, TransactionPriority, PoolStatus};
+pub use self::dropped_transactions::DropReason;
 pub use self::local_transactions::{Status as LocalTransactionStatus};
+pub use self::transaction_filter::{TransactionFilter, FilterList as TransactionFilterList};
+pub use self::standby::StandbyOptions;
 pub use client::TransactionImportResult;
 pub use self::work_notify::NotifyWork;
 pub use self::stratum::{Stratum, Error as StratumError, Options as StratumOptions};
@@ -85,6 +91,19 @@ pub trait MinerService : Send + Sync {
 	/// Set info necessary to sign consensus messages.
 	fn set_engine_signer(&self, address: Address, password: String) -> Result<(), ::account_provider::SignError>;
 
+	/// Replace the account signing consensus messages without interleaving with internal sealing.
+	fn set_engine_signer_secure(&self, address: Address, password: String) -> Result<(), ::account_provider::SignError>;
+
+	/// Vote on adding (`authorize`) or removing a signer in the blocks we seal.
+	/// Returns `false` if the engine doesn't vote on its signers.
+	fn propose_signer(&self, candidate: Address, authorize: bool) -> bool;
+
+	/// Stop voting on the signer. Returns `false` if the engine doesn't vote on its signers.
+	fn discard_signer(&self, candidate: &Address) -> bool;
+
+	/// Signers we vote on and whether to authorize them, `None` if the engine doesn't vote on its signers.
+	fn signer_proposals(&self) -> Option<BTreeMap<Address, bool>>;
+
 	/// Get the extra_data that we will seal blocks with.
 	fn extra_data(&self) -> Bytes;
 
@@ -116,6 +135,12 @@ pub trait MinerService : Send + Sync {
 	/// Set maximal number of transactions kept in the queue (both current and future).
 	fn set_transactions_limit(&self, limit: usize);
 
+	/// Get the percentage by which the gas price has to be higher to replace a queued transaction.
+	fn replacement_price_bump(&self) -> u32;
+
+	/// Set the percentage by which the gas price has to be higher to replace a queued transaction.
+	fn set_replacement_price_bump(&self, percent: u32);
+
 	/// Set maximum amount of gas allowed for any single transaction to mine.
 	fn set_tx_gas_limit(&self, limit: U256);
 
@@ -166,6 +191,12 @@ pub trait MinerService : Send + Sync {
 	/// Get a list of local transactions with statuses.
 	fn local_transactions(&self) -> BTreeMap<H256, LocalTransactionStatus>;
 
+	/// Get detailed status of the transaction queue.
+	fn pool_status(&self) -> PoolStatus;
+
+	/// Get position and priority of a queued transaction or the reason why it is not in the queue.
+	fn pool_transaction_info(&self, hash: &H256) -> Option<TransactionInfo>;
+
 	/// Get a list of all pending receipts.
 	fn pending_receipts(&self, best_block: BlockNumber) -> BTreeMap<H256, Receipt>;
 
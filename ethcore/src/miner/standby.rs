@@ -0,0 +1,346 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hot-standby sealing for nodes sharing the same engine signer.
+//!
+//! Nodes configured with a sealing lock only seal while they hold it. The lock is a file
+//! shared by the nodes, holding a random token of its holder, a generation increased on every
+//! take-over and the time of its last sealing attempt. A lock which has not been refreshed
+//! within the timeout is free. A standby node in addition does not take the lock before no
+//! block authored by the signer has been imported for the timeout, so that it never seals next
+//! to a live primary.
+//!
+//! The lock file is never written in place: a free lock is taken by hard linking a complete
+//! file (failing if the lock exists), an expired one is first moved aside and checked to be
+//! the one found expired, and the holder refreshes it by renaming a complete file over it.
+//! A node which has taken the lock waits for the grace period before sealing, so that a
+//! previous holder refreshing the lock late is noticed, and imports a sealed block only if the
+//! lock still carries its token and generation (fencing). An empty or unreadable lock file is
+//! held until its modification time is older than the timeout.
+//!
+//! The lock is refreshed only on sealing attempts, so the timeout has to be longer than the
+//! longest time between them, e.g. a full rotation of validators sealing in turns.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use client::MiningBlockChainClient;
+use types::ids::BlockId;
+use util::{Address, H256, Mutex};
+
+/// Sealing lock configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StandbyOptions {
+	/// Lock file shared by the nodes sealing with the same signer.
+	pub lock_file: PathBuf,
+	/// Wait for the signer's blocks to stop before sealing for the first time.
+	pub standby: bool,
+	/// Time after which the lock and the signer are considered abandoned.
+	pub timeout: Duration,
+	/// Time to wait after taking the lock before sealing.
+	pub grace: Duration,
+}
+
+/// Contents of the lock file.
+#[derive(Debug, Clone, PartialEq)]
+struct LockState {
+	token: H256,
+	generation: u64,
+	time: u64,
+}
+
+/// The lock as held by this node.
+#[derive(Debug, Clone, Copy)]
+struct Held {
+	generation: u64,
+	since: u64,
+}
+
+/// Sealing lock held by at most one node at a time.
+pub struct SealingLock {
+	options: StandbyOptions,
+	token: H256,
+	held: Mutex<Option<Held>>,
+}
+
+impl SealingLock {
+	/// Create a new lock handle, not holding the lock yet.
+	pub fn new(options: StandbyOptions) -> Self {
+		SealingLock {
+			options: options,
+			token: H256::random(),
+			held: Mutex::new(None),
+		}
+	}
+
+	/// Checks whether this node may seal blocks for `author` now, refreshing the lock if so.
+	pub fn may_seal(&self, chain: &MiningBlockChainClient, author: &Address) -> bool {
+		let now = unix_now();
+		let timeout = self.options.timeout.as_secs();
+		let mut held = self.held.lock();
+
+		let current = read_state(&self.options.lock_file);
+		let generation = match current {
+			Some(ref state) if state.token == self.token => state.generation,
+			Some(ref state) if now < state.time + timeout => {
+				if held.take().is_some() {
+					warn!(target: "miner", "Sealing lock taken over by another node, standing by.");
+				}
+				return false;
+			},
+			_ => {
+				if held.take().is_some() {
+					warn!(target: "miner", "Sealing lock {} lost, taking it again.", self.options.lock_file.display());
+				}
+				if self.options.standby && self.recently_sealed(chain, author, now) {
+					trace!(target: "miner", "Signer {} is still sealing, standing by.", author);
+					return false;
+				}
+				match self.acquire(current, now) {
+					Ok(generation) => {
+						info!(target: "miner", "Acquired sealing lock {}.", self.options.lock_file.display());
+						*held = Some(Held { generation: generation, since: now });
+						generation
+					},
+					Err(e) => {
+						trace!(target: "miner", "Sealing lock {} not acquired: {}", self.options.lock_file.display(), e);
+						return false;
+					},
+				}
+			},
+		};
+
+		let since = match *held {
+			Some(ref h) if h.generation == generation => h.since,
+			_ => now,
+		};
+		*held = Some(Held { generation: generation, since: since });
+
+		if let Err(e) = self.refresh(generation, now) {
+			warn!(target: "miner", "Unable to write sealing lock {}: {}", self.options.lock_file.display(), e);
+			*held = None;
+			return false;
+		}
+		// Read the lock back in case another node took it over at the same time.
+		if !self.holds(generation) {
+			*held = None;
+			return false;
+		}
+		if now < since + self.options.grace.as_secs() {
+			trace!(target: "miner", "Sealing lock acquired recently, waiting for the grace period.");
+			return false;
+		}
+		true
+	}
+
+	/// Checks whether the lock still carries the token and generation of this node.
+	/// Sealed blocks are imported only while this holds.
+	pub fn is_held(&self) -> bool {
+		match *self.held.lock() {
+			Some(ref held) => self.holds(held.generation),
+			None => false,
+		}
+	}
+
+	fn holds(&self, generation: u64) -> bool {
+		read_state(&self.options.lock_file).map_or(false, |state| state.token == self.token && state.generation == generation)
+	}
+
+	// Whether a block authored by `author` was imported within the timeout.
+	fn recently_sealed(&self, chain: &MiningBlockChainClient, author: &Address, now: u64) -> bool {
+		let mut id = BlockId::Latest;
+		while let Some(header) = chain.block_header(id) {
+			if header.timestamp() + self.options.timeout.as_secs() < now {
+				return false;
+			}
+			if header.author() == *author {
+				return true;
+			}
+			if header.number() == 0 {
+				return false;
+			}
+			id = BlockId::Hash(header.parent_hash());
+		}
+		false
+	}
+
+	// Takes the free or expired lock, returns the new generation.
+	fn acquire(&self, expired: Option<LockState>, now: u64) -> io::Result<u64> {
+		let lock_file = &self.options.lock_file;
+		let generation = expired.as_ref().map_or(0, |state| state.generation) + 1;
+		if let Some(expired) = expired {
+			let stale = self.side_file("stale");
+			fs::rename(lock_file, &stale)?;
+			if read_state(&stale) != Some(expired) {
+				// Refreshed or taken by another node in the meantime, put it back unless replaced already.
+				let _ = fs::hard_link(&stale, lock_file);
+				let _ = fs::remove_file(&stale);
+				return Err(io::Error::new(io::ErrorKind::Other, "lock changed while taking it over"));
+			}
+			fs::remove_file(&stale)?;
+		}
+
+		let temp = self.side_file("new");
+		write_state(&temp, &LockState { token: self.token, generation: generation, time: now })?;
+		let linked = fs::hard_link(&temp, lock_file);
+		let _ = fs::remove_file(&temp);
+		linked.map(|_| generation)
+	}
+
+	fn refresh(&self, generation: u64, now: u64) -> io::Result<()> {
+		let temp = self.side_file("new");
+		write_state(&temp, &LockState { token: self.token, generation: generation, time: now })?;
+		fs::rename(&temp, &self.options.lock_file)
+	}
+
+	// File next to the lock file, private to this node.
+	fn side_file(&self, kind: &str) -> PathBuf {
+		let mut name = self.options.lock_file.file_name().map(|name| name.to_os_string()).unwrap_or_default();
+		name.push(format!(".{}.{}", self.token.hex(), kind));
+		self.options.lock_file.with_file_name(name)
+	}
+}
+
+// Contents of the lock file. A file which can't be parsed (e.g. being written by a node not
+// using a temporary file, or left empty by a crash) is held by nobody known since its last modification.
+fn read_state(path: &Path) -> Option<LockState> {
+	let mut file = match File::open(path) {
+		Ok(file) => file,
+		Err(_) => return None,
+	};
+	let mut contents = String::new();
+	let _ = file.read_to_string(&mut contents);
+	let mut parts = contents.split_whitespace();
+	match (parts.next().and_then(|t| t.parse().ok()), parts.next().and_then(|t| t.parse().ok()), parts.next().and_then(|t| t.parse().ok())) {
+		(Some(token), Some(generation), Some(time)) => Some(LockState { token: token, generation: generation, time: time }),
+		_ => {
+			let modified = file.metadata().and_then(|metadata| metadata.modified()).ok()
+				.and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+				.map_or(0, |modified| modified.as_secs());
+			Some(LockState { token: H256::zero(), generation: 0, time: modified })
+		},
+	}
+}
+
+fn write_state(path: &Path, state: &LockState) -> io::Result<()> {
+	let mut file = File::create(path)?;
+	write!(file, "{:?} {} {}", state.token, state.generation, state.time)?;
+	file.sync_all()
+}
+
+fn unix_now() -> u64 {
+	UNIX_EPOCH.elapsed().expect("Valid time has to be set in your system.").as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use std::io::Write;
+	use std::time::Duration;
+	use devtools::RandomTempPath;
+	use client::{BlockChainClient, TestBlockChainClient, EachBlockWith};
+	use header::Header;
+	use rlp::RlpStream;
+	use util::Address;
+	use super::{SealingLock, StandbyOptions, unix_now};
+
+	fn lock(path: &RandomTempPath, standby: bool) -> SealingLock {
+		lock_with(path, standby, 60, 0)
+	}
+
+	fn lock_with(path: &RandomTempPath, standby: bool, timeout: u64, grace: u64) -> SealingLock {
+		SealingLock::new(StandbyOptions {
+			lock_file: path.as_path().to_owned(),
+			standby: standby,
+			timeout: Duration::from_secs(timeout),
+			grace: Duration::from_secs(grace),
+		})
+	}
+
+	#[test]
+	fn seals_only_while_holding_the_lock() {
+		let path = RandomTempPath::new();
+		let client = TestBlockChainClient::new();
+		let primary = lock(&path, false);
+		let standby = lock(&path, true);
+
+		assert!(primary.may_seal(&client, &Address::default()));
+		assert!(!standby.may_seal(&client, &Address::default()));
+		assert!(primary.may_seal(&client, &Address::default()));
+
+		// The primary stops refreshing the lock.
+		File::create(path.as_path()).unwrap()
+			.write_all(format!("{:?} 1 {}", primary.token, unix_now() - 61).as_bytes()).unwrap();
+		assert!(standby.may_seal(&client, &Address::default()));
+		assert!(standby.is_held());
+		assert!(!primary.is_held());
+		assert!(!primary.may_seal(&client, &Address::default()));
+	}
+
+	#[test]
+	fn waits_for_grace_period_after_taking_the_lock() {
+		let path = RandomTempPath::new();
+		let client = TestBlockChainClient::new();
+		let primary = lock_with(&path, false, 60, 60);
+		let other = lock(&path, false);
+
+		assert!(!primary.may_seal(&client, &Address::default()));
+		assert!(primary.is_held());
+		assert!(!other.may_seal(&client, &Address::default()));
+		assert!(!primary.may_seal(&client, &Address::default()));
+	}
+
+	#[test]
+	fn holds_unreadable_lock_until_timeout() {
+		let path = RandomTempPath::new();
+		let client = TestBlockChainClient::new();
+		File::create(path.as_path()).unwrap();
+
+		assert!(!lock(&path, false).may_seal(&client, &Address::default()));
+		let node = lock_with(&path, false, 0, 0);
+		assert!(node.may_seal(&client, &Address::default()));
+		assert!(node.is_held());
+	}
+
+	#[test]
+	fn standby_waits_for_signer_to_stop_sealing() {
+		let path = RandomTempPath::new();
+		let client = TestBlockChainClient::new();
+		client.add_blocks(1, EachBlockWith::Nothing);
+		let author = Address::from(1);
+
+		// A block from the signer has just been imported.
+		let mut header = Header::new();
+		header.set_number(client.chain_info().best_block_number + 1);
+		header.set_parent_hash(client.chain_info().best_block_hash);
+		header.set_gas_limit(1_000_000.into());
+		header.set_author(author);
+		header.set_timestamp(unix_now());
+		let mut rlp = RlpStream::new_list(3);
+		rlp.append(&header);
+		rlp.append_raw(&::rlp::EMPTY_LIST_RLP, 1);
+		rlp.append_raw(&::rlp::EMPTY_LIST_RLP, 1);
+		client.import_block(rlp.out()).unwrap();
+
+		let standby = lock(&path, true);
+		assert!(!standby.may_seal(&client, &author));
+		assert!(standby.may_seal(&client, &Address::from(2)));
+		// Once active its own blocks do not stop it.
+		assert!(standby.may_seal(&client, &author));
+	}
+}
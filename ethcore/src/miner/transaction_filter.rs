@@ -0,0 +1,203 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Filtering of transactions by banned senders, recipients and contract code hashes.
+//!
+//! Banned items are taken from a static list and from an on-chain contract.
+//! Filtered transactions are neither imported to the queue nor included in blocks.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use futures::{future, BoxFuture, Future};
+use native_contracts::TransactionFilter as Contract;
+use client::MiningBlockChainClient;
+use error::TransactionError;
+use transaction::{Action, SignedTransaction};
+use types::ids::BlockId;
+use util::{Address, H256, Hashable};
+
+/// Static list of banned senders, recipients and hashes of contract creation code.
+///
+/// Parsed from lines of `sender <address>`, `recipient <address>` or `code <hash>`,
+/// empty lines and lines starting with `#` are ignored.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FilterList {
+	/// Banned senders.
+	pub senders: HashSet<Address>,
+	/// Banned recipients.
+	pub recipients: HashSet<Address>,
+	/// Banned hashes of contract creation code.
+	pub codes: HashSet<H256>,
+}
+
+impl FilterList {
+	/// Returns true if nothing is banned.
+	pub fn is_empty(&self) -> bool {
+		self.senders.is_empty() && self.recipients.is_empty() && self.codes.is_empty()
+	}
+}
+
+impl FromStr for FilterList {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut list = FilterList::default();
+		for (number, line) in s.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			let invalid = || format!("Invalid transaction filter entry on line {}: {}", number + 1, line);
+			let mut parts = line.split_whitespace();
+			let (kind, value) = match (parts.next(), parts.next(), parts.next()) {
+				(Some(kind), Some(value), None) => (kind, value.trim_left_matches("0x")),
+				_ => return Err(invalid()),
+			};
+
+			match kind {
+				"sender" => { list.senders.insert(value.parse().map_err(|_| invalid())?); },
+				"recipient" => { list.recipients.insert(value.parse().map_err(|_| invalid())?); },
+				"code" => { list.codes.insert(value.parse().map_err(|_| invalid())?); },
+				_ => return Err(invalid()),
+			}
+		}
+		Ok(list)
+	}
+}
+
+/// Transaction filter checking the static list and the contract.
+pub struct TransactionFilter {
+	list: FilterList,
+	contract: Option<Contract>,
+}
+
+impl TransactionFilter {
+	/// Create a filter of the given banned items and the contract at the given address.
+	pub fn new(list: FilterList, contract: Option<Address>) -> Self {
+		TransactionFilter {
+			list: list,
+			contract: contract.map(Contract::new),
+		}
+	}
+
+	/// Returns true if no transaction is filtered.
+	pub fn is_empty(&self) -> bool {
+		self.list.is_empty() && self.contract.is_none()
+	}
+
+	/// Checks if the transaction may be imported to the queue or included in a block.
+	/// Transactions are rejected when the contract can't be queried.
+	pub fn check(&self, client: &MiningBlockChainClient, tx: &SignedTransaction) -> Result<(), TransactionError> {
+		let call = |address, data| future::done(client.call_contract(BlockId::Latest, address, data));
+
+		let sender = tx.sender();
+		if self.list.senders.contains(&sender) || self.banned_by_contract(|contract| contract.banned_sender(&call, sender)) {
+			debug!(target: "txqueue", "Filtering transaction {:?} of banned sender {:?}", tx.hash(), sender);
+			return Err(TransactionError::SenderBanned);
+		}
+
+		match tx.action {
+			Action::Call(recipient) => {
+				if self.list.recipients.contains(&recipient) || self.banned_by_contract(|contract| contract.banned_recipient(&call, recipient)) {
+					debug!(target: "txqueue", "Filtering transaction {:?} to banned recipient {:?}", tx.hash(), recipient);
+					return Err(TransactionError::RecipientBanned);
+				}
+			},
+			Action::Create => {
+				let code_hash = tx.data.sha3();
+				if self.list.codes.contains(&code_hash) || self.banned_by_contract(|contract| contract.banned_code(&call, code_hash)) {
+					debug!(target: "txqueue", "Filtering transaction {:?} creating banned code {:?}", tx.hash(), code_hash);
+					return Err(TransactionError::CodeBanned);
+				}
+			},
+		}
+
+		Ok(())
+	}
+
+	fn banned_by_contract<F>(&self, query: F) -> bool where F: FnOnce(&Contract) -> BoxFuture<bool, String> {
+		match self.contract {
+			Some(ref contract) => match query(contract).wait() {
+				Ok(banned) => banned,
+				Err(e) => {
+					warn!(target: "txqueue", "Unable to query the transaction filter contract: {}", e);
+					true
+				},
+			},
+			None => false,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use ethkey::{Generator, Random};
+	use client::TestBlockChainClient;
+	use error::TransactionError;
+	use transaction::{Action, Transaction};
+	use util::{Address, H256, U256, Hashable};
+	use super::{FilterList, TransactionFilter};
+
+	#[test]
+	fn should_parse_filter_list() {
+		let list: FilterList = r#"
+			# banned accounts
+			sender 0x00000000000000000000000000000000000000aa
+			recipient 00000000000000000000000000000000000000bb
+
+			code 0x0000000000000000000000000000000000000000000000000000000000000001
+		"#.parse().unwrap();
+
+		assert_eq!(list.senders.into_iter().collect::<Vec<_>>(), vec![Address::from(0xaa)]);
+		assert_eq!(list.recipients.into_iter().collect::<Vec<_>>(), vec![Address::from(0xbb)]);
+		assert_eq!(list.codes.into_iter().collect::<Vec<_>>(), vec![H256::from(1)]);
+		assert!("sender".parse::<FilterList>().is_err());
+		assert!("owner 0x00000000000000000000000000000000000000aa".parse::<FilterList>().is_err());
+		assert!("sender 0x12".parse::<FilterList>().is_err());
+	}
+
+	#[test]
+	fn should_filter_banned_transactions() {
+		// given
+		let client = TestBlockChainClient::default();
+		let keypair = Random.generate().unwrap();
+		let transaction = |action, data: Vec<u8>| Transaction {
+			action: action,
+			value: U256::zero(),
+			data: data,
+			gas: U256::from(100_000),
+			gas_price: U256::zero(),
+			nonce: U256::zero(),
+		}.sign(keypair.secret(), None);
+
+		let mut list = FilterList::default();
+		list.recipients.insert(Address::from(0xbb));
+		list.codes.insert(vec![1u8].sha3());
+		let filter = TransactionFilter::new(list.clone(), None);
+		list.senders.insert(keypair.address());
+		let sender_filter = TransactionFilter::new(list, None);
+
+		// then
+		assert_eq!(filter.check(&client, &transaction(Action::Call(Address::from(0xaa)), vec![])), Ok(()));
+		assert_eq!(filter.check(&client, &transaction(Action::Call(Address::from(0xbb)), vec![])), Err(TransactionError::RecipientBanned));
+		assert_eq!(filter.check(&client, &transaction(Action::Create, vec![2])), Ok(()));
+		assert_eq!(filter.check(&client, &transaction(Action::Create, vec![1])), Err(TransactionError::CodeBanned));
+		assert_eq!(sender_filter.check(&client, &transaction(Action::Call(Address::from(0xaa)), vec![])), Err(TransactionError::SenderBanned));
+		assert!(TransactionFilter::new(FilterList::default(), None).is_empty());
+	}
+}
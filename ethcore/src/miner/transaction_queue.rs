@@ -97,21 +97,26 @@
 //!	3. `cull` is used to inform the queue about client (state) nonce changes.
 //!     - It removes all transactions (either from `current` or `future`) with nonce < client nonce
 //!     - It moves matching `future` transactions to `current`
+//!     - Removed transactions are reported as stale until `mark_mined` is called with the ones included in blocks
 //! 4. `remove_old` is used as convenient method to update the state nonce for all senders in the queue.
 //!		- Invokes `cull` with latest state nonce for all senders.
+//!		- Evicts non-local transactions which stayed in `current` or `future` longer than allowed for that set.
 
 use std::ops::Deref;
 use std::cmp::Ordering;
 use std::cmp;
+use std::mem;
 use std::collections::{HashSet, HashMap, BTreeSet, BTreeMap};
 use linked_hash_map::LinkedHashMap;
-use util::{Address, H256, Uint, U256};
+use lru_cache::LruCache;
+use util::{Address, H256, Uint, U256, HeapSizeOf};
 use util::table::Table;
 use transaction::*;
 use error::{Error, TransactionError};
 use client::TransactionImportResult;
 use header::BlockNumber;
 use miner::local_transactions::{LocalTransactionsList, Status as LocalTransactionStatus};
+use miner::dropped_transactions::{DroppedTransactions, DropReason};
 
 /// Transaction origin
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -179,6 +184,10 @@ struct TransactionOrder {
 	origin: TransactionOrigin,
 	/// Penalties
 	penalties: usize,
+	/// Memory used by the transaction
+	mem_usage: usize,
+	/// Whether a local transaction goes before non-local ones
+	local_priority: bool,
 }
 
 
@@ -195,6 +204,21 @@ impl TransactionOrder {
 			hash: tx.hash(),
 			origin: tx.origin,
 			penalties: 0,
+			mem_usage: tx.mem_usage(),
+			local_priority: true,
+		}
+	}
+
+	fn with_local_priority(mut self, local_priority: bool) -> Self {
+		self.local_priority = local_priority;
+		self
+	}
+
+	/// Origin used to order the transaction.
+	fn priority_origin(&self) -> TransactionOrigin {
+		match self.origin {
+			TransactionOrigin::Local if !self.local_priority => TransactionOrigin::External,
+			origin => origin,
 		}
 	}
 
@@ -228,9 +252,10 @@ impl Ord for TransactionOrder {
 			return self.penalties.cmp(&b.penalties);
 		}
 
-		// Local transactions should always have priority
-		if self.origin != b.origin {
-			return self.origin.cmp(&b.origin);
+		// Local transactions should have priority (unless disabled by the policy)
+		let (origin, b_origin) = (self.priority_origin(), b.priority_origin());
+		if origin != b_origin {
+			return origin.cmp(&b_origin);
 		}
 
 		// Check nonce_height
@@ -294,6 +319,10 @@ impl VerifiedTransaction {
 		self.transaction.hash()
 	}
 
+	fn mem_usage(&self) -> usize {
+		mem::size_of::<VerifiedTransaction>() + self.transaction.heap_size_of_children()
+	}
+
 	fn nonce(&self) -> U256 {
 		self.transaction.nonce
 	}
@@ -359,6 +388,12 @@ struct TransactionSet {
 	by_gas_price: GasPriceQueue,
 	limit: usize,
 	gas_limit: U256,
+	/// Maximal number of transactions of a single sender, `None` for no limit.
+	sender_limit: Option<usize>,
+	/// Maximal memory used by transactions in bytes, `None` for no limit.
+	memory_limit: Option<usize>,
+	/// Treatment of local transactions.
+	locals: LocalsPolicy,
 }
 
 impl TransactionSet {
@@ -388,9 +423,16 @@ impl TransactionSet {
 	///
 	/// It drops transactions from this set but also removes associated `VerifiedTransaction`.
 	/// Returns addresses and lowest nonces of transactions removed because of limit.
-	fn enforce_limit(&mut self, by_hash: &mut HashMap<H256, VerifiedTransaction>, local: &mut LocalTransactionsList) -> Option<HashMap<Address, U256>> {
+	fn enforce_limit(
+		&mut self,
+		by_hash: &mut HashMap<H256, VerifiedTransaction>,
+		local: &mut LocalTransactionsList,
+		dropped: &mut DroppedTransactions,
+	) -> Option<HashMap<Address, U256>> {
 		let mut count = 0;
 		let mut gas: U256 = 0.into();
+		let mut mem_usage = 0usize;
+		let memory_limit = self.memory_limit.unwrap_or(usize::max_value());
 		let to_drop : Vec<(Address, U256)> = {
 			self.by_priority
 				.iter()
@@ -399,9 +441,11 @@ impl TransactionSet {
 					let r = gas.overflowing_add(order.gas);
 					if r.1 { return false }
 					gas = r.0;
-					// Own and retracted transactions are allowed to go above all limits.
-					order.origin != TransactionOrigin::Local && order.origin != TransactionOrigin::RetractedBlock &&
-					(gas > self.gas_limit || count > self.limit)
+					mem_usage = mem_usage.saturating_add(order.mem_usage);
+					// Own (if retained by the policy) and retracted transactions are allowed to go above all limits.
+					!(order.origin == TransactionOrigin::Local && self.locals.retains()) &&
+					order.origin != TransactionOrigin::RetractedBlock &&
+					(gas > self.gas_limit || count > self.limit || mem_usage > memory_limit)
 				})
 				.map(|order| by_hash.get(&order.hash)
 					.expect("All transactions in `self.by_priority` and `self.by_address` are kept in sync with `by_hash`."))
@@ -418,6 +462,7 @@ impl TransactionSet {
 				let order = by_hash.remove(&order.hash)
 					.expect("hash is in `by_priorty`; all hashes in `by_priority` must be in `by_hash`; qed");
 
+				dropped.mark(order.hash(), DropReason::LimitReached);
 				if order.origin.is_local() {
 					local.mark_dropped(order.transaction);
 				}
@@ -457,6 +502,20 @@ impl TransactionSet {
 		self.limit = limit;
 	}
 
+	/// Returns the limit of transactions per sender if the sender has reached it
+	/// and the transaction with given nonce would not replace one of theirs.
+	fn sender_limit_reached(&self, sender: &Address, nonce: &U256) -> Option<usize> {
+		let limit = match self.sender_limit {
+			Some(limit) => limit,
+			None => return None,
+		};
+		let count = self.by_address.row(sender).map_or(0, |row| row.len());
+		match count >= limit && self.by_address.get(sender, nonce).is_none() {
+			true => Some(limit),
+			false => None,
+		}
+	}
+
 	/// Get the minimum gas price that we can accept into this queue that wouldn't cause the transaction to
 	/// immediately be dropped. 0 if the queue isn't at capacity; 1 plus the lowest if it is.
 	fn gas_price_entry_limit(&self) -> U256 {
@@ -476,6 +535,66 @@ pub struct TransactionQueueStatus {
 	pub future: usize,
 }
 
+/// Priority of a transaction in the queue.
+/// Transactions are ordered by penalties, origin, nonce height and then gas price.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TransactionPriority {
+	/// Number of times the sender was penalized
+	pub penalties: usize,
+	/// Whether the transaction is local (local transactions go first)
+	pub local: bool,
+	/// Difference between transaction nonce and the sender's nonce in state
+	pub nonce_height: U256,
+	/// Gas price of the transaction
+	pub gas_price: U256,
+	/// Gas price lowered by the gas usage factor of the prioritization strategy
+	pub effective_gas_price: U256,
+}
+
+/// State of a single transaction known to the queue.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TransactionInfo {
+	/// Transaction is ready to go to a block.
+	Pending {
+		/// Position among pending transactions (0 is mined first)
+		position: usize,
+		/// Priority of the transaction
+		priority: TransactionPriority,
+	},
+	/// Transaction waits for a transaction with lower nonce.
+	Future {
+		/// Position among future transactions
+		position: usize,
+		/// Priority of the transaction
+		priority: TransactionPriority,
+		/// Nonce of the first missing transaction of the sender
+		missing_nonce: U256,
+	},
+	/// Transaction was recently rejected or removed from the queue.
+	Dropped(DropReason),
+}
+
+/// Detailed status of the queue.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PoolStatus {
+	/// Number of pending transactions
+	pub pending: usize,
+	/// Number of future transactions
+	pub future: usize,
+	/// Limit of pending transactions
+	pub pending_limit: usize,
+	/// Limit of future transactions
+	pub future_limit: usize,
+	/// Number of distinct senders of queued transactions
+	pub senders: usize,
+	/// Minimal gas price of transactions accepted to the queue
+	pub minimal_gas_price: U256,
+	/// Minimal gas price of transactions accepted when the queue is full
+	pub effective_minimal_gas_price: U256,
+	/// Number of recently dropped transactions whose reasons are remembered
+	pub recently_dropped: usize,
+}
+
 /// Details of account
 pub struct AccountDetails {
 	/// Most recent account nonce
@@ -510,6 +629,35 @@ pub enum PrioritizationStrategy {
 	GasFactorAndGasPrice,
 }
 
+/// Treatment of local transactions when the queue is full.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LocalsPolicy {
+	/// Local transactions are never dropped because of limits
+	/// and are included in blocks before other transactions regardless of gas price.
+	Prioritize,
+	/// Local transactions are never dropped because of limits,
+	/// but are ordered together with other transactions.
+	Retain,
+	/// Local transactions are dropped and ordered as other transactions.
+	Ignore,
+}
+
+impl Default for LocalsPolicy {
+	fn default() -> Self {
+		LocalsPolicy::Prioritize
+	}
+}
+
+impl LocalsPolicy {
+	fn retains(&self) -> bool {
+		*self != LocalsPolicy::Ignore
+	}
+
+	fn prioritizes(&self) -> bool {
+		*self == LocalsPolicy::Prioritize
+	}
+}
+
 /// Reason to remove single transaction from the queue.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum RemovalReason {
@@ -521,7 +669,10 @@ pub enum RemovalReason {
 
 /// Point in time when transaction was inserted.
 pub type QueuingInstant = BlockNumber;
-const DEFAULT_QUEUING_PERIOD: BlockNumber = 128;
+/// Default number of blocks a transaction may stay in the queue.
+pub const DEFAULT_QUEUING_PERIOD: BlockNumber = 128;
+/// Number of hashes of evicted transactions remembered (if enabled).
+const EVICTED_HASHES: usize = 4096;
 
 /// `TransactionQueue` transaction details provider.
 pub trait TransactionDetailsProvider {
@@ -543,20 +694,33 @@ pub struct TransactionQueue {
 	tx_gas_limit: U256,
 	/// Current gas limit (block gas limit * factor). Transactions above the limit will not be accepted (default to !0)
 	gas_limit: U256,
-	/// Maximal time transaction may occupy the queue.
-	/// When we reach `max_time_in_queue / 2^3` we re-validate
+	/// Maximal time transaction may occupy `current`.
+	/// When we reach `max_time_in_current / 2^3` we re-validate
 	/// account balance.
-	max_time_in_queue: QueuingInstant,
+	max_time_in_current: QueuingInstant,
+	/// Maximal time transaction may occupy `future`.
+	max_time_in_future: QueuingInstant,
+	/// Hashes of evicted transactions which are not accepted again from the network.
+	/// `None` if evicted transactions are not remembered.
+	evicted: Option<LruCache<H256, ()>>,
 	/// Priority queue for transactions that can go to block
 	current: TransactionSet,
 	/// Priority queue for transactions that has been received but are not yet valid to go to block
 	future: TransactionSet,
+	/// Limit of `future` if different from the limit of `current`.
+	future_limit: Option<usize>,
+	/// Maximal number of missing nonces before a non-local transaction, `None` for no limit.
+	max_nonce_gap: Option<U256>,
+	/// Percentage by which the gas price has to be higher to replace a transaction with the same nonce.
+	replacement_bump: u32,
 	/// All transactions managed by queue indexed by hash
 	by_hash: HashMap<H256, VerifiedTransaction>,
 	/// Last nonce of transaction in current (to quickly check next expected transaction)
 	last_nonces: HashMap<Address, U256>,
 	/// List of local transactions and their statuses.
 	local_transactions: LocalTransactionsList,
+	/// Reasons why recently seen transactions are not in the queue.
+	dropped: DroppedTransactions,
 }
 
 impl Default for TransactionQueue {
@@ -579,6 +743,9 @@ impl TransactionQueue {
 			by_gas_price: Default::default(),
 			limit: limit,
 			gas_limit: gas_limit,
+			sender_limit: None,
+			memory_limit: None,
+			locals: LocalsPolicy::Prioritize,
 		};
 
 		let future = TransactionSet {
@@ -587,6 +754,9 @@ impl TransactionQueue {
 			by_gas_price: Default::default(),
 			limit: limit,
 			gas_limit: gas_limit,
+			sender_limit: None,
+			memory_limit: None,
+			locals: LocalsPolicy::Prioritize,
 		};
 
 		TransactionQueue {
@@ -594,22 +764,28 @@ impl TransactionQueue {
 			minimal_gas_price: U256::zero(),
 			tx_gas_limit: tx_gas_limit,
 			gas_limit: !U256::zero(),
-			max_time_in_queue: DEFAULT_QUEUING_PERIOD,
+			max_time_in_current: DEFAULT_QUEUING_PERIOD,
+			max_time_in_future: DEFAULT_QUEUING_PERIOD,
+			evicted: None,
 			current: current,
 			future: future,
+			future_limit: None,
+			max_nonce_gap: None,
+			replacement_bump: 0,
 			by_hash: HashMap::new(),
 			last_nonces: HashMap::new(),
 			local_transactions: LocalTransactionsList::default(),
+			dropped: DroppedTransactions::default(),
 		}
 	}
 
 	/// Set the new limit for `current` and `future` queue.
 	pub fn set_limit(&mut self, limit: usize) {
 		self.current.set_limit(limit);
-		self.future.set_limit(limit);
+		self.future.set_limit(self.future_limit.unwrap_or(limit));
 		// And ensure the limits
-		self.current.enforce_limit(&mut self.by_hash, &mut self.local_transactions);
-		self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions);
+		self.current.enforce_limit(&mut self.by_hash, &mut self.local_transactions, &mut self.dropped);
+		self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions, &mut self.dropped);
 	}
 
 	/// Returns current limit of transactions in the queue.
@@ -617,6 +793,65 @@ impl TransactionQueue {
 		self.current.limit
 	}
 
+	/// Set the limit of `future` queue, `None` for the limit of `current` queue.
+	pub fn set_future_limit(&mut self, limit: Option<usize>) {
+		self.future_limit = limit;
+		self.future.set_limit(limit.unwrap_or(self.current.limit));
+		self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions, &mut self.dropped);
+	}
+
+	/// Set the maximal memory in bytes used by transactions in `future`, `None` for no limit.
+	pub fn set_future_memory_limit(&mut self, limit: Option<usize>) {
+		self.future.memory_limit = limit;
+		self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions, &mut self.dropped);
+	}
+
+	/// Set the treatment of local transactions. Ordering of already queued transactions is not changed.
+	pub fn set_locals_policy(&mut self, policy: LocalsPolicy) {
+		self.current.locals = policy;
+		self.future.locals = policy;
+		self.current.enforce_limit(&mut self.by_hash, &mut self.local_transactions, &mut self.dropped);
+		self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions, &mut self.dropped);
+	}
+
+	/// Set how many nonces may be missing before a non-local transaction accepted to `future`, `None` for no limit.
+	pub fn set_max_nonce_gap(&mut self, gap: Option<usize>) {
+		self.max_nonce_gap = gap.map(U256::from);
+	}
+
+	/// Set the maximal number of blocks a non-local transaction may stay in `current` and in `future`.
+	pub fn set_max_time_in_queue(&mut self, current: QueuingInstant, future: QueuingInstant) {
+		self.max_time_in_current = current;
+		self.max_time_in_future = future;
+	}
+
+	/// Set whether hashes of evicted transactions are remembered, so they are not imported from the network again.
+	pub fn set_remember_evicted(&mut self, remember: bool) {
+		match (remember, self.evicted.is_some()) {
+			(true, false) => self.evicted = Some(LruCache::new(EVICTED_HASHES)),
+			(false, true) => self.evicted = None,
+			_ => {},
+		}
+	}
+
+	/// Get the percentage by which the gas price has to be higher to replace a transaction with the same nonce.
+	pub fn replacement_price_bump(&self) -> u32 {
+		self.replacement_bump
+	}
+
+	/// Set the percentage by which the gas price has to be higher to replace a transaction with the same nonce.
+	/// Transactions already in the queue are not affected.
+	pub fn set_replacement_price_bump(&mut self, percent: u32) {
+		self.replacement_bump = percent;
+	}
+
+	/// Set the maximal numbers of pending and future transactions of a single sender, `None` for no limit.
+	/// Local and retracted transactions are not limited. Transactions already in the queue are not affected.
+	pub fn set_sender_limits(&mut self, pending: Option<usize>, future: Option<usize>) {
+		self.current.sender_limit = pending;
+		self.future.sender_limit = future;
+	}
+
 	/// Get the minimal gas price.
 	pub fn minimal_gas_price(&self) -> &U256 {
 		&self.minimal_gas_price
@@ -649,7 +884,7 @@ impl TransactionQueue {
 	pub fn set_total_gas_limit(&mut self, gas_limit: U256) {
 		self.future.gas_limit = gas_limit;
 		self.current.gas_limit = gas_limit;
-		self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions);
+		self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions, &mut self.dropped);
 	}
 
 	/// Set the new limit for the amount of gas any individual transaction may have.
@@ -666,6 +901,86 @@ impl TransactionQueue {
 		}
 	}
 
+	/// Returns detailed status of the queue.
+	pub fn pool_status(&self) -> PoolStatus {
+		let senders = self.current.by_address.keys()
+			.chain(self.future.by_address.keys())
+			.collect::<HashSet<_>>()
+			.len();
+
+		PoolStatus {
+			pending: self.current.by_priority.len(),
+			future: self.future.by_priority.len(),
+			pending_limit: self.current.limit,
+			future_limit: self.future.limit,
+			senders: senders,
+			minimal_gas_price: self.minimal_gas_price,
+			effective_minimal_gas_price: self.effective_minimum_gas_price(),
+			recently_dropped: self.dropped.len(),
+		}
+	}
+
+	/// Returns state of the transaction with given hash:
+	/// its position and priority if queued or the reason why it was recently dropped.
+	pub fn transaction_info(&self, hash: &H256) -> Option<TransactionInfo> {
+		let tx = match self.by_hash.get(hash) {
+			Some(tx) => tx,
+			None => return self.dropped.get(hash).cloned().map(TransactionInfo::Dropped),
+		};
+		let (sender, nonce) = (tx.sender(), tx.nonce());
+
+		let priority = |order: &TransactionOrder| TransactionPriority {
+			penalties: order.penalties,
+			local: order.origin.is_local(),
+			nonce_height: order.nonce_height,
+			gas_price: order.gas_price,
+			effective_gas_price: match order.strategy {
+				PrioritizationStrategy::GasFactorAndGasPrice => match order.gas_price.overflowing_sub(order.gas_factor) {
+					(price, false) => price,
+					(_, true) => U256::zero(),
+				},
+				_ => order.gas_price,
+			},
+		};
+		let position = |set: &TransactionSet| set.by_priority.iter()
+			.position(|order| order.hash == *hash)
+			.expect("Transactions in `by_address` are also in `by_priority`; qed");
+
+		if let Some(order) = self.current.by_address.get(&sender, &nonce) {
+			return Some(TransactionInfo::Pending {
+				position: position(&self.current),
+				priority: priority(order),
+			});
+		}
+
+		let order = self.future.by_address.get(&sender, &nonce)
+			.expect("All transactions in `by_hash` are either in `current` or in `future`; qed");
+		let missing_nonce = self.last_nonces.get(&sender)
+			.map_or(nonce - order.nonce_height, |last| *last + U256::one());
+		Some(TransactionInfo::Future {
+			position: position(&self.future),
+			priority: priority(order),
+			missing_nonce: missing_nonce,
+		})
+	}
+
+	/// Remember that the transaction was rejected before entering the queue.
+	pub fn mark_rejected(&mut self, hash: H256, err: TransactionError) {
+		// the transaction is either queued or its previous state is more interesting
+		if err == TransactionError::AlreadyImported {
+			return;
+		}
+		self.dropped.mark(hash, DropReason::Rejected(err));
+	}
+
+	/// Remember that transactions removed by `cull` were included in a block.
+	/// Other transactions with the same nonces stay reported as stale.
+	pub fn mark_mined(&mut self, hashes: &[H256]) {
+		for hash in hashes {
+			self.dropped.mark_mined(hash);
+		}
+	}
+
 	/// Add signed transaction to queue to be verified and imported.
 	///
 	/// NOTE details_provider methods should be cheap to compute
@@ -716,6 +1031,11 @@ impl TransactionQueue {
 		condition: Option<Condition>,
 		details_provider: &TransactionDetailsProvider,
 	) -> Result<TransactionImportResult, Error> {
+		if origin == TransactionOrigin::External && self.evicted.as_mut().map_or(false, |evicted| evicted.contains_key(&tx.hash())) {
+			trace!(target: "txqueue", "Dropping previously evicted transaction: {:?}", tx.hash());
+			return Err(Error::Transaction(TransactionError::AlreadyImported));
+		}
+
 		if origin != TransactionOrigin::Local && tx.gas_price < self.minimal_gas_price {
 			// if it is non-service-transaction => drop
 			let is_service_transaction = tx.gas_price.is_zero();
@@ -825,7 +1145,11 @@ impl TransactionQueue {
 		tx.check_low_s()?;
 		// No invalid transactions beyond this point.
 		let vtx = VerifiedTransaction::new(tx, origin, time, condition);
+		let hash = vtx.hash();
 		let r = self.import_tx(vtx, client_account.nonce).map_err(Error::Transaction);
+		if r.is_ok() {
+			self.dropped.remove(&hash);
+		}
 		assert_eq!(self.future.by_priority.len() + self.current.by_priority.len(), self.by_hash.len());
 		r
 	}
@@ -867,7 +1191,8 @@ impl TransactionQueue {
 	}
 
 	/// Checks the current nonce for all transactions' senders in the queue and removes the old transactions.
-	pub fn remove_old<F>(&mut self, fetch_account: &F, current_time: QueuingInstant) where
+	/// Returns hashes of transactions evicted for occupying the queue too long.
+	pub fn remove_old<F>(&mut self, fetch_account: &F, current_time: QueuingInstant) -> Vec<H256> where
 		F: Fn(&Address) -> AccountDetails,
 	{
 		let senders = self.current.by_address.keys()
@@ -879,35 +1204,53 @@ impl TransactionQueue {
 			self.cull(*sender, details.nonce);
 		}
 
-		let max_time = self.max_time_in_queue;
-		let balance_check = max_time >> 3;
+		let (max_time_current, max_time_future) = (self.max_time_in_current, self.max_time_in_future);
+		let balance_check = max_time_current >> 3;
 		// Clear transactions occupying the queue too long
-		let invalid = self.by_hash.iter()
-			.filter(|&(_, ref tx)| !tx.origin.is_local())
-			.map(|(hash, tx)| (hash, tx, current_time.saturating_sub(tx.insertion_time)))
-			.filter_map(|(hash, tx, time_diff)| {
-				if time_diff > max_time {
-					return Some(*hash);
-				}
-
-				if time_diff > balance_check {
-					return match senders.get(&tx.sender()) {
-						Some(details) if tx.cost() > details.balance => {
-							Some(*hash)
-						},
-						_ => None,
+		let (evicted, invalid): (Vec<_>, Vec<_>) = {
+			let current = &self.current;
+			self.by_hash.iter()
+				.filter(|&(_, ref tx)| !tx.origin.is_local())
+				.map(|(hash, tx)| (hash, tx, current_time.saturating_sub(tx.insertion_time)))
+				.filter_map(|(hash, tx, time_diff)| {
+					let max_time = match current.by_address.get(&tx.sender(), &tx.nonce()) {
+						Some(_) => max_time_current,
+						None => max_time_future,
 					};
-				}
+					if time_diff > max_time {
+						return Some((*hash, true));
+					}
 
-				None
-			})
-			.collect::<Vec<_>>();
+					if time_diff > balance_check {
+						return match senders.get(&tx.sender()) {
+							Some(details) if tx.cost() > details.balance => {
+								Some((*hash, false))
+							},
+							_ => None,
+						};
+					}
+
+					None
+				})
+				.partition(|&(_, evicted)| evicted)
+		};
 		let fetch_nonce = |a: &Address| senders.get(a)
 			.expect("We fetch details for all senders from both current and future")
 			.nonce;
-		for hash in invalid {
+		for (hash, _) in invalid {
 			self.remove(&hash, &fetch_nonce, RemovalReason::Invalid);
 		}
+
+		let evicted = evicted.into_iter().map(|(hash, _)| hash).collect::<Vec<_>>();
+		for hash in &evicted {
+			trace!(target: "txqueue", "Evicting transaction occupying the queue too long: {:?}", hash);
+			self.remove(hash, &fetch_nonce, RemovalReason::Invalid);
+			self.dropped.mark(*hash, DropReason::Evicted);
+			if let Some(ref mut remembered) = self.evicted {
+				remembered.insert(*hash, ());
+			}
+		}
+		evicted
 	}
 
 	/// Penalize transactions from sender of transaction with given hash.
@@ -970,6 +1313,10 @@ impl TransactionQueue {
 		let current_nonce = fetch_nonce(&sender);
 
 		trace!(target: "txqueue", "Removing invalid transaction: {:?}", transaction.hash());
+		self.dropped.mark(*transaction_hash, match reason {
+			RemovalReason::Invalid => DropReason::Invalid,
+			RemovalReason::Canceled => DropReason::Canceled,
+		});
 
 		// Mark in locals
 		if self.local_transactions.contains(transaction_hash) {
@@ -1047,6 +1394,7 @@ impl TransactionQueue {
 				trace!(target: "txqueue", "Removing old transaction: {:?} (nonce: {} < {})", order.hash, k, current_nonce);
 				// Remove the transaction completely
 				self.by_hash.remove(&order.hash).expect("All transactions in `future` are also in `by_hash`");
+				self.dropped.mark(order.hash, DropReason::Stale);
 			}
 		}
 	}
@@ -1069,17 +1417,18 @@ impl TransactionQueue {
 					self.local_transactions.mark_future(order.hash);
 				}
 				if let Some(old) = self.future.insert(*sender, k, order.clone()) {
-					Self::replace_orders(*sender, k, old, order, &mut self.future, &mut self.by_hash, &mut self.local_transactions);
+					Self::replace_orders(*sender, k, old, order, 0, &mut self.future, &mut self.by_hash, &mut self.local_transactions, &mut self.dropped);
 				}
 			} else {
 				trace!(target: "txqueue", "Removing old transaction: {:?} (nonce: {} < {})", order.hash, k, current_nonce);
 				let tx = self.by_hash.remove(&order.hash).expect("All transactions in `future` are also in `by_hash`");
+				self.dropped.mark(order.hash, DropReason::Stale);
 				if tx.origin.is_local() {
 					self.local_transactions.mark_mined(tx.transaction);
 				}
 			}
 		}
-		self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions);
+		self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions, &mut self.dropped);
 	}
 
 	/// Returns top transactions from the queue ordered by priority.
@@ -1190,7 +1539,7 @@ impl TransactionQueue {
 					self.local_transactions.mark_pending(order.hash);
 				}
 				if let Some(old) = self.current.insert(address, current_nonce, order.clone()) {
-					Self::replace_orders(address, current_nonce, old, order, &mut self.current, &mut self.by_hash, &mut self.local_transactions);
+					Self::replace_orders(address, current_nonce, old, order, 0, &mut self.current, &mut self.by_hash, &mut self.local_transactions, &mut self.dropped);
 				}
 				update_last_nonce_to = Some(current_nonce);
 				current_nonce = current_nonce + U256::one();
@@ -1251,35 +1600,55 @@ impl TransactionQueue {
 
 		// Future transaction
 		if nonce > next_nonce {
+			if tx.origin == TransactionOrigin::External {
+				if let Some(limit) = self.max_nonce_gap {
+					let gap = nonce - next_nonce;
+					if gap > limit {
+						trace!(target: "txqueue", "Dropping transaction too far in future: {:?} (nonce: {} > {} + {})", hash, nonce, next_nonce, limit);
+						return Err(TransactionError::NonceGapTooLarge { limit: limit, got: gap });
+					}
+				}
+				if let Some(limit) = self.future.sender_limit_reached(&address, &nonce) {
+					trace!(target: "txqueue", "Dropping transaction above future limit of the sender: {:?} (sender: {:?})", hash, address);
+					return Err(TransactionError::SenderFutureLimitReached { limit: limit });
+				}
+			}
 			// We have a gap - put to future.
 			// Insert transaction (or replace old one with lower gas price)
 			check_too_cheap(
-				Self::replace_transaction(tx, state_nonce, min_gas_price, &mut self.future, &mut self.by_hash, &mut self.local_transactions)
+				Self::replace_transaction(tx, state_nonce, min_gas_price, self.replacement_bump, &mut self.future, &mut self.by_hash, &mut self.local_transactions, &mut self.dropped)
 			)?;
 			// Enforce limit in Future
-			let removed = self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions);
+			let removed = self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions, &mut self.dropped);
 			// Return an error if this transaction was not imported because of limit.
-			check_if_removed(&address, &nonce, removed)?;
+			check_if_removed(&address, &nonce, removed).map_err(|_| TransactionError::FutureLimitReached)?;
 
 			debug!(target: "txqueue", "Importing transaction to future: {:?}", hash);
 			debug!(target: "txqueue", "status: {:?}", self.status());
 			return Ok(TransactionImportResult::Future);
 		}
 
+		if tx.origin == TransactionOrigin::External {
+			if let Some(limit) = self.current.sender_limit_reached(&address, &nonce) {
+				trace!(target: "txqueue", "Dropping transaction above pending limit of the sender: {:?} (sender: {:?})", hash, address);
+				return Err(TransactionError::SenderLimitReached { limit: limit });
+			}
+		}
+
 		// We might have filled a gap - move some more transactions from future
 		self.move_matching_future_to_current(address, nonce, state_nonce);
 		self.move_matching_future_to_current(address, nonce + U256::one(), state_nonce);
 
 		// Replace transaction if any
 		check_too_cheap(
-			Self::replace_transaction(tx, state_nonce, min_gas_price, &mut self.current, &mut self.by_hash, &mut self.local_transactions)
+			Self::replace_transaction(tx, state_nonce, min_gas_price, self.replacement_bump, &mut self.current, &mut self.by_hash, &mut self.local_transactions, &mut self.dropped)
 		)?;
 		// Keep track of highest nonce stored in current
 		let new_max = self.last_nonces.get(&address).map_or(nonce, |n| cmp::max(nonce, *n));
 		self.last_nonces.insert(address, new_max);
 
 		// Also enforce the limit
-		let removed = self.current.enforce_limit(&mut self.by_hash, &mut self.local_transactions);
+		let removed = self.current.enforce_limit(&mut self.by_hash, &mut self.local_transactions, &mut self.dropped);
 		// If some transaction were removed because of limit we need to update last_nonces also.
 		self.update_last_nonces(&removed);
 		// Trigger error if the transaction we are importing was removed.
@@ -1305,7 +1674,8 @@ impl TransactionQueue {
 
 	/// Replaces transaction in given set (could be `future` or `current`).
 	///
-	/// If there is already transaction with same `(sender, nonce)` it will be replaced iff `gas_price` is higher.
+	/// If there is already transaction with same `(sender, nonce)` it will be replaced iff `gas_price` is higher
+	/// by at least `replacement_bump` percent.
 	/// One of the transactions is dropped from set and also removed from queue entirely (from `by_hash`).
 	///
 	/// Returns `true` if transaction actually got to the queue (`false` if there was already a transaction with higher
@@ -1314,11 +1684,14 @@ impl TransactionQueue {
 		tx: VerifiedTransaction,
 		base_nonce: U256,
 		min_gas_price: (U256, PrioritizationStrategy),
+		replacement_bump: u32,
 		set: &mut TransactionSet,
 		by_hash: &mut HashMap<H256, VerifiedTransaction>,
 		local: &mut LocalTransactionsList,
+		dropped: &mut DroppedTransactions,
 	) -> bool {
-		let order = TransactionOrder::for_transaction(&tx, base_nonce, min_gas_price.0, min_gas_price.1);
+		let order = TransactionOrder::for_transaction(&tx, base_nonce, min_gas_price.0, min_gas_price.1)
+			.with_local_priority(set.locals.prioritizes());
 		let hash = tx.hash();
 		let address = tx.sender();
 		let nonce = tx.nonce();
@@ -1329,7 +1702,7 @@ impl TransactionQueue {
 		trace!(target: "txqueue", "Inserting: {:?}", order);
 
 		if let Some(old) = set.insert(address, nonce, order.clone()) {
-			Self::replace_orders(address, nonce, old, order, set, by_hash, local)
+			Self::replace_orders(address, nonce, old, order, replacement_bump, set, by_hash, local, dropped)
 		} else {
 			true
 		}
@@ -1340,16 +1713,18 @@ impl TransactionQueue {
 		nonce: U256,
 		old: TransactionOrder,
 		order: TransactionOrder,
+		replacement_bump: u32,
 		set: &mut TransactionSet,
 		by_hash: &mut HashMap<H256, VerifiedTransaction>,
 		local: &mut LocalTransactionsList,
+		dropped: &mut DroppedTransactions,
 	) -> bool {
 		// There was already transaction in queue. Let's check which one should stay
 		let old_hash = old.hash;
 		let new_hash = order.hash;
 		let old_fee = old.gas_price;
 		let new_fee = order.gas_price;
-		if old_fee.cmp(&new_fee) == Ordering::Greater {
+		if new_fee < min_replacement_fee(old_fee, replacement_bump) {
 			trace!(target: "txqueue", "Didn't insert transaction because gas price was too low: {:?} ({:?} stays in the queue)", order.hash, old.hash);
 			// Put back old transaction since it has greater priority (higher gas_price)
 			set.insert(address, nonce, old);
@@ -1363,6 +1738,7 @@ impl TransactionQueue {
 			trace!(target: "txqueue", "Replaced transaction: {:?} with transaction with higher gas price: {:?}", old.hash, order.hash);
 			// Make sure we remove old transaction entirely
 			let old = by_hash.remove(&old.hash).expect("The hash is coming from `future` so it has to be in `by_hash`.");
+			dropped.mark(old_hash, DropReason::Replaced(new_fee, new_hash));
 			if old.origin.is_local() {
 				local.mark_replaced(old.transaction, new_fee, new_hash);
 			}
@@ -1371,6 +1747,18 @@ impl TransactionQueue {
 	}
 }
 
+/// Minimal gas price of a transaction replacing one with `old_fee`, higher by `bump` percent.
+fn min_replacement_fee(old_fee: U256, bump: u32) -> U256 {
+	let extra = match old_fee.overflowing_mul(U256::from(bump)) {
+		(extra, false) => extra / U256::from(100),
+		(_, true) => old_fee / U256::from(100) * U256::from(bump),
+	};
+	match old_fee.overflowing_add(extra) {
+		(fee, false) => fee,
+		(_, true) => !U256::zero(),
+	}
+}
+
 fn check_too_cheap(is_in: bool) -> Result<(), TransactionError> {
 	if is_in {
 		Ok(())
@@ -1598,12 +1986,16 @@ pub mod test {
 	fn should_create_transaction_set() {
 		// given
 		let mut local = LocalTransactionsList::default();
+		let mut dropped = DroppedTransactions::default();
 		let mut set = TransactionSet {
 			by_priority: BTreeSet::new(),
 			by_address: Table::new(),
 			by_gas_price: Default::default(),
 			limit: 1,
 			gas_limit: !U256::zero(),
+			sender_limit: None,
+			memory_limit: None,
+			locals: LocalsPolicy::Prioritize,
 		};
 		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
 		let tx1 = VerifiedTransaction::new(tx1, TransactionOrigin::External, 0, None);
@@ -1625,7 +2017,7 @@ pub mod test {
 		assert_eq!(set.by_address.len(), 2);
 
 		// when
-		set.enforce_limit(&mut by_hash, &mut local);
+		set.enforce_limit(&mut by_hash, &mut local, &mut dropped);
 
 		// then
 		assert_eq!(by_hash.len(), 1);
@@ -1645,6 +2037,9 @@ pub mod test {
 			by_gas_price: Default::default(),
 			limit: 1,
 			gas_limit: !U256::zero(),
+			sender_limit: None,
+			memory_limit: None,
+			locals: LocalsPolicy::Prioritize,
 		};
 		// Create two transactions with same nonce
 		// (same hash)
@@ -1694,6 +2089,9 @@ pub mod test {
 			by_gas_price: Default::default(),
 			limit: 2,
 			gas_limit: !U256::zero(),
+			sender_limit: None,
+			memory_limit: None,
+			locals: LocalsPolicy::Prioritize,
 		};
 		let tx = new_tx_default();
 		let tx1 = VerifiedTransaction::new(tx.clone(), TransactionOrigin::External, 0, None);
@@ -1712,6 +2110,9 @@ pub mod test {
 			by_gas_price: Default::default(),
 			limit: 1,
 			gas_limit: !U256::zero(),
+			sender_limit: None,
+			memory_limit: None,
+			locals: LocalsPolicy::Prioritize,
 		};
 
 		assert_eq!(set.gas_price_entry_limit(), 0.into());
@@ -1991,6 +2392,42 @@ pub mod test {
 		assert_eq!(top.len(), 2);
 	}
 
+	#[test]
+	fn should_order_local_transactions_by_gas_price_if_not_prioritized() {
+		// given
+		let mut txq = TransactionQueue::default();
+		txq.set_locals_policy(LocalsPolicy::Retain);
+		let tx = new_tx_default();
+		let (_, tx2) = new_similar_tx_pair();
+
+		// when
+		txq.add(tx2.clone(), TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		txq.add(tx.clone(), TransactionOrigin::Local, 0, None, &default_tx_provider()).unwrap();
+
+		// then
+		let top = txq.top_transactions();
+		assert_eq!(top[0], tx2);
+		assert_eq!(top[1], tx);
+		assert_eq!(top.len(), 2);
+	}
+
+	#[test]
+	fn should_drop_local_transactions_because_of_limit_if_ignored() {
+		// given
+		let mut txq = TransactionQueue::with_limits(PrioritizationStrategy::GasPriceOnly, 1, !U256::zero(), !U256::zero());
+		txq.set_locals_policy(LocalsPolicy::Ignore);
+		let tx = new_tx_default();
+		let tx2 = new_tx(default_nonce(), default_gas_price() + 1.into());
+		txq.add(tx.clone(), TransactionOrigin::Local, 0, None, &default_tx_provider()).unwrap();
+
+		// when
+		txq.add(tx2.clone(), TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+
+		// then
+		assert_eq!(txq.top_transactions(), vec![tx2]);
+		assert_eq!(txq.local_transactions()[&tx.hash()], LocalTransactionStatus::Dropped(tx));
+	}
+
 	#[test]
 	fn when_importing_local_should_mark_others_from_the_same_sender_as_local() {
 		// given
@@ -2362,6 +2799,98 @@ pub mod test {
 		assert_eq!(txq.status().future, 1);
 	}
 
+	#[test]
+	fn should_limit_transactions_per_sender() {
+		// given
+		let mut txq = TransactionQueue::default();
+		txq.set_sender_limits(Some(2), Some(1));
+		let keypair = Random.generate().unwrap();
+		let tx = |nonce: u64, gas_price: u64| new_unsigned_tx(default_nonce() + U256::from(nonce), default_gas_val(), U256::from(gas_price)).sign(keypair.secret(), None);
+		let add = |txq: &mut TransactionQueue, tx: SignedTransaction, origin: TransactionOrigin| txq.add(tx, origin, 0, None, &default_tx_provider());
+
+		// when
+		add(&mut txq, tx(0, 1), TransactionOrigin::External).unwrap();
+		add(&mut txq, tx(1, 1), TransactionOrigin::External).unwrap();
+		let pending_err = unwrap_tx_err(add(&mut txq, tx(2, 1), TransactionOrigin::External));
+		add(&mut txq, tx(5, 1), TransactionOrigin::External).unwrap();
+		let future_err = unwrap_tx_err(add(&mut txq, tx(6, 1), TransactionOrigin::External));
+
+		// then
+		assert_eq!(pending_err, TransactionError::SenderLimitReached { limit: 2 });
+		assert_eq!(future_err, TransactionError::SenderFutureLimitReached { limit: 1 });
+		// replacements, other senders and own transactions are not limited
+		add(&mut txq, tx(1, 2), TransactionOrigin::External).unwrap();
+		add(&mut txq, tx(5, 2), TransactionOrigin::External).unwrap();
+		add(&mut txq, new_tx_default(), TransactionOrigin::External).unwrap();
+		add(&mut txq, tx(2, 1), TransactionOrigin::Local).unwrap();
+		add(&mut txq, tx(7, 1), TransactionOrigin::Local).unwrap();
+		assert_eq!(txq.status().pending, 4);
+		assert_eq!(txq.status().future, 2);
+	}
+
+	#[test]
+	fn should_limit_future_queue_separately() {
+		// given
+		let mut txq = TransactionQueue::with_limits(PrioritizationStrategy::GasPriceOnly, 10, !U256::zero(), !U256::zero());
+		txq.set_future_limit(Some(1));
+		let (tx1, tx2) = new_tx_pair_default(4.into(), 1.into());
+		let (tx3, tx4) = new_tx_pair_default(4.into(), 0.into());
+		txq.add(tx1, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		txq.add(tx3, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		txq.add(tx2, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+
+		// when
+		let err = unwrap_tx_err(txq.add(tx4, TransactionOrigin::External, 0, None, &default_tx_provider()));
+		txq.set_limit(20);
+
+		// then
+		assert_eq!(err, TransactionError::FutureLimitReached);
+		assert_eq!(txq.status().future, 1);
+		assert_eq!(txq.future.limit, 1);
+		assert_eq!(txq.current.limit, 20);
+	}
+
+	#[test]
+	fn should_limit_nonce_gap_of_future_transactions() {
+		// given
+		let mut txq = TransactionQueue::default();
+		txq.set_max_nonce_gap(Some(2));
+		let tx1 = new_tx(default_nonce() + 2.into(), default_gas_price());
+		let tx2 = new_tx(default_nonce() + 3.into(), default_gas_price());
+		let tx3 = new_tx(default_nonce() + 5.into(), default_gas_price());
+
+		// when
+		let res1 = txq.add(tx1, TransactionOrigin::External, 0, None, &default_tx_provider());
+		let err2 = unwrap_tx_err(txq.add(tx2, TransactionOrigin::External, 0, None, &default_tx_provider()));
+		let res3 = txq.add(tx3, TransactionOrigin::Local, 0, None, &default_tx_provider());
+
+		// then
+		assert_eq!(res1.unwrap(), TransactionImportResult::Future);
+		assert_eq!(err2, TransactionError::NonceGapTooLarge { limit: 2.into(), got: 3.into() });
+		assert_eq!(res3.unwrap(), TransactionImportResult::Future);
+		assert_eq!(txq.status().future, 2);
+	}
+
+	#[test]
+	fn should_limit_memory_of_future_transactions() {
+		// given
+		let mut txq = TransactionQueue::default();
+		let tx1 = new_tx(default_nonce() + 1.into(), default_gas_price());
+		let tx2 = new_tx(default_nonce() + 1.into(), default_gas_price() + 1.into());
+		let mem_usage = VerifiedTransaction::new(tx1.clone(), TransactionOrigin::External, 0, None).mem_usage();
+		txq.set_future_memory_limit(Some(mem_usage * 3 / 2));
+		let (hash1, hash2) = (tx1.hash(), tx2.hash());
+
+		// when
+		txq.add(tx1, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		txq.add(tx2, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+
+		// then
+		assert_eq!(txq.status().future, 1);
+		assert_eq!(txq.transaction_info(&hash1), Some(TransactionInfo::Dropped(DropReason::LimitReached)));
+		assert!(txq.transaction_info(&hash2).is_some());
+	}
+
 	#[test]
 	fn should_limit_by_gas() {
 		let mut txq = TransactionQueue::with_limits(PrioritizationStrategy::GasPriceOnly, 100, default_gas_val() * U256::from(2), !U256::zero());
@@ -2493,6 +3022,38 @@ pub mod test {
 		assert_eq!(txq.top_transactions()[0].gas_price, U256::from(200));
 	}
 
+	#[test]
+	fn should_require_replacement_price_bump() {
+		// given
+		let mut txq = TransactionQueue::default();
+		txq.set_replacement_price_bump(10);
+		let keypair = Random.generate().unwrap();
+		let tx = new_unsigned_tx(123.into(), default_gas_val(), 100.into()).sign(keypair.secret(), None);
+		let with_price = |gas_price: u64| {
+			let mut tx2 = (**tx).clone();
+			tx2.gas_price = U256::from(gas_price);
+			tx2.sign(keypair.secret(), None)
+		};
+		let (tx2, tx3) = (with_price(105), with_price(110));
+		txq.add(tx, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+
+		// when
+		let err = unwrap_tx_err(txq.add(tx2, TransactionOrigin::External, 0, None, &default_tx_provider()));
+		txq.add(tx3, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+
+		// then
+		assert_eq!(err, TransactionError::TooCheapToReplace);
+		assert_eq!(txq.status().pending, 1);
+		assert_eq!(txq.top_transactions()[0].gas_price, U256::from(110));
+	}
+
+	#[test]
+	fn should_compute_min_replacement_fee() {
+		assert_eq!(super::min_replacement_fee(100.into(), 0), 100.into());
+		assert_eq!(super::min_replacement_fee(100.into(), 12), 112.into());
+		assert_eq!(super::min_replacement_fee(!U256::zero(), 10), !U256::zero());
+	}
+
 	#[test]
 	fn should_replace_same_transaction_when_importing_to_futures() {
 		// given
@@ -2738,6 +3299,51 @@ pub mod test {
 		assert_eq!(txq.top_transactions(), vec![tx1, tx3]);
 	}
 
+	#[test]
+	fn should_evict_future_transactions_after_their_own_lifetime() {
+		// given
+		let mut txq = TransactionQueue::default();
+		txq.set_max_time_in_queue(100, 10);
+		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
+		let (tx3, tx4) = new_tx_pair_default(1.into(), 0.into());
+		txq.add(tx1.clone(), TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		txq.add(tx2.clone(), TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		txq.add(tx4.clone(), TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		assert_eq!(txq.future_transactions().len(), 1);
+
+		// when
+		let evicted = txq.remove_old(&default_account_details_for_addr, 11);
+
+		// then
+		assert_eq!(evicted, vec![tx4.hash()]);
+		assert_eq!(txq.top_transactions(), vec![tx1, tx2]);
+		assert_eq!(txq.future_transactions().len(), 0);
+		// not remembered by default
+		txq.add(tx3, TransactionOrigin::External, 11, None, &default_tx_provider()).unwrap();
+		txq.add(tx4, TransactionOrigin::External, 11, None, &default_tx_provider()).unwrap();
+		assert_eq!(txq.status().pending, 4);
+	}
+
+	#[test]
+	fn should_not_reimport_remembered_evicted_transactions() {
+		// given
+		let mut txq = TransactionQueue::default();
+		txq.set_max_time_in_queue(10, 10);
+		txq.set_remember_evicted(true);
+		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
+		txq.add(tx1.clone(), TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		txq.add(tx2.clone(), TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		assert_eq!(txq.remove_old(&default_account_details_for_addr, 11).len(), 2);
+
+		// when
+		let res = txq.add(tx1.clone(), TransactionOrigin::External, 11, None, &default_tx_provider());
+
+		// then
+		assert_eq!(unwrap_tx_err(res), TransactionError::AlreadyImported);
+		txq.add(tx1, TransactionOrigin::Local, 11, None, &default_tx_provider()).unwrap();
+		assert_eq!(txq.status().pending, 1);
+	}
+
 	#[test]
 	fn should_accept_local_service_transaction() {
 		// given
@@ -2809,4 +3415,85 @@ pub mod test {
 		// then
 		assert_eq!(txq.top_transactions().len(), 1);
 	}
+
+	#[test]
+	fn should_report_position_and_priority_of_queued_transactions() {
+		// given
+		let mut txq = TransactionQueue::default();
+		let (tx1, tx2) = new_tx_pair_default(1.into(), 1.into());
+		let tx3 = new_tx(default_nonce() + 2.into(), default_gas_price());
+		let (hash1, hash2, hash3) = (tx1.hash(), tx2.hash(), tx3.hash());
+		let priority = |nonce_height: u64, gas_price: U256| TransactionPriority {
+			penalties: 0,
+			local: false,
+			nonce_height: nonce_height.into(),
+			gas_price: gas_price,
+			effective_gas_price: gas_price,
+		};
+
+		// when
+		txq.add(tx1, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		txq.add(tx2, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		txq.add(tx3, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+
+		// then
+		assert_eq!(txq.transaction_info(&hash1), Some(TransactionInfo::Pending {
+			position: 0,
+			priority: priority(0, default_gas_price()),
+		}));
+		assert_eq!(txq.transaction_info(&hash2), Some(TransactionInfo::Pending {
+			position: 1,
+			priority: priority(1, default_gas_price() + 1.into()),
+		}));
+		assert_eq!(txq.transaction_info(&hash3), Some(TransactionInfo::Future {
+			position: 0,
+			priority: priority(2, default_gas_price()),
+			missing_nonce: default_nonce(),
+		}));
+		assert_eq!(txq.transaction_info(&H256::default()), None);
+		let status = txq.pool_status();
+		assert_eq!((status.pending, status.future, status.senders), (2, 1, 2));
+	}
+
+	#[test]
+	fn should_remember_reasons_of_dropped_transactions() {
+		// given
+		let mut txq = TransactionQueue::default();
+		let (tx1, tx2) = new_similar_tx_pair();
+		let (hash1, hash2) = (tx1.hash(), tx2.hash());
+		let gas_price = tx2.gas_price;
+		let rejected = new_tx_default().hash();
+
+		// when
+		txq.add(tx1, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		txq.add(tx2, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		txq.mark_rejected(rejected, TransactionError::Old);
+		txq.mark_rejected(hash2, TransactionError::AlreadyImported);
+		txq.remove(&hash2, &|_| default_nonce(), RemovalReason::Canceled);
+
+		// then
+		assert_eq!(txq.transaction_info(&hash1), Some(TransactionInfo::Dropped(DropReason::Replaced(gas_price, hash2))));
+		assert_eq!(txq.transaction_info(&hash2), Some(TransactionInfo::Dropped(DropReason::Canceled)));
+		assert_eq!(txq.transaction_info(&rejected), Some(TransactionInfo::Dropped(DropReason::Rejected(TransactionError::Old))));
+		assert_eq!(txq.pool_status().recently_dropped, 3);
+	}
+
+	#[test]
+	fn should_report_culled_transactions_as_stale_unless_mined() {
+		// given
+		let mut txq = TransactionQueue::default();
+		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
+		let (hash1, hash2) = (tx1.hash(), tx2.hash());
+		let sender = tx1.sender();
+		txq.add(tx1, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+		txq.add(tx2, TransactionOrigin::External, 0, None, &default_tx_provider()).unwrap();
+
+		// when
+		txq.cull(sender, default_nonce() + 2.into());
+		txq.mark_mined(&[hash1]);
+
+		// then
+		assert_eq!(txq.transaction_info(&hash1), Some(TransactionInfo::Dropped(DropReason::Mined)));
+		assert_eq!(txq.transaction_info(&hash2), Some(TransactionInfo::Dropped(DropReason::Stale)));
+	}
 }
@@ -20,7 +20,7 @@ use util::*;
 use io::*;
 use spec::Spec;
 use error::*;
-use client::{Client, ClientConfig, ChainNotify};
+use client::{Client, ClientConfig, ChainNotify, BlockChainClient, DiskSpace};
 use miner::Miner;
 
 use snapshot::ManifestData;
@@ -64,7 +64,7 @@ pub struct ClientService {
 impl ClientService {
 	/// Start the `ClientService`.
 	pub fn start(
-		config: ClientConfig,
+		mut config: ClientConfig,
 		spec: &Spec,
 		client_path: &Path,
 		snapshot_path: &Path,
@@ -95,7 +95,9 @@ impl ClientService {
 		).map_err(::client::Error::Database)?);
 
 
+		config.disk_guard.path = Some(client_path.to_owned());
 		let pruning = config.pruning;
+		let retention = config.snapshot_retention.clone();
 		let client = Client::new(config, &spec, db.clone(), miner, io_service.channel())?;
 
 		let snapshot_params = SnapServiceParams {
@@ -106,6 +108,7 @@ impl ClientService {
 			channel: io_service.channel(),
 			snapshot_root: snapshot_path.into(),
 			db_restore: client.clone(),
+			retention: retention,
 		};
 		let snapshot = Arc::new(SnapshotService::new(snapshot_params)?);
 
@@ -209,6 +212,11 @@ impl IoHandler<ClientIoMessage> for ClientIoHandler {
 			ClientIoMessage::FeedStateChunk(ref hash, ref chunk) => self.snapshot.feed_state_chunk(*hash, chunk),
 			ClientIoMessage::FeedBlockChunk(ref hash, ref chunk) => self.snapshot.feed_block_chunk(*hash, chunk),
 			ClientIoMessage::TakeSnapshot(num) => {
+				if self.client.disk_space() >= DiskSpace::NoSnapshots {
+					info!(target: "snapshot", "Not taking snapshot at block #{}: low disk space", num);
+					return;
+				}
+
 				let client = self.client.clone();
 				let snapshot = self.snapshot.clone();
 
@@ -59,6 +59,12 @@ pub enum Error {
 	ChunkTooSmall,
 	/// Snapshots not supported by the consensus engine.
 	SnapshotsUnsupported,
+	/// State of the base snapshot at the given block has been pruned.
+	BaseStatePruned(u64),
+	/// Delta snapshot doesn't follow the given manifest. (expected, found)
+	WrongDeltaBase(H256, Option<H256>),
+	/// Chunk data doesn't match its hash. (expected, found)
+	WrongChunkHash(H256, H256),
 }
 
 impl fmt::Display for Error {
@@ -82,6 +88,10 @@ impl fmt::Display for Error {
 			Error::VersionNotSupported(ref ver) => write!(f, "Snapshot version {} is not supprted.", ver),
 			Error::ChunkTooSmall => write!(f, "Chunk size is too small."),
 			Error::SnapshotsUnsupported => write!(f, "Snapshots unsupported by consensus engine."),
+			Error::BaseStatePruned(ref num) => write!(f, "State of the base snapshot at block {} is no longer available. \
+				Take a full snapshot or increase --pruning-history.", num),
+			Error::WrongDeltaBase(ref expected, ref found) => write!(f, "Delta snapshot has wrong base. Expected {:?}, got {:?}", expected, found),
+			Error::WrongChunkHash(ref expected, ref found) => write!(f, "Mismatched chunk hash. Expected {:?}, got {:?}", expected, found),
 		}
 	}
 }
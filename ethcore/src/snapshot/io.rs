@@ -120,7 +120,7 @@ impl SnapshotWriter for PackedWriter {
 	fn finish(mut self, manifest: ManifestData) -> io::Result<()> {
 		// we ignore the hashes fields of the manifest under the assumption that
 		// they are consistent with ours.
-		let mut stream = RlpStream::new_list(6);
+		let mut stream = RlpStream::new_list(if manifest.base.is_some() { 7 } else { 6 });
 		stream
 			.append(&SNAPSHOT_VERSION)
 			.append_list(&self.state_hashes)
@@ -128,6 +128,9 @@ impl SnapshotWriter for PackedWriter {
 			.append(&manifest.state_root)
 			.append(&manifest.block_number)
 			.append(&manifest.block_hash);
+		if let Some(ref base) = manifest.base {
+			stream.append(base);
+		}
 
 		let manifest_rlp = stream.out();
 
@@ -278,6 +281,10 @@ impl PackedReader {
 			state_root: rlp.val_at(2 + start)?,
 			block_number: rlp.val_at(3 + start)?,
 			block_hash: rlp.val_at(4 + start)?,
+			base: match rlp.item_count()? > 5 + start {
+				true => Some(rlp.val_at(5 + start)?),
+				false => None,
+			},
 		};
 
 		Ok(Some(PackedReader {
@@ -392,6 +399,7 @@ mod tests {
 			state_root: b"notarealroot".sha3(),
 			block_number: 12345678987654321,
 			block_hash: b"notarealblock".sha3(),
+			base: None,
 		};
 
 		writer.finish(manifest.clone()).unwrap();
@@ -431,6 +439,7 @@ mod tests {
 			state_root: b"notarealroot".sha3(),
 			block_number: 12345678987654321,
 			block_hash: b"notarealblock".sha3(),
+			base: None,
 		};
 
 		writer.finish(manifest.clone()).unwrap();
@@ -19,6 +19,7 @@
 //! Documentation of the format can be found at
 //! https://github.com/paritytech/parity/wiki/Warp-Sync-Snapshot-Format
 
+use std::cmp;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
@@ -34,7 +35,7 @@ use util::Mutex;
 use util::hash::{H256};
 use util::journaldb::{self, Algorithm, JournalDB};
 use util::kvdb::Database;
-use util::trie::{TrieDB, TrieDBMut, Trie, TrieMut};
+use util::trie::{TrieDB, TrieDBMut, Trie, TrieMut, TrieIterator, Result as TrieResult};
 use util::sha3::SHA3_NULL_RLP;
 use rlp::{RlpStream, UntrustedRlp};
 use bloom_journal::Bloom;
@@ -50,9 +51,11 @@ use rand::{Rng, OsRng};
 pub use self::error::Error;
 
 pub use self::consensus::*;
-pub use self::service::{Service, DatabaseRestore};
+pub use self::service::{Service, DatabaseRestore, RetentionPolicy};
 pub use self::traits::SnapshotService;
-pub use self::watcher::Watcher;
+pub use self::watcher::{Watcher, Schedule, Period, TimeWindow};
+pub use self::throttle::{ThrottleConfig, ThrottledWriter};
+pub use self::verify::{verify_chunks, verify_restoration};
 pub use types::snapshot_manifest::ManifestData;
 pub use types::restoration_status::RestorationStatus;
 pub use types::basic_account::BasicAccount;
@@ -64,6 +67,8 @@ mod account;
 mod block;
 mod consensus;
 mod error;
+mod throttle;
+mod verify;
 mod watcher;
 
 #[cfg(test)]
@@ -83,6 +88,9 @@ mod traits {
 // Try to have chunks be around 4MB (before compression)
 const PREFERRED_CHUNK_SIZE: usize = 4 * 1024 * 1024;
 
+// State is split between threads by the first byte of account hashes.
+const MAX_STATE_PARTS: usize = 256;
+
 /// A progress indicator for snapshots.
 #[derive(Debug, Default)]
 pub struct Progress {
@@ -118,11 +126,15 @@ impl Progress {
 
 }
 /// Take a snapshot using the given blockchain, starting block hash, and database, writing into the given writer.
+/// If `base` is given, only the state changed since the base snapshot is written.
+/// State of full snapshots is chunked by `threads` worker threads (at least one).
 pub fn take_snapshot<W: SnapshotWriter + Send>(
 	engine: &Engine,
 	chain: &BlockChain,
 	block_at: H256,
 	state_db: &HashDB,
+	base: Option<&ManifestData>,
+	threads: usize,
 	writer: W,
 	p: &Progress
 ) -> Result<(), Error> {
@@ -131,14 +143,35 @@ pub fn take_snapshot<W: SnapshotWriter + Send>(
 	let state_root = start_header.state_root();
 	let number = start_header.number();
 
-	info!("Taking snapshot starting at block {}", number);
+	if let Some(base) = base {
+		if !state_db.contains(&base.state_root) {
+			return Err(Error::BaseStatePruned(base.block_number));
+		}
+		info!("Taking delta snapshot starting at block {} from block {}", number, base.block_number);
+	} else {
+		info!("Taking snapshot starting at block {}", number);
+	}
 
 	let writer = Mutex::new(writer);
 	let chunker = engine.snapshot_components().ok_or(Error::SnapshotsUnsupported)?;
 	let (state_hashes, block_hashes) = scope(|scope| {
 		let writer = &writer;
 		let block_guard = scope.spawn(move || chunk_secondary(chunker, chain, block_at, writer, p));
-		let state_res = chunk_state(state_db, state_root, writer, p);
+		let state_res = match base {
+			Some(base) => chunk_state_delta(state_db, &base.state_root, state_root, writer, p),
+			None => {
+				let parts = cmp::min(cmp::max(threads, 1), MAX_STATE_PARTS);
+				let part_guards: Vec<_> = (0..parts)
+					.map(|part| scope.spawn(move || chunk_state_part(state_db, state_root, part, parts, writer, p)))
+					.collect();
+
+				// chunks of the parts are listed in order of account hashes.
+				part_guards.into_iter()
+					.map(|guard| guard.join())
+					.collect::<Result<Vec<_>, _>>()
+					.map(|hashes| hashes.into_iter().flat_map(|h| h).collect())
+			},
+		};
 
 		state_res.and_then(|state_hashes| {
 			block_guard.join().map(|block_hashes| (state_hashes, block_hashes))
@@ -154,6 +187,7 @@ pub fn take_snapshot<W: SnapshotWriter + Send>(
 		state_root: *state_root,
 		block_number: number,
 		block_hash: block_at,
+		base: base.map(|base| base.clone().into_rlp().sha3()),
 	};
 
 	writer.into_inner().finish(manifest_data)?;
@@ -252,6 +286,33 @@ impl<'a> StateChunker<'a> {
 	fn chunk_size(&self) -> usize {
 		self.cur_size
 	}
+
+	// Push all fat RLPs of the account, splitting them between chunks.
+	fn push_account(&mut self, db: &HashDB, account_key_hash: H256, account_data: &[u8], used_code: &mut HashSet<H256>) -> Result<(), Error> {
+		let account = ::rlp::decode(account_data);
+		let account_db = AccountDB::from_hash(db, account_key_hash);
+
+		let fat_rlps = account::to_fat_rlps(&account_key_hash, &account, &account_db, used_code, PREFERRED_CHUNK_SIZE - self.chunk_size(), PREFERRED_CHUNK_SIZE)?;
+		for (i, fat_rlp) in fat_rlps.into_iter().enumerate() {
+			if i > 0 {
+				self.write_chunk()?;
+			}
+			self.push(fat_rlp)?;
+		}
+		Ok(())
+	}
+
+	// Push a removed account, encoded with an empty body.
+	fn push_removed(&mut self, account_key_hash: H256) -> Result<(), Error> {
+		let mut stream = RlpStream::new_list(2);
+		stream.append(&account_key_hash).begin_list(0);
+		let data = stream.out();
+
+		if self.chunk_size() + data.len() > PREFERRED_CHUNK_SIZE {
+			self.write_chunk()?;
+		}
+		self.push(data)
+	}
 }
 
 /// Walk the given state database starting from the given root,
@@ -260,7 +321,17 @@ impl<'a> StateChunker<'a> {
 /// Returns a list of hashes of chunks created, or any error it may
 /// have encountered.
 pub fn chunk_state<'a>(db: &HashDB, root: &H256, writer: &Mutex<SnapshotWriter + 'a>, progress: &'a Progress) -> Result<Vec<H256>, Error> {
+	chunk_state_part(db, root, 0, 1, writer, progress)
+}
+
+/// Like `chunk_state`, but only walks the accounts of the `part`-th of `parts`
+/// ranges of account hashes, so that the parts can be chunked in parallel.
+///
+/// Code is inlined once per part. Chunks of all parts concatenated in order
+/// cover the whole state.
+pub fn chunk_state_part<'a>(db: &HashDB, root: &H256, part: usize, parts: usize, writer: &Mutex<SnapshotWriter + 'a>, progress: &'a Progress) -> Result<Vec<H256>, Error> {
 	let account_trie = TrieDB::new(db, &root)?;
+	let (from, to) = (part * MAX_STATE_PARTS / parts, (part + 1) * MAX_STATE_PARTS / parts);
 
 	let mut chunker = StateChunker {
 		hashes: Vec::new(),
@@ -273,23 +344,79 @@ pub fn chunk_state<'a>(db: &HashDB, root: &H256, writer: &Mutex<SnapshotWriter +
 
 	let mut used_code = HashSet::new();
 
+	let mut iter = account_trie.iter()?;
+	if from > 0 {
+		iter.seek(&[from as u8])?;
+	}
+
 	// account_key here is the address' hash.
-	for item in account_trie.iter()? {
+	for item in iter {
 		let (account_key, account_data) = item?;
-		let account = ::rlp::decode(&*account_data);
-		let account_key_hash = H256::from_slice(&account_key);
+		if account_key[0] as usize >= to {
+			break;
+		}
+		chunker.push_account(db, H256::from_slice(&account_key), &account_data, &mut used_code)?;
+	}
 
-		let account_db = AccountDB::from_hash(db, account_key_hash);
+	if chunker.cur_size != 0 {
+		chunker.write_chunk()?;
+	}
 
-		let fat_rlps = account::to_fat_rlps(&account_key_hash, &account, &account_db, &mut used_code, PREFERRED_CHUNK_SIZE - chunker.chunk_size(), PREFERRED_CHUNK_SIZE)?;
-		for (i, fat_rlp) in fat_rlps.into_iter().enumerate() {
-			if i > 0 {
-				chunker.write_chunk()?;
+	Ok(chunker.hashes)
+}
+
+/// Walk the state at `root` along with the state at `base_root`, creating chunks
+/// of accounts which were added, changed or removed since the base.
+///
+/// Both tries are iterated in the order of account hashes, so the chunks are ordered
+/// the same way as in a full snapshot.
+pub fn chunk_state_delta<'a>(db: &HashDB, base_root: &H256, root: &H256, writer: &Mutex<SnapshotWriter + 'a>, progress: &'a Progress) -> Result<Vec<H256>, Error> {
+	let base_trie = TrieDB::new(db, base_root)?;
+	let account_trie = TrieDB::new(db, root)?;
+
+	let mut chunker = StateChunker {
+		hashes: Vec::new(),
+		rlps: Vec::new(),
+		cur_size: 0,
+		snappy_buffer: vec![0; snappy::max_compressed_len(PREFERRED_CHUNK_SIZE)],
+		writer: writer,
+		progress: progress,
+	};
+
+	// code is inlined once per delta, so that it doesn't depend on accounts of the base.
+	let mut used_code = HashSet::new();
+
+	let mut base_iter = base_trie.iter()?;
+	let mut base_next = next_item(&mut base_iter)?;
+
+	for item in account_trie.iter()? {
+		let (account_key, account_data) = item?;
+
+		// accounts of the base before this one were removed.
+		while base_next.as_ref().map_or(false, |&(ref key, _)| *key < account_key) {
+			if let Some((base_key, _)) = base_next.take() {
+				chunker.push_removed(H256::from_slice(&base_key))?;
 			}
-			chunker.push(fat_rlp)?;
+			base_next = next_item(&mut base_iter)?;
+		}
+
+		let in_base = base_next.as_ref().map_or(false, |&(ref key, _)| *key == account_key);
+		let unchanged = in_base && base_next.as_ref().map_or(false, |&(_, ref data)| &**data == &*account_data);
+		if in_base {
+			base_next = next_item(&mut base_iter)?;
+		}
+
+		if !unchanged {
+			chunker.push_account(db, H256::from_slice(&account_key), &account_data, &mut used_code)?;
 		}
 	}
 
+	// remaining accounts of the base were removed.
+	while let Some((base_key, _)) = base_next.take() {
+		chunker.push_removed(H256::from_slice(&base_key))?;
+		base_next = next_item(&mut base_iter)?;
+	}
+
 	if chunker.cur_size != 0 {
 		chunker.write_chunk()?;
 	}
@@ -297,6 +424,14 @@ pub fn chunk_state<'a>(db: &HashDB, root: &H256, writer: &Mutex<SnapshotWriter +
 	Ok(chunker.hashes)
 }
 
+// get the next item of a trie iterator.
+fn next_item<I>(iter: &mut I) -> Result<Option<(Bytes, DBValue)>, Error> where I: Iterator<Item = TrieResult<(Bytes, DBValue)>> {
+	match iter.next() {
+		Some(item) => Ok(Some(item?)),
+		None => Ok(None),
+	}
+}
+
 /// Used to rebuild the state trie piece by piece.
 pub struct StateRebuilder {
 	db: Box<JournalDB>,
@@ -365,6 +500,12 @@ impl StateRebuilder {
 			for (hash, thin_rlp) in pairs {
 				if !flag.load(Ordering::SeqCst) { return Err(Error::RestorationAborted.into()) }
 
+				// removed by a delta snapshot.
+				if thin_rlp.is_empty() {
+					account_trie.remove(&hash)?;
+					continue;
+				}
+
 				if &thin_rlp[..] != &empty_rlp[..] {
 					self.bloom.set(&*hash);
 				}
@@ -397,6 +538,12 @@ impl StateRebuilder {
 
 	/// Get the state root of the rebuilder.
 	pub fn state_root(&self) -> H256 { self.state_root }
+
+	/// Start feeding chunks of a delta snapshot. Accounts in the delta replace
+	/// the ones fed before, including their storage.
+	pub fn begin_delta(&mut self) {
+		self.known_storage_roots.clear();
+	}
 }
 
 #[derive(Default)]
@@ -423,6 +570,12 @@ fn rebuild_accounts(
 		let hash: H256 = account_rlp.val_at(0)?;
 		let fat_rlp = account_rlp.at(1)?;
 
+		// account removed by a delta snapshot.
+		if fat_rlp.is_empty() {
+			*out = (hash, Vec::new());
+			continue;
+		}
+
 		let thin_rlp = {
 
 			// fill out the storage trie and code while decoding.
@@ -462,10 +615,14 @@ fn rebuild_accounts(
 		*out = (hash, thin_rlp);
 	}
 	if let Some(&(ref hash, ref rlp)) = out_chunk.iter().last() {
-		known_storage_roots.insert(*hash, ::rlp::decode::<BasicAccount>(rlp).storage_root);
+		if !rlp.is_empty() {
+			known_storage_roots.insert(*hash, ::rlp::decode::<BasicAccount>(rlp).storage_root);
+		}
 	}
 	if let Some(&(ref hash, ref rlp)) = out_chunk.iter().next() {
-		known_storage_roots.insert(*hash, ::rlp::decode::<BasicAccount>(rlp).storage_root);
+		if !rlp.is_empty() {
+			known_storage_roots.insert(*hash, ::rlp::decode::<BasicAccount>(rlp).storage_root);
+		}
 	}
 	Ok(status)
 }
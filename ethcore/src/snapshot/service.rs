@@ -16,10 +16,10 @@
 
 //! Snapshot network service implementation.
 
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::io::ErrorKind;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
@@ -35,7 +35,7 @@ use service::ClientIoMessage;
 
 use io::IoChannel;
 
-use util::{Bytes, H256, Mutex, RwLock, RwLockReadGuard, UtilError};
+use util::{Bytes, H256, Hashable, Mutex, RwLock, RwLockReadGuard, UtilError};
 use util::journaldb::Algorithm;
 use util::kvdb::{Database, DatabaseConfig};
 use util::snappy;
@@ -67,6 +67,8 @@ pub trait DatabaseRestore: Send + Sync {
 struct Restoration {
 	manifest: ManifestData,
 	state_chunks_left: HashSet<H256>,
+	// state chunks of delta snapshots, fed in order after the current ones.
+	delta_chunks: VecDeque<HashSet<H256>>,
 	block_chunks_left: HashSet<H256>,
 	state: StateRebuilder,
 	secondary: Box<Rebuilder>,
@@ -79,6 +81,7 @@ struct Restoration {
 
 struct RestorationParams<'a> {
 	manifest: ManifestData, // manifest to base restoration on.
+	deltas: Vec<ManifestData>, // delta snapshots applied on top of the manifest, in order.
 	pruning: Algorithm, // pruning algorithm for the database.
 	db_path: PathBuf, // database path
 	db_config: &'a DatabaseConfig, // configuration for the database.
@@ -90,10 +93,12 @@ struct RestorationParams<'a> {
 
 impl Restoration {
 	// make a new restoration using the given parameters.
-	fn new(params: RestorationParams) -> Result<Self, Error> {
-		let manifest = params.manifest;
+	fn new(mut params: RestorationParams) -> Result<Self, Error> {
+		let state_chunks = params.manifest.state_hashes.iter().cloned().collect();
+		let delta_chunks = params.deltas.iter().map(|delta| delta.state_hashes.iter().cloned().collect()).collect();
 
-		let state_chunks = manifest.state_hashes.iter().cloned().collect();
+		// blocks and the final state are those of the last delta.
+		let manifest = params.deltas.pop().unwrap_or(params.manifest);
 		let block_chunks = manifest.block_hashes.iter().cloned().collect();
 
 		let raw_db = Arc::new(Database::open(params.db_config, &*params.db_path.to_string_lossy())
@@ -109,6 +114,7 @@ impl Restoration {
 		Ok(Restoration {
 			manifest: manifest,
 			state_chunks_left: state_chunks,
+			delta_chunks: delta_chunks,
 			block_chunks_left: block_chunks,
 			state: StateRebuilder::new(raw_db.clone(), params.pruning),
 			secondary: secondary,
@@ -130,6 +136,17 @@ impl Restoration {
 			if let Some(ref mut writer) = self.writer.as_mut() {
 				writer.write_state_chunk(hash, chunk)?;
 			}
+
+			// move on to the next delta.
+			while self.state_chunks_left.is_empty() {
+				match self.delta_chunks.pop_front() {
+					Some(chunks) => {
+						self.state_chunks_left = chunks;
+						self.state.begin_delta();
+					},
+					None => break,
+				}
+			}
 		}
 
 		Ok(())
@@ -178,7 +195,7 @@ impl Restoration {
 
 	// is everything done?
 	fn is_done(&self) -> bool {
-		self.block_chunks_left.is_empty() && self.state_chunks_left.is_empty()
+		self.block_chunks_left.is_empty() && self.state_chunks_left.is_empty() && self.delta_chunks.is_empty()
 	}
 }
 
@@ -202,6 +219,19 @@ pub struct ServiceParams {
 	pub snapshot_root: PathBuf,
 	/// A handle for database restoration.
 	pub db_restore: Arc<DatabaseRestore>,
+	/// Which of the previous snapshots to keep on disk.
+	pub retention: RetentionPolicy,
+}
+
+/// Policy of keeping snapshots replaced by newer ones.
+/// Previous snapshots are stored under "<snapshot root>/archive/<block number>".
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct RetentionPolicy {
+	/// Number of previous snapshots to keep.
+	pub keep_last: usize,
+	/// Maximal size in bytes of all snapshots on disk. The oldest previous snapshots
+	/// are removed first, the current one is always kept.
+	pub max_size: Option<u64>,
 }
 
 /// `SnapshotService` implementation.
@@ -222,6 +252,7 @@ pub struct Service {
 	progress: super::Progress,
 	taking_snapshot: AtomicBool,
 	restoring_snapshot: AtomicBool,
+	retention: RetentionPolicy,
 }
 
 impl Service {
@@ -243,6 +274,7 @@ impl Service {
 			progress: Default::default(),
 			taking_snapshot: AtomicBool::new(false),
 			restoring_snapshot: AtomicBool::new(false),
+			retention: params.retention,
 		};
 
 		// create the root snapshot dir if it doesn't exist.
@@ -279,6 +311,21 @@ impl Service {
 		dir
 	}
 
+	// get the dir of previous snapshots.
+	fn archive_dir(&self) -> PathBuf {
+		let mut dir = self.snapshot_root.clone();
+		dir.push("archive");
+		dir
+	}
+
+	// remove previous snapshots exceeding the retention policy.
+	fn collect_garbage(&self) {
+		let current_size = dir_size(&self.snapshot_dir()).unwrap_or(0);
+		if let Err(e) = remove_archived(&self.archive_dir(), current_size, &self.retention) {
+			warn!(target: "snapshot", "Error removing previous snapshots: {}", e);
+		}
+	}
+
 	// get the temporary snapshot dir.
 	fn temp_snapshot_dir(&self) -> PathBuf {
 		let mut dir = self.snapshot_root.clone();
@@ -371,10 +418,18 @@ impl Service {
 		let mut reader = self.reader.write();
 
 		// destroy the old snapshot reader.
-		*reader = None;
+		let previous = reader.take().map(|r| r.manifest().block_number);
 
 		if snapshot_dir.exists() {
-			fs::remove_dir_all(&snapshot_dir)?;
+			match previous {
+				Some(number) if self.retention.keep_last > 0 => {
+					let archived = self.archive_dir().join(number.to_string());
+					let _ = fs::remove_dir_all(&archived);
+					fs::create_dir_all(self.archive_dir())?;
+					fs::rename(&snapshot_dir, &archived)?;
+				},
+				_ => fs::remove_dir_all(&snapshot_dir)?,
+			}
 		}
 
 		fs::rename(temp_dir, &snapshot_dir)?;
@@ -382,12 +437,30 @@ impl Service {
 		*reader = Some(LooseReader::new(snapshot_dir)?);
 
 		guard.disarm();
+		drop(reader);
+
+		self.collect_garbage();
 		Ok(())
 	}
 
 	/// Initialize the restoration synchronously.
 	/// The recover flag indicates whether to recover the restored snapshot.
 	pub fn init_restore(&self, manifest: ManifestData, recover: bool) -> Result<(), Error> {
+		self.init_restore_with_deltas(manifest, Vec::new(), recover)
+	}
+
+	/// Initialize the restoration of a snapshot and delta snapshots following it synchronously.
+	/// State chunks must be fed in order of the snapshots, starting with the full one.
+	/// Only a full snapshot can be recovered.
+	pub fn init_restore_with_deltas(&self, manifest: ManifestData, deltas: Vec<ManifestData>, recover: bool) -> Result<(), Error> {
+		let mut base = manifest.clone().into_rlp().sha3();
+		for delta in &deltas {
+			if delta.base != Some(base) {
+				return Err(::snapshot::Error::WrongDeltaBase(base, delta.base).into());
+			}
+			base = delta.clone().into_rlp().sha3();
+		}
+
 		let rest_dir = self.restoration_dir();
 
 		let mut res = self.restoration.lock();
@@ -409,13 +482,17 @@ impl Service {
 		fs::create_dir_all(&rest_dir)?;
 
 		// make new restoration.
-		let writer = match recover {
+		let writer = match recover && deltas.is_empty() {
 			true => Some(LooseWriter::new(self.temp_recovery_dir())?),
 			false => None
 		};
 
+		let state_chunks = manifest.state_hashes.len() + deltas.iter().map(|delta| delta.state_hashes.len()).sum::<usize>();
+		let block_chunks = deltas.last().unwrap_or(&manifest).block_hashes.len();
+
 		let params = RestorationParams {
 			manifest: manifest,
+			deltas: deltas,
 			pruning: self.pruning,
 			db_path: self.restoration_db(),
 			db_config: &self.db_config,
@@ -425,9 +502,6 @@ impl Service {
 			engine: &*self.engine,
 		};
 
-		let state_chunks = params.manifest.state_hashes.len();
-		let block_chunks = params.manifest.block_hashes.len();
-
 		*res = Some(Restoration::new(params)?);
 
 		*self.status.lock() = RestorationStatus::Ongoing {
@@ -599,6 +673,42 @@ impl Drop for Service {
 	}
 }
 
+// total size of files in the directory.
+fn dir_size(path: &Path) -> ::std::io::Result<u64> {
+	let mut size = 0;
+	for entry in fs::read_dir(path)? {
+		size += entry?.metadata()?.len();
+	}
+	Ok(size)
+}
+
+// remove the oldest previous snapshots which are over the retention limits.
+fn remove_archived(archive: &Path, current_size: u64, policy: &RetentionPolicy) -> ::std::io::Result<()> {
+	if !archive.exists() {
+		return Ok(());
+	}
+
+	let mut archived = Vec::new();
+	for entry in fs::read_dir(archive)? {
+		let entry = entry?;
+		if let Some(number) = entry.file_name().to_str().and_then(|name| name.parse::<u64>().ok()) {
+			archived.push((number, entry.path()));
+		}
+	}
+
+	// newest first, once over the budget all older ones are removed too.
+	archived.sort_by(|a, b| b.0.cmp(&a.0));
+	let mut total = current_size;
+	for (index, (number, path)) in archived.into_iter().enumerate() {
+		total += dir_size(&path)?;
+		if index >= policy.keep_last || policy.max_size.map_or(false, |max| total > max) {
+			info!(target: "snapshot", "Removing previous snapshot at #{}", number);
+			fs::remove_dir_all(&path)?;
+		}
+	}
+	Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 	use std::sync::Arc;
@@ -637,6 +747,7 @@ mod tests {
 			channel: service.channel(),
 			snapshot_root: dir,
 			db_restore: Arc::new(NoopDBRestore),
+			retention: Default::default(),
 		};
 
 		let service = Service::new(snapshot_params).unwrap();
@@ -652,6 +763,7 @@ mod tests {
 			state_root: Default::default(),
 			block_number: 0,
 			block_hash: Default::default(),
+			base: None,
 		};
 
 		service.begin_restore(manifest);
@@ -659,4 +771,28 @@ mod tests {
 		service.restore_state_chunk(Default::default(), vec![]);
 		service.restore_block_chunk(Default::default(), vec![]);
 	}
+
+	#[test]
+	fn removes_previous_snapshots() {
+		use std::fs::{self, File};
+		use std::io::Write;
+
+		let dir = RandomTempPath::create_dir();
+		let archive = dir.as_path().join("archive");
+		for number in &[10, 20, 30] {
+			let snapshot = archive.join(number.to_string());
+			fs::create_dir_all(&snapshot).unwrap();
+			File::create(snapshot.join("MANIFEST")).unwrap().write_all(&[0u8; 100]).unwrap();
+		}
+
+		let keep_two = RetentionPolicy { keep_last: 2, max_size: None };
+		remove_archived(&archive, 100, &keep_two).unwrap();
+		assert!(!archive.join("10").exists());
+		assert!(archive.join("20").exists());
+
+		let budget = RetentionPolicy { keep_last: 2, max_size: Some(250) };
+		remove_archived(&archive, 100, &budget).unwrap();
+		assert!(archive.join("30").exists());
+		assert!(!archive.join("20").exists());
+	}
 }
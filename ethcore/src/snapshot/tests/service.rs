@@ -73,6 +73,7 @@ fn restored_is_equivalent() {
 		channel: IoChannel::disconnected(),
 		snapshot_root: path,
 		db_restore: client2.clone(),
+		retention: Default::default(),
 	};
 
 	let service = Service::new(service_params).unwrap();
@@ -116,6 +117,7 @@ fn guards_delete_folders() {
 		channel: IoChannel::disconnected(),
 		snapshot_root: path.clone(),
 		db_restore: Arc::new(NoopDBRestore),
+		retention: Default::default(),
 	};
 
 	let service = Service::new(service_params).unwrap();
@@ -127,6 +129,7 @@ fn guards_delete_folders() {
 		block_hashes: vec![],
 		block_number: 0,
 		block_hash: Default::default(),
+		base: None,
 		state_root: Default::default(),
 	};
 
@@ -18,7 +18,7 @@
 
 use basic_account::BasicAccount;
 use snapshot::account;
-use snapshot::{chunk_state, Error as SnapshotError, Progress, StateRebuilder};
+use snapshot::{chunk_state, chunk_state_part, chunk_state_delta, Error as SnapshotError, Progress, StateRebuilder};
 use snapshot::io::{PackedReader, PackedWriter, SnapshotReader, SnapshotWriter};
 use super::helpers::{compare_dbs, StateProducer};
 
@@ -64,6 +64,7 @@ fn snap_and_restore() {
 		state_root: state_root,
 		block_number: 1000,
 		block_hash: H256::default(),
+		base: None,
 	}).unwrap();
 
 	let mut db_path = snap_dir.as_path().to_owned();
@@ -94,6 +95,118 @@ fn snap_and_restore() {
 	compare_dbs(&old_db, new_db.as_hashdb());
 }
 
+#[test]
+fn snap_and_restore_in_parts() {
+	let mut producer = StateProducer::new();
+	let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+	let mut old_db = MemoryDB::new();
+	let db_cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+
+	for _ in 0..150 {
+		producer.tick(&mut rng, &mut old_db);
+	}
+
+	let snap_dir = RandomTempPath::create_dir();
+	let state_root = producer.state_root();
+	let writer = Mutex::new(PackedWriter::new(&snap_dir.as_path().join("SNAP")).unwrap());
+	let progress = Progress::default();
+
+	let mut state_hashes = Vec::new();
+	for part in 0..3 {
+		state_hashes.extend(chunk_state_part(&old_db, &state_root, part, 3, &writer, &progress).unwrap());
+	}
+	assert!(progress.accounts() > 0);
+
+	writer.into_inner().finish(::snapshot::ManifestData {
+		version: 2,
+		state_hashes: state_hashes,
+		block_hashes: Vec::new(),
+		state_root: state_root,
+		block_number: 1000,
+		block_hash: H256::default(),
+		base: None,
+	}).unwrap();
+
+	let db = Arc::new(Database::open(&db_cfg, &snap_dir.as_path().join("db").to_string_lossy()).unwrap());
+	let mut rebuilder = StateRebuilder::new(db.clone(), Algorithm::OverlayRecent);
+	let reader = PackedReader::new(&snap_dir.as_path().join("SNAP")).unwrap().unwrap();
+	let flag = AtomicBool::new(true);
+
+	for chunk_hash in &reader.manifest().state_hashes {
+		let raw = reader.chunk(*chunk_hash).unwrap();
+		rebuilder.feed(&::util::snappy::decompress(&raw).unwrap(), &flag).unwrap();
+	}
+
+	assert_eq!(rebuilder.state_root(), state_root);
+	rebuilder.finalize(1000, H256::default()).unwrap();
+
+	let new_db = journaldb::new(db, Algorithm::OverlayRecent, ::db::COL_STATE);
+	compare_dbs(&old_db, new_db.as_hashdb());
+}
+
+#[test]
+fn delta_snap_and_restore() {
+	use util::trie::{TrieDB, TrieDBMut, Trie, TrieMut};
+
+	// keeps state chunks in order.
+	struct Chunks(Vec<Vec<u8>>);
+	impl SnapshotWriter for Chunks {
+		fn write_state_chunk(&mut self, _: H256, chunk: &[u8]) -> ::std::io::Result<()> {
+			self.0.push(chunk.to_vec());
+			Ok(())
+		}
+		fn write_block_chunk(&mut self, _: H256, _: &[u8]) -> ::std::io::Result<()> { Ok(()) }
+		fn finish(self, _: ::snapshot::ManifestData) -> ::std::io::Result<()> { Ok(()) }
+	}
+
+	let mut producer = StateProducer::new();
+	let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+	let mut old_db = MemoryDB::new();
+	let db_cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+
+	for _ in 0..150 {
+		producer.tick(&mut rng, &mut old_db);
+	}
+	let base_root = producer.state_root();
+
+	for _ in 0..10 {
+		producer.tick(&mut rng, &mut old_db);
+	}
+
+	// remove the first account.
+	let mut state_root = producer.state_root();
+	{
+		let first = TrieDB::new(&old_db, &state_root).unwrap().iter().unwrap().next().unwrap().unwrap().0;
+		let mut trie = TrieDBMut::from_existing(&mut old_db, &mut state_root).unwrap();
+		trie.remove(&first).unwrap();
+	}
+
+	let base_writer = Mutex::new(Chunks(Vec::new()));
+	chunk_state(&old_db, &base_root, &base_writer, &Progress::default()).unwrap();
+	let delta_writer = Mutex::new(Chunks(Vec::new()));
+	let delta_hashes = chunk_state_delta(&old_db, &base_root, &state_root, &delta_writer, &Progress::default()).unwrap();
+	assert!(!delta_hashes.is_empty());
+
+	let snap_dir = RandomTempPath::create_dir();
+	let mut db_path = snap_dir.as_path().to_owned();
+	db_path.push("db");
+	let new_db = Arc::new(Database::open(&db_cfg, &db_path.to_string_lossy()).unwrap());
+	let mut rebuilder = StateRebuilder::new(new_db.clone(), Algorithm::OverlayRecent);
+	let flag = AtomicBool::new(true);
+
+	for chunk in base_writer.into_inner().0 {
+		rebuilder.feed(&::util::snappy::decompress(&chunk).unwrap(), &flag).unwrap();
+	}
+	assert_eq!(rebuilder.state_root(), base_root);
+
+	rebuilder.begin_delta();
+	for chunk in delta_writer.into_inner().0 {
+		rebuilder.feed(&::util::snappy::decompress(&chunk).unwrap(), &flag).unwrap();
+	}
+	assert_eq!(rebuilder.state_root(), state_root);
+	rebuilder.finalize(1000, H256::default()).unwrap();
+}
+
 #[test]
 fn get_code_from_prev_chunk() {
 	use std::collections::HashSet;
@@ -176,6 +289,7 @@ fn checks_flag() {
 		state_root: state_root,
 		block_number: 0,
 		block_hash: H256::default(),
+		base: None,
 	}).unwrap();
 
 	let mut db_path = snap_dir.as_path().to_owned();
@@ -0,0 +1,140 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Throttling of snapshot creation, so that it doesn't starve block import and RPC.
+
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use util::hash::H256;
+
+use super::ManifestData;
+use super::io::SnapshotWriter;
+
+/// How often to check whether the import queue has drained.
+const BUSY_POLL_MS: u64 = 100;
+
+/// Limits of the snapshot creation rate.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ThrottleConfig {
+	/// Maximal number of chunks written per second.
+	pub max_chunks_per_sec: Option<u32>,
+	/// Pause while there are blocks waiting for import.
+	pub yield_to_import: bool,
+}
+
+/// Writer delaying chunks according to the throttle configuration.
+///
+/// Chunkers block while writing, so delaying writes pauses all of them.
+pub struct ThrottledWriter<'a, W> {
+	inner: W,
+	interval: Option<Duration>,
+	next_write: Instant,
+	busy: Option<&'a (Fn() -> bool + Sync)>,
+}
+
+impl<'a, W: SnapshotWriter> ThrottledWriter<'a, W> {
+	/// Wrap the writer. `busy` tells whether there is other work which should go first.
+	pub fn new(inner: W, config: &ThrottleConfig, busy: &'a (Fn() -> bool + Sync)) -> Self {
+		ThrottledWriter {
+			inner: inner,
+			interval: config.max_chunks_per_sec.map(|n| Duration::from_secs(1) / ::std::cmp::max(n, 1)),
+			next_write: Instant::now(),
+			busy: if config.yield_to_import { Some(busy) } else { None },
+		}
+	}
+
+	fn wait(&mut self) {
+		if let Some(busy) = self.busy {
+			while busy() {
+				thread::sleep(Duration::from_millis(BUSY_POLL_MS));
+			}
+		}
+
+		if let Some(interval) = self.interval {
+			let now = Instant::now();
+			if self.next_write > now {
+				thread::sleep(self.next_write - now);
+				self.next_write += interval;
+			} else {
+				self.next_write = now + interval;
+			}
+		}
+	}
+}
+
+impl<'a, W: SnapshotWriter> SnapshotWriter for ThrottledWriter<'a, W> {
+	fn write_state_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()> {
+		self.wait();
+		self.inner.write_state_chunk(hash, chunk)
+	}
+
+	fn write_block_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()> {
+		self.wait();
+		self.inner.write_block_chunk(hash, chunk)
+	}
+
+	fn finish(self, manifest: ManifestData) -> io::Result<()> {
+		self.inner.finish(manifest)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::time::{Duration, Instant};
+	use util::hash::H256;
+	use snapshot::ManifestData;
+	use snapshot::io::SnapshotWriter;
+	use super::{ThrottleConfig, ThrottledWriter};
+
+	struct Counter(usize);
+
+	impl SnapshotWriter for Counter {
+		fn write_state_chunk(&mut self, _hash: H256, _chunk: &[u8]) -> io::Result<()> { self.0 += 1; Ok(()) }
+		fn write_block_chunk(&mut self, _hash: H256, _chunk: &[u8]) -> io::Result<()> { self.0 += 1; Ok(()) }
+		fn finish(self, _manifest: ManifestData) -> io::Result<()> { Ok(()) }
+	}
+
+	#[test]
+	fn limits_chunk_rate() {
+		let config = ThrottleConfig { max_chunks_per_sec: Some(50), yield_to_import: false };
+		let busy = || true;
+		let mut writer = ThrottledWriter::new(Counter(0), &config, &busy);
+
+		let started = Instant::now();
+		for _ in 0..4 {
+			writer.write_state_chunk(H256::default(), &[]).unwrap();
+		}
+		// the first chunk is written immediately.
+		assert!(started.elapsed() >= Duration::from_millis(60));
+		assert_eq!(writer.inner.0, 4);
+	}
+
+	#[test]
+	fn yields_to_import() {
+		let config = ThrottleConfig { max_chunks_per_sec: None, yield_to_import: true };
+		let polls = AtomicUsize::new(0);
+		let busy = || polls.fetch_add(1, Ordering::SeqCst) < 2;
+		let mut writer = ThrottledWriter::new(Counter(0), &config, &busy);
+
+		writer.write_block_chunk(H256::default(), &[]).unwrap();
+		assert_eq!(polls.load(Ordering::SeqCst), 3);
+		assert_eq!(writer.inner.0, 1);
+	}
+}
@@ -0,0 +1,75 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Offline verification of snapshots.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use blockchain::BlockChain;
+use engines::Engine;
+use util::{Hashable, UtilError};
+use util::journaldb::Algorithm;
+use util::kvdb::{Database, DatabaseConfig};
+use util::snappy;
+use util::trie::TrieError;
+
+use super::{Error, StateRebuilder};
+use super::io::SnapshotReader;
+
+/// Check that all chunks listed in the manifest can be read and match their hashes.
+pub fn verify_chunks(reader: &SnapshotReader) -> Result<(), Error> {
+	let manifest = reader.manifest();
+	for &hash in manifest.state_hashes.iter().chain(&manifest.block_hashes) {
+		let found = reader.chunk(hash)?.sha3();
+		if found != hash {
+			return Err(Error::WrongChunkHash(hash, found));
+		}
+	}
+	Ok(())
+}
+
+/// Restore the snapshot into a fresh database at `db_path`, checking the final state root,
+/// contract code and the blocks. Only full snapshots can be restored on their own.
+pub fn verify_restoration(reader: &SnapshotReader, engine: &Engine, genesis: &[u8], db_path: &Path) -> Result<(), ::error::Error> {
+	let manifest = reader.manifest();
+
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let db = Arc::new(Database::open(&db_config, &db_path.to_string_lossy()).map_err(UtilError::SimpleString)?);
+	let flag = AtomicBool::new(true);
+
+	let mut state = StateRebuilder::new(db.clone(), Algorithm::OverlayRecent);
+	for &hash in &manifest.state_hashes {
+		state.feed(&snappy::decompress(&reader.chunk(hash)?)?, &flag)?;
+	}
+
+	let root = state.state_root();
+	if root != manifest.state_root {
+		return Err(TrieError::InvalidStateRoot(root).into());
+	}
+	state.finalize(manifest.block_number, manifest.block_hash)?;
+
+	let chain = BlockChain::new(Default::default(), genesis, db.clone());
+	let components = engine.snapshot_components().ok_or(Error::SnapshotsUnsupported)?;
+	let mut blocks = components.rebuilder(chain, db.clone(), manifest)?;
+	for &hash in &manifest.block_hashes {
+		blocks.feed(&snappy::decompress(&reader.chunk(hash)?)?, engine, &flag)?;
+	}
+	blocks.finalize()?;
+
+	Ok(())
+}
@@ -24,13 +24,80 @@ use service::ClientIoMessage;
 use io::IoChannel;
 use util::{H256, Bytes};
 
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use time;
+
+/// How often periodic snapshots are taken.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Period {
+	/// Every given number of blocks.
+	Blocks(u64),
+	/// Once the given time has passed since the last snapshot.
+	Interval(Duration),
+}
+
+/// Time of the day (UTC) when periodic snapshots may be started.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TimeWindow {
+	/// Start of the window in minutes since midnight.
+	pub start: u32,
+	/// End of the window in minutes since midnight, may be less than `start` if the window spans midnight.
+	pub end: u32,
+}
+
+impl TimeWindow {
+	/// Checks if the window contains given minute of the day.
+	pub fn contains(&self, minute: u32) -> bool {
+		if self.start <= self.end {
+			minute >= self.start && minute < self.end
+		} else {
+			minute >= self.start || minute < self.end
+		}
+	}
+}
+
+impl FromStr for TimeWindow {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let minute = |t: &str| -> Option<u32> {
+			let mut parts = t.split(':');
+			let hours = parts.next().and_then(|h| h.parse::<u32>().ok());
+			let minutes = parts.next().and_then(|m| m.parse::<u32>().ok());
+			match (hours, minutes, parts.next()) {
+				(Some(h), Some(m), None) if h < 24 && m < 60 => Some(h * 60 + m),
+				_ => None,
+			}
+		};
+		let mut parts = s.split('-');
+		match (parts.next().and_then(&minute), parts.next().and_then(&minute), parts.next()) {
+			(Some(start), Some(end), None) if start != end => Ok(TimeWindow { start: start, end: end }),
+			_ => Err(format!("Invalid time window: {}. Expected HH:MM-HH:MM", s)),
+		}
+	}
+}
+
+/// Periodic snapshot schedule.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Schedule {
+	/// How often to take snapshots.
+	pub period: Period,
+	/// How many blocks to wait before taking a snapshot of a block.
+	pub history: u64,
+	/// Snapshots due outside of the window are postponed until it opens.
+	pub window: Option<TimeWindow>,
+}
 
 // helper trait for transforming hashes to numbers and checking if syncing.
 trait Oracle: Send + Sync {
 	fn to_number(&self, hash: H256) -> Option<u64>;
 
 	fn is_major_importing(&self) -> bool;
+
+	// current UTC time in minutes since midnight.
+	fn minute_of_day(&self) -> u32;
 }
 
 struct StandardOracle<F> where F: 'static + Send + Sync + Fn() -> bool {
@@ -48,6 +115,11 @@ impl<F> Oracle for StandardOracle<F>
 	fn is_major_importing(&self) -> bool {
 		(self.sync_status)()
 	}
+
+	fn minute_of_day(&self) -> u32 {
+		let now = time::now_utc();
+		now.tm_hour as u32 * 60 + now.tm_min as u32
+	}
 }
 
 // helper trait for broadcasting a block to take a snapshot at.
@@ -71,31 +143,76 @@ impl Broadcast for Mutex<IoChannel<ClientIoMessage>> {
 }
 
 /// A `ChainNotify` implementation which will trigger a snapshot event
-/// at certain block numbers.
+/// according to the schedule.
 pub struct Watcher {
 	oracle: Box<Oracle>,
 	broadcast: Box<Broadcast>,
-	period: u64,
-	history: u64,
+	schedule: Schedule,
+	// time of the last snapshot, used for interval based schedules.
+	last_taken: Mutex<Instant>,
+	// a snapshot was due outside of the time window.
+	postponed: Mutex<bool>,
 }
 
 impl Watcher {
 	/// Create a new `Watcher` which will trigger a snapshot event
-	/// once every `period` blocks, but only after that block is
-	/// `history` blocks old.
-	pub fn new<F>(client: Arc<Client>, sync_status: F, channel: IoChannel<ClientIoMessage>, period: u64, history: u64) -> Self
+	/// according to the `schedule`.
+	pub fn new<F>(client: Arc<Client>, sync_status: F, channel: IoChannel<ClientIoMessage>, schedule: Schedule) -> Self
 		where F: 'static + Send + Sync + Fn() -> bool
 	{
-		Watcher {
-			oracle: Box::new(StandardOracle {
+		Watcher::with(
+			Box::new(StandardOracle {
 				client: client,
 				sync_status: sync_status,
 			}),
-			broadcast: Box::new(Mutex::new(channel)),
-			period: period,
-			history: history,
+			Box::new(Mutex::new(channel)),
+			schedule,
+		)
+	}
+
+	fn with(oracle: Box<Oracle>, broadcast: Box<Broadcast>, schedule: Schedule) -> Self {
+		Watcher {
+			oracle: oracle,
+			broadcast: broadcast,
+			schedule: schedule,
+			last_taken: Mutex::new(Instant::now()),
+			postponed: Mutex::new(false),
 		}
 	}
+
+	// block to take a snapshot at, if one is due.
+	fn due(&self, numbers: Vec<u64>) -> Option<u64> {
+		let history = self.schedule.history;
+		let latest = numbers.iter().cloned().fold(0, ::std::cmp::max);
+		let boundary = match self.schedule.period {
+			Period::Blocks(period) => numbers.into_iter()
+				.filter(|&num| num >= period + history)
+				.map(|num| num - history)
+				.filter(|num| num % period == 0)
+				.fold(0, ::std::cmp::max),
+			Period::Interval(interval) => match self.last_taken.lock().elapsed() >= interval && latest > history {
+				true => latest - history,
+				false => 0,
+			},
+		};
+
+		let in_window = self.schedule.window.as_ref().map_or(true, |w| w.contains(self.oracle.minute_of_day()));
+		let mut postponed = self.postponed.lock();
+		let num = match (boundary, in_window) {
+			(0, true) if *postponed && latest > history => latest - history,
+			(0, _) => return None,
+			(_, false) => {
+				trace!(target: "snapshot_watcher", "snapshot at {} postponed until the time window", boundary);
+				*postponed = true;
+				return None;
+			},
+			(num, true) => num,
+		};
+
+		*postponed = false;
+		*self.last_taken.lock() = Instant::now();
+		Some(num)
+	}
 }
 
 impl ChainNotify for Watcher {
@@ -113,31 +230,27 @@ impl ChainNotify for Watcher {
 
 		trace!(target: "snapshot_watcher", "{} imported", imported.len());
 
-		let highest = imported.into_iter()
+		let numbers = imported.into_iter()
 			.filter_map(|h| self.oracle.to_number(h))
-			.filter(|&num| num >= self.period + self.history)
-			.map(|num| num - self.history)
-			.filter(|num| num % self.period == 0)
-			.fold(0, ::std::cmp::max);
-
-		match highest {
-			0 => self.broadcast.take_at(None),
-			_ => self.broadcast.take_at(Some(highest)),
-		}
+			.collect();
+
+		self.broadcast.take_at(self.due(numbers));
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::{Broadcast, Oracle, Watcher};
+	use super::{Broadcast, Oracle, Watcher, Schedule, Period, TimeWindow};
 
 	use client::ChainNotify;
 
 	use util::{H256, U256};
 
 	use std::collections::HashMap;
+	use std::thread;
+	use std::time::Duration;
 
-	struct TestOracle(HashMap<H256, u64>);
+	struct TestOracle(HashMap<H256, u64>, u32);
 
 	impl Oracle for TestOracle {
 		fn to_number(&self, hash: H256) -> Option<u64> {
@@ -145,6 +258,8 @@ mod tests {
 		}
 
 		fn is_major_importing(&self) -> bool { false }
+
+		fn minute_of_day(&self) -> u32 { self.1 }
 	}
 
 	struct TestBroadcast(Option<u64>);
@@ -156,18 +271,8 @@ mod tests {
 		}
 	}
 
-	// helper harness for tests which expect a notification.
-	fn harness(numbers: Vec<u64>, period: u64, history: u64, expected: Option<u64>) {
-		let hashes: Vec<_> = numbers.clone().into_iter().map(|x| H256::from(U256::from(x))).collect();
-		let map = hashes.clone().into_iter().zip(numbers).collect();
-
-		let watcher = Watcher {
-			oracle: Box::new(TestOracle(map)),
-			broadcast: Box::new(TestBroadcast(expected)),
-			period: period,
-			history: history,
-		};
-
+	fn notify(watcher: &Watcher, numbers: Vec<u64>) {
+		let hashes = numbers.into_iter().map(|x| H256::from(U256::from(x))).collect();
 		watcher.new_blocks(
 			hashes,
 			vec![],
@@ -179,6 +284,17 @@ mod tests {
 		);
 	}
 
+	fn watcher(schedule: Schedule, minute_of_day: u32, expected: Option<u64>) -> Watcher {
+		let map = (0..100).map(|x| (H256::from(U256::from(x)), x)).collect();
+		Watcher::with(Box::new(TestOracle(map, minute_of_day)), Box::new(TestBroadcast(expected)), schedule)
+	}
+
+	// helper harness for tests which expect a notification.
+	fn harness(numbers: Vec<u64>, period: u64, history: u64, expected: Option<u64>) {
+		let schedule = Schedule { period: Period::Blocks(period), history: history, window: None };
+		notify(&watcher(schedule, 0, expected), numbers);
+	}
+
 	// helper
 
 	#[test]
@@ -200,4 +316,41 @@ mod tests {
 	fn doesnt_fire_before_history() {
 		harness(vec![10, 11], 10, 5, None);
 	}
+
+	#[test]
+	fn postpones_until_window() {
+		let window = TimeWindow { start: 22 * 60, end: 4 * 60 };
+		let schedule = Schedule { period: Period::Blocks(10), history: 5, window: Some(window) };
+		let mut watcher = watcher(schedule, 12 * 60, None);
+		notify(&watcher, vec![15]);
+		assert!(*watcher.postponed.lock());
+
+		watcher.oracle = Box::new(TestOracle((0..100).map(|x| (H256::from(U256::from(x)), x)).collect(), 23 * 60));
+		watcher.broadcast = Box::new(TestBroadcast(Some(12)));
+		notify(&watcher, vec![17]);
+		assert!(!*watcher.postponed.lock());
+	}
+
+	#[test]
+	fn fires_after_interval() {
+		let schedule = Schedule { period: Period::Interval(Duration::from_millis(50)), history: 5, window: None };
+		let mut watcher = watcher(schedule, 0, None);
+		notify(&watcher, vec![20]);
+
+		thread::sleep(Duration::from_millis(60));
+		watcher.broadcast = Box::new(TestBroadcast(Some(16)));
+		notify(&watcher, vec![21]);
+		watcher.broadcast = Box::new(TestBroadcast(None));
+		notify(&watcher, vec![22]);
+	}
+
+	#[test]
+	fn parses_time_window() {
+		assert_eq!("22:00-04:30".parse(), Ok(TimeWindow { start: 22 * 60, end: 4 * 60 + 30 }));
+		assert!("24:00-04:00".parse::<TimeWindow>().is_err());
+		assert!("22:00".parse::<TimeWindow>().is_err());
+		assert!(TimeWindow { start: 22 * 60, end: 4 * 60 }.contains(60));
+		assert!(!TimeWindow { start: 22 * 60, end: 4 * 60 }.contains(12 * 60));
+		assert!(TimeWindow { start: 60, end: 120 }.contains(60));
+	}
 }
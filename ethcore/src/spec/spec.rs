@@ -18,7 +18,7 @@
 
 use util::*;
 use builtin::Builtin;
-use engines::{Engine, NullEngine, InstantSeal, BasicAuthority, AuthorityRound, Tendermint};
+use engines::{Engine, NullEngine, InstantSeal, BasicAuthority, AuthorityRound, Tendermint, Clique};
 use factory::Factories;
 use executive::Executive;
 use trace::{NoopTracer, NoopVMTracer};
@@ -27,15 +27,60 @@ use types::executed::CallType;
 use state::{Backend, State, Substate};
 use env_info::EnvInfo;
 use pod_state::*;
+use pod_account::PodAccount;
 use account_db::*;
 use header::{BlockNumber, Header};
 use state_db::StateDB;
+use encoded;
 use super::genesis::Genesis;
 use super::seal::Generic as GenericSeal;
 use ethereum;
 use ethjson;
 use rlp::{Rlp, RlpStream};
 
+/// Trusted block the chain can be synced from without verifying all preceding headers.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Checkpoint {
+	/// Block number.
+	pub number: BlockNumber,
+	/// Block hash.
+	pub hash: H256,
+	/// Total difficulty of the chain up to and including this block.
+	pub total_difficulty: U256,
+}
+
+/// Trusted header a light client can start syncing from, along with the roots
+/// of the canonical hash tries (CHTs) covering every block before it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LightCheckpoint {
+	/// Header of the first block following the last CHT.
+	pub header: encoded::Header,
+	/// Total difficulty of the chain up to and including this block.
+	pub total_difficulty: U256,
+	/// CHT roots, the oldest first.
+	pub chts: Vec<H256>,
+}
+
+impl From<ethjson::spec::LightCheckpoint> for LightCheckpoint {
+	fn from(c: ethjson::spec::LightCheckpoint) -> Self {
+		LightCheckpoint {
+			header: encoded::Header::new(c.header.into()),
+			total_difficulty: c.total_difficulty.into(),
+			chts: c.chts.into_iter().map(Into::into).collect(),
+		}
+	}
+}
+
+impl LightCheckpoint {
+	/// Loads the checkpoint from json.
+	pub fn load<R>(reader: R) -> Result<Self, String> where R: Read {
+		match ethjson::spec::LightCheckpoint::load(reader) {
+			Ok(checkpoint) => Ok(checkpoint.into()),
+			Err(e) => Err(format!("Light checkpoint json is invalid: {}", e)),
+		}
+	}
+}
+
 /// Parameters common to all engines.
 #[derive(Debug, PartialEq, Clone, Default)]
 pub struct CommonParams {
@@ -53,6 +98,8 @@ pub struct CommonParams {
 	pub min_gas_limit: U256,
 	/// Fork block to check.
 	pub fork_block: Option<(BlockNumber, H256)>,
+	/// Trusted checkpoint.
+	pub checkpoint: Option<Checkpoint>,
 	/// Number of first block where EIP-98 rules begin.
 	pub eip98_transition: BlockNumber,
 	/// Validate block receipts root.
@@ -71,6 +118,10 @@ impl From<ethjson::spec::Params> for CommonParams {
 			subprotocol_name: p.subprotocol_name.unwrap_or_else(|| "eth".to_owned()),
 			min_gas_limit: p.min_gas_limit.into(),
 			fork_block: if let (Some(n), Some(h)) = (p.fork_block, p.fork_hash) { Some((n.into(), h.into())) } else { None },
+			checkpoint: match (p.checkpoint_block, p.checkpoint_hash, p.checkpoint_total_difficulty) {
+				(Some(n), Some(h), Some(td)) => Some(Checkpoint { number: n.into(), hash: h.into(), total_difficulty: td.into() }),
+				_ => None,
+			},
 			eip98_transition: p.eip98_transition.map_or(0, Into::into),
 			validate_receipts_transition: p.validate_receipts_transition.map_or(0, Into::into),
 			eip86_transition: p.eip86_transition.map_or(BlockNumber::max_value(), Into::into),
@@ -115,6 +166,9 @@ pub struct Spec {
 	/// Each seal field, expressed as RLP, concatenated.
 	pub seal_rlp: Bytes,
 
+	/// Trusted checkpoint for light clients.
+	pub light_checkpoint: Option<LightCheckpoint>,
+
 	/// Contract constructors to be executed on genesis.
 	constructors: Vec<(Address, Bytes)>,
 
@@ -147,6 +201,7 @@ impl From<ethjson::spec::Spec> for Spec {
 			timestamp: g.timestamp,
 			extra_data: g.extra_data,
 			seal_rlp: seal_rlp,
+			light_checkpoint: s.light_checkpoint.map(Into::into),
 			constructors: s.accounts.constructors().into_iter().map(|(a, c)| (a.into(), c.into())).collect(),
 			state_root_memo: RwLock::new(g.state_root),
 			genesis_state: From::from(s.accounts),
@@ -166,11 +221,12 @@ impl Spec {
 	fn engine(engine_spec: ethjson::spec::Engine, params: CommonParams, builtins: BTreeMap<Address, Builtin>) -> Arc<Engine> {
 		match engine_spec {
 			ethjson::spec::Engine::Null => Arc::new(NullEngine::new(params, builtins)),
-			ethjson::spec::Engine::InstantSeal(instant) => Arc::new(InstantSeal::new(params, instant.params.registrar.map_or_else(Address::new, Into::into), builtins)),
+			ethjson::spec::Engine::InstantSeal(instant) => InstantSeal::new(params, From::from(instant.params), builtins).expect("Failed to start the InstantSeal consensus engine."),
 			ethjson::spec::Engine::Ethash(ethash) => Arc::new(ethereum::Ethash::new(params, From::from(ethash.params), builtins)),
 			ethjson::spec::Engine::BasicAuthority(basic_authority) => Arc::new(BasicAuthority::new(params, From::from(basic_authority.params), builtins)),
 			ethjson::spec::Engine::AuthorityRound(authority_round) => AuthorityRound::new(params, From::from(authority_round.params), builtins).expect("Failed to start AuthorityRound consensus engine."),
 			ethjson::spec::Engine::Tendermint(tendermint) => Tendermint::new(params, From::from(tendermint.params), builtins).expect("Failed to start the Tendermint consensus engine."),
+			ethjson::spec::Engine::Clique(clique) => Clique::new(params, From::from(clique.params), builtins).expect("Failed to start the Clique consensus engine."),
 		}
 	}
 
@@ -195,6 +251,9 @@ impl Spec {
 	/// Get the configured network fork block.
 	pub fn fork_block(&self) -> Option<(BlockNumber, H256)> { self.params.fork_block }
 
+	/// Get the trusted checkpoint, if any.
+	pub fn checkpoint(&self) -> Option<Checkpoint> { self.params.checkpoint }
+
 	/// Get the header of the genesis block.
 	pub fn genesis_header(&self) -> Header {
 		let mut header: Header = Default::default();
@@ -219,6 +278,23 @@ impl Spec {
 		header
 	}
 
+	/// Sorted block numbers of all scheduled forks, as used for fork identifiers.
+	pub fn fork_blocks(&self) -> Vec<BlockNumber> {
+		let mut forks: Vec<BlockNumber> = self.engine.fork_transitions().into_iter()
+			// transitions at genesis are not forks, far-future ones mark disabled rules.
+			.filter(|t| *t > 0 && *t < DISABLED_TRANSITION)
+			.collect();
+		forks.sort();
+		forks.dedup();
+		forks
+	}
+
+	/// Fork identifier (EIP-2124) at the given block: checksum of the genesis hash and
+	/// all passed fork blocks, together with the next scheduled fork (zero if none).
+	pub fn fork_id(&self, head: BlockNumber) -> (u32, u64) {
+		fork_id(&self.genesis_header().hash(), &self.fork_blocks(), head)
+	}
+
 	/// Compose the genesis block for this chain.
 	pub fn genesis_block(&self) -> Bytes {
 		let empty_list = RlpStream::new_list(0).out();
@@ -252,6 +328,21 @@ impl Spec {
 		*self.state_root_memo.write() = None;
 	}
 
+	/// Credit the given accounts with a balance in the genesis state.
+	pub fn fund_genesis_accounts<I>(&mut self, balances: I) where I: IntoIterator<Item=(Address, U256)> {
+		let mut accounts = self.genesis_state.get().clone();
+		for (address, balance) in balances {
+			let nonce = self.params.account_start_nonce;
+			accounts.entry(address).or_insert_with(|| PodAccount {
+				balance: U256::zero(),
+				nonce: nonce,
+				code: Some(Vec::new()),
+				storage: BTreeMap::new(),
+			}).balance = balance;
+		}
+		self.set_genesis_state(PodState::from(accounts));
+	}
+
 	/// Returns `false` if the memoized state root is invalid. `true` otherwise.
 	pub fn is_state_root_valid(&self) -> bool {
 		self.state_root_memo.read().clone().map_or(true, |sr| sr == self.genesis_state.root())
@@ -346,6 +437,17 @@ impl Spec {
 	/// Create a new Spec with InstantSeal consensus which does internal sealing (not requiring work).
 	pub fn new_instant() -> Spec { load_bundled!("instant_seal") }
 
+	/// Create a new Spec with InstantSeal consensus which seals a block every `block_time` seconds.
+	/// With `seal_on_transactions` blocks with transactions are sealed right away, empty ones every `block_time` seconds.
+	pub fn new_instant_with_block_time(block_time: u64, seal_on_transactions: bool) -> Spec {
+		let mut spec = ethjson::spec::Spec::load(include_bytes!("../../res/instant_seal.json") as &[u8]).expect("Chain spec instant_seal is invalid.");
+		if let ethjson::spec::Engine::InstantSeal(ref mut instant) = spec.engine {
+			instant.params.block_time = Some(ethjson::uint::Uint(block_time.into()));
+			instant.params.seal_on_transactions = Some(seal_on_transactions);
+		}
+		spec.into()
+	}
+
 	/// Create a new Spec with AuthorityRound consensus which does internal sealing (not requiring work).
 	/// Accounts with secrets "0".sha3() and "1".sha3() are the validators.
 	pub fn new_test_round() -> Self { load_bundled!("authority_round") }
@@ -354,6 +456,10 @@ impl Spec {
 	/// Account "0".sha3() and "1".sha3() are a authorities.
 	pub fn new_test_tendermint() -> Self { load_bundled!("tendermint") }
 
+	/// Create a new Spec with Clique consensus which does internal sealing (not requiring work).
+	/// Account with secret "".sha3() is the only signer.
+	pub fn new_test_clique() -> Self { load_bundled!("clique") }
+
 	/// TestList.sol used in both specs: https://github.com/paritytech/contracts/pull/30/files
 	/// Accounts with secrets "0".sha3() and "1".sha3() are initially the validators.
 	/// Create a new Spec with BasicAuthority which uses a contract at address 5 to determine the current validators using `getValidators`.
@@ -370,6 +476,59 @@ impl Spec {
 	pub fn new_validator_multi() -> Self { load_bundled!("validator_multi") }
 }
 
+/// Transitions at or above this block are used by chain specs to disable a rule.
+const DISABLED_TRANSITION: BlockNumber = 0x7fffffffffffff;
+
+/// IEEE CRC32 over `data`, continuing from `crc`.
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+	for byte in data {
+		crc ^= *byte as u32;
+		for _ in 0..8 {
+			crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+		}
+	}
+	crc
+}
+
+/// Fork identifier (EIP-2124) of a chain with given genesis and sorted fork blocks at `head`.
+pub fn fork_id(genesis: &H256, forks: &[BlockNumber], head: BlockNumber) -> (u32, u64) {
+	let passed = forks.iter().take_while(|f| **f <= head).count();
+	(fork_checksums(genesis, forks)[passed], forks.get(passed).cloned().unwrap_or(0))
+}
+
+/// Whether a peer announcing `remote` fork identifier may be on the same chain as we are,
+/// following the validation rules of EIP-2124.
+pub fn is_fork_id_compatible(genesis: &H256, forks: &[BlockNumber], head: BlockNumber, remote: (u32, u64)) -> bool {
+	let (remote_hash, remote_next) = remote;
+	let checksums = fork_checksums(genesis, forks);
+	let passed = forks.iter().take_while(|f| **f <= head).count();
+
+	match checksums.iter().position(|c| *c == remote_hash) {
+		// same forks passed: the peer must not announce a fork we have already passed without it.
+		Some(i) if i == passed => remote_next == 0 || head < remote_next,
+		// peer is behind: it has to know about the next fork we have already applied.
+		Some(i) if i < passed => remote_next == forks[i],
+		// peer is ahead, we may just not have synced that far.
+		Some(_) => true,
+		None => false,
+	}
+}
+
+/// Fork checksums after passing 0, 1, ... all of the given fork blocks.
+fn fork_checksums(genesis: &H256, forks: &[BlockNumber]) -> Vec<u32> {
+	let mut crc = crc32_update(!0, genesis);
+	let mut checksums = vec![!crc];
+	for fork in forks {
+		let bytes = [
+			(fork >> 56) as u8, (fork >> 48) as u8, (fork >> 40) as u8, (fork >> 32) as u8,
+			(fork >> 24) as u8, (fork >> 16) as u8, (fork >> 8) as u8, *fork as u8,
+		];
+		crc = crc32_update(crc, &bytes);
+		checksums.push(!crc);
+	}
+	checksums
+}
+
 #[cfg(test)]
 mod tests {
 	use util::*;
@@ -402,4 +561,70 @@ mod tests {
 		let expected = H256::from_str("0000000000000000000000000000000000000000000000000000000000000001").unwrap();
 		assert_eq!(state.storage_at(&Address::from_str("0000000000000000000000000000000000000005").unwrap(), &H256::zero()).unwrap(), expected);
 	}
+
+	#[test]
+	fn funds_genesis_accounts() {
+		let mut spec = Spec::new_test();
+		let genesis_hash = spec.genesis_header().hash();
+		let address = Address::from(7);
+		spec.fund_genesis_accounts(vec![(address, 1000.into())]);
+		assert!(spec.genesis_header().hash() != genesis_hash);
+
+		let db = spec.ensure_db_good(get_temp_state_db(), &Default::default()).unwrap();
+		let state = State::from_existing(db.boxed_clone(), spec.state_root(), spec.engine.account_start_nonce(), Default::default()).unwrap();
+		assert_eq!(state.balance(&address).unwrap(), 1000.into());
+	}
+
+	#[test]
+	fn converts_light_checkpoint() {
+		use ethjson;
+
+		let header = Spec::new_test().genesis_header();
+		let checkpoint = LightCheckpoint::from(ethjson::spec::LightCheckpoint {
+			header: ethjson::bytes::Bytes::new(::rlp::encode(&header).to_vec()),
+			total_difficulty: ethjson::uint::Uint(1000.into()),
+			chts: vec![ethjson::hash::H256(H256::from(1))],
+		});
+
+		assert_eq!(checkpoint.header.hash(), header.hash());
+		assert_eq!(checkpoint.total_difficulty, 1000.into());
+		assert_eq!(checkpoint.chts, vec![H256::from(1)]);
+		assert!(Spec::new_test().light_checkpoint.is_none());
+	}
+
+	#[test]
+	fn fork_id() {
+		let spec = ::ethereum::new_foundation();
+		assert_eq!(spec.fork_id(0), (0xfc64ec04, 1150000));
+		assert_eq!(spec.fork_id(1149999), (0xfc64ec04, 1150000));
+		assert_eq!(spec.fork_id(1150000), (0x97c2c34c, 1920000));
+		assert_eq!(spec.fork_id(1920000), (0x91d1f948, 2463000));
+		assert_eq!(spec.fork_id(2463000), (0x7a64da13, 2675000));
+	}
+
+	#[test]
+	fn fork_id_compatibility() {
+		let spec = ::ethereum::new_foundation();
+		let genesis = spec.genesis_header().hash();
+		let forks = spec.fork_blocks();
+		let compatible = |head, remote| super::is_fork_id_compatible(&genesis, &forks, head, remote);
+
+		// same state, with and without knowledge of the next fork.
+		assert!(compatible(1940000, (0x91d1f948, 2463000)));
+		assert!(compatible(1940000, (0x91d1f948, 0)));
+		// remote announces a fork we have already passed without applying it.
+		assert!(!compatible(1940000, (0x91d1f948, 1930000)));
+		// remote is stuck before a fork we have applied.
+		assert!(!compatible(2500000, (0x91d1f948, 0)));
+		// remote is behind but knows about the fork we passed.
+		assert!(compatible(1940000, (0x97c2c34c, 1920000)));
+		// remote is behind and unaware of the fork we passed.
+		assert!(!compatible(1940000, (0x97c2c34c, 0)));
+		// remote is behind and expects a different next fork.
+		assert!(!compatible(1940000, (0x97c2c34c, 1930000)));
+		// remote is ahead of us.
+		assert!(compatible(0, (0x7a64da13, 2675000)));
+		// unknown chain.
+		assert!(!compatible(1940000, (0xdeadbeef, 0)));
+	}
 }
@@ -23,7 +23,7 @@ use util::hash::{H256};
 use util::hashdb::HashDB;
 use state::{self, Account};
 use header::BlockNumber;
-use util::{Arc, Address, DBTransaction, UtilError, Mutex, Hashable};
+use util::{Arc, Address, U256, DBTransaction, UtilError, Mutex, Hashable};
 use bloom_journal::{Bloom, BloomJournal};
 use db::COL_ACCOUNT_BLOOM;
 use byteorder::{LittleEndian, ByteOrder};
@@ -357,6 +357,15 @@ impl StateDB {
 		self.cache_size
 	}
 
+	/// Balances of the accounts modified by the committed state, before the cache is synced.
+	/// Killed accounts have zero balance.
+	pub fn modified_balances(&self) -> Vec<(Address, U256)> {
+		self.local_cache.iter()
+			.filter(|item| item.modified)
+			.map(|item| (item.address, item.account.as_ref().map_or_else(U256::zero, |account| *account.balance())))
+			.collect()
+	}
+
 	/// Check if the account can be returned from cache by matching current block parent hash against canonical
 	/// state and filtering out account modified in later blocks.
 	fn is_allowed(addr: &Address, parent_hash: &Option<H256>, modifications: &VecDeque<BlockChanges>) -> bool {
@@ -15,7 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use io::IoChannel;
-use client::{BlockChainClient, MiningBlockChainClient, Client, ClientConfig, BlockId};
+use client::{BlockChainClient, MiningBlockChainClient, Client, ClientConfig, BlockId, BlockRange, BlockImportError};
 use state::{self, State, CleanupMode};
 use executive::Executive;
 use ethereum;
@@ -27,6 +27,7 @@ use devtools::*;
 use miner::Miner;
 use spec::Spec;
 use views::BlockView;
+use error::ImportError;
 use ethkey::{KeyPair, Secret};
 use transaction::{PendingTransaction, Transaction, Action, Condition};
 use miner::MinerService;
@@ -179,6 +180,19 @@ fn returns_block_body() {
 	assert_eq!(body.at(1).as_raw()[..], block.rlp().at(2).as_raw()[..]);
 }
 
+#[test]
+fn returns_block_receipts() {
+	use client::TransactionId;
+
+	let client = generate_dummy_client_with_data(2, 3, slice_into![1, 2, 3]);
+	let receipts = client.localized_block_receipts(BlockId::Number(1)).unwrap();
+	assert_eq!(receipts.len(), 3);
+	for (index, receipt) in receipts.into_iter().enumerate() {
+		let id = TransactionId::Location(BlockId::Number(1), index);
+		assert_eq!(Some(receipt), client.transaction_receipt(id));
+	}
+}
+
 #[test]
 fn imports_block_sequence() {
 	let client = generate_dummy_client(6);
@@ -187,6 +201,19 @@ fn imports_block_sequence() {
 	assert!(!block.into_inner().is_empty());
 }
 
+#[test]
+fn returns_author_statistics() {
+	let client = generate_dummy_client(6);
+	let statistics = client.author_statistics(BlockId::Number(2), BlockId::Latest).unwrap();
+
+	assert_eq!(statistics.len(), 1);
+	assert_eq!(statistics[0].sealed_blocks, 5);
+	assert_eq!(statistics[0].missed_steps, 0);
+	assert_eq!(statistics[0].last_block, Some(6));
+	assert_eq!(statistics[0].last_seen, Some(client.block_header(BlockId::Number(6)).unwrap().timestamp()));
+	assert!(client.author_statistics(BlockId::Number(2), BlockId::Number(7)).is_none());
+}
+
 #[test]
 fn can_collect_garbage() {
 	let client = generate_dummy_client(100);
@@ -245,6 +272,73 @@ fn can_handle_long_fork() {
 	assert_eq!(2000, client.chain_info().best_block_number);
 }
 
+#[test]
+fn pauses_import_on_deep_reorg() {
+	let test_spec = get_test_spec();
+	let mut config = ClientConfig::default();
+	config.max_reorg_depth = Some(5);
+	let client = Client::new(
+		config,
+		&test_spec,
+		new_db(),
+		Arc::new(Miner::with_spec(&test_spec)),
+		IoChannel::disconnected(),
+	).unwrap();
+
+	push_blocks_to_client(&client, 53, 1, 10);
+	client.flush_queue();
+	let best_hash = client.chain_info().best_block_hash;
+	assert_eq!(client.chain_info().best_block_number, 10);
+
+	// heavier fork from genesis.
+	for block in get_good_dummy_block_fork_seq(1, 2, &test_spec.genesis_header().hash()) {
+		client.import_block(block).unwrap();
+	}
+	client.flush_queue();
+
+	let alert = client.reorg_alert().expect("Deep reorganisation should raise an alert");
+	assert_eq!(alert.block_number, 1);
+	assert_eq!(alert.depth, 10);
+	assert_eq!(alert.ancestor, test_spec.genesis_header().hash());
+	assert_eq!(client.chain_info().best_block_hash, best_hash);
+
+	client.acknowledge_reorg();
+	client.import_verified_blocks();
+	assert_eq!(client.reorg_alert(), None);
+	assert_eq!(client.chain_info().best_block_number, 3);
+}
+
+#[test]
+fn imports_and_serves_only_block_range() {
+	let test_spec = get_test_spec();
+	let mut config = ClientConfig::default();
+	config.block_range = Some(BlockRange::Fixed(5, 8));
+	let client = Client::new(
+		config,
+		&test_spec,
+		new_db(),
+		Arc::new(Miner::with_spec(&test_spec)),
+		IoChannel::disconnected(),
+	).unwrap();
+
+	for (i, block) in get_good_dummy_block_seq(9).into_iter().enumerate() {
+		match client.import_block(block) {
+			Err(BlockImportError::Import(ImportError::OutOfRange)) => assert!(i + 1 > 8),
+			result => assert!(result.is_ok()),
+		}
+	}
+	client.flush_queue();
+
+	assert_eq!(client.chain_info().best_block_number, 8);
+	assert!(client.block_header(BlockId::Number(4)).is_none());
+	assert!(client.block(BlockId::Number(4)).is_none());
+	assert!(client.block_header(BlockId::Number(5)).is_some());
+	assert!(client.block(BlockId::Number(8)).is_some());
+	assert!(client.is_out_of_range(BlockId::Number(4)));
+	assert!(!client.is_out_of_range(BlockId::Number(5)));
+	assert!(!client.is_out_of_range(BlockId::Number(9)));
+}
+
 #[test]
 fn can_mine() {
 	let dummy_blocks = get_good_dummy_block_seq(2);
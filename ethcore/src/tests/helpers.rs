@@ -269,7 +269,7 @@ pub fn get_test_client_with_blocks(blocks: Vec<Bytes>) -> Arc<Client> {
 	client
 }
 
-fn new_db() -> Arc<KeyValueDB> {
+pub fn new_db() -> Arc<KeyValueDB> {
 	Arc::new(::util::kvdb::in_memory(::db::NUM_COLUMNS.unwrap_or(0)))
 }
 
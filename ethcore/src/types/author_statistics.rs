@@ -0,0 +1,49 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Block authoring statistics.
+
+use util::Address;
+use header::BlockNumber;
+
+/// Blocks sealed and turns missed by an author over a range of blocks.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ipc", binary)]
+pub struct AuthorStatistics {
+	/// Author address.
+	pub author: Address,
+	/// Number of blocks sealed by the author.
+	pub sealed_blocks: u64,
+	/// Number of turns the author was expected to seal a block in, but none was sealed.
+	pub missed_steps: u64,
+	/// Number of the last block sealed by the author.
+	pub last_block: Option<BlockNumber>,
+	/// Timestamp of the last block sealed by the author.
+	pub last_seen: Option<u64>,
+}
+
+impl AuthorStatistics {
+	/// Statistics of an author which has not been seen yet.
+	pub fn new(author: Address) -> Self {
+		AuthorStatistics {
+			author: author,
+			sealed_blocks: 0,
+			missed_steps: 0,
+			last_block: None,
+			last_seen: None,
+		}
+	}
+}
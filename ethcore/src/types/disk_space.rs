@@ -0,0 +1,40 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Disk space level type.
+
+/// Free space left on the database volume, in order of severity.
+/// Each level implies the restrictions of the previous ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "ipc", binary)]
+pub enum DiskSpace {
+	/// Enough space.
+	Sufficient,
+	/// Space is running low, warnings are logged.
+	Low,
+	/// Snapshots are neither taken nor served to peers.
+	NoSnapshots,
+	/// Download of ancient blocks is paused.
+	NoAncientBlocks,
+	/// Block import is halted.
+	Exhausted,
+}
+
+impl Default for DiskSpace {
+	fn default() -> Self {
+		DiskSpace::Sufficient
+	}
+}
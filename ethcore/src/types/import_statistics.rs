@@ -0,0 +1,53 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Block import statistics.
+
+use util::U256;
+
+/// Percentiles of a measured value.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "ipc", binary)]
+pub struct Percentiles {
+	/// Median.
+	pub p50: u64,
+	/// 90th percentile.
+	pub p90: u64,
+	/// 99th percentile.
+	pub p99: u64,
+	/// Maximum.
+	pub max: u64,
+}
+
+/// Rolling statistics of recently imported blocks.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "ipc", binary)]
+pub struct ImportStatistics {
+	/// Number of recently imported blocks the percentiles are computed from.
+	pub samples: u64,
+	/// Time spent verifying a block within the client, in microseconds.
+	pub verification_time: Percentiles,
+	/// Time spent executing a block, in microseconds.
+	pub execution_time: Percentiles,
+	/// Time spent committing a block to the database, in microseconds.
+	pub commit_time: Percentiles,
+	/// Gas processed per second of block import.
+	pub gas_per_sec: Percentiles,
+	/// Number of blocks waiting in the verification queue.
+	pub queued_blocks: u64,
+	/// Gas used by the blocks waiting in the verification queue.
+	pub gas_backlog: U256,
+}
@@ -0,0 +1,38 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Deep chain reorganisation alert.
+
+use util::H256;
+
+/// Raised when an imported block would cause a chain reorganisation deeper than allowed.
+/// Block import is paused until the alert is acknowledged.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ipc", binary)]
+pub struct ReorgAlert {
+	/// Hash of the block which would become the new best block.
+	pub block_hash: H256,
+	/// Number of the block which would become the new best block.
+	pub block_number: u64,
+	/// Current best block.
+	pub best_block_hash: H256,
+	/// Best common ancestor of the current and the new best block.
+	pub ancestor: H256,
+	/// Number of canonical blocks which would be retracted.
+	pub depth: u64,
+	/// Maximal allowed reorganisation depth.
+	pub max_depth: u64,
+}
@@ -36,18 +36,24 @@ pub struct ManifestData {
 	pub block_number: u64,
 	/// Block hash this snapshot was taken at.
 	pub block_hash: H256,
+	/// Hash of the manifest this snapshot is a delta of. State chunks of delta snapshots
+	/// only contain accounts changed since the base, removed accounts have empty bodies.
+	pub base: Option<H256>,
 }
 
 impl ManifestData {
 	/// Encode the manifest data to rlp.
 	pub fn into_rlp(self) -> Bytes {
-		let mut stream = RlpStream::new_list(6);
+		let mut stream = RlpStream::new_list(if self.base.is_some() { 7 } else { 6 });
 		stream.append(&self.version);
 		stream.append_list(&self.state_hashes);
 		stream.append_list(&self.block_hashes);
 		stream.append(&self.state_root);
 		stream.append(&self.block_number);
 		stream.append(&self.block_hash);
+		if let Some(ref base) = self.base {
+			stream.append(base);
+		}
 
 		stream.out()
 	}
@@ -66,6 +72,10 @@ impl ManifestData {
 		let state_root: H256 = decoder.val_at(start + 2)?;
 		let block_number: u64 = decoder.val_at(start + 3)?;
 		let block_hash: H256 = decoder.val_at(start + 4)?;
+		let base = match decoder.item_count()? > start + 5 {
+			true => Some(decoder.val_at(start + 5)?),
+			false => None,
+		};
 
 		Ok(ManifestData {
 			version: version,
@@ -74,6 +84,7 @@ impl ManifestData {
 			state_root: state_root,
 			block_number: block_number,
 			block_hash: block_hash,
+			base: base,
 		})
 	}
 }
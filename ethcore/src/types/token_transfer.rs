@@ -0,0 +1,44 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! ERC-20 token transfer.
+
+use util::{H256, U256, Address};
+use header::BlockNumber;
+
+/// Token transfer announced by a `Transfer(address,address,uint256)` event.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ipc", binary)]
+pub struct TokenTransfer {
+	/// Token contract.
+	pub token: Address,
+	/// Sender.
+	pub from: Address,
+	/// Recipient.
+	pub to: Address,
+	/// Transferred amount.
+	pub value: U256,
+	/// Block number.
+	pub block_number: BlockNumber,
+	/// Block hash.
+	pub block_hash: H256,
+	/// Transaction hash.
+	pub transaction_hash: H256,
+	/// Transaction index within the block.
+	pub transaction_index: usize,
+	/// Log index within the block.
+	pub log_index: usize,
+}
@@ -18,6 +18,7 @@
 
 use engines::Engine;
 use error::Error;
+use header::BlockNumber;
 
 use util::{HeapSizeOf, H256, U256};
 
@@ -34,6 +35,12 @@ pub trait BlockLike {
 
 	/// Get the difficulty of this item.
 	fn difficulty(&self) -> U256;
+
+	/// Get the number of this item.
+	fn number(&self) -> BlockNumber;
+
+	/// Get the gas used by this item, as claimed by its header.
+	fn gas_used(&self) -> U256;
 }
 
 /// Defines transitions between stages of verification.
@@ -69,7 +76,7 @@ pub mod blocks {
 
 	use engines::Engine;
 	use error::Error;
-	use header::Header;
+	use header::{BlockNumber, Header};
 	use verification::{PreverifiedBlock, verify_block_basic, verify_block_unordered};
 
 	use util::{Bytes, HeapSizeOf, H256, U256};
@@ -141,6 +148,14 @@ pub mod blocks {
 		fn difficulty(&self) -> U256 {
 			self.header.difficulty().clone()
 		}
+
+		fn number(&self) -> BlockNumber {
+			self.header.number()
+		}
+
+		fn gas_used(&self) -> U256 {
+			self.header.gas_used().clone()
+		}
 	}
 
 	impl BlockLike for PreverifiedBlock {
@@ -155,6 +170,14 @@ pub mod blocks {
 		fn difficulty(&self) -> U256 {
 			self.header.difficulty().clone()
 		}
+
+		fn number(&self) -> BlockNumber {
+			self.header.number()
+		}
+
+		fn gas_used(&self) -> U256 {
+			self.header.gas_used().clone()
+		}
 	}
 }
 
@@ -164,7 +187,7 @@ pub mod headers {
 
 	use engines::Engine;
 	use error::Error;
-	use header::Header;
+	use header::{BlockNumber, Header};
 	use verification::verify_header_params;
 
 	use util::hash::H256;
@@ -174,6 +197,8 @@ pub mod headers {
 		fn hash(&self) -> H256 { self.hash() }
 		fn parent_hash(&self) -> H256 { self.parent_hash().clone() }
 		fn difficulty(&self) -> U256 { self.difficulty().clone() }
+		fn number(&self) -> BlockNumber { self.number() }
+		fn gas_used(&self) -> U256 { self.gas_used().clone() }
 	}
 
 	/// A mode for verifying headers.
@@ -25,6 +25,7 @@ use io::*;
 use error::*;
 use engines::Engine;
 use service::*;
+use spec::Checkpoint;
 
 use self::kind::{BlockLike, Kind};
 
@@ -55,6 +56,11 @@ pub struct Config {
 	pub max_mem_use: usize,
 	/// Settings for the number of verifiers and adaptation strategy.
 	pub verifier_settings: VerifierSettings,
+	/// Trusted checkpoint. Seals of the checkpoint and of its ancestors, proven by following
+	/// parent hashes of the queued items, are not verified and an item at the checkpoint height
+	/// must match its hash. Only ancestors imported after the checkpoint or still unverified
+	/// when it's imported are proven, seals of items verified before are checked as usual.
+	pub checkpoint: Option<Checkpoint>,
 }
 
 impl Default for Config {
@@ -63,6 +69,7 @@ impl Default for Config {
 			max_queue_size: 30000,
 			max_mem_use: 50 * 1024 * 1024,
 			verifier_settings: VerifierSettings::default(),
+			checkpoint: None,
 		}
 	}
 }
@@ -75,6 +82,8 @@ pub struct VerifierSettings {
 	pub scale_verifiers: bool,
 	/// Beginning amount of verifiers.
 	pub num_verifiers: usize,
+	/// Maximal amount of verifier threads, never exceeded when scaling.
+	pub max_verifiers: Option<usize>,
 }
 
 impl Default for VerifierSettings {
@@ -82,6 +91,7 @@ impl Default for VerifierSettings {
 		VerifierSettings {
 			scale_verifiers: false,
 			num_verifiers: MAX_VERIFIERS,
+			max_verifiers: None,
 		}
 	}
 }
@@ -132,7 +142,7 @@ pub struct VerificationQueue<K: Kind> {
 	deleting: Arc<AtomicBool>,
 	ready_signal: Arc<QueueSignal>,
 	empty: Arc<SCondvar>,
-	processing: RwLock<HashMap<H256, U256>>, // hash to difficulty
+	processing: RwLock<HashMap<H256, (U256, U256)>>, // hash to difficulty and gas used
 	ticks_since_adjustment: AtomicUsize,
 	max_queue_size: usize,
 	max_mem_use: usize,
@@ -140,6 +150,7 @@ pub struct VerificationQueue<K: Kind> {
 	verifier_handles: Vec<JoinHandle<()>>,
 	state: Arc<(Mutex<State>, Condvar)>,
 	total_difficulty: RwLock<U256>,
+	total_gas: RwLock<U256>,
 }
 
 struct QueueSignal {
@@ -190,10 +201,13 @@ struct Verification<K: Kind> {
 	verifying: Mutex<VecDeque<Verifying<K>>>,
 	verified: Mutex<VecDeque<K::Verified>>,
 	bad: Mutex<HashSet<H256>>,
+	// hashes of the checkpoint and its ancestors, known by following parent hashes.
+	checkpoint_ancestors: Mutex<HashSet<H256>>,
 	more_to_verify: SMutex<()>,
 	empty: SMutex<()>,
 	sizes: Sizes,
 	check_seal: bool,
+	checkpoint: Option<Checkpoint>,
 }
 
 impl<K: Kind> VerificationQueue<K> {
@@ -204,6 +218,7 @@ impl<K: Kind> VerificationQueue<K> {
 			verifying: Mutex::new(VecDeque::new()),
 			verified: Mutex::new(VecDeque::new()),
 			bad: Mutex::new(HashSet::new()),
+			checkpoint_ancestors: Mutex::new(HashSet::new()),
 			more_to_verify: SMutex::new(()),
 			empty: SMutex::new(()),
 			sizes: Sizes {
@@ -212,6 +227,7 @@ impl<K: Kind> VerificationQueue<K> {
 				verified: AtomicUsize::new(0),
 			},
 			check_seal: check_seal,
+			checkpoint: config.checkpoint,
 		});
 		let more_to_verify = Arc::new(SCondvar::new());
 		let deleting = Arc::new(AtomicBool::new(false));
@@ -226,6 +242,7 @@ impl<K: Kind> VerificationQueue<K> {
 
 		let num_cpus = ::num_cpus::get();
 		let max_verifiers = min(num_cpus, MAX_VERIFIERS);
+		let max_verifiers = max(1, config.verifier_settings.max_verifiers.map_or(max_verifiers, |cap| min(cap, max_verifiers)));
 		let default_amount = max(1, min(max_verifiers, config.verifier_settings.num_verifiers));
 		let state = Arc::new((Mutex::new(State::Work(default_amount)), Condvar::new()));
 		let mut verifier_handles = Vec::with_capacity(max_verifiers);
@@ -279,6 +296,7 @@ impl<K: Kind> VerificationQueue<K> {
 			verifier_handles: verifier_handles,
 			state: state,
 			total_difficulty: RwLock::new(0.into()),
+			total_gas: RwLock::new(0.into()),
 		}
 	}
 
@@ -350,7 +368,8 @@ impl<K: Kind> VerificationQueue<K> {
 			};
 
 			let hash = item.hash();
-			let is_ready = match K::verify(item, &*engine, verification.check_seal) {
+			let trusted = verification.checkpoint_ancestors.lock().remove(&hash);
+			let is_ready = match K::verify(item, &*engine, verification.check_seal && !trusted) {
 				Ok(verified) => {
 					let mut verifying = verification.verifying.lock();
 					let mut idx = None;
@@ -437,6 +456,7 @@ impl<K: Kind> VerificationQueue<K> {
 		sizes.verifying.store(0, AtomicOrdering::Release);
 		sizes.verified.store(0, AtomicOrdering::Release);
 		*self.total_difficulty.write() = 0.into();
+		*self.total_gas.write() = 0.into();
 
 		self.processing.write().clear();
 	}
@@ -477,17 +497,48 @@ impl<K: Kind> VerificationQueue<K> {
 				bad.insert(h.clone());
 				return Err(ImportError::KnownBad.into());
 			}
+
+			if let Some(checkpoint) = self.verification.checkpoint {
+				if input.number() == checkpoint.number && h != checkpoint.hash {
+					bad.insert(h.clone());
+					return Err(BlockError::CheckpointMismatch(Mismatch { expected: checkpoint.hash, found: h }).into());
+				}
+			}
+		}
+
+		if let Some(checkpoint) = self.verification.checkpoint {
+			let unverified = self.verification.unverified.lock();
+			let mut ancestors = self.verification.checkpoint_ancestors.lock();
+			if h == checkpoint.hash {
+				// items queued before the checkpoint are proven by walking back from it.
+				// the walk stops at items taken by the verifiers, their seals are checked.
+				ancestors.insert(h);
+				let mut parent = input.parent_hash();
+				ancestors.insert(parent);
+				for item in unverified.iter().rev() {
+					if item.hash() == parent {
+						parent = item.parent_hash();
+						ancestors.insert(parent);
+					}
+				}
+			} else if ancestors.contains(&h) {
+				ancestors.insert(input.parent_hash());
+			}
 		}
 
 		match K::create(input, &*self.engine) {
 			Ok(item) => {
 				self.verification.sizes.unverified.fetch_add(item.heap_size_of_children(), AtomicOrdering::SeqCst);
 
-				self.processing.write().insert(h.clone(), item.difficulty());
+				self.processing.write().insert(h.clone(), (item.difficulty(), item.gas_used()));
 				{
 					let mut td = self.total_difficulty.write();
 					*td = *td + item.difficulty();
 				}
+				{
+					let mut gas = self.total_gas.write();
+					*gas = *gas + item.gas_used();
+				}
 				self.verification.unverified.lock().push_back(item);
 				self.more_to_verify.notify_all();
 				Ok(h)
@@ -518,10 +569,7 @@ impl<K: Kind> VerificationQueue<K> {
 		bad.reserve(hashes.len());
 		for hash in hashes {
 			bad.insert(hash.clone());
-			if let Some(difficulty) = processing.remove(hash) {
-				let mut td = self.total_difficulty.write();
-				*td = *td - difficulty;
-			}
+			self.remove_processing(&mut processing, hash);
 		}
 
 		let mut new_verified = VecDeque::new();
@@ -530,10 +578,7 @@ impl<K: Kind> VerificationQueue<K> {
 			if bad.contains(&output.parent_hash()) {
 				removed_size += output.heap_size_of_children();
 				bad.insert(output.hash());
-				if let Some(difficulty) = processing.remove(&output.hash()) {
-					let mut td = self.total_difficulty.write();
-					*td = *td - difficulty;
-				}
+				self.remove_processing(&mut processing, &output.hash());
 			} else {
 				new_verified.push_back(output);
 			}
@@ -551,12 +596,20 @@ impl<K: Kind> VerificationQueue<K> {
 		}
 		let mut processing = self.processing.write();
 		for hash in hashes {
-			if let Some(difficulty) = processing.remove(hash) {
+			self.remove_processing(&mut processing, hash);
+		}
+		processing.is_empty()
+	}
+
+	fn remove_processing(&self, processing: &mut HashMap<H256, (U256, U256)>, hash: &H256) {
+		if let Some((difficulty, gas_used)) = processing.remove(hash) {
+			{
 				let mut td = self.total_difficulty.write();
 				*td = *td - difficulty;
 			}
+			let mut gas = self.total_gas.write();
+			*gas = *gas - gas_used;
 		}
-		processing.is_empty()
 	}
 
 	/// Removes up to `max` verified items from the queue
@@ -613,6 +666,11 @@ impl<K: Kind> VerificationQueue<K> {
 		self.total_difficulty.read().clone()
 	}
 
+	/// Get the total gas used by all the blocks in the queue, as claimed by their headers.
+	pub fn total_gas(&self) -> U256 {
+		self.total_gas.read().clone()
+	}
+
 	/// Get the current number of working verifiers.
 	pub fn num_verifiers(&self) -> usize {
 		match *self.state.0.lock() {
@@ -729,8 +787,10 @@ mod tests {
 	use util::*;
 	use io::*;
 	use spec::*;
-	use super::{BlockQueue, Config, State};
+	use super::{BlockQueue, HeaderQueue, Config, State, Status};
 	use super::kind::blocks::Unverified;
+	use header::Header;
+	use rlp::encode;
 	use tests::helpers::*;
 	use error::*;
 	use views::*;
@@ -781,6 +841,94 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn rejects_blocks_not_matching_checkpoint() {
+		let spec = get_test_spec();
+		let block = get_good_dummy_block();
+		let number = BlockView::new(&block).header().number();
+
+		let mut config = Config::default();
+		config.checkpoint = Some(Checkpoint { number: number, hash: H256::from(1), total_difficulty: U256::zero() });
+		let queue = BlockQueue::new(config, spec.engine, IoChannel::disconnected(), true);
+
+		match queue.import(Unverified::new(block)) {
+			Err(Error::Block(BlockError::CheckpointMismatch(_))) => {},
+			other => panic!("must return CheckpointMismatch error, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn verifies_seals_of_side_chain_below_checkpoint() {
+		let spec = Spec::new_test_clique();
+		let genesis = spec.genesis_header();
+		let header = |number: u64, parent: H256, timestamp: u64| {
+			let mut header = Header::new();
+			header.set_number(number);
+			header.set_parent_hash(parent);
+			header.set_timestamp(timestamp);
+			header.set_gas_limit(genesis.gas_limit().clone());
+			header.set_difficulty(2.into());
+			// not signed by anybody
+			header.set_extra_data(vec![0; 32 + 65]);
+			header.set_seal(vec![encode(&H256::default()).to_vec(), encode(&H64::default()).to_vec()]);
+			header
+		};
+		let ancestor = header(1, genesis.hash(), 1);
+		let checkpoint = header(2, ancestor.hash(), 2);
+		let side = header(1, genesis.hash(), 3);
+
+		let mut config = Config::default();
+		config.checkpoint = Some(Checkpoint { number: 2, hash: checkpoint.hash(), total_difficulty: U256::zero() });
+		let queue = HeaderQueue::new(config, spec.engine, IoChannel::disconnected(), true);
+
+		queue.import(checkpoint.clone()).unwrap();
+		queue.import(ancestor.clone()).unwrap();
+		queue.import(side.clone()).unwrap();
+		queue.flush();
+
+		let verified: Vec<_> = queue.drain(10).into_iter().map(|header| header.hash()).collect();
+		assert!(verified.contains(&checkpoint.hash()));
+		assert!(verified.contains(&ancestor.hash()));
+		assert!(!verified.contains(&side.hash()));
+		match queue.status(&side.hash()) {
+			Status::Bad => {},
+			_ => panic!("side chain block below the checkpoint must fail seal verification"),
+		}
+	}
+
+	#[test]
+	fn verifies_seals_of_ancestors_verified_before_checkpoint() {
+		let spec = Spec::new_test_clique();
+		let genesis = spec.genesis_header();
+		let header = |number: u64, parent: H256| {
+			let mut header = Header::new();
+			header.set_number(number);
+			header.set_parent_hash(parent);
+			header.set_timestamp(number);
+			header.set_gas_limit(genesis.gas_limit().clone());
+			header.set_difficulty(2.into());
+			// not signed by anybody
+			header.set_extra_data(vec![0; 32 + 65]);
+			header.set_seal(vec![encode(&H256::default()).to_vec(), encode(&H64::default()).to_vec()]);
+			header
+		};
+		let ancestor = header(1, genesis.hash());
+		let checkpoint = header(2, ancestor.hash());
+
+		let mut config = Config::default();
+		config.checkpoint = Some(Checkpoint { number: 2, hash: checkpoint.hash(), total_difficulty: U256::zero() });
+		let queue = HeaderQueue::new(config, spec.engine, IoChannel::disconnected(), true);
+
+		// the ancestor is verified before the checkpoint proves it.
+		queue.import(ancestor.clone()).unwrap();
+		queue.flush();
+		match queue.status(&ancestor.hash()) {
+			Status::Bad => {},
+			_ => panic!("seal of the ancestor verified before the checkpoint must be checked"),
+		}
+		assert!(queue.import(checkpoint).is_err());
+	}
+
 	#[test]
 	fn returns_total_difficulty() {
 		let queue = get_test_queue(false);
@@ -854,6 +1002,19 @@ mod tests {
 		assert!(queue.num_verifiers() == 1);
 	}
 
+	#[test]
+	fn caps_verifier_threads() {
+		let spec = get_test_spec();
+		let mut config = Config::default();
+		config.verifier_settings.scale_verifiers = true;
+		config.verifier_settings.max_verifiers = Some(1);
+		let queue = BlockQueue::new(config, spec.engine, IoChannel::disconnected(), true);
+
+		assert_eq!(queue.verifier_handles.len(), 1);
+		queue.scale_verifiers(4);
+		assert_eq!(queue.num_verifiers(), 1);
+	}
+
 	#[test]
 	fn readjust_verifiers() {
 		let queue = get_test_queue(true);
@@ -28,6 +28,7 @@ use blockchain::*;
 use header::{BlockNumber, Header};
 use rlp::UntrustedRlp;
 use transaction::SignedTransaction;
+use client::{Span, QUEUED_VERIFICATION};
 use views::BlockView;
 use time::get_time;
 
@@ -39,6 +40,8 @@ pub struct PreverifiedBlock {
 	pub transactions: Vec<SignedTransaction>,
 	/// Block bytes
 	pub bytes: Bytes,
+	/// Time spent in `verify_block_unordered`, reported with the spans of the import.
+	pub span: Span,
 }
 
 impl HeapSizeOf for PreverifiedBlock {
@@ -72,25 +75,27 @@ pub fn verify_block_basic(header: &Header, bytes: &[u8], engine: &Engine) -> Res
 /// Still operates on a individual block
 /// Returns a `PreverifiedBlock` structure populated with transactions
 pub fn verify_block_unordered(header: Header, bytes: Bytes, engine: &Engine, check_seal: bool) -> Result<PreverifiedBlock, Error> {
-	if check_seal {
-		engine.verify_block_unordered(&header, Some(&bytes))?;
-		for u in UntrustedRlp::new(&bytes).at(2)?.iter().map(|rlp| rlp.as_val::<Header>()) {
-			engine.verify_block_unordered(&u?, None)?;
+	let (span, transactions) = Span::measure(QUEUED_VERIFICATION, || -> Result<_, Error> {
+		if check_seal {
+			engine.verify_block_unordered(&header, Some(&bytes))?;
+			for u in UntrustedRlp::new(&bytes).at(2)?.iter().map(|rlp| rlp.as_val::<Header>()) {
+				engine.verify_block_unordered(&u?, None)?;
+			}
 		}
-	}
-	// Verify transactions.
-	let mut transactions = Vec::new();
-	{
+		// Verify transactions.
+		let mut transactions = Vec::new();
 		let v = BlockView::new(&bytes);
 		for t in v.transactions() {
 			let t = engine.verify_transaction(t, &header)?;
 			transactions.push(t);
 		}
-	}
+		Ok(transactions)
+	});
 	Ok(PreverifiedBlock {
 		header: header,
-		transactions: transactions,
+		transactions: transactions?,
 		bytes: bytes,
+		span: span,
 	})
 }
 
@@ -57,6 +57,14 @@ pub fn public_negate(public: &mut Public) -> Result<(), Error> {
 	Ok(())
 }
 
+/// Decompress a public key from its 33-byte compressed form
+pub fn public_from_compressed(data: &[u8]) -> Result<Public, Error> {
+	let key_public = key::PublicKey::from_slice(&SECP256K1, data)?;
+	let mut public = Public::default();
+	set_public(&mut public, &key_public);
+	Ok(public)
+}
+
 /// Return base point of secp256k1
 pub fn generation_point() -> Public {
 	let mut public_sec_raw = [0u8; 65];
@@ -215,6 +215,24 @@ export default {
     }
   },
 
+  engineParams: {
+    section: SECTION_NODE,
+    desc: 'Returns the parameters of the consensus engine from the chain spec. Tendermint reports its step timeouts (`timeoutPropose`, `timeoutPrevote`, `timeoutPrecommit`, `timeoutCommit`) and `timeoutRoundIncrease`, by which the propose, prevote and precommit timeouts grow in every further round, in milliseconds.',
+    params: [],
+    returns: {
+      type: Object,
+      desc: 'Engine parameter names mapped to their values.',
+      example: {
+        registrar: '0000000000000000000000000000000000000000',
+        timeoutCommit: '10000',
+        timeoutPrecommit: '10000',
+        timeoutPrevote: '10000',
+        timeoutPropose: '10000',
+        timeoutRoundIncrease: '500'
+      }
+    }
+  },
+
   enode: {
     section: SECTION_NODE,
     desc: 'Returns the node enode URI.',
@@ -1755,6 +1773,28 @@ export default {
     }
   },
 
+  setEngineSignerSecure: {
+    subdoc: SUBDOC_SET,
+    desc: 'Rotates the authority account for signing consensus messages. No block is sealed while the account is being swapped.',
+    params: [
+      {
+        type: Address,
+        desc: 'Identifier of a valid authority account.',
+        example: '0x407d73d8a49eeb85d32cf465507dd71d507100c1'
+      },
+      {
+        type: String,
+        desc: 'Passphrase to unlock the account.',
+        example: 'hunter2'
+      }
+    ],
+    returns: {
+      type: Boolean,
+      desc: 'True if the call succeeded',
+      example: true
+    }
+  },
+
   upgradeReady: {
     subdoc: SUBDOC_SET,
     desc: 'Returns a ReleaseInfo object describing the release which is available for upgrade or `null` if none is available.',
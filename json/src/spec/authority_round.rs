@@ -16,6 +16,7 @@
 
 //! Authority params deserialization.
 
+use std::collections::BTreeMap;
 use uint::Uint;
 use hash::Address;
 use super::ValidatorSet;
@@ -29,6 +30,9 @@ pub struct AuthorityRoundParams {
 	/// Block duration.
 	#[serde(rename="stepDuration")]
 	pub step_duration: Uint,
+	/// Step durations taking effect at the given blocks.
+	#[serde(rename="stepDurationTransitions")]
+	pub step_duration_transitions: Option<BTreeMap<Uint, Uint>>,
 	/// Valid authorities
 	pub validators: ValidatorSet,
 	/// Block reward.
@@ -58,6 +62,8 @@ pub struct AuthorityRound {
 #[cfg(test)]
 mod tests {
 	use serde_json;
+	use uint::Uint;
+	use util::U256;
 	use spec::authority_round::AuthorityRound;
 
 	#[test]
@@ -66,6 +72,10 @@ mod tests {
 			"params": {
 				"gasLimitBoundDivisor": "0x0400",
 				"stepDuration": "0x02",
+				"stepDurationTransitions": {
+					"1000": 5,
+					"0x7d0": "0x01"
+				},
 				"validators": {
 					"list" : ["0xc6d9d2cd449a754c494264e1809c50e34d64562b"]
 				},
@@ -75,6 +85,9 @@ mod tests {
 			}
 		}"#;
 
-		let _deserialized: AuthorityRound = serde_json::from_str(s).unwrap();
+		let deserialized: AuthorityRound = serde_json::from_str(s).unwrap();
+		let transitions = deserialized.params.step_duration_transitions.unwrap();
+		assert_eq!(transitions.get(&Uint(U256::from(1000))), Some(&Uint(U256::from(5))));
+		assert_eq!(transitions.get(&Uint(U256::from(2000))), Some(&Uint(U256::from(1))));
 	}
 }
@@ -17,12 +17,19 @@
 //! Instant params deserialization.
 
 use hash::Address;
+use uint::Uint;
 
 /// Instant params deserialization.
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct InstantSealParams {
 	/// Address of the registrar contract.
 	pub registrar: Option<Address>,
+	/// Minimal number of seconds between blocks.
+	#[serde(rename="blockTime")]
+	pub block_time: Option<Uint>,
+	/// Whether blocks with transactions are sealed without waiting for the block time.
+	#[serde(rename="sealOnTransactions")]
+	pub seal_on_transactions: Option<bool>,
 }
 
 /// Instant engine deserialization.
@@ -35,6 +42,8 @@ pub struct InstantSeal {
 #[cfg(test)]
 mod tests {
 	use serde_json;
+	use uint::Uint;
+	use util::U256;
 	use spec::instant_seal::InstantSeal;
 
 	#[test]
@@ -47,4 +56,18 @@ mod tests {
 
 		let _deserialized: InstantSeal = serde_json::from_str(s).unwrap();
 	}
+
+	#[test]
+	fn instant_seal_block_time_deserialization() {
+		let s = r#"{
+			"params": {
+				"blockTime": 5,
+				"sealOnTransactions": true
+			}
+		}"#;
+
+		let deserialized: InstantSeal = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.params.block_time, Some(Uint(U256::from(5))));
+		assert_eq!(deserialized.params.seal_on_transactions, Some(true));
+	}
 }
@@ -0,0 +1,68 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Light client checkpoint deserialization.
+
+use std::io::Read;
+use serde_json;
+use serde_json::Error;
+use bytes::Bytes;
+use hash::H256;
+use uint::Uint;
+
+/// Trusted header the light client can start syncing from, with the CHT roots of all preceding blocks.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct LightCheckpoint {
+	/// RLP encoded header of the first block after the CHTs.
+	pub header: Bytes,
+	/// Total difficulty of the header.
+	#[serde(rename="totalDifficulty")]
+	pub total_difficulty: Uint,
+	/// Roots of the CHTs preceding the header, the oldest first.
+	#[serde(rename="CHTs")]
+	pub chts: Vec<H256>,
+}
+
+impl LightCheckpoint {
+	/// Loads the checkpoint from json.
+	pub fn load<R>(reader: R) -> Result<Self, Error> where R: Read {
+		serde_json::from_reader(reader)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use uint::Uint;
+	use util::U256;
+	use spec::light_checkpoint::LightCheckpoint;
+
+	#[test]
+	fn light_checkpoint_deserialization() {
+		let s = r#"{
+			"header": "0xf90200a0d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+			"totalDifficulty": "0x400000000",
+			"CHTs": [
+				"0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+				"0x0000000000000000000000000000000000000000000000000000000000000001"
+			]
+		}"#;
+
+		let deserialized: LightCheckpoint = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.total_difficulty, Uint(U256::from(0x400000000u64)));
+		assert_eq!(deserialized.chts.len(), 2);
+	}
+}
@@ -30,6 +30,8 @@ pub mod instant_seal;
 pub mod basic_authority;
 pub mod authority_round;
 pub mod tendermint;
+pub mod clique;
+pub mod light_checkpoint;
 
 pub use self::account::Account;
 pub use self::builtin::{Builtin, Pricing, Linear};
@@ -45,3 +47,5 @@ pub use self::instant_seal::{InstantSeal, InstantSealParams};
 pub use self::basic_authority::{BasicAuthority, BasicAuthorityParams};
 pub use self::authority_round::{AuthorityRound, AuthorityRoundParams};
 pub use self::tendermint::{Tendermint, TendermintParams};
+pub use self::clique::{Clique, CliqueParams};
+pub use self::light_checkpoint::LightCheckpoint;
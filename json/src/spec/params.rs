@@ -50,6 +50,16 @@ pub struct Params {
 	#[serde(rename="forkCanonHash")]
 	pub fork_hash: Option<H256>,
 
+	/// Trusted checkpoint block number.
+	#[serde(rename="checkpointBlock")]
+	pub checkpoint_block: Option<Uint>,
+	/// Trusted checkpoint block hash.
+	#[serde(rename="checkpointHash")]
+	pub checkpoint_hash: Option<H256>,
+	/// Total difficulty of the chain at trusted checkpoint.
+	#[serde(rename="checkpointTotalDifficulty")]
+	pub checkpoint_total_difficulty: Option<Uint>,
+
 	/// See `CommonParams` docs.
 	#[serde(rename="eip98Transition")]
 	pub eip98_transition: Option<Uint>,
@@ -64,8 +74,27 @@ pub struct Params {
 #[cfg(test)]
 mod tests {
 	use serde_json;
+	use uint::Uint;
+	use hash::H256;
 	use spec::params::Params;
 
+	#[test]
+	fn params_checkpoint_deserialization() {
+		let s = r#"{
+			"maximumExtraDataSize": "0x20",
+			"networkID" : "0x1",
+			"minGasLimit": "0x1388",
+			"checkpointBlock": "0x3d0900",
+			"checkpointHash": "0x0000000000000000000000000000000000000000000000000000000000000001",
+			"checkpointTotalDifficulty": "0x10"
+		}"#;
+
+		let deserialized: Params = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.checkpoint_block, Some(Uint(4_000_000.into())));
+		assert_eq!(deserialized.checkpoint_hash, Some(H256(1.into())));
+		assert_eq!(deserialized.checkpoint_total_difficulty, Some(Uint(16.into())));
+	}
+
 	#[test]
 	fn params_deserialization() {
 		let s = r#"{
@@ -40,6 +40,9 @@ pub struct TendermintParams {
 	/// Commit step timeout in milliseconds.
 	#[serde(rename="timeoutCommit")]
 	pub timeout_commit: Option<Uint>,
+	/// Milliseconds added to the propose, prevote and precommit timeouts in every further round of a height.
+	#[serde(rename="timeoutRoundIncrease")]
+	pub timeout_round_increase: Option<Uint>,
 	/// Block reward.
 	#[serde(rename="blockReward")]
 	pub block_reward: Option<Uint>,
@@ -57,6 +60,8 @@ pub struct Tendermint {
 #[cfg(test)]
 mod tests {
 	use serde_json;
+	use uint::Uint;
+	use util::U256;
 	use spec::tendermint::Tendermint;
 
 	#[test]
@@ -73,4 +78,23 @@ mod tests {
 
 		let _deserialized: Tendermint = serde_json::from_str(s).unwrap();
 	}
+
+	#[test]
+	fn tendermint_timeouts_deserialization() {
+		let s = r#"{
+			"params": {
+				"gasLimitBoundDivisor": "0x0400",
+				"validators": {
+					"list": ["0xc6d9d2cd449a754c494264e1809c50e34d64562b"]
+				},
+				"timeoutPropose": 3000,
+				"timeoutRoundIncrease": "0x1f4"
+			}
+		}"#;
+
+		let deserialized: Tendermint = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.params.timeout_propose, Some(Uint(U256::from(3000))));
+		assert_eq!(deserialized.params.timeout_prevote, None);
+		assert_eq!(deserialized.params.timeout_round_increase, Some(Uint(U256::from(500))));
+	}
 }
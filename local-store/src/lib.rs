@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Manages local node data: pending local transactions, sync security level
+//! Manages local node data: pending local transactions, the transaction pool, sync security level
 
 use std::sync::Arc;
 use std::fmt;
@@ -26,7 +26,7 @@ use ethcore::transaction::{
 use ethcore::service::ClientIoMessage;
 use io::IoHandler;
 use rlp::UntrustedRlp;
-use util::kvdb::KeyValueDB;
+use util::kvdb::{KeyValueDB, DBTransaction};
 
 extern crate ethcore;
 extern crate ethcore_util as util;
@@ -45,6 +45,7 @@ extern crate log;
 extern crate ethkey;
 
 const LOCAL_TRANSACTIONS_KEY: &'static [u8] = &*b"LOCAL_TXS";
+const POOL_TRANSACTIONS_KEY: &'static [u8] = &*b"POOL_TXS";
 
 const UPDATE_TIMER: ::io::TimerToken = 0;
 const UPDATE_TIMEOUT_MS: u64 = 15 * 60 * 1000; // once every 15 minutes.
@@ -127,19 +128,35 @@ impl From<PendingTransaction> for TransactionEntry {
 	}
 }
 
+/// Transactions of the queue which are persisted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Persistence {
+	/// No transactions.
+	None,
+	/// Pending and future transactions of local origin.
+	Local,
+	/// All pending and future transactions.
+	All,
+}
+
 /// Something which can provide information about the local node.
 pub trait NodeInfo: Send + Sync {
 	/// Get all pending transactions of local origin.
 	fn pending_transactions(&self) -> Vec<PendingTransaction>;
+
+	/// Get all pending and future transactions not of local origin.
+	fn external_transactions(&self) -> Vec<PendingTransaction>;
 }
 
 /// Create a new local data store, given a database, a column to write to, and a node.
 /// Attempts to read data out of the store, and move it into the node.
+/// Only transactions of local origin are persisted.
 pub fn create<T: NodeInfo>(db: Arc<KeyValueDB>, col: Option<u32>, node: T) -> LocalDataStore<T> {
 	LocalDataStore {
 		db: db,
 		col: col,
 		node: node,
+		persistence: Persistence::Local,
 	}
 }
 
@@ -151,39 +168,73 @@ pub struct LocalDataStore<T: NodeInfo> {
 	db: Arc<KeyValueDB>,
 	col: Option<u32>,
 	node: T,
+	persistence: Persistence,
 }
 
 impl<T: NodeInfo> LocalDataStore<T> {
-	/// Attempt to read pending transactions out of the local store.
-	pub fn pending_transactions(&self) -> Result<Vec<PendingTransaction>, Error> {
-		if let Some(val) = self.db.get(self.col, LOCAL_TRANSACTIONS_KEY).map_err(Error::Database)? {
-			let local_txs: Vec<_> = ::serde_json::from_slice::<Vec<TransactionEntry>>(&val)
+	/// Set the transactions to persist.
+	pub fn with_persistence(mut self, persistence: Persistence) -> Self {
+		self.persistence = persistence;
+		self
+	}
+
+	fn read_transactions(&self, key: &[u8]) -> Result<Vec<PendingTransaction>, Error> {
+		if let Some(val) = self.db.get(self.col, key).map_err(Error::Database)? {
+			let txs: Vec<_> = ::serde_json::from_slice::<Vec<TransactionEntry>>(&val)
 				.map_err(Error::Json)?
 				.into_iter()
 				.filter_map(TransactionEntry::into_pending)
 				.collect();
 
-			Ok(local_txs)
+			Ok(txs)
 		} else {
 			Ok(Vec::new())
 		}
 	}
 
+	/// Attempt to read pending transactions out of the local store.
+	pub fn pending_transactions(&self) -> Result<Vec<PendingTransaction>, Error> {
+		match self.persistence {
+			Persistence::None => Ok(Vec::new()),
+			Persistence::Local | Persistence::All => self.read_transactions(LOCAL_TRANSACTIONS_KEY),
+		}
+	}
+
+	/// Attempt to read transactions of the pool not of local origin out of the local store.
+	/// They have to be validated again when imported.
+	pub fn pool_transactions(&self) -> Result<Vec<PendingTransaction>, Error> {
+		match self.persistence {
+			Persistence::None | Persistence::Local => Ok(Vec::new()),
+			Persistence::All => self.read_transactions(POOL_TRANSACTIONS_KEY),
+		}
+	}
+
+	fn write_transactions(&self, batch: &mut DBTransaction, key: &[u8], txs: Vec<PendingTransaction>) -> Result<(), Error> {
+		let entries: Vec<TransactionEntry> = txs.into_iter().map(Into::into).collect();
+		let json = ::serde_json::to_value(&entries).map_err(Error::Json)?;
+		let json_str = format!("{}", json);
+
+		batch.put_vec(self.col, key, json_str.into_bytes());
+		Ok(())
+	}
+
 	/// Update the entries in the database.
 	pub fn update(&self) -> Result<(), Error> {
 		trace!(target: "local_store", "Updating local store entries.");
 
 		let mut batch = self.db.transaction();
 
-		let local_entries: Vec<TransactionEntry> = self.node.pending_transactions()
-			.into_iter()
-			.map(Into::into)
-			.collect();
-
-		let local_json = ::serde_json::to_value(&local_entries).map_err(Error::Json)?;
-		let json_str = format!("{}", local_json);
+		let (local, pool) = match self.persistence {
+			Persistence::None => (Vec::new(), Vec::new()),
+			Persistence::Local => (self.node.pending_transactions(), Vec::new()),
+			Persistence::All => (self.node.pending_transactions(), self.node.external_transactions()),
+		};
+		self.write_transactions(&mut batch, LOCAL_TRANSACTIONS_KEY, local)?;
+		match pool.is_empty() {
+			true => batch.delete(self.col, POOL_TRANSACTIONS_KEY),
+			false => self.write_transactions(&mut batch, POOL_TRANSACTIONS_KEY, pool)?,
+		}
 
-		batch.put_vec(self.col, LOCAL_TRANSACTIONS_KEY, json_str.into_bytes());
 		self.db.write(batch).map_err(Error::Database)
 	}
 }
@@ -214,7 +265,7 @@ impl<T: NodeInfo> Drop for LocalDataStore<T> {
 
 #[cfg(test)]
 mod tests {
-	use super::NodeInfo;
+	use super::{NodeInfo, Persistence};
 
 	use std::sync::Arc;
 	use ethcore::transaction::{Transaction, Condition, PendingTransaction};
@@ -226,6 +277,22 @@ mod tests {
 	struct Dummy(Vec<PendingTransaction>);
 	impl NodeInfo for Dummy {
 		fn pending_transactions(&self) -> Vec<PendingTransaction> { self.0.clone() }
+		fn external_transactions(&self) -> Vec<PendingTransaction> { Vec::new() }
+	}
+
+	struct Pool(Vec<PendingTransaction>, Vec<PendingTransaction>);
+	impl NodeInfo for Pool {
+		fn pending_transactions(&self) -> Vec<PendingTransaction> { self.0.clone() }
+		fn external_transactions(&self) -> Vec<PendingTransaction> { self.1.clone() }
+	}
+
+	fn transactions(secret: &str, count: u64) -> Vec<PendingTransaction> {
+		let keypair = Brain::new(secret.into()).generate().unwrap();
+		(0..count).map(|nonce| {
+			let mut tx = Transaction::default();
+			tx.nonce = nonce.into();
+			PendingTransaction::new(tx.sign(keypair.secret(), None), None)
+		}).collect()
 	}
 
 	#[test]
@@ -278,6 +345,40 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn persists_pool() {
+		let local = transactions("local", 2);
+		let external = transactions("external", 3);
+		let db = Arc::new(::util::kvdb::in_memory(0));
+
+		{
+			// only local transactions are written.
+			let _store = super::create(db.clone(), None, Pool(local.clone(), external.clone()));
+		}
+		{
+			let store = super::create(db.clone(), None, Pool(local.clone(), external.clone())).with_persistence(Persistence::All);
+			assert_eq!(store.pending_transactions().unwrap(), local);
+			assert_eq!(store.pool_transactions().unwrap(), vec![]);
+		}
+		{
+			// the whole pool was written.
+			let store = super::create(db.clone(), None, Pool(local.clone(), external.clone())).with_persistence(Persistence::All);
+			assert_eq!(store.pending_transactions().unwrap(), local);
+			assert_eq!(store.pool_transactions().unwrap(), external);
+		}
+		{
+			let store = super::create(db.clone(), None, Pool(local.clone(), external.clone())).with_persistence(Persistence::None);
+			assert_eq!(store.pending_transactions().unwrap(), vec![]);
+			assert_eq!(store.pool_transactions().unwrap(), vec![]);
+		}
+		{
+			// the store was cleared.
+			let store = super::create(db.clone(), None, Pool(vec![], vec![])).with_persistence(Persistence::All);
+			assert_eq!(store.pending_transactions().unwrap(), vec![]);
+			assert_eq!(store.pool_transactions().unwrap(), vec![]);
+		}
+	}
+
 	#[test]
 	fn skips_bad_transactions() {
 		let keypair = Brain::new("abcd".into()).generate().unwrap();
@@ -0,0 +1,129 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Windows event log sink.
+
+use std::io;
+use rlog::LogLevel;
+
+/// Sends warnings and errors to the Windows event log.
+pub struct EventLog {
+	#[cfg(windows)]
+	handle: windows::Handle,
+}
+
+/// Only warnings and errors are reported, the event log is not meant for progress messages.
+pub fn is_reported(level: LogLevel) -> bool {
+	level <= LogLevel::Warn
+}
+
+#[cfg(windows)]
+impl EventLog {
+	/// Register the event source of the given name.
+	pub fn new(source: &str) -> io::Result<Self> {
+		windows::Handle::register(source).map(|handle| EventLog { handle: handle })
+	}
+
+	/// Report a message. Errors are ignored, there is nowhere to report them.
+	pub fn send(&self, level: LogLevel, message: &str) {
+		let kind = match level {
+			LogLevel::Error => windows::EVENTLOG_ERROR_TYPE,
+			LogLevel::Warn => windows::EVENTLOG_WARNING_TYPE,
+			_ => windows::EVENTLOG_INFORMATION_TYPE,
+		};
+		self.handle.report(kind, message);
+	}
+}
+
+#[cfg(not(windows))]
+impl EventLog {
+	/// Register the event source of the given name.
+	pub fn new(_source: &str) -> io::Result<Self> {
+		Err(io::Error::new(io::ErrorKind::Other, "Event log is only available on Windows"))
+	}
+
+	/// Report a message. Errors are ignored, there is nowhere to report them.
+	pub fn send(&self, _level: LogLevel, _message: &str) {}
+}
+
+#[cfg(windows)]
+mod windows {
+	use std::ffi::OsStr;
+	use std::io;
+	use std::os::windows::ffi::OsStrExt;
+	use std::ptr;
+
+	pub const EVENTLOG_ERROR_TYPE: u16 = 0x0001;
+	pub const EVENTLOG_WARNING_TYPE: u16 = 0x0002;
+	pub const EVENTLOG_INFORMATION_TYPE: u16 = 0x0004;
+
+	#[link(name = "advapi32")]
+	extern "system" {
+		fn RegisterEventSourceW(server: *const u16, source: *const u16) -> *mut u8;
+		fn DeregisterEventSource(handle: *mut u8) -> i32;
+		fn ReportEventW(handle: *mut u8, kind: u16, category: u16, event_id: u32, user_sid: *mut u8, num_strings: u16, data_size: u32, strings: *const *const u16, data: *mut u8) -> i32;
+	}
+
+	fn wide(s: &str) -> Vec<u16> {
+		OsStr::new(s).encode_wide().chain(Some(0)).collect()
+	}
+
+	/// Registered event source.
+	pub struct Handle(*mut u8);
+
+	// event log handles may be used from any thread.
+	unsafe impl Send for Handle {}
+	unsafe impl Sync for Handle {}
+
+	impl Handle {
+		pub fn register(source: &str) -> io::Result<Self> {
+			let source = wide(source);
+			let handle = unsafe { RegisterEventSourceW(ptr::null(), source.as_ptr()) };
+			match handle.is_null() {
+				true => Err(io::Error::last_os_error()),
+				false => Ok(Handle(handle)),
+			}
+		}
+
+		pub fn report(&self, kind: u16, message: &str) {
+			let message = wide(message);
+			let strings = [message.as_ptr()];
+			unsafe {
+				ReportEventW(self.0, kind, 0, 0, ptr::null_mut(), 1, 0, strings.as_ptr(), ptr::null_mut());
+			}
+		}
+	}
+
+	impl Drop for Handle {
+		fn drop(&mut self) {
+			unsafe { DeregisterEventSource(self.0); }
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rlog::LogLevel;
+	use super::is_reported;
+
+	#[test]
+	fn should_report_warnings_and_errors_only() {
+		assert!(is_reported(LogLevel::Error));
+		assert!(is_reported(LogLevel::Warn));
+		assert!(!is_reported(LogLevel::Info));
+		assert!(!is_reported(LogLevel::Trace));
+	}
+}
@@ -0,0 +1,196 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Log file with size based rotation.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+/// Rotation settings of the log file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rotation {
+	/// Rotate the file once it grows over this size in bytes.
+	pub max_size: u64,
+	/// Number of rotated files to keep.
+	pub max_files: usize,
+	/// Gzip rotated files (in the background, so logging isn't blocked).
+	pub compress: bool,
+}
+
+/// Log file, rotated to `<path>.1`, `<path>.2`, ... (with `.gz` suffix if compressed)
+/// when it exceeds the maximal size.
+pub struct LogFile {
+	path: PathBuf,
+	file: File,
+	size: u64,
+	rotation: Option<Rotation>,
+	compression: Option<thread::JoinHandle<()>>,
+}
+
+impl LogFile {
+	/// Open the file for appending.
+	pub fn open(path: &str, rotation: Option<Rotation>) -> io::Result<Self> {
+		let file = OpenOptions::new().append(true).create(true).open(path)?;
+		let size = file.metadata()?.len();
+		Ok(LogFile {
+			path: path.into(),
+			file: file,
+			size: size,
+			rotation: rotation,
+			compression: None,
+		})
+	}
+
+	/// Append a line and rotate the file if needed.
+	pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+		self.file.write_all(line.as_bytes())?;
+		self.file.write_all(b"\n")?;
+		self.size += line.len() as u64 + 1;
+		let rotate = self.rotation.as_ref().map_or(false, |r| self.size >= r.max_size);
+		if rotate {
+			self.rotate()?;
+		}
+		Ok(())
+	}
+
+	fn rotated_path(&self, index: usize, compressed: bool) -> PathBuf {
+		let mut path = self.path.clone().into_os_string();
+		path.push(format!(".{}", index));
+		if compressed {
+			path.push(".gz");
+		}
+		path.into()
+	}
+
+	fn rotate(&mut self) -> io::Result<()> {
+		let (max_files, compress) = match self.rotation {
+			Some(ref rotation) => (rotation.max_files, rotation.compress),
+			None => return Ok(()),
+		};
+		self.file.flush()?;
+		// the previous file has to be compressed before it's shifted.
+		self.wait_for_compression();
+
+		// shift older files, dropping the oldest one
+		for index in (1..max_files + 1).rev() {
+			for &compressed in &[false, true] {
+				let from = self.rotated_path(index, compressed);
+				if !from.exists() {
+					continue;
+				}
+				if index == max_files {
+					fs::remove_file(&from)?;
+				} else {
+					fs::rename(&from, self.rotated_path(index + 1, compressed))?;
+				}
+			}
+		}
+
+		if max_files == 0 {
+			fs::remove_file(&self.path)?;
+		} else {
+			let rotated = self.rotated_path(1, false);
+			fs::rename(&self.path, &rotated)?;
+			if compress {
+				let compressed = self.rotated_path(1, true);
+				let handle = thread::Builder::new().name("Log compression".into()).spawn(move || {
+					if let Err(e) = compress_file(&rotated, &compressed) {
+						// can't use the logger while it's holding the file.
+						let _ = writeln!(io::stderr(), "Error compressing log file {}: {}", rotated.display(), e);
+					}
+				})?;
+				self.compression = Some(handle);
+			}
+		}
+
+		self.file = OpenOptions::new().append(true).create(true).open(&self.path)?;
+		self.size = 0;
+		Ok(())
+	}
+
+	fn wait_for_compression(&mut self) {
+		if let Some(handle) = self.compression.take() {
+			let _ = handle.join();
+		}
+	}
+}
+
+impl Drop for LogFile {
+	fn drop(&mut self) {
+		self.wait_for_compression();
+	}
+}
+
+/// Gzip `from` into `to` and remove `from`.
+fn compress_file(from: &Path, to: &Path) -> io::Result<()> {
+	let mut input = File::open(from)?;
+	let mut encoder = GzEncoder::new(File::create(to)?, Compression::Default);
+	io::copy(&mut input, &mut encoder)?;
+	encoder.finish()?;
+	fs::remove_file(from)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use std::io::Read;
+	use std::path::Path;
+	use devtools::RandomTempPath;
+	use super::{LogFile, Rotation};
+
+	fn read(path: &Path) -> String {
+		let mut content = String::new();
+		File::open(path).unwrap().read_to_string(&mut content).unwrap();
+		content
+	}
+
+	#[test]
+	fn should_rotate_files() {
+		let dir = RandomTempPath::create_dir();
+		let rotation = Rotation { max_size: 10, max_files: 2, compress: false };
+		let mut file = LogFile::open(&dir.new_in("parity.log"), Some(rotation)).unwrap();
+
+		file.write_line("first line").unwrap();
+		file.write_line("second line").unwrap();
+		file.write_line("third line").unwrap();
+		file.write_line("fourth").unwrap();
+
+		assert_eq!(read(&dir.join("parity.log")), "fourth\n");
+		assert_eq!(read(&dir.join("parity.log.1")), "third line\n");
+		assert_eq!(read(&dir.join("parity.log.2")), "second line\n");
+		assert!(!dir.join("parity.log.3").exists());
+	}
+
+	#[test]
+	fn should_compress_rotated_files() {
+		let dir = RandomTempPath::create_dir();
+		let rotation = Rotation { max_size: 10, max_files: 2, compress: true };
+		let mut file = LogFile::open(&dir.new_in("parity.log"), Some(rotation)).unwrap();
+
+		file.write_line("first line").unwrap();
+		file.write_line("second line").unwrap();
+		// wait for the background compression.
+		drop(file);
+
+		assert!(dir.join("parity.log.1.gz").exists());
+		assert!(dir.join("parity.log.2.gz").exists());
+		assert!(!dir.join("parity.log.1").exists());
+	}
+}
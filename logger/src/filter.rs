@@ -0,0 +1,183 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Log targets filter which can be changed at runtime.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use rlog::{LogLevelFilter, LogMetadata, LogRecord, MaxLogLevelFilter, Log};
+use env_logger::{LogBuilder, Logger};
+use parking_lot::{Mutex, RwLock};
+
+struct State {
+	targets: String,
+	/// Used only for matching records, output is written by the parity logger.
+	matcher: Logger,
+	/// Increased on every change, so that outdated resets are skipped.
+	generation: u64,
+}
+
+/// Filter of log records by target and level, e.g. `sync=trace,rpc=debug`.
+pub struct LogFilter {
+	initial: String,
+	state: RwLock<State>,
+	max_level: Mutex<Option<MaxLogLevelFilter>>,
+}
+
+impl LogFilter {
+	/// Create a new filter with given targets.
+	pub fn new(targets: &str) -> Self {
+		LogFilter {
+			initial: targets.into(),
+			state: RwLock::new(State {
+				targets: targets.into(),
+				matcher: matcher(targets),
+				generation: 0,
+			}),
+			max_level: Mutex::new(None),
+		}
+	}
+
+	/// Current targets.
+	pub fn targets(&self) -> String {
+		self.state.read().targets.clone()
+	}
+
+	/// Maximal enabled level.
+	pub fn filter(&self) -> LogLevelFilter {
+		self.state.read().matcher.filter()
+	}
+
+	/// Checks if records with given metadata are enabled.
+	pub fn enabled(&self, metadata: &LogMetadata) -> bool {
+		self.state.read().matcher.enabled(metadata)
+	}
+
+	/// Checks if the record should be logged.
+	pub fn matches(&self, record: &LogRecord) -> bool {
+		self.state.read().matcher.matches(record)
+	}
+
+	/// Keep the global maximal log level in sync with the filter.
+	pub fn set_max_level(&self, max_level: MaxLogLevelFilter) {
+		max_level.set(self.filter());
+		*self.max_level.lock() = Some(max_level);
+	}
+
+	/// Replace the targets. Returns the generation of the new targets.
+	pub fn set_targets(&self, targets: &str) -> Result<u64, String> {
+		validate_targets(targets)?;
+		Ok(self.replace(targets))
+	}
+
+	/// Restore the targets the filter was created with, unless they were changed again after `generation`.
+	pub fn reset(&self, generation: u64) {
+		if self.state.read().generation == generation {
+			let initial = self.initial.clone();
+			self.replace(&initial);
+		}
+	}
+
+	fn replace(&self, targets: &str) -> u64 {
+		let matcher = matcher(targets);
+		let mut state = self.state.write();
+		if let Some(ref max_level) = *self.max_level.lock() {
+			max_level.set(matcher.filter());
+		}
+		state.targets = targets.into();
+		state.matcher = matcher;
+		state.generation += 1;
+		state.generation
+	}
+}
+
+/// Replace the targets of the filter and restore the initial ones after `duration`.
+pub fn set_targets_for(filter: &Arc<LogFilter>, targets: &str, duration: Option<Duration>) -> Result<(), String> {
+	let generation = filter.set_targets(targets)?;
+	if let Some(duration) = duration {
+		let filter = Arc::downgrade(filter);
+		thread::Builder::new().name("log-reset".into()).spawn(move || {
+			thread::sleep(duration);
+			if let Some(filter) = filter.upgrade() {
+				filter.reset(generation);
+			}
+		}).map_err(|e| format!("Unable to schedule logging targets reset: {}", e))?;
+	}
+	Ok(())
+}
+
+fn matcher(targets: &str) -> Logger {
+	let mut builder = LogBuilder::new();
+	// Disable ws info logging by default.
+	builder.filter(Some("ws"), LogLevelFilter::Warn);
+	// Disable rustls info logging by default.
+	builder.filter(Some("rustls"), LogLevelFilter::Warn);
+	builder.filter(None, LogLevelFilter::Info);
+	builder.parse(targets);
+	builder.build()
+}
+
+/// Check the `target=level,...` syntax, env_logger silently ignores invalid directives.
+fn validate_targets(targets: &str) -> Result<(), String> {
+	let directives = targets.splitn(2, '/').next().unwrap_or("");
+	for directive in directives.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+		let mut parts = directive.split('=');
+		let (target, level) = (parts.next(), parts.next());
+		if parts.next().is_some() || target.map_or(true, str::is_empty) {
+			return Err(format!("Invalid logging directive: {}", directive));
+		}
+		if let Some(level) = level {
+			LogLevelFilter::from_str(level).map_err(|_| format!("Invalid log level in directive: {}", directive))?;
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use rlog::LogLevelFilter;
+	use super::{LogFilter, validate_targets};
+
+	#[test]
+	fn should_change_targets() {
+		let filter = LogFilter::new("rpc=debug");
+		assert_eq!(filter.filter(), LogLevelFilter::Debug);
+
+		let generation = filter.set_targets("sync=trace,rpc=info").unwrap();
+		assert_eq!(filter.targets(), "sync=trace,rpc=info");
+		assert_eq!(filter.filter(), LogLevelFilter::Trace);
+		assert!(filter.set_targets("sync=verbose").is_err());
+		assert_eq!(filter.targets(), "sync=trace,rpc=info");
+
+		filter.reset(generation);
+		assert_eq!(filter.targets(), "rpc=debug");
+		// outdated reset is ignored
+		filter.set_targets("sync=trace").unwrap();
+		filter.reset(generation);
+		assert_eq!(filter.targets(), "sync=trace");
+	}
+
+	#[test]
+	fn should_validate_targets() {
+		assert!(validate_targets("").is_ok());
+		assert!(validate_targets("info,sync=trace,own_tx=debug/import").is_ok());
+		assert!(validate_targets("sync=trace=debug").is_err());
+		assert!(validate_targets("=trace").is_err());
+		assert!(validate_targets("sync=loud").is_err());
+	}
+}
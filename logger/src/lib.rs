@@ -26,25 +26,74 @@ extern crate time;
 extern crate lazy_static;
 extern crate parking_lot;
 extern crate ansi_term;
+extern crate flate2;
 
+#[cfg(test)]
+extern crate ethcore_devtools as devtools;
+
+mod event_log;
+mod file;
+mod filter;
 mod rotating;
+mod syslog;
 
-use std::{env, thread, fs};
+use std::{env, thread};
+use std::fmt::Write;
+use std::io::{self, Write as IoWrite};
+use std::str::FromStr;
 use std::sync::{Weak, Arc};
-use std::io::Write;
 use isatty::{stderr_isatty, stdout_isatty};
-use env_logger::LogBuilder;
 use regex::Regex;
 use ansi_term::Colour;
 use parking_lot::Mutex;
 
 pub use rotating::{RotatingLogger, init_log};
+pub use file::Rotation;
+pub use filter::{LogFilter, set_targets_for};
+pub use syslog::{SyslogConfig, SyslogTarget, SyslogFacility};
+use syslog::Syslog;
+use event_log::EventLog;
+use file::LogFile;
+
+/// Format of log lines.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LogFormat {
+	/// Human readable text.
+	Text,
+	/// One JSON object per line.
+	Json,
+}
+
+impl Default for LogFormat {
+	fn default() -> Self {
+		LogFormat::Text
+	}
+}
+
+impl FromStr for LogFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"text" => Ok(LogFormat::Text),
+			"json" => Ok(LogFormat::Json),
+			other => Err(format!("Invalid log format: {}", other)),
+		}
+	}
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Config {
 	pub mode: Option<String>,
 	pub color: bool,
 	pub file: Option<String>,
+	/// Rotation of the log file.
+	pub rotation: Option<Rotation>,
+	/// Send logs to syslog as well.
+	pub syslog: Option<SyslogConfig>,
+	/// Send warnings and errors to the Windows event log under the given source name.
+	pub event_log: Option<String>,
+	pub format: LogFormat,
 }
 
 impl Default for Config {
@@ -53,6 +102,10 @@ impl Default for Config {
 			mode: None,
 			color: !cfg!(windows),
 			file: None,
+			rotation: None,
+			syslog: None,
+			event_log: None,
+			format: LogFormat::Text,
 		}
 	}
 }
@@ -66,34 +119,36 @@ pub fn setup_log(config: &Config) -> Result<Arc<RotatingLogger>, String> {
 	use rlog::*;
 
 	let mut levels = String::new();
-	let mut builder = LogBuilder::new();
-	// Disable ws info logging by default.
-	builder.filter(Some("ws"), LogLevelFilter::Warn);
-	// Disable rustls info logging by default.
-	builder.filter(Some("rustls"), LogLevelFilter::Warn);
-	builder.filter(None, LogLevelFilter::Info);
 
 	if let Ok(lvl) = env::var("RUST_LOG") {
 		levels.push_str(&lvl);
 		levels.push_str(",");
-		builder.parse(&lvl);
 	}
 
 	if let Some(ref s) = config.mode {
 		levels.push_str(s);
-		builder.parse(s);
 	}
 
 	let isatty = stderr_isatty();
 	let enable_color = config.color && isatty;
-	let logs = Arc::new(RotatingLogger::new(levels));
+	let json = config.format == LogFormat::Json;
+	let filter = Arc::new(LogFilter::new(&levels));
+	let logs = Arc::new(RotatingLogger::with_filter(filter.clone()));
 	let logger = logs.clone();
-	let mut open_options = fs::OpenOptions::new();
 
 	let maybe_file = match config.file.as_ref() {
-		Some(f) => Some(open_options
-			.append(true).create(true).open(f)
-			.map_err(|_| format!("Cannot write to log file given: {}", f))?),
+		Some(f) => Some(Mutex::new(LogFile::open(f, config.rotation.clone())
+			.map_err(|_| format!("Cannot write to log file given: {}", f))?)),
+		None => None,
+	};
+
+	let maybe_syslog = match config.syslog.as_ref() {
+		Some(c) => Some(Syslog::new(c.clone()).map_err(|e| format!("Cannot connect to syslog: {}", e))?),
+		None => None,
+	};
+
+	let maybe_event_log = match config.event_log.as_ref() {
+		Some(source) => Some(EventLog::new(source).map_err(|e| format!("Cannot register event source {}: {}", source, e))?),
 		None => None,
 	};
 
@@ -109,15 +164,27 @@ pub fn setup_log(config: &Config) -> Result<Arc<RotatingLogger>, String> {
 
 		let removed_color = kill_color(with_color.as_ref());
 
-		let ret = match enable_color {
-			true => with_color,
-			false => removed_color.clone(),
+		let ret = match (json, enable_color) {
+			(true, _) => format_json(record),
+			(false, true) => with_color,
+			(false, false) => removed_color.clone(),
 		};
 
-		if let Some(mut file) = maybe_file.as_ref() {
+		if let Some(file) = maybe_file.as_ref() {
+			let line = if json { &ret } else { &removed_color };
 			// ignore errors - there's nothing we can do
-			let _ = file.write_all(removed_color.as_bytes());
-			let _ = file.write_all(b"\n");
+			let _ = file.lock().write_line(line);
+		}
+		if let Some(syslog) = maybe_syslog.as_ref() {
+			match json {
+				true => syslog.send(record.level(), &ret),
+				false => syslog.send(record.level(), &kill_color(&format!("{}", record.args()))),
+			}
+		}
+		if let Some(event_log) = maybe_event_log.as_ref() {
+			if event_log::is_reported(record.level()) {
+				event_log.send(record.level(), &kill_color(&format!("{}", record.args())));
+			}
 		}
 		logger.append(removed_color);
 		if !isatty && record.level() <= LogLevel::Info && stdout_isatty() {
@@ -126,10 +193,15 @@ pub fn setup_log(config: &Config) -> Result<Arc<RotatingLogger>, String> {
 		}
 
 		ret
-    };
+	};
 
-	builder.format(format);
-	builder.init()
+	set_logger(|max_level| {
+		filter.set_max_level(max_level);
+		Box::new(ParityLogger {
+			filter: filter.clone(),
+			format: Box::new(format),
+		})
+	})
 		.and_then(|_| {
 			*ROTATING_LOGGER.lock() = Arc::downgrade(&logs);
 			Ok(logs)
@@ -142,6 +214,82 @@ pub fn setup_log(config: &Config) -> Result<Arc<RotatingLogger>, String> {
 		})
 }
 
+/// Logger writing records matching the runtime configurable filter to stderr.
+struct ParityLogger {
+	filter: Arc<LogFilter>,
+	format: Box<Fn(&rlog::LogRecord) -> String + Send + Sync>,
+}
+
+impl rlog::Log for ParityLogger {
+	fn enabled(&self, metadata: &rlog::LogMetadata) -> bool {
+		self.filter.enabled(metadata)
+	}
+
+	fn log(&self, record: &rlog::LogRecord) {
+		if self.filter.matches(record) {
+			let _ = writeln!(io::stderr(), "{}", (self.format)(record));
+		}
+	}
+}
+
+/// Format a record as a single-line JSON object. Block numbers (`#123`), peer ids and
+/// 32-byte hashes found in the message are exposed as separate fields.
+fn format_json(record: &rlog::LogRecord) -> String {
+	let message = kill_color(&format!("{}", record.args()));
+	let mut out = String::with_capacity(message.len() + 128);
+	out.push_str("{\"timestamp\":\"");
+	let _ = write!(out, "{}", time::now_utc().rfc3339());
+	let _ = write!(out, "\",\"level\":\"{}\",\"target\":", record.level());
+	push_json_string(&mut out, record.target());
+	if let Some(name) = thread::current().name() {
+		out.push_str(",\"thread\":");
+		push_json_string(&mut out, name);
+	}
+	out.push_str(",\"message\":");
+	push_json_string(&mut out, &message);
+	for (field, value) in extract_fields(&message) {
+		let _ = write!(out, ",\"{}\":{}", field, value);
+	}
+	out.push('}');
+	out
+}
+
+/// Structured fields found in a log message, values are already JSON-encoded.
+fn extract_fields(message: &str) -> Vec<(&'static str, String)> {
+	lazy_static! {
+		static ref BLOCK: Regex = Regex::new(r"#(\d+)").unwrap();
+		static ref PEER: Regex = Regex::new(r"(?i)\bpeer(?:\s+id)?[\s:=#]+(\d+)\b").unwrap();
+		static ref HASH: Regex = Regex::new(r"\b(?:0x)?([0-9a-f]{64})\b").unwrap();
+	}
+	let mut fields = Vec::new();
+	if let Some(block) = BLOCK.captures(message).and_then(|c| c.get(1)) {
+		fields.push(("blockNumber", block.as_str().to_owned()));
+	}
+	if let Some(peer) = PEER.captures(message).and_then(|c| c.get(1)) {
+		fields.push(("peerId", peer.as_str().to_owned()));
+	}
+	if let Some(hash) = HASH.captures(message).and_then(|c| c.get(1)) {
+		fields.push(("hash", format!("\"0x{}\"", hash.as_str())));
+	}
+	fields
+}
+
+fn push_json_string(out: &mut String, s: &str) {
+	out.push('"');
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); },
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+}
+
 fn kill_color(s: &str) -> String {
 	lazy_static! {
 		static ref RE: Regex = Regex::new("\x1b\\[[^m]+m").unwrap();
@@ -156,6 +304,22 @@ fn should_remove_colour() {
 	assert_eq!(after, "test");
 }
 
+#[test]
+fn should_extract_json_fields() {
+	let hash = "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3";
+	let fields = extract_fields(&format!("Imported #1234 0x{} (2 txs) from peer 7", hash));
+	assert_eq!(fields, vec![
+		("blockNumber", "1234".to_owned()),
+		("peerId", "7".to_owned()),
+		("hash", format!("\"0x{}\"", hash)),
+	]);
+	assert!(extract_fields("Syncing 12.34 blk/s").is_empty());
+
+	let mut out = String::new();
+	push_json_string(&mut out, "a \"quoted\"\tline\n\\");
+	assert_eq!(out, r#""a \"quoted\"\tline\n\\""#);
+}
+
 #[test]
 fn should_remove_multiple_colour() {
 	let t = format!("{} {}", Colour::Red.bold().paint("test"), Colour::White.normal().paint("again"));
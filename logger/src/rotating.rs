@@ -17,9 +17,12 @@
 //! Common log helper functions
 
 use std::env;
+use std::sync::Arc;
+use std::time::Duration;
 use rlog::LogLevelFilter;
 use env_logger::LogBuilder;
 use arrayvec::ArrayVec;
+use filter::{LogFilter, set_targets_for};
 
 use parking_lot::{RwLock, RwLockReadGuard};
 
@@ -47,8 +50,8 @@ const LOG_SIZE : usize = 128;
 
 /// Logger implementation that keeps up to `LOG_SIZE` log elements.
 pub struct RotatingLogger {
-	/// Defined logger levels
-	levels: String,
+	/// Filter with defined logger levels
+	filter: Arc<LogFilter>,
 	/// Logs array. Latest log is always at index 0
 	logs: RwLock<ArrayVec<[String; LOG_SIZE]>>,
 }
@@ -58,8 +61,13 @@ impl RotatingLogger {
 	/// Creates new `RotatingLogger` with given levels.
 	/// It does not enforce levels - it's just read only.
 	pub fn new(levels: String) -> Self {
+		Self::with_filter(Arc::new(LogFilter::new(&levels)))
+	}
+
+	/// Creates new `RotatingLogger` reporting levels of given filter.
+	pub fn with_filter(filter: Arc<LogFilter>) -> Self {
 		RotatingLogger {
-			levels: levels,
+			filter: filter,
 			logs: RwLock::new(ArrayVec::<[_; LOG_SIZE]>::new()),
 		}
 	}
@@ -70,8 +78,13 @@ impl RotatingLogger {
 	}
 
 	/// Return levels
-	pub fn levels(&self) -> &str {
-		&self.levels
+	pub fn levels(&self) -> String {
+		self.filter.targets()
+	}
+
+	/// Change levels, optionally only for given duration.
+	pub fn set_levels(&self, levels: &str, duration: Option<Duration>) -> Result<(), String> {
+		set_targets_for(&self.filter, levels, duration)
 	}
 
 	/// Return logs
@@ -0,0 +1,245 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Syslog sink sending to the local syslog socket or a remote server over UDP or TCP.
+
+use std::{env, io};
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream, UdpSocket};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use rlog::LogLevel;
+use parking_lot::Mutex;
+use time;
+
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+
+/// Paths of the local syslog socket on common platforms.
+#[cfg(unix)]
+const LOCAL_SOCKETS: &'static [&'static str] = &["/dev/log", "/var/run/syslog", "/var/run/log"];
+
+/// Do not try to reconnect to a TCP server more often than this, connecting blocks the logging thread.
+const TCP_RECONNECT_DELAY_SECS: u64 = 10;
+
+/// Where to send the messages.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SyslogTarget {
+	/// Local syslog daemon socket.
+	Local,
+	/// Remote server over UDP.
+	Udp(SocketAddr),
+	/// Remote server over TCP, messages are separated by newlines.
+	Tcp(SocketAddr),
+}
+
+impl FromStr for SyslogTarget {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let parse_addr = |addr: &str| addr.parse::<SocketAddr>().map_err(|_| format!("Invalid syslog server address: {}", addr));
+		if s == "local" {
+			Ok(SyslogTarget::Local)
+		} else if s.starts_with("udp://") {
+			parse_addr(&s[6..]).map(SyslogTarget::Udp)
+		} else if s.starts_with("tcp://") {
+			parse_addr(&s[6..]).map(SyslogTarget::Tcp)
+		} else {
+			Err(format!("Invalid syslog target: {}. Expected local, udp://IP:PORT or tcp://IP:PORT", s))
+		}
+	}
+}
+
+/// Syslog facility code.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SyslogFacility(pub u8);
+
+impl FromStr for SyslogFacility {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let code = match s {
+			"kern" => 0,
+			"user" => 1,
+			"mail" => 2,
+			"daemon" => 3,
+			"auth" => 4,
+			"syslog" => 5,
+			"lpr" => 6,
+			"news" => 7,
+			"uucp" => 8,
+			"cron" => 9,
+			"authpriv" => 10,
+			"ftp" => 11,
+			"local0" => 16,
+			"local1" => 17,
+			"local2" => 18,
+			"local3" => 19,
+			"local4" => 20,
+			"local5" => 21,
+			"local6" => 22,
+			"local7" => 23,
+			other => return Err(format!("Invalid syslog facility: {}", other)),
+		};
+		Ok(SyslogFacility(code))
+	}
+}
+
+/// Syslog sink configuration.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SyslogConfig {
+	pub target: SyslogTarget,
+	pub facility: SyslogFacility,
+	/// Application name attached to every message.
+	pub tag: String,
+}
+
+enum Transport {
+	#[cfg(unix)]
+	Local(UnixDatagram),
+	Udp(UdpSocket, SocketAddr),
+	/// Connected lazily and re-established after errors. Keeps time of the last failed attempt.
+	Tcp(Option<TcpStream>, SocketAddr, Option<Instant>),
+}
+
+/// Sends log messages to syslog.
+pub struct Syslog {
+	config: SyslogConfig,
+	hostname: String,
+	transport: Mutex<Transport>,
+}
+
+impl Syslog {
+	/// Create the sink. Local socket and UDP errors are reported immediately,
+	/// TCP connection is (re-)established when sending.
+	pub fn new(config: SyslogConfig) -> io::Result<Self> {
+		let transport = match config.target {
+			SyslogTarget::Local => local_transport()?,
+			SyslogTarget::Udp(addr) => {
+				let bind = if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+				Transport::Udp(UdpSocket::bind(bind)?, addr)
+			},
+			SyslogTarget::Tcp(addr) => Transport::Tcp(None, addr, None),
+		};
+		Ok(Syslog {
+			config: config,
+			hostname: env::var("HOSTNAME").ok().and_then(|h| if h.is_empty() { None } else { Some(h) }).unwrap_or_else(|| "-".into()),
+			transport: Mutex::new(transport),
+		})
+	}
+
+	/// Send a message. Errors are ignored, there is nowhere to report them.
+	pub fn send(&self, level: LogLevel, message: &str) {
+		let mut transport = self.transport.lock();
+		let _ = match *transport {
+			#[cfg(unix)]
+			Transport::Local(ref socket) => {
+				socket.send(format_local(&self.config, level, message).as_bytes())
+			},
+			Transport::Udp(ref socket, ref addr) => {
+				socket.send_to(format_remote(&self.config, &self.hostname, level, message).as_bytes(), addr)
+			},
+			Transport::Tcp(ref mut stream, ref addr, ref mut failed_at) => {
+				if stream.is_none() && failed_at.map_or(false, |t| t.elapsed() < Duration::from_secs(TCP_RECONNECT_DELAY_SECS)) {
+					return;
+				}
+				let mut line = format_remote(&self.config, &self.hostname, level, message);
+				line.push('\n');
+				let result = match stream.take() {
+					Some(s) => Ok(s),
+					None => TcpStream::connect(addr),
+				}.and_then(|mut s| s.write_all(line.as_bytes()).map(|_| s));
+				match result {
+					Ok(s) => {
+						*stream = Some(s);
+						*failed_at = None;
+						Ok(line.len())
+					},
+					Err(e) => {
+						*failed_at = Some(Instant::now());
+						Err(e)
+					},
+				}
+			},
+		};
+	}
+}
+
+#[cfg(unix)]
+fn local_transport() -> io::Result<Transport> {
+	let socket = UnixDatagram::unbound()?;
+	for path in LOCAL_SOCKETS {
+		if socket.connect(path).is_ok() {
+			return Ok(Transport::Local(socket));
+		}
+	}
+	Err(io::Error::new(io::ErrorKind::NotFound, "Local syslog socket not found"))
+}
+
+#[cfg(not(unix))]
+fn local_transport() -> io::Result<Transport> {
+	Err(io::Error::new(io::ErrorKind::Other, "Local syslog is not supported on this platform, use udp:// or tcp://"))
+}
+
+fn severity(level: LogLevel) -> u8 {
+	match level {
+		LogLevel::Error => 3,
+		LogLevel::Warn => 4,
+		LogLevel::Info => 6,
+		LogLevel::Debug | LogLevel::Trace => 7,
+	}
+}
+
+fn priority(config: &SyslogConfig, level: LogLevel) -> u8 {
+	config.facility.0 * 8 + severity(level)
+}
+
+/// BSD syslog format understood by local daemons, which add timestamp and hostname themselves.
+fn format_local(config: &SyslogConfig, level: LogLevel, message: &str) -> String {
+	format!("<{}>{}: {}", priority(config, level), config.tag, message)
+}
+
+/// RFC 5424 format for remote servers.
+fn format_remote(config: &SyslogConfig, hostname: &str, level: LogLevel, message: &str) -> String {
+	format!("<{}>1 {} {} {} - - - {}", priority(config, level), time::now_utc().rfc3339(), hostname, config.tag, message)
+}
+
+#[cfg(test)]
+mod tests {
+	use rlog::LogLevel;
+	use super::{SyslogConfig, SyslogTarget, SyslogFacility, format_local};
+
+	#[test]
+	fn should_parse_syslog_config() {
+		assert_eq!("local".parse(), Ok(SyslogTarget::Local));
+		assert_eq!("udp://10.0.0.1:514".parse(), Ok(SyslogTarget::Udp("10.0.0.1:514".parse().unwrap())));
+		assert_eq!("tcp://[::1]:601".parse(), Ok(SyslogTarget::Tcp("[::1]:601".parse().unwrap())));
+		assert!("10.0.0.1:514".parse::<SyslogTarget>().is_err());
+		assert_eq!("local3".parse(), Ok(SyslogFacility(19)));
+		assert!("daemons".parse::<SyslogFacility>().is_err());
+	}
+
+	#[test]
+	fn should_format_priority() {
+		let config = SyslogConfig {
+			target: SyslogTarget::Local,
+			facility: SyslogFacility(3),
+			tag: "parity".into(),
+		};
+		assert_eq!(format_local(&config, LogLevel::Warn, "Block import failed"), "<28>parity: Block import failed");
+		assert_eq!(format_local(&config, LogLevel::Info, "Imported #1"), "<30>parity: Imported #1");
+	}
+}
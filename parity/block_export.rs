@@ -0,0 +1,281 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Output of the block export: optionally compressed, split into multiple files
+//! and checkpointed, so that an interrupted export can be resumed.
+
+use std::{io, fs};
+use std::io::{Read, Write, Seek, SeekFrom};
+use flate2::Compression as GzLevel;
+use flate2::write::GzEncoder;
+use blockchain::Compression;
+
+/// Write a checkpoint every this many blocks.
+const CHECKPOINT_BLOCKS: u64 = 10000;
+
+enum Sink {
+	Plain(Box<Write>),
+	Gzip(GzEncoder<Box<Write>>),
+}
+
+impl Sink {
+	fn new(out: Box<Write>, compression: Compression) -> Self {
+		match compression {
+			Compression::None => Sink::Plain(out),
+			Compression::Gzip => Sink::Gzip(GzEncoder::new(out, GzLevel::Default)),
+		}
+	}
+
+	/// Flush all data. Gzip stream is finished, so the output is a valid file at this point.
+	fn finish(self) -> io::Result<Box<Write>> {
+		let mut out = match self {
+			Sink::Plain(out) => out,
+			Sink::Gzip(encoder) => encoder.finish()?,
+		};
+		out.flush()?;
+		Ok(out)
+	}
+}
+
+impl Write for Sink {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		match *self {
+			Sink::Plain(ref mut out) => out.write(buf),
+			Sink::Gzip(ref mut out) => out.write(buf),
+		}
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		match *self {
+			Sink::Plain(ref mut out) => out.flush(),
+			Sink::Gzip(ref mut out) => out.flush(),
+		}
+	}
+}
+
+/// Export progress, stored next to the exported file.
+#[derive(Debug, PartialEq)]
+pub struct Progress {
+	/// First block of the export.
+	pub from: u64,
+	/// Last block written before the checkpoint.
+	pub last: u64,
+	/// Size of the file containing the last block at the checkpoint.
+	pub offset: u64,
+}
+
+/// Block export output.
+///
+/// Files are split at multiples of `blocks_per_file` blocks counted from the first
+/// exported block and named `<path>.<first block of the file>`.
+/// Compressed checkpoints are separate gzip members, which decoders read as a single stream.
+pub struct ExportWriter {
+	path: Option<String>,
+	compression: Compression,
+	from: u64,
+	blocks_per_file: Option<u64>,
+	/// Current output between gzip members.
+	out: Option<Box<Write>>,
+	sink: Option<Sink>,
+	file: Option<fs::File>,
+}
+
+impl ExportWriter {
+	/// Create the writer. Output goes to stdout if `path` is not given.
+	pub fn new(path: Option<String>, compression: Compression, from: u64, blocks_per_file: Option<u64>) -> Self {
+		ExportWriter {
+			path: path,
+			compression: compression,
+			from: from,
+			blocks_per_file: blocks_per_file,
+			out: None,
+			sink: None,
+			file: None,
+		}
+	}
+
+	/// Progress of a previous export to the same path, if any.
+	pub fn progress(&self) -> Result<Option<Progress>, String> {
+		let path = match self.path {
+			Some(ref path) => progress_path(path),
+			None => return Err("Resuming export requires an output file".into()),
+		};
+		let mut content = String::new();
+		match fs::File::open(&path) {
+			Ok(mut file) => file.read_to_string(&mut content).map_err(|e| format!("Cannot read {}: {}", path, e))?,
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(format!("Cannot read {}: {}", path, e)),
+		};
+		let values = content.split_whitespace().map(str::parse).collect::<Result<Vec<u64>, _>>()
+			.map_err(|_| format!("Invalid export progress file: {}", path))?;
+		match values.len() {
+			3 => Ok(Some(Progress { from: values[0], last: values[1], offset: values[2] })),
+			_ => Err(format!("Invalid export progress file: {}", path)),
+		}
+	}
+
+	/// Path of the file containing given block.
+	pub fn file_path(&self, block: u64) -> Option<String> {
+		self.path.as_ref().map(|path| match self.blocks_per_file {
+			Some(n) => format!("{}.{}", path, self.from + (block - self.from) / n * n),
+			None => path.clone(),
+		})
+	}
+
+	fn is_file_start(&self, block: u64) -> bool {
+		self.blocks_per_file.map_or(block == self.from, |n| (block - self.from) % n == 0)
+	}
+
+	fn is_file_end(&self, block: u64) -> bool {
+		self.blocks_per_file.map_or(false, |n| (block + 1 - self.from) % n == 0)
+	}
+
+	/// Start writing at given block. If `offset` is given, the file is truncated to it and appended to.
+	pub fn open(&mut self, block: u64, offset: Option<u64>) -> Result<(), String> {
+		self.close()?;
+		let out: Box<Write> = match self.file_path(block) {
+			Some(path) => {
+				let mut file = match offset {
+					Some(offset) => {
+						let mut file = fs::OpenOptions::new().write(true).open(&path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+						file.set_len(offset).and_then(|_| file.seek(SeekFrom::End(0)))
+							.map_err(|e| format!("Cannot truncate {}: {}", path, e))?;
+						file
+					},
+					None => fs::File::create(&path).map_err(|_| format!("Cannot write to file given: {}", path))?,
+				};
+				self.file = Some(file.try_clone().map_err(|e| format!("Cannot open {}: {}", path, e))?);
+				Box::new(file)
+			},
+			None => Box::new(io::stdout()),
+		};
+		self.out = Some(out);
+		Ok(())
+	}
+
+	/// Write the block, switching to the next file if needed.
+	pub fn write_block(&mut self, number: u64, data: &[u8]) -> Result<(), String> {
+		if (self.out.is_none() && self.sink.is_none()) || (self.blocks_per_file.is_some() && self.is_file_start(number)) {
+			self.open(number, None)?;
+		}
+		if self.sink.is_none() {
+			let out = self.out.take().expect("opened above; qed");
+			self.sink = Some(Sink::new(out, self.compression));
+		}
+		self.sink.as_mut().expect("created above; qed").write_all(data).map_err(|e| format!("Cannot write block #{}: {}", number, e))?;
+		if self.is_file_end(number) {
+			self.checkpoint(number)?;
+			self.close()?;
+		} else if (number + 1 - self.from) % CHECKPOINT_BLOCKS == 0 {
+			self.checkpoint(number)?;
+		}
+		Ok(())
+	}
+
+	/// Make sure everything up to `last` is on disk and record the progress.
+	pub fn checkpoint(&mut self, last: u64) -> Result<(), String> {
+		if let Some(sink) = self.sink.take() {
+			self.out = Some(sink.finish().map_err(|e| format!("Cannot write export file: {}", e))?);
+		}
+		let (path, file) = match (self.path.clone(), self.file.as_ref()) {
+			(Some(path), Some(file)) => (path, file),
+			_ => return Ok(()),
+		};
+		let offset = file.sync_all().and_then(|_| file.metadata()).map_err(|e| format!("Cannot sync export file: {}", e))?.len();
+		let progress = progress_path(&path);
+		fs::File::create(&progress)
+			.and_then(|mut f| writeln!(f, "{} {} {}", self.from, last, offset).and_then(|_| f.sync_all()))
+			.map_err(|e| format!("Cannot write {}: {}", progress, e))
+	}
+
+	fn close(&mut self) -> Result<(), String> {
+		self.file = None;
+		self.out = None;
+		match self.sink.take() {
+			Some(sink) => sink.finish().map(|_| ()).map_err(|e| format!("Cannot write export file: {}", e)),
+			None => Ok(()),
+		}
+	}
+
+	/// Finish the export.
+	pub fn finish(mut self, last: u64) -> Result<(), String> {
+		self.checkpoint(last)?;
+		self.close()
+	}
+}
+
+fn progress_path(path: &str) -> String {
+	format!("{}.progress", path)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+	use std::io::Read;
+	use flate2::read::MultiGzDecoder;
+	use devtools::RandomTempPath;
+	use blockchain::Compression;
+	use super::{ExportWriter, Progress};
+
+	fn read_gzip(path: &str) -> Vec<u8> {
+		let mut data = Vec::new();
+		MultiGzDecoder::new(fs::File::open(path).unwrap()).unwrap().read_to_end(&mut data).unwrap();
+		data
+	}
+
+	#[test]
+	fn should_split_files() {
+		let temp = RandomTempPath::new();
+		let path = temp.as_str().to_owned();
+		let mut writer = ExportWriter::new(Some(path.clone()), Compression::Gzip, 5, Some(2));
+		for i in 5..10 {
+			writer.write_block(i, &[i as u8]).unwrap();
+		}
+		writer.finish(9).unwrap();
+
+		assert_eq!(read_gzip(&format!("{}.5", path)), vec![5, 6]);
+		assert_eq!(read_gzip(&format!("{}.7", path)), vec![7, 8]);
+		assert_eq!(read_gzip(&format!("{}.9", path)), vec![9]);
+	}
+
+	#[test]
+	fn should_resume_from_checkpoint() {
+		let temp = RandomTempPath::new();
+		let path = temp.as_str().to_owned();
+		{
+			let mut writer = ExportWriter::new(Some(path.clone()), Compression::Gzip, 1, None);
+			writer.write_block(1, &[1]).unwrap();
+			writer.write_block(2, &[2]).unwrap();
+			writer.checkpoint(2).unwrap();
+			// interrupted, the block after the checkpoint is discarded
+			writer.write_block(3, &[3]).unwrap();
+		}
+
+		let mut writer = ExportWriter::new(Some(path.clone()), Compression::Gzip, 1, None);
+		let progress = writer.progress().unwrap().unwrap();
+		assert_eq!(progress.from, 1);
+		assert_eq!(progress.last, 2);
+		writer.open(3, Some(progress.offset)).unwrap();
+		writer.write_block(3, &[3]).unwrap();
+		writer.write_block(4, &[4]).unwrap();
+		writer.finish(4).unwrap();
+
+		assert_eq!(read_gzip(&path), vec![1, 2, 3, 4]);
+		let offset = fs::metadata(&path).unwrap().len();
+		let writer = ExportWriter::new(Some(path.clone()), Compression::Gzip, 1, None);
+		assert_eq!(writer.progress().unwrap(), Some(Progress { from: 1, last: 4, offset: offset }));
+	}
+}
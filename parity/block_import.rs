@@ -0,0 +1,196 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Checkpoints and progress reporting of the block import.
+
+use std::{io, fs};
+use std::cell::Cell;
+use std::io::{Read, Write};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::{Instant, Duration};
+use util::H256;
+
+/// Save a checkpoint every this many blocks.
+pub const CHECKPOINT_BLOCKS: u64 = 10000;
+
+/// Report the progress at most this often.
+const REPORT_INTERVAL_SECS: u64 = 10;
+
+/// Reader counting consumed bytes.
+pub struct CountingReader<R> {
+	inner: R,
+	count: Rc<Cell<u64>>,
+}
+
+impl<R> CountingReader<R> {
+	pub fn new(inner: R, count: Rc<Cell<u64>>) -> Self {
+		CountingReader {
+			inner: inner,
+			count: count,
+		}
+	}
+}
+
+impl<R: Read> Read for CountingReader<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let n = self.inner.read(buf)?;
+		self.count.set(self.count.get() + n as u64);
+		Ok(n)
+	}
+}
+
+/// Position of the import in the file.
+#[derive(Debug, PartialEq)]
+pub struct Checkpoint {
+	/// Number of blocks read from the file and imported.
+	pub blocks: u64,
+	/// Hash of the last of these blocks' RLP, used to detect a different file.
+	pub hash: H256,
+}
+
+/// Tracks the import progress and stores checkpoints in `<file>.import-progress`.
+pub struct ImportProgress {
+	path: Option<String>,
+	size: Option<u64>,
+	read: Rc<Cell<u64>>,
+	started: Instant,
+	last_report: Instant,
+	/// Blocks skipped and bytes read when resuming, excluded from the rate.
+	skipped: u64,
+	skipped_bytes: u64,
+}
+
+impl ImportProgress {
+	/// Create the progress of importing `file` of `size` bytes. `read` counts bytes read from the file.
+	pub fn new(file: Option<&str>, size: Option<u64>, read: Rc<Cell<u64>>) -> Self {
+		ImportProgress {
+			path: file.map(|f| format!("{}.import-progress", f)),
+			size: size,
+			read: read,
+			started: Instant::now(),
+			last_report: Instant::now(),
+			skipped: 0,
+			skipped_bytes: 0,
+		}
+	}
+
+	/// Load the checkpoint of a previous import.
+	pub fn load(&self) -> Result<Option<Checkpoint>, String> {
+		let path = match self.path {
+			Some(ref path) => path,
+			None => return Err("Resuming import requires an input file".into()),
+		};
+		let mut content = String::new();
+		match fs::File::open(path) {
+			Ok(mut file) => file.read_to_string(&mut content).map_err(|e| format!("Cannot read {}: {}", path, e))?,
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(format!("Cannot read {}: {}", path, e)),
+		};
+		let mut parts = content.split_whitespace();
+		match (parts.next().map(u64::from_str), parts.next().map(H256::from_str)) {
+			(Some(Ok(blocks)), Some(Ok(hash))) => Ok(Some(Checkpoint { blocks: blocks, hash: hash })),
+			_ => Err(format!("Invalid import progress file: {}", path)),
+		}
+	}
+
+	/// Store the checkpoint. Blocks up to it must be already imported.
+	pub fn save(&self, checkpoint: &Checkpoint) -> Result<(), String> {
+		match self.path {
+			Some(ref path) => fs::File::create(path)
+				.and_then(|mut f| writeln!(f, "{} {}", checkpoint.blocks, checkpoint.hash.hex()).and_then(|_| f.sync_all()))
+				.map_err(|e| format!("Cannot write {}: {}", path, e)),
+			None => Ok(()),
+		}
+	}
+
+	/// Remove the checkpoint after a completed import.
+	pub fn clear(&self) {
+		if let Some(ref path) = self.path {
+			let _ = fs::remove_file(path);
+		}
+	}
+
+	/// Blocks up to the checkpoint were skipped, measure the rate from now on.
+	pub fn resumed(&mut self, blocks: u64) {
+		self.skipped = blocks;
+		self.skipped_bytes = self.read.get();
+		self.started = Instant::now();
+	}
+
+	/// Print the rate and estimated time left, if it's time to.
+	pub fn report(&mut self, blocks: u64) {
+		if self.last_report.elapsed() < Duration::from_secs(REPORT_INTERVAL_SECS) {
+			return;
+		}
+		self.last_report = Instant::now();
+
+		let elapsed = seconds(self.started.elapsed());
+		let imported = blocks.saturating_sub(self.skipped);
+		let read = self.read.get();
+		let rate = imported as f64 / elapsed;
+		match self.size {
+			Some(size) if size > 0 && read > self.skipped_bytes => {
+				let bytes_rate = (read - self.skipped_bytes) as f64 / elapsed;
+				let left = size.saturating_sub(read) as f64 / bytes_rate;
+				info!("Imported {} blocks, {:.1} blocks/s, {:.1}% of the file, ETA {}", blocks, rate, read as f64 * 100.0 / size as f64, format_eta(left as u64));
+			},
+			_ => info!("Imported {} blocks, {:.1} blocks/s", blocks, rate),
+		}
+	}
+}
+
+fn seconds(d: Duration) -> f64 {
+	d.as_secs() as f64 + d.subsec_nanos() as f64 / 1_000_000_000.0
+}
+
+fn format_eta(secs: u64) -> String {
+	match secs {
+		s if s >= 3600 => format!("{}h {:02}m", s / 3600, s % 3600 / 60),
+		s if s >= 60 => format!("{}m {:02}s", s / 60, s % 60),
+		s => format!("{}s", s),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::rc::Rc;
+	use std::cell::Cell;
+	use devtools::RandomTempPath;
+	use util::H256;
+	use super::{ImportProgress, Checkpoint, format_eta};
+
+	#[test]
+	fn should_save_and_load_checkpoint() {
+		let temp = RandomTempPath::new();
+		let progress = ImportProgress::new(Some(temp.as_str()), None, Rc::new(Cell::new(0)));
+		assert_eq!(progress.load().unwrap(), None);
+
+		let checkpoint = Checkpoint { blocks: 20000, hash: H256::from(7) };
+		progress.save(&checkpoint).unwrap();
+		assert_eq!(progress.load().unwrap(), Some(checkpoint));
+
+		progress.clear();
+		assert_eq!(progress.load().unwrap(), None);
+	}
+
+	#[test]
+	fn should_format_eta() {
+		assert_eq!(format_eta(42), "42s");
+		assert_eq!(format_eta(125), "2m 05s");
+		assert_eq!(format_eta(7384), "2h 03m");
+	}
+}
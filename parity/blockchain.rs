@@ -16,16 +16,19 @@
 
 use std::str::{FromStr, from_utf8};
 use std::{io, fs};
-use std::io::{BufReader, BufRead};
+use std::io::{BufReader, BufRead, Write};
 use std::time::{Instant, Duration};
 use std::thread::sleep;
 use std::sync::Arc;
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+use std::cell::Cell;
 use rustc_serialize::hex::FromHex;
 use io::{PanicHandler, ForwardPanic};
 use util::{ToPretty, Uint, U256, H256, Address, Hashable};
-use rlp::PayloadInfo;
+use rlp::{PayloadInfo, RlpStream};
 use ethcore::service::ClientService;
-use ethcore::client::{Mode, DatabaseCompactionProfile, VMType, BlockImportError, BlockChainClient, BlockId};
+use ethcore::client::{Mode, DatabaseCompactionProfile, VMType, BlockImportError, BlockChainClient, BlockId, TraceFilter};
 use ethcore::error::ImportError;
 use ethcore::miner::Miner;
 use ethcore::verification::queue::VerifierSettings;
@@ -36,11 +39,19 @@ use helpers::{to_client_config, execute_upgrades};
 use dir::Directories;
 use user_defaults::UserDefaults;
 use fdlimit;
+use flate2::read::MultiGzDecoder;
+use block_export::ExportWriter;
+use csv_export::{Table, Ledger, receipt_row, trace_row};
+use block_import::{CountingReader, ImportProgress, Checkpoint, CHECKPOINT_BLOCKS};
 
 #[derive(Debug, PartialEq)]
 pub enum DataFormat {
 	Hex,
 	Binary,
+	/// Only supported by the state export.
+	Json,
+	/// Only supported by the receipts and traces export.
+	Csv,
 }
 
 impl Default for DataFormat {
@@ -56,17 +67,45 @@ impl FromStr for DataFormat {
 		match s {
 			"binary" | "bin" => Ok(DataFormat::Binary),
 			"hex" => Ok(DataFormat::Hex),
+			"json" => Ok(DataFormat::Json),
+			"csv" => Ok(DataFormat::Csv),
 			x => Err(format!("Invalid format: {}", x))
 		}
 	}
 }
 
+/// Compression of exported blocks.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Compression {
+	None,
+	Gzip,
+}
+
+impl Default for Compression {
+	fn default() -> Self {
+		Compression::None
+	}
+}
+
+impl FromStr for Compression {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"none" => Ok(Compression::None),
+			"gzip" | "gz" => Ok(Compression::Gzip),
+			x => Err(format!("Invalid compression: {}", x))
+		}
+	}
+}
+
 #[derive(Debug, PartialEq)]
 pub enum BlockchainCmd {
 	Kill(KillBlockchain),
 	Import(ImportBlockchain),
 	Export(ExportBlockchain),
 	ExportState(ExportState),
+	ExportTable(ExportTable),
 }
 
 #[derive(Debug, PartialEq)]
@@ -94,6 +133,7 @@ pub struct ImportBlockchain {
 	pub check_seal: bool,
 	pub with_color: bool,
 	pub verifier_settings: VerifierSettings,
+	pub resume: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -113,6 +153,9 @@ pub struct ExportBlockchain {
 	pub from_block: BlockId,
 	pub to_block: BlockId,
 	pub check_seal: bool,
+	pub compression: Compression,
+	pub blocks_per_file: Option<u64>,
+	pub resume: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -134,6 +177,28 @@ pub struct ExportState {
 	pub code: bool,
 	pub min_balance: Option<U256>,
 	pub max_balance: Option<U256>,
+	pub min_address: Option<Address>,
+	pub max_address: Option<Address>,
+	pub only_contracts: bool,
+	pub only_with_storage: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ExportTable {
+	pub spec: SpecType,
+	pub cache_config: CacheConfig,
+	pub dirs: Directories,
+	pub file_path: Option<String>,
+	pub pruning: Pruning,
+	pub pruning_history: u64,
+	pub pruning_memory: usize,
+	pub compaction: DatabaseCompactionProfile,
+	pub wal: bool,
+	pub fat_db: Switch,
+	pub tracing: Switch,
+	pub from_block: BlockId,
+	pub to_block: BlockId,
+	pub table: Table,
 }
 
 pub fn execute(cmd: BlockchainCmd) -> Result<(), String> {
@@ -142,6 +207,7 @@ pub fn execute(cmd: BlockchainCmd) -> Result<(), String> {
 		BlockchainCmd::Import(import_cmd) => execute_import(import_cmd),
 		BlockchainCmd::Export(export_cmd) => execute_export(export_cmd),
 		BlockchainCmd::ExportState(export_cmd) => execute_export_state(export_cmd),
+		BlockchainCmd::ExportTable(export_cmd) => execute_export_table(export_cmd),
 	}
 }
 
@@ -222,9 +288,23 @@ fn execute_import(cmd: ImportBlockchain) -> Result<(), String> {
 	panic_handler.forward_from(&service);
 	let client = service.client();
 
-	let mut instream: Box<io::Read> = match cmd.file_path {
-		Some(f) => Box::new(fs::File::open(&f).map_err(|_| format!("Cannot open given file: {}", f))?),
-		None => Box::new(io::stdin()),
+	let read = Rc::new(Cell::new(0));
+	let (instream, size): (Box<io::Read>, Option<u64>) = match cmd.file_path {
+		Some(ref f) => {
+			let file = fs::File::open(f).map_err(|_| format!("Cannot open given file: {}", f))?;
+			let size = file.metadata().ok().map(|m| m.len());
+			(Box::new(CountingReader::new(file, read.clone())), size)
+		},
+		None => (Box::new(io::stdin()), None),
+	};
+
+	// gzipped exports are detected by their magic bytes.
+	let mut instream = BufReader::new(instream);
+	let gzipped = instream.fill_buf().map_err(|_| "Error reading from the file/stream.")?.starts_with(&[0x1f, 0x8b]);
+	let mut instream: Box<io::Read> = if gzipped {
+		Box::new(MultiGzDecoder::new(instream).map_err(|e| format!("Invalid gzip stream: {}", e))?)
+	} else {
+		Box::new(instream)
 	};
 
 	const READAHEAD_BYTES: usize = 8;
@@ -260,34 +340,74 @@ fn execute_import(cmd: ImportBlockchain) -> Result<(), String> {
 		Ok(())
 	};
 
-	match format {
-		DataFormat::Binary => {
-			loop {
-				let mut bytes = if first_read > 0 {first_bytes.clone()} else {vec![0; READAHEAD_BYTES]};
-				let n = if first_read > 0 {
-					first_read
-				} else {
-					instream.read(&mut bytes).map_err(|_| "Error reading from the file/stream.")?
-				};
-				if n == 0 { break; }
-				first_read = 0;
-				let s = PayloadInfo::from(&bytes).map_err(|e| format!("Invalid RLP in the file/stream: {:?}", e))?.total();
-				bytes.resize(s, 0);
-				instream.read_exact(&mut bytes[n..]).map_err(|_| "Error reading from the file/stream.")?;
-				do_import(bytes)?;
+	let mut progress = ImportProgress::new(cmd.file_path.as_ref().map(|f| &f[..]), size, read);
+	let checkpoint = if cmd.resume { progress.load()? } else { None };
+	if let Some(ref checkpoint) = checkpoint {
+		info!("Skipping {} blocks imported before the checkpoint", checkpoint.blocks);
+	}
+	let mut blocks = 0u64;
+
+	{
+		let mut import_next = |bytes: Vec<u8>| -> Result<(), String> {
+			blocks += 1;
+			if let Some(ref checkpoint) = checkpoint {
+				if blocks < checkpoint.blocks {
+					return Ok(());
+				}
+				if blocks == checkpoint.blocks {
+					if bytes.sha3() != checkpoint.hash {
+						return Err("The file doesn't match the import checkpoint, import it without --resume".into());
+					}
+					progress.resumed(blocks);
+					return Ok(());
+				}
 			}
-		}
-		DataFormat::Hex => {
-			for line in BufReader::new(instream).lines() {
-				let s = line.map_err(|_| "Error reading from the file/stream.")?;
-				let s = if first_read > 0 {from_utf8(&first_bytes).unwrap().to_owned() + &(s[..])} else {s};
-				first_read = 0;
-				let bytes = s.from_hex().map_err(|_| "Invalid hex in file/stream.")?;
-				do_import(bytes)?;
+
+			let hash = if blocks % CHECKPOINT_BLOCKS == 0 { Some(bytes.sha3()) } else { None };
+			do_import(bytes)?;
+			if let Some(hash) = hash {
+				// everything read so far must be in the database before it's skipped on resume.
+				client.flush_queue();
+				progress.save(&Checkpoint { blocks: blocks, hash: hash })?;
 			}
+			progress.report(blocks);
+			Ok(())
+		};
+
+		match format {
+			DataFormat::Binary => {
+				loop {
+					let mut bytes = if first_read > 0 {first_bytes.clone()} else {vec![0; READAHEAD_BYTES]};
+					let n = if first_read > 0 {
+						first_read
+					} else {
+						instream.read(&mut bytes).map_err(|_| "Error reading from the file/stream.")?
+					};
+					if n == 0 { break; }
+					first_read = 0;
+					let s = PayloadInfo::from(&bytes).map_err(|e| format!("Invalid RLP in the file/stream: {:?}", e))?.total();
+					bytes.resize(s, 0);
+					instream.read_exact(&mut bytes[n..]).map_err(|_| "Error reading from the file/stream.")?;
+					import_next(bytes)?;
+				}
+			}
+			DataFormat::Hex => {
+				for line in BufReader::new(instream).lines() {
+					let s = line.map_err(|_| "Error reading from the file/stream.")?;
+					let s = if first_read > 0 {from_utf8(&first_bytes).unwrap().to_owned() + &(s[..])} else {s};
+					first_read = 0;
+					let bytes = s.from_hex().map_err(|_| "Invalid hex in file/stream.")?;
+					import_next(bytes)?;
+				}
+			}
+			DataFormat::Json | DataFormat::Csv => return Err("Only hex and binary blocks can be imported".into()),
 		}
 	}
 	client.flush_queue();
+	if checkpoint.map_or(false, |c| c.blocks > blocks) {
+		return Err("The file is shorter than the import checkpoint".into());
+	}
+	progress.clear();
 
 	// save user defaults
 	user_defaults.pruning = algorithm;
@@ -405,28 +525,49 @@ fn execute_export(cmd: ExportBlockchain) -> Result<(), String> {
 	)?;
 	let panic_handler = PanicHandler::new_in_arc();
 	let format = cmd.format.unwrap_or_default();
+	if format == DataFormat::Json || format == DataFormat::Csv {
+		return Err("Blocks can only be exported in hex or binary format".into());
+	}
 
 	panic_handler.forward_from(&service);
 	let client = service.client();
 
-	let mut out: Box<io::Write> = match cmd.file_path {
-		Some(f) => Box::new(fs::File::create(&f).map_err(|_| format!("Cannot write to file given: {}", f))?),
-		None => Box::new(io::stdout()),
-	};
-
 	let from = client.block_number(cmd.from_block).ok_or("From block could not be found")?;
 	let to = client.block_number(cmd.to_block).ok_or("To block could not be found")?;
 
-	for i in from..(to + 1) {
+	let mut writer = ExportWriter::new(cmd.file_path, cmd.compression, from, cmd.blocks_per_file);
+	let mut start = from;
+	if cmd.resume {
+		match writer.progress()? {
+			Some(ref progress) if progress.from != from => {
+				return Err(format!("Previous export started at block #{}, use the same --from to resume it", progress.from));
+			},
+			Some(progress) => {
+				info!("Resuming export after block #{}", progress.last);
+				start = progress.last + 1;
+				// the block may start a new file, which is created when writing it.
+				if start <= to && writer.file_path(start) == writer.file_path(progress.last) {
+					writer.open(start, Some(progress.offset))?;
+				}
+			},
+			None => info!("No previous export found, starting from block #{}", from),
+		}
+	}
+
+	for i in start..(to + 1) {
 		if i % 10000 == 0 {
 			info!("#{}", i);
 		}
 		let b = client.block(BlockId::Number(i)).ok_or("Error exporting incomplete chain")?.into_inner();
 		match format {
-			DataFormat::Binary => { out.write(&b).expect("Couldn't write to stream."); }
-			DataFormat::Hex => { out.write_fmt(format_args!("{}", b.pretty())).expect("Couldn't write to stream."); }
+			DataFormat::Binary => writer.write_block(i, &b)?,
+			DataFormat::Hex => writer.write_block(i, b.pretty().as_bytes())?,
+			DataFormat::Json | DataFormat::Csv => unreachable!(),
 		}
 	}
+	if start <= to {
+		writer.finish(to)?;
+	}
 
 	info!("Export completed.");
 	Ok(())
@@ -459,9 +600,15 @@ fn execute_export_state(cmd: ExportState) -> Result<(), String> {
 
 	let mut last: Option<Address> = None;
 	let at = cmd.at;
+	let format = cmd.format.unwrap_or(DataFormat::Json);
+	if format == DataFormat::Csv {
+		return Err("CSV format is only supported by receipts and traces export".into());
+	}
 	let mut i = 0usize;
 
-	out.write_fmt(format_args!("{{ \"state\": {{", )).expect("Couldn't write to stream.");
+	if format == DataFormat::Json {
+		out.write_fmt(format_args!("{{ \"state\": {{", )).expect("Couldn't write to stream.");
+	}
 	loop {
 		let accounts = client.list_accounts(at, last.as_ref(), 1000).ok_or("Specified block not found")?;
 		if accounts.is_empty() {
@@ -469,59 +616,230 @@ fn execute_export_state(cmd: ExportState) -> Result<(), String> {
 		}
 
 		for account in accounts.into_iter() {
+			last = Some(account);
+			// accounts are listed in the order of their hashes, so the range is checked for each of them.
+			if cmd.min_address.map_or(false, |m| account < m) || cmd.max_address.map_or(false, |m| account > m) {
+				continue; //filtered out
+			}
 			let balance = client.balance(&account, at).unwrap_or_else(U256::zero);
 			if cmd.min_balance.map_or(false, |m| balance < m) || cmd.max_balance.map_or(false, |m| balance > m) {
-				last = Some(account);
 				continue; //filtered out
 			}
-
-			if i != 0 {
-				out.write(b",").expect("Write error");
-			}
-			out.write_fmt(format_args!("\n\"0x{}\": {{\"balance\": \"{:x}\", \"nonce\": \"{:x}\"", account.hex(), balance, client.nonce(&account, at).unwrap_or_else(U256::zero))).expect("Write error");
 			let code = client.code(&account, at).unwrap_or(None).unwrap_or_else(Vec::new);
-			if !code.is_empty() {
-				out.write_fmt(format_args!(", \"code_hash\": \"0x{}\"", code.sha3().hex())).expect("Write error");
-				if cmd.code {
-					out.write_fmt(format_args!(", \"code\": \"{}\"", code.to_hex())).expect("Write error");
-				}
+			if cmd.only_contracts && code.is_empty() {
+				continue; //filtered out
 			}
 			let storage_root = client.storage_root(&account, at).unwrap_or(::util::SHA3_NULL_RLP);
-			if storage_root != ::util::SHA3_NULL_RLP {
-				out.write_fmt(format_args!(", \"storage_root\": \"0x{}\"", storage_root.hex())).expect("Write error");
-				if cmd.storage {
-					out.write_fmt(format_args!(", \"storage\": {{")).expect("Write error");
-					let mut last_storage: Option<H256> = None;
-					loop {
-						let keys = client.list_storage(at, &account, last_storage.as_ref(), 1000).ok_or("Specified block not found")?;
-						if keys.is_empty() {
-							break;
-						}
+			if cmd.only_with_storage && storage_root == ::util::SHA3_NULL_RLP {
+				continue; //filtered out
+			}
+			let nonce = client.nonce(&account, at).unwrap_or_else(U256::zero);
 
-						for key in keys.into_iter() {
-							if last_storage.is_some() {
-								out.write(b",").expect("Write error");
-							}
-							out.write_fmt(format_args!("\n\t\"0x{}\": \"0x{}\"", key.hex(), client.storage_at(&account, &key, at).unwrap_or_else(Default::default).hex())).expect("Write error");
-							last_storage = Some(key);
+			match format {
+				DataFormat::Json => {
+					if i != 0 {
+						out.write(b",").expect("Write error");
+					}
+					out.write_fmt(format_args!("\n\"0x{}\": {{\"balance\": \"{:x}\", \"nonce\": \"{:x}\"", account.hex(), balance, nonce)).expect("Write error");
+					if !code.is_empty() {
+						out.write_fmt(format_args!(", \"code_hash\": \"0x{}\"", code.sha3().hex())).expect("Write error");
+						if cmd.code {
+							out.write_fmt(format_args!(", \"code\": \"{}\"", code.to_hex())).expect("Write error");
 						}
 					}
-					out.write(b"\n}").expect("Write error");
-				}
+					if storage_root != ::util::SHA3_NULL_RLP {
+						out.write_fmt(format_args!(", \"storage_root\": \"0x{}\"", storage_root.hex())).expect("Write error");
+						if cmd.storage {
+							out.write_fmt(format_args!(", \"storage\": {{")).expect("Write error");
+							let mut first = true;
+							for_each_storage(&*client, at, &account, |key, value| {
+								if !first {
+									out.write(b",").expect("Write error");
+								}
+								first = false;
+								out.write_fmt(format_args!("\n\t\"0x{}\": \"0x{}\"", key.hex(), value.hex())).expect("Write error");
+							})?;
+							out.write(b"\n}").expect("Write error");
+						}
+					}
+					out.write(b"}").expect("Write error");
+				},
+				DataFormat::Csv => unreachable!(),
+				DataFormat::Binary | DataFormat::Hex => {
+					// [address, balance, nonce, code, [[key, value], ...]], storage of a single account is kept in memory.
+					let mut storage = Vec::new();
+					if cmd.storage && storage_root != ::util::SHA3_NULL_RLP {
+						for_each_storage(&*client, at, &account, |key, value| storage.push((key, value)))?;
+					}
+					let mut rlp = RlpStream::new_list(5);
+					rlp.append(&account).append(&balance).append(&nonce);
+					if cmd.code { rlp.append(&code); } else { rlp.append_empty_data(); }
+					rlp.begin_list(storage.len());
+					for (key, value) in storage {
+						rlp.begin_list(2).append(&key).append(&value);
+					}
+					let bytes = rlp.out();
+					match format {
+						DataFormat::Hex => { out.write_fmt(format_args!("{}\n", bytes.to_hex())).expect("Write error"); }
+						_ => { out.write(&bytes).expect("Write error"); }
+					}
+				},
 			}
-			out.write(b"}").expect("Write error");
 			i += 1;
 			if i % 10000 == 0 {
 				info!("Account #{}", i);
 			}
-			last = Some(account);
 		}
 	}
-	out.write_fmt(format_args!("\n}}}}")).expect("Write error");
+	if format == DataFormat::Json {
+		out.write_fmt(format_args!("\n}}}}")).expect("Write error");
+	}
 	info!("Export completed.");
 	Ok(())
 }
 
+/// Calls `f` with every storage key and value of the account.
+fn for_each_storage<C, F>(client: &C, at: BlockId, account: &Address, mut f: F) -> Result<(), String>
+	where C: BlockChainClient + ?Sized, F: FnMut(H256, H256)
+{
+	let mut last_storage: Option<H256> = None;
+	loop {
+		let keys = client.list_storage(at, account, last_storage.as_ref(), 1000).ok_or("Specified block not found")?;
+		if keys.is_empty() {
+			return Ok(());
+		}
+
+		for key in keys.into_iter() {
+			f(key, client.storage_at(account, &key, at).unwrap_or_else(Default::default));
+			last_storage = Some(key);
+		}
+	}
+}
+
+fn execute_export_table(cmd: ExportTable) -> Result<(), String> {
+	// Setup panic handler
+	let service = start_client(
+		cmd.dirs,
+		cmd.spec,
+		cmd.pruning,
+		cmd.pruning_history,
+		cmd.pruning_memory,
+		cmd.tracing,
+		cmd.fat_db,
+		cmd.compaction,
+		cmd.wal,
+		cmd.cache_config
+	)?;
+
+	let panic_handler = PanicHandler::new_in_arc();
+
+	panic_handler.forward_from(&service);
+	let client = service.client();
+
+	let mut out: Box<io::Write> = match cmd.file_path {
+		Some(ref f) => {
+			let schema_path = format!("{}.schema.json", f);
+			fs::File::create(&schema_path).and_then(|mut file| file.write_all(cmd.table.schema().as_bytes()))
+				.map_err(|e| format!("Cannot write schema to {}: {}", schema_path, e))?;
+			Box::new(io::BufWriter::new(fs::File::create(f).map_err(|_| format!("Cannot write to file given: {}", f))?))
+		},
+		None => Box::new(io::stdout()),
+	};
+	let write_error = |e: io::Error| format!("Cannot write {}: {}", cmd.table.name(), e);
+
+	let from = client.block_number(cmd.from_block).ok_or("From block could not be found")?;
+	let to = client.block_number(cmd.to_block).ok_or("To block could not be found")?;
+
+	let mut ledger = None;
+	let blocks: Box<Iterator<Item = u64>> = match cmd.table {
+		Table::Transfers(address) => {
+			let (blocks, balance) = transfer_blocks(&*client, &address, from, to)?;
+			ledger = Some(Ledger::new(address, balance));
+			Box::new(blocks.into_iter())
+		},
+		_ => Box::new(from..(to + 1)),
+	};
+
+	writeln!(out, "{}", cmd.table.header()).map_err(&write_error)?;
+	for i in blocks {
+		if i % 10000 == 0 {
+			info!("#{}", i);
+		}
+		match cmd.table {
+			Table::Receipts => {
+				let receipts = client.localized_block_receipts(BlockId::Number(i)).ok_or("Error exporting incomplete chain")?;
+				for receipt in &receipts {
+					writeln!(out, "{}", receipt_row(receipt)).map_err(&write_error)?;
+				}
+			},
+			Table::Traces => {
+				let traces = client.block_traces(BlockId::Number(i))
+					.ok_or_else(|| format!("Traces of block #{} not found. Tracing must be enabled to export traces.", i))?;
+				for trace in &traces {
+					writeln!(out, "{}", trace_row(trace)).map_err(&write_error)?;
+				}
+			},
+			Table::Transfers(_) => {
+				let ledger = ledger.as_mut().expect("ledger is created for transfers above; qed");
+				for row in transfer_rows(&*client, ledger, i)? {
+					writeln!(out, "{}", row).map_err(&write_error)?;
+				}
+			},
+		}
+	}
+	out.flush().map_err(&write_error)?;
+
+	info!("Export completed.");
+	Ok(())
+}
+
+/// Blocks with traces sending value from or to the address, including the last block
+/// to account for untraced balance changes, and the balance before the first block.
+fn transfer_blocks(client: &BlockChainClient, address: &Address, from: u64, to: u64) -> Result<(BTreeSet<u64>, U256), String> {
+	let balance = match from {
+		0 => U256::zero(),
+		n => client.balance(address, BlockId::Number(n - 1))
+			.ok_or_else(|| format!("State at block #{} is not available. Exporting transfers requires --pruning archive.", n - 1))?,
+	};
+
+	let mut blocks = BTreeSet::new();
+	blocks.insert(to);
+	for &(ref from_address, ref to_address) in &[(vec![*address], vec![]), (vec![], vec![*address])] {
+		let filter = TraceFilter {
+			range: BlockId::Number(from)..BlockId::Number(to),
+			from_address: from_address.clone(),
+			to_address: to_address.clone(),
+		};
+		let traces = client.filter_traces(filter).ok_or("Tracing must be enabled to export transfers.")?;
+		blocks.extend(traces.into_iter().map(|t| t.block_number));
+	}
+	Ok((blocks, balance))
+}
+
+/// Transfer lines of the ledger's address in the block, followed by a correction to the balance in the state.
+fn transfer_rows(client: &BlockChainClient, ledger: &mut Ledger, number: u64) -> Result<Vec<String>, String> {
+	let id = BlockId::Number(number);
+	let traces = client.block_traces(id)
+		.ok_or_else(|| format!("Traces of block #{} not found. Tracing must be enabled to export transfers.", number))?;
+	let (block, receipts) = match (client.block(id), client.localized_block_receipts(id)) {
+		(Some(block), Some(receipts)) => (block, receipts),
+		_ => return Err("Error exporting incomplete chain".into()),
+	};
+	let fees = block.view().localized_transactions().into_iter()
+		.zip(receipts.iter())
+		.filter_map(|(mut tx, receipt)| match tx.sender() == *ledger.address() {
+			true => Some((tx.hash(), receipt.gas_used * tx.gas_price)),
+			false => None,
+		})
+		.collect::<HashMap<_, _>>();
+
+	let mut rows = ledger.block_rows(&traces, &fees, &block.header_view().author());
+	if let Some(balance) = client.balance(ledger.address(), id) {
+		rows.extend(ledger.reconcile(number, balance));
+	}
+	Ok(rows)
+}
+
 pub fn kill_db(cmd: KillBlockchain) -> Result<(), String> {
 	let spec = cmd.spec.spec()?;
 	let genesis_hash = spec.genesis_header().hash();
@@ -537,12 +855,22 @@ pub fn kill_db(cmd: KillBlockchain) -> Result<(), String> {
 
 #[cfg(test)]
 mod test {
-	use super::DataFormat;
+	use super::{DataFormat, Compression};
 
 	#[test]
 	fn test_data_format_parsing() {
 		assert_eq!(DataFormat::Binary, "binary".parse().unwrap());
 		assert_eq!(DataFormat::Binary, "bin".parse().unwrap());
 		assert_eq!(DataFormat::Hex, "hex".parse().unwrap());
+		assert_eq!(DataFormat::Json, "json".parse().unwrap());
+		assert_eq!(DataFormat::Csv, "csv".parse().unwrap());
+	}
+
+	#[test]
+	fn test_compression_parsing() {
+		assert_eq!(Compression::Gzip, "gzip".parse().unwrap());
+		assert_eq!(Compression::Gzip, "gz".parse().unwrap());
+		assert_eq!(Compression::None, "none".parse().unwrap());
+		assert!("zstd".parse::<Compression>().is_err());
 	}
 }
@@ -108,6 +108,10 @@ Usage:
 				color: self.flag_no_color || cfg!(windows),
 				mode: self.flag_logging.clone(),
 				file: self.flag_log_file.clone(),
+				rotation: None,
+				syslog: None,
+				event_log: None,
+				format: Default::default(),
 			}
 		}
 	}
@@ -19,9 +19,22 @@ use std::cmp::max;
 const MIN_BC_CACHE_MB: u32 = 4;
 const MIN_DB_CACHE_MB: u32 = 2;
 const MIN_BLOCK_QUEUE_SIZE_LIMIT_MB: u32 = 16;
-const DEFAULT_BLOCK_QUEUE_SIZE_LIMIT_MB: u32 = 50;
+pub const DEFAULT_DB_CACHE_SIZE: u32 = 64;
+pub const DEFAULT_BC_CACHE_SIZE: u32 = 8;
+pub const DEFAULT_BLOCK_QUEUE_SIZE_LIMIT_MB: u32 = 50;
 const DEFAULT_TRACE_CACHE_SIZE: u32 = 20;
-const DEFAULT_STATE_CACHE_SIZE: u32 = 25;
+pub const DEFAULT_STATE_CACHE_SIZE: u32 = 25;
+pub const DEFAULT_TX_QUEUE_SIZE: usize = 1024;
+
+/// Estimated memory used by a single queued transaction, in KB.
+const TX_QUEUE_ENTRY_KB: u32 = 2;
+
+// Shares of the memory budget, in percent.
+const DB_SHARE: u32 = 50;
+const BC_SHARE: u32 = 10;
+const BLOCK_QUEUE_SHARE: u32 = 10;
+const STATE_SHARE: u32 = 20;
+const TX_QUEUE_SHARE: u32 = 10;
 
 /// Configuration for application cache sizes.
 /// All	values are represented in MB.
@@ -41,7 +54,7 @@ pub struct CacheConfig {
 
 impl Default for CacheConfig {
 	fn default() -> Self {
-		CacheConfig::new(64, 8, DEFAULT_BLOCK_QUEUE_SIZE_LIMIT_MB, DEFAULT_STATE_CACHE_SIZE)
+		CacheConfig::new(DEFAULT_DB_CACHE_SIZE, DEFAULT_BC_CACHE_SIZE, DEFAULT_BLOCK_QUEUE_SIZE_LIMIT_MB, DEFAULT_STATE_CACHE_SIZE)
 	}
 }
 
@@ -104,9 +117,60 @@ impl CacheConfig {
 	}
 }
 
+/// Total memory to use for caches and queues, together with the sizes given explicitly.
+/// All values are represented in MB, except the transaction queue limit.
+#[derive(Debug, Default, PartialEq)]
+pub struct MemoryBudget {
+	/// Total size.
+	pub total: u32,
+	/// Size of rocksDB cache.
+	pub db: Option<u32>,
+	/// Size of blockchain cache.
+	pub blockchain: Option<u32>,
+	/// Size of block queue.
+	pub queue: Option<u32>,
+	/// Size of the state cache.
+	pub state: Option<u32>,
+	/// Maximal number of queued transactions.
+	pub tx_queue: Option<usize>,
+}
+
+impl MemoryBudget {
+	/// Splits the budget into cache sizes and the transaction queue limit.
+	/// Sizes given explicitly are kept and the rest of the budget is shared
+	/// by the other caches in fixed proportions.
+	pub fn split(&self) -> Result<(CacheConfig, usize), String> {
+		let tx_queue = self.tx_queue.map(|limit| (limit as u64 * TX_QUEUE_ENTRY_KB as u64 / 1024) as u32);
+		let parts = [
+			(self.db, DB_SHARE),
+			(self.blockchain, BC_SHARE),
+			(self.queue, BLOCK_QUEUE_SHARE),
+			(self.state, STATE_SHARE),
+			(tx_queue, TX_QUEUE_SHARE),
+		];
+		let given = parts.iter().filter_map(|&(size, _)| size).fold(0u64, |sum, size| sum + size as u64);
+		if given > self.total as u64 {
+			return Err(format!("Memory budget of {} MB is less than the {} MB given to particular caches.", self.total, given));
+		}
+
+		let remaining = self.total as u64 - given;
+		let shares = parts.iter().filter(|&&(size, _)| size.is_none()).fold(0, |sum, &(_, share)| sum + share);
+		let size = |size: Option<u32>, share: u32| size.unwrap_or_else(|| (remaining * share as u64 / shares as u64) as u32);
+
+		let cache = CacheConfig::new(
+			size(self.db, DB_SHARE),
+			size(self.blockchain, BC_SHARE),
+			size(self.queue, BLOCK_QUEUE_SHARE),
+			size(self.state, STATE_SHARE),
+		);
+		let tx_queue = self.tx_queue.unwrap_or_else(|| (size(None, TX_QUEUE_SHARE) * 1024 / TX_QUEUE_ENTRY_KB) as usize);
+		Ok((cache, tx_queue))
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use super::CacheConfig;
+	use super::{CacheConfig, MemoryBudget};
 
 	#[test]
 	fn test_cache_config_constructor() {
@@ -126,6 +190,38 @@ mod tests {
 		assert_eq!(config.db_state_cache_size(), 210);
 	}
 
+	#[test]
+	fn test_memory_budget_split() {
+		let budget = MemoryBudget {
+			total: 1000,
+			..Default::default()
+		};
+		let (config, tx_queue) = budget.split().unwrap();
+		assert_eq!(config, CacheConfig::new(500, 100, 100, 200));
+		assert_eq!(tx_queue, 51_200);
+	}
+
+	#[test]
+	fn test_memory_budget_split_with_overrides() {
+		let budget = MemoryBudget {
+			total: 1000,
+			db: Some(300),
+			tx_queue: Some(51_200),
+			..Default::default()
+		};
+		let (config, tx_queue) = budget.split().unwrap();
+		// the remaining 600 MB are shared by the blockchain, block queue and state caches
+		assert_eq!(config, CacheConfig::new(300, 150, 150, 300));
+		assert_eq!(tx_queue, 51_200);
+
+		let too_small = MemoryBudget {
+			total: 100,
+			db: Some(300),
+			..Default::default()
+		};
+		assert!(too_small.split().is_err());
+	}
+
 	#[test]
 	fn test_cache_config_default() {
 		assert_eq!(CacheConfig::default(),
@@ -0,0 +1,189 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `--check-config`: validation of the configuration without starting the node.
+
+use std::fmt;
+use std::path::Path;
+use configuration::{Configuration, Cmd};
+use ethcore_logger::Config as LogConfig;
+use helpers::passwords_from_files;
+use run::RunCmd;
+use service::ServiceCmd;
+
+/// A configuration problem found by the check.
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+	/// Part of the configuration, e.g. `chain` or `paths`.
+	pub section: &'static str,
+	pub message: String,
+}
+
+impl Problem {
+	fn new<S: Into<String>>(section: &'static str, message: S) -> Self {
+		Problem {
+			section: section,
+			message: message.into(),
+		}
+	}
+}
+
+impl fmt::Display for Problem {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}: {}", self.section, self.message)
+	}
+}
+
+#[cfg(unix)]
+fn is_writable(path: &Path) -> bool {
+	use std::ffi::CString;
+	use std::os::unix::ffi::OsStrExt;
+
+	match CString::new(path.as_os_str().as_bytes()) {
+		Ok(path) => unsafe { ::libc::access(path.as_ptr(), ::libc::W_OK) == 0 },
+		Err(_) => false,
+	}
+}
+
+#[cfg(not(unix))]
+fn is_writable(path: &Path) -> bool {
+	path.metadata().map(|m| !m.permissions().readonly()).unwrap_or(false)
+}
+
+/// Check that the directory is writable or can be created.
+fn check_writable_dir(path: &str) -> Result<(), String> {
+	let mut existing = Path::new(path);
+	while !existing.exists() {
+		existing = match existing.parent() {
+			Some(parent) if parent.as_os_str().is_empty() => Path::new("."),
+			Some(parent) => parent,
+			None => return Err(format!("{} can't be created", path)),
+		};
+	}
+
+	match (existing.is_dir(), is_writable(existing)) {
+		(false, _) => Err(format!("{} is not a directory", existing.display())),
+		(true, false) if existing == Path::new(path) => Err(format!("{} is not writable", path)),
+		(true, false) => Err(format!("{} can't be created, {} is not writable", path, existing.display())),
+		(true, true) => Ok(()),
+	}
+}
+
+fn check_log_file(logger: &LogConfig, problems: &mut Vec<Problem>) {
+	let file = match logger.file {
+		Some(ref file) => Path::new(file),
+		None => return,
+	};
+
+	let result = match (file.exists(), file.parent()) {
+		(true, _) if !is_writable(file) => Err(format!("Log file {} is not writable", file.display())),
+		(true, _) => Ok(()),
+		(false, Some(parent)) if !parent.as_os_str().is_empty() => check_writable_dir(&parent.to_string_lossy()),
+		(false, _) => check_writable_dir("."),
+	};
+	if let Err(e) = result {
+		problems.push(Problem::new("logging", e));
+	}
+}
+
+fn check_run(cmd: &RunCmd, problems: &mut Vec<Problem>) {
+	if let Err(e) = cmd.spec.spec() {
+		problems.push(Problem::new("chain", e));
+	}
+
+	let dirs = vec![
+		(true, &cmd.dirs.base),
+		(true, &cmd.dirs.db),
+		(true, &cmd.dirs.keys),
+		(cmd.signer_conf.enabled, &cmd.dirs.signer),
+		(cmd.dapps_conf.enabled, &cmd.dirs.dapps),
+		(cmd.secretstore_conf.enabled, &cmd.dirs.secretstore),
+	];
+	for (_, dir) in dirs.into_iter().filter(|&(enabled, _)| enabled) {
+		if let Err(e) = check_writable_dir(dir) {
+			problems.push(Problem::new("paths", e));
+		}
+	}
+
+	if let Err(e) = passwords_from_files(&cmd.acc_conf.password_files) {
+		problems.push(Problem::new("account", e));
+	}
+}
+
+/// Problems of the configuration, empty if it's valid.
+pub fn check(conf: Configuration) -> Vec<Problem> {
+	let execute = match conf.into_command() {
+		Ok(execute) => execute,
+		Err(e) => return vec![Problem::new("options", e)],
+	};
+
+	let mut problems = Vec::new();
+	check_log_file(&execute.logger, &mut problems);
+	match execute.cmd {
+		Cmd::Run(ref cmd) | Cmd::Service(ServiceCmd::Run { ref cmd, .. }) => check_run(cmd, &mut problems),
+		_ => {},
+	}
+	problems
+}
+
+/// Validate the configuration. Problems are reported one per line as `section: message`.
+pub fn execute(conf: Configuration) -> Result<String, String> {
+	let problems = check(conf);
+	match problems.is_empty() {
+		true => Ok("Configuration is valid.".into()),
+		false => Err(problems.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n")),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use devtools::RandomTempPath;
+	use configuration::Configuration;
+	use super::{check, check_writable_dir};
+
+	#[test]
+	fn should_check_writable_dirs() {
+		let temp = RandomTempPath::create_dir();
+		let dir = temp.as_str().to_owned();
+		let file = format!("{}/file", dir);
+		File::create(&file).unwrap();
+
+		assert_eq!(check_writable_dir(&dir), Ok(()));
+		assert_eq!(check_writable_dir(&format!("{}/new/nested", dir)), Ok(()));
+		assert!(check_writable_dir(&file).is_err());
+		assert!(check_writable_dir(&format!("{}/sub", file)).is_err());
+	}
+
+	#[test]
+	fn should_report_problems_by_section() {
+		let temp = RandomTempPath::create_dir();
+		let base = temp.as_str().to_owned();
+
+		let conf = Configuration::parse(&["parity", "--no-config", "--base-path", base.as_str()], None).unwrap();
+		assert_eq!(check(conf), vec![]);
+
+		let conf = Configuration::parse(&["parity", "--no-config", "--base-path", base.as_str(), "--shutdown-timeout", "0"], None).unwrap();
+		assert_eq!(check(conf).iter().map(|p| p.section).collect::<Vec<_>>(), vec!["options"]);
+
+		let conf = Configuration::parse(&[
+			"parity", "--no-config", "--base-path", base.as_str(),
+			"--chain", "/nonexistent/spec.json",
+			"--password", "/nonexistent/password",
+		], None).unwrap();
+		assert_eq!(check(conf).iter().map(|p| p.section).collect::<Vec<_>>(), vec!["chain", "account"]);
+	}
+}
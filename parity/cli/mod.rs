@@ -29,19 +29,31 @@ usage! {
 		cmd_export: bool,
 		cmd_blocks: bool,
 		cmd_state: bool,
+		cmd_receipts: bool,
+		cmd_traces: bool,
+		cmd_transfers: bool,
 		cmd_import: bool,
 		cmd_signer: bool,
 		cmd_new_token: bool,
 		cmd_sign: bool,
+		cmd_approve: bool,
 		cmd_reject: bool,
 		cmd_snapshot: bool,
 		cmd_restore: bool,
+		cmd_verify: bool,
 		cmd_ui: bool,
 		cmd_dapp: bool,
 		cmd_tools: bool,
 		cmd_hash: bool,
 		cmd_kill: bool,
 		cmd_db: bool,
+		cmd_service: bool,
+		cmd_install: bool,
+		cmd_uninstall: bool,
+		cmd_run: bool,
+		cmd_config: bool,
+		cmd_dump: bool,
+		cmd_encrypt: bool,
 
 		// Arguments
 		arg_pid_file: String,
@@ -80,12 +92,34 @@ usage! {
 		// -- Miscellaneous Options
 		flag_version: bool,
 		flag_no_config: bool,
+		flag_profile: Option<String>,
+		flag_config_env: Option<String>,
+		flag_supervise: bool,
+		flag_check_config: bool,
+		flag_config_key_file: Option<String>,
+		flag_geth_compat_config: Option<String>,
+		flag_config_public_key: Option<String>,
+		flag_config_signing_key: Option<String>,
+		flag_config_valid_for: Option<u64>,
 	}
 	{
 		// -- Operating Options
 		flag_mode: String = "last", or |c: &Config| otry!(c.parity).mode.clone(),
 		flag_mode_timeout: u64 = 300u64, or |c: &Config| otry!(c.parity).mode_timeout.clone(),
 		flag_mode_alarm: u64 = 3600u64, or |c: &Config| otry!(c.parity).mode_alarm.clone(),
+		flag_wake_on_rpc: Option<String> = None,
+			or |c: &Config| otry!(c.parity).wake_on_rpc.as_ref().map(|vec| Some(vec.join(","))),
+		flag_nice: Option<i32> = None,
+			or |c: &Config| otry!(c.parity).nice.clone().map(Some),
+		flag_ionice: Option<String> = None,
+			or |c: &Config| otry!(c.parity).ionice.clone().map(Some),
+		flag_service_name: String = "parity", or |c: &Config| otry!(c.parity).service_name.clone(),
+		flag_shutdown_timeout: Option<u64> = None,
+			or |c: &Config| otry!(c.parity).shutdown_timeout.clone().map(Some),
+		flag_supervise_max_restarts: usize = 5usize,
+			or |c: &Config| otry!(c.parity).supervise_max_restarts.clone(),
+		flag_supervise_window: u64 = 600u64,
+			or |c: &Config| otry!(c.parity).supervise_window.clone(),
 		flag_auto_update: String = "critical", or |c: &Config| otry!(c.parity).auto_update.clone(),
 		flag_release_track: String = "current", or |c: &Config| otry!(c.parity).release_track.clone(),
 		flag_public_node: bool = false, or |c: &Config| otry!(c.parity).public_node.clone(),
@@ -95,6 +129,10 @@ usage! {
 		flag_keys_path: String = "$BASE/keys", or |c: &Config| otry!(c.parity).keys_path.clone(),
 		flag_identity: String = "", or |c: &Config| otry!(c.parity).identity.clone(),
 		flag_light: bool = false, or |c: &Config| otry!(c.parity).light,
+		flag_hybrid: bool = false, or |c: &Config| otry!(c.parity).hybrid,
+		flag_hybrid_rpc_port: u16 = 8549u16, or |c: &Config| otry!(c.parity).hybrid_rpc_port.clone(),
+		flag_hybrid_port: u16 = 30304u16, or |c: &Config| otry!(c.parity).hybrid_port.clone(),
+		flag_hybrid_full_node: bool = false, or |_| None,
 
 		// -- Account Options
 		flag_unlock: Option<String> = None,
@@ -117,6 +155,8 @@ usage! {
 			or |c: &Config| otry!(c.ui).interface.clone(),
 		flag_ui_path: String = "$BASE/signer",
 			or |c: &Config| otry!(c.ui).path.clone(),
+		flag_signer_policy: Option<String> = None,
+			or |c: &Config| otry!(c.ui).signer_policy.clone().map(Some),
 		// NOTE [todr] For security reasons don't put this to config files
 		flag_ui_no_validation: bool = false, or |_| None,
 
@@ -131,12 +171,40 @@ usage! {
 			or |c: &Config| otry!(c.network).max_peers.clone(),
 		flag_max_pending_peers: u16 = 64u16,
 			or |c: &Config| otry!(c.network).max_pending_peers.clone(),
+		flag_max_peers_per_ip: u16 = 0u16,
+			or |c: &Config| otry!(c.network).max_peers_per_ip.clone(),
+		flag_max_peers_per_subnet: u16 = 0u16,
+			or |c: &Config| otry!(c.network).max_peers_per_subnet.clone(),
+		flag_protocol_peers: Option<String> = None,
+			or |c: &Config| otry!(c.network).protocol_peers.as_ref().map(|vec| Some(vec.join(","))),
+		flag_max_upload: Option<u64> = None,
+			or |c: &Config| otry!(c.network).max_upload.map(Some),
+		flag_max_download: Option<u64> = None,
+			or |c: &Config| otry!(c.network).max_download.map(Some),
 		flag_snapshot_peers: u16 = 0u16,
 			or |c: &Config| otry!(c.network).snapshot_peers.clone(),
+		flag_snapshot_preferred_peers: Option<String> = None,
+			or |c: &Config| otry!(c.network).snapshot_preferred_peers.as_ref().map(|vec| Some(vec.join(","))),
+		flag_snapshot_download_ahead: usize = 3usize,
+			or |c: &Config| otry!(c.network).snapshot_download_ahead.clone(),
+		flag_snapshot_download_peers: Option<usize> = None,
+			or |c: &Config| otry!(c.network).snapshot_download_peers.map(Some),
+		flag_snapshot_chunks_per_peer: usize = 1usize,
+			or |c: &Config| otry!(c.network).snapshot_chunks_per_peer.clone(),
+		flag_snapshot_chunk_timeout: u64 = 120u64,
+			or |c: &Config| otry!(c.network).snapshot_chunk_timeout.clone(),
+		flag_snapshot_chunk_retries: Option<usize> = None,
+			or |c: &Config| otry!(c.network).snapshot_chunk_retries.map(Some),
+		flag_warp_from: Option<String> = None,
+			or |c: &Config| otry!(c.network).warp_from.clone().map(Some),
+		flag_warp_from_hash: Option<String> = None,
+			or |c: &Config| otry!(c.network).warp_from_hash.clone().map(Some),
 		flag_nat: String = "any",
 			or |c: &Config| otry!(c.network).nat.clone(),
 		flag_allow_ips: String = "all",
 			or |c: &Config| otry!(c.network).allow_ips.clone(),
+		flag_deny_ips: Option<String> = None,
+			or |c: &Config| otry!(c.network).deny_ips.as_ref().map(|vec| Some(vec.join(","))),
 		flag_network_id: Option<u64> = None,
 			or |c: &Config| otry!(c.network).id.clone().map(Some),
 		flag_bootnodes: Option<String> = None,
@@ -150,8 +218,76 @@ usage! {
 		flag_reserved_only: bool = false,
 			or |c: &Config| otry!(c.network).reserved_only.clone(),
 		flag_no_ancient_blocks: bool = false, or |_| None,
+		flag_ancient_blocks_rate: Option<usize> = None,
+			or |c: &Config| otry!(c.network).ancient_blocks_rate.map(Some),
 		flag_no_serve_light: bool = false,
 			or |c: &Config| otry!(c.network).no_serve_light.clone(),
+		flag_pip_peers: Option<usize> = None,
+			or |c: &Config| otry!(c.network).pip_peers.map(Some),
+		flag_pip_credit_limit: u64 = 50_000_000u64,
+			or |c: &Config| otry!(c.network).pip_credit_limit.clone(),
+		flag_pip_recharge_rate: u64 = 100_000u64,
+			or |c: &Config| otry!(c.network).pip_recharge_rate.clone(),
+		flag_pip_request_costs: Option<String> = None,
+			or |c: &Config| otry!(c.network).pip_request_costs.clone().map(Some),
+		flag_pip_ws_port: Option<u16> = None,
+			or |c: &Config| otry!(c.network).pip_ws_port.map(Some),
+		flag_serve_les: bool = false,
+			or |c: &Config| otry!(c.network).serve_les.clone(),
+		flag_les_peers: u16 = 10u16,
+			or |c: &Config| otry!(c.network).les_peers.clone(),
+		flag_les_buffer_limit: u64 = 300_000_000u64,
+			or |c: &Config| otry!(c.network).les_buffer_limit.clone(),
+		flag_les_recharge_rate: u64 = 50_000u64,
+			or |c: &Config| otry!(c.network).les_recharge_rate.clone(),
+		flag_warp_serve_bandwidth: Option<usize> = None,
+			or |c: &Config| otry!(c.network).warp_serve_bandwidth.map(Some),
+		flag_warp_serve_peers: Option<usize> = None,
+			or |c: &Config| otry!(c.network).warp_serve_peers.map(Some),
+		flag_warp_serve_hours: Option<String> = None,
+			or |c: &Config| otry!(c.network).warp_serve_hours.clone().map(Some),
+		flag_checkpoint: Option<String> = None,
+			or |c: &Config| otry!(c.network).checkpoint.clone().map(Some),
+		flag_no_checkpoint: bool = false,
+			or |c: &Config| otry!(c.network).no_checkpoint.clone(),
+		flag_light_checkpoint: Option<String> = None,
+			or |c: &Config| otry!(c.network).light_checkpoint.clone().map(Some),
+		flag_light_checkpoint_key: Option<String> = None,
+			or |c: &Config| otry!(c.network).light_checkpoint_key.clone().map(Some),
+		flag_no_light_checkpoint: bool = false,
+			or |c: &Config| otry!(c.network).no_light_checkpoint.clone(),
+		flag_no_persistent_light_cache: bool = false,
+			or |c: &Config| otry!(c.network).no_persistent_light_cache.clone(),
+		flag_light_fallback_url: Option<String> = None,
+			or |c: &Config| otry!(c.network).light_fallback_url.clone().map(Some),
+		flag_light_ws_servers: Option<String> = None,
+			or |c: &Config| otry!(c.network).light_ws_servers.as_ref().map(|vec| Some(vec.join(","))),
+		flag_on_demand_timeouts: Option<String> = None,
+			or |c: &Config| otry!(c.network).on_demand_timeouts.clone().map(Some),
+		flag_on_demand_retries: usize = 5usize,
+			or |c: &Config| otry!(c.network).on_demand_retries.clone(),
+		flag_on_demand_peer_selection: String = "first",
+			or |c: &Config| otry!(c.network).on_demand_peer_selection.clone(),
+		flag_light_serving_peers: Option<String> = None,
+			or |c: &Config| otry!(c.network).light_serving_peers.as_ref().map(|vec| Some(vec.join(","))),
+		flag_light_serving_peers_only: bool = false,
+			or |c: &Config| otry!(c.network).light_serving_peers_only.clone(),
+		flag_light_relay_peers: Option<usize> = None,
+			or |c: &Config| otry!(c.network).light_relay_peers.map(Some),
+		flag_max_reorg_depth: Option<u64> = None,
+			or |c: &Config| otry!(c.network).max_reorg_depth.map(Some),
+		flag_reorg_alert_url: Option<String> = None,
+			or |c: &Config| otry!(c.network).reorg_alert_url.as_ref().map(|vec| Some(vec.join(","))),
+		flag_sync_schedule: Option<String> = None,
+			or |c: &Config| otry!(c.network).sync_schedule.clone().map(Some),
+		flag_proxy: Option<String> = None,
+			or |c: &Config| otry!(c.network).proxy.clone().map(Some),
+		flag_local_tx_delay: u64 = 0u64,
+			or |c: &Config| otry!(c.network).local_tx_delay.clone(),
+		flag_local_tx_jitter: u64 = 0u64,
+			or |c: &Config| otry!(c.network).local_tx_jitter.clone(),
+		flag_local_tx_relay_peers: Option<String> = None,
+			or |c: &Config| otry!(c.network).local_tx_relay_peers.as_ref().map(|vec| Some(vec.join(","))),
 
 		// -- API and Console Options
 		// RPC
@@ -167,8 +303,18 @@ usage! {
 			or |c: &Config| otry!(c.rpc).apis.as_ref().map(|vec| vec.join(",")),
 		flag_jsonrpc_hosts: String = "none",
 			or |c: &Config| otry!(c.rpc).hosts.as_ref().map(|vec| vec.join(",")),
+		flag_jsonrpc_api_cors: Option<String> = None,
+			or |c: &Config| otry!(c.rpc).api_cors.as_ref().map(|vec| Some(vec.join(";"))),
+		flag_jsonrpc_api_hosts: Option<String> = None,
+			or |c: &Config| otry!(c.rpc).api_hosts.as_ref().map(|vec| Some(vec.join(";"))),
 		flag_jsonrpc_threads: Option<usize> = None,
 			or |c: &Config| otry!(c.rpc).threads.map(Some),
+		flag_jsonrpc_max_batch_size: Option<usize> = None,
+			or |c: &Config| otry!(c.rpc).max_batch_size.map(Some),
+		flag_jsonrpc_max_request_size: Option<usize> = None,
+			or |c: &Config| otry!(c.rpc).max_request_size.map(Some),
+		flag_jsonrpc_max_response_size: Option<usize> = None,
+			or |c: &Config| otry!(c.rpc).max_response_size.map(Some),
 
 		// WS
 		flag_no_ws: bool = false,
@@ -177,7 +323,7 @@ usage! {
 			or |c: &Config| otry!(c.websockets).port.clone(),
 		flag_ws_interface: String  = "local",
 			or |c: &Config| otry!(c.websockets).interface.clone(),
-		flag_ws_apis: String = "web3,eth,net,parity,traces,rpc",
+		flag_ws_apis: String = "web3,eth,pubsub,net,parity,traces,rpc",
 			or |c: &Config| otry!(c.websockets).apis.as_ref().map(|vec| vec.join(",")),
 		flag_ws_origins: String = "none",
 			or |c: &Config| otry!(c.websockets).origins.as_ref().map(|vec| vec.join(",")),
@@ -189,7 +335,7 @@ usage! {
 			or |c: &Config| otry!(c.ipc).disable.clone(),
 		flag_ipc_path: String = "$BASE/jsonrpc.ipc",
 			or |c: &Config| otry!(c.ipc).path.clone(),
-		flag_ipc_apis: String = "web3,eth,net,parity,parity_accounts,traces,rpc",
+		flag_ipc_apis: String = "web3,eth,pubsub,net,parity,parity_accounts,traces,rpc",
 			or |c: &Config| otry!(c.ipc).apis.as_ref().map(|vec| vec.join(",")),
 
 		// DAPPS
@@ -228,11 +374,47 @@ usage! {
 		flag_ipfs_api_hosts: String = "none",
 			or |c: &Config| otry!(c.ipfs).hosts.as_ref().map(|vec| vec.join(",")),
 
+		// Health
+		flag_health_api: bool = false,
+			or |c: &Config| otry!(c.health).enable.clone(),
+		flag_health_api_port: u16 = 8547u16,
+			or |c: &Config| otry!(c.health).port.clone(),
+		flag_health_api_interface: String = "local",
+			or |c: &Config| otry!(c.health).interface.clone(),
+		flag_health_max_block_lag: u64 = 10u64,
+			or |c: &Config| otry!(c.health).max_block_lag.clone(),
+		flag_health_min_peers: u16 = 1u16,
+			or |c: &Config| otry!(c.health).min_peers.clone(),
+		flag_health_max_clock_drift: u64 = 10u64,
+			or |c: &Config| otry!(c.health).max_clock_drift.clone(),
+		flag_health_ntp_server: String = "pool.ntp.org:123",
+			or |c: &Config| otry!(c.health).ntp_server.clone(),
+
 		// -- Sealing/Mining Options
 		flag_author: Option<String> = None,
 			or |c: &Config| otry!(c.mining).author.clone().map(Some),
 		flag_engine_signer: Option<String> = None,
 			or |c: &Config| otry!(c.mining).engine_signer.clone().map(Some),
+		flag_engine_lock: Option<String> = None,
+			or |c: &Config| otry!(c.mining).engine_lock.clone().map(Some),
+		flag_engine_standby: bool = false,
+			or |c: &Config| otry!(c.mining).engine_standby.clone(),
+		flag_engine_lock_timeout: u64 = 60u64,
+			or |c: &Config| otry!(c.mining).engine_lock_timeout.clone(),
+		flag_sealing_watchdog: bool = false,
+			or |c: &Config| otry!(c.mining).sealing_watchdog.clone(),
+		flag_sealing_watchdog_missed: usize = 3usize,
+			or |c: &Config| otry!(c.mining).sealing_watchdog_missed.clone(),
+		flag_sealing_watchdog_url: Option<String> = None,
+			or |c: &Config| otry!(c.mining).sealing_watchdog_url.as_ref().map(|vec| Some(vec.join(","))),
+		flag_sealing_watchdog_exit: bool = false,
+			or |c: &Config| otry!(c.mining).sealing_watchdog_exit.clone(),
+		flag_dev_block_time: Option<u64> = None,
+			or |c: &Config| otry!(c.mining).dev_block_time.clone().map(Some),
+		flag_dev_seal_on_txs: bool = false,
+			or |c: &Config| otry!(c.mining).dev_seal_on_txs.clone(),
+		flag_dev_accounts: Option<String> = None,
+			or |c: &Config| otry!(c.mining).dev_accounts.clone().map(Some),
 		flag_force_sealing: bool = false,
 			or |c: &Config| otry!(c.mining).force_sealing.clone(),
 		flag_reseal_on_txs: String = "own",
@@ -261,22 +443,52 @@ usage! {
 			or |c: &Config| otry!(c.mining).gas_cap.clone(),
 		flag_extra_data: Option<String> = None,
 			or |c: &Config| otry!(c.mining).extra_data.clone().map(Some),
-		flag_tx_queue_size: usize = 1024usize,
-			or |c: &Config| otry!(c.mining).tx_queue_size.clone(),
+		flag_tx_queue_size: Option<usize> = None,
+			or |c: &Config| otry!(c.mining).tx_queue_size.clone().map(Some),
+		flag_tx_queue_future_size: Option<usize> = None,
+			or |c: &Config| otry!(c.mining).tx_queue_future_size.clone().map(Some),
+		flag_tx_queue_per_sender: Option<usize> = None,
+			or |c: &Config| otry!(c.mining).tx_queue_per_sender.clone().map(Some),
+		flag_tx_queue_future_per_sender: Option<usize> = None,
+			or |c: &Config| otry!(c.mining).tx_queue_future_per_sender.clone().map(Some),
+		flag_tx_queue_nonce_gap: Option<usize> = None,
+			or |c: &Config| otry!(c.mining).tx_queue_nonce_gap.clone().map(Some),
+		flag_tx_queue_future_mem: Option<usize> = None,
+			or |c: &Config| otry!(c.mining).tx_queue_future_mem.clone().map(Some),
+		flag_tx_queue_replace_bump: u32 = 0u32,
+			or |c: &Config| otry!(c.mining).tx_queue_replace_bump.clone(),
+		flag_tx_queue_pending_lifetime: u64 = 128u64,
+			or |c: &Config| otry!(c.mining).tx_queue_pending_lifetime.clone(),
+		flag_tx_queue_future_lifetime: u64 = 128u64,
+			or |c: &Config| otry!(c.mining).tx_queue_future_lifetime.clone(),
+		flag_tx_queue_remember_evicted: bool = false,
+			or |c: &Config| otry!(c.mining).tx_queue_remember_evicted.clone(),
 		flag_tx_queue_gas: String = "auto",
 			or |c: &Config| otry!(c.mining).tx_queue_gas.clone(),
 		flag_tx_queue_strategy: String = "gas_price",
 			or |c: &Config| otry!(c.mining).tx_queue_strategy.clone(),
+		flag_tx_queue_locals: String = "prioritize",
+			or |c: &Config| otry!(c.mining).tx_queue_locals.clone(),
 		flag_tx_queue_ban_count: u16 = 1u16,
 			or |c: &Config| otry!(c.mining).tx_queue_ban_count.clone(),
 		flag_tx_queue_ban_time: u16 = 180u16,
 			or |c: &Config| otry!(c.mining).tx_queue_ban_time.clone(),
+		flag_tx_queue_persist: String = "local",
+			or |c: &Config| otry!(c.mining).tx_queue_persist.clone(),
 		flag_remove_solved: bool = false,
 			or |c: &Config| otry!(c.mining).remove_solved.clone(),
 		flag_notify_work: Option<String> = None,
 			or |c: &Config| otry!(c.mining).notify_work.as_ref().map(|vec| Some(vec.join(","))),
 		flag_refuse_service_transactions: bool = false,
 			or |c: &Config| otry!(c.mining).refuse_service_transactions.clone(),
+		flag_zero_gas_price_senders: Option<String> = None,
+			or |c: &Config| otry!(c.mining).zero_gas_price_senders.as_ref().map(|vec| Some(vec.join(","))),
+		flag_zero_gas_price_certifier: Option<String> = None,
+			or |c: &Config| otry!(c.mining).zero_gas_price_certifier.clone().map(Some),
+		flag_tx_filter_file: Option<String> = None,
+			or |c: &Config| otry!(c.mining).tx_filter_file.clone().map(Some),
+		flag_tx_filter_contract: Option<String> = None,
+			or |c: &Config| otry!(c.mining).tx_filter_contract.clone().map(Some),
 
 		flag_stratum: bool = false,
 			or |c: &Config| Some(c.stratum.is_some()),
@@ -296,16 +508,18 @@ usage! {
 			or |c: &Config| otry!(c.footprint).pruning_history.clone(),
 		flag_pruning_memory: usize = 75usize,
 			or |c: &Config| otry!(c.footprint).pruning_memory.clone(),
-		flag_cache_size_db: u32 = 64u32,
-			or |c: &Config| otry!(c.footprint).cache_size_db.clone(),
-		flag_cache_size_blocks: u32 = 8u32,
-			or |c: &Config| otry!(c.footprint).cache_size_blocks.clone(),
-		flag_cache_size_queue: u32 = 50u32,
-			or |c: &Config| otry!(c.footprint).cache_size_queue.clone(),
-		flag_cache_size_state: u32 = 25u32,
-			or |c: &Config| otry!(c.footprint).cache_size_state.clone(),
+		flag_cache_size_db: Option<u32> = None,
+			or |c: &Config| otry!(c.footprint).cache_size_db.clone().map(Some),
+		flag_cache_size_blocks: Option<u32> = None,
+			or |c: &Config| otry!(c.footprint).cache_size_blocks.clone().map(Some),
+		flag_cache_size_queue: Option<u32> = None,
+			or |c: &Config| otry!(c.footprint).cache_size_queue.clone().map(Some),
+		flag_cache_size_state: Option<u32> = None,
+			or |c: &Config| otry!(c.footprint).cache_size_state.clone().map(Some),
 		flag_cache_size: Option<u32> = None,
 			or |c: &Config| otry!(c.footprint).cache_size.clone().map(Some),
+		flag_memory_budget: Option<u32> = None,
+			or |c: &Config| otry!(c.footprint).memory_budget.clone().map(Some),
 		flag_fast_and_loose: bool = false,
 			or |c: &Config| otry!(c.footprint).fast_and_loose.clone(),
 		flag_db_compaction: String = "auto",
@@ -316,21 +530,69 @@ usage! {
 			or |c: &Config| otry!(c.footprint).scale_verifiers.clone(),
 		flag_num_verifiers: Option<usize> = None,
 			or |c: &Config| otry!(c.footprint).num_verifiers.clone().map(Some),
+		flag_max_verifiers: Option<usize> = None,
+			or |c: &Config| otry!(c.footprint).max_verifiers.clone().map(Some),
+		flag_block_range: Option<String> = None,
+			or |c: &Config| otry!(c.footprint).block_range.clone().map(Some),
+		flag_token_index: bool = false,
+			or |c: &Config| otry!(c.footprint).token_index.clone(),
+		flag_balance_history: bool = false,
+			or |c: &Config| otry!(c.footprint).balance_history.clone(),
+		flag_log_index: bool = false,
+			or |c: &Config| otry!(c.footprint).log_index.clone(),
+		flag_disk_space_warn: u64 = 4096u64,
+			or |c: &Config| otry!(c.footprint).disk_space_warn.clone(),
+		flag_disk_space_snapshots: u64 = 2048u64,
+			or |c: &Config| otry!(c.footprint).disk_space_snapshots.clone(),
+		flag_disk_space_ancient: u64 = 1024u64,
+			or |c: &Config| otry!(c.footprint).disk_space_ancient.clone(),
+		flag_disk_space_import: u64 = 512u64,
+			or |c: &Config| otry!(c.footprint).disk_space_import.clone(),
 
 		// -- Import/Export Options
 		flag_from: String = "1", or |_| None,
 		flag_to: String = "latest", or |_| None,
 		flag_format: Option<String> = None, or |_| None,
 		flag_no_seal_check: bool = false, or |_| None,
+		flag_compression: Option<String> = None, or |_| None,
+		flag_blocks_per_file: Option<u64> = None, or |_| None,
+		flag_resume: bool = false, or |_| None,
 		flag_no_storage: bool = false, or |_| None,
 		flag_no_code: bool = false, or |_| None,
 		flag_min_balance: Option<String> = None, or |_| None,
 		flag_max_balance: Option<String> = None, or |_| None,
+		flag_min_address: Option<String> = None, or |_| None,
+		flag_max_address: Option<String> = None, or |_| None,
+		flag_only_contracts: bool = false, or |_| None,
+		flag_only_with_storage: bool = false, or |_| None,
+		flag_address: Option<String> = None, or |_| None,
 
 		// -- Snapshot Optons
 		flag_at: String = "latest", or |_| None,
 		flag_no_periodic_snapshot: bool = false,
 			or |c: &Config| otry!(c.snapshots).disable_periodic.clone(),
+		flag_delta_from: Option<String> = None, or |_| None,
+		flag_with_deltas: Option<String> = None, or |_| None,
+		flag_scratch_dir: Option<String> = None, or |_| None,
+		flag_snapshot_passphrase_file: Option<String> = None, or |_| None,
+		flag_snapshot_recipients: Option<String> = None, or |_| None,
+		flag_snapshot_secret_file: Option<String> = None, or |_| None,
+		flag_snapshot_every: u64 = 10000u64,
+			or |c: &Config| otry!(c.snapshots).every.clone(),
+		flag_snapshot_interval: Option<u64> = None,
+			or |c: &Config| otry!(c.snapshots).interval.map(Some),
+		flag_snapshot_window: Option<String> = None,
+			or |c: &Config| otry!(c.snapshots).window.clone().map(Some),
+		flag_snapshot_threads: usize = 1usize,
+			or |c: &Config| otry!(c.snapshots).threads.clone(),
+		flag_snapshot_chunks_per_sec: Option<u32> = None,
+			or |c: &Config| otry!(c.snapshots).chunks_per_sec.map(Some),
+		flag_snapshot_yield_to_import: bool = false,
+			or |c: &Config| otry!(c.snapshots).yield_to_import.clone(),
+		flag_snapshot_keep: usize = 0usize,
+			or |c: &Config| otry!(c.snapshots).keep.clone(),
+		flag_snapshot_max_disk: Option<u64> = None,
+			or |c: &Config| otry!(c.snapshots).max_disk.map(Some),
 
 		// -- Virtual Machine Options
 		flag_jitvm: bool = false,
@@ -342,8 +604,46 @@ usage! {
 			or |c: &Config| otry!(c.misc).logging.clone().map(Some),
 		flag_log_file: Option<String> = None,
 			or |c: &Config| otry!(c.misc).log_file.clone().map(Some),
+		flag_log_max_size: Option<u64> = None,
+			or |c: &Config| otry!(c.misc).log_max_size.clone().map(Some),
+		flag_log_max_files: usize = 5usize,
+			or |c: &Config| otry!(c.misc).log_max_files.clone(),
+		flag_log_compress: bool = false,
+			or |c: &Config| otry!(c.misc).log_compress.clone(),
+		flag_syslog: Option<String> = None,
+			or |c: &Config| otry!(c.misc).syslog.clone().map(Some),
+		flag_syslog_facility: String = "daemon",
+			or |c: &Config| otry!(c.misc).syslog_facility.clone(),
+		flag_syslog_tag: String = "parity",
+			or |c: &Config| otry!(c.misc).syslog_tag.clone(),
 		flag_no_color: bool = false,
 			or |c: &Config| otry!(c.misc).color.map(|c| !c).clone(),
+		flag_log_format: String = "text",
+			or |c: &Config| otry!(c.misc).log_format.clone(),
+		flag_statsd: Option<String> = None,
+			or |c: &Config| otry!(c.misc).statsd.clone().map(Some),
+		flag_statsd_prefix: String = "parity",
+			or |c: &Config| otry!(c.misc).statsd_prefix.clone(),
+		flag_statsd_interval: u64 = 10u64,
+			or |c: &Config| otry!(c.misc).statsd_interval.clone(),
+		flag_span_collector: Option<String> = None,
+			or |c: &Config| otry!(c.misc).span_collector.clone().map(Some),
+		flag_span_service: String = "parity",
+			or |c: &Config| otry!(c.misc).span_service.clone(),
+		flag_sql_export: Option<String> = None,
+			or |c: &Config| otry!(c.misc).sql_export.clone().map(Some),
+		flag_sql_export_batch: usize = 100usize,
+			or |c: &Config| otry!(c.misc).sql_export_batch.clone(),
+		flag_sql_export_psql: String = "psql",
+			or |c: &Config| otry!(c.misc).sql_export_psql.clone(),
+		flag_event_publisher: Option<String> = None,
+			or |c: &Config| otry!(c.misc).event_publisher.clone().map(Some),
+		flag_event_subject_prefix: String = "parity",
+			or |c: &Config| otry!(c.misc).event_subject_prefix.clone(),
+		flag_event_log_addresses: Option<String> = None,
+			or |c: &Config| otry!(c.misc).event_log_addresses.as_ref().map(|vec| Some(vec.join(","))),
+		flag_event_replay_from: Option<u64> = None,
+			or |_| None,
 
 
 		// -- Legacy Options supported in configs
@@ -382,6 +682,7 @@ struct Config {
 	dapps: Option<Dapps>,
 	secretstore: Option<SecretStore>,
 	ipfs: Option<Ipfs>,
+	health: Option<Health>,
 	mining: Option<Mining>,
 	footprint: Option<Footprint>,
 	snapshots: Option<Snapshots>,
@@ -395,6 +696,13 @@ struct Operating {
 	mode: Option<String>,
 	mode_timeout: Option<u64>,
 	mode_alarm: Option<u64>,
+	wake_on_rpc: Option<Vec<String>>,
+	nice: Option<i32>,
+	ionice: Option<String>,
+	service_name: Option<String>,
+	shutdown_timeout: Option<u64>,
+	supervise_max_restarts: Option<usize>,
+	supervise_window: Option<u64>,
 	auto_update: Option<String>,
 	release_track: Option<String>,
 	public_node: Option<bool>,
@@ -406,6 +714,9 @@ struct Operating {
 	keys_path: Option<String>,
 	identity: Option<String>,
 	light: Option<bool>,
+	hybrid: Option<bool>,
+	hybrid_rpc_port: Option<u16>,
+	hybrid_port: Option<u16>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -423,6 +734,7 @@ struct Ui {
 	port: Option<u16>,
 	interface: Option<String>,
 	path: Option<String>,
+	signer_policy: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -432,9 +744,23 @@ struct Network {
 	min_peers: Option<u16>,
 	max_peers: Option<u16>,
 	snapshot_peers: Option<u16>,
+	snapshot_preferred_peers: Option<Vec<String>>,
+	snapshot_download_ahead: Option<usize>,
+	snapshot_download_peers: Option<usize>,
+	snapshot_chunks_per_peer: Option<usize>,
+	snapshot_chunk_timeout: Option<u64>,
+	snapshot_chunk_retries: Option<usize>,
+	warp_from: Option<String>,
+	warp_from_hash: Option<String>,
 	max_pending_peers: Option<u16>,
+	max_peers_per_ip: Option<u16>,
+	max_peers_per_subnet: Option<u16>,
+	protocol_peers: Option<Vec<String>>,
+	max_upload: Option<u64>,
+	max_download: Option<u64>,
 	nat: Option<String>,
 	allow_ips: Option<String>,
+	deny_ips: Option<Vec<String>>,
 	id: Option<u64>,
 	bootnodes: Option<Vec<String>>,
 	discovery: Option<bool>,
@@ -442,6 +768,40 @@ struct Network {
 	reserved_peers: Option<String>,
 	reserved_only: Option<bool>,
 	no_serve_light: Option<bool>,
+	serve_les: Option<bool>,
+	les_peers: Option<u16>,
+	les_buffer_limit: Option<u64>,
+	pip_peers: Option<usize>,
+	pip_credit_limit: Option<u64>,
+	pip_recharge_rate: Option<u64>,
+	pip_request_costs: Option<String>,
+	pip_ws_port: Option<u16>,
+	les_recharge_rate: Option<u64>,
+	ancient_blocks_rate: Option<usize>,
+	warp_serve_bandwidth: Option<usize>,
+	warp_serve_peers: Option<usize>,
+	warp_serve_hours: Option<String>,
+	checkpoint: Option<String>,
+	no_checkpoint: Option<bool>,
+	light_checkpoint: Option<String>,
+	light_checkpoint_key: Option<String>,
+	no_light_checkpoint: Option<bool>,
+	no_persistent_light_cache: Option<bool>,
+	light_fallback_url: Option<String>,
+	light_ws_servers: Option<Vec<String>>,
+	on_demand_timeouts: Option<String>,
+	on_demand_retries: Option<usize>,
+	on_demand_peer_selection: Option<String>,
+	light_serving_peers: Option<Vec<String>>,
+	light_serving_peers_only: Option<bool>,
+	light_relay_peers: Option<usize>,
+	max_reorg_depth: Option<u64>,
+	reorg_alert_url: Option<Vec<String>>,
+	sync_schedule: Option<String>,
+	proxy: Option<String>,
+	local_tx_delay: Option<u64>,
+	local_tx_jitter: Option<u64>,
+	local_tx_relay_peers: Option<Vec<String>>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -452,7 +812,12 @@ struct Rpc {
 	cors: Option<String>,
 	apis: Option<Vec<String>>,
 	hosts: Option<Vec<String>>,
+	api_cors: Option<Vec<String>>,
+	api_hosts: Option<Vec<String>>,
 	threads: Option<usize>,
+	max_batch_size: Option<usize>,
+	max_request_size: Option<usize>,
+	max_response_size: Option<usize>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -505,10 +870,31 @@ struct Ipfs {
 	hosts: Option<Vec<String>>,
 }
 
+#[derive(Default, Debug, PartialEq, RustcDecodable)]
+struct Health {
+	enable: Option<bool>,
+	port: Option<u16>,
+	interface: Option<String>,
+	max_block_lag: Option<u64>,
+	min_peers: Option<u16>,
+	max_clock_drift: Option<u64>,
+	ntp_server: Option<String>,
+}
+
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
 struct Mining {
 	author: Option<String>,
 	engine_signer: Option<String>,
+	engine_lock: Option<String>,
+	engine_standby: Option<bool>,
+	engine_lock_timeout: Option<u64>,
+	sealing_watchdog: Option<bool>,
+	sealing_watchdog_missed: Option<usize>,
+	sealing_watchdog_url: Option<Vec<String>>,
+	sealing_watchdog_exit: Option<bool>,
+	dev_block_time: Option<u64>,
+	dev_seal_on_txs: Option<bool>,
+	dev_accounts: Option<String>,
 	force_sealing: Option<bool>,
 	reseal_on_txs: Option<String>,
 	reseal_min_period: Option<u64>,
@@ -524,13 +910,28 @@ struct Mining {
 	gas_cap: Option<String>,
 	extra_data: Option<String>,
 	tx_queue_size: Option<usize>,
+	tx_queue_future_size: Option<usize>,
+	tx_queue_per_sender: Option<usize>,
+	tx_queue_future_per_sender: Option<usize>,
+	tx_queue_nonce_gap: Option<usize>,
+	tx_queue_future_mem: Option<usize>,
+	tx_queue_replace_bump: Option<u32>,
+	tx_queue_pending_lifetime: Option<u64>,
+	tx_queue_future_lifetime: Option<u64>,
+	tx_queue_remember_evicted: Option<bool>,
 	tx_queue_gas: Option<String>,
 	tx_queue_strategy: Option<String>,
+	tx_queue_locals: Option<String>,
 	tx_queue_ban_count: Option<u16>,
 	tx_queue_ban_time: Option<u16>,
+	tx_queue_persist: Option<String>,
 	remove_solved: Option<bool>,
 	notify_work: Option<Vec<String>>,
 	refuse_service_transactions: Option<bool>,
+	zero_gas_price_senders: Option<Vec<String>>,
+	zero_gas_price_certifier: Option<String>,
+	tx_filter_file: Option<String>,
+	tx_filter_contract: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -552,15 +953,33 @@ struct Footprint {
 	cache_size_blocks: Option<u32>,
 	cache_size_queue: Option<u32>,
 	cache_size_state: Option<u32>,
+	memory_budget: Option<u32>,
 	db_compaction: Option<String>,
 	fat_db: Option<String>,
 	scale_verifiers: Option<bool>,
 	num_verifiers: Option<usize>,
+	max_verifiers: Option<usize>,
+	block_range: Option<String>,
+	token_index: Option<bool>,
+	balance_history: Option<bool>,
+	log_index: Option<bool>,
+	disk_space_warn: Option<u64>,
+	disk_space_snapshots: Option<u64>,
+	disk_space_ancient: Option<u64>,
+	disk_space_import: Option<u64>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
 struct Snapshots {
 	disable_periodic: Option<bool>,
+	every: Option<u64>,
+	interval: Option<u64>,
+	window: Option<String>,
+	threads: Option<usize>,
+	chunks_per_sec: Option<u32>,
+	yield_to_import: Option<bool>,
+	keep: Option<usize>,
+	max_disk: Option<u64>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -572,14 +991,32 @@ struct VM {
 struct Misc {
 	logging: Option<String>,
 	log_file: Option<String>,
+	log_max_size: Option<u64>,
+	log_max_files: Option<usize>,
+	log_compress: Option<bool>,
+	syslog: Option<String>,
+	syslog_facility: Option<String>,
+	syslog_tag: Option<String>,
 	color: Option<bool>,
+	log_format: Option<String>,
+	statsd: Option<String>,
+	statsd_prefix: Option<String>,
+	statsd_interval: Option<u64>,
+	span_collector: Option<String>,
+	span_service: Option<String>,
+	sql_export: Option<String>,
+	sql_export_batch: Option<usize>,
+	sql_export_psql: Option<String>,
+	event_publisher: Option<String>,
+	event_subject_prefix: Option<String>,
+	event_log_addresses: Option<Vec<String>>,
 }
 
 #[cfg(test)]
 mod tests {
 	use super::{
 		Args, ArgsError,
-		Config, Operating, Account, Ui, Network, Ws, Rpc, Ipc, Dapps, Ipfs, Mining, Footprint,
+		Config, Operating, Account, Ui, Network, Ws, Rpc, Ipc, Dapps, Ipfs, Health, Mining, Footprint,
 		Snapshots, VM, Misc, SecretStore,
 	};
 	use toml;
@@ -647,19 +1084,31 @@ mod tests {
 			cmd_export: false,
 			cmd_state: false,
 			cmd_blocks: false,
+			cmd_receipts: false,
+			cmd_traces: false,
+			cmd_transfers: false,
 			cmd_import: false,
 			cmd_signer: false,
 			cmd_sign: false,
+			cmd_approve: false,
 			cmd_reject: false,
 			cmd_new_token: false,
 			cmd_snapshot: false,
 			cmd_restore: false,
+			cmd_verify: false,
 			cmd_ui: false,
 			cmd_dapp: false,
 			cmd_tools: false,
 			cmd_hash: false,
 			cmd_db: false,
 			cmd_kill: false,
+			cmd_service: false,
+			cmd_install: false,
+			cmd_uninstall: false,
+			cmd_run: false,
+			cmd_config: false,
+			cmd_dump: false,
+			cmd_encrypt: false,
 
 			// Arguments
 			arg_pid_file: "".into(),
@@ -671,6 +1120,13 @@ mod tests {
 			flag_mode: "last".into(),
 			flag_mode_timeout: 300u64,
 			flag_mode_alarm: 3600u64,
+			flag_wake_on_rpc: None,
+			flag_nice: None,
+			flag_ionice: None,
+			flag_service_name: "parity".into(),
+			flag_shutdown_timeout: None,
+			flag_supervise_max_restarts: 5usize,
+			flag_supervise_window: 600u64,
 			flag_auto_update: "none".into(),
 			flag_release_track: "current".into(),
 			flag_public_node: false,
@@ -682,6 +1138,10 @@ mod tests {
 			flag_keys_path: "$HOME/.parity/keys".into(),
 			flag_identity: "".into(),
 			flag_light: false,
+			flag_hybrid: false,
+			flag_hybrid_rpc_port: 8549u16,
+			flag_hybrid_port: 30304u16,
+			flag_hybrid_full_node: false,
 
 			// -- Account Options
 			flag_unlock: Some("0xdeadbeefcafe0000000000000000000000000000".into()),
@@ -694,6 +1154,7 @@ mod tests {
 			flag_ui_port: 8180u16,
 			flag_ui_interface: "127.0.0.1".into(),
 			flag_ui_path: "$HOME/.parity/signer".into(),
+			flag_signer_policy: None,
 			flag_ui_no_validation: false,
 
 			// -- Networking Options
@@ -702,8 +1163,22 @@ mod tests {
 			flag_min_peers: 25u16,
 			flag_max_peers: 50u16,
 			flag_max_pending_peers: 64u16,
+			flag_max_peers_per_ip: 0u16,
+			flag_max_peers_per_subnet: 0u16,
+			flag_protocol_peers: None,
+			flag_max_upload: None,
+			flag_max_download: None,
 			flag_snapshot_peers: 0u16,
+			flag_snapshot_preferred_peers: None,
+			flag_snapshot_download_ahead: 3usize,
+			flag_snapshot_download_peers: None,
+			flag_snapshot_chunks_per_peer: 1usize,
+			flag_snapshot_chunk_timeout: 120u64,
+			flag_snapshot_chunk_retries: None,
+			flag_warp_from: None,
+			flag_warp_from_hash: None,
 			flag_allow_ips: "all".into(),
+			flag_deny_ips: None,
 			flag_nat: "any".into(),
 			flag_network_id: Some(1),
 			flag_bootnodes: Some("".into()),
@@ -713,6 +1188,40 @@ mod tests {
 			flag_reserved_only: false,
 			flag_no_ancient_blocks: false,
 			flag_no_serve_light: false,
+			flag_serve_les: false,
+			flag_les_peers: 10u16,
+			flag_les_buffer_limit: 300_000_000u64,
+			flag_pip_peers: None,
+			flag_pip_credit_limit: 50_000_000u64,
+			flag_pip_recharge_rate: 100_000u64,
+			flag_pip_request_costs: None,
+			flag_pip_ws_port: None,
+			flag_les_recharge_rate: 50_000u64,
+			flag_ancient_blocks_rate: None,
+			flag_warp_serve_bandwidth: None,
+			flag_warp_serve_peers: None,
+			flag_warp_serve_hours: None,
+			flag_checkpoint: None,
+			flag_no_checkpoint: false,
+			flag_light_checkpoint: None,
+			flag_light_checkpoint_key: None,
+			flag_no_light_checkpoint: false,
+			flag_no_persistent_light_cache: false,
+			flag_light_fallback_url: None,
+			flag_light_ws_servers: None,
+			flag_on_demand_timeouts: None,
+			flag_on_demand_retries: 5usize,
+			flag_on_demand_peer_selection: "first".into(),
+			flag_light_serving_peers: None,
+			flag_light_serving_peers_only: false,
+			flag_light_relay_peers: None,
+			flag_max_reorg_depth: None,
+			flag_reorg_alert_url: None,
+			flag_sync_schedule: None,
+			flag_proxy: None,
+			flag_local_tx_delay: 0u64,
+			flag_local_tx_jitter: 0u64,
+			flag_local_tx_relay_peers: None,
 
 			// -- API and Console Options
 			// RPC
@@ -722,7 +1231,12 @@ mod tests {
 			flag_jsonrpc_cors: Some("null".into()),
 			flag_jsonrpc_apis: "web3,eth,net,parity,traces,rpc".into(),
 			flag_jsonrpc_hosts: "none".into(),
+			flag_jsonrpc_api_cors: None,
+			flag_jsonrpc_api_hosts: None,
 			flag_jsonrpc_threads: None,
+			flag_jsonrpc_max_batch_size: None,
+			flag_jsonrpc_max_request_size: None,
+			flag_jsonrpc_max_response_size: None,
 
 			// WS
 			flag_no_ws: false,
@@ -757,9 +1271,28 @@ mod tests {
 			flag_ipfs_api_cors: Some("null".into()),
 			flag_ipfs_api_hosts: "none".into(),
 
+			// Health
+			flag_health_api: false,
+			flag_health_api_port: 8547u16,
+			flag_health_api_interface: "local".into(),
+			flag_health_max_block_lag: 10u64,
+			flag_health_min_peers: 1u16,
+			flag_health_max_clock_drift: 10u64,
+			flag_health_ntp_server: "pool.ntp.org:123".into(),
+
 			// -- Sealing/Mining Options
 			flag_author: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
 			flag_engine_signer: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
+			flag_engine_lock: None,
+			flag_engine_standby: false,
+			flag_engine_lock_timeout: 60u64,
+			flag_sealing_watchdog: false,
+			flag_sealing_watchdog_missed: 3usize,
+			flag_sealing_watchdog_url: None,
+			flag_sealing_watchdog_exit: false,
+			flag_dev_block_time: None,
+			flag_dev_seal_on_txs: false,
+			flag_dev_accounts: None,
 			flag_force_sealing: true,
 			flag_reseal_on_txs: "all".into(),
 			flag_reseal_min_period: 4000u64,
@@ -774,14 +1307,29 @@ mod tests {
 			flag_gas_floor_target: "4700000".into(),
 			flag_gas_cap: "6283184".into(),
 			flag_extra_data: Some("Parity".into()),
-			flag_tx_queue_size: 1024usize,
+			flag_tx_queue_size: Some(1024),
+			flag_tx_queue_future_size: None,
+			flag_tx_queue_per_sender: Some(16),
+			flag_tx_queue_future_per_sender: None,
+			flag_tx_queue_nonce_gap: None,
+			flag_tx_queue_future_mem: None,
+			flag_tx_queue_replace_bump: 0u32,
+			flag_tx_queue_pending_lifetime: 128u64,
+			flag_tx_queue_future_lifetime: 128u64,
+			flag_tx_queue_remember_evicted: false,
 			flag_tx_queue_gas: "auto".into(),
 			flag_tx_queue_strategy: "gas_factor".into(),
+			flag_tx_queue_locals: "prioritize".into(),
 			flag_tx_queue_ban_count: 1u16,
 			flag_tx_queue_ban_time: 180u16,
+			flag_tx_queue_persist: "local".into(),
 			flag_remove_solved: false,
 			flag_notify_work: Some("http://localhost:3001".into()),
 			flag_refuse_service_transactions: false,
+			flag_zero_gas_price_senders: None,
+			flag_zero_gas_price_certifier: None,
+			flag_tx_filter_file: None,
+			flag_tx_filter_contract: None,
 
 			flag_stratum: false,
 			flag_stratum_interface: "local".to_owned(),
@@ -793,30 +1341,62 @@ mod tests {
 			flag_pruning: "auto".into(),
 			flag_pruning_history: 64u64,
 			flag_pruning_memory: 500usize,
-			flag_cache_size_db: 64u32,
-			flag_cache_size_blocks: 8u32,
-			flag_cache_size_queue: 50u32,
-			flag_cache_size_state: 25u32,
+			flag_cache_size_db: Some(64),
+			flag_cache_size_blocks: Some(8),
+			flag_cache_size_queue: Some(50),
+			flag_cache_size_state: Some(25),
 			flag_cache_size: Some(128),
+			flag_memory_budget: None,
 			flag_fast_and_loose: false,
 			flag_db_compaction: "ssd".into(),
 			flag_fat_db: "auto".into(),
 			flag_scale_verifiers: true,
 			flag_num_verifiers: Some(6),
+			flag_max_verifiers: None,
+			flag_block_range: None,
+			flag_token_index: false,
+			flag_balance_history: false,
+			flag_log_index: false,
+			flag_disk_space_warn: 4096u64,
+			flag_disk_space_snapshots: 2048u64,
+			flag_disk_space_ancient: 1024u64,
+			flag_disk_space_import: 512u64,
 
 			// -- Import/Export Options
 			flag_from: "1".into(),
 			flag_to: "latest".into(),
 			flag_format: None,
 			flag_no_seal_check: false,
+			flag_compression: None,
+			flag_blocks_per_file: None,
+			flag_resume: false,
 			flag_no_code: false,
 			flag_no_storage: false,
 			flag_min_balance: None,
 			flag_max_balance: None,
+			flag_min_address: None,
+			flag_max_address: None,
+			flag_only_contracts: false,
+			flag_only_with_storage: false,
+			flag_address: None,
 
 			// -- Snapshot Optons
 			flag_at: "latest".into(),
 			flag_no_periodic_snapshot: false,
+			flag_delta_from: None,
+			flag_with_deltas: None,
+			flag_scratch_dir: None,
+			flag_snapshot_passphrase_file: None,
+			flag_snapshot_recipients: None,
+			flag_snapshot_secret_file: None,
+			flag_snapshot_every: 5000u64,
+			flag_snapshot_interval: None,
+			flag_snapshot_window: Some("22:00-04:00".into()),
+			flag_snapshot_threads: 1usize,
+			flag_snapshot_chunks_per_sec: None,
+			flag_snapshot_yield_to_import: false,
+			flag_snapshot_keep: 2usize,
+			flag_snapshot_max_disk: None,
 
 			// -- Virtual Machine Options
 			flag_jitvm: false,
@@ -862,11 +1442,189 @@ mod tests {
 			flag_config: "$BASE/config.toml".into(),
 			flag_logging: Some("own_tx=trace".into()),
 			flag_log_file: Some("/var/log/parity.log".into()),
+			flag_log_max_size: None,
+			flag_log_max_files: 5usize,
+			flag_log_compress: false,
+			flag_syslog: None,
+			flag_syslog_facility: "daemon".into(),
+			flag_syslog_tag: "parity".into(),
 			flag_no_color: false,
+			flag_log_format: "text".into(),
+			flag_statsd: None,
+			flag_statsd_prefix: "parity".into(),
+			flag_statsd_interval: 10u64,
+			flag_span_collector: None,
+			flag_span_service: "parity".into(),
+			flag_sql_export: None,
+			flag_sql_export_batch: 100usize,
+			flag_sql_export_psql: "psql".into(),
+			flag_event_publisher: None,
+			flag_event_subject_prefix: "parity".into(),
+			flag_event_log_addresses: None,
+			flag_event_replay_from: None,
 			flag_no_config: false,
+			flag_profile: None,
+			flag_config_env: None,
+			flag_check_config: false,
+			flag_config_key_file: None,
+			flag_geth_compat_config: None,
+			flag_config_public_key: None,
+			flag_config_signing_key: None,
+			flag_config_valid_for: None,
+			flag_supervise: false,
 		});
 	}
 
+	#[test]
+	fn should_load_includes_and_profile() {
+		use std::fs::File;
+		use std::io::Write;
+		use devtools::RandomTempPath;
+
+		// given
+		let dir = RandomTempPath::create_dir();
+		let write = |name: &str, content: &str| File::create(dir.as_path().join(name)).unwrap().write_all(content.as_bytes()).unwrap();
+		write("base.toml", "[parity]\nmode = \"dark\"\n[network]\nport = 30305\nmax_peers = 10\n");
+		write("node.toml", "include = [\"base.toml\"]\n[network]\nport = 30304\n[profile.archive.footprint]\npruning = \"archive\"\n");
+		write("loop.toml", "include = [\"loop.toml\"]\n");
+		let node = dir.as_path().join("node.toml").to_string_lossy().into_owned();
+		let cycle = dir.as_path().join("loop.toml").to_string_lossy().into_owned();
+
+		// when
+		let args = Args::parse(&["parity", "--config", &node]).unwrap();
+		let profile = Args::parse(&["parity", "--config", &node, "--profile", "archive"]).unwrap();
+
+		// then
+		assert_eq!(args.flag_mode, "dark");
+		assert_eq!(args.flag_port, 30304);
+		assert_eq!(args.flag_max_peers, 10);
+		assert_eq!(args.flag_pruning, "auto");
+		assert_eq!(profile.flag_pruning, "archive");
+		assert_eq!(profile.flag_port, 30304);
+
+		match Args::parse(&["parity", "--config", &node, "--profile", "unknown"]) {
+			Err(ArgsError::ConfigFile(_)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		match Args::parse(&["parity", "--config", &cycle]) {
+			Err(ArgsError::ConfigFile(_)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_apply_section_of_selected_chain() {
+		use std::fs::File;
+		use std::io::Write;
+		use devtools::RandomTempPath;
+
+		// given
+		let dir = RandomTempPath::create_dir();
+		let write = |name: &str, content: &str| File::create(dir.as_path().join(name)).unwrap().write_all(content.as_bytes()).unwrap();
+		write("chains.toml", "[parity]\nchain = \"kovan\"\n[network]\nport = 30304\n[chain.kovan.network]\nport = 30305\n[chain.mainnet.network]\nmax_peers = 100\n");
+		write("ambiguous.toml", "[chain.mainnet.network]\nport = 1\n[chain.foundation.network]\nport = 2\n");
+		write("recursive.toml", "[chain.kovan.parity]\nchain = \"ropsten\"\n");
+		let chains = dir.as_path().join("chains.toml").to_string_lossy().into_owned();
+		let ambiguous = dir.as_path().join("ambiguous.toml").to_string_lossy().into_owned();
+		let recursive = dir.as_path().join("recursive.toml").to_string_lossy().into_owned();
+
+		// when
+		let kovan = Args::parse(&["parity", "--config", &chains]).unwrap();
+		let foundation = Args::parse(&["parity", "--config", &chains, "--chain", "foundation"]).unwrap();
+		let ropsten = Args::parse(&["parity", "--config", &chains, "--chain", "ropsten"]).unwrap();
+
+		// then
+		assert_eq!((kovan.flag_port, kovan.flag_max_peers), (30305, 50));
+		assert_eq!((foundation.flag_port, foundation.flag_max_peers), (30304, 100));
+		assert_eq!((ropsten.flag_port, ropsten.flag_max_peers), (30304, 50));
+
+		match Args::parse(&["parity", "--config", &ambiguous]) {
+			Err(ArgsError::ConfigFile(_)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		match Args::parse(&["parity", "--config", &recursive, "--chain", "testnet"]) {
+			Err(ArgsError::ConfigFile(_)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_decrypt_encrypted_values() {
+		use std::fs::File;
+		use std::io::Write;
+		use devtools::RandomTempPath;
+
+		// given
+		let dir = RandomTempPath::create_dir();
+		let write = |name: &str, content: &str| File::create(dir.as_path().join(name)).unwrap().write_all(content.as_bytes()).unwrap();
+		let secret = ::config_secrets::encrypt("s3cr3t", "master");
+		write("config.toml", &format!("[stratum]\nsecret = \"{}\"\n", secret));
+		write("master.key", "master\n");
+		write("wrong.key", "other\n");
+		let path = |name: &str| dir.as_path().join(name).to_string_lossy().into_owned();
+		let (config, master, wrong) = (path("config.toml"), path("master.key"), path("wrong.key"));
+
+		// when
+		let args = Args::parse(&["parity", "--config", &config, "--config-key-file", &master]).unwrap();
+
+		// then
+		assert_eq!(args.flag_stratum_secret, Some("s3cr3t".into()));
+		match Args::parse(&["parity", "--config", &config, "--config-key-file", &wrong]) {
+			Err(ArgsError::ConfigFile(_)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_require_public_key_of_remote_config() {
+		match Args::parse(&["parity", "--config", "https://config.example.com/node.toml"]) {
+			Err(ArgsError::ConfigFile(ref e)) if e.contains("--config-public-key") => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_substitute_environment_variables() {
+		use super::{EnvPolicy, substitute_env_vars};
+
+		let lookup = |name: &str| match name {
+			"HOST" => Some("node1".to_owned()),
+			"SECRET" => Some("s3cr3t".to_owned()),
+			_ => None,
+		};
+		assert_eq!(substitute_env_vars("/data/${HOST}/keys", EnvPolicy::Strict, &lookup), Ok("/data/node1/keys".into()));
+		assert_eq!(substitute_env_vars("${HOST}:${SECRET}", EnvPolicy::Strict, &lookup), Ok("node1:s3cr3t".into()));
+		assert_eq!(substitute_env_vars("$BASE/$${HOST}", EnvPolicy::Strict, &lookup), Ok("$BASE/${HOST}".into()));
+		assert_eq!(substitute_env_vars("a${MISSING}b", EnvPolicy::Lenient, &lookup), Ok("ab".into()));
+		assert!(substitute_env_vars("a${MISSING}b", EnvPolicy::Strict, &lookup).is_err());
+		assert!(substitute_env_vars("a${HOST", EnvPolicy::Strict, &lookup).is_err());
+		assert!(substitute_env_vars("a${}", EnvPolicy::Strict, &lookup).is_err());
+	}
+
+	#[test]
+	fn should_tell_sources_of_values() {
+		use super::{RawArgs, ValueSource, flag_name};
+
+		let config = Args::parse_config("[network]\nport = 30304\nmax_peers = 100\n").unwrap();
+		let raw = RawArgs::parse(&["parity", "--max-peers", "10", "--geth"]).unwrap();
+		let sources = raw.sources(&config);
+		let source = |name: &str| sources.iter().find(|s| s.0 == name).map(|s| s.1);
+
+		assert_eq!(source("flag_max_peers"), Some(ValueSource::CommandLine));
+		assert_eq!(source("flag_port"), Some(ValueSource::ConfigFile));
+		assert_eq!(source("flag_chain"), Some(ValueSource::Default));
+		assert_eq!(source("flag_geth"), Some(ValueSource::CommandLine));
+		assert_eq!(source("flag_testnet"), None);
+
+		let args = raw.into_args(config);
+		let values = args.flag_values();
+		let value = |name: &str| values.iter().find(|v| v.0 == name).and_then(|v| v.1.clone());
+		assert_eq!(value("flag_port"), Some("30304".into()));
+		assert_eq!(value("flag_chain"), Some("\"foundation\"".into()));
+		assert_eq!(value("flag_unlock"), None);
+		assert_eq!(flag_name("flag_max_peers"), "--max-peers");
+	}
+
 	#[test]
 	fn should_parse_config_and_return_errors() {
 		let config1 = Args::parse_config(include_str!("./config.invalid1.toml"));
@@ -890,6 +1648,13 @@ mod tests {
 				mode: Some("dark".into()),
 				mode_timeout: Some(15u64),
 				mode_alarm: Some(10u64),
+				wake_on_rpc: None,
+				nice: None,
+				ionice: None,
+				service_name: None,
+				shutdown_timeout: None,
+				supervise_max_restarts: None,
+				supervise_window: None,
 				auto_update: None,
 				release_track: None,
 				public_node: None,
@@ -901,6 +1666,9 @@ mod tests {
 				keys_path: None,
 				identity: None,
 				light: None,
+				hybrid: None,
+				hybrid_rpc_port: None,
+				hybrid_port: None,
 			}),
 			account: Some(Account {
 				unlock: Some(vec!["0x1".into(), "0x2".into(), "0x3".into()]),
@@ -914,6 +1682,7 @@ mod tests {
 				port: None,
 				interface: None,
 				path: None,
+				signer_policy: None,
 			}),
 			network: Some(Network {
 				warp: Some(false),
@@ -921,8 +1690,22 @@ mod tests {
 				min_peers: Some(10),
 				max_peers: Some(20),
 				max_pending_peers: Some(30),
+				max_peers_per_ip: None,
+				max_peers_per_subnet: None,
+				protocol_peers: None,
+				max_upload: None,
+				max_download: None,
 				snapshot_peers: Some(40),
+				snapshot_preferred_peers: None,
+				snapshot_download_ahead: None,
+				snapshot_download_peers: None,
+				snapshot_chunks_per_peer: None,
+				snapshot_chunk_timeout: None,
+				snapshot_chunk_retries: None,
+				warp_from: None,
+				warp_from_hash: None,
 				allow_ips: Some("public".into()),
+				deny_ips: None,
 				nat: Some("any".into()),
 				id: None,
 				bootnodes: None,
@@ -931,6 +1714,40 @@ mod tests {
 				reserved_peers: Some("./path/to/reserved_peers".into()),
 				reserved_only: Some(true),
 				no_serve_light: None,
+				serve_les: None,
+				les_peers: None,
+				les_buffer_limit: None,
+				pip_peers: None,
+				pip_credit_limit: None,
+				pip_recharge_rate: None,
+				pip_request_costs: None,
+				pip_ws_port: None,
+				les_recharge_rate: None,
+				ancient_blocks_rate: None,
+				warp_serve_bandwidth: None,
+				warp_serve_peers: None,
+				warp_serve_hours: None,
+				checkpoint: None,
+				no_checkpoint: None,
+				light_checkpoint: None,
+				light_checkpoint_key: None,
+				no_light_checkpoint: None,
+				no_persistent_light_cache: None,
+				light_fallback_url: None,
+				light_ws_servers: None,
+				on_demand_timeouts: None,
+				on_demand_retries: None,
+				on_demand_peer_selection: None,
+				light_serving_peers: None,
+				light_serving_peers_only: None,
+				light_relay_peers: None,
+				max_reorg_depth: None,
+				reorg_alert_url: None,
+				sync_schedule: None,
+				proxy: None,
+				local_tx_delay: None,
+				local_tx_jitter: None,
+				local_tx_relay_peers: None,
 			}),
 			websockets: Some(Ws {
 				disable: Some(true),
@@ -947,7 +1764,12 @@ mod tests {
 				cors: None,
 				apis: None,
 				hosts: None,
+				api_cors: None,
+				api_hosts: None,
 				threads: None,
+				max_batch_size: None,
+				max_request_size: None,
+				max_response_size: None,
 			}),
 			ipc: Some(Ipc {
 				disable: None,
@@ -981,9 +1803,20 @@ mod tests {
 				cors: None,
 				hosts: None,
 			}),
+			health: None,
 			mining: Some(Mining {
 				author: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
 				engine_signer: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
+				engine_lock: None,
+				engine_standby: None,
+				engine_lock_timeout: None,
+				sealing_watchdog: None,
+				sealing_watchdog_missed: None,
+				sealing_watchdog_url: None,
+				sealing_watchdog_exit: None,
+				dev_block_time: None,
+				dev_seal_on_txs: None,
+				dev_accounts: None,
 				force_sealing: Some(true),
 				reseal_on_txs: Some("all".into()),
 				reseal_min_period: Some(4000),
@@ -996,16 +1829,31 @@ mod tests {
 				gas_floor_target: None,
 				gas_cap: None,
 				tx_queue_size: Some(1024),
+				tx_queue_future_size: None,
+				tx_queue_per_sender: None,
+				tx_queue_future_per_sender: None,
+				tx_queue_nonce_gap: None,
+				tx_queue_future_mem: None,
+				tx_queue_replace_bump: None,
+				tx_queue_pending_lifetime: None,
+				tx_queue_future_lifetime: None,
+				tx_queue_remember_evicted: None,
 				tx_queue_gas: Some("auto".into()),
 				tx_queue_strategy: None,
+				tx_queue_locals: None,
 				tx_queue_ban_count: None,
 				tx_queue_ban_time: None,
+				tx_queue_persist: None,
 				tx_gas_limit: None,
 				tx_time_limit: None,
 				extra_data: None,
 				remove_solved: None,
 				notify_work: None,
 				refuse_service_transactions: None,
+				zero_gas_price_senders: None,
+				zero_gas_price_certifier: None,
+				tx_filter_file: None,
+				tx_filter_contract: None,
 			}),
 			footprint: Some(Footprint {
 				tracing: Some("on".into()),
@@ -1018,13 +1866,31 @@ mod tests {
 				cache_size_blocks: Some(16),
 				cache_size_queue: Some(100),
 				cache_size_state: Some(25),
+				memory_budget: None,
 				db_compaction: Some("ssd".into()),
 				fat_db: Some("off".into()),
 				scale_verifiers: Some(false),
 				num_verifiers: None,
+				max_verifiers: None,
+				block_range: None,
+				token_index: None,
+				balance_history: None,
+				log_index: None,
+				disk_space_warn: None,
+				disk_space_snapshots: None,
+				disk_space_ancient: None,
+				disk_space_import: None,
 			}),
 			snapshots: Some(Snapshots {
 				disable_periodic: Some(true),
+				every: None,
+				interval: None,
+				window: None,
+				threads: None,
+				chunks_per_sec: None,
+				yield_to_import: None,
+				keep: None,
+				max_disk: None,
 			}),
 			vm: Some(VM {
 				jit: Some(false),
@@ -1032,7 +1898,25 @@ mod tests {
 			misc: Some(Misc {
 				logging: Some("own_tx=trace".into()),
 				log_file: Some("/var/log/parity.log".into()),
+				log_max_size: None,
+				log_max_files: None,
+				log_compress: None,
+				syslog: None,
+				syslog_facility: None,
+				syslog_tag: None,
 				color: Some(true),
+				log_format: None,
+				statsd: None,
+				statsd_prefix: None,
+				statsd_interval: None,
+				span_collector: None,
+				span_service: None,
+				sql_export: None,
+				sql_export_batch: None,
+				sql_export_psql: None,
+				event_publisher: None,
+				event_subject_prefix: None,
+				event_log_addresses: None,
 			}),
 			stratum: None,
 		});
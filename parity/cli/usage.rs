@@ -50,8 +50,9 @@ macro_rules! usage {
 		}
 	) => {
 		use toml;
-		use std::{fs, io, process};
+		use std::{fmt, fs, io, process};
 		use std::io::{Read, Write};
+		use std::path::{Path, PathBuf};
 		use util::version;
 		use docopt::{Docopt, Error as DocoptError};
 		use helpers::replace_home;
@@ -64,6 +65,161 @@ macro_rules! usage {
 			Decode(toml::DecodeError),
 			Config(String, io::Error),
 			UnknownFields(String),
+			/// Invalid include or unknown profile.
+			ConfigFile(String),
+		}
+
+		/// Maximal depth of nested config file includes.
+		const MAX_INCLUDE_DEPTH: usize = 8;
+
+		/// Handling of `${VAR}` references to environment variables which are not set.
+		#[derive(Debug, PartialEq, Clone, Copy)]
+		pub enum EnvPolicy {
+			/// Fail to load the config file.
+			Strict,
+			/// Substitute an empty string and print a warning.
+			Lenient,
+		}
+
+		impl ::std::str::FromStr for EnvPolicy {
+			type Err = String;
+
+			fn from_str(s: &str) -> Result<Self, Self::Err> {
+				match s {
+					"strict" => Ok(EnvPolicy::Strict),
+					"lenient" => Ok(EnvPolicy::Lenient),
+					other => Err(format!("Invalid environment variable policy: {}", other)),
+				}
+			}
+		}
+
+		/// Replace `${VAR}` with the value of the variable, `$${` stands for a literal `${`.
+		pub fn substitute_env_vars<F>(value: &str, policy: EnvPolicy, lookup: &F) -> Result<String, String>
+			where F: Fn(&str) -> Option<String>
+		{
+			let mut result = String::with_capacity(value.len());
+			let mut rest = value;
+			while let Some(start) = rest.find("${") {
+				if rest[..start].ends_with('$') {
+					result.push_str(&rest[..start - 1]);
+					result.push_str("${");
+					rest = &rest[start + 2..];
+					continue;
+				}
+				result.push_str(&rest[..start]);
+				let end = rest[start..].find('}').ok_or_else(|| format!("Unterminated variable reference in {}", value))?;
+				let name = &rest[start + 2..start + end];
+				if name.is_empty() {
+					return Err(format!("Empty variable reference in {}", value));
+				}
+				match (lookup(name), policy) {
+					(Some(var), _) => result.push_str(&var),
+					(None, EnvPolicy::Strict) => return Err(format!("Environment variable {} is not set", name)),
+					(None, EnvPolicy::Lenient) => println_stderr!("Environment variable {} is not set, using an empty string", name),
+				}
+				rest = &rest[start + end + 1..];
+			}
+			result.push_str(rest);
+			Ok(result)
+		}
+
+		/// Replace all strings of the value, including those in nested arrays and tables.
+		fn map_strings<F>(value: toml::Value, f: &F) -> Result<toml::Value, String>
+			where F: Fn(&str) -> Result<String, String>
+		{
+			Ok(match value {
+				toml::Value::String(s) => toml::Value::String(f(&s)?),
+				toml::Value::Array(items) => toml::Value::Array(items.into_iter()
+					.map(|item| map_strings(item, f))
+					.collect::<Result<_, _>>()?),
+				toml::Value::Table(table) => toml::Value::Table(table.into_iter()
+					.map(|(key, item)| map_strings(item, f).map(|item| (key, item)))
+					.collect::<Result<_, _>>()?),
+				other => other,
+			})
+		}
+
+		/// Source of the effective value of a flag.
+		#[derive(Debug, PartialEq, Clone, Copy)]
+		pub enum ValueSource {
+			/// Built-in default.
+			Default,
+			/// Config file, including its includes and the selected profile.
+			ConfigFile,
+			/// Command line.
+			CommandLine,
+		}
+
+		impl fmt::Display for ValueSource {
+			fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+				match *self {
+					ValueSource::Default => write!(f, "default"),
+					ValueSource::ConfigFile => write!(f, "config file"),
+					ValueSource::CommandLine => write!(f, "command line"),
+				}
+			}
+		}
+
+		/// Rendering of flag values, `None` if the flag is not set.
+		pub trait FlagValue {
+			fn render(&self) -> Option<String>;
+		}
+
+		impl FlagValue for String {
+			fn render(&self) -> Option<String> { Some(format!("{:?}", self)) }
+		}
+
+		impl FlagValue for Vec<String> {
+			fn render(&self) -> Option<String> { Some(format!("{:?}", self)) }
+		}
+
+		impl FlagValue for bool {
+			fn render(&self) -> Option<String> { Some(self.to_string()) }
+		}
+
+		impl FlagValue for i32 {
+			fn render(&self) -> Option<String> { Some(self.to_string()) }
+		}
+
+		impl FlagValue for u16 {
+			fn render(&self) -> Option<String> { Some(self.to_string()) }
+		}
+
+		impl FlagValue for u32 {
+			fn render(&self) -> Option<String> { Some(self.to_string()) }
+		}
+
+		impl FlagValue for u64 {
+			fn render(&self) -> Option<String> { Some(self.to_string()) }
+		}
+
+		impl FlagValue for usize {
+			fn render(&self) -> Option<String> { Some(self.to_string()) }
+		}
+
+		impl<T: FlagValue> FlagValue for Option<T> {
+			fn render(&self) -> Option<String> { self.as_ref().and_then(FlagValue::render) }
+		}
+
+		/// Command line flag of the `Args` field, e.g. `--max-peers` for `flag_max_peers`.
+		pub fn flag_name(field: &str) -> String {
+			format!("--{}", field.trim_left_matches("flag_").replace('_', "-"))
+		}
+
+		/// Merge `overrides` into `base`, tables are merged recursively, other values are replaced.
+		fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+			for (key, value) in overrides {
+				let value = match (base.get_mut(&key), value) {
+					(Some(&mut toml::Value::Table(ref mut table)), toml::Value::Table(overrides)) => {
+						merge_tables(table, overrides);
+						None
+					},
+					(_, value) => Some(value),
+				};
+				if let Some(value) = value {
+					base.insert(key, value);
+				}
+			}
 		}
 
 		impl ArgsError {
@@ -91,7 +247,12 @@ macro_rules! usage {
 						println_stderr!("You have some extra fields in your config file:");
 						println_stderr!("{}", fields);
 						process::exit(2)
-					}
+					},
+					ArgsError::ConfigFile(e) => {
+						println_stderr!("There is an error in config file.");
+						println_stderr!("{}", e);
+						process::exit(2)
+					},
 				}
 			}
 		}
@@ -153,32 +314,82 @@ macro_rules! usage {
 		impl Args {
 
 			pub fn parse<S: AsRef<str>>(command: &[S]) -> Result<Self, ArgsError> {
+				Self::parse_with_sources(command).map(|(args, _)| args)
+			}
+
+			/// Parse the arguments and tell where the values come from.
+			/// Command line only fields are listed only if given.
+			pub fn parse_with_sources<S: AsRef<str>>(command: &[S]) -> Result<(Self, Vec<(&'static str, ValueSource)>), ArgsError> {
 				let raw_args = RawArgs::parse(command)?;
 
 				// Skip loading config file if no_config flag is specified
 				if raw_args.flag_no_config {
-					return Ok(raw_args.into_args(Config::default()));
+					if let Some(ref profile) = raw_args.flag_profile {
+						return Err(ArgsError::ConfigFile(format!("Profile {} can't be used with --no-config", profile)));
+					}
+					let sources = raw_args.sources(&Config::default());
+					return Ok((raw_args.into_args(Config::default()), sources));
 				}
 
 				let config_file = raw_args.flag_config.clone().unwrap_or_else(|| raw_args.clone().into_args(Config::default()).flag_config);
 				let config_file = replace_home(&::dir::default_data_path(), &config_file);
-				let config = match (fs::File::open(&config_file), raw_args.flag_config.is_some()) {
+				let table = match (fs::File::open(&config_file), raw_args.flag_config.is_some()) {
+					// Fetch remote config file
+					_ if ::remote_config::is_remote(&config_file) => {
+						Some(Self::load_remote_config_table(&config_file, raw_args.flag_config_public_key.as_ref().map(|k| k.as_str()), &raw_args.data_path())?)
+					},
 					// Load config file
-					(Ok(mut file), _) => {
+					(Ok(_), _) => {
 						println_stderr!("Loading config file from {}", &config_file);
-						let mut config = String::new();
-						file.read_to_string(&mut config).map_err(|e| ArgsError::Config(config_file, e))?;
-						Self::parse_config(&config)?
+						Some(Self::load_config_table(Path::new(&config_file), &mut Vec::new())?)
+					},
+					(Err(_), false) if raw_args.flag_profile.is_some() => {
+						return Err(ArgsError::ConfigFile(format!("Config file {} with the profile not found", config_file)));
 					},
 					// Don't display error in case default config cannot be loaded.
-					(Err(_), false) => Config::default(),
+					(Err(_), false) => None,
 					// Config set from CLI (fail with error)
 					(Err(e), true) => {
 						return Err(ArgsError::Config(config_file, e));
 					},
 				};
 
-				Ok(raw_args.into_args(config))
+				let config = match table {
+					Some(table) => {
+						let table = Self::apply_profile(table, raw_args.flag_profile.as_ref().map(|p| p.as_str()))?;
+						let chain = match (raw_args.flag_chain.as_ref(), raw_args.flag_testnet) {
+							(Some(chain), _) => Some(chain.as_str()),
+							(None, true) => Some("testnet"),
+							(None, false) => None,
+						};
+						let table = Self::apply_chain_section(table, chain)?;
+						let policy = match raw_args.flag_config_env {
+							Some(ref policy) => policy.parse().map_err(ArgsError::ConfigFile)?,
+							None => EnvPolicy::Strict,
+						};
+						let master_key = ::config_secrets::master_key(raw_args.flag_config_key_file.as_ref().map(|f| f.as_str()))
+							.map_err(ArgsError::ConfigFile)?;
+						// variables are substituted first, so encrypted values may come from the environment
+						let resolve = |value: &str| {
+							let value = substitute_env_vars(value, policy, &|name: &str| ::std::env::var(name).ok())?;
+							match (::config_secrets::is_encrypted(&value), master_key.as_ref()) {
+								(false, _) => Ok(value),
+								(true, Some(key)) => ::config_secrets::decrypt(&value, key),
+								(true, None) => Err(format!("Encrypted value found, but no master key given with --config-key-file or {}", ::config_secrets::KEY_ENV)),
+							}
+						};
+						let table = match map_strings(toml::Value::Table(table), &resolve) {
+							Ok(toml::Value::Table(table)) => table,
+							Ok(_) => unreachable!("tables are substituted to tables; qed"),
+							Err(e) => return Err(ArgsError::ConfigFile(e)),
+						};
+						Self::decode_config(table)?
+					},
+					None => Config::default(),
+				};
+
+				let sources = raw_args.sources(&config);
+				Ok((raw_args.into_args(config), sources))
 			}
 
 			#[cfg(test)]
@@ -191,21 +402,177 @@ macro_rules! usage {
 				RawArgs::parse(command).map(|raw| raw.into_args(config)).map_err(ArgsError::Docopt)
 			}
 
+			#[cfg(test)]
 			fn parse_config(config: &str) -> Result<Config, ArgsError> {
+				Self::parse_config_table(config).and_then(Self::decode_config)
+			}
+
+			fn parse_config_table(config: &str) -> Result<toml::Table, ArgsError> {
 				let mut value_parser = toml::Parser::new(&config);
 				match value_parser.parse() {
-					Some(value) => {
-						let mut decoder = toml::Decoder::new(toml::Value::Table(value));
-						let result = rustc_serialize::Decodable::decode(&mut decoder);
-
-						match (result, decoder.toml) {
-							(Err(e), _) => Err(e.into()),
-							(_, Some(toml)) => Err(ArgsError::UnknownFields(toml::encode_str(&toml))),
-							(Ok(config), None) => Ok(config),
+					Some(value) => Ok(value),
+					None => Err(ArgsError::Parsing(value_parser.errors)),
+				}
+			}
+
+			fn decode_config(table: toml::Table) -> Result<Config, ArgsError> {
+				let mut decoder = toml::Decoder::new(toml::Value::Table(table));
+				let result = rustc_serialize::Decodable::decode(&mut decoder);
+
+				match (result, decoder.toml) {
+					(Err(e), _) => Err(e.into()),
+					(_, Some(toml)) => Err(ArgsError::UnknownFields(toml::encode_str(&toml))),
+					(Ok(config), None) => Ok(config),
+				}
+			}
+
+			/// Read the config file merged over the files it includes. Relative include paths
+			/// are resolved against the directory of the including file.
+			fn load_config_table(path: &Path, parents: &mut Vec<PathBuf>) -> Result<toml::Table, ArgsError> {
+				let display = path.display().to_string();
+				let path = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+				if parents.contains(&path) {
+					return Err(ArgsError::ConfigFile(format!("Config file {} includes itself", display)));
+				}
+				if parents.len() >= MAX_INCLUDE_DEPTH {
+					return Err(ArgsError::ConfigFile(format!("Includes of {} are nested too deeply", display)));
+				}
+
+				let mut config = String::new();
+				fs::File::open(&path)
+					.and_then(|mut file| file.read_to_string(&mut config))
+					.map_err(|e| ArgsError::Config(display.clone(), e))?;
+				let mut table = Self::parse_config_table(&config)?;
+
+				let includes = match table.remove("include") {
+					None => Vec::new(),
+					Some(toml::Value::Array(includes)) => includes,
+					Some(_) => return Err(ArgsError::ConfigFile(format!("include in {} must be an array of file names", display))),
+				};
+
+				parents.push(path.clone());
+				let mut merged = toml::Table::new();
+				for include in includes {
+					let include = match include {
+						toml::Value::String(include) => replace_home(&::dir::default_data_path(), &include),
+						_ => return Err(ArgsError::ConfigFile(format!("include in {} must be an array of file names", display))),
+					};
+					let include = path.parent().map_or_else(|| PathBuf::from(&include), |dir| dir.join(&include));
+					let included = Self::load_config_table(&include, parents)?;
+					merge_tables(&mut merged, included);
+				}
+				parents.pop();
+
+				merge_tables(&mut merged, table);
+				Ok(merged)
+			}
+
+			/// Fetch the config file from the URL and verify its signature. Remote config files can't include other files.
+			/// Versions of accepted config files are stored under `data_path`.
+			fn load_remote_config_table(url: &str, public_key: Option<&str>, data_path: &str) -> Result<toml::Table, ArgsError> {
+				let public_key = public_key.ok_or_else(|| ArgsError::ConfigFile(format!("Config file {} can't be verified without --config-public-key", url)))?;
+				println_stderr!("Fetching config file from {}", url);
+				let config = ::remote_config::fetch(url, public_key, Path::new(&data_path)).map_err(ArgsError::ConfigFile)?;
+				let table = Self::parse_config_table(&config)?;
+				if table.contains_key("include") {
+					return Err(ArgsError::ConfigFile(format!("Remote config file {} can't include other files", url)));
+				}
+				Ok(table)
+			}
+
+			/// Merge the selected `[profile.NAME]` section over the rest of the config.
+			fn apply_profile(mut table: toml::Table, profile: Option<&str>) -> Result<toml::Table, ArgsError> {
+				let mut profiles = match table.remove("profile") {
+					None => toml::Table::new(),
+					Some(toml::Value::Table(profiles)) => profiles,
+					Some(_) => return Err(ArgsError::ConfigFile("profile must be a table of named profiles".into())),
+				};
+				if let Some(name) = profile {
+					match profiles.remove(name) {
+						Some(toml::Value::Table(overrides)) => merge_tables(&mut table, overrides),
+						Some(_) => return Err(ArgsError::ConfigFile(format!("Profile {} must be a table", name))),
+						None => return Err(ArgsError::ConfigFile(format!("Profile {} not found in config file", name))),
+					}
+				}
+				Ok(table)
+			}
+
+			/// Merge the `[chain.NAME]` section of the selected chain. Names are compared
+			/// as chain specifications, so `[chain.mainnet]` applies to `--chain foundation`.
+			/// The chain is taken from the command line, then from the config file.
+			fn apply_chain_section(mut table: toml::Table, chain: Option<&str>) -> Result<toml::Table, ArgsError> {
+				use params::SpecType;
+
+				let sections = match table.remove("chain") {
+					None => return Ok(table),
+					Some(toml::Value::Table(sections)) => sections,
+					Some(_) => return Err(ArgsError::ConfigFile("chain must be a table of chain sections".into())),
+				};
+				let chain = match (chain, table.get("parity")) {
+					(Some(chain), _) => chain.to_owned(),
+					(None, Some(&toml::Value::Table(ref parity))) => match parity.get("chain") {
+						Some(&toml::Value::String(ref chain)) => chain.clone(),
+						_ => Args::default().flag_chain,
+					},
+					(None, _) => Args::default().flag_chain,
+				};
+				let selected: Result<SpecType, _> = chain.parse();
+
+				let mut matching = sections.into_iter().filter(|&(ref name, _)| name.parse::<SpecType>() == selected);
+				match (matching.next(), matching.next()) {
+					(None, _) => {},
+					(Some((first, _)), Some((second, _))) => {
+						return Err(ArgsError::ConfigFile(format!("Sections chain.{} and chain.{} both apply to chain {}", first, second, chain)));
+					},
+					(Some((name, toml::Value::Table(overrides))), None) => {
+						let sets_chain = match overrides.get("parity") {
+							Some(&toml::Value::Table(ref parity)) => parity.contains_key("chain"),
+							_ => false,
+						};
+						if sets_chain {
+							return Err(ArgsError::ConfigFile(format!("Section chain.{} can't change the chain", name)));
 						}
+						merge_tables(&mut table, overrides);
 					},
-					None => Err(ArgsError::Parsing(value_parser.errors)),
+					(Some((name, _)), None) => return Err(ArgsError::ConfigFile(format!("Section chain.{} must be a table", name))),
 				}
+				Ok(table)
+			}
+
+			/// Names of the fields which differ from `other`.
+			pub fn changed_flags(&self, other: &Args) -> Vec<&'static str> {
+				let mut changed = Vec::new();
+				$(
+					if self.$field_a != other.$field_a {
+						changed.push(stringify!($field_a));
+					}
+				)*
+				$(
+					if self.$field != other.$field {
+						changed.push(stringify!($field));
+					}
+				)*
+				$(
+					if self.$field_s != other.$field_s {
+						changed.push(stringify!($field_s));
+					}
+				)*
+				changed
+			}
+
+			/// Rendered values of all fields, `None` if not set.
+			pub fn flag_values(&self) -> Vec<(&'static str, Option<String>)> {
+				vec![
+					$(
+						(stringify!($field_a), self.$field_a.render()),
+					)*
+					$(
+						(stringify!($field), self.$field.render()),
+					)*
+					$(
+						(stringify!($field_s), self.$field_s.render()),
+					)*
+				]
 			}
 
 			pub fn print_version() -> String {
@@ -214,6 +581,12 @@ macro_rules! usage {
 		}
 
 		impl RawArgs {
+			/// Data directory given on the command line (the config file isn't loaded yet), resolved like `Configuration::directories`.
+			fn data_path(&self) -> String {
+				let base_path = self.flag_base_path.clone().and_then(|path| path).unwrap_or_else(::dir::default_data_path);
+				replace_home("", self.flag_datadir.as_ref().unwrap_or(&base_path))
+			}
+
 			fn into_args(self, config: Config) -> Args {
 				let mut args = Args::default();
 				$(
@@ -228,6 +601,33 @@ macro_rules! usage {
 				args
 			}
 
+			fn sources(&self, config: &Config) -> Vec<(&'static str, ValueSource)> {
+				let defaults = RawArgs::default();
+				let mut sources = Vec::new();
+				$(
+					if self.$field_a != defaults.$field_a {
+						sources.push((stringify!($field_a), ValueSource::CommandLine));
+					}
+				)*
+				$(
+					let from_config: Option<$typ> = $from_config(config);
+					sources.push((stringify!($field), match (self.$field.is_some(), from_config.is_some()) {
+						(true, _) => ValueSource::CommandLine,
+						(false, true) => ValueSource::ConfigFile,
+						(false, false) => ValueSource::Default,
+					}));
+				)*
+				$(
+					let from_config: Option<$typ_s> = $from_config_s(config);
+					sources.push((stringify!($field_s), match (self.$field_s.is_some(), from_config.is_some()) {
+						(true, _) => ValueSource::CommandLine,
+						(false, true) => ValueSource::ConfigFile,
+						(false, false) => ValueSource::Default,
+					}));
+				)*
+				sources
+			}
+
 			pub fn parse<S: AsRef<str>>(command: &[S]) -> Result<Self, DocoptError> {
 				Docopt::new(Self::usage()).and_then(|d| d.argv(command).decode())
 			}
@@ -0,0 +1,187 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `parity config dump`: the effective configuration and where its values come from.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use ethcore::client::Mode;
+use cli::{Args, ValueSource, flag_name};
+use dir::Directories;
+use params::{SpecType, Pruning, Switch};
+use user_defaults::UserDefaults;
+
+/// Where the effective value of a flag comes from.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Source {
+	/// Command line, config file or the built-in default.
+	Args(ValueSource),
+	/// Value remembered from the last run, used when the flag is `auto` or `last`.
+	UserDefaults,
+}
+
+impl fmt::Display for Source {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Source::Args(ref source) => write!(f, "{}", source),
+			Source::UserDefaults => write!(f, "user defaults"),
+		}
+	}
+}
+
+/// Effective value of a flag.
+#[derive(Debug, PartialEq)]
+pub struct FlagEntry {
+	/// Name of the `Args` field.
+	pub name: &'static str,
+	/// Rendered value, `None` if the flag is not set.
+	pub value: Option<String>,
+	pub source: Source,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ConfigDumpCmd {
+	pub flags: Vec<FlagEntry>,
+	pub spec: SpecType,
+	pub dirs: Directories,
+	pub pruning: Pruning,
+	pub tracing: Switch,
+	pub fat_db: Switch,
+	pub mode: Option<Mode>,
+}
+
+/// Values and sources of the flags. Command line only flags are included only if given.
+pub fn flag_entries(args: &Args, sources: &[(&'static str, ValueSource)]) -> Vec<FlagEntry> {
+	let values: BTreeMap<_, _> = args.flag_values().into_iter().collect();
+	sources.iter()
+		.filter(|&&(name, _)| name.starts_with("flag_"))
+		.map(|&(name, source)| FlagEntry {
+			name: name,
+			value: values.get(name).cloned().and_then(|value| value),
+			source: Source::Args(source),
+		})
+		.collect()
+}
+
+fn switch_value(enabled: bool) -> &'static str {
+	match enabled {
+		true => "on",
+		false => "off",
+	}
+}
+
+/// Replace `auto` and `last` values with the values remembered from the last run.
+fn apply_user_defaults(flags: &mut [FlagEntry], cmd: &ConfigDumpCmd, user_defaults: &UserDefaults) {
+	if user_defaults.is_first_launch {
+		return;
+	}
+
+	for flag in flags {
+		let value = match flag.name {
+			"flag_pruning" if cmd.pruning == Pruning::Auto => user_defaults.pruning.as_str().to_owned(),
+			"flag_tracing" if cmd.tracing == Switch::Auto => switch_value(user_defaults.tracing).to_owned(),
+			"flag_fat_db" if cmd.fat_db == Switch::Auto => switch_value(user_defaults.fat_db).to_owned(),
+			"flag_mode" if cmd.mode.is_none() => user_defaults.mode.to_string(),
+			_ => continue,
+		};
+		flag.value = Some(format!("{:?}", value));
+		flag.source = Source::UserDefaults;
+	}
+}
+
+/// One `--flag = value  # source` line per flag, with aligned comments.
+fn render(flags: &[FlagEntry]) -> String {
+	let lines: Vec<_> = flags.iter()
+		.map(|flag| (format!("{} = {}", flag_name(flag.name), flag.value.as_ref().map_or("<not set>", |v| v.as_str())), flag.source))
+		.collect();
+	let width = lines.iter().map(|&(ref line, _)| line.chars().count()).max().unwrap_or(0);
+	lines.into_iter()
+		.map(|(line, source)| format!("{:width$}  # {}", line, source, width = width))
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+pub fn execute(mut cmd: ConfigDumpCmd) -> Result<String, String> {
+	let spec = cmd.spec.spec()?;
+	let genesis_hash = spec.genesis_header().hash();
+	let db_dirs = cmd.dirs.database(genesis_hash, cmd.spec.legacy_fork_name(), spec.data_dir.clone());
+	let user_defaults = UserDefaults::load(&db_dirs.user_defaults_path())?;
+
+	let mut flags = ::std::mem::replace(&mut cmd.flags, Vec::new());
+	apply_user_defaults(&mut flags, &cmd, &user_defaults);
+	Ok(render(&flags))
+}
+
+#[cfg(test)]
+mod tests {
+	use util::journaldb::Algorithm;
+	use cli::ValueSource;
+	use dir::Directories;
+	use params::{SpecType, Pruning, Switch};
+	use user_defaults::UserDefaults;
+	use super::{ConfigDumpCmd, FlagEntry, Source, apply_user_defaults, render};
+
+	fn entry(name: &'static str, value: &str, source: ValueSource) -> FlagEntry {
+		FlagEntry {
+			name: name,
+			value: Some(value.into()),
+			source: Source::Args(source),
+		}
+	}
+
+	#[test]
+	fn should_render_aligned_values_with_sources() {
+		let flags = vec![
+			entry("flag_chain", "\"kovan\"", ValueSource::ConfigFile),
+			entry("flag_max_peers", "100", ValueSource::CommandLine),
+			FlagEntry { name: "flag_unlock", value: None, source: Source::Args(ValueSource::Default) },
+		];
+
+		assert_eq!(render(&flags), "\
+--chain = \"kovan\"     # config file
+--max-peers = 100     # command line
+--unlock = <not set>  # default");
+	}
+
+	#[test]
+	fn should_use_remembered_values_for_auto_flags() {
+		let cmd = ConfigDumpCmd {
+			flags: Vec::new(),
+			spec: SpecType::Foundation,
+			dirs: Directories::default(),
+			pruning: Pruning::Auto,
+			tracing: Switch::On,
+			fat_db: Switch::Auto,
+			mode: None,
+		};
+		let mut user_defaults = UserDefaults::default();
+		user_defaults.is_first_launch = false;
+		user_defaults.pruning = Algorithm::Archive;
+		let mut flags = vec![
+			entry("flag_pruning", "\"auto\"", ValueSource::Default),
+			entry("flag_tracing", "\"on\"", ValueSource::CommandLine),
+			entry("flag_fat_db", "\"auto\"", ValueSource::Default),
+			entry("flag_mode", "\"last\"", ValueSource::Default),
+		];
+
+		apply_user_defaults(&mut flags, &cmd, &user_defaults);
+
+		assert_eq!(flags[0], FlagEntry { name: "flag_pruning", value: Some("\"archive\"".into()), source: Source::UserDefaults });
+		assert_eq!(flags[1], entry("flag_tracing", "\"on\"", ValueSource::CommandLine));
+		assert_eq!(flags[2], FlagEntry { name: "flag_fat_db", value: Some("\"off\"".into()), source: Source::UserDefaults });
+		assert_eq!(flags[3], FlagEntry { name: "flag_mode", value: Some("\"active\"".into()), source: Source::UserDefaults });
+	}
+}
@@ -0,0 +1,161 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Encrypted values of the config file.
+//!
+//! String values starting with `enc:` are followed by the hex encoded
+//! `salt ++ iv ++ ciphertext ++ mac`. The value is encrypted with AES-128-CTR
+//! using a key derived from the master key, like in key files.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::env;
+use isatty::stdin_isatty;
+use rpassword::read_password;
+use ethcrypto::{self, aes};
+use rustc_serialize::hex::{ToHex, FromHex};
+use util::{H128, H256};
+use util::sha3::Hashable;
+
+/// Prefix of encrypted values.
+pub const PREFIX: &'static str = "enc:";
+/// Environment variable with the master key, used if no key file is given.
+pub const KEY_ENV: &'static str = "PARITY_CONFIG_KEY";
+
+const KEY_ITERATIONS: u32 = 10240;
+const SALT_LEN: usize = 32;
+const IV_LEN: usize = 16;
+const MAC_LEN: usize = 32;
+
+/// Master key read from the file or, without a file, from the environment.
+pub fn master_key(file: Option<&str>) -> Result<Option<String>, String> {
+	match file {
+		Some(path) => {
+			let mut key = String::new();
+			File::open(path).and_then(|mut f| f.read_to_string(&mut key))
+				.map_err(|e| format!("Unable to read config key file {}: {}", path, e))?;
+			Ok(Some(key.trim().to_owned()))
+		},
+		None => Ok(env::var(KEY_ENV).ok()),
+	}
+}
+
+/// Checks if the value is encrypted.
+pub fn is_encrypted(value: &str) -> bool {
+	value.starts_with(PREFIX)
+}
+
+fn mac(derived_right_bits: &[u8], encrypted: &[u8]) -> H256 {
+	ethcrypto::derive_mac(derived_right_bits, encrypted).sha3()
+}
+
+/// Encrypt the value with the master key, the result includes the prefix.
+pub fn encrypt(value: &str, master_key: &str) -> String {
+	let salt = H256::random();
+	let iv = H128::random();
+	let (derived_left_bits, derived_right_bits) = ethcrypto::derive_key_iterations(master_key, &salt.0, KEY_ITERATIONS);
+	let mut encrypted = vec![0u8; value.len()];
+	aes::encrypt(&derived_left_bits, &iv, value.as_bytes(), &mut encrypted);
+
+	let mut data = Vec::with_capacity(SALT_LEN + IV_LEN + encrypted.len() + MAC_LEN);
+	data.extend_from_slice(&salt);
+	data.extend_from_slice(&iv);
+	data.extend_from_slice(&encrypted);
+	data.extend_from_slice(&mac(&derived_right_bits, &encrypted));
+	format!("{}{}", PREFIX, data.to_hex())
+}
+
+/// Decrypt a value with the prefix.
+pub fn decrypt(value: &str, master_key: &str) -> Result<String, String> {
+	let data = match value.starts_with(PREFIX) {
+		true => value[PREFIX.len()..].from_hex().map_err(|_| "Encrypted value is not valid hex".to_owned())?,
+		false => return Err("Value is not encrypted".into()),
+	};
+	if data.len() < SALT_LEN + IV_LEN + MAC_LEN {
+		return Err("Encrypted value is too short".into());
+	}
+
+	let (salt, rest) = data.split_at(SALT_LEN);
+	let (iv, rest) = rest.split_at(IV_LEN);
+	let (encrypted, expected_mac) = rest.split_at(rest.len() - MAC_LEN);
+	let (derived_left_bits, derived_right_bits) = ethcrypto::derive_key_iterations(master_key, &H256::from_slice(salt).0, KEY_ITERATIONS);
+	if &*mac(&derived_right_bits, encrypted) != expected_mac {
+		return Err("Unable to decrypt value, invalid master key".into());
+	}
+
+	let mut plain = vec![0u8; encrypted.len()];
+	aes::decrypt(&derived_left_bits, iv, encrypted, &mut plain);
+	String::from_utf8(plain).map_err(|_| "Decrypted value is not valid UTF-8".into())
+}
+
+/// Read the value to encrypt from the standard input.
+/// On a terminal the value is typed twice without echo, otherwise the input is read until EOF.
+/// The value is never passed as an argument, so it doesn't end up in the shell history or the process list.
+fn read_value() -> Result<String, String> {
+	if !stdin_isatty() {
+		let mut value = String::new();
+		io::stdin().read_to_string(&mut value).map_err(|e| format!("Unable to read the value from stdin: {}", e))?;
+		return Ok(value.trim_right_matches(|c: char| c == '\n' || c == '\r').to_owned());
+	}
+
+	const STDIN_ERROR: &'static str = "Unable to read the value from the terminal.";
+	// prompts go to stderr, stdout is the encrypted value only.
+	let prompt = |text: &str| {
+		let mut stderr = io::stderr();
+		let _ = write!(stderr, "{}", text).and_then(|_| stderr.flush());
+	};
+
+	prompt("Type value: ");
+	let value = read_password().map_err(|_| STDIN_ERROR.to_owned())?;
+	prompt("Repeat value: ");
+	let value_repeat = read_password().map_err(|_| STDIN_ERROR.to_owned())?;
+
+	if value != value_repeat {
+		return Err("Values do not match!".into());
+	}
+	Ok(value)
+}
+
+/// Encrypt a value for the config file, `parity tools encrypt`. The value is read from stdin.
+pub fn execute_encrypt(key_file: Option<String>) -> Result<String, String> {
+	let key = match master_key(key_file.as_ref().map(|f| f.as_str()))? {
+		Some(key) => key,
+		None => return Err(format!("Master key is required, use --config-key-file or set {}", KEY_ENV)),
+	};
+	let value = read_value()?;
+	if value.is_empty() {
+		return Err("Value to encrypt is empty.".into());
+	}
+	Ok(encrypt(&value, &key))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{encrypt, decrypt, is_encrypted};
+
+	#[test]
+	fn should_encrypt_and_decrypt_values() {
+		let encrypted = encrypt("stratum-s3cr3t", "master");
+
+		assert!(is_encrypted(&encrypted));
+		assert!(!encrypted.contains("stratum-s3cr3t"));
+		assert_eq!(decrypt(&encrypted, "master"), Ok("stratum-s3cr3t".into()));
+		assert!(decrypt(&encrypted, "other").is_err());
+		assert!(decrypt("enc:00ff", "master").is_err());
+		assert!(decrypt("enc:xyz", "master").is_err());
+		assert!(decrypt("plain", "master").is_err());
+	}
+}
@@ -15,43 +15,74 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::time::Duration;
+use std::fs::File;
 use std::io::{Read, Write, stderr};
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::cmp::max;
-use cli::{Args, ArgsError};
-use util::{Hashable, H256, U256, Uint, Bytes, version_data, Address};
+use cli::{Args, ArgsError, ValueSource};
+use util::{Hashable, H256, U256, Uint, Bytes, version_data, Address, clean_0x};
 use util::journaldb::Algorithm;
 use util::Colour;
-use ethsync::{NetworkConfiguration, is_valid_node_url, AllowIP};
+use ethsync::{NetworkConfiguration, SnapshotServingConfig, SnapshotDownloadConfig, LesConfig, PipConfig, SyncSchedule, ProtocolSlots, is_valid_node_url, enode_id, parse_wss_url, AllowIP};
 use ethcore::ethstore::ethkey::{Secret, Public};
-use ethcore::client::{VMType};
-use ethcore::miner::{MinerOptions, Banning, StratumOptions};
+use ethcore::client::{VMType, BlockRange, SpanExportConfig, DiskGuardConfig};
+use ethcore::miner::{MinerOptions, Banning, StratumOptions, TransactionFilterList, StandbyOptions};
 use ethcore::verification::queue::VerifierSettings;
+use ethcore::spec::{Checkpoint, LightCheckpoint};
+use ethcore::snapshot::{Schedule as SnapshotSchedule, Period as SnapshotPeriod, ThrottleConfig as SnapshotThrottle, RetentionPolicy};
 
 use rpc::{IpcConfiguration, HttpConfiguration, WsConfiguration};
-use rpc_apis::ApiSet;
+use rpc_apis::{Api, ApiSet, ApiWake};
 use parity_rpc::NetworkSettings;
-use cache::CacheConfig;
+use parity_rpc::limits::RequestLimits;
+use priority::PriorityConfig;
+use cache::{CacheConfig, MemoryBudget, DEFAULT_DB_CACHE_SIZE, DEFAULT_BC_CACHE_SIZE, DEFAULT_BLOCK_QUEUE_SIZE_LIMIT_MB,
+	DEFAULT_STATE_CACHE_SIZE, DEFAULT_TX_QUEUE_SIZE};
 use helpers::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_price, replace_home, replace_home_for_db,
-geth_ipc_path, parity_ipc_path, to_bootnodes, to_addresses, to_address, to_gas_limit, to_queue_strategy};
-use params::{SpecType, ResealPolicy, AccountsConfig, GasPricerConfig, MinerExtras, Pruning, Switch};
-use ethcore_logger::Config as LogConfig;
+geth_ipc_path, parity_ipc_path, to_bootnodes, to_addresses, to_address, to_gas_limit, to_queue_strategy, to_locals_policy, to_ip_network,
+passwords_from_files, to_tx_queue_persistence};
+use params::{SpecType, ResealPolicy, AccountsConfig, GasPricerConfig, MinerExtras, Pruning, Switch, CheckpointConfig, LightCheckpointConfig};
+use ethcore_logger::{Config as LogConfig, Rotation as LogRotation, SyslogConfig};
 use dir::{self, Directories, default_hypervisor_path, default_local_path, default_data_path};
 use dapps::Configuration as DappsConfiguration;
 use ipfs::Configuration as IpfsConfiguration;
-use signer::{Configuration as SignerConfiguration};
+use health::Configuration as HealthConfiguration;
+use sealing_watchdog::Configuration as SealingWatchdogConfiguration;
+use dev_accounts::DevAccounts;
+use light::on_demand::{Config as OnDemandConfig, RequestTimeouts, PeerSelection};
+use light::net::request_credits::{CostTable, FlowParams};
+use light::request::Kind as RequestKind;
+use remote_config;
+use signer::{self, Configuration as SignerConfiguration};
 use secretstore::Configuration as SecretStoreConfiguration;
 use updater::{UpdatePolicy, UpdateFilter, ReleaseTrack};
 use run::RunCmd;
-use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, KillBlockchain, ExportState, DataFormat};
+use hybrid::{self, Hybrid};
+use service::ServiceCmd;
+use supervisor::SupervisorCmd;
+use config_dump::{self, ConfigDumpCmd};
+use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, KillBlockchain, ExportState, ExportTable, DataFormat};
+use csv_export::Table;
 use presale::ImportWallet;
 use account::{AccountCmd, NewAccount, ListAccounts, ImportAccounts, ImportFromGethAccounts};
-use snapshot::{self, SnapshotCommand};
+use snapshot::{self, SnapshotCommand, WarpFrom};
+use snapshot_encryption::EncryptionKeys;
+use reserved_peers::read_reserved_peers;
+use reload::Settings as ReloadableSettings;
+use metrics::StatsdConfig;
+use sql_export::{SqlExportConfig, connection_env};
+use event_publisher::EventPublisherConfig;
 
 const AUTHCODE_FILENAME: &'static str = "authcodes";
 
+// how many blocks to wait before starting a periodic snapshot.
+const SNAPSHOT_HISTORY: u64 = 100;
+
+// seconds to wait after taking the engine lock before sealing.
+const ENGINE_LOCK_GRACE_PERIOD: u64 = 5;
+
 #[derive(Debug, PartialEq)]
 pub enum Cmd {
 	Run(RunCmd),
@@ -66,17 +97,37 @@ pub enum Cmd {
 		port: u16,
 		authfile: PathBuf,
 	},
+	SignerApprove {
+		id: Option<usize>,
+		pwfile: Option<PathBuf>,
+		port: u16,
+		ipc_path: PathBuf,
+		authfile: PathBuf,
+	},
 	SignerList {
 		port: u16,
+		ipc_path: PathBuf,
 		authfile: PathBuf
 	},
 	SignerReject {
 		id: Option<usize>,
 		port: u16,
+		ipc_path: PathBuf,
 		authfile: PathBuf
 	},
 	Snapshot(SnapshotCommand),
 	Hash(Option<String>),
+	Service(ServiceCmd),
+	Supervise(SupervisorCmd),
+	ConfigDump(ConfigDumpCmd),
+	EncryptConfigValue {
+		key_file: Option<String>,
+	},
+	SignConfig {
+		file: String,
+		key_file: Option<String>,
+		valid_for_days: Option<u64>,
+	},
 }
 
 pub struct Execute {
@@ -88,15 +139,18 @@ pub struct Execute {
 pub struct Configuration {
 	pub args: Args,
 	pub spec_name_override: Option<String>,
+	/// Where the values of the flags come from.
+	pub sources: Vec<(&'static str, ValueSource)>,
 }
 
 impl Configuration {
 	pub fn parse<S: AsRef<str>>(command: &[S], spec_name_override: Option<String>) -> Result<Self, ArgsError> {
-		let args = Args::parse(command)?;
+		let (args, sources) = Args::parse_with_sources(command)?;
 
 		let config = Configuration {
 			args: args,
 			spec_name_override: spec_name_override,
+			sources: sources,
 		};
 
 		Ok(config)
@@ -113,13 +167,13 @@ impl Configuration {
 			mode => Some(to_mode(&mode, self.args.flag_mode_timeout, self.args.flag_mode_alarm)?),
 		};
 		let update_policy = self.update_policy()?;
-		let logger_config = self.logger_config();
+		let logger_config = self.logger_config()?;
 		let ws_conf = self.ws_config()?;
 		let http_conf = self.http_config()?;
 		let ipc_conf = self.ipc_config()?;
 		let net_conf = self.net_config()?;
 		let network_id = self.network_id();
-		let cache_config = self.cache_config();
+		let cache_config = self.cache_config()?;
 		let tracing = self.args.flag_tracing.parse()?;
 		let fat_db = self.args.flag_fat_db.parse()?;
 		let compaction = self.args.flag_db_compaction.parse()?;
@@ -137,6 +191,8 @@ impl Configuration {
 		let ui_address = self.ui_port().map(|port| (self.ui_interface(), port));
 		let mut dapps_conf = self.dapps_config();
 		let ipfs_conf = self.ipfs_config();
+		let health_conf = self.health_config();
+		let sealing_watchdog_conf = self.sealing_watchdog_config();
 		let signer_conf = self.signer_config();
 		let secretstore_conf = self.secretstore_config()?;
 		let format = self.format()?;
@@ -164,15 +220,28 @@ impl Configuration {
 					port: signer_conf.port,
 					authfile: authfile,
 				}
+			} else if self.args.cmd_approve {
+				let pwfile = self.args.flag_password.get(0).map(|pwfile| {
+					PathBuf::from(pwfile)
+				});
+				Cmd::SignerApprove {
+					id: self.args.arg_id,
+					pwfile: pwfile,
+					port: signer_conf.port,
+					ipc_path: signer::ipc_path(&signer_conf.signer_path),
+					authfile: authfile,
+				}
 			} else if self.args.cmd_reject  {
 				Cmd::SignerReject {
 					id: self.args.arg_id,
 					port: signer_conf.port,
+					ipc_path: signer::ipc_path(&signer_conf.signer_path),
 					authfile: authfile,
 				}
 			} else if self.args.cmd_list  {
 				Cmd::SignerList {
 					port: signer_conf.port,
+					ipc_path: signer::ipc_path(&signer_conf.signer_path),
 					authfile: authfile,
 				}
 			} else {
@@ -180,6 +249,16 @@ impl Configuration {
 			}
 		} else if self.args.cmd_tools && self.args.cmd_hash {
 			Cmd::Hash(self.args.arg_file)
+		} else if self.args.cmd_tools && self.args.cmd_sign {
+			Cmd::SignConfig {
+				file: self.args.arg_file.expect("file is required by the usage; qed"),
+				key_file: self.args.flag_config_signing_key,
+				valid_for_days: self.args.flag_config_valid_for,
+			}
+		} else if self.args.cmd_tools && self.args.cmd_encrypt {
+			Cmd::EncryptConfigValue {
+				key_file: self.args.flag_config_key_file,
+			}
 		} else if self.args.cmd_db && self.args.cmd_kill {
 			Cmd::Blockchain(BlockchainCmd::Kill(KillBlockchain {
 				spec: spec,
@@ -230,7 +309,10 @@ impl Configuration {
 				password_file: self.args.flag_password.first().cloned(),
 			};
 			Cmd::ImportPresaleWallet(presale_cmd)
-		} else if self.args.cmd_import {
+		} else if self.args.cmd_import && !self.args.cmd_snapshot {
+			if self.args.flag_resume && self.args.arg_file.is_none() {
+				return Err("--resume requires an input file".into());
+			}
 			let import_cmd = ImportBlockchain {
 				spec: spec,
 				cache_config: cache_config,
@@ -248,10 +330,17 @@ impl Configuration {
 				check_seal: !self.args.flag_no_seal_check,
 				with_color: logger_config.color,
 				verifier_settings: self.verifier_settings(),
+				resume: self.args.flag_resume,
 			};
 			Cmd::Blockchain(BlockchainCmd::Import(import_cmd))
-		} else if self.args.cmd_export {
+		} else if self.args.cmd_export && !self.args.cmd_snapshot {
 			if self.args.cmd_blocks {
+				if self.args.arg_file.is_none() && (self.args.flag_blocks_per_file.is_some() || self.args.flag_resume) {
+					return Err("--blocks-per-file and --resume require an output file".into());
+				}
+				if self.args.flag_blocks_per_file == Some(0) {
+					return Err("--blocks-per-file must be greater than 0".into());
+				}
 				let export_cmd = ExportBlockchain {
 					spec: spec,
 					cache_config: cache_config,
@@ -268,6 +357,12 @@ impl Configuration {
 					from_block: to_block_id(&self.args.flag_from)?,
 					to_block: to_block_id(&self.args.flag_to)?,
 					check_seal: !self.args.flag_no_seal_check,
+					compression: match self.args.flag_compression {
+						Some(ref c) => c.parse()?,
+						None => Default::default(),
+					},
+					blocks_per_file: self.args.flag_blocks_per_file,
+					resume: self.args.flag_resume,
 				};
 				Cmd::Blockchain(BlockchainCmd::Export(export_cmd))
 			} else if self.args.cmd_state {
@@ -289,12 +384,62 @@ impl Configuration {
 					code: !self.args.flag_no_code,
 					min_balance: self.args.flag_min_balance.and_then(|s| to_u256(&s).ok()),
 					max_balance: self.args.flag_max_balance.and_then(|s| to_u256(&s).ok()),
+					min_address: match self.args.flag_min_address {
+						Some(ref a) => Some(to_address(Some(a.clone()))?),
+						None => None,
+					},
+					max_address: match self.args.flag_max_address {
+						Some(ref a) => Some(to_address(Some(a.clone()))?),
+						None => None,
+					},
+					only_contracts: self.args.flag_only_contracts,
+					only_with_storage: self.args.flag_only_with_storage,
 				};
 				Cmd::Blockchain(BlockchainCmd::ExportState(export_cmd))
+			} else if self.args.cmd_receipts || self.args.cmd_traces || self.args.cmd_transfers {
+				if format.map_or(false, |f| f != DataFormat::Csv) {
+					return Err("Receipts, traces and transfers can only be exported in CSV format".into());
+				}
+				let table = if self.args.cmd_receipts {
+					Table::Receipts
+				} else if self.args.cmd_traces {
+					Table::Traces
+				} else {
+					match self.args.flag_address {
+						Some(ref a) => Table::Transfers(to_address(Some(a.clone()))?),
+						None => return Err("Exporting transfers requires --address".into()),
+					}
+				};
+				let export_cmd = ExportTable {
+					spec: spec,
+					cache_config: cache_config,
+					dirs: dirs,
+					file_path: self.args.arg_file.clone(),
+					pruning: pruning,
+					pruning_history: pruning_history,
+					pruning_memory: self.args.flag_pruning_memory,
+					compaction: compaction,
+					wal: wal,
+					tracing: tracing,
+					fat_db: fat_db,
+					from_block: to_block_id(&self.args.flag_from)?,
+					to_block: to_block_id(&self.args.flag_to)?,
+					table: table,
+				};
+				Cmd::Blockchain(BlockchainCmd::ExportTable(export_cmd))
 			} else {
 				unreachable!();
 			}
 		} else if self.args.cmd_snapshot {
+			let kind = if self.args.cmd_export {
+				snapshot::Kind::Export
+			} else if self.args.cmd_import {
+				snapshot::Kind::Import
+			} else if self.args.cmd_verify {
+				snapshot::Kind::Verify
+			} else {
+				snapshot::Kind::Take
+			};
 			let snapshot_cmd = SnapshotCommand {
 				cache_config: cache_config,
 				dirs: dirs,
@@ -307,8 +452,13 @@ impl Configuration {
 				compaction: compaction,
 				file_path: self.args.arg_file.clone(),
 				wal: wal,
-				kind: snapshot::Kind::Take,
+				kind: kind,
 				block_at: to_block_id(&self.args.flag_at)?,
+				delta_from: self.args.flag_delta_from.clone(),
+				deltas: Vec::new(),
+				scratch_dir: self.args.flag_scratch_dir.clone(),
+				threads: self.args.flag_snapshot_threads,
+				encryption: self.snapshot_encryption()?,
 			};
 			Cmd::Snapshot(snapshot_cmd)
 		} else if self.args.cmd_restore {
@@ -326,8 +476,31 @@ impl Configuration {
 				wal: wal,
 				kind: snapshot::Kind::Restore,
 				block_at: to_block_id("latest")?, // unimportant.
+				delta_from: None,
+				scratch_dir: None,
+				threads: self.args.flag_snapshot_threads,
+				encryption: Default::default(),
+				deltas: self.args.flag_with_deltas.as_ref().map_or_else(Vec::new, |deltas| {
+					deltas.split(',').filter(|s| !s.is_empty()).map(Into::into).collect()
+				}),
 			};
 			Cmd::Snapshot(restore_cmd)
+		} else if self.args.cmd_config && self.args.cmd_dump {
+			Cmd::ConfigDump(ConfigDumpCmd {
+				flags: config_dump::flag_entries(&self.args, &self.sources),
+				spec: spec,
+				dirs: dirs,
+				pruning: pruning,
+				tracing: tracing,
+				fat_db: fat_db,
+				mode: mode,
+			})
+		} else if self.args.cmd_service && !self.args.cmd_run {
+			let name = self.args.flag_service_name.clone();
+			Cmd::Service(match self.args.cmd_install {
+				true => ServiceCmd::Install { name: name },
+				false => ServiceCmd::Uninstall { name: name },
+			})
 		} else {
 			let daemon = if self.args.cmd_daemon {
 				Some(self.args.arg_pid_file.clone())
@@ -336,14 +509,20 @@ impl Configuration {
 			};
 
 			let verifier_settings = self.verifier_settings();
+			let dev_accounts = self.dev_accounts(&spec)?;
 
 			// Special presets are present for the dev chain.
 			let (gas_pricer, miner_options) = match spec {
-				SpecType::Dev => (GasPricerConfig::Fixed(0.into()), self.miner_options(0)?),
+				SpecType::Dev => {
+					let mut miner_options = self.miner_options(0)?;
+					// Empty blocks have to be prepared for the engine to seal them on the block time.
+					miner_options.force_sealing |= self.args.flag_dev_block_time.is_some();
+					(GasPricerConfig::Fixed(0.into()), miner_options)
+				},
 				_ => (self.gas_pricer_config()?, self.miner_options(self.args.flag_reseal_min_period)?),
 			};
 
-			let run_cmd = RunCmd {
+			let mut run_cmd = RunCmd {
 				cache_config: cache_config,
 				dirs: dirs,
 				spec: spec,
@@ -364,6 +543,8 @@ impl Configuration {
 				stratum: self.stratum_options()?,
 				update_policy: update_policy,
 				mode: mode,
+				wake_on_rpc: self.wake_on_rpc()?,
+				offline_serve: self.args.flag_mode == "offline-serve",
 				tracing: tracing,
 				fat_db: fat_db,
 				compaction: compaction,
@@ -376,20 +557,74 @@ impl Configuration {
 				net_settings: self.network_settings(),
 				dapps_conf: dapps_conf,
 				ipfs_conf: ipfs_conf,
+				health_conf: health_conf,
+				sealing_watchdog_conf: sealing_watchdog_conf,
+				dev_block_time: self.args.flag_dev_block_time,
+				dev_seal_on_txs: self.args.flag_dev_seal_on_txs,
+				dev_accounts: dev_accounts,
 				signer_conf: signer_conf,
+				signer_policy: self.signer_policy()?,
 				secretstore_conf: secretstore_conf,
 				dapp: self.dapp_to_open()?,
 				ui: self.args.cmd_ui,
 				name: self.args.flag_identity,
 				custom_bootnodes: self.args.flag_bootnodes.is_some(),
-				no_periodic_snapshot: self.args.flag_no_periodic_snapshot,
+				snapshot_schedule: self.snapshot_schedule()?,
+				snapshot_threads: self.args.flag_snapshot_threads,
+				snapshot_throttle: self.snapshot_throttle()?,
+				snapshot_retention: self.snapshot_retention(),
+				warp_from: self.warp_from()?,
 				check_seal: !self.args.flag_no_seal_check,
 				download_old_blocks: !self.args.flag_no_ancient_blocks,
+				ancient_blocks_rate: self.args.flag_ancient_blocks_rate,
 				verifier_settings: verifier_settings,
 				serve_light: !self.args.flag_no_serve_light,
-				light: self.args.flag_light,
+				pip: self.pip_config()?,
+				les: self.les_config(),
+				snapshot_serving: self.snapshot_serving()?,
+				snapshot_download: self.snapshot_download(),
+				checkpoint: self.checkpoint()?,
+				light_checkpoint: self.light_checkpoint()?,
+				persist_light_cache: !self.args.flag_no_persistent_light_cache,
+				light_fallback_url: self.light_fallback_url()?,
+				light_ws_servers: self.light_ws_servers()?,
+				on_demand: self.on_demand_config()?,
+				tx_relay_peers: self.args.flag_light_relay_peers,
+				max_reorg_depth: self.args.flag_max_reorg_depth,
+				reorg_alert_urls: self.reorg_alert_urls(),
+				block_range: self.block_range()?,
+				token_index: self.args.flag_token_index,
+				balance_history: self.args.flag_balance_history,
+				log_index: self.args.flag_log_index,
+				disk_guard: self.disk_guard(),
+				priority: self.priority()?,
+				shutdown_timeout: self.shutdown_timeout()?,
+				reserved_peers_file: self.args.flag_reserved_peers.clone(),
+				statsd: self.statsd()?,
+				span_export: self.span_export()?,
+				sql_export: self.sql_export()?,
+				event_publisher: self.event_publisher()?,
+				tx_queue_persistence: to_tx_queue_persistence(&self.args.flag_tx_queue_persist)?,
+				light: self.args.flag_light || self.args.flag_hybrid,
+				hybrid: self.hybrid()?,
 			};
-			Cmd::Run(run_cmd)
+			if run_cmd.hybrid == Hybrid::Full {
+				hybrid::into_background_full_node(&mut run_cmd, self.args.flag_hybrid_rpc_port);
+				return Ok(Execute {
+					logger: logger_config,
+					cmd: Cmd::Run(run_cmd),
+				});
+			}
+			match (self.args.cmd_service, self.args.flag_supervise) {
+				(true, true) => return Err("--supervise can't be used with a service, the service manager restarts it".into()),
+				(true, false) => Cmd::Service(ServiceCmd::Run { name: self.args.flag_service_name.clone(), cmd: run_cmd }),
+				(false, true) => Cmd::Supervise(SupervisorCmd {
+					max_restarts: self.args.flag_supervise_max_restarts,
+					window: Duration::from_secs(self.args.flag_supervise_window),
+					daemon: run_cmd.daemon,
+				}),
+				(false, false) => Cmd::Run(run_cmd),
+			}
 		};
 
 		Ok(Execute {
@@ -412,7 +647,7 @@ impl Configuration {
 			extra_data: self.extra_data()?,
 			gas_floor_target: to_u256(&self.args.flag_gas_floor_target)?,
 			gas_ceil_target: to_u256(&self.args.flag_gas_cap)?,
-			transactions_limit: self.args.flag_tx_queue_size,
+			transactions_limit: self.tx_queue_size()?,
 			engine_signer: self.engine_signer()?,
 		};
 
@@ -434,24 +669,75 @@ impl Configuration {
 		}
 	}
 
-	fn cache_config(&self) -> CacheConfig {
-		match self.args.flag_cache_size.or(self.args.flag_cache) {
-			Some(size) => CacheConfig::new_with_total_cache_size(size),
-			None => CacheConfig::new(
-				self.args.flag_cache_size_db,
-				self.args.flag_cache_size_blocks,
-				self.args.flag_cache_size_queue,
-				self.args.flag_cache_size_state,
-			),
+	fn memory_budget(&self) -> Option<MemoryBudget> {
+		self.args.flag_memory_budget.map(|total| MemoryBudget {
+			total: total,
+			db: self.args.flag_cache_size_db,
+			blockchain: self.args.flag_cache_size_blocks,
+			queue: self.args.flag_cache_size_queue,
+			state: self.args.flag_cache_size_state,
+			tx_queue: self.args.flag_tx_queue_size,
+		})
+	}
+
+	fn cache_config(&self) -> Result<CacheConfig, String> {
+		match (self.memory_budget(), self.args.flag_cache_size.or(self.args.flag_cache)) {
+			(Some(_), Some(_)) => Err("--memory-budget can't be used together with --cache-size.".into()),
+			(Some(budget), None) => budget.split().map(|(cache, _)| cache),
+			(None, Some(size)) => Ok(CacheConfig::new_with_total_cache_size(size)),
+			(None, None) => Ok(CacheConfig::new(
+				self.args.flag_cache_size_db.unwrap_or(DEFAULT_DB_CACHE_SIZE),
+				self.args.flag_cache_size_blocks.unwrap_or(DEFAULT_BC_CACHE_SIZE),
+				self.args.flag_cache_size_queue.unwrap_or(DEFAULT_BLOCK_QUEUE_SIZE_LIMIT_MB),
+				self.args.flag_cache_size_state.unwrap_or(DEFAULT_STATE_CACHE_SIZE),
+			)),
+		}
+	}
+
+	fn tx_queue_size(&self) -> Result<usize, String> {
+		match self.memory_budget() {
+			Some(budget) => budget.split().map(|(_, tx_queue)| tx_queue),
+			None => Ok(self.args.flag_tx_queue_size.unwrap_or(DEFAULT_TX_QUEUE_SIZE)),
 		}
 	}
 
-	fn logger_config(&self) -> LogConfig {
-		LogConfig {
+	fn logger_config(&self) -> Result<LogConfig, String> {
+		let mut config = LogConfig {
 			mode: self.args.flag_logging.clone(),
 			color: !self.args.flag_no_color && !cfg!(windows),
 			file: self.args.flag_log_file.clone(),
+			rotation: match self.args.flag_log_max_size {
+				Some(0) => return Err("Log file size limit must be at least 1 MB".into()),
+				Some(size) => Some(LogRotation {
+					max_size: size * 1024 * 1024,
+					max_files: self.args.flag_log_max_files,
+					compress: self.args.flag_log_compress,
+				}),
+				None => None,
+			},
+			syslog: match self.args.flag_syslog {
+				Some(ref target) => Some(SyslogConfig {
+					target: target.parse()?,
+					facility: self.args.flag_syslog_facility.parse()?,
+					tag: self.args.flag_syslog_tag.clone(),
+				}),
+				None => None,
+			},
+			event_log: match self.args.cmd_service && self.args.cmd_run {
+				true => Some(self.args.flag_service_name.clone()),
+				false => None,
+			},
+			format: self.args.flag_log_format.parse()?,
+		};
+
+		// the background full node of a hybrid client has no console.
+		if self.args.flag_hybrid_full_node {
+			config.file = Some(Path::new(&self.directories().base).join("hybrid-full.log").to_string_lossy().into_owned());
+			config.color = false;
+			config.event_log = None;
 		}
+
+		Ok(config)
 	}
 
 	fn chain(&self) -> String {
@@ -470,12 +756,27 @@ impl Configuration {
 		max(self.min_peers(), peers)
 	}
 
-	fn allow_ips(&self) -> Result<AllowIP, String> {
-		match self.args.flag_allow_ips.as_str() {
-			"all" => Ok(AllowIP::All),
-			"public" => Ok(AllowIP::Public),
-			"private" => Ok(AllowIP::Private),
-			_ => Err("Invalid IP filter value".to_owned()),
+	fn allow_ips(&self) -> Result<(AllowIP, Vec<String>), String> {
+		let mut predefined = None;
+		let mut networks = Vec::new();
+		for item in self.args.flag_allow_ips.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+			match AllowIP::parse(item) {
+				Some(_) if predefined.is_some() => return Err("Only one of all, public, private or none can be given in the IP filter".to_owned()),
+				Some(allow) => predefined = Some(allow),
+				None => networks.push(to_ip_network(item)?),
+			}
+		}
+		match predefined {
+			Some(predefined) => Ok((predefined, networks)),
+			None if !networks.is_empty() => Ok((AllowIP::None, networks)),
+			None => Err("Invalid IP filter value".to_owned()),
+		}
+	}
+
+	fn deny_ips(&self) -> Result<Vec<String>, String> {
+		match self.args.flag_deny_ips {
+			Some(ref networks) => networks.split(',').map(str::trim).filter(|s| !s.is_empty()).map(to_ip_network).collect(),
+			None => Ok(Vec::new()),
 		}
 	}
 
@@ -491,6 +792,201 @@ impl Configuration {
 		self.args.flag_snapshot_peers as u32
 	}
 
+	fn protocol_slots(&self) -> Result<Vec<ProtocolSlots>, String> {
+		let spec = match self.args.flag_protocol_peers {
+			Some(ref spec) => spec,
+			None => return Ok(Vec::new()),
+		};
+		spec.split(',').map(str::trim).filter(|s| !s.is_empty()).map(|entry| {
+			let invalid = || format!("Invalid protocol peers entry: {}. Expected PROTOCOL=MIN:MAX.", entry);
+			let mut parts = entry.splitn(2, '=');
+			let name = parts.next().unwrap_or("").trim();
+			let slots = parts.next().ok_or_else(|| invalid())?;
+			if name.len() != 3 || !name.bytes().all(|b| b >= b'a' && b <= b'z') {
+				return Err(invalid());
+			}
+			let parse_slots = |s: &str| match s.trim() {
+				"" => Ok(None),
+				s => s.parse::<u32>().map(Some).map_err(|_| invalid()),
+			};
+			let mut bounds = slots.splitn(2, ':');
+			let min = parse_slots(bounds.next().unwrap_or(""))?;
+			let max = match bounds.next() {
+				Some(max) => parse_slots(max)?,
+				None => None,
+			};
+			let mut protocol = [0u8; 3];
+			protocol.copy_from_slice(name.as_bytes());
+			Ok(ProtocolSlots { protocol: protocol, min: min, max: max })
+		}).collect()
+	}
+
+	fn les_peers(&self) -> u32 {
+		match self.args.flag_serve_les {
+			true => self.args.flag_les_peers as u32,
+			false => 0,
+		}
+	}
+
+	fn light_fallback_url(&self) -> Result<Option<String>, String> {
+		match self.args.flag_light_fallback_url {
+			Some(ref url) if !url.starts_with("http://") && !url.starts_with("https://") =>
+				Err(format!("Invalid --light-fallback-url: {}. Expected an HTTP(S) URL.", url)),
+			ref url => Ok(url.clone()),
+		}
+	}
+
+	fn light_ws_servers(&self) -> Result<Vec<String>, String> {
+		let urls = match self.args.flag_light_ws_servers {
+			Some(ref urls) => urls,
+			None => return Ok(Vec::new()),
+		};
+
+		urls.split(',').map(str::trim).filter(|url| !url.is_empty()).map(|url| match url {
+			url if url.starts_with("wss://") => parse_wss_url(url)
+				.map(|_| url.to_owned())
+				.map_err(|e| format!("Invalid --light-ws-servers URL: {}. {}", url, e)),
+			url if !url.starts_with("ws://") || url.len() == "ws://".len() =>
+				Err(format!("Invalid --light-ws-servers URL: {}. Expected a ws:// or wss:// URL.", url)),
+			url => Ok(url.to_owned()),
+		}).collect()
+	}
+
+	fn hybrid(&self) -> Result<Hybrid, String> {
+		if self.args.flag_hybrid_full_node {
+			return Ok(Hybrid::Full);
+		}
+		if !self.args.flag_hybrid {
+			return Ok(Hybrid::Disabled);
+		}
+
+		if self.args.flag_hybrid_port == self.args.flag_port {
+			return Err(format!("--hybrid-port {} is the network port of the light client, choose another one.", self.args.flag_hybrid_port));
+		}
+		let rpc_port = self.args.flag_hybrid_rpc_port;
+		let http_port = self.args.flag_rpcport.unwrap_or(self.args.flag_jsonrpc_port);
+		if self.rpc_enabled() && rpc_port == http_port {
+			return Err(format!("--hybrid-rpc-port {} is the JSON-RPC port of the light client, choose another one.", rpc_port));
+		}
+
+		Ok(Hybrid::Light { rpc_port: rpc_port })
+	}
+
+	fn pip_config(&self) -> Result<PipConfig, String> {
+		let mut costs = CostTable::default();
+		if let Some(ref spec) = self.args.flag_pip_request_costs {
+			for cost in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+				let invalid = || format!("Invalid --pip-request-costs entry: {}. Expected KIND:CREDITS.", cost);
+				let mut parts = cost.splitn(2, ':');
+				let kind = parts.next().expect("splitn always yields at least one item; qed");
+				let credits = to_u256(parts.next().ok_or_else(&invalid)?).map_err(|_| invalid())?;
+
+				match kind {
+					"base" => costs.set_base(credits),
+					"headers" => costs.set_cost(RequestKind::Headers, credits),
+					"body" => costs.set_cost(RequestKind::Body, credits),
+					"receipts" => costs.set_cost(RequestKind::Receipts, credits),
+					"account" => costs.set_cost(RequestKind::Account, credits),
+					"storage" => costs.set_cost(RequestKind::Storage, credits),
+					"code" => costs.set_cost(RequestKind::Code, credits),
+					"header-proof" => costs.set_cost(RequestKind::HeaderProof, credits),
+					"execution" => costs.set_cost(RequestKind::Execution, credits),
+					_ => return Err(format!("Invalid --pip-request-costs request kind: {}", kind)),
+				}
+			}
+		}
+
+		if self.args.flag_pip_peers == Some(0) {
+			return Err("--pip-peers must be greater than 0, use --no-serve-light to disable serving light peers.".into());
+		}
+
+		Ok(PipConfig {
+			max_peers: self.args.flag_pip_peers,
+			flow_params: FlowParams::new(self.args.flag_pip_credit_limit.into(), costs, self.args.flag_pip_recharge_rate.into()),
+			ws_listen: self.args.flag_pip_ws_port.map(|port| SocketAddr::new("0.0.0.0".parse().unwrap(), port)),
+		})
+	}
+
+	fn les_config(&self) -> Option<LesConfig> {
+		match self.args.flag_serve_les {
+			true => Some(LesConfig {
+				max_peers: self.args.flag_les_peers as usize,
+				buffer_limit: self.args.flag_les_buffer_limit,
+				recharge_rate: self.args.flag_les_recharge_rate,
+			}),
+			false => None,
+		}
+	}
+
+	fn on_demand_config(&self) -> Result<OnDemandConfig, String> {
+		let mut timeouts = RequestTimeouts::default();
+		if let Some(ref spec) = self.args.flag_on_demand_timeouts {
+			for timeout in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+				let invalid = || format!("Invalid --on-demand-timeouts entry: {}. Expected KIND:MS.", timeout);
+				let mut parts = timeout.splitn(2, ':');
+				let kind = parts.next().expect("splitn always yields at least one item; qed");
+				let ms: i64 = parts.next().ok_or_else(&invalid)?.parse().map_err(|_| invalid())?;
+				if ms <= 0 {
+					return Err(invalid());
+				}
+
+				let ms = ::time::Duration::milliseconds(ms);
+				match kind {
+					"all" => timeouts = RequestTimeouts::uniform(ms),
+					"header-proof" => timeouts.header_proof = ms,
+					"header" => timeouts.header_by_hash = ms,
+					"body" => timeouts.body = ms,
+					"receipts" => timeouts.receipts = ms,
+					"account" => timeouts.account = ms,
+					"code" => timeouts.code = ms,
+					"execution" => timeouts.transaction_proof = ms,
+					_ => return Err(format!("Invalid --on-demand-timeouts request kind: {}", kind)),
+				}
+			}
+		}
+
+		let peer_selection = match self.args.flag_on_demand_peer_selection.as_str() {
+			"first" => PeerSelection::First,
+			"random" => PeerSelection::Random,
+			"least-loaded" => PeerSelection::LeastLoaded,
+			other => return Err(format!("Invalid --on-demand-peer-selection: {}", other)),
+		};
+
+		let preferred_peers = self.light_serving_peers()?;
+		if self.args.flag_light_serving_peers_only && preferred_peers.is_empty() {
+			return Err("--light-serving-peers-only requires serving peers given with --light-serving-peers.".into());
+		}
+
+		Ok(OnDemandConfig {
+			timeouts: timeouts,
+			max_retries: self.args.flag_on_demand_retries,
+			peer_selection: peer_selection,
+			preferred_peers: preferred_peers.iter().filter_map(|url| enode_id(url)).collect(),
+			preferred_peers_only: self.args.flag_light_serving_peers_only,
+		})
+	}
+
+	fn light_serving_peers(&self) -> Result<Vec<String>, String> {
+		match self.args.flag_light_serving_peers {
+			Some(ref peers) => peers.split(',').filter(|s| !s.is_empty()).map(|s| {
+				if is_valid_node_url(s) {
+					Ok(s.to_owned())
+				} else {
+					Err(format!("Invalid node address format given for a light serving peer: {}", s))
+				}
+			}).collect(),
+			None => Ok(Vec::new()),
+		}
+	}
+
+	fn reorg_alert_urls(&self) -> Vec<String> {
+		self.args.flag_reorg_alert_url.as_ref().map_or_else(Vec::new, |s| s.split(',').filter(|s| !s.is_empty()).map(|s| s.to_owned()).collect())
+	}
+
+	fn block_range(&self) -> Result<Option<BlockRange>, String> {
+		self.args.flag_block_range.as_ref().map_or(Ok(None), |range| range.parse().map(Some))
+	}
+
 	fn work_notify(&self) -> Vec<String> {
 		self.args.flag_notify_work.as_ref().map_or_else(Vec::new, |s| s.split(',').map(|s| s.to_owned()).collect())
 	}
@@ -530,9 +1026,19 @@ impl Configuration {
 				Some(ref d) => to_u256(d)?,
 				None => U256::max_value(),
 			},
-			tx_queue_size: self.args.flag_tx_queue_size,
+			tx_queue_size: self.tx_queue_size()?,
+			tx_queue_future_size: self.args.flag_tx_queue_future_size,
+			tx_queue_per_sender: self.args.flag_tx_queue_per_sender,
+			tx_queue_future_per_sender: self.args.flag_tx_queue_future_per_sender,
+			tx_queue_max_nonce_gap: self.args.flag_tx_queue_nonce_gap,
+			tx_queue_future_memory: self.args.flag_tx_queue_future_mem.map(|mb| mb * 1024 * 1024),
+			tx_queue_replacement_bump: self.args.flag_tx_queue_replace_bump,
+			tx_queue_pending_lifetime: self.args.flag_tx_queue_pending_lifetime,
+			tx_queue_future_lifetime: self.args.flag_tx_queue_future_lifetime,
+			tx_queue_remember_evicted: self.args.flag_tx_queue_remember_evicted,
 			tx_queue_gas_limit: to_gas_limit(&self.args.flag_tx_queue_gas)?,
 			tx_queue_strategy: to_queue_strategy(&self.args.flag_tx_queue_strategy)?,
+			tx_queue_locals: to_locals_policy(&self.args.flag_tx_queue_locals)?,
 			pending_set: to_pending_set(&self.args.flag_relay_set)?,
 			reseal_min_period: Duration::from_millis(reseal_min_period),
 			reseal_max_period: Duration::from_millis(self.args.flag_reseal_max_period),
@@ -547,6 +1053,17 @@ impl Configuration {
 				None => Banning::Disabled,
 			},
 			refuse_service_transactions: self.args.flag_refuse_service_transactions,
+			zero_gas_price_senders: to_addresses(&self.args.flag_zero_gas_price_senders)?,
+			zero_gas_price_certifier: match self.args.flag_zero_gas_price_certifier {
+				Some(ref certifier) => Some(to_address(Some(certifier.clone()))?),
+				None => None,
+			},
+			tx_filter_list: self.tx_filter_list()?,
+			tx_filter_contract: match self.args.flag_tx_filter_contract {
+				Some(ref contract) => Some(to_address(Some(contract.clone()))?),
+				None => None,
+			},
+			standby: self.standby_options()?,
 		};
 
 		Ok(options)
@@ -597,6 +1114,39 @@ impl Configuration {
 		}
 	}
 
+	fn health_config(&self) -> HealthConfiguration {
+		HealthConfiguration {
+			enabled: self.args.flag_health_api,
+			port: self.args.flag_health_api_port,
+			interface: Self::interface(&self.args.flag_health_api_interface),
+			max_block_lag: self.args.flag_health_max_block_lag,
+			min_peers: self.args.flag_health_min_peers as usize,
+			max_clock_drift: self.args.flag_health_max_clock_drift * 1000,
+			ntp_server: match self.args.flag_health_ntp_server.as_str() {
+				"none" | "" => None,
+				server => Some(server.to_owned()),
+			},
+		}
+	}
+
+	fn sealing_watchdog_config(&self) -> SealingWatchdogConfiguration {
+		SealingWatchdogConfiguration {
+			enabled: self.args.flag_sealing_watchdog,
+			max_missed: self.args.flag_sealing_watchdog_missed,
+			alert_urls: self.args.flag_sealing_watchdog_url.as_ref()
+				.map_or_else(Vec::new, |s| s.split(',').filter(|s| !s.is_empty()).map(|s| s.to_owned()).collect()),
+			exit: self.args.flag_sealing_watchdog_exit,
+		}
+	}
+
+	fn dev_accounts(&self, spec: &SpecType) -> Result<Option<DevAccounts>, String> {
+		match (spec, self.args.flag_dev_accounts.as_ref()) {
+			(_, None) => Ok(None),
+			(&SpecType::Dev, Some(accounts)) => accounts.parse().map(Some),
+			(_, Some(_)) => Err("Dev accounts can only be generated on the dev chain (--chain dev).".into()),
+		}
+	}
+
 	fn dapp_to_open(&self) -> Result<Option<String>, String> {
 		if !self.args.cmd_dapp {
 			return Ok(None);
@@ -610,6 +1160,20 @@ impl Configuration {
 		Ok(Some(name.into()))
 	}
 
+	/// Settings which can be reloaded while the node is running.
+	pub fn reloadable_settings(&self) -> Result<ReloadableSettings, String> {
+		Ok(ReloadableSettings {
+			logging: self.args.flag_logging.clone(),
+			min_peers: self.min_peers(),
+			max_peers: self.max_peers(),
+			gas_pricer: match self.chain().parse()? {
+				SpecType::Dev => GasPricerConfig::Fixed(0.into()),
+				_ => self.gas_pricer_config()?,
+			},
+			http_limits: self.http_config()?.limits,
+		})
+	}
+
 	fn gas_pricer_config(&self) -> Result<GasPricerConfig, String> {
 		fn wei_per_gas(usd_per_tx: f32, usd_per_eth: f32) -> U256 {
 			let wei_per_usd: f32 = 1.0e18 / usd_per_eth;
@@ -655,26 +1219,46 @@ impl Configuration {
 		}
 	}
 
-	fn init_reserved_nodes(&self) -> Result<Vec<String>, String> {
-		use std::fs::File;
+	fn standby_options(&self) -> Result<Option<StandbyOptions>, String> {
+		match self.args.flag_engine_lock {
+			Some(_) if self.args.flag_engine_lock_timeout <= ENGINE_LOCK_GRACE_PERIOD => {
+				Err(format!("--engine-lock-timeout has to be longer than the grace period of {} seconds.", ENGINE_LOCK_GRACE_PERIOD))
+			},
+			Some(ref path) => Ok(Some(StandbyOptions {
+				lock_file: path.into(),
+				standby: self.args.flag_engine_standby,
+				timeout: Duration::from_secs(self.args.flag_engine_lock_timeout),
+				grace: Duration::from_secs(ENGINE_LOCK_GRACE_PERIOD),
+			})),
+			None if self.args.flag_engine_standby => Err("--engine-standby requires --engine-lock.".into()),
+			None => Ok(None),
+		}
+	}
 
-		match self.args.flag_reserved_peers {
+	fn tx_filter_list(&self) -> Result<TransactionFilterList, String> {
+		match self.args.flag_tx_filter_file {
 			Some(ref path) => {
-				let mut buffer = String::new();
-				let mut node_file = File::open(path).map_err(|e| format!("Error opening reserved nodes file: {}", e))?;
-				node_file.read_to_string(&mut buffer).map_err(|_| "Error reading reserved node file")?;
-				let lines = buffer.lines().map(|s| s.trim().to_owned()).filter(|s| !s.is_empty()).collect::<Vec<_>>();
-				if let Some(invalid) = lines.iter().find(|s| !is_valid_node_url(s)) {
-					return Err(format!("Invalid node address format given for a boot node: {}", invalid));
-				}
-				Ok(lines)
+				let mut contents = String::new();
+				File::open(path).and_then(|mut file| file.read_to_string(&mut contents))
+					.map_err(|e| format!("Unable to read transaction filter file {}: {}", path, e))?;
+				contents.parse()
 			},
+			None => Ok(Default::default()),
+		}
+	}
+
+	fn init_reserved_nodes(&self) -> Result<Vec<String>, String> {
+		match self.args.flag_reserved_peers {
+			Some(ref path) => read_reserved_peers(path),
 			None => Ok(Vec::new())
 		}
 	}
 
 	fn net_addresses(&self) -> Result<(Option<SocketAddr>, Option<SocketAddr>), String> {
-		let port = self.args.flag_port;
+		let port = match self.args.flag_hybrid_full_node {
+			true => self.args.flag_hybrid_port,
+			false => self.args.flag_port,
+		};
 		let listen_address = Some(SocketAddr::new("0.0.0.0".parse().unwrap(), port));
 		let public_address = if self.args.flag_nat.starts_with("extip:") {
 			let host = &self.args.flag_nat[6..];
@@ -686,9 +1270,311 @@ impl Configuration {
 		Ok((listen_address, public_address))
 	}
 
+	fn snapshot_serving(&self) -> Result<SnapshotServingConfig, String> {
+		let hours = match self.args.flag_warp_serve_hours {
+			Some(ref hours) => {
+				let mut parts = hours.splitn(2, '-').map(|h| h.trim().parse::<u8>().ok().and_then(|h| if h < 24 { Some(h) } else { None }));
+				match (parts.next(), parts.next()) {
+					(Some(Some(from)), Some(Some(to))) => Some((from, to)),
+					_ => return Err(format!("Invalid --warp-serve-hours: {}. Expected FROM-TO with hours between 0 and 23.", hours)),
+				}
+			},
+			None => None,
+		};
+
+		Ok(SnapshotServingConfig {
+			max_bandwidth: kilobytes(self.args.flag_warp_serve_bandwidth, "--warp-serve-bandwidth")?,
+			max_peers: self.args.flag_warp_serve_peers,
+			hours: hours,
+		})
+	}
+
+	fn snapshot_download(&self) -> SnapshotDownloadConfig {
+		SnapshotDownloadConfig {
+			max_chunks_ahead: self.args.flag_snapshot_download_ahead,
+			chunks_per_peer: max(self.args.flag_snapshot_chunks_per_peer, 1),
+			max_peers: self.args.flag_snapshot_download_peers,
+			chunk_timeout: self.args.flag_snapshot_chunk_timeout,
+			max_chunk_retries: self.args.flag_snapshot_chunk_retries,
+		}
+	}
+
+	fn sync_schedule(&self) -> Result<SyncSchedule, String> {
+		match self.args.flag_sync_schedule {
+			Some(ref schedule) => schedule.parse(),
+			None => Ok(SyncSchedule::default()),
+		}
+	}
+
+	fn proxy(&self) -> Result<Option<String>, String> {
+		match self.args.flag_proxy {
+			Some(ref proxy) => proxy.parse::<SocketAddr>()
+				.map(|addr| Some(format!("{}", addr)))
+				.map_err(|_| format!("Invalid proxy address given with --proxy: {}", proxy)),
+			None => Ok(None),
+		}
+	}
+
+	fn statsd(&self) -> Result<Option<StatsdConfig>, String> {
+		let address = match self.args.flag_statsd {
+			Some(ref address) => address.parse::<SocketAddr>()
+				.map_err(|_| format!("Invalid StatsD address given with --statsd: {}", address))?,
+			None => return Ok(None),
+		};
+		if self.args.flag_statsd_interval == 0 {
+			return Err("StatsD interval must be at least one second".into());
+		}
+		Ok(Some(StatsdConfig {
+			address: address,
+			prefix: self.args.flag_statsd_prefix.trim_matches('.').to_owned(),
+			interval: self.args.flag_statsd_interval,
+		}))
+	}
+
+	fn span_export(&self) -> Result<Option<SpanExportConfig>, String> {
+		let url = match self.args.flag_span_collector {
+			Some(ref url) if url.starts_with("http://") => url.clone(),
+			Some(ref url) => return Err(format!("Invalid span collector URL given with --span-collector: {}. Only http:// URLs are supported.", url)),
+			None => return Ok(None),
+		};
+		Ok(Some(SpanExportConfig {
+			url: url,
+			service_name: self.args.flag_span_service.clone(),
+		}))
+	}
+
+	fn sql_export(&self) -> Result<Option<SqlExportConfig>, String> {
+		let dsn = match self.args.flag_sql_export {
+			Some(ref dsn) => dsn.clone(),
+			None => return Ok(None),
+		};
+		if self.args.flag_sql_export_batch == 0 {
+			return Err("SQL export batch size must be at least one block".into());
+		}
+		connection_env(&dsn)?;
+		Ok(Some(SqlExportConfig {
+			dsn: dsn,
+			batch_size: self.args.flag_sql_export_batch,
+			psql: self.args.flag_sql_export_psql.clone(),
+		}))
+	}
+
+	fn event_publisher(&self) -> Result<Option<EventPublisherConfig>, String> {
+		let address = match self.args.flag_event_publisher {
+			Some(ref url) if url.starts_with("nats://") => url["nats://".len()..].trim_right_matches('/').to_owned(),
+			Some(ref url) => return Err(format!("Invalid event publisher URL given with --event-publisher: {}. Only nats:// URLs are supported.", url)),
+			None => return Ok(None),
+		};
+		let prefix = &self.args.flag_event_subject_prefix;
+		if prefix.is_empty() || prefix.contains(char::is_whitespace) || prefix.contains('*') || prefix.contains('>') {
+			return Err(format!("Invalid event subject prefix: {}", prefix));
+		}
+		Ok(Some(EventPublisherConfig {
+			address: address,
+			subject_prefix: prefix.trim_matches('.').to_owned(),
+			log_addresses: to_addresses(&self.args.flag_event_log_addresses)?,
+			replay_from: self.args.flag_event_replay_from,
+		}))
+	}
+
+	fn snapshot_schedule(&self) -> Result<Option<SnapshotSchedule>, String> {
+		if self.args.flag_no_periodic_snapshot {
+			return Ok(None);
+		}
+		let period = match self.args.flag_snapshot_interval {
+			Some(0) => return Err("Invalid snapshot interval given with --snapshot-interval: 0".into()),
+			Some(secs) => SnapshotPeriod::Interval(Duration::from_secs(secs)),
+			None if self.args.flag_snapshot_every == 0 => return Err("Invalid snapshot period given with --snapshot-every: 0".into()),
+			None => SnapshotPeriod::Blocks(self.args.flag_snapshot_every),
+		};
+		let window = match self.args.flag_snapshot_window {
+			Some(ref window) => Some(window.parse()?),
+			None => None,
+		};
+		Ok(Some(SnapshotSchedule {
+			period: period,
+			history: SNAPSHOT_HISTORY,
+			window: window,
+		}))
+	}
+
+	fn snapshot_throttle(&self) -> Result<SnapshotThrottle, String> {
+		if self.args.flag_snapshot_chunks_per_sec == Some(0) {
+			return Err("Invalid snapshot rate given with --snapshot-chunks-per-sec: 0".into());
+		}
+		Ok(SnapshotThrottle {
+			max_chunks_per_sec: self.args.flag_snapshot_chunks_per_sec,
+			yield_to_import: self.args.flag_snapshot_yield_to_import,
+		})
+	}
+
+	fn snapshot_retention(&self) -> RetentionPolicy {
+		RetentionPolicy {
+			keep_last: self.args.flag_snapshot_keep,
+			max_size: self.args.flag_snapshot_max_disk.map(|mb| mb * 1024 * 1024),
+		}
+	}
+
+	fn disk_guard(&self) -> DiskGuardConfig {
+		let mb = |mb: u64| mb * 1024 * 1024;
+		DiskGuardConfig {
+			warn: mb(self.args.flag_disk_space_warn),
+			stop_snapshots: mb(self.args.flag_disk_space_snapshots),
+			pause_ancient_blocks: mb(self.args.flag_disk_space_ancient),
+			halt_import: mb(self.args.flag_disk_space_import),
+			path: None,
+		}
+	}
+
+	fn shutdown_timeout(&self) -> Result<Option<Duration>, String> {
+		match self.args.flag_shutdown_timeout {
+			Some(0) => Err("Shutdown timeout must be at least 1 second".into()),
+			timeout => Ok(timeout.map(Duration::from_secs)),
+		}
+	}
+
+	fn priority(&self) -> Result<PriorityConfig, String> {
+		let nice = match self.args.flag_nice {
+			Some(nice) if nice < -20 || nice > 19 => return Err(format!("Invalid niceness: {}. Expected a number from -20 to 19.", nice)),
+			nice => nice,
+		};
+		Ok(PriorityConfig {
+			nice: nice,
+			io_class: match self.args.flag_ionice {
+				Some(ref class) => Some(class.parse()?),
+				None => None,
+			},
+		})
+	}
+
+	fn snapshot_encryption(&self) -> Result<EncryptionKeys, String> {
+		let passphrase = match self.args.flag_snapshot_passphrase_file {
+			Some(ref file) => Some(passwords_from_files(&[file.clone()])?.into_iter().next()
+				.ok_or_else(|| format!("No passphrase found in {}", file))?),
+			None => None,
+		};
+		let recipients = match self.args.flag_snapshot_recipients {
+			Some(ref keys) => keys.split(',').filter(|s| !s.is_empty())
+				.map(|key| clean_0x(key).parse().map_err(|_| format!("Invalid public key given with --snapshot-recipients: {}", key)))
+				.collect::<Result<Vec<_>, _>>()?,
+			None => Vec::new(),
+		};
+		let secret = match self.args.flag_snapshot_secret_file {
+			Some(ref file) => {
+				let key = passwords_from_files(&[file.clone()])?.into_iter().next()
+					.ok_or_else(|| format!("No secret key found in {}", file))?;
+				Some(clean_0x(&key).parse().map_err(|_| format!("Invalid secret key in {}", file))?)
+			},
+			None => None,
+		};
+		Ok(EncryptionKeys {
+			passphrase: passphrase,
+			recipients: recipients,
+			secret: secret,
+		})
+	}
+
+	fn warp_from(&self) -> Result<Option<WarpFrom>, String> {
+		let url = match self.args.flag_warp_from {
+			Some(ref url) if url.starts_with("http://") || url.starts_with("https://") => url.clone(),
+			Some(ref url) => return Err(format!("Invalid snapshot URL given with --warp-from: {}", url)),
+			None => return Ok(None),
+		};
+		let manifest_hash = match self.args.flag_warp_from_hash {
+			Some(ref hash) => Some(clean_0x(hash).parse().map_err(|_| format!("Invalid manifest hash given with --warp-from-hash: {}", hash))?),
+			None => None,
+		};
+		Ok(Some(WarpFrom {
+			url: url,
+			manifest_hash: manifest_hash,
+		}))
+	}
+
+	fn snapshot_preferred_peers(&self) -> Result<Vec<String>, String> {
+		match self.args.flag_snapshot_preferred_peers {
+			Some(ref peers) => peers.split(',').filter(|s| !s.is_empty()).map(|s| {
+				if is_valid_node_url(s) {
+					Ok(s.to_owned())
+				} else {
+					Err(format!("Invalid node address format given for a preferred snapshot peer: {}", s))
+				}
+			}).collect(),
+			None => Ok(Vec::new()),
+		}
+	}
+
+	fn local_tx_relay_peers(&self) -> Result<Vec<String>, String> {
+		match self.args.flag_local_tx_relay_peers {
+			Some(ref peers) => peers.split(',').filter(|s| !s.is_empty()).map(|s| {
+				if is_valid_node_url(s) {
+					Ok(s.to_owned())
+				} else {
+					Err(format!("Invalid node address format given for a local transactions relay peer: {}", s))
+				}
+			}).collect(),
+			None => Ok(Vec::new()),
+		}
+	}
+
+	fn checkpoint(&self) -> Result<CheckpointConfig, String> {
+		if self.args.flag_no_checkpoint {
+			return Ok(CheckpointConfig::Disabled);
+		}
+
+		let checkpoint = match self.args.flag_checkpoint {
+			Some(ref checkpoint) => checkpoint,
+			None => return Ok(CheckpointConfig::Spec),
+		};
+
+		let invalid = || format!("Invalid --checkpoint: {}. Expected NUMBER,HASH,TOTAL_DIFFICULTY.", checkpoint);
+		let parts: Vec<_> = checkpoint.split(',').map(str::trim).collect();
+		if parts.len() != 3 {
+			return Err(invalid());
+		}
+
+		Ok(CheckpointConfig::Custom(Checkpoint {
+			number: parts[0].parse().map_err(|_| invalid())?,
+			hash: clean_0x(parts[1]).parse().map_err(|_| invalid())?,
+			total_difficulty: to_u256(parts[2]).map_err(|_| invalid())?,
+		}))
+	}
+
+	fn light_checkpoint(&self) -> Result<LightCheckpointConfig, String> {
+		if self.args.flag_no_light_checkpoint {
+			return Ok(LightCheckpointConfig::Disabled);
+		}
+
+		let path = match self.args.flag_light_checkpoint {
+			Some(ref path) => path,
+			None if self.args.flag_light_checkpoint_key.is_some() => return Err("--light-checkpoint-key requires --light-checkpoint.".into()),
+			None => return Ok(LightCheckpointConfig::Spec),
+		};
+
+		let read = |path: &str| {
+			let mut contents = String::new();
+			File::open(path).and_then(|mut file| file.read_to_string(&mut contents))
+				.map_err(|e| format!("Unable to read light checkpoint file {}: {}", path, e))
+				.map(|_| contents)
+		};
+
+		let contents = read(path)?;
+		if let Some(ref key) = self.args.flag_light_checkpoint_key {
+			let public: Public = clean_0x(key).parse().map_err(|_| format!("Invalid --light-checkpoint-key: {}", key))?;
+			let signature = read(&format!("{}{}", path, remote_config::SIGNATURE_SUFFIX))?;
+			// signed with `parity tools sign` like config files, the version is not needed for a local file.
+			remote_config::verify(&contents, &signature, &public, remote_config::now())
+				.map_err(|_| format!("Signature of {} doesn't match --light-checkpoint-key or has expired", path))?;
+		}
+
+		LightCheckpoint::load(contents.as_bytes())
+			.map(LightCheckpointConfig::Custom)
+			.map_err(|e| format!("{}: {}", path, e))
+	}
+
 	fn net_config(&self) -> Result<NetworkConfiguration, String> {
 		let mut ret = NetworkConfiguration::new();
 		ret.nat_enabled = self.args.flag_nat == "any" || self.args.flag_nat == "upnp";
+		ret.nat_pmp_enabled = self.args.flag_nat == "any" || self.args.flag_nat == "pmp";
 		ret.boot_nodes = to_bootnodes(&self.args.flag_bootnodes)?;
 		let (listen, public) = self.net_addresses()?;
 		ret.listen_address = listen.map(|l| format!("{}", l));
@@ -704,12 +1590,31 @@ impl Configuration {
 		ret.max_peers = self.max_peers();
 		ret.min_peers = self.min_peers();
 		ret.snapshot_peers = self.snapshot_peers();
-		ret.allow_ips = self.allow_ips()?;
+		ret.les_peers = self.les_peers();
+		ret.snapshot_preferred_peers = self.snapshot_preferred_peers()?;
+		ret.sync_schedule = self.sync_schedule()?;
+		ret.local_tx_delay = self.args.flag_local_tx_delay;
+		ret.local_tx_jitter = self.args.flag_local_tx_jitter;
+		ret.local_tx_relay_peers = self.local_tx_relay_peers()?;
+		ret.proxy = self.proxy()?;
+		let (allow_ips, allow_networks) = self.allow_ips()?;
+		ret.allow_ips = allow_ips;
+		ret.allow_networks = allow_networks;
+		ret.deny_networks = self.deny_ips()?;
 		ret.max_pending_peers = self.max_pending_peers();
+		ret.max_peers_per_ip = self.args.flag_max_peers_per_ip as u32;
+		ret.max_peers_per_subnet = self.args.flag_max_peers_per_subnet as u32;
+		ret.protocol_slots = self.protocol_slots()?;
+		ret.max_upload = self.args.flag_max_upload.map(|kbps| kbps * 1024);
+		ret.max_download = self.args.flag_max_download.map(|kbps| kbps * 1024);
 		let mut net_path = PathBuf::from(self.directories().base);
 		net_path.push("network");
 		ret.config_path = Some(net_path.to_str().unwrap().to_owned());
 		ret.reserved_nodes = self.init_reserved_nodes()?;
+		if self.args.flag_light {
+			// stay connected to the preferred serving peers.
+			ret.reserved_nodes.extend(self.light_serving_peers()?);
+		}
 		ret.allow_non_reserved = !self.args.flag_reserved_only;
 		Ok(ret)
 	}
@@ -759,8 +1664,48 @@ impl Configuration {
 		Self::hosts(&self.args.flag_jsonrpc_hosts)
 	}
 
-	fn ws_hosts(&self) -> Option<Vec<String>> {
-		Self::hosts(&self.args.flag_ws_hosts)
+	fn api_rules(rules: Option<&String>) -> Result<HashMap<Api, Option<Vec<String>>>, String> {
+		let rules = match rules {
+			Some(rules) => rules,
+			None => return Ok(HashMap::new()),
+		};
+
+		rules.split(';').filter(|rule| !rule.is_empty()).map(|rule| {
+			let mut parts = rule.splitn(2, '=');
+			match (parts.next(), parts.next()) {
+				(Some(api), Some(domains)) => Ok((api.parse()?, Self::hosts(domains))),
+				_ => Err(format!("Invalid API rule: {}. Expected API=DOMAINS.", rule)),
+			}
+		}).collect()
+	}
+
+	fn wake_on_rpc(&self) -> Result<ApiWake, String> {
+		let apis = match self.args.flag_wake_on_rpc {
+			Some(ref apis) => apis,
+			None => return Ok(ApiWake::new()),
+		};
+
+		apis.split(',').filter(|api| !api.is_empty()).map(|api| {
+			let mut parts = api.splitn(2, '=');
+			let name = parts.next().expect("splitn always returns at least one item; qed");
+			let idle = match parts.next() {
+				Some(secs) => secs.parse().map_err(|_| format!("Invalid idle time of API {}: {}", name, secs))?,
+				None => self.args.flag_mode_timeout,
+			};
+			Ok((name.parse()?, Duration::from_secs(idle)))
+		}).collect()
+	}
+
+	fn rpc_api_cors(&self) -> Result<HashMap<Api, Option<Vec<String>>>, String> {
+		Self::api_rules(self.args.flag_jsonrpc_api_cors.as_ref())
+	}
+
+	fn rpc_api_hosts(&self) -> Result<HashMap<Api, Option<Vec<String>>>, String> {
+		Self::api_rules(self.args.flag_jsonrpc_api_hosts.as_ref())
+	}
+
+	fn ws_hosts(&self) -> Option<Vec<String>> {
+		Self::hosts(&self.args.flag_ws_hosts)
 	}
 
 	fn ws_origins(&self) -> Option<Vec<String>> {
@@ -801,11 +1746,22 @@ impl Configuration {
 			},
 			hosts: self.rpc_hosts(),
 			cors: self.rpc_cors(),
+			api_cors: self.rpc_api_cors()?,
+			api_hosts: self.rpc_api_hosts()?,
 			threads: match self.args.flag_jsonrpc_threads {
+				// the multi-threaded server reads whole bodies before they can be checked.
+				Some(_) if self.args.flag_jsonrpc_max_request_size.is_some() => {
+					return Err("--jsonrpc-max-request-size can't be used with --jsonrpc-threads.".into());
+				},
 				Some(threads) if threads > 0 => Some(threads),
 				None => None,
 				_ => return Err("--jsonrpc-threads number needs to be positive.".into()),
-			}
+			},
+			limits: RequestLimits {
+				max_batch_size: self.args.flag_jsonrpc_max_batch_size,
+				max_request_size: kilobytes(self.args.flag_jsonrpc_max_request_size, "--jsonrpc-max-request-size")?,
+				max_response_size: kilobytes(self.args.flag_jsonrpc_max_response_size, "--jsonrpc-max-response-size")?,
+			},
 		};
 
 		Ok(conf)
@@ -1004,6 +1960,13 @@ impl Configuration {
 		!self.args.flag_no_secretstore && cfg!(feature = "secretstore")
 	}
 
+	fn signer_policy(&self) -> Result<Option<String>, String> {
+		match self.args.flag_signer_policy {
+			Some(_) if !self.ui_enabled() => Err("--signer-policy requires Trusted Signer, which is disabled by --no-ui, --unlock or --geth.".into()),
+			ref policy => Ok(policy.clone()),
+		}
+	}
+
 	fn ui_enabled(&self) -> bool {
 		if self.args.flag_force_ui {
 			return true;
@@ -1022,28 +1985,49 @@ impl Configuration {
 		if let Some(num_verifiers) = self.args.flag_num_verifiers {
 			settings.num_verifiers = num_verifiers;
 		}
+		settings.max_verifiers = self.args.flag_max_verifiers;
 
 		settings
 	}
 }
 
+/// Converts a size given in kilobytes to bytes, rejecting values which don't fit.
+fn kilobytes(kb: Option<usize>, flag: &str) -> Result<Option<usize>, String> {
+	match kb {
+		Some(kb) => kb.checked_mul(1024).map(Some).ok_or_else(|| format!("{} is too large.", flag)),
+		None => Ok(None),
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use cli::Args;
 	use parity_rpc::NetworkSettings;
-	use ethcore::client::{VMType, BlockId};
-	use ethcore::miner::{MinerOptions, PrioritizationStrategy};
+	use ethcore::client::{VMType, BlockId, SpanExportConfig, Mode, DiskGuardConfig};
+	use ethcore::miner::{MinerOptions, PrioritizationStrategy, LocalsPolicy};
 	use helpers::{default_network_config};
 	use run::RunCmd;
+	use service::ServiceCmd;
+	use supervisor::SupervisorCmd;
 	use dir::{Directories, default_hypervisor_path};
 	use signer::{Configuration as SignerConfiguration};
-	use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, DataFormat, ExportState};
+	use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, DataFormat, ExportState, ExportTable, Compression};
+	use csv_export::Table;
 	use presale::ImportWallet;
 	use params::SpecType;
 	use account::{AccountCmd, NewAccount, ImportAccounts, ListAccounts};
 	use devtools::{RandomTempPath};
 	use updater::{UpdatePolicy, UpdateFilter, ReleaseTrack};
+	use ethsync::{AllowIP, ProtocolSlots};
+	use metrics::StatsdConfig;
+	use sql_export::SqlExportConfig;
+	use event_publisher::EventPublisherConfig;
+	use local_store::Persistence;
+	use ethcore_logger::{LogFormat, Rotation as LogRotation, SyslogConfig, SyslogTarget, SyslogFacility};
+	use health::Configuration as HealthConfiguration;
+	use sealing_watchdog::Configuration as SealingWatchdogConfiguration;
+	use ethcore::snapshot::TimeWindow;
 	use std::io::Write;
 	use std::fs::{File, create_dir};
 
@@ -1054,6 +2038,7 @@ mod tests {
 		Configuration {
 			args: Args::parse_without_config(args).unwrap(),
 			spec_name_override: None,
+			sources: Vec::new(),
 		}
 	}
 
@@ -1064,6 +2049,63 @@ mod tests {
 		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Version);
 	}
 
+	#[test]
+	fn test_command_service() {
+		let conf = parse(&["parity", "service", "install", "--service-name", "parity-kovan", "--chain", "kovan"]);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Service(ServiceCmd::Install { name: "parity-kovan".into() }));
+
+		let conf = parse(&["parity", "service", "uninstall"]);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Service(ServiceCmd::Uninstall { name: "parity".into() }));
+
+		let conf = parse(&["parity", "service", "run", "--chain", "kovan"]);
+		let execute = conf.into_command().unwrap();
+		assert_eq!(execute.logger.event_log, Some("parity".into()));
+		match execute.cmd {
+			Cmd::Service(ServiceCmd::Run { name, cmd }) => {
+				assert_eq!(name, "parity");
+				assert_eq!(cmd.spec, SpecType::Kovan);
+			},
+			_ => panic!("Should be a service run command"),
+		}
+	}
+
+	#[test]
+	fn test_command_config_dump() {
+		let conf = Configuration::parse(&["parity", "config", "dump", "--no-config", "--chain", "kovan"], None).unwrap();
+		match conf.into_command().unwrap().cmd {
+			Cmd::ConfigDump(cmd) => {
+				assert_eq!(cmd.spec, SpecType::Kovan);
+				let chain = cmd.flags.iter().find(|flag| flag.name == "flag_chain").unwrap();
+				assert_eq!(chain.value, Some("\"kovan\"".into()));
+				assert_eq!(chain.source, ::config_dump::Source::Args(ValueSource::CommandLine));
+				assert!(cmd.flags.iter().any(|flag| flag.name == "flag_no_config"));
+				assert!(!cmd.flags.iter().any(|flag| flag.name == "cmd_dump" || flag.name == "flag_geth"));
+			},
+			_ => panic!("Should be a config dump command"),
+		}
+	}
+
+	#[test]
+	fn test_command_sign_config() {
+		let args = vec!["parity", "tools", "sign", "node.toml", "--config-signing-key", "signing.key", "--config-valid-for", "7"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::SignConfig {
+			file: "node.toml".into(),
+			key_file: Some("signing.key".into()),
+			valid_for_days: Some(7),
+		});
+	}
+
+	#[test]
+	fn test_command_supervise() {
+		let conf = parse(&["parity", "daemon", "/run/parity.pid", "--supervise", "--supervise-max-restarts", "3"]);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Supervise(SupervisorCmd {
+			max_restarts: 3,
+			window: Duration::from_secs(600),
+			daemon: Some("/run/parity.pid".into()),
+		}));
+	}
+
 	#[test]
 	fn test_command_account_new() {
 		let args = vec!["parity", "account", "new"];
@@ -1133,9 +2175,22 @@ mod tests {
 			check_seal: true,
 			with_color: !cfg!(windows),
 			verifier_settings: Default::default(),
+			resume: false,
 		})));
 	}
 
+	#[test]
+	fn test_command_blockchain_import_resume() {
+		let args = vec!["parity", "import", "--resume", "blockchain.rlp"];
+		match parse(&args).into_command().unwrap().cmd {
+			Cmd::Blockchain(BlockchainCmd::Import(cmd)) => assert!(cmd.resume),
+			_ => panic!("Expected import command"),
+		}
+
+		let args = vec!["parity", "import", "--resume"];
+		assert!(parse(&args).into_command().is_err());
+	}
+
 	#[test]
 	fn test_command_blockchain_export() {
 		let args = vec!["parity", "export", "blocks", "blockchain.json"];
@@ -1156,9 +2211,108 @@ mod tests {
 			from_block: BlockId::Number(1),
 			to_block: BlockId::Latest,
 			check_seal: true,
+			compression: Compression::None,
+			blocks_per_file: None,
+			resume: false,
 		})));
 	}
 
+	#[test]
+	fn test_command_blockchain_export_compressed() {
+		let args = vec!["parity", "export", "blocks", "--compression", "gzip", "--blocks-per-file", "100000", "--resume", "blocks.rlp.gz"];
+		let conf = parse(&args);
+		match conf.into_command().unwrap().cmd {
+			Cmd::Blockchain(BlockchainCmd::Export(cmd)) => {
+				assert_eq!(cmd.compression, Compression::Gzip);
+				assert_eq!(cmd.blocks_per_file, Some(100000));
+				assert!(cmd.resume);
+			},
+			_ => panic!("Expected blocks export command"),
+		}
+
+		let args = vec!["parity", "export", "blocks", "--resume"];
+		assert!(parse(&args).into_command().is_err());
+		let args = vec!["parity", "export", "blocks", "--compression", "zstd", "blocks.rlp"];
+		assert!(parse(&args).into_command().is_err());
+	}
+
+	#[test]
+	fn test_command_snapshot_export() {
+		let args = vec!["parity", "snapshot", "export", "snapshot.pack"];
+		let conf = parse(&args);
+		match conf.into_command().unwrap().cmd {
+			Cmd::Snapshot(cmd) => {
+				assert_eq!(cmd.kind, snapshot::Kind::Export);
+				assert_eq!(cmd.file_path, Some("snapshot.pack".into()));
+			},
+			_ => panic!("Expected snapshot command"),
+		}
+
+		let args = vec!["parity", "snapshot", "import", "snapshot.pack"];
+		let conf = parse(&args);
+		match conf.into_command().unwrap().cmd {
+			Cmd::Snapshot(cmd) => assert_eq!(cmd.kind, snapshot::Kind::Import),
+			_ => panic!("Expected snapshot command"),
+		}
+	}
+
+	#[test]
+	fn test_command_snapshot_verify() {
+		let args = vec!["parity", "snapshot", "verify", "snapshot.pack", "--scratch-dir", "/tmp"];
+		let conf = parse(&args);
+		match conf.into_command().unwrap().cmd {
+			Cmd::Snapshot(cmd) => {
+				assert_eq!(cmd.kind, snapshot::Kind::Verify);
+				assert_eq!(cmd.scratch_dir, Some("/tmp".into()));
+			},
+			_ => panic!("Expected snapshot command"),
+		}
+	}
+
+	#[test]
+	fn test_command_snapshot_threads() {
+		let args = vec!["parity", "snapshot", "snapshot.pack", "--snapshot-threads", "4"];
+		let conf = parse(&args);
+		match conf.into_command().unwrap().cmd {
+			Cmd::Snapshot(cmd) => {
+				assert_eq!(cmd.kind, snapshot::Kind::Take);
+				assert_eq!(cmd.threads, 4);
+			},
+			_ => panic!("Expected snapshot command"),
+		}
+	}
+
+	#[test]
+	fn test_command_snapshot_export_encrypted() {
+		let public = "0x8a4af9d3a53e0e2bbb2ec9b8b3e7e1ef9b1a8f22ac8d6ab6e2a4f2f95c0ad0a3e1bb4c9d8cfa3d1c42fa5c1b2a8fd09cde7c9bb10f2df36ab4a0a2a9c8d7e6f5";
+		let args = vec!["parity", "snapshot", "export", "snapshot.pack", "--snapshot-recipients", public];
+		let conf = parse(&args);
+		match conf.into_command().unwrap().cmd {
+			Cmd::Snapshot(cmd) => {
+				assert_eq!(cmd.kind, snapshot::Kind::Export);
+				assert_eq!(cmd.encryption.recipients, vec![public[2..].parse().unwrap()]);
+				assert!(cmd.encryption.encrypts());
+			},
+			_ => panic!("Expected snapshot command"),
+		}
+
+		let args = vec!["parity", "snapshot", "export", "snapshot.pack", "--snapshot-recipients", "0x1234"];
+		assert!(parse(&args).into_command().is_err());
+	}
+
+	#[test]
+	fn test_command_restore_with_deltas() {
+		let args = vec!["parity", "restore", "base.pack", "--with-deltas", "delta1.pack,delta2.pack"];
+		let conf = parse(&args);
+		match conf.into_command().unwrap().cmd {
+			Cmd::Snapshot(cmd) => {
+				assert_eq!(cmd.kind, snapshot::Kind::Restore);
+				assert_eq!(cmd.deltas, vec!["delta1.pack".to_owned(), "delta2.pack".to_owned()]);
+			},
+			_ => panic!("Expected snapshot command"),
+		}
+	}
+
 	#[test]
 	fn test_command_state_export() {
 		let args = vec!["parity", "export", "state", "state.json"];
@@ -1181,7 +2335,72 @@ mod tests {
 			code: true,
 			min_balance: None,
 			max_balance: None,
+			min_address: None,
+			max_address: None,
+			only_contracts: false,
+			only_with_storage: false,
+		})));
+	}
+
+	#[test]
+	fn test_command_state_export_filtered() {
+		let args = vec!["parity", "export", "state", "--format", "binary", "--only-contracts", "--only-with-storage",
+			"--min-address", "0x0000000000000000000000000000000000000100", "--max-address", "00000000000000000000000000000000000001ff", "state.bin"];
+		let conf = parse(&args);
+		match conf.into_command().unwrap().cmd {
+			Cmd::Blockchain(BlockchainCmd::ExportState(cmd)) => {
+				assert_eq!(cmd.format, Some(DataFormat::Binary));
+				assert!(cmd.only_contracts);
+				assert!(cmd.only_with_storage);
+				assert_eq!(cmd.min_address, Some(Address::from(0x100)));
+				assert_eq!(cmd.max_address, Some(Address::from(0x1ff)));
+			},
+			_ => panic!("Expected state export command"),
+		}
+
+		let args = vec!["parity", "export", "state", "--min-address", "0x01", "state.json"];
+		assert!(parse(&args).into_command().is_err());
+	}
+
+	#[test]
+	fn test_command_traces_export() {
+		let args = vec!["parity", "export", "traces", "--from", "100", "--to", "200", "traces.csv"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Blockchain(BlockchainCmd::ExportTable(ExportTable {
+			spec: Default::default(),
+			cache_config: Default::default(),
+			dirs: Default::default(),
+			file_path: Some("traces.csv".into()),
+			pruning: Default::default(),
+			pruning_history: 64,
+			pruning_memory: 75,
+			compaction: Default::default(),
+			wal: true,
+			tracing: Default::default(),
+			fat_db: Default::default(),
+			from_block: BlockId::Number(100),
+			to_block: BlockId::Number(200),
+			table: Table::Traces,
 		})));
+
+		let args = vec!["parity", "export", "receipts", "--format", "hex", "receipts.csv"];
+		assert!(parse(&args).into_command().is_err());
+	}
+
+	#[test]
+	fn test_command_transfers_export() {
+		let args = vec!["parity", "export", "transfers", "--address", "0x0000000000000000000000000000000000000001", "--from", "100", "transfers.csv"];
+		let conf = parse(&args);
+		match conf.into_command().unwrap().cmd {
+			Cmd::Blockchain(BlockchainCmd::ExportTable(cmd)) => {
+				assert_eq!(cmd.table, Table::Transfers(1.into()));
+				assert_eq!(cmd.from_block, BlockId::Number(100));
+			},
+			_ => panic!("Should be an export command"),
+		}
+
+		let args = vec!["parity", "export", "transfers", "transfers.csv"];
+		assert!(parse(&args).into_command().is_err());
 	}
 
 	#[test]
@@ -1204,6 +2423,9 @@ mod tests {
 			from_block: BlockId::Number(1),
 			to_block: BlockId::Latest,
 			check_seal: true,
+			compression: Compression::None,
+			blocks_per_file: None,
+			resume: false,
 		})));
 	}
 
@@ -1221,6 +2443,20 @@ mod tests {
 		}));
 	}
 
+	#[test]
+	fn test_command_signer_approve() {
+		let args = vec!["parity", "signer", "approve", "5", "--password", "pwfile"];
+		let conf = parse(&args);
+		let signer_path = Directories::default().signer;
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::SignerApprove {
+			id: Some(5),
+			pwfile: Some("pwfile".into()),
+			port: 8180,
+			ipc_path: Path::new(&signer_path).join("signer.ipc"),
+			authfile: Path::new(&signer_path).join("authcodes"),
+		});
+	}
+
 	#[test]
 	fn test_run_cmd() {
 		let args = vec!["parity"];
@@ -1247,6 +2483,8 @@ mod tests {
 			miner_extras: Default::default(),
 			update_policy: UpdatePolicy { enable_downloading: true, require_consensus: true, filter: UpdateFilter::Critical, track: ReleaseTrack::Unknown, path: default_hypervisor_path() },
 			mode: Default::default(),
+			wake_on_rpc: Default::default(),
+			offline_serve: false,
 			tracing: Default::default(),
 			compaction: Default::default(),
 			wal: true,
@@ -1256,20 +2494,66 @@ mod tests {
 			net_settings: Default::default(),
 			dapps_conf: Default::default(),
 			ipfs_conf: Default::default(),
+			health_conf: Default::default(),
+			sealing_watchdog_conf: Default::default(),
+			dev_block_time: None,
+			dev_seal_on_txs: false,
+			dev_accounts: None,
 			signer_conf: Default::default(),
+			signer_policy: None,
 			secretstore_conf: Default::default(),
 			ui: false,
 			dapp: None,
 			name: "".into(),
 			custom_bootnodes: false,
 			fat_db: Default::default(),
-			no_periodic_snapshot: false,
+			snapshot_schedule: Some(SnapshotSchedule {
+				period: SnapshotPeriod::Blocks(10000),
+				history: 100,
+				window: None,
+			}),
+			snapshot_threads: 1,
+			snapshot_throttle: Default::default(),
+			snapshot_retention: Default::default(),
+			warp_from: None,
 			stratum: None,
 			check_seal: true,
 			download_old_blocks: true,
+			ancient_blocks_rate: None,
 			verifier_settings: Default::default(),
 			serve_light: true,
+			pip: Default::default(),
+			les: None,
+			snapshot_serving: Default::default(),
+			snapshot_download: Default::default(),
+			checkpoint: Default::default(),
+			light_checkpoint: Default::default(), persist_light_cache: true, light_fallback_url: None,
+			light_ws_servers: Vec::new(),
+			on_demand: Default::default(),
+			tx_relay_peers: None,
+			max_reorg_depth: None,
+			reorg_alert_urls: Vec::new(),
+			block_range: None,
+			token_index: false,
+			balance_history: false,
+			log_index: false,
+			disk_guard: DiskGuardConfig {
+				warn: 4096 * 1024 * 1024,
+				stop_snapshots: 2048 * 1024 * 1024,
+				pause_ancient_blocks: 1024 * 1024 * 1024,
+				halt_import: 512 * 1024 * 1024,
+				path: None,
+			},
+			priority: Default::default(),
+			shutdown_timeout: None,
+			reserved_peers_file: None,
+			statsd: None,
+			span_export: None,
+			sql_export: None,
+			event_publisher: None,
+			tx_queue_persistence: Persistence::Local,
 			light: false,
+			hybrid: Hybrid::Disabled,
 		};
 		expected.secretstore_conf.enabled = cfg!(feature = "secretstore");
 		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Run(expected));
@@ -1285,6 +2569,8 @@ mod tests {
 		let conf1 = parse(&["parity", "--tx-queue-strategy", "gas_factor"]);
 		let conf2 = parse(&["parity", "--tx-queue-strategy", "gas_price"]);
 		let conf3 = parse(&["parity", "--tx-queue-strategy", "gas"]);
+		let conf4 = parse(&["parity", "--tx-queue-locals", "retain"]);
+		let conf5 = parse(&["parity", "--tx-queue-locals", "all"]);
 
 		// then
 		let min_period = conf0.args.flag_reseal_min_period;
@@ -1295,6 +2581,52 @@ mod tests {
 		assert_eq!(conf2.miner_options(min_period).unwrap(), mining_options);
 		mining_options.tx_queue_strategy = PrioritizationStrategy::GasAndGasPrice;
 		assert_eq!(conf3.miner_options(min_period).unwrap(), mining_options);
+		mining_options.tx_queue_strategy = PrioritizationStrategy::GasPriceOnly;
+		mining_options.tx_queue_locals = LocalsPolicy::Retain;
+		assert_eq!(conf4.miner_options(min_period).unwrap(), mining_options);
+		assert!(conf5.miner_options(min_period).is_err());
+	}
+
+	#[test]
+	fn should_persist_only_local_transactions_by_default() {
+		// given
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--tx-queue-persist", "all"]);
+
+		// then
+		match conf0.into_command().unwrap().cmd {
+			Cmd::Run(cmd) => assert_eq!(cmd.tx_queue_persistence, Persistence::Local),
+			_ => panic!("Should be Cmd::Run"),
+		}
+		match conf1.into_command().unwrap().cmd {
+			Cmd::Run(cmd) => assert_eq!(cmd.tx_queue_persistence, Persistence::All),
+			_ => panic!("Should be Cmd::Run"),
+		}
+	}
+
+	#[test]
+	fn should_parse_engine_standby_options() {
+		// when
+		let conf0 = parse(&["parity", "--engine-lock", "/mnt/shared/signer.lock"]);
+		let conf1 = parse(&["parity", "--engine-lock", "/mnt/shared/signer.lock", "--engine-standby", "--engine-lock-timeout", "30"]);
+		let conf2 = parse(&["parity", "--engine-standby"]);
+
+		// then
+		let min_period = conf0.args.flag_reseal_min_period;
+		assert_eq!(conf0.miner_options(min_period).unwrap().standby, Some(StandbyOptions {
+			lock_file: "/mnt/shared/signer.lock".into(),
+			standby: false,
+			timeout: Duration::from_secs(60),
+			grace: Duration::from_secs(5),
+		}));
+		assert_eq!(conf1.miner_options(min_period).unwrap().standby, Some(StandbyOptions {
+			lock_file: "/mnt/shared/signer.lock".into(),
+			standby: true,
+			timeout: Duration::from_secs(30),
+			grace: Duration::from_secs(5),
+		}));
+		assert!(conf2.miner_options(min_period).is_err());
+		assert!(parse(&["parity", "--engine-lock", "/mnt/shared/signer.lock", "--engine-lock-timeout", "5"]).miner_options(min_period).is_err());
 	}
 
 	#[test]
@@ -1362,139 +2694,937 @@ mod tests {
 	}
 
 	#[test]
-	fn should_parse_rpc_hosts() {
+	fn should_parse_rpc_limits() {
 		// given
 
 		// when
 		let conf0 = parse(&["parity"]);
-		let conf1 = parse(&["parity", "--jsonrpc-hosts", "none"]);
-		let conf2 = parse(&["parity", "--jsonrpc-hosts", "all"]);
-		let conf3 = parse(&["parity", "--jsonrpc-hosts", "ethcore.io,something.io"]);
+		let conf1 = parse(&["parity",
+						 "--jsonrpc-max-batch-size", "10",
+						 "--jsonrpc-max-request-size", "2",
+						 "--jsonrpc-max-response-size", "1024"
+						 ]);
 
 		// then
-		assert_eq!(conf0.rpc_hosts(), Some(Vec::new()));
-		assert_eq!(conf1.rpc_hosts(), Some(Vec::new()));
-		assert_eq!(conf2.rpc_hosts(), None);
-		assert_eq!(conf3.rpc_hosts(), Some(vec!["ethcore.io".into(), "something.io".into()]));
+		assert_eq!(conf0.http_config().unwrap().limits, RequestLimits::default());
+		assert_eq!(conf1.http_config().unwrap().limits, RequestLimits {
+			max_batch_size: Some(10),
+			max_request_size: Some(2048),
+			max_response_size: Some(1024 * 1024),
+		});
 	}
 
 	#[test]
-	fn should_parse_ipfs_hosts() {
+	fn should_reject_overflowing_rpc_limits() {
 		// given
+		let too_large = format!("{}", usize::max_value() / 1024 + 1);
 
 		// when
-		let conf0 = parse(&["parity"]);
-		let conf1 = parse(&["parity", "--ipfs-api-hosts", "none"]);
-		let conf2 = parse(&["parity", "--ipfs-api-hosts", "all"]);
-		let conf3 = parse(&["parity", "--ipfs-api-hosts", "ethcore.io,something.io"]);
+		let conf = parse(&["parity", "--jsonrpc-max-response-size", &too_large]);
 
 		// then
-		assert_eq!(conf0.ipfs_hosts(), Some(Vec::new()));
-		assert_eq!(conf1.ipfs_hosts(), Some(Vec::new()));
-		assert_eq!(conf2.ipfs_hosts(), None);
-		assert_eq!(conf3.ipfs_hosts(), Some(vec!["ethcore.io".into(), "something.io".into()]));
+		assert!(conf.http_config().is_err());
 	}
 
 	#[test]
-	fn should_parse_ipfs_cors() {
+	fn should_reject_request_size_limit_of_threaded_server() {
+		// given
+		let conf = parse(&["parity", "--no-dapps", "--jsonrpc-threads", "4", "--jsonrpc-max-request-size", "64"]);
+
+		// then
+		assert!(conf.http_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_warp_serving_limits() {
 		// given
 
 		// when
 		let conf0 = parse(&["parity"]);
-		let conf1 = parse(&["parity", "--ipfs-api-cors", "*"]);
-		let conf2 = parse(&["parity", "--ipfs-api-cors", "http://ethcore.io,http://something.io"]);
+		let conf1 = parse(&["parity",
+						 "--warp-serve-bandwidth", "512",
+						 "--warp-serve-peers", "4",
+						 "--warp-serve-hours", "22-6"
+						 ]);
+		let conf2 = parse(&["parity", "--warp-serve-hours", "22-24"]);
+		let conf3 = parse(&["parity", "--warp-serve-hours", "22"]);
+		let conf4 = parse(&["parity", "--warp-serve-bandwidth", &format!("{}", usize::max_value() / 1024 + 1)]);
 
 		// then
-		assert_eq!(conf0.ipfs_cors(), None);
-		assert_eq!(conf1.ipfs_cors(), Some(vec!["*".into()]));
-		assert_eq!(conf2.ipfs_cors(), Some(vec!["http://ethcore.io".into(),"http://something.io".into()]));
+		assert_eq!(conf0.snapshot_serving().unwrap(), Default::default());
+		assert_eq!(conf1.snapshot_serving().unwrap(), SnapshotServingConfig {
+			max_bandwidth: Some(512 * 1024),
+			max_peers: Some(4),
+			hours: Some((22, 6)),
+		});
+		assert!(conf2.snapshot_serving().is_err());
+		assert!(conf3.snapshot_serving().is_err());
+		assert!(conf4.snapshot_serving().is_err());
 	}
 
 	#[test]
-	fn should_disable_signer_in_geth_compat() {
+	fn should_parse_snapshot_download_settings() {
 		// given
+		let enode = "enode://6f8a80d14311c39f35f516fa664deaaaa13e85b2f7493f37f6144d86991ec012937307647bd3b9a82abe2974e1407241d54947bbb39763a4cac9f77166ad92a0@10.3.58.6:30303";
 
 		// when
-		let conf0 = parse(&["parity", "--geth"]);
-		let conf1 = parse(&["parity", "--geth", "--force-ui"]);
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity",
+						 "--snapshot-download-ahead", "10",
+						 "--snapshot-download-peers", "8",
+						 "--snapshot-chunks-per-peer", "4",
+						 "--snapshot-chunk-timeout", "30",
+						 "--snapshot-chunk-retries", "5",
+						 "--snapshot-preferred-peers", enode,
+						 ]);
+		let conf2 = parse(&["parity", "--snapshot-preferred-peers", "10.3.58.6"]);
 
 		// then
-		assert_eq!(conf0.ui_enabled(), false);
-		assert_eq!(conf1.ui_enabled(), true);
+		assert_eq!(conf0.snapshot_download(), Default::default());
+		assert_eq!(conf1.snapshot_download(), SnapshotDownloadConfig {
+			max_chunks_ahead: 10,
+			chunks_per_peer: 4,
+			max_peers: Some(8),
+			chunk_timeout: 30,
+			max_chunk_retries: Some(5),
+		});
+		assert_eq!(conf0.snapshot_preferred_peers().unwrap(), Vec::<String>::new());
+		assert_eq!(conf1.snapshot_preferred_peers().unwrap(), vec![enode.to_owned()]);
+		assert!(conf2.snapshot_preferred_peers().is_err());
 	}
 
 	#[test]
-	fn should_disable_signer_when_account_is_unlocked() {
+	fn should_parse_local_transactions_propagation_settings() {
 		// given
+		let enode = "enode://6f8a80d14311c39f35f516fa664deaaaa13e85b2f7493f37f6144d86991ec012937307647bd3b9a82abe2974e1407241d54947bbb39763a4cac9f77166ad92a0@10.3.58.6:30303";
 
 		// when
-		let conf0 = parse(&["parity", "--unlock", "0x0"]);
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity",
+						 "--local-tx-delay", "10",
+						 "--local-tx-jitter", "20",
+						 "--local-tx-relay-peers", enode,
+						 ]);
+		let conf2 = parse(&["parity", "--local-tx-relay-peers", "10.3.58.6"]);
 
 		// then
-		assert_eq!(conf0.ui_enabled(), false);
+		let net0 = conf0.net_config().unwrap();
+		assert_eq!((net0.local_tx_delay, net0.local_tx_jitter), (0, 0));
+		assert!(net0.local_tx_relay_peers.is_empty());
+		let net1 = conf1.net_config().unwrap();
+		assert_eq!((net1.local_tx_delay, net1.local_tx_jitter), (10, 20));
+		assert_eq!(net1.local_tx_relay_peers, vec![enode.to_owned()]);
+		assert!(conf2.net_config().is_err());
 	}
 
 	#[test]
-	fn should_parse_signer_configration() {
-		// given
-
+	fn should_parse_les_settings() {
 		// when
-		let conf0 = parse(&["parity", "--ui-path", "signer"]);
-		let conf1 = parse(&["parity", "--ui-path", "signer", "--ui-no-validation"]);
-		let conf2 = parse(&["parity", "--ui-path", "signer", "--ui-port", "3123"]);
-		let conf3 = parse(&["parity", "--ui-path", "signer", "--ui-interface", "test"]);
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity",
+						 "--serve-les",
+						 "--les-peers", "5",
+						 "--les-buffer-limit", "1000",
+						 "--les-recharge-rate", "10",
+						 ]);
 
 		// then
-		assert_eq!(conf0.signer_config(), SignerConfiguration {
-			enabled: true,
-			port: 8180,
-			interface: "127.0.0.1".into(),
-			signer_path: "signer".into(),
-			skip_origin_validation: false,
-		});
-		assert_eq!(conf1.signer_config(), SignerConfiguration {
-			enabled: true,
-			port: 8180,
-			interface: "127.0.0.1".into(),
-			signer_path: "signer".into(),
-			skip_origin_validation: true,
-		});
-		assert_eq!(conf2.signer_config(), SignerConfiguration {
-			enabled: true,
-			port: 3123,
-			interface: "127.0.0.1".into(),
-			signer_path: "signer".into(),
-			skip_origin_validation: false,
-		});
-		assert_eq!(conf3.signer_config(), SignerConfiguration {
-			enabled: true,
-			port: 8180,
-			interface: "test".into(),
-			signer_path: "signer".into(),
-			skip_origin_validation: false,
-		});
+		assert_eq!(conf0.les_config(), None);
+		assert_eq!(conf0.net_config().unwrap().les_peers, 0);
+		assert_eq!(conf1.les_config(), Some(LesConfig {
+			max_peers: 5,
+			buffer_limit: 1000,
+			recharge_rate: 10,
+		}));
+		assert_eq!(conf1.net_config().unwrap().les_peers, 5);
 	}
 
 	#[test]
-	fn should_parse_dapp_opening() {
+	fn should_parse_checkpoint() {
 		// given
-		let temp = RandomTempPath::new();
-		let name = temp.file_name().unwrap().to_str().unwrap();
-		create_dir(temp.as_str().to_owned()).unwrap();
 
 		// when
-		let conf0 = parse(&["parity", "dapp", temp.to_str().unwrap()]);
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--checkpoint", "4000000,0x0000000000000000000000000000000000000000000000000000000000000001,0x10"]);
+		let conf2 = parse(&["parity", "--checkpoint", "4000000,0x01", "--no-checkpoint"]);
+		let conf3 = parse(&["parity", "--checkpoint", "4000000,0x01"]);
 
 		// then
-		assert_eq!(conf0.dapp_to_open(), Ok(Some(name.into())));
-		let extra_dapps = conf0.dapps_config().extra_dapps;
-		assert_eq!(extra_dapps, vec![temp.to_owned()]);
+		assert_eq!(conf0.checkpoint().unwrap(), CheckpointConfig::Spec);
+		assert_eq!(conf1.checkpoint().unwrap(), CheckpointConfig::Custom(Checkpoint {
+			number: 4_000_000,
+			hash: 1.into(),
+			total_difficulty: 16.into(),
+		}));
+		assert_eq!(conf2.checkpoint().unwrap(), CheckpointConfig::Disabled);
+		assert!(conf3.checkpoint().is_err());
 	}
 
 	#[test]
-	fn should_not_bail_on_empty_line_in_reserved_peers() {
-		let temp = RandomTempPath::new();
-		create_dir(temp.as_str().to_owned()).unwrap();
+	fn should_parse_on_demand_config() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--on-demand-timeouts", "all:10000,execution:30000", "--on-demand-retries", "2", "--on-demand-peer-selection", "least-loaded"]);
+		let conf2 = parse(&["parity", "--on-demand-timeouts", "block:1000"]);
+		let conf3 = parse(&["parity", "--on-demand-timeouts", "all:0"]);
+		let conf4 = parse(&["parity", "--on-demand-peer-selection", "fastest"]);
+
+		// then
+		assert_eq!(conf0.on_demand_config().unwrap(), Default::default());
+		assert_eq!(conf1.on_demand_config().unwrap(), OnDemandConfig {
+			timeouts: RequestTimeouts {
+				transaction_proof: ::time::Duration::seconds(30),
+				..RequestTimeouts::uniform(::time::Duration::seconds(10))
+			},
+			max_retries: 2,
+			peer_selection: PeerSelection::LeastLoaded,
+			preferred_peers: Vec::new(),
+			preferred_peers_only: false,
+		});
+		assert!(conf2.on_demand_config().is_err());
+		assert!(conf3.on_demand_config().is_err());
+		assert!(conf4.on_demand_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_light_serving_peers() {
+		// given
+		let enode = "enode://6f8a80d14311c39f35f516fa664deaaaa13e85b2f7493f37f6144d86991ec012937307647bd3b9a82abe2974e1407241d54947bbb39763a4cac9f77166ad92a0@10.3.58.6:30303";
+
+		// when
+		let conf0 = parse(&["parity", "--light", "--light-serving-peers", enode, "--light-serving-peers-only"]);
+		let conf1 = parse(&["parity", "--light", "--light-serving-peers", "10.3.58.6"]);
+		let conf2 = parse(&["parity", "--light", "--light-serving-peers-only"]);
+
+		// then
+		let on_demand = conf0.on_demand_config().unwrap();
+		assert_eq!(on_demand.preferred_peers, vec![enode[8..136].parse().unwrap()]);
+		assert!(on_demand.preferred_peers_only);
+		assert!(conf0.net_config().unwrap().reserved_nodes.contains(&enode.to_owned()));
+		assert!(conf1.on_demand_config().is_err());
+		assert!(conf2.on_demand_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_light_fallback_url() {
+		// when
+		let conf0 = parse(&["parity", "--light"]);
+		let conf1 = parse(&["parity", "--light", "--light-fallback-url", "https://node.example.com:8545"]);
+		let conf2 = parse(&["parity", "--light", "--light-fallback-url", "node.example.com:8545"]);
+
+		// then
+		assert_eq!(conf0.light_fallback_url(), Ok(None));
+		assert_eq!(conf1.light_fallback_url(), Ok(Some("https://node.example.com:8545".into())));
+		assert!(conf2.light_fallback_url().is_err());
+	}
+
+	#[test]
+	fn should_parse_light_ws_servers() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--light-ws-servers", "ws://node1.example.com:8547, ws://10.0.0.1:8547"]);
+		let conf2 = parse(&["parity", "--light-ws-servers", "wss://node1.example.com"]);
+		let conf3 = parse(&["parity", "--light-ws-servers", "http://node1.example.com:8547"]);
+		let conf4 = parse(&["parity", "--light-ws-servers", "wss://node1.example.com:port"]);
+
+		// then
+		assert_eq!(conf0.light_ws_servers(), Ok(Vec::new()));
+		assert_eq!(conf1.light_ws_servers(), Ok(vec!["ws://node1.example.com:8547".into(), "ws://10.0.0.1:8547".into()]));
+		assert_eq!(conf2.light_ws_servers(), Ok(vec!["wss://node1.example.com".into()]));
+		assert!(conf3.light_ws_servers().is_err());
+		assert!(conf4.light_ws_servers().is_err());
+	}
+
+	#[test]
+	fn should_parse_hybrid_mode() {
+		// when
+		let conf0 = parse(&["parity", "--hybrid"]);
+		let conf1 = parse(&["parity", "--hybrid", "--hybrid-rpc-port", "8545"]);
+		let conf2 = parse(&["parity", "--hybrid", "--hybrid-port", "30303"]);
+		let conf3 = parse(&["parity", "--hybrid", "--hybrid-full-node"]);
+
+		// then
+		assert_eq!(conf0.hybrid(), Ok(Hybrid::Light { rpc_port: 8549 }));
+		assert!(conf1.hybrid().is_err());
+		assert!(conf2.hybrid().is_err());
+		assert_eq!(conf3.hybrid(), Ok(Hybrid::Full));
+		assert_eq!(conf3.net_addresses().unwrap().0, Some("0.0.0.0:30304".parse().unwrap()));
+		match conf0.into_command().unwrap().cmd {
+			Cmd::Run(cmd) => assert!(cmd.light),
+			_ => panic!("Should be Cmd::Run"),
+		}
+		match conf3.into_command().unwrap().cmd {
+			Cmd::Run(cmd) => {
+				assert!(!cmd.light);
+				assert!(!cmd.ws_conf.enabled);
+				assert_eq!(cmd.http_conf.interface, "127.0.0.1");
+				assert_eq!(cmd.http_conf.port, 8549);
+				assert!(cmd.logger_config.file.unwrap().ends_with("hybrid-full.log"));
+			},
+			_ => panic!("Should be Cmd::Run"),
+		}
+	}
+
+	#[test]
+	fn should_parse_pip_config() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--pip-peers", "10", "--pip-credit-limit", "1000000", "--pip-recharge-rate", "500", "--pip-request-costs", "base:100,execution:3"]);
+		let conf2 = parse(&["parity", "--pip-request-costs", "transaction:3"]);
+		let conf3 = parse(&["parity", "--pip-request-costs", "base"]);
+		let conf4 = parse(&["parity", "--pip-peers", "0"]);
+		let conf5 = parse(&["parity", "--pip-ws-port", "8547"]);
+
+		// then
+		let mut costs = CostTable::default();
+		costs.set_base(100.into());
+		costs.set_cost(RequestKind::Execution, 3.into());
+
+		assert_eq!(conf0.pip_config().unwrap(), Default::default());
+		assert_eq!(conf1.pip_config().unwrap(), PipConfig {
+			max_peers: Some(10),
+			flow_params: FlowParams::new(1_000_000.into(), costs, 500.into()),
+			ws_listen: None,
+		});
+		assert!(conf2.pip_config().is_err());
+		assert!(conf3.pip_config().is_err());
+		assert!(conf4.pip_config().is_err());
+		assert_eq!(conf5.pip_config().unwrap().ws_listen, Some("0.0.0.0:8547".parse().unwrap()));
+	}
+
+	#[test]
+	fn should_parse_reorg_alert_urls() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--max-reorg-depth", "10", "--reorg-alert-url", "http://localhost:8080,http://localhost:8081"]);
+
+		// then
+		assert_eq!(conf0.args.flag_max_reorg_depth, None);
+		assert_eq!(conf0.reorg_alert_urls(), Vec::<String>::new());
+		assert_eq!(conf1.args.flag_max_reorg_depth, Some(10));
+		assert_eq!(conf1.reorg_alert_urls(), vec!["http://localhost:8080".to_owned(), "http://localhost:8081".to_owned()]);
+	}
+
+	#[test]
+	fn should_parse_sync_schedule() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--sync-schedule", "8-18:peers=10,bandwidth=512;18-8:peers=100"]);
+		let conf2 = parse(&["parity", "--sync-schedule", "8-18:speed=10"]);
+
+		// then
+		assert!(conf0.sync_schedule().unwrap().is_empty());
+		let schedule = conf1.sync_schedule().unwrap();
+		assert_eq!(schedule.limits_at(12).max_peers, Some(10));
+		assert_eq!(schedule.limits_at(12).max_bandwidth, Some(512 * 1024));
+		assert_eq!(schedule.limits_at(20).max_peers, Some(100));
+		assert!(conf2.sync_schedule().is_err());
+	}
+
+	#[test]
+	fn should_parse_protocol_peers() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--protocol-peers", "les=:10, par=5:20,shh=2"]);
+		let conf2 = parse(&["parity", "--protocol-peers", "light=1:2"]);
+		let conf3 = parse(&["parity", "--protocol-peers", "les=a:2"]);
+
+		// then
+		assert_eq!(conf0.protocol_slots(), Ok(vec![]));
+		assert_eq!(conf1.protocol_slots(), Ok(vec![
+			ProtocolSlots { protocol: *b"les", min: None, max: Some(10) },
+			ProtocolSlots { protocol: *b"par", min: Some(5), max: Some(20) },
+			ProtocolSlots { protocol: *b"shh", min: Some(2), max: None },
+		]));
+		assert!(conf2.protocol_slots().is_err());
+		assert!(conf3.protocol_slots().is_err());
+	}
+
+	#[test]
+	fn should_parse_bandwidth_limits() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--max-upload", "256", "--max-download", "1024"]);
+
+		// then
+		let net0 = conf0.net_config().unwrap();
+		assert_eq!((net0.max_upload, net0.max_download), (None, None));
+		let net1 = conf1.net_config().unwrap();
+		assert_eq!((net1.max_upload, net1.max_download), (Some(256 * 1024), Some(1024 * 1024)));
+	}
+
+	#[test]
+	fn should_parse_log_format() {
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--log-format", "json"]);
+		let conf2 = parse(&["parity", "--log-format", "xml"]);
+
+		assert_eq!(conf0.logger_config().unwrap().format, LogFormat::Text);
+		assert_eq!(conf1.logger_config().unwrap().format, LogFormat::Json);
+		assert!(conf2.logger_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_log_rotation() {
+		let conf0 = parse(&["parity", "--log-file", "parity.log"]);
+		let conf1 = parse(&["parity", "--log-file", "parity.log", "--log-max-size", "100", "--log-max-files", "3", "--log-compress"]);
+		let conf2 = parse(&["parity", "--log-max-size", "0"]);
+
+		assert_eq!(conf0.logger_config().unwrap().rotation, None);
+		assert_eq!(conf1.logger_config().unwrap().rotation, Some(LogRotation {
+			max_size: 100 * 1024 * 1024,
+			max_files: 3,
+			compress: true,
+		}));
+		assert!(conf2.logger_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_syslog() {
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--syslog", "udp://10.0.0.1:514", "--syslog-facility", "local3", "--syslog-tag", "node1"]);
+		let conf2 = parse(&["parity", "--syslog", "local", "--syslog-facility", "daemons"]);
+
+		assert_eq!(conf0.logger_config().unwrap().syslog, None);
+		assert_eq!(conf1.logger_config().unwrap().syslog, Some(SyslogConfig {
+			target: SyslogTarget::Udp("10.0.0.1:514".parse().unwrap()),
+			facility: SyslogFacility(19),
+			tag: "node1".into(),
+		}));
+		assert!(conf2.logger_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_statsd() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--statsd", "127.0.0.1:8125", "--statsd-prefix", "node1.", "--statsd-interval", "30"]);
+		let conf2 = parse(&["parity", "--statsd", "localhost"]);
+
+		// then
+		assert_eq!(conf0.statsd(), Ok(None));
+		assert_eq!(conf1.statsd(), Ok(Some(StatsdConfig {
+			address: "127.0.0.1:8125".parse().unwrap(),
+			prefix: "node1".into(),
+			interval: 30,
+		})));
+		assert!(conf2.statsd().is_err());
+	}
+
+	#[test]
+	fn should_parse_span_export() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--span-collector", "http://localhost:9411/api/v2/spans", "--span-service", "node1"]);
+		let conf2 = parse(&["parity", "--span-collector", "localhost:9411"]);
+
+		// then
+		assert_eq!(conf0.span_export(), Ok(None));
+		assert_eq!(conf1.span_export(), Ok(Some(SpanExportConfig {
+			url: "http://localhost:9411/api/v2/spans".into(),
+			service_name: "node1".into(),
+		})));
+		assert!(conf2.span_export().is_err());
+	}
+
+	#[test]
+	fn should_parse_sql_export() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--sql-export", "postgresql://localhost/chain", "--sql-export-batch", "10"]);
+		let conf2 = parse(&["parity", "--sql-export", "postgresql://localhost/chain", "--sql-export-batch", "0"]);
+		let conf3 = parse(&["parity", "--sql-export", "postgresql://localhost/chain?options=-c"]);
+
+		// then
+		assert_eq!(conf0.sql_export(), Ok(None));
+		assert_eq!(conf1.sql_export(), Ok(Some(SqlExportConfig {
+			dsn: "postgresql://localhost/chain".into(),
+			batch_size: 10,
+			psql: "psql".into(),
+		})));
+		assert!(conf2.sql_export().is_err());
+		assert!(conf3.sql_export().is_err());
+	}
+
+	#[test]
+	fn should_parse_event_publisher() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--event-publisher", "nats://localhost:4222", "--event-subject-prefix", "chain.",
+			"--event-log-addresses", "0x0000000000000000000000000000000000000001", "--event-replay-from", "100"]);
+		let conf2 = parse(&["parity", "--event-publisher", "amqp://localhost"]);
+		let conf3 = parse(&["parity", "--event-publisher", "nats://localhost:4222", "--event-subject-prefix", "chain.*"]);
+
+		// then
+		assert_eq!(conf0.event_publisher(), Ok(None));
+		assert_eq!(conf1.event_publisher(), Ok(Some(EventPublisherConfig {
+			address: "localhost:4222".into(),
+			subject_prefix: "chain".into(),
+			log_addresses: vec![1.into()],
+			replay_from: Some(100),
+		})));
+		assert!(conf2.event_publisher().is_err());
+		assert!(conf3.event_publisher().is_err());
+	}
+
+	#[test]
+	fn should_parse_snapshot_schedule() {
+		// when
+		let conf0 = parse(&["parity", "--no-periodic-snapshot"]);
+		let conf1 = parse(&["parity", "--snapshot-interval", "21600", "--snapshot-window", "22:00-04:00"]);
+		let conf2 = parse(&["parity", "--snapshot-every", "0"]);
+		let conf3 = parse(&["parity", "--snapshot-window", "22:00"]);
+
+		// then
+		assert_eq!(conf0.snapshot_schedule(), Ok(None));
+		assert_eq!(conf1.snapshot_schedule(), Ok(Some(SnapshotSchedule {
+			period: SnapshotPeriod::Interval(Duration::from_secs(21600)),
+			history: 100,
+			window: Some(TimeWindow { start: 22 * 60, end: 4 * 60 }),
+		})));
+		assert!(conf2.snapshot_schedule().is_err());
+		assert!(conf3.snapshot_schedule().is_err());
+	}
+
+	#[test]
+	fn should_parse_snapshot_throttle() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--snapshot-chunks-per-sec", "2", "--snapshot-yield-to-import"]);
+		let conf2 = parse(&["parity", "--snapshot-chunks-per-sec", "0"]);
+
+		// then
+		assert_eq!(conf0.snapshot_throttle(), Ok(Default::default()));
+		assert_eq!(conf1.snapshot_throttle(), Ok(SnapshotThrottle {
+			max_chunks_per_sec: Some(2),
+			yield_to_import: true,
+		}));
+		assert!(conf2.snapshot_throttle().is_err());
+	}
+
+	#[test]
+	fn should_parse_snapshot_retention() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--snapshot-keep", "3", "--snapshot-max-disk", "2048"]);
+
+		// then
+		assert_eq!(conf0.snapshot_retention(), RetentionPolicy { keep_last: 0, max_size: None });
+		assert_eq!(conf1.snapshot_retention(), RetentionPolicy {
+			keep_last: 3,
+			max_size: Some(2048 * 1024 * 1024),
+		});
+	}
+
+	#[test]
+	fn should_parse_warp_from() {
+		// when
+		let hash = "0x2bbd1fc9a2b8fbd5e8da1e5a1ac6e4d3e3bff1e3e1b8bcb1e07af2ef3bb38e59";
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--warp-from", "https://mirror.local/snapshot.pack", "--warp-from-hash", hash]);
+		let conf2 = parse(&["parity", "--warp-from", "mirror.local/snapshot.pack"]);
+		let conf3 = parse(&["parity", "--warp-from", "http://mirror.local/snapshot.pack", "--warp-from-hash", "0x12"]);
+
+		// then
+		assert_eq!(conf0.warp_from(), Ok(None));
+		assert_eq!(conf1.warp_from(), Ok(Some(WarpFrom {
+			url: "https://mirror.local/snapshot.pack".into(),
+			manifest_hash: Some(hash[2..].parse().unwrap()),
+		})));
+		assert!(conf2.warp_from().is_err());
+		assert!(conf3.warp_from().is_err());
+	}
+
+	#[test]
+	fn should_parse_proxy() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--proxy", "127.0.0.1:9050"]);
+		let conf2 = parse(&["parity", "--proxy", "localhost"]);
+
+		// then
+		assert_eq!(conf0.proxy(), Ok(None));
+		assert_eq!(conf1.proxy(), Ok(Some("127.0.0.1:9050".into())));
+		assert!(conf2.proxy().is_err());
+	}
+
+	#[test]
+	fn should_parse_ip_filter() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--allow-ips", "private,192.0.2.0/24"]);
+		let conf2 = parse(&["parity", "--allow-ips", "10.0.0.0/8, 2001:db8::/32", "--deny-ips", "10.1.0.0/16"]);
+		let conf3 = parse(&["parity", "--allow-ips", "public,private"]);
+		let conf4 = parse(&["parity", "--allow-ips", "10.0.0.0/64"]);
+		let conf5 = parse(&["parity", "--deny-ips", "bad"]);
+
+		// then
+		assert_eq!(conf0.allow_ips(), Ok((AllowIP::All, vec![])));
+		assert_eq!(conf0.deny_ips(), Ok(vec![]));
+		assert_eq!(conf1.allow_ips(), Ok((AllowIP::Private, vec!["192.0.2.0/24".into()])));
+		assert_eq!(conf2.allow_ips(), Ok((AllowIP::None, vec!["10.0.0.0/8".into(), "2001:db8::/32".into()])));
+		assert_eq!(conf2.deny_ips(), Ok(vec!["10.1.0.0/16".into()]));
+		assert!(conf3.allow_ips().is_err());
+		assert!(conf4.allow_ips().is_err());
+		assert!(conf5.deny_ips().is_err());
+	}
+
+	#[test]
+	fn should_parse_block_range() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--block-range", "1000..2000"]);
+		let conf2 = parse(&["parity", "--block-range", "1000000"]);
+		let conf3 = parse(&["parity", "--block-range", "2000..1000"]);
+
+		// then
+		assert_eq!(conf0.block_range(), Ok(None));
+		assert_eq!(conf1.block_range(), Ok(Some(BlockRange::Fixed(1000, 2000))));
+		assert_eq!(conf2.block_range(), Ok(Some(BlockRange::Recent(1_000_000))));
+		assert!(conf3.block_range().is_err());
+	}
+
+	#[test]
+	fn should_parse_wake_on_rpc() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--mode-timeout", "120", "--wake-on-rpc", "eth=600,traces"]);
+		let conf2 = parse(&["parity", "--wake-on-rpc", "eth=soon"]);
+		let conf3 = parse(&["parity", "--wake-on-rpc", "unknown"]);
+
+		// then
+		assert_eq!(conf0.wake_on_rpc(), Ok(ApiWake::new()));
+		assert_eq!(conf1.wake_on_rpc(), Ok(vec![
+			(Api::Eth, Duration::from_secs(600)),
+			(Api::Traces, Duration::from_secs(120)),
+		].into_iter().collect()));
+		assert!(conf2.wake_on_rpc().is_err());
+		assert!(conf3.wake_on_rpc().is_err());
+	}
+
+	#[test]
+	fn should_split_memory_budget() {
+		let conf0 = parse(&["parity", "--memory-budget", "1000"]);
+		let conf1 = parse(&["parity", "--memory-budget", "1000", "--cache-size-db", "300", "--tx-queue-size", "51200"]);
+		let conf2 = parse(&["parity", "--memory-budget", "1000", "--cache-size", "200"]);
+		let conf3 = parse(&["parity", "--cache-size-db", "300"]);
+
+		assert_eq!(conf0.cache_config(), Ok(CacheConfig::new(500, 100, 100, 200)));
+		assert_eq!(conf0.tx_queue_size(), Ok(51_200));
+		assert_eq!(conf1.cache_config(), Ok(CacheConfig::new(300, 150, 150, 300)));
+		assert_eq!(conf1.tx_queue_size(), Ok(51_200));
+		assert!(conf2.cache_config().is_err());
+		assert_eq!(conf3.cache_config(), Ok(CacheConfig::new(300, 8, 50, 25)));
+		assert_eq!(conf3.tx_queue_size(), Ok(1024));
+	}
+
+	#[test]
+	fn should_parse_shutdown_timeout() {
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--shutdown-timeout", "25"]);
+		let conf2 = parse(&["parity", "--shutdown-timeout", "0"]);
+
+		assert_eq!(conf0.shutdown_timeout(), Ok(None));
+		assert_eq!(conf1.shutdown_timeout(), Ok(Some(Duration::from_secs(25))));
+		assert!(conf2.shutdown_timeout().is_err());
+	}
+
+	#[test]
+	fn should_compare_reloadable_settings() {
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--max-peers", "100", "--gasprice", "1000", "--port", "30304"]);
+
+		let mut changed = conf0.args.changed_flags(&conf1.args);
+		changed.sort();
+		assert_eq!(changed, vec!["flag_gasprice", "flag_max_peers", "flag_port"]);
+		assert!(conf0.args.changed_flags(&conf0.args).is_empty());
+
+		let settings = conf1.reloadable_settings().unwrap();
+		assert_eq!(settings.max_peers, 100);
+		assert_eq!(settings.gas_pricer, GasPricerConfig::Fixed(1000.into()));
+	}
+
+	#[test]
+	fn should_parse_priority() {
+		use priority::{PriorityConfig, IoClass};
+
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--nice", "10", "--ionice", "best-effort:6"]);
+		let conf2 = parse(&["parity", "--nice", "20"]);
+		let conf3 = parse(&["parity", "--ionice", "realtime"]);
+
+		assert_eq!(conf0.priority(), Ok(PriorityConfig::default()));
+		assert_eq!(conf1.priority(), Ok(PriorityConfig { nice: Some(10), io_class: Some(IoClass::BestEffort(6)) }));
+		assert!(conf2.priority().is_err());
+		assert!(conf3.priority().is_err());
+	}
+
+	#[test]
+	fn should_parse_disk_guard() {
+		let conf0 = parse(&["parity", "--disk-space-warn", "100", "--disk-space-import", "0"]);
+
+		assert_eq!(conf0.disk_guard(), DiskGuardConfig {
+			warn: 100 * 1024 * 1024,
+			stop_snapshots: 2048 * 1024 * 1024,
+			pause_ancient_blocks: 1024 * 1024 * 1024,
+			halt_import: 0,
+			path: None,
+		});
+	}
+
+	#[test]
+	fn should_parse_offline_serve_mode() {
+		let conf = parse(&["parity", "--mode", "offline-serve"]);
+		match conf.into_command().unwrap().cmd {
+			Cmd::Run(c) => {
+				assert_eq!(c.mode, Some(Mode::Off));
+				assert!(c.offline_serve);
+			},
+			_ => panic!("Should be Cmd::Run"),
+		}
+	}
+
+	#[test]
+	fn should_parse_rpc_api_cors_and_hosts() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity",
+						 "--jsonrpc-api-cors", "eth=all;personal=none;parity_set=http://parity.io,null",
+						 "--jsonrpc-api-hosts", "parity_set=none"
+						 ]);
+		let conf2 = parse(&["parity", "--jsonrpc-api-cors", "eth"]);
+		let conf3 = parse(&["parity", "--jsonrpc-api-cors", "unknown=all"]);
+
+		// then
+		let http0 = conf0.http_config().unwrap();
+		assert!(http0.api_cors.is_empty());
+		assert!(http0.api_hosts.is_empty());
+
+		let http1 = conf1.http_config().unwrap();
+		assert_eq!(http1.api_cors, vec![
+			(Api::Eth, None),
+			(Api::Personal, Some(vec![])),
+			(Api::ParitySet, Some(vec!["http://parity.io".into(), "null".into()])),
+		].into_iter().collect());
+		assert_eq!(http1.api_hosts, vec![(Api::ParitySet, Some(vec![]))].into_iter().collect());
+
+		assert!(conf2.http_config().is_err());
+		assert!(conf3.http_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_rpc_hosts() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--jsonrpc-hosts", "none"]);
+		let conf2 = parse(&["parity", "--jsonrpc-hosts", "all"]);
+		let conf3 = parse(&["parity", "--jsonrpc-hosts", "ethcore.io,something.io"]);
+
+		// then
+		assert_eq!(conf0.rpc_hosts(), Some(Vec::new()));
+		assert_eq!(conf1.rpc_hosts(), Some(Vec::new()));
+		assert_eq!(conf2.rpc_hosts(), None);
+		assert_eq!(conf3.rpc_hosts(), Some(vec!["ethcore.io".into(), "something.io".into()]));
+	}
+
+	#[test]
+	fn should_parse_ipfs_hosts() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--ipfs-api-hosts", "none"]);
+		let conf2 = parse(&["parity", "--ipfs-api-hosts", "all"]);
+		let conf3 = parse(&["parity", "--ipfs-api-hosts", "ethcore.io,something.io"]);
+
+		// then
+		assert_eq!(conf0.ipfs_hosts(), Some(Vec::new()));
+		assert_eq!(conf1.ipfs_hosts(), Some(Vec::new()));
+		assert_eq!(conf2.ipfs_hosts(), None);
+		assert_eq!(conf3.ipfs_hosts(), Some(vec!["ethcore.io".into(), "something.io".into()]));
+	}
+
+	#[test]
+	fn should_parse_ipfs_cors() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--ipfs-api-cors", "*"]);
+		let conf2 = parse(&["parity", "--ipfs-api-cors", "http://ethcore.io,http://something.io"]);
+
+		// then
+		assert_eq!(conf0.ipfs_cors(), None);
+		assert_eq!(conf1.ipfs_cors(), Some(vec!["*".into()]));
+		assert_eq!(conf2.ipfs_cors(), Some(vec!["http://ethcore.io".into(),"http://something.io".into()]));
+	}
+
+	#[test]
+	fn should_parse_health_config() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--health-api", "--health-api-interface", "all", "--health-min-peers", "5", "--health-max-clock-drift", "2", "--health-ntp-server", "none"]);
+
+		// then
+		assert_eq!(conf0.health_config(), Default::default());
+		assert_eq!(conf1.health_config(), HealthConfiguration {
+			enabled: true,
+			interface: "0.0.0.0".into(),
+			min_peers: 5,
+			max_clock_drift: 2000,
+			ntp_server: None,
+			..Default::default()
+		});
+	}
+
+	#[test]
+	fn should_parse_sealing_watchdog_config() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--sealing-watchdog", "--sealing-watchdog-missed", "5", "--sealing-watchdog-url", "http://localhost:8080,http://localhost:8081", "--sealing-watchdog-exit"]);
+
+		// then
+		assert_eq!(conf0.sealing_watchdog_config(), Default::default());
+		assert_eq!(conf1.sealing_watchdog_config(), SealingWatchdogConfiguration {
+			enabled: true,
+			max_missed: 5,
+			alert_urls: vec!["http://localhost:8080".to_owned(), "http://localhost:8081".to_owned()],
+			exit: true,
+		});
+	}
+
+	#[test]
+	fn should_parse_dev_block_time() {
+		let conf = parse(&["parity", "--chain", "dev", "--dev-block-time", "5", "--dev-seal-on-txs"]);
+		match conf.into_command().unwrap().cmd {
+			Cmd::Run(c) => {
+				assert_eq!(c.dev_block_time, Some(5));
+				assert!(c.dev_seal_on_txs);
+				assert!(c.miner_options.force_sealing);
+			},
+			_ => panic!("Should be Cmd::Run"),
+		}
+	}
+
+	#[test]
+	fn should_parse_dev_accounts() {
+		let conf0 = parse(&["parity", "--chain", "dev", "--dev-accounts", "2:10"]);
+		let conf1 = parse(&["parity", "--dev-accounts", "2"]);
+
+		assert_eq!(conf0.dev_accounts(&SpecType::Dev), Ok(Some(DevAccounts {
+			count: 2,
+			balance: U256::from(10_000_000_000_000_000_000u64),
+		})));
+		assert!(conf1.dev_accounts(&SpecType::Foundation).is_err());
+	}
+
+	#[test]
+	fn should_disable_signer_in_geth_compat() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity", "--geth"]);
+		let conf1 = parse(&["parity", "--geth", "--force-ui"]);
+
+		// then
+		assert_eq!(conf0.ui_enabled(), false);
+		assert_eq!(conf1.ui_enabled(), true);
+	}
+
+	#[test]
+	fn should_disable_signer_when_account_is_unlocked() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity", "--unlock", "0x0"]);
+
+		// then
+		assert_eq!(conf0.ui_enabled(), false);
+	}
+
+	#[test]
+	fn should_refuse_signer_policy_when_signer_is_disabled() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity", "--signer-policy", "policy.json"]);
+		let conf1 = parse(&["parity", "--no-ui", "--signer-policy", "policy.json"]);
+
+		// then
+		assert_eq!(conf0.signer_policy(), Ok(Some("policy.json".into())));
+		assert!(conf1.signer_policy().is_err());
+		assert!(conf1.into_command().is_err());
+	}
+
+	#[test]
+	fn should_parse_signer_configration() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity", "--ui-path", "signer"]);
+		let conf1 = parse(&["parity", "--ui-path", "signer", "--ui-no-validation"]);
+		let conf2 = parse(&["parity", "--ui-path", "signer", "--ui-port", "3123"]);
+		let conf3 = parse(&["parity", "--ui-path", "signer", "--ui-interface", "test"]);
+
+		// then
+		assert_eq!(conf0.signer_config(), SignerConfiguration {
+			enabled: true,
+			port: 8180,
+			interface: "127.0.0.1".into(),
+			signer_path: "signer".into(),
+			skip_origin_validation: false,
+		});
+		assert_eq!(conf1.signer_config(), SignerConfiguration {
+			enabled: true,
+			port: 8180,
+			interface: "127.0.0.1".into(),
+			signer_path: "signer".into(),
+			skip_origin_validation: true,
+		});
+		assert_eq!(conf2.signer_config(), SignerConfiguration {
+			enabled: true,
+			port: 3123,
+			interface: "127.0.0.1".into(),
+			signer_path: "signer".into(),
+			skip_origin_validation: false,
+		});
+		assert_eq!(conf3.signer_config(), SignerConfiguration {
+			enabled: true,
+			port: 8180,
+			interface: "test".into(),
+			signer_path: "signer".into(),
+			skip_origin_validation: false,
+		});
+	}
+
+	#[test]
+	fn should_parse_dapp_opening() {
+		// given
+		let temp = RandomTempPath::new();
+		let name = temp.file_name().unwrap().to_str().unwrap();
+		create_dir(temp.as_str().to_owned()).unwrap();
+
+		// when
+		let conf0 = parse(&["parity", "dapp", temp.to_str().unwrap()]);
+
+		// then
+		assert_eq!(conf0.dapp_to_open(), Ok(Some(name.into())));
+		let extra_dapps = conf0.dapps_config().extra_dapps;
+		assert_eq!(extra_dapps, vec![temp.to_owned()]);
+	}
+
+	#[test]
+	fn should_not_bail_on_empty_line_in_reserved_peers() {
+		let temp = RandomTempPath::new();
+		create_dir(temp.as_str().to_owned()).unwrap();
 		let filename = temp.as_str().to_owned() + "/peers";
 		File::create(filename.clone()).unwrap().write_all(b"  \n\t\n").unwrap();
 		let args = vec!["parity", "--reserved-peers", &filename];
@@ -1502,6 +3632,50 @@ mod tests {
 		assert!(conf.init_reserved_nodes().is_ok());
 	}
 
+	#[test]
+	fn should_load_signed_light_checkpoint() {
+		use ethkey::{Random, Generator};
+
+		// given
+		let temp = RandomTempPath::new();
+		create_dir(temp.as_str().to_owned()).unwrap();
+		let filename = temp.as_str().to_owned() + "/checkpoint.json";
+		let contents = r#"{"header":"0xc0","totalDifficulty":"0x10","CHTs":["0x0000000000000000000000000000000000000000000000000000000000000001"]}"#;
+		File::create(filename.clone()).unwrap().write_all(contents.as_bytes()).unwrap();
+		let pair = Random.generate().unwrap();
+		let other = Random.generate().unwrap();
+		let now = remote_config::now();
+		let signature = remote_config::sign(contents, pair.secret(), now, now + 3600).unwrap();
+		File::create(filename.clone() + ".sig").unwrap().write_all(signature.as_bytes()).unwrap();
+		let expired_filename = temp.as_str().to_owned() + "/expired.json";
+		File::create(expired_filename.clone()).unwrap().write_all(contents.as_bytes()).unwrap();
+		let expired = remote_config::sign(contents, pair.secret(), now - 7200, now - 3600).unwrap();
+		File::create(expired_filename.clone() + ".sig").unwrap().write_all(expired.as_bytes()).unwrap();
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--light-checkpoint", &filename]);
+		let conf2 = parse(&["parity", "--light-checkpoint", &filename, "--light-checkpoint-key", &format!("{:?}", pair.public())]);
+		let conf3 = parse(&["parity", "--light-checkpoint", &filename, "--light-checkpoint-key", &format!("{:?}", other.public())]);
+		let conf4 = parse(&["parity", "--light-checkpoint", &filename, "--no-light-checkpoint"]);
+		let conf5 = parse(&["parity", "--light-checkpoint-key", &format!("{:?}", pair.public())]);
+		let conf6 = parse(&["parity", "--light-checkpoint", &expired_filename, "--light-checkpoint-key", &format!("{:?}", pair.public())]);
+
+		// then
+		let expected = LightCheckpointConfig::Custom(LightCheckpoint {
+			header: ::ethcore::encoded::Header::new(vec![0xc0]),
+			total_difficulty: 16.into(),
+			chts: vec![1.into()],
+		});
+		assert_eq!(conf0.light_checkpoint().unwrap(), LightCheckpointConfig::Spec);
+		assert_eq!(conf1.light_checkpoint().unwrap(), expected);
+		assert_eq!(conf2.light_checkpoint().unwrap(), expected);
+		assert!(conf3.light_checkpoint().is_err());
+		assert_eq!(conf4.light_checkpoint().unwrap(), LightCheckpointConfig::Disabled);
+		assert!(conf5.light_checkpoint().is_err());
+		assert!(conf6.light_checkpoint().is_err());
+	}
+
 	#[test]
 	fn test_dev_chain() {
 		let args = vec!["parity", "--chain", "dev"];
@@ -0,0 +1,384 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Receipts, traces and transfers tables in CSV format.
+//!
+//! Columns are described by a JSON schema file with a version, which is increased
+//! whenever the columns change. Values never contain separators or quotes:
+//! numbers are decimal, hashes, addresses and bytes are 0x-prefixed hex.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use util::{H256, U256, Address, ToPretty};
+use ethcore::receipt::LocalizedReceipt;
+use ethcore::trace::LocalizedTrace;
+use ethcore::trace::trace::{Action, Res};
+use ethcore::executed::CallType;
+
+/// Exported table.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Table {
+	Receipts,
+	Traces,
+	/// Value movements of the address.
+	Transfers(Address),
+}
+
+const RECEIPTS_COLUMNS: &'static [(&'static str, &'static str)] = &[
+	("block_number", "uint64"),
+	("block_hash", "hash"),
+	("transaction_index", "uint64"),
+	("transaction_hash", "hash"),
+	("gas_used", "uint256"),
+	("cumulative_gas_used", "uint256"),
+	("contract_address", "address"),
+	("state_root", "hash"),
+	("log_count", "uint64"),
+];
+
+const TRACES_COLUMNS: &'static [(&'static str, &'static str)] = &[
+	("block_number", "uint64"),
+	("block_hash", "hash"),
+	("transaction_index", "uint64"),
+	("transaction_hash", "hash"),
+	("trace_address", "string"),
+	("subtraces", "uint64"),
+	("type", "string"),
+	("call_type", "string"),
+	("from", "address"),
+	("to", "address"),
+	("value", "uint256"),
+	("gas", "uint256"),
+	("input", "bytes"),
+	("gas_used", "uint256"),
+	("output", "bytes"),
+	("error", "string"),
+];
+
+const TRANSFERS_COLUMNS: &'static [(&'static str, &'static str)] = &[
+	("block_number", "uint64"),
+	("transaction_hash", "hash"),
+	("trace_address", "string"),
+	("kind", "string"),
+	("counterparty", "address"),
+	("value_in", "uint256"),
+	("value_out", "uint256"),
+	("balance", "uint256"),
+];
+
+impl Table {
+	/// Name of the table.
+	pub fn name(&self) -> &'static str {
+		match *self {
+			Table::Receipts => "receipts",
+			Table::Traces => "traces",
+			Table::Transfers(_) => "transfers",
+		}
+	}
+
+	/// Version of the columns.
+	pub fn version(&self) -> u32 {
+		match *self {
+			Table::Receipts => 1,
+			Table::Traces => 1,
+			Table::Transfers(_) => 1,
+		}
+	}
+
+	fn columns(&self) -> &'static [(&'static str, &'static str)] {
+		match *self {
+			Table::Receipts => RECEIPTS_COLUMNS,
+			Table::Traces => TRACES_COLUMNS,
+			Table::Transfers(_) => TRANSFERS_COLUMNS,
+		}
+	}
+
+	/// CSV header line.
+	pub fn header(&self) -> String {
+		self.columns().iter().map(|&(name, _)| name).collect::<Vec<_>>().join(",")
+	}
+
+	/// JSON description of the columns.
+	pub fn schema(&self) -> String {
+		let columns = self.columns().iter()
+			.map(|&(name, ty)| format!("{{\"name\":\"{}\",\"type\":\"{}\"}}", name, ty))
+			.collect::<Vec<_>>()
+			.join(",");
+		format!("{{\"table\":\"{}\",\"version\":{},\"columns\":[{}]}}\n", self.name(), self.version(), columns)
+	}
+}
+
+fn hash(h: &H256) -> String {
+	format!("0x{}", h.hex())
+}
+
+fn bytes(b: &[u8]) -> String {
+	format!("0x{}", b.to_hex())
+}
+
+/// CSV line of the receipt.
+pub fn receipt_row(r: &LocalizedReceipt) -> String {
+	[
+		r.block_number.to_string(),
+		hash(&r.block_hash),
+		r.transaction_index.to_string(),
+		hash(&r.transaction_hash),
+		r.gas_used.to_string(),
+		r.cumulative_gas_used.to_string(),
+		r.contract_address.map_or_else(String::new, |a| format!("0x{}", a.hex())),
+		r.state_root.as_ref().map_or_else(String::new, hash),
+		r.logs.len().to_string(),
+	].join(",")
+}
+
+/// CSV line of the trace.
+pub fn trace_row(t: &LocalizedTrace) -> String {
+	let (kind, call_type, from, to, value, gas, input) = match t.action {
+		Action::Call(ref call) => {
+			let call_type = match call.call_type {
+				CallType::None => "",
+				CallType::Call => "call",
+				CallType::CallCode => "callcode",
+				CallType::DelegateCall => "delegatecall",
+			};
+			("call", call_type, call.from, Some(call.to), call.value.to_string(), call.gas.to_string(), bytes(&call.input))
+		},
+		Action::Create(ref create) => ("create", "", create.from, None, create.value.to_string(), create.gas.to_string(), bytes(&create.init)),
+		Action::Suicide(ref suicide) => ("suicide", "", suicide.address, Some(suicide.refund_address), suicide.balance.to_string(), String::new(), String::new()),
+	};
+	let (to, gas_used, output, error) = match t.result {
+		Res::Call(ref result) => (to, result.gas_used.to_string(), bytes(&result.output), String::new()),
+		// created contract goes to the `to` column.
+		Res::Create(ref result) => (Some(result.address), result.gas_used.to_string(), bytes(&result.code), String::new()),
+		Res::FailedCall(ref e) | Res::FailedCreate(ref e) => (to, String::new(), String::new(), e.to_string()),
+		Res::None => (to, String::new(), String::new(), String::new()),
+	};
+
+	[
+		t.block_number.to_string(),
+		hash(&t.block_hash),
+		t.transaction_number.to_string(),
+		hash(&t.transaction_hash),
+		t.trace_address.iter().map(|i| i.to_string()).collect::<Vec<_>>().join("-"),
+		t.subtraces.to_string(),
+		kind.into(),
+		call_type.into(),
+		format!("0x{}", from.hex()),
+		to.map_or_else(String::new, |a| format!("0x{}", a.hex())),
+		value,
+		gas,
+		input,
+		gas_used,
+		output,
+		error,
+	].join(",")
+}
+
+fn is_failed(result: &Res) -> bool {
+	match *result {
+		Res::FailedCall(_) | Res::FailedCreate(_) => true,
+		_ => false,
+	}
+}
+
+/// Running balance of an address, turning its value movements into CSV lines.
+pub struct Ledger {
+	address: Address,
+	balance: U256,
+}
+
+impl Ledger {
+	/// Start with the balance before the first exported block.
+	pub fn new(address: Address, balance: U256) -> Self {
+		Ledger {
+			address: address,
+			balance: balance,
+		}
+	}
+
+	/// Accounted address.
+	pub fn address(&self) -> &Address {
+		&self.address
+	}
+
+	fn entry(&mut self, block_number: u64, transaction: &str, trace_address: &str, kind: &str, counterparty: &str, value_in: U256, value_out: U256) -> String {
+		self.balance = self.balance + value_in;
+		self.balance = if self.balance > value_out { self.balance - value_out } else { U256::zero() };
+		[
+			block_number.to_string(),
+			transaction.into(),
+			trace_address.into(),
+			kind.into(),
+			counterparty.into(),
+			value_in.to_string(),
+			value_out.to_string(),
+			self.balance.to_string(),
+		].join(",")
+	}
+
+	/// Lines of value movements in the block, given all its traces.
+	/// `fees` are transaction fees paid by the address, which go to the block `author`.
+	/// Calls and creations reverted by a failure of theirs or of a parent call don't move any value.
+	pub fn block_rows(&mut self, traces: &[LocalizedTrace], fees: &HashMap<H256, U256>, author: &Address) -> Vec<String> {
+		let failed: HashSet<(usize, &[usize])> = traces.iter()
+			.filter(|t| is_failed(&t.result))
+			.map(|t| (t.transaction_number, &t.trace_address[..]))
+			.collect();
+
+		let mut rows = Vec::new();
+		for t in traces {
+			let transaction = hash(&t.transaction_hash);
+			let trace_address = t.trace_address.iter().map(|i| i.to_string()).collect::<Vec<_>>().join("-");
+			if t.trace_address.is_empty() {
+				if let Some(fee) = fees.get(&t.transaction_hash) {
+					let author = format!("0x{}", author.hex());
+					rows.push(self.entry(t.block_number, &transaction, &trace_address, "fee", &author, U256::zero(), *fee));
+				}
+			}
+
+			let reverted = (0..t.trace_address.len() + 1).any(|i| failed.contains(&(t.transaction_number, &t.trace_address[..i])));
+			if reverted {
+				continue;
+			}
+			let (kind, from, to, value) = match (&t.action, &t.result) {
+				// delegated calls execute code without moving value
+				(&Action::Call(ref call), _) if call.call_type == CallType::DelegateCall || call.call_type == CallType::CallCode => continue,
+				(&Action::Call(ref call), _) => ("call", call.from, call.to, call.value),
+				(&Action::Create(ref create), &Res::Create(ref result)) => ("create", create.from, result.address, create.value),
+				(&Action::Create(_), _) => continue,
+				(&Action::Suicide(ref suicide), _) => ("suicide", suicide.address, suicide.refund_address, suicide.balance),
+			};
+			let value_in = if to == self.address { value } else { U256::zero() };
+			let value_out = if from == self.address { value } else { U256::zero() };
+			if value_in.is_zero() && value_out.is_zero() {
+				continue;
+			}
+			let counterparty = if from == self.address { to } else { from };
+			let counterparty = format!("0x{}", counterparty.hex());
+			rows.push(self.entry(t.block_number, &transaction, &trace_address, kind, &counterparty, value_in, value_out));
+		}
+		rows
+	}
+
+	/// Line correcting the running balance to the balance in the state after the block,
+	/// accounting for value movements which aren't traced, like mining rewards.
+	pub fn reconcile(&mut self, block_number: u64, balance: U256) -> Option<String> {
+		match balance.cmp(&self.balance) {
+			Ordering::Equal => None,
+			Ordering::Greater => {
+				let value = balance - self.balance;
+				Some(self.entry(block_number, "", "", "other", "", value, U256::zero()))
+			},
+			Ordering::Less => {
+				let value = self.balance - balance;
+				Some(self.entry(block_number, "", "", "other", "", U256::zero(), value))
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use util::{H256, Address};
+	use ethcore::trace::LocalizedTrace;
+	use ethcore::trace::trace::{Action, Res, Call, CallResult};
+	use ethcore::trace::TraceError;
+	use ethcore::executed::CallType;
+	use super::{Table, Ledger, trace_row};
+
+	fn call(transaction: u64, trace_address: Vec<usize>, from: u64, to: u64, value: u64, failed: bool) -> LocalizedTrace {
+		LocalizedTrace {
+			action: Action::Call(Call {
+				from: Address::from(from),
+				to: Address::from(to),
+				value: value.into(),
+				gas: 21000.into(),
+				input: vec![],
+				call_type: CallType::Call,
+			}),
+			result: match failed {
+				true => Res::FailedCall(TraceError::OutOfGas),
+				false => Res::Call(CallResult { gas_used: 21000.into(), output: vec![] }),
+			},
+			subtraces: 0,
+			trace_address: trace_address,
+			transaction_number: transaction as usize,
+			transaction_hash: H256::from(transaction),
+			block_number: 7,
+			block_hash: H256::from(8),
+		}
+	}
+
+	#[test]
+	fn should_describe_columns() {
+		assert_eq!(Table::Receipts.header(), "block_number,block_hash,transaction_index,transaction_hash,gas_used,cumulative_gas_used,contract_address,state_root,log_count");
+		assert!(Table::Traces.schema().starts_with(r#"{"table":"traces","version":1,"columns":[{"name":"block_number","type":"uint64"},"#));
+	}
+
+	#[test]
+	fn should_format_trace() {
+		let trace = LocalizedTrace {
+			action: Action::Call(Call {
+				from: Address::from(1),
+				to: Address::from(2),
+				value: 1000.into(),
+				gas: 21000.into(),
+				input: vec![0xab],
+				call_type: CallType::DelegateCall,
+			}),
+			result: Res::Call(CallResult {
+				gas_used: 500.into(),
+				output: vec![],
+			}),
+			subtraces: 0,
+			trace_address: vec![0, 2],
+			transaction_number: 3,
+			transaction_hash: H256::from(4),
+			block_number: 5,
+			block_hash: H256::from(6),
+		};
+
+		let row = trace_row(&trace);
+		let columns: Vec<_> = row.split(',').collect();
+		assert_eq!(columns.len(), Table::Traces.header().split(',').count());
+		assert_eq!(&columns[4..], &["0-2", "0", "call", "delegatecall",
+			"0x0000000000000000000000000000000000000001", "0x0000000000000000000000000000000000000002",
+			"1000", "21000", "0xab", "500", "0x", ""]);
+	}
+
+	#[test]
+	fn should_account_transfers() {
+		let traces = vec![
+			call(0, vec![], 1, 2, 100, false),
+			// value sent by a call reverted by its parent
+			call(1, vec![], 3, 4, 0, true),
+			call(1, vec![0], 4, 1, 50, false),
+			call(2, vec![], 3, 1, 30, false),
+		];
+		let mut fees = HashMap::new();
+		fees.insert(H256::from(0), 21.into());
+		let mut ledger = Ledger::new(Address::from(1), 1000.into());
+
+		let rows = ledger.block_rows(&traces, &fees, &Address::from(9));
+		let balances: Vec<_> = rows.iter().map(|row| row.split(',').collect::<Vec<_>>()).map(|c| (c[3].to_owned(), c[7].to_owned())).collect();
+		assert_eq!(balances, vec![("fee".into(), "979".into()), ("call".into(), "879".into()), ("call".into(), "909".into())]);
+		assert!(rows[0].ends_with(",,fee,0x0000000000000000000000000000000000000009,0,21,979"));
+
+		assert_eq!(ledger.reconcile(7, 909.into()), None);
+		assert_eq!(ledger.reconcile(7, 1000.into()), Some("7,,,other,,91,0,1000".into()));
+	}
+}
@@ -0,0 +1,111 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prefunded, unlocked accounts of the dev chain.
+
+use std::str::FromStr;
+use ethcore::account_provider::AccountProvider;
+use ethcore::spec::Spec;
+use ethkey::{Brain, Generator, KeyPair};
+use util::U256;
+
+/// Balance of every account in ether if not given.
+const DEFAULT_BALANCE: u64 = 100;
+/// Number of wei in one ether.
+const WEI_PER_ETHER: u64 = 1_000_000_000_000_000_000;
+
+/// Accounts generated for the dev chain, given as `N[:BALANCE]` with the balance in ether.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DevAccounts {
+	/// Number of accounts.
+	pub count: usize,
+	/// Genesis balance of every account in wei.
+	pub balance: U256,
+}
+
+impl FromStr for DevAccounts {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = s.splitn(2, ':');
+		let count = parts.next().unwrap_or("").parse::<usize>()
+			.map_err(|_| format!("Invalid number of dev accounts: {}", s))?;
+		let balance = match parts.next() {
+			Some(balance) => U256::from_dec_str(balance).map_err(|_| format!("Invalid dev account balance: {}", balance))?,
+			None => DEFAULT_BALANCE.into(),
+		};
+		Ok(DevAccounts {
+			count: count,
+			balance: balance * WEI_PER_ETHER.into(),
+		})
+	}
+}
+
+impl DevAccounts {
+	/// Key pairs of the accounts. They are derived from their index, so the dev chain
+	/// keeps its genesis and database across restarts.
+	pub fn key_pairs(&self) -> Vec<KeyPair> {
+		(0..self.count)
+			.map(|i| Brain::new(format!("parity dev account {}", i)).generate().expect("Brain wallet generator is infallible; qed"))
+			.collect()
+	}
+
+	/// Credit the accounts with their balance in the genesis of the spec.
+	pub fn fund(&self, spec: &mut Spec) {
+		let balance = self.balance;
+		spec.fund_genesis_accounts(self.key_pairs().into_iter().map(|pair| (pair.address(), balance)));
+	}
+
+	/// Import the accounts with an empty password, unlock them permanently and print their keys.
+	pub fn unlock(&self, account_provider: &AccountProvider) -> Result<(), String> {
+		for (i, pair) in self.key_pairs().into_iter().enumerate() {
+			let address = pair.address();
+			if !account_provider.has_account(address).unwrap_or(false) {
+				account_provider.insert_account(pair.secret().clone(), "")
+					.map_err(|e| format!("Could not import dev account {:?}: {}", address, e))?;
+			}
+			account_provider.unlock_account_permanently(address, String::new())
+				.map_err(|e| format!("Could not unlock dev account {:?}: {}", address, e))?;
+			info!("Dev account #{}: 0x{:?} (secret key: 0x{:?})", i, address, **pair.secret());
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::U256;
+	use super::DevAccounts;
+
+	#[test]
+	fn should_parse_dev_accounts() {
+		let ether = U256::from(1_000_000_000_000_000_000u64);
+		assert_eq!("3".parse(), Ok(DevAccounts { count: 3, balance: ether * 100.into() }));
+		assert_eq!("10:5000".parse(), Ok(DevAccounts { count: 10, balance: ether * 5000.into() }));
+		assert!("".parse::<DevAccounts>().is_err());
+		assert!("3:lots".parse::<DevAccounts>().is_err());
+	}
+
+	#[test]
+	fn should_derive_stable_accounts() {
+		let accounts = DevAccounts { count: 2, balance: 0.into() };
+		let first = accounts.key_pairs();
+		let second = accounts.key_pairs();
+		assert_eq!(first.len(), 2);
+		assert_eq!(first[0].address(), second[0].address());
+		assert!(first[0].address() != first[1].address());
+	}
+}
@@ -198,6 +198,13 @@ impl DatabaseDirectories {
 		dir
 	}
 
+	/// Get the path of the last block published to the event sink.
+	pub fn event_cursor_path(&self) -> PathBuf {
+		let mut dir = self.spec_root_path();
+		dir.push("event_cursor");
+		dir
+	}
+
 	/// Get the path for the snapshot directory given the genesis hash and fork name.
 	pub fn snapshot_path(&self) -> PathBuf {
 		let mut dir = self.db_root_path();
@@ -211,6 +218,20 @@ impl DatabaseDirectories {
 		dir.push("network");
 		dir
 	}
+
+	/// Get the path for the light client database in hybrid mode, next to the full node's.
+	pub fn hybrid_light_path(&self) -> PathBuf {
+		let mut dir = self.db_root_path();
+		dir.push("hybrid-light");
+		dir
+	}
+
+	/// Get the path for the network directory of the background full node in hybrid mode.
+	pub fn hybrid_network_path(&self) -> PathBuf {
+		let mut dir = self.network_path();
+		dir.push("hybrid");
+		dir
+	}
 }
 
 pub fn default_data_path() -> String {
@@ -0,0 +1,446 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Publishing of chain events to a NATS server.
+//!
+//! For every block of the canonical chain the header is published to `<prefix>.heads`,
+//! followed by its transactions to `<prefix>.transactions` and matching logs to `<prefix>.logs`,
+//! in the JSON-RPC format. Retracted blocks are announced on `<prefix>.reorgs` before the new ones.
+//!
+//! Delivery is at most once: NATS doesn't store messages, subscribers which are not connected
+//! when a block is published never receive its events. The last block processed by the server
+//! is stored in a cursor file and publishing continues after it on reconnect or restart,
+//! so no block is skipped by the publisher itself. Use `--event-replay-from` to publish
+//! the events of earlier blocks again.
+
+use std::{io, fs};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Weak, mpsc};
+use std::thread;
+use std::time::Duration;
+use serde_json::Value;
+use serde_json::ser::to_string;
+use ethcore::client::{BlockChainClient, BlockId, ChainNotify};
+use parity_rpc::v1::types::{Header, Transaction, Log, Bytes as RpcBytes};
+use util::{H256, Address, Bytes, Mutex};
+
+/// Delay before reconnecting after a failure.
+const RETRY_DELAY_SECS: u64 = 5;
+
+/// Timeout of reads and writes on the server connection.
+const IO_TIMEOUT_SECS: u64 = 30;
+
+const SERIALIZE_PROOF: &'static str = "serialization of RPC types and JSON values does not fail; qed";
+
+/// Event publisher configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventPublisherConfig {
+	/// Address of the NATS server, `host:port`.
+	pub address: String,
+	/// Prefix of the subjects events are published to.
+	pub subject_prefix: String,
+	/// Publish only logs of these contracts, all logs if empty.
+	pub log_addresses: Vec<Address>,
+	/// Publish again starting at this block, ignoring the cursor.
+	pub replay_from: Option<u64>,
+}
+
+/// Last published block.
+#[derive(Debug, PartialEq)]
+struct Cursor {
+	number: u64,
+	hash: H256,
+}
+
+/// Cursor file.
+struct CursorFile {
+	path: PathBuf,
+}
+
+impl CursorFile {
+	fn load(&self) -> Result<Option<Cursor>, String> {
+		let mut content = String::new();
+		match fs::File::open(&self.path) {
+			Ok(mut file) => file.read_to_string(&mut content).map_err(|e| format!("Cannot read {}: {}", self.path.display(), e))?,
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(format!("Cannot read {}: {}", self.path.display(), e)),
+		};
+		let mut parts = content.split_whitespace();
+		match (parts.next().map(u64::from_str), parts.next().map(H256::from_str)) {
+			(Some(Ok(number)), Some(Ok(hash))) => Ok(Some(Cursor { number: number, hash: hash })),
+			_ => Err(format!("Invalid event cursor file: {}", self.path.display())),
+		}
+	}
+
+	fn save(&self, cursor: &Cursor) -> Result<(), String> {
+		fs::File::create(&self.path)
+			.and_then(|mut f| writeln!(f, "{} {}", cursor.number, cursor.hash.hex()).and_then(|_| f.sync_all()))
+			.map_err(|e| format!("Cannot write {}: {}", self.path.display(), e))
+	}
+}
+
+/// Connection to a NATS server.
+struct Connection<S> {
+	stream: BufReader<S>,
+	pending: Vec<u8>,
+}
+
+impl<S: Read + Write> Connection<S> {
+	/// Read the server info and identify ourselves.
+	fn new(stream: S) -> Result<Self, String> {
+		let mut connection = Connection {
+			stream: BufReader::new(stream),
+			pending: Vec::new(),
+		};
+		let info = connection.read_line()?;
+		if !info.starts_with("INFO ") {
+			return Err(format!("Unexpected greeting: {}", info));
+		}
+		connection.stream.get_mut().write_all(b"CONNECT {\"verbose\":false,\"pedantic\":false,\"name\":\"parity\"}\r\n")
+			.map_err(|e| format!("Cannot write to the server: {}", e))?;
+		Ok(connection)
+	}
+
+	fn read_line(&mut self) -> Result<String, String> {
+		let mut line = String::new();
+		match self.stream.read_line(&mut line) {
+			Ok(0) => Err("Connection closed by the server".into()),
+			Ok(_) => Ok(line.trim_right().to_owned()),
+			Err(e) => Err(format!("Cannot read from the server: {}", e)),
+		}
+	}
+
+	/// Queue a message, it's sent on `flush`.
+	fn publish(&mut self, subject: &str, payload: &str) {
+		self.pending.extend_from_slice(format!("PUB {} {}\r\n{}\r\n", subject, payload.len(), payload).as_bytes());
+	}
+
+	/// Send queued messages and wait until the server processed them.
+	/// The server doesn't confirm that the messages reached any subscriber.
+	fn flush(&mut self) -> Result<(), String> {
+		self.pending.extend_from_slice(b"PING\r\n");
+		let pending = ::std::mem::replace(&mut self.pending, Vec::new());
+		self.stream.get_mut().write_all(&pending).map_err(|e| format!("Cannot write to the server: {}", e))?;
+		loop {
+			let line = self.read_line()?;
+			match line.as_str() {
+				"PONG" => return Ok(()),
+				"PING" => self.stream.get_mut().write_all(b"PONG\r\n").map_err(|e| format!("Cannot write to the server: {}", e))?,
+				_ if line.starts_with("-ERR") => return Err(format!("Server error: {}", &line[4..].trim())),
+				// +OK and INFO updates
+				_ => {},
+			}
+		}
+	}
+}
+
+/// Chain notification handler waking up the publisher.
+pub struct EventPublisher {
+	sender: Mutex<mpsc::Sender<()>>,
+}
+
+impl EventPublisher {
+	/// Start the publisher thread. Blocks after the cursor are published first.
+	pub fn start(config: EventPublisherConfig, cursor_path: PathBuf, client: Weak<BlockChainClient>) -> Result<Arc<Self>, String> {
+		let (sender, receiver) = mpsc::channel();
+		let eip86_transition = client.upgrade().map_or(u64::max_value(), |client| client.eip86_transition());
+		let worker = Worker {
+			replay_from: config.replay_from,
+			config: config,
+			cursor: CursorFile { path: cursor_path },
+			client: client,
+			eip86_transition: eip86_transition,
+			connection: None,
+		};
+		thread::Builder::new()
+			.name("event-publisher".into())
+			.spawn(move || worker.run(receiver))
+			.map_err(|e| format!("Unable to start event publisher: {}", e))?;
+
+		Ok(Arc::new(EventPublisher {
+			sender: Mutex::new(sender),
+		}))
+	}
+}
+
+impl ChainNotify for EventPublisher {
+	fn new_blocks(&self, _imported: Vec<H256>, _invalid: Vec<H256>, enacted: Vec<H256>, retracted: Vec<H256>, _sealed: Vec<H256>, _proposed: Vec<Bytes>, _duration: u64) {
+		if enacted.is_empty() && retracted.is_empty() {
+			return;
+		}
+		let _ = self.sender.lock().send(());
+	}
+}
+
+struct Worker {
+	config: EventPublisherConfig,
+	replay_from: Option<u64>,
+	cursor: CursorFile,
+	client: Weak<BlockChainClient>,
+	eip86_transition: u64,
+	connection: Option<Connection<TcpStream>>,
+}
+
+impl Worker {
+	fn run(mut self, receiver: mpsc::Receiver<()>) {
+		loop {
+			match self.publish_pending() {
+				Ok(true) => {},
+				Ok(false) => return,
+				Err(e) => {
+					warn!(target: "events", "Publishing chain events failed, retrying in {}s: {}", RETRY_DELAY_SECS, e);
+					self.connection = None;
+					thread::sleep(Duration::from_secs(RETRY_DELAY_SECS));
+					continue;
+				},
+			}
+			if receiver.recv().is_err() {
+				return;
+			}
+			// one pass publishes all blocks imported so far
+			while receiver.try_recv().is_ok() {}
+		}
+	}
+
+	fn connection(&mut self) -> Result<&mut Connection<TcpStream>, String> {
+		if self.connection.is_none() {
+			let stream = TcpStream::connect(self.config.address.as_str())
+				.and_then(|s| s.set_read_timeout(Some(Duration::from_secs(IO_TIMEOUT_SECS))).map(|_| s))
+				.and_then(|s| s.set_write_timeout(Some(Duration::from_secs(IO_TIMEOUT_SECS))).map(|_| s))
+				.map_err(|e| format!("Cannot connect to {}: {}", self.config.address, e))?;
+			self.connection = Some(Connection::new(stream)?);
+			info!(target: "events", "Publishing chain events to {}", self.config.address);
+		}
+		Ok(self.connection.as_mut().expect("created above; qed"))
+	}
+
+	fn subject(&self, name: &str) -> String {
+		format!("{}.{}", self.config.subject_prefix, name)
+	}
+
+	/// Publish events of the blocks after the cursor up to the best block.
+	/// Returns `false` if the client was shut down.
+	fn publish_pending(&mut self) -> Result<bool, String> {
+		'chain: loop {
+			let client = match self.client.upgrade() {
+				Some(client) => client,
+				None => return Ok(false),
+			};
+			let chain_info = client.chain_info();
+			let (mut last, next) = match (self.replay_from, self.cursor.load()?) {
+				(Some(number), _) => (None, number),
+				(None, Some(cursor)) => {
+					let route = client.tree_route(&cursor.hash, &chain_info.best_block_hash)
+						.ok_or_else(|| format!("Unknown block {} in {}, remove the file to start with the next block", cursor.hash, self.cursor.path.display()))?;
+					let ancestor = Cursor { number: cursor.number - route.index as u64, hash: route.ancestor };
+					if route.index > 0 {
+						let reorg = reorg_event(&route.blocks[..route.index], &ancestor);
+						let subject = self.subject("reorgs");
+						{
+							let connection = self.connection()?;
+							connection.publish(&subject, &reorg);
+							connection.flush()?;
+						}
+						self.cursor.save(&ancestor)?;
+					}
+					(Some(ancestor.hash), ancestor.number + 1)
+				},
+				(None, None) => {
+					// nothing published yet, start with the next block
+					self.cursor.save(&Cursor { number: chain_info.best_block_number, hash: chain_info.best_block_hash })?;
+					return Ok(true);
+				},
+			};
+			drop(client);
+
+			for number in next..chain_info.best_block_number + 1 {
+				let events = match self.client.upgrade() {
+					Some(client) => self.block_events(&*client, number),
+					None => return Ok(false),
+				};
+				let events = match events {
+					Some(events) => events,
+					None => continue 'chain,
+				};
+				if last.map_or(false, |hash| hash != events.parent_hash) {
+					// reorganised in the meantime
+					continue 'chain;
+				}
+
+				let subjects = (self.subject("heads"), self.subject("transactions"), self.subject("logs"));
+				{
+					let connection = self.connection()?;
+					connection.publish(&subjects.0, &events.head);
+					for tx in &events.transactions {
+						connection.publish(&subjects.1, tx);
+					}
+					for log in &events.logs {
+						connection.publish(&subjects.2, log);
+					}
+					connection.flush()?;
+				}
+				self.cursor.save(&Cursor { number: number, hash: events.hash })?;
+				self.replay_from = None;
+				last = Some(events.hash);
+			}
+			return Ok(true);
+		}
+	}
+
+	fn block_events(&self, client: &BlockChainClient, number: u64) -> Option<BlockEvents> {
+		let id = BlockId::Number(number);
+		let (block, receipts) = match (client.block(id), client.localized_block_receipts(id)) {
+			(Some(block), Some(receipts)) => (block, receipts),
+			_ => return None,
+		};
+		let view = block.header_view();
+		let head = Header {
+			hash: Some(view.hash().into()),
+			size: Some(block.rlp().as_raw().len().into()),
+			parent_hash: view.parent_hash().into(),
+			uncles_hash: view.uncles_hash().into(),
+			author: view.author().into(),
+			miner: view.author().into(),
+			state_root: view.state_root().into(),
+			transactions_root: view.transactions_root().into(),
+			receipts_root: view.receipts_root().into(),
+			number: Some(view.number().into()),
+			gas_used: view.gas_used().into(),
+			gas_limit: view.gas_limit().into(),
+			logs_bloom: view.log_bloom().into(),
+			timestamp: view.timestamp().into(),
+			difficulty: view.difficulty().into(),
+			seal_fields: view.seal().into_iter().map(Into::into).collect(),
+			extra_data: RpcBytes::new(view.extra_data()),
+		};
+
+		Some(BlockEvents {
+			hash: view.hash(),
+			parent_hash: view.parent_hash(),
+			head: to_string(&head).expect(SERIALIZE_PROOF),
+			transactions: block.view().localized_transactions().into_iter()
+				.map(|t| to_string(&Transaction::from_localized(t, self.eip86_transition)).expect(SERIALIZE_PROOF))
+				.collect(),
+			logs: receipts.into_iter()
+				.flat_map(|r| r.logs.into_iter())
+				.filter(|log| self.config.log_addresses.is_empty() || self.config.log_addresses.contains(&log.entry.address))
+				.map(|log| to_string(&Log::from(log)).expect(SERIALIZE_PROOF))
+				.collect(),
+		})
+	}
+}
+
+/// Serialized events of a single block.
+struct BlockEvents {
+	hash: H256,
+	parent_hash: H256,
+	head: String,
+	transactions: Vec<String>,
+	logs: Vec<String>,
+}
+
+fn reorg_event(retracted: &[H256], ancestor: &Cursor) -> String {
+	let mut map: BTreeMap<String, Value> = BTreeMap::new();
+	map.insert("retracted".into(), Value::Array(retracted.iter().map(|h| Value::String(format!("0x{}", h.hex()))).collect()));
+	map.insert("ancestorHash".into(), Value::String(format!("0x{}", ancestor.hash.hex())));
+	map.insert("ancestorNumber".into(), Value::String(format!("{:#x}", ancestor.number)));
+	to_string(&map).expect(SERIALIZE_PROOF)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::{self, Read, Write, Cursor as IoCursor};
+	use devtools::RandomTempPath;
+	use util::H256;
+	use super::{Connection, Cursor, CursorFile, reorg_event};
+
+	struct MockStream {
+		input: IoCursor<Vec<u8>>,
+		output: Vec<u8>,
+	}
+
+	impl MockStream {
+		fn new(input: &str) -> Self {
+			MockStream {
+				input: IoCursor::new(input.as_bytes().to_vec()),
+				output: Vec::new(),
+			}
+		}
+	}
+
+	impl Read for MockStream {
+		fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+			self.input.read(buf)
+		}
+	}
+
+	impl Write for MockStream {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.output.write(buf)
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn should_publish_and_wait_for_ack() {
+		let stream = MockStream::new("INFO {\"server_id\":\"a\"}\r\n+OK\r\nPING\r\nPONG\r\n");
+		let mut connection = Connection::new(stream).unwrap();
+		connection.publish("parity.heads", "{}");
+		connection.flush().unwrap();
+
+		let output = String::from_utf8(connection.stream.get_ref().output.clone()).unwrap();
+		assert_eq!(output, "CONNECT {\"verbose\":false,\"pedantic\":false,\"name\":\"parity\"}\r\n\
+			PUB parity.heads 2\r\n{}\r\nPING\r\nPONG\r\n");
+	}
+
+	#[test]
+	fn should_fail_on_server_error() {
+		let stream = MockStream::new("INFO {}\r\n-ERR 'Maximum Payload Exceeded'\r\n");
+		let mut connection = Connection::new(stream).unwrap();
+		connection.publish("parity.heads", "{}");
+		assert_eq!(connection.flush(), Err("Server error: 'Maximum Payload Exceeded'".into()));
+
+		let stream = MockStream::new("INFO {}\r\n");
+		let mut connection = Connection::new(stream).unwrap();
+		assert!(connection.flush().is_err());
+	}
+
+	#[test]
+	fn should_save_and_load_cursor() {
+		let temp = RandomTempPath::new();
+		let file = CursorFile { path: temp.as_path().clone() };
+		assert_eq!(file.load().unwrap(), None);
+
+		let cursor = Cursor { number: 100, hash: H256::from(5) };
+		file.save(&cursor).unwrap();
+		assert_eq!(file.load().unwrap(), Some(cursor));
+	}
+
+	#[test]
+	fn should_describe_reorg() {
+		let event = reorg_event(&[H256::from(2)], &Cursor { number: 26, hash: H256::from(1) });
+		assert_eq!(event, "{\"ancestorHash\":\"0x0000000000000000000000000000000000000000000000000000000000000001\",\
+			\"ancestorNumber\":\"0x1a\",\
+			\"retracted\":[\"0x0000000000000000000000000000000000000000000000000000000000000002\"]}");
+	}
+}
@@ -0,0 +1,388 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Translation of Geth command line flags and Geth configuration files to Parity options.
+//!
+//! Flags only known to Geth are replaced by their Parity equivalents before the
+//! arguments are parsed. Options of the Geth configuration file given with
+//! `--geth-compat-config` are appended, unless the command line sets them already.
+
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use toml;
+
+/// Flag giving the Geth configuration file.
+const CONFIG_FLAG: &'static str = "--geth-compat-config";
+
+/// Translation of a single Geth option.
+enum Rule {
+	/// Option with a value renamed to the Parity flag.
+	Rename(&'static str),
+	/// Switch replaced by the Parity arguments.
+	Switch(&'static [&'static str]),
+	/// Value converted to Parity arguments.
+	Convert(fn(&str) -> Result<Vec<String>, String>),
+	/// Option without an equivalent, `true` if it takes a value.
+	Ignore(bool, &'static str),
+}
+
+impl Rule {
+	fn takes_value(&self) -> bool {
+		match *self {
+			Rule::Rename(_) | Rule::Convert(_) => true,
+			Rule::Switch(_) => false,
+			Rule::Ignore(takes_value, _) => takes_value,
+		}
+	}
+}
+
+/// Geth flags unknown to Parity. Flags with the same name in both clients are left untouched.
+const FLAGS: &'static [(&'static str, Rule)] = &[
+	("maxpeers", Rule::Rename("--max-peers")),
+	("syncmode", Rule::Convert(sync_mode)),
+	("fast", Rule::Ignore(false, "Parity uses warp sync by default.")),
+	("dev", Rule::Switch(&["--chain", "dev"])),
+	("rinkeby", Rule::Ignore(false, "Rinkeby is not built in, use --chain with its chain specification.")),
+	("keystore", Rule::Rename("--keys-path")),
+	("verbosity", Rule::Convert(verbosity)),
+	("rpcvhosts", Rule::Rename("--jsonrpc-hosts")),
+	("ws", Rule::Ignore(false, "The WebSockets server is on by default.")),
+	("wsaddr", Rule::Rename("--ws-interface")),
+	("wsport", Rule::Rename("--ws-port")),
+	("wsapi", Rule::Rename("--ws-apis")),
+	("wsorigins", Rule::Rename("--ws-origins")),
+	("mine", Rule::Switch(&["--force-sealing"])),
+	("minerthreads", Rule::Ignore(true, "Parity doesn't mine on the CPU, use an external miner.")),
+	("targetgaslimit", Rule::Rename("--gas-floor-target")),
+	("lightserv", Rule::Ignore(true, "Light clients are served by default.")),
+	("ethstats", Rule::Ignore(true, "Reporting to ethstats is not supported.")),
+];
+
+/// Options of the Geth configuration file (as written by `geth dumpconfig`) by section.
+const CONFIG_KEYS: &'static [(&'static str, &'static str, Rule)] = &[
+	("Eth", "NetworkId", Rule::Convert(network_id)),
+	("Eth", "SyncMode", Rule::Convert(sync_mode)),
+	("Eth", "DatabaseCache", Rule::Rename("--cache-size")),
+	("Eth", "Etherbase", Rule::Rename("--author")),
+	("Eth", "GasPrice", Rule::Rename("--gasprice")),
+	("Node", "DataDir", Rule::Rename("--base-path")),
+	("Node", "IPCPath", Rule::Rename("--ipc-path")),
+	("Node", "KeyStoreDir", Rule::Rename("--keys-path")),
+	("Node", "UserIdent", Rule::Rename("--identity")),
+	("Node", "HTTPHost", Rule::Rename("--jsonrpc-interface")),
+	("Node", "HTTPPort", Rule::Rename("--jsonrpc-port")),
+	("Node", "HTTPCors", Rule::Rename("--jsonrpc-cors")),
+	("Node", "HTTPVirtualHosts", Rule::Rename("--jsonrpc-hosts")),
+	("Node", "HTTPModules", Rule::Rename("--jsonrpc-apis")),
+	("Node", "WSHost", Rule::Rename("--ws-interface")),
+	("Node", "WSPort", Rule::Rename("--ws-port")),
+	("Node", "WSOrigins", Rule::Rename("--ws-origins")),
+	("Node", "WSModules", Rule::Rename("--ws-apis")),
+	("Node.P2P", "MaxPeers", Rule::Rename("--max-peers")),
+	("Node.P2P", "NoDiscovery", Rule::Switch(&["--no-discovery"])),
+	("Node.P2P", "BootstrapNodes", Rule::Rename("--bootnodes")),
+	("Node.P2P", "ListenAddr", Rule::Convert(listen_addr)),
+];
+
+fn sync_mode(mode: &str) -> Result<Vec<String>, String> {
+	match mode {
+		"full" => Ok(vec!["--no-warp".into()]),
+		// warp sync is the default
+		"fast" => Ok(vec![]),
+		"light" => Ok(vec!["--light".into()]),
+		other => Err(format!("Invalid Geth sync mode: {}", other)),
+	}
+}
+
+fn verbosity(level: &str) -> Result<Vec<String>, String> {
+	let logging = match level {
+		"0" | "1" => "error",
+		"2" => "warn",
+		"3" => "info",
+		"4" => "debug",
+		"5" => "trace",
+		other => return Err(format!("Invalid Geth verbosity: {}", other)),
+	};
+	Ok(vec!["--logging".into(), logging.into()])
+}
+
+fn network_id(id: &str) -> Result<Vec<String>, String> {
+	let chain = match id {
+		"1" => "foundation",
+		"3" => "ropsten",
+		"4" => "rinkeby",
+		"5" => "goerli",
+		"42" => "kovan",
+		other => return Ok(vec!["--network-id".into(), other.into()]),
+	};
+	Ok(vec!["--chain".into(), chain.into()])
+}
+
+fn listen_addr(addr: &str) -> Result<Vec<String>, String> {
+	match addr.rfind(':').map(|i| &addr[i + 1..]) {
+		Some(port) if port.parse::<u16>().is_ok() => Ok(vec!["--port".into(), port.into()]),
+		_ => Err(format!("Invalid Geth listen address: {}", addr)),
+	}
+}
+
+fn apply(rule: &Rule, value: Option<&str>) -> Result<(Vec<String>, Option<&'static str>), String> {
+	match (rule, value) {
+		(&Rule::Rename(flag), Some(value)) => Ok((vec![flag.into(), value.into()], None)),
+		(&Rule::Switch(args), _) => Ok((args.iter().map(|arg| arg.to_string()).collect(), None)),
+		(&Rule::Convert(convert), Some(value)) => convert(value).map(|args| (args, None)),
+		(&Rule::Ignore(_, note), _) => Ok((vec![], Some(note))),
+		(_, None) => unreachable!("values of options taking a value are always given; qed"),
+	}
+}
+
+/// Geth option and its Parity equivalent.
+#[derive(Debug, PartialEq)]
+pub struct Translated {
+	/// Geth flag or configuration option with its value.
+	pub from: String,
+	/// Equivalent Parity arguments, empty if there's none.
+	pub to: Vec<String>,
+	/// Why the option was not translated.
+	pub note: Option<&'static str>,
+}
+
+impl fmt::Display for Translated {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match (self.to.is_empty(), self.note) {
+			(_, Some(note)) => write!(f, "{}: ignored. {}", self.from, note),
+			(true, None) => write!(f, "{}: nothing to do, that's the default of Parity.", self.from),
+			(false, None) => write!(f, "{} -> {}", self.from, self.to.join(" ")),
+		}
+	}
+}
+
+/// Replaces Geth flags of the command line by Parity flags.
+fn translate_flags(args: Vec<String>) -> Result<(Vec<String>, Vec<Translated>), String> {
+	let mut result = Vec::with_capacity(args.len());
+	let mut translated = Vec::new();
+	let mut args = args.into_iter();
+	// executable
+	result.extend(args.next());
+
+	while let Some(arg) = args.next() {
+		if arg == "--" {
+			result.push(arg);
+			result.extend(args.by_ref());
+			break;
+		}
+
+		let (name, inline_value) = match arg.find('=') {
+			Some(i) => (arg[..i].to_owned(), Some(arg[i + 1..].to_owned())),
+			None => (arg.clone(), None),
+		};
+		let rule = match FLAGS.iter().find(|&&(flag, _)| name.starts_with("--") && name[2..] == *flag) {
+			Some(&(_, ref rule)) => rule,
+			None => {
+				result.push(arg);
+				continue;
+			},
+		};
+
+		let value = match (rule.takes_value(), inline_value) {
+			(true, Some(value)) => Some(value),
+			(true, None) => Some(args.next().ok_or_else(|| format!("Geth flag {} requires a value", name))?),
+			(false, _) => None,
+		};
+		let (to, note) = apply(rule, value.as_ref().map(|v| v.as_str()))?;
+		result.extend(to.iter().cloned());
+		translated.push(Translated {
+			from: match value {
+				Some(value) => format!("{} {}", name, value),
+				None => name,
+			},
+			to: to,
+			note: note,
+		});
+	}
+
+	Ok((result, translated))
+}
+
+fn value_string(value: &toml::Value) -> Result<String, String> {
+	match *value {
+		toml::Value::String(ref s) => Ok(s.clone()),
+		toml::Value::Integer(i) => Ok(i.to_string()),
+		toml::Value::Boolean(b) => Ok(b.to_string()),
+		toml::Value::Array(ref values) => values.iter().map(value_string).collect::<Result<Vec<_>, _>>().map(|values| values.join(",")),
+		ref other => Err(format!("Unsupported value: {}", other)),
+	}
+}
+
+fn translate_table(section: &str, table: &toml::Table, translated: &mut Vec<Translated>) -> Result<(), String> {
+	for (key, value) in table {
+		if let toml::Value::Table(ref table) = *value {
+			let section = match section.is_empty() {
+				true => key.clone(),
+				false => format!("{}.{}", section, key),
+			};
+			translate_table(&section, table, translated)?;
+			continue;
+		}
+
+		let from = format!("[{}] {} = {}", section, key, value);
+		let rule = CONFIG_KEYS.iter().find(|&&(s, k, _)| s == section && k == key.as_str()).map(|&(_, _, ref rule)| rule);
+		let (to, note) = match (rule, value) {
+			(None, _) => (vec![], Some("No Parity equivalent.")),
+			// switches are only turned on
+			(Some(&Rule::Switch(_)), &toml::Value::Boolean(false)) => (vec![], None),
+			(Some(rule), value) => {
+				let value = value_string(value).map_err(|e| format!("{}: {}", from, e))?;
+				apply(rule, Some(&value)).map_err(|e| format!("{}: {}", from, e))?
+			},
+		};
+		translated.push(Translated {
+			from: from,
+			to: to,
+			note: note,
+		});
+	}
+	Ok(())
+}
+
+/// Translates options of the Geth configuration file.
+fn translate_config(config: &str) -> Result<Vec<Translated>, String> {
+	let mut parser = toml::Parser::new(config);
+	let table = parser.parse().ok_or_else(|| format!("Invalid Geth configuration file: {:?}", parser.errors))?;
+	let mut translated = Vec::new();
+	translate_table("", &table, &mut translated)?;
+	Ok(translated)
+}
+
+/// Value of `--geth-compat-config` in the arguments.
+fn config_path(args: &[String]) -> Option<String> {
+	let prefix = format!("{}=", CONFIG_FLAG);
+	args.iter().enumerate().filter_map(|(i, arg)| match *arg == CONFIG_FLAG {
+		true => args.get(i + 1).cloned(),
+		false if arg.starts_with(&prefix) => Some(arg[prefix.len()..].to_owned()),
+		false => None,
+	}).last()
+}
+
+/// Translates Geth flags of the command line and options of the Geth configuration file
+/// given with `--geth-compat-config`. Returns Parity arguments and the translation report.
+pub fn translate(args: Vec<String>) -> Result<(Vec<String>, Vec<Translated>), String> {
+	let (mut args, mut translated) = translate_flags(args)?;
+
+	let path = match config_path(&args) {
+		Some(path) => path,
+		None => return Ok((args, translated)),
+	};
+	let mut config = String::new();
+	File::open(&path).and_then(|mut file| file.read_to_string(&mut config))
+		.map_err(|e| format!("Error reading Geth configuration file {}: {}", path, e))?;
+
+	for mut option in translate_config(&config)? {
+		// the command line takes precedence
+		let overridden = option.to.first().map_or(false, |flag| {
+			let prefix = format!("{}=", flag);
+			args.iter().any(|arg| arg == flag || arg.starts_with(&prefix))
+		});
+		match overridden {
+			true => {
+				option.to.clear();
+				option.note = Some("Overridden by the command line.");
+			},
+			false => args.extend(option.to.iter().cloned()),
+		}
+		translated.push(option);
+	}
+
+	Ok((args, translated))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use std::io::Write;
+	use devtools::RandomTempPath;
+	use super::{translate, translate_config, Translated};
+
+	fn args(args: &[&str]) -> Vec<String> {
+		args.iter().map(|arg| arg.to_string()).collect()
+	}
+
+	#[test]
+	fn should_translate_geth_flags() {
+		// when
+		let (result, translated) = translate(args(&[
+			"parity", "--maxpeers", "50", "--syncmode=full", "--verbosity", "4", "--minerthreads", "2", "--port", "30304", "--mine",
+		])).unwrap();
+
+		// then
+		assert_eq!(result, args(&[
+			"parity", "--max-peers", "50", "--no-warp", "--logging", "debug", "--port", "30304", "--force-sealing",
+		]));
+		assert_eq!(translated.iter().map(|t| t.to_string()).collect::<Vec<_>>(), vec![
+			"--maxpeers 50 -> --max-peers 50",
+			"--syncmode full -> --no-warp",
+			"--verbosity 4 -> --logging debug",
+			"--minerthreads 2: ignored. Parity doesn't mine on the CPU, use an external miner.",
+			"--mine -> --force-sealing",
+		]);
+		assert!(translate(args(&["parity", "--maxpeers"])).is_err());
+		assert!(translate(args(&["parity", "--syncmode", "turbo"])).is_err());
+	}
+
+	#[test]
+	fn should_translate_geth_config() {
+		// when
+		let translated = translate_config(r#"
+[Eth]
+NetworkId = 3
+SyncMode = "light"
+
+[Node]
+HTTPModules = ["eth", "net", "web3"]
+
+[Node.P2P]
+NoDiscovery = false
+ListenAddr = ":30305"
+DialRatio = 3
+"#).unwrap();
+
+		// then
+		assert_eq!(translated, vec![
+			Translated { from: "[Eth] NetworkId = 3".into(), to: args(&["--chain", "ropsten"]), note: None },
+			Translated { from: "[Eth] SyncMode = \"light\"".into(), to: args(&["--light"]), note: None },
+			Translated { from: "[Node] HTTPModules = [\"eth\", \"net\", \"web3\"]".into(), to: args(&["--jsonrpc-apis", "eth,net,web3"]), note: None },
+			Translated { from: "[Node.P2P] DialRatio = 3".into(), to: vec![], note: Some("No Parity equivalent.") },
+			Translated { from: "[Node.P2P] ListenAddr = \":30305\"".into(), to: args(&["--port", "30305"]), note: None },
+			Translated { from: "[Node.P2P] NoDiscovery = false".into(), to: vec![], note: None },
+		]);
+	}
+
+	#[test]
+	fn should_prefer_command_line_to_geth_config() {
+		// given
+		let path = RandomTempPath::new();
+		File::create(path.as_path()).unwrap().write_all(b"[Node.P2P]\nMaxPeers = 25\nListenAddr = \":30305\"\n").unwrap();
+		let path = path.as_str().to_owned();
+
+		// when
+		let (result, translated) = translate(args(&["parity", "--geth-compat-config", &path, "--maxpeers=10"])).unwrap();
+
+		// then
+		assert_eq!(result, args(&["parity", "--geth-compat-config", &path, "--max-peers", "10", "--port", "30305"]));
+		assert_eq!(translated[2].note, Some("Overridden by the command line."));
+		assert!(translate(args(&["parity", "--geth-compat-config=/non-existent"])).is_err());
+	}
+}
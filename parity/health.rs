@@ -0,0 +1,387 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `/health` and `/ready` HTTP endpoints for load balancers and orchestration probes.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::net::{SocketAddr, IpAddr, UdpSocket, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use io::{TimerToken, IoContext, IoHandler};
+use ethcore::client::{Client, BlockChainClient};
+use ethcore::service::ClientIoMessage;
+use ethsync::SyncProvider;
+use sealing_watchdog::SealingWatchdog;
+use parity_rpc::{hyper, is_major_importing};
+use self::hyper::server::{Handler, Request, Response};
+use self::hyper::net::HttpStream;
+use self::hyper::header::{ContentLength, ContentType};
+use self::hyper::{Next, Encoder, Decoder, Method, RequestUri, StatusCode};
+use util::Mutex;
+
+const REFRESH_TIMER: TimerToken = 0;
+const REFRESH_TIMER_INTERVAL_MS: u64 = 30_000;
+const NTP_TIMEOUT_MS: u64 = 1000;
+/// Seconds between 1900-01-01 (NTP epoch) and 1970-01-01.
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Configuration {
+	pub enabled: bool,
+	pub port: u16,
+	pub interface: String,
+	/// Maximal number of blocks the node may be behind the best known block to be ready.
+	pub max_block_lag: u64,
+	/// Minimal number of connected peers to be ready.
+	pub min_peers: usize,
+	/// Maximal clock offset to the NTP server in milliseconds.
+	pub max_clock_drift: u64,
+	/// NTP server used to measure the clock drift. Drift is not checked if `None`.
+	pub ntp_server: Option<String>,
+}
+
+impl Default for Configuration {
+	fn default() -> Self {
+		Configuration {
+			enabled: false,
+			port: 8547,
+			interface: "127.0.0.1".into(),
+			max_block_lag: 10,
+			min_peers: 1,
+			max_clock_drift: 10_000,
+			ntp_server: Some("pool.ntp.org:123".into()),
+		}
+	}
+}
+
+/// Snapshot of the node state the checks are evaluated on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeState {
+	pub importing: bool,
+	pub best_block: u64,
+	pub highest_block: u64,
+	pub peers: usize,
+	pub db_writable: bool,
+	/// Clock offset in milliseconds, `None` if unknown.
+	pub clock_drift: Option<i64>,
+	/// Whether the node seals blocks in its turns, always true without the sealing watchdog.
+	pub sealing: bool,
+}
+
+/// Outcome of the checks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+	pub synced: bool,
+	pub enough_peers: bool,
+	pub db_writable: bool,
+	pub clock_ok: bool,
+	pub sealing_ok: bool,
+	pub state: NodeState,
+}
+
+impl Report {
+	/// The node is working correctly, though it may not be able to serve up-to-date data.
+	pub fn is_healthy(&self) -> bool {
+		self.db_writable && self.clock_ok && self.sealing_ok
+	}
+
+	/// The node is healthy, synced and connected to enough peers.
+	pub fn is_ready(&self) -> bool {
+		self.is_healthy() && self.synced && self.enough_peers
+	}
+
+	fn to_json(&self) -> String {
+		let drift = self.state.clock_drift.map_or("null".to_owned(), |d| d.to_string());
+		format!(
+			concat!(
+				"{{\"healthy\":{},\"ready\":{},",
+				"\"sync\":{{\"ok\":{},\"importing\":{},\"bestBlock\":{},\"highestBlock\":{}}},",
+				"\"peers\":{{\"ok\":{},\"connected\":{}}},",
+				"\"db\":{{\"ok\":{}}},",
+				"\"clock\":{{\"ok\":{},\"driftMs\":{}}},",
+				"\"sealing\":{{\"ok\":{}}}}}"
+			),
+			self.is_healthy(), self.is_ready(),
+			self.synced, self.state.importing, self.state.best_block, self.state.highest_block,
+			self.enough_peers, self.state.peers,
+			self.db_writable,
+			self.clock_ok, drift,
+			self.sealing_ok,
+		)
+	}
+}
+
+/// Evaluate the configured criteria.
+pub fn evaluate(conf: &Configuration, state: NodeState) -> Report {
+	Report {
+		synced: !state.importing && state.highest_block.saturating_sub(state.best_block) <= conf.max_block_lag,
+		enough_peers: state.peers >= conf.min_peers,
+		db_writable: state.db_writable,
+		// unknown drift is not reported as a failure, the NTP server may just be unreachable
+		clock_ok: conf.ntp_server.is_none() || state.clock_drift.map_or(true, |d| d.abs() as u64 <= conf.max_clock_drift),
+		sealing_ok: state.sealing,
+		state: state,
+	}
+}
+
+/// Gathers the node state. DB and clock checks are slow, so they are refreshed periodically.
+pub struct HealthChecker {
+	conf: Configuration,
+	client: Arc<Client>,
+	sync: Arc<SyncProvider>,
+	db_path: PathBuf,
+	sealing_watchdog: Option<Arc<SealingWatchdog>>,
+	db_writable: Mutex<bool>,
+	clock_drift: Mutex<Option<i64>>,
+}
+
+impl HealthChecker {
+	pub fn new(conf: Configuration, client: Arc<Client>, sync: Arc<SyncProvider>, db_path: PathBuf, sealing_watchdog: Option<Arc<SealingWatchdog>>) -> Self {
+		let checker = HealthChecker {
+			conf: conf,
+			client: client,
+			sync: sync,
+			db_path: db_path,
+			sealing_watchdog: sealing_watchdog,
+			db_writable: Mutex::new(true),
+			clock_drift: Mutex::new(None),
+		};
+		*checker.db_writable.lock() = checker.check_db();
+		checker
+	}
+
+	/// Evaluate the current state.
+	pub fn report(&self) -> Report {
+		let status = self.sync.status();
+		let best_block = self.client.chain_info().best_block_number;
+		let state = NodeState {
+			importing: is_major_importing(Some(status.state), self.client.queue_info()),
+			best_block: best_block,
+			highest_block: status.highest_block_number.unwrap_or(best_block),
+			peers: status.num_peers,
+			db_writable: *self.db_writable.lock(),
+			clock_drift: *self.clock_drift.lock(),
+			sealing: self.sealing_watchdog.as_ref().map_or(true, |w| w.is_sealing()),
+		};
+		evaluate(&self.conf, state)
+	}
+
+	/// Re-run the DB and clock checks.
+	pub fn refresh(&self) {
+		*self.db_writable.lock() = self.check_db();
+		if let Some(ref server) = self.conf.ntp_server {
+			let drift = match query_clock_drift(server) {
+				Ok(drift) => Some(drift),
+				Err(e) => {
+					debug!(target: "health", "Error querying NTP server {}: {}", server, e);
+					None
+				},
+			};
+			*self.clock_drift.lock() = drift;
+		}
+	}
+
+	fn check_db(&self) -> bool {
+		let path = self.db_path.join(".health");
+		let writable = File::create(&path).and_then(|mut f| f.write_all(b"ok")).is_ok();
+		let _ = fs::remove_file(&path);
+		if !writable {
+			warn!("Database directory {} is not writable", self.db_path.display());
+		}
+		writable
+	}
+}
+
+impl IoHandler<ClientIoMessage> for HealthChecker {
+	fn initialize(&self, io: &IoContext<ClientIoMessage>) {
+		self.refresh();
+		io.register_timer(REFRESH_TIMER, REFRESH_TIMER_INTERVAL_MS).expect("Error registering timer");
+	}
+
+	fn timeout(&self, _io: &IoContext<ClientIoMessage>, timer: TimerToken) {
+		if timer == REFRESH_TIMER {
+			self.refresh();
+		}
+	}
+}
+
+/// Milliseconds since the unix epoch.
+fn now_ms() -> i64 {
+	let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+	(now.as_secs() * 1000) as i64 + (now.subsec_nanos() / 1_000_000) as i64
+}
+
+/// Decode an NTP timestamp into milliseconds since the unix epoch.
+fn decode_ntp_timestamp(data: &[u8]) -> i64 {
+	let secs = (data[0] as u64) << 24 | (data[1] as u64) << 16 | (data[2] as u64) << 8 | data[3] as u64;
+	let fraction = (data[4] as u64) << 24 | (data[5] as u64) << 16 | (data[6] as u64) << 8 | data[7] as u64;
+	(secs as i64 - NTP_UNIX_OFFSET as i64) * 1000 + ((fraction * 1000) >> 32) as i64
+}
+
+/// Clock offset from SNTP timestamps: request sent, received by server, reply sent, reply received.
+fn clock_offset(sent: i64, server_received: i64, server_sent: i64, received: i64) -> i64 {
+	((server_received - sent) + (server_sent - received)) / 2
+}
+
+/// Measure the local clock offset against an (S)NTP server in milliseconds.
+fn query_clock_drift(server: &str) -> Result<i64, String> {
+	let address = server.to_socket_addrs().map_err(|e| e.to_string())?
+		.next().ok_or_else(|| "Address not resolved".to_owned())?;
+	let bind = if address.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+	let socket = UdpSocket::bind(bind).map_err(|e| e.to_string())?;
+	socket.set_read_timeout(Some(Duration::from_millis(NTP_TIMEOUT_MS))).map_err(|e| e.to_string())?;
+
+	// LI = 0, version 3, client mode
+	let mut request = [0u8; 48];
+	request[0] = 0x1b;
+	let sent = now_ms();
+	socket.send_to(&request, &address).map_err(|e| e.to_string())?;
+	let mut response = [0u8; 48];
+	let (len, _) = socket.recv_from(&mut response).map_err(|e| e.to_string())?;
+	let received = now_ms();
+	if len < 48 || response[0] & 0x07 != 4 {
+		return Err("Invalid NTP response".into());
+	}
+	Ok(clock_offset(sent, decode_ntp_timestamp(&response[32..40]), decode_ntp_timestamp(&response[40..48]), received))
+}
+
+/// Serves a single request.
+struct HealthHandler {
+	checker: Arc<HealthChecker>,
+	status: StatusCode,
+	out: String,
+	out_progress: usize,
+}
+
+impl Handler<HttpStream> for HealthHandler {
+	fn on_request(&mut self, req: Request<HttpStream>) -> Next {
+		if *req.method() != Method::Get {
+			self.status = StatusCode::MethodNotAllowed;
+			return Next::write();
+		}
+		let path = match *req.uri() {
+			RequestUri::AbsolutePath { ref path, .. } => path.clone(),
+			_ => return Next::write(),
+		};
+		let report = self.checker.report();
+		let ok = match path.as_str() {
+			"/health" => report.is_healthy(),
+			"/ready" => report.is_ready(),
+			_ => return Next::write(),
+		};
+		self.status = if ok { StatusCode::Ok } else { StatusCode::ServiceUnavailable };
+		self.out = report.to_json();
+		Next::write()
+	}
+
+	fn on_request_readable(&mut self, _decoder: &mut Decoder<HttpStream>) -> Next {
+		Next::write()
+	}
+
+	fn on_response(&mut self, res: &mut Response) -> Next {
+		res.set_status(self.status);
+		res.headers_mut().set(ContentLength(self.out.len() as u64));
+		res.headers_mut().set(ContentType::json());
+		Next::write()
+	}
+
+	fn on_response_writable(&mut self, transport: &mut Encoder<HttpStream>) -> Next {
+		let chunk = &self.out.as_bytes()[self.out_progress..];
+		match transport.write(chunk) {
+			Ok(written) if written < chunk.len() => {
+				self.out_progress += written;
+				Next::write()
+			},
+			_ => Next::end(),
+		}
+	}
+}
+
+pub fn start_server(conf: &Configuration, checker: Arc<HealthChecker>) -> Result<Option<hyper::server::Listening>, String> {
+	if !conf.enabled {
+		return Ok(None);
+	}
+
+	let ip: IpAddr = conf.interface.parse().map_err(|_| format!("Invalid health API interface given: {}", conf.interface))?;
+	let addr = SocketAddr::new(ip, conf.port);
+	let server = hyper::Server::http(&addr).map_err(|e| format!("Health API error: {:?}", e))?;
+	server.handle(move |_| HealthHandler {
+		checker: checker.clone(),
+		status: StatusCode::NotFound,
+		out: String::new(),
+		out_progress: 0,
+	})
+		.map(|(listening, srv)| {
+			::std::thread::spawn(move || {
+				srv.run();
+			});
+			Some(listening)
+		})
+		.map_err(|e| format!("Health API error: {:?}", e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Configuration, NodeState, evaluate, decode_ntp_timestamp, clock_offset};
+
+	fn state() -> NodeState {
+		NodeState {
+			importing: false,
+			best_block: 100,
+			highest_block: 105,
+			peers: 3,
+			db_writable: true,
+			clock_drift: Some(-200),
+			sealing: true,
+		}
+	}
+
+	#[test]
+	fn should_evaluate_criteria() {
+		let conf = Configuration::default();
+		let report = evaluate(&conf, state());
+		assert!(report.is_healthy() && report.is_ready());
+
+		let report = evaluate(&conf, NodeState { highest_block: 200, ..state() });
+		assert!(report.is_healthy() && !report.is_ready());
+
+		let report = evaluate(&Configuration { min_peers: 5, ..conf.clone() }, state());
+		assert!(!report.enough_peers && !report.is_ready());
+
+		let report = evaluate(&conf, NodeState { clock_drift: Some(20_000), ..state() });
+		assert!(!report.is_healthy() && !report.is_ready());
+		let report = evaluate(&Configuration { ntp_server: None, ..conf.clone() }, NodeState { clock_drift: Some(20_000), ..state() });
+		assert!(report.is_healthy());
+
+		let report = evaluate(&conf, NodeState { db_writable: false, ..state() });
+		assert!(!report.is_healthy());
+		assert!(report.to_json().starts_with("{\"healthy\":false,\"ready\":false,"));
+
+		let report = evaluate(&conf, NodeState { sealing: false, ..state() });
+		assert!(!report.is_healthy());
+		assert!(report.to_json().ends_with("\"sealing\":{\"ok\":false}}"));
+	}
+
+	#[test]
+	fn should_compute_clock_offset() {
+		// 2017-01-01T00:00:00.5Z
+		let timestamp = [0xdc, 0x12, 0xc5, 0x00, 0x80, 0, 0, 0];
+		assert_eq!(decode_ntp_timestamp(&timestamp), 1_483_228_800_500);
+		assert_eq!(clock_offset(1000, 1600, 1610, 1030), 590);
+	}
+}
@@ -21,12 +21,13 @@ use std::fs::File;
 use util::{clean_0x, U256, Uint, Address, CompactionProfile};
 use util::journaldb::Algorithm;
 use ethcore::client::{Mode, BlockId, VMType, DatabaseCompactionProfile, ClientConfig, VerifierType};
-use ethcore::miner::{PendingSet, GasLimit, PrioritizationStrategy};
+use ethcore::miner::{PendingSet, GasLimit, PrioritizationStrategy, LocalsPolicy};
 use cache::CacheConfig;
 use dir::DatabaseDirectories;
 use upgrade::{upgrade, upgrade_data_paths};
 use migration::migrate;
-use ethsync::is_valid_node_url;
+use ethsync::{is_valid_boot_node_url, IpNetwork};
+use local_store::Persistence;
 use path;
 
 pub fn to_duration(s: &str) -> Result<Duration, String> {
@@ -58,8 +59,8 @@ pub fn to_mode(s: &str, timeout: u64, alarm: u64) -> Result<Mode, String> {
 		"active" => Ok(Mode::Active),
 		"passive" => Ok(Mode::Passive(Duration::from_secs(timeout), Duration::from_secs(alarm))),
 		"dark" => Ok(Mode::Dark(Duration::from_secs(timeout))),
-		"offline" => Ok(Mode::Off),
-		_ => Err(format!("{}: Invalid value for --mode. Must be one of active, passive, dark or offline.", s)),
+		"offline" | "offline-serve" => Ok(Mode::Off),
+		_ => Err(format!("{}: Invalid value for --mode. Must be one of active, passive, dark, offline or offline-serve.", s)),
 	}
 }
 
@@ -111,6 +112,24 @@ pub fn to_queue_strategy(s: &str) -> Result<PrioritizationStrategy, String> {
 	}
 }
 
+pub fn to_locals_policy(s: &str) -> Result<LocalsPolicy, String> {
+	match s {
+		"prioritize" => Ok(LocalsPolicy::Prioritize),
+		"retain" => Ok(LocalsPolicy::Retain),
+		"none" => Ok(LocalsPolicy::Ignore),
+		other => Err(format!("Invalid local transactions policy: {}", other)),
+	}
+}
+
+pub fn to_tx_queue_persistence(s: &str) -> Result<Persistence, String> {
+	match s {
+		"all" => Ok(Persistence::All),
+		"local" => Ok(Persistence::Local),
+		"none" => Ok(Persistence::None),
+		other => Err(format!("Invalid transaction queue persistence: {}", other)),
+	}
+}
+
 pub fn to_address(s: Option<String>) -> Result<Address, String> {
 	match s {
 		Some(ref a) => clean_0x(a).parse().map_err(|_| format!("Invalid address: {:?}", a)),
@@ -179,7 +198,7 @@ pub fn parity_ipc_path(base: &str, s: &str) -> String {
 pub fn to_bootnodes(bootnodes: &Option<String>) -> Result<Vec<String>, String> {
 	match *bootnodes {
 		Some(ref x) if !x.is_empty() => x.split(',').map(|s| {
-			if is_valid_node_url(s) {
+			if is_valid_boot_node_url(s) {
 				Ok(s.to_owned())
 			} else {
 				Err(format!("Invalid node address format given for a boot node: {}", s))
@@ -190,6 +209,13 @@ pub fn to_bootnodes(bootnodes: &Option<String>) -> Result<Vec<String>, String> {
 	}
 }
 
+/// Validates and normalizes a network in CIDR notation.
+pub fn to_ip_network(s: &str) -> Result<String, String> {
+	s.parse::<IpNetwork>()
+		.map(|network| format!("{}", network))
+		.map_err(|e| format!("Invalid network given for an IP filter: {} ({})", s, e))
+}
+
 #[cfg(test)]
 pub fn default_network_config() -> ::ethsync::NetworkConfiguration {
 	use ethsync::{NetworkConfiguration, AllowIP};
@@ -200,14 +226,31 @@ pub fn default_network_config() -> ::ethsync::NetworkConfiguration {
 		public_address: None,
 		udp_port: None,
 		nat_enabled: true,
+		nat_pmp_enabled: true,
 		discovery_enabled: true,
+		proxy: None,
+		fork_id: None,
+		fork_blocks: Vec::new(),
 		boot_nodes: Vec::new(),
 		use_secret: None,
 		max_peers: 50,
 		min_peers: 25,
 		snapshot_peers: 0,
+		les_peers: 0,
+		snapshot_preferred_peers: Vec::new(),
+		sync_schedule: Default::default(),
+		local_tx_delay: 0,
+		local_tx_jitter: 0,
+		local_tx_relay_peers: Vec::new(),
 		max_pending_peers: 64,
 		allow_ips: AllowIP::All,
+		allow_networks: Vec::new(),
+		deny_networks: Vec::new(),
+		max_peers_per_ip: 0,
+		max_peers_per_subnet: 0,
+		max_upload: None,
+		max_download: None,
+		protocol_slots: Vec::new(),
 		reserved_nodes: Vec::new(),
 		allow_non_reserved: true,
 	}
@@ -371,6 +414,7 @@ mod tests {
 		assert_eq!(to_mode("active", 0, 0).unwrap(), Mode::Active);
 		assert_eq!(to_mode("passive", 10, 20).unwrap(), Mode::Passive(Duration::from_secs(10), Duration::from_secs(20)));
 		assert_eq!(to_mode("dark", 20, 30).unwrap(), Mode::Dark(Duration::from_secs(20)));
+		assert_eq!(to_mode("offline-serve", 20, 30).unwrap(), Mode::Off);
 		assert!(to_mode("other", 20, 30).is_err());
 	}
 
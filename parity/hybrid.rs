@@ -0,0 +1,244 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Hybrid light-then-full mode.
+//!
+//! The node starts as a light client, so RPC is usable right away, and runs a full node
+//! with the same command line as a child process. The full node syncs in the background
+//! and serves JSON-RPC over HTTP on loopback only. Once it has caught up with the chain
+//! seen by the light client, RPC calls are handed over to it. Subscriptions and methods
+//! outside of the HTTP APIs of the full node keep being served by the light client.
+
+use std::env;
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use futures::Future;
+use serde_json::{self, Value};
+use hash_fetch::fetch::Client as FetchClient;
+use ethsync::LightSync;
+use light::client::Client as LightClient;
+use util::Mutex;
+use run::RunCmd;
+
+/// Number of blocks the full node may be behind the light client to take over.
+const MAX_BLOCK_LAG: u64 = 2;
+/// Interval between checks of the full node.
+const CHECK_INTERVAL_SECS: u64 = 10;
+
+/// Role of this process in hybrid mode.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Hybrid {
+	/// Not running in hybrid mode.
+	Disabled,
+	/// Light client serving RPC, with the background full node listening on the given port.
+	Light {
+		rpc_port: u16,
+	},
+	/// Background full node started by the light client.
+	Full,
+}
+
+/// URL of the background full node's JSON-RPC server.
+pub fn rpc_url(rpc_port: u16) -> String {
+	format!("http://127.0.0.1:{}", rpc_port)
+}
+
+/// Turn the command of a hybrid client into the one of its background full node.
+///
+/// The full node only serves HTTP JSON-RPC on loopback, everything else facing users
+/// is provided by the light client.
+pub fn into_background_full_node(cmd: &mut RunCmd, rpc_port: u16) {
+	cmd.light = false;
+	cmd.hybrid = Hybrid::Full;
+	cmd.daemon = None;
+	cmd.http_conf.enabled = true;
+	cmd.http_conf.interface = "127.0.0.1".into();
+	cmd.http_conf.port = rpc_port;
+	cmd.http_conf.cors = None;
+	cmd.ws_conf.enabled = false;
+	cmd.ipc_conf.enabled = false;
+	cmd.dapps_conf.enabled = false;
+	cmd.ipfs_conf.enabled = false;
+	cmd.health_conf.enabled = false;
+	cmd.signer_conf.enabled = false;
+	cmd.signer_policy = None;
+	cmd.secretstore_conf.enabled = false;
+	cmd.stratum = None;
+	cmd.ui_address = None;
+	cmd.ui = false;
+	cmd.dapp = None;
+	cmd.pip.ws_listen = None;
+}
+
+/// Background full node of a hybrid client.
+pub struct FullNode {
+	child: Arc<Mutex<Child>>,
+	ready: Arc<AtomicBool>,
+	stopped: Arc<AtomicBool>,
+}
+
+impl FullNode {
+	/// Start the full node and watch it catch up with the light client.
+	pub fn spawn(rpc_port: u16, fetch: FetchClient, client: Arc<LightClient>, sync: Arc<LightSync>) -> Result<Self, String> {
+		let exe = env::current_exe().map_err(|e| format!("Unable to find the executable: {}", e))?;
+		let args: Vec<String> = env::args().skip(1).collect();
+		let child = Command::new(&exe)
+			.args(&child_args(&args))
+			.stdin(Stdio::null())
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.spawn()
+			.map_err(|e| format!("Unable to start the background full node: {}", e))?;
+		info!("Started background full node, process id {}", child.id());
+
+		let node = FullNode {
+			child: Arc::new(Mutex::new(child)),
+			ready: Arc::new(AtomicBool::new(false)),
+			stopped: Arc::new(AtomicBool::new(false)),
+		};
+
+		let child = node.child.clone();
+		let ready = node.ready.clone();
+		let stopped = node.stopped.clone();
+		thread::Builder::new().name("hybrid".into()).spawn(move || {
+			watch(&rpc_url(rpc_port), &fetch, &*client, &*sync, &child, &ready, &stopped)
+		}).map_err(|e| format!("Unable to start the background full node watcher: {}", e))?;
+
+		Ok(node)
+	}
+
+	/// Flag set once the full node has caught up and takes over RPC.
+	pub fn ready(&self) -> Arc<AtomicBool> {
+		self.ready.clone()
+	}
+}
+
+impl Drop for FullNode {
+	fn drop(&mut self) {
+		self.stopped.store(true, Ordering::SeqCst);
+		let mut child = self.child.lock();
+		if let Ok(None) = child.try_wait() {
+			info!("Stopping background full node");
+			::supervisor::terminate(child.id());
+			let _ = child.wait();
+		}
+	}
+}
+
+// command line of the full node.
+fn child_args(args: &[String]) -> Vec<String> {
+	let mut result = args.to_vec();
+	for flag in &["--hybrid-full-node", "--force-direct"] {
+		if !result.iter().any(|arg| arg == flag) {
+			result.push(flag.to_string());
+		}
+	}
+	result
+}
+
+// check the full node until it takes over or exits.
+fn watch(url: &str, fetch: &FetchClient, client: &LightClient, sync: &LightSync, child: &Mutex<Child>, ready: &AtomicBool, stopped: &AtomicBool) {
+	loop {
+		thread::sleep(Duration::from_secs(CHECK_INTERVAL_SECS));
+		if stopped.load(Ordering::SeqCst) {
+			return;
+		}
+
+		match child.lock().try_wait() {
+			Ok(None) => {},
+			Ok(Some(status)) => {
+				ready.store(false, Ordering::SeqCst);
+				warn!("Background full node exited ({}), serving RPC as a light client only", status);
+				return;
+			},
+			Err(e) => {
+				ready.store(false, Ordering::SeqCst);
+				warn!("Unable to check the background full node: {}", e);
+				return;
+			},
+		}
+
+		if ready.load(Ordering::SeqCst) {
+			continue;
+		}
+
+		let full_best = match best_block(url, fetch) {
+			Ok(number) => number,
+			Err(e) => {
+				debug!(target: "hybrid", "Background full node not available yet: {}", e);
+				continue;
+			},
+		};
+		let light_best = client.best_block_header().number();
+		if caught_up(full_best, light_best, sync.highest_block(), sync.is_major_importing()) {
+			info!("Background full node caught up at #{}, handing RPC over to it", full_best);
+			ready.store(true, Ordering::SeqCst);
+		} else {
+			debug!(target: "hybrid", "Background full node at #{}, light client at #{}", full_best, light_best);
+		}
+	}
+}
+
+// whether the full node can take over. The light client must be in sync with the
+// network first, otherwise both would agree on an outdated chain.
+fn caught_up(full_best: u64, light_best: u64, light_highest: Option<u64>, light_importing: bool) -> bool {
+	light_highest.is_some() && !light_importing && full_best + MAX_BLOCK_LAG >= light_best
+}
+
+// best block number of the full node.
+fn best_block(url: &str, fetch: &FetchClient) -> Result<u64, String> {
+	let request = r#"{"jsonrpc":"2.0","method":"eth_blockNumber","params":[],"id":1}"#;
+	let mut response = fetch.post_json(url, request.into()).wait().map_err(|e| format!("{:?}", e))?;
+	if !response.is_success() {
+		return Err(format!("HTTP status {}", response.status()));
+	}
+
+	let mut body = String::new();
+	response.read_to_string(&mut body).map_err(|e| e.to_string())?;
+	let value: Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+	value.get("result")
+		.and_then(Value::as_str)
+		.and_then(|number| u64::from_str_radix(number.trim_left_matches("0x"), 16).ok())
+		.ok_or_else(|| format!("Invalid response: {}", body))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{caught_up, child_args};
+
+	#[test]
+	fn should_run_full_node_with_same_arguments() {
+		let args: Vec<String> = vec!["--hybrid".into(), "--chain".into(), "kovan".into(), "--force-direct".into()];
+		assert_eq!(child_args(&args), vec!["--hybrid".to_owned(), "--chain".into(), "kovan".into(), "--force-direct".into(), "--hybrid-full-node".into()]);
+	}
+
+	#[test]
+	fn should_take_over_when_caught_up() {
+		// light client without peers
+		assert!(!caught_up(0, 0, None, false));
+		// light client still syncing
+		assert!(!caught_up(100, 100, Some(200), true));
+		// full node behind
+		assert!(!caught_up(100, 200, Some(200), false));
+		assert!(caught_up(198, 200, Some(200), false));
+		assert!(caught_up(201, 200, Some(200), false));
+	}
+}
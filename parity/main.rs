@@ -28,9 +28,11 @@ extern crate ctrlc;
 extern crate docopt;
 extern crate env_logger;
 extern crate fdlimit;
+extern crate flate2;
 extern crate futures;
 extern crate isatty;
 extern crate jsonrpc_core;
+extern crate libc;
 extern crate num_cpus;
 extern crate number_prefix;
 extern crate regex;
@@ -53,6 +55,7 @@ extern crate ethcore_light as light;
 extern crate ethcore_logger;
 extern crate ethcore_signer;
 extern crate ethcore_util as util;
+extern crate ethcrypto;
 extern crate ethkey;
 extern crate ethsync;
 extern crate parity_hash_fetch as hash_fetch;
@@ -66,6 +69,8 @@ extern crate rpc_cli;
 
 #[macro_use]
 extern crate log as rlog;
+#[macro_use]
+extern crate lazy_static;
 
 #[cfg(feature="stratum")]
 extern crate ethcore_stratum;
@@ -96,30 +101,53 @@ macro_rules! dependency {
 }
 
 mod account;
+mod block_export;
+mod block_import;
 mod blockchain;
 mod cache;
 mod cli;
 mod configuration;
+mod csv_export;
 mod dapps;
+mod dev_accounts;
 mod ipfs;
 mod deprecated;
 mod dir;
+mod event_publisher;
+mod health;
+mod sealing_watchdog;
 mod helpers;
+mod hybrid;
 mod informant;
 mod light_helpers;
+mod metrics;
 mod migration;
 mod modules;
 mod params;
 mod presale;
+mod priority;
+mod reserved_peers;
 mod rpc;
 mod rpc_apis;
 mod run;
 mod signer;
 mod snapshot;
+mod snapshot_encryption;
+mod sql_export;
 mod secretstore;
 mod upgrade;
 mod url;
 mod user_defaults;
+mod service;
+mod shutdown;
+mod supervisor;
+mod systemd;
+mod reload;
+mod config_dump;
+mod check_config;
+mod config_secrets;
+mod geth_compat;
+mod remote_config;
 
 #[cfg(feature="ipc")]
 mod boot;
@@ -172,14 +200,26 @@ fn execute(command: Execute, can_restart: bool) -> Result<PostExecutionAction, S
 		Cmd::Blockchain(blockchain_cmd) => blockchain::execute(blockchain_cmd).map(|_| PostExecutionAction::Quit),
 		Cmd::SignerToken(signer_cmd) => signer::execute(signer_cmd).map(|s| PostExecutionAction::Print(s)),
 		Cmd::SignerSign { id, pwfile, port, authfile } => rpc_cli::signer_sign(id, pwfile, port, authfile).map(|s| PostExecutionAction::Print(s)),
-		Cmd::SignerList { port, authfile } => rpc_cli::signer_list(port, authfile).map(|s| PostExecutionAction::Print(s)),
-		Cmd::SignerReject { id, port, authfile } => rpc_cli::signer_reject(id, port, authfile).map(|s| PostExecutionAction::Print(s)),
+		Cmd::SignerApprove { id, pwfile, port, ipc_path, authfile } => rpc_cli::signer_approve(id, pwfile, port, ipc_path, authfile).map(|s| PostExecutionAction::Print(s)),
+		Cmd::SignerList { port, ipc_path, authfile } => rpc_cli::signer_list(port, ipc_path, authfile).map(|s| PostExecutionAction::Print(s)),
+		Cmd::SignerReject { id, port, ipc_path, authfile } => rpc_cli::signer_reject(id, port, ipc_path, authfile).map(|s| PostExecutionAction::Print(s)),
 		Cmd::Snapshot(snapshot_cmd) => snapshot::execute(snapshot_cmd).map(|s| PostExecutionAction::Print(s)),
+		Cmd::Service(service_cmd) => service::execute(service_cmd, logger).map(|s| PostExecutionAction::Print(s)),
+		Cmd::Supervise(supervisor_cmd) => supervisor::execute(supervisor_cmd).map(|_| PostExecutionAction::Quit),
+		Cmd::ConfigDump(dump_cmd) => config_dump::execute(dump_cmd).map(|s| PostExecutionAction::Print(s)),
+		Cmd::EncryptConfigValue { key_file } => config_secrets::execute_encrypt(key_file).map(|s| PostExecutionAction::Print(s)),
+		Cmd::SignConfig { file, key_file, valid_for_days } => remote_config::execute_sign(file, key_file, valid_for_days).map(|s| PostExecutionAction::Print(s)),
 	}
 }
 
 fn start(can_restart: bool) -> Result<PostExecutionAction, String> {
-	let args: Vec<String> = env::args().collect();
+	let (args, translated) = geth_compat::translate(env::args().collect())?;
+	if !translated.is_empty() {
+		let _ = writeln!(&mut stdio::stderr(), "Translated Geth options:");
+		for option in translated {
+			let _ = writeln!(&mut stdio::stderr(), "  {}", option);
+		}
+	}
 	let conf = Configuration::parse(&args, take_spec_name_override()).unwrap_or_else(|e| e.exit());
 
 	let deprecated = find_deprecated(&conf.args);
@@ -187,6 +227,10 @@ fn start(can_restart: bool) -> Result<PostExecutionAction, String> {
 		println!("{}", d);
 	}
 
+	if conf.args.flag_check_config {
+		return check_config::execute(conf).map(PostExecutionAction::Print);
+	}
+
 	let cmd = conf.into_command()?;
 	execute(cmd, can_restart)
 }
@@ -0,0 +1,244 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node metrics registry and the StatsD exporter.
+
+use std::collections::HashMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::Arc;
+use io::{TimerToken, IoContext, IoHandler};
+use ethcore::client::{Client, BlockChainClient};
+use ethcore::service::ClientIoMessage;
+use ethsync::{SyncProvider, ManageNetwork};
+use parity_rpc::informant::RpcStats;
+use util::Mutex;
+
+const FLUSH_TIMER: TimerToken = 0;
+/// Keep datagrams below the common MTU.
+const MAX_DATAGRAM_SIZE: usize = 1400;
+
+/// StatsD exporter configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsdConfig {
+	/// Address of the StatsD server.
+	pub address: SocketAddr,
+	/// Prefix of all metric names.
+	pub prefix: String,
+	/// Flush interval in seconds.
+	pub interval: u64,
+}
+
+/// Kind of a metric.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricKind {
+	/// Current level of something.
+	Gauge,
+	/// Monotonically increasing total.
+	Counter,
+}
+
+/// Single metric sample.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+	/// Dot-separated name.
+	pub name: String,
+	/// Kind of the metric.
+	pub kind: MetricKind,
+	/// Current value.
+	pub value: u64,
+}
+
+impl Metric {
+	fn gauge(name: &str, value: u64) -> Self {
+		Metric { name: name.to_owned(), kind: MetricKind::Gauge, value: value }
+	}
+
+	fn counter(name: &str, value: u64) -> Self {
+		Metric { name: name.to_owned(), kind: MetricKind::Counter, value: value }
+	}
+}
+
+/// Collects metrics of the running node. Exporters take samples from here.
+pub struct MetricsRegistry {
+	client: Arc<Client>,
+	sync: Option<Arc<SyncProvider>>,
+	net: Option<Arc<ManageNetwork>>,
+	rpc_stats: Option<Arc<RpcStats>>,
+}
+
+impl MetricsRegistry {
+	/// Create a new registry.
+	pub fn new(client: Arc<Client>, sync: Option<Arc<SyncProvider>>, net: Option<Arc<ManageNetwork>>, rpc_stats: Option<Arc<RpcStats>>) -> Self {
+		MetricsRegistry {
+			client: client,
+			sync: sync,
+			net: net,
+			rpc_stats: rpc_stats,
+		}
+	}
+
+	/// Take a sample of all metrics.
+	pub fn collect(&self) -> Vec<Metric> {
+		let chain_info = self.client.chain_info();
+		let queue_info = self.client.queue_info();
+		let report = self.client.report();
+		let mut metrics = vec![
+			Metric::gauge("chain.best_block", chain_info.best_block_number),
+			Metric::counter("chain.blocks_imported", report.blocks_imported as u64),
+			Metric::counter("chain.transactions_applied", report.transactions_applied as u64),
+			Metric::counter("chain.gas_processed", report.gas_processed.low_u64()),
+			Metric::gauge("queue.unverified", queue_info.unverified_queue_size as u64),
+			Metric::gauge("queue.verified", queue_info.verified_queue_size as u64),
+			Metric::gauge("memory.state_db", report.state_db_mem as u64),
+			Metric::gauge("memory.chain_cache", self.client.blockchain_cache_info().total() as u64),
+			Metric::gauge("memory.queue", queue_info.mem_used as u64),
+		];
+		if let Some(ref sync) = self.sync {
+			let status = sync.status();
+			metrics.push(Metric::gauge("sync.peers", status.num_peers as u64));
+			metrics.push(Metric::gauge("sync.active_peers", status.num_active_peers as u64));
+			metrics.push(Metric::gauge("memory.sync", status.mem_used as u64));
+			if let Some(ref net) = self.net {
+				let config = net.network_config();
+				metrics.push(Metric::gauge("sync.max_peers", status.current_max_peers(config.min_peers, config.max_peers) as u64));
+			}
+		}
+		if let Some(ref net) = self.net {
+			let traffic = net.traffic();
+			metrics.push(Metric::counter("network.bytes_received", traffic.received));
+			metrics.push(Metric::counter("network.bytes_sent", traffic.sent));
+			for (protocol, traffic) in net.protocol_traffic() {
+				metrics.push(Metric::counter(&format!("network.{}.bytes_received", protocol), traffic.received));
+				metrics.push(Metric::counter(&format!("network.{}.bytes_sent", protocol), traffic.sent));
+			}
+		}
+		if let Some(ref rpc_stats) = self.rpc_stats {
+			metrics.push(Metric::gauge("rpc.sessions", rpc_stats.sessions() as u64));
+			metrics.push(Metric::gauge("rpc.requests_rate", rpc_stats.requests_rate() as u64));
+			metrics.push(Metric::gauge("rpc.roundtrip_us", rpc_stats.approximated_roundtrip() as u64));
+		}
+		metrics
+	}
+}
+
+/// Pushes metrics to a StatsD server over UDP in regular intervals.
+pub struct StatsdExporter {
+	config: StatsdConfig,
+	registry: Arc<MetricsRegistry>,
+	socket: UdpSocket,
+	/// Counter values at the last flush, StatsD counters are sent as increments.
+	last_counters: Mutex<HashMap<String, u64>>,
+}
+
+impl StatsdExporter {
+	/// Create a new exporter.
+	pub fn new(config: StatsdConfig, registry: Arc<MetricsRegistry>) -> Result<Self, String> {
+		let bind = if config.address.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+		let socket = UdpSocket::bind(bind).map_err(|e| format!("Error binding StatsD socket: {}", e))?;
+		Ok(StatsdExporter {
+			config: config,
+			registry: registry,
+			socket: socket,
+			last_counters: Mutex::new(HashMap::new()),
+		})
+	}
+
+	/// Send the current metrics.
+	pub fn flush(&self) {
+		let metrics = self.registry.collect();
+		let lines = format_statsd(&self.config.prefix, &metrics, &mut self.last_counters.lock());
+		for datagram in pack_datagrams(&lines) {
+			if let Err(e) = self.socket.send_to(datagram.as_bytes(), &self.config.address) {
+				debug!(target: "metrics", "Error sending metrics to {}: {}", self.config.address, e);
+				return;
+			}
+		}
+	}
+}
+
+impl IoHandler<ClientIoMessage> for StatsdExporter {
+	fn initialize(&self, io: &IoContext<ClientIoMessage>) {
+		io.register_timer(FLUSH_TIMER, self.config.interval * 1000).expect("Error registering timer");
+	}
+
+	fn timeout(&self, _io: &IoContext<ClientIoMessage>, timer: TimerToken) {
+		if timer == FLUSH_TIMER {
+			self.flush();
+		}
+	}
+}
+
+/// Format metrics in StatsD line protocol. Counters are reported as increments since `last_counters`.
+fn format_statsd(prefix: &str, metrics: &[Metric], last_counters: &mut HashMap<String, u64>) -> Vec<String> {
+	let name = |metric: &Metric| match prefix.is_empty() {
+		true => metric.name.clone(),
+		false => format!("{}.{}", prefix, metric.name),
+	};
+	metrics.iter().map(|metric| match metric.kind {
+		MetricKind::Gauge => format!("{}:{}|g", name(metric), metric.value),
+		MetricKind::Counter => {
+			let last = last_counters.insert(metric.name.clone(), metric.value).unwrap_or(metric.value);
+			format!("{}:{}|c", name(metric), metric.value.saturating_sub(last))
+		},
+	}).collect()
+}
+
+/// Join lines into newline-separated datagrams of limited size.
+fn pack_datagrams(lines: &[String]) -> Vec<String> {
+	let mut datagrams = Vec::new();
+	let mut current = String::new();
+	for line in lines {
+		if !current.is_empty() && current.len() + 1 + line.len() > MAX_DATAGRAM_SIZE {
+			datagrams.push(::std::mem::replace(&mut current, String::new()));
+		}
+		if !current.is_empty() {
+			current.push('\n');
+		}
+		current.push_str(line);
+	}
+	if !current.is_empty() {
+		datagrams.push(current);
+	}
+	datagrams
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use super::{Metric, format_statsd, pack_datagrams, MAX_DATAGRAM_SIZE};
+
+	#[test]
+	fn should_format_statsd_lines() {
+		let mut last = HashMap::new();
+		let metrics = vec![Metric::gauge("sync.peers", 12), Metric::counter("chain.blocks_imported", 100)];
+		assert_eq!(format_statsd("parity", &metrics, &mut last), vec![
+			"parity.sync.peers:12|g".to_owned(),
+			"parity.chain.blocks_imported:0|c".to_owned(),
+		]);
+
+		let metrics = vec![Metric::counter("chain.blocks_imported", 130)];
+		assert_eq!(format_statsd("", &metrics, &mut last), vec!["chain.blocks_imported:30|c".to_owned()]);
+	}
+
+	#[test]
+	fn should_pack_datagrams() {
+		let lines: Vec<String> = (0..100).map(|i| format!("parity.metric{}:{}|g", i, i)).collect();
+		let datagrams = pack_datagrams(&lines);
+		assert!(datagrams.len() > 1);
+		assert!(datagrams.iter().all(|d| d.len() <= MAX_DATAGRAM_SIZE));
+		assert_eq!(datagrams.join("\n"), lines.join("\n"));
+	}
+}
@@ -30,7 +30,7 @@ use ethcore::migrations::Extract;
 /// Database is assumed to be at default version, when no version file is found.
 const DEFAULT_VERSION: u32 = 5;
 /// Current version of database models.
-const CURRENT_VERSION: u32 = 12;
+const CURRENT_VERSION: u32 = 15;
 /// First version of the consolidated database.
 const CONSOLIDATION_VERSION: u32 = 9;
 /// Defines how many items are migrated to the new version of database at once.
@@ -148,6 +148,9 @@ fn consolidated_database_migrations(compaction_profile: &CompactionProfile) -> R
 	manager.add_migration(migrations::ToV10::new()).map_err(|_| Error::MigrationImpossible)?;
 	manager.add_migration(migrations::TO_V11).map_err(|_| Error::MigrationImpossible)?;
 	manager.add_migration(migrations::TO_V12).map_err(|_| Error::MigrationImpossible)?;
+	manager.add_migration(migrations::TO_V13).map_err(|_| Error::MigrationImpossible)?;
+	manager.add_migration(migrations::TO_V14).map_err(|_| Error::MigrationImpossible)?;
+	manager.add_migration(migrations::TO_V15).map_err(|_| Error::MigrationImpossible)?;
 	Ok(manager)
 }
 
@@ -18,7 +18,7 @@ use std::{str, fs, fmt};
 use std::time::Duration;
 use util::{Address, U256, version_data};
 use util::journaldb::Algorithm;
-use ethcore::spec::Spec;
+use ethcore::spec::{Spec, Checkpoint, LightCheckpoint};
 use ethcore::ethereum;
 use ethcore::client::Mode;
 use ethcore::miner::{GasPricer, GasPriceCalibratorOptions};
@@ -30,6 +30,8 @@ pub enum SpecType {
 	Morden,
 	Ropsten,
 	Kovan,
+	Rinkeby,
+	Goerli,
 	Olympic,
 	Classic,
 	Expanse,
@@ -53,6 +55,8 @@ impl str::FromStr for SpecType {
 			"morden" | "classic-testnet" => SpecType::Morden,
 			"ropsten" => SpecType::Ropsten,
 			"kovan" | "testnet" => SpecType::Kovan,
+			"rinkeby" => SpecType::Rinkeby,
+			"goerli" | "görli" => SpecType::Goerli,
 			"olympic" => SpecType::Olympic,
 			"expanse" => SpecType::Expanse,
 			"dev" => SpecType::Dev,
@@ -72,6 +76,8 @@ impl fmt::Display for SpecType {
 			SpecType::Classic => "classic",
 			SpecType::Expanse => "expanse",
 			SpecType::Kovan => "kovan",
+			SpecType::Rinkeby => "rinkeby",
+			SpecType::Goerli => "goerli",
 			SpecType::Dev => "dev",
 			SpecType::Custom(ref custom) => custom,
 		})
@@ -88,6 +94,8 @@ impl SpecType {
 			SpecType::Classic => Ok(ethereum::new_classic()),
 			SpecType::Expanse => Ok(ethereum::new_expanse()),
 			SpecType::Kovan => Ok(ethereum::new_kovan()),
+			SpecType::Rinkeby => Ok(ethereum::new_rinkeby()),
+			SpecType::Goerli => Ok(ethereum::new_goerli()),
 			SpecType::Dev => Ok(Spec::new_instant()),
 			SpecType::Custom(ref filename) => {
 				let file = fs::File::open(filename).map_err(|_| "Could not load specification file.")?;
@@ -137,6 +145,58 @@ impl Pruning {
 	}
 }
 
+#[derive(Debug, PartialEq)]
+pub enum CheckpointConfig {
+	/// Use the checkpoint from chain spec (if any).
+	Spec,
+	/// Use user-supplied checkpoint.
+	Custom(Checkpoint),
+	/// Sync and verify the whole chain from genesis.
+	Disabled,
+}
+
+impl Default for CheckpointConfig {
+	fn default() -> Self {
+		CheckpointConfig::Spec
+	}
+}
+
+impl CheckpointConfig {
+	pub fn to_checkpoint(&self, spec: &Spec) -> Option<Checkpoint> {
+		match *self {
+			CheckpointConfig::Spec => spec.checkpoint(),
+			CheckpointConfig::Custom(checkpoint) => Some(checkpoint),
+			CheckpointConfig::Disabled => None,
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LightCheckpointConfig {
+	/// Use the light checkpoint from chain spec (if any).
+	Spec,
+	/// Use a checkpoint loaded from file.
+	Custom(LightCheckpoint),
+	/// Sync all headers from genesis.
+	Disabled,
+}
+
+impl Default for LightCheckpointConfig {
+	fn default() -> Self {
+		LightCheckpointConfig::Spec
+	}
+}
+
+impl LightCheckpointConfig {
+	pub fn to_checkpoint(&self, spec: &Spec) -> Option<LightCheckpoint> {
+		match *self {
+			LightCheckpointConfig::Spec => spec.light_checkpoint.clone(),
+			LightCheckpointConfig::Custom(ref checkpoint) => Some(checkpoint.clone()),
+			LightCheckpointConfig::Disabled => None,
+		}
+	}
+}
+
 #[derive(Debug, PartialEq)]
 pub struct ResealPolicy {
 	pub own: bool,
@@ -332,6 +392,9 @@ mod tests {
 		assert_eq!(SpecType::Olympic, "olympic".parse().unwrap());
 		assert_eq!(SpecType::Classic, "classic".parse().unwrap());
 		assert_eq!(SpecType::Morden, "classic-testnet".parse().unwrap());
+		assert_eq!(SpecType::Rinkeby, "rinkeby".parse().unwrap());
+		assert_eq!(SpecType::Goerli, "goerli".parse().unwrap());
+		assert_eq!(SpecType::Goerli, "görli".parse().unwrap());
 	}
 
 	#[test]
@@ -348,6 +411,8 @@ mod tests {
 		assert_eq!(format!("{}", SpecType::Classic), "classic");
 		assert_eq!(format!("{}", SpecType::Expanse), "expanse");
 		assert_eq!(format!("{}", SpecType::Kovan), "kovan");
+		assert_eq!(format!("{}", SpecType::Rinkeby), "rinkeby");
+		assert_eq!(format!("{}", SpecType::Goerli), "goerli");
 		assert_eq!(format!("{}", SpecType::Dev), "dev");
 		assert_eq!(format!("{}", SpecType::Custom("foo/bar".into())), "foo/bar");
 	}
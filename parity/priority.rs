@@ -0,0 +1,123 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! CPU and I/O scheduling priority of the process.
+//!
+//! On Linux both priorities are per thread and inherited by new threads,
+//! so they have to be set before any other thread is spawned.
+
+use std::io;
+use std::str::FromStr;
+
+/// I/O scheduling class.
+#[derive(Debug, PartialEq, Clone)]
+pub enum IoClass {
+	/// Best-effort with a level from 0 (highest) to 7 (lowest).
+	BestEffort(u8),
+	/// Disk access only when no other process needs it.
+	Idle,
+}
+
+impl FromStr for IoClass {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = s.splitn(2, ':');
+		match (parts.next(), parts.next()) {
+			(Some("idle"), None) => Ok(IoClass::Idle),
+			(Some("best-effort"), None) => Ok(IoClass::BestEffort(4)),
+			(Some("best-effort"), Some(level)) => match level.parse() {
+				Ok(level) if level <= 7 => Ok(IoClass::BestEffort(level)),
+				_ => Err(format!("Invalid best-effort I/O level: {}. Expected a number from 0 to 7.", level)),
+			},
+			_ => Err(format!("Invalid I/O class: {}. Expected best-effort[:LEVEL] or idle.", s)),
+		}
+	}
+}
+
+/// Scheduling priority of the process.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct PriorityConfig {
+	/// Niceness, from -20 (highest priority) to 19 (lowest).
+	pub nice: Option<i32>,
+	/// I/O scheduling class.
+	pub io_class: Option<IoClass>,
+}
+
+/// Applies the priorities to the current thread and all threads spawned later.
+pub fn set_priority(config: &PriorityConfig) -> Result<(), String> {
+	if let Some(nice) = config.nice {
+		set_nice(nice).map_err(|e| format!("Unable to set process niceness to {}: {}", nice, e))?;
+	}
+	if let Some(ref class) = config.io_class {
+		set_io_class(class).map_err(|e| format!("Unable to set I/O class to {:?}: {}", class, e))?;
+	}
+	Ok(())
+}
+
+#[cfg(unix)]
+fn set_nice(nice: i32) -> io::Result<()> {
+	use libc;
+
+	match unsafe { libc::setpriority(libc::PRIO_PROCESS as _, 0, nice) } {
+		0 => Ok(()),
+		_ => Err(io::Error::last_os_error()),
+	}
+}
+
+#[cfg(not(unix))]
+fn set_nice(_nice: i32) -> io::Result<()> {
+	Err(io::Error::new(io::ErrorKind::Other, "not supported on this platform"))
+}
+
+#[cfg(target_os = "linux")]
+fn set_io_class(class: &IoClass) -> io::Result<()> {
+	use libc;
+
+	const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+	const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+	const IOPRIO_CLASS_BE: libc::c_long = 2;
+	const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+
+	let priority = match *class {
+		IoClass::BestEffort(level) => (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | level as libc::c_long,
+		IoClass::Idle => IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+	};
+	match unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, priority) } {
+		0 => Ok(()),
+		_ => Err(io::Error::last_os_error()),
+	}
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_io_class(_class: &IoClass) -> io::Result<()> {
+	Err(io::Error::new(io::ErrorKind::Other, "only supported on Linux"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::IoClass;
+
+	#[test]
+	fn should_parse_io_class() {
+		assert_eq!("idle".parse(), Ok(IoClass::Idle));
+		assert_eq!("best-effort".parse(), Ok(IoClass::BestEffort(4)));
+		assert_eq!("best-effort:7".parse(), Ok(IoClass::BestEffort(7)));
+		assert!("best-effort:8".parse::<IoClass>().is_err());
+		assert!("idle:1".parse::<IoClass>().is_err());
+		assert!("realtime".parse::<IoClass>().is_err());
+	}
+}
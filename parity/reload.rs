@@ -0,0 +1,242 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reloading of runtime settings from the config file on SIGHUP.
+
+use std::env;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering, ATOMIC_BOOL_INIT};
+use io::{TimerToken, IoContext, IoHandler};
+use ethcore::miner::{Miner, MinerService};
+use ethcore::service::ClientIoMessage;
+use ethcore_logger::RotatingLogger;
+use ethsync::ManageNetwork;
+use parity_rpc::limits::{RequestLimits, SharedLimits};
+use cli::{Args, flag_name};
+use configuration::Configuration;
+use params::GasPricerConfig;
+use reserved_peers::ReservedPeersWatcher;
+use util::Mutex;
+
+const RELOAD_TIMER: TimerToken = 0;
+const RELOAD_TIMER_INTERVAL_MS: u64 = 1000;
+
+const LOGGING_FLAGS: &'static [&'static str] = &["flag_logging"];
+const PEER_FLAGS: &'static [&'static str] = &["flag_peers", "flag_min_peers", "flag_max_peers"];
+const GAS_PRICE_FLAGS: &'static [&'static str] = &["flag_gasprice", "flag_usd_per_tx", "flag_usd_per_eth", "flag_price_update_period"];
+const LIMIT_FLAGS: &'static [&'static str] = &["flag_jsonrpc_max_batch_size", "flag_jsonrpc_max_request_size", "flag_jsonrpc_max_response_size"];
+
+static RELOAD_REQUESTED: AtomicBool = ATOMIC_BOOL_INIT;
+
+/// Settings which can be changed while the node is running.
+#[derive(Debug, PartialEq)]
+pub struct Settings {
+	/// Logging targets given with `--logging`.
+	pub logging: Option<String>,
+	/// Minimal number of connected peers.
+	pub min_peers: u32,
+	/// Maximal number of connected peers.
+	pub max_peers: u32,
+	/// Only a fixed minimal gas price can be changed.
+	pub gas_pricer: GasPricerConfig,
+	/// Limits of HTTP JSON-RPC requests.
+	pub http_limits: RequestLimits,
+}
+
+/// Request a reload on SIGHUP instead of terminating.
+#[cfg(unix)]
+pub fn set_reload_handler() {
+	extern "C" fn handle_sighup(_: ::libc::c_int) {
+		RELOAD_REQUESTED.store(true, Ordering::SeqCst);
+	}
+
+	unsafe { ::libc::signal(::libc::SIGHUP, handle_sighup as ::libc::sighandler_t); }
+}
+
+/// SIGHUP is not available on this platform.
+#[cfg(not(unix))]
+pub fn set_reload_handler() {}
+
+/// Changed flags which can't be applied without a restart.
+fn restart_required(changed: &[&'static str], old: &Settings, new: &Settings) -> Vec<String> {
+	let gas_price_reloadable = match (&old.gas_pricer, &new.gas_pricer) {
+		(&GasPricerConfig::Fixed(_), &GasPricerConfig::Fixed(_)) => true,
+		_ => false,
+	};
+
+	changed.iter()
+		.filter(|flag| !LOGGING_FLAGS.contains(flag) && !PEER_FLAGS.contains(flag) && !LIMIT_FLAGS.contains(flag))
+		.filter(|flag| !gas_price_reloadable || !GAS_PRICE_FLAGS.contains(flag))
+		.map(|flag| flag_name(flag))
+		.collect()
+}
+
+/// Re-reads the config file on SIGHUP and applies the settings which can change at runtime.
+pub struct ConfigReloader {
+	argv: Vec<String>,
+	/// Flags the node was started with.
+	args: Args,
+	/// Settings currently in effect.
+	current: Mutex<Settings>,
+	logger: Arc<RotatingLogger>,
+	net: Arc<ManageNetwork>,
+	miner: Arc<Miner>,
+	http_limits: SharedLimits,
+	reserved_peers: Option<Arc<ReservedPeersWatcher>>,
+}
+
+impl ConfigReloader {
+	/// Create a new reloader. `argv` is the command line the node was started with.
+	pub fn new(
+		argv: Vec<String>,
+		logger: Arc<RotatingLogger>,
+		net: Arc<ManageNetwork>,
+		miner: Arc<Miner>,
+		http_limits: SharedLimits,
+		reserved_peers: Option<Arc<ReservedPeersWatcher>>,
+	) -> Result<Self, String> {
+		let (args, current) = Self::load(&argv)?;
+		Ok(ConfigReloader {
+			argv: argv,
+			args: args,
+			current: Mutex::new(current),
+			logger: logger,
+			net: net,
+			miner: miner,
+			http_limits: http_limits,
+			reserved_peers: reserved_peers,
+		})
+	}
+
+	fn load(argv: &[String]) -> Result<(Args, Settings), String> {
+		let conf = Configuration::parse(argv, None).map_err(|e| format!("{:?}", e))?;
+		let settings = conf.reloadable_settings()?;
+		Ok((conf.args, settings))
+	}
+
+	/// Re-read the configuration and apply the changes. Changes which require a restart are only reported.
+	pub fn reload(&self) {
+		info!("Reloading configuration");
+		let (args, settings) = match Self::load(&self.argv) {
+			Ok(loaded) => loaded,
+			Err(e) => {
+				warn!("Configuration not reloaded: {}", e);
+				return;
+			},
+		};
+
+		let mut current = self.current.lock();
+		if current.logging != settings.logging {
+			let mut levels = env::var("RUST_LOG").map(|l| l + ",").unwrap_or_else(|_| String::new());
+			levels.push_str(settings.logging.as_ref().map_or("", |l| l.as_str()));
+			match self.logger.set_levels(&levels, None) {
+				Ok(()) => info!("Logging targets changed to {}", levels),
+				Err(e) => warn!("Logging targets not changed: {}", e),
+			}
+		}
+
+		if (current.min_peers, current.max_peers) != (settings.min_peers, settings.max_peers) {
+			self.net.set_peer_limits(settings.min_peers, settings.max_peers);
+			info!("Peer limits changed to {}-{}", settings.min_peers, settings.max_peers);
+		}
+
+		if let (&GasPricerConfig::Fixed(old), &GasPricerConfig::Fixed(new)) = (&current.gas_pricer, &settings.gas_pricer) {
+			if old != new {
+				self.miner.set_minimal_gas_price(new);
+				info!("Minimal gas price changed to {} wei", new);
+			}
+		}
+
+		if current.http_limits != settings.http_limits {
+			self.http_limits.set(settings.http_limits.clone());
+			info!("JSON-RPC request limits changed");
+		}
+
+		if let Some(ref reserved_peers) = self.reserved_peers {
+			reserved_peers.reload();
+		}
+
+		// changes are compared to the startup flags, so they are reported until the node is restarted
+		let changed = self.args.changed_flags(&args);
+		let restart = restart_required(&changed, &current, &settings);
+		if !restart.is_empty() {
+			warn!("Changes of {} require a restart and were not applied", restart.join(", "));
+		}
+
+		let gas_price_applied = match (&current.gas_pricer, &settings.gas_pricer) {
+			(&GasPricerConfig::Fixed(_), &GasPricerConfig::Fixed(_)) => true,
+			_ => false,
+		};
+		if gas_price_applied {
+			*current = settings;
+		} else {
+			current.logging = settings.logging;
+			current.min_peers = settings.min_peers;
+			current.max_peers = settings.max_peers;
+			current.http_limits = settings.http_limits;
+		}
+	}
+}
+
+impl IoHandler<ClientIoMessage> for ConfigReloader {
+	fn initialize(&self, io: &IoContext<ClientIoMessage>) {
+		io.register_timer(RELOAD_TIMER, RELOAD_TIMER_INTERVAL_MS).expect("Error registering timer");
+	}
+
+	fn timeout(&self, _io: &IoContext<ClientIoMessage>, timer: TimerToken) {
+		if timer == RELOAD_TIMER && RELOAD_REQUESTED.swap(false, Ordering::SeqCst) {
+			self.reload();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use params::GasPricerConfig;
+	use super::{Settings, restart_required};
+
+	fn settings(gas_pricer: GasPricerConfig) -> Settings {
+		Settings {
+			logging: None,
+			min_peers: 25,
+			max_peers: 50,
+			gas_pricer: gas_pricer,
+			http_limits: Default::default(),
+		}
+	}
+
+	fn calibrated() -> GasPricerConfig {
+		GasPricerConfig::Calibrated {
+			initial_minimum: 0.into(),
+			usd_per_tx: 0.0025,
+			recalibration_period: Duration::from_secs(3600),
+		}
+	}
+
+	#[test]
+	fn should_report_changes_requiring_restart() {
+		// given
+		let fixed = settings(GasPricerConfig::Fixed(1.into()));
+		let changed = vec!["flag_logging", "flag_max_peers", "flag_port", "flag_usd_per_tx", "flag_jsonrpc_max_batch_size"];
+
+		// then
+		assert_eq!(restart_required(&changed, &fixed, &settings(GasPricerConfig::Fixed(2.into()))), vec!["--port".to_owned()]);
+		assert_eq!(restart_required(&changed, &fixed, &settings(calibrated())), vec!["--port".to_owned(), "--usd-per-tx".to_owned()]);
+		assert_eq!(restart_required(&changed, &settings(calibrated()), &fixed), vec!["--port".to_owned(), "--usd-per-tx".to_owned()]);
+		assert!(restart_required(&[], &fixed, &fixed).is_empty());
+	}
+}
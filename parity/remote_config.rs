@@ -0,0 +1,194 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Config files fetched over HTTP(S).
+//!
+//! The detached signature of the config file at `URL` is fetched from `URL.sig`.
+//! It's created with `parity tools sign <file>` and must match the key given with
+//! `--config-public-key`. The signature file contains the version of the config file,
+//! the time the signature expires at and the hex encoded signature of the Keccak hash
+//! of the version, expiry and the file. Expired signatures are rejected, as well as
+//! config files older than the last one accepted from the same URL.
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use ethkey::{self, KeyPair, Public, Secret, Signature};
+use hash_fetch::fetch::{Fetch, Client as FetchClient};
+use util::{H256, ToPretty};
+use util::sha3::Hashable;
+
+/// Suffix of the URL of the detached signature.
+pub const SIGNATURE_SUFFIX: &'static str = ".sig";
+
+/// Default validity of created signatures, in days.
+pub const DEFAULT_VALIDITY_DAYS: u64 = 30;
+
+/// Maximal size of a fetched config file.
+const MAX_SIZE: usize = 1024 * 1024;
+
+/// Directory with the versions of accepted config files, relative to the data path.
+const VERSIONS_DIR: &'static str = "remote-config";
+
+/// Checks if the config file has to be fetched.
+pub fn is_remote(path: &str) -> bool {
+	path.starts_with("http://") || path.starts_with("https://")
+}
+
+fn fetch_text(client: &FetchClient, url: &str) -> Result<String, String> {
+	let mut response = client.fetch_sync(url).map_err(|e| format!("Error fetching {}: {:?}", url, e))?;
+	if !response.is_success() {
+		return Err(format!("Error fetching {}: {}", url, response.status()));
+	}
+
+	let mut content = String::new();
+	response.read_to_string(&mut content).map_err(|e| format!("Error fetching {}: {}", url, e))?;
+	Ok(content)
+}
+
+/// Current time in seconds since the epoch, as used for the expiry of signatures.
+pub fn now() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Hash of the signed payload.
+fn payload_hash(config: &str, version: u64, expires: u64) -> H256 {
+	format!("{}\n{}\n{}", version, expires, config).sha3()
+}
+
+/// Verify the signature file `<version> <expires> <signature>` of the config file at time `now`.
+/// Returns the version of the config file.
+pub fn verify(config: &str, signature: &str, public: &Public, now: u64) -> Result<u64, String> {
+	let invalid = || "Invalid signature of the config file, re-sign it with `parity tools sign`".to_owned();
+	let parts: Vec<_> = signature.split_whitespace().collect();
+	if parts.len() != 3 {
+		return Err(invalid());
+	}
+	let version: u64 = parts[0].parse().map_err(|_| invalid())?;
+	let expires: u64 = parts[1].parse().map_err(|_| invalid())?;
+	let signature: Signature = parts[2].trim_left_matches("0x").parse().map_err(|_| invalid())?;
+	match ethkey::verify_public(public, &signature, &payload_hash(config, version, expires)) {
+		Ok(true) => {},
+		_ => return Err("Signature of the config file doesn't match --config-public-key".into()),
+	}
+	if expires <= now {
+		return Err("Signature of the config file has expired, re-sign it with `parity tools sign`".into());
+	}
+	Ok(version)
+}
+
+/// Reject a config file older than the last one accepted from the URL, then remember its version.
+fn check_version(data_path: &Path, url: &str, version: u64) -> Result<(), String> {
+	let dir = data_path.join(VERSIONS_DIR);
+	let path = dir.join(url.sha3().hex());
+	let mut content = String::new();
+	let last = match File::open(&path).and_then(|mut f| f.read_to_string(&mut content)) {
+		Ok(_) => content.trim().parse::<u64>().map_err(|_| format!("Invalid config file version in {}", path.display()))?,
+		Err(_) => 0,
+	};
+	if version < last {
+		return Err(format!("Config file version {} is older than the previously accepted version {}", version, last));
+	}
+	if version > last {
+		fs::create_dir_all(&dir)
+			.and_then(|_| File::create(&path))
+			.and_then(|mut f| f.write_all(version.to_string().as_bytes()))
+			.map_err(|e| format!("Unable to store the config file version in {}: {}", path.display(), e))?;
+	}
+	Ok(())
+}
+
+/// Fetch the config file and its signature, returns the verified content.
+/// Versions of accepted config files are stored under `data_path`.
+pub fn fetch(url: &str, public: &str, data_path: &Path) -> Result<String, String> {
+	let public: Public = public.trim_left_matches("0x").parse()
+		.map_err(|_| format!("Invalid config public key: {}", public))?;
+	let client = FetchClient::with_limit(Some(MAX_SIZE)).map_err(|e| format!("Error starting fetch client: {:?}", e))?;
+
+	let config = fetch_text(&client, url)?;
+	let signature = fetch_text(&client, &format!("{}{}", url, SIGNATURE_SUFFIX))?;
+	let version = verify(&config, &signature, &public, now()).map_err(|e| format!("{}: {}", url, e))?;
+	check_version(data_path, url, version).map_err(|e| format!("{}: {}", url, e))?;
+	Ok(config)
+}
+
+/// Sign the config file with given version and expiry, returns the content of the signature file.
+pub fn sign(config: &str, secret: &Secret, version: u64, expires: u64) -> Result<String, String> {
+	let signature = ethkey::sign(secret, &payload_hash(config, version, expires))
+		.map_err(|e| format!("Unable to sign the config file: {}", e))?;
+	Ok(format!("{} {} {}", version, expires, signature))
+}
+
+/// Print the signature of the config file and the public key to verify it with.
+/// The version is the current time, so that a newly signed file always supersedes the previous one.
+pub fn execute_sign(file: String, key_file: Option<String>, valid_for_days: Option<u64>) -> Result<String, String> {
+	let read = |path: &str| {
+		let mut content = String::new();
+		File::open(path).and_then(|mut f| f.read_to_string(&mut content))
+			.map_err(|e| format!("Unable to read {}: {}", path, e))
+			.map(|_| content)
+	};
+
+	let key_file = key_file.ok_or_else(|| "Signing key is required, use --config-signing-key".to_owned())?;
+	let secret: Secret = read(&key_file)?.trim().trim_left_matches("0x").parse()
+		.map_err(|_| format!("Invalid secret key in {}", key_file))?;
+	let public = KeyPair::from_secret(secret.clone()).map_err(|e| format!("Invalid secret key in {}: {}", key_file, e))?.public().clone();
+	let version = now();
+	let expires = version.saturating_add(valid_for_days.unwrap_or(DEFAULT_VALIDITY_DAYS).saturating_mul(24 * 60 * 60));
+	let signature = sign(&read(&file)?, &secret, version, expires)?;
+	Ok(format!("{}\n\nStore the signature as {}{} next to the config file, verify it with --config-public-key {:?}", signature, file, SIGNATURE_SUFFIX, public))
+}
+
+#[cfg(test)]
+mod tests {
+	use devtools::RandomTempPath;
+	use ethkey::{Random, Generator};
+	use super::{is_remote, sign, verify, check_version};
+
+	#[test]
+	fn should_verify_signature_of_config() {
+		// given
+		let pair = Random.generate().unwrap();
+		let other = Random.generate().unwrap();
+		let config = "[network]\nmax_peers = 50\n";
+		let signature = sign(config, pair.secret(), 7, 1000).unwrap();
+
+		// then
+		assert_eq!(verify(config, &signature, pair.public(), 999), Ok(7));
+		assert_eq!(verify(config, &format!("{}\n", signature), pair.public(), 999), Ok(7));
+		assert!(verify(config, &signature, pair.public(), 1000).is_err());
+		assert!(verify(config, &signature.replacen("1000", "2000", 1), pair.public(), 999).is_err());
+		assert!(verify(config, &signature.replacen("7", "8", 1), pair.public(), 999).is_err());
+		assert!(verify("[network]\nmax_peers = 500\n", &signature, pair.public(), 999).is_err());
+		assert!(verify(config, &signature, other.public(), 999).is_err());
+		assert!(verify(config, "7 1000 00ff", pair.public(), 999).is_err());
+		assert!(is_remote("https://example.com/node.toml"));
+		assert!(!is_remote("/etc/parity/config.toml"));
+	}
+
+	#[test]
+	fn should_reject_rollback_of_config() {
+		let temp = RandomTempPath::create_dir();
+		let url = "https://example.com/node.toml";
+		assert!(check_version(temp.as_path(), url, 5).is_ok());
+		assert!(check_version(temp.as_path(), url, 5).is_ok());
+		assert!(check_version(temp.as_path(), url, 4).is_err());
+		assert!(check_version(temp.as_path(), "https://example.com/other.toml", 4).is_ok());
+		assert!(check_version(temp.as_path(), url, 6).is_ok());
+		assert!(check_version(temp.as_path(), url, 5).is_err());
+	}
+}
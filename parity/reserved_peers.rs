@@ -0,0 +1,170 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reserved peers file loading and live reloading.
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::sync::Arc;
+use std::time::SystemTime;
+use io::{TimerToken, IoContext, IoHandler};
+use ethcore::service::ClientIoMessage;
+use ethsync::{ManageNetwork, is_valid_node_url};
+use util::Mutex;
+
+const RELOAD_TIMER: TimerToken = 0;
+const RELOAD_TIMER_INTERVAL_MS: u64 = 5000;
+
+/// Read reserved peers from file, one enode URL per line.
+pub fn read_reserved_peers(path: &str) -> Result<Vec<String>, String> {
+	let mut buffer = String::new();
+	let mut node_file = File::open(path).map_err(|e| format!("Error opening reserved nodes file: {}", e))?;
+	node_file.read_to_string(&mut buffer).map_err(|_| "Error reading reserved node file")?;
+	let lines = buffer.lines().map(|s| s.trim().to_owned()).filter(|s| !s.is_empty()).collect::<Vec<_>>();
+	if let Some(invalid) = lines.iter().find(|s| !is_valid_node_url(s)) {
+		return Err(format!("Invalid node address format given for a boot node: {}", invalid));
+	}
+	Ok(lines)
+}
+
+/// Watches the reserved peers file and applies additions and removals to the running network.
+pub struct ReservedPeersWatcher {
+	path: String,
+	net: Arc<ManageNetwork>,
+	peers: Mutex<HashSet<String>>,
+	modified: Mutex<Option<SystemTime>>,
+}
+
+impl ReservedPeersWatcher {
+	/// Create a new watcher. `peers` are the reserved peers the network was started with.
+	pub fn new(path: String, peers: Vec<String>, net: Arc<ManageNetwork>) -> Self {
+		let modified = modified_time(&path);
+		ReservedPeersWatcher {
+			path: path,
+			net: net,
+			peers: Mutex::new(peers.into_iter().collect()),
+			modified: Mutex::new(modified),
+		}
+	}
+
+	/// Reload the file if it was modified since the last check.
+	pub fn check(&self) {
+		let modified = modified_time(&self.path);
+		if modified.is_none() || *self.modified.lock() == modified {
+			return;
+		}
+		*self.modified.lock() = modified;
+		self.reload();
+	}
+
+	/// Reload the file and apply the changes. Invalid files are ignored and the current set is kept.
+	pub fn reload(&self) {
+		let new_peers: HashSet<String> = match read_reserved_peers(&self.path) {
+			Ok(peers) => peers.into_iter().collect(),
+			Err(e) => {
+				warn!("Reserved peers file not reloaded: {}", e);
+				return;
+			}
+		};
+
+		let mut peers = self.peers.lock();
+		for peer in peers.difference(&new_peers) {
+			if let Err(e) = self.net.remove_reserved_peer(peer.clone()) {
+				warn!("Error removing reserved peer {}: {}", peer, e);
+			}
+		}
+		for peer in new_peers.difference(&peers) {
+			if let Err(e) = self.net.add_reserved_peer(peer.clone()) {
+				warn!("Error adding reserved peer {}: {}", peer, e);
+			}
+		}
+		info!("Reserved peers reloaded: {} peers", new_peers.len());
+		*peers = new_peers;
+	}
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+	fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl IoHandler<ClientIoMessage> for ReservedPeersWatcher {
+	fn initialize(&self, io: &IoContext<ClientIoMessage>) {
+		io.register_timer(RELOAD_TIMER, RELOAD_TIMER_INTERVAL_MS).expect("Error registering timer");
+	}
+
+	fn timeout(&self, _io: &IoContext<ClientIoMessage>, timer: TimerToken) {
+		if timer == RELOAD_TIMER {
+			self.check();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+	use std::fs::File;
+	use std::io::Write;
+	use std::sync::Arc;
+	use devtools::RandomTempPath;
+	use ethsync::{ManageNetwork, NetworkConfiguration, PortMapping, NetworkTraffic};
+	use util::Mutex;
+	use super::ReservedPeersWatcher;
+
+	const NODE1: &'static str = "enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770";
+	const NODE2: &'static str = "enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770";
+
+	#[derive(Default)]
+	struct TestNetwork {
+		added: Mutex<Vec<String>>,
+		removed: Mutex<Vec<String>>,
+	}
+
+	impl ManageNetwork for TestNetwork {
+		fn accept_unreserved_peers(&self) { }
+		fn deny_unreserved_peers(&self) { }
+		fn remove_reserved_peer(&self, peer: String) -> Result<(), String> { self.removed.lock().push(peer); Ok(()) }
+		fn add_reserved_peer(&self, peer: String) -> Result<(), String> { self.added.lock().push(peer); Ok(()) }
+		fn start_network(&self) {}
+		fn stop_network(&self) {}
+		fn network_config(&self) -> NetworkConfiguration { NetworkConfiguration::new_local() }
+		fn port_mapping(&self) -> Option<PortMapping> { None }
+		fn ip_limited_peers(&self) -> usize { 0 }
+		fn traffic(&self) -> NetworkTraffic { Default::default() }
+		fn protocol_traffic(&self) -> BTreeMap<String, NetworkTraffic> { BTreeMap::new() }
+		fn set_peer_limits(&self, _min_peers: u32, _max_peers: u32) { }
+	}
+
+	#[test]
+	fn should_apply_reserved_peers_changes() {
+		let path = RandomTempPath::new();
+		let path_str = path.as_str().to_owned();
+		File::create(&path_str).unwrap().write_all(format!("{}\n", NODE1).as_bytes()).unwrap();
+
+		let net = Arc::new(TestNetwork::default());
+		let watcher = ReservedPeersWatcher::new(path_str.clone(), vec![NODE1.into()], net.clone());
+
+		File::create(&path_str).unwrap().write_all(format!("{}\n\n{}\n", NODE2, "invalid").as_bytes()).unwrap();
+		watcher.reload();
+		assert!(net.added.lock().is_empty());
+		assert!(net.removed.lock().is_empty());
+
+		File::create(&path_str).unwrap().write_all(format!("{}\n", NODE2).as_bytes()).unwrap();
+		watcher.reload();
+		assert_eq!(*net.added.lock(), vec![NODE2.to_owned()]);
+		assert_eq!(*net.removed.lock(), vec![NODE1.to_owned()]);
+	}
+}
@@ -15,16 +15,19 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::io;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use dapps;
 use dir::default_data_path;
 use parity_rpc::informant::{RpcStats, Middleware};
-use parity_rpc::{self as rpc, HttpServerError, Metadata, Origin, DomainsValidation};
+use parity_rpc::limits::{RequestLimits, SharedLimits};
+use parity_rpc::access::AccessRule;
+use parity_rpc::{self as rpc, HttpServerError, Metadata, Origin, DomainsValidation, PubSubSession};
 use helpers::parity_ipc_path;
 use jsonrpc_core::MetaIoHandler;
 use parity_reactor::TokioRemote;
-use rpc_apis::{self, ApiSet};
+use rpc_apis::{self, Api, ApiSet, ApiAccess};
 
 pub use parity_rpc::{IpcServer, HttpServer, RequestMiddleware};
 pub use parity_rpc::ws::Server as WsServer;
@@ -37,7 +40,10 @@ pub struct HttpConfiguration {
 	pub apis: ApiSet,
 	pub cors: Option<Vec<String>>,
 	pub hosts: Option<Vec<String>>,
+	pub api_cors: HashMap<Api, Option<Vec<String>>>,
+	pub api_hosts: HashMap<Api, Option<Vec<String>>>,
 	pub threads: Option<usize>,
+	pub limits: RequestLimits,
 }
 
 impl Default for HttpConfiguration {
@@ -49,7 +55,10 @@ impl Default for HttpConfiguration {
 			apis: ApiSet::UnsafeContext,
 			cors: None,
 			hosts: Some(Vec::new()),
+			api_cors: Default::default(),
+			api_hosts: Default::default(),
 			threads: None,
+			limits: Default::default(),
 		}
 	}
 }
@@ -88,7 +97,11 @@ impl Default for WsConfiguration {
 			enabled: true,
 			interface: "127.0.0.1".into(),
 			port: 8546,
-			apis: ApiSet::UnsafeContext,
+			apis: ApiSet::List({
+				let mut apis = ApiSet::UnsafeContext.list_apis();
+				apis.insert(Api::PubSub);
+				apis
+			}),
 			origins: Some(Vec::new()),
 			hosts: Some(Vec::new()),
 		}
@@ -99,19 +112,22 @@ pub struct Dependencies<D: rpc_apis::Dependencies> {
 	pub apis: Arc<D>,
 	pub remote: TokioRemote,
 	pub stats: Arc<RpcStats>,
+	/// Limits of requests on all transports, shared so they can be changed at runtime.
+	pub limits: SharedLimits,
 }
 
 pub struct RpcExtractor;
 impl rpc::HttpMetaExtractor for RpcExtractor {
 	type Metadata = Metadata;
 
-	fn read_metadata(&self, origin: String, dapps_origin: Option<String>) -> Metadata {
+	fn read_metadata(&self, origin: String, host: Option<String>, dapps_origin: Option<String>) -> Metadata {
 		let mut metadata = Metadata::default();
 
 		metadata.origin = match (origin.as_str(), dapps_origin) {
 			("null", Some(dapp)) => Origin::Dapps(dapp.into()),
 			_ => Origin::Rpc(origin),
 		};
+		metadata.host = host;
 
 		metadata
 	}
@@ -131,6 +147,7 @@ impl rpc::ws::MetaExtractor<Metadata> for RpcExtractor {
 		let mut metadata = Metadata::default();
 		let id = req.session_id as u64;
 		metadata.origin = Origin::Ws(id.into());
+		metadata.session = Some(Arc::new(PubSubSession::new(req.sender())));
 		metadata
 	}
 }
@@ -149,10 +166,10 @@ impl rpc::ws::SessionStats for WsStats {
 	}
 }
 
-fn setup_apis<D>(apis: ApiSet, deps: &Dependencies<D>) -> MetaIoHandler<Metadata, Middleware<D::Notifier>>
+fn setup_apis<D>(apis: ApiSet, limits: SharedLimits, access: &ApiAccess, deps: &Dependencies<D>) -> MetaIoHandler<Metadata, Middleware<D::Notifier>>
 	where D: rpc_apis::Dependencies
 {
-	rpc_apis::setup_rpc(deps.stats.clone(), &*deps.apis, apis)
+	rpc_apis::setup_rpc(deps.stats.clone(), &*deps.apis, apis, limits, access)
 }
 
 pub fn new_ws<D: rpc_apis::Dependencies>(
@@ -165,7 +182,7 @@ pub fn new_ws<D: rpc_apis::Dependencies>(
 
 	let url = format!("{}:{}", conf.interface, conf.port);
 	let addr = url.parse().map_err(|_| format!("Invalid WebSockets listen host/port given: {}", url))?;
-	let handler = setup_apis(conf.apis, deps);
+	let handler = setup_apis(conf.apis, deps.limits.clone(), &Default::default(), deps);
 	let remote = deps.remote.clone();
 	let allowed_origins = into_domains(conf.origins);
 	let allowed_hosts = into_domains(conf.hosts);
@@ -202,11 +219,15 @@ pub fn new_http<D: rpc_apis::Dependencies>(
 
 	let url = format!("{}:{}", conf.interface, conf.port);
 	let addr = url.parse().map_err(|_| format!("Invalid HTTP JSON-RPC listen host/port given: {}", url))?;
-	let handler = setup_apis(conf.apis, deps);
+	let (cors, hosts, access) = match conf.api_cors.is_empty() && conf.api_hosts.is_empty() {
+		true => (conf.cors.clone(), conf.hosts.clone(), ApiAccess::default()),
+		false => api_access(&conf, &url),
+	};
+	let handler = setup_apis(conf.apis, deps.limits.clone(), &access, deps);
 	let remote = deps.remote.clone();
 
-	let cors_domains = into_domains(conf.cors);
-	let allowed_hosts = into_domains(conf.hosts);
+	let cors_domains = into_domains(cors);
+	let allowed_hosts = into_domains(hosts);
 
 	let start_result = rpc::start_http(
 		&addr,
@@ -215,6 +236,7 @@ pub fn new_http<D: rpc_apis::Dependencies>(
 		handler,
 		remote,
 		RpcExtractor,
+		deps.limits.clone(),
 		match (conf.threads, middleware) {
 			(Some(threads), None) => rpc::HttpSettings::Threads(threads),
 			(None, middleware) => rpc::HttpSettings::Dapps(middleware),
@@ -233,6 +255,41 @@ pub fn new_http<D: rpc_apis::Dependencies>(
 	}
 }
 
+/// Combines global and per-API CORS and hosts settings.
+/// Returns server-wide CORS domains and hosts (permitting requests allowed by any of the APIs)
+/// together with the rules for each API, which are validated for every method call.
+fn api_access(conf: &HttpConfiguration, address: &str) -> (Option<Vec<String>>, Option<Vec<String>>, ApiAccess) {
+	// Server address is always a valid host (same as for the server-wide validation).
+	let with_address = |hosts: Option<Vec<String>>| hosts.map(|mut hosts| {
+		hosts.push(address.into());
+		hosts.push(address.replace("127.0.0.1", "localhost"));
+		hosts
+	});
+
+	let access = conf.apis.list_apis().into_iter().map(|api| {
+		let rule = AccessRule {
+			// Without CORS headers browsers are not able to use the API at all.
+			origins: conf.api_cors.get(&api).cloned().unwrap_or_else(|| conf.cors.clone().or_else(|| Some(Vec::new()))),
+			hosts: with_address(conf.api_hosts.get(&api).cloned().unwrap_or_else(|| conf.hosts.clone())),
+		};
+		(api, rule)
+	}).collect::<ApiAccess>();
+
+	let union = |lists: Vec<&Option<Vec<String>>>| -> Option<Vec<String>> {
+		if lists.iter().any(|list| list.is_none()) {
+			return None;
+		}
+		let mut union = lists.into_iter().flat_map(|list| list.iter().flat_map(|v| v.iter().cloned())).collect::<Vec<_>>();
+		union.sort();
+		union.dedup();
+		Some(union)
+	};
+
+	let cors = union(access.values().map(|rule| &rule.origins).collect()).or_else(|| Some(vec!["*".into()]));
+	let hosts = union(access.values().map(|rule| &rule.hosts).collect());
+	(cors, hosts, access)
+}
+
 fn into_domains<T: From<String>>(items: Option<Vec<String>>) -> DomainsValidation<T> {
 	items.map(|vals| vals.into_iter().map(T::from).collect()).into()
 }
@@ -245,7 +302,7 @@ pub fn new_ipc<D: rpc_apis::Dependencies>(
 		return Ok(None);
 	}
 
-	let handler = setup_apis(conf.apis, dependencies);
+	let handler = setup_apis(conf.apis, dependencies.limits.clone(), &Default::default(), dependencies);
 	let remote = dependencies.remote.clone();
 	match rpc::start_ipc(&conf.socket_addr, handler, remote, RpcExtractor) {
 		Ok(server) => Ok(Some(server)),
@@ -255,8 +312,9 @@ pub fn new_ipc<D: rpc_apis::Dependencies>(
 
 #[cfg(test)]
 mod tests {
-	use super::RpcExtractor;
+	use super::{RpcExtractor, HttpConfiguration, api_access};
 	use parity_rpc::{HttpMetaExtractor, Origin};
+	use rpc_apis::{Api, ApiSet};
 
 	#[test]
 	fn should_extract_rpc_origin() {
@@ -264,12 +322,14 @@ mod tests {
 		let extractor = RpcExtractor;
 
 		// when
-		let meta = extractor.read_metadata("http://parity.io".into(), None);
-		let meta1 = extractor.read_metadata("http://parity.io".into(), Some("ignored".into()));
+		let meta = extractor.read_metadata("http://parity.io".into(), None, None);
+		let meta1 = extractor.read_metadata("http://parity.io".into(), Some("localhost:8545".into()), Some("ignored".into()));
 
 		// then
 		assert_eq!(meta.origin, Origin::Rpc("http://parity.io".into()));
+		assert_eq!(meta.host, None);
 		assert_eq!(meta1.origin, Origin::Rpc("http://parity.io".into()));
+		assert_eq!(meta1.host, Some("localhost:8545".into()));
 	}
 
 	#[test]
@@ -279,9 +339,54 @@ mod tests {
 		let dapp = "https://wallet.ethereum.org".to_owned();
 
 		// when
-		let meta = extractor.read_metadata("null".into(), Some(dapp.clone()));
+		let meta = extractor.read_metadata("null".into(), None, Some(dapp.clone()));
 
 		// then
 		assert_eq!(meta.origin, Origin::Dapps(dapp.into()));
 	}
+
+	#[test]
+	fn should_combine_api_access_rules() {
+		// given
+		let conf = HttpConfiguration {
+			apis: ApiSet::List(vec![Api::Eth, Api::Personal, Api::ParitySet].into_iter().collect()),
+			cors: Some(vec!["http://parity.io".into()]),
+			hosts: Some(vec![]),
+			api_cors: vec![(Api::Personal, Some(vec![]))].into_iter().collect(),
+			api_hosts: vec![(Api::Eth, None)].into_iter().collect(),
+			..Default::default()
+		};
+
+		// when
+		let (cors, hosts, access) = api_access(&conf, "127.0.0.1:8545");
+
+		// then
+		assert_eq!(cors, Some(vec!["http://parity.io".into()]));
+		assert_eq!(hosts, None);
+		assert_eq!(access.len(), 3);
+		assert_eq!(access[&Api::Eth].origins, Some(vec!["http://parity.io".into()]));
+		assert_eq!(access[&Api::Eth].hosts, None);
+		assert_eq!(access[&Api::Personal].origins, Some(vec![]));
+		assert_eq!(access[&Api::ParitySet].hosts, Some(vec!["127.0.0.1:8545".into(), "localhost:8545".into()]));
+	}
+
+	#[test]
+	fn should_allow_all_origins_on_server_if_any_api_allows_them() {
+		// given
+		let conf = HttpConfiguration {
+			apis: ApiSet::List(vec![Api::Eth, Api::Personal].into_iter().collect()),
+			cors: None,
+			api_cors: vec![(Api::Eth, None)].into_iter().collect(),
+			..Default::default()
+		};
+
+		// when
+		let (cors, hosts, access) = api_access(&conf, "127.0.0.1:8545");
+
+		// then
+		assert_eq!(cors, Some(vec!["*".into()]));
+		assert_eq!(hosts, Some(vec!["127.0.0.1:8545".into(), "localhost:8545".into()]));
+		assert_eq!(access[&Api::Eth].origins, None);
+		assert_eq!(access[&Api::Personal].origins, Some(vec![]));
+	}
 }
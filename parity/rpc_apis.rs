@@ -16,9 +16,11 @@
 
 use std::cmp::PartialEq;
 use std::collections::BTreeMap;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
 
 pub use parity_rpc::SignerService;
 
@@ -28,11 +30,19 @@ use ethcore::miner::{Miner, ExternalMiner};
 use ethcore::snapshot::SnapshotService;
 use parity_rpc::{Metadata, NetworkSettings};
 use parity_rpc::informant::{ActivityNotifier, Middleware, RpcStats, ClientNotifier};
+use parity_rpc::limits::SharedLimits;
+use parity_rpc::access::{AccessPolicy, AccessRule};
+use parity_rpc::offline::OfflinePolicy;
+use parity_rpc::fallback::FallbackPolicy;
+use parity_rpc::wake::WakePolicy;
 use parity_rpc::dispatch::{FullDispatcher, LightDispatcher};
+use parity_rpc::v1::PubSubClient;
+use parity_rpc::v1::light::PubSubClient as LightPubSubClient;
 use ethsync::{ManageNetwork, SyncProvider, LightSync};
 use hash_fetch::fetch::Client as FetchClient;
-use jsonrpc_core::{MetaIoHandler};
+use jsonrpc_core::{self as rpc, MetaIoHandler, RemoteProcedure};
 use light::{TransactionQueue as LightTransactionQueue, Cache as LightDataCache};
+use parity_reactor::Remote;
 use updater::Updater;
 use util::{Mutex, RwLock};
 use ethcore_logger::RotatingLogger;
@@ -59,6 +69,10 @@ pub enum Api {
 	Traces,
 	/// Rpc (Safe)
 	Rpc,
+	/// Eth and Parity Pub-Sub (Safe)
+	PubSub,
+	/// Clique - Votes on signers (UNSAFE: Side Effects affecting consensus)
+	Clique,
 }
 
 impl FromStr for Api {
@@ -78,11 +92,19 @@ impl FromStr for Api {
 			"parity_set" => Ok(ParitySet),
 			"traces" => Ok(Traces),
 			"rpc" => Ok(Rpc),
+			"pubsub" => Ok(PubSub),
+			"clique" => Ok(Clique),
 			api => Err(format!("Unknown api: {}", api))
 		}
 	}
 }
 
+/// Origin and Host restrictions for particular APIs.
+pub type ApiAccess = HashMap<Api, AccessRule>;
+
+/// APIs waking the client from passive or dark mode and the idle time after their requests.
+pub type ApiWake = HashMap<Api, Duration>;
+
 #[derive(Debug, Clone)]
 pub enum ApiSet {
 	// Safe context (like token-protected WS interface)
@@ -156,12 +178,34 @@ fn to_modules(apis: &[Api]) -> BTreeMap<String, String> {
 			Api::ParitySet => ("parity_set", "1.0"),
 			Api::Traces => ("traces", "1.0"),
 			Api::Rpc => ("rpc", "1.0"),
+			Api::PubSub => ("pubsub", "1.0"),
+			Api::Clique => ("clique", "1.0"),
 		};
 		modules.insert(name.into(), version.into());
 	}
 	modules
 }
 
+/// Collection that RPC endpoints can be added to.
+pub trait ExtendWith {
+	/// Add given methods to the collection.
+	fn extend_with<F>(&mut self, methods: F) where F: Into<HashMap<String, RemoteProcedure<Metadata>>>;
+}
+
+impl<S: rpc::Middleware<Metadata>> ExtendWith for MetaIoHandler<Metadata, S> {
+	fn extend_with<F>(&mut self, methods: F) where F: Into<HashMap<String, RemoteProcedure<Metadata>>> {
+		MetaIoHandler::extend_with(self, methods)
+	}
+}
+
+/// Collects only the names of methods.
+impl ExtendWith for HashSet<String> {
+	fn extend_with<F>(&mut self, methods: F) where F: Into<HashMap<String, RemoteProcedure<Metadata>>> {
+		let methods: HashMap<_, _> = methods.into();
+		self.extend(methods.into_iter().map(|(name, _)| name));
+	}
+}
+
 /// RPC dependencies can be used to initialize RPC endpoints from APIs.
 pub trait Dependencies {
 	type Notifier: ActivityNotifier;
@@ -170,7 +214,22 @@ pub trait Dependencies {
 	fn activity_notifier(&self) -> Self::Notifier;
 
 	/// Extend the given I/O handler with endpoints for each API.
-	fn extend_with_set(&self, handler: &mut MetaIoHandler<Metadata, Middleware<Self::Notifier>>, apis: &[Api]);
+	fn extend_with_set<H: ExtendWith>(&self, handler: &mut H, apis: &[Api]);
+
+	/// APIs waking the client. Any request keeps the client awake if empty.
+	fn wake_on_rpc(&self) -> ApiWake {
+		ApiWake::new()
+	}
+
+	/// Whether the node serves the local database only and never starts the network.
+	fn offline_serve(&self) -> bool {
+		false
+	}
+
+	/// Forwarding of calls which can't be served locally.
+	fn fallback_policy(&self) -> FallbackPolicy {
+		FallbackPolicy::default()
+	}
 }
 
 /// RPC dependencies for a full node.
@@ -191,18 +250,43 @@ pub struct FullDependencies {
 	pub dapps_interface: Option<String>,
 	pub dapps_port: Option<u16>,
 	pub fetch: FetchClient,
+	pub remote: Remote,
+	pub wake_on_rpc: ApiWake,
+	pub offline_serve: bool,
+	/// Pub-sub client shared by all servers, created on first use.
+	pub pubsub: Mutex<Option<PubSubClient<Client, SyncProvider, SnapshotService>>>,
+}
+
+impl FullDependencies {
+	/// Pub-sub client shared by all servers, so that a single thread samples the sync status.
+	fn pubsub_client(&self) -> PubSubClient<Client, SyncProvider, SnapshotService> {
+		let mut pubsub = self.pubsub.lock();
+		if let Some(ref client) = *pubsub {
+			return client.clone();
+		}
+		let client = PubSubClient::new(&self.client, &self.sync, &self.snapshot, self.remote.clone());
+		self.miner.add_eviction_listener(client.eviction_listener());
+		*pubsub = Some(client.clone());
+		client
+	}
 }
 
 impl Dependencies for FullDependencies {
 	type Notifier = ClientNotifier;
 
 	fn activity_notifier(&self) -> ClientNotifier {
-		ClientNotifier {
-			client: self.client.clone(),
-		}
+		ClientNotifier::new(self.client.clone(), self.sync.clone())
+	}
+
+	fn wake_on_rpc(&self) -> ApiWake {
+		self.wake_on_rpc.clone()
 	}
 
-	fn extend_with_set(&self, handler: &mut MetaIoHandler<Metadata, Middleware>, apis: &[Api]) {
+	fn offline_serve(&self) -> bool {
+		self.offline_serve
+	}
+
+	fn extend_with_set<H: ExtendWith>(&self, handler: &mut H, apis: &[Api]) {
 		use parity_rpc::v1::*;
 
 		macro_rules! add_signing_methods {
@@ -286,6 +370,8 @@ impl Dependencies for FullDependencies {
 						&self.miner,
 						&self.updater,
 						&self.net_service,
+						&self.snapshot,
+						self.logger.clone(),
 						self.fetch.clone(),
 					).to_delegate())
 				},
@@ -295,7 +381,15 @@ impl Dependencies for FullDependencies {
 				Api::Rpc => {
 					let modules = to_modules(&apis);
 					handler.extend_with(RpcClient::new(modules).to_delegate());
-				}
+				},
+				Api::PubSub => {
+					let client = self.pubsub_client();
+					handler.extend_with(EthPubSub::to_delegate(client.clone()));
+					handler.extend_with(ParityPubSub::to_delegate(client));
+				},
+				Api::Clique => {
+					handler.extend_with(CliqueClient::new(&self.miner).to_delegate());
+				},
 			}
 		}
 	}
@@ -324,13 +418,51 @@ pub struct LightDependencies {
 	pub dapps_port: Option<u16>,
 	pub fetch: FetchClient,
 	pub geth_compatibility: bool,
+	pub fallback_url: Option<String>,
+	/// URL of the background full node in hybrid mode, whether it took over and
+	/// the APIs it serves over HTTP.
+	pub handover: Option<(String, Arc<AtomicBool>, ApiSet)>,
+	pub remote: Remote,
+	/// Pub-sub client shared by all servers, created on first use.
+	pub pubsub: Mutex<Option<LightPubSubClient>>,
+}
+
+impl LightDependencies {
+	/// Pub-sub client shared by all servers, so that a single thread samples the chain.
+	fn pubsub_client(&self) -> LightPubSubClient {
+		let mut pubsub = self.pubsub.lock();
+		if let Some(ref client) = *pubsub {
+			return client.clone();
+		}
+		let client = LightPubSubClient::new(self.client.clone(), self.sync.clone(), self.transaction_queue.clone(), self.remote.clone());
+		*pubsub = Some(client.clone());
+		client
+	}
 }
 
 impl Dependencies for LightDependencies {
 	type Notifier = LightClientNotifier;
 
 	fn activity_notifier(&self) -> Self::Notifier { LightClientNotifier }
-	fn extend_with_set(&self, handler: &mut MetaIoHandler<Metadata, Middleware<Self::Notifier>>, apis: &[Api]) {
+
+	fn fallback_policy(&self) -> FallbackPolicy {
+		let policy = match self.fallback_url {
+			Some(ref url) => FallbackPolicy::new(url.clone(), self.fetch.clone()),
+			None => FallbackPolicy::default(),
+		};
+		match self.handover {
+			Some((ref url, ref ready, ref apis)) => {
+				// subscriptions are never handed over, notifications can't be sent over HTTP.
+				let apis = apis.list_apis().into_iter().filter(|api| *api != Api::PubSub).collect::<Vec<_>>();
+				let mut methods = HashSet::new();
+				self.extend_with_set(&mut methods, &apis);
+				policy.with_handover(url.clone(), self.fetch.clone(), ready.clone(), methods)
+			},
+			None => policy,
+		}
+	}
+
+	fn extend_with_set<H: ExtendWith>(&self, handler: &mut H, apis: &[Api]) {
 		use parity_rpc::v1::*;
 
 		let dispatcher = LightDispatcher::new(
@@ -415,6 +547,7 @@ impl Dependencies for LightDependencies {
 				Api::ParitySet => {
 					handler.extend_with(light::ParitySetClient::new(
 						self.sync.clone(),
+						self.logger.clone(),
 						self.fetch.clone(),
 					).to_delegate())
 				},
@@ -424,7 +557,13 @@ impl Dependencies for LightDependencies {
 				Api::Rpc => {
 					let modules = to_modules(&apis);
 					handler.extend_with(RpcClient::new(modules).to_delegate());
-				}
+				},
+				Api::PubSub => {
+					handler.extend_with(EthPubSub::to_delegate(self.pubsub_client()));
+				},
+				Api::Clique => {
+					// light clients don't seal blocks, there are no votes to cast
+				},
 			}
 		}
 	}
@@ -437,6 +576,7 @@ impl ApiSet {
 	}
 
 	pub fn list_apis(&self) -> HashSet<Api> {
+		// subscriptions need a session, they are not available over HTTP by default.
 		let mut public_list = vec![
 			Api::Web3, Api::Net, Api::Eth, Api::Parity, Api::Rpc,
 		].into_iter().collect();
@@ -449,21 +589,26 @@ impl ApiSet {
 			},
 			ApiSet::IpcContext => {
 				public_list.insert(Api::Traces);
+				public_list.insert(Api::PubSub);
 				public_list.insert(Api::ParityAccounts);
 				public_list
 			},
 			ApiSet::SafeContext => {
 				public_list.insert(Api::Traces);
+				public_list.insert(Api::PubSub);
 				public_list.insert(Api::ParityAccounts);
 				public_list.insert(Api::ParitySet);
 				public_list.insert(Api::Signer);
+				public_list.insert(Api::Clique);
 				public_list
 			},
 			ApiSet::All => {
 				public_list.insert(Api::Traces);
+				public_list.insert(Api::PubSub);
 				public_list.insert(Api::ParityAccounts);
 				public_list.insert(Api::ParitySet);
 				public_list.insert(Api::Signer);
+				public_list.insert(Api::Clique);
 				public_list.insert(Api::Personal);
 				public_list
 			},
@@ -471,15 +616,56 @@ impl ApiSet {
 	}
 }
 
-pub fn setup_rpc<D: Dependencies>(stats: Arc<RpcStats>, deps: &D, apis: ApiSet) -> MetaIoHandler<Metadata, Middleware<D::Notifier>> {
-	let mut handler = MetaIoHandler::with_middleware(Middleware::new(stats, deps.activity_notifier()));
+pub fn setup_rpc<D: Dependencies>(
+	stats: Arc<RpcStats>,
+	deps: &D,
+	apis: ApiSet,
+	limits: SharedLimits,
+	access: &ApiAccess,
+) -> MetaIoHandler<Metadata, Middleware<D::Notifier>> {
 	// it's turned into vector, cause ont of the cases requires &[]
 	let apis = apis.list_apis().into_iter().collect::<Vec<_>>();
+	let middleware = Middleware::new(stats, deps.activity_notifier(), limits)
+		.with_access_policy(access_policy(deps, &apis, access))
+		.with_wake_policy(wake_policy(deps, &apis))
+		.with_offline_policy(match deps.offline_serve() {
+			true => OfflinePolicy::network_methods(),
+			false => OfflinePolicy::default(),
+		})
+		.with_fallback_policy(deps.fallback_policy());
+	let mut handler = MetaIoHandler::with_middleware(middleware);
 	deps.extend_with_set(&mut handler, &apis[..]);
 
 	handler
 }
 
+/// Applies access rules of each API to all of its methods.
+fn access_policy<D: Dependencies>(deps: &D, apis: &[Api], access: &ApiAccess) -> AccessPolicy {
+	let mut policy = AccessPolicy::default();
+	for api in apis {
+		if let Some(rule) = access.get(api) {
+			let mut methods = HashSet::new();
+			deps.extend_with_set(&mut methods, &[api.clone()]);
+			policy.restrict(methods, rule.clone());
+		}
+	}
+	policy
+}
+
+/// Wakes the client on all methods of the APIs configured to wake it.
+fn wake_policy<D: Dependencies>(deps: &D, apis: &[Api]) -> WakePolicy {
+	let wake_on_rpc = deps.wake_on_rpc();
+	let mut policy = WakePolicy::default();
+	for api in apis {
+		if let Some(idle) = wake_on_rpc.get(api) {
+			let mut methods = HashSet::new();
+			deps.extend_with_set(&mut methods, &[api.clone()]);
+			policy.wake_on(methods, *idle);
+		}
+	}
+	policy
+}
+
 #[cfg(test)]
 mod test {
 	use super::{Api, ApiSet};
@@ -496,6 +682,8 @@ mod test {
 		assert_eq!(Api::ParitySet, "parity_set".parse().unwrap());
 		assert_eq!(Api::Traces, "traces".parse().unwrap());
 		assert_eq!(Api::Rpc, "rpc".parse().unwrap());
+		assert_eq!(Api::PubSub, "pubsub".parse().unwrap());
+		assert_eq!(Api::Clique, "clique".parse().unwrap());
 		assert!("rp".parse::<Api>().is_err());
 	}
 
@@ -522,7 +710,7 @@ mod test {
 	fn test_api_set_ipc_context() {
 		let expected = vec![
 			// safe
-			Api::Web3, Api::Net, Api::Eth, Api::Parity, Api::Traces, Api::Rpc,
+			Api::Web3, Api::Net, Api::Eth, Api::Parity, Api::Traces, Api::Rpc, Api::PubSub,
 			// semi-safe
 			Api::ParityAccounts
 		].into_iter().collect();
@@ -533,11 +721,11 @@ mod test {
 	fn test_api_set_safe_context() {
 		let expected = vec![
 			// safe
-			Api::Web3, Api::Net, Api::Eth, Api::Parity, Api::Traces, Api::Rpc,
+			Api::Web3, Api::Net, Api::Eth, Api::Parity, Api::Traces, Api::Rpc, Api::PubSub,
 			// semi-safe
 			Api::ParityAccounts,
 			// Unsafe
-			Api::ParitySet, Api::Signer,
+			Api::ParitySet, Api::Signer, Api::Clique,
 		].into_iter().collect();
 		assert_eq!(ApiSet::SafeContext.list_apis(), expected);
 	}
@@ -545,9 +733,9 @@ mod test {
 	#[test]
 	fn test_all_apis() {
 		assert_eq!("all".parse::<ApiSet>().unwrap(), ApiSet::List(vec![
-			Api::Web3, Api::Net, Api::Eth, Api::Parity, Api::Traces, Api::Rpc,
+			Api::Web3, Api::Net, Api::Eth, Api::Parity, Api::Traces, Api::Rpc, Api::PubSub,
 			Api::ParityAccounts,
-			Api::ParitySet, Api::Signer,
+			Api::ParitySet, Api::Signer, Api::Clique,
 			Api::Personal
 		].into_iter().collect()));
 	}
@@ -555,9 +743,9 @@ mod test {
 	#[test]
 	fn test_all_without_personal_apis() {
 		assert_eq!("personal,all,-personal".parse::<ApiSet>().unwrap(), ApiSet::List(vec![
-			Api::Web3, Api::Net, Api::Eth, Api::Parity, Api::Traces, Api::Rpc,
+			Api::Web3, Api::Net, Api::Eth, Api::Parity, Api::Traces, Api::Rpc, Api::PubSub,
 			Api::ParityAccounts,
-			Api::ParitySet, Api::Signer,
+			Api::ParitySet, Api::Signer, Api::Clique,
 		].into_iter().collect()));
 	}
 
@@ -14,40 +14,59 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::env;
 use std::sync::Arc;
 use std::net::{TcpListener};
+use std::time::Duration;
 use ctrlc::CtrlC;
 use fdlimit::raise_fd_limit;
 use parity_rpc::{NetworkSettings, informant, is_major_importing};
+use parity_rpc::limits::SharedLimits;
 use ethsync::NetworkConfiguration;
 use util::{Colour, version, Mutex, Condvar};
 use io::{MayPanic, ForwardPanic, PanicHandler};
 use ethcore_logger::{Config as LogConfig, RotatingLogger};
 use ethcore::miner::{StratumOptions, Stratum};
-use ethcore::client::{Client, Mode, DatabaseCompactionProfile, VMType, BlockChainClient};
+use ethcore::client::{Client, Mode, DatabaseCompactionProfile, VMType, BlockChainClient, BlockRange, SpanExportConfig, DiskGuardConfig};
 use ethcore::service::ClientService;
 use ethcore::account_provider::{AccountProvider, AccountProviderSettings};
 use ethcore::miner::{Miner, MinerService, ExternalMiner, MinerOptions};
 use ethcore::snapshot;
+use ethcore::spec::Spec;
 use ethcore::verification::queue::VerifierSettings;
 use light::Cache as LightDataCache;
-use ethsync::SyncConfig;
+use ethsync::{SyncConfig, SnapshotServingConfig, SnapshotDownloadConfig, LesConfig, PipConfig};
 use informant::Informant;
+use metrics::{MetricsRegistry, StatsdConfig, StatsdExporter};
+use sql_export::{SqlExportConfig, SqlExporter};
+use event_publisher::{EventPublisherConfig, EventPublisher};
+use reserved_peers::ReservedPeersWatcher;
+use local_store::Persistence;
+use reload::{self, ConfigReloader};
+use geth_compat;
+use hybrid::{self, Hybrid};
 use updater::{UpdatePolicy, Updater};
 use parity_reactor::EventLoop;
 use hash_fetch::fetch::{Fetch, Client as FetchClient};
 
 use params::{
-	SpecType, Pruning, AccountsConfig, GasPricerConfig, MinerExtras, Switch,
+	SpecType, Pruning, AccountsConfig, GasPricerConfig, MinerExtras, Switch, CheckpointConfig, LightCheckpointConfig,
 	tracing_switch_to_bool, fatdb_switch_to_bool, mode_switch_to_bool
 };
 use helpers::{to_client_config, execute_upgrades, passwords_from_files};
 use upgrade::upgrade_key_location;
 use dir::Directories;
 use cache::CacheConfig;
+use priority::{PriorityConfig, set_priority};
+use shutdown::{Shutdown, Stage as ShutdownStage};
 use user_defaults::UserDefaults;
 use dapps;
 use ipfs;
+use health::{self, HealthChecker};
+use sealing_watchdog::{self, SealingWatchdog};
+use dev_accounts::DevAccounts;
+use systemd::{self, SystemdNotifier};
+use service;
 use signer;
 use secretstore;
 use modules;
@@ -55,12 +74,6 @@ use rpc_apis;
 use rpc;
 use url;
 
-// how often to take periodic snapshots.
-const SNAPSHOT_PERIOD: u64 = 10000;
-
-// how many blocks to wait before starting a periodic snapshot.
-const SNAPSHOT_HISTORY: u64 = 100;
-
 // Number of minutes before a given gas price corpus should expire.
 // Light client only.
 const GAS_CORPUS_EXPIRATION_MINUTES: i64 = 60 * 6;
@@ -92,6 +105,8 @@ pub struct RunCmd {
 	pub miner_extras: MinerExtras,
 	pub update_policy: UpdatePolicy,
 	pub mode: Option<Mode>,
+	pub wake_on_rpc: rpc_apis::ApiWake,
+	pub offline_serve: bool,
 	pub tracing: Switch,
 	pub fat_db: Switch,
 	pub compaction: DatabaseCompactionProfile,
@@ -102,19 +117,62 @@ pub struct RunCmd {
 	pub net_settings: NetworkSettings,
 	pub dapps_conf: dapps::Configuration,
 	pub ipfs_conf: ipfs::Configuration,
+	pub health_conf: health::Configuration,
+	pub sealing_watchdog_conf: sealing_watchdog::Configuration,
+	pub dev_block_time: Option<u64>,
+	pub dev_seal_on_txs: bool,
+	pub dev_accounts: Option<DevAccounts>,
 	pub signer_conf: signer::Configuration,
+	/// File with the rules deciding signing requests before the Trusted UI.
+	pub signer_policy: Option<String>,
 	pub secretstore_conf: secretstore::Configuration,
 	pub dapp: Option<String>,
 	pub ui: bool,
 	pub name: String,
 	pub custom_bootnodes: bool,
 	pub stratum: Option<StratumOptions>,
-	pub no_periodic_snapshot: bool,
+	pub snapshot_schedule: Option<snapshot::Schedule>,
+	pub snapshot_threads: usize,
+	pub snapshot_throttle: snapshot::ThrottleConfig,
+	pub snapshot_retention: snapshot::RetentionPolicy,
+	pub warp_from: Option<::snapshot::WarpFrom>,
 	pub check_seal: bool,
 	pub download_old_blocks: bool,
+	pub ancient_blocks_rate: Option<usize>,
 	pub verifier_settings: VerifierSettings,
 	pub serve_light: bool,
+	pub pip: PipConfig,
+	pub les: Option<LesConfig>,
+	pub snapshot_serving: SnapshotServingConfig,
+	pub snapshot_download: SnapshotDownloadConfig,
+	pub checkpoint: CheckpointConfig,
+	pub light_checkpoint: LightCheckpointConfig,
+	pub persist_light_cache: bool,
+	pub light_fallback_url: Option<String>,
+	pub light_ws_servers: Vec<String>,
+	pub on_demand: ::light::on_demand::Config,
+	pub tx_relay_peers: Option<usize>,
+	pub max_reorg_depth: Option<u64>,
+	pub reorg_alert_urls: Vec<String>,
+	pub block_range: Option<BlockRange>,
+	pub token_index: bool,
+	pub balance_history: bool,
+	pub log_index: bool,
+	pub disk_guard: DiskGuardConfig,
+	pub priority: PriorityConfig,
+	/// Time allowed for the shutdown before the process exits forcibly.
+	pub shutdown_timeout: Option<Duration>,
+	/// Reserved peers file, reloaded on changes.
+	pub reserved_peers_file: Option<String>,
+	pub statsd: Option<StatsdConfig>,
+	pub span_export: Option<SpanExportConfig>,
+	pub sql_export: Option<SqlExportConfig>,
+	pub event_publisher: Option<EventPublisherConfig>,
+	/// Transactions of the queue saved on shutdown.
+	pub tx_queue_persistence: Persistence,
 	pub light: bool,
+	/// Role in the hybrid light-then-full mode.
+	pub hybrid: Hybrid,
 }
 
 pub fn open_ui(signer_conf: &signer::Configuration) -> Result<(), String> {
@@ -154,6 +212,15 @@ impl ::local_store::NodeInfo for FullNodeInfo {
 			.filter(|tx| local_txs.contains_key(&tx.hash()))
 			.collect()
 	}
+
+	fn external_transactions(&self) -> Vec<::ethcore::transaction::PendingTransaction> {
+		let local_txs = self.miner.local_transactions();
+		self.miner.pending_transactions()
+			.into_iter()
+			.chain(self.miner.future_transactions())
+			.filter(|tx| !local_txs.contains_key(&tx.hash()))
+			.collect()
+	}
 }
 
 // helper for light execution.
@@ -191,7 +258,10 @@ fn execute_light(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) ->
 	cmd.dirs.create_dirs(cmd.dapps_conf.enabled, cmd.signer_conf.enabled, cmd.secretstore_conf.enabled)?;
 
 	info!("Starting {}", Colour::White.bold().paint(version()));
-	info!("Running in experimental {} mode.", Colour::Blue.bold().paint("Light Client"));
+	match cmd.hybrid {
+		Hybrid::Light { .. } => info!("Running in experimental {} mode.", Colour::Blue.bold().paint("Hybrid")),
+		_ => info!("Running in experimental {} mode.", Colour::Blue.bold().paint("Light Client")),
+	}
 
 	// TODO: configurable cache size.
 	let cache = LightDataCache::new(Default::default(), ::time::Duration::minutes(GAS_CORPUS_EXPIRATION_MINUTES));
@@ -204,12 +274,21 @@ fn execute_light(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) ->
 		db_cache_size: Some(cmd.cache_config.blockchain() as usize * 1024 * 1024),
 		db_compaction: compaction,
 		db_wal: cmd.wal,
+		checkpoint: cmd.light_checkpoint.to_checkpoint(&spec),
+		persist_cache: cmd.persist_light_cache,
 	};
 
 	config.queue.max_mem_use = cmd.cache_config.queue() as usize * 1024 * 1024;
 	config.queue.verifier_settings = cmd.verifier_settings;
+	config.queue.checkpoint = cmd.checkpoint.to_checkpoint(&spec);
 
-	let service = light_client::Service::start(config, &spec, &db_dirs.client_path(algorithm), cache.clone())
+	// in hybrid mode the full node owns the regular database.
+	let client_path = match cmd.hybrid {
+		Hybrid::Light { .. } => db_dirs.hybrid_light_path(),
+		_ => db_dirs.client_path(algorithm),
+	};
+
+	let service = light_client::Service::start(config, &spec, &client_path, cache.clone())
 		.map_err(|e| format!("Error starting light client: {}", e))?;
 	let txq = Arc::new(RwLock::new(::light::transaction_queue::TransactionQueue::default()));
 	let provider = ::light::provider::LightProvider::new(service.client().clone(), txq.clone());
@@ -223,7 +302,7 @@ fn execute_light(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) ->
 
 	// start on_demand service.
 	let account_start_nonce = service.client().engine().account_start_nonce();
-	let on_demand = Arc::new(::light::on_demand::OnDemand::new(cache.clone(), account_start_nonce));
+	let on_demand = Arc::new(::light::on_demand::OnDemand::with_config(cache.clone(), account_start_nonce, cmd.on_demand));
 
 	// set network path.
 	net_conf.net_config_path = Some(db_dirs.network_path().to_string_lossy().into_owned());
@@ -233,6 +312,8 @@ fn execute_light(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) ->
 		network_id: cmd.network_id.unwrap_or(spec.network_id()),
 		subprotocol_name: ::ethsync::LIGHT_PROTOCOL,
 		handlers: vec![on_demand.clone()],
+		tx_relay_peers: cmd.tx_relay_peers,
+		ws_servers: cmd.light_ws_servers,
 	};
 	let light_sync = LightSync::new(sync_params).map_err(|e| format!("Error starting network: {}", e))?;
 	let light_sync = Arc::new(light_sync);
@@ -256,18 +337,25 @@ fn execute_light(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) ->
 
 	// fetch service
 	let fetch = FetchClient::new().map_err(|e| format!("Error starting fetch client: {:?}", e))?;
+
+	// start the background full node.
+	let full_node = match cmd.hybrid {
+		Hybrid::Light { rpc_port } => Some((rpc_port, hybrid::FullNode::spawn(rpc_port, fetch.clone(), service.client().clone(), light_sync.clone())?)),
+		_ => None,
+	};
+
 	let passwords = passwords_from_files(&cmd.acc_conf.password_files)?;
 
 	// prepare account provider
 	let account_provider = Arc::new(prepare_account_provider(&cmd.spec, &cmd.dirs, &spec.data_dir, cmd.acc_conf, &passwords)?);
 	let rpc_stats = Arc::new(informant::RpcStats::default());
-	let signer_path = cmd.signer_conf.signer_path.clone();
+	let signer_service = signer::new_service(cmd.signer_conf.signer_path.clone(), cmd.ui_address, cmd.signer_policy.as_ref().map(|p| p.as_str()))?;
 
 	// start RPCs
+	// the background full node serves the same APIs over HTTP.
+	let http_apis = cmd.http_conf.apis.clone();
 	let deps_for_rpc_apis = Arc::new(rpc_apis::LightDependencies {
-		signer_service: Arc::new(rpc_apis::SignerService::new(move || {
-			signer::generate_new_token(signer_path.clone()).map_err(|e| format!("{:?}", e))
-		}, cmd.ui_address)),
+		signer_service: Arc::new(signer_service),
 		client: service.client().clone(),
 		sync: light_sync.clone(),
 		net: light_sync.clone(),
@@ -287,12 +375,17 @@ fn execute_light(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) ->
 		},
 		fetch: fetch,
 		geth_compatibility: cmd.geth_compatibility,
+		fallback_url: cmd.light_fallback_url,
+		handover: full_node.as_ref().map(|&(rpc_port, ref node)| (hybrid::rpc_url(rpc_port), node.ready(), http_apis)),
+		remote: event_loop.remote(),
+		pubsub: Default::default(),
 	});
 
 	let dependencies = rpc::Dependencies {
 		apis: deps_for_rpc_apis.clone(),
 		remote: event_loop.raw_remote(),
 		stats: rpc_stats.clone(),
+		limits: SharedLimits::new(cmd.http_conf.limits.clone()),
 	};
 
 	// start rpc servers
@@ -335,16 +428,22 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 	// increase max number of open files
 	raise_fd_limit();
 
+	// set before any threads are spawned, so that all of them inherit the priorities
+	set_priority(&cmd.priority)?;
+
 	// run as light client.
 	if cmd.light {
 		return execute_light(cmd, can_restart, logger);
 	}
 
+	// created first to be dropped last, after everything it supervises
+	let shutdown = Shutdown::new(cmd.shutdown_timeout);
+
 	// set up panic handler
 	let panic_handler = PanicHandler::new_in_arc();
 
 	// load spec
-	let spec = cmd.spec.spec()?;
+	let spec = load_spec(&cmd)?;
 
 	// load genesis hash
 	let genesis_hash = spec.genesis_header().hash();
@@ -422,17 +521,27 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 		sync_config.subprotocol_name.clone_from_slice(spec.subprotocol_name().as_bytes());
 	}
 	sync_config.fork_block = spec.fork_block();
+	sync_config.checkpoint = cmd.checkpoint.to_checkpoint(&spec);
 	sync_config.warp_sync = cmd.warp_sync;
 	sync_config.download_old_blocks = cmd.download_old_blocks;
+	sync_config.old_blocks_rate = cmd.ancient_blocks_rate;
 	sync_config.serve_light = cmd.serve_light;
+	sync_config.pip = cmd.pip;
+	sync_config.les = cmd.les;
+	sync_config.snapshot_serving = cmd.snapshot_serving;
+	sync_config.snapshot_download = cmd.snapshot_download;
 
 	let passwords = passwords_from_files(&cmd.acc_conf.password_files)?;
 
 	// prepare account provider
 	let account_provider = Arc::new(prepare_account_provider(&cmd.spec, &cmd.dirs, &spec.data_dir, cmd.acc_conf, &passwords)?);
+	if let Some(ref dev_accounts) = cmd.dev_accounts {
+		dev_accounts.unlock(&account_provider)?;
+	}
 
 	// create miner
 	let initial_min_gas_price = cmd.gas_pricer.initial_min();
+	let force_sealing = cmd.miner_options.force_sealing;
 	let miner = Miner::new(cmd.miner_options, cmd.gas_pricer.into(), &spec, Some(account_provider.clone()));
 	miner.set_author(cmd.miner_extras.author);
 	miner.set_gas_floor_target(cmd.miner_extras.gas_floor_target);
@@ -478,6 +587,18 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 	);
 
 	client_config.queue.verifier_settings = cmd.verifier_settings;
+	client_config.queue.checkpoint = sync_config.checkpoint;
+	client_config.max_reorg_depth = cmd.max_reorg_depth;
+	client_config.reorg_alert_urls = cmd.reorg_alert_urls.clone();
+	client_config.block_range = cmd.block_range;
+	client_config.token_index = cmd.token_index;
+	client_config.balance_history = cmd.balance_history;
+	client_config.log_index = cmd.log_index;
+	client_config.disk_guard = cmd.disk_guard.clone();
+	client_config.span_export = cmd.span_export.clone();
+	client_config.snapshot_threads = cmd.snapshot_threads;
+	client_config.snapshot_throttle = cmd.snapshot_throttle.clone();
+	client_config.snapshot_retention = cmd.snapshot_retention.clone();
 
 	// set up bootnodes
 	let mut net_conf = cmd.net_conf;
@@ -486,7 +607,12 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 	}
 
 	// set network path.
-	net_conf.net_config_path = Some(db_dirs.network_path().to_string_lossy().into_owned());
+	let network_path = match cmd.hybrid {
+		Hybrid::Full => db_dirs.hybrid_network_path(),
+		_ => db_dirs.network_path(),
+	};
+	net_conf.net_config_path = Some(network_path.to_string_lossy().into_owned());
+	let reserved_peers = net_conf.reserved_nodes.clone();
 
 	// create supervisor
 	let mut hypervisor = modules::hypervisor(&cmd.dirs.ipc_path());
@@ -501,6 +627,11 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 		miner.clone(),
 	).map_err(|e| format!("Client service error: {:?}", e))?;
 
+	// advertise the fork identifier of the current head in the node record,
+	// sync updates it whenever the head passes one of the fork blocks.
+	net_conf.fork_id = Some(spec.fork_id(service.client().chain_info().best_block_number));
+	net_conf.fork_blocks = spec.fork_blocks();
+
 	// drop the spec to free up genesis state.
 	drop(spec);
 
@@ -511,6 +642,13 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 	let client = service.client();
 	let snapshot_service = service.snapshot_service();
 
+	// restore a fresh node from the snapshot mirror before syncing.
+	if let Some(ref warp_from) = cmd.warp_from {
+		if client.chain_info().best_block_number == 0 {
+			::snapshot::restore_from_url(snapshot_service.clone(), warp_from, &snapshot_path)?;
+		}
+	}
+
 	// initialize the local node information store.
 	let store = {
 		let db = service.db();
@@ -518,7 +656,8 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 			miner: miner.clone(),
 		};
 
-		let store = ::local_store::create(db, ::ethcore::db::COL_NODE_INFO, node_info);
+		let store = ::local_store::create(db, ::ethcore::db::COL_NODE_INFO, node_info)
+			.with_persistence(cmd.tx_queue_persistence);
 
 		// re-queue pending transactions.
 		match store.pending_transactions() {
@@ -532,6 +671,21 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 			Err(e) => warn!("Error loading cached pending transactions from disk: {}", e),
 		}
 
+		// re-queue the rest of the pool, validated against the current best block.
+		match store.pool_transactions() {
+			Ok(ref pool) if pool.is_empty() => {},
+			Ok(pool) => {
+				let count = pool.len();
+				let transactions = pool.into_iter().map(|pending| pending.transaction.into()).collect();
+				let imported = miner.import_external_transactions(&*client, transactions)
+					.into_iter()
+					.filter(Result::is_ok)
+					.count();
+				info!("Restored {} of {} saved queued transactions", imported, count);
+			},
+			Err(e) => warn!("Error loading cached queued transactions from disk: {}", e),
+		}
+
 		Arc::new(store)
 	};
 
@@ -558,11 +712,16 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 		&cmd.logger_config,
 	).map_err(|e| format!("Sync error: {}", e))?;
 
-	service.add_notify(chain_notify.clone());
-
 	// start network
-	if network_enabled {
-		chain_notify.start();
+	match cmd.offline_serve {
+		// without the notification the network is not started when the mode changes either
+		true => info!("Serving the local database only, the network is never started."),
+		false => {
+			service.add_notify(chain_notify.clone());
+			if network_enabled {
+				chain_notify.start();
+			}
+		},
 	}
 
 	// spin up event loop
@@ -583,16 +742,14 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 
 	// set up dependencies for rpc servers
 	let rpc_stats = Arc::new(informant::RpcStats::default());
-	let signer_path = cmd.signer_conf.signer_path.clone();
+	let signer_service = signer::new_service(cmd.signer_conf.signer_path.clone(), cmd.ui_address, cmd.signer_policy.as_ref().map(|p| p.as_str()))?;
 	let secret_store = match cmd.public_node {
 		true => None,
 		false => Some(account_provider.clone())
 	};
 
 	let deps_for_rpc_apis = Arc::new(rpc_apis::FullDependencies {
-		signer_service: Arc::new(rpc_apis::SignerService::new(move || {
-			signer::generate_new_token(signer_path.clone()).map_err(|e| format!("{:?}", e))
-		}, cmd.ui_address)),
+		signer_service: Arc::new(signer_service),
 		snapshot: snapshot_service.clone(),
 		client: client.clone(),
 		sync: sync_provider.clone(),
@@ -614,12 +771,18 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 			false => None,
 		},
 		fetch: fetch.clone(),
+		remote: event_loop.remote(),
+		wake_on_rpc: cmd.wake_on_rpc.clone(),
+		offline_serve: cmd.offline_serve,
+		pubsub: Default::default(),
 	});
 
+	let http_limits = SharedLimits::new(cmd.http_conf.limits.clone());
 	let dependencies = rpc::Dependencies {
 		apis: deps_for_rpc_apis.clone(),
 		remote: event_loop.raw_remote(),
 		stats: rpc_stats.clone(),
+		limits: http_limits.clone(),
 	};
 
 	// the dapps server
@@ -672,11 +835,111 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 	service.add_notify(informant.clone());
 	service.register_io_handler(informant.clone()).map_err(|_| "Unable to register informant handler".to_owned())?;
 
+	// push metrics to StatsD
+	if let Some(statsd) = cmd.statsd.clone() {
+		let registry = Arc::new(MetricsRegistry::new(
+			service.client(),
+			Some(sync_provider.clone()),
+			Some(manage_network.clone()),
+			Some(rpc_stats.clone()),
+		));
+		let exporter = Arc::new(StatsdExporter::new(statsd, registry)?);
+		service.register_io_handler(exporter).map_err(|_| "Unable to register StatsD exporter".to_owned())?;
+	}
+
+	// stream the chain to PostgreSQL
+	if let Some(sql_export) = cmd.sql_export.clone() {
+		let client: Arc<BlockChainClient> = service.client();
+		let exporter = SqlExporter::start(sql_export, Arc::downgrade(&client))?;
+		service.add_notify(exporter);
+	}
+
+	// publish chain events
+	if let Some(event_publisher) = cmd.event_publisher.clone() {
+		let client: Arc<BlockChainClient> = service.client();
+		let publisher = EventPublisher::start(event_publisher, db_dirs.event_cursor_path(), Arc::downgrade(&client))?;
+		service.add_notify(publisher);
+	}
+
+	// alert when our turns to seal blocks are missed
+	let sealing_watchdog = match cmd.sealing_watchdog_conf.enabled {
+		true => {
+			let watchdog = Arc::new(SealingWatchdog::new(
+				cmd.sealing_watchdog_conf.clone(),
+				force_sealing,
+				client.clone(),
+				miner.clone(),
+			));
+			service.register_io_handler(watchdog.clone()).map_err(|_| "Unable to register sealing watchdog".to_owned())?;
+			Some(watchdog)
+		},
+		false => None,
+	};
+
+	// health and readiness endpoints, also used for the systemd watchdog
+	let systemd_notifier = systemd::Notifier::from_env();
+	let health_checker = match (cmd.health_conf.enabled, systemd_notifier.is_some()) {
+		(false, false) => None,
+		(enabled, _) => {
+			let conf = match enabled {
+				true => cmd.health_conf.clone(),
+				// don't query NTP servers unless asked to
+				false => health::Configuration { ntp_server: None, ..cmd.health_conf.clone() },
+			};
+			let checker = Arc::new(HealthChecker::new(
+				conf,
+				client.clone(),
+				sync_provider.clone(),
+				client_path.clone(),
+				sealing_watchdog,
+			));
+			service.register_io_handler(checker.clone()).map_err(|_| "Unable to register health checker".to_owned())?;
+			Some(checker)
+		},
+	};
+	let health_server = match (cmd.health_conf.enabled, health_checker.clone()) {
+		(true, Some(checker)) => health::start_server(&cmd.health_conf, checker)?,
+		_ => None,
+	};
+	let systemd_notifier = match (systemd_notifier, health_checker) {
+		(Some(notifier), Some(checker)) => {
+			let notifier = Arc::new(SystemdNotifier::new(notifier, checker));
+			service.register_io_handler(notifier.clone()).map_err(|_| "Unable to register systemd notifier".to_owned())?;
+			Some(notifier)
+		},
+		_ => None,
+	};
+
+	// reload reserved peers on file changes
+	let reserved_peers_watcher = match cmd.reserved_peers_file.clone() {
+		Some(path) => {
+			let watcher = Arc::new(ReservedPeersWatcher::new(path, reserved_peers, manage_network.clone()));
+			service.register_io_handler(watcher.clone()).map_err(|_| "Unable to register reserved peers handler".to_owned())?;
+			Some(watcher)
+		},
+		None => None,
+	};
+
+	// reload runtime settings from the config file on SIGHUP
+	let reloader = Arc::new(ConfigReloader::new(
+		geth_compat::translate(env::args().collect())?.0,
+		logger.clone(),
+		manage_network.clone(),
+		miner.clone(),
+		http_limits,
+		reserved_peers_watcher,
+	)?);
+	service.register_io_handler(reloader).map_err(|_| "Unable to register config reloader".to_owned())?;
+	reload::set_reload_handler();
+
 	// save user defaults
 	user_defaults.pruning = algorithm;
 	user_defaults.tracing = tracing;
 	user_defaults.fat_db = fat_db;
-	user_defaults.mode = mode;
+	// offline-serve is not remembered as the last mode
+	if !cmd.offline_serve {
+		user_defaults.mode = mode;
+	}
 	user_defaults.save(&user_defaults_path)?;
 
 	// tell client how to save the default mode if it gets changed.
@@ -688,17 +951,16 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 	});
 
 	// the watcher must be kept alive.
-	let _watcher = match cmd.no_periodic_snapshot {
-		true => None,
-		false => {
+	let _watcher = match cmd.snapshot_schedule {
+		None => None,
+		Some(schedule) => {
 			let sync = sync_provider.clone();
 			let client = client.clone();
 			let watcher = Arc::new(snapshot::Watcher::new(
 				service.client(),
 				move || is_major_importing(Some(sync.status().state), client.queue_info()),
 				service.io().channel(),
-				SNAPSHOT_PERIOD,
-				SNAPSHOT_HISTORY,
+				schedule,
 			));
 
 			service.add_notify(watcher.clone());
@@ -715,14 +977,25 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 		open_dapp(&cmd.dapps_conf, &cmd.http_conf, &dapp)?;
 	}
 
+	// database is open and all servers are up
+	if let Some(ref notifier) = systemd_notifier {
+		notifier.ready();
+	}
+
 	// Handle exit
-	let restart = wait_for_exit(panic_handler, Some(updater), Some(client), can_restart);
+	let restart = wait_for_exit(panic_handler, Some(updater), Some(client.clone()), can_restart);
 
-	// drop this stuff as soon as exit detected.
-	drop((ws_server, http_server, ipc_server, signer_server, secretstore_key_server, ipfs_server, event_loop));
+	if let Some(ref notifier) = systemd_notifier {
+		notifier.stopping();
+	}
 
 	info!("Finishing work, please wait...");
 
+	// drop this stuff as soon as exit detected.
+	shutdown.stage(ShutdownStage::StoppingServers);
+	drop((ws_server, http_server, ipc_server, signer_server, secretstore_key_server, ipfs_server, health_server, event_loop));
+
+	shutdown.stage(ShutdownStage::StoppingServices);
 	// to make sure timer does not spawn requests while shutdown is in progress
 	informant.shutdown();
 	// just Arc is dropping here, to allow other reference release in its default time
@@ -732,11 +1005,21 @@ pub fn execute(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>) -> R
 	// terminated gracefully
 	drop(hypervisor);
 
+	shutdown.stage(ShutdownStage::FinishingImport);
+	client.stop_import();
+
+	shutdown.stage(ShutdownStage::FlushingCaches);
+	if let Err(e) = service.db().flush() {
+		warn!("Error flushing the database: {}", e);
+	}
+
+	// the remaining references are released when returning
+	shutdown.stage(ShutdownStage::ClosingDatabase);
 	Ok(restart)
 }
 
 #[cfg(not(windows))]
-fn daemonize(pid_file: String) -> Result<(), String> {
+pub fn daemonize(pid_file: String) -> Result<(), String> {
 	extern crate daemonize;
 
 	daemonize::Daemonize::new()
@@ -748,10 +1031,22 @@ fn daemonize(pid_file: String) -> Result<(), String> {
 }
 
 #[cfg(windows)]
-fn daemonize(_pid_file: String) -> Result<(), String> {
+pub fn daemonize(_pid_file: String) -> Result<(), String> {
 	Err("daemon is no supported on windows".into())
 }
 
+/// Loads the chain spec, sealing the dev chain on the configured block time and funding its dev accounts.
+fn load_spec(cmd: &RunCmd) -> Result<Spec, String> {
+	let mut spec = match (&cmd.spec, cmd.dev_block_time) {
+		(&SpecType::Dev, Some(block_time)) => Spec::new_instant_with_block_time(block_time, cmd.dev_seal_on_txs),
+		_ => cmd.spec.spec()?,
+	};
+	if let Some(ref dev_accounts) = cmd.dev_accounts {
+		dev_accounts.fund(&mut spec);
+	}
+	Ok(spec)
+}
+
 fn prepare_account_provider(spec: &SpecType, dirs: &Directories, data_dir: &str, cfg: AccountsConfig, passwords: &[String]) -> Result<AccountProvider, String> {
 	use ethcore::ethstore::EthStore;
 	use ethcore::ethstore::dir::RootDiskDirectory;
@@ -803,6 +1098,10 @@ fn wait_for_exit(
 	let e = exit.clone();
 	CtrlC::set_handler(move || { e.1.notify_all(); });
 
+	// Handle Windows service stop requests, which may arrive before we wait for them
+	let e = exit.clone();
+	service::set_stop_handler(move || { let _l = e.0.lock(); e.1.notify_all(); });
+
 	// Handle panics
 	let e = exit.clone();
 	panic_handler.on_panic(move |_reason| { e.1.notify_all(); });
@@ -0,0 +1,262 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Watchdog alerting when this authority misses its turns to seal blocks.
+
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use io::{TimerToken, IoContext, IoHandler};
+use ethcore::client::{Client, BlockChainClient, BlockId, AlertPoster};
+use ethcore::miner::{Miner, MinerService};
+use ethcore::service::ClientIoMessage;
+use util::{Address, Mutex};
+
+const CHECK_TIMER: TimerToken = 0;
+const CHECK_TIMER_INTERVAL_MS: u64 = 500;
+/// Exit code used when exiting because of missed turns.
+const EXIT_CODE: i32 = 3;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Configuration {
+	pub enabled: bool,
+	/// Number of consecutive missed turns raising the alert.
+	pub max_missed: usize,
+	/// Webhook URLs the alerts are posted to.
+	pub alert_urls: Vec<String>,
+	/// Exit the process on alert, so that it can be restarted by a supervisor.
+	pub exit: bool,
+}
+
+impl Default for Configuration {
+	fn default() -> Self {
+		Configuration {
+			enabled: false,
+			max_missed: 3,
+			alert_urls: Vec::new(),
+			exit: false,
+		}
+	}
+}
+
+/// Change of the sealing status.
+#[derive(Debug, PartialEq)]
+enum Event {
+	/// Given number of consecutive turns were missed.
+	Missed(usize),
+	/// A block was sealed after the alert.
+	Recovered,
+}
+
+/// Turn in which this node was expected to seal.
+struct Turn {
+	step: usize,
+	best_block: u64,
+}
+
+/// Counts consecutive missed turns.
+struct Tracker {
+	max_missed: usize,
+	turn: Option<Turn>,
+	missed: usize,
+	alerting: bool,
+}
+
+impl Tracker {
+	fn new(max_missed: usize) -> Self {
+		Tracker {
+			max_missed: max_missed,
+			turn: None,
+			missed: 0,
+			alerting: false,
+		}
+	}
+
+	/// Observe the current step. `sealed_since` tells whether a block of this node
+	/// was imported on top of the given block.
+	fn tick<F>(&mut self, step: usize, expected: bool, best_block: u64, sealed_since: F) -> Option<Event>
+		where F: Fn(u64) -> bool
+	{
+		let mut event = None;
+		if let Some(turn) = self.turn.take() {
+			if turn.step == step {
+				self.turn = Some(turn);
+				return None;
+			}
+			if sealed_since(turn.best_block) {
+				self.missed = 0;
+				if self.alerting {
+					self.alerting = false;
+					event = Some(Event::Recovered);
+				}
+			} else {
+				self.missed += 1;
+				if self.missed >= self.max_missed && !self.alerting {
+					self.alerting = true;
+					event = Some(Event::Missed(self.missed));
+				}
+			}
+		}
+		if expected {
+			self.turn = Some(Turn { step: step, best_block: best_block });
+		}
+		event
+	}
+}
+
+/// Watches the turns of the engine signer.
+pub struct SealingWatchdog {
+	conf: Configuration,
+	force_sealing: bool,
+	client: Arc<Client>,
+	miner: Arc<Miner>,
+	tracker: Mutex<Tracker>,
+	poster: Option<AlertPoster>,
+	sealing: AtomicBool,
+}
+
+impl SealingWatchdog {
+	pub fn new(conf: Configuration, force_sealing: bool, client: Arc<Client>, miner: Arc<Miner>) -> Self {
+		SealingWatchdog {
+			tracker: Mutex::new(Tracker::new(conf.max_missed)),
+			poster: match conf.alert_urls.is_empty() {
+				true => None,
+				false => Some(AlertPoster::new(&conf.alert_urls)),
+			},
+			conf: conf,
+			force_sealing: force_sealing,
+			client: client,
+			miner: miner,
+			sealing: AtomicBool::new(true),
+		}
+	}
+
+	/// Whether the node is sealing in its turns.
+	pub fn is_sealing(&self) -> bool {
+		self.sealing.load(Ordering::SeqCst)
+	}
+
+	fn check(&self) {
+		let signer = self.miner.author();
+		if signer == Address::default() {
+			return;
+		}
+		let chain_info = self.client.chain_info();
+		let (step, proposer) = match self.client.engine().expected_proposer(&chain_info.best_block_hash) {
+			Some(turn) => turn,
+			None => return,
+		};
+		// blocks are only sealed when forced or when there are transactions to include
+		let expected = proposer == signer && (self.force_sealing || self.miner.status().transactions_in_pending_queue > 0);
+		let client = &*self.client;
+		let event = self.tracker.lock().tick(step, expected, chain_info.best_block_number, |since| sealed_since(client, &signer, since));
+
+		match event {
+			Some(Event::Missed(missed)) => {
+				self.sealing.store(false, Ordering::SeqCst);
+				warn!(target: "watchdog", "Engine signer {} missed its last {} turns to seal a block.", signer, missed);
+				if let Some(ref poster) = self.poster {
+					poster.post(&alert_json(&signer, step, missed, false));
+				}
+				if self.conf.exit {
+					warn!(target: "watchdog", "Exiting because of missed sealing turns.");
+					process::exit(EXIT_CODE);
+				}
+			},
+			Some(Event::Recovered) => {
+				self.sealing.store(true, Ordering::SeqCst);
+				info!(target: "watchdog", "Engine signer {} is sealing blocks again.", signer);
+				if let Some(ref poster) = self.poster {
+					poster.post(&alert_json(&signer, step, 0, true));
+				}
+			},
+			None => {},
+		}
+	}
+}
+
+/// Whether a block authored by `author` was imported on top of block number `since`.
+fn sealed_since(client: &BlockChainClient, author: &Address, since: u64) -> bool {
+	let mut id = BlockId::Latest;
+	while let Some(header) = client.block_header(id) {
+		if header.number() <= since {
+			return false;
+		}
+		if header.author() == *author {
+			return true;
+		}
+		id = BlockId::Hash(header.parent_hash());
+	}
+	false
+}
+
+fn alert_json(signer: &Address, step: usize, missed: usize, recovered: bool) -> String {
+	format!(
+		r#"{{ "sealingAlert": {{ "signer": "0x{}", "step": "0x{:x}", "missed": "0x{:x}", "recovered": {} }} }}"#,
+		signer.hex(), step, missed, recovered
+	)
+}
+
+impl IoHandler<ClientIoMessage> for SealingWatchdog {
+	fn initialize(&self, io: &IoContext<ClientIoMessage>) {
+		io.register_timer(CHECK_TIMER, CHECK_TIMER_INTERVAL_MS).expect("Error registering timer");
+	}
+
+	fn timeout(&self, _io: &IoContext<ClientIoMessage>, timer: TimerToken) {
+		if timer == CHECK_TIMER {
+			self.check();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::Address;
+	use super::{Tracker, Event, alert_json};
+
+	#[test]
+	fn should_alert_after_missed_turns() {
+		let mut tracker = Tracker::new(2);
+		assert_eq!(tracker.tick(10, true, 100, |_| false), None);
+		assert_eq!(tracker.tick(10, true, 100, |_| false), None);
+		// first turn missed
+		assert_eq!(tracker.tick(11, false, 100, |_| false), None);
+		assert_eq!(tracker.tick(12, true, 100, |_| false), None);
+		// second turn missed
+		assert_eq!(tracker.tick(13, true, 100, |_| false), Some(Event::Missed(2)));
+		// still missing, alerted once
+		assert_eq!(tracker.tick(14, true, 100, |_| false), None);
+		assert_eq!(tracker.tick(15, false, 101, |since| since == 100), Some(Event::Recovered));
+		assert_eq!(tracker.missed, 0);
+	}
+
+	#[test]
+	fn should_not_count_turns_sealed() {
+		let mut tracker = Tracker::new(1);
+		assert_eq!(tracker.tick(10, true, 100, |_| true), None);
+		assert_eq!(tracker.tick(11, true, 101, |_| true), None);
+		assert_eq!(tracker.tick(12, false, 102, |_| true), None);
+		assert_eq!(tracker.missed, 0);
+	}
+
+	#[test]
+	fn should_format_alert() {
+		let json = alert_json(&Address::from(1), 20, 3, false);
+		assert!(json.contains(r#""step": "0x14""#));
+		assert!(json.contains(r#""missed": "0x3""#));
+		assert!(json.contains(r#""recovered": false"#));
+	}
+}
@@ -0,0 +1,472 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Windows service: `parity service install | uninstall | run`.
+//!
+//! The installed service starts `parity service run` with the options given at
+//! install time. Stop and shutdown requests of the service control manager
+//! shut the node down like Ctrl-C does.
+
+use std::sync::Arc;
+use ethcore_logger::RotatingLogger;
+use util::Mutex;
+use run::RunCmd;
+
+/// Command managing or running the Windows service.
+#[derive(Debug, PartialEq)]
+pub enum ServiceCmd {
+	/// Register the service to run with the current command line.
+	Install {
+		name: String,
+	},
+	/// Stop and remove the service.
+	Uninstall {
+		name: String,
+	},
+	/// Run the node as the service, called by the service control manager.
+	Run {
+		name: String,
+		cmd: RunCmd,
+	},
+}
+
+/// Stop requests received before the node waits for them are remembered.
+#[derive(Default)]
+struct StopSignal {
+	requested: bool,
+	handler: Option<Box<Fn() + Send>>,
+}
+
+impl StopSignal {
+	#[cfg_attr(not(windows), allow(dead_code))]
+	fn request(&mut self) {
+		self.requested = true;
+		if let Some(ref handler) = self.handler {
+			handler();
+		}
+	}
+
+	fn set_handler(&mut self, handler: Box<Fn() + Send>) {
+		if self.requested {
+			handler();
+		}
+		self.handler = Some(handler);
+	}
+}
+
+lazy_static! {
+	static ref STOP_SIGNAL: Mutex<StopSignal> = Mutex::new(Default::default());
+}
+
+/// Set the function shutting the node down when the service is asked to stop.
+/// It's called immediately if the stop was requested already.
+pub fn set_stop_handler<F>(handler: F) where F: Fn() + Send + 'static {
+	STOP_SIGNAL.lock().set_handler(Box::new(handler));
+}
+
+/// Quote an argument of the service command line.
+fn quote_argument(arg: &str) -> String {
+	if !arg.is_empty() && !arg.contains(|c: char| c == ' ' || c == '\t' || c == '"') {
+		return arg.to_owned();
+	}
+
+	let mut quoted = String::from("\"");
+	let mut backslashes = 0;
+	for c in arg.chars() {
+		match c {
+			'\\' => backslashes += 1,
+			'"' => {
+				// escape the preceding backslashes and the quote itself
+				quoted.extend((0..backslashes * 2 + 1).map(|_| '\\'));
+				backslashes = 0;
+			},
+			_ => {
+				quoted.extend((0..backslashes).map(|_| '\\'));
+				backslashes = 0;
+			},
+		}
+		if c != '\\' {
+			quoted.push(c);
+		}
+	}
+	// backslashes before the closing quote must be escaped
+	quoted.extend((0..backslashes * 2).map(|_| '\\'));
+	quoted.push('"');
+	quoted
+}
+
+/// Command line of the service: the executable followed by the install-time arguments
+/// with `install` replaced by `run`. The updater can't restart a service, so it runs directly.
+fn service_command_line(exe: &str, args: &[String]) -> String {
+	let mut replaced = false;
+	let mut service_args: Vec<String> = args.iter().map(|arg| match arg.as_str() {
+		"install" if !replaced => {
+			replaced = true;
+			"run".to_owned()
+		},
+		_ => arg.clone(),
+	}).collect();
+	if !service_args.iter().any(|arg| arg == "--force-direct") {
+		service_args.push("--force-direct".into());
+	}
+
+	Some(exe.to_owned()).into_iter()
+		.chain(service_args)
+		.map(|arg| quote_argument(&arg))
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+/// Install, uninstall or run the service.
+pub fn execute(cmd: ServiceCmd, logger: Arc<RotatingLogger>) -> Result<String, String> {
+	match cmd {
+		ServiceCmd::Install { name } => {
+			use std::env;
+
+			let exe = env::current_exe().map_err(|e| format!("Unable to determine the executable path: {}", e))?;
+			let args: Vec<String> = env::args().skip(1).collect();
+			let command_line = service_command_line(&exe.to_string_lossy(), &args);
+			platform::install(&name, &command_line)?;
+			Ok(format!("Service {} installed, it runs: {}", name, command_line))
+		},
+		ServiceCmd::Uninstall { name } => {
+			platform::uninstall(&name)?;
+			Ok(format!("Service {} uninstalled", name))
+		},
+		// runs under the service control manager until it's asked to stop
+		ServiceCmd::Run { name, cmd } => platform::run(&name, cmd, logger).map(|_| format!("Service {} stopped", name)),
+	}
+}
+
+#[cfg(not(windows))]
+mod platform {
+	use std::sync::Arc;
+	use ethcore_logger::RotatingLogger;
+	use run::RunCmd;
+
+	const NOT_SUPPORTED: &'static str = "Services are only supported on Windows, use systemd or `parity daemon` instead";
+
+	pub fn install(_name: &str, _command_line: &str) -> Result<(), String> {
+		Err(NOT_SUPPORTED.into())
+	}
+
+	pub fn uninstall(_name: &str) -> Result<(), String> {
+		Err(NOT_SUPPORTED.into())
+	}
+
+	pub fn run(_name: &str, _cmd: RunCmd, _logger: Arc<RotatingLogger>) -> Result<(), String> {
+		Err(NOT_SUPPORTED.into())
+	}
+}
+
+#[cfg(windows)]
+mod platform {
+	use std::ffi::OsStr;
+	use std::os::windows::ffi::OsStrExt;
+	use std::sync::Arc;
+	use std::{io, ptr, thread};
+	use std::time::Duration;
+	use ethcore_logger::RotatingLogger;
+	use util::Mutex;
+	use run::{self, RunCmd};
+	use super::STOP_SIGNAL;
+
+	type Handle = *mut u8;
+
+	#[repr(C)]
+	struct ServiceStatus {
+		service_type: u32,
+		current_state: u32,
+		controls_accepted: u32,
+		win32_exit_code: u32,
+		service_specific_exit_code: u32,
+		check_point: u32,
+		wait_hint: u32,
+	}
+
+	#[repr(C)]
+	struct ServiceTableEntry {
+		name: *const u16,
+		main: Option<unsafe extern "system" fn(u32, *mut *mut u16)>,
+	}
+
+	#[repr(C)]
+	struct ServiceDescription {
+		description: *const u16,
+	}
+
+	const SC_MANAGER_CONNECT: u32 = 0x0001;
+	const SC_MANAGER_CREATE_SERVICE: u32 = 0x0002;
+	const SERVICE_QUERY_STATUS: u32 = 0x0004;
+	const SERVICE_CHANGE_CONFIG: u32 = 0x0002;
+	const SERVICE_STOP: u32 = 0x0020;
+	const DELETE: u32 = 0x0001_0000;
+	const SERVICE_WIN32_OWN_PROCESS: u32 = 0x0000_0010;
+	const SERVICE_AUTO_START: u32 = 0x0000_0002;
+	const SERVICE_ERROR_NORMAL: u32 = 0x0000_0001;
+	const SERVICE_CONFIG_DESCRIPTION: u32 = 1;
+	const SERVICE_CONTROL_STOP: u32 = 0x0000_0001;
+	const SERVICE_CONTROL_SHUTDOWN: u32 = 0x0000_0005;
+	const SERVICE_ACCEPT_STOP: u32 = 0x0000_0001;
+	const SERVICE_ACCEPT_SHUTDOWN: u32 = 0x0000_0004;
+	const SERVICE_STOPPED: u32 = 0x0000_0001;
+	const SERVICE_START_PENDING: u32 = 0x0000_0002;
+	const SERVICE_STOP_PENDING: u32 = 0x0000_0003;
+	const SERVICE_RUNNING: u32 = 0x0000_0004;
+	const ERROR_SERVICE_SPECIFIC_ERROR: u32 = 1066;
+	const ERROR_SERVICE_NOT_ACTIVE: i32 = 1062;
+
+	/// Time to wait for a running service to stop before removing it.
+	const UNINSTALL_STOP_TIMEOUT_SECS: u64 = 60;
+	/// Shutdown flushes the database, tell the service control manager to be patient.
+	const STOP_WAIT_HINT_MS: u32 = 60_000;
+
+	#[link(name = "advapi32")]
+	extern "system" {
+		fn OpenSCManagerW(machine: *const u16, database: *const u16, access: u32) -> Handle;
+		fn CreateServiceW(manager: Handle, name: *const u16, display_name: *const u16, access: u32, service_type: u32, start_type: u32, error_control: u32, binary_path: *const u16, load_order_group: *const u16, tag_id: *mut u32, dependencies: *const u16, start_name: *const u16, password: *const u16) -> Handle;
+		fn ChangeServiceConfig2W(service: Handle, info_level: u32, info: *mut u8) -> i32;
+		fn OpenServiceW(manager: Handle, name: *const u16, access: u32) -> Handle;
+		fn ControlService(service: Handle, control: u32, status: *mut ServiceStatus) -> i32;
+		fn QueryServiceStatus(service: Handle, status: *mut ServiceStatus) -> i32;
+		fn DeleteService(service: Handle) -> i32;
+		fn CloseServiceHandle(handle: Handle) -> i32;
+		fn StartServiceCtrlDispatcherW(table: *const ServiceTableEntry) -> i32;
+		fn RegisterServiceCtrlHandlerW(name: *const u16, handler: unsafe extern "system" fn(u32)) -> Handle;
+		fn SetServiceStatus(handle: Handle, status: *mut ServiceStatus) -> i32;
+	}
+
+	fn wide(s: &str) -> Vec<u16> {
+		OsStr::new(s).encode_wide().chain(Some(0)).collect()
+	}
+
+	/// Service control manager handle, closed on drop.
+	struct ScHandle(Handle);
+
+	impl ScHandle {
+		fn new(handle: Handle, what: &str) -> Result<Self, String> {
+			match handle.is_null() {
+				true => Err(format!("{}: {}", what, io::Error::last_os_error())),
+				false => Ok(ScHandle(handle)),
+			}
+		}
+	}
+
+	impl Drop for ScHandle {
+		fn drop(&mut self) {
+			unsafe { CloseServiceHandle(self.0); }
+		}
+	}
+
+	fn status(state: u32) -> ServiceStatus {
+		ServiceStatus {
+			service_type: SERVICE_WIN32_OWN_PROCESS,
+			current_state: state,
+			controls_accepted: match state {
+				SERVICE_RUNNING => SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_SHUTDOWN,
+				_ => 0,
+			},
+			win32_exit_code: 0,
+			service_specific_exit_code: 0,
+			check_point: 0,
+			wait_hint: match state {
+				SERVICE_START_PENDING | SERVICE_STOP_PENDING => STOP_WAIT_HINT_MS,
+				_ => 0,
+			},
+		}
+	}
+
+	pub fn install(name: &str, command_line: &str) -> Result<(), String> {
+		let manager = ScHandle::new(unsafe { OpenSCManagerW(ptr::null(), ptr::null(), SC_MANAGER_CONNECT | SC_MANAGER_CREATE_SERVICE) }, "Unable to connect to the service control manager")?;
+		let (name, display_name, command_line) = (wide(name), wide("Parity Ethereum Client"), wide(command_line));
+		let service = ScHandle::new(unsafe {
+			CreateServiceW(manager.0, name.as_ptr(), display_name.as_ptr(), SERVICE_CHANGE_CONFIG,
+				SERVICE_WIN32_OWN_PROCESS, SERVICE_AUTO_START, SERVICE_ERROR_NORMAL, command_line.as_ptr(),
+				ptr::null(), ptr::null_mut(), ptr::null(), ptr::null(), ptr::null())
+		}, "Unable to create the service")?;
+
+		let description = wide("Ethereum node");
+		let mut info = ServiceDescription { description: description.as_ptr() };
+		// the description is cosmetic, ignore failures
+		unsafe { ChangeServiceConfig2W(service.0, SERVICE_CONFIG_DESCRIPTION, &mut info as *mut _ as *mut u8); }
+		Ok(())
+	}
+
+	pub fn uninstall(name: &str) -> Result<(), String> {
+		let manager = ScHandle::new(unsafe { OpenSCManagerW(ptr::null(), ptr::null(), SC_MANAGER_CONNECT) }, "Unable to connect to the service control manager")?;
+		let name = wide(name);
+		let service = ScHandle::new(unsafe { OpenServiceW(manager.0, name.as_ptr(), SERVICE_STOP | SERVICE_QUERY_STATUS | DELETE) }, "Unable to open the service")?;
+
+		let mut current = status(SERVICE_STOPPED);
+		if unsafe { ControlService(service.0, SERVICE_CONTROL_STOP, &mut current) } == 0 {
+			let err = io::Error::last_os_error();
+			if err.raw_os_error() != Some(ERROR_SERVICE_NOT_ACTIVE) {
+				return Err(format!("Unable to stop the service: {}", err));
+			}
+		} else {
+			let mut waited = 0;
+			while current.current_state != SERVICE_STOPPED && waited < UNINSTALL_STOP_TIMEOUT_SECS {
+				thread::sleep(Duration::from_secs(1));
+				waited += 1;
+				if unsafe { QueryServiceStatus(service.0, &mut current) } == 0 {
+					break;
+				}
+			}
+		}
+
+		match unsafe { DeleteService(service.0) } {
+			0 => Err(format!("Unable to delete the service: {}", io::Error::last_os_error())),
+			_ => Ok(()),
+		}
+	}
+
+	/// State passed to the service main function, which takes no context.
+	struct Service {
+		name: String,
+		cmd: Option<RunCmd>,
+		logger: Arc<RotatingLogger>,
+		result: Result<(), String>,
+	}
+
+	lazy_static! {
+		static ref SERVICE: Mutex<Option<Service>> = Mutex::new(None);
+		static ref STATUS_HANDLE: Mutex<usize> = Mutex::new(0);
+	}
+
+	fn set_status(state: u32, exit_code: Option<u32>) {
+		let handle = *STATUS_HANDLE.lock() as Handle;
+		let mut status = status(state);
+		if let Some(code) = exit_code {
+			status.win32_exit_code = ERROR_SERVICE_SPECIFIC_ERROR;
+			status.service_specific_exit_code = code;
+		}
+		unsafe { SetServiceStatus(handle, &mut status); }
+	}
+
+	unsafe extern "system" fn control_handler(control: u32) {
+		match control {
+			SERVICE_CONTROL_STOP | SERVICE_CONTROL_SHUTDOWN => {
+				info!("Service stop requested");
+				set_status(SERVICE_STOP_PENDING, None);
+				STOP_SIGNAL.lock().request();
+			},
+			_ => {},
+		}
+	}
+
+	unsafe extern "system" fn service_main(_argc: u32, _argv: *mut *mut u16) {
+		let (name, cmd, logger) = match SERVICE.lock().as_mut() {
+			Some(service) => (service.name.clone(), service.cmd.take(), service.logger.clone()),
+			None => return,
+		};
+		let cmd = match cmd {
+			Some(cmd) => cmd,
+			None => return,
+		};
+
+		let wide_name = wide(&name);
+		let handle = RegisterServiceCtrlHandlerW(wide_name.as_ptr(), control_handler);
+		if handle.is_null() {
+			if let Some(service) = SERVICE.lock().as_mut() {
+				service.result = Err(format!("Unable to register the service control handler: {}", io::Error::last_os_error()));
+			}
+			return;
+		}
+		*STATUS_HANDLE.lock() = handle as usize;
+
+		set_status(SERVICE_START_PENDING, None);
+		// the node is started in the background, stop requests are remembered until it waits for them.
+		set_status(SERVICE_RUNNING, None);
+		info!("Service {} started", name);
+
+		let result = run::execute(cmd, false, logger).map(|_| ());
+		match result {
+			Ok(()) => {
+				info!("Service {} stopped", name);
+				set_status(SERVICE_STOPPED, None);
+			},
+			Err(ref e) => {
+				error!("Service {} failed: {}", name, e);
+				set_status(SERVICE_STOPPED, Some(1));
+			},
+		}
+		if let Some(service) = SERVICE.lock().as_mut() {
+			service.result = result;
+		}
+	}
+
+	pub fn run(name: &str, cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
+		let wide_name = wide(name);
+		*SERVICE.lock() = Some(Service {
+			name: name.to_owned(),
+			cmd: Some(cmd),
+			logger: logger,
+			result: Ok(()),
+		});
+
+		let table = [
+			ServiceTableEntry { name: wide_name.as_ptr(), main: Some(service_main) },
+			ServiceTableEntry { name: ptr::null(), main: None },
+		];
+		// blocks until the service stops
+		if unsafe { StartServiceCtrlDispatcherW(table.as_ptr()) } == 0 {
+			return Err(format!("Unable to connect to the service control manager, `parity service run` is started by Windows: {}", io::Error::last_os_error()));
+		}
+
+		let service = SERVICE.lock().take().expect("set above; qed");
+		*STATUS_HANDLE.lock() = 0;
+		service.result
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use super::{StopSignal, quote_argument, service_command_line};
+
+	#[test]
+	fn should_quote_arguments() {
+		assert_eq!(quote_argument("--chain"), "--chain");
+		assert_eq!(quote_argument(""), "\"\"");
+		assert_eq!(quote_argument("C:\\Program Files\\Parity\\parity.exe"), "\"C:\\Program Files\\Parity\\parity.exe\"");
+		assert_eq!(quote_argument("C:\\My Dir\\"), "\"C:\\My Dir\\\\\"");
+		assert_eq!(quote_argument("say \"hi\""), "\"say \\\"hi\\\"\"");
+	}
+
+	#[test]
+	fn should_build_service_command_line() {
+		let args: Vec<String> = vec!["service".into(), "install".into(), "--chain".into(), "kovan".into()];
+		assert_eq!(
+			service_command_line("C:\\Program Files\\Parity\\parity.exe", &args),
+			"\"C:\\Program Files\\Parity\\parity.exe\" service run --chain kovan --force-direct"
+		);
+	}
+
+	#[test]
+	fn should_remember_early_stop_request() {
+		let calls = Arc::new(AtomicUsize::new(0));
+		let mut signal = StopSignal::default();
+		signal.request();
+
+		let c = calls.clone();
+		signal.set_handler(Box::new(move || { c.fetch_add(1, Ordering::SeqCst); }));
+		assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+		signal.request();
+		assert_eq!(calls.load(Ordering::SeqCst), 2);
+	}
+}
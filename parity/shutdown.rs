@@ -0,0 +1,149 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Graceful shutdown with a deadline.
+//!
+//! Every stage of the shutdown is logged. If the deadline passes before the
+//! shutdown completes, the process exits with the stage it was stuck in.
+
+use std::{fmt, process, thread};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use util::{Mutex, Condvar};
+
+/// Exit code of a shutdown which didn't complete in time.
+const FORCED_EXIT_CODE: i32 = 2;
+
+/// Stage of the shutdown.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Stage {
+	/// Closing the RPC, UI, IPFS and health servers.
+	StoppingServers,
+	/// Stopping the informant and the hypervised modules.
+	StoppingServices,
+	/// Waiting for the block being imported.
+	FinishingImport,
+	/// Writing buffered changes to the database.
+	FlushingCaches,
+	/// Releasing the client and closing the database.
+	ClosingDatabase,
+}
+
+impl fmt::Display for Stage {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let description = match *self {
+			Stage::StoppingServers => "stopping servers",
+			Stage::StoppingServices => "stopping services",
+			Stage::FinishingImport => "finishing current block import",
+			Stage::FlushingCaches => "flushing caches",
+			Stage::ClosingDatabase => "closing database",
+		};
+		f.write_str(description)
+	}
+}
+
+struct State {
+	stage: Option<Stage>,
+	done: bool,
+}
+
+/// Tracks the shutdown stages and forces the exit after the deadline.
+/// Should outlive everything it's meant to supervise.
+pub struct Shutdown {
+	deadline: Option<Duration>,
+	state: Arc<(Mutex<State>, Condvar)>,
+}
+
+impl Shutdown {
+	/// Create a tracker, the deadline (if any) starts running with the first stage.
+	pub fn new(deadline: Option<Duration>) -> Self {
+		Shutdown {
+			deadline: deadline,
+			state: Arc::new((Mutex::new(State { stage: None, done: false }), Condvar::new())),
+		}
+	}
+
+	/// Enter the next stage.
+	pub fn stage(&self, stage: Stage) {
+		info!("Shutdown: {}", stage);
+		let started = {
+			let mut state = self.state.0.lock();
+			let started = state.stage.is_some();
+			state.stage = Some(stage);
+			started
+		};
+
+		if let (false, Some(deadline)) = (started, self.deadline) {
+			self.start_watchdog(deadline);
+		}
+	}
+
+	fn start_watchdog(&self, deadline: Duration) {
+		let state = self.state.clone();
+		let spawned = thread::Builder::new().name("Shutdown".into()).spawn(move || {
+			let started = Instant::now();
+			let mut s = state.0.lock();
+			while !s.done {
+				let elapsed = started.elapsed();
+				if elapsed >= deadline {
+					error!("Shutdown didn't complete in {} s, forcing exit while {}", deadline.as_secs(), s.stage.expect("watchdog is started with the first stage; qed"));
+					process::exit(FORCED_EXIT_CODE);
+				}
+				state.1.wait_for(&mut s, deadline - elapsed);
+			}
+		});
+		if let Err(e) = spawned {
+			warn!("Unable to enforce the shutdown deadline: {}", e);
+		}
+	}
+}
+
+impl Drop for Shutdown {
+	fn drop(&mut self) {
+		let mut state = self.state.0.lock();
+		if state.stage.is_some() {
+			info!("Shutdown: complete");
+		}
+		state.done = true;
+		self.state.1.notify_all();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use super::{Shutdown, Stage};
+
+	#[test]
+	fn should_track_stages() {
+		let shutdown = Shutdown::new(Some(Duration::from_secs(60)));
+		assert_eq!(shutdown.state.0.lock().stage, None);
+
+		shutdown.stage(Stage::StoppingServers);
+		shutdown.stage(Stage::FinishingImport);
+		assert_eq!(shutdown.state.0.lock().stage, Some(Stage::FinishingImport));
+
+		let state = shutdown.state.clone();
+		drop(shutdown);
+		assert!(state.0.lock().done);
+	}
+
+	#[test]
+	fn should_describe_stages() {
+		assert_eq!(format!("{}", Stage::FinishingImport), "finishing current block import");
+		assert_eq!(format!("{}", Stage::ClosingDatabase), "closing database");
+	}
+}
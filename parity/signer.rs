@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::fs::File;
 use std::io;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -21,17 +22,21 @@ use std::sync::Arc;
 pub use ethcore_signer::Server as SignerServer;
 
 use ansi_term::Colour;
+use rustc_serialize::hex::FromHex;
 use dir::default_data_path;
 use parity_rpc::informant::RpcStats;
 use parity_rpc::{self, ConfirmationsQueue};
+use parity_rpc::signing_policy::{SigningPolicy, Rule, Action};
+use serde_json::{self, Value};
 use ethcore_signer as signer;
-use helpers::replace_home;
+use helpers::{replace_home, to_u256};
 use parity_reactor::TokioRemote;
 use rpc_apis;
 use path::restrict_permissions_owner;
-use util::H256;
+use util::{H256, Address, clean_0x};
 
 const CODES_FILENAME: &'static str = "authcodes";
+const IPC_FILENAME: &'static str = "signer.ipc";
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Configuration {
@@ -96,6 +101,13 @@ fn codes_path(path: String) -> PathBuf {
 	p
 }
 
+/// Path of the IPC socket local tools use to talk to the Trusted Signer.
+pub fn ipc_path(signer_path: &str) -> PathBuf {
+	let mut p = PathBuf::from(signer_path);
+	p.push(IPC_FILENAME);
+	p
+}
+
 pub fn execute(cmd: Configuration) -> Result<String, String> {
 	Ok(generate_token_and_url(&cmd)?.message)
 }
@@ -129,6 +141,113 @@ pub fn generate_new_token(path: String) -> io::Result<String> {
 	Ok(code)
 }
 
+/// Creates the signer service, deciding requests by the policy in given file if any.
+pub fn new_service(signer_path: String, address: Option<(String, u16)>, policy_file: Option<&str>) -> Result<rpc_apis::SignerService, String> {
+	let service = rpc_apis::SignerService::new(move || {
+		generate_new_token(signer_path.clone()).map_err(|e| format!("{:?}", e))
+	}, address);
+
+	match policy_file {
+		Some(path) => {
+			let policy = load_policy(path)?;
+			info!("Signing requests are decided by the policy in {}", path);
+			Ok(service.with_policy(policy))
+		},
+		None => Ok(service),
+	}
+}
+
+/// Loads the signing policy from a JSON file like:
+///
+/// ```json
+/// {
+///   "rules": [
+///     { "to": ["0x..."], "methods": ["0xa9059cbb"], "maxDailyValue": "1000000000000000000", "action": "approve" },
+///     { "maxValue": "10000000000000000000", "action": "escalate", "confirmations": 2 }
+///   ],
+///   "default": { "action": "reject" }
+/// }
+/// ```
+///
+/// Rules may also restrict the signing accounts with `from`. Values are in wei.
+/// Requests not matching any rule are escalated with a single confirmation by default.
+pub fn load_policy(path: &str) -> Result<SigningPolicy, String> {
+	let file = File::open(path).map_err(|e| format!("Error opening signer policy file {}: {}", path, e))?;
+	let policy: Value = serde_json::from_reader(file).map_err(|e| format!("Invalid signer policy file {}: {}", path, e))?;
+	parse_policy(&policy).map_err(|e| format!("Invalid signer policy file {}: {}", path, e))
+}
+
+fn parse_policy(policy: &Value) -> Result<SigningPolicy, String> {
+	let rules = match policy.get("rules") {
+		Some(&Value::Array(ref rules)) => rules.iter().map(parse_rule).collect::<Result<_, _>>()?,
+		Some(_) => return Err("rules must be an array".into()),
+		None => Vec::new(),
+	};
+	let default = match policy.get("default") {
+		Some(default) => parse_action(default)?,
+		None => Action::default(),
+	};
+
+	Ok(SigningPolicy::new(rules, default))
+}
+
+fn parse_rule(rule: &Value) -> Result<Rule, String> {
+	let addresses = |key: &str| -> Result<Option<Vec<Address>>, String> {
+		match rule.get(key) {
+			Some(&Value::Array(ref addresses)) => addresses.iter()
+				.map(|a| a.as_str().and_then(|a| clean_0x(a).parse().ok()).ok_or_else(|| format!("invalid address in {}: {}", key, a)))
+				.collect::<Result<_, _>>()
+				.map(Some),
+			Some(_) => Err(format!("{} must be an array of addresses", key)),
+			None => Ok(None),
+		}
+	};
+	let value = |key: &str| -> Result<Option<::util::U256>, String> {
+		match rule.get(key) {
+			Some(&Value::String(ref value)) => to_u256(value).map(Some),
+			Some(value) => value.as_u64().map(|value| Some(value.into())).ok_or_else(|| format!("invalid {}: {}", key, value)),
+			None => Ok(None),
+		}
+	};
+	let methods = match rule.get("methods") {
+		Some(&Value::Array(ref methods)) => Some(methods.iter().map(|method| {
+			let selector = method.as_str().and_then(|m| clean_0x(m).from_hex().ok()).unwrap_or_default();
+			match selector.len() {
+				4 => Ok([selector[0], selector[1], selector[2], selector[3]]),
+				_ => Err(format!("invalid method selector: {}, expected 4 bytes", method)),
+			}
+		}).collect::<Result<_, _>>()?),
+		Some(_) => return Err("methods must be an array of selectors".into()),
+		None => None,
+	};
+
+	Ok(Rule {
+		from: addresses("from")?,
+		to: addresses("to")?,
+		methods: methods,
+		max_value: value("maxValue")?,
+		max_daily_value: value("maxDailyValue")?,
+		action: parse_action(rule)?,
+	})
+}
+
+fn parse_action(value: &Value) -> Result<Action, String> {
+	let confirmations = match value.get("confirmations") {
+		Some(confirmations) => match confirmations.as_u64() {
+			Some(confirmations) if confirmations > 0 => confirmations as usize,
+			_ => return Err(format!("invalid confirmations: {}, expected a positive number", confirmations)),
+		},
+		None => 1,
+	};
+
+	match value.get("action").and_then(Value::as_str) {
+		Some("approve") => Ok(Action::Approve),
+		Some("reject") => Ok(Action::Reject),
+		Some("escalate") => Ok(Action::Escalate(confirmations)),
+		_ => Err(format!("invalid action in {}, expected approve, reject or escalate", value)),
+	}
+}
+
 fn do_start<D: rpc_apis::Dependencies>(
 	conf: Configuration,
 	queue: Arc<ConfirmationsQueue>,
@@ -141,7 +260,7 @@ fn do_start<D: rpc_apis::Dependencies>(
 	let start_result = {
 		let server = signer::ServerBuilder::new(
 			queue,
-			codes_path(conf.signer_path),
+			codes_path(conf.signer_path.clone()),
 		);
 		if conf.skip_origin_validation {
 			warn!("{}", Colour::Red.bold().paint("*** INSECURE *** Running Trusted Signer with no origin validation."));
@@ -149,7 +268,8 @@ fn do_start<D: rpc_apis::Dependencies>(
 		}
 		let server = server.skip_origin_validation(conf.skip_origin_validation);
 		let server = server.stats(deps.rpc_stats.clone());
-		let handler = rpc_apis::setup_rpc(deps.rpc_stats, &*deps.apis, rpc_apis::ApiSet::SafeContext);
+		let server = server.ipc_path(ipc_path(&conf.signer_path));
+		let handler = rpc_apis::setup_rpc(deps.rpc_stats, &*deps.apis, rpc_apis::ApiSet::SafeContext, Default::default(), &Default::default());
 		let remote = deps.remote.clone();
 		server.start_with_extractor(addr, handler, remote, StandardExtractor)
 	};
@@ -164,4 +284,45 @@ fn do_start<D: rpc_apis::Dependencies>(
 	}
 }
 
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use parity_rpc::signing_policy::{Rule, Action};
+	use super::{parse_rule, parse_policy};
+
+	#[test]
+	fn should_parse_policy_rules() {
+		let rule = serde_json::from_str(r#"{
+			"to": ["0xd46e8dd67c5d32be8058bb8eb970870f07244567"],
+			"methods": ["0xa9059cbb"],
+			"maxValue": "0x10",
+			"maxDailyValue": 100,
+			"action": "escalate",
+			"confirmations": 2
+		}"#).unwrap();
+
+		assert_eq!(parse_rule(&rule), Ok(Rule {
+			from: None,
+			to: Some(vec!["d46e8dd67c5d32be8058bb8eb970870f07244567".parse().unwrap()]),
+			methods: Some(vec![[0xa9, 0x05, 0x9c, 0xbb]]),
+			max_value: Some(16.into()),
+			max_daily_value: Some(100.into()),
+			action: Action::Escalate(2),
+		}));
+	}
 
+	#[test]
+	fn should_reject_invalid_policy() {
+		let invalid = [
+			r#"{"rules": [{"action": "sign"}]}"#,
+			r#"{"rules": [{"methods": ["0xa9059c"], "action": "approve"}]}"#,
+			r#"{"rules": [{"to": ["0x01"], "action": "approve"}]}"#,
+			r#"{"rules": [], "default": {"action": "escalate", "confirmations": 0}}"#,
+			r#"{"rules": {}}"#,
+		];
+
+		for policy in &invalid {
+			assert!(parse_policy(&serde_json::from_str(policy).unwrap()).is_err(), "{} should be invalid", policy);
+		}
+	}
+}
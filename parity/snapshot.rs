@@ -19,19 +19,24 @@
 use std::time::Duration;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::fs::{self, File};
+use std::io::{self, ErrorKind};
 
-use ethcore::snapshot::{Progress, RestorationStatus, SnapshotService as SS};
-use ethcore::snapshot::io::{SnapshotReader, PackedReader, PackedWriter};
+use ethcore::snapshot::{Progress, RestorationStatus, SnapshotService as SS, verify_chunks, verify_restoration};
+use ethcore::snapshot::io::{SnapshotReader, SnapshotWriter, PackedReader, PackedWriter, LooseReader, LooseWriter};
 use ethcore::snapshot::service::Service as SnapshotService;
 use ethcore::service::ClientService;
 use ethcore::client::{Mode, DatabaseCompactionProfile, VMType};
 use ethcore::miner::Miner;
 use ethcore::ids::BlockId;
+use hash_fetch::fetch::{Fetch, Client as FetchClient};
+use util::{Bytes, H256};
 
 use cache::CacheConfig;
 use params::{SpecType, Pruning, Switch, tracing_switch_to_bool, fatdb_switch_to_bool};
 use helpers::{to_client_config, execute_upgrades};
 use dir::Directories;
+use snapshot_encryption::{EncryptionKeys, encrypt_file, decrypt_file, is_encrypted};
 use user_defaults::UserDefaults;
 use fdlimit;
 
@@ -43,7 +48,22 @@ pub enum Kind {
 	/// Take a snapshot.
 	Take,
 	/// Restore a snapshot.
-	Restore
+	Restore,
+	/// Pack the local snapshot into a single file.
+	Export,
+	/// Replace the local snapshot with a packed one.
+	Import,
+	/// Check a packed snapshot.
+	Verify,
+}
+
+/// Snapshot mirror a fresh node is restored from.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WarpFrom {
+	/// URL of a packed snapshot.
+	pub url: String,
+	/// Expected hash of the snapshot manifest RLP.
+	pub manifest_hash: Option<H256>,
 }
 
 /// Command for snapshot creation or restoration.
@@ -62,22 +82,36 @@ pub struct SnapshotCommand {
 	pub wal: bool,
 	pub kind: Kind,
 	pub block_at: BlockId,
+	/// Packed snapshot to take a delta snapshot from.
+	pub delta_from: Option<String>,
+	/// Packed delta snapshots to apply on top of the restored one, in order.
+	pub deltas: Vec<String>,
+	/// Directory to restore the snapshot into when verifying it.
+	pub scratch_dir: Option<String>,
+	/// Number of threads chunking the state.
+	pub threads: usize,
+	/// Keys to encrypt exported or decrypt imported files with.
+	pub encryption: EncryptionKeys,
 }
 
 // helper for reading chunks from arbitrary reader and feeding them into the
-// service.
-fn restore_using<R: SnapshotReader>(snapshot: Arc<SnapshotService>, reader: &R, recover: bool) -> Result<(), String> {
+// service. state of the delta snapshots is applied in order after the full one.
+fn restore_using(snapshot: Arc<SnapshotService>, reader: &SnapshotReader, deltas: &[PackedReader], recover: bool) -> Result<(), String> {
 	use util::sha3::Hashable;
 
 	let manifest = reader.manifest();
+	let readers: Vec<&SnapshotReader> = ::std::iter::once(reader).chain(deltas.iter().map(|d| d as &SnapshotReader)).collect();
+	let last = readers[readers.len() - 1].manifest();
 
-	info!("Restoring to block #{} (0x{:?})", manifest.block_number, manifest.block_hash);
+	info!("Restoring to block #{} (0x{:?})", last.block_number, last.block_hash);
 
-	snapshot.init_restore(manifest.clone(), recover).map_err(|e| {
+	let delta_manifests = deltas.iter().map(|d| d.manifest().clone()).collect();
+	snapshot.init_restore_with_deltas(manifest.clone(), delta_manifests, recover).map_err(|e| {
 		format!("Failed to begin restoration: {}", e)
 	})?;
 
-	let (num_state, num_blocks) = (manifest.state_hashes.len(), manifest.block_hashes.len());
+	let num_state = readers.iter().map(|r| r.manifest().state_hashes.len()).sum::<usize>();
+	let num_blocks = last.block_hashes.len();
 
 	let informant_handle = snapshot.clone();
 	::std::thread::spawn(move || {
@@ -89,24 +123,27 @@ fn restore_using<R: SnapshotReader>(snapshot: Arc<SnapshotService>, reader: &R,
  	});
 
  	info!("Restoring state");
- 	for &state_hash in &manifest.state_hashes {
- 		if snapshot.status() == RestorationStatus::Failed {
- 			return Err("Restoration failed".into());
- 		}
+	for reader in &readers {
+		for &state_hash in &reader.manifest().state_hashes {
+			if snapshot.status() == RestorationStatus::Failed {
+				return Err("Restoration failed".into());
+			}
 
- 		let chunk = reader.chunk(state_hash)
-			.map_err(|e| format!("Encountered error while reading chunk {:?}: {}", state_hash, e))?;
+			let chunk = reader.chunk(state_hash)
+				.map_err(|e| format!("Encountered error while reading chunk {:?}: {}", state_hash, e))?;
 
-		let hash = chunk.sha3();
-		if hash != state_hash {
-			return Err(format!("Mismatched chunk hash. Expected {:?}, got {:?}", state_hash, hash));
-		}
+			let hash = chunk.sha3();
+			if hash != state_hash {
+				return Err(format!("Mismatched chunk hash. Expected {:?}, got {:?}", state_hash, hash));
+			}
 
- 		snapshot.feed_state_chunk(state_hash, &chunk);
- 	}
+			snapshot.feed_state_chunk(state_hash, &chunk);
+		}
+	}
 
 	info!("Restoring blocks");
-	for &block_hash in &manifest.block_hashes {
+	let reader = readers[readers.len() - 1];
+	for &block_hash in &last.block_hashes {
 		if snapshot.status() == RestorationStatus::Failed {
 			return Err("Restoration failed".into());
 		}
@@ -131,7 +168,90 @@ fn restore_using<R: SnapshotReader>(snapshot: Arc<SnapshotService>, reader: &R,
 	}
 }
 
+/// Download a packed snapshot and restore from it, keeping it as the local snapshot.
+pub fn restore_from_url(snapshot: Arc<SnapshotService>, warp_from: &WarpFrom, snapshot_root: &Path) -> Result<(), String> {
+	use util::sha3::Hashable;
+
+	let path = snapshot_root.join("download.pack");
+	let result = download(&warp_from.url, &path).and_then(|_| {
+		let reader = PackedReader::new(&path)
+			.map_err(|e| format!("Couldn't open downloaded snapshot: {}", e))?
+			.ok_or("Downloaded snapshot has invalid format.".to_owned())?;
+
+		let hash = reader.manifest().clone().into_rlp().sha3();
+		match warp_from.manifest_hash {
+			Some(expected) if expected != hash => {
+				return Err(format!("Mismatched snapshot manifest hash. Expected {:?}, got {:?}", expected, hash));
+			},
+			Some(_) => {},
+			None => warn!("Restoring snapshot with unverified manifest hash {:?}, use --warp-from-hash to pin it.", hash),
+		}
+
+		restore_using(snapshot, &reader, &[], true)
+	});
+
+	let _ = fs::remove_file(&path);
+	result
+}
+
+fn download(url: &str, path: &Path) -> Result<(), String> {
+	info!("Downloading snapshot from {}", url);
+
+	let fetch = FetchClient::with_limit(None).map_err(|e| format!("Error starting fetch client: {:?}", e))?;
+	let mut response = fetch.fetch_sync(url).map_err(|e| format!("Error downloading snapshot from {}: {:?}", url, e))?;
+	if !response.is_success() {
+		return Err(format!("Error downloading snapshot from {}: {}", url, response.status()));
+	}
+
+	let mut file = File::create(path).map_err(|e| format!("Couldn't create {}: {}", path.display(), e))?;
+	let size = io::copy(&mut response, &mut file).map_err(|e| format!("Error downloading snapshot from {}: {}", url, e))?;
+	info!("Downloaded snapshot of {}", ::informant::format_bytes(size as usize));
+	Ok(())
+}
+
+fn open_packed(file: &str) -> Result<PackedReader, String> {
+	PackedReader::new(Path::new(file))
+		.map_err(|e| format!("Couldn't open snapshot file {}: {}", file, e))?
+		.ok_or_else(|| format!("Snapshot file {} has invalid format.", file))
+}
+
+// copy all chunks of a snapshot, checking their hashes.
+fn copy_using<R: SnapshotReader, W: SnapshotWriter>(reader: &R, mut writer: W) -> Result<(), String> {
+	use util::sha3::Hashable;
+
+	let manifest = reader.manifest().clone();
+	let read = |hash: H256| -> Result<Bytes, String> {
+		let chunk = reader.chunk(hash)
+			.map_err(|e| format!("Encountered error while reading chunk {:?}: {}", hash, e))?;
+		let actual = chunk.sha3();
+		if actual != hash {
+			return Err(format!("Mismatched chunk hash. Expected {:?}, got {:?}", hash, actual));
+		}
+		Ok(chunk)
+	};
+
+	for &hash in &manifest.state_hashes {
+		writer.write_state_chunk(hash, &read(hash)?)
+			.map_err(|e| format!("Failed to write chunk {:?}: {}", hash, e))?;
+	}
+	for &hash in &manifest.block_hashes {
+		writer.write_block_chunk(hash, &read(hash)?)
+			.map_err(|e| format!("Failed to write chunk {:?}: {}", hash, e))?;
+	}
+
+	info!("Copied {} state chunks and {} block chunks of snapshot at block #{}",
+		manifest.state_hashes.len(), manifest.block_hashes.len(), manifest.block_number);
+	writer.finish(manifest).map_err(|e| format!("Failed to write snapshot manifest: {}", e))
+}
+
 impl SnapshotCommand {
+	// root of the local snapshot directories.
+	fn snapshot_root(&self) -> Result<PathBuf, String> {
+		let spec = self.spec.spec()?;
+		let db_dirs = self.dirs.database(spec.genesis_header().hash(), None, spec.data_dir.clone());
+		Ok(db_dirs.snapshot_path())
+	}
+
 	// shared portion of snapshot commands: start the client service
 	fn start_service(self) -> Result<(ClientService, Arc<PanicHandler>), String> {
 		// Setup panic handler
@@ -171,7 +291,7 @@ impl SnapshotCommand {
 		execute_upgrades(&self.dirs.base, &db_dirs, algorithm, self.compaction.compaction_profile(db_dirs.db_root_path().as_path()))?;
 
 		// prepare client config
-		let client_config = to_client_config(
+		let mut client_config = to_client_config(
 			&self.cache_config,
 			spec.name.to_lowercase(),
 			Mode::Active,
@@ -186,6 +306,7 @@ impl SnapshotCommand {
 			self.pruning_memory,
 			true
 		);
+		client_config.snapshot_threads = self.threads;
 
 		let service = ClientService::start(
 			client_config,
@@ -201,6 +322,10 @@ impl SnapshotCommand {
 	/// restore from a snapshot
 	pub fn restore(self) -> Result<(), String> {
 		let file = self.file_path.clone();
+		let deltas = self.deltas.iter().map(|path| open_packed(path)).collect::<Result<Vec<_>, _>>()?;
+		if !deltas.is_empty() && file.is_none() {
+			return Err("Delta snapshots can only be restored on top of a snapshot file.".into());
+		}
 		let (service, _panic_handler) = self.start_service()?;
 
 		warn!("Snapshot restoration is experimental and the format may be subject to change.");
@@ -211,19 +336,15 @@ impl SnapshotCommand {
 		if let Some(file) = file {
 			info!("Attempting to restore from snapshot at '{}'", file);
 
-			let reader = PackedReader::new(Path::new(&file))
-				.map_err(|e| format!("Couldn't open snapshot file: {}", e))
-				.and_then(|x| x.ok_or("Snapshot file has invalid format.".into()));
-
-			let reader = reader?;
-			restore_using(snapshot, &reader, true)?;
+			let reader = open_packed(&file)?;
+			restore_using(snapshot, &reader, &deltas, true)?;
 		} else {
 			info!("Attempting to restore from local snapshot.");
 
 			// attempting restoration with recovery will lead to deadlock
 			// as we currently hold a read lock on the service's reader.
 			match *snapshot.reader() {
-				Some(ref reader) => restore_using(snapshot.clone(), reader, false)?,
+				Some(ref reader) => restore_using(snapshot.clone(), reader, &[], false)?,
 				None => return Err("No local snapshot found.".into()),
 			}
 		}
@@ -236,6 +357,10 @@ impl SnapshotCommand {
 		let file_path = self.file_path.clone().ok_or("No file path provided.".to_owned())?;
 		let file_path: PathBuf = file_path.into();
 		let block_at = self.block_at;
+		let base = match self.delta_from {
+			Some(ref base) => Some(open_packed(base)?.manifest().clone()),
+			None => None,
+		};
 		let (service, _panic_handler) = self.start_service()?;
 
 		warn!("Snapshots are currently experimental. File formats may be subject to change.");
@@ -261,7 +386,12 @@ impl SnapshotCommand {
 			}
  		});
 
-		if let Err(e) = service.client().take_snapshot(writer, block_at, &*progress) {
+		let result = match base {
+			Some(ref base) => service.client().take_delta_snapshot(writer, block_at, base, &*progress),
+			None => service.client().take_snapshot(writer, block_at, &*progress),
+		};
+
+		if let Err(e) = result {
 			let _ = ::std::fs::remove_file(&file_path);
 			return Err(format!("Encountered fatal error while creating snapshot: {}", e));
 		}
@@ -272,14 +402,127 @@ impl SnapshotCommand {
 		informant_handle.join().map_err(|_| "failed to join logger thread")?;
 
 		Ok(())
+}
+
+	/// Pack the latest local snapshot into a single file.
+	pub fn export_snapshot(self) -> Result<(), String> {
+		let file_path: PathBuf = self.file_path.clone().ok_or("No file path provided.".to_owned())?.into();
+		let reader = LooseReader::new(self.snapshot_root()?.join("current"))
+			.map_err(|e| format!("No local snapshot found: {}", e))?;
+
+		// the file is packed next to the local snapshot first if it's going to be encrypted.
+		let encrypt = self.encryption.encrypts();
+		let packed_path = match encrypt {
+			true => self.snapshot_root()?.join("export.pack"),
+			false => file_path.clone(),
+		};
+		let writer = PackedWriter::new(&packed_path)
+			.map_err(|e| format!("Failed to open snapshot writer: {}", e))?;
+
+		if let Err(e) = copy_using(&reader, writer) {
+			let _ = fs::remove_file(&packed_path);
+			return Err(e);
+		}
+
+		if encrypt {
+			let result = encrypt_file(&packed_path, &file_path, &self.encryption);
+			let _ = fs::remove_file(&packed_path);
+			if let Err(e) = result {
+				let _ = fs::remove_file(&file_path);
+				return Err(e);
+			}
+		}
+
+		info!("Snapshot exported to '{}'", file_path.display());
+		Ok(())
+	}
+
+	/// Replace the local snapshot with a packed one. It is served to peers once the node is restarted.
+	pub fn import_snapshot(self) -> Result<(), String> {
+		let file = self.file_path.clone().ok_or("No file path provided.".to_owned())?;
+		let root = self.snapshot_root()?;
+
+		let encrypted = is_encrypted(Path::new(&file)).map_err(|e| format!("Couldn't open snapshot file: {}", e))?;
+		let packed_path = match encrypted {
+			true => root.join("import.pack"),
+			false => PathBuf::from(&file),
+		};
+		let result = match encrypted {
+			true => decrypt_file(Path::new(&file), &packed_path, &self.encryption),
+			false => Ok(()),
+		}.and_then(|_| self.import_packed(&packed_path, &root));
+		if encrypted {
+			let _ = fs::remove_file(&packed_path);
+		}
+		result?;
+
+		info!("Snapshot imported from '{}'", file);
+		Ok(())
+	}
+
+	// replace the local snapshot with the packed one at `path`.
+	fn import_packed(&self, path: &Path, root: &Path) -> Result<(), String> {
+		let reader = PackedReader::new(path)
+			.map_err(|e| format!("Couldn't open snapshot file: {}", e))?
+			.ok_or("Snapshot file has invalid format.".to_owned())?;
+
+		// write next to the current snapshot, so that it's replaced only if the whole file is valid.
+		let (temp, current) = (root.join("in_progress"), root.join("current"));
+		let _ = fs::remove_dir_all(&temp);
+		let writer = LooseWriter::new(temp.clone())
+			.map_err(|e| format!("Failed to open snapshot writer: {}", e))?;
+
+		if let Err(e) = copy_using(&reader, writer) {
+			let _ = fs::remove_dir_all(&temp);
+			return Err(e);
+		}
+
+		if let Err(e) = fs::remove_dir_all(&current) {
+			if e.kind() != ErrorKind::NotFound {
+				return Err(format!("Failed to remove the current snapshot: {}", e));
+			}
+		}
+		fs::rename(&temp, &current).map_err(|e| format!("Failed to replace the current snapshot: {}", e))
+	}
+
+	/// Check chunks of a packed snapshot and optionally restore it into a scratch database.
+	pub fn verify_snapshot(self) -> Result<String, String> {
+		let file = self.file_path.clone().ok_or("No file path provided.".to_owned())?;
+		let reader = open_packed(&file)?;
+		let manifest = reader.manifest().clone();
+
+		info!("Checking {} state chunks and {} block chunks", manifest.state_hashes.len(), manifest.block_hashes.len());
+		verify_chunks(&reader).map_err(|e| format!("Snapshot verification failed: {}", e))?;
+
+		let scratch_dir = match self.scratch_dir {
+			Some(ref dir) => dir,
+			None => return Ok(format!("Snapshot at block #{} has all chunks intact.", manifest.block_number)),
+		};
+		if manifest.base.is_some() {
+			return Err("Delta snapshots can't be restored on their own, verify without --scratch-dir.".into());
+		}
+
+		let spec = self.spec.spec()?;
+		let db_path = Path::new(scratch_dir).join("snapshot-verify");
+		let _ = fs::remove_dir_all(&db_path);
+
+		info!("Restoring snapshot into {}", db_path.display());
+		let result = verify_restoration(&reader, &*spec.engine, &spec.genesis_block(), &db_path);
+		let _ = fs::remove_dir_all(&db_path);
+
+		result.map_err(|e| format!("Snapshot verification failed: {}", e))?;
+		Ok(format!("Snapshot at block #{} restored successfully, state root {:?}.", manifest.block_number, manifest.state_root))
 	}
 }
 
 /// Execute this snapshot command.
 pub fn execute(cmd: SnapshotCommand) -> Result<String, String> {
 	match cmd.kind {
+		Kind::Verify => return cmd.verify_snapshot(),
 		Kind::Take => cmd.take_snapshot()?,
 		Kind::Restore => cmd.restore()?,
+		Kind::Export => cmd.export_snapshot()?,
+		Kind::Import => cmd.import_snapshot()?,
 	}
 
 	Ok(String::new())
@@ -0,0 +1,368 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Encryption of exported snapshot files.
+//!
+//! The file starts with a magic, followed by the length-prefixed RLP header
+//! `[iv, [salt, iterations, encrypted key, mac]?, [ecies encrypted key, ...]]` and its MAC.
+//! The random file key is encrypted with a key derived from the passphrase (like in key files)
+//! and to each of the recipients' public keys. The header MAC is keyed with the file key,
+//! so the iv and the recipients can't be changed without it.
+//!
+//! The contents are split into segments encrypted with AES-128-CTR, each followed by a MAC
+//! over its index, a flag marking the last segment and the ciphertext. The last segment is
+//! always shorter than `SEGMENT_SIZE`, so truncated files are detected.
+
+use std::fs::File;
+use std::io::{self, Read, Write, BufReader, BufWriter};
+use std::path::Path;
+
+use ethcrypto::{self, aes, ecies};
+use ethkey::{Public, Secret};
+use rlp::{RlpStream, UntrustedRlp, DecoderError};
+use util::{H128, H256};
+use util::sha3::Hashable;
+
+const MAGIC: &'static [u8] = b"PARITY-SNAPSHOT-ENCRYPTED-1\n";
+const SEGMENT_SIZE: usize = 1024 * 1024;
+const MAC_SIZE: usize = 32;
+const KEY_ITERATIONS: u32 = 262144;
+/// Files asking for more iterations are rejected, so they can't stall the import.
+const MAX_KEY_ITERATIONS: u32 = KEY_ITERATIONS * 4;
+/// Headers are small, unless the file is malformed.
+const MAX_HEADER_SIZE: usize = 1024 * 1024;
+
+/// Keys of encrypted snapshot files.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct EncryptionKeys {
+	/// Passphrase the file key is encrypted with.
+	pub passphrase: Option<String>,
+	/// Public keys the file key is encrypted to when exporting.
+	pub recipients: Vec<Public>,
+	/// Secret key of one of the recipients when importing.
+	pub secret: Option<Secret>,
+}
+
+impl EncryptionKeys {
+	/// Whether exported files should be encrypted.
+	pub fn encrypts(&self) -> bool {
+		self.passphrase.is_some() || !self.recipients.is_empty()
+	}
+}
+
+/// Checks if the file is an encrypted snapshot.
+pub fn is_encrypted(path: &Path) -> io::Result<bool> {
+	let mut magic = vec![0u8; MAGIC.len()];
+	let read = read_full(&mut File::open(path)?, &mut magic)?;
+	Ok(read == MAGIC.len() && &magic[..] == MAGIC)
+}
+
+/// Encrypt the file `from` into `to`.
+pub fn encrypt_file(from: &Path, to: &Path, keys: &EncryptionKeys) -> Result<(), String> {
+	let file_key = H256::random();
+	let iv = H128::random();
+
+	let mut header = RlpStream::new_list(3);
+	header.append(&iv);
+	match keys.passphrase {
+		Some(ref passphrase) => {
+			let salt = H256::random();
+			let (encrypted_key, mac) = seal_with_passphrase(passphrase, &salt, KEY_ITERATIONS, &iv, &file_key);
+			header.begin_list(4).append(&salt).append(&KEY_ITERATIONS).append(&encrypted_key).append(&mac);
+		},
+		None => { header.begin_list(0); },
+	}
+	header.begin_list(keys.recipients.len());
+	for public in &keys.recipients {
+		let encrypted_key = ecies::encrypt(public, &[], &file_key).map_err(|e| format!("Failed to encrypt snapshot key: {}", e))?;
+		header.append(&encrypted_key);
+	}
+	let header = header.out();
+
+	let mut input = BufReader::new(File::open(from).map_err(|e| format!("Failed to open snapshot file: {}", e))?);
+	let mut output = BufWriter::new(File::create(to).map_err(|e| format!("Failed to create encrypted snapshot file: {}", e))?);
+	let write_error = |e: io::Error| format!("Failed to write encrypted snapshot: {}", e);
+
+	output.write_all(MAGIC).map_err(&write_error)?;
+	output.write_all(&u32_le(header.len() as u32)).map_err(&write_error)?;
+	output.write_all(&header).map_err(&write_error)?;
+	output.write_all(&header_mac(&file_key, &header)).map_err(&write_error)?;
+
+	let mut plain = vec![0u8; SEGMENT_SIZE];
+	let mut encrypted = vec![0u8; SEGMENT_SIZE];
+	let mut index = 0u64;
+	loop {
+		let len = read_full(&mut input, &mut plain).map_err(|e| format!("Failed to read snapshot file: {}", e))?;
+		let last = len < SEGMENT_SIZE;
+		aes::encrypt(&file_key[..16], &segment_iv(&iv, index), &plain[..len], &mut encrypted[..len]);
+		output.write_all(&encrypted[..len]).map_err(&write_error)?;
+		output.write_all(&segment_mac(&file_key, index, last, &encrypted[..len])).map_err(&write_error)?;
+		if last {
+			break;
+		}
+		index += 1;
+	}
+	output.flush().map_err(&write_error)
+}
+
+/// Decrypt the file `from` into `to` using the passphrase or the secret key of one of the recipients.
+pub fn decrypt_file(from: &Path, to: &Path, keys: &EncryptionKeys) -> Result<(), String> {
+	let read_error = |e: io::Error| format!("Failed to read encrypted snapshot: {}", e);
+	let mut input = BufReader::new(File::open(from).map_err(&read_error)?);
+
+	let mut magic = vec![0u8; MAGIC.len()];
+	input.read_exact(&mut magic).map_err(&read_error)?;
+	if &magic[..] != MAGIC {
+		return Err("Snapshot file is not encrypted.".into());
+	}
+	let mut header_len = [0u8; 4];
+	input.read_exact(&mut header_len).map_err(&read_error)?;
+	let header_len = header_len.iter().rev().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+	if header_len > MAX_HEADER_SIZE {
+		return Err("Encrypted snapshot has invalid header.".into());
+	}
+	let mut header = vec![0u8; header_len];
+	input.read_exact(&mut header).map_err(&read_error)?;
+
+	let (iv, file_key) = open_header(&header, keys)?;
+	let mut mac = [0u8; MAC_SIZE];
+	input.read_exact(&mut mac).map_err(&read_error)?;
+	if &header_mac(&file_key, &header)[..] != &mac[..] {
+		return Err("Encrypted snapshot header is corrupted.".into());
+	}
+
+	let mut output = BufWriter::new(File::create(to).map_err(|e| format!("Failed to create snapshot file: {}", e))?);
+	let mut encrypted = vec![0u8; SEGMENT_SIZE + MAC_SIZE];
+	let mut plain = vec![0u8; SEGMENT_SIZE];
+	let mut index = 0u64;
+	loop {
+		let read = read_full(&mut input, &mut encrypted).map_err(&read_error)?;
+		if read < MAC_SIZE {
+			return Err("Encrypted snapshot is truncated.".into());
+		}
+		let len = read - MAC_SIZE;
+		let last = len < SEGMENT_SIZE;
+		if &segment_mac(&file_key, index, last, &encrypted[..len])[..] != &encrypted[len..read] {
+			return Err("Encrypted snapshot is corrupted or truncated.".into());
+		}
+		aes::decrypt(&file_key[..16], &segment_iv(&iv, index), &encrypted[..len], &mut plain[..len]);
+		output.write_all(&plain[..len]).map_err(|e| format!("Failed to write snapshot file: {}", e))?;
+		if last {
+			break;
+		}
+		index += 1;
+	}
+	output.flush().map_err(|e| format!("Failed to write snapshot file: {}", e))
+}
+
+// recover the iv and the file key from the header.
+fn open_header(header: &[u8], keys: &EncryptionKeys) -> Result<(H128, H256), String> {
+	let invalid = |_: DecoderError| "Encrypted snapshot has invalid header.".to_owned();
+	let rlp = UntrustedRlp::new(header);
+	let iv: H128 = rlp.val_at(0).map_err(&invalid)?;
+
+	if let Some(ref passphrase) = keys.passphrase {
+		let sealed = rlp.at(1).map_err(&invalid)?;
+		if sealed.item_count().map_err(&invalid)? == 4 {
+			let salt: H256 = sealed.val_at(0).map_err(&invalid)?;
+			let iterations: u32 = sealed.val_at(1).map_err(&invalid)?;
+			if iterations > MAX_KEY_ITERATIONS {
+				return Err(format!("Encrypted snapshot requires too many key derivation iterations ({}, at most {} are allowed).", iterations, MAX_KEY_ITERATIONS));
+			}
+			let encrypted_key: Vec<u8> = sealed.val_at(2).map_err(&invalid)?;
+			let mac: Vec<u8> = sealed.val_at(3).map_err(&invalid)?;
+			if encrypted_key.len() == 32 {
+				let (key, expected_mac) = open_with_passphrase(passphrase, &salt, iterations, &iv, &encrypted_key);
+				if expected_mac == mac {
+					return Ok((iv, key));
+				}
+			}
+			return Err("Invalid snapshot passphrase.".into());
+		}
+	}
+
+	if let Some(ref secret) = keys.secret {
+		for encrypted_key in rlp.at(2).map_err(&invalid)?.iter() {
+			let encrypted_key: Vec<u8> = encrypted_key.as_val().map_err(&invalid)?;
+			if let Ok(key) = ecies::decrypt(secret, &[], &encrypted_key) {
+				if key.len() == 32 {
+					return Ok((iv, H256::from_slice(&key)));
+				}
+			}
+		}
+		return Err("Snapshot is not encrypted to the given secret key.".into());
+	}
+
+	Err("Snapshot file is encrypted, provide --snapshot-passphrase-file or --snapshot-secret-file.".into())
+}
+
+// encrypt the file key like key files do, returning the encrypted key and its mac.
+fn seal_with_passphrase(passphrase: &str, salt: &H256, iterations: u32, iv: &H128, file_key: &H256) -> (Vec<u8>, Vec<u8>) {
+	let (derived_left_bits, derived_right_bits) = ethcrypto::derive_key_iterations(passphrase, &salt.0, iterations);
+	let mut encrypted_key = vec![0u8; 32];
+	aes::encrypt(&derived_left_bits, iv, file_key, &mut encrypted_key);
+	let mac = ethcrypto::derive_mac(&derived_right_bits, &encrypted_key).sha3().to_vec();
+	(encrypted_key, mac)
+}
+
+// decrypt the file key, returning it along with the expected mac.
+fn open_with_passphrase(passphrase: &str, salt: &H256, iterations: u32, iv: &H128, encrypted_key: &[u8]) -> (H256, Vec<u8>) {
+	let (derived_left_bits, derived_right_bits) = ethcrypto::derive_key_iterations(passphrase, &salt.0, iterations);
+	let mut key = H256::default();
+	aes::decrypt(&derived_left_bits, iv, encrypted_key, &mut key);
+	(key, ethcrypto::derive_mac(&derived_right_bits, encrypted_key).sha3().to_vec())
+}
+
+// every segment uses a distinct iv, derived from the file iv and the segment index.
+fn segment_iv(iv: &H128, index: u64) -> H128 {
+	let mut data = iv.to_vec();
+	data.extend_from_slice(&u64_be(index));
+	H128::from_slice(&data.sha3()[..16])
+}
+
+fn header_mac(file_key: &H256, header: &[u8]) -> H256 {
+	let mut data = Vec::with_capacity(16 + 6 + header.len());
+	data.extend_from_slice(&file_key[16..]);
+	data.extend_from_slice(b"header");
+	data.extend_from_slice(header);
+	data.sha3()
+}
+
+fn segment_mac(file_key: &H256, index: u64, last: bool, encrypted: &[u8]) -> H256 {
+	let mut data = Vec::with_capacity(16 + 8 + 1 + encrypted.len());
+	data.extend_from_slice(&file_key[16..]);
+	data.extend_from_slice(&u64_be(index));
+	data.push(last as u8);
+	data.extend_from_slice(encrypted);
+	data.sha3()
+}
+
+fn u32_le(value: u32) -> [u8; 4] {
+	[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]
+}
+
+fn u64_be(value: u64) -> [u8; 8] {
+	let mut bytes = [0u8; 8];
+	for (i, byte) in bytes.iter_mut().enumerate() {
+		*byte = (value >> (56 - 8 * i)) as u8;
+	}
+	bytes
+}
+
+// read until the buffer is full or the end of the input, returning the number of bytes read.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+	let mut read = 0;
+	while read < buf.len() {
+		match reader.read(&mut buf[read..]) {
+			Ok(0) => break,
+			Ok(n) => read += n,
+			Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+			Err(e) => return Err(e),
+		}
+	}
+	Ok(read)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use std::io::{Read, Write};
+	use devtools::RandomTempPath;
+	use ethkey::{Generator, Random};
+	use rlp::RlpStream;
+	use util::{H128, H256};
+	use super::{EncryptionKeys, encrypt_file, decrypt_file, is_encrypted, open_header, SEGMENT_SIZE, MAGIC, MAX_KEY_ITERATIONS};
+
+	fn read(path: &::std::path::Path) -> Vec<u8> {
+		let mut content = Vec::new();
+		File::open(path).unwrap().read_to_end(&mut content).unwrap();
+		content
+	}
+
+	#[test]
+	fn should_encrypt_with_passphrase_and_recipients() {
+		let dir = RandomTempPath::create_dir();
+		let (plain, encrypted, decrypted) = (dir.join("plain"), dir.join("encrypted"), dir.join("decrypted"));
+		let content: Vec<u8> = (0..SEGMENT_SIZE * 2 + 10).map(|i| i as u8).collect();
+		File::create(&plain).unwrap().write_all(&content).unwrap();
+
+		let pair = Random.generate().unwrap();
+		let keys = EncryptionKeys {
+			passphrase: Some("correct horse".into()),
+			recipients: vec![pair.public().clone()],
+			secret: None,
+		};
+		encrypt_file(&plain, &encrypted, &keys).unwrap();
+		assert!(is_encrypted(&encrypted).unwrap());
+		assert!(!is_encrypted(&plain).unwrap());
+
+		let wrong = EncryptionKeys { passphrase: Some("battery staple".into()), ..Default::default() };
+		assert_eq!(decrypt_file(&encrypted, &decrypted, &wrong), Err("Invalid snapshot passphrase.".into()));
+
+		let by_passphrase = EncryptionKeys { passphrase: Some("correct horse".into()), ..Default::default() };
+		decrypt_file(&encrypted, &decrypted, &by_passphrase).unwrap();
+		assert_eq!(read(&decrypted), content);
+
+		let by_secret = EncryptionKeys { secret: Some(pair.secret().clone()), ..Default::default() };
+		decrypt_file(&encrypted, &decrypted, &by_secret).unwrap();
+		assert_eq!(read(&decrypted), content);
+	}
+
+	#[test]
+	fn should_detect_truncation() {
+		let dir = RandomTempPath::create_dir();
+		let (plain, encrypted, decrypted) = (dir.join("plain"), dir.join("encrypted"), dir.join("decrypted"));
+		File::create(&plain).unwrap().write_all(&vec![7u8; SEGMENT_SIZE + 1]).unwrap();
+
+		let keys = EncryptionKeys { passphrase: Some("pass".into()), ..Default::default() };
+		encrypt_file(&plain, &encrypted, &keys).unwrap();
+
+		// drop the last segment.
+		let content = read(&encrypted);
+		File::create(&encrypted).unwrap().write_all(&content[..content.len() - 33]).unwrap();
+		assert!(decrypt_file(&encrypted, &decrypted, &keys).is_err());
+	}
+
+	#[test]
+	fn should_detect_modified_header() {
+		let dir = RandomTempPath::create_dir();
+		let (plain, encrypted, decrypted) = (dir.join("plain"), dir.join("encrypted"), dir.join("decrypted"));
+		File::create(&plain).unwrap().write_all(&vec![7u8; 100]).unwrap();
+
+		let pair = Random.generate().unwrap();
+		let keys = EncryptionKeys { recipients: vec![pair.public().clone()], ..Default::default() };
+		encrypt_file(&plain, &encrypted, &keys).unwrap();
+
+		// change the iv, which follows the two byte list prefix and the item prefix.
+		let mut content = read(&encrypted);
+		content[MAGIC.len() + 4 + 3] ^= 1;
+		File::create(&encrypted).unwrap().write_all(&content).unwrap();
+
+		let by_secret = EncryptionKeys { secret: Some(pair.secret().clone()), ..Default::default() };
+		assert_eq!(decrypt_file(&encrypted, &decrypted, &by_secret), Err("Encrypted snapshot header is corrupted.".into()));
+	}
+
+	#[test]
+	fn should_reject_too_many_key_iterations() {
+		let mut header = RlpStream::new_list(3);
+		header.append(&H128::random());
+		header.begin_list(4).append(&H256::random()).append(&(MAX_KEY_ITERATIONS + 1)).append(&vec![0u8; 32]).append(&vec![0u8; 32]);
+		header.begin_list(0);
+
+		let keys = EncryptionKeys { passphrase: Some("pass".into()), ..Default::default() };
+		assert!(open_header(&header.out(), &keys).unwrap_err().contains("too many key derivation iterations"));
+	}
+}
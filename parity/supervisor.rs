@@ -0,0 +1,244 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Supervisor restarting the node after crashes.
+//!
+//! The supervisor runs the node as a child process with the same command line
+//! and restarts it with an exponential backoff whenever it panics or gets killed,
+//! e.g. by the OOM killer. It gives up if the node keeps crashing. When daemonized,
+//! the supervisor is the daemon and owns the PID file, the node runs in the foreground.
+
+use std::collections::VecDeque;
+use std::{cmp, env};
+use std::fs::metadata;
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use ctrlc::CtrlC;
+use util::{Mutex, Condvar};
+
+/// Delay before the first restart, doubled by every crash within the window.
+const INITIAL_BACKOFF_SECS: u64 = 1;
+/// Maximal delay between restarts.
+const MAX_BACKOFF_SECS: u64 = 300;
+
+/// Supervisor settings.
+#[derive(Debug, PartialEq)]
+pub struct SupervisorCmd {
+	/// Give up after this many crashes within the window.
+	pub max_restarts: usize,
+	/// Crashes older than this are forgotten and the backoff is reset.
+	pub window: Duration,
+	/// Some if the supervisor should be daemonized. Contains pid_file path.
+	pub daemon: Option<String>,
+}
+
+/// Recent crashes of the node.
+struct Crashes {
+	window: Duration,
+	times: VecDeque<Instant>,
+}
+
+impl Crashes {
+	fn new(window: Duration) -> Self {
+		Crashes {
+			window: window,
+			times: VecDeque::new(),
+		}
+	}
+
+	/// Record a crash, returns the number of crashes within the window.
+	fn record(&mut self, now: Instant) -> usize {
+		while self.times.front().map_or(false, |time| now.duration_since(*time) > self.window) {
+			self.times.pop_front();
+		}
+		self.times.push_back(now);
+		self.times.len()
+	}
+}
+
+/// Delay before restarting after the given number of recent crashes.
+fn backoff(crashes: usize) -> Duration {
+	let exponent = cmp::min(crashes.saturating_sub(1), 16) as u32;
+	Duration::from_secs(cmp::min(INITIAL_BACKOFF_SECS << exponent, MAX_BACKOFF_SECS))
+}
+
+/// Executable of the node: the latest update if it's newer than the running executable.
+fn node_exe() -> Result<PathBuf, String> {
+	let exe = env::current_exe().map_err(|e| format!("Unable to determine the executable path: {}", e))?;
+	let modified = |path: &PathBuf| metadata(path).and_then(|m| m.modified()).ok();
+	Ok(match ::latest_exe_path() {
+		Some(ref latest) if modified(latest).map_or(false, |l| modified(&exe).map_or(true, |e| l > e)) => latest.clone(),
+		_ => exe,
+	})
+}
+
+/// Arguments of the node: the supervisor's own, except `--supervise` and the daemon command.
+/// The node runs directly and exits to be restarted by the supervisor, e.g. after an update.
+fn child_args(args: &[String], daemon: Option<&str>) -> Vec<String> {
+	let mut result = Vec::with_capacity(args.len());
+	let mut args = args.iter().peekable();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--supervise" => {},
+			"daemon" if daemon.is_some() && args.peek().map(|a| a.as_str()) == daemon => {
+				// skip the pid file
+				args.next();
+			},
+			_ => result.push(arg.clone()),
+		}
+	}
+	for flag in &["--force-direct", "--can-restart"] {
+		if !result.iter().any(|arg| arg == flag) {
+			result.push(flag.to_string());
+		}
+	}
+	result
+}
+
+fn describe(status: &ExitStatus) -> String {
+	#[cfg(unix)]
+	{
+		use std::os::unix::process::ExitStatusExt;
+		if let Some(signal) = status.signal() {
+			return match signal {
+				::libc::SIGKILL => format!("killed by signal {} (possibly out of memory)", signal),
+				_ => format!("killed by signal {}", signal),
+			};
+		}
+	}
+	match status.code() {
+		Some(code) => format!("exited with code {}", code),
+		None => "terminated".into(),
+	}
+}
+
+#[cfg(unix)]
+pub fn terminate(pid: u32) {
+	unsafe { ::libc::kill(pid as ::libc::pid_t, ::libc::SIGTERM); }
+}
+
+#[cfg(not(unix))]
+pub fn terminate(_pid: u32) {
+	// the console control event reaches the node directly
+}
+
+/// Stop requested by a signal, shared with the signal handler.
+struct Stop {
+	/// The signal was received.
+	requested: bool,
+	/// Process id of the running node.
+	child: Option<u32>,
+}
+
+/// Run the node until it exits normally, restarting it after crashes.
+pub fn execute(cmd: SupervisorCmd) -> Result<(), String> {
+	if let Some(pid_file) = cmd.daemon.clone() {
+		::run::daemonize(pid_file)?;
+	}
+
+	let args: Vec<String> = env::args().skip(1).collect();
+	let args = child_args(&args, cmd.daemon.as_ref().map(|d| d.as_str()));
+
+	let stop = Arc::new((Mutex::new(Stop { requested: false, child: None }), Condvar::new()));
+	let s = stop.clone();
+	CtrlC::set_handler(move || {
+		let mut stop = s.0.lock();
+		stop.requested = true;
+		if let Some(pid) = stop.child {
+			terminate(pid);
+		}
+		s.1.notify_all();
+	});
+
+	let mut crashes = Crashes::new(cmd.window);
+	loop {
+		let mut child = {
+			let mut stop = stop.0.lock();
+			if stop.requested {
+				return Ok(());
+			}
+			let exe = node_exe()?;
+			let child = Command::new(&exe).args(&args).spawn().map_err(|e| format!("Unable to start {}: {}", exe.display(), e))?;
+			info!(target: "supervisor", "Started node, process id {}", child.id());
+			stop.child = Some(child.id());
+			child
+		};
+
+		let status = child.wait().map_err(|e| format!("Error waiting for the node: {}", e));
+		stop.0.lock().child = None;
+		let status = status?;
+
+		if stop.0.lock().requested || status.success() {
+			info!(target: "supervisor", "Node {}, exiting", describe(&status));
+			return Ok(());
+		}
+		if status.code() == Some(::PLEASE_RESTART_EXIT_CODE) {
+			info!(target: "supervisor", "Node asked to be restarted");
+			continue;
+		}
+
+		let count = crashes.record(Instant::now());
+		if count > cmd.max_restarts {
+			return Err(format!("Node {}, it crashed {} times within {} s, giving up", describe(&status), count, cmd.window.as_secs()));
+		}
+		let delay = backoff(count);
+		warn!(target: "supervisor", "Node {}, restarting in {} s ({} of {} restarts)", describe(&status), delay.as_secs(), count, cmd.max_restarts);
+
+		let mut s = stop.0.lock();
+		if !s.requested {
+			stop.1.wait_for(&mut s, delay);
+		}
+		if s.requested {
+			return Ok(());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use super::{Crashes, backoff, child_args};
+
+	#[test]
+	fn should_back_off_exponentially() {
+		assert_eq!(backoff(1), Duration::from_secs(1));
+		assert_eq!(backoff(2), Duration::from_secs(2));
+		assert_eq!(backoff(4), Duration::from_secs(8));
+		assert_eq!(backoff(10), Duration::from_secs(300));
+		assert_eq!(backoff(100), Duration::from_secs(300));
+	}
+
+	#[test]
+	fn should_forget_old_crashes() {
+		let mut crashes = Crashes::new(Duration::from_secs(600));
+		let start = Instant::now();
+		assert_eq!(crashes.record(start), 1);
+		assert_eq!(crashes.record(start + Duration::from_secs(10)), 2);
+		assert_eq!(crashes.record(start + Duration::from_secs(605)), 2);
+		assert_eq!(crashes.record(start + Duration::from_secs(2000)), 1);
+	}
+
+	#[test]
+	fn should_strip_supervisor_arguments() {
+		let args: Vec<String> = vec!["daemon".into(), "/run/parity.pid".into(), "--supervise".into(), "--chain".into(), "kovan".into()];
+		assert_eq!(child_args(&args, Some("/run/parity.pid")), vec!["--chain".to_owned(), "kovan".into(), "--force-direct".into(), "--can-restart".into()]);
+
+		let args: Vec<String> = vec!["--supervise".into(), "--chain".into(), "daemon".into(), "--force-direct".into()];
+		assert_eq!(child_args(&args, None), vec!["--chain".to_owned(), "daemon".into(), "--force-direct".into(), "--can-restart".into()]);
+	}
+}
@@ -0,0 +1,215 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! systemd service notifications for `Type=notify` units.
+//!
+//! Readiness is signalled once the database is open and the RPC servers are up,
+//! watchdog keepalives are sent only while the health checks pass and the sync
+//! status is reported in the unit's status line.
+
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use io::{TimerToken, IoContext, IoHandler};
+use ethcore::service::ClientIoMessage;
+use health::{HealthChecker, Report};
+
+const STATUS_TIMER: TimerToken = 0;
+const STATUS_TIMER_INTERVAL_MS: u64 = 5000;
+const WATCHDOG_TIMER: TimerToken = 1;
+
+/// Sender of notifications to the socket given by systemd.
+pub struct Notifier {
+	path: String,
+}
+
+impl Notifier {
+	/// Notifier of the service manager, `None` if not started by systemd with `Type=notify`.
+	pub fn from_env() -> Option<Self> {
+		match env::var("NOTIFY_SOCKET") {
+			Ok(ref path) if path.starts_with('@') => {
+				warn!(target: "systemd", "Abstract notification socket {} is not supported", path);
+				None
+			},
+			Ok(path) => Some(Notifier { path: path }),
+			Err(_) => None,
+		}
+	}
+
+	/// Send newline-separated `VARIABLE=value` assignments.
+	pub fn notify(&self, state: &str) {
+		trace!(target: "systemd", "Notifying: {}", state);
+		if let Err(e) = send(&self.path, state) {
+			debug!(target: "systemd", "Unable to notify systemd at {}: {}", self.path, e);
+		}
+	}
+}
+
+#[cfg(unix)]
+fn send(path: &str, state: &str) -> ::std::io::Result<()> {
+	use std::os::unix::net::UnixDatagram;
+
+	let socket = UnixDatagram::unbound()?;
+	socket.send_to(state.as_bytes(), path).map(|_| ())
+}
+
+#[cfg(not(unix))]
+fn send(_path: &str, _state: &str) -> ::std::io::Result<()> {
+	use std::io;
+
+	Err(io::Error::new(io::ErrorKind::Other, "not supported on this platform"))
+}
+
+#[cfg(unix)]
+fn process_id() -> u32 {
+	unsafe { ::libc::getpid() as u32 }
+}
+
+#[cfg(not(unix))]
+fn process_id() -> u32 {
+	0
+}
+
+/// Interval of watchdog keepalives, half of the timeout set by systemd.
+/// `None` if the watchdog is disabled or meant for another process.
+fn watchdog_interval(usec: Option<String>, pid: Option<String>, own_pid: u32) -> Option<Duration> {
+	if let Some(pid) = pid {
+		if pid.parse::<u32>().ok() != Some(own_pid) {
+			return None;
+		}
+	}
+	match usec.and_then(|usec| usec.parse::<u64>().ok()) {
+		Some(usec) if usec > 0 => Some(Duration::from_millis(::std::cmp::max(1, usec / 2000))),
+		_ => None,
+	}
+}
+
+/// Status line describing the sync progress.
+fn status(report: &Report) -> String {
+	let state = &report.state;
+	let mut status = match report.synced {
+		true => format!("Synced #{}, {} peers", state.best_block, state.peers),
+		false => format!("Syncing #{}/{}, {} peers", state.best_block, state.highest_block, state.peers),
+	};
+	if !report.db_writable {
+		status.push_str(", database not writable");
+	}
+	if !report.clock_ok {
+		status.push_str(", clock drift too large");
+	}
+	if !report.sealing_ok {
+		status.push_str(", missing sealing turns");
+	}
+	status
+}
+
+/// Reports readiness, status and watchdog keepalives to systemd.
+pub struct SystemdNotifier {
+	notifier: Notifier,
+	checker: Arc<HealthChecker>,
+	watchdog: Option<Duration>,
+}
+
+impl SystemdNotifier {
+	/// Create new notifier, sending keepalives if the watchdog is enabled for this process.
+	pub fn new(notifier: Notifier, checker: Arc<HealthChecker>) -> Self {
+		let watchdog = watchdog_interval(env::var("WATCHDOG_USEC").ok(), env::var("WATCHDOG_PID").ok(), process_id());
+		if let Some(interval) = watchdog {
+			info!(target: "systemd", "Sending watchdog keepalives every {} ms", interval.as_secs() * 1000 + interval.subsec_nanos() as u64 / 1_000_000);
+		}
+		SystemdNotifier {
+			notifier: notifier,
+			checker: checker,
+			watchdog: watchdog,
+		}
+	}
+
+	/// Signal that the startup is finished.
+	pub fn ready(&self) {
+		self.notifier.notify(&format!("READY=1\nSTATUS={}", status(&self.checker.report())));
+	}
+
+	/// Signal that the shutdown has begun.
+	pub fn stopping(&self) {
+		self.notifier.notify("STOPPING=1\nSTATUS=Shutting down");
+	}
+}
+
+impl IoHandler<ClientIoMessage> for SystemdNotifier {
+	fn initialize(&self, io: &IoContext<ClientIoMessage>) {
+		io.register_timer(STATUS_TIMER, STATUS_TIMER_INTERVAL_MS).expect("Error registering timer");
+		if let Some(interval) = self.watchdog {
+			let ms = interval.as_secs() * 1000 + interval.subsec_nanos() as u64 / 1_000_000;
+			io.register_timer(WATCHDOG_TIMER, ms).expect("Error registering timer");
+		}
+	}
+
+	fn timeout(&self, _io: &IoContext<ClientIoMessage>, timer: TimerToken) {
+		match timer {
+			STATUS_TIMER => self.notifier.notify(&format!("STATUS={}", status(&self.checker.report()))),
+			WATCHDOG_TIMER => {
+				let report = self.checker.report();
+				match report.is_healthy() {
+					true => self.notifier.notify("WATCHDOG=1"),
+					false => warn!(target: "systemd", "Health checks failed ({}), not sending watchdog keepalive", status(&report)),
+				}
+			},
+			_ => {},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use health::{NodeState, Report};
+	use super::{watchdog_interval, status};
+
+	#[test]
+	fn should_parse_watchdog_interval() {
+		assert_eq!(watchdog_interval(None, None, 10), None);
+		assert_eq!(watchdog_interval(Some("30000000".into()), None, 10), Some(Duration::from_secs(15)));
+		assert_eq!(watchdog_interval(Some("30000000".into()), Some("10".into()), 10), Some(Duration::from_secs(15)));
+		assert_eq!(watchdog_interval(Some("30000000".into()), Some("11".into()), 10), None);
+		assert_eq!(watchdog_interval(Some("0".into()), None, 10), None);
+		assert_eq!(watchdog_interval(Some("abc".into()), None, 10), None);
+	}
+
+	#[test]
+	fn should_describe_sync_status() {
+		let mut report = Report {
+			synced: false,
+			enough_peers: true,
+			db_writable: true,
+			clock_ok: true,
+			sealing_ok: true,
+			state: NodeState {
+				importing: true,
+				best_block: 100,
+				highest_block: 200,
+				peers: 5,
+				db_writable: true,
+				clock_drift: None,
+				sealing: true,
+			},
+		};
+		assert_eq!(status(&report), "Syncing #100/200, 5 peers");
+
+		report.synced = true;
+		report.db_writable = false;
+		assert_eq!(status(&report), "Synced #100, 5 peers, database not writable");
+	}
+}
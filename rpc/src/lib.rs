@@ -35,6 +35,7 @@ extern crate jsonrpc_core;
 extern crate jsonrpc_http_server as http;
 extern crate jsonrpc_minihttp_server as minihttp;
 extern crate jsonrpc_ipc_server as ipc;
+extern crate jsonrpc_pubsub;
 
 extern crate ethash;
 extern crate ethcore;
@@ -75,6 +76,7 @@ pub extern crate jsonrpc_ws_server as ws;
 mod metadata;
 pub mod v1;
 
+pub use jsonrpc_pubsub::Session as PubSubSession;
 pub use ipc::{Server as IpcServer, MetaExtractor as IpcMetaExtractor, RequestContext as IpcRequestContext};
 pub use http::{
 	hyper,
@@ -82,11 +84,12 @@ pub use http::{
 	AccessControlAllowOrigin, Host, DomainsValidation
 };
 
-pub use v1::{SigningQueue, SignerService, ConfirmationsQueue, NetworkSettings, Metadata, Origin, informant, dispatch};
+pub use v1::{SigningQueue, SignerService, ConfirmationsQueue, NetworkSettings, Metadata, Origin, access, fallback, informant, limits, offline, dispatch, signing_policy, wake};
 pub use v1::block_import::is_major_importing;
 
 use std::net::SocketAddr;
 use http::tokio_core;
+use v1::limits::SharedLimits;
 
 /// RPC HTTP Server instance
 pub enum HttpServer {
@@ -129,7 +132,7 @@ pub trait HttpMetaExtractor: Send + Sync + 'static {
 	/// Type of Metadata
 	type Metadata: jsonrpc_core::Metadata;
 	/// Extracts metadata from given params.
-	fn read_metadata(&self, origin: String, dapps_origin: Option<String>) -> Self::Metadata;
+	fn read_metadata(&self, origin: String, host: Option<String>, dapps_origin: Option<String>) -> Self::Metadata;
 }
 
 /// HTTP server implementation-specific settings.
@@ -140,7 +143,47 @@ pub enum HttpSettings<R: RequestMiddleware> {
 	Dapps(Option<R>),
 }
 
+/// Rejects requests declaring a body larger than the limit before the body is read.
+/// With a limit set, bodies of unknown length (e.g. chunked) are rejected as well.
+/// Other requests are passed to the inner middleware.
+struct BodyLimit<R> {
+	limits: SharedLimits,
+	inner: Option<R>,
+}
+
+impl<R: RequestMiddleware> RequestMiddleware for BodyLimit<R> {
+	fn on_request(&self, request: hyper::server::Request) -> RequestMiddlewareAction {
+		let limit = self.limits.get().max_request_size;
+		let size = request.headers().get::<hyper::header::ContentLength>().map(|length| length.0);
+		let encoded = request.headers().has::<hyper::header::TransferEncoding>();
+		match (limit, size) {
+			(Some(limit), Some(size)) if size > limit as u64 => {
+				return http::Response {
+					code: hyper::StatusCode::PayloadTooLarge,
+					content_type: hyper::header::ContentType::plaintext(),
+					content: format!("Request body is too large (limit: {} bytes, got: {} bytes).\n", limit, size),
+				}.into();
+			},
+			(Some(limit), None) if encoded => {
+				return http::Response {
+					code: hyper::StatusCode::LengthRequired,
+					content_type: hyper::header::ContentType::plaintext(),
+					content: format!("Request body size has to be given with Content-Length (limit: {} bytes).\n", limit),
+				}.into();
+			},
+			_ => {},
+		}
+
+		match self.inner {
+			Some(ref inner) => inner.on_request(request),
+			None => request.into(),
+		}
+	}
+}
+
 /// Start http server asynchronously and returns result with `Server` handle on success or an error.
+/// Size of the request body is checked against the `limits` before it's read (only by the standard server,
+/// the multi-threaded one can't be started with a request size limit).
 pub fn start_http<M, S, H, T, R>(
 	addr: &SocketAddr,
 	cors_domains: http::DomainsValidation<http::AccessControlAllowOrigin>,
@@ -148,6 +191,7 @@ pub fn start_http<M, S, H, T, R>(
 	handler: H,
 	remote: tokio_core::reactor::Remote,
 	extractor: T,
+	limits: SharedLimits,
 	settings: HttpSettings<R>,
 ) -> Result<HttpServer, HttpServerError> where
 	M: jsonrpc_core::Metadata,
@@ -158,16 +202,13 @@ pub fn start_http<M, S, H, T, R>(
 {
 	Ok(match settings {
 		HttpSettings::Dapps(middleware) => {
-			let mut builder = http::ServerBuilder::new(handler)
+			http::ServerBuilder::new(handler)
 				.event_loop_remote(remote)
 				.meta_extractor(metadata::HyperMetaExtractor::new(extractor))
 				.cors(cors_domains.into())
-				.allowed_hosts(allowed_hosts.into());
-
-			if let Some(dapps) = middleware {
-				builder = builder.request_middleware(dapps)
-			}
-			builder.start_http(addr)
+				.allowed_hosts(allowed_hosts.into())
+				.request_middleware(BodyLimit { limits: limits, inner: middleware })
+				.start_http(addr)
 				.map(HttpServer::Hyper)?
 		},
 		HttpSettings::Threads(threads) => {
@@ -224,3 +265,55 @@ pub fn start_ws<M, S, H, T, U>(
 		.session_stats(stats)
 		.start(addr)
 }
+
+#[cfg(test)]
+mod tests {
+	use futures::Future;
+	use http::{hyper, NoopRequestMiddleware, RequestMiddleware, RequestMiddlewareAction};
+	use v1::limits::{RequestLimits, SharedLimits};
+	use super::BodyLimit;
+
+	fn body_limit(max_request_size: usize) -> BodyLimit<NoopRequestMiddleware> {
+		BodyLimit {
+			limits: SharedLimits::new(RequestLimits {
+				max_request_size: Some(max_request_size),
+				..Default::default()
+			}),
+			inner: None,
+		}
+	}
+
+	fn request(body: &'static str, chunked: bool) -> hyper::server::Request {
+		let mut request = hyper::server::Request::new(hyper::Method::Post, "/".parse().unwrap());
+		match chunked {
+			true => request.headers_mut().set(hyper::header::TransferEncoding::chunked()),
+			false => request.headers_mut().set(hyper::header::ContentLength(body.len() as u64)),
+		}
+		request.set_body(body);
+		request
+	}
+
+	fn status(action: RequestMiddlewareAction) -> Option<hyper::StatusCode> {
+		match action {
+			RequestMiddlewareAction::Respond { response, .. } => Some(response.wait().unwrap().status()),
+			RequestMiddlewareAction::Proceed { .. } => None,
+		}
+	}
+
+	#[test]
+	fn should_reject_too_large_bodies() {
+		let limit = body_limit(16);
+		assert_eq!(status(limit.on_request(request("{\"id\":1}", false))), None);
+		assert_eq!(status(limit.on_request(request("{\"jsonrpc\":\"2.0\",\"id\":1}", false))), Some(hyper::StatusCode::PayloadTooLarge));
+	}
+
+	#[test]
+	fn should_reject_chunked_bodies_over_the_limit() {
+		let limit = body_limit(16);
+		assert_eq!(status(limit.on_request(request("{\"jsonrpc\":\"2.0\",\"id\":1}", true))), Some(hyper::StatusCode::LengthRequired));
+
+		// without a limit the body is read as usual.
+		let unlimited = BodyLimit::<NoopRequestMiddleware> { limits: SharedLimits::default(), inner: None };
+		assert_eq!(status(unlimited.on_request(request("{\"jsonrpc\":\"2.0\",\"id\":1}", true))), None);
+	}
+}
@@ -40,10 +40,15 @@ impl<M, T> http::MetaExtractor<M> for HyperMetaExtractor<T> where
 		let origin = req.headers().get::<hyper::header::Origin>()
 			.map(|origin| format!("{}://{}", origin.scheme, origin.host))
 			.unwrap_or_else(|| "unknown".into());
+		let host = req.headers().get::<hyper::header::Host>()
+			.map(|host| match host.port {
+				Some(port) => format!("{}:{}", host.hostname, port),
+				None => host.hostname.clone(),
+			});
 		let dapps_origin = req.headers().get_raw("x-parity-origin")
 			.and_then(|raw| raw.one())
 			.map(|raw| String::from_utf8_lossy(raw).into_owned());
-		self.extractor.read_metadata(origin, dapps_origin)
+		self.extractor.read_metadata(origin, host, dapps_origin)
 	}
 }
 
@@ -67,8 +72,9 @@ impl<M, T> minihttp::MetaExtractor<M> for MiniMetaExtractor<T> where
 		let origin = req.header("origin")
 			.unwrap_or_else(|| "unknown")
 			.to_owned();
+		let host = req.header("host").map(|h| h.to_owned());
 		let dapps_origin = req.header("x-parity-origin").map(|h| h.to_owned());
 
-		self.extractor.read_metadata(origin, dapps_origin)
+		self.extractor.read_metadata(origin, host, dapps_origin)
 	}
 }
@@ -0,0 +1,229 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-method `Origin` and `Host` header restrictions.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use jsonrpc_core::{Request, Response, Call, Failure, Id, Version};
+use v1::helpers::errors;
+use v1::metadata::Metadata;
+use v1::types::Origin;
+
+/// Allowed values of `Origin` and `Host` headers for a group of methods.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccessRule {
+	/// Allowed origins (`None` means that all origins are allowed).
+	pub origins: Option<Vec<String>>,
+	/// Allowed hosts (`None` means that all hosts are allowed).
+	pub hosts: Option<Vec<String>>,
+}
+
+impl AccessRule {
+	fn is_allowed(&self, origin: &str, host: Option<&str>) -> bool {
+		self.is_origin_allowed(origin) && self.is_host_allowed(host)
+	}
+
+	fn is_origin_allowed(&self, origin: &str) -> bool {
+		match self.origins {
+			None => true,
+			// Requests without `Origin` header are not made by browsers.
+			Some(_) if origin == "unknown" => true,
+			Some(ref origins) => origins.iter().any(|o| o == "*" || o == origin),
+		}
+	}
+
+	fn is_host_allowed(&self, host: Option<&str>) -> bool {
+		match (self.hosts.as_ref(), host) {
+			(None, _) => true,
+			(Some(_), None) => false,
+			(Some(hosts), Some(host)) => {
+				let hostname = host.rsplitn(2, ':').last().unwrap_or(host);
+				hosts.iter().any(|h| h == host || h == hostname)
+			},
+		}
+	}
+}
+
+/// Access rules for particular RPC methods.
+/// Only requests coming through the HTTP JSON-RPC server (including dapps) are validated.
+#[derive(Debug, Default, Clone)]
+pub struct AccessPolicy {
+	rules: HashMap<String, Vec<Arc<AccessRule>>>,
+}
+
+impl AccessPolicy {
+	/// Restricts access to given methods.
+	/// A method restricted more than once has to satisfy all of its rules.
+	pub fn restrict<I, S>(&mut self, methods: I, rule: AccessRule) where
+		I: IntoIterator<Item = S>,
+		S: Into<String>,
+	{
+		let rule = Arc::new(rule);
+		for method in methods {
+			self.rules.entry(method.into()).or_insert_with(Vec::new).push(rule.clone());
+		}
+	}
+
+	/// Returns true if no method is restricted.
+	pub fn is_empty(&self) -> bool {
+		self.rules.is_empty()
+	}
+
+	/// Validates the request against configured rules.
+	/// Returns an error response if any of the calls is not allowed.
+	pub fn check_request(&self, request: &Request, meta: &Metadata) -> Result<(), Response> {
+		if self.rules.is_empty() {
+			return Ok(());
+		}
+
+		let origin = match meta.origin {
+			Origin::Rpc(ref origin) => origin.as_str(),
+			// Dapps are sending requests from `null` origin.
+			Origin::Dapps(_) => "null",
+			_ => return Ok(()),
+		};
+		let host = meta.host.as_ref().map(|host| host.as_str());
+
+		let denied = |call: &Call| {
+			let method = match *call {
+				Call::MethodCall(ref call) => &call.method,
+				Call::Notification(ref notification) => &notification.method,
+				Call::Invalid(_) => return None,
+			};
+
+			match self.rules.get(method) {
+				Some(rules) if !rules.iter().all(|rule| rule.is_allowed(origin, host)) => Some(method.clone()),
+				_ => None,
+			}
+		};
+
+		let (method, id, version) = match *request {
+			Request::Single(ref call) => match denied(call) {
+				None => return Ok(()),
+				Some(method) => match *call {
+					Call::MethodCall(ref call) => (method, call.id.clone(), call.jsonrpc),
+					_ => (method, Id::Null, Some(Version::V2)),
+				},
+			},
+			Request::Batch(ref calls) => match calls.iter().filter_map(denied).next() {
+				None => return Ok(()),
+				Some(method) => (method, Id::Null, Some(Version::V2)),
+			},
+		};
+
+		Err(Failure {
+			jsonrpc: version,
+			error: errors::access_denied(&method),
+			id: id,
+		}.into())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use jsonrpc_core::{Request, Response, Call, MethodCall, Output, Id, Version, Params};
+	use v1::metadata::Metadata;
+	use v1::types::Origin;
+	use super::{AccessPolicy, AccessRule};
+
+	fn call(method: &str) -> Call {
+		Call::MethodCall(MethodCall {
+			jsonrpc: Some(Version::V2),
+			method: method.into(),
+			params: Some(Params::Array(vec![])),
+			id: Id::Num(1),
+		})
+	}
+
+	fn meta(origin: &str, host: &str) -> Metadata {
+		Metadata {
+			origin: Origin::Rpc(origin.into()),
+			host: Some(host.into()),
+			session: None,
+		}
+	}
+
+	fn policy() -> AccessPolicy {
+		let mut policy = AccessPolicy::default();
+		policy.restrict(vec!["eth_accounts", "parity_setAuthor"], AccessRule {
+			origins: Some(vec!["http://parity.io".into()]),
+			hosts: Some(vec!["localhost".into()]),
+		});
+		policy.restrict(vec!["eth_blockNumber"], AccessRule::default());
+		policy
+	}
+
+	fn is_denied(result: Result<(), Response>) -> bool {
+		match result {
+			Ok(()) => false,
+			Err(Response::Single(Output::Failure(failure))) => {
+				assert_eq!(failure.error.code.code(), -32085);
+				true
+			},
+			Err(other) => panic!("Unexpected response: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_allow_unrestricted_methods() {
+		// given
+		let policy = policy();
+
+		// then
+		assert!(!is_denied(policy.check_request(&Request::Single(call("eth_blockNumber")), &meta("http://evil.com", "evil.com"))));
+		assert!(!is_denied(policy.check_request(&Request::Single(call("net_version")), &meta("http://evil.com", "evil.com"))));
+	}
+
+	#[test]
+	fn should_validate_origin_and_host() {
+		// given
+		let policy = policy();
+		let request = Request::Single(call("parity_setAuthor"));
+
+		// then
+		assert!(!is_denied(policy.check_request(&request, &meta("http://parity.io", "localhost:8545"))));
+		assert!(!is_denied(policy.check_request(&request, &meta("unknown", "localhost"))));
+		assert!(is_denied(policy.check_request(&request, &meta("http://evil.com", "localhost:8545"))));
+		assert!(is_denied(policy.check_request(&request, &meta("http://parity.io", "evil.com"))));
+	}
+
+	#[test]
+	fn should_reject_batch_with_restricted_method() {
+		// given
+		let policy = policy();
+		let request = Request::Batch(vec![call("eth_blockNumber"), call("eth_accounts")]);
+
+		// then
+		assert!(!is_denied(policy.check_request(&request, &meta("http://parity.io", "localhost"))));
+		assert!(is_denied(policy.check_request(&request, &meta("http://evil.com", "localhost"))));
+	}
+
+	#[test]
+	fn should_not_validate_other_transports() {
+		// given
+		let policy = policy();
+		let meta = Metadata {
+			origin: Origin::Ws(Default::default()),
+			host: None,
+			session: None,
+		};
+
+		// then
+		assert!(!is_denied(policy.check_request(&Request::Single(call("eth_accounts")), &meta)));
+	}
+}
@@ -34,6 +34,10 @@ mod codes {
 	pub const NO_NEW_WORK: i64 = -32003;
 	pub const UNKNOWN_ERROR: i64 = -32009;
 	pub const TRANSACTION_ERROR: i64 = -32010;
+	pub const SENDER_LIMIT_REACHED: i64 = -32011;
+	pub const SENDER_FUTURE_LIMIT_REACHED: i64 = -32012;
+	pub const FUTURE_LIMIT_REACHED: i64 = -32013;
+	pub const NONCE_GAP_TOO_LARGE: i64 = -32014;
 	pub const EXECUTION_ERROR: i64 = -32015;
 	pub const EXCEPTION_ERROR: i64 = -32016;
 	pub const DATABASE_ERROR: i64 = -32017;
@@ -43,11 +47,23 @@ mod codes {
 	pub const REQUEST_REJECTED: i64 = -32040;
 	pub const REQUEST_REJECTED_LIMIT: i64 = -32041;
 	pub const REQUEST_NOT_FOUND: i64 = -32042;
+	pub const REQUEST_REJECTED_BY_POLICY: i64 = -32043;
+	pub const CONFIRMATIONS_REQUIRED: i64 = -32044;
 	pub const ENCRYPTION_ERROR: i64 = -32055;
 	pub const ENCODING_ERROR: i64 = -32058;
 	pub const FETCH_ERROR: i64 = -32060;
 	pub const NO_LIGHT_PEERS: i64 = -32065;
 	pub const DEPRECATED: i64 = -32070;
+	pub const BATCH_TOO_LARGE: i64 = -32080;
+	pub const REQUEST_TOO_LARGE: i64 = -32081;
+	pub const RESPONSE_TOO_LARGE: i64 = -32082;
+	pub const ACCESS_DENIED: i64 = -32085;
+	pub const TOKEN_INDEX_BEHIND: i64 = -32090;
+	pub const TOO_MANY_TOKEN_TRANSFERS: i64 = -32091;
+	pub const TOO_MANY_BALANCE_CHANGES: i64 = -32092;
+	pub const NETWORK_UNAVAILABLE: i64 = -32093;
+	pub const BLOCK_RANGE_TOO_LARGE: i64 = -32094;
+	pub const FULL_NODE_UNAVAILABLE: i64 = -32095;
 }
 
 pub fn unimplemented(details: Option<String>) -> Error {
@@ -58,6 +74,14 @@ pub fn unimplemented(details: Option<String>) -> Error {
 	}
 }
 
+pub fn signer_voting_unsupported() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+		message: "The consensus engine of this chain doesn't vote on signers.".into(),
+		data: None,
+	}
+}
+
 pub fn light_unimplemented(details: Option<String>) -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
@@ -66,6 +90,26 @@ pub fn light_unimplemented(details: Option<String>) -> Error {
 	}
 }
 
+/// Returns true if the error says the request is not supported.
+pub fn is_unsupported(error: &Error) -> bool {
+	error.code == ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST)
+}
+
+pub fn light_fallback(url: &str, error: Error, details: String) -> Error {
+	Error {
+		data: Some(Value::String(format!("Forwarding to full node {} failed: {}", url, details))),
+		..error
+	}
+}
+
+pub fn full_node_unavailable(url: &str, details: String) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::FULL_NODE_UNAVAILABLE),
+		message: format!("Calls are handed over to the full node at {}, which can't be reached.", url),
+		data: Some(Value::String(details)),
+	}
+}
+
 pub fn public_unsupported(details: Option<String>) -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
@@ -98,6 +142,38 @@ pub fn request_rejected_limit() -> Error {
 	}
 }
 
+pub fn request_rejected_by_policy() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::REQUEST_REJECTED_BY_POLICY),
+		message: "Request has been rejected by the signing policy.".into(),
+		data: None,
+	}
+}
+
+pub fn confirmations_required(missing: usize) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::CONFIRMATIONS_REQUIRED),
+		message: format!("Confirmation recorded, the signing policy requires {} more before the request is signed.", missing),
+		data: None,
+	}
+}
+
+pub fn already_confirmed(missing: usize) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::CONFIRMATIONS_REQUIRED),
+		message: format!("Request already confirmed with this token, the signing policy requires {} more from other Trusted Signer tokens.", missing),
+		data: None,
+	}
+}
+
+pub fn confirmations_from_signer_required(required: usize) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::CONFIRMATIONS_REQUIRED),
+		message: format!("The signing policy requires {} confirmations from distinct Trusted Signer tokens.", required),
+		data: None,
+	}
+}
+
 pub fn account<T: fmt::Debug>(error: &str, details: T) -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::ACCOUNT_ERROR),
@@ -141,6 +217,14 @@ pub fn state_pruned() -> Error {
 	}
 }
 
+pub fn block_out_of_range() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+		message: "This request is not supported because the block is outside of the range kept by your node. Adjust --block-range.".into(),
+		data: None
+	}
+}
+
 pub fn state_corrupt() -> Error {
 	internal("State corrupt", "")
 }
@@ -209,6 +293,54 @@ pub fn dapps_disabled() -> Error {
 	}
 }
 
+pub fn token_index_disabled() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+		message: "Token transfer index is disabled. Run with --token-index to enable this API.".into(),
+		data: None,
+	}
+}
+
+pub fn token_index_behind(head: u64) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::TOKEN_INDEX_BEHIND),
+		message: format!("Token transfers are indexed up to block {} only.", head),
+		data: None,
+	}
+}
+
+pub fn too_many_token_transfers(limit: usize) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::TOO_MANY_TOKEN_TRANSFERS),
+		message: format!("More than {} transfers in the block range. Try narrowing the query.", limit),
+		data: None,
+	}
+}
+
+pub fn balance_history_disabled() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+		message: "Balance history is disabled. Run with --balance-history to enable this API.".into(),
+		data: None,
+	}
+}
+
+pub fn too_many_balance_changes(limit: usize) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::TOO_MANY_BALANCE_CHANGES),
+		message: format!("More than {} balance changes in the block range. Try narrowing the query.", limit),
+		data: None,
+	}
+}
+
+pub fn block_range_too_large(limit: u64) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::BLOCK_RANGE_TOO_LARGE),
+		message: format!("The block range spans more than {} blocks. Try narrowing the query.", limit),
+		data: None,
+	}
+}
+
 pub fn network_disabled() -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
@@ -217,6 +349,14 @@ pub fn network_disabled() -> Error {
 	}
 }
 
+pub fn network_unavailable(method: &str) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::NETWORK_UNAVAILABLE),
+		message: format!("Method {} requires the network, which is never started in offline-serve mode.", method),
+		data: None,
+	}
+}
+
 pub fn encryption_error<T: fmt::Debug>(error: T) -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::ENCRYPTION_ERROR),
@@ -277,6 +417,18 @@ pub fn transaction_message(error: TransactionError) -> String {
 		LimitReached => {
 			"There are too many transactions in the queue. Your transaction was dropped due to limit. Try increasing the fee.".into()
 		},
+		FutureLimitReached => {
+			"There are too many transactions waiting for lower nonces in the queue. Your transaction was dropped due to limit. Try increasing the fee or sending the missing transactions first.".into()
+		},
+		SenderLimitReached { limit } => {
+			format!("There are too many pending transactions of the sender in the queue (limit: {}). Wait for some of them to be mined.", limit)
+		},
+		SenderFutureLimitReached { limit } => {
+			format!("There are too many transactions of the sender waiting for lower nonces in the queue (limit: {}). Send the missing transactions first.", limit)
+		},
+		NonceGapTooLarge { limit, got } => {
+			format!("Transaction nonce is too far ahead of the next nonce of the sender (limit: {}, got: {}). Send the missing transactions first.", limit, got)
+		},
 		InsufficientGas { minimal, got } => {
 			format!("Transaction gas is too low. There is not enough gas to cover minimal cost of the transaction (minimal: {}, got: {}). Try increasing supplied gas.", minimal, got)
 		},
@@ -300,8 +452,15 @@ pub fn transaction_message(error: TransactionError) -> String {
 pub fn from_transaction_error(error: EthcoreError) -> Error {
 
 	if let EthcoreError::Transaction(e) = error {
+		let code = match e {
+			TransactionError::SenderLimitReached { .. } => codes::SENDER_LIMIT_REACHED,
+			TransactionError::SenderFutureLimitReached { .. } => codes::SENDER_FUTURE_LIMIT_REACHED,
+			TransactionError::FutureLimitReached => codes::FUTURE_LIMIT_REACHED,
+			TransactionError::NonceGapTooLarge { .. } => codes::NONCE_GAP_TOO_LARGE,
+			_ => codes::TRANSACTION_ERROR,
+		};
 		Error {
-			code: ErrorCode::ServerError(codes::TRANSACTION_ERROR),
+			code: ErrorCode::ServerError(code),
 			message: transaction_message(e),
 			data: None,
 		}
@@ -356,6 +515,38 @@ pub fn deprecated<T: Into<Option<String>>>(message: T) -> Error {
 	}
 }
 
+pub fn batch_too_large(limit: usize, got: usize) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::BATCH_TOO_LARGE),
+		message: format!("Batch contains too many requests (limit: {}, got: {}).", limit, got),
+		data: None,
+	}
+}
+
+pub fn request_too_large(limit: usize) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::REQUEST_TOO_LARGE),
+		message: format!("Request body is too large (limit: {} bytes).", limit),
+		data: None,
+	}
+}
+
+pub fn response_too_large(limit: usize) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::RESPONSE_TOO_LARGE),
+		message: format!("Response is too large (limit: {} bytes). Try narrowing the query.", limit),
+		data: None,
+	}
+}
+
+pub fn access_denied(method: &str) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::ACCESS_DENIED),
+		message: format!("Method {} is not allowed for this origin or host.", method),
+		data: None,
+	}
+}
+
 // on-demand sender cancelled.
 pub fn on_demand_cancel(_cancel: ::futures::sync::oneshot::Canceled) -> Error {
 	internal("on-demand sender cancelled", "")
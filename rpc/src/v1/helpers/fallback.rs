@@ -0,0 +1,458 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Forwarding of calls the light client can't serve with proofs to a trusted full node.
+//!
+//! Only read-only queries of chain data are forwarded, calls changing the state of the
+//! node or carrying passwords never leave it. Results of forwarded calls are not verified
+//! against the chain synced by the light client, so they are tagged in the response:
+//!
+//! ```json
+//! {"jsonrpc":"2.0","result":{"proofVerified":false,"source":"<url>","result":<full node result>},"id":1}
+//! ```
+//!
+//! Failures of forwarded calls carry the full node URL.
+//!
+//! In hybrid mode calls are handed over to the full node synced in the background,
+//! once it caught up with the light client. Only requests made of methods the full node
+//! serves over HTTP are handed over, subscriptions are always kept by the light client.
+
+use std::collections::HashSet;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use futures::{future, Future, BoxFuture};
+use jsonrpc_core::{Request, Response, Call, MethodCall, Output, Success, Failure, Error, Version, Value};
+use serde_json::{self, Map};
+use fetch::{Client as FetchClient, Fetch};
+use v1::helpers::errors;
+
+/// Read-only methods which may be forwarded if the light client can't serve them.
+const FORWARDED_METHODS: &'static [&'static str] = &[
+	"eth_getStorageAt",
+	"eth_getTransactionByHash",
+	"eth_getTransactionByBlockHashAndIndex",
+	"eth_getTransactionByBlockNumberAndIndex",
+	"eth_getTransactionReceipt",
+	"eth_getUncleByBlockHashAndIndex",
+	"eth_getUncleByBlockNumberAndIndex",
+	"trace_filter",
+	"trace_get",
+	"trace_transaction",
+	"trace_block",
+	"trace_call",
+	"trace_rawTransaction",
+	"trace_replayTransaction",
+	"parity_listAccounts",
+	"parity_listStorageKeys",
+	"parity_tokenTransfers",
+	"parity_tokenBalance",
+	"parity_balanceHistory",
+	"parity_authorStatistics",
+];
+
+/// Methods bound to the session of the caller, which can't be served over HTTP by another node.
+const SESSION_METHODS: &'static [&'static str] = &[
+	"eth_subscribe",
+	"eth_unsubscribe",
+	"parity_subscribe",
+	"parity_unsubscribe",
+	"signer_subscribePending",
+	"signer_unsubscribePending",
+];
+
+/// Forwards read-only calls failing as unsupported to a trusted full node.
+#[derive(Clone, Default)]
+pub struct FallbackPolicy {
+	remote: Option<Arc<Remote>>,
+	handover: Option<Arc<Handover>>,
+}
+
+struct Remote {
+	url: String,
+	fetch: FetchClient,
+}
+
+struct Handover {
+	remote: Remote,
+	ready: Arc<AtomicBool>,
+	methods: HashSet<String>,
+}
+
+impl Handover {
+	fn is_handed_over(&self, call: &Call) -> bool {
+		let method = match *call {
+			Call::MethodCall(ref call) => &call.method,
+			Call::Notification(ref notification) => &notification.method,
+			Call::Invalid(_) => return true,
+		};
+		self.methods.contains(method) && !SESSION_METHODS.contains(&method.as_str())
+	}
+}
+
+impl FallbackPolicy {
+	/// Forward unsupported calls to the full node JSON-RPC endpoint at `url`.
+	pub fn new(url: String, fetch: FetchClient) -> Self {
+		FallbackPolicy {
+			remote: Some(Arc::new(Remote {
+				url: url,
+				fetch: fetch,
+			})),
+			handover: None,
+		}
+	}
+
+	/// Hand calls over to the full node JSON-RPC endpoint at `url` while `ready` is set.
+	/// `methods` are the methods served by the full node, requests calling any other method
+	/// are processed locally.
+	pub fn with_handover(mut self, url: String, fetch: FetchClient, ready: Arc<AtomicBool>, methods: HashSet<String>) -> Self {
+		self.handover = Some(Arc::new(Handover {
+			remote: Remote {
+				url: url,
+				fetch: fetch,
+			},
+			ready: ready,
+			methods: methods,
+		}));
+		self
+	}
+
+	/// Returns true if no call is ever forwarded.
+	pub fn is_empty(&self) -> bool {
+		self.remote.is_none() && self.handover.is_none()
+	}
+
+	/// Returns true if the request is currently handed over to the full node.
+	pub fn is_handed_over(&self, request: &Request) -> bool {
+		let handover = match self.handover {
+			Some(ref handover) if handover.ready.load(Ordering::SeqCst) => handover,
+			_ => return false,
+		};
+
+		match *request {
+			Request::Single(ref call) => handover.is_handed_over(call),
+			Request::Batch(ref calls) => calls.iter().all(|call| handover.is_handed_over(call)),
+		}
+	}
+
+	/// Sends the whole request to the full node calls are handed over to.
+	pub fn hand_over(&self, request: Request) -> BoxFuture<Option<Response>, ()> {
+		match self.handover {
+			Some(ref handover) => handover.remote.hand_over(request),
+			None => future::ok(failure_response(request, errors::internal("Calls are not handed over.", ""))).boxed(),
+		}
+	}
+
+	/// Replaces failures of unsupported method calls in the response with responses of the full node.
+	pub fn process(&self, request: Request, response: Option<Response>) -> BoxFuture<Option<Response>, ()> {
+		let (remote, response) = match (self.remote.clone(), response) {
+			(Some(remote), Some(response)) => (remote, response),
+			(_, response) => return future::ok(response).boxed(),
+		};
+
+		let calls = match request {
+			Request::Single(call) => vec![call],
+			Request::Batch(calls) => calls,
+		};
+
+		let forward_output = move |output: Output| -> BoxFuture<Output, ()> {
+			match output {
+				Output::Failure(failure) => match method_call(&calls, &failure) {
+					Some(call) => remote.forward(call, failure),
+					None => future::ok(Output::Failure(failure)).boxed(),
+				},
+				output => future::ok(output).boxed(),
+			}
+		};
+
+		match response {
+			Response::Single(output) => forward_output(output)
+				.map(|output| Some(Response::Single(output)))
+				.boxed(),
+			Response::Batch(outputs) => future::join_all(outputs.into_iter().map(forward_output).collect::<Vec<_>>())
+				.map(|outputs| Some(Response::Batch(outputs)))
+				.boxed(),
+		}
+	}
+}
+
+impl Remote {
+	// post the call to the full node, keeping the local failure if that fails.
+	fn forward(&self, call: MethodCall, failure: Failure) -> BoxFuture<Output, ()> {
+		info!(target: "rpc", "Forwarding {} to {}, the response is not proof-verified.", call.method, self.url);
+
+		let url = self.url.clone();
+		let body = serde_json::to_string(&Request::Single(Call::MethodCall(call)))
+			.expect("Serialization of requests is infallible; qed");
+
+		let response = self.fetch.post_json(&self.url, body).then(move |res| {
+			let output = res.map_err(|e| format!("{:?}", e)).and_then(|mut response| {
+				if !response.is_success() {
+					return Err(format!("HTTP status {}", response.status()));
+				}
+
+				let mut body = String::new();
+				response.read_to_string(&mut body).map_err(|e| e.to_string())?;
+				serde_json::from_str::<Output>(&body).map_err(|e| e.to_string())
+			});
+
+			Ok::<_, ()>(match output {
+				Ok(Output::Success(success)) => Output::Success(Success {
+					result: proxied_result(&url, success.result),
+					..success
+				}),
+				Ok(output) => output,
+				Err(e) => {
+					warn!(target: "rpc", "Error forwarding call to {}: {}", url, e);
+					Output::Failure(Failure {
+						error: errors::light_fallback(&url, failure.error, e),
+						..failure
+					})
+				},
+			})
+		});
+
+		self.fetch.process(response)
+	}
+
+	// post the whole request to the full node, failing all calls if that fails.
+	fn hand_over(&self, request: Request) -> BoxFuture<Option<Response>, ()> {
+		let url = self.url.clone();
+		let body = serde_json::to_string(&request)
+			.expect("Serialization of requests is infallible; qed");
+
+		let response = self.fetch.post_json(&self.url, body).then(move |res| {
+			let response = res.map_err(|e| format!("{:?}", e)).and_then(|mut response| {
+				if !response.is_success() {
+					return Err(format!("HTTP status {}", response.status()));
+				}
+
+				let mut body = String::new();
+				response.read_to_string(&mut body).map_err(|e| e.to_string())?;
+				parse_response(&body)
+			});
+
+			Ok::<_, ()>(match response {
+				Ok(response) => response,
+				Err(e) => {
+					warn!(target: "rpc", "Error handing call over to {}: {}", url, e);
+					failure_response(request, errors::full_node_unavailable(&url, e))
+				},
+			})
+		});
+
+		self.fetch.process(response)
+	}
+}
+
+// tag the result of a forwarded call as not proof-verified.
+fn proxied_result(url: &str, result: Value) -> Value {
+	let mut tagged = Map::new();
+	tagged.insert("proofVerified".into(), Value::Bool(false));
+	tagged.insert("source".into(), Value::String(url.into()));
+	tagged.insert("result".into(), result);
+	Value::Object(tagged)
+}
+
+// parse the full node's response, empty if the request had notifications only.
+fn parse_response(body: &str) -> Result<Option<Response>, String> {
+	if body.trim().is_empty() {
+		return Ok(None);
+	}
+
+	let response = match serde_json::from_str(body).map_err(|e| e.to_string())? {
+		Value::Array(outputs) => Response::Batch(outputs.into_iter()
+			.map(serde_json::from_value)
+			.collect::<Result<_, _>>()
+			.map_err(|e| e.to_string())?),
+		output => Response::Single(serde_json::from_value(output).map_err(|e| e.to_string())?),
+	};
+
+	Ok(Some(response))
+}
+
+// fail every call of the request with the given error.
+fn failure_response(request: Request, error: Error) -> Option<Response> {
+	let failure = |call: Call| match call {
+		Call::MethodCall(call) => Some(Output::Failure(Failure {
+			jsonrpc: call.jsonrpc,
+			error: error.clone(),
+			id: call.id,
+		})),
+		Call::Invalid(id) => Some(Output::Failure(Failure {
+			jsonrpc: Some(Version::V2),
+			error: error.clone(),
+			id: id,
+		})),
+		Call::Notification(_) => None,
+	};
+
+	match request {
+		Request::Single(call) => failure(call).map(Response::Single),
+		Request::Batch(calls) => {
+			let outputs: Vec<_> = calls.into_iter().filter_map(failure).collect();
+			match outputs.is_empty() {
+				true => None,
+				false => Some(Response::Batch(outputs)),
+			}
+		}
+	}
+}
+
+// the forwardable method call of an output failing as unsupported.
+fn method_call(calls: &[Call], failure: &Failure) -> Option<MethodCall> {
+	if !errors::is_unsupported(&failure.error) {
+		return None;
+	}
+
+	calls.iter().filter_map(|call| match *call {
+		Call::MethodCall(ref call) if call.id == failure.id && FORWARDED_METHODS.contains(&call.method.as_str()) => Some(call.clone()),
+		_ => None,
+	}).next()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use futures::Future;
+	use jsonrpc_core::{Request, Response, Call, MethodCall, Output, Failure, Success, Id, Version, Params, Value};
+	use fetch::{Client as FetchClient, Fetch};
+	use v1::helpers::errors;
+	use super::{FallbackPolicy, method_call, parse_response, failure_response, proxied_result};
+
+	fn call(method: &str, id: u64) -> Call {
+		Call::MethodCall(MethodCall {
+			jsonrpc: Some(Version::V2),
+			method: method.into(),
+			params: Some(Params::Array(vec![])),
+			id: Id::Num(id),
+		})
+	}
+
+	fn failure(error: ::jsonrpc_core::Error, id: u64) -> Failure {
+		Failure {
+			jsonrpc: Some(Version::V2),
+			error: error,
+			id: Id::Num(id),
+		}
+	}
+
+	#[test]
+	fn should_only_forward_unsupported_calls() {
+		// given
+		let calls = vec![call("eth_blockNumber", 1), call("trace_filter", 2)];
+
+		// then
+		assert_eq!(method_call(&calls, &failure(errors::light_unimplemented(None), 2)).map(|c| c.method), Some("trace_filter".into()));
+		assert_eq!(method_call(&calls, &failure(errors::no_light_peers(), 2)), None);
+		assert_eq!(method_call(&calls, &failure(errors::light_unimplemented(None), 3)), None);
+	}
+
+	#[test]
+	fn should_never_forward_calls_changing_state() {
+		// given
+		let calls = vec![
+			call("parity_setEngineSigner", 1),
+			call("parity_executeUpgrade", 2),
+			call("parity_setMode", 3),
+			call("signer_confirmRequest", 4),
+		];
+
+		// then
+		assert_eq!(method_call(&calls, &failure(errors::light_unimplemented(None), 1)), None);
+		assert_eq!(method_call(&calls, &failure(errors::light_unimplemented(None), 2)), None);
+		assert_eq!(method_call(&calls, &failure(errors::light_unimplemented(None), 3)), None);
+		assert_eq!(method_call(&calls, &failure(errors::signer_disabled(), 4)), None);
+	}
+
+	#[test]
+	fn should_tag_forwarded_results() {
+		// given
+		let result = proxied_result("http://127.0.0.1:8545", Value::String("0x1".into()));
+
+		// then
+		assert_eq!(result.get("proofVerified"), Some(&Value::Bool(false)));
+		assert_eq!(result.get("source"), Some(&Value::String("http://127.0.0.1:8545".into())));
+		assert_eq!(result.get("result"), Some(&Value::String("0x1".into())));
+	}
+
+	#[test]
+	fn should_pass_responses_through_by_default() {
+		// given
+		let policy = FallbackPolicy::default();
+		let unsupported = || Response::Single(Output::Failure(failure(errors::light_unimplemented(None), 1)));
+		let success = || Response::Single(Output::Success(Success {
+			jsonrpc: Some(Version::V2),
+			result: Value::Bool(true),
+			id: Id::Num(1),
+		}));
+
+		// then
+		assert!(policy.is_empty());
+		assert_eq!(policy.process(Request::Single(call("eth_getLogs", 1)), Some(unsupported())).wait(), Ok(Some(unsupported())));
+		assert_eq!(policy.process(Request::Single(call("eth_getLogs", 1)), Some(success())).wait(), Ok(Some(success())));
+		assert!(!policy.is_handed_over(&Request::Single(call("eth_getLogs", 1))));
+	}
+
+	#[test]
+	fn should_hand_over_only_methods_served_by_full_node() {
+		// given
+		let ready = Arc::new(AtomicBool::new(false));
+		let methods = vec!["eth_blockNumber".to_owned(), "eth_subscribe".into()].into_iter().collect();
+		let policy = FallbackPolicy::default()
+			.with_handover("http://127.0.0.1:8549".into(), FetchClient::new().unwrap(), ready.clone(), methods);
+		let batch = |methods: &[&str]| Request::Batch(methods.iter().enumerate().map(|(id, method)| call(method, id as u64)).collect());
+
+		// then
+		assert!(!policy.is_handed_over(&batch(&["eth_blockNumber"])));
+		ready.store(true, Ordering::SeqCst);
+		assert!(policy.is_handed_over(&batch(&["eth_blockNumber"])));
+		assert!(!policy.is_handed_over(&batch(&["eth_blockNumber", "personal_sendTransaction"])));
+		assert!(!policy.is_handed_over(&Request::Single(call("eth_subscribe", 1))));
+	}
+
+	#[test]
+	fn should_parse_handed_over_responses() {
+		// given
+		let success = |id| Output::Success(Success {
+			jsonrpc: Some(Version::V2),
+			result: Value::String("0x1".into()),
+			id: Id::Num(id),
+		});
+
+		// then
+		assert_eq!(parse_response(r#"{"jsonrpc":"2.0","result":"0x1","id":1}"#), Ok(Some(Response::Single(success(1)))));
+		assert_eq!(parse_response(r#"[{"jsonrpc":"2.0","result":"0x1","id":1},{"jsonrpc":"2.0","result":"0x1","id":2}]"#),
+			Ok(Some(Response::Batch(vec![success(1), success(2)]))));
+		assert_eq!(parse_response(""), Ok(None));
+		assert!(parse_response("<html>").is_err());
+	}
+
+	#[test]
+	fn should_fail_all_calls_if_full_node_is_unavailable() {
+		// given
+		let error = errors::full_node_unavailable("http://127.0.0.1:8549", "Connection refused".into());
+		let request = Request::Batch(vec![call("eth_blockNumber", 1), call("eth_getLogs", 2)]);
+
+		// then
+		assert_eq!(failure_response(request, error.clone()), Some(Response::Batch(vec![
+			Output::Failure(failure(error.clone(), 1)),
+			Output::Failure(failure(error, 2)),
+		])));
+	}
+}
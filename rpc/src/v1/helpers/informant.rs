@@ -19,14 +19,26 @@
 use std::fmt;
 use std::sync::Arc;
 use std::sync::atomic::{self, AtomicUsize};
+use std::thread;
 use std::time;
-use futures::Future;
+use futures::{future, Future};
 use jsonrpc_core as rpc;
 use order_stat;
-use util::RwLock;
+use util::{RwLock, Mutex};
+use ethcore::client::{BlockChainClient, Client};
+use ethsync::SyncProvider;
+use v1::helpers::access::AccessPolicy;
+use v1::helpers::block_import::is_major_importing;
+use v1::helpers::fallback::FallbackPolicy;
+use v1::helpers::limits::SharedLimits;
+use v1::helpers::offline::OfflinePolicy;
+use v1::helpers::wake::WakePolicy;
+use v1::Metadata;
 
 const RATE_SECONDS: usize = 10;
 const STATS_SAMPLES: usize = 60;
+/// Longest time a request waits for a woken client to catch up.
+const MAX_CATCH_UP_SECONDS: u64 = 60;
 
 struct RateCalculator {
 	era: time::Instant,
@@ -178,61 +190,252 @@ impl RpcStats {
 pub trait ActivityNotifier: Send + Sync + 'static {
 	/// Activity on RPC interface
 	fn active(&self);
+
+	/// Request which needs an up-to-date client, which should then stay awake for `idle`.
+	/// Returns when the request can be served.
+	fn wake(&self, _idle: time::Duration) {
+		self.active()
+	}
 }
 
 /// Stats-counting RPC middleware
 pub struct Middleware<T: ActivityNotifier = ClientNotifier> {
 	stats: Arc<RpcStats>,
 	notifier: T,
+	limits: SharedLimits,
+	access: AccessPolicy,
+	wake: WakePolicy,
+	offline: OfflinePolicy,
+	fallback: FallbackPolicy,
 }
 
 impl<T: ActivityNotifier> Middleware<T> {
-	/// Create new Middleware with stats counter and activity notifier.
-	pub fn new(stats: Arc<RpcStats>, notifier: T) -> Self {
+	/// Create new Middleware with stats counter, activity notifier and request limits.
+	pub fn new(stats: Arc<RpcStats>, notifier: T, limits: SharedLimits) -> Self {
 		Middleware {
 			stats: stats,
 			notifier: notifier,
+			limits: limits,
+			access: Default::default(),
+			wake: Default::default(),
+			offline: Default::default(),
+			fallback: Default::default(),
 		}
 	}
 
+	/// Restrict access to methods according to given policy.
+	pub fn with_access_policy(mut self, access: AccessPolicy) -> Self {
+		self.access = access;
+		self
+	}
+
+	/// Wake the client only on methods of given policy.
+	/// Without a policy any request keeps the client awake.
+	pub fn with_wake_policy(mut self, wake: WakePolicy) -> Self {
+		self.wake = wake;
+		self
+	}
+
+	/// Reject methods of given policy as requiring the network.
+	pub fn with_offline_policy(mut self, offline: OfflinePolicy) -> Self {
+		self.offline = offline;
+		self
+	}
+
+	/// Forward calls which fail as unsupported, or hand all calls over, according to given policy.
+	pub fn with_fallback_policy(mut self, fallback: FallbackPolicy) -> Self {
+		self.fallback = fallback;
+		self
+	}
+
 	fn as_micro(dur: time::Duration) -> u32 {
 		(dur.as_secs() * 1_000_000) as u32 + dur.subsec_nanos() / 1_000
 	}
+
+	// process the request by this node, forwarding calls it can't serve if configured.
+	fn process_locally<F>(&self, request: rpc::Request, meta: Metadata, process: F) -> rpc::FutureResponse where
+		F: FnOnce(rpc::Request, Metadata) -> rpc::FutureResponse,
+	{
+		let forwarded = match self.fallback.is_empty() {
+			true => None,
+			false => Some((self.fallback.clone(), request.clone())),
+		};
+
+		let response = match self.wake.is_empty() {
+			true => {
+				let response = process(request, meta);
+				self.notifier.active();
+				response
+			},
+			// processing can't be deferred, so the request is held until the client catches up.
+			false => {
+				if let Some(idle) = self.wake.idle_timeout(&request) {
+					self.notifier.wake(idle);
+				}
+				process(request, meta)
+			},
+		};
+		match forwarded {
+			Some((fallback, request)) => response.and_then(move |res| fallback.process(request, res)).boxed(),
+			None => response,
+		}
+	}
 }
 
-impl<M: rpc::Metadata, T: ActivityNotifier> rpc::Middleware<M> for Middleware<T> {
-	fn on_request<F>(&self, request: rpc::Request, meta: M, process: F) -> rpc::FutureResponse where
-		F: FnOnce(rpc::Request, M) -> rpc::FutureResponse,
+impl<T: ActivityNotifier> rpc::Middleware<Metadata> for Middleware<T> {
+	fn on_request<F>(&self, request: rpc::Request, meta: Metadata, process: F) -> rpc::FutureResponse where
+		F: FnOnce(rpc::Request, Metadata) -> rpc::FutureResponse,
 	{
 		let start = time::Instant::now();
-		let response = process(request, meta);
-
-		self.notifier.active();
 		let stats = self.stats.clone();
 		stats.count_request();
+
+		let limits = self.limits.get();
+		if let Err(response) = limits.check_request(&request)
+			.and_then(|_| self.access.check_request(&request, &meta))
+			.and_then(|_| self.offline.check_request(&request))
+		{
+			return future::ok(Some(response)).boxed();
+		}
+
+		let response = match self.fallback.is_handed_over(&request) {
+			true => self.fallback.hand_over(request),
+			false => self.process_locally(request, meta, process),
+		};
+
 		response.map(move |res| {
 			stats.add_roundtrip(Self::as_micro(start.elapsed()));
-			res
+			res.map(|res| limits.check_response(res))
 		}).boxed()
 	}
 }
 
 /// Client Notifier
 pub struct ClientNotifier {
-	/// Client
-	pub client: Arc<::ethcore::client::Client>,
+	client: Arc<Client>,
+	sync: Arc<SyncProvider>,
+	/// When the client was woken by a request and hasn't caught up since.
+	woken_at: Mutex<Option<time::Instant>>,
+}
+
+impl ClientNotifier {
+	/// Create new notifier of the client, waiting for the sync to catch up after waking it.
+	pub fn new(client: Arc<Client>, sync: Arc<SyncProvider>) -> Self {
+		ClientNotifier {
+			client: client,
+			sync: sync,
+			woken_at: Mutex::new(None),
+		}
+	}
+
+	fn is_caught_up(&self) -> bool {
+		let status = self.sync.status();
+		let best_block = self.client.chain_info().best_block_number;
+		status.num_active_peers > 0
+			&& !is_major_importing(Some(status.state), self.client.queue_info())
+			&& status.highest_block_number.map_or(true, |highest| best_block >= highest)
+	}
 }
 
 impl ActivityNotifier for ClientNotifier {
 	fn active(&self) {
 		self.client.keep_alive()
 	}
+
+	fn wake(&self, idle: time::Duration) {
+		let woken_at = {
+			let mut woken_at = self.woken_at.lock();
+			if self.client.keep_alive_for(idle) {
+				*woken_at = Some(time::Instant::now());
+			}
+			match *woken_at {
+				Some(woken_at) => woken_at,
+				None => return,
+			}
+		};
+
+		let deadline = woken_at + time::Duration::from_secs(MAX_CATCH_UP_SECONDS);
+		while !self.is_caught_up() {
+			if time::Instant::now() > deadline {
+				warn!(target: "rpc", "Serving a request before the client caught up with the chain.");
+				*self.woken_at.lock() = None;
+				return;
+			}
+			thread::sleep(time::Duration::from_millis(200));
+		}
+		*self.woken_at.lock() = None;
+	}
 }
 
 #[cfg(test)]
 mod tests {
+	use std::io::{Read, Write};
+	use std::net::TcpListener;
+	use std::sync::Arc;
+	use std::sync::atomic::AtomicBool;
+	use std::thread;
+	use futures::{future, Future};
+	use jsonrpc_core::{self as rpc, Middleware as RpcMiddleware, Response, Output, Success, Id, Version, Value};
+	use fetch::{Client as FetchClient, Fetch};
+	use serde_json;
+	use v1::helpers::fallback::FallbackPolicy;
+	use v1::helpers::limits::SharedLimits;
+	use v1::Metadata;
+	use super::{RateCalculator, StatsCalculator, RpcStats, Middleware, ActivityNotifier};
+
+	struct TestNotifier;
+
+	impl ActivityNotifier for TestNotifier {
+		fn active(&self) {}
+	}
 
-	use super::{RateCalculator, StatsCalculator, RpcStats};
+	fn success(result: &str) -> Response {
+		Response::Single(Output::Success(Success {
+			jsonrpc: Some(Version::V2),
+			result: Value::String(result.into()),
+			id: Id::Num(1),
+		}))
+	}
+
+	// serve a single HTTP request with given JSON body, returns the URL.
+	fn serve_once(body: &'static str) -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}", listener.local_addr().unwrap());
+		thread::spawn(move || {
+			let (mut stream, _) = listener.accept().unwrap();
+			let mut request = Vec::new();
+			let mut buf = [0u8; 1024];
+			while !String::from_utf8_lossy(&request).contains("\"id\"") {
+				match stream.read(&mut buf) {
+					Ok(0) | Err(_) => break,
+					Ok(read) => request.extend_from_slice(&buf[..read]),
+				}
+			}
+			write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body).unwrap();
+		});
+		url
+	}
+
+	fn handle(middleware: &Middleware<TestNotifier>, request: &str) -> Option<Response> {
+		let request: rpc::Request = serde_json::from_str(request).unwrap();
+		middleware.on_request(request, Metadata::default(), |_, _| future::ok(Some(success("local"))).boxed()).wait().unwrap()
+	}
+
+	#[test]
+	fn should_hand_over_requests_through_middleware() {
+		// given
+		let url = serve_once(r#"{"jsonrpc":"2.0","result":"full","id":1}"#);
+		let methods = vec!["eth_blockNumber".to_owned(), "eth_subscribe".into()].into_iter().collect();
+		let fallback = FallbackPolicy::default()
+			.with_handover(url, FetchClient::new().unwrap(), Arc::new(AtomicBool::new(true)), methods);
+		let middleware = Middleware::new(Arc::new(RpcStats::default()), TestNotifier, SharedLimits::default())
+			.with_fallback_policy(fallback);
+
+		// then
+		assert_eq!(handle(&middleware, r#"{"jsonrpc":"2.0","method":"eth_subscribe","params":["newHeads"],"id":1}"#), Some(success("local")));
+		assert_eq!(handle(&middleware, r#"{"jsonrpc":"2.0","method":"personal_unlockAccount","params":[],"id":1}"#), Some(success("local")));
+		assert_eq!(handle(&middleware, r#"{"jsonrpc":"2.0","method":"eth_blockNumber","params":[],"id":1}"#), Some(success("full")));
+	}
 
 	#[test]
 	fn should_calculate_rate() {
@@ -20,6 +20,7 @@ use std::sync::Arc;
 
 use ethcore::basic_account::BasicAccount;
 use ethcore::encoded;
+use ethcore::engines::Engine;
 use ethcore::executed::{Executed, ExecutionError};
 use ethcore::ids::BlockId;
 use ethcore::transaction::{Action, Transaction as EthTransaction};
@@ -37,7 +38,7 @@ use ethsync::LightSync;
 use util::{Address, Mutex, Uint, U256};
 
 use v1::helpers::{CallRequest as CallRequestHelper, errors, dispatch};
-use v1::types::{BlockNumber, CallRequest};
+use v1::types::{BlockNumber, Bytes, CallRequest, Header, RichHeader};
 
 /// Helper for fetching blockchain data either from the light client or the network
 /// as necessary.
@@ -55,6 +56,34 @@ pub struct LightFetch {
 /// Type alias for convenience.
 pub type ExecutionResult = Result<Executed, ExecutionError>;
 
+/// Convert a header known to the light client into its RPC representation.
+pub fn rich_header(engine: &Engine, encoded: encoded::Header) -> RichHeader {
+	let header = encoded.decode();
+	let extra_info = engine.extra_info(&header);
+	RichHeader {
+		inner: Header {
+			hash: Some(header.hash().into()),
+			size: Some(encoded.rlp().as_raw().len().into()),
+			parent_hash: header.parent_hash().clone().into(),
+			uncles_hash: header.uncles_hash().clone().into(),
+			author: header.author().clone().into(),
+			miner: header.author().clone().into(),
+			state_root: header.state_root().clone().into(),
+			transactions_root: header.transactions_root().clone().into(),
+			receipts_root: header.receipts_root().clone().into(),
+			number: Some(header.number().into()),
+			gas_used: header.gas_used().clone().into(),
+			gas_limit: header.gas_limit().clone().into(),
+			logs_bloom: header.log_bloom().clone().into(),
+			timestamp: header.timestamp().into(),
+			difficulty: header.difficulty().clone().into(),
+			seal_fields: header.seal().iter().cloned().map(Into::into).collect(),
+			extra_data: Bytes::new(header.extra_data().clone()),
+		},
+		extra_info: extra_info,
+	}
+}
+
 impl LightFetch {
 	/// Get a block header from the on demand service or client, or error.
 	pub fn header(&self, id: BlockId) -> BoxFuture<Option<encoded::Header>, Error> {
@@ -0,0 +1,340 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC request and response size limits.
+
+use std::io;
+use std::sync::Arc;
+use jsonrpc_core::{self as rpc, Request, Response, Call, Output, Failure, Id, Version, Value};
+use serde::Serialize;
+use serde_json;
+use util::RwLock;
+use v1::helpers::errors;
+
+/// Limits applied to incoming requests and outgoing responses.
+/// `None` means that given limit is not enforced.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RequestLimits {
+	/// Maximal number of calls in a single batch request.
+	pub max_batch_size: Option<usize>,
+	/// Maximal size of a serialized request (in bytes).
+	pub max_request_size: Option<usize>,
+	/// Maximal size of a serialized response (in bytes).
+	pub max_response_size: Option<usize>,
+}
+
+impl RequestLimits {
+	/// Returns true if none of the limits is enforced.
+	pub fn is_unlimited(&self) -> bool {
+		self.max_batch_size.is_none() && self.max_request_size.is_none() && self.max_response_size.is_none()
+	}
+
+	/// Validates the request against configured limits.
+	/// Returns an error response if the request should be rejected without processing.
+	pub fn check_request(&self, request: &Request) -> Result<(), Response> {
+		if let (Some(limit), &Request::Batch(ref calls)) = (self.max_batch_size, request) {
+			if calls.len() > limit {
+				return Err(error_response(errors::batch_too_large(limit, calls.len())));
+			}
+		}
+
+		if let Some(limit) = self.max_request_size {
+			if exceeds(request, limit) {
+				let (id, version) = match *request {
+					Request::Single(ref call) => call_id(call),
+					Request::Batch(_) => (Id::Null, Some(Version::V2)),
+				};
+				return Err(Failure {
+					jsonrpc: version,
+					error: errors::request_too_large(limit),
+					id: id,
+				}.into());
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Validates the response against configured limits.
+	/// Outputs of a response that is too large are replaced with errors (preserving the ids).
+	pub fn check_response(&self, response: Response) -> Response {
+		let limit = match self.max_response_size {
+			Some(limit) => limit,
+			None => return response,
+		};
+
+		// most responses are way below the limit, so serialization is avoided when possible.
+		if response_size_bound(&response) <= limit || !exceeds(&response, limit) {
+			return response;
+		}
+
+		let error = errors::response_too_large(limit);
+		match response {
+			Response::Single(output) => Response::Single(into_failure(output, error)),
+			Response::Batch(outputs) => Response::Batch(
+				outputs.into_iter().map(|output| into_failure(output, error.clone())).collect()
+			),
+		}
+	}
+}
+
+/// Request limits which can be changed while the server is running.
+#[derive(Debug, Default, Clone)]
+pub struct SharedLimits(Arc<RwLock<RequestLimits>>);
+
+impl SharedLimits {
+	/// Create shared limits with given initial value.
+	pub fn new(limits: RequestLimits) -> Self {
+		SharedLimits(Arc::new(RwLock::new(limits)))
+	}
+
+	/// Currently enforced limits.
+	pub fn get(&self) -> RequestLimits {
+		self.0.read().clone()
+	}
+
+	/// Replace the limits, applies to subsequent requests.
+	pub fn set(&self, limits: RequestLimits) {
+		*self.0.write() = limits;
+	}
+}
+
+/// Counts written bytes, failing as soon as the limit is exceeded.
+struct SizeLimit {
+	size: usize,
+	limit: usize,
+}
+
+impl io::Write for SizeLimit {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.size = self.size.saturating_add(buf.len());
+		match self.size > self.limit {
+			true => Err(io::Error::new(io::ErrorKind::Other, "Size limit exceeded")),
+			false => Ok(buf.len()),
+		}
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+/// Checks if serialized value is larger than the limit.
+/// Serialization stops at the limit and nothing is allocated.
+fn exceeds<T: Serialize>(value: &T, limit: usize) -> bool {
+	serde_json::to_writer(&mut SizeLimit { size: 0, limit: limit }, value).is_err()
+}
+
+// Upper bound of a serialized string: quotes and every character escaped as `\uXXXX`.
+fn string_size_bound(s: &str) -> usize {
+	s.len().saturating_mul(6).saturating_add(2)
+}
+
+/// Upper bound of the serialized value size, cheap to compute compared to serialization.
+fn value_size_bound(value: &Value) -> usize {
+	match *value {
+		Value::Null | Value::Bool(_) => 5,
+		Value::Number(_) => 32,
+		Value::String(ref s) => string_size_bound(s),
+		Value::Array(ref values) => values.iter()
+			.fold(2, |size: usize, v| size.saturating_add(value_size_bound(v)).saturating_add(1)),
+		Value::Object(ref map) => map.iter()
+			.fold(2, |size: usize, (k, v)| size
+				.saturating_add(string_size_bound(k))
+				.saturating_add(value_size_bound(v))
+				.saturating_add(2)
+			),
+	}
+}
+
+/// Upper bound of the serialized response size.
+fn response_size_bound(response: &Response) -> usize {
+	// `jsonrpc`, `id` and `result`/`error` keys with separators.
+	const OUTPUT_OVERHEAD: usize = 64;
+
+	let id_size = |id: &Id| match *id {
+		Id::Str(ref s) => string_size_bound(s),
+		_ => 32,
+	};
+	let output_size = |output: &Output| {
+		let size = match *output {
+			Output::Success(ref success) => value_size_bound(&success.result)
+				.saturating_add(id_size(&success.id)),
+			// error object has its own `code`, `message` and `data` keys.
+			Output::Failure(ref failure) => string_size_bound(&failure.error.message)
+				.saturating_add(failure.error.data.as_ref().map_or(0, value_size_bound))
+				.saturating_add(id_size(&failure.id))
+				.saturating_add(OUTPUT_OVERHEAD),
+		};
+		size.saturating_add(OUTPUT_OVERHEAD)
+	};
+
+	match *response {
+		Response::Single(ref output) => output_size(output),
+		Response::Batch(ref outputs) => outputs.iter()
+			.fold(2, |size: usize, output| size.saturating_add(output_size(output)).saturating_add(1)),
+	}
+}
+
+fn error_response(error: rpc::Error) -> Response {
+	Failure {
+		jsonrpc: Some(Version::V2),
+		error: error,
+		id: Id::Null,
+	}.into()
+}
+
+fn call_id(call: &Call) -> (Id, Option<Version>) {
+	match *call {
+		Call::MethodCall(ref call) => (call.id.clone(), call.jsonrpc),
+		Call::Notification(ref notification) => (Id::Null, notification.jsonrpc),
+		Call::Invalid(ref id) => (id.clone(), Some(Version::V2)),
+	}
+}
+
+fn into_failure(output: Output, error: rpc::Error) -> Output {
+	let (id, version) = match output {
+		Output::Success(success) => (success.id, success.jsonrpc),
+		Output::Failure(failure) => (failure.id, failure.jsonrpc),
+	};
+
+	Output::Failure(Failure {
+		jsonrpc: version,
+		error: error,
+		id: id,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use jsonrpc_core::{Request, Response, Call, MethodCall, Output, Success, Id, Version, Params, Value};
+	use super::{RequestLimits, exceeds, response_size_bound};
+
+	fn call(id: u64) -> Call {
+		Call::MethodCall(MethodCall {
+			jsonrpc: Some(Version::V2),
+			method: "eth_blockNumber".into(),
+			params: Some(Params::Array(vec![])),
+			id: Id::Num(id),
+		})
+	}
+
+	fn success(id: u64, result: &str) -> Output {
+		Output::Success(Success {
+			jsonrpc: Some(Version::V2),
+			result: Value::String(result.into()),
+			id: Id::Num(id),
+		})
+	}
+
+	#[test]
+	fn should_accept_everything_without_limits() {
+		// given
+		let limits = RequestLimits::default();
+		let request = Request::Batch((0..1000).map(call).collect());
+		let response = Response::Single(success(1, &"x".repeat(10_000)));
+
+		// then
+		assert!(limits.is_unlimited());
+		assert!(limits.check_request(&request).is_ok());
+		assert_eq!(limits.check_response(response), Response::Single(success(1, &"x".repeat(10_000))));
+	}
+
+	#[test]
+	fn should_reject_too_large_batches() {
+		// given
+		let limits = RequestLimits {
+			max_batch_size: Some(2),
+			..Default::default()
+		};
+
+		// then
+		assert!(limits.check_request(&Request::Batch(vec![call(1), call(2)])).is_ok());
+		assert!(limits.check_request(&Request::Single(call(1))).is_ok());
+		match limits.check_request(&Request::Batch(vec![call(1), call(2), call(3)])) {
+			Err(Response::Single(Output::Failure(failure))) => {
+				assert_eq!(failure.id, Id::Null);
+				assert_eq!(failure.error.code.code(), -32080);
+			},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_reject_too_large_requests() {
+		// given
+		let limits = RequestLimits {
+			max_request_size: Some(64),
+			..Default::default()
+		};
+
+		// then
+		assert!(limits.check_request(&Request::Single(call(1))).is_ok());
+		match limits.check_request(&Request::Batch(vec![call(1), call(2)])) {
+			Err(Response::Single(Output::Failure(failure))) => assert_eq!(failure.error.code.code(), -32081),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_replace_too_large_responses_with_errors() {
+		// given
+		let limits = RequestLimits {
+			max_response_size: Some(100),
+			..Default::default()
+		};
+
+		// when
+		let small = limits.check_response(Response::Single(success(1, "0x1")));
+		let large = limits.check_response(Response::Batch(vec![success(1, &"x".repeat(64)), success(2, "0x1")]));
+
+		// then
+		assert_eq!(small, Response::Single(success(1, "0x1")));
+		match large {
+			Response::Batch(outputs) => {
+				assert_eq!(outputs.len(), 2);
+				for (output, id) in outputs.into_iter().zip(1..) {
+					match output {
+						Output::Failure(failure) => {
+							assert_eq!(failure.id, Id::Num(id));
+							assert_eq!(failure.error.code.code(), -32082);
+						},
+						other => panic!("Unexpected output: {:?}", other),
+					}
+				}
+			},
+			other => panic!("Unexpected response: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_bound_serialized_response_size() {
+		// given
+		let responses = vec![
+			Response::Single(success(1, "0x1")),
+			Response::Single(success(2, "\"\n\u{1}\\")),
+			Response::Batch(vec![success(1, &"x".repeat(1_000)), success(2, "0x1")]),
+		];
+
+		// then
+		for response in responses {
+			let size = ::serde_json::to_vec(&response).unwrap().len();
+			assert!(response_size_bound(&response) >= size);
+			assert!(!exceeds(&response, size));
+			assert!(exceeds(&response, size - 1));
+		}
+	}
+}
@@ -17,14 +17,20 @@
 #[macro_use]
 pub mod errors;
 
+pub mod access;
 pub mod accounts;
 pub mod block_import;
 pub mod dispatch;
+pub mod fallback;
 pub mod fake_sign;
 pub mod light_fetch;
 pub mod informant;
+pub mod limits;
+pub mod offline;
 pub mod oneshot;
 pub mod ipfs;
+pub mod signing_policy;
+pub mod wake;
 
 mod network_settings;
 mod poll_manager;
@@ -32,6 +38,7 @@ mod poll_filter;
 mod requests;
 mod signer;
 mod signing_queue;
+mod subscribers;
 
 pub use self::dispatch::{Dispatcher, FullDispatcher};
 pub use self::network_settings::NetworkSettings;
@@ -44,4 +51,5 @@ pub use self::signing_queue::{
 	ConfirmationsQueue, ConfirmationPromise, ConfirmationResult, SigningQueue, QueueEvent, DefaultAccount,
 	QUEUE_LIMIT as SIGNING_QUEUE_LIMIT,
 };
-pub use self::signer::SignerService;
+pub use self::signer::{SignerService, Confirmation};
+pub use self::subscribers::Subscribers;
@@ -0,0 +1,154 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Rejection of methods requiring the network when serving from the local database only.
+
+use std::collections::HashSet;
+
+use jsonrpc_core::{Request, Response, Call, Failure, Id, Version};
+use v1::helpers::errors;
+
+/// Methods which broadcast transactions, take part in mining,
+/// manage peers or fetch remote content.
+pub const NETWORK_METHODS: &'static [&'static str] = &[
+	"eth_sendTransaction",
+	"eth_sendRawTransaction",
+	"eth_submitTransaction",
+	"eth_getWork",
+	"eth_submitWork",
+	"eth_submitHashrate",
+	"personal_sendTransaction",
+	"personal_signAndSendTransaction",
+	"parity_postTransaction",
+	"parity_hashContent",
+	"parity_executeUpgrade",
+	"parity_setMode",
+	"parity_startNetwork",
+	"parity_addReservedPeer",
+	"parity_removeReservedPeer",
+	"parity_acceptNonReservedPeers",
+	"parity_dropNonReservedPeers",
+];
+
+/// Methods rejected because the network is never started.
+#[derive(Debug, Default, Clone)]
+pub struct OfflinePolicy {
+	rejected: HashSet<String>,
+}
+
+impl OfflinePolicy {
+	/// Policy rejecting all methods which require the network.
+	pub fn network_methods() -> Self {
+		OfflinePolicy {
+			rejected: NETWORK_METHODS.iter().map(|method| method.to_string()).collect(),
+		}
+	}
+
+	/// Returns true if no method is rejected.
+	pub fn is_empty(&self) -> bool {
+		self.rejected.is_empty()
+	}
+
+	/// Returns an error response if any of the calls requires the network.
+	pub fn check_request(&self, request: &Request) -> Result<(), Response> {
+		if self.rejected.is_empty() {
+			return Ok(());
+		}
+
+		let rejected = |call: &Call| {
+			let method = match *call {
+				Call::MethodCall(ref call) => &call.method,
+				Call::Notification(ref notification) => &notification.method,
+				Call::Invalid(_) => return None,
+			};
+
+			match self.rejected.contains(method) {
+				true => Some(method.clone()),
+				false => None,
+			}
+		};
+
+		let (method, id, version) = match *request {
+			Request::Single(ref call) => match rejected(call) {
+				None => return Ok(()),
+				Some(method) => match *call {
+					Call::MethodCall(ref call) => (method, call.id.clone(), call.jsonrpc),
+					_ => (method, Id::Null, Some(Version::V2)),
+				},
+			},
+			Request::Batch(ref calls) => match calls.iter().filter_map(rejected).next() {
+				None => return Ok(()),
+				Some(method) => (method, Id::Null, Some(Version::V2)),
+			},
+		};
+
+		Err(Failure {
+			jsonrpc: version,
+			error: errors::network_unavailable(&method),
+			id: id,
+		}.into())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use jsonrpc_core::{Request, Response, Call, MethodCall, Output, Id, Version, Params};
+	use super::OfflinePolicy;
+
+	fn call(method: &str) -> Call {
+		Call::MethodCall(MethodCall {
+			jsonrpc: Some(Version::V2),
+			method: method.into(),
+			params: Some(Params::Array(vec![])),
+			id: Id::Num(1),
+		})
+	}
+
+	fn is_rejected(result: Result<(), Response>) -> bool {
+		match result {
+			Ok(()) => false,
+			Err(Response::Single(Output::Failure(failure))) => {
+				assert_eq!(failure.error.code.code(), -32093);
+				true
+			},
+			Err(other) => panic!("Unexpected response: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_reject_methods_requiring_network() {
+		// given
+		let policy = OfflinePolicy::network_methods();
+
+		// then
+		assert!(is_rejected(policy.check_request(&Request::Single(call("eth_sendRawTransaction")))));
+		assert!(is_rejected(policy.check_request(&Request::Single(call("parity_addReservedPeer")))));
+		assert!(!is_rejected(policy.check_request(&Request::Single(call("eth_getBalance")))));
+		assert!(!is_rejected(policy.check_request(&Request::Single(call("trace_replayTransaction")))));
+		assert!(policy.check_request(&Request::Batch(vec![call("eth_blockNumber"), call("eth_submitWork")])).is_err());
+		assert!(policy.check_request(&Request::Batch(vec![call("eth_blockNumber"), call("eth_getLogs")])).is_ok());
+	}
+
+	#[test]
+	fn should_allow_everything_by_default() {
+		// given
+		let policy = OfflinePolicy::default();
+
+		// then
+		assert!(policy.is_empty());
+		assert!(!is_rejected(policy.check_request(&Request::Single(call("eth_sendRawTransaction")))));
+	}
+}
@@ -14,23 +14,50 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::ops::Deref;
-use util::Mutex;
+use jsonrpc_core::Error;
+use util::{Mutex, U256, H256};
 use transient_hashmap::TransientHashMap;
 
 use ethstore::random_string;
 
+use v1::helpers::{errors, ConfirmationPayload};
+use v1::helpers::signing_policy::{SigningPolicy, Action};
 use v1::helpers::signing_queue::{ConfirmationsQueue};
 
 const TOKEN_LIFETIME_SECS: u32 = 3600;
 
+/// Outcome of a confirmation of a queued request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Confirmation {
+	/// The confirmation completes the request, it should be signed.
+	Sign,
+	/// The confirmation by the given token has to be verified and counted, it doesn't complete the request.
+	Pending(H256),
+}
+
+/// Confirmations of a request escalated by the signing policy.
+#[derive(Debug, Default)]
+struct Confirmations {
+	required: usize,
+	/// The payload confirmed so far, `None` before the first confirmation.
+	payload: Option<ConfirmationPayload>,
+	/// Authorization tokens which confirmed the payload.
+	tokens: HashSet<H256>,
+}
+
 /// Manages communication with Signer crate
 pub struct SignerService {
 	queue: Arc<ConfirmationsQueue>,
 	web_proxy_tokens: Mutex<TransientHashMap<String, ()>>,
 	generate_new_token: Box<Fn() -> Result<String, String> + Send + Sync + 'static>,
 	address: Option<(String, u16)>,
+	policy: Option<SigningPolicy>,
+	/// Confirmations of requests escalated by the policy.
+	confirmations: Mutex<HashMap<U256, Confirmations>>,
 }
 
 impl SignerService {
@@ -42,6 +69,106 @@ impl SignerService {
 			web_proxy_tokens: Mutex::new(TransientHashMap::new(TOKEN_LIFETIME_SECS)),
 			generate_new_token: Box::new(new_token),
 			address: address,
+			policy: None,
+			confirmations: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Decide signing requests according to given policy before they are queued.
+	pub fn with_policy(mut self, policy: SigningPolicy) -> Self {
+		self.policy = Some(policy);
+		self
+	}
+
+	/// Returns true if signing requests are decided by a policy.
+	pub fn has_policy(&self) -> bool {
+		self.policy.is_some()
+	}
+
+	/// Decides what to do with the signing request, approves everything if there is no policy.
+	/// The value of a request which isn't rejected is reserved until released with `release_decision`.
+	pub fn decide(&self, payload: &ConfirmationPayload) -> Action {
+		match self.policy {
+			Some(ref policy) => policy.decide(payload),
+			None => Action::Approve,
+		}
+	}
+
+	/// Requires given number of confirmations of a queued request, the last one signs it.
+	pub fn require_confirmations(&self, id: U256, confirmations: usize) {
+		if confirmations > 1 {
+			self.confirmations.lock().insert(id, Confirmations {
+				required: confirmations,
+				..Default::default()
+			});
+		}
+	}
+
+	/// Checks a confirmation of the request with given (possibly modified) payload by the
+	/// Trusted Signer authorized with `token`. The policy is evaluated again on the payload,
+	/// confirmations of a differently modified payload don't count.
+	/// Pending confirmations count only once they are verified with `count_confirmation`.
+	/// The decision has to be released with `release_decision` if a completed request fails to be signed.
+	pub fn confirmation(&self, id: U256, payload: &ConfirmationPayload, token: Option<H256>) -> Result<Confirmation, Error> {
+		let required = match self.decide(payload) {
+			Action::Reject => return Err(errors::request_rejected_by_policy()),
+			Action::Approve => 1,
+			Action::Escalate(confirmations) => confirmations,
+		};
+
+		let confirmation = self.check_confirmation(id, payload, token, required);
+		match confirmation {
+			Ok(Confirmation::Sign) => {},
+			_ => self.release_decision(payload),
+		}
+		confirmation
+	}
+
+	fn check_confirmation(&self, id: U256, payload: &ConfirmationPayload, token: Option<H256>, required: usize) -> Result<Confirmation, Error> {
+		let mut confirmations = self.confirmations.lock();
+		if required <= 1 && !confirmations.contains_key(&id) {
+			return Ok(Confirmation::Sign);
+		}
+		let confirmations = confirmations.entry(id).or_insert_with(Default::default);
+		confirmations.required = cmp::max(confirmations.required, required);
+		if confirmations.payload.as_ref().map_or(false, |confirmed| confirmed != payload) {
+			confirmations.tokens.clear();
+		}
+
+		let collected = confirmations.tokens.len();
+		if collected + 1 >= confirmations.required {
+			return Ok(Confirmation::Sign);
+		}
+
+		match token {
+			None => Err(errors::confirmations_from_signer_required(confirmations.required)),
+			Some(ref token) if confirmations.tokens.contains(token) => Err(errors::already_confirmed(confirmations.required - collected)),
+			Some(token) => Ok(Confirmation::Pending(token)),
+		}
+	}
+
+	/// Counts a verified confirmation of the payload by the Trusted Signer authorized with `token`.
+	/// Returns the number of confirmations still missing.
+	pub fn count_confirmation(&self, id: U256, payload: &ConfirmationPayload, token: H256) -> usize {
+		let mut confirmations = self.confirmations.lock();
+		let confirmations = confirmations.entry(id).or_insert_with(Default::default);
+		if confirmations.payload.as_ref() != Some(payload) {
+			confirmations.payload = Some(payload.clone());
+			confirmations.tokens.clear();
+		}
+		confirmations.tokens.insert(token);
+		confirmations.required.saturating_sub(confirmations.tokens.len())
+	}
+
+	/// Forgets the confirmations of a request which is no longer queued.
+	pub fn forget_confirmations(&self, id: &U256) {
+		self.confirmations.lock().remove(id);
+	}
+
+	/// Releases the value reserved by the decision on a request which wasn't signed.
+	pub fn release_decision(&self, payload: &ConfirmationPayload) {
+		if let Some(ref policy) = self.policy {
+			policy.release(payload);
 		}
 	}
 
@@ -0,0 +1,269 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Operator-defined rules approving, rejecting or escalating signing requests
+//! before they are shown in the Trusted Signer.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use util::{Address, U256, Mutex};
+use v1::helpers::ConfirmationPayload;
+
+/// Window of the daily value limits.
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// What to do with a signing request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+	/// Sign right away if the account is unlocked, escalate with a single confirmation otherwise.
+	Approve,
+	/// Reject right away.
+	Reject,
+	/// Wait for the given number of confirmations in the Trusted Signer.
+	Escalate(usize),
+}
+
+impl Default for Action {
+	fn default() -> Self {
+		Action::Escalate(1)
+	}
+}
+
+/// A rule of the signing policy. It matches requests satisfying all of its conditions.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Rule {
+	/// Accounts signing the request (`None` for any).
+	pub from: Option<Vec<Address>>,
+	/// Recipients of the transaction (`None` for any). Contract creations match only `None`.
+	pub to: Option<Vec<Address>>,
+	/// Selectors of the called contract methods (`None` for any). Plain transfers match only `None`.
+	pub methods: Option<Vec<[u8; 4]>>,
+	/// Maximal value of the transaction.
+	pub max_value: Option<U256>,
+	/// Maximal value sent by the account within 24 hours, including the transaction.
+	pub max_daily_value: Option<U256>,
+	/// Action taken on matching requests.
+	pub action: Action,
+}
+
+impl Rule {
+	fn has_transaction_conditions(&self) -> bool {
+		self.to.is_some() || self.methods.is_some() || self.max_value.is_some() || self.max_daily_value.is_some()
+	}
+
+	fn matches(&self, payload: &ConfirmationPayload, sent_today: U256) -> bool {
+		if let Some(ref from) = self.from {
+			if !from.contains(&payload.sender()) {
+				return false;
+			}
+		}
+
+		let request = match *payload {
+			ConfirmationPayload::SendTransaction(ref request) | ConfirmationPayload::SignTransaction(ref request) => request,
+			// messages and decryption are only matched by the sender.
+			_ => return !self.has_transaction_conditions(),
+		};
+
+		let to_allowed = match (self.to.as_ref(), request.to.as_ref()) {
+			(None, _) => true,
+			(Some(to), Some(recipient)) => to.contains(recipient),
+			(Some(_), None) => false,
+		};
+		let method_allowed = match self.methods {
+			None => true,
+			Some(_) if request.data.len() < 4 => false,
+			Some(ref methods) => methods.iter().any(|selector| &request.data[..4] == &selector[..]),
+		};
+		let value_allowed = self.max_value.map_or(true, |max| request.value <= max);
+		let daily_value_allowed = self.max_daily_value.map_or(true, |max| {
+			let (total, overflow) = sent_today.overflowing_add(request.value);
+			!overflow && total <= max
+		});
+
+		to_allowed && method_allowed && value_allowed && daily_value_allowed
+	}
+}
+
+/// Signing policy enforced before requests reach the Trusted Signer.
+/// Rules are checked in order, the first matching one decides.
+#[derive(Debug)]
+pub struct SigningPolicy {
+	rules: Vec<Rule>,
+	default: Action,
+	sent: Mutex<HashMap<Address, VecDeque<(Instant, U256)>>>,
+}
+
+impl SigningPolicy {
+	/// Creates a policy taking the `default` action on requests not matching any rule.
+	pub fn new(rules: Vec<Rule>, default: Action) -> Self {
+		SigningPolicy {
+			rules: rules,
+			default: default,
+			sent: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Decides what to do with the request.
+	/// Unless the request is rejected, its value is reserved towards the daily limits in the same step,
+	/// so concurrent requests can't exceed the limits together. The reservation has to be released
+	/// with `release` if the request doesn't get signed.
+	pub fn decide(&self, payload: &ConfirmationPayload) -> Action {
+		self.decide_at(payload, Instant::now())
+	}
+
+	/// Releases the value reserved by `decide` for a request which wasn't signed.
+	pub fn release(&self, payload: &ConfirmationPayload) {
+		let value = transaction_value(payload);
+		if value.is_zero() {
+			return;
+		}
+
+		let mut sent = self.sent.lock();
+		if let Some(values) = sent.get_mut(&payload.sender()) {
+			if let Some(index) = values.iter().rposition(|&(_, reserved)| reserved == value) {
+				values.remove(index);
+			}
+		}
+	}
+
+	fn decide_at(&self, payload: &ConfirmationPayload, now: Instant) -> Action {
+		let mut sent = self.sent.lock();
+		let values = sent.entry(payload.sender()).or_insert_with(VecDeque::new);
+		let sent_today = sent_today(values, now);
+		let action = self.rules.iter()
+			.find(|rule| rule.matches(payload, sent_today))
+			.map_or(self.default, |rule| rule.action);
+
+		let value = transaction_value(payload);
+		if action != Action::Reject && !value.is_zero() {
+			values.push_back((now, value));
+		}
+		action
+	}
+}
+
+// value of a transaction request, zero for other requests.
+fn transaction_value(payload: &ConfirmationPayload) -> U256 {
+	match *payload {
+		ConfirmationPayload::SendTransaction(ref request) | ConfirmationPayload::SignTransaction(ref request) => request.value,
+		_ => U256::zero(),
+	}
+}
+
+// value sent by the account within the last 24 hours.
+fn sent_today(values: &mut VecDeque<(Instant, U256)>, now: Instant) -> U256 {
+	while values.front().map_or(false, |&(time, _)| now.duration_since(time) >= Duration::from_secs(DAY_SECS)) {
+		values.pop_front();
+	}
+	values.iter().fold(U256::zero(), |total, &(_, value)| match total.overflowing_add(value) {
+		(_, true) => U256::max_value(),
+		(total, false) => total,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use util::{Address, U256};
+	use v1::helpers::{ConfirmationPayload, FilledTransactionRequest};
+	use super::{SigningPolicy, Rule, Action};
+
+	fn transaction(to: Option<Address>, value: u64, data: Vec<u8>) -> ConfirmationPayload {
+		ConfirmationPayload::SendTransaction(FilledTransactionRequest {
+			from: Address::from(1),
+			used_default_from: false,
+			to: to,
+			gas_price: U256::zero(),
+			gas: 21_000.into(),
+			value: value.into(),
+			data: data,
+			nonce: None,
+			condition: None,
+		})
+	}
+
+	#[test]
+	fn should_take_first_matching_rule() {
+		let token = Address::from(10);
+		let policy = SigningPolicy::new(vec![
+			Rule {
+				to: Some(vec![token]),
+				methods: Some(vec![[0xa9, 0x05, 0x9c, 0xbb]]),
+				action: Action::Approve,
+				..Default::default()
+			},
+			Rule {
+				max_value: Some(100.into()),
+				action: Action::Escalate(2),
+				..Default::default()
+			},
+		], Action::Reject);
+
+		assert_eq!(policy.decide(&transaction(Some(token), 0, vec![0xa9, 0x05, 0x9c, 0xbb, 0x00])), Action::Approve);
+		assert_eq!(policy.decide(&transaction(Some(token), 0, vec![0x09, 0x5e, 0xa7, 0xb3])), Action::Escalate(2));
+		assert_eq!(policy.decide(&transaction(Some(Address::from(11)), 0, vec![0xa9, 0x05, 0x9c, 0xbb])), Action::Escalate(2));
+		assert_eq!(policy.decide(&transaction(None, 0, vec![0xa9, 0x05, 0x9c, 0xbb])), Action::Escalate(2));
+		assert_eq!(policy.decide(&transaction(Some(token), 101, vec![])), Action::Reject);
+		assert_eq!(policy.decide(&ConfirmationPayload::EthSignMessage(Address::from(1), vec![])), Action::Reject);
+	}
+
+	#[test]
+	fn should_limit_daily_value() {
+		let policy = SigningPolicy::new(vec![
+			Rule {
+				from: Some(vec![Address::from(1)]),
+				max_value: Some(60.into()),
+				max_daily_value: Some(100.into()),
+				action: Action::Approve,
+				..Default::default()
+			},
+		], Action::Escalate(1));
+		let now = Instant::now();
+
+		assert_eq!(policy.decide_at(&transaction(None, 50, vec![]), now), Action::Approve);
+		assert_eq!(policy.decide_at(&transaction(None, 70, vec![]), now), Action::Escalate(1));
+		policy.release(&transaction(None, 70, vec![]));
+		assert_eq!(policy.decide_at(&transaction(None, 50, vec![]), now), Action::Approve);
+		assert_eq!(policy.decide_at(&transaction(None, 1, vec![]), now), Action::Escalate(1));
+		policy.release(&transaction(None, 1, vec![]));
+
+		// released values don't count
+		policy.release(&transaction(None, 50, vec![]));
+		assert_eq!(policy.decide_at(&transaction(None, 50, vec![]), now), Action::Approve);
+
+		let later = now + Duration::from_secs(24 * 60 * 60);
+		assert_eq!(policy.decide_at(&transaction(None, 60, vec![]), later), Action::Approve);
+		assert_eq!(policy.decide_at(&transaction(None, 40, vec![]), later), Action::Approve);
+		assert_eq!(policy.decide_at(&transaction(None, 1, vec![]), later), Action::Escalate(1));
+	}
+
+	#[test]
+	fn should_reserve_daily_value_of_undecided_requests() {
+		let policy = SigningPolicy::new(vec![
+			Rule {
+				max_daily_value: Some(100.into()),
+				action: Action::Approve,
+				..Default::default()
+			},
+		], Action::Reject);
+
+		// neither request is signed yet, together they exceed the limit.
+		assert_eq!(policy.decide(&transaction(None, 60, vec![])), Action::Approve);
+		assert_eq!(policy.decide(&transaction(None, 60, vec![])), Action::Reject);
+		// rejected requests don't reserve anything.
+		assert_eq!(policy.decide(&transaction(None, 40, vec![])), Action::Approve);
+	}
+}
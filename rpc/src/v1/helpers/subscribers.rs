@@ -0,0 +1,117 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A map of subscribers.
+
+use std::ops;
+use std::collections::HashMap;
+use jsonrpc_macros::pubsub::{Subscriber, Sink};
+use jsonrpc_pubsub::SubscriptionId;
+use util::H64;
+
+/// Subscription identifier (random, so that subscriptions can't be guessed by other sessions).
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct Id(H64);
+
+impl Id {
+	/// Parse subscription id from its string representation.
+	pub fn parse(val: &str) -> Option<Self> {
+		if !val.starts_with("0x") || val.len() != 18 {
+			return None;
+		}
+
+		val[2..].parse().ok().map(Id)
+	}
+
+	/// Hex-encoded representation of the id.
+	pub fn as_string(&self) -> String {
+		format!("0x{}", self.0.hex())
+	}
+}
+
+/// Subscribers collection.
+pub struct Subscribers<T> {
+	subscriptions: HashMap<Id, T>,
+}
+
+impl<T> Default for Subscribers<T> {
+	fn default() -> Self {
+		Subscribers {
+			subscriptions: HashMap::new(),
+		}
+	}
+}
+
+impl<T> Subscribers<T> {
+	/// Insert new subscription and return assigned id.
+	pub fn insert(&mut self, val: T) -> SubscriptionId {
+		let id = Id(H64::random());
+		self.subscriptions.insert(id.clone(), val);
+		SubscriptionId::String(id.as_string())
+	}
+
+	/// Remove subscription with given id.
+	pub fn remove(&mut self, id: &SubscriptionId) -> Option<T> {
+		match *id {
+			SubscriptionId::String(ref id) => match Id::parse(id) {
+				Some(id) => self.subscriptions.remove(&id),
+				None => None,
+			},
+			_ => None,
+		}
+	}
+}
+
+impl<T> Subscribers<Sink<T>> {
+	/// Assign an id to the subscriber and start tracking it.
+	pub fn push(&mut self, sub: Subscriber<T>) {
+		let id = Id(H64::random());
+		if let Ok(sink) = sub.assign_id(SubscriptionId::String(id.as_string())) {
+			self.subscriptions.insert(id, sink);
+		}
+	}
+}
+
+impl<T> ops::Deref for Subscribers<T> {
+	type Target = HashMap<Id, T>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.subscriptions
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use jsonrpc_pubsub::SubscriptionId;
+	use super::{Id, Subscribers};
+
+	#[test]
+	fn should_insert_and_remove_subscriptions() {
+		let mut subscribers = Subscribers::default();
+
+		let id = subscribers.insert(5);
+		assert_eq!(subscribers.len(), 1);
+		match id {
+			SubscriptionId::String(ref id) => assert!(Id::parse(id).is_some()),
+			ref other => panic!("Unexpected id: {:?}", other),
+		}
+
+		assert_eq!(subscribers.remove(&SubscriptionId::String("0x1".into())), None);
+		assert_eq!(subscribers.remove(&SubscriptionId::Number(1)), None);
+		assert_eq!(subscribers.remove(&id), Some(5));
+		assert!(subscribers.is_empty());
+	}
+}
@@ -0,0 +1,96 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-method waking of a sleeping client.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use jsonrpc_core::{Request, Call};
+
+/// Methods which wake the client from passive or dark mode,
+/// together with the time the client stays awake after the last of them.
+#[derive(Debug, Default, Clone)]
+pub struct WakePolicy {
+	idle_timeouts: HashMap<String, Duration>,
+}
+
+impl WakePolicy {
+	/// Wake the client on given methods and keep it awake for `idle` after them.
+	/// A method added more than once keeps the longest timeout.
+	pub fn wake_on<I, S>(&mut self, methods: I, idle: Duration) where
+		I: IntoIterator<Item = S>,
+		S: Into<String>,
+	{
+		for method in methods {
+			let timeout = self.idle_timeouts.entry(method.into()).or_insert(idle);
+			*timeout = cmp::max(*timeout, idle);
+		}
+	}
+
+	/// Returns true if no method wakes the client.
+	pub fn is_empty(&self) -> bool {
+		self.idle_timeouts.is_empty()
+	}
+
+	/// Time the client should stay awake after the request,
+	/// `None` if none of its calls wakes the client.
+	pub fn idle_timeout(&self, request: &Request) -> Option<Duration> {
+		let timeout = |call: &Call| match *call {
+			Call::MethodCall(ref call) => self.idle_timeouts.get(&call.method).cloned(),
+			Call::Notification(ref notification) => self.idle_timeouts.get(&notification.method).cloned(),
+			Call::Invalid(_) => None,
+		};
+
+		match *request {
+			Request::Single(ref call) => timeout(call),
+			Request::Batch(ref calls) => calls.iter().filter_map(timeout).max(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use jsonrpc_core::{Request, Call, MethodCall, Id, Version, Params};
+	use super::WakePolicy;
+
+	fn call(method: &str) -> Call {
+		Call::MethodCall(MethodCall {
+			jsonrpc: Some(Version::V2),
+			method: method.into(),
+			params: Some(Params::Array(vec![])),
+			id: Id::Num(1),
+		})
+	}
+
+	#[test]
+	fn should_return_longest_idle_timeout() {
+		// given
+		let mut policy = WakePolicy::default();
+		policy.wake_on(vec!["eth_getBalance", "eth_call"], Duration::from_secs(60));
+		policy.wake_on(vec!["eth_call", "trace_call"], Duration::from_secs(300));
+
+		// then
+		assert!(!policy.is_empty());
+		assert_eq!(policy.idle_timeout(&Request::Single(call("eth_getBalance"))), Some(Duration::from_secs(60)));
+		assert_eq!(policy.idle_timeout(&Request::Single(call("eth_call"))), Some(Duration::from_secs(300)));
+		assert_eq!(policy.idle_timeout(&Request::Single(call("web3_clientVersion"))), None);
+		assert_eq!(policy.idle_timeout(&Request::Batch(vec![call("web3_clientVersion"), call("eth_getBalance"), call("trace_call")])), Some(Duration::from_secs(300)));
+		assert_eq!(policy.idle_timeout(&Request::Batch(vec![call("web3_clientVersion")])), None);
+	}
+}
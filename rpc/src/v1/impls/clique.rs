@@ -0,0 +1,62 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Clique rpc implementation.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Weak};
+
+use ethcore::miner::MinerService;
+use jsonrpc_core::Error;
+use v1::helpers::errors;
+use v1::traits::Clique;
+use v1::types::H160;
+
+/// Clique rpc implementation.
+pub struct CliqueClient<M> {
+	miner: Weak<M>,
+}
+
+impl<M> CliqueClient<M> {
+	/// Creates new CliqueClient.
+	pub fn new(miner: &Arc<M>) -> Self {
+		CliqueClient {
+			miner: Arc::downgrade(miner),
+		}
+	}
+}
+
+impl<M> Clique for CliqueClient<M> where M: MinerService + 'static {
+	fn propose(&self, signer: H160, authorize: bool) -> Result<bool, Error> {
+		match take_weak!(self.miner).propose_signer(signer.into(), authorize) {
+			true => Ok(true),
+			false => Err(errors::signer_voting_unsupported()),
+		}
+	}
+
+	fn discard(&self, signer: H160) -> Result<bool, Error> {
+		match take_weak!(self.miner).discard_signer(&signer.into()) {
+			true => Ok(true),
+			false => Err(errors::signer_voting_unsupported()),
+		}
+	}
+
+	fn proposals(&self) -> Result<BTreeMap<H160, bool>, Error> {
+		take_weak!(self.miner).signer_proposals()
+			.map(|proposals| proposals.into_iter().map(|(signer, authorize)| (signer.into(), authorize)).collect())
+			.ok_or_else(errors::signer_voting_unsupported)
+	}
+}
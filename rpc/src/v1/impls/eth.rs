@@ -31,6 +31,7 @@ use ethash::SeedHashCompute;
 use ethcore::account_provider::{AccountProvider, DappId};
 use ethcore::block::IsBlock;
 use ethcore::client::{MiningBlockChainClient, BlockId, TransactionId, UncleId};
+use ethcore::encoded;
 use ethcore::ethereum::Ethash;
 use ethcore::filter::Filter as EthcoreFilter;
 use ethcore::header::{Header as BlockHeader, BlockNumber as EthBlockNumber};
@@ -142,9 +143,18 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> EthClient<C, SN, S, M, EM> where
 		unwrap_provider(&self.accounts)
 	}
 
+	/// Fetches the block, failing if it is not served because of the configured block range.
+	fn client_block(&self, id: BlockId) -> Result<Option<encoded::Block>, Error> {
+		let client = take_weak!(self.client);
+		match client.block(id.clone()) {
+			None if client.is_out_of_range(id) => Err(errors::block_out_of_range()),
+			block => Ok(block),
+		}
+	}
+
 	fn block(&self, id: BlockId, include_txs: bool) -> Result<Option<RichBlock>, Error> {
 		let client = take_weak!(self.client);
-		match (client.block(id.clone()), client.block_total_difficulty(id)) {
+		match (self.client_block(id.clone())?, client.block_total_difficulty(id)) {
 			(Some(block), Some(total_difficulty)) => {
 				let view = block.header_view();
 				Ok(Some(RichBlock {
@@ -442,32 +452,32 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> Eth for EthClient<C, SN, S, M, EM> where
 	}
 
 	fn block_transaction_count_by_hash(&self, hash: RpcH256) -> BoxFuture<Option<RpcU256>, Error> {
-		future::ok(take_weakf!(self.client).block(BlockId::Hash(hash.into()))
-			.map(|block| block.transactions_count().into())).boxed()
+		future::done(self.client_block(BlockId::Hash(hash.into()))
+			.map(|block| block.map(|block| block.transactions_count().into()))).boxed()
 	}
 
 	fn block_transaction_count_by_number(&self, num: BlockNumber) -> BoxFuture<Option<RpcU256>, Error> {
-		future::ok(match num {
-			BlockNumber::Pending => Some(
+		future::done(match num {
+			BlockNumber::Pending => Ok(Some(
 				take_weakf!(self.miner).status().transactions_in_pending_block.into()
-			),
+			)),
 			_ =>
-				take_weakf!(self.client).block(num.into())
-					.map(|block| block.transactions_count().into())
+				self.client_block(num.into())
+					.map(|block| block.map(|block| block.transactions_count().into()))
 		}).boxed()
 	}
 
 	fn block_uncles_count_by_hash(&self, hash: RpcH256) -> BoxFuture<Option<RpcU256>, Error> {
-			future::ok(take_weakf!(self.client).block(BlockId::Hash(hash.into()))
-				.map(|block| block.uncles_count().into()))
+			future::done(self.client_block(BlockId::Hash(hash.into()))
+				.map(|block| block.map(|block| block.uncles_count().into())))
 				.boxed()
 	}
 
 	fn block_uncles_count_by_number(&self, num: BlockNumber) -> BoxFuture<Option<RpcU256>, Error> {
-		future::ok(match num {
-			BlockNumber::Pending => Some(0.into()),
-			_ => take_weakf!(self.client).block(num.into())
-					.map(|block| block.uncles_count().into()
+		future::done(match num {
+			BlockNumber::Pending => Ok(Some(0.into())),
+			_ => self.client_block(num.into())
+					.map(|block| block.map(|block| block.uncles_count().into())
 			),
 		}).boxed()
 	}
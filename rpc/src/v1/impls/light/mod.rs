@@ -22,11 +22,13 @@
 pub mod eth;
 pub mod parity;
 pub mod parity_set;
+pub mod pubsub;
 pub mod trace;
 pub mod net;
 
 pub use self::eth::EthClient;
 pub use self::parity::ParityClient;
 pub use self::parity_set::ParitySetClient;
+pub use self::pubsub::PubSubClient;
 pub use self::net::NetClient;
 pub use self::trace::TracesClient;
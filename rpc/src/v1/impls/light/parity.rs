@@ -25,7 +25,7 @@ use util::misc::version_data;
 use crypto::ecies;
 use ethkey::{Brain, Generator};
 use ethstore::random_phrase;
-use ethsync::LightSyncProvider;
+use ethsync::{LightSyncProvider, ManageNetwork};
 use ethcore::account_provider::AccountProvider;
 
 use light::client::LightChainClient;
@@ -34,16 +34,16 @@ use jsonrpc_core::Error;
 use jsonrpc_macros::Trailing;
 use v1::helpers::{errors, ipfs, SigningQueue, SignerService, NetworkSettings};
 use v1::helpers::dispatch::{LightDispatcher, DEFAULT_MAC};
-use v1::helpers::light_fetch::LightFetch;
+use v1::helpers::light_fetch::{LightFetch, rich_header};
 use v1::metadata::Metadata;
 use v1::traits::Parity;
 use v1::types::{
 	Bytes, U256, H160, H256, H512,
-	Peers, Transaction, RpcSettings, Histogram,
-	TransactionStats, LocalTransactionStatus,
+	Peers, NetworkTraffic, Transaction, RpcSettings, Histogram,
+	TransactionStats, LocalTransactionStatus, PoolStatus, PoolTransactionInfo,
 	BlockNumber, ConsensusCapability, VersionInfo,
-	OperationsInfo, DappId, ChainStatus,
-	AccountInfo, HwAccountInfo, Header, RichHeader,
+	OperationsInfo, DappId, ChainStatus, ReorgAlert, ImportStatistics,
+	AccountInfo, HwAccountInfo, RichHeader, PortMapping, TokenTransfer, BalanceChange, AuthorStatistics,
 };
 
 /// Parity implementation for light client.
@@ -143,6 +143,10 @@ impl Parity for ParityClient {
 		Ok(usize::max_value())
 	}
 
+	fn replacement_price_bump(&self) -> Result<u32, Error> {
+		Ok(0)
+	}
+
 	fn min_gas_price(&self) -> Result<U256, Error> {
 		Ok(U256::default())
 	}
@@ -165,7 +169,7 @@ impl Parity for ParityClient {
 	}
 
 	fn dev_logs_levels(&self) -> Result<String, Error> {
-		Ok(self.logger.levels().to_owned())
+		Ok(self.logger.levels())
 	}
 
 	fn net_chain(&self) -> Result<String, Error> {
@@ -175,19 +179,37 @@ impl Parity for ParityClient {
 	fn net_peers(&self) -> Result<Peers, Error> {
 		let peers = self.light_dispatch.sync.peers().into_iter().map(Into::into).collect();
 		let peer_numbers = self.light_dispatch.sync.peer_numbers();
+		let net_config = self.light_dispatch.sync.network_config();
 
 		Ok(Peers {
 			active: peer_numbers.active,
 			connected: peer_numbers.connected,
 			max: peer_numbers.max as u32,
+			max_per_ip: net_config.max_peers_per_ip,
+			max_per_subnet: net_config.max_peers_per_subnet,
+			ip_limited: self.light_dispatch.sync.ip_limited_peers(),
 			peers: peers,
 		})
 	}
 
+	fn net_traffic(&self) -> Result<NetworkTraffic, Error> {
+		let sync = &self.light_dispatch.sync;
+		let traffic = sync.traffic();
+		Ok(NetworkTraffic {
+			received: traffic.received,
+			sent: traffic.sent,
+			protocols: sync.protocol_traffic().into_iter().map(|(p, t)| (p, t.into())).collect(),
+		})
+	}
+
 	fn net_port(&self) -> Result<u16, Error> {
 		Ok(self.settings.network_port)
 	}
 
+	fn net_port_mapping(&self) -> Result<Option<PortMapping>, Error> {
+		Ok(self.light_dispatch.sync.port_mapping().map(Into::into))
+	}
+
 	fn node_name(&self) -> Result<String, Error> {
 		Ok(self.settings.name.clone())
 	}
@@ -196,6 +218,10 @@ impl Parity for ParityClient {
 		Err(errors::light_unimplemented(None))
 	}
 
+	fn engine_params(&self) -> Result<BTreeMap<String, String>, Error> {
+		Err(errors::light_unimplemented(None))
+	}
+
 	fn rpc_settings(&self) -> Result<RpcSettings, Error> {
 		Ok(RpcSettings {
 			enabled: self.settings.rpc_enabled,
@@ -293,6 +319,14 @@ impl Parity for ParityClient {
 		Ok(map)
 	}
 
+	fn pool_status(&self) -> Result<PoolStatus, Error> {
+		Err(errors::light_unimplemented(None))
+	}
+
+	fn pool_transaction_info(&self, _hash: H256) -> Result<Option<PoolTransactionInfo>, Error> {
+		Err(errors::light_unimplemented(None))
+	}
+
 	fn signer_port(&self) -> Result<u16, Error> {
 		self.signer
 			.clone()
@@ -350,6 +384,31 @@ impl Parity for ParityClient {
 		})
 	}
 
+	fn reorg_alert(&self) -> Result<Option<ReorgAlert>, Error> {
+		// light client doesn't limit reorganisation depth.
+		Ok(None)
+	}
+
+	fn import_statistics(&self) -> Result<ImportStatistics, Error> {
+		Err(errors::light_unimplemented(None))
+	}
+
+	fn token_transfers(&self, _token: H160, _holder: H160, _from: BlockNumber, _to: BlockNumber) -> Result<Vec<TokenTransfer>, Error> {
+		Err(errors::light_unimplemented(None))
+	}
+
+	fn token_balance(&self, _token: H160, _holder: H160, _block: Trailing<BlockNumber>) -> Result<U256, Error> {
+		Err(errors::light_unimplemented(None))
+	}
+
+	fn balance_history(&self, _address: H160, _from: BlockNumber, _to: BlockNumber) -> Result<Vec<BalanceChange>, Error> {
+		Err(errors::light_unimplemented(None))
+	}
+
+	fn author_statistics(&self, _from: BlockNumber, _to: BlockNumber) -> Result<Vec<AuthorStatistics>, Error> {
+		Err(errors::light_unimplemented(None))
+	}
+
 	fn node_kind(&self) -> Result<::v1::types::NodeKind, Error> {
 		use ::v1::types::{NodeKind, Availability, Capability};
 
@@ -360,37 +419,10 @@ impl Parity for ParityClient {
 	}
 
 	fn block_header(&self, number: Trailing<BlockNumber>) -> BoxFuture<Option<RichHeader>, Error> {
-		use ethcore::encoded;
-
 		let engine = self.light_dispatch.client.engine().clone();
-		let from_encoded = move |encoded: encoded::Header| {
-			let header = encoded.decode();
-			let extra_info = engine.extra_info(&header);
-			RichHeader {
-				inner: Header {
-					hash: Some(header.hash().into()),
-					size: Some(encoded.rlp().as_raw().len().into()),
-					parent_hash: header.parent_hash().clone().into(),
-					uncles_hash: header.uncles_hash().clone().into(),
-					author: header.author().clone().into(),
-					miner: header.author().clone().into(),
-					state_root: header.state_root().clone().into(),
-					transactions_root: header.transactions_root().clone().into(),
-					receipts_root: header.receipts_root().clone().into(),
-					number: Some(header.number().into()),
-					gas_used: header.gas_used().clone().into(),
-					gas_limit: header.gas_limit().clone().into(),
-					logs_bloom: header.log_bloom().clone().into(),
-					timestamp: header.timestamp().into(),
-					difficulty: header.difficulty().clone().into(),
-					seal_fields: header.seal().iter().cloned().map(Into::into).collect(),
-					extra_data: Bytes::new(header.extra_data().clone()),
-				},
-				extra_info: extra_info,
-			}
-		};
-
-		self.fetcher().header(number.0.into()).map(move |encoded| encoded.map(from_encoded)).boxed()
+		self.fetcher().header(number.0.into())
+			.map(move |encoded| encoded.map(|encoded| rich_header(&**engine, encoded)))
+			.boxed()
 	}
 
 	fn ipfs_cid(&self, content: Bytes) -> Result<String, Error> {
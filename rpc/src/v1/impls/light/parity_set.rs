@@ -19,28 +19,33 @@
 
 use std::io;
 use std::sync::Arc;
+use std::time::Duration;
 
+use ethcore_logger::RotatingLogger;
 use ethsync::ManageNetwork;
 use fetch::Fetch;
 use futures::{BoxFuture, Future};
 use util::sha3;
 
 use jsonrpc_core::Error;
+use jsonrpc_macros::Trailing;
 use v1::helpers::errors;
 use v1::traits::ParitySet;
-use v1::types::{Bytes, H160, H256, U256, ReleaseInfo, Transaction};
+use v1::types::{Bytes, H160, H256, U256, ReleaseInfo, Transaction, SnapshotManifest};
 
 /// Parity-specific rpc interface for operations altering the settings.
 pub struct ParitySetClient<F> {
 	net: Arc<ManageNetwork>,
+	logger: Arc<RotatingLogger>,
 	fetch: F,
 }
 
 impl<F: Fetch> ParitySetClient<F> {
 	/// Creates new `ParitySetClient` with given `Fetch`.
-	pub fn new(net: Arc<ManageNetwork>, fetch: F) -> Self {
+	pub fn new(net: Arc<ManageNetwork>, logger: Arc<RotatingLogger>, fetch: F) -> Self {
 		ParitySetClient {
 			net: net,
+			logger: logger,
 			fetch: fetch,
 		}
 	}
@@ -71,10 +76,18 @@ impl<F: Fetch> ParitySet for ParitySetClient<F> {
 		Err(errors::light_unimplemented(None))
 	}
 
+	fn set_engine_signer_secure(&self, _address: H160, _password: String) -> Result<bool, Error> {
+		Err(errors::light_unimplemented(None))
+	}
+
 	fn set_transactions_limit(&self, _limit: usize) -> Result<bool, Error> {
 		Err(errors::light_unimplemented(None))
 	}
 
+	fn set_replacement_price_bump(&self, _percent: u32) -> Result<bool, Error> {
+		Err(errors::light_unimplemented(None))
+	}
+
 	fn set_tx_gas_limit(&self, _limit: U256) -> Result<bool, Error> {
 		Err(errors::light_unimplemented(None))
 	}
@@ -143,4 +156,29 @@ impl<F: Fetch> ParitySet for ParitySetClient<F> {
 	fn remove_transaction(&self, _hash: H256) -> Result<Option<Transaction>, Error> {
 		Err(errors::light_unimplemented(None))
 	}
+
+	fn acknowledge_reorg(&self) -> Result<bool, Error> {
+		Err(errors::light_unimplemented(None))
+	}
+
+	fn set_logging_targets(&self, targets: String, duration: Trailing<u64>) -> Result<bool, Error> {
+		let duration = match duration.0 {
+			0 => None,
+			secs => Some(Duration::from_secs(secs)),
+		};
+		self.logger.set_levels(&targets, duration).map_err(|e| errors::invalid_params("targets", e))?;
+		Ok(true)
+	}
+
+	fn logging_targets(&self) -> Result<String, Error> {
+		Ok(self.logger.levels())
+	}
+
+	fn snapshot_manifest(&self) -> Result<Option<SnapshotManifest>, Error> {
+		Err(errors::light_unimplemented(None))
+	}
+
+	fn snapshot_chunk(&self, _hash: H256) -> Result<Option<Bytes>, Error> {
+		Err(errors::light_unimplemented(None))
+	}
 }
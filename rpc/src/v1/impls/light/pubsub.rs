@@ -0,0 +1,256 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Eth PUB-SUB rpc implementation for the light client.
+
+use std::collections::HashSet;
+use std::mem;
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
+
+use futures::{self, BoxFuture, Future};
+use jsonrpc_core::Error;
+use jsonrpc_macros::pubsub::{Sink, Subscriber};
+use jsonrpc_pubsub::SubscriptionId;
+
+use ethcore::encoded;
+use ethcore::ids::BlockId;
+use ethsync::LightSync;
+use light::TransactionQueue;
+use light::client::LightChainClient;
+use parity_reactor::Remote;
+use util::{self, Mutex, RwLock};
+
+use v1::helpers::{errors, Subscribers};
+use v1::helpers::light_fetch::rich_header;
+use v1::metadata::Metadata;
+use v1::traits::EthPubSub;
+use v1::types::{pubsub, PubSubSyncStatus, SyncInfo, U256};
+
+/// How often the chain head and the transaction queue are sampled.
+const SAMPLE_INTERVAL_MS: u64 = 1000;
+/// Maximal number of headers announced at once, e.g. after catching up with the network.
+const MAX_HEADERS: usize = 64;
+
+/// Eth PubSub implementation for the light client.
+#[derive(Clone)]
+pub struct PubSubClient {
+	handler: Arc<ChainHandler>,
+}
+
+impl PubSubClient {
+	/// Creates new `PubSubClient` and spawns a thread sampling the chain head, the sync status and the transaction queue.
+	pub fn new(client: Arc<LightChainClient>, sync: Arc<LightSync>, transaction_queue: Arc<RwLock<TransactionQueue>>, remote: Remote) -> Self {
+		let handler = Arc::new(ChainHandler {
+			client: Arc::downgrade(&client),
+			sync: Arc::downgrade(&sync),
+			transaction_queue: Arc::downgrade(&transaction_queue),
+			remote: remote,
+			heads_subscribers: Default::default(),
+			syncing_subscribers: Default::default(),
+			transactions_subscribers: Default::default(),
+			last_head: Mutex::new(None),
+			last_syncing: Mutex::new(None),
+			known_transactions: Mutex::new(None),
+		});
+
+		let weak = Arc::downgrade(&handler);
+		let spawned = thread::Builder::new().name("pubsub-light".into()).spawn(move || loop {
+			thread::sleep(Duration::from_millis(SAMPLE_INTERVAL_MS));
+			match weak.upgrade() {
+				Some(handler) => handler.tick(),
+				None => break,
+			}
+		});
+		if let Err(e) = spawned {
+			warn!(target: "rpc", "Unable to spawn chain head sampling thread: {}", e);
+		}
+
+		PubSubClient {
+			handler: handler,
+		}
+	}
+}
+
+/// Samples the chain head, the sync status and the transaction queue and notifies subscribers.
+struct ChainHandler {
+	client: Weak<LightChainClient>,
+	sync: Weak<LightSync>,
+	transaction_queue: Weak<RwLock<TransactionQueue>>,
+	remote: Remote,
+	heads_subscribers: RwLock<Subscribers<Sink<pubsub::Result>>>,
+	syncing_subscribers: RwLock<Subscribers<Sink<pubsub::Result>>>,
+	transactions_subscribers: RwLock<Subscribers<Sink<pubsub::Result>>>,
+	/// Hash and number of the last announced head (`None` until sampled with subscribers present).
+	last_head: Mutex<Option<(util::H256, u64)>>,
+	/// Syncing flag sent in the last notification (`None` forces a notification).
+	last_syncing: Mutex<Option<bool>>,
+	/// Transactions queued when last sampled (`None` until sampled with subscribers present).
+	known_transactions: Mutex<Option<HashSet<util::H256>>>,
+}
+
+impl ChainHandler {
+	fn tick(&self) {
+		if self.heads_subscribers.read().is_empty() {
+			*self.last_head.lock() = None;
+		} else if let Some(client) = self.client.upgrade() {
+			let info = client.chain_info();
+			let last = mem::replace(&mut *self.last_head.lock(), Some((info.best_block_hash, info.best_block_number)));
+			if let Some(last) = last {
+				for header in new_heads(&*client, info.best_block_hash, last) {
+					let header = pubsub::Result::Header(rich_header(&**client.engine(), header));
+					for subscriber in self.heads_subscribers.read().values() {
+						self.notify(subscriber, header.clone());
+					}
+				}
+			}
+		}
+
+		if !self.syncing_subscribers.read().is_empty() {
+			if let (Some(client), Some(sync)) = (self.client.upgrade(), self.sync.upgrade()) {
+				let syncing = sync.is_major_importing();
+				let changed = mem::replace(&mut *self.last_syncing.lock(), Some(syncing)) != Some(syncing);
+				if changed {
+					let status = pubsub::Result::Syncing(PubSubSyncStatus {
+						syncing: syncing,
+						status: if syncing { Some(sync_info(&*client, &*sync)) } else { None },
+					});
+					for subscriber in self.syncing_subscribers.read().values() {
+						self.notify(subscriber, status.clone());
+					}
+				}
+			}
+		}
+
+		if self.transactions_subscribers.read().is_empty() {
+			*self.known_transactions.lock() = None;
+		} else if let Some(transaction_queue) = self.transaction_queue.upgrade() {
+			let queued = transaction_queue.read().transaction_hashes();
+			for hash in new_transactions(&mut *self.known_transactions.lock(), queued) {
+				let hash = pubsub::Result::TransactionHash(hash.into());
+				for subscriber in self.transactions_subscribers.read().values() {
+					self.notify(subscriber, hash.clone());
+				}
+			}
+		}
+	}
+
+	fn notify(&self, subscriber: &Sink<pubsub::Result>, result: pubsub::Result) {
+		self.remote.spawn(subscriber
+			.notify(Ok(result))
+			.map(|_| ())
+			.map_err(|e| warn!(target: "rpc", "Unable to send notification: {}", e))
+		);
+	}
+}
+
+fn sync_info(client: &LightChainClient, sync: &LightSync) -> SyncInfo {
+	let current_block = client.chain_info().best_block_number;
+	SyncInfo {
+		starting_block: U256::from(sync.start_block()),
+		current_block: U256::from(current_block),
+		highest_block: U256::from(sync.highest_block().unwrap_or(current_block)),
+		warp_chunks_amount: None,
+		warp_chunks_processed: None,
+	}
+}
+
+/// Headers from the one following the last announced head up to the best block, oldest first.
+/// After a reorganisation the new best block is announced even if it's not higher.
+fn new_heads(client: &LightChainClient, best_hash: util::H256, last: (util::H256, u64)) -> Vec<encoded::Header> {
+	let (last_hash, last_number) = last;
+	if best_hash == last_hash {
+		return Vec::new();
+	}
+
+	let mut headers = Vec::new();
+	let mut hash = best_hash;
+	while headers.len() < MAX_HEADERS {
+		let header = match client.block_header(BlockId::Hash(hash)) {
+			Some(header) => header,
+			None => break,
+		};
+		if header.number() <= last_number && !headers.is_empty() {
+			break;
+		}
+
+		hash = header.parent_hash();
+		headers.push(header);
+		if hash == last_hash {
+			break;
+		}
+	}
+
+	headers.reverse();
+	headers
+}
+
+/// Hashes of queued transactions which weren't known when last sampled.
+/// Nothing is reported on the first sample.
+fn new_transactions(known: &mut Option<HashSet<util::H256>>, queued: Vec<util::H256>) -> Vec<util::H256> {
+	let queued: HashSet<_> = queued.into_iter().collect();
+	match mem::replace(known, Some(queued.clone())) {
+		Some(known) => queued.difference(&known).cloned().collect(),
+		None => Vec::new(),
+	}
+}
+
+impl EthPubSub for PubSubClient {
+	type Metadata = Metadata;
+
+	fn subscribe(&self, _meta: Metadata, subscriber: Subscriber<pubsub::Result>, kind: pubsub::Kind) {
+		match kind {
+			pubsub::Kind::NewHeads => {
+				self.handler.heads_subscribers.write().push(subscriber);
+			},
+			pubsub::Kind::NewPendingTransactions => {
+				self.handler.transactions_subscribers.write().push(subscriber);
+			},
+			pubsub::Kind::Syncing => {
+				self.handler.syncing_subscribers.write().push(subscriber);
+				// make sure the new subscriber learns the current status
+				*self.handler.last_syncing.lock() = None;
+			},
+			_ => {
+				let _ = subscriber.reject(errors::light_unimplemented(None));
+			},
+		}
+	}
+
+	fn unsubscribe(&self, id: SubscriptionId) -> BoxFuture<bool, Error> {
+		let res = self.handler.heads_subscribers.write().remove(&id).is_some()
+			|| self.handler.syncing_subscribers.write().remove(&id).is_some()
+			|| self.handler.transactions_subscribers.write().remove(&id).is_some();
+		futures::future::ok(res).boxed()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::H256;
+	use super::new_transactions;
+
+	#[test]
+	fn should_report_only_new_transactions() {
+		let mut known = None;
+
+		assert!(new_transactions(&mut known, vec![H256::from(1)]).is_empty());
+		assert_eq!(new_transactions(&mut known, vec![H256::from(1), H256::from(2)]), vec![H256::from(2)]);
+		assert!(new_transactions(&mut known, vec![H256::from(2)]).is_empty());
+		assert_eq!(new_transactions(&mut known, vec![H256::from(1), H256::from(2)]), vec![H256::from(1)]);
+	}
+}
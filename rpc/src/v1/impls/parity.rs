@@ -44,13 +44,22 @@ use v1::metadata::Metadata;
 use v1::traits::Parity;
 use v1::types::{
 	Bytes, U256, H160, H256, H512,
-	Peers, Transaction, RpcSettings, Histogram,
-	TransactionStats, LocalTransactionStatus,
+	Peers, NetworkTraffic, Transaction, RpcSettings, Histogram,
+	TransactionStats, LocalTransactionStatus, PoolStatus, PoolTransactionInfo,
 	BlockNumber, ConsensusCapability, VersionInfo,
-	OperationsInfo, DappId, ChainStatus,
-	AccountInfo, HwAccountInfo, Header, RichHeader
+	OperationsInfo, DappId, ChainStatus, ReorgAlert, ImportStatistics,
+	AccountInfo, HwAccountInfo, Header, RichHeader, PortMapping, TokenTransfer, BalanceChange, AuthorStatistics,
 };
 
+/// Maximal number of transfers returned by `parity_tokenTransfers`.
+const TOKEN_TRANSFERS_LIMIT: usize = 10_000;
+
+/// Maximal number of changes returned by `parity_balanceHistory`.
+const BALANCE_CHANGES_LIMIT: usize = 10_000;
+
+/// Maximal number of blocks covered by `parity_authorStatistics`.
+const AUTHOR_STATISTICS_RANGE_LIMIT: u64 = 100_000;
+
 /// Parity implementation.
 pub struct ParityClient<C, M, S: ?Sized, U> where
 	C: MiningBlockChainClient,
@@ -115,6 +124,16 @@ impl<C, M, S: ?Sized, U> ParityClient<C, M, S, U> where
 	}
 }
 
+/// Fails unless the token transfer index is enabled and covers the given block.
+fn ensure_token_index_at<C: MiningBlockChainClient>(client: &C, block: BlockId) -> Result<(), Error> {
+	let head = client.token_index_head().ok_or_else(errors::token_index_disabled)?;
+	match client.block_number(block) {
+		Some(number) if number > head => Err(errors::token_index_behind(head)),
+		Some(_) => Ok(()),
+		None => Err(errors::unknown_block()),
+	}
+}
+
 impl<C, M, S: ?Sized, U> Parity for ParityClient<C, M, S, U> where
 	M: MinerService + 'static,
 	C: MiningBlockChainClient + 'static,
@@ -170,6 +189,10 @@ impl<C, M, S: ?Sized, U> Parity for ParityClient<C, M, S, U> where
 		Ok(take_weak!(self.miner).transactions_limit())
 	}
 
+	fn replacement_price_bump(&self) -> Result<u32, Error> {
+		Ok(take_weak!(self.miner).replacement_price_bump())
+	}
+
 	fn min_gas_price(&self) -> Result<U256, Error> {
 		Ok(U256::from(take_weak!(self.miner).minimal_gas_price()))
 	}
@@ -192,7 +215,7 @@ impl<C, M, S: ?Sized, U> Parity for ParityClient<C, M, S, U> where
 	}
 
 	fn dev_logs_levels(&self) -> Result<String, Error> {
-		Ok(self.logger.levels().to_owned())
+		Ok(self.logger.levels())
 	}
 
 	fn net_chain(&self) -> Result<String, Error> {
@@ -206,21 +229,39 @@ impl<C, M, S: ?Sized, U> Parity for ParityClient<C, M, S, U> where
 	fn net_peers(&self) -> Result<Peers, Error> {
 		let sync = take_weak!(self.sync);
 		let sync_status = sync.status();
-		let net_config = take_weak!(self.net).network_config();
+		let net = take_weak!(self.net);
+		let net_config = net.network_config();
 		let peers = sync.peers().into_iter().map(Into::into).collect();
 
 		Ok(Peers {
 			active: sync_status.num_active_peers,
 			connected: sync_status.num_peers,
 			max: sync_status.current_max_peers(net_config.min_peers, net_config.max_peers),
+			max_per_ip: net_config.max_peers_per_ip,
+			max_per_subnet: net_config.max_peers_per_subnet,
+			ip_limited: net.ip_limited_peers(),
 			peers: peers
 		})
 	}
 
+	fn net_traffic(&self) -> Result<NetworkTraffic, Error> {
+		let net = take_weak!(self.net);
+		let traffic = net.traffic();
+		Ok(NetworkTraffic {
+			received: traffic.received,
+			sent: traffic.sent,
+			protocols: net.protocol_traffic().into_iter().map(|(p, t)| (p, t.into())).collect(),
+		})
+	}
+
 	fn net_port(&self) -> Result<u16, Error> {
 		Ok(self.settings.network_port)
 	}
 
+	fn net_port_mapping(&self) -> Result<Option<PortMapping>, Error> {
+		Ok(take_weak!(self.net).port_mapping().map(Into::into))
+	}
+
 	fn node_name(&self) -> Result<String, Error> {
 		Ok(self.settings.name.clone())
 	}
@@ -235,6 +276,10 @@ impl<C, M, S: ?Sized, U> Parity for ParityClient<C, M, S, U> where
 		)
 	}
 
+	fn engine_params(&self) -> Result<BTreeMap<String, String>, Error> {
+		Ok(take_weak!(self.client).additional_params())
+	}
+
 	fn rpc_settings(&self) -> Result<RpcSettings, Error> {
 		Ok(RpcSettings {
 			enabled: self.settings.rpc_enabled,
@@ -317,6 +362,14 @@ impl<C, M, S: ?Sized, U> Parity for ParityClient<C, M, S, U> where
 		)
 	}
 
+	fn pool_status(&self) -> Result<PoolStatus, Error> {
+		Ok(take_weak!(self.miner).pool_status().into())
+	}
+
+	fn pool_transaction_info(&self, hash: H256) -> Result<Option<PoolTransactionInfo>, Error> {
+		Ok(take_weak!(self.miner).pool_transaction_info(&hash.into()).map(Into::into))
+	}
+
 	fn signer_port(&self) -> Result<u16, Error> {
 		self.signer
 			.clone()
@@ -386,6 +439,68 @@ impl<C, M, S: ?Sized, U> Parity for ParityClient<C, M, S, U> where
 		})
 	}
 
+	fn reorg_alert(&self) -> Result<Option<ReorgAlert>, Error> {
+		Ok(take_weak!(self.client).reorg_alert().map(Into::into))
+	}
+
+	fn import_statistics(&self) -> Result<ImportStatistics, Error> {
+		Ok(take_weak!(self.client).import_statistics().into())
+	}
+
+	fn token_transfers(&self, token: H160, holder: H160, from: BlockNumber, to: BlockNumber) -> Result<Vec<TokenTransfer>, Error> {
+		let client = take_weak!(self.client);
+		let to: BlockId = to.into();
+		ensure_token_index_at(&*client, to)?;
+
+		let transfers = client.token_transfers(&token.into(), &holder.into(), from.into(), to, TOKEN_TRANSFERS_LIMIT + 1)
+			.ok_or_else(errors::unknown_block)?;
+		if transfers.len() > TOKEN_TRANSFERS_LIMIT {
+			return Err(errors::too_many_token_transfers(TOKEN_TRANSFERS_LIMIT));
+		}
+		Ok(transfers.into_iter().map(Into::into).collect())
+	}
+
+	fn token_balance(&self, token: H160, holder: H160, block: Trailing<BlockNumber>) -> Result<U256, Error> {
+		let client = take_weak!(self.client);
+		let block: BlockId = block.0.into();
+		ensure_token_index_at(&*client, block)?;
+
+		client.token_balance(&token.into(), &holder.into(), block)
+			.map(Into::into)
+			.ok_or_else(errors::unknown_block)
+	}
+
+	fn balance_history(&self, address: H160, from: BlockNumber, to: BlockNumber) -> Result<Vec<BalanceChange>, Error> {
+		let client = take_weak!(self.client);
+		let (from, to): (BlockId, BlockId) = (from.into(), to.into());
+		if client.block_number(from).is_none() || client.block_number(to).is_none() {
+			return Err(errors::unknown_block());
+		}
+
+		let changes = client.balance_history(&address.into(), from, to, BALANCE_CHANGES_LIMIT + 1)
+			.ok_or_else(errors::balance_history_disabled)?;
+		if changes.len() > BALANCE_CHANGES_LIMIT {
+			return Err(errors::too_many_balance_changes(BALANCE_CHANGES_LIMIT));
+		}
+		Ok(changes.into_iter().map(Into::into).collect())
+	}
+
+	fn author_statistics(&self, from: BlockNumber, to: BlockNumber) -> Result<Vec<AuthorStatistics>, Error> {
+		let client = take_weak!(self.client);
+		let (from, to): (BlockId, BlockId) = (from.into(), to.into());
+		let (first, last) = match (client.block_number(from), client.block_number(to)) {
+			(Some(first), Some(last)) => (first, last),
+			_ => return Err(errors::unknown_block()),
+		};
+		if last >= first && last - first >= AUTHOR_STATISTICS_RANGE_LIMIT {
+			return Err(errors::block_range_too_large(AUTHOR_STATISTICS_RANGE_LIMIT));
+		}
+
+		client.author_statistics(from, to)
+			.map(|statistics| statistics.into_iter().map(Into::into).collect())
+			.ok_or_else(errors::unknown_block)
+	}
+
 	fn node_kind(&self) -> Result<::v1::types::NodeKind, Error> {
 		use ::v1::types::{NodeKind, Availability, Capability};
 
@@ -407,6 +522,7 @@ impl<C, M, S: ?Sized, U> Parity for ParityClient<C, M, S, U> where
 		let id: BlockId = number.0.into();
 		let encoded = match client.block_header(id.clone()) {
 			Some(encoded) => encoded,
+			None if client.is_out_of_range(id) => return future::err(errors::block_out_of_range()).boxed(),
 			None => return future::ok(None).boxed(),
 		};
 
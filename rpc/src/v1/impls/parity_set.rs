@@ -17,10 +17,13 @@
 /// Parity-specific rpc interface for operations altering the settings.
 use std::io;
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 
 use ethcore::miner::MinerService;
 use ethcore::client::MiningBlockChainClient;
 use ethcore::mode::Mode;
+use ethcore::snapshot::SnapshotService;
+use ethcore_logger::RotatingLogger;
 use ethsync::ManageNetwork;
 use fetch::{self, Fetch};
 use futures::{BoxFuture, Future};
@@ -28,9 +31,10 @@ use util::sha3;
 use updater::{Service as UpdateService};
 
 use jsonrpc_core::Error;
+use jsonrpc_macros::Trailing;
 use v1::helpers::errors;
 use v1::traits::ParitySet;
-use v1::types::{Bytes, H160, H256, U256, ReleaseInfo, Transaction};
+use v1::types::{Bytes, H160, H256, U256, ReleaseInfo, Transaction, SnapshotManifest};
 
 /// Parity-specific rpc interface for operations altering the settings.
 pub struct ParitySetClient<C, M, U, F = fetch::Client> {
@@ -38,6 +42,8 @@ pub struct ParitySetClient<C, M, U, F = fetch::Client> {
 	miner: Weak<M>,
 	updater: Weak<U>,
 	net: Weak<ManageNetwork>,
+	snapshot: Weak<SnapshotService>,
+	logger: Arc<RotatingLogger>,
 	fetch: F,
 	eip86_transition: u64,
 }
@@ -46,12 +52,14 @@ impl<C, M, U, F> ParitySetClient<C, M, U, F>
 	where C: MiningBlockChainClient + 'static,
 {
 	/// Creates new `ParitySetClient` with given `Fetch`.
-	pub fn new(client: &Arc<C>, miner: &Arc<M>, updater: &Arc<U>, net: &Arc<ManageNetwork>, fetch: F) -> Self {
+	pub fn new(client: &Arc<C>, miner: &Arc<M>, updater: &Arc<U>, net: &Arc<ManageNetwork>, snapshot: &Arc<SnapshotService>, logger: Arc<RotatingLogger>, fetch: F) -> Self {
 		ParitySetClient {
 			client: Arc::downgrade(client),
 			miner: Arc::downgrade(miner),
 			updater: Arc::downgrade(updater),
 			net: Arc::downgrade(net),
+			snapshot: Arc::downgrade(snapshot),
+			logger: logger,
 			fetch: fetch,
 			eip86_transition: client.eip86_transition(),
 		}
@@ -95,11 +103,21 @@ impl<C, M, U, F> ParitySet for ParitySetClient<C, M, U, F> where
 		Ok(true)
 	}
 
+	fn set_engine_signer_secure(&self, address: H160, password: String) -> Result<bool, Error> {
+		take_weak!(self.miner).set_engine_signer_secure(address.into(), password).map_err(Into::into).map_err(errors::from_password_error)?;
+		Ok(true)
+	}
+
 	fn set_transactions_limit(&self, limit: usize) -> Result<bool, Error> {
 		take_weak!(self.miner).set_transactions_limit(limit);
 		Ok(true)
 	}
 
+	fn set_replacement_price_bump(&self, percent: u32) -> Result<bool, Error> {
+		take_weak!(self.miner).set_replacement_price_bump(percent);
+		Ok(true)
+	}
+
 	fn set_tx_gas_limit(&self, limit: U256) -> Result<bool, Error> {
 		take_weak!(self.miner).set_tx_gas_limit(limit.into());
 		Ok(true)
@@ -184,4 +202,36 @@ impl<C, M, U, F> ParitySet for ParitySetClient<C, M, U, F> where
 
 		Ok(miner.remove_pending_transaction(&*client, &hash).map(|t| Transaction::from_pending(t, block_number, self.eip86_transition)))
 	}
+
+	fn acknowledge_reorg(&self) -> Result<bool, Error> {
+		let client = take_weak!(self.client);
+		match client.reorg_alert() {
+			Some(_) => {
+				client.acknowledge_reorg();
+				Ok(true)
+			},
+			None => Ok(false),
+		}
+	}
+
+	fn set_logging_targets(&self, targets: String, duration: Trailing<u64>) -> Result<bool, Error> {
+		let duration = match duration.0 {
+			0 => None,
+			secs => Some(Duration::from_secs(secs)),
+		};
+		self.logger.set_levels(&targets, duration).map_err(|e| errors::invalid_params("targets", e))?;
+		Ok(true)
+	}
+
+	fn logging_targets(&self) -> Result<String, Error> {
+		Ok(self.logger.levels())
+	}
+
+	fn snapshot_manifest(&self) -> Result<Option<SnapshotManifest>, Error> {
+		Ok(take_weak!(self.snapshot).manifest().map(Into::into))
+	}
+
+	fn snapshot_chunk(&self, hash: H256) -> Result<Option<Bytes>, Error> {
+		Ok(take_weak!(self.snapshot).chunk(hash.into()).map(Into::into))
+	}
 }
@@ -0,0 +1,425 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Eth and Parity PUB-SUB rpc implementation.
+
+use std::mem;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::sync::{Arc, Weak};
+
+use futures::{self, BoxFuture, Future};
+use jsonrpc_core::Error;
+use jsonrpc_macros::pubsub::{Sink, Subscriber};
+use jsonrpc_pubsub::SubscriptionId;
+
+use ethcore::client::BlockChainClient;
+use ethcore::snapshot::{SnapshotService, RestorationStatus};
+use ethsync::{SyncProvider, SyncState};
+use parity_reactor::Remote;
+use util::{self, Mutex, RwLock};
+
+use v1::helpers::{errors, Subscribers};
+use v1::helpers::block_import::is_major_importing;
+use v1::metadata::Metadata;
+use v1::traits::{EthPubSub, ParityPubSub};
+use v1::types::{pubsub, PubSubSyncStatus, SyncInfo, SyncProgress, SyncStage, H256, U256};
+
+/// How often the sync status is sampled.
+const SAMPLE_INTERVAL_MS: u64 = 1000;
+
+/// Eth and Parity PubSub implementation.
+pub struct PubSubClient<C, S: ?Sized, SN: ?Sized> where
+	C: BlockChainClient,
+	S: SyncProvider,
+	SN: SnapshotService,
+{
+	handler: Arc<SyncHandler<C, S, SN>>,
+	evicted_subscribers: Arc<RwLock<Subscribers<Sink<Vec<H256>>>>>,
+}
+
+impl<C, S: ?Sized, SN: ?Sized> PubSubClient<C, S, SN> where
+	C: BlockChainClient + 'static,
+	S: SyncProvider + 'static,
+	SN: SnapshotService + 'static,
+{
+	/// Creates new `PubSubClient` and spawns a thread sampling the sync status.
+	pub fn new(client: &Arc<C>, sync: &Arc<S>, snapshot: &Arc<SN>, remote: Remote) -> Self {
+		let handler = Arc::new(SyncHandler {
+			client: Arc::downgrade(client),
+			sync: Arc::downgrade(sync),
+			snapshot: Arc::downgrade(snapshot),
+			remote: remote,
+			syncing_subscribers: Default::default(),
+			progress_subscribers: Default::default(),
+			last_syncing: Mutex::new(None),
+			last_sample: Mutex::new(None),
+		});
+
+		let weak = Arc::downgrade(&handler);
+		let spawned = thread::Builder::new().name("pubsub-sync".into()).spawn(move || loop {
+			thread::sleep(Duration::from_millis(SAMPLE_INTERVAL_MS));
+			match weak.upgrade() {
+				Some(handler) => handler.tick(),
+				None => break,
+			}
+		});
+		if let Err(e) = spawned {
+			warn!(target: "rpc", "Unable to spawn sync status sampling thread: {}", e);
+		}
+
+		PubSubClient {
+			handler: handler,
+			evicted_subscribers: Default::default(),
+		}
+	}
+
+	/// Returns a listener notifying subscribers about transactions evicted from the queue.
+	pub fn eviction_listener(&self) -> Box<Fn(&[util::H256]) + Send + Sync> {
+		let subscribers = Arc::downgrade(&self.evicted_subscribers);
+		let remote = self.handler.remote.clone();
+		Box::new(move |hashes| {
+			let subscribers = match subscribers.upgrade() {
+				Some(subscribers) => subscribers,
+				None => return,
+			};
+			let hashes = hashes.iter().cloned().map(Into::into).collect::<Vec<H256>>();
+			for subscriber in subscribers.read().values() {
+				notify(&remote, subscriber, hashes.clone());
+			}
+		})
+	}
+}
+
+impl<C, S: ?Sized, SN: ?Sized> Clone for PubSubClient<C, S, SN> where
+	C: BlockChainClient,
+	S: SyncProvider,
+	SN: SnapshotService,
+{
+	fn clone(&self) -> Self {
+		PubSubClient {
+			handler: self.handler.clone(),
+			evicted_subscribers: self.evicted_subscribers.clone(),
+		}
+	}
+}
+
+/// A single sample of the sync status.
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+	time: Instant,
+	stage: SyncStage,
+	syncing: bool,
+	starting_block: u64,
+	current_block: u64,
+	highest_block: u64,
+	ancient_block: Option<u64>,
+	/// Processed and total number of snapshot chunks.
+	chunks: Option<(u64, u64)>,
+}
+
+impl Sample {
+	fn sync_info(&self) -> SyncInfo {
+		SyncInfo {
+			starting_block: U256::from(self.starting_block),
+			current_block: U256::from(self.current_block),
+			highest_block: U256::from(self.highest_block),
+			warp_chunks_amount: self.chunks.map(|(_, total)| U256::from(total)),
+			warp_chunks_processed: self.chunks.map(|(done, _)| U256::from(done)),
+		}
+	}
+}
+
+fn stage(state: SyncState, restoration: &RestorationStatus, major_importing: bool, ancient: bool) -> SyncStage {
+	match *restoration {
+		RestorationStatus::Ongoing { state_chunks, state_chunks_done, .. } if state_chunks_done < state_chunks => SyncStage::StateChunks,
+		RestorationStatus::Ongoing { .. } => SyncStage::BlockChunks,
+		_ => match state {
+			SyncState::WaitingPeers => SyncStage::WaitingPeers,
+			SyncState::SnapshotManifest => SyncStage::SnapshotManifest,
+			SyncState::SnapshotData | SyncState::SnapshotWaiting => SyncStage::StateChunks,
+			_ if major_importing => SyncStage::Blocks,
+			_ if ancient => SyncStage::AncientBlocks,
+			_ => SyncStage::Idle,
+		},
+	}
+}
+
+/// Computes rates and ETA of the current sample compared to the previous one.
+fn progress(previous: Option<&Sample>, current: &Sample) -> SyncProgress {
+	let rate = |prev: u64, cur: u64, elapsed: f64| if elapsed > 0.0 && cur > prev {
+		(cur - prev) as f64 / elapsed
+	} else {
+		0.0
+	};
+
+	let (blocks_per_second, chunks_per_second) = match previous {
+		Some(previous) if previous.time < current.time => {
+			let elapsed = current.time.duration_since(previous.time);
+			let elapsed = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000.0;
+			let blocks = match (current.stage, previous.ancient_block, current.ancient_block) {
+				(SyncStage::AncientBlocks, Some(prev), Some(cur)) => rate(prev, cur, elapsed),
+				_ => rate(previous.current_block, current.current_block, elapsed),
+			};
+			let chunks = match (previous.chunks, current.chunks) {
+				(Some((prev, _)), Some((cur, _))) => rate(prev, cur, elapsed),
+				_ => 0.0,
+			};
+			(blocks, chunks)
+		},
+		_ => (0.0, 0.0),
+	};
+
+	let eta = |remaining: u64, rate: f64| if rate > 0.0 {
+		Some((remaining as f64 / rate).ceil() as u64)
+	} else {
+		None
+	};
+
+	let eta = match (current.stage, current.chunks) {
+		(SyncStage::StateChunks, Some((done, total))) | (SyncStage::BlockChunks, Some((done, total))) =>
+			eta(total.saturating_sub(done), chunks_per_second),
+		(SyncStage::Blocks, _) => eta(current.highest_block.saturating_sub(current.current_block), blocks_per_second),
+		_ => None,
+	};
+
+	SyncProgress {
+		stage: current.stage,
+		starting_block: current.starting_block.into(),
+		current_block: current.current_block.into(),
+		highest_block: current.highest_block.into(),
+		current_ancient_block: current.ancient_block.map(Into::into),
+		warp_chunks_amount: current.chunks.map(|(_, total)| total.into()),
+		warp_chunks_processed: current.chunks.map(|(done, _)| done.into()),
+		blocks_per_second: blocks_per_second,
+		chunks_per_second: chunks_per_second,
+		eta: eta,
+	}
+}
+
+/// Samples the sync status and notifies subscribers.
+struct SyncHandler<C, S: ?Sized, SN: ?Sized> {
+	client: Weak<C>,
+	sync: Weak<S>,
+	snapshot: Weak<SN>,
+	remote: Remote,
+	syncing_subscribers: RwLock<Subscribers<Sink<pubsub::Result>>>,
+	progress_subscribers: RwLock<Subscribers<Sink<SyncProgress>>>,
+	/// Syncing flag sent in the last notification (`None` forces a notification).
+	last_syncing: Mutex<Option<bool>>,
+	last_sample: Mutex<Option<Sample>>,
+}
+
+impl<C, S: ?Sized, SN: ?Sized> SyncHandler<C, S, SN> where
+	C: BlockChainClient,
+	S: SyncProvider,
+	SN: SnapshotService,
+{
+	fn sample(&self) -> Option<Sample> {
+		let (client, sync, snapshot) = match (self.client.upgrade(), self.sync.upgrade(), self.snapshot.upgrade()) {
+			(Some(client), Some(sync), Some(snapshot)) => (client, sync, snapshot),
+			_ => return None,
+		};
+
+		let status = sync.status();
+		let restoration = snapshot.status();
+		let chunks = match restoration {
+			RestorationStatus::Ongoing { state_chunks, block_chunks, state_chunks_done, block_chunks_done } =>
+				Some(((state_chunks_done + block_chunks_done) as u64, (state_chunks + block_chunks) as u64)),
+			_ => None,
+		};
+		let major_importing = is_major_importing(Some(status.state), client.queue_info());
+		let ancient_block = status.last_imported_old_block_number;
+
+		Some(Sample {
+			time: Instant::now(),
+			stage: stage(status.state, &restoration, major_importing, ancient_block.is_some()),
+			syncing: chunks.is_some() || major_importing,
+			starting_block: status.start_block_number,
+			current_block: client.chain_info().best_block_number,
+			highest_block: status.highest_block_number.unwrap_or(status.start_block_number),
+			ancient_block: ancient_block,
+			chunks: chunks,
+		})
+	}
+
+	fn tick(&self) {
+		if self.syncing_subscribers.read().is_empty() && self.progress_subscribers.read().is_empty() {
+			*self.last_sample.lock() = None;
+			return;
+		}
+
+		let sample = match self.sample() {
+			Some(sample) => sample,
+			None => return,
+		};
+		let previous = mem::replace(&mut *self.last_sample.lock(), Some(sample.clone()));
+
+		let notify_syncing = {
+			let mut last_syncing = self.last_syncing.lock();
+			let changed = *last_syncing != Some(sample.syncing);
+			*last_syncing = Some(sample.syncing);
+			changed
+		};
+		if notify_syncing {
+			let status = PubSubSyncStatus {
+				syncing: sample.syncing,
+				status: if sample.syncing { Some(sample.sync_info()) } else { None },
+			};
+			for subscriber in self.syncing_subscribers.read().values() {
+				self.notify(subscriber, pubsub::Result::Syncing(status.clone()));
+			}
+		}
+
+		let was_idle = previous.as_ref().map_or(false, |previous| previous.stage == SyncStage::Idle);
+		if sample.stage != SyncStage::Idle || !was_idle {
+			let progress = progress(previous.as_ref(), &sample);
+			for subscriber in self.progress_subscribers.read().values() {
+				self.notify(subscriber, progress.clone());
+			}
+		}
+	}
+
+	fn notify<T: ::serde::Serialize>(&self, subscriber: &Sink<T>, result: T) {
+		notify(&self.remote, subscriber, result)
+	}
+}
+
+fn notify<T: ::serde::Serialize>(remote: &Remote, subscriber: &Sink<T>, result: T) {
+	remote.spawn(subscriber
+		.notify(Ok(result))
+		.map(|_| ())
+		.map_err(|e| warn!(target: "rpc", "Unable to send notification: {}", e))
+	);
+}
+
+impl<C, S: ?Sized, SN: ?Sized> EthPubSub for PubSubClient<C, S, SN> where
+	C: BlockChainClient + 'static,
+	S: SyncProvider + 'static,
+	SN: SnapshotService + 'static,
+{
+	type Metadata = Metadata;
+
+	fn subscribe(&self, _meta: Metadata, subscriber: Subscriber<pubsub::Result>, kind: pubsub::Kind) {
+		match kind {
+			pubsub::Kind::Syncing => {
+				self.handler.syncing_subscribers.write().push(subscriber);
+				// make sure the new subscriber learns the current status
+				*self.handler.last_syncing.lock() = None;
+			},
+			_ => {
+				let _ = subscriber.reject(errors::unimplemented(None));
+			},
+		}
+	}
+
+	fn unsubscribe(&self, id: SubscriptionId) -> BoxFuture<bool, Error> {
+		// any subscription may be cancelled, whichever method created it.
+		let res = self.handler.syncing_subscribers.write().remove(&id).is_some()
+			|| self.handler.progress_subscribers.write().remove(&id).is_some()
+			|| self.evicted_subscribers.write().remove(&id).is_some();
+		futures::future::ok(res).boxed()
+	}
+}
+
+impl<C, S: ?Sized, SN: ?Sized> ParityPubSub for PubSubClient<C, S, SN> where
+	C: BlockChainClient + 'static,
+	S: SyncProvider + 'static,
+	SN: SnapshotService + 'static,
+{
+	type Metadata = Metadata;
+
+	fn subscribe_sync_progress(&self, _meta: Metadata, subscriber: Subscriber<SyncProgress>) {
+		self.handler.progress_subscribers.write().push(subscriber);
+	}
+
+	fn unsubscribe_sync_progress(&self, id: SubscriptionId) -> BoxFuture<bool, Error> {
+		let res = self.handler.progress_subscribers.write().remove(&id).is_some();
+		futures::future::ok(res).boxed()
+	}
+
+	fn subscribe_evicted_transactions(&self, _meta: Metadata, subscriber: Subscriber<Vec<H256>>) {
+		self.evicted_subscribers.write().push(subscriber);
+	}
+
+	fn unsubscribe_evicted_transactions(&self, id: SubscriptionId) -> BoxFuture<bool, Error> {
+		let res = self.evicted_subscribers.write().remove(&id).is_some();
+		futures::future::ok(res).boxed()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use ethcore::snapshot::RestorationStatus;
+	use ethsync::SyncState;
+	use v1::types::SyncStage;
+	use super::{Sample, stage, progress};
+
+	fn sample(time: Instant, stage: SyncStage, current_block: u64, chunks: Option<(u64, u64)>) -> Sample {
+		Sample {
+			time: time,
+			stage: stage,
+			syncing: true,
+			starting_block: 0,
+			current_block: current_block,
+			highest_block: 1000,
+			ancient_block: None,
+			chunks: chunks,
+		}
+	}
+
+	#[test]
+	fn should_determine_stage() {
+		let restoring = |done| RestorationStatus::Ongoing { state_chunks: 10, block_chunks: 5, state_chunks_done: done, block_chunks_done: 0 };
+
+		assert_eq!(stage(SyncState::SnapshotWaiting, &restoring(3), false, false), SyncStage::StateChunks);
+		assert_eq!(stage(SyncState::SnapshotWaiting, &restoring(10), false, false), SyncStage::BlockChunks);
+		assert_eq!(stage(SyncState::WaitingPeers, &RestorationStatus::Inactive, false, false), SyncStage::WaitingPeers);
+		assert_eq!(stage(SyncState::SnapshotManifest, &RestorationStatus::Inactive, false, false), SyncStage::SnapshotManifest);
+		assert_eq!(stage(SyncState::Blocks, &RestorationStatus::Inactive, true, true), SyncStage::Blocks);
+		assert_eq!(stage(SyncState::Idle, &RestorationStatus::Inactive, false, true), SyncStage::AncientBlocks);
+		assert_eq!(stage(SyncState::Idle, &RestorationStatus::Inactive, false, false), SyncStage::Idle);
+	}
+
+	#[test]
+	fn should_compute_block_rate_and_eta() {
+		let now = Instant::now();
+		let previous = sample(now, SyncStage::Blocks, 100, None);
+		let current = sample(now + Duration::from_secs(2), SyncStage::Blocks, 200, None);
+
+		let first = progress(None, &previous);
+		assert_eq!(first.blocks_per_second, 0.0);
+		assert_eq!(first.eta, None);
+
+		let progress = progress(Some(&previous), &current);
+		assert_eq!(progress.blocks_per_second, 50.0);
+		assert_eq!(progress.eta, Some(16));
+	}
+
+	#[test]
+	fn should_compute_chunk_rate_and_eta() {
+		let now = Instant::now();
+		let previous = sample(now, SyncStage::StateChunks, 0, Some((10, 100)));
+		let current = sample(now + Duration::from_secs(4), SyncStage::StateChunks, 0, Some((30, 100)));
+
+		let progress = progress(Some(&previous), &current);
+		assert_eq!(progress.chunks_per_second, 5.0);
+		assert_eq!(progress.blocks_per_second, 0.0);
+		assert_eq!(progress.eta, Some(14));
+		assert_eq!(progress.warp_chunks_processed, Some(30.into()));
+	}
+}
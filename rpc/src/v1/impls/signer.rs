@@ -19,17 +19,20 @@
 use std::sync::{Arc, Weak};
 
 use rlp::UntrustedRlp;
-use ethcore::account_provider::AccountProvider;
+use ethcore::account_provider::{AccountProvider, SignError as AccountError};
 use ethcore::transaction::{SignedTransaction, PendingTransaction};
 use ethkey;
+use ethstore::Error as SSError;
 use futures::{future, BoxFuture, Future, IntoFuture};
+use util::H256;
 
 use jsonrpc_core::Error;
 use v1::helpers::accounts::unwrap_provider;
 use v1::helpers::dispatch::{self, Dispatcher, WithToken, eth_data_hash};
-use v1::helpers::{errors, SignerService, SigningQueue, ConfirmationPayload, FilledTransactionRequest};
+use v1::helpers::{errors, SignerService, SigningQueue, Confirmation, ConfirmationPayload, FilledTransactionRequest};
+use v1::metadata::Metadata;
 use v1::traits::Signer;
-use v1::types::{TransactionModification, ConfirmationRequest, ConfirmationResponse, ConfirmationResponseWithToken, U256, Bytes};
+use v1::types::{TransactionModification, ConfirmationRequest, ConfirmationResponse, ConfirmationResponseWithToken, U256, Bytes, Origin};
 
 /// Transactions confirmation (personal) rpc implementation.
 pub struct SignerClient<D: Dispatcher> {
@@ -57,7 +60,8 @@ impl<D: Dispatcher + 'static> SignerClient<D> {
 		unwrap_provider(&self.accounts)
 	}
 
-	fn confirm_internal<F, T>(&self, id: U256, modification: TransactionModification, f: F) -> BoxFuture<WithToken<ConfirmationResponse>, Error> where
+	fn confirm_internal<V, F, T>(&self, meta: Metadata, id: U256, modification: TransactionModification, verify: V, f: F) -> BoxFuture<WithToken<ConfirmationResponse>, Error> where
+		V: FnOnce(&AccountProvider, &ConfirmationPayload) -> Result<(), Error>,
 		F: FnOnce(D, Arc<AccountProvider>, ConfirmationPayload) -> T,
 		T: IntoFuture<Item=WithToken<ConfirmationResponse>, Error=Error>,
 		T::Future: Send + 'static
@@ -93,11 +97,28 @@ impl<D: Dispatcher + 'static> SignerClient<D> {
 					request.condition = condition.clone().map(Into::into);
 				}
 			}
+
+			// the policy decides on the modified request.
+			match signer.confirmation(id, &payload, signer_token(&meta)) {
+				Ok(Confirmation::Sign) => {},
+				Ok(Confirmation::Pending(token)) => {
+					let counted = verify(&*accounts, &payload)
+						.and_then(|_| Err(errors::confirmations_required(signer.count_confirmation(id, &payload, token))));
+					return future::done(counted).boxed();
+				},
+				Err(e) => return future::err(e).boxed(),
+			}
+
+			let signed = payload.clone();
 			let fut = f(dispatcher, accounts, payload);
 			fut.into_future().then(move |result| {
 				// Execute
-				if let Ok(ref response) = result {
-					signer.request_confirmed(id, Ok((*response).clone()));
+				match result {
+					Ok(ref response) => {
+						signer.request_confirmed(id, Ok((*response).clone()));
+						signer.forget_confirmations(&id);
+					},
+					Err(_) => signer.release_decision(&signed),
 				}
 
 				result
@@ -106,6 +127,36 @@ impl<D: Dispatcher + 'static> SignerClient<D> {
 		.unwrap_or_else(|| future::err(errors::invalid_params("Unknown RequestID", id)).boxed())
 	}
 
+	// verifies the signed data of the request, dispatching it only if `dispatch` is set.
+	fn confirm_raw(&self, payload: ConfirmationPayload, bytes: Bytes, dispatch: bool) -> Result<ConfirmationResponse, Error> {
+		match payload {
+			ConfirmationPayload::SendTransaction(request) => {
+				Self::verify_transaction(bytes, request, |pending_transaction| match dispatch {
+					true => self.dispatcher.dispatch_transaction(pending_transaction)
+						.map(Into::into)
+						.map(ConfirmationResponse::SendTransaction),
+					false => Ok(ConfirmationResponse::SendTransaction(pending_transaction.transaction.hash().into())),
+				})
+			},
+			ConfirmationPayload::SignTransaction(request) => {
+				Self::verify_transaction(bytes, request, |pending_transaction| {
+					Ok(ConfirmationResponse::SignTransaction(pending_transaction.transaction.into()))
+				})
+			},
+			ConfirmationPayload::EthSignMessage(address, data) => {
+				let expected_hash = eth_data_hash(data);
+				let signature = ethkey::Signature::from_vrs(&bytes.0);
+				match ethkey::verify_address(&address, &signature, &expected_hash) {
+					Ok(true) => Ok(ConfirmationResponse::Signature(bytes.0.as_slice().into())),
+					Ok(false) => Err(errors::invalid_params("Sender address does not match the signature.", ())),
+					Err(err) => Err(errors::invalid_params("Invalid signature received.", err)),
+				}
+			},
+			// TODO [ToDr]: Decrypt - pass through?
+			_ => Err(errors::unimplemented(Some("Non-transaction requests does not support RAW signing yet.".into()))),
+		}
+	}
+
 	fn verify_transaction<F>(bytes: Bytes, request: FilledTransactionRequest, process: F) -> Result<ConfirmationResponse, Error> where
 		F: FnOnce(PendingTransaction) -> Result<ConfirmationResponse, Error>,
 	{
@@ -138,7 +189,25 @@ impl<D: Dispatcher + 'static> SignerClient<D> {
 	}
 }
 
+// identity of the Trusted Signer token the call was authorized with.
+fn signer_token(meta: &Metadata) -> Option<H256> {
+	match meta.origin {
+		Origin::Signer(ref token) => Some(token.clone().into()),
+		_ => None,
+	}
+}
+
+// checks the password of the request sender without signing anything.
+fn verify_password(accounts: &AccountProvider, payload: &ConfirmationPayload, pass: &str) -> Result<(), Error> {
+	match accounts.test_password(&payload.sender(), pass) {
+		Ok(true) => Ok(()),
+		Ok(false) => Err(errors::from_password_error(AccountError::SStore(SSError::InvalidPassword))),
+		Err(e) => Err(errors::from_password_error(AccountError::SStore(e))),
+	}
+}
+
 impl<D: Dispatcher + 'static> Signer for SignerClient<D> {
+	type Metadata = Metadata;
 
 	fn requests_to_confirm(&self) -> Result<Vec<ConfirmationRequest>, Error> {
 		let signer = take_weak!(self.signer);
@@ -152,18 +221,23 @@ impl<D: Dispatcher + 'static> Signer for SignerClient<D> {
 
 	// TODO [ToDr] TransactionModification is redundant for some calls
 	// might be better to replace it in future
-	fn confirm_request(&self, id: U256, modification: TransactionModification, pass: String)
+	fn confirm_request(&self, meta: Metadata, id: U256, modification: TransactionModification, pass: String)
 		-> BoxFuture<ConfirmationResponse, Error>
 	{
-		self.confirm_internal(id, modification, move |dis, accounts, payload| {
+		let verify_pass = pass.clone();
+		self.confirm_internal(meta, id, modification, move |accounts, payload| {
+			verify_password(accounts, payload, &verify_pass)
+		}, move |dis, accounts, payload| {
 			dispatch::execute(dis, accounts, payload, dispatch::SignWith::Password(pass))
 		}).map(|v| v.into_value()).boxed()
 	}
 
-	fn confirm_request_with_token(&self, id: U256, modification: TransactionModification, token: String)
+	fn confirm_request_with_token(&self, meta: Metadata, id: U256, modification: TransactionModification, token: String)
 		-> BoxFuture<ConfirmationResponseWithToken, Error>
 	{
-		self.confirm_internal(id, modification, move |dis, accounts, payload| {
+		self.confirm_internal(meta, id, modification, |_, _| {
+			Err(errors::unimplemented(Some("Requests escalated by the signing policy have to be confirmed with a password or a signature.".into())))
+		}, move |dis, accounts, payload| {
 			dispatch::execute(dis, accounts, payload, dispatch::SignWith::Token(token))
 		}).and_then(|v| match v {
 			WithToken::No(_) => Err(errors::internal("Unexpected response without token.", "")),
@@ -174,47 +248,40 @@ impl<D: Dispatcher + 'static> Signer for SignerClient<D> {
 		}).boxed()
 	}
 
-	fn confirm_request_raw(&self, id: U256, bytes: Bytes) -> Result<ConfirmationResponse, Error> {
+	fn confirm_request_raw(&self, meta: Metadata, id: U256, bytes: Bytes) -> BoxFuture<ConfirmationResponse, Error> {
 		let id = id.into();
-		let signer = take_weak!(self.signer);
-
-		signer.peek(&id).map(|confirmation| {
-			let result = match confirmation.payload {
-				ConfirmationPayload::SendTransaction(request) => {
-					Self::verify_transaction(bytes, request, |pending_transaction| {
-						self.dispatcher.dispatch_transaction(pending_transaction)
-							.map(Into::into)
-							.map(ConfirmationResponse::SendTransaction)
-					})
+		let signer = take_weakf!(self.signer);
+
+		let result = signer.peek(&id).map(|confirmation| {
+			let payload = confirmation.payload;
+			match signer.confirmation(id, &payload, signer_token(&meta))? {
+				Confirmation::Sign => {},
+				Confirmation::Pending(token) => {
+					self.confirm_raw(payload.clone(), bytes, false)?;
+					return Err(errors::confirmations_required(signer.count_confirmation(id, &payload, token)));
 				},
-				ConfirmationPayload::SignTransaction(request) => {
-					Self::verify_transaction(bytes, request, |pending_transaction| {
-						Ok(ConfirmationResponse::SignTransaction(pending_transaction.transaction.into()))
-					})
-				},
-				ConfirmationPayload::EthSignMessage(address, data) => {
-					let expected_hash = eth_data_hash(data);
-					let signature = ethkey::Signature::from_vrs(&bytes.0);
-					match ethkey::verify_address(&address, &signature, &expected_hash) {
-						Ok(true) => Ok(ConfirmationResponse::Signature(bytes.0.as_slice().into())),
-						Ok(false) => Err(errors::invalid_params("Sender address does not match the signature.", ())),
-						Err(err) => Err(errors::invalid_params("Invalid signature received.", err)),
-					}
+			}
+
+			let result = self.confirm_raw(payload.clone(), bytes, true);
+			match result {
+				Ok(ref response) => {
+					signer.request_confirmed(id, Ok(response.clone()));
+					signer.forget_confirmations(&id);
 				},
-				// TODO [ToDr]: Decrypt - pass through?
-				_ => Err(errors::unimplemented(Some("Non-transaction requests does not support RAW signing yet.".into()))),
-			};
-			if let Ok(ref response) = result {
-				signer.request_confirmed(id, Ok(response.clone()));
+				Err(_) => signer.release_decision(&payload),
 			}
 			result
-		}).unwrap_or_else(|| Err(errors::invalid_params("Unknown RequestID", id)))
+		}).unwrap_or_else(|| Err(errors::invalid_params("Unknown RequestID", id)));
+
+		future::done(result).boxed()
 	}
 
 	fn reject_request(&self, id: U256) -> Result<bool, Error> {
 		let signer = take_weak!(self.signer);
 
-		let res = signer.request_rejected(id.into());
+		let id = id.into();
+		let res = signer.request_rejected(id);
+		signer.forget_confirmations(&id);
 		Ok(res.is_some())
 	}
 
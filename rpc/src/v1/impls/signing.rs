@@ -31,6 +31,7 @@ use v1::helpers::{
 };
 use v1::helpers::dispatch::{self, Dispatcher};
 use v1::helpers::accounts::unwrap_provider;
+use v1::helpers::signing_policy::Action;
 use v1::metadata::Metadata;
 use v1::traits::{EthSigning, ParitySigning};
 use v1::types::{
@@ -117,18 +118,33 @@ impl<D: Dispatcher + 'static> SigningQueueClient<D> {
 		dispatch::from_rpc(payload, default_account, &dispatcher)
 			.and_then(move |payload| {
 				let sender = payload.sender();
-				if accounts.is_unlocked(sender) {
-					dispatch::execute(dispatcher, accounts, payload, dispatch::SignWith::Nothing)
-						.map(|v| v.into_value())
-						.map(DispatchResult::Value)
-						.boxed()
-				} else {
-					future::done(
-						signer.add_request(payload, origin)
-							.map(DispatchResult::Promise)
-							.map_err(|_| errors::request_rejected_limit())
-					).boxed()
-				}
+				let confirmations = match signer.decide(&payload) {
+					Action::Reject => return future::err(errors::request_rejected_by_policy()).boxed(),
+					Action::Approve if accounts.is_unlocked(sender) => {
+						let signed = payload.clone();
+						return dispatch::execute(dispatcher, accounts, payload, dispatch::SignWith::Nothing)
+							.then(move |result| {
+								if result.is_err() {
+									signer.release_decision(&signed);
+								}
+								result.map(|v| DispatchResult::Value(v.into_value()))
+							})
+							.boxed();
+					},
+					Action::Approve => 1,
+					Action::Escalate(confirmations) => confirmations,
+				};
+				// queued requests are decided again once confirmed.
+				signer.release_decision(&payload);
+
+				future::done(
+					signer.add_request(payload, origin)
+						.map(|promise| {
+							signer.require_confirmations(promise.id(), confirmations);
+							DispatchResult::Promise(promise)
+						})
+						.map_err(|_| errors::request_rejected_limit())
+				).boxed()
 			})
 			.boxed()
 	}
@@ -14,15 +14,22 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::sync::Arc;
+
 use jsonrpc_core;
+use jsonrpc_pubsub::{Session, PubSubMetadata};
 
 use v1::types::{DappId, Origin};
 
 /// RPC methods metadata.
-#[derive(Clone, Default, Debug, PartialEq)]
+#[derive(Clone, Default, Debug)]
 pub struct Metadata {
 	/// Request origin
 	pub origin: Origin,
+	/// Value of the `Host` header (HTTP requests only)
+	pub host: Option<String>,
+	/// Request PubSub Session (WebSockets only)
+	pub session: Option<Arc<Session>>,
 }
 
 impl Metadata {
@@ -37,3 +44,9 @@ impl Metadata {
 
 impl jsonrpc_core::Metadata for Metadata {}
 
+impl PubSubMetadata for Metadata {
+	fn session(&self) -> Option<Arc<Session>> {
+		self.session.clone()
+	}
+}
+
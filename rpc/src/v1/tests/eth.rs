@@ -24,7 +24,7 @@ use ethcore::spec::{Genesis, Spec};
 use ethcore::block::Block;
 use ethcore::views::BlockView;
 use ethcore::ethereum;
-use ethcore::miner::{MinerOptions, Banning, GasPricer, MinerService, ExternalMiner, Miner, PendingSet, PrioritizationStrategy, GasLimit};
+use ethcore::miner::{MinerOptions, Banning, GasPricer, MinerService, ExternalMiner, Miner, PendingSet, PrioritizationStrategy, LocalsPolicy, GasLimit};
 use ethcore::account_provider::AccountProvider;
 use ethjson::blockchain::BlockChain;
 use io::IoChannel;
@@ -58,8 +58,18 @@ fn miner_service(spec: &Spec, accounts: Arc<AccountProvider>) -> Arc<Miner> {
 			reseal_on_external_tx: true,
 			reseal_on_own_tx: true,
 			tx_queue_size: 1024,
+			tx_queue_future_size: None,
+			tx_queue_per_sender: None,
+			tx_queue_future_per_sender: None,
+			tx_queue_max_nonce_gap: None,
+			tx_queue_future_memory: None,
+			tx_queue_replacement_bump: 0,
+			tx_queue_pending_lifetime: 128,
+			tx_queue_future_lifetime: 128,
+			tx_queue_remember_evicted: false,
 			tx_gas_limit: !U256::zero(),
 			tx_queue_strategy: PrioritizationStrategy::GasPriceOnly,
+			tx_queue_locals: LocalsPolicy::Prioritize,
 			tx_queue_gas_limit: GasLimit::None,
 			tx_queue_banning: Banning::Disabled,
 			pending_set: PendingSet::SealingOrElseQueue,
@@ -68,6 +78,11 @@ fn miner_service(spec: &Spec, accounts: Arc<AccountProvider>) -> Arc<Miner> {
 			work_queue_size: 50,
 			enable_resubmission: true,
 			refuse_service_transactions: false,
+			zero_gas_price_senders: Vec::new(),
+			zero_gas_price_certifier: None,
+			tx_filter_list: Default::default(),
+			tx_filter_contract: None,
+			standby: None,
 		},
 		GasPricer::new_fixed(20_000_000_000u64.into()),
 		&spec,
@@ -25,7 +25,7 @@ use ethcore::block::{ClosedBlock, IsBlock};
 use ethcore::header::BlockNumber;
 use ethcore::transaction::{UnverifiedTransaction, SignedTransaction, PendingTransaction};
 use ethcore::receipt::{Receipt, RichReceipt};
-use ethcore::miner::{MinerService, MinerStatus, TransactionImportResult, LocalTransactionStatus};
+use ethcore::miner::{MinerService, MinerStatus, TransactionImportResult, LocalTransactionStatus, PoolStatus, TransactionInfo};
 use ethcore::account_provider::SignError as AccountError;
 
 /// Test miner service.
@@ -40,16 +40,21 @@ pub struct TestMinerService {
 	pub local_transactions: Mutex<BTreeMap<H256, LocalTransactionStatus>>,
 	/// Pre-existed pending receipts
 	pub pending_receipts: Mutex<BTreeMap<H256, Receipt>>,
+	/// Pre-existed states of transactions in the pool.
+	pub pool_transactions: Mutex<HashMap<H256, TransactionInfo>>,
 	/// Last nonces.
 	pub last_nonces: RwLock<HashMap<Address, U256>>,
 	/// Password held by Engine.
 	pub password: RwLock<String>,
+	/// Votes on signers, `None` if the engine doesn't vote.
+	pub signer_proposals: RwLock<Option<BTreeMap<Address, bool>>>,
 
 	min_gas_price: RwLock<U256>,
 	gas_range_target: RwLock<(U256, U256)>,
 	author: RwLock<Address>,
 	extra_data: RwLock<Bytes>,
 	limit: RwLock<usize>,
+	replacement_bump: RwLock<u32>,
 	tx_gas_limit: RwLock<U256>,
 }
 
@@ -61,13 +66,16 @@ impl Default for TestMinerService {
 			pending_transactions: Mutex::new(HashMap::new()),
 			local_transactions: Mutex::new(BTreeMap::new()),
 			pending_receipts: Mutex::new(BTreeMap::new()),
+			pool_transactions: Mutex::new(HashMap::new()),
 			last_nonces: RwLock::new(HashMap::new()),
 			min_gas_price: RwLock::new(U256::from(20_000_000)),
 			gas_range_target: RwLock::new((U256::from(12345), U256::from(54321))),
 			author: RwLock::new(Address::zero()),
 			password: RwLock::new(String::new()),
+			signer_proposals: RwLock::new(None),
 			extra_data: RwLock::new(vec![1, 2, 3, 4]),
 			limit: RwLock::new(1024),
+			replacement_bump: RwLock::new(0),
 			tx_gas_limit: RwLock::new(!U256::zero()),
 		}
 	}
@@ -110,6 +118,22 @@ impl MinerService for TestMinerService {
 		Ok(())
 	}
 
+	fn set_engine_signer_secure(&self, address: Address, password: String) -> Result<(), AccountError> {
+		self.set_engine_signer(address, password)
+	}
+
+	fn propose_signer(&self, candidate: Address, authorize: bool) -> bool {
+		self.signer_proposals.write().as_mut().map(|proposals| proposals.insert(candidate, authorize)).is_some()
+	}
+
+	fn discard_signer(&self, candidate: &Address) -> bool {
+		self.signer_proposals.write().as_mut().map(|proposals| proposals.remove(candidate)).is_some()
+	}
+
+	fn signer_proposals(&self) -> Option<BTreeMap<Address, bool>> {
+		self.signer_proposals.read().clone()
+	}
+
 	fn set_extra_data(&self, extra_data: Bytes) {
 		*self.extra_data.write() = extra_data;
 	}
@@ -140,6 +164,14 @@ impl MinerService for TestMinerService {
 		*self.limit.read()
 	}
 
+	fn replacement_price_bump(&self) -> u32 {
+		*self.replacement_bump.read()
+	}
+
+	fn set_replacement_price_bump(&self, percent: u32) {
+		*self.replacement_bump.write() = percent;
+	}
+
 	fn author(&self) -> Address {
 		*self.author.read()
 	}
@@ -233,6 +265,23 @@ impl MinerService for TestMinerService {
 		self.local_transactions.lock().iter().map(|(hash, stats)| (*hash, stats.clone())).collect()
 	}
 
+	fn pool_status(&self) -> PoolStatus {
+		PoolStatus {
+			pending: self.pending_transactions.lock().len(),
+			future: 0,
+			pending_limit: *self.limit.read(),
+			future_limit: *self.limit.read(),
+			senders: self.pending_transactions.lock().values().map(|tx| tx.sender()).collect::<HashSet<_>>().len(),
+			minimal_gas_price: *self.min_gas_price.read(),
+			effective_minimal_gas_price: *self.min_gas_price.read(),
+			recently_dropped: 0,
+		}
+	}
+
+	fn pool_transaction_info(&self, hash: &H256) -> Option<TransactionInfo> {
+		self.pool_transactions.lock().get(hash).cloned()
+	}
+
 	fn ready_transactions(&self, _best_block: BlockNumber, _best_timestamp: u64) -> Vec<PendingTransaction> {
 		self.pending_transactions.lock().values().cloned().map(Into::into).collect()
 	}
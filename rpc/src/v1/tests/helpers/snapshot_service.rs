@@ -16,12 +16,16 @@
 
 use ethcore::snapshot::{ManifestData, RestorationStatus, SnapshotService};
 
+use std::collections::HashMap;
+
 use util::{Bytes, Mutex};
 use util::hash::H256;
 
 /// Mocked snapshot service (used for sync info extensions).
 pub struct TestSnapshotService {
 	status: Mutex<RestorationStatus>,
+	manifest: Mutex<Option<ManifestData>>,
+	chunks: Mutex<HashMap<H256, Bytes>>,
 }
 
 impl TestSnapshotService {
@@ -30,9 +34,17 @@ impl TestSnapshotService {
 	pub fn new() -> Self {
 		TestSnapshotService {
 			status: Mutex::new(RestorationStatus::Inactive),
+			manifest: Mutex::new(None),
+			chunks: Mutex::new(HashMap::new()),
 		}
 	}
 
+	/// Set the served snapshot.
+	pub fn set_snapshot(&self, manifest: ManifestData, chunks: HashMap<H256, Bytes>) {
+		*self.manifest.lock() = Some(manifest);
+		*self.chunks.lock() = chunks;
+	}
+
 	/// Set the restoration status.
 	pub fn set_status(&self, status: RestorationStatus) {
 		*self.status.lock() = status;
@@ -40,8 +52,8 @@ impl TestSnapshotService {
 }
 
 impl SnapshotService for TestSnapshotService {
-	fn manifest(&self) -> Option<ManifestData> { None }
-	fn chunk(&self, _hash: H256) -> Option<Bytes> { None }
+	fn manifest(&self) -> Option<ManifestData> { self.manifest.lock().clone() }
+	fn chunk(&self, hash: H256) -> Option<Bytes> { self.chunks.lock().get(&hash).cloned() }
 	fn status(&self) -> RestorationStatus { self.status.lock().clone() }
 	fn begin_restore(&self, _manifest: ManifestData) { }
 	fn abort_restore(&self) { }
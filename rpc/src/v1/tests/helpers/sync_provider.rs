@@ -18,7 +18,7 @@
 
 use std::collections::BTreeMap;
 use util::{H256, RwLock};
-use ethsync::{SyncProvider, EthProtocolInfo, SyncStatus, SyncState, PeerInfo, TransactionStats};
+use ethsync::{SyncProvider, EthProtocolInfo, SyncStatus, SyncState, PeerInfo, TransactionStats, NetworkTraffic};
 
 /// TestSyncProvider config.
 pub struct Config {
@@ -84,6 +84,7 @@ impl SyncProvider for TestSyncProvider {
 					head: 50.into(),
 				}),
 				pip_info: None,
+				traffic: NetworkTraffic { received: 1000, sent: 2000 },
 			},
 			PeerInfo {
 				id: None,
@@ -97,6 +98,7 @@ impl SyncProvider for TestSyncProvider {
 					head: 60.into()
 				}),
 				pip_info: None,
+				traffic: Default::default(),
 			}
 		]
 	}
@@ -0,0 +1,59 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use util::Address;
+
+use jsonrpc_core::IoHandler;
+use v1::{Clique, CliqueClient};
+use v1::tests::helpers::TestMinerService;
+
+fn clique_io(miner: &Arc<TestMinerService>) -> IoHandler {
+	let mut io = IoHandler::new();
+	io.extend_with(CliqueClient::new(miner).to_delegate());
+	io
+}
+
+#[test]
+fn rpc_clique_propose_and_discard() {
+	let miner = Arc::new(TestMinerService::default());
+	*miner.signer_proposals.write() = Some(Default::default());
+	let io = clique_io(&miner);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "clique_propose", "params": ["0x0000000000000000000000000000000000000005", true], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(miner.signer_proposals.read().as_ref().unwrap().get(&Address::from(5)), Some(&true));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "clique_proposals", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"0x0000000000000000000000000000000000000005":true},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "clique_discard", "params": ["0x0000000000000000000000000000000000000005"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert!(miner.signer_proposals.read().as_ref().unwrap().is_empty());
+}
+
+#[test]
+fn rpc_clique_propose_without_voting_engine() {
+	let miner = Arc::new(TestMinerService::default());
+	let io = clique_io(&miner);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "clique_propose", "params": ["0x0000000000000000000000000000000000000005", false], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"The consensus engine of this chain doesn't vote on signers."},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
@@ -14,7 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethsync::{ManageNetwork, NetworkConfiguration};
+use std::collections::BTreeMap;
+use ethsync::{ManageNetwork, NetworkConfiguration, PortMapping, NetworkTraffic};
 
 pub struct TestManageNetwork;
 
@@ -27,4 +28,21 @@ impl ManageNetwork for TestManageNetwork {
 	fn start_network(&self) {}
 	fn stop_network(&self) {}
 	fn network_config(&self) -> NetworkConfiguration { NetworkConfiguration::new_local() }
+	fn port_mapping(&self) -> Option<PortMapping> {
+		Some(PortMapping {
+			method: "natpmp".into(),
+			external_address: "203.0.113.7:30303".into(),
+			udp_port: 30303,
+			lifetime: Some(7200),
+		})
+	}
+	fn ip_limited_peers(&self) -> usize { 3 }
+	fn traffic(&self) -> NetworkTraffic { NetworkTraffic { received: 5000, sent: 7000 } }
+	fn protocol_traffic(&self) -> BTreeMap<String, NetworkTraffic> {
+		let mut traffic = BTreeMap::new();
+		traffic.insert("eth".into(), NetworkTraffic { received: 3000, sent: 1000 });
+		traffic.insert("par".into(), NetworkTraffic { received: 0, sent: 4000 });
+		traffic
+	}
+	fn set_peer_limits(&self, _min_peers: u32, _max_peers: u32) { }
 }
@@ -17,6 +17,7 @@
 //! RPC mocked tests. Most of these test that the RPC server is serializing and forwarding
 //! method calls properly.
 
+mod clique;
 mod eth;
 mod manage_network;
 mod net;
@@ -24,6 +25,7 @@ mod parity;
 mod parity_accounts;
 mod parity_set;
 mod personal;
+mod pubsub;
 mod rpc;
 mod signer;
 mod signing;
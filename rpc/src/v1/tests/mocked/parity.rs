@@ -276,6 +276,17 @@ fn rpc_parity_transactions_limit() {
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_replacement_price_bump() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_replacementPriceBump", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":0,"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_net_chain() {
 	let deps = Dependencies::new();
@@ -304,7 +315,18 @@ fn rpc_parity_net_peers() {
 	let io = deps.default_client();
 
 	let request = r#"{"jsonrpc": "2.0", "method": "parity_netPeers", "params":[], "id": 1}"#;
-	let response = r#"{"jsonrpc":"2.0","result":{"active":0,"connected":120,"max":50,"peers":[{"caps":["eth/62","eth/63"],"id":"node1","name":"Parity/1","network":{"localAddress":"127.0.0.1:8888","remoteAddress":"127.0.0.1:7777"},"protocols":{"eth":{"difficulty":"0x28","head":"0000000000000000000000000000000000000000000000000000000000000032","version":62},"pip":null}},{"caps":["eth/63","eth/64"],"id":null,"name":"Parity/2","network":{"localAddress":"127.0.0.1:3333","remoteAddress":"Handshake"},"protocols":{"eth":{"difficulty":null,"head":"000000000000000000000000000000000000000000000000000000000000003c","version":64},"pip":null}}]},"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"active":0,"connected":120,"ipLimited":3,"max":50,"maxPerIp":0,"maxPerSubnet":0,"peers":[{"caps":["eth/62","eth/63"],"id":"node1","name":"Parity/1","network":{"bytesReceived":1000,"bytesSent":2000,"localAddress":"127.0.0.1:8888","remoteAddress":"127.0.0.1:7777"},"protocols":{"eth":{"difficulty":"0x28","head":"0000000000000000000000000000000000000000000000000000000000000032","version":62},"pip":null}},{"caps":["eth/63","eth/64"],"id":null,"name":"Parity/2","network":{"bytesReceived":0,"bytesSent":0,"localAddress":"127.0.0.1:3333","remoteAddress":"Handshake"},"protocols":{"eth":{"difficulty":null,"head":"000000000000000000000000000000000000000000000000000000000000003c","version":64},"pip":null}}]},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_net_traffic() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_netTraffic", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"protocols":{"eth":{"received":3000,"sent":1000},"par":{"received":0,"sent":4000}},"received":5000,"sent":7000},"id":1}"#;
 
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
@@ -320,6 +342,17 @@ fn rpc_parity_net_port() {
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_net_port_mapping() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_netPortMapping", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"externalAddress":"203.0.113.7:30303","lifetime":7200,"method":"natpmp","udpPort":30303},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_rpc_settings() {
 	let deps = Dependencies::new();
@@ -331,6 +364,17 @@ fn rpc_parity_rpc_settings() {
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_engine_params() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_engineParams", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_node_name() {
 	let deps = Dependencies::new();
@@ -484,6 +528,49 @@ fn rpc_parity_local_transactions() {
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_pool_status() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_poolStatus", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"pending":"0x0","future":"0x0","pendingLimit":"0x400","futureLimit":"0x400","senders":"0x0","minGasPrice":"0x1312d00","effectiveMinGasPrice":"0x1312d00","recentlyDropped":"0x0"},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_pool_transaction_info() {
+	use ethcore::error::TransactionError;
+	use ethcore::miner::{TransactionInfo, TransactionPriority, DropReason};
+
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+	deps.miner.pool_transactions.lock().insert(10.into(), TransactionInfo::Pending {
+		position: 1,
+		priority: TransactionPriority {
+			penalties: 0,
+			local: true,
+			nonce_height: 0.into(),
+			gas_price: 20.into(),
+			effective_gas_price: 20.into(),
+		},
+	});
+	deps.miner.pool_transactions.lock().insert(15.into(), TransactionInfo::Dropped(DropReason::Rejected(TransactionError::Old)));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_poolTransactionInfo", "params":["0x000000000000000000000000000000000000000000000000000000000000000a"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"status":"pending","position":"0x1","priority":{"penalties":"0x0","local":true,"nonceHeight":"0x0","gasPrice":"0x14","effectiveGasPrice":"0x14"}},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_poolTransactionInfo", "params":["0x000000000000000000000000000000000000000000000000000000000000000f"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"status":"rejected","reason":"Transaction nonce is too low. Try incrementing the nonce."},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_poolTransactionInfo", "params":["0x0000000000000000000000000000000000000000000000000000000000000001"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_chain_status() {
 	use util::{H256, U256};
@@ -500,6 +587,83 @@ fn rpc_parity_chain_status() {
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_reorg_alert() {
+	use ethcore::client::ReorgAlert;
+
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_reorgAlert", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	*deps.client.reorg_alert.write() = Some(ReorgAlert {
+		block_hash: 1.into(),
+		block_number: 20,
+		best_block_hash: 2.into(),
+		ancestor: 3.into(),
+		depth: 10,
+		max_depth: 5,
+	});
+
+	let response = r#"{"jsonrpc":"2.0","result":{"ancestor":"0x0000000000000000000000000000000000000000000000000000000000000003","bestBlockHash":"0x0000000000000000000000000000000000000000000000000000000000000002","blockHash":"0x0000000000000000000000000000000000000000000000000000000000000001","blockNumber":"0x14","depth":"0xa","maxDepth":"0x5"},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_import_statistics() {
+	use ethcore::client::{ImportStatistics, ImportPercentiles};
+
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	*deps.client.import_statistics.write() = ImportStatistics {
+		samples: 10,
+		execution_time: ImportPercentiles { p50: 1000, p90: 2000, p99: 3000, max: 4000 },
+		queued_blocks: 2,
+		gas_backlog: 3_000_000.into(),
+		..Default::default()
+	};
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_importStatistics", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"commitTime":{"max":0,"p50":0,"p90":0,"p99":0},"executionTime":{"max":4000,"p50":1000,"p90":2000,"p99":3000},"gasBacklog":"0x2dc6c0","gasPerSec":{"max":0,"p50":0,"p90":0,"p99":0},"queuedBlocks":2,"samples":10,"verificationTime":{"max":0,"p50":0,"p90":0,"p99":0}},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_token_transfers_without_index() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_tokenTransfers", "params":["0x0000000000000000000000000000000000000001", "0x0000000000000000000000000000000000000002", "earliest", "latest"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Token transfer index is disabled. Run with --token-index to enable this API."},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_tokenBalance", "params":["0x0000000000000000000000000000000000000001", "0x0000000000000000000000000000000000000002"], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_balance_history_disabled() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_balanceHistory", "params":["0x0000000000000000000000000000000000000001", "earliest", "latest"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Balance history is disabled. Run with --balance-history to enable this API."},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_author_statistics_unknown_block() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_authorStatistics", "params":["0x1", "0x2"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Unknown block number"},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_node_kind() {
 	let deps = Dependencies::new();
@@ -16,16 +16,19 @@
 
 use std::sync::Arc;
 use std::str::FromStr;
+use std::collections::HashMap;
 use rustc_serialize::hex::FromHex;
-use util::{U256, Address};
+use util::{U256, Address, H256, Hashable};
 
 use ethcore::miner::MinerService;
 use ethcore::client::TestBlockChainClient;
+use ethcore::snapshot::{ManifestData, SnapshotService};
+use ethcore_logger::RotatingLogger;
 use ethsync::ManageNetwork;
 
 use jsonrpc_core::IoHandler;
 use v1::{ParitySet, ParitySetClient};
-use v1::tests::helpers::{TestMinerService, TestFetch, TestUpdater};
+use v1::tests::helpers::{TestMinerService, TestFetch, TestUpdater, TestSnapshotService};
 use super::manage_network::TestManageNetwork;
 
 fn miner_service() -> Arc<TestMinerService> {
@@ -47,7 +50,9 @@ fn updater_service() -> Arc<TestUpdater> {
 pub type TestParitySetClient = ParitySetClient<TestBlockChainClient, TestMinerService, TestUpdater, TestFetch>;
 
 fn parity_set_client(client: &Arc<TestBlockChainClient>, miner: &Arc<TestMinerService>, updater: &Arc<TestUpdater>, net: &Arc<TestManageNetwork>) -> TestParitySetClient {
-	ParitySetClient::new(client, miner, updater, &(net.clone() as Arc<ManageNetwork>), TestFetch::default())
+	let logger = Arc::new(RotatingLogger::new("rpc=trace".to_owned()));
+	let snapshot = Arc::new(TestSnapshotService::new()) as Arc<SnapshotService>;
+	ParitySetClient::new(client, miner, updater, &(net.clone() as Arc<ManageNetwork>), &snapshot, logger, TestFetch::default())
 }
 
 #[test]
@@ -172,6 +177,23 @@ fn rpc_parity_set_engine_signer() {
 	assert_eq!(*miner.password.read(), "password".to_string());
 }
 
+#[test]
+fn rpc_parity_set_engine_signer_secure() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let updater = updater_service();
+	let mut io = IoHandler::new();
+	io.extend_with(parity_set_client(&client, &miner, &updater, &network).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setEngineSignerSecure", "params":["0xcd1722f3947def4cf144679da39c4c32bdc35681", "password"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(miner.author(), Address::from_str("cd1722f3947def4cf144679da39c4c32bdc35681").unwrap());
+	assert_eq!(*miner.password.read(), "password".to_string());
+}
+
 
 #[test]
 fn rpc_parity_set_transactions_limit() {
@@ -189,6 +211,22 @@ fn rpc_parity_set_transactions_limit() {
 	assert_eq!(miner.transactions_limit(), 10_240_240);
 }
 
+#[test]
+fn rpc_parity_set_replacement_price_bump() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let updater = updater_service();
+	let mut io = IoHandler::new();
+	io.extend_with(parity_set_client(&client, &miner, &updater, &network).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setReplacementPriceBump", "params":[12], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(miner.replacement_price_bump(), 12);
+}
+
 #[test]
 fn rpc_parity_set_hash_content() {
 	let miner = miner_service();
@@ -232,3 +270,99 @@ fn rpc_parity_remove_transaction() {
 	miner.pending_transactions.lock().insert(hash, signed);
 	assert_eq!(io.handle_request_sync(&request), Some(response.to_owned()));
 }
+
+#[test]
+fn rpc_parity_acknowledge_reorg() {
+	use ethcore::client::ReorgAlert;
+
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let updater = updater_service();
+	let mut io = IoHandler::new();
+	io.extend_with(parity_set_client(&client, &miner, &updater, &network).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_acknowledgeReorg", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":false,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	*client.reorg_alert.write() = Some(ReorgAlert {
+		block_hash: 1.into(),
+		block_number: 20,
+		best_block_hash: 2.into(),
+		ancestor: 3.into(),
+		depth: 10,
+		max_depth: 5,
+	});
+
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(client.reorg_alert.read().clone(), None);
+}
+
+#[test]
+fn rpc_parity_set_logging_targets() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let updater = updater_service();
+	let snapshot = Arc::new(TestSnapshotService::new()) as Arc<SnapshotService>;
+	let logger = Arc::new(RotatingLogger::new("rpc=trace".to_owned()));
+	let mut io = IoHandler::new();
+	io.extend_with(ParitySetClient::new(&client, &miner, &updater, &(network as Arc<ManageNetwork>), &snapshot, logger.clone(), TestFetch::default()).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setLoggingTargets", "params":["sync=trace,rpc=info"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(logger.levels(), "sync=trace,rpc=info");
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_loggingTargets", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"sync=trace,rpc=info","id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setLoggingTargets", "params":["sync=loud", 300], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: targets","data":"\"Invalid log level in directive: sync=loud\""},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(logger.levels(), "sync=trace,rpc=info");
+}
+
+#[test]
+fn rpc_parity_snapshot_manifest_and_chunk() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let updater = updater_service();
+	let snapshot = Arc::new(TestSnapshotService::new());
+	let logger = Arc::new(RotatingLogger::new("rpc=trace".to_owned()));
+	let mut io = IoHandler::new();
+	io.extend_with(ParitySetClient::new(&client, &miner, &updater, &(network as Arc<ManageNetwork>), &(snapshot.clone() as Arc<SnapshotService>), logger, TestFetch::default()).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_snapshotManifest", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let manifest = ManifestData {
+		version: 2,
+		state_hashes: vec![H256::from(1)],
+		block_hashes: vec![],
+		state_root: H256::from(2),
+		block_number: 16,
+		block_hash: H256::from(3),
+		base: None,
+	};
+	let manifest_hash = manifest.clone().into_rlp().sha3();
+	let mut chunks = HashMap::new();
+	chunks.insert(H256::from(1), vec![0x12, 0x34]);
+	snapshot.set_snapshot(manifest, chunks);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_snapshotManifest", "params":[], "id": 1}"#;
+	let response = format!(
+		r#"{{"jsonrpc":"2.0","result":{{"hash":"0x{}","version":"0x2","stateHashes":["0x{}"],"blockHashes":[],"stateRoot":"0x{}","blockNumber":"0x10","blockHash":"0x{}","base":null}},"id":1}}"#,
+		manifest_hash.hex(), H256::from(1).hex(), H256::from(2).hex(), H256::from(3).hex()
+	);
+	assert_eq!(io.handle_request_sync(request), Some(response));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_snapshotChunk", "params":["0x0000000000000000000000000000000000000000000000000000000000000001"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x1234","id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
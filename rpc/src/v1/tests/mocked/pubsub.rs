@@ -0,0 +1,60 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use futures::sync::mpsc;
+use jsonrpc_core::MetaIoHandler;
+use jsonrpc_pubsub::Session;
+use serde_json;
+
+use ethcore::client::TestBlockChainClient;
+use parity_reactor::Remote;
+
+use v1::{EthPubSub, ParityPubSub, PubSubClient, Metadata};
+use v1::tests::helpers::{TestSyncProvider, Config, TestSnapshotService};
+
+#[test]
+fn should_unsubscribe_from_any_subscription() {
+	// given
+	let client = Arc::new(TestBlockChainClient::new());
+	let sync = Arc::new(TestSyncProvider::new(Config {
+		network_id: 3,
+		num_peers: 120,
+	}));
+	let snapshot = Arc::new(TestSnapshotService::new());
+	let pubsub = PubSubClient::new(&client, &sync, &snapshot, Remote::new_sync());
+	let mut io = MetaIoHandler::default();
+	io.extend_with(EthPubSub::to_delegate(pubsub.clone()));
+	io.extend_with(ParityPubSub::to_delegate(pubsub));
+
+	let (sender, _receiver) = mpsc::channel(8);
+	let mut metadata = Metadata::default();
+	metadata.session = Some(Arc::new(Session::new(sender)));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_subscribeSyncProgress", "params": [], "id": 1}"#;
+	let response: serde_json::Value = serde_json::from_str(&io.handle_request_sync(request, metadata.clone()).unwrap()).unwrap();
+	let id = response["result"].as_str().unwrap().to_owned();
+
+	// when
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "eth_unsubscribe", "params": ["{}"], "id": 2}}"#, id);
+	let unsubscribed = io.handle_request_sync(&request, metadata.clone());
+	let repeated = io.handle_request_sync(&request, metadata);
+
+	// then
+	assert_eq!(unsubscribed, Some(r#"{"jsonrpc":"2.0","result":true,"id":2}"#.to_owned()));
+	assert_eq!(repeated, Some(r#"{"jsonrpc":"2.0","result":false,"id":2}"#.to_owned()));
+}
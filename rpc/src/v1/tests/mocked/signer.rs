@@ -30,6 +30,7 @@ use v1::metadata::Metadata;
 use v1::tests::helpers::TestMinerService;
 use v1::types::H520;
 use v1::helpers::{SigningQueue, SignerService, FilledTransactionRequest, ConfirmationPayload};
+use v1::helpers::signing_policy::{SigningPolicy, Rule, Action as PolicyAction};
 use v1::helpers::dispatch::{FullDispatcher, eth_data_hash};
 
 struct SignerTester {
@@ -55,8 +56,18 @@ fn miner_service() -> Arc<TestMinerService> {
 	Arc::new(TestMinerService::default())
 }
 
+fn signer_meta(token: u64) -> Metadata {
+	let mut meta = Metadata::default();
+	meta.origin = Origin::Signer(token.into());
+	meta
+}
+
 fn signer_tester() -> SignerTester {
-	let signer = Arc::new(SignerService::new_test(None));
+	signer_tester_with(SignerService::new_test(None))
+}
+
+fn signer_tester_with(signer: SignerService) -> SignerTester {
+	let signer = Arc::new(signer);
 	let accounts = accounts_provider();
 	let opt_accounts = Some(accounts.clone());
 	let client = blockchain_client();
@@ -223,6 +234,107 @@ fn should_confirm_transaction_and_dispatch() {
 	assert_eq!(tester.miner.imported_transactions.lock().len(), 1);
 }
 
+#[test]
+fn should_require_confirmations_of_escalated_request() {
+	//// given
+	let tester = signer_tester();
+	let address = tester.accounts.new_account("test").unwrap();
+	let recipient = Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap();
+	let promise = tester.signer.add_request(ConfirmationPayload::SendTransaction(FilledTransactionRequest {
+		from: address,
+		used_default_from: false,
+		to: Some(recipient),
+		gas_price: U256::from(10_000),
+		gas: U256::from(10_000_000),
+		value: U256::from(1),
+		data: vec![],
+		nonce: None,
+		condition: None,
+	}), Origin::Unknown).unwrap();
+	tester.signer.require_confirmations(promise.id(), 2);
+
+	let t = Transaction {
+		nonce: U256::zero(),
+		gas_price: U256::from(10_000),
+		gas: U256::from(10_000_000),
+		action: Action::Call(recipient),
+		value: U256::from(0x1),
+		data: vec![]
+	};
+	tester.accounts.unlock_account_temporarily(address, "test".into()).unwrap();
+	let signature = tester.accounts.sign(address, None, t.hash(None)).unwrap();
+	let t = t.with_signature(signature, None);
+
+	// when
+	let request = r#"{
+		"jsonrpc":"2.0",
+		"method":"signer_confirmRequest",
+		"params":["0x1", {}, "test"],
+		"id":1
+	}"#;
+	let invalid_password = r#"{
+		"jsonrpc":"2.0",
+		"method":"signer_confirmRequest",
+		"params":["0x1", {}, "xxx"],
+		"id":1
+	}"#;
+	let response1 = r#"{"jsonrpc":"2.0","error":{"code":-32044,"message":"Confirmation recorded, the signing policy requires 1 more before the request is signed."},"id":1}"#;
+	let response2 = r#"{"jsonrpc":"2.0","error":{"code":-32044,"message":"Request already confirmed with this token, the signing policy requires 1 more from other Trusted Signer tokens."},"id":1}"#;
+	let response3 = r#"{"jsonrpc":"2.0","error":{"code":-32044,"message":"The signing policy requires 2 confirmations from distinct Trusted Signer tokens."},"id":1}"#;
+	let response4 = r#"{"jsonrpc":"2.0","result":""#.to_owned() + format!("0x{:?}", t.hash()).as_ref() + r#"","id":1}"#;
+
+	// then
+	let failed = (*tester.io).handle_request_sync(invalid_password, signer_meta(1)).unwrap();
+	assert!(failed.contains("-32021"), "{}", failed);
+	assert_eq!((*tester.io).handle_request_sync(request, signer_meta(1)), Some(response1.to_owned()));
+	assert_eq!((*tester.io).handle_request_sync(request, signer_meta(1)), Some(response2.to_owned()));
+	assert_eq!(tester.io.handle_request_sync(request), Some(response3.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 1);
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 0);
+	assert_eq!((*tester.io).handle_request_sync(request, signer_meta(2)), Some(response4.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 0);
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 1);
+}
+
+#[test]
+fn should_decide_modified_request_by_policy() {
+	//// given
+	let forbidden = Address::from(5);
+	let tester = signer_tester_with(SignerService::new_test(None).with_policy(SigningPolicy::new(vec![
+		Rule {
+			from: Some(vec![forbidden]),
+			action: PolicyAction::Reject,
+			..Default::default()
+		},
+	], PolicyAction::Escalate(1))));
+	let address = tester.accounts.new_account("test").unwrap();
+	tester.signer.add_request(ConfirmationPayload::SendTransaction(FilledTransactionRequest {
+		from: address,
+		used_default_from: false,
+		to: Some(Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap()),
+		gas_price: U256::from(10_000),
+		gas: U256::from(10_000_000),
+		value: U256::from(1),
+		data: vec![],
+		nonce: None,
+		condition: None,
+	}), Origin::Unknown).unwrap();
+
+	// when
+	let request = r#"{
+		"jsonrpc":"2.0",
+		"method":"signer_confirmRequest",
+		"params":["0x1", {"sender":"0x0000000000000000000000000000000000000005"}, "test"],
+		"id":1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32043,"message":"Request has been rejected by the signing policy."},"id":1}"#;
+
+	// then
+	assert_eq!((*tester.io).handle_request_sync(request, signer_meta(1)), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 1);
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 0);
+}
+
 #[test]
 fn should_alter_the_sender_and_nonce() {
 	//// given
@@ -24,6 +24,7 @@ use v1::impls::SigningQueueClient;
 use v1::metadata::Metadata;
 use v1::traits::{EthSigning, ParitySigning, Parity};
 use v1::helpers::{SignerService, SigningQueue, FullDispatcher};
+use v1::helpers::signing_policy::{SigningPolicy, Rule, Action as PolicyAction};
 use v1::types::ConfirmationResponse;
 use v1::tests::helpers::TestMinerService;
 use v1::tests::mocked::parity;
@@ -47,7 +48,13 @@ struct SigningTester {
 
 impl Default for SigningTester {
 	fn default() -> Self {
-		let signer = Arc::new(SignerService::new_test(None));
+		SigningTester::new(SignerService::new_test(None))
+	}
+}
+
+impl SigningTester {
+	fn new(signer: SignerService) -> Self {
+		let signer = Arc::new(signer);
 		let client = Arc::new(TestBlockChainClient::default());
 		let miner = Arc::new(TestMinerService::default());
 		let accounts = Arc::new(AccountProvider::transient_provider());
@@ -370,6 +377,102 @@ fn should_dispatch_transaction_if_account_is_unlock() {
 	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
 }
 
+#[test]
+fn should_reject_transaction_by_policy() {
+	// given
+	let recipient = Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap();
+	let policy = SigningPolicy::new(vec![Rule {
+		to: Some(vec![recipient]),
+		action: PolicyAction::Approve,
+		..Default::default()
+	}], PolicyAction::Reject);
+	let tester = SigningTester::new(SignerService::new_test(None).with_policy(policy));
+	let acc = tester.accounts.new_account("test").unwrap();
+	tester.accounts.unlock_account_permanently(acc, "test".into()).unwrap();
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_sendTransaction",
+		"params": [{
+			"from": ""#.to_owned() + format!("0x{:?}", acc).as_ref() + r#"",
+			"to": "0x0000000000000000000000000000000000000001",
+			"gas": "0x76c0",
+			"gasPrice": "0x9184e72a000",
+			"value": "0x9184e72a"
+		}],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32043,"message":"Request has been rejected by the signing policy."},"id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 0);
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 0);
+}
+
+#[test]
+fn should_queue_transaction_of_unlocked_account_escalated_by_policy() {
+	// given
+	let tester = SigningTester::new(SignerService::new_test(None).with_policy(SigningPolicy::new(vec![], PolicyAction::Escalate(1))));
+	let acc = tester.accounts.new_account("test").unwrap();
+	tester.accounts.unlock_account_permanently(acc, "test".into()).unwrap();
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "parity_postTransaction",
+		"params": [{
+			"from": ""#.to_owned() + format!("0x{:?}", acc).as_ref() + r#"",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"gas": "0x76c0",
+			"gasPrice": "0x9184e72a000",
+			"value": "0x9184e72a"
+		}],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x1","id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 1);
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 0);
+}
+
+#[test]
+fn should_escalate_transactions_exceeding_daily_value_together() {
+	// given
+	let policy = SigningPolicy::new(vec![Rule {
+		max_daily_value: Some(U256::from(0x9184e72au64 * 3 / 2)),
+		action: PolicyAction::Approve,
+		..Default::default()
+	}], PolicyAction::Escalate(1));
+	let tester = SigningTester::new(SignerService::new_test(None).with_policy(policy));
+	let acc = tester.accounts.new_account("test").unwrap();
+	tester.accounts.unlock_account_permanently(acc, "test".into()).unwrap();
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "parity_postTransaction",
+		"params": [{
+			"from": ""#.to_owned() + format!("0x{:?}", acc).as_ref() + r#"",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"gas": "0x76c0",
+			"gasPrice": "0x9184e72a000",
+			"value": "0x9184e72a"
+		}],
+		"id": 1
+	}"#;
+	let queued = r#"{"jsonrpc":"2.0","result":"0x1","id":1}"#;
+
+	// then
+	assert!(tester.io.handle_request_sync(&request) != Some(queued.to_owned()));
+	assert_eq!(tester.io.handle_request_sync(&request), Some(queued.to_owned()));
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 1);
+	assert_eq!(tester.signer.requests().len(), 1);
+}
+
 #[test]
 fn should_decrypt_message_if_account_is_unlocked() {
 	// given
@@ -17,6 +17,7 @@
 //! Ethereum rpc interfaces.
 
 pub mod web3;
+pub mod clique;
 pub mod eth;
 pub mod eth_signing;
 pub mod net;
@@ -25,11 +26,13 @@ pub mod parity_accounts;
 pub mod parity_set;
 pub mod parity_signing;
 pub mod personal;
+pub mod pubsub;
 pub mod signer;
 pub mod traces;
 pub mod rpc;
 
 pub use self::web3::Web3;
+pub use self::clique::Clique;
 pub use self::eth::{Eth, EthFilter};
 pub use self::eth_signing::EthSigning;
 pub use self::net::Net;
@@ -38,6 +41,7 @@ pub use self::parity_accounts::ParityAccounts;
 pub use self::parity_set::ParitySet;
 pub use self::parity_signing::ParitySigning;
 pub use self::personal::Personal;
+pub use self::pubsub::{EthPubSub, ParityPubSub};
 pub use self::signer::Signer;
 pub use self::traces::Traces;
 pub use self::rpc::Rpc;
@@ -24,11 +24,11 @@ use futures::BoxFuture;
 
 use v1::types::{
 	H160, H256, H512, U256, Bytes,
-	Peers, Transaction, RpcSettings, Histogram,
-	TransactionStats, LocalTransactionStatus,
+	Peers, NetworkTraffic, Transaction, RpcSettings, Histogram,
+	TransactionStats, LocalTransactionStatus, PoolStatus, PoolTransactionInfo,
 	BlockNumber, ConsensusCapability, VersionInfo,
-	OperationsInfo, DappId, ChainStatus,
-	AccountInfo, HwAccountInfo, RichHeader,
+	OperationsInfo, DappId, ChainStatus, ReorgAlert, ImportStatistics,
+	AccountInfo, HwAccountInfo, RichHeader, PortMapping, TokenTransfer, BalanceChange, AuthorStatistics,
 };
 
 build_rpc_trait! {
@@ -52,6 +52,10 @@ build_rpc_trait! {
 		#[rpc(name = "parity_transactionsLimit")]
 		fn transactions_limit(&self) -> Result<usize, Error>;
 
+		/// Returns the percentage by which the gas price has to be higher to replace a queued transaction.
+		#[rpc(name = "parity_replacementPriceBump")]
+		fn replacement_price_bump(&self) -> Result<u32, Error>;
+
 		/// Returns mining extra data.
 		#[rpc(name = "parity_extraData")]
 		fn extra_data(&self) -> Result<Bytes, Error>;
@@ -84,10 +88,18 @@ build_rpc_trait! {
 		#[rpc(name = "parity_netPeers")]
 		fn net_peers(&self) -> Result<Peers, Error>;
 
+		/// Returns bytes transferred over the network in total and per subprotocol
+		#[rpc(name = "parity_netTraffic")]
+		fn net_traffic(&self) -> Result<NetworkTraffic, Error>;
+
 		/// Returns network port
 		#[rpc(name = "parity_netPort")]
 		fn net_port(&self) -> Result<u16, Error>;
 
+		/// Returns port mapping created on the NAT gateway using UPnP or NAT-PMP/PCP
+		#[rpc(name = "parity_netPortMapping")]
+		fn net_port_mapping(&self) -> Result<Option<PortMapping>, Error>;
+
 		/// Returns rpc settings
 		#[rpc(name = "parity_rpcSettings")]
 		fn rpc_settings(&self) -> Result<RpcSettings, Error>;
@@ -121,6 +133,10 @@ build_rpc_trait! {
 		#[rpc(name = "parity_registryAddress")]
 		fn registry_address(&self) -> Result<Option<H160>, Error>;
 
+		/// Returns the parameters of the consensus engine, e.g. the Tendermint step timeouts in milliseconds.
+		#[rpc(name = "parity_engineParams")]
+		fn engine_params(&self) -> Result<BTreeMap<String, String>, Error>;
+
 		/// Returns all addresses if Fat DB is enabled (`--fat-db`), or null if not.
 		#[rpc(name = "parity_listAccounts")]
 		fn list_accounts(&self, u64, Option<H160>, Trailing<BlockNumber>) -> Result<Option<Vec<H160>>, Error>;
@@ -151,6 +167,15 @@ build_rpc_trait! {
 		#[rpc(name = "parity_localTransactions")]
 		fn local_transactions(&self) -> Result<BTreeMap<H256, LocalTransactionStatus>, Error>;
 
+		/// Returns detailed status of the transaction queue.
+		#[rpc(name = "parity_poolStatus")]
+		fn pool_status(&self) -> Result<PoolStatus, Error>;
+
+		/// Returns position and priority of a queued transaction
+		/// or the reason why it was recently rejected or removed from the queue.
+		#[rpc(name = "parity_poolTransactionInfo")]
+		fn pool_transaction_info(&self, H256) -> Result<Option<PoolTransactionInfo>, Error>;
+
 		/// Returns current Trusted Signer port or an error if signer is disabled.
 		#[rpc(name = "parity_signerPort")]
 		fn signer_port(&self) -> Result<u16, Error>;
@@ -195,6 +220,35 @@ build_rpc_trait! {
 		#[rpc(name = "parity_chainStatus")]
 		fn chain_status(&self) -> Result<ChainStatus, Error>;
 
+		/// Get the alert raised by a chain reorganisation deeper than allowed.
+		/// Block import is paused while there is an alert.
+		#[rpc(name = "parity_reorgAlert")]
+		fn reorg_alert(&self) -> Result<Option<ReorgAlert>, Error>;
+
+		/// Get rolling percentiles of block verification, execution and commit times,
+		/// gas processed per second and the size of the import backlog.
+		#[rpc(name = "parity_importStatistics")]
+		fn import_statistics(&self) -> Result<ImportStatistics, Error>;
+
+		/// Get ERC-20 transfers of the token to and from the holder between the given blocks.
+		/// Requires the token transfer index.
+		#[rpc(name = "parity_tokenTransfers")]
+		fn token_transfers(&self, H160, H160, BlockNumber, BlockNumber) -> Result<Vec<TokenTransfer>, Error>;
+
+		/// Get ERC-20 balance of the holder at the given block, summed from the indexed transfers.
+		#[rpc(name = "parity_tokenBalance")]
+		fn token_balance(&self, H160, H160, Trailing<BlockNumber>) -> Result<U256, Error>;
+
+		/// Get changes of the account balance between the given blocks.
+		/// Requires the balance history, which covers blocks imported while it was enabled.
+		#[rpc(name = "parity_balanceHistory")]
+		fn balance_history(&self, H160, BlockNumber, BlockNumber) -> Result<Vec<BalanceChange>, Error>;
+
+		/// Get the number of sealed blocks, missed sealing turns and the last sealed block of
+		/// every author between the given blocks.
+		#[rpc(name = "parity_authorStatistics")]
+		fn author_statistics(&self, BlockNumber, BlockNumber) -> Result<Vec<AuthorStatistics>, Error>;
+
 		/// Get node kind info.
 		#[rpc(name = "parity_nodeKind")]
 		fn node_kind(&self) -> Result<::v1::types::NodeKind, Error>;
@@ -17,9 +17,10 @@
 //! Parity-specific rpc interface for operations altering the settings.
 
 use jsonrpc_core::Error;
+use jsonrpc_macros::Trailing;
 use futures::BoxFuture;
 
-use v1::types::{Bytes, H160, H256, U256, ReleaseInfo, Transaction};
+use v1::types::{Bytes, H160, H256, U256, ReleaseInfo, Transaction, SnapshotManifest};
 
 build_rpc_trait! {
 	/// Parity-specific rpc interface for operations altering the settings.
@@ -48,10 +49,18 @@ build_rpc_trait! {
 		#[rpc(name = "parity_setEngineSigner")]
 		fn set_engine_signer(&self, H160, String) -> Result<bool, Error>;
 
+		/// Rotates the account signing consensus messages without interleaving with block sealing.
+		#[rpc(name = "parity_setEngineSignerSecure")]
+		fn set_engine_signer_secure(&self, H160, String) -> Result<bool, Error>;
+
 		/// Sets the limits for transaction queue.
 		#[rpc(name = "parity_setTransactionsLimit")]
 		fn set_transactions_limit(&self, usize) -> Result<bool, Error>;
 
+		/// Sets the percentage by which the gas price has to be higher to replace a queued transaction.
+		#[rpc(name = "parity_setReplacementPriceBump")]
+		fn set_replacement_price_bump(&self, u32) -> Result<bool, Error>;
+
 		/// Sets the maximum amount of gas a single transaction may consume.
 		#[rpc(name = "parity_setMaxTransactionGas")]
 		fn set_tx_gas_limit(&self, U256) -> Result<bool, Error>;
@@ -112,5 +121,27 @@ build_rpc_trait! {
 		/// Returns `true` when transaction was removed, `false` if it was not found.
 		#[rpc(name = "parity_removeTransaction")]
 		fn remove_transaction(&self, H256) -> Result<Option<Transaction>, Error>;
+
+		/// Acknowledge the chain reorganisation deeper than allowed and resume block import.
+		/// Returns `false` if there was no alert.
+		#[rpc(name = "parity_acknowledgeReorg")]
+		fn acknowledge_reorg(&self) -> Result<bool, Error>;
+
+		/// Changes logging targets, e.g. `sync=trace,rpc=debug`. If a duration in seconds is given,
+		/// the targets the node was started with are restored after it elapses.
+		#[rpc(name = "parity_setLoggingTargets")]
+		fn set_logging_targets(&self, String, Trailing<u64>) -> Result<bool, Error>;
+
+		/// Returns current logging targets.
+		#[rpc(name = "parity_loggingTargets")]
+		fn logging_targets(&self) -> Result<String, Error>;
+
+		/// Returns the manifest of the snapshot served by the node, if any.
+		#[rpc(name = "parity_snapshotManifest")]
+		fn snapshot_manifest(&self) -> Result<Option<SnapshotManifest>, Error>;
+
+		/// Returns a compressed chunk of the served snapshot by its hash.
+		#[rpc(name = "parity_snapshotChunk")]
+		fn snapshot_chunk(&self, H256) -> Result<Option<Bytes>, Error>;
 	}
 }
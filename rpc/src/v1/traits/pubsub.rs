@@ -0,0 +1,69 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Eth and Parity PUB-SUB rpc interfaces.
+
+use jsonrpc_core::Error;
+use jsonrpc_macros::pubsub::Subscriber;
+use jsonrpc_pubsub::SubscriptionId;
+
+use futures::BoxFuture;
+
+use v1::types::{pubsub, SyncProgress, H256};
+
+build_rpc_trait! {
+	/// Eth PUB-SUB rpc interface.
+	pub trait EthPubSub {
+		type Metadata;
+
+		#[pubsub(name = "eth_subscription")] {
+			/// Subscribe to Eth subscription.
+			#[rpc(name = "eth_subscribe")]
+			fn subscribe(&self, Self::Metadata, Subscriber<pubsub::Result>, pubsub::Kind);
+
+			/// Unsubscribe from existing Eth subscription.
+			#[rpc(name = "eth_unsubscribe")]
+			fn unsubscribe(&self, SubscriptionId) -> BoxFuture<bool, Error>;
+		}
+	}
+}
+
+build_rpc_trait! {
+	/// Parity-specific PUB-SUB rpc interface.
+	pub trait ParityPubSub {
+		type Metadata;
+
+		#[pubsub(name = "parity_syncProgress")] {
+			/// Subscribe to detailed sync progress notifications.
+			#[rpc(name = "parity_subscribeSyncProgress")]
+			fn subscribe_sync_progress(&self, Self::Metadata, Subscriber<SyncProgress>);
+
+			/// Unsubscribe from sync progress notifications.
+			#[rpc(name = "parity_unsubscribeSyncProgress")]
+			fn unsubscribe_sync_progress(&self, SubscriptionId) -> BoxFuture<bool, Error>;
+		}
+
+		#[pubsub(name = "parity_evictedTransactions")] {
+			/// Subscribe to hashes of transactions evicted from the queue for staying in it too long.
+			#[rpc(name = "parity_subscribeEvictedTransactions")]
+			fn subscribe_evicted_transactions(&self, Self::Metadata, Subscriber<Vec<H256>>);
+
+			/// Unsubscribe from evicted transactions notifications.
+			#[rpc(name = "parity_unsubscribeEvictedTransactions")]
+			fn unsubscribe_evicted_transactions(&self, SubscriptionId) -> BoxFuture<bool, Error>;
+		}
+	}
+}
@@ -23,22 +23,23 @@ use v1::types::{U256, Bytes, TransactionModification, ConfirmationRequest, Confi
 build_rpc_trait! {
 	/// Signer extension for confirmations rpc interface.
 	pub trait Signer {
+		type Metadata;
 
 		/// Returns a list of items to confirm.
 		#[rpc(name = "signer_requestsToConfirm")]
 		fn requests_to_confirm(&self) -> Result<Vec<ConfirmationRequest>, Error>;
 
 		/// Confirm specific request.
-		#[rpc(async, name = "signer_confirmRequest")]
-		fn confirm_request(&self, U256, TransactionModification, String) -> BoxFuture<ConfirmationResponse, Error>;
+		#[rpc(meta, name = "signer_confirmRequest")]
+		fn confirm_request(&self, Self::Metadata, U256, TransactionModification, String) -> BoxFuture<ConfirmationResponse, Error>;
 
 		/// Confirm specific request with token.
-		#[rpc(async, name = "signer_confirmRequestWithToken")]
-		fn confirm_request_with_token(&self, U256, TransactionModification, String) -> BoxFuture<ConfirmationResponseWithToken, Error>;
+		#[rpc(meta, name = "signer_confirmRequestWithToken")]
+		fn confirm_request_with_token(&self, Self::Metadata, U256, TransactionModification, String) -> BoxFuture<ConfirmationResponseWithToken, Error>;
 
 		/// Confirm specific request with already signed data.
-		#[rpc(name = "signer_confirmRequestRaw")]
-		fn confirm_request_raw(&self, U256, Bytes) -> Result<ConfirmationResponse, Error>;
+		#[rpc(meta, name = "signer_confirmRequestRaw")]
+		fn confirm_request_raw(&self, Self::Metadata, U256, Bytes) -> BoxFuture<ConfirmationResponse, Error>;
 
 		/// Reject the confirmation request.
 		#[rpc(name = "signer_rejectRequest")]
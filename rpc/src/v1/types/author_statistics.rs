@@ -0,0 +1,68 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::client::AuthorStatistics as EthAuthorStatistics;
+use v1::types::{H160, U256};
+
+/// Blocks sealed and turns missed by an author
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct AuthorStatistics {
+	/// Author address
+	pub author: H160,
+	/// Number of sealed blocks
+	#[serde(rename="sealedBlocks")]
+	pub sealed_blocks: U256,
+	/// Number of missed sealing turns (Aura steps)
+	#[serde(rename="missedSteps")]
+	pub missed_steps: U256,
+	/// Number of the last sealed block
+	#[serde(rename="lastBlock")]
+	pub last_block: Option<U256>,
+	/// Timestamp of the last sealed block
+	#[serde(rename="lastSeen")]
+	pub last_seen: Option<U256>,
+}
+
+impl From<EthAuthorStatistics> for AuthorStatistics {
+	fn from(s: EthAuthorStatistics) -> AuthorStatistics {
+		AuthorStatistics {
+			author: s.author.into(),
+			sealed_blocks: s.sealed_blocks.into(),
+			missed_steps: s.missed_steps.into(),
+			last_block: s.last_block.map(Into::into),
+			last_seen: s.last_seen.map(Into::into),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::AuthorStatistics;
+
+	#[test]
+	fn author_statistics_serialization() {
+		let s = AuthorStatistics {
+			author: 5.into(),
+			sealed_blocks: 10.into(),
+			missed_steps: 2.into(),
+			last_block: Some(100.into()),
+			last_seen: None,
+		};
+		let serialized = serde_json::to_string(&s).unwrap();
+		assert_eq!(serialized, r#"{"author":"0x0000000000000000000000000000000000000005","sealedBlocks":"0xa","missedSteps":"0x2","lastBlock":"0x64","lastSeen":null}"#);
+	}
+}
@@ -0,0 +1,45 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::client::BalanceChange as EthBalanceChange;
+use v1::types::{H256, U256};
+
+/// Account balance change
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct BalanceChange {
+	/// Block Number
+	#[serde(rename="blockNumber")]
+	pub block_number: U256,
+	/// Block Hash
+	#[serde(rename="blockHash")]
+	pub block_hash: H256,
+	/// Balance before the block
+	#[serde(rename="previousBalance")]
+	pub previous_balance: U256,
+	/// Balance after the block
+	pub balance: U256,
+}
+
+impl From<EthBalanceChange> for BalanceChange {
+	fn from(c: EthBalanceChange) -> BalanceChange {
+		BalanceChange {
+			block_number: c.block_number.into(),
+			block_hash: c.block_hash.into(),
+			previous_balance: c.previous_balance.into(),
+			balance: c.balance.into(),
+		}
+	}
+}
@@ -97,7 +97,7 @@ pub struct Block {
 }
 
 /// Block header representation.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Header {
 	/// Hash of the block
 	pub hash: Option<H256>,
@@ -153,7 +153,7 @@ pub type RichBlock = Rich<Block>;
 pub type RichHeader = Rich<Header>;
 
 /// Value representation with additional info
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Rich<T> {
 	/// Standard value.
 	pub inner: T,
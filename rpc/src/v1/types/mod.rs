@@ -18,6 +18,8 @@
 //! RPC types
 
 mod account_info;
+mod author_statistics;
+mod balance_change;
 mod block;
 mod block_number;
 mod bytes;
@@ -31,10 +33,14 @@ mod histogram;
 mod index;
 mod log;
 mod node_kind;
+mod pool;
 mod provenance;
+pub mod pubsub;
 mod receipt;
 mod rpc_settings;
+mod snapshot;
 mod sync;
+mod token_transfer;
 mod trace;
 mod trace_filter;
 mod transaction;
@@ -44,6 +50,8 @@ mod uint;
 mod work;
 
 pub use self::account_info::{AccountInfo, HwAccountInfo};
+pub use self::author_statistics::AuthorStatistics;
+pub use self::balance_change::BalanceChange;
 pub use self::bytes::Bytes;
 pub use self::block::{RichBlock, Block, BlockTransactions, Header, RichHeader, Rich};
 pub use self::block_number::BlockNumber;
@@ -60,13 +68,18 @@ pub use self::histogram::Histogram;
 pub use self::index::Index;
 pub use self::log::Log;
 pub use self::node_kind::{NodeKind, Availability, Capability};
+pub use self::pool::{PoolStatus, PoolTransactionInfo, TransactionPriority};
 pub use self::provenance::{Origin, DappId};
+pub use self::pubsub::{PubSubSyncStatus, SyncProgress, SyncStage};
 pub use self::receipt::Receipt;
 pub use self::rpc_settings::RpcSettings;
+pub use self::snapshot::SnapshotManifest;
 pub use self::sync::{
 	SyncStatus, SyncInfo, Peers, PeerInfo, PeerNetworkInfo, PeerProtocolsInfo,
-	TransactionStats, ChainStatus, EthProtocolInfo, PipProtocolInfo,
+	TransactionStats, ChainStatus, ReorgAlert, EthProtocolInfo, PipProtocolInfo,
+	PortMapping, ImportStatistics, NetworkTraffic,
 };
+pub use self::token_transfer::TokenTransfer;
 pub use self::trace::{LocalizedTrace, TraceResults};
 pub use self::trace_filter::TraceFilter;
 pub use self::transaction::{Transaction, RichRawTransaction, LocalTransactionStatus};
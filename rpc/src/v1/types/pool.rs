@@ -0,0 +1,237 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeStruct;
+use ethcore::miner;
+use v1::helpers::errors;
+use v1::types::{H256, U256};
+
+/// Transaction queue status
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct PoolStatus {
+	/// Number of pending transactions
+	pub pending: U256,
+	/// Number of future transactions
+	pub future: U256,
+	/// Limit of pending transactions
+	#[serde(rename="pendingLimit")]
+	pub pending_limit: U256,
+	/// Limit of future transactions
+	#[serde(rename="futureLimit")]
+	pub future_limit: U256,
+	/// Number of distinct senders
+	pub senders: U256,
+	/// Minimal gas price of accepted transactions
+	#[serde(rename="minGasPrice")]
+	pub min_gas_price: U256,
+	/// Minimal gas price of accepted transactions when the queue is full
+	#[serde(rename="effectiveMinGasPrice")]
+	pub effective_min_gas_price: U256,
+	/// Number of recently dropped transactions with known reasons
+	#[serde(rename="recentlyDropped")]
+	pub recently_dropped: U256,
+}
+
+impl From<miner::PoolStatus> for PoolStatus {
+	fn from(s: miner::PoolStatus) -> PoolStatus {
+		PoolStatus {
+			pending: s.pending.into(),
+			future: s.future.into(),
+			pending_limit: s.pending_limit.into(),
+			future_limit: s.future_limit.into(),
+			senders: s.senders.into(),
+			min_gas_price: s.minimal_gas_price.into(),
+			effective_min_gas_price: s.effective_minimal_gas_price.into(),
+			recently_dropped: s.recently_dropped.into(),
+		}
+	}
+}
+
+/// Priority of a queued transaction
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct TransactionPriority {
+	/// Number of sender penalties
+	pub penalties: U256,
+	/// Whether the transaction is local
+	pub local: bool,
+	/// Difference between transaction nonce and sender nonce
+	#[serde(rename="nonceHeight")]
+	pub nonce_height: U256,
+	/// Gas price
+	#[serde(rename="gasPrice")]
+	pub gas_price: U256,
+	/// Gas price used for ordering
+	#[serde(rename="effectiveGasPrice")]
+	pub effective_gas_price: U256,
+}
+
+impl From<miner::TransactionPriority> for TransactionPriority {
+	fn from(p: miner::TransactionPriority) -> TransactionPriority {
+		TransactionPriority {
+			penalties: p.penalties.into(),
+			local: p.local,
+			nonce_height: p.nonce_height.into(),
+			gas_price: p.gas_price.into(),
+			effective_gas_price: p.effective_gas_price.into(),
+		}
+	}
+}
+
+/// State of a transaction in the pool
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PoolTransactionInfo {
+	/// Transaction is pending
+	Pending(U256, TransactionPriority),
+	/// Transaction waits for a missing nonce
+	Future(U256, TransactionPriority, U256),
+	/// Transaction never got into the queue
+	Rejected(String),
+	/// Transaction was dropped because of limit
+	Dropped,
+	/// Transaction was replaced by transaction with higher gas price
+	Replaced(U256, H256),
+	/// Transaction is invalid
+	Invalid,
+	/// Transaction was canceled
+	Canceled,
+	/// Transaction occupied the queue too long
+	Evicted,
+	/// Transaction nonce was used by another mined transaction
+	Stale,
+	/// Transaction was mined
+	Mined,
+}
+
+impl Serialize for PoolTransactionInfo {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+		where S: Serializer
+	{
+		use self::PoolTransactionInfo::*;
+
+		let elems = match *self {
+			Pending(..) => 3,
+			Future(..) => 5,
+			Replaced(..) => 4,
+			_ => 2,
+		};
+
+		let status = "status";
+		let reason = "reason";
+
+		let mut struc = serializer.serialize_struct("PoolTransactionInfo", elems)?;
+		match *self {
+			Pending(ref position, ref priority) => {
+				struc.serialize_field(status, "pending")?;
+				struc.serialize_field("position", position)?;
+				struc.serialize_field("priority", priority)?;
+			},
+			Future(ref position, ref priority, ref nonce) => {
+				struc.serialize_field(status, "future")?;
+				struc.serialize_field("position", position)?;
+				struc.serialize_field("priority", priority)?;
+				struc.serialize_field("missingNonce", nonce)?;
+				struc.serialize_field(reason, &format!("Waiting for a transaction with nonce {:#x}.", nonce))?;
+			},
+			Rejected(ref error) => {
+				struc.serialize_field(status, "rejected")?;
+				struc.serialize_field(reason, error)?;
+			},
+			Dropped => {
+				struc.serialize_field(status, "dropped")?;
+				struc.serialize_field(reason, "Dropped because of the queue limit.")?;
+			},
+			Replaced(ref gas_price, ref hash) => {
+				struc.serialize_field(status, "replaced")?;
+				struc.serialize_field(reason, "Replaced by a transaction with higher gas price.")?;
+				struc.serialize_field("hash", hash)?;
+				struc.serialize_field("gasPrice", gas_price)?;
+			},
+			Invalid => {
+				struc.serialize_field(status, "invalid")?;
+				struc.serialize_field(reason, "Transaction became invalid.")?;
+			},
+			Canceled => {
+				struc.serialize_field(status, "canceled")?;
+				struc.serialize_field(reason, "Transaction was canceled.")?;
+			},
+			Evicted => {
+				struc.serialize_field(status, "evicted")?;
+				struc.serialize_field(reason, "Transaction occupied the queue too long.")?;
+			},
+			Stale => {
+				struc.serialize_field(status, "stale")?;
+				struc.serialize_field(reason, "Transaction nonce was used by another mined transaction.")?;
+			},
+			Mined => {
+				struc.serialize_field(status, "mined")?;
+				struc.serialize_field(reason, "Transaction was included in a block.")?;
+			},
+		}
+
+		struc.end()
+	}
+}
+
+impl From<miner::TransactionInfo> for PoolTransactionInfo {
+	fn from(info: miner::TransactionInfo) -> PoolTransactionInfo {
+		use ethcore::miner::TransactionInfo::*;
+		use ethcore::miner::DropReason;
+
+		match info {
+			Pending { position, priority } => PoolTransactionInfo::Pending(position.into(), priority.into()),
+			Future { position, priority, missing_nonce } => PoolTransactionInfo::Future(position.into(), priority.into(), missing_nonce.into()),
+			Dropped(reason) => match reason {
+				DropReason::Rejected(err) => PoolTransactionInfo::Rejected(errors::transaction_message(err)),
+				DropReason::LimitReached => PoolTransactionInfo::Dropped,
+				DropReason::Replaced(gas_price, hash) => PoolTransactionInfo::Replaced(gas_price.into(), hash.into()),
+				DropReason::Invalid => PoolTransactionInfo::Invalid,
+				DropReason::Canceled => PoolTransactionInfo::Canceled,
+				DropReason::Evicted => PoolTransactionInfo::Evicted,
+				DropReason::Stale => PoolTransactionInfo::Stale,
+				DropReason::Mined => PoolTransactionInfo::Mined,
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::{PoolTransactionInfo, TransactionPriority};
+
+	#[test]
+	fn should_serialize_pool_transaction_info() {
+		let priority = TransactionPriority {
+			penalties: 0.into(),
+			local: false,
+			nonce_height: 2.into(),
+			gas_price: 10.into(),
+			effective_gas_price: 10.into(),
+		};
+		let future = PoolTransactionInfo::Future(1.into(), priority, 5.into());
+		let rejected = PoolTransactionInfo::Rejected("Transaction nonce is too low. Try incrementing the nonce.".into());
+
+		assert_eq!(
+			serde_json::to_string(&future).unwrap(),
+			r#"{"status":"future","position":"0x1","priority":{"penalties":"0x0","local":false,"nonceHeight":"0x2","gasPrice":"0xa","effectiveGasPrice":"0xa"},"missingNonce":"0x5","reason":"Waiting for a transaction with nonce 0x5."}"#
+		);
+		assert_eq!(
+			serde_json::to_string(&rejected).unwrap(),
+			r#"{"status":"rejected","reason":"Transaction nonce is too low. Try incrementing the nonce."}"#
+		);
+	}
+}
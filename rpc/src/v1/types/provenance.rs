@@ -36,7 +36,7 @@ pub enum Origin {
 	/// WS server (includes session hash)
 	#[serde(rename="ws")]
 	Ws(H256),
-	/// Signer (includes session hash)
+	/// Signer (includes hash of the authorization token)
 	#[serde(rename="signer")]
 	Signer(H256),
 	/// Unknown
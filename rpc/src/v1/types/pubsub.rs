@@ -0,0 +1,187 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Pub-Sub types.
+
+use serde::{Serialize, Serializer};
+use v1::types::{RichHeader, SyncInfo, H256, U256};
+
+/// Subscription kind.
+#[derive(Debug, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub enum Kind {
+	/// New block headers subscription.
+	#[serde(rename="newHeads")]
+	NewHeads,
+	/// Logs subscription.
+	#[serde(rename="logs")]
+	Logs,
+	/// New Pending Transactions subscription.
+	#[serde(rename="newPendingTransactions")]
+	NewPendingTransactions,
+	/// Node syncing status subscription.
+	#[serde(rename="syncing")]
+	Syncing,
+}
+
+/// Subscription result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Result {
+	/// New block header.
+	Header(RichHeader),
+	/// New pending transaction hash.
+	TransactionHash(H256),
+	/// Syncing status.
+	Syncing(PubSubSyncStatus),
+}
+
+impl Serialize for Result {
+	fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+		where S: Serializer
+	{
+		match *self {
+			Result::Header(ref header) => header.serialize(serializer),
+			Result::TransactionHash(ref hash) => hash.serialize(serializer),
+			Result::Syncing(ref status) => status.serialize(serializer),
+		}
+	}
+}
+
+/// Syncing status notification (compatible with `eth_subscribe("syncing")`).
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct PubSubSyncStatus {
+	/// Is the node syncing.
+	pub syncing: bool,
+	/// Sync details (only present while syncing).
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub status: Option<SyncInfo>,
+}
+
+/// Current stage of the sync process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SyncStage {
+	/// Sync is complete, following the chain head.
+	#[serde(rename="idle")]
+	Idle,
+	/// Looking for peers to sync with.
+	#[serde(rename="waitingPeers")]
+	WaitingPeers,
+	/// Looking for a snapshot manifest.
+	#[serde(rename="snapshotManifest")]
+	SnapshotManifest,
+	/// Downloading and restoring state chunks of a snapshot.
+	#[serde(rename="stateChunks")]
+	StateChunks,
+	/// Downloading and restoring block chunks of a snapshot.
+	#[serde(rename="blockChunks")]
+	BlockChunks,
+	/// Downloading headers and bodies of recent blocks.
+	#[serde(rename="blocks")]
+	Blocks,
+	/// Downloading blocks preceding a restored snapshot.
+	#[serde(rename="ancientBlocks")]
+	AncientBlocks,
+}
+
+/// Detailed sync progress notification.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SyncProgress {
+	/// Current stage.
+	pub stage: SyncStage,
+	/// Block number the sync started at.
+	#[serde(rename="startingBlock")]
+	pub starting_block: U256,
+	/// Current best block number.
+	#[serde(rename="currentBlock")]
+	pub current_block: U256,
+	/// Highest known block number.
+	#[serde(rename="highestBlock")]
+	pub highest_block: U256,
+	/// Last imported ancient block number (if ancient blocks are being downloaded).
+	#[serde(rename="currentAncientBlock")]
+	pub current_ancient_block: Option<U256>,
+	/// Total number of snapshot chunks (if warping).
+	#[serde(rename="warpChunksAmount")]
+	pub warp_chunks_amount: Option<U256>,
+	/// Number of snapshot chunks processed (if warping).
+	#[serde(rename="warpChunksProcessed")]
+	pub warp_chunks_processed: Option<U256>,
+	/// Block import rate since the last notification.
+	#[serde(rename="blocksPerSecond")]
+	pub blocks_per_second: f64,
+	/// Snapshot chunk restoration rate since the last notification.
+	#[serde(rename="chunksPerSecond")]
+	pub chunks_per_second: f64,
+	/// Estimated number of seconds until current stage is complete (if known).
+	pub eta: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::{Kind, Result, PubSubSyncStatus, SyncProgress, SyncStage};
+	use v1::types::SyncInfo;
+
+	#[test]
+	fn should_deserialize_kind() {
+		assert_eq!(serde_json::from_str::<Kind>(r#""newHeads""#).unwrap(), Kind::NewHeads);
+		assert_eq!(serde_json::from_str::<Kind>(r#""logs""#).unwrap(), Kind::Logs);
+		assert_eq!(serde_json::from_str::<Kind>(r#""newPendingTransactions""#).unwrap(), Kind::NewPendingTransactions);
+		assert_eq!(serde_json::from_str::<Kind>(r#""syncing""#).unwrap(), Kind::Syncing);
+	}
+
+	#[test]
+	fn should_serialize_sync_status() {
+		let not_syncing = Result::Syncing(PubSubSyncStatus::default());
+		let syncing = Result::Syncing(PubSubSyncStatus {
+			syncing: true,
+			status: Some(SyncInfo::default()),
+		});
+
+		assert_eq!(serde_json::to_string(&not_syncing).unwrap(), r#"{"syncing":false}"#);
+		assert_eq!(
+			serde_json::to_string(&syncing).unwrap(),
+			r#"{"syncing":true,"status":{"startingBlock":"0x0","currentBlock":"0x0","highestBlock":"0x0","warpChunksAmount":null,"warpChunksProcessed":null}}"#
+		);
+	}
+
+	#[test]
+	fn should_serialize_transaction_hash() {
+		let hash = Result::TransactionHash(5.into());
+
+		assert_eq!(serde_json::to_string(&hash).unwrap(), r#""0x0000000000000000000000000000000000000000000000000000000000000005""#);
+	}
+
+	#[test]
+	fn should_serialize_sync_progress() {
+		let progress = SyncProgress {
+			stage: SyncStage::StateChunks,
+			starting_block: 0.into(),
+			current_block: 0.into(),
+			highest_block: 0x10.into(),
+			current_ancient_block: None,
+			warp_chunks_amount: Some(10.into()),
+			warp_chunks_processed: Some(4.into()),
+			blocks_per_second: 0.0,
+			chunks_per_second: 2.0,
+			eta: Some(3),
+		};
+
+		assert_eq!(
+			serde_json::to_string(&progress).unwrap(),
+			r#"{"stage":"stateChunks","startingBlock":"0x0","currentBlock":"0x0","highestBlock":"0x10","currentAncientBlock":null,"warpChunksAmount":"0xa","warpChunksProcessed":"0x4","blocksPerSecond":0.0,"chunksPerSecond":2.0,"eta":3}"#
+		);
+	}
+}
@@ -0,0 +1,62 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Snapshot manifest.
+
+use ethcore::snapshot::ManifestData;
+use util::sha3::Hashable;
+use v1::types::{H256, U256};
+
+/// Manifest of the snapshot served by the node.
+#[derive(Debug, Serialize)]
+pub struct SnapshotManifest {
+	/// Hash of the RLP encoded manifest.
+	pub hash: H256,
+	/// Snapshot format version.
+	pub version: U256,
+	/// Hashes of state chunks.
+	#[serde(rename="stateHashes")]
+	pub state_hashes: Vec<H256>,
+	/// Hashes of block chunks.
+	#[serde(rename="blockHashes")]
+	pub block_hashes: Vec<H256>,
+	/// State root at the snapshot block.
+	#[serde(rename="stateRoot")]
+	pub state_root: H256,
+	/// Number of the snapshot block.
+	#[serde(rename="blockNumber")]
+	pub block_number: U256,
+	/// Hash of the snapshot block.
+	#[serde(rename="blockHash")]
+	pub block_hash: H256,
+	/// Hash of the manifest the delta snapshot was taken from.
+	pub base: Option<H256>,
+}
+
+impl From<ManifestData> for SnapshotManifest {
+	fn from(manifest: ManifestData) -> Self {
+		SnapshotManifest {
+			hash: manifest.clone().into_rlp().sha3().into(),
+			version: manifest.version.into(),
+			state_hashes: manifest.state_hashes.into_iter().map(Into::into).collect(),
+			block_hashes: manifest.block_hashes.into_iter().map(Into::into).collect(),
+			state_root: manifest.state_root.into(),
+			block_number: manifest.block_number.into(),
+			block_hash: manifest.block_hash.into(),
+			base: manifest.base.map(Into::into),
+		}
+	}
+}
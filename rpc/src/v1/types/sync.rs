@@ -15,12 +15,13 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::BTreeMap;
-use ethsync::{self, PeerInfo as SyncPeerInfo, TransactionStats as SyncTransactionStats};
+use ethsync::{self, PeerInfo as SyncPeerInfo, TransactionStats as SyncTransactionStats, PortMapping as SyncPortMapping, NetworkTraffic as SyncNetworkTraffic};
 use serde::{Serialize, Serializer};
-use v1::types::{U256, H512};
+use ethcore::client::{ReorgAlert as EthReorgAlert, ImportStatistics as EthImportStatistics, ImportPercentiles as EthImportPercentiles};
+use v1::types::{U256, H256, H512};
 
 /// Sync info
-#[derive(Default, Debug, Serialize, PartialEq)]
+#[derive(Default, Debug, Clone, Serialize, PartialEq)]
 pub struct SyncInfo {
 	/// Starting block
 	#[serde(rename="startingBlock")]
@@ -48,6 +49,15 @@ pub struct Peers {
 	pub connected: usize,
 	/// Max number of peers
 	pub max: u32,
+	/// Max number of peers from a single IP address, zero if unlimited
+	#[serde(rename="maxPerIp")]
+	pub max_per_ip: u32,
+	/// Max number of peers from a single /24 (IPv4) or /64 (IPv6) subnet, zero if unlimited
+	#[serde(rename="maxPerSubnet")]
+	pub max_per_subnet: u32,
+	/// Number of peers dropped by the per-address limits
+	#[serde(rename="ipLimited")]
+	pub ip_limited: usize,
 	/// Detailed information on peers
 	pub peers: Vec<PeerInfo>,
 }
@@ -76,6 +86,12 @@ pub struct PeerNetworkInfo {
 	/// Local endpoint address
 	#[serde(rename="localAddress")]
 	pub local_address: String,
+	/// Packet bytes received from the peer
+	#[serde(rename="bytesReceived")]
+	pub bytes_received: u64,
+	/// Packet bytes sent to the peer
+	#[serde(rename="bytesSent")]
+	pub bytes_sent: u64,
 }
 
 /// Peer protocols information
@@ -168,6 +184,8 @@ impl From<SyncPeerInfo> for PeerInfo {
 			network: PeerNetworkInfo {
 				remote_address: p.remote_address,
 				local_address: p.local_address,
+				bytes_received: p.traffic.received,
+				bytes_sent: p.traffic.sent,
 			},
 			protocols: PeerProtocolsInfo {
 				eth: p.eth_info.map(Into::into),
@@ -197,11 +215,163 @@ pub struct ChainStatus {
 	pub block_gap: Option<(U256, U256)>,
 }
 
+/// Alert raised by a chain reorganisation deeper than allowed. Block import is paused until acknowledged.
+#[derive(Debug, Serialize)]
+pub struct ReorgAlert {
+	/// Hash of the block which would become the new best block.
+	#[serde(rename="blockHash")]
+	pub block_hash: H256,
+	/// Number of the block which would become the new best block.
+	#[serde(rename="blockNumber")]
+	pub block_number: U256,
+	/// Current best block.
+	#[serde(rename="bestBlockHash")]
+	pub best_block_hash: H256,
+	/// Best common ancestor of the current and the new best block.
+	pub ancestor: H256,
+	/// Number of canonical blocks which would be retracted.
+	pub depth: U256,
+	/// Maximal allowed reorganisation depth.
+	#[serde(rename="maxDepth")]
+	pub max_depth: U256,
+}
+
+impl From<EthReorgAlert> for ReorgAlert {
+	fn from(alert: EthReorgAlert) -> Self {
+		ReorgAlert {
+			block_hash: alert.block_hash.into(),
+			block_number: alert.block_number.into(),
+			best_block_hash: alert.best_block_hash.into(),
+			ancestor: alert.ancestor.into(),
+			depth: alert.depth.into(),
+			max_depth: alert.max_depth.into(),
+		}
+	}
+}
+
+/// Bytes transferred over the network.
+#[derive(Default, Debug, Serialize)]
+pub struct Traffic {
+	/// Bytes received.
+	pub received: u64,
+	/// Bytes sent.
+	pub sent: u64,
+}
+
+impl From<SyncNetworkTraffic> for Traffic {
+	fn from(t: SyncNetworkTraffic) -> Self {
+		Traffic {
+			received: t.received,
+			sent: t.sent,
+		}
+	}
+}
+
+/// Network traffic totals.
+#[derive(Default, Debug, Serialize)]
+pub struct NetworkTraffic {
+	/// Bytes received over all connections, including encryption and framing.
+	pub received: u64,
+	/// Bytes sent over all connections, including encryption and framing.
+	pub sent: u64,
+	/// Packet payload bytes per subprotocol, e.g. `eth`, `par` (snapshots) or `pip`.
+	pub protocols: BTreeMap<String, Traffic>,
+}
+
+/// Percentiles of a measured value.
+#[derive(Default, Debug, Serialize)]
+pub struct ImportPercentiles {
+	/// Median.
+	pub p50: u64,
+	/// 90th percentile.
+	pub p90: u64,
+	/// 99th percentile.
+	pub p99: u64,
+	/// Maximum.
+	pub max: u64,
+}
+
+impl From<EthImportPercentiles> for ImportPercentiles {
+	fn from(p: EthImportPercentiles) -> Self {
+		ImportPercentiles {
+			p50: p.p50,
+			p90: p.p90,
+			p99: p.p99,
+			max: p.max,
+		}
+	}
+}
+
+/// Rolling statistics of recently imported blocks.
+#[derive(Default, Debug, Serialize)]
+pub struct ImportStatistics {
+	/// Number of recently imported blocks the percentiles are computed from.
+	pub samples: u64,
+	/// Block verification time in microseconds.
+	#[serde(rename="verificationTime")]
+	pub verification_time: ImportPercentiles,
+	/// Block execution time in microseconds.
+	#[serde(rename="executionTime")]
+	pub execution_time: ImportPercentiles,
+	/// Database commit time in microseconds.
+	#[serde(rename="commitTime")]
+	pub commit_time: ImportPercentiles,
+	/// Gas processed per second.
+	#[serde(rename="gasPerSec")]
+	pub gas_per_sec: ImportPercentiles,
+	/// Number of blocks waiting in the verification queue.
+	#[serde(rename="queuedBlocks")]
+	pub queued_blocks: u64,
+	/// Gas used by the blocks waiting in the verification queue.
+	#[serde(rename="gasBacklog")]
+	pub gas_backlog: U256,
+}
+
+impl From<EthImportStatistics> for ImportStatistics {
+	fn from(s: EthImportStatistics) -> Self {
+		ImportStatistics {
+			samples: s.samples,
+			verification_time: s.verification_time.into(),
+			execution_time: s.execution_time.into(),
+			commit_time: s.commit_time.into(),
+			gas_per_sec: s.gas_per_sec.into(),
+			queued_blocks: s.queued_blocks,
+			gas_backlog: s.gas_backlog.into(),
+		}
+	}
+}
+
+/// Port mapping created on the NAT gateway.
+#[derive(Debug, Serialize)]
+pub struct PortMapping {
+	/// Mapping method: `upnp`, `natpmp` or `pcp`.
+	pub method: String,
+	/// External address and TCP port.
+	#[serde(rename="externalAddress")]
+	pub external_address: String,
+	/// External UDP port.
+	#[serde(rename="udpPort")]
+	pub udp_port: u16,
+	/// Mapping lifetime in seconds, renewed automatically. `null` for permanent mappings.
+	pub lifetime: Option<u32>,
+}
+
+impl From<SyncPortMapping> for PortMapping {
+	fn from(mapping: SyncPortMapping) -> Self {
+		PortMapping {
+			method: mapping.method,
+			external_address: mapping.external_address,
+			udp_port: mapping.udp_port,
+			lifetime: mapping.lifetime,
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use serde_json;
 	use std::collections::BTreeMap;
-	use super::{SyncInfo, SyncStatus, Peers, TransactionStats, ChainStatus};
+	use super::{SyncInfo, SyncStatus, Peers, TransactionStats, ChainStatus, ReorgAlert};
 
 	#[test]
 	fn test_serialize_sync_info() {
@@ -240,6 +410,21 @@ mod tests {
 		assert_eq!(serialized, r#"{"blockGap":["0x1","0x5"]}"#);
 	}
 
+	#[test]
+	fn test_serialize_reorg_alert() {
+		let alert = ReorgAlert {
+			block_hash: 1.into(),
+			block_number: 20.into(),
+			best_block_hash: 2.into(),
+			ancestor: 3.into(),
+			depth: 10.into(),
+			max_depth: 5.into(),
+		};
+
+		let serialized = serde_json::to_string(&alert).unwrap();
+		assert_eq!(serialized, r#"{"blockHash":"0x0000000000000000000000000000000000000000000000000000000000000001","blockNumber":"0x14","bestBlockHash":"0x0000000000000000000000000000000000000000000000000000000000000002","ancestor":"0x0000000000000000000000000000000000000000000000000000000000000003","depth":"0xa","maxDepth":"0x5"}"#);
+	}
+
 	#[test]
 	fn test_serialize_transaction_stats() {
 		let stats = TransactionStats {
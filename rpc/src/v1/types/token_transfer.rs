@@ -0,0 +1,87 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::client::TokenTransfer as EthTokenTransfer;
+use v1::types::{H160, H256, U256};
+
+/// ERC-20 token transfer
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct TokenTransfer {
+	/// Token contract
+	pub token: H160,
+	/// Sender
+	pub from: H160,
+	/// Recipient
+	pub to: H160,
+	/// Transferred amount
+	pub value: U256,
+	/// Block Number
+	#[serde(rename="blockNumber")]
+	pub block_number: U256,
+	/// Block Hash
+	#[serde(rename="blockHash")]
+	pub block_hash: H256,
+	/// Transaction Hash
+	#[serde(rename="transactionHash")]
+	pub transaction_hash: H256,
+	/// Transaction Index
+	#[serde(rename="transactionIndex")]
+	pub transaction_index: U256,
+	/// Log Index in Block
+	#[serde(rename="logIndex")]
+	pub log_index: U256,
+}
+
+impl From<EthTokenTransfer> for TokenTransfer {
+	fn from(t: EthTokenTransfer) -> TokenTransfer {
+		TokenTransfer {
+			token: t.token.into(),
+			from: t.from.into(),
+			to: t.to.into(),
+			value: t.value.into(),
+			block_number: t.block_number.into(),
+			block_hash: t.block_hash.into(),
+			transaction_hash: t.transaction_hash.into(),
+			transaction_index: t.transaction_index.into(),
+			log_index: t.log_index.into(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use ethcore::client::TokenTransfer as EthTokenTransfer;
+	use super::TokenTransfer;
+
+	#[test]
+	fn token_transfer_serialization() {
+		let transfer: TokenTransfer = EthTokenTransfer {
+			token: 1.into(),
+			from: 2.into(),
+			to: 3.into(),
+			value: 1000.into(),
+			block_number: 5,
+			block_hash: 6.into(),
+			transaction_hash: 7.into(),
+			transaction_index: 1,
+			log_index: 2,
+		}.into();
+
+		let serialized = serde_json::to_string(&transfer).unwrap();
+		assert_eq!(serialized, r#"{"token":"0x0000000000000000000000000000000000000001","from":"0x0000000000000000000000000000000000000002","to":"0x0000000000000000000000000000000000000003","value":"0x3e8","blockNumber":"0x5","blockHash":"0x0000000000000000000000000000000000000000000000000000000000000006","transactionHash":"0x0000000000000000000000000000000000000000000000000000000000000007","transactionIndex":"0x1","logIndex":"0x2"}"#);
+	}
+}
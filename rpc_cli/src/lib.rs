@@ -10,7 +10,7 @@ extern crate parity_rpc_client as client;
 use rpc::v1::types::{U256, ConfirmationRequest};
 use client::signer_client::SignerRpc;
 use std::io::{Write, BufRead, BufReader, stdout, stdin};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs::File;
 
 use futures::Future;
@@ -115,56 +115,86 @@ fn reject_transaction(
 	}).wait()?
 }
 
+fn read_password(pwfile: Option<PathBuf>) -> Result<String, String> {
+	match pwfile {
+		Some(pwfile) => {
+			match File::open(pwfile) {
+				Ok(fd) => {
+					match BufReader::new(fd).lines().next() {
+						Some(Ok(line)) => Ok(line),
+						_ => Err(format!("No password in file"))
+					}
+				},
+				Err(e) =>
+					Err(format!("Could not open password file: {}", e))
+			}
+		}
+		None => {
+			rpassword::prompt_password_stdout("Password: ").map_err(|e| {
+				format!("{}", e)
+			})
+		}
+	}
+}
+
+/// Connects to the Trusted Signer over its IPC socket.
+#[cfg(unix)]
+fn connect_local(
+	ipc_path: &Path, _signerport: u16, authfile: &PathBuf
+) -> Result<SignerRpc, String> {
+	SignerRpc::new_ipc(ipc_path, authfile).map_err(|err| {
+		format!("Could not connect to Trusted Signer at {}: {:?}", ipc_path.display(), err)
+	})
+}
+
+/// IPC sockets are not supported here, connects to the Trusted Signer over `WebSockets`.
+#[cfg(not(unix))]
+fn connect_local(
+	_ipc_path: &Path, signerport: u16, authfile: &PathBuf
+) -> Result<SignerRpc, String> {
+	let addr = &format!("ws://127.0.0.1:{}", signerport);
+	SignerRpc::new(addr, authfile).map_err(|err| {
+		format!("{:?}", err)
+	})
+}
+
 // cmds
 
 pub fn signer_list(
-	signerport: u16, authfile: PathBuf
+	signerport: u16, ipc_path: PathBuf, authfile: PathBuf
 ) -> Result<String, String> {
-	let addr = &format!("ws://127.0.0.1:{}", signerport);
-	let mut signer = SignerRpc::new(addr, &authfile).map_err(|err| {
-		format!("{:?}", err)
-	})?;
+	let mut signer = connect_local(&ipc_path, signerport, &authfile)?;
 	list_transactions(&mut signer)
 }
 
 pub fn signer_reject(
-	id: Option<usize>, signerport: u16, authfile: PathBuf
+	id: Option<usize>, signerport: u16, ipc_path: PathBuf, authfile: PathBuf
 ) -> Result<String, String> {
 	let id = id.ok_or(format!("id required for signer reject"))?;
-	let addr = &format!("ws://127.0.0.1:{}", signerport);
-	let mut signer = SignerRpc::new(addr, &authfile).map_err(|err| {
-		format!("{:?}", err)
-	})?;
+	let mut signer = connect_local(&ipc_path, signerport, &authfile)?;
 	reject_transaction(&mut signer, U256::from(id))
 }
 
+pub fn signer_approve(
+	id: Option<usize>,
+	pwfile: Option<PathBuf>,
+	signerport: u16,
+	ipc_path: PathBuf,
+	authfile: PathBuf
+) -> Result<String, String> {
+	let id = id.ok_or(format!("id required for signer approve"))?;
+	let password = read_password(pwfile)?;
+	let mut signer = connect_local(&ipc_path, signerport, &authfile)?;
+	sign_transaction(&mut signer, U256::from(id), &password)
+}
+
 pub fn signer_sign(
 	id: Option<usize>,
 	pwfile: Option<PathBuf>,
 	signerport: u16,
 	authfile: PathBuf
 ) -> Result<String, String> {
-	let password;
-	match pwfile {
-		Some(pwfile) => {
-			match File::open(pwfile) {
-				Ok(fd) => {
-					match BufReader::new(fd).lines().next() {
-						Some(Ok(line)) => password = line,
-						_ => return Err(format!("No password in file"))
-					}
-				},
-				Err(e) =>
-					return Err(format!("Could not open password file: {}", e))
-			}
-		}
-		None => {
-			password = match rpassword::prompt_password_stdout("Password: ") {
-				Ok(p) => p,
-				Err(e) => return Err(format!("{}", e)),
-			}
-		}
-	}
+	let password = read_password(pwfile)?;
 
 	let addr = &format!("ws://127.0.0.1:{}", signerport);
 	let mut signer = SignerRpc::new(addr, &authfile).map_err(|err| {
@@ -1,7 +1,11 @@
 extern crate jsonrpc_core;
 
 use std::fmt::{Debug, Formatter, Error as FmtError};
-use std::io::{BufReader, BufRead};
+use std::io::{self, BufReader, BufRead};
+#[cfg(unix)]
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::collections::BTreeMap;
@@ -9,6 +13,8 @@ use std::thread;
 use std::time;
 
 use std::path::PathBuf;
+#[cfg(unix)]
+use std::path::Path;
 use util::{Hashable, Mutex};
 use url::Url;
 use std::fs::File;
@@ -68,12 +74,9 @@ impl Handler for RpcHandler {
 	fn build_request(&mut self, url: &Url) -> WsResult<Request> {
 		match Request::from_url(url) {
 			Ok(mut r) => {
-				let timestamp = time::UNIX_EPOCH.elapsed().map_err(|err| {
+				let proto = auth_protocol(&self.auth_code).map_err(|err| {
 					WsError::new(WsErrorKind::Internal, format!("{}", err))
 				})?;
-				let secs = timestamp.as_secs();
-				let hashed = format!("{}:{}", self.auth_code, secs).sha3();
-				let proto = format!("{:?}_{}", hashed, secs);
 				r.add_protocol(&proto);
 				Ok(r)
 			},
@@ -94,7 +97,7 @@ impl Handler for RpcHandler {
 		match (self.complete.take(), self.out.take()) {
 			(Some(c), Some(out)) => {
 				let res = c.send(Ok(Rpc {
-					out: out,
+					out: Transport::Ws(out),
 					counter: AtomicUsize::new(0),
 					pending: self.pending.clone(),
 				}));
@@ -110,10 +113,40 @@ impl Handler for RpcHandler {
 		}
 	}
 	fn on_message(&mut self, msg: Message) -> WsResult<()> {
+		self.pending.resolve(&msg.to_string());
+		Ok(())
+	}
+}
+
+/// Keeping track of issued requests to be matched up with responses
+#[derive(Clone)]
+struct Pending(
+	Arc<Mutex<BTreeMap<usize, Complete<Result<JsonValue, RpcError>>>>>
+);
+
+impl Pending {
+	fn new() -> Self {
+		Pending(Arc::new(Mutex::new(BTreeMap::new())))
+	}
+	fn insert(&mut self, k: usize, v: Complete<Result<JsonValue, RpcError>>) {
+		self.0.lock().insert(k, v);
+	}
+	fn remove(
+		&mut self,
+		k: usize
+	) -> Option<Complete<Result<JsonValue, RpcError>>> {
+		self.0.lock().remove(&k)
+	}
+	/// Cancels all requests still waiting for a response
+	#[cfg(unix)]
+	fn cancel_all(&mut self) {
+		self.0.lock().clear();
+	}
+	/// Completes the request the given response message belongs to
+	fn resolve(&mut self, string: &str) {
 		let ret: Result<JsonValue, JsonRpcError>;
 		let response_id;
-		let string = &msg.to_string();
-		match json::from_str::<Output>(&string) {
+		match json::from_str::<Output>(string) {
 			Ok(Output::Success(Success { result, id: Id::Num(id), .. })) =>
 			{
 				ret = Ok(result);
@@ -130,7 +163,7 @@ impl Handler for RpcHandler {
 					string,
 					e
 				);
-				return Ok(())
+				return
 			},
 			_ => {
 				warn!(
@@ -138,11 +171,11 @@ impl Handler for RpcHandler {
 					"recieved invalid message: {}",
 					string
 				);
-				return Ok(())
+				return
 			}
 		}
 
-		match self.pending.remove(response_id) {
+		match self.remove(response_id) {
 			Some(c) => if let Err(_) = c.send(ret.map_err(|err| RpcError::JsonRpc(err))) {
 				warn!(target: "rpc-client", "Unable to send response.")
 			},
@@ -152,29 +185,14 @@ impl Handler for RpcHandler {
 				response_id
 			),
 		}
-		Ok(())
 	}
 }
 
-/// Keeping track of issued requests to be matched up with responses
-#[derive(Clone)]
-struct Pending(
-	Arc<Mutex<BTreeMap<usize, Complete<Result<JsonValue, RpcError>>>>>
-);
-
-impl Pending {
-	fn new() -> Self {
-		Pending(Arc::new(Mutex::new(BTreeMap::new())))
-	}
-	fn insert(&mut self, k: usize, v: Complete<Result<JsonValue, RpcError>>) {
-		self.0.lock().insert(k, v);
-	}
-	fn remove(
-		&mut self,
-		k: usize
-	) -> Option<Complete<Result<JsonValue, RpcError>>> {
-		self.0.lock().remove(&k)
-	}
+/// Proof of knowing the auth code, sent when opening a connection
+fn auth_protocol(auth_code: &str) -> Result<String, time::SystemTimeError> {
+	let secs = time::UNIX_EPOCH.elapsed()?.as_secs();
+	let hashed = format!("{}:{}", auth_code, secs).sha3();
+	Ok(format!("{:?}_{}", hashed, secs))
 }
 
 fn get_authcode(path: &PathBuf) -> Result<String, RpcError> {
@@ -191,9 +209,16 @@ fn get_authcode(path: &PathBuf) -> Result<String, RpcError> {
 	Err(RpcError::NoAuthCode)
 }
 
+/// The way requests are sent to the server
+enum Transport {
+	Ws(Sender),
+	#[cfg(unix)]
+	Ipc(UnixStream),
+}
+
 /// The handle to the connection
 pub struct Rpc {
-	out: Sender,
+	out: Transport,
 	counter: AtomicUsize,
 	pending: Pending,
 }
@@ -204,6 +229,46 @@ impl Rpc {
 		let rpc = Self::connect(url, authpath).map(|rpc| rpc).wait()?;
 		rpc
 	}
+	/// Blocking, returns a new connection over the Signer IPC socket or RpcError
+	#[cfg(unix)]
+	pub fn new_ipc(path: &Path, authpath: &PathBuf) -> Result<Self, RpcError> {
+		let code = get_authcode(authpath)?;
+		let proto = auth_protocol(&code).map_err(|err| {
+			RpcError::IoError(io::Error::new(io::ErrorKind::Other, err))
+		})?;
+
+		let mut stream = UnixStream::connect(path)?;
+		writeln!(stream, "{}", proto)?;
+		let mut lines = BufReader::new(stream.try_clone()?).lines();
+		// The server echoes the protocol line once authorized
+		// and closes the connection otherwise.
+		match lines.next() {
+			Some(Ok(ref line)) if *line == proto => {},
+			Some(Err(err)) => return Err(RpcError::IoError(err)),
+			_ => return Err(RpcError::Unauthorized),
+		}
+
+		let pending = Pending::new();
+		let mut p = pending.clone();
+		thread::spawn(move || {
+			for line in lines {
+				match line {
+					Ok(line) => p.resolve(&line),
+					Err(err) => {
+						warn!(target: "rpc-client", "Error reading from IPC socket: {}", err);
+						break;
+					},
+				}
+			}
+			p.cancel_all();
+		});
+
+		Ok(Rpc {
+			out: Transport::Ipc(stream),
+			counter: AtomicUsize::new(0),
+			pending: pending,
+		})
+	}
 	/// Non-blocking, returns a future
 	pub fn connect(
 		url: &str, authpath: &PathBuf
@@ -261,7 +326,16 @@ impl Rpc {
 
 		let serialized = json::to_string(&request)
 			.expect("request is serializable");
-		let _ = self.out.send(serialized);
+		match self.out {
+			Transport::Ws(ref out) => {
+				let _ = out.send(serialized);
+			},
+			// the request is canceled once the reader notices a broken connection.
+			#[cfg(unix)]
+			Transport::Ipc(ref mut stream) => {
+				let _ = writeln!(stream, "{}", serialized);
+			},
+		}
 
 		p.map(|result| {
 			match result {
@@ -281,9 +355,11 @@ pub enum RpcError {
 	MalformedResponse(String),
 	JsonRpc(JsonRpcError),
 	WsError(WsError),
+	IoError(io::Error),
 	Canceled(Canceled),
 	UnexpectedId,
 	NoAuthCode,
+	Unauthorized,
 }
 
 impl Debug for RpcError {
@@ -299,12 +375,16 @@ impl Debug for RpcError {
 				=> write!(f, "JsonRpc error: {:?}", json),
 			RpcError::WsError(ref s)
 				=> write!(f, "Websocket error: {}", s),
+			RpcError::IoError(ref s)
+				=> write!(f, "IO error: {}", s),
 			RpcError::Canceled(ref s)
 				=> write!(f, "Futures error: {:?}", s),
 			RpcError::UnexpectedId
 				=> write!(f, "Unexpected response id"),
 			RpcError::NoAuthCode
 				=> write!(f, "No authcodes available"),
+			RpcError::Unauthorized
+				=> write!(f, "Connection was not authorized, make sure the authcodes are valid"),
 		}
 	}
 }
@@ -321,6 +401,12 @@ impl From<WsError> for RpcError {
 	}
 }
 
+impl From<io::Error> for RpcError {
+	fn from(err: io::Error) -> RpcError {
+		RpcError::IoError(err)
+	}
+}
+
 impl From<Canceled> for RpcError {
 	fn from(err: Canceled) -> RpcError {
 		RpcError::Canceled(err)
@@ -68,4 +68,33 @@ mod tests {
 		}).wait();
 	}
 
+	#[cfg(unix)]
+	#[test]
+	fn test_ipc_authcode_correct() {
+		let (srv, _, mut authcodes) = ethcore_signer::tests::serve();
+
+		let _ = authcodes.generate_new();
+		authcodes.to_file(&authcodes.path).unwrap();
+
+		let conn = Rpc::new_ipc(&srv.ipc_path, authcodes.path.as_path());
+
+		assert!(conn.is_ok());
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn test_ipc_authcode_incorrect() {
+		use std::fs::File;
+		use std::io::Write;
+		use tempdir::TempDir;
+
+		let (srv, _, _) = ethcore_signer::tests::serve();
+		let dir = TempDir::new("authcodes").unwrap();
+		let path = dir.path().join("authcodes");
+		writeln!(File::create(&path).unwrap(), "invalid;0").unwrap();
+
+		let conn = Rpc::new_ipc(&srv.ipc_path, &path);
+
+		assert!(matches!(conn, Err(RpcError::Unauthorized)));
+	}
 }
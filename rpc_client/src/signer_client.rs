@@ -3,6 +3,8 @@ use rpc::v1::types::{ConfirmationRequest, TransactionModification, U256, Transac
 use serde;
 use serde_json::{Value as JsonValue, to_value};
 use std::path::PathBuf;
+#[cfg(unix)]
+use std::path::Path;
 use futures::{BoxFuture, Canceled};
 
 pub struct SignerRpc {
@@ -13,6 +15,10 @@ impl SignerRpc {
 	pub fn new(url: &str, authfile: &PathBuf) -> Result<Self, RpcError> {
 		Ok(SignerRpc { rpc: Rpc::new(&url, authfile)? })
 	}
+	#[cfg(unix)]
+	pub fn new_ipc(path: &Path, authfile: &PathBuf) -> Result<Self, RpcError> {
+		Ok(SignerRpc { rpc: Rpc::new_ipc(path, authfile)? })
+	}
 	pub fn requests_to_confirm(&mut self) ->
 		BoxFuture<Result<Vec<ConfirmationRequest>, RpcError>, Canceled>
 	{
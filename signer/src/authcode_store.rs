@@ -153,11 +153,18 @@ impl<T: TimeProvider> AuthCodes<T> {
 	/// Updates this hash last used field in case it's valid.
 	#[cfg_attr(feature="dev", allow(wrong_self_convention))]
 	pub fn is_valid(&mut self, hash: &H256, time: u64) -> bool {
+		self.authorize(hash, time).is_some()
+	}
+
+	/// Checks if given hash is correct authcode of `SignerUI`
+	/// and returns the hash identifying the code it was created with.
+	/// Updates this hash last used field in case it's valid.
+	pub fn authorize(&mut self, hash: &H256, time: u64) -> Option<H256> {
 		let now = self.now.now();
 		// check time
 		if time >= now + TIME_THRESHOLD || time <= now - TIME_THRESHOLD {
 			warn!(target: "signer", "Received old authentication request. ({} vs {})", now, time);
-			return false;
+			return None;
 		}
 
 		let as_token = |code| format!("{}:{}", code, time).sha3();
@@ -168,19 +175,20 @@ impl<T: TimeProvider> AuthCodes<T> {
 			// Initial token can be used only once.
 			if initial {
 				let _ = self.generate_new();
+				return Some(INITIAL_TOKEN.sha3());
 			}
-			return initial;
+			return None;
 		}
 
 		// look for code
 		for mut code in &mut self.codes {
 			if &as_token(&code.code) == hash {
 				code.last_used_at = Some(time::Duration::from_secs(now));
-				return true;
+				return Some(code.code.sha3());
 			}
 		}
 
-		false
+		None
 	}
 
 	/// Generates and returns a new code that can be used by `SignerUIs`
@@ -267,6 +275,24 @@ mod tests {
 		assert_eq!(res, true);
 	}
 
+	#[test]
+	fn should_identify_code_used_at_different_times() {
+		// given
+		let code = "23521352asdfasdfadf";
+		let other = "09876543asdfasdfadf";
+		let mut codes = AuthCodes::new(vec![code.into(), other.into()], || 100);
+
+		// when
+		let res1 = codes.authorize(&generate_hash(code, 99), 99);
+		let res2 = codes.authorize(&generate_hash(code, 101), 101);
+		let res3 = codes.authorize(&generate_hash(other, 101), 101);
+
+		// then
+		assert_eq!(res1, Some(code.sha3()));
+		assert_eq!(res1, res2);
+		assert_eq!(res3, Some(other.sha3()));
+	}
+
 	#[test]
 	fn should_return_false_if_code_is_unknown() {
 		// given
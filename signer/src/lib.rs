@@ -23,7 +23,8 @@
 //! This module manages your private keys and accounts/identities
 //! that can be used within Dapps.
 //!
-//! It exposes API (over `WebSockets`) accessed by Signer UIs.
+//! It exposes API (over `WebSockets`) accessed by Signer UIs
+//! and optionally over a local IPC socket used by command line tools.
 //! Each transaction sent by Dapp is broadcasted to Signer UIs
 //! and their responsibility is to confirm (or confirm and sign)
 //! the transaction for you.
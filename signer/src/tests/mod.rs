@@ -15,6 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use devtools::http_client;
@@ -53,6 +54,8 @@ pub struct ServerLoop {
 	pub server: Server,
 	/// RPC Event Loop
 	pub event_loop: RpcEventLoop,
+	/// Path of the Signer IPC socket
+	pub ipc_path: PathBuf,
 }
 
 impl Deref for ServerLoop {
@@ -68,7 +71,8 @@ pub fn serve() -> (ServerLoop, usize, GuardedAuthCodes) {
 	let mut path = RandomTempPath::new();
 	path.panic_on_drop_failure = false;
 	let queue = Arc::new(ConfirmationsQueue::default());
-	let builder = ServerBuilder::new(queue, path.to_path_buf());
+	let ipc_path = PathBuf::from(format!("{}.ipc", path.as_str()));
+	let builder = ServerBuilder::new(queue, path.to_path_buf()).ipc_path(ipc_path.clone());
 	let port = 35000 + rand::random::<usize>() % 10000;
 	let event_loop = RpcEventLoop::spawn().unwrap();
 	let io = IoHandler::default();
@@ -77,6 +81,7 @@ pub fn serve() -> (ServerLoop, usize, GuardedAuthCodes) {
 	let res = ServerLoop {
 		server: server,
 		event_loop: event_loop,
+		ipc_path: ipc_path,
 	};
 
 	(res, port, GuardedAuthCodes {
@@ -303,4 +308,51 @@ mod testing {
 		assert_eq!(response2.status, "HTTP/1.1 403 FORBIDDEN".to_owned());
 		http_client::assert_security_headers_present(&response2.headers, None);
 	}
+
+	#[cfg(unix)]
+	#[test]
+	fn should_close_ipc_connection_if_authorization_is_incorrect() {
+		use std::io::{BufRead, BufReader, Write};
+		use std::os::unix::net::UnixStream;
+
+		// given
+		let server = serve().0;
+		let mut stream = UnixStream::connect(&server.ipc_path).unwrap();
+
+		// when
+		writeln!(stream, "{:?}_{}", "invalid".sha3(), 1).unwrap();
+		let mut response = String::new();
+		BufReader::new(stream).read_line(&mut response).unwrap();
+
+		// then
+		assert_eq!(response, "");
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn should_handle_ipc_requests_if_authorization_is_correct() {
+		use std::io::{BufRead, BufReader, Write};
+		use std::os::unix::net::UnixStream;
+
+		// given
+		let (server, _, mut authcodes) = serve();
+		let code = authcodes.generate_new().unwrap().replace("-", "");
+		authcodes.to_file(&authcodes.path).unwrap();
+		let timestamp = time::UNIX_EPOCH.elapsed().unwrap().as_secs();
+		let mut stream = UnixStream::connect(&server.ipc_path).unwrap();
+		let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+		// when
+		let protocol = format!("{:?}_{}", format!("{}:{}", code, timestamp).sha3(), timestamp);
+		writeln!(stream, "{}", protocol).unwrap();
+		let mut handshake = String::new();
+		reader.read_line(&mut handshake).unwrap();
+		writeln!(stream, r#"{{"jsonrpc":"2.0","method":"signer_requestsToConfirm","params":[],"id":1}}"#).unwrap();
+		let mut response = String::new();
+		reader.read_line(&mut response).unwrap();
+
+		// then
+		assert_eq!(handshake.trim(), protocol);
+		assert!(response.contains("Method not found"), "{}", response);
+	}
 }
@@ -0,0 +1,183 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Local IPC endpoint of the Trusted Signer.
+//!
+//! Each connection starts with a line carrying the hashed authorization token
+//! in the same `<hash>_<timestamp>` form as the `WebSockets` protocol header.
+//! The server echoes the line back once authorized and then handles
+//! newline-delimited JSON-RPC requests.
+
+pub use self::imp::Server;
+
+#[cfg(unix)]
+mod imp {
+	use std::io::{self, BufRead, BufReader, Write};
+	use std::fs;
+	use std::os::unix::fs::PermissionsExt;
+	use std::os::unix::net::{UnixListener, UnixStream};
+	use std::path::{Path, PathBuf};
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use std::thread;
+
+	use jsonrpc_core::{Metadata, Middleware, MetaIoHandler};
+	use jsonrpc_core::futures::Future;
+	use jsonrpc_server_utils::tokio_core::reactor::Remote;
+	use util::Mutex;
+
+	use super::super::session::{auth_token_hash, MetaExtractor};
+
+	/// Signer IPC server, stops listening and removes the socket when dropped.
+	pub struct Server {
+		path: PathBuf,
+		closing: Arc<AtomicBool>,
+		handle: Option<thread::JoinHandle<()>>,
+	}
+
+	impl Server {
+		/// Starts listening on the socket at given path, accessible to the owner only.
+		pub fn start<M: Metadata, S: Middleware<M>, T: MetaExtractor<M>>(
+			path: &Path,
+			handler: Arc<MetaIoHandler<M, S>>,
+			remote: Remote,
+			authcodes_path: PathBuf,
+			meta_extractor: T,
+		) -> io::Result<Self> {
+			if path.exists() {
+				// make sure not to take over the socket of a running instance.
+				if UnixStream::connect(path).is_ok() {
+					return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("{} is in use", path.display())));
+				}
+				fs::remove_file(path)?;
+			}
+
+			let listener = UnixListener::bind(path)?;
+			fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+
+			let closing = Arc::new(AtomicBool::new(false));
+			let c = closing.clone();
+			let handle = thread::spawn(move || {
+				for stream in listener.incoming() {
+					if c.load(Ordering::SeqCst) {
+						break;
+					}
+
+					match stream {
+						Ok(stream) => {
+							let handler = handler.clone();
+							let remote = remote.clone();
+							let authcodes_path = authcodes_path.clone();
+							let meta_extractor = meta_extractor.clone();
+							thread::spawn(move || {
+								if let Err(e) = handle_connection(stream, handler, remote, &authcodes_path, meta_extractor) {
+									debug!(target: "signer", "IPC connection closed: {:?}", e);
+								}
+							});
+						},
+						Err(e) => warn!(target: "signer", "Error accepting IPC connection: {:?}", e),
+					}
+				}
+			});
+
+			Ok(Server {
+				path: path.to_owned(),
+				closing: closing,
+				handle: Some(handle),
+			})
+		}
+	}
+
+	fn handle_connection<M: Metadata, S: Middleware<M>, T: MetaExtractor<M>>(
+		stream: UnixStream,
+		handler: Arc<MetaIoHandler<M, S>>,
+		remote: Remote,
+		authcodes_path: &Path,
+		meta_extractor: T,
+	) -> io::Result<()> {
+		let out = Arc::new(Mutex::new(stream.try_clone()?));
+		let mut lines = BufReader::new(stream).lines();
+
+		let protocol = match lines.next() {
+			Some(line) => line?,
+			None => return Ok(()),
+		};
+		let session_id = match auth_token_hash(authcodes_path, Ok(vec![protocol.as_str()])) {
+			Some(auth) => auth,
+			None => {
+				info!(target: "signer", "Unauthorized IPC connection to Signer API blocked.");
+				return Ok(());
+			},
+		};
+		writeln!(out.lock(), "{}", protocol)?;
+
+		for line in lines {
+			let line = line?;
+			let out = out.clone();
+			let metadata = meta_extractor.extract_metadata(&session_id);
+
+			let future = handler.handle_request(&line, metadata).map(move |response| {
+				if let Some(result) = response {
+					if let Err(e) = writeln!(out.lock(), "{}", result) {
+						warn!(target: "signer", "Error while sending response: {:?}", e);
+					}
+				}
+			});
+			remote.spawn(move |_| future);
+		}
+
+		Ok(())
+	}
+
+	impl Drop for Server {
+		fn drop(&mut self) {
+			self.closing.store(true, Ordering::SeqCst);
+			// wake up the listener so it notices it's closing.
+			let _ = UnixStream::connect(&self.path);
+			self.handle.take().unwrap().join().unwrap();
+			let _ = fs::remove_file(&self.path);
+		}
+	}
+}
+
+#[cfg(not(unix))]
+mod imp {
+	use std::io;
+	use std::path::{Path, PathBuf};
+	use std::sync::Arc;
+
+	use jsonrpc_core::{Metadata, Middleware, MetaIoHandler};
+	use jsonrpc_server_utils::tokio_core::reactor::Remote;
+
+	use super::super::session::MetaExtractor;
+
+	/// Signer IPC server, not available on this platform.
+	pub struct Server;
+
+	impl Server {
+		/// Does nothing, IPC sockets are supported on unix only.
+		pub fn start<M: Metadata, S: Middleware<M>, T: MetaExtractor<M>>(
+			path: &Path,
+			_handler: Arc<MetaIoHandler<M, S>>,
+			_remote: Remote,
+			_authcodes_path: PathBuf,
+			_meta_extractor: T,
+		) -> io::Result<Self> {
+			warn!(target: "signer", "Signer IPC is not supported on this platform, not listening on {}.", path.display());
+			Ok(Server)
+		}
+	}
+}
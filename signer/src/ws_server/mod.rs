@@ -31,6 +31,7 @@ use jsonrpc_server_utils::tokio_core::reactor::Remote;
 use rpc::{ConfirmationsQueue};
 use rpc::informant::RpcStats;
 
+mod ipc;
 mod session;
 
 pub use self::session::MetaExtractor;
@@ -64,6 +65,7 @@ pub struct ServerBuilder {
 	authcodes_path: PathBuf,
 	skip_origin_validation: bool,
 	stats: Option<Arc<RpcStats>>,
+	ipc_path: Option<PathBuf>,
 }
 
 impl ServerBuilder {
@@ -74,6 +76,7 @@ impl ServerBuilder {
 			authcodes_path: authcodes_path,
 			skip_origin_validation: false,
 			stats: None,
+			ipc_path: None,
 		}
 	}
 
@@ -90,6 +93,13 @@ impl ServerBuilder {
 		self
 	}
 
+	/// Additionally serve the API over IPC socket at given path.
+	/// Connections have to be authorized with a token, same as `WebSockets` ones.
+	pub fn ipc_path(mut self, path: PathBuf) -> Self {
+		self.ipc_path = Some(path);
+		self
+	}
+
 	/// Starts a new `WebSocket` server in separate thread.
 	/// Returns a `Server` handle which closes the server when droped.
 	pub fn start<M: Metadata, S: Middleware<M>, H: Into<MetaIoHandler<M, S>>>(
@@ -118,6 +128,7 @@ impl ServerBuilder {
 			self.authcodes_path,
 			self.skip_origin_validation,
 			self.stats,
+			self.ipc_path,
 			meta_extractor,
 		)
 	}
@@ -128,6 +139,7 @@ impl ServerBuilder {
 pub struct Server {
 	handle: Option<thread::JoinHandle<()>>,
 	broadcaster_handle: Option<thread::JoinHandle<()>>,
+	ipc: Option<ipc::Server>,
 	queue: Arc<ConfirmationsQueue>,
 	panic_handler: Arc<PanicHandler>,
 	addr: SocketAddr,
@@ -149,6 +161,7 @@ impl Server {
 		authcodes_path: PathBuf,
 		skip_origin_validation: bool,
 		stats: Option<Arc<RpcStats>>,
+		ipc_path: Option<PathBuf>,
 		meta_extractor: T,
 	) -> Result<Server, ServerError> {
 		let config = {
@@ -163,6 +176,12 @@ impl Server {
 		// Create WebSocket
 		let origin = format!("{}", addr);
 		let port = addr.port();
+		let handler = Arc::new(handler);
+		let ipc = match ipc_path {
+			Some(path) => Some(ipc::Server::start(&path, handler.clone(), remote.clone(), authcodes_path.clone(), meta_extractor.clone())
+				.map_err(ServerError::IoError)?),
+			None => None,
+		};
 		let ws = ws::Builder::new().with_settings(config).build(
 			session::Factory::new(handler, remote, origin, port, authcodes_path, skip_origin_validation, stats, meta_extractor)
 		)?;
@@ -209,6 +228,7 @@ impl Server {
 		Ok(Server {
 			handle: Some(handle),
 			broadcaster_handle: Some(broadcaster_handle),
+			ipc: ipc,
 			queue: queue,
 			panic_handler: panic_handler,
 			addr: addr,
@@ -225,6 +245,7 @@ impl MayPanic for Server {
 impl Drop for Server {
 	fn drop(&mut self) {
 		self.queue.finish();
+		self.ipc.take();
 		self.broadcaster_handle.take().unwrap().join().unwrap();
 		self.handle.take().unwrap().join().unwrap();
 	}
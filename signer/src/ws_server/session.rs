@@ -80,7 +80,8 @@ fn origin_is_allowed(self_origin: &str, header: Option<&[u8]>) -> bool {
 	}
 }
 
-fn auth_token_hash(codes_path: &Path, protocols: ws::Result<Vec<&str>>) -> Option<H256> {
+/// Validates the authorization protocol, returning the hash identifying the token used.
+pub fn auth_token_hash(codes_path: &Path, protocols: ws::Result<Vec<&str>>) -> Option<H256> {
 	match protocols {
 		Ok(ref protocols) if protocols.len() == 1 => {
 			let protocol = protocols[0];
@@ -96,17 +97,13 @@ fn auth_token_hash(codes_path: &Path, protocols: ws::Result<Vec<&str>>) -> Optio
 						// remove old tokens
 						codes.clear_garbage();
 
-						let res = codes.is_valid(&auth, time);
+						let res = codes.authorize(&auth, time);
 						// make sure to save back authcodes - it might have been modified
 						if codes.to_file(codes_path).is_err() {
 							warn!(target: "signer", "Couldn't save authorization codes to file.");
 						}
 
-						if res {
-							Some(auth)
-						} else {
-							None
-						}
+						res
 					})
 			} else {
 				None
@@ -266,7 +263,7 @@ pub struct Factory<M: Metadata, S: Middleware<M>, T> {
 
 impl<M: Metadata, S: Middleware<M>, T> Factory<M, S, T> {
 	pub fn new(
-		handler: MetaIoHandler<M, S>,
+		handler: Arc<MetaIoHandler<M, S>>,
 		remote: Remote,
 		self_origin: String,
 		self_port: u16,
@@ -276,7 +273,7 @@ impl<M: Metadata, S: Middleware<M>, T> Factory<M, S, T> {
 		meta_extractor: T,
 	) -> Self {
 		Factory {
-			handler: Arc::new(handler),
+			handler: handler,
 			remote: remote,
 			skip_origin_validation: skip_origin_validation,
 			self_origin: self_origin,
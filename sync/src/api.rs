@@ -17,19 +17,26 @@
 use std::sync::Arc;
 use std::collections::{HashMap, BTreeMap};
 use std::io;
+use std::time::Duration;
 use util::Bytes;
 use network::{NetworkProtocolHandler, NetworkService, NetworkContext, PeerId, ProtocolId,
 	NetworkConfiguration as BasicNetworkConfiguration, NonReservedPeerMode, NetworkError,
-	AllowIP as NetworkAllowIP};
+	AllowIP as NetworkAllowIP, IpFilter, NodeId, PortMapping as NetworkPortMapping, PortMappingMethod, ForkId, Traffic};
 use util::{U256, H256, H512};
 use io::{TimerToken};
 use ethcore::ethstore::ethkey::Secret;
 use ethcore::client::{BlockChainClient, ChainNotify};
 use ethcore::snapshot::SnapshotService;
+use ethcore::spec::{Checkpoint, fork_id};
 use ethcore::header::BlockNumber;
 use sync_io::NetSyncIo;
 use chain::{ChainSync, SyncStatus as EthSyncStatus};
-use std::net::{SocketAddr, AddrParseError};
+use snapshot_serving::SnapshotServingConfig;
+use local_propagation::LocalPropagationConfig;
+use schedule::SyncSchedule;
+use snapshot::SnapshotDownloadConfig;
+use les::{LesServer, LesConfig};
+use std::net::SocketAddr;
 use ipc::{BinaryConvertable, BinaryConvertError, IpcConfig};
 use std::str::FromStr;
 use parking_lot::RwLock;
@@ -37,6 +44,8 @@ use chain::{ETH_PACKET_COUNT, SNAPSHOT_SYNC_PACKET_COUNT};
 use light::client::AsLightClient;
 use light::Provider;
 use light::net::{self as light_net, LightProtocol, Params as LightParams, Capabilities, Handler as LightHandler, EventContext};
+use light::net::request_credits::FlowParams;
+use light_ws::{LightWs, WsPeers};
 
 /// Parity sync protocol
 pub const WARP_SYNC_PROTOCOL_ID: ProtocolId = *b"par";
@@ -44,6 +53,8 @@ pub const WARP_SYNC_PROTOCOL_ID: ProtocolId = *b"par";
 pub const ETH_PROTOCOL: ProtocolId = *b"eth";
 /// Ethereum light protocol
 pub const LIGHT_PROTOCOL: ProtocolId = *b"pip";
+/// Light Ethereum Subprotocol (LES) served to Geth light clients
+pub const LES_PROTOCOL: ProtocolId = *b"les";
 
 /// Sync configuration
 #[derive(Debug, Clone, Copy)]
@@ -52,6 +63,8 @@ pub struct SyncConfig {
 	pub max_download_ahead_blocks: usize,
 	/// Enable ancient block download.
 	pub download_old_blocks: bool,
+	/// Maximal number of ancient blocks imported per second.
+	pub old_blocks_rate: Option<usize>,
 	/// Network ID
 	pub network_id: u64,
 	/// Main "eth" subprotocol name.
@@ -60,10 +73,20 @@ pub struct SyncConfig {
 	pub light_subprotocol_name: [u8; 3],
 	/// Fork block to check
 	pub fork_block: Option<(BlockNumber, H256)>,
+	/// Trusted checkpoint to sync from.
+	pub checkpoint: Option<Checkpoint>,
 	/// Enable snapshot sync
 	pub warp_sync: bool,
 	/// Enable light client server.
 	pub serve_light: bool,
+	/// Light (PIP) server configuration.
+	pub pip: PipConfig,
+	/// LES server configuration (`None` disables serving LES clients).
+	pub les: Option<LesConfig>,
+	/// Limits for serving snapshots to other peers.
+	pub snapshot_serving: SnapshotServingConfig,
+	/// Snapshot download settings.
+	pub snapshot_download: SnapshotDownloadConfig,
 }
 
 impl Default for SyncConfig {
@@ -71,16 +94,33 @@ impl Default for SyncConfig {
 		SyncConfig {
 			max_download_ahead_blocks: 20000,
 			download_old_blocks: true,
+			old_blocks_rate: None,
 			network_id: 1,
 			subprotocol_name: ETH_PROTOCOL,
 			light_subprotocol_name: LIGHT_PROTOCOL,
 			fork_block: None,
+			checkpoint: None,
 			warp_sync: false,
 			serve_light: false,
+			pip: Default::default(),
+			les: None,
+			snapshot_serving: Default::default(),
+			snapshot_download: Default::default(),
 		}
 	}
 }
 
+/// Light (PIP) server configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PipConfig {
+	/// Maximal number of light peers served at the same time. `None` for no limit.
+	pub max_peers: Option<usize>,
+	/// Request credits of each peer: credit limit, recharge rate and request costs.
+	pub flow_params: FlowParams,
+	/// Address to accept light clients over WebSockets on, if any.
+	pub ws_listen: Option<SocketAddr>,
+}
+
 binary_fixed_size!(SyncConfig);
 binary_fixed_size!(EthSyncStatus);
 
@@ -127,6 +167,8 @@ pub struct PeerInfo {
 	pub eth_info: Option<EthProtocolInfo>,
 	/// Light protocol info.
 	pub pip_info: Option<PipProtocolInfo>,
+	/// Packet bytes transferred over the session.
+	pub traffic: NetworkTraffic,
 }
 
 /// Ethereum protocol info.
@@ -181,44 +223,81 @@ pub struct Params {
 /// Ethereum network protocol handler
 pub struct EthSync {
 	/// Network service
-	network: NetworkService,
+	network: Arc<NetworkService>,
 	/// Main (eth/par) protocol handler
 	eth_handler: Arc<SyncProtocolHandler>,
 	/// Light (pip) protocol handler
 	light_proto: Option<Arc<LightProtocol>>,
+	/// Light (pip) protocol WebSocket transport
+	light_ws: Option<LightWs>,
+	/// LES server
+	les_server: Option<Arc<LesServer>>,
 	/// The main subprotocol name
 	subprotocol_name: [u8; 3],
 	/// Light subprotocol name.
 	light_subprotocol_name: [u8; 3],
+	/// Scheduled fork blocks, the node record is updated when the head passes one.
+	fork_blocks: Vec<BlockNumber>,
 }
 
 impl EthSync {
 	/// Creates and register protocol with the network service
 	pub fn new(params: Params) -> Result<Arc<EthSync>, NetworkError> {
 		let pruning_info = params.chain.pruning_info();
+		let les_server = params.config.les.map(|config| Arc::new(
+			LesServer::new(params.provider.clone(), params.chain.clone(), params.config.network_id, config)
+		));
+		let light_ws_peers = match params.config.serve_light {
+			true => params.config.pip.ws_listen.map(|_| Arc::new(WsPeers::new())),
+			false => None,
+		};
 		let light_proto = match params.config.serve_light {
 			false => None,
 			true => Some({
 				let light_params = LightParams {
 					network_id: params.config.network_id,
-					flow_params: Default::default(),
+					flow_params: params.config.pip.flow_params,
 					capabilities: Capabilities {
 						serve_headers: true,
 						serve_chain_since: Some(pruning_info.earliest_chain),
 						serve_state_since: Some(pruning_info.earliest_state),
 						tx_relay: true,
 					},
+					tx_relay_peers: None,
+					max_peers: params.config.pip.max_peers,
 				};
 
 				let mut light_proto = LightProtocol::new(params.provider, light_params);
 				light_proto.add_handler(Arc::new(TxRelay(params.chain.clone())));
+				if let Some(ref peers) = light_ws_peers {
+					light_proto.set_transport(peers.clone());
+				}
 
 				Arc::new(light_proto)
 			})
 		};
 
-		let chain_sync = ChainSync::new(params.config, &*params.chain);
-		let service = NetworkService::new(params.network_config.clone().into_basic()?)?;
+		let mut chain_sync = ChainSync::new(params.config, &*params.chain);
+		chain_sync.set_fork_blocks(params.network_config.fork_blocks.clone());
+		chain_sync.set_preferred_snapshot_peers(params.network_config.snapshot_preferred_peers.iter().filter_map(|url| enode_id(url)));
+		chain_sync.set_schedule(params.network_config.sync_schedule.clone(), params.network_config.min_peers, params.network_config.max_peers);
+		chain_sync.set_local_propagation(LocalPropagationConfig {
+			delay: Duration::from_secs(params.network_config.local_tx_delay),
+			jitter: Duration::from_secs(params.network_config.local_tx_jitter),
+			relay_peers: params.network_config.local_tx_relay_peers.iter().filter_map(|url| enode_id(url)).collect(),
+		});
+		let service = Arc::new(NetworkService::new(params.network_config.clone().into_basic()?)?);
+		let light_ws = match (light_proto.as_ref(), light_ws_peers) {
+			(Some(light_proto), Some(peers)) => Some(LightWs::new(
+				peers,
+				light_proto.clone(),
+				service.clone(),
+				params.config.light_subprotocol_name,
+				Vec::new(),
+				params.config.pip.ws_listen,
+			)),
+			_ => None,
+		};
 
 		let sync = Arc::new(EthSync {
 			network: service,
@@ -229,8 +308,11 @@ impl EthSync {
 				overlay: RwLock::new(HashMap::new()),
 			}),
 			light_proto: light_proto,
+			light_ws: light_ws,
+			les_server: les_server,
 			subprotocol_name: params.config.subprotocol_name,
 			light_subprotocol_name: params.config.light_subprotocol_name,
+			fork_blocks: params.network_config.fork_blocks.clone(),
 		});
 
 		Ok(sync)
@@ -246,7 +328,7 @@ impl SyncProvider for EthSync {
 
 	/// Get sync peers
 	fn peers(&self) -> Vec<PeerInfo> {
-		self.network.with_context_eval(self.subprotocol_name, |ctx| {
+		let mut peers = self.network.with_context_eval(self.subprotocol_name, |ctx| {
 			let peer_ids = self.network.connected_peers();
 			let eth_sync = self.eth_handler.sync.read();
 			let light_proto = self.light_proto.as_ref();
@@ -265,9 +347,16 @@ impl SyncProvider for EthSync {
 					local_address: session_info.local_address,
 					eth_info: eth_sync.peer_info(&peer_id),
 					pip_info: light_proto.as_ref().and_then(|lp| lp.peer_status(&peer_id)).map(Into::into),
+					traffic: session_info.traffic.into(),
 				})
 			}).collect()
-		}).unwrap_or_else(Vec::new)
+		}).unwrap_or_else(Vec::new);
+
+		if let (Some(light_ws), Some(light_proto)) = (self.light_ws.as_ref(), self.light_proto.as_ref()) {
+			peers.extend(ws_peers_info(light_ws, light_proto));
+		}
+
+		peers
 	}
 
 	fn enode(&self) -> Option<String> {
@@ -368,7 +457,17 @@ impl ChainNotify for EthSync {
 				serve_chain_since: None,
 				tx_relay: false,
 			})
-		})
+		});
+
+		if let Some(les_server) = self.les_server.as_ref() {
+			self.network.with_context(LES_PROTOCOL, |context| les_server.announce(context));
+		}
+
+		if !enacted.is_empty() && !self.fork_blocks.is_empty() {
+			let chain_info = self.eth_handler.chain.chain_info();
+			let (hash, next) = fork_id(&chain_info.genesis_hash, &self.fork_blocks, chain_info.best_block_number);
+			self.network.set_fork_id(ForkId { hash: hash, next: next });
+		}
 	}
 
 	fn start(&self) {
@@ -377,10 +476,10 @@ impl ChainNotify for EthSync {
 			Err(err) => warn!("Error starting network: {}", err),
 			_ => {},
 		}
-		self.network.register_protocol(self.eth_handler.clone(), self.subprotocol_name, ETH_PACKET_COUNT, &[62u8, 63u8])
+		self.network.register_protocol(self.eth_handler.clone(), self.subprotocol_name, ETH_PACKET_COUNT, &[62u8, 63u8, 64u8, 65u8])
 			.unwrap_or_else(|e| warn!("Error registering ethereum protocol: {:?}", e));
 		// register the warp sync subprotocol
-		self.network.register_protocol(self.eth_handler.clone(), WARP_SYNC_PROTOCOL_ID, SNAPSHOT_SYNC_PACKET_COUNT, &[1u8, 2u8])
+		self.network.register_protocol(self.eth_handler.clone(), WARP_SYNC_PROTOCOL_ID, SNAPSHOT_SYNC_PACKET_COUNT, &[1u8, 2u8, 3u8])
 			.unwrap_or_else(|e| warn!("Error registering snapshot sync protocol: {:?}", e));
 
 		// register the light protocol.
@@ -388,6 +487,17 @@ impl ChainNotify for EthSync {
 			self.network.register_protocol(light_proto, self.light_subprotocol_name, ::light::net::PACKET_COUNT, ::light::net::PROTOCOL_VERSIONS)
 				.unwrap_or_else(|e| warn!("Error registering light client protocol: {:?}", e));
 		}
+
+		// accept light clients over WebSockets.
+		if let Some(light_ws) = self.light_ws.as_ref() {
+			light_ws.start();
+		}
+
+		// register the LES server.
+		if let Some(les_server) = self.les_server.as_ref().map(|x| x.clone()) {
+			self.network.register_protocol(les_server, LES_PROTOCOL, ::les::PACKET_COUNT, ::les::PROTOCOL_VERSIONS)
+				.unwrap_or_else(|e| warn!("Error registering LES protocol: {:?}", e));
+		}
 	}
 
 	fn stop(&self) {
@@ -408,6 +518,20 @@ impl ChainNotify for EthSync {
 	}
 }
 
+// peers information of the light protocol's WebSocket peers.
+fn ws_peers_info(light_ws: &LightWs, light_proto: &LightProtocol) -> Vec<PeerInfo> {
+	light_ws.peers().into_iter().map(|peer| PeerInfo {
+		id: Some(peer.node_id.hex()),
+		client_version: String::new(),
+		capabilities: vec![format!("pip/{}", light_net::MAX_PROTOCOL_VERSION)],
+		remote_address: peer.address,
+		local_address: String::new(),
+		eth_info: None,
+		pip_info: light_proto.peer_status(&peer.id).map(Into::into),
+		traffic: Default::default(),
+	}).collect()
+}
+
 /// PIP event handler.
 /// Simply queues transactions from light client peers.
 struct TxRelay(Arc<BlockChainClient>);
@@ -438,6 +562,16 @@ pub trait ManageNetwork : Send + Sync {
 	fn stop_network(&self);
 	/// Query the current configuration of the network
 	fn network_config(&self) -> NetworkConfiguration;
+	/// Port mapping created on the NAT gateway, if any
+	fn port_mapping(&self) -> Option<PortMapping>;
+	/// Number of peers dropped by the per-address peer limits
+	fn ip_limited_peers(&self) -> usize;
+	/// Total bytes transferred over all connections
+	fn traffic(&self) -> NetworkTraffic;
+	/// Packet payload bytes transferred per subprotocol
+	fn protocol_traffic(&self) -> BTreeMap<String, NetworkTraffic>;
+	/// Change limits for the number of connected peers
+	fn set_peer_limits(&self, min_peers: u32, max_peers: u32);
 }
 
 
@@ -469,16 +603,53 @@ impl ManageNetwork for EthSync {
 			self.eth_handler.sync.write().abort(&mut sync_io);
 		});
 
+		if let Some(light_ws) = self.light_ws.as_ref() {
+			light_ws.stop();
+		}
+
 		if let Some(light_proto) = self.light_proto.as_ref() {
 			light_proto.abort();
 		}
 
+		if let Some(les_server) = self.les_server.as_ref() {
+			les_server.abort();
+		}
+
 		self.stop();
 	}
 
 	fn network_config(&self) -> NetworkConfiguration {
 		NetworkConfiguration::from(self.network.config().clone())
 	}
+
+	fn port_mapping(&self) -> Option<PortMapping> {
+		self.network.port_mapping().map(Into::into)
+	}
+
+	fn ip_limited_peers(&self) -> usize {
+		self.network.stats().ip_limited()
+	}
+
+	fn traffic(&self) -> NetworkTraffic {
+		let stats = self.network.stats();
+		NetworkTraffic {
+			received: stats.recv() as u64,
+			sent: stats.send() as u64,
+		}
+	}
+
+	fn protocol_traffic(&self) -> BTreeMap<String, NetworkTraffic> {
+		self.network.stats().protocols().into_iter()
+			.map(|(protocol, traffic)| (String::from_utf8_lossy(&protocol).into_owned(), traffic.into()))
+			.collect()
+	}
+
+	fn set_peer_limits(&self, min_peers: u32, max_peers: u32) {
+		self.network.with_context(self.subprotocol_name, |context| {
+			let mut sync_io = NetSyncIo::new(context, &*self.eth_handler.chain, &*self.eth_handler.snapshot_service, &self.eth_handler.overlay);
+			self.eth_handler.sync.write().set_peer_limits(&mut sync_io, min_peers, max_peers);
+		});
+	}
 }
 
 /// IP fiter
@@ -491,6 +662,8 @@ pub enum AllowIP {
 	Private,
 	/// Connect to public network only
 	Public,
+	/// Connect to explicitly allowed networks only
+	None,
 }
 
 impl AllowIP {
@@ -500,11 +673,25 @@ impl AllowIP {
 			"all" => Some(AllowIP::All),
 			"private" => Some(AllowIP::Private),
 			"public" => Some(AllowIP::Public),
+			"none" => Some(AllowIP::None),
 			_ => None,
 		}
 	}
 }
 
+/// Peer slots of a single subprotocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ipc", binary)]
+pub struct ProtocolSlots {
+	/// Subprotocol name, e.g. `eth`.
+	pub protocol: [u8; 3],
+	/// Slots reserved for peers of the protocol in addition to regular peers.
+	/// Overrides `snapshot_peers` and `les_peers` for their protocols.
+	pub min: Option<u32>,
+	/// Maximum number of peers of the protocol.
+	pub max: Option<u32>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "ipc", binary)]
 /// Network service configuration
@@ -521,8 +708,16 @@ pub struct NetworkConfiguration {
 	pub udp_port: Option<u16>,
 	/// Enable NAT configuration
 	pub nat_enabled: bool,
+	/// Enable NAT-PMP/PCP port mapping
+	pub nat_pmp_enabled: bool,
 	/// Enable discovery
 	pub discovery_enabled: bool,
+	/// SOCKS5 proxy address used for outbound connections
+	pub proxy: Option<String>,
+	/// Fork identifier (hash, next fork block) advertised in the node record
+	pub fork_id: Option<(u32, u64)>,
+	/// Scheduled fork blocks, used for the fork identifier exchanged with eth/64+ peers
+	pub fork_blocks: Vec<u64>,
 	/// List of initial node addresses
 	pub boot_nodes: Vec<String>,
 	/// Use provided node key instead of default
@@ -535,12 +730,38 @@ pub struct NetworkConfiguration {
 	pub max_pending_peers: u32,
 	/// Reserved snapshot sync peers.
 	pub snapshot_peers: u32,
+	/// Reserved LES client peers.
+	pub les_peers: u32,
+	/// Node addresses preferred for snapshot download.
+	pub snapshot_preferred_peers: Vec<String>,
+	/// Time-of-day limits for sync bandwidth and peer counts.
+	pub sync_schedule: SyncSchedule,
+	/// Minimal delay of propagation of local transactions in seconds.
+	pub local_tx_delay: u64,
+	/// Maximal random delay of propagation of local transactions in seconds.
+	pub local_tx_jitter: u64,
+	/// Node addresses local transactions are propagated to. Any peer if empty.
+	pub local_tx_relay_peers: Vec<String>,
 	/// List of reserved node addresses.
 	pub reserved_nodes: Vec<String>,
 	/// The non-reserved peer mode.
 	pub allow_non_reserved: bool,
 	/// IP Filtering
 	pub allow_ips: AllowIP,
+	/// Networks in CIDR notation allowed in addition to `allow_ips`
+	pub allow_networks: Vec<String>,
+	/// Networks in CIDR notation that are never allowed
+	pub deny_networks: Vec<String>,
+	/// Maximum number of peers from a single public IP address, zero for no limit
+	pub max_peers_per_ip: u32,
+	/// Maximum number of peers from a single public /24 or /64 subnet, zero for no limit
+	pub max_peers_per_subnet: u32,
+	/// Upload limit for all peer connections in bytes per second
+	pub max_upload: Option<u64>,
+	/// Download limit for all peer connections in bytes per second
+	pub max_download: Option<u64>,
+	/// Per-subprotocol peer slots.
+	pub protocol_slots: Vec<ProtocolSlots>,
 }
 
 impl NetworkConfiguration {
@@ -555,7 +776,17 @@ impl NetworkConfiguration {
 	}
 
 	/// Attempt to convert this config into a BasicNetworkConfiguration.
-	pub fn into_basic(self) -> Result<BasicNetworkConfiguration, AddrParseError> {
+	pub fn into_basic(self) -> Result<BasicNetworkConfiguration, NetworkError> {
+		let mut reserved_protocols = hash_map![WARP_SYNC_PROTOCOL_ID => self.snapshot_peers, LES_PROTOCOL => self.les_peers];
+		let mut protocol_limits = HashMap::new();
+		for slots in &self.protocol_slots {
+			if let Some(min) = slots.min {
+				reserved_protocols.insert(slots.protocol, min);
+			}
+			if let Some(max) = slots.max {
+				protocol_limits.insert(slots.protocol, max);
+			}
+		}
 		Ok(BasicNetworkConfiguration {
 			config_path: self.config_path,
 			net_config_path: self.net_config_path,
@@ -563,19 +794,32 @@ impl NetworkConfiguration {
 			public_address:  match self.public_address { None => None, Some(addr) => Some(SocketAddr::from_str(&addr)?) },
 			udp_port: self.udp_port,
 			nat_enabled: self.nat_enabled,
+			nat_pmp_enabled: self.nat_pmp_enabled,
 			discovery_enabled: self.discovery_enabled,
+			proxy: match self.proxy { None => None, Some(addr) => Some(SocketAddr::from_str(&addr)?) },
+			fork_id: self.fork_id.map(|(hash, next)| ForkId { hash: hash, next: next }),
 			boot_nodes: self.boot_nodes,
 			use_secret: self.use_secret,
 			max_peers: self.max_peers,
 			min_peers: self.min_peers,
 			max_handshakes: self.max_pending_peers,
-			reserved_protocols: hash_map![WARP_SYNC_PROTOCOL_ID => self.snapshot_peers],
+			reserved_protocols: reserved_protocols,
+			protocol_limits: protocol_limits,
 			reserved_nodes: self.reserved_nodes,
-			allow_ips: match self.allow_ips {
-				AllowIP::All => NetworkAllowIP::All,
-				AllowIP::Private => NetworkAllowIP::Private,
-				AllowIP::Public => NetworkAllowIP::Public,
+			ip_filter: IpFilter {
+				predefined: match self.allow_ips {
+					AllowIP::All => NetworkAllowIP::All,
+					AllowIP::Private => NetworkAllowIP::Private,
+					AllowIP::Public => NetworkAllowIP::Public,
+					AllowIP::None => NetworkAllowIP::None,
+				},
+				custom_allow: self.allow_networks.iter().map(|n| n.parse()).collect::<Result<_, _>>()?,
+				custom_block: self.deny_networks.iter().map(|n| n.parse()).collect::<Result<_, _>>()?,
 			},
+			max_peers_per_ip: self.max_peers_per_ip,
+			max_peers_per_subnet: self.max_peers_per_subnet,
+			max_upload: self.max_upload,
+			max_download: self.max_download,
 			non_reserved_mode: if self.allow_non_reserved { NonReservedPeerMode::Accept } else { NonReservedPeerMode::Deny },
 		})
 	}
@@ -583,6 +827,12 @@ impl NetworkConfiguration {
 
 impl From<BasicNetworkConfiguration> for NetworkConfiguration {
 	fn from(other: BasicNetworkConfiguration) -> Self {
+		let mut protocol_slots: Vec<_> = other.protocol_limits.iter().map(|(protocol, max)| ProtocolSlots {
+			protocol: *protocol,
+			min: other.reserved_protocols.get(protocol).cloned(),
+			max: Some(*max),
+		}).collect();
+		protocol_slots.sort_by_key(|slots| slots.protocol);
 		NetworkConfiguration {
 			config_path: other.config_path,
 			net_config_path: other.net_config_path,
@@ -590,24 +840,99 @@ impl From<BasicNetworkConfiguration> for NetworkConfiguration {
 			public_address: other.public_address.and_then(|addr| Some(format!("{}", addr))),
 			udp_port: other.udp_port,
 			nat_enabled: other.nat_enabled,
+			nat_pmp_enabled: other.nat_pmp_enabled,
 			discovery_enabled: other.discovery_enabled,
+			proxy: other.proxy.map(|addr| format!("{}", addr)),
+			fork_id: other.fork_id.map(|id| (id.hash, id.next)),
+			fork_blocks: Vec::new(),
 			boot_nodes: other.boot_nodes,
 			use_secret: other.use_secret,
 			max_peers: other.max_peers,
 			min_peers: other.min_peers,
 			max_pending_peers: other.max_handshakes,
 			snapshot_peers: *other.reserved_protocols.get(&WARP_SYNC_PROTOCOL_ID).unwrap_or(&0),
+			les_peers: *other.reserved_protocols.get(&LES_PROTOCOL).unwrap_or(&0),
+			snapshot_preferred_peers: Vec::new(),
+			sync_schedule: SyncSchedule::default(),
+			local_tx_delay: 0,
+			local_tx_jitter: 0,
+			local_tx_relay_peers: Vec::new(),
 			reserved_nodes: other.reserved_nodes,
-			allow_ips: match other.allow_ips {
+			allow_ips: match other.ip_filter.predefined {
 				NetworkAllowIP::All => AllowIP::All,
 				NetworkAllowIP::Private => AllowIP::Private,
 				NetworkAllowIP::Public => AllowIP::Public,
+				NetworkAllowIP::None => AllowIP::None,
 			},
+			allow_networks: other.ip_filter.custom_allow.iter().map(|n| format!("{}", n)).collect(),
+			deny_networks: other.ip_filter.custom_block.iter().map(|n| format!("{}", n)).collect(),
+			max_peers_per_ip: other.max_peers_per_ip,
+			max_peers_per_subnet: other.max_peers_per_subnet,
+			max_upload: other.max_upload,
+			max_download: other.max_download,
+			protocol_slots: protocol_slots,
 			allow_non_reserved: match other.non_reserved_mode { NonReservedPeerMode::Accept => true, _ => false } ,
 		}
 	}
 }
 
+/// Port mapping created on the NAT gateway.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ipc", binary)]
+pub struct PortMapping {
+	/// Method used to create the mapping: `upnp`, `natpmp` or `pcp`.
+	pub method: String,
+	/// External address and TCP port.
+	pub external_address: String,
+	/// External UDP port.
+	pub udp_port: u16,
+	/// Mapping lifetime in seconds. `None` for permanent mappings.
+	pub lifetime: Option<u32>,
+}
+
+impl From<NetworkPortMapping> for PortMapping {
+	fn from(other: NetworkPortMapping) -> Self {
+		PortMapping {
+			method: match other.method {
+				PortMappingMethod::UPnP => "upnp",
+				PortMappingMethod::NatPmp => "natpmp",
+				PortMappingMethod::Pcp => "pcp",
+			}.into(),
+			external_address: format!("{}", other.external_address),
+			udp_port: other.udp_port,
+			lifetime: other.lifetime,
+		}
+	}
+}
+
+/// Bytes transferred over the network.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ipc", binary)]
+pub struct NetworkTraffic {
+	/// Bytes received.
+	pub received: u64,
+	/// Bytes sent.
+	pub sent: u64,
+}
+
+impl From<Traffic> for NetworkTraffic {
+	fn from(other: Traffic) -> Self {
+		NetworkTraffic {
+			received: other.recv,
+			sent: other.send,
+		}
+	}
+}
+
+/// Extract node id from enode URL.
+pub fn enode_id(url: &str) -> Option<NodeId> {
+	if url.len() > 136 && url.starts_with("enode://") && &url[136..137] == "@" {
+		url[8..136].parse().ok()
+	} else {
+		None
+	}
+}
+
 /// Configuration for IPC service.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "ipc", binary)]
@@ -664,13 +989,18 @@ pub struct LightSyncParams<L> {
 	pub subprotocol_name: [u8; 3],
 	/// Other handlers to attach.
 	pub handlers: Vec<Arc<LightHandler>>,
+	/// Maximum number of peers each local transaction is relayed to at a time.
+	pub tx_relay_peers: Option<usize>,
+	/// URLs of light servers to connect to over WebSockets.
+	pub ws_servers: Vec<String>,
 }
 
 /// Service for light synchronization.
 pub struct LightSync {
 	proto: Arc<LightProtocol>,
 	sync: Arc<::light_sync::SyncInfo + Sync + Send>,
-	network: NetworkService,
+	network: Arc<NetworkService>,
+	ws: Option<LightWs>,
 	subprotocol_name: [u8; 3],
 	network_id: u64,
 }
@@ -682,6 +1012,11 @@ impl LightSync {
 	{
 		use light_sync::LightSync as SyncHandler;
 
+		let ws_peers = match params.ws_servers.is_empty() {
+			true => None,
+			false => Some(Arc::new(WsPeers::new())),
+		};
+
 		// initialize light protocol handler and attach sync module.
 		let (sync, light_proto) = {
 			let light_params = LightParams {
@@ -693,6 +1028,8 @@ impl LightSync {
 					serve_state_since: None,
 					tx_relay: false,
 				},
+				tx_relay_peers: params.tx_relay_peers,
+				max_peers: None,
 			};
 
 			let mut light_proto = LightProtocol::new(params.client.clone(), light_params);
@@ -703,15 +1040,31 @@ impl LightSync {
 				light_proto.add_handler(handler);
 			}
 
+			if let Some(ref peers) = ws_peers {
+				light_proto.set_transport(peers.clone());
+			}
+
 			(sync_handler, Arc::new(light_proto))
 		};
 
-		let service = try!(NetworkService::new(params.network_config));
+		let service = Arc::new(try!(NetworkService::new(params.network_config)));
+		let ws = match ws_peers {
+			Some(peers) => Some(LightWs::new(
+				peers,
+				light_proto.clone(),
+				service.clone(),
+				params.subprotocol_name,
+				params.ws_servers,
+				None,
+			)),
+			None => None,
+		};
 
 		Ok(LightSync {
 			proto: light_proto,
 			sync: sync,
 			network: service,
+			ws: ws,
 			subprotocol_name: params.subprotocol_name,
 			network_id: params.network_id,
 		})
@@ -762,9 +1115,17 @@ impl ManageNetwork for LightSync {
 
 		self.network.register_protocol(light_proto, self.subprotocol_name, ::light::net::PACKET_COUNT, ::light::net::PROTOCOL_VERSIONS)
 			.unwrap_or_else(|e| warn!("Error registering light client protocol: {:?}", e));
+
+		if let Some(ws) = self.ws.as_ref() {
+			ws.start();
+		}
 	}
 
 	fn stop_network(&self) {
+		if let Some(ws) = self.ws.as_ref() {
+			ws.stop();
+		}
+
 		self.proto.abort();
 		if let Err(e) = self.network.stop() {
 			warn!("Error stopping network: {}", e);
@@ -774,6 +1135,32 @@ impl ManageNetwork for LightSync {
 	fn network_config(&self) -> NetworkConfiguration {
 		NetworkConfiguration::from(self.network.config().clone())
 	}
+
+	fn port_mapping(&self) -> Option<PortMapping> {
+		self.network.port_mapping().map(Into::into)
+	}
+
+	fn ip_limited_peers(&self) -> usize {
+		self.network.stats().ip_limited()
+	}
+
+	fn traffic(&self) -> NetworkTraffic {
+		let stats = self.network.stats();
+		NetworkTraffic {
+			received: stats.recv() as u64,
+			sent: stats.send() as u64,
+		}
+	}
+
+	fn protocol_traffic(&self) -> BTreeMap<String, NetworkTraffic> {
+		self.network.stats().protocols().into_iter()
+			.map(|(protocol, traffic)| (String::from_utf8_lossy(&protocol).into_owned(), traffic.into()))
+			.collect()
+	}
+
+	fn set_peer_limits(&self, min_peers: u32, max_peers: u32) {
+		self.network.with_context(self.subprotocol_name, |context| context.set_peer_limits(min_peers, max_peers));
+	}
 }
 
 impl LightSyncProvider for LightSync {
@@ -789,7 +1176,7 @@ impl LightSyncProvider for LightSync {
 	}
 
 	fn peers(&self) -> Vec<PeerInfo> {
-		self.network.with_context_eval(self.subprotocol_name, |ctx| {
+		let mut peers = self.network.with_context_eval(self.subprotocol_name, |ctx| {
 			let peer_ids = self.network.connected_peers();
 
 			peer_ids.into_iter().filter_map(|peer_id| {
@@ -806,9 +1193,16 @@ impl LightSyncProvider for LightSync {
 					local_address: session_info.local_address,
 					eth_info: None,
 					pip_info: self.proto.peer_status(&peer_id).map(Into::into),
+					traffic: session_info.traffic.into(),
 				})
 			}).collect()
-		}).unwrap_or_else(Vec::new)
+		}).unwrap_or_else(Vec::new);
+
+		if let Some(ws) = self.ws.as_ref() {
+			peers.extend(ws_peers_info(ws, &self.proto));
+		}
+
+		peers
 	}
 
 	fn enode(&self) -> Option<String> {
@@ -820,6 +1214,11 @@ impl LightSyncProvider for LightSync {
 	}
 
 	fn transactions_stats(&self) -> BTreeMap<H256, TransactionStats> {
-		Default::default() // TODO
+		self.proto.transaction_relays().into_iter()
+			.map(|(hash, relay)| (hash, TransactionStats {
+				first_seen: relay.first_seen,
+				propagated_to: relay.relayed_to,
+			}))
+			.collect()
 	}
 }
@@ -468,6 +468,7 @@ impl BlockDownloader {
 	/// Checks if there are blocks fully downloaded that can be imported into the blockchain and does the import.
 	pub fn collect_blocks(&mut self, io: &mut SyncIo, allow_out_of_order: bool) -> Result<(), BlockDownloaderImportError> {
 		let mut bad = false;
+		let mut out_of_range = false;
 		let mut imported = HashSet::new();
 		let blocks = self.blocks.drain();
 		let count = blocks.len();
@@ -512,6 +513,11 @@ impl BlockDownloader {
 					imported.insert(h.clone());
 					self.block_imported(&h, number, &parent);
 				},
+				Err(BlockImportError::Import(ImportError::OutOfRange)) => {
+					trace!(target: "sync", "Block {:?} is outside of the configured range", h);
+					out_of_range = true;
+					break;
+				},
 				Err(BlockImportError::Block(BlockError::UnknownParent(_))) if allow_out_of_order => {
 					break;
 				},
@@ -533,6 +539,13 @@ impl BlockDownloader {
 			return Err(BlockDownloaderImportError::Invalid);
 		}
 
+		if out_of_range {
+			// nothing more to download
+			self.blocks.clear();
+			self.state = State::Complete;
+			return Ok(());
+		}
+
 		if self.blocks.is_empty() {
 			// complete sync round
 			trace!(target: "sync", "Sync round complete");
@@ -19,6 +19,11 @@
 /// Syncs to peers and keeps up to date.
 /// This implementation uses ethereum protocol v63
 ///
+/// Peers negotiating eth/64 or eth/65 append the fork identifier (EIP-2124) to the status message;
+/// peers with an incompatible identifier are disconnected.
+/// Peers negotiating eth/65 (or par/3) exchange transaction hashes with `NewPooledTransactionHashes`
+/// instead of full transactions. Unknown transactions are then fetched with `GetPooledTransactions`.
+///
 /// Syncing strategy summary.
 /// Split the chain into ranges of N blocks each. Download ranges sequentially. Split each range into subchains of M blocks. Download subchains in parallel.
 /// State.
@@ -89,20 +94,25 @@
 /// All other messages are ignored.
 ///
 
+use std::time::{Duration, Instant};
 use util::*;
 use rlp::*;
 use network::*;
 use ethcore::header::{BlockNumber, Header as BlockHeader};
-use ethcore::client::{BlockChainClient, BlockStatus, BlockId, BlockChainInfo, BlockImportError, BlockQueueInfo};
+use ethcore::client::{BlockChainClient, BlockStatus, BlockId, BlockChainInfo, BlockImportError, BlockQueueInfo, DiskSpace};
 use ethcore::error::*;
 use ethcore::snapshot::{ManifestData, RestorationStatus};
+use ethcore::spec::{Checkpoint, fork_id as fork_id_at, is_fork_id_compatible};
 use ethcore::transaction::PendingTransaction;
 use sync_io::SyncIo;
 use time;
 use super::SyncConfig;
 use block_sync::{BlockDownloader, BlockRequest, BlockDownloaderImportError as DownloaderImportError, DownloadAction};
 use rand::Rng;
-use snapshot::{Snapshot, ChunkType};
+use snapshot::{Snapshot, ChunkType, SnapshotDownloadConfig};
+use snapshot_serving::SnapshotServing;
+use local_propagation::{LocalPropagation, LocalPropagationConfig};
+use schedule::{SyncSchedule, SyncLimits};
 use api::{EthProtocolInfo as PeerInfoDigest, WARP_SYNC_PROTOCOL_ID};
 use transactions_stats::{TransactionsStats, Stats as TransactionStats};
 
@@ -110,10 +120,13 @@ known_heap_size!(0, PeerInfo);
 
 type PacketDecodeError = DecoderError;
 
+const PROTOCOL_VERSION_65: u8 = 65;
+const PROTOCOL_VERSION_64: u8 = 64;
 const PROTOCOL_VERSION_63: u8 = 63;
 const PROTOCOL_VERSION_62: u8 = 62;
 const PROTOCOL_VERSION_1: u8 = 1;
 const PROTOCOL_VERSION_2: u8 = 2;
+const PROTOCOL_VERSION_3: u8 = 3;
 const MAX_BODIES_TO_SEND: usize = 256;
 const MAX_HEADERS_TO_SEND: usize = 512;
 const MAX_NODE_DATA_TO_SEND: usize = 1024;
@@ -128,6 +141,12 @@ const MAX_NEW_BLOCK_AGE: BlockNumber = 20;
 const MAX_TRANSACTION_SIZE: usize = 300*1024;
 // Maximal number of transactions in sent in single packet.
 const MAX_TRANSACTIONS_TO_PROPAGATE: usize = 64;
+// Maximal number of transaction hashes sent in single announcement packet.
+const MAX_TRANSACTION_HASHES_TO_ANNOUNCE: usize = 4096;
+// Maximal number of pooled transactions requested in single packet.
+const MAX_POOLED_TRANSACTIONS_TO_REQUEST: usize = 256;
+// Maximal number of pooled transactions returned in single packet.
+const MAX_POOLED_TRANSACTIONS_TO_SEND: usize = 256;
 // Min number of blocks to be behind for a snapshot sync
 const SNAPSHOT_RESTORE_THRESHOLD: BlockNumber = 100000;
 const SNAPSHOT_MIN_PEERS: usize = 3;
@@ -140,6 +159,9 @@ const BLOCK_HEADERS_PACKET: u8 = 0x04;
 const GET_BLOCK_BODIES_PACKET: u8 = 0x05;
 const BLOCK_BODIES_PACKET: u8 = 0x06;
 const NEW_BLOCK_PACKET: u8 = 0x07;
+const NEW_POOLED_TRANSACTION_HASHES_PACKET: u8 = 0x08;
+const GET_POOLED_TRANSACTIONS_PACKET: u8 = 0x09;
+const POOLED_TRANSACTIONS_PACKET: u8 = 0x0a;
 
 const GET_NODE_DATA_PACKET: u8 = 0x0d;
 const NODE_DATA_PACKET: u8 = 0x0e;
@@ -156,7 +178,6 @@ const CONSENSUS_DATA_PACKET: u8 = 0x15;
 
 pub const SNAPSHOT_SYNC_PACKET_COUNT: u8 = 0x16;
 
-const MAX_SNAPSHOT_CHUNKS_DOWNLOAD_AHEAD: usize = 3;
 
 const MIN_SUPPORTED_SNAPSHOT_MANIFEST_VERSION: u64 = 1;
 
@@ -167,7 +188,7 @@ const BODIES_TIMEOUT_SEC: u64 = 10;
 const RECEIPTS_TIMEOUT_SEC: u64 = 10;
 const FORK_HEADER_TIMEOUT_SEC: u64 = 3;
 const SNAPSHOT_MANIFEST_TIMEOUT_SEC: u64 = 5;
-const SNAPSHOT_DATA_TIMEOUT_SEC: u64 = 120;
+const POOLED_TRANSACTIONS_TIMEOUT_SEC: u64 = 30;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 /// Sync state
@@ -297,8 +318,10 @@ struct PeerInfo {
 	asking_blocks: Vec<H256>,
 	/// Holds requested header hash if currently requesting block header by hash
 	asking_hash: Option<H256>,
-	/// Holds requested snapshot chunk hash if any.
-	asking_snapshot_data: Option<H256>,
+	/// Holds requested snapshot chunk hashes.
+	asking_snapshot_data: Vec<H256>,
+	/// Peer is preferred for snapshot download.
+	snapshot_preferred: bool,
 	/// Request timestamp
 	ask_time: u64,
 	/// Holds a set of transactions recently sent to this peer to avoid spamming.
@@ -324,6 +347,11 @@ impl PeerInfo {
 		self.confirmation != ForkConfirmation::Unconfirmed && !self.expired
 	}
 
+	/// Peer exchanges transaction hash announcements instead of full transactions (eth/65, par/3).
+	fn announces_transactions(&self) -> bool {
+		self.protocol_version == PROTOCOL_VERSION_65 || self.protocol_version == PROTOCOL_VERSION_3
+	}
+
 	fn reset_asking(&mut self) {
 		self.asking_blocks.clear();
 		self.asking_hash = None;
@@ -368,8 +396,16 @@ pub struct ChainSync {
 	network_id: u64,
 	/// Optional fork block to check
 	fork_block: Option<(BlockNumber, H256)>,
+	/// Scheduled fork blocks for the fork identifier.
+	fork_blocks: Vec<BlockNumber>,
+	/// Optional trusted checkpoint. Peers with lower total difficulty are not used for sync.
+	checkpoint: Option<Checkpoint>,
 	/// Snapshot downloader.
 	snapshot: Snapshot,
+	/// Snapshot download settings.
+	snapshot_download: SnapshotDownloadConfig,
+	/// Peers preferred for snapshot download.
+	preferred_snapshot_peers: HashSet<NodeId>,
 	/// Connected peers pending Status message.
 	/// Value is request timestamp.
 	handshaking_peers: HashMap<PeerId, u64>,
@@ -379,8 +415,27 @@ pub struct ChainSync {
 	transactions_stats: TransactionsStats,
 	/// Enable ancient block downloading
 	download_old_blocks: bool,
+	/// Maximal number of ancient blocks imported per second.
+	old_blocks_rate: Option<usize>,
+	/// Ancient blocks are not requested before this time.
+	old_blocks_next_request: Option<Instant>,
 	/// Enable warp sync.
 	enable_warp_sync: bool,
+	/// Limits for serving snapshot data.
+	snapshot_serving: SnapshotServing,
+	/// Propagation of locally submitted transactions.
+	local_propagation: LocalPropagation,
+	/// Announced transactions requested from peers.
+	/// Value is request timestamp.
+	requested_transactions: H256FastMap<u64>,
+	/// Time-of-day bandwidth and peer limits.
+	schedule: SyncSchedule,
+	/// Minimal and maximal number of peers used when no schedule entry limits peers.
+	default_peer_limits: (u32, u32),
+	/// Currently applied schedule limits.
+	active_limits: Option<SyncLimits>,
+	/// Block and snapshot data is not requested before this time.
+	download_next_request: Option<Instant>,
 }
 
 type RlpResponseResult = Result<Option<(PacketId, RlpStream)>, PacketDecodeError>;
@@ -400,17 +455,61 @@ impl ChainSync {
 			old_blocks: None,
 			last_sent_block_number: 0,
 			network_id: config.network_id,
-			fork_block: config.fork_block,
+			fork_block: config.checkpoint.map(|c| (c.number, c.hash)).or(config.fork_block),
+			fork_blocks: Vec::new(),
+			checkpoint: config.checkpoint,
 			download_old_blocks: config.download_old_blocks,
+			old_blocks_rate: config.old_blocks_rate,
+			old_blocks_next_request: None,
 			snapshot: Snapshot::new(),
+			snapshot_download: config.snapshot_download,
+			preferred_snapshot_peers: HashSet::new(),
 			sync_start_time: None,
 			transactions_stats: TransactionsStats::default(),
 			enable_warp_sync: config.warp_sync,
+			snapshot_serving: SnapshotServing::new(config.snapshot_serving),
+			local_propagation: LocalPropagation::new(Default::default()),
+			requested_transactions: H256FastMap::default(),
+			schedule: SyncSchedule::default(),
+			default_peer_limits: (0, 0),
+			active_limits: None,
+			download_next_request: None,
 		};
 		sync.update_targets(chain);
 		sync
 	}
 
+	/// Set scheduled fork blocks, announced and checked in the eth/64+ status.
+	pub fn set_fork_blocks(&mut self, forks: Vec<BlockNumber>) {
+		self.fork_blocks = forks;
+	}
+
+	/// Set peers preferred for snapshot download.
+	pub fn set_preferred_snapshot_peers<I: IntoIterator<Item=NodeId>>(&mut self, peers: I) {
+		self.preferred_snapshot_peers = peers.into_iter().collect();
+	}
+
+	/// Set delay and relay peers of locally submitted transactions.
+	pub fn set_local_propagation(&mut self, config: LocalPropagationConfig) {
+		self.local_propagation = LocalPropagation::new(config);
+	}
+
+	/// Set time-of-day limits. Given peer limits are used when no schedule entry limits peers.
+	pub fn set_schedule(&mut self, schedule: SyncSchedule, min_peers: u32, max_peers: u32) {
+		self.schedule = schedule;
+		self.default_peer_limits = (min_peers, max_peers);
+		self.active_limits = None;
+	}
+
+	/// Change peer limits. Limits of an active schedule entry take precedence and are reapplied on the next tick.
+	pub fn set_peer_limits(&mut self, io: &mut SyncIo, min_peers: u32, max_peers: u32) {
+		self.default_peer_limits = (min_peers, max_peers);
+		self.active_limits = None;
+		if self.schedule.is_empty() {
+			io.set_peer_limits(min_peers, max_peers);
+		}
+	}
+
 	/// Returns synchonization status
 	pub fn status(&self) -> SyncStatus {
 		let last_imported_number = self.new_blocks.last_imported_block_number();
@@ -602,7 +701,7 @@ impl ChainSync {
 		self.handshaking_peers.remove(&peer_id);
 		let protocol_version: u8 = r.val_at(0)?;
 		let warp_protocol = io.protocol_version(&WARP_SYNC_PROTOCOL_ID, peer_id) != 0;
-		let peer = PeerInfo {
+		let mut peer = PeerInfo {
 			protocol_version: protocol_version,
 			network_id: r.val_at(1)?,
 			difficulty: Some(r.val_at(2)?),
@@ -615,7 +714,8 @@ impl ChainSync {
 			last_sent_transactions: HashSet::new(),
 			expired: false,
 			confirmation: if self.fork_block.is_none() { ForkConfirmation::Confirmed } else { ForkConfirmation::Unconfirmed },
-			asking_snapshot_data: None,
+			asking_snapshot_data: Vec::new(),
+			snapshot_preferred: io.peer_session_info(peer_id).and_then(|info| info.id).map_or(false, |id| self.preferred_snapshot_peers.contains(&id)),
 			snapshot_hash: if warp_protocol { Some(r.val_at(5)?) } else { None },
 			snapshot_number: if warp_protocol { Some(r.val_at(6)?) } else { None },
 			block_set: None,
@@ -647,21 +747,50 @@ impl ChainSync {
 			trace!(target: "sync", "Peer {} network id mismatch (ours: {}, theirs: {})", peer_id, self.network_id, peer.network_id);
 			return Ok(());
 		}
-		if (warp_protocol && peer.protocol_version != PROTOCOL_VERSION_1 && peer.protocol_version != PROTOCOL_VERSION_2) || (!warp_protocol && peer.protocol_version != PROTOCOL_VERSION_63 && peer.protocol_version != PROTOCOL_VERSION_62) {
+		let supported = match warp_protocol {
+			true => [PROTOCOL_VERSION_1, PROTOCOL_VERSION_2, PROTOCOL_VERSION_3].contains(&peer.protocol_version),
+			false => [PROTOCOL_VERSION_62, PROTOCOL_VERSION_63, PROTOCOL_VERSION_64, PROTOCOL_VERSION_65].contains(&peer.protocol_version),
+		};
+		if !supported {
 			io.disable_peer(peer_id);
 			trace!(target: "sync", "Peer {} unsupported eth protocol ({})", peer_id, peer.protocol_version);
 			return Ok(());
 		}
+		if !warp_protocol && peer.protocol_version >= PROTOCOL_VERSION_64 {
+			// Packet layout:
+			// [ ..., forkid: [ hash: B_4, next: P ] ]
+			let fork_id = r.at(5)?;
+			let hash: Vec<u8> = fork_id.val_at(0)?;
+			if hash.len() != 4 {
+				return Err(DecoderError::Custom("Invalid fork id hash"));
+			}
+			let hash = (hash[0] as u32) << 24 | (hash[1] as u32) << 16 | (hash[2] as u32) << 8 | hash[3] as u32;
+			let next: u64 = fork_id.val_at(1)?;
+			if !is_fork_id_compatible(&chain_info.genesis_hash, &self.fork_blocks, chain_info.best_block_number, (hash, next)) {
+				io.disable_peer(peer_id);
+				trace!(target: "sync", "Peer {} fork id mismatch (ours: {:?}, theirs: {:?})", peer_id,
+					fork_id_at(&chain_info.genesis_hash, &self.fork_blocks, chain_info.best_block_number), (hash, next));
+				return Ok(());
+			}
+		}
 
+		if let Some(checkpoint) = self.checkpoint {
+			if peer.difficulty.map_or(false, |d| d < checkpoint.total_difficulty) {
+				trace!(target: "sync", "Peer {} total difficulty is below the trusted checkpoint", peer_id);
+				peer.confirmation = ForkConfirmation::TooShort;
+			}
+		}
+
+		let confirmation = peer.confirmation.clone();
 		self.peers.insert(peer_id.clone(), peer);
 		// Don't activate peer immediatelly when searching for common block.
 		// Let the current sync round complete first.
 		self.active_peers.insert(peer_id.clone());
 		debug!(target: "sync", "Connected {}:{}", peer_id, io.peer_info(peer_id));
-		if let Some((fork_block, _)) = self.fork_block {
-			self.request_fork_header_by_number(io, peer_id, fork_block);
-		} else {
-			self.sync_peer(io, peer_id, false);
+		match (self.fork_block, confirmation) {
+			(_, ForkConfirmation::TooShort) => {},
+			(Some((fork_block, _)), _) => self.request_fork_header_by_number(io, peer_id, fork_block),
+			(None, _) => self.sync_peer(io, peer_id, false),
 		}
 		Ok(())
 	}
@@ -1048,6 +1177,12 @@ impl ChainSync {
 			self.continue_sync(io);
 			return Ok(());
 		}
+		if manifest.base.is_some() {
+			trace!(target: "sync", "{}: Ignored delta snapshot manifest", peer_id);
+			io.disable_peer(peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
 		self.snapshot.reset_to(&manifest, &manifest_rlp.as_raw().sha3());
 		io.snapshot_service().begin_restore(manifest);
 		self.state = SyncState::SnapshotData;
@@ -1065,9 +1200,26 @@ impl ChainSync {
 			trace!(target: "sync", "Ignoring snapshot data from unconfirmed peer {}", peer_id);
 			return Ok(());
 		}
-		self.clear_peer_download(peer_id);
-		if !self.reset_peer_asking(peer_id, PeerAsking::SnapshotData) || (self.state != SyncState::SnapshotData && self.state != SyncState::SnapshotWaiting) {
+		let snapshot_data: Bytes = r.val_at(0)?;
+		let expected = match self.peers.get_mut(&peer_id) {
+			Some(ref mut peer) if peer.asking == PeerAsking::SnapshotData => {
+				// more chunks may still be pending from this peer.
+				let hash = snapshot_data.sha3();
+				peer.asking_snapshot_data.retain(|h| *h != hash);
+				peer.expired = false;
+				if peer.asking_snapshot_data.is_empty() {
+					peer.asking = PeerAsking::Nothing;
+				} else {
+					peer.ask_time = time::precise_time_ns();
+				}
+				true
+			},
+			_ => false,
+		};
+		if !expected || (self.state != SyncState::SnapshotData && self.state != SyncState::SnapshotWaiting) {
 			trace!(target: "sync", "{}: Ignored unexpected snapshot data", peer_id);
+			self.clear_peer_download(peer_id);
+			self.reset_peer_asking(peer_id, PeerAsking::SnapshotData);
 			self.continue_sync(io);
 			return Ok(());
 		}
@@ -1086,7 +1238,6 @@ impl ChainSync {
 			},
 		}
 
-		let snapshot_data: Bytes = r.val_at(0)?;
 		match self.snapshot.validate_chunk(&snapshot_data) {
 			Ok(ChunkType::Block(hash)) => {
 				trace!(target: "sync", "{}: Processing block chunk", peer_id);
@@ -1119,6 +1270,7 @@ impl ChainSync {
 	pub fn on_peer_aborting(&mut self, io: &mut SyncIo, peer: PeerId) {
 		trace!(target: "sync", "== Disconnecting {}: {}", peer, io.peer_info(peer));
 		self.handshaking_peers.remove(&peer);
+		self.snapshot_serving.on_peer_aborting(peer);
 		if self.peers.contains_key(&peer) {
 			debug!(target: "sync", "Disconnected {}", peer);
 			self.clear_peer_download(peer);
@@ -1146,6 +1298,10 @@ impl ChainSync {
 		random::new().shuffle(&mut peers); //TODO: sort by rating
 		// prefer peers with higher protocol version
 		peers.sort_by(|&(_, _, ref v1), &(_, _, ref v2)| v1.cmp(v2));
+		if self.state == SyncState::SnapshotData {
+			// give chunks to preferred snapshot peers first
+			peers.sort_by_key(|&(p, _, _)| !self.peers.get(&p).map_or(false, |p| p.snapshot_preferred));
+		}
 		trace!(target: "sync", "Syncing with peers: {} active, {} confirmed, {} total", self.active_peers.len(), peers.len(), self.peers.len());
 		for (p, _, _) in peers {
 			if self.active_peers.contains(&p) {
@@ -1180,7 +1336,9 @@ impl ChainSync {
 		}
 		let (peer_latest, peer_difficulty, peer_snapshot_number, peer_snapshot_hash) = {
 			if let Some(peer) = self.peers.get_mut(&peer_id) {
-				if peer.asking != PeerAsking::Nothing || !peer.can_sync() {
+				// snapshot chunk requests may be pipelined.
+				let pipelining = peer.asking == PeerAsking::SnapshotData && self.state == SyncState::SnapshotData;
+				if (peer.asking != PeerAsking::Nothing && !pipelining) || !peer.can_sync() {
 					trace!(target: "sync", "Skipping busy peer {}", peer_id);
 					return;
 				}
@@ -1197,6 +1355,10 @@ impl ChainSync {
 				return;
 			}
 		};
+		if self.download_next_request.map_or(false, |next| Instant::now() < next) {
+			trace!(target: "sync", "Download is throttled by the sync schedule");
+			return;
+		}
 		let chain_info = io.chain().chain_info();
 		let syncing_difficulty = chain_info.pending_total_difficulty;
 		let num_active_peers = self.peers.values().filter(|p| p.asking != PeerAsking::Nothing).count();
@@ -1228,6 +1390,16 @@ impl ChainSync {
 						}
 					}
 
+					if self.old_blocks_next_request.map_or(false, |next| Instant::now() < next) {
+						trace!(target: "sync", "Ancient blocks download is throttled");
+						return;
+					}
+
+					if self.old_blocks.is_some() && io.chain().disk_space() >= DiskSpace::NoAncientBlocks {
+						trace!(target: "sync", "Ancient blocks download is paused, low disk space");
+						return;
+					}
+
 					if let Some(request) = self.old_blocks.as_mut().and_then(|d| d.request_blocks(io, num_active_peers)) {
 						self.request_blocks(io, peer_id, request, BlockSet::OldBlocks);
 						return;
@@ -1235,13 +1407,17 @@ impl ChainSync {
 				},
 				SyncState::SnapshotData => {
 					if let RestorationStatus::Ongoing { state_chunks_done, block_chunks_done, .. } = io.snapshot_service().status() {
-						if self.snapshot.done_chunks() - (state_chunks_done + block_chunks_done) as usize > MAX_SNAPSHOT_CHUNKS_DOWNLOAD_AHEAD {
+						if self.snapshot.done_chunks() - (state_chunks_done + block_chunks_done) as usize > self.snapshot_download.max_chunks_ahead {
 							trace!(target: "sync", "Snapshot queue full, pausing sync");
 							self.state = SyncState::SnapshotWaiting;
 							return;
 						}
 					}
 					if peer_snapshot_hash.is_some() && peer_snapshot_hash == self.snapshot.snapshot_hash() {
+						if !self.can_download_snapshot_from(peer_id) {
+							trace!(target: "sync", "Too many peers downloading snapshot, skipping {}", peer_id);
+							return;
+						}
 						self.request_snapshot_data(io, peer_id);
 					}
 				},
@@ -1268,13 +1444,37 @@ impl ChainSync {
 		}
 	}
 
-	/// Find some headers or blocks to download for a peer.
+	/// Checks if snapshot chunks can be requested from the peer.
+	fn can_download_snapshot_from(&self, peer_id: PeerId) -> bool {
+		let max_peers = match self.snapshot_download.max_peers {
+			Some(max_peers) => max_peers,
+			None => return true,
+		};
+		match self.peers.get(&peer_id) {
+			Some(peer) if peer.snapshot_preferred || peer.asking == PeerAsking::SnapshotData => true,
+			Some(_) => {
+				let downloading = self.peers.values().filter(|p| !p.snapshot_preferred && p.asking == PeerAsking::SnapshotData).count();
+				downloading < max_peers
+			},
+			None => false,
+		}
+	}
+
+	/// Find some snapshot chunks to download for a peer.
 	fn request_snapshot_data(&mut self, io: &mut SyncIo, peer_id: PeerId) {
-		self.clear_peer_download(peer_id);
-		// find chunk data to download
-		if let Some(hash) = self.snapshot.needed_chunk() {
+		let chunks_per_peer = ::std::cmp::max(self.snapshot_download.chunks_per_peer, 1);
+		loop {
+			match self.peers.get(&peer_id) {
+				Some(peer) if peer.asking_snapshot_data.len() < chunks_per_peer => {},
+				_ => return,
+			}
+			// find chunk data to download
+			let hash = match self.snapshot.needed_chunk() {
+				Some(hash) => hash,
+				None => return,
+			};
 			if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
-				peer.asking_snapshot_data = Some(hash.clone());
+				peer.asking_snapshot_data.push(hash.clone());
 			}
 			self.request_snapshot_chunk(io, peer_id, &hash);
 		}
@@ -1305,7 +1505,7 @@ impl ChainSync {
 					}
 				},
 				PeerAsking::SnapshotData => {
-					if let Some(hash) = peer.asking_snapshot_data {
+					for hash in peer.asking_snapshot_data.drain(..) {
 						self.snapshot.clear_chunk_download(&hash);
 					}
 				},
@@ -1324,16 +1524,72 @@ impl ChainSync {
 				}
 			},
 			BlockSet::OldBlocks => {
+				let last_imported = self.old_blocks.as_ref().map(|d| d.last_imported_block_number());
 				if self.old_blocks.as_mut().map_or(false, |downloader| { downloader.collect_blocks(io, false) == Err(DownloaderImportError::Invalid) }) {
 					self.restart(io);
 				} else if self.old_blocks.as_ref().map_or(false, |downloader| { downloader.is_complete() }) {
 					trace!(target: "sync", "Background block download is complete");
 					self.old_blocks = None;
+				} else if let (Some(before), Some(after)) = (last_imported, self.old_blocks.as_ref().map(|d| d.last_imported_block_number())) {
+					self.throttle_old_blocks(after.saturating_sub(before));
 				}
 			}
 		}
 	}
 
+	/// Delay next ancient blocks request according to the configured rate.
+	fn throttle_old_blocks(&mut self, imported: BlockNumber) {
+		let rate = match self.old_blocks_rate {
+			Some(rate) if imported > 0 => ::std::cmp::max(rate, 1) as u64,
+			_ => return,
+		};
+
+		let now = Instant::now();
+		let delay = Duration::from_millis(imported * 1000 / rate);
+		let start = match self.old_blocks_next_request {
+			Some(next) if next > now => next,
+			_ => now,
+		};
+		self.old_blocks_next_request = Some(start + delay);
+	}
+
+	/// Delay next download request according to the scheduled bandwidth.
+	fn throttle_download(&mut self, size: usize) {
+		let bandwidth = match self.active_limits.and_then(|limits| limits.max_bandwidth) {
+			Some(bandwidth) => ::std::cmp::max(bandwidth, 1) as u64,
+			None => return,
+		};
+
+		let now = Instant::now();
+		let size = size as u64;
+		let delay = Duration::new(size / bandwidth, ((size % bandwidth) * 1_000_000_000 / bandwidth) as u32);
+		let start = match self.download_next_request {
+			Some(next) if next > now => next,
+			_ => now,
+		};
+		self.download_next_request = Some(start + delay);
+	}
+
+	/// Apply limits of the schedule entry active at given hour.
+	fn apply_schedule(&mut self, io: &mut SyncIo, hour: u8) {
+		if self.schedule.is_empty() {
+			return;
+		}
+		let limits = self.schedule.limits_at(hour);
+		if self.active_limits == Some(limits) {
+			return;
+		}
+
+		debug!(target: "sync", "Applying sync schedule limits: {:?}", limits);
+		let (min_peers, max_peers) = self.default_peer_limits;
+		let max_peers = limits.max_peers.unwrap_or(max_peers);
+		io.set_peer_limits(min(min_peers, max_peers), max_peers);
+		if limits.max_bandwidth.is_none() {
+			self.download_next_request = None;
+		}
+		self.active_limits = Some(limits);
+	}
+
 	/// Request headers from a peer by block hash
 	#[cfg_attr(feature="dev", allow(too_many_arguments))]
 	fn request_headers_by_hash(&mut self, sync: &mut SyncIo, peer_id: PeerId, h: &H256, count: u64, skip: u64, reverse: bool, set: BlockSet) {
@@ -1422,7 +1678,7 @@ impl ChainSync {
 	/// Generic request sender
 	fn send_request(&mut self, sync: &mut SyncIo, peer_id: PeerId, asking: PeerAsking,  packet_id: PacketId, packet: Bytes) {
 		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
-			if peer.asking != PeerAsking::Nothing {
+			if peer.asking != PeerAsking::Nothing && (peer.asking != asking || asking != PeerAsking::SnapshotData) {
 				warn!(target:"sync", "Asking {:?} while requesting {:?}", peer.asking, asking);
 			}
 			peer.asking = asking;
@@ -1475,21 +1731,93 @@ impl ChainSync {
 		Ok(())
 	}
 
+	/// Called when peer announces hashes of new transactions (eth/65)
+	fn on_peer_new_pooled_transaction_hashes(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		// Accept transactions only when fully synced
+		if !io.is_chain_queue_empty() || (self.state != SyncState::Idle && self.state != SyncState::NewBlocks) {
+			trace!(target: "sync", "{} Ignoring transaction hashes while syncing", peer_id);
+			return Ok(());
+		}
+
+		if !self.peers.get(&peer_id).map_or(false, |p| p.announces_transactions()) {
+			trace!(target: "sync", "{} Ignoring transaction hashes from peer without eth/65", peer_id);
+			return Ok(());
+		}
+
+		let item_count = min(r.item_count()?, MAX_TRANSACTION_HASHES_TO_ANNOUNCE);
+		trace!(target: "sync", "{:02} -> NewPooledTransactionHashes ({} entries)", peer_id, item_count);
+		let tick = time::precise_time_ns();
+		let mut to_request = Vec::new();
+		{
+			let peer = match self.peers.get_mut(&peer_id) {
+				Some(peer) => peer,
+				None => return Ok(()),
+			};
+			for i in 0 .. item_count {
+				let hash: H256 = r.val_at(i)?;
+				// peer knows the transaction, no need to propagate it back
+				peer.last_sent_transactions.insert(hash);
+				if to_request.len() >= MAX_POOLED_TRANSACTIONS_TO_REQUEST {
+					continue;
+				}
+				let requested = self.requested_transactions.get(&hash)
+					.map_or(false, |ask_time| (tick - ask_time) / 1_000_000_000 <= POOLED_TRANSACTIONS_TIMEOUT_SEC);
+				if !requested && io.chain().pending_transaction(hash).is_none() {
+					to_request.push(hash);
+				}
+			}
+		}
+
+		if to_request.is_empty() {
+			return Ok(());
+		}
+		let mut packet = RlpStream::new_list(to_request.len());
+		for hash in &to_request {
+			packet.append(hash);
+			self.requested_transactions.insert(*hash, tick);
+		}
+		trace!(target: "sync", "{:02} <- GetPooledTransactions ({} entries)", peer_id, to_request.len());
+		self.send_packet(io, peer_id, GET_POOLED_TRANSACTIONS_PACKET, packet.out());
+		Ok(())
+	}
+
+	/// Called when peer sends us transactions requested by hash (eth/65)
+	fn on_peer_pooled_transactions(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		for tx in r.iter() {
+			self.requested_transactions.remove(&tx.as_raw().sha3());
+		}
+		self.on_peer_transactions(io, peer_id, r)
+	}
+
 	/// Send Status message
 	fn send_status(&mut self, io: &mut SyncIo, peer: PeerId) -> Result<(), NetworkError> {
 		let warp_protocol_version = io.protocol_version(&WARP_SYNC_PROTOCOL_ID, peer);
 		let warp_protocol = warp_protocol_version != 0;
-		let protocol = if warp_protocol { warp_protocol_version } else { PROTOCOL_VERSION_63 };
+		let protocol = match warp_protocol {
+			true => warp_protocol_version,
+			false => match io.eth_protocol_version(peer) {
+				v @ PROTOCOL_VERSION_64 | v @ PROTOCOL_VERSION_65 => v,
+				_ => PROTOCOL_VERSION_63,
+			},
+		};
+		let fork_id = !warp_protocol && protocol >= PROTOCOL_VERSION_64;
 		trace!(target: "sync", "Sending status to {}, protocol version {}", peer, protocol);
-		let mut packet = RlpStream::new_list(if warp_protocol { 7 } else { 5 });
+		let mut packet = RlpStream::new_list(if warp_protocol { 7 } else if fork_id { 6 } else { 5 });
 		let chain = io.chain().chain_info();
 		packet.append(&(protocol as u32));
 		packet.append(&self.network_id);
 		packet.append(&chain.total_difficulty);
 		packet.append(&chain.best_block_hash);
 		packet.append(&chain.genesis_hash);
+		if fork_id {
+			let (hash, next) = fork_id_at(&chain.genesis_hash, &self.fork_blocks, chain.best_block_number);
+			let hash = [(hash >> 24) as u8, (hash >> 16) as u8, (hash >> 8) as u8, hash as u8];
+			packet.begin_list(2);
+			packet.append(&&hash[..]);
+			packet.append(&next);
+		}
 		if warp_protocol {
-			let manifest = match self.old_blocks.is_some() {
+			let manifest = match self.old_blocks.is_some() || io.chain().disk_space() >= DiskSpace::NoSnapshots {
 				true => None,
 				false => io.snapshot_service().manifest(),
 			};
@@ -1621,6 +1949,23 @@ impl ChainSync {
 		Ok(Some((NODE_DATA_PACKET, rlp)))
 	}
 
+	fn return_pooled_transactions(io: &SyncIo, r: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
+		let count = min(r.item_count().unwrap_or(0), MAX_POOLED_TRANSACTIONS_TO_SEND);
+		trace!(target: "sync", "{} -> GetPooledTransactions: {} entries", peer_id, count);
+		let mut transactions = Vec::new();
+		for i in 0..count {
+			if let Some(tx) = io.chain().pending_transaction(r.val_at(i)?) {
+				transactions.push(tx);
+			}
+		}
+		trace!(target: "sync", "{} -> GetPooledTransactions: return {} entries", peer_id, transactions.len());
+		let mut rlp = RlpStream::new_list(transactions.len());
+		for tx in &transactions {
+			rlp.append(&tx.transaction);
+		}
+		Ok(Some((POOLED_TRANSACTIONS_PACKET, rlp)))
+	}
+
 	fn return_receipts(io: &SyncIo, rlp: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
 		let mut count = rlp.item_count().unwrap_or(0);
 		trace!(target: "sync", "{} -> GetReceipts: {} entries", peer_id, count);
@@ -1646,13 +1991,21 @@ impl ChainSync {
 	}
 
 	/// Respond to GetSnapshotManifest request
-	fn return_snapshot_manifest(io: &SyncIo, r: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
+	fn return_snapshot_manifest(sync: &RwLock<ChainSync>, io: &SyncIo, r: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
 		let count = r.item_count().unwrap_or(0);
 		trace!(target: "sync", "{} -> GetSnapshotManifest", peer_id);
 		if count != 0 {
 			debug!(target: "sync", "Invalid GetSnapshotManifest request, ignoring.");
 			return Ok(None);
 		}
+		if !sync.read().snapshot_serving.can_serve_manifest(time::now_utc().tm_hour as u8) {
+			trace!(target: "sync", "{}: Not serving snapshots at this time", peer_id);
+			return Ok(Some((SNAPSHOT_MANIFEST_PACKET, RlpStream::new_list(0))));
+		}
+		if io.chain().disk_space() >= DiskSpace::NoSnapshots {
+			trace!(target: "sync", "{}: Not serving snapshots, low disk space", peer_id);
+			return Ok(Some((SNAPSHOT_MANIFEST_PACKET, RlpStream::new_list(0))));
+		}
 		let rlp = match io.snapshot_service().manifest() {
 			Some(manifest) => {
 				trace!(target: "sync", "{} <- SnapshotManifest", peer_id);
@@ -1669,13 +2022,23 @@ impl ChainSync {
 	}
 
 	/// Respond to GetSnapshotData request
-	fn return_snapshot_data(io: &SyncIo, r: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
+	fn return_snapshot_data(sync: &RwLock<ChainSync>, io: &SyncIo, r: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
 		let hash: H256 = r.val_at(0)?;
 		trace!(target: "sync", "{} -> GetSnapshotData {:?}", peer_id, hash);
+		let now = Instant::now();
+		if !sync.write().snapshot_serving.can_serve_chunk(peer_id, now, time::now_utc().tm_hour as u8) {
+			trace!(target: "sync", "{}: Snapshot serving limit reached", peer_id);
+			return Ok(Some((SNAPSHOT_DATA_PACKET, RlpStream::new_list(0))));
+		}
+		if io.chain().disk_space() >= DiskSpace::NoSnapshots {
+			trace!(target: "sync", "{}: Not serving snapshots, low disk space", peer_id);
+			return Ok(Some((SNAPSHOT_DATA_PACKET, RlpStream::new_list(0))));
+		}
 		let rlp = match io.snapshot_service().chunk(hash) {
 			Some(data) => {
 				let mut rlp = RlpStream::new_list(1);
 				trace!(target: "sync", "{} <- SnapshotData", peer_id);
+				sync.write().snapshot_serving.on_chunk_served(peer_id, data.len(), now);
 				rlp.append(&data);
 				rlp
 			},
@@ -1722,12 +2085,16 @@ impl ChainSync {
 				ChainSync::return_node_data,
 				|e| format!("Error sending nodes: {:?}", e)),
 
+			GET_POOLED_TRANSACTIONS_PACKET => ChainSync::return_rlp(io, &rlp, peer,
+				ChainSync::return_pooled_transactions,
+				|e| format!("Error sending pooled transactions: {:?}", e)),
+
 			GET_SNAPSHOT_MANIFEST_PACKET => ChainSync::return_rlp(io, &rlp, peer,
-				ChainSync::return_snapshot_manifest,
+				|io, rlp, peer| ChainSync::return_snapshot_manifest(sync, io, rlp, peer),
 				|e| format!("Error sending snapshot manifest: {:?}", e)),
 
 			GET_SNAPSHOT_DATA_PACKET => ChainSync::return_rlp(io, &rlp, peer,
-				ChainSync::return_snapshot_data,
+				|io, rlp, peer| ChainSync::return_snapshot_data(sync, io, rlp, peer),
 				|e| format!("Error sending snapshot data: {:?}", e)),
 			CONSENSUS_DATA_PACKET => ChainSync::on_consensus_packet(io, peer, &rlp),
 			_ => {
@@ -1745,10 +2112,16 @@ impl ChainSync {
 			debug!(target:"sync", "Unexpected packet {} from unregistered peer: {}:{}", packet_id, peer, io.peer_info(peer));
 			return;
 		}
+		match packet_id {
+			BLOCK_HEADERS_PACKET | BLOCK_BODIES_PACKET | RECEIPTS_PACKET | SNAPSHOT_DATA_PACKET => self.throttle_download(data.len()),
+			_ => {},
+		}
 		let rlp = UntrustedRlp::new(data);
 		let result = match packet_id {
 			STATUS_PACKET => self.on_peer_status(io, peer, &rlp),
 			TRANSACTIONS_PACKET => self.on_peer_transactions(io, peer, &rlp),
+			NEW_POOLED_TRANSACTION_HASHES_PACKET => self.on_peer_new_pooled_transaction_hashes(io, peer, &rlp),
+			POOLED_TRANSACTIONS_PACKET => self.on_peer_pooled_transactions(io, peer, &rlp),
 			BLOCK_HEADERS_PACKET => self.on_peer_block_headers(io, peer, &rlp),
 			BLOCK_BODIES_PACKET => self.on_peer_block_bodies(io, peer, &rlp),
 			RECEIPTS_PACKET => self.on_peer_block_receipts(io, peer, &rlp),
@@ -1770,6 +2143,7 @@ impl ChainSync {
 	pub fn maintain_peers(&mut self, io: &mut SyncIo) {
 		let tick = time::precise_time_ns();
 		let mut aborting = Vec::new();
+		let mut failed_chunks = Vec::new();
 		for (peer_id, peer) in &self.peers {
 			let elapsed = (tick - peer.ask_time) / 1_000_000_000;
 			let timeout = match peer.asking {
@@ -1779,17 +2153,31 @@ impl ChainSync {
 				PeerAsking::Nothing => false,
 				PeerAsking::ForkHeader => elapsed > FORK_HEADER_TIMEOUT_SEC,
 				PeerAsking::SnapshotManifest => elapsed > SNAPSHOT_MANIFEST_TIMEOUT_SEC,
-				PeerAsking::SnapshotData => elapsed > SNAPSHOT_DATA_TIMEOUT_SEC,
+				PeerAsking::SnapshotData => elapsed > self.snapshot_download.chunk_timeout,
 			};
 			if timeout {
 				trace!(target:"sync", "Timeout {}", peer_id);
 				io.disconnect_peer(*peer_id);
 				aborting.push(*peer_id);
+				if peer.asking == PeerAsking::SnapshotData {
+					failed_chunks.extend(peer.asking_snapshot_data.iter().cloned());
+				}
 			}
 		}
 		for p in aborting {
 			self.on_peer_aborting(io, p);
 		}
+		for hash in failed_chunks {
+			let failures = self.snapshot.chunk_failed(&hash);
+			if self.snapshot_download.max_chunk_retries.map_or(false, |max| failures > max) {
+				debug!(target: "sync", "Snapshot chunk {} failed {} times, restarting snapshot sync", hash, failures);
+				io.snapshot_service().abort_restore();
+				self.snapshot.clear();
+				self.state = SyncState::WaitingPeers;
+				self.continue_sync(io);
+				break;
+			}
+		}
 
 		// Check for handshake timeouts
 		for (peer, ask_time) in &self.handshaking_peers {
@@ -1799,6 +2187,9 @@ impl ChainSync {
 				io.disconnect_peer(*peer);
 			}
 		}
+
+		// Forget unanswered transaction requests so they can be asked from other peers
+		self.requested_transactions.retain(|_, ask_time| (tick - *ask_time) / 1_000_000_000 <= POOLED_TRANSACTIONS_TIMEOUT_SEC);
 	}
 
 	fn check_resume(&mut self, io: &mut SyncIo) {
@@ -1813,7 +2204,7 @@ impl ChainSync {
 					self.continue_sync(io);
 				},
 				RestorationStatus::Ongoing { state_chunks_done, block_chunks_done, .. } => {
-					if !self.snapshot.is_complete() && self.snapshot.done_chunks() - (state_chunks_done + block_chunks_done) as usize <= MAX_SNAPSHOT_CHUNKS_DOWNLOAD_AHEAD {
+					if !self.snapshot.is_complete() && self.snapshot.done_chunks() - (state_chunks_done + block_chunks_done) as usize <= self.snapshot_download.max_chunks_ahead {
 						trace!(target:"sync", "Resuming snapshot sync");
 						self.state = SyncState::SnapshotData;
 						self.continue_sync(io);
@@ -1826,6 +2217,14 @@ impl ChainSync {
 					self.continue_sync(io);
 				},
 			}
+		} else if self.old_blocks.is_some() && self.old_blocks_next_request.map_or(false, |next| Instant::now() >= next) {
+			trace!(target: "sync", "Resuming ancient blocks download");
+			self.old_blocks_next_request = None;
+			self.continue_sync(io);
+		} else if self.download_next_request.map_or(false, |next| Instant::now() >= next) {
+			trace!(target: "sync", "Resuming throttled download");
+			self.download_next_request = None;
+			self.continue_sync(io);
 		}
 	}
 
@@ -1910,7 +2309,7 @@ impl ChainSync {
 	}
 
 	fn get_consensus_peers(&self) -> Vec<PeerId> {
-		self.peers.iter().filter_map(|(id, p)| if p.protocol_version == PROTOCOL_VERSION_2 { Some(*id) } else { None }).collect()
+		self.peers.iter().filter_map(|(id, p)| if p.protocol_version == PROTOCOL_VERSION_2 || p.protocol_version == PROTOCOL_VERSION_3 { Some(*id) } else { None }).collect()
 	}
 
 	/// propagates latest block to a set of peers
@@ -1969,14 +2368,42 @@ impl ChainSync {
 			return 0;
 		}
 
+		// local transactions are held back until released and then possibly sent to relay peers only
+		let (transactions, local_transactions): (Vec<_>, Vec<_>) = match self.local_propagation.is_enabled() {
+			false => (transactions, Vec::new()),
+			true => {
+				let now = Instant::now();
+				self.local_propagation.update(&io.chain().local_transaction_hashes(), now, &mut random::new());
+				let propagation = &self.local_propagation;
+				transactions.into_iter()
+					.filter(|tx| propagation.is_released(&tx.transaction.hash(), now))
+					.partition(|tx| !propagation.relays_only() || !propagation.is_local(&tx.transaction.hash()))
+			},
+		};
+
+		let relay_peers = match local_transactions.is_empty() {
+			true => HashSet::new(),
+			false => self.peers.keys()
+				.cloned()
+				.filter(|peer_id| self.local_propagation.is_relay(io.peer_session_info(*peer_id).and_then(|info| info.id).as_ref()))
+				.collect::<HashSet<_>>(),
+		};
+
 		let (transactions, service_transactions): (Vec<_>, Vec<_>) = transactions.into_iter()
 			.partition(|tx| !tx.transaction.gas_price.is_zero());
 
 		// usual transactions could be propagated to all peers
+		// relay peers get them together with local transactions
 		let mut affected_peers = HashSet::new();
 		if !transactions.is_empty() {
-			let peers = self.select_peers_for_transactions(|_| true);
-			affected_peers = self.propagate_transactions_to_peers(io, peers, transactions);
+			let peers = self.select_peers_for_transactions(|peer_id| !relay_peers.contains(peer_id));
+			affected_peers = self.propagate_transactions_to_peers(io, peers, transactions.clone());
+		}
+
+		if !relay_peers.is_empty() {
+			let relay_transactions = transactions.into_iter().chain(local_transactions).collect();
+			let relay_affected_peers = self.propagate_transactions_to_peers(io, relay_peers.into_iter().collect(), relay_transactions);
+			affected_peers.extend(&relay_affected_peers);
 		}
 
 		// most of times service_transactions will be empty
@@ -2014,6 +2441,11 @@ impl ChainSync {
 			for tx in &transactions { packet.append(&tx.transaction); }
 			packet.out()
 		};
+		let all_transactions_hashes_rlp = {
+			let mut packet = RlpStream::new_list(transactions.len());
+			for tx in &transactions { packet.append(&tx.transaction.hash()); }
+			packet.out()
+		};
 
 		// Clear old transactions from stats
 		self.transactions_stats.retain(&all_transactions_hashes);
@@ -2027,6 +2459,7 @@ impl ChainSync {
 					let stats = &mut self.transactions_stats;
 					let peer_info = self.peers.get_mut(&peer_id)
 						.expect("peer_id is form peers; peers is result of select_peers_for_transactions; select_peers_for_transactions selects peers from self.peers; qed");
+					let announce = peer_info.announces_transactions();
 
 					// Send all transactions
 					if peer_info.last_sent_transactions.is_empty() {
@@ -2036,12 +2469,15 @@ impl ChainSync {
 							stats.propagated(*hash, id, block_number);
 						}
 						peer_info.last_sent_transactions = all_transactions_hashes.clone();
-						return Some((peer_id, all_transactions_hashes.len(), all_transactions_rlp.clone()));
+						return Some(match announce {
+							true => (peer_id, all_transactions_hashes.len(), NEW_POOLED_TRANSACTION_HASHES_PACKET, all_transactions_hashes_rlp.clone()),
+							false => (peer_id, all_transactions_hashes.len(), TRANSACTIONS_PACKET, all_transactions_rlp.clone()),
+						});
 					}
 
 					// Get hashes of all transactions to send to this peer
 					let to_send = all_transactions_hashes.difference(&peer_info.last_sent_transactions)
-						.take(MAX_TRANSACTIONS_TO_PROPAGATE)
+						.take(if announce { MAX_TRANSACTION_HASHES_TO_ANNOUNCE } else { MAX_TRANSACTIONS_TO_PROPAGATE })
 						.cloned()
 						.collect::<HashSet<_>>();
 					if to_send.is_empty() {
@@ -2052,7 +2488,10 @@ impl ChainSync {
 					let mut packet = RlpStream::new_list(to_send.len());
 					for tx in &transactions {
 						if to_send.contains(&tx.transaction.hash()) {
-							packet.append(&tx.transaction);
+							match announce {
+								true => packet.append(&tx.transaction.hash()),
+								false => packet.append(&tx.transaction),
+							};
 							// update stats
 							let id = io.peer_session_info(peer_id).and_then(|info| info.id);
 							stats.propagated(tx.transaction.hash(), id, block_number);
@@ -2064,7 +2503,8 @@ impl ChainSync {
 						.chain(&to_send)
 						.cloned()
 						.collect();
-					Some((peer_id, to_send.len(), packet.out()))
+					let packet_id = if announce { NEW_POOLED_TRANSACTION_HASHES_PACKET } else { TRANSACTIONS_PACKET };
+					Some((peer_id, to_send.len(), packet_id, packet.out()))
 				})
 				.collect::<Vec<_>>()
 		};
@@ -2074,10 +2514,10 @@ impl ChainSync {
 		if lucky_peers.len() > 0 {
 			let mut max_sent = 0;
 			let lucky_peers_len = lucky_peers.len();
-			for (peer_id, sent, rlp) in lucky_peers {
+			for (peer_id, sent, packet_id, rlp) in lucky_peers {
 				peers.insert(peer_id);
-				self.send_packet(io, peer_id, TRANSACTIONS_PACKET, rlp);
-				trace!(target: "sync", "{:02} <- Transactions ({} entries)", peer_id, sent);
+				self.send_packet(io, peer_id, packet_id, rlp);
+				trace!(target: "sync", "{:02} <- Transactions ({} entries, packet {:#x})", peer_id, sent, packet_id);
 				max_sent = max(max_sent, sent);
 			}
 			debug!(target: "sync", "Sent up to {} transactions to {} peers.", max_sent, lucky_peers_len);
@@ -2123,6 +2563,7 @@ impl ChainSync {
 
 	/// Maintain other peers. Send out any new blocks and transactions
 	pub fn maintain_sync(&mut self, io: &mut SyncIo) {
+		self.apply_schedule(io, time::now_utc().tm_hour as u8);
 		self.maybe_start_snapshot_sync(io);
 		self.check_resume(io);
 	}
@@ -2450,7 +2891,8 @@ mod tests {
 				confirmation: super::ForkConfirmation::Confirmed,
 				snapshot_number: None,
 				snapshot_hash: None,
-				asking_snapshot_data: None,
+				asking_snapshot_data: Vec::new(),
+				snapshot_preferred: false,
 				block_set: None,
 			});
 
@@ -2547,6 +2989,63 @@ mod tests {
 		assert_eq!(0x07, io.packets[0].packet_id);
 	}
 
+	#[test]
+	fn throttles_old_blocks_download() {
+		let client = TestBlockChainClient::new();
+		let mut config = SyncConfig::default();
+		config.old_blocks_rate = Some(100);
+		let mut sync = ChainSync::new(config, &client);
+
+		sync.throttle_old_blocks(0);
+		assert!(sync.old_blocks_next_request.is_none());
+
+		let now = Instant::now();
+		sync.throttle_old_blocks(50);
+		let next = sync.old_blocks_next_request.unwrap();
+		assert!(next >= now + Duration::from_millis(500));
+		assert!(next < now + Duration::from_secs(5));
+	}
+
+	#[test]
+	fn does_not_serve_snapshots_on_low_disk_space() {
+		let client = TestBlockChainClient::new();
+		let queue = RwLock::new(VecDeque::new());
+		let sync = RwLock::new(dummy_sync_with_peer(H256::new(), &client));
+		let ss = TestSnapshotService::new_with_snapshot(2, H256::new(), 10);
+		let request = RlpStream::new_list(0).out();
+		let manifest_items = |client: &TestBlockChainClient| {
+			let io = TestIo::new(client, &ss, &queue, None);
+			let (_, rlp) = ChainSync::return_snapshot_manifest(&sync, &io, &UntrustedRlp::new(&request), 0).unwrap().unwrap();
+			UntrustedRlp::new(&rlp.out()).item_count().unwrap()
+		};
+
+		assert_eq!(manifest_items(&client), 1);
+		*client.disk_space.write() = DiskSpace::NoSnapshots;
+		assert_eq!(manifest_items(&client), 0);
+	}
+
+	#[test]
+	fn applies_sync_schedule() {
+		let mut client = TestBlockChainClient::new();
+		let mut sync = ChainSync::new(SyncConfig::default(), &client);
+		sync.set_schedule("8-18:peers=10,bandwidth=1".parse().unwrap(), 25, 50);
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+		sync.apply_schedule(&mut io, 12);
+		assert_eq!(io.peer_limits, Some((10, 10)));
+		let now = Instant::now();
+		sync.throttle_download(2048);
+		assert!(sync.download_next_request.unwrap() >= now + Duration::from_secs(2));
+
+		sync.apply_schedule(&mut io, 20);
+		assert_eq!(io.peer_limits, Some((25, 50)));
+		assert!(sync.download_next_request.is_none());
+		sync.throttle_download(2048);
+		assert!(sync.download_next_request.is_none());
+	}
+
 	#[test]
 	fn sends_proposed_block() {
 		let mut client = TestBlockChainClient::new();
@@ -2571,7 +3070,8 @@ mod tests {
 				confirmation: super::ForkConfirmation::Confirmed,
 				snapshot_number: None,
 				snapshot_hash: None,
-				asking_snapshot_data: None,
+				asking_snapshot_data: Vec::new(),
+				snapshot_preferred: false,
 				block_set: None,
 			});
 		let ss = TestSnapshotService::new();
@@ -2611,6 +3111,138 @@ mod tests {
 		assert_eq!(0x02, io.packets[0].packet_id);
 	}
 
+	#[test]
+	fn announces_transactions_to_eth65_peers() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		client.insert_transaction_to_queue();
+		let hash = client.ready_transactions()[0].transaction.hash();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		sync.peers.get_mut(&0).unwrap().protocol_version = super::PROTOCOL_VERSION_65;
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, None);
+		let peer_count = sync.propagate_new_transactions(&mut io);
+
+		assert_eq!(1, peer_count);
+		assert_eq!(1, io.packets.len());
+		// NEW_POOLED_TRANSACTION_HASHES_PACKET
+		assert_eq!(0x08, io.packets[0].packet_id);
+		let rlp = UntrustedRlp::new(&io.packets[0].data);
+		assert_eq!(hash, rlp.val_at::<H256>(0).unwrap());
+	}
+
+	#[test]
+	fn requests_announced_transactions_once() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		insert_dummy_peer(&mut sync, 1, client.block_hash_delta_minus(1));
+		sync.peers.get_mut(&0).unwrap().protocol_version = super::PROTOCOL_VERSION_65;
+		sync.peers.get_mut(&1).unwrap().protocol_version = super::PROTOCOL_VERSION_65;
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+		let mut announcement = RlpStream::new_list(1);
+		announcement.append(&H256::from(1));
+		let announcement = announcement.out();
+		sync.on_peer_new_pooled_transaction_hashes(&mut io, 0, &UntrustedRlp::new(&announcement)).unwrap();
+		// the same hash announced by another peer is not requested again
+		sync.on_peer_new_pooled_transaction_hashes(&mut io, 1, &UntrustedRlp::new(&announcement)).unwrap();
+
+		assert_eq!(1, io.packets.len());
+		// GET_POOLED_TRANSACTIONS_PACKET
+		assert_eq!(0x09, io.packets[0].packet_id);
+		assert!(sync.peers[&1].last_sent_transactions.contains(&H256::from(1)));
+	}
+
+	#[test]
+	fn ignores_transaction_hashes_from_peers_without_eth65() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		sync.peers.get_mut(&0).unwrap().protocol_version = super::PROTOCOL_VERSION_63;
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+		let mut announcement = RlpStream::new_list(1);
+		announcement.append(&H256::from(1));
+		sync.on_peer_new_pooled_transaction_hashes(&mut io, 0, &UntrustedRlp::new(&announcement.out())).unwrap();
+
+		assert!(io.packets.is_empty());
+		assert!(sync.peers[&0].last_sent_transactions.is_empty());
+	}
+
+	fn eth64_status(chain: &BlockChainClient, fork_hash: u32, fork_next: u64) -> Bytes {
+		let info = chain.chain_info();
+		let mut status = RlpStream::new_list(6);
+		status.append(&(super::PROTOCOL_VERSION_64 as u32));
+		status.append(&1u64);
+		status.append(&info.total_difficulty);
+		status.append(&info.best_block_hash);
+		status.append(&info.genesis_hash);
+		status.begin_list(2);
+		let hash = [(fork_hash >> 24) as u8, (fork_hash >> 16) as u8, (fork_hash >> 8) as u8, fork_hash as u8];
+		status.append(&&hash[..]);
+		status.append(&fork_next);
+		status.out()
+	}
+
+	#[test]
+	fn exchanges_fork_id_with_eth64_peers() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Nothing);
+		let mut sync = ChainSync::new(SyncConfig::default(), &client);
+		sync.set_fork_blocks(vec![50, 200]);
+		let info = client.chain_info();
+		let (hash, next) = ::ethcore::spec::fork_id(&info.genesis_hash, &[50, 200], info.best_block_number);
+		assert_eq!(next, 200);
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, Some(0));
+		io.eth_protocol_version = super::PROTOCOL_VERSION_64;
+		io.warp_protocol_version = 0;
+
+		sync.send_status(&mut io, 0).unwrap();
+		let status = UntrustedRlp::new(&io.packets[0].data);
+		assert_eq!(status.item_count().unwrap(), 6);
+		let fork_id = status.at(5).unwrap();
+		assert_eq!(fork_id.val_at::<Vec<u8>>(0).unwrap(), vec![(hash >> 24) as u8, (hash >> 16) as u8, (hash >> 8) as u8, hash as u8]);
+		assert_eq!(fork_id.val_at::<u64>(1).unwrap(), 200);
+
+		let compatible = eth64_status(io.chain, hash, 200);
+		sync.on_peer_status(&mut io, 1, &UntrustedRlp::new(&compatible)).unwrap();
+		assert!(sync.peers.contains_key(&1));
+
+		// the peer has passed the fork at block 50 but does not know about it
+		let incompatible = eth64_status(io.chain, hash, 40);
+		sync.on_peer_status(&mut io, 2, &UntrustedRlp::new(&incompatible)).unwrap();
+		assert!(!sync.peers.contains_key(&2));
+		assert!(io.to_disconnect.contains(&2));
+	}
+
+	#[test]
+	fn returns_pooled_transactions() {
+		let mut client = TestBlockChainClient::new();
+		client.insert_transaction_to_queue();
+		let hash = client.ready_transactions()[0].transaction.hash();
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let io = TestIo::new(&mut client, &ss, &queue, None);
+
+		let mut request = RlpStream::new_list(2);
+		request.append(&hash);
+		request.append(&H256::from(1));
+		let request = request.out();
+		let result = ChainSync::return_pooled_transactions(&io, &UntrustedRlp::new(&request), 0).unwrap().unwrap();
+
+		assert_eq!(super::POOLED_TRANSACTIONS_PACKET, result.0);
+		let rlp = result.1.out();
+		assert_eq!(1, Rlp::new(&rlp).item_count());
+	}
+
 	#[test]
 	fn does_not_propagate_new_transactions_after_new_block() {
 		let mut client = TestBlockChainClient::new();
@@ -0,0 +1,1005 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! LES (Light Ethereum Subprotocol) version 1 server.
+//!
+//! Allows light clients speaking LES (e.g. Geth) to use this node as a server.
+//! Only the serving side of the protocol is implemented, requests are answered
+//! using a light `Provider`. Header proofs are given against a canonical hash
+//! trie in the LES/1 layout, which is built in memory in the background as the chain
+//! grows. Only the roots of the most recent sections are kept provable, nodes of
+//! older roots are pruned.
+//!
+//! Requests are charged for the maximal number of items before being served and
+//! refunded for the items which weren't returned. Requests for more items than
+//! the serving limits are refused instead of being answered partially.
+
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use ethcore::client::{BlockChainClient, BlockId};
+use ethcore::receipt::Receipt;
+use ethcore::transaction::UnverifiedTransaction;
+use io::TimerToken;
+use light::Provider;
+use light::net::Error;
+use light::request::{
+	HashOrNumber, CompleteHeadersRequest, CompleteBodyRequest, CompleteReceiptsRequest,
+	CompleteAccountRequest, CompleteStorageRequest, CompleteCodeRequest,
+};
+use network::{NetworkProtocolHandler, NetworkContext, PeerId};
+use parking_lot::RwLock;
+use rlp::{DecoderError, Encodable, RlpStream, UntrustedRlp};
+use util::{Bytes, H256, U256, DBValue, HashDB, MemoryDB};
+use util::sha3::SHA3_NULL_RLP;
+use util::trie::{Trie, TrieMut, TrieDB, TrieDBMut, Recorder};
+
+/// Supported protocol versions.
+pub const PROTOCOL_VERSIONS: &'static [u8] = &[1];
+
+/// Packet count for LES.
+pub const PACKET_COUNT: u8 = 15;
+
+const TIMEOUT: TimerToken = 0;
+const TIMEOUT_INTERVAL_MS: u64 = 1000;
+
+/// Time given to the peer to send its status.
+const HANDSHAKE_TIMEOUT_MS: u64 = 2500;
+
+// packet ID definitions.
+mod packet {
+	pub const STATUS: u8 = 0x00;
+	pub const ANNOUNCE: u8 = 0x01;
+
+	pub const GET_BLOCK_HEADERS: u8 = 0x02;
+	pub const BLOCK_HEADERS: u8 = 0x03;
+	pub const GET_BLOCK_BODIES: u8 = 0x04;
+	pub const BLOCK_BODIES: u8 = 0x05;
+	pub const GET_RECEIPTS: u8 = 0x06;
+	pub const RECEIPTS: u8 = 0x07;
+	pub const GET_PROOFS: u8 = 0x08;
+	pub const PROOFS: u8 = 0x09;
+	pub const GET_CONTRACT_CODES: u8 = 0x0a;
+	pub const CONTRACT_CODES: u8 = 0x0b;
+	pub const SEND_TRANSACTIONS: u8 = 0x0c;
+	pub const GET_HEADER_PROOFS: u8 = 0x0d;
+	pub const HEADER_PROOFS: u8 = 0x0e;
+}
+
+// maximal number of items served in a single request.
+mod limits {
+	pub const HEADERS: usize = 192;
+	pub const BODIES: usize = 32;
+	pub const RECEIPTS: usize = 128;
+	pub const PROOFS: usize = 64;
+	pub const CODES: usize = 64;
+	pub const TRANSACTIONS: usize = 64;
+	pub const HEADER_PROOFS: usize = 64;
+}
+
+/// Number of blocks in a section of the canonical hash trie.
+const CHT_SIZE: u64 = 4096;
+
+/// Number of confirmations a section needs before being added to the canonical hash trie.
+const CHT_CONFIRMATIONS: u64 = 2048;
+
+/// Maximal number of sections added to the canonical hash trie on each timer tick.
+const CHT_SECTIONS_PER_TICK: u64 = 4;
+
+/// Number of the most recent sections of the canonical hash trie proofs are given against.
+/// The latest root covers all blocks, light clients use older ones only until they learn of it.
+const CHT_KEPT_SECTIONS: usize = 16;
+
+/// Request packets and their (base cost, cost per item) in the flow control cost table.
+// arbitrarily chosen constants.
+const COSTS: &'static [(u8, u64, u64)] = &[
+	(packet::GET_BLOCK_HEADERS, 150_000, 30_000),
+	(packet::GET_BLOCK_BODIES, 0, 700_000),
+	(packet::GET_RECEIPTS, 0, 1_000_000),
+	(packet::GET_PROOFS, 0, 1_000_000),
+	(packet::GET_CONTRACT_CODES, 0, 500_000),
+	(packet::SEND_TRANSACTIONS, 0, 450_000),
+	(packet::GET_HEADER_PROOFS, 0, 600_000),
+];
+
+/// LES server configuration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LesConfig {
+	/// Maximal number of light clients served at the same time.
+	pub max_peers: usize,
+	/// Flow control buffer limit of each client.
+	pub buffer_limit: u64,
+	/// Flow control buffer recharge rate (per second).
+	pub recharge_rate: u64,
+}
+
+impl Default for LesConfig {
+	fn default() -> Self {
+		LesConfig {
+			max_peers: 10,
+			buffer_limit: 300_000_000,
+			recharge_rate: 50_000,
+		}
+	}
+}
+
+/// Flow control buffer of a single client.
+#[derive(Debug, Clone, PartialEq)]
+struct Buffer {
+	value: u64,
+	updated: Instant,
+}
+
+impl Buffer {
+	fn new(config: &LesConfig, now: Instant) -> Self {
+		Buffer {
+			value: config.buffer_limit,
+			updated: now,
+		}
+	}
+
+	/// Give back credits charged for work which wasn't done.
+	fn refund(&mut self, config: &LesConfig, amount: u64) {
+		self.value = cmp::min(config.buffer_limit, self.value.saturating_add(amount));
+	}
+
+	/// Recharge the buffer and deduct the cost. Fails if the client exceeded its buffer.
+	fn deduct(&mut self, config: &LesConfig, cost: u64, now: Instant) -> Result<(), Error> {
+		if now > self.updated {
+			let elapsed = now.duration_since(self.updated);
+			let elapsed_ms = elapsed.as_secs() * 1000 + (elapsed.subsec_nanos() / 1_000_000) as u64;
+			let recharge = elapsed_ms.saturating_mul(config.recharge_rate) / 1000;
+			self.value = cmp::min(config.buffer_limit, self.value.saturating_add(recharge));
+			self.updated = now;
+		}
+
+		match cost > self.value {
+			true => Err(Error::NoCredits),
+			false => {
+				self.value -= cost;
+				Ok(())
+			}
+		}
+	}
+}
+
+/// Cost of serving a request packet with given number of items.
+fn cost(packet_id: u8, items: usize) -> u64 {
+	COSTS.iter()
+		.find(|&&(id, _, _)| id == packet_id)
+		.map_or(0, |&(_, base, per_item)| base.saturating_add(per_item.saturating_mul(items as u64)))
+}
+
+/// Maximal number of items served for a request packet.
+fn limit(packet_id: u8) -> usize {
+	match packet_id {
+		packet::GET_BLOCK_HEADERS => limits::HEADERS,
+		packet::GET_BLOCK_BODIES => limits::BODIES,
+		packet::GET_RECEIPTS => limits::RECEIPTS,
+		packet::GET_PROOFS => limits::PROOFS,
+		packet::GET_CONTRACT_CODES => limits::CODES,
+		packet::SEND_TRANSACTIONS => limits::TRANSACTIONS,
+		packet::GET_HEADER_PROOFS => limits::HEADER_PROOFS,
+		_ => 0,
+	}
+}
+
+// trie database deferring the removal of nodes, so that proofs can be given
+// against the roots of the kept sections of the canonical hash trie.
+struct ChtDB {
+	db: MemoryDB,
+	// nodes removed by the update in progress.
+	removed: Vec<H256>,
+	// nodes removed by the updates adding the kept sections, oldest first.
+	journal: VecDeque<Vec<H256>>,
+}
+
+impl ChtDB {
+	// finish the update adding a section. nodes are purged once no kept root refers to them.
+	fn commit(&mut self, kept_sections: usize) {
+		let removed = ::std::mem::replace(&mut self.removed, Vec::new());
+		self.journal.push_back(removed);
+		// the removals of the update adding section `n` drop nodes of the root of section `n - 1`.
+		while self.journal.len() >= kept_sections {
+			for key in self.journal.pop_front().expect("journal is not empty; qed") {
+				self.db.remove_and_purge(&key);
+			}
+		}
+	}
+}
+
+impl HashDB for ChtDB {
+	fn keys(&self) -> HashMap<H256, i32> {
+		self.db.keys()
+	}
+
+	fn get(&self, key: &H256) -> Option<DBValue> {
+		self.db.get(key)
+	}
+
+	fn contains(&self, key: &H256) -> bool {
+		self.db.contains(key)
+	}
+
+	fn insert(&mut self, value: &[u8]) -> H256 {
+		self.db.insert(value)
+	}
+
+	fn emplace(&mut self, key: H256, value: DBValue) {
+		self.db.emplace(key, value)
+	}
+
+	fn remove(&mut self, key: &H256) {
+		self.removed.push(*key);
+	}
+}
+
+/// Canonical hash trie in the LES/1 layout.
+///
+/// A single trie of all canonical blocks, keyed by the big-endian block number
+/// and holding `[hash, total_difficulty]`. Its root is taken after every
+/// `CHT_SIZE` blocks; section `n` covers all blocks up to `(n + 1) * CHT_SIZE - 1`.
+/// Nodes are kept only for the roots of the last `kept_sections` sections.
+struct Cht {
+	db: ChtDB,
+	root: H256,
+	roots: Vec<H256>,
+	kept_sections: usize,
+}
+
+impl Cht {
+	fn new(kept_sections: usize) -> Self {
+		Cht {
+			db: ChtDB {
+				db: MemoryDB::new(),
+				removed: Vec::new(),
+				journal: VecDeque::new(),
+			},
+			root: SHA3_NULL_RLP,
+			roots: Vec::new(),
+			kept_sections: cmp::max(kept_sections, 1),
+		}
+	}
+
+	/// Number of complete sections.
+	fn sections(&self) -> u64 {
+		self.roots.len() as u64
+	}
+
+	/// Add the next section, using given closure to fetch the hash and total difficulty
+	/// of each block. Nothing is added if any of the blocks is unavailable.
+	fn add_section<F>(&mut self, fetch: F) -> bool
+		where F: Fn(u64) -> Option<(H256, U256)>
+	{
+		let start = self.sections() * CHT_SIZE;
+		let mut entries = Vec::with_capacity(CHT_SIZE as usize);
+		for num in start..start + CHT_SIZE {
+			match fetch(num) {
+				Some(entry) => entries.push((num, entry)),
+				None => return false,
+			}
+		}
+
+		{
+			let mut t = TrieDBMut::from_existing(&mut self.db, &mut self.root)
+				.expect("root is always in the in-memory database; qed");
+			for (num, (hash, td)) in entries {
+				let mut val = RlpStream::new_list(2);
+				val.append(&hash).append(&td);
+				t.insert(&cht_key(num), &val.out())
+					.expect("in-memory database is infallible; qed");
+			}
+		}
+
+		self.db.commit(self.kept_sections);
+		self.roots.push(self.root);
+		true
+	}
+
+	/// Prove the entry of given block against the root of given section, omitting nodes before
+	/// `from_level`. `None` if the section isn't kept or doesn't cover the block.
+	fn prove(&self, section: u64, num: u64, from_level: u32) -> Option<Vec<Bytes>> {
+		if section + (self.kept_sections as u64) < self.sections() {
+			return None;
+		}
+		let root = match self.roots.get(section as usize) {
+			Some(root) if num < (section + 1) * CHT_SIZE => root,
+			_ => return None,
+		};
+
+		let mut recorder = Recorder::with_depth(from_level);
+		let res = TrieDB::new(&self.db, root).and_then(|t| t.get_with(&cht_key(num), &mut recorder));
+		match res {
+			Ok(Some(_)) => Some(recorder.drain().into_iter().map(|r| r.data).collect()),
+			_ => None,
+		}
+	}
+}
+
+fn cht_key(num: u64) -> [u8; 8] {
+	let mut key = [0u8; 8];
+	for (i, byte) in key.iter_mut().enumerate() {
+		*byte = (num >> (8 * (7 - i))) as u8;
+	}
+	key
+}
+
+/// Static data needed to produce our status packet.
+#[derive(Debug, Clone, PartialEq)]
+struct Status {
+	protocol_version: u8,
+	network_id: u64,
+	head_td: U256,
+	head_hash: H256,
+	head_num: u64,
+	genesis_hash: H256,
+	serve_chain_since: u64,
+	serve_state_since: Option<u64>,
+}
+
+fn encode_pair<T: Encodable>(stream: &mut RlpStream, key: &str, val: &T) {
+	stream.begin_list(2).append(&key).append(val);
+}
+
+fn encode_flag(stream: &mut RlpStream, key: &str) {
+	stream.begin_list(2).append(&key).append_empty_data();
+}
+
+/// Write the server status (handshake) packet.
+fn write_status(status: &Status, config: &LesConfig) -> Bytes {
+	let pairs = 13 + status.serve_state_since.map_or(0, |_| 1);
+	let mut stream = RlpStream::new_list(pairs);
+	encode_pair(&mut stream, "protocolVersion", &(status.protocol_version as u32));
+	encode_pair(&mut stream, "networkId", &status.network_id);
+	encode_pair(&mut stream, "headTd", &status.head_td);
+	encode_pair(&mut stream, "headHash", &status.head_hash);
+	encode_pair(&mut stream, "headNum", &status.head_num);
+	encode_pair(&mut stream, "genesisHash", &status.genesis_hash);
+	encode_flag(&mut stream, "serveHeaders");
+	encode_pair(&mut stream, "serveChainSince", &status.serve_chain_since);
+	if let Some(ref since) = status.serve_state_since {
+		encode_pair(&mut stream, "serveStateSince", since);
+	}
+	encode_flag(&mut stream, "txRelay");
+	encode_pair(&mut stream, "flowControl/BL", &config.buffer_limit);
+	stream.begin_list(2).append(&"flowControl/MRC").begin_list(COSTS.len());
+	for &(packet_id, base, per_item) in COSTS {
+		stream.begin_list(3).append(&packet_id).append(&base).append(&per_item);
+	}
+	encode_pair(&mut stream, "flowControl/MRR", &config.recharge_rate);
+	// announce type, understood by newer clients.
+	encode_pair(&mut stream, "announceType", &0u8);
+	// reserved for future use.
+	encode_flag(&mut stream, "");
+	stream.out()
+}
+
+/// Status sent by the client: (protocol version, network id, genesis hash).
+fn parse_status(rlp: &UntrustedRlp) -> Result<(u32, u64, H256), DecoderError> {
+	let mut protocol_version = None;
+	let mut network_id = None;
+	let mut genesis_hash = None;
+
+	for pair in rlp.iter() {
+		let key: String = pair.val_at(0)?;
+		match key.as_str() {
+			"protocolVersion" => protocol_version = Some(pair.val_at(1)?),
+			"networkId" => network_id = Some(pair.val_at(1)?),
+			"genesisHash" => genesis_hash = Some(pair.val_at(1)?),
+			_ => {},
+		}
+	}
+
+	match (protocol_version, network_id, genesis_hash) {
+		(Some(pv), Some(id), Some(hash)) => Ok((pv, id, hash)),
+		_ => Err(DecoderError::Custom("Missing status keys")),
+	}
+}
+
+/// A connected light client.
+struct Peer {
+	/// Status received from the peer.
+	handshake_done: bool,
+	connected: Instant,
+	buffer: Buffer,
+	/// Last chain head announced to the peer.
+	sent_head: H256,
+}
+
+/// Serves LES requests using a light data provider.
+pub struct LesServer {
+	provider: Arc<Provider>,
+	chain: Arc<BlockChainClient>,
+	network_id: u64,
+	config: LesConfig,
+	serve_chain_since: u64,
+	serve_state_since: Option<u64>,
+	peers: RwLock<HashMap<PeerId, Peer>>,
+	cht: RwLock<Cht>,
+}
+
+impl LesServer {
+	/// Create a new LES server. Transactions sent by clients are imported into given chain.
+	pub fn new(provider: Arc<Provider>, chain: Arc<BlockChainClient>, network_id: u64, config: LesConfig) -> Self {
+		let pruning_info = chain.pruning_info();
+		LesServer {
+			provider: provider,
+			chain: chain,
+			network_id: network_id,
+			config: config,
+			serve_chain_since: pruning_info.earliest_chain,
+			serve_state_since: Some(pruning_info.earliest_state),
+			peers: RwLock::new(HashMap::new()),
+			cht: RwLock::new(Cht::new(CHT_KEPT_SECTIONS)),
+		}
+	}
+
+	/// Number of connected light clients.
+	pub fn peer_count(&self) -> usize {
+		self.peers.read().len()
+	}
+
+	/// Announce new chain head to all clients.
+	pub fn announce(&self, io: &NetworkContext) {
+		let chain_info = self.provider.chain_info();
+		for (peer_id, peer) in self.peers.write().iter_mut() {
+			if !peer.handshake_done || peer.sent_head == chain_info.best_block_hash {
+				continue;
+			}
+
+			let reorg_depth = self.provider.reorg_depth(&chain_info.best_block_hash, &peer.sent_head).unwrap_or(0);
+			peer.sent_head = chain_info.best_block_hash;
+
+			let mut stream = RlpStream::new_list(5);
+			stream.append(&chain_info.best_block_hash)
+				.append(&chain_info.best_block_number)
+				.append(&chain_info.total_difficulty)
+				.append(&reorg_depth)
+				.begin_list(0);
+
+			if let Err(e) = io.send(*peer_id, packet::ANNOUNCE, stream.out()) {
+				debug!(target: "les", "Error sending announcement to peer {}: {:?}", peer_id, e);
+			}
+		}
+	}
+
+	/// Disconnect all clients.
+	pub fn abort(&self) {
+		self.peers.write().clear();
+	}
+
+	fn on_connect(&self, io: &NetworkContext, peer: PeerId) {
+		let protocol_version = match io.protocol_version(io.subprotocol_name(), peer) {
+			Some(pv) if PROTOCOL_VERSIONS.contains(&pv) => pv,
+			other => {
+				debug!(target: "les", "Peer {} connected with unsupported protocol version {:?}", peer, other);
+				io.disconnect_peer(peer);
+				return;
+			}
+		};
+
+		if self.peers.read().len() >= self.config.max_peers {
+			debug!(target: "les", "Too many light clients, rejecting peer {}", peer);
+			io.disconnect_peer(peer);
+			return;
+		}
+
+		let chain_info = self.provider.chain_info();
+		let status = Status {
+			protocol_version: protocol_version,
+			network_id: self.network_id,
+			head_td: chain_info.total_difficulty,
+			head_hash: chain_info.best_block_hash,
+			head_num: chain_info.best_block_number,
+			genesis_hash: chain_info.genesis_hash,
+			serve_chain_since: self.serve_chain_since,
+			serve_state_since: self.serve_state_since,
+		};
+
+		let now = Instant::now();
+		self.peers.write().insert(peer, Peer {
+			handshake_done: false,
+			connected: now,
+			buffer: Buffer::new(&self.config, now),
+			sent_head: chain_info.best_block_hash,
+		});
+
+		trace!(target: "les", "Sending status to peer {}", peer);
+		if let Err(e) = io.send(peer, packet::STATUS, write_status(&status, &self.config)) {
+			debug!(target: "les", "Error sending status to peer {}: {:?}", peer, e);
+		}
+	}
+
+	fn on_disconnect(&self, peer: PeerId) {
+		trace!(target: "les", "Peer {} disconnecting", peer);
+		self.peers.write().remove(&peer);
+	}
+
+	fn timeout_check(&self, io: &NetworkContext) {
+		let now = Instant::now();
+		let timeout = Duration::from_millis(HANDSHAKE_TIMEOUT_MS);
+		let mut peers = self.peers.write();
+		let slowpokes: Vec<_> = peers.iter()
+			.filter(|&(_, peer)| !peer.handshake_done && now.duration_since(peer.connected) > timeout)
+			.map(|(id, _)| *id)
+			.collect();
+
+		for peer in slowpokes {
+			debug!(target: "les", "Peer {} handshake timed out", peer);
+			peers.remove(&peer);
+			io.disconnect_peer(peer);
+		}
+	}
+
+	fn handle_packet(&self, io: &NetworkContext, peer: PeerId, packet_id: u8, data: &[u8]) {
+		let rlp = UntrustedRlp::new(data);
+		trace!(target: "les", "Incoming packet {} from peer {}", packet_id, peer);
+
+		let res = match packet_id {
+			packet::STATUS => self.status(io, peer, &rlp),
+			packet::ANNOUNCE => Ok(()),
+			packet::SEND_TRANSACTIONS => self.relay_transactions(peer, &rlp),
+			packet::GET_BLOCK_HEADERS | packet::GET_BLOCK_BODIES | packet::GET_RECEIPTS
+				| packet::GET_PROOFS | packet::GET_CONTRACT_CODES | packet::GET_HEADER_PROOFS =>
+				self.request(io, peer, packet_id, &rlp),
+			other => Err(Error::UnrecognizedPacket(other)),
+		};
+
+		if let Err(e) = res {
+			punish(io, peer, e);
+		}
+	}
+
+	fn status(&self, io: &NetworkContext, peer: PeerId, rlp: &UntrustedRlp) -> Result<(), Error> {
+		let (protocol_version, network_id, genesis_hash) = parse_status(rlp)?;
+		let mut peers = self.peers.write();
+		let info = peers.get_mut(&peer).ok_or(Error::UnknownPeer)?;
+		if info.handshake_done {
+			return Err(Error::UnexpectedHandshake);
+		}
+
+		if (network_id, genesis_hash) != (self.network_id, self.provider.chain_info().genesis_hash) {
+			return Err(Error::WrongNetwork);
+		}
+
+		if Some(protocol_version as u8) != io.protocol_version(io.subprotocol_name(), peer) {
+			return Err(Error::BadProtocolVersion);
+		}
+
+		trace!(target: "les", "Light client {} connected", peer);
+		info.handshake_done = true;
+		Ok(())
+	}
+
+	// charge the peer for the request. returns the remaining buffer value.
+	fn charge(&self, peer: PeerId, packet_id: u8, items: usize) -> Result<u64, Error> {
+		let mut peers = self.peers.write();
+		match peers.get_mut(&peer) {
+			Some(info) if info.handshake_done => {
+				info.buffer.deduct(&self.config, cost(packet_id, items), Instant::now())?;
+				Ok(info.buffer.value)
+			},
+			_ => Err(Error::UnknownPeer),
+		}
+	}
+
+	// give back credits for the items which weren't served. returns the remaining buffer value.
+	fn refund(&self, peer: PeerId, amount: u64) -> Result<u64, Error> {
+		let mut peers = self.peers.write();
+		let info = peers.get_mut(&peer).ok_or(Error::UnknownPeer)?;
+		info.buffer.refund(&self.config, amount);
+		Ok(info.buffer.value)
+	}
+
+	fn request(&self, io: &NetworkContext, peer: PeerId, packet_id: u8, rlp: &UntrustedRlp) -> Result<(), Error> {
+		let req_id: u64 = rlp.val_at(0)?;
+		let req = rlp.at(1)?;
+
+		// answering partially would leave the peer waiting for the rest.
+		let requested = match packet_id {
+			packet::GET_BLOCK_HEADERS => req.val_at::<u64>(1)?,
+			_ => req.item_count()? as u64,
+		};
+		if requested > limit(packet_id) as u64 {
+			return Err(Error::OversizedRequest);
+		}
+
+		let requested = requested as usize;
+		self.charge(peer, packet_id, requested)?;
+
+		let (response_id, served, response) = match packet_id {
+			packet::GET_BLOCK_HEADERS => {
+				let request = CompleteHeadersRequest {
+					start: req.val_at::<HashOrNumber>(0)?,
+					max: requested as u64,
+					skip: req.val_at(2)?,
+					reverse: req.val_at(3)?,
+				};
+				let (served, response) = self.headers(request);
+				(packet::BLOCK_HEADERS, served, response)
+			},
+			packet::GET_BLOCK_BODIES => {
+				let (served, response) = self.bodies(&req.as_list::<H256>()?);
+				(packet::BLOCK_BODIES, served, response)
+			},
+			packet::GET_RECEIPTS => {
+				let (served, response) = self.receipts(&req.as_list::<H256>()?);
+				(packet::RECEIPTS, served, response)
+			},
+			packet::GET_PROOFS => {
+				(packet::PROOFS, requested, self.proofs(&req.iter().collect::<Vec<_>>())?)
+			},
+			packet::GET_CONTRACT_CODES => {
+				(packet::CONTRACT_CODES, requested, self.codes(&req.iter().collect::<Vec<_>>())?)
+			},
+			packet::GET_HEADER_PROOFS => {
+				let (served, response) = self.header_proofs(&req.iter().collect::<Vec<_>>())?;
+				(packet::HEADER_PROOFS, served, response)
+			},
+			other => return Err(Error::UnrecognizedPacket(other)),
+		};
+
+		let buffer_value = self.refund(peer, cost(packet_id, requested) - cost(packet_id, served))?;
+		trace!(target: "les", "Responding to request {} from peer {} ({}/{} items), buffer left: {}", req_id, peer, served, requested, buffer_value);
+
+		let mut stream = RlpStream::new_list(3);
+		stream.append(&req_id).append(&buffer_value).append_raw(&response, 1);
+		io.respond(response_id, stream.out())?;
+		Ok(())
+	}
+
+	fn headers(&self, request: CompleteHeadersRequest) -> (usize, Bytes) {
+		let headers = self.provider.block_headers(request).map_or_else(Vec::new, |res| res.headers);
+		let served = headers.len();
+		let mut stream = RlpStream::new_list(served);
+		for header in headers {
+			stream.append_raw(&header.into_inner(), 1);
+		}
+		(served, stream.out())
+	}
+
+	fn bodies(&self, hashes: &[H256]) -> (usize, Bytes) {
+		let bodies: Vec<_> = hashes.iter()
+			.filter_map(|hash| self.provider.block_body(CompleteBodyRequest { hash: *hash }))
+			.collect();
+		let served = bodies.len();
+		let mut stream = RlpStream::new_list(served);
+		for res in bodies {
+			stream.append_raw(&res.body.into_inner(), 1);
+		}
+		(served, stream.out())
+	}
+
+	fn receipts(&self, hashes: &[H256]) -> (usize, Bytes) {
+		let receipts: Vec<_> = hashes.iter()
+			.filter_map(|hash| self.provider.block_receipts(CompleteReceiptsRequest { hash: *hash }))
+			.collect();
+		let served = receipts.len();
+		let mut stream = RlpStream::new_list(served);
+		for res in receipts {
+			stream.append_list::<Receipt, _>(&res.receipts);
+		}
+		(served, stream.out())
+	}
+
+	// each request is `[cht_number, block_number, from_level]`, answered with
+	// `[header, proof]`. requests which can't be proven are left out.
+	fn header_proofs(&self, reqs: &[UntrustedRlp]) -> Result<(usize, Bytes), Error> {
+		let cht = self.cht.read();
+		let mut proofs = Vec::new();
+		for req in reqs {
+			let section: u64 = req.val_at(0)?;
+			let num: u64 = req.val_at(1)?;
+			let from_level: u32 = req.val_at(2)?;
+
+			let proof = cht.prove(section, num, from_level)
+				.and_then(|proof| self.chain.block_header(BlockId::Number(num)).map(|header| (header, proof)));
+			if let Some(proof) = proof {
+				proofs.push(proof);
+			}
+		}
+
+		let served = proofs.len();
+		let mut stream = RlpStream::new_list(served);
+		for (header, proof) in proofs {
+			stream.begin_list(2).append_raw(&header.into_inner(), 1).begin_list(proof.len());
+			for node in proof {
+				stream.append_raw(&node, 1);
+			}
+		}
+		Ok((served, stream.out()))
+	}
+
+	// add confirmed sections to the canonical hash trie.
+	fn update_cht(&self) {
+		let best_block = self.chain.chain_info().best_block_number;
+		let mut cht = self.cht.write();
+		for _ in 0..CHT_SECTIONS_PER_TICK {
+			let last_num = (cht.sections() + 1) * CHT_SIZE - 1;
+			if last_num + CHT_CONFIRMATIONS > best_block {
+				break;
+			}
+
+			let chain = &self.chain;
+			let added = cht.add_section(|num| {
+				let id = BlockId::Number(num);
+				chain.block_hash(id).and_then(|hash| chain.block_total_difficulty(id).map(|td| (hash, td)))
+			});
+			if !added {
+				debug!(target: "les", "Missing blocks of CHT section {}, header proofs are limited", cht.sections());
+				break;
+			}
+			trace!(target: "les", "Added CHT section {}", cht.sections() - 1);
+		}
+	}
+
+	// each request is `[block_hash, account_key, key, from_level]`.
+	// empty account key means that the account proof for `key` is requested.
+	fn proofs(&self, reqs: &[UntrustedRlp]) -> Result<Bytes, Error> {
+		let mut stream = RlpStream::new_list(reqs.len());
+		for req in reqs {
+			let block_hash: H256 = req.val_at(0)?;
+			let account_key: Bytes = req.val_at(1)?;
+			let key = hash_key(&req.val_at::<Bytes>(2)?)?;
+			let from_level: usize = req.val_at(3)?;
+
+			let proof = match account_key.is_empty() {
+				true => self.provider.account_proof(CompleteAccountRequest {
+					block_hash: block_hash,
+					address_hash: key,
+				}).map(|res| res.proof),
+				false => self.provider.storage_proof(CompleteStorageRequest {
+					block_hash: block_hash,
+					address_hash: hash_key(&account_key)?,
+					key_hash: key,
+				}).map(|res| res.proof),
+			}.unwrap_or_else(Vec::new);
+
+			let nodes = proof.into_iter().skip(from_level).collect::<Vec<_>>();
+			stream.begin_list(nodes.len());
+			for node in nodes {
+				stream.append_raw(&node, 1);
+			}
+		}
+		Ok(stream.out())
+	}
+
+	// each request is `[block_hash, account_key]`.
+	fn codes(&self, reqs: &[UntrustedRlp]) -> Result<Bytes, Error> {
+		let mut stream = RlpStream::new_list(reqs.len());
+		for req in reqs {
+			let block_hash: H256 = req.val_at(0)?;
+			let address_hash = hash_key(&req.val_at::<Bytes>(1)?)?;
+
+			let code = self.provider.account_proof(CompleteAccountRequest {
+				block_hash: block_hash,
+				address_hash: address_hash,
+			}).and_then(|account| self.provider.contract_code(CompleteCodeRequest {
+				block_hash: block_hash,
+				code_hash: account.code_hash,
+			})).map_or_else(Vec::new, |res| res.code);
+
+			stream.append(&code);
+		}
+		Ok(stream.out())
+	}
+
+	fn relay_transactions(&self, peer: PeerId, rlp: &UntrustedRlp) -> Result<(), Error> {
+		if rlp.item_count()? > limits::TRANSACTIONS {
+			return Err(Error::OversizedRequest);
+		}
+
+		let transactions = rlp.iter().collect::<Vec<_>>();
+		self.charge(peer, packet::SEND_TRANSACTIONS, transactions.len())?;
+
+		let transactions = transactions.into_iter()
+			.map(|tx| tx.as_val::<UnverifiedTransaction>().map(|_| tx.as_raw().to_vec()))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		debug!(target: "les", "Received {} transactions to relay from peer {}", transactions.len(), peer);
+		self.chain.queue_transactions(transactions, peer);
+		Ok(())
+	}
+}
+
+fn hash_key(key: &[u8]) -> Result<H256, DecoderError> {
+	match key.len() {
+		32 => Ok(H256::from_slice(key)),
+		_ => Err(DecoderError::Custom("Invalid trie key")),
+	}
+}
+
+// if something went wrong, figure out how much to punish the peer.
+fn punish(io: &NetworkContext, peer: PeerId, e: Error) {
+	match e {
+		Error::Network(_) | Error::Overburdened => {},
+		Error::Rlp(_) | Error::NoCredits | Error::WrongNetwork
+			| Error::UnsupportedProtocolVersion(_) | Error::BadProtocolVersion => {
+			debug!(target: "les", "Disabling peer {}: {}", peer, e);
+			io.disable_peer(peer);
+		},
+		_ => {
+			debug!(target: "les", "Disconnecting peer {}: {}", peer, e);
+			io.disconnect_peer(peer);
+		},
+	}
+}
+
+impl NetworkProtocolHandler for LesServer {
+	fn initialize(&self, io: &NetworkContext) {
+		io.register_timer(TIMEOUT, TIMEOUT_INTERVAL_MS)
+			.expect("Error registering LES timer.");
+	}
+
+	fn read(&self, io: &NetworkContext, peer: &PeerId, packet_id: u8, data: &[u8]) {
+		self.handle_packet(io, *peer, packet_id, data);
+	}
+
+	fn connected(&self, io: &NetworkContext, peer: &PeerId) {
+		self.on_connect(io, *peer);
+	}
+
+	fn disconnected(&self, _io: &NetworkContext, peer: &PeerId) {
+		self.on_disconnect(*peer);
+	}
+
+	fn timeout(&self, io: &NetworkContext, timer: TimerToken) {
+		match timer {
+			TIMEOUT => {
+				self.timeout_check(io);
+				self.update_cht();
+			},
+			_ => warn!(target: "les", "received timeout on unknown token {}", timer),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use rlp::{RlpStream, UntrustedRlp};
+	use util::{H256, U256, HashDB, MemoryDB};
+	use util::trie::{Trie, TrieDB};
+	use super::{Buffer, Cht, CHT_SIZE, CHT_KEPT_SECTIONS, LesConfig, Status, write_status, parse_status, cost, limit, cht_key, packet};
+
+	#[test]
+	fn should_write_and_parse_status() {
+		let status = Status {
+			protocol_version: 1,
+			network_id: 42,
+			head_td: 100.into(),
+			head_hash: H256::from(1),
+			head_num: 10,
+			genesis_hash: H256::from(2),
+			serve_chain_since: 0,
+			serve_state_since: Some(5),
+		};
+
+		let encoded = write_status(&status, &LesConfig::default());
+		let rlp = UntrustedRlp::new(&encoded);
+		assert_eq!(parse_status(&rlp).unwrap(), (1, 42, H256::from(2)));
+
+		let keys: Vec<String> = rlp.iter().map(|pair| pair.val_at(0).unwrap()).collect();
+		assert!(keys.contains(&"serveStateSince".to_owned()));
+		assert!(keys.contains(&"flowControl/MRC".to_owned()));
+	}
+
+	#[test]
+	fn should_reject_incomplete_status() {
+		let status = ::rlp::encode_list::<Vec<u8>, _>(&[]);
+		assert!(parse_status(&UntrustedRlp::new(&status)).is_err());
+	}
+
+	#[test]
+	fn should_charge_and_recharge_buffer() {
+		let config = LesConfig { max_peers: 1, buffer_limit: 1000, recharge_rate: 100 };
+		let now = Instant::now();
+		let mut buffer = Buffer::new(&config, now);
+
+		assert!(buffer.deduct(&config, 800, now).is_ok());
+		assert!(buffer.deduct(&config, 300, now).is_err());
+		assert_eq!(buffer.value, 200);
+
+		// recharged by 100 per second, but never above the limit.
+		assert!(buffer.deduct(&config, 300, now + Duration::from_secs(1)).is_ok());
+		assert_eq!(buffer.value, 0);
+		assert!(buffer.deduct(&config, 0, now + Duration::from_secs(100)).is_ok());
+		assert_eq!(buffer.value, 1000);
+
+		// refunds never go above the limit either.
+		assert!(buffer.deduct(&config, 600, now + Duration::from_secs(100)).is_ok());
+		buffer.refund(&config, 400);
+		assert_eq!(buffer.value, 800);
+		buffer.refund(&config, 400);
+		assert_eq!(buffer.value, 1000);
+	}
+
+	#[test]
+	fn should_compute_request_cost() {
+		assert_eq!(cost(packet::GET_BLOCK_HEADERS, 2), 210_000);
+		assert_eq!(cost(packet::GET_RECEIPTS, 3), 3_000_000);
+		assert_eq!(cost(packet::STATUS, 3), 0);
+		assert_eq!(cost(packet::GET_RECEIPTS, usize::max_value()), u64::max_value());
+		assert_eq!(limit(packet::GET_BLOCK_HEADERS), 192);
+		assert_eq!(limit(packet::STATUS), 0);
+	}
+
+	#[test]
+	fn should_prove_blocks_in_cht() {
+		let entry = |num: u64| (H256::from(num + 1), U256::from(num * 10));
+		let mut cht = Cht::new(CHT_KEPT_SECTIONS);
+		assert!(cht.add_section(|num| Some(entry(num))));
+		let first_root = cht.roots[0];
+		assert!(cht.add_section(|num| Some(entry(num))));
+		assert!(!cht.add_section(|num| if num < 2 * CHT_SIZE + 10 { Some(entry(num)) } else { None }));
+		assert_eq!(cht.sections(), 2);
+
+		// blocks of the first section can be proven against both roots.
+		for &(section, root) in &[(0, first_root), (1, cht.roots[1])] {
+			let proof = cht.prove(section, 100, 0).unwrap();
+			let mut db = MemoryDB::new();
+			for node in &proof {
+				db.insert(node);
+			}
+
+			let mut expected = RlpStream::new_list(2);
+			expected.append(&H256::from(101)).append(&U256::from(1000));
+			let t = TrieDB::new(&db, &root).unwrap();
+			assert_eq!(&*t.get(&cht_key(100)).unwrap().unwrap(), &*expected.out());
+		}
+
+		// nodes before the requested level are left out.
+		assert_eq!(cht.prove(1, 100, 1).unwrap().len(), cht.prove(1, 100, 0).unwrap().len() - 1);
+
+		// blocks not covered by the section.
+		assert!(cht.prove(0, CHT_SIZE, 0).is_none());
+		assert!(cht.prove(2, 100, 0).is_none());
+	}
+
+	#[test]
+	fn should_prune_nodes_of_old_sections() {
+		let entry = |num: u64| (H256::from(num + 1), U256::from(num * 10));
+		let mut cht = Cht::new(2);
+		let mut archive = Cht::new(100);
+		assert!(cht.add_section(|num| Some(entry(num))));
+		assert!(cht.add_section(|num| Some(entry(num))));
+		assert!(cht.prove(0, 100, 0).is_some());
+
+		assert!(cht.add_section(|num| Some(entry(num))));
+		assert!(cht.prove(0, 100, 0).is_none());
+		assert!(cht.prove(1, 100, 0).is_some());
+		assert!(cht.prove(2, 100, 0).is_some());
+
+		assert!(cht.add_section(|num| Some(entry(num))));
+		assert!(cht.prove(1, 100, 0).is_none());
+		assert!(cht.prove(2, 2 * CHT_SIZE, 0).is_some());
+
+		// the same roots, without the nodes of the pruned ones.
+		for _ in 0..4 {
+			assert!(archive.add_section(|num| Some(entry(num))));
+		}
+		assert_eq!(archive.roots, cht.roots);
+		assert!(archive.prove(0, 100, 0).is_some());
+		assert!(cht.db.keys().len() < archive.db.keys().len());
+	}
+
+	#[test]
+	fn should_encode_cht_key() {
+		assert_eq!(cht_key(0x0102), [0, 0, 0, 0, 0, 0, 1, 2]);
+	}
+}
@@ -37,6 +37,10 @@ extern crate semver;
 extern crate parking_lot;
 extern crate smallvec;
 extern crate rlp;
+extern crate ws;
+extern crate native_tls;
+extern crate rustc_serialize;
+extern crate sha1;
 
 extern crate ethcore_light as light;
 
@@ -57,9 +61,15 @@ mod blocks;
 mod block_sync;
 mod sync_io;
 mod snapshot;
+mod snapshot_serving;
+mod local_propagation;
+mod schedule;
 mod transactions_stats;
+mod les;
+mod ws_tls;
 
 pub mod light_sync;
+pub mod light_ws;
 
 #[cfg(test)]
 mod tests;
@@ -75,7 +85,13 @@ mod api;
 
 pub use api::*;
 pub use chain::{SyncStatus, SyncState};
-pub use network::{is_valid_node_url, NonReservedPeerMode, NetworkError};
+pub use snapshot_serving::SnapshotServingConfig;
+pub use local_propagation::LocalPropagationConfig;
+pub use schedule::{SyncSchedule, SyncLimits, ScheduleEntry};
+pub use snapshot::SnapshotDownloadConfig;
+pub use les::LesConfig;
+pub use ws_tls::parse_url as parse_wss_url;
+pub use network::{is_valid_node_url, is_valid_boot_node_url, NonReservedPeerMode, NetworkError, IpNetwork};
 
 /// IPC interfaces
 #[cfg(feature="ipc")]
@@ -95,6 +95,8 @@ impl Peer {
 				serve_state_since: None,
 				tx_relay: true,
 			},
+			tx_relay_peers: None,
+			max_peers: None,
 		};
 
 		let proto = LightProtocol::new(chain.clone(), params);
@@ -117,6 +119,8 @@ impl Peer {
 				serve_state_since: None,
 				tx_relay: false,
 			},
+			tx_relay_peers: None,
+			max_peers: None,
 		};
 
 		let provider = LightProvider::new(chain.clone(), Arc::new(RwLock::new(Default::default())));
@@ -0,0 +1,507 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Light protocol over WebSockets.
+//!
+//! Carries PIP packets over WebSocket connections instead of devp2p sessions,
+//! so light clients behind networks which only let HTTP(S) traffic through can
+//! still sync with proof verification. Every binary message holds a single
+//! packet: the packet id followed by the packet body.
+//!
+//! Servers can be reached over `ws://` or `wss://`. Clients are only accepted over
+//! plain connections, TLS is expected to be terminated by a proxy in front of the
+//! serving node.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{mpsc, Arc};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use light::net::{IoContext, LightProtocol, Transport, TransportIo, MAX_PROTOCOL_VERSION};
+use network::{NetworkService, NodeId, PeerId, ProtocolId};
+use util::{Hashable, Mutex, RwLock};
+use ws;
+use ws_tls;
+
+// ids of WebSocket peers start here, well clear of devp2p session tokens.
+const FIRST_PEER: PeerId = 1 << 20;
+// delay before reconnecting to a server after the connection was lost.
+const RECONNECT_DELAY_MS: u64 = 10_000;
+// interval of checking whether the transport was stopped while waiting to reconnect.
+const RECONNECT_POLL_MS: u64 = 250;
+// longest time outgoing packets wait on a secure connection while reading.
+const TLS_POLL_MS: u64 = 20;
+
+/// Light protocol peer connected over a WebSocket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WsPeerInfo {
+	/// Peer id used by the protocol handler.
+	pub id: PeerId,
+	/// Persistent id derived from the peer's address.
+	pub node_id: NodeId,
+	/// Server URL or remote address of the peer.
+	pub address: String,
+}
+
+// sending half of a connection.
+enum Out {
+	Ws(ws::Sender),
+	// secure connections are driven by their own thread.
+	Tls(Mutex<mpsc::Sender<Outgoing>>),
+}
+
+enum Outgoing {
+	Message(Vec<u8>),
+	Close(u16),
+}
+
+impl Out {
+	fn send(&self, message: Vec<u8>) -> Result<(), String> {
+		match *self {
+			Out::Ws(ref out) => out.send(ws::Message::Binary(message)).map_err(|e| e.to_string()),
+			Out::Tls(ref out) => out.lock().send(Outgoing::Message(message)).map_err(|_| "Connection closed".into()),
+		}
+	}
+
+	fn close(&self, code: ws::CloseCode) {
+		match *self {
+			Out::Ws(ref out) => {
+				let _ = out.close(code);
+			},
+			Out::Tls(ref out) => {
+				let code = match code {
+					ws::CloseCode::Away => 1001,
+					_ => 1008,
+				};
+				let _ = out.lock().send(Outgoing::Close(code));
+			},
+		}
+	}
+}
+
+struct Peer {
+	out: Out,
+	info: WsPeerInfo,
+}
+
+/// Peers connected over WebSockets, to be attached to the protocol handler
+/// with `LightProtocol::set_transport`.
+pub struct WsPeers {
+	peers: RwLock<HashMap<PeerId, Peer>>,
+	next_id: AtomicUsize,
+}
+
+impl WsPeers {
+	/// Create an empty set of peers.
+	pub fn new() -> Self {
+		WsPeers {
+			peers: RwLock::new(HashMap::new()),
+			next_id: AtomicUsize::new(FIRST_PEER),
+		}
+	}
+
+	/// Information about all connected peers.
+	pub fn peers(&self) -> Vec<WsPeerInfo> {
+		self.peers.read().values().map(|peer| peer.info.clone()).collect()
+	}
+
+	fn add(&self, out: Out, address: String) -> PeerId {
+		let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+		let mut node_id = NodeId::default();
+		node_id.clone_from_slice(&*address.as_bytes().sha3());
+
+		self.peers.write().insert(id, Peer {
+			out: out,
+			info: WsPeerInfo {
+				id: id,
+				node_id: node_id,
+				address: address,
+			},
+		});
+
+		id
+	}
+
+	fn remove(&self, peer: PeerId) {
+		self.peers.write().remove(&peer);
+	}
+
+	fn disconnect_all(&self) {
+		for peer in self.peers.read().values() {
+			peer.out.close(ws::CloseCode::Away);
+		}
+	}
+}
+
+impl Transport for WsPeers {
+	fn has_peer(&self, peer: PeerId) -> bool {
+		self.peers.read().contains_key(&peer)
+	}
+
+	fn send(&self, peer: PeerId, packet_id: u8, packet_body: Vec<u8>) {
+		if let Some(p) = self.peers.read().get(&peer) {
+			if let Err(e) = p.out.send(encode(packet_id, packet_body)) {
+				debug!(target: "pip", "Error sending packet to WebSocket peer {}: {}", peer, e);
+			}
+		}
+	}
+
+	fn disconnect_peer(&self, peer: PeerId) {
+		trace!(target: "pip", "Initiating disconnect of WebSocket peer {}", peer);
+		if let Some(p) = self.peers.read().get(&peer) {
+			p.out.close(ws::CloseCode::Policy);
+		}
+	}
+
+	fn protocol_version(&self, peer: PeerId) -> Option<u8> {
+		match self.has_peer(peer) {
+			true => Some(MAX_PROTOCOL_VERSION),
+			false => None,
+		}
+	}
+
+	fn persistent_peer_id(&self, peer: PeerId) -> Option<NodeId> {
+		self.peers.read().get(&peer).map(|p| p.info.node_id)
+	}
+}
+
+// prefix the packet body with its id.
+fn encode(packet_id: u8, packet_body: Vec<u8>) -> Vec<u8> {
+	let mut message = Vec::with_capacity(packet_body.len() + 1);
+	message.push(packet_id);
+	message.extend(packet_body);
+	message
+}
+
+// split a message into the packet id and body.
+fn decode(message: &[u8]) -> Option<(u8, &[u8])> {
+	message.split_first().map(|(id, body)| (*id, body))
+}
+
+struct Shared {
+	peers: Arc<WsPeers>,
+	proto: Arc<LightProtocol>,
+	network: Arc<NetworkService>,
+	protocol: ProtocolId,
+	// bumped on every start and stop, so threads of earlier runs wind down.
+	generation: AtomicUsize,
+}
+
+impl Shared {
+	fn is_current(&self, generation: usize) -> bool {
+		self.generation.load(Ordering::SeqCst) == generation
+	}
+
+	fn connect(&self, out: Out, address: String) -> PeerId {
+		debug!(target: "pip", "WebSocket peer connected: {}", address);
+		let peer = self.peers.add(out, address);
+		let proto = &self.proto;
+		self.with_io(None, |io| proto.on_connect(&peer, io));
+		peer
+	}
+
+	fn disconnect(&self, peer: PeerId) {
+		debug!(target: "pip", "WebSocket peer {} disconnected", peer);
+		self.peers.remove(peer);
+		let proto = &self.proto;
+		self.with_io(None, |io| proto.on_disconnect(peer, io));
+	}
+
+	// handle a message of the peer. false if it's invalid.
+	fn on_message(&self, peer: PeerId, data: &[u8]) -> bool {
+		match decode(data) {
+			Some((packet_id, body)) => {
+				let proto = &self.proto;
+				self.with_io(Some(peer), |io| proto.handle_packet(io, &peer, packet_id, body));
+				true
+			},
+			None => {
+				debug!(target: "pip", "WebSocket peer {} sent an empty message", peer);
+				false
+			},
+		}
+	}
+
+	// run a closure with an I/O context reaching both WebSocket and devp2p peers.
+	fn with_io<F>(&self, sender: Option<PeerId>, f: F) where F: FnOnce(&IoContext) {
+		let transport: &Transport = &*self.peers;
+		let mut f = Some(f);
+
+		self.network.with_context(self.protocol, |ctx| if let Some(f) = f.take() {
+			f(&TransportIo {
+				network: Some(ctx as &IoContext),
+				transport: transport,
+				sender: sender,
+			})
+		});
+
+		// devp2p isn't running: WebSocket peers are all we can reach.
+		if let Some(f) = f {
+			f(&TransportIo {
+				network: None,
+				transport: transport,
+				sender: sender,
+			})
+		}
+	}
+}
+
+struct Connection {
+	out: ws::Sender,
+	// server URL for outgoing connections, taken from the handshake otherwise.
+	address: Option<String>,
+	peer: Option<PeerId>,
+	shared: Arc<Shared>,
+	generation: usize,
+}
+
+impl Connection {
+	fn new(out: ws::Sender, address: Option<String>, shared: Arc<Shared>, generation: usize) -> Self {
+		Connection {
+			out: out,
+			address: address,
+			peer: None,
+			shared: shared,
+			generation: generation,
+		}
+	}
+}
+
+impl ws::Handler for Connection {
+	fn on_open(&mut self, shake: ws::Handshake) -> ws::Result<()> {
+		if !self.shared.is_current(self.generation) {
+			return self.out.close(ws::CloseCode::Away);
+		}
+
+		let address = match self.address.take() {
+			Some(url) => url,
+			None => shake.remote_addr().ok().and_then(|addr| addr).unwrap_or_else(|| "unknown".into()),
+		};
+
+		self.peer = Some(self.shared.connect(Out::Ws(self.out.clone()), address));
+		Ok(())
+	}
+
+	fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
+		let peer = match self.peer {
+			Some(peer) => peer,
+			None => return Ok(()),
+		};
+
+		let data = match msg {
+			ws::Message::Binary(data) => data,
+			ws::Message::Text(_) => {
+				debug!(target: "pip", "WebSocket peer {} sent a text message", peer);
+				return self.out.close(ws::CloseCode::Unsupported);
+			}
+		};
+
+		match self.shared.on_message(peer, &data) {
+			true => Ok(()),
+			false => self.out.close(ws::CloseCode::Protocol),
+		}
+	}
+}
+
+impl Drop for Connection {
+	fn drop(&mut self) {
+		if let Some(peer) = self.peer.take() {
+			self.shared.disconnect(peer);
+		}
+	}
+}
+
+/// WebSocket transport for the light protocol: keeps connections to the
+/// given light servers and optionally accepts light clients.
+pub struct LightWs {
+	shared: Arc<Shared>,
+	servers: Vec<String>,
+	listen: Option<SocketAddr>,
+	listener: Mutex<Option<ws::Sender>>,
+}
+
+impl LightWs {
+	/// Create the transport. `peers` must be attached to `proto`, which is
+	/// registered with `network` under the `protocol` id.
+	pub fn new(
+		peers: Arc<WsPeers>,
+		proto: Arc<LightProtocol>,
+		network: Arc<NetworkService>,
+		protocol: ProtocolId,
+		servers: Vec<String>,
+		listen: Option<SocketAddr>,
+	) -> Self {
+		LightWs {
+			shared: Arc::new(Shared {
+				peers: peers,
+				proto: proto,
+				network: network,
+				protocol: protocol,
+				generation: AtomicUsize::new(0),
+			}),
+			servers: servers,
+			listen: listen,
+			listener: Mutex::new(None),
+		}
+	}
+
+	/// Information about all connected peers.
+	pub fn peers(&self) -> Vec<WsPeerInfo> {
+		self.shared.peers.peers()
+	}
+
+	/// Start connecting to the servers and listening for clients.
+	pub fn start(&self) {
+		let generation = self.shared.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+		for url in &self.servers {
+			let shared = self.shared.clone();
+			let url = url.clone();
+			let spawned = thread::Builder::new()
+				.name("pip-ws-client".into())
+				.spawn(move || keep_connected(shared, url, generation));
+
+			if let Err(e) = spawned {
+				warn!("Error starting light protocol WebSocket client: {}", e);
+			}
+		}
+
+		if let Some(addr) = self.listen {
+			let shared = self.shared.clone();
+			let mut settings = ws::Settings::default();
+			// the node decides on its own when to shut down.
+			settings.shutdown_on_interrupt = false;
+
+			let socket = ws::Builder::new().with_settings(settings).build(move |out| {
+				Connection::new(out, None, shared.clone(), generation)
+			});
+
+			let socket = match socket {
+				Ok(socket) => socket,
+				Err(e) => {
+					warn!("Error starting light protocol WebSocket server: {}", e);
+					return;
+				}
+			};
+
+			*self.listener.lock() = Some(socket.broadcaster());
+			let spawned = thread::Builder::new()
+				.name("pip-ws-server".into())
+				.spawn(move || if let Err(e) = socket.listen(addr) {
+					warn!("Error listening for light clients over WebSockets on {}: {}", addr, e);
+				});
+
+			if let Err(e) = spawned {
+				warn!("Error starting light protocol WebSocket server: {}", e);
+			}
+		}
+	}
+
+	/// Drop all connections and stop reconnecting and listening.
+	pub fn stop(&self) {
+		self.shared.generation.fetch_add(1, Ordering::SeqCst);
+
+		if let Some(listener) = self.listener.lock().take() {
+			if let Err(e) = listener.shutdown() {
+				debug!(target: "pip", "Error stopping light protocol WebSocket server: {}", e);
+			}
+		}
+
+		self.shared.peers.disconnect_all();
+	}
+}
+
+// connect to the server, reconnecting whenever the connection is lost.
+fn keep_connected(shared: Arc<Shared>, url: String, generation: usize) {
+	while shared.is_current(generation) {
+		let connected = match url.starts_with("wss://") {
+			true => connect_tls(&shared, &url, generation),
+			false => ws::connect(url.clone(), |out| {
+				Connection::new(out, Some(url.clone()), shared.clone(), generation)
+			}).map_err(|e| e.to_string()),
+		};
+
+		if let Err(e) = connected {
+			debug!(target: "pip", "WebSocket connection to {} failed: {}", url, e);
+		}
+
+		let mut waited = 0;
+		while waited < RECONNECT_DELAY_MS && shared.is_current(generation) {
+			thread::sleep(Duration::from_millis(RECONNECT_POLL_MS));
+			waited += RECONNECT_POLL_MS;
+		}
+	}
+}
+
+// connect to the server over TLS and serve the connection until it's closed.
+fn connect_tls(shared: &Shared, url: &str, generation: usize) -> Result<(), String> {
+	let mut client = ws_tls::Client::connect(url, Duration::from_millis(TLS_POLL_MS))?;
+	let (tx, rx) = mpsc::channel();
+	let peer = shared.connect(Out::Tls(Mutex::new(tx)), url.to_owned());
+	let served = serve_tls(shared, &mut client, &rx, peer, generation);
+	shared.disconnect(peer);
+	served.map_err(|e| e.to_string())
+}
+
+fn serve_tls(shared: &Shared, client: &mut ws_tls::Client, rx: &mpsc::Receiver<Outgoing>, peer: PeerId, generation: usize) -> io::Result<()> {
+	loop {
+		if !shared.is_current(generation) {
+			client.close(1001);
+			return Ok(());
+		}
+
+		while let Ok(outgoing) = rx.try_recv() {
+			match outgoing {
+				Outgoing::Message(message) => client.send(&message)?,
+				Outgoing::Close(code) => {
+					client.close(code);
+					return Ok(());
+				},
+			}
+		}
+
+		match client.read()? {
+			None => {},
+			Some(ws_tls::Event::Message(data)) => if !shared.on_message(peer, &data) {
+				client.close(1002);
+				return Ok(());
+			},
+			Some(ws_tls::Event::Text) => {
+				debug!(target: "pip", "WebSocket peer {} sent a text message", peer);
+				client.close(1003);
+				return Ok(());
+			},
+			Some(ws_tls::Event::Closed) => return Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{encode, decode};
+
+	#[test]
+	fn packet_framing() {
+		let message = encode(0x02, vec![0xc0, 0x01]);
+		assert_eq!(message, vec![0x02, 0xc0, 0x01]);
+		assert_eq!(decode(&message), Some((0x02, &[0xc0, 0x01][..])));
+		assert_eq!(decode(&encode(0x04, Vec::new())), Some((0x04, &[][..])));
+		assert_eq!(decode(&[]), None);
+	}
+}
@@ -0,0 +1,156 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Privacy of the propagation of locally submitted transactions.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use rand::Rng;
+use network::NodeId;
+use util::H256;
+
+/// Local transactions propagation configuration.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LocalPropagationConfig {
+	/// Local transactions are not propagated sooner than this after they are first seen in the queue.
+	pub delay: Duration,
+	/// Maximal random delay added to `delay`.
+	pub jitter: Duration,
+	/// Local transactions are sent only to these peers, or to any peer if empty.
+	pub relay_peers: HashSet<NodeId>,
+}
+
+impl LocalPropagationConfig {
+	/// Returns true if local transactions are propagated differently than other transactions.
+	pub fn is_enabled(&self) -> bool {
+		*self != Self::default()
+	}
+}
+
+/// Keeps track of the times local transactions may be propagated at.
+pub struct LocalPropagation {
+	config: LocalPropagationConfig,
+	/// Local transactions with the time they may be propagated.
+	release: HashMap<H256, Instant>,
+}
+
+impl LocalPropagation {
+	/// Create new instance with given configuration.
+	pub fn new(config: LocalPropagationConfig) -> Self {
+		LocalPropagation {
+			config: config,
+			release: HashMap::new(),
+		}
+	}
+
+	/// Returns true if local transactions need special handling.
+	pub fn is_enabled(&self) -> bool {
+		self.config.is_enabled()
+	}
+
+	/// Returns true if local transactions are sent only to relay peers.
+	pub fn relays_only(&self) -> bool {
+		!self.config.relay_peers.is_empty()
+	}
+
+	/// Returns true if local transactions may be sent to the peer with given node id.
+	pub fn is_relay(&self, id: Option<&NodeId>) -> bool {
+		!self.relays_only() || id.map_or(false, |id| self.config.relay_peers.contains(id))
+	}
+
+	/// Update the set of local transactions in the queue.
+	/// Newly seen transactions are scheduled for propagation after a random delay.
+	pub fn update<R: Rng>(&mut self, local: &[H256], now: Instant, rng: &mut R) {
+		let jitter_ms = self.config.jitter.as_secs() * 1000 + (self.config.jitter.subsec_nanos() / 1_000_000) as u64;
+		let delay = self.config.delay;
+		let local = local.iter().cloned().collect::<HashSet<_>>();
+
+		self.release.retain(|hash, _| local.contains(hash));
+		for hash in local {
+			self.release.entry(hash).or_insert_with(|| {
+				let jitter = match jitter_ms {
+					0 => 0,
+					max => rng.gen_range(0, max + 1),
+				};
+				now + delay + Duration::from_millis(jitter)
+			});
+		}
+	}
+
+	/// Returns true if the transaction is local.
+	pub fn is_local(&self, hash: &H256) -> bool {
+		self.release.contains_key(hash)
+	}
+
+	/// Returns true if the local transaction may be propagated now.
+	pub fn is_released(&self, hash: &H256, now: Instant) -> bool {
+		self.release.get(hash).map_or(true, |release| *release <= now)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::time::{Duration, Instant};
+	use rand::{SeedableRng, XorShiftRng};
+	use network::NodeId;
+	use util::H256;
+	use super::{LocalPropagation, LocalPropagationConfig};
+
+	#[test]
+	fn should_delay_local_transactions() {
+		let mut rng = XorShiftRng::from_seed([0, 1, 2, 3]);
+		let mut propagation = LocalPropagation::new(LocalPropagationConfig {
+			delay: Duration::from_secs(5),
+			jitter: Duration::from_secs(10),
+			..Default::default()
+		});
+		let now = Instant::now();
+		let (local, other) = (H256::from(1), H256::from(2));
+
+		propagation.update(&[local], now, &mut rng);
+		assert!(propagation.is_local(&local));
+		assert!(!propagation.is_local(&other));
+		assert!(!propagation.is_released(&local, now + Duration::from_secs(4)));
+		assert!(propagation.is_released(&local, now + Duration::from_secs(15)));
+		assert!(propagation.is_released(&other, now));
+
+		// the release time is not changed by later updates
+		propagation.update(&[local], now + Duration::from_secs(100), &mut rng);
+		assert!(propagation.is_released(&local, now + Duration::from_secs(15)));
+
+		// transactions which left the queue are forgotten
+		propagation.update(&[], now, &mut rng);
+		assert!(!propagation.is_local(&local));
+	}
+
+	#[test]
+	fn should_relay_only_through_configured_peers() {
+		let relay = NodeId::from(1);
+		let any = LocalPropagation::new(Default::default());
+		let relays = LocalPropagation::new(LocalPropagationConfig {
+			relay_peers: vec![relay].into_iter().collect(),
+			..Default::default()
+		});
+
+		assert!(!any.is_enabled());
+		assert!(any.is_relay(None));
+		assert!(relays.is_enabled());
+		assert!(relays.relays_only());
+		assert!(relays.is_relay(Some(&relay)));
+		assert!(!relays.is_relay(Some(&NodeId::from(2))));
+		assert!(!relays.is_relay(None));
+	}
+}
@@ -0,0 +1,143 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Time-of-day schedule for sync bandwidth and peer limits.
+
+use std::str::FromStr;
+
+/// Limits applied to sync while a schedule entry is active.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncLimits {
+	/// Maximal download bandwidth used for blocks and snapshot chunks (in bytes per second).
+	pub max_bandwidth: Option<usize>,
+	/// Maximal number of connected peers.
+	pub max_peers: Option<u32>,
+}
+
+/// Single schedule entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleEntry {
+	/// First hour (UTC) of the entry.
+	pub from: u8,
+	/// Hour (UTC) the entry ends at, exclusive. May wrap around midnight, e.g. `22-6`.
+	pub to: u8,
+	/// Limits applied within the hours.
+	pub limits: SyncLimits,
+}
+
+impl ScheduleEntry {
+	/// Returns true if the entry is active at given hour.
+	pub fn contains(&self, hour: u8) -> bool {
+		match (self.from, self.to) {
+			(from, to) if from == to => true,
+			(from, to) if from < to => hour >= from && hour < to,
+			(from, to) => hour >= from || hour < to,
+		}
+	}
+}
+
+/// Sync limits changing with the time of day.
+/// The first entry containing the current hour is used; no limits apply outside of all entries.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncSchedule {
+	entries: Vec<ScheduleEntry>,
+}
+
+impl SyncSchedule {
+	/// Create a schedule from entries.
+	pub fn new(entries: Vec<ScheduleEntry>) -> Self {
+		SyncSchedule {
+			entries: entries,
+		}
+	}
+
+	/// Returns true if there are no entries.
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Limits active at given hour (UTC).
+	pub fn limits_at(&self, hour: u8) -> SyncLimits {
+		self.entries.iter()
+			.find(|entry| entry.contains(hour))
+			.map_or_else(SyncLimits::default, |entry| entry.limits)
+	}
+}
+
+impl FromStr for SyncSchedule {
+	type Err = String;
+
+	/// Parses `FROM-TO:LIMITS` entries separated by `;`.
+	/// `LIMITS` is a comma-separated list of `peers=NUM` and `bandwidth=KBPS`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let entries = s.split(';').filter(|e| !e.trim().is_empty()).map(|entry| {
+			let invalid = || format!("Invalid sync schedule entry: {}. Expected FROM-TO:peers=NUM,bandwidth=KBPS.", entry);
+			let mut parts = entry.splitn(2, ':');
+			let (hours, settings) = match (parts.next(), parts.next()) {
+				(Some(hours), Some(settings)) => (hours, settings),
+				_ => return Err(invalid()),
+			};
+
+			let mut hours = hours.splitn(2, '-').map(|h| h.trim().parse::<u8>().ok().and_then(|h| if h < 24 { Some(h) } else { None }));
+			let (from, to) = match (hours.next(), hours.next()) {
+				(Some(Some(from)), Some(Some(to))) => (from, to),
+				_ => return Err(invalid()),
+			};
+
+			let mut limits = SyncLimits::default();
+			for setting in settings.split(',') {
+				let mut kv = setting.splitn(2, '=').map(str::trim);
+				match (kv.next(), kv.next().and_then(|v| v.parse::<u32>().ok())) {
+					(Some("peers"), Some(peers)) => limits.max_peers = Some(peers),
+					(Some("bandwidth"), Some(kbps)) => limits.max_bandwidth = Some(kbps as usize * 1024),
+					_ => return Err(invalid()),
+				}
+			}
+
+			Ok(ScheduleEntry {
+				from: from,
+				to: to,
+				limits: limits,
+			})
+		}).collect::<Result<Vec<_>, _>>()?;
+
+		Ok(SyncSchedule::new(entries))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{SyncSchedule, SyncLimits};
+
+	#[test]
+	fn should_parse_schedule() {
+		let schedule: SyncSchedule = "8-18:peers=10,bandwidth=512; 22-6:peers=100".parse().unwrap();
+
+		assert_eq!(schedule.limits_at(8), SyncLimits { max_peers: Some(10), max_bandwidth: Some(512 * 1024) });
+		assert_eq!(schedule.limits_at(23), SyncLimits { max_peers: Some(100), max_bandwidth: None });
+		assert_eq!(schedule.limits_at(5), SyncLimits { max_peers: Some(100), max_bandwidth: None });
+		assert_eq!(schedule.limits_at(20), SyncLimits::default());
+		assert!("".parse::<SyncSchedule>().unwrap().is_empty());
+	}
+
+	#[test]
+	fn should_reject_invalid_schedule() {
+		assert!("8-18".parse::<SyncSchedule>().is_err());
+		assert!("8-25:peers=10".parse::<SyncSchedule>().is_err());
+		assert!("8-18:speed=10".parse::<SyncSchedule>().is_err());
+		assert!("8-18:peers=ten".parse::<SyncSchedule>().is_err());
+	}
+}
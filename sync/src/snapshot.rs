@@ -16,9 +16,38 @@
 
 
 use util::{H256, Hashable};
-use std::collections::HashSet;
+use std::collections::{HashSet, HashMap};
 use ethcore::snapshot::ManifestData;
 
+/// Snapshot download configuration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapshotDownloadConfig {
+	/// Maximal number of chunks downloaded ahead of restoration.
+	pub max_chunks_ahead: usize,
+	/// Maximal number of chunks requested from a single peer at a time.
+	pub chunks_per_peer: usize,
+	/// Maximal number of peers to download chunks from at the same time.
+	/// Preferred peers are not counted.
+	pub max_peers: Option<usize>,
+	/// Time to wait for a requested chunk (in seconds).
+	pub chunk_timeout: u64,
+	/// Maximal number of failed attempts to download a single chunk
+	/// before the snapshot sync is restarted.
+	pub max_chunk_retries: Option<usize>,
+}
+
+impl Default for SnapshotDownloadConfig {
+	fn default() -> Self {
+		SnapshotDownloadConfig {
+			max_chunks_ahead: 3,
+			chunks_per_peer: 1,
+			max_peers: None,
+			chunk_timeout: 120,
+			max_chunk_retries: None,
+		}
+	}
+}
+
 #[derive(PartialEq, Eq, Debug)]
 pub enum ChunkType {
 	State(H256),
@@ -30,6 +59,7 @@ pub struct Snapshot {
 	pending_block_chunks: Vec<H256>,
 	downloading_chunks: HashSet<H256>,
 	completed_chunks: HashSet<H256>,
+	failed_chunks: HashMap<H256, usize>,
 	snapshot_hash: Option<H256>,
 }
 
@@ -41,6 +71,7 @@ impl Snapshot {
 			pending_block_chunks: Vec::new(),
 			downloading_chunks: HashSet::new(),
 			completed_chunks: HashSet::new(),
+			failed_chunks: HashMap::new(),
 			snapshot_hash: None,
 		}
 	}
@@ -51,6 +82,7 @@ impl Snapshot {
 		self.pending_block_chunks.clear();
 		self.downloading_chunks.clear();
 		self.completed_chunks.clear();
+		self.failed_chunks.clear();
 		self.snapshot_hash = None;
 	}
 
@@ -109,6 +141,14 @@ impl Snapshot {
 		self.downloading_chunks.remove(hash);
 	}
 
+	/// Note failed chunk download. Returns the number of failures so far.
+	pub fn chunk_failed(&mut self, hash: &H256) -> usize {
+		self.downloading_chunks.remove(hash);
+		let failures = self.failed_chunks.entry(hash.clone()).or_insert(0);
+		*failures += 1;
+		*failures
+	}
+
 	pub fn snapshot_hash(&self) -> Option<H256> {
 		self.snapshot_hash
 	}
@@ -137,6 +177,7 @@ mod test {
 		snapshot.pending_state_chunks.is_empty() &&
 		snapshot.completed_chunks.is_empty() &&
 		snapshot.downloading_chunks.is_empty() &&
+		snapshot.failed_chunks.is_empty() &&
 		snapshot.snapshot_hash.is_none()
 	}
 
@@ -150,6 +191,7 @@ mod test {
 			state_root: H256::new(),
 			block_number: 42,
 			block_hash: H256::new(),
+			base: None,
 		};
 		let mhash = manifest.clone().into_rlp().sha3();
 		(manifest, mhash, state_chunks, block_chunks)
@@ -205,5 +247,20 @@ mod test {
 		assert_eq!(snapshot.done_chunks(), snapshot.total_chunks());
 		assert_eq!(snapshot.snapshot_hash(), Some(manifest.into_rlp().sha3()));
 	}
+
+	#[test]
+	fn counts_failed_chunks() {
+		let mut snapshot = Snapshot::new();
+		let (manifest, mhash, _, _) = test_manifest();
+		snapshot.reset_to(&manifest, &mhash);
+
+		let chunk = snapshot.needed_chunk().unwrap();
+		assert_eq!(snapshot.chunk_failed(&chunk), 1);
+		assert_eq!(snapshot.needed_chunk(), Some(chunk));
+		assert_eq!(snapshot.chunk_failed(&chunk), 2);
+
+		snapshot.clear();
+		assert!(is_empty(&snapshot));
+	}
 }
 
@@ -0,0 +1,163 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Limits for serving snapshot data to other peers.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use network::PeerId;
+
+/// Peer is considered to be served if it requested a chunk recently.
+const PEER_SERVING_TIMEOUT_SEC: u64 = 30;
+
+/// Snapshot serving configuration.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SnapshotServingConfig {
+	/// Maximal upload bandwidth used for snapshot chunks (in bytes per second).
+	pub max_bandwidth: Option<usize>,
+	/// Maximal number of peers served at the same time.
+	pub max_peers: Option<usize>,
+	/// Serve snapshots only between given hours (UTC), `to` is exclusive.
+	/// Window may wrap around midnight, e.g. `(22, 6)`.
+	pub hours: Option<(u8, u8)>,
+}
+
+impl SnapshotServingConfig {
+	/// Returns true if snapshots can be served at given hour.
+	pub fn is_within_hours(&self, hour: u8) -> bool {
+		match self.hours {
+			None => true,
+			Some((from, to)) if from == to => true,
+			Some((from, to)) if from < to => hour >= from && hour < to,
+			Some((from, to)) => hour >= from || hour < to,
+		}
+	}
+}
+
+/// Keeps track of peers and bandwidth used for serving snapshot chunks.
+pub struct SnapshotServing {
+	config: SnapshotServingConfig,
+	/// Peers being served with time of the last request.
+	peers: HashMap<PeerId, Instant>,
+	/// Next chunk can't be sent before this time.
+	next_allowed: Option<Instant>,
+}
+
+impl SnapshotServing {
+	/// Create new instance with given limits.
+	pub fn new(config: SnapshotServingConfig) -> Self {
+		SnapshotServing {
+			config: config,
+			peers: HashMap::new(),
+			next_allowed: None,
+		}
+	}
+
+	/// Returns true if snapshot manifest can be served at given hour.
+	pub fn can_serve_manifest(&self, hour: u8) -> bool {
+		self.config.is_within_hours(hour)
+	}
+
+	/// Returns true if a chunk can be sent to the peer now.
+	pub fn can_serve_chunk(&mut self, peer: PeerId, now: Instant, hour: u8) -> bool {
+		if !self.config.is_within_hours(hour) {
+			return false;
+		}
+
+		if self.next_allowed.map_or(false, |next| now < next) {
+			return false;
+		}
+
+		let timeout = Duration::from_secs(PEER_SERVING_TIMEOUT_SEC);
+		self.peers.retain(|_, last| now.duration_since(*last) < timeout);
+		match self.config.max_peers {
+			Some(max_peers) => self.peers.contains_key(&peer) || self.peers.len() < max_peers,
+			None => true,
+		}
+	}
+
+	/// Note that a chunk of given size was sent to the peer.
+	pub fn on_chunk_served(&mut self, peer: PeerId, size: usize, now: Instant) {
+		self.peers.insert(peer, now);
+		if let Some(bandwidth) = self.config.max_bandwidth {
+			let bandwidth = ::std::cmp::max(bandwidth, 1) as u64;
+			let size = size as u64;
+			let delay = Duration::new(size / bandwidth, ((size % bandwidth) * 1_000_000_000 / bandwidth) as u32);
+			let start = match self.next_allowed {
+				Some(next) if next > now => next,
+				_ => now,
+			};
+			self.next_allowed = Some(start + delay);
+		}
+	}
+
+	/// Stop tracking disconnected peer.
+	pub fn on_peer_aborting(&mut self, peer: PeerId) {
+		self.peers.remove(&peer);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::time::{Duration, Instant};
+	use super::{SnapshotServing, SnapshotServingConfig};
+
+	#[test]
+	fn should_serve_within_hours() {
+		let always = SnapshotServingConfig::default();
+		let day = SnapshotServingConfig { hours: Some((8, 20)), ..Default::default() };
+		let night = SnapshotServingConfig { hours: Some((22, 6)), ..Default::default() };
+
+		assert!(always.is_within_hours(3));
+		assert!(day.is_within_hours(8));
+		assert!(!day.is_within_hours(20));
+		assert!(!day.is_within_hours(3));
+		assert!(night.is_within_hours(23));
+		assert!(night.is_within_hours(3));
+		assert!(!night.is_within_hours(6));
+		assert!(!night.is_within_hours(12));
+	}
+
+	#[test]
+	fn should_limit_number_of_served_peers() {
+		let mut serving = SnapshotServing::new(SnapshotServingConfig { max_peers: Some(1), ..Default::default() });
+		let now = Instant::now();
+
+		assert!(serving.can_serve_chunk(1, now, 0));
+		serving.on_chunk_served(1, 100, now);
+		assert!(serving.can_serve_chunk(1, now, 0));
+		assert!(!serving.can_serve_chunk(2, now, 0));
+
+		// inactive peers are not counted
+		assert!(serving.can_serve_chunk(2, now + Duration::from_secs(31), 0));
+
+		// disconnected peers are not counted
+		serving.on_peer_aborting(1);
+		assert!(serving.can_serve_chunk(2, now, 0));
+	}
+
+	#[test]
+	fn should_limit_bandwidth() {
+		let mut serving = SnapshotServing::new(SnapshotServingConfig { max_bandwidth: Some(1000), ..Default::default() });
+		let now = Instant::now();
+
+		assert!(serving.can_serve_chunk(1, now, 0));
+		serving.on_chunk_served(1, 2500, now);
+		assert!(!serving.can_serve_chunk(1, now + Duration::from_secs(2), 0));
+		assert!(!serving.can_serve_chunk(2, now + Duration::from_secs(2), 0));
+		assert!(serving.can_serve_chunk(2, now + Duration::from_millis(2500), 0));
+	}
+}
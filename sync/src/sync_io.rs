@@ -58,6 +58,8 @@ pub trait SyncIo {
 	fn is_expired(&self) -> bool;
 	/// Return sync overlay
 	fn chain_overlay(&self) -> &RwLock<HashMap<BlockNumber, Bytes>>;
+	/// Change limits for the number of connected peers.
+	fn set_peer_limits(&mut self, min_peers: u32, max_peers: u32);
 }
 
 /// Wraps `NetworkContext` and the blockchain client
@@ -135,6 +137,10 @@ impl<'s, 'h> SyncIo for NetSyncIo<'s, 'h> {
 	fn peer_info(&self, peer_id: PeerId) -> String {
 		self.network.peer_client_version(peer_id)
 	}
+
+	fn set_peer_limits(&mut self, min_peers: u32, max_peers: u32) {
+		self.network.set_peer_limits(min_peers, max_peers)
+	}
 }
 
 
@@ -49,6 +49,9 @@ pub struct TestIo<'p, C> where C: FlushingBlockChainClient, C: 'p {
 	pub to_disconnect: HashSet<PeerId>,
 	pub packets: Vec<TestPacket>,
 	pub peers_info: HashMap<PeerId, String>,
+	pub peer_limits: Option<(u32, u32)>,
+	pub eth_protocol_version: u8,
+	pub warp_protocol_version: u8,
 	overlay: RwLock<HashMap<BlockNumber, Bytes>>,
 }
 
@@ -63,6 +66,9 @@ impl<'p, C> TestIo<'p, C> where C: FlushingBlockChainClient, C: 'p {
 			overlay: RwLock::new(HashMap::new()),
 			packets: Vec::new(),
 			peers_info: HashMap::new(),
+			peer_limits: None,
+			eth_protocol_version: 63,
+			warp_protocol_version: 2,
 		}
 	}
 }
@@ -127,16 +133,20 @@ impl<'p, C> SyncIo for TestIo<'p, C> where C: FlushingBlockChainClient, C: 'p {
 	}
 
 	fn eth_protocol_version(&self, _peer: PeerId) -> u8 {
-		63
+		self.eth_protocol_version
 	}
 
 	fn protocol_version(&self, protocol: &ProtocolId, peer_id: PeerId) -> u8 {
-		if protocol == &WARP_SYNC_PROTOCOL_ID { 2 } else { self.eth_protocol_version(peer_id) }
+		if protocol == &WARP_SYNC_PROTOCOL_ID { self.warp_protocol_version } else { self.eth_protocol_version(peer_id) }
 	}
 
 	fn chain_overlay(&self) -> &RwLock<HashMap<BlockNumber, Bytes>> {
 		&self.overlay
 	}
+
+	fn set_peer_limits(&mut self, min_peers: u32, max_peers: u32) {
+		self.peer_limits = Some((min_peers, max_peers));
+	}
 }
 
 /// Abstract messages between peers.
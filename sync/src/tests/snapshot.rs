@@ -53,6 +53,7 @@ impl TestSnapshotService {
 			state_root: H256::new(),
 			block_number: block_number,
 			block_hash: block_hash,
+			base: None,
 		};
 		let mut chunks: HashMap<H256, Bytes> = state_chunks.into_iter().map(|data| (data.sha3(), data)).collect();
 		chunks.extend(block_chunks.into_iter().map(|data| (data.sha3(), data)));
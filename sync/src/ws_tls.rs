@@ -0,0 +1,402 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! WebSocket client over TLS, used for `wss://` light servers.
+//!
+//! The WebSocket library only runs over plain TCP, so secure connections are made
+//! with the platform TLS implementation, which verifies the server certificate and
+//! host name, and the framing (RFC 6455) is done here. Only what the light protocol
+//! needs is supported: binary messages, pings and closing the connection.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use native_tls::{TlsConnector, TlsStream, HandshakeError};
+use rand::{self, Rng};
+use rustc_serialize::base64::{ToBase64, STANDARD};
+use sha1::Sha1;
+
+const GUID: &'static str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const DEFAULT_PORT: u16 = 443;
+/// Longest accepted handshake response.
+const MAX_HANDSHAKE_SIZE: usize = 8192;
+/// Largest accepted message.
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+/// Timeout of connecting, the handshakes and writes.
+const IO_TIMEOUT_SECS: u64 = 10;
+
+mod opcode {
+	pub const CONTINUATION: u8 = 0x0;
+	pub const TEXT: u8 = 0x1;
+	pub const BINARY: u8 = 0x2;
+	pub const CLOSE: u8 = 0x8;
+	pub const PING: u8 = 0x9;
+	pub const PONG: u8 = 0xa;
+}
+
+/// Server address of a `wss://` URL.
+#[derive(Debug, PartialEq)]
+pub struct Target {
+	/// Host name, used for certificate verification.
+	pub host: String,
+	/// TCP port.
+	pub port: u16,
+	/// Requested resource.
+	pub resource: String,
+}
+
+/// Parse a `wss://host[:port][/resource]` URL.
+pub fn parse_url(url: &str) -> Result<Target, String> {
+	if !url.starts_with("wss://") {
+		return Err(format!("Not a wss:// URL: {}", url));
+	}
+
+	let rest = &url["wss://".len()..];
+	let (authority, resource) = match rest.find('/') {
+		Some(pos) => (&rest[..pos], &rest[pos..]),
+		None => (rest, "/"),
+	};
+	let (host, port) = match authority.rfind(':') {
+		Some(pos) if !authority[pos..].contains(']') => {
+			let port = authority[pos + 1..].parse().map_err(|_| format!("Invalid port in {}", url))?;
+			(&authority[..pos], port)
+		},
+		_ => (authority, DEFAULT_PORT),
+	};
+	let host = host.trim_left_matches('[').trim_right_matches(']');
+	if host.is_empty() {
+		return Err(format!("Missing host in {}", url));
+	}
+
+	Ok(Target {
+		host: host.into(),
+		port: port,
+		resource: resource.into(),
+	})
+}
+
+/// Value of the `Sec-WebSocket-Accept` header the server has to answer with.
+fn accept_key(key: &str) -> String {
+	let mut sha1 = Sha1::new();
+	sha1.update(key.as_bytes());
+	sha1.update(GUID.as_bytes());
+	sha1.digest().bytes().to_base64(STANDARD)
+}
+
+// validate the server's handshake response.
+fn check_handshake(response: &str, key: &str) -> Result<(), String> {
+	let mut lines = response.lines();
+	let status = lines.next().unwrap_or("");
+	if status.split_whitespace().nth(1) != Some("101") {
+		return Err(format!("Unexpected handshake response: {}", status));
+	}
+
+	let expected = accept_key(key);
+	let accepted = lines.filter_map(|line| {
+		let mut parts = line.splitn(2, ':');
+		match (parts.next(), parts.next()) {
+			(Some(name), Some(value)) if name.trim().to_lowercase() == "sec-websocket-accept" => Some(value.trim()),
+			_ => None,
+		}
+	}).any(|value| value == expected);
+
+	match accepted {
+		true => Ok(()),
+		false => Err("Invalid Sec-WebSocket-Accept header in handshake response".into()),
+	}
+}
+
+// encode a frame sent by the client, which must be masked.
+fn encode_frame(opcode: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+	let mut frame = Vec::with_capacity(payload.len() + 14);
+	frame.push(0x80 | opcode);
+	match payload.len() {
+		len if len < 126 => frame.push(0x80 | len as u8),
+		len if len <= 0xffff => {
+			frame.push(0x80 | 126);
+			frame.extend_from_slice(&[(len >> 8) as u8, len as u8]);
+		},
+		len => {
+			frame.push(0x80 | 127);
+			frame.extend((0..8).rev().map(|i| ((len as u64) >> (8 * i)) as u8));
+		},
+	}
+	frame.extend_from_slice(&mask);
+	frame.extend(payload.iter().enumerate().map(|(i, byte)| byte ^ mask[i % 4]));
+	frame
+}
+
+fn protocol_error(msg: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// A frame received from the server.
+#[derive(Debug, PartialEq)]
+struct Frame {
+	fin: bool,
+	opcode: u8,
+	payload: Vec<u8>,
+}
+
+// decode a frame sent by the server. returns the frame and its length,
+// or `None` if more data is needed.
+fn decode_frame(data: &[u8]) -> io::Result<Option<(Frame, usize)>> {
+	if data.len() < 2 {
+		return Ok(None);
+	}
+	if data[1] & 0x80 != 0 {
+		return Err(protocol_error("Masked frame from server"));
+	}
+
+	let (len, offset) = match data[1] & 0x7f {
+		126 if data.len() < 4 => return Ok(None),
+		126 => (((data[2] as u64) << 8) | data[3] as u64, 4),
+		127 if data.len() < 10 => return Ok(None),
+		127 => (data[2..10].iter().fold(0u64, |len, byte| (len << 8) | *byte as u64), 10),
+		len => (len as u64, 2),
+	};
+	if len > MAX_MESSAGE_SIZE as u64 {
+		return Err(protocol_error("Frame too large"));
+	}
+
+	let end = offset + len as usize;
+	if data.len() < end {
+		return Ok(None);
+	}
+
+	Ok(Some((Frame {
+		fin: data[0] & 0x80 != 0,
+		opcode: data[0] & 0x0f,
+		payload: data[offset..end].to_vec(),
+	}, end)))
+}
+
+/// What was received from the server.
+#[derive(Debug, PartialEq)]
+pub enum Event {
+	/// A binary message.
+	Message(Vec<u8>),
+	/// A text message.
+	Text,
+	/// The connection was closed.
+	Closed,
+}
+
+// connects to the first reachable address of the target, with read and write timeouts set.
+fn connect_tcp(target: &Target) -> Result<TcpStream, String> {
+	let timeout = Duration::from_secs(IO_TIMEOUT_SECS);
+	let addresses = (target.host.as_str(), target.port).to_socket_addrs()
+		.map_err(|e| format!("Cannot resolve {}: {}", target.host, e))?;
+
+	let mut error = None;
+	for address in addresses {
+		let stream = TcpStream::connect_timeout(&address, timeout)
+			.and_then(|s| s.set_read_timeout(Some(timeout)).map(|_| s))
+			.and_then(|s| s.set_write_timeout(Some(timeout)).map(|_| s));
+		match stream {
+			Ok(stream) => return Ok(stream),
+			Err(e) => error = Some(e),
+		}
+	}
+	Err(match error {
+		Some(e) => format!("Cannot connect to {}: {}", target.host, e),
+		None => format!("No address found for {}", target.host),
+	})
+}
+
+/// Blocking WebSocket connection to a server over TLS.
+pub struct Client {
+	stream: TlsStream<TcpStream>,
+	buffer: Vec<u8>,
+	// opcode and payload of a fragmented message being received.
+	fragments: Option<(u8, Vec<u8>)>,
+}
+
+impl Client {
+	/// Connect to the server at given `wss://` URL. Connecting, the handshakes and writes time out
+	/// after `IO_TIMEOUT_SECS`. Reads give up after `poll`, so that messages can be sent in between.
+	pub fn connect(url: &str, poll: Duration) -> Result<Self, String> {
+		let target = parse_url(url)?;
+		let tcp = connect_tcp(&target)?;
+		let connector = TlsConnector::builder().and_then(|builder| builder.build()).map_err(|e| e.to_string())?;
+		let mut stream = match connector.connect(&target.host, tcp) {
+			Ok(stream) => stream,
+			Err(HandshakeError::Failure(e)) => return Err(format!("TLS handshake failed: {}", e)),
+			// the socket is blocking, so it's interrupted only by the timeout.
+			Err(HandshakeError::Interrupted(_)) => return Err("TLS handshake timed out".into()),
+		};
+
+		let key = rand::thread_rng().gen::<[u8; 16]>().to_base64(STANDARD);
+		let host = match target.port {
+			DEFAULT_PORT => target.host.clone(),
+			port => format!("{}:{}", target.host, port),
+		};
+		write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+			target.resource, host, key).map_err(|e| e.to_string())?;
+
+		let mut buffer = Vec::new();
+		let mut chunk = [0u8; 1024];
+		while !buffer.windows(4).any(|w| w == b"\r\n\r\n") {
+			if buffer.len() > MAX_HANDSHAKE_SIZE {
+				return Err("Handshake response too large".into());
+			}
+			match stream.read(&mut chunk).map_err(|e| e.to_string())? {
+				0 => return Err("Connection closed during handshake".into()),
+				read => buffer.extend_from_slice(&chunk[..read]),
+			}
+		}
+
+		let end = buffer.windows(4).position(|w| w == b"\r\n\r\n").expect("loop ends once the headers are complete; qed") + 4;
+		check_handshake(&String::from_utf8_lossy(&buffer[..end]), &key)?;
+		buffer.drain(..end);
+		stream.get_ref().set_read_timeout(Some(poll)).map_err(|e| e.to_string())?;
+
+		Ok(Client {
+			stream: stream,
+			buffer: buffer,
+			fragments: None,
+		})
+	}
+
+	fn write_frame(&mut self, opcode: u8, payload: &[u8]) -> io::Result<()> {
+		let frame = encode_frame(opcode, payload, rand::thread_rng().gen());
+		self.stream.write_all(&frame)?;
+		self.stream.flush()
+	}
+
+	/// Send a binary message.
+	pub fn send(&mut self, message: &[u8]) -> io::Result<()> {
+		self.write_frame(opcode::BINARY, message)
+	}
+
+	/// Close the connection with given status code.
+	pub fn close(&mut self, code: u16) {
+		let _ = self.write_frame(opcode::CLOSE, &[(code >> 8) as u8, code as u8]);
+		let _ = self.stream.shutdown();
+	}
+
+	/// Read the next message. `None` if nothing was received in time.
+	pub fn read(&mut self) -> io::Result<Option<Event>> {
+		loop {
+			while let Some((frame, len)) = decode_frame(&self.buffer)? {
+				self.buffer.drain(..len);
+				if let Some(event) = self.on_frame(frame)? {
+					return Ok(Some(event));
+				}
+			}
+
+			let mut chunk = [0u8; 4096];
+			match self.stream.read(&mut chunk) {
+				Ok(0) => return Ok(Some(Event::Closed)),
+				Ok(read) => self.buffer.extend_from_slice(&chunk[..read]),
+				Err(ref e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => return Ok(None),
+				Err(e) => return Err(e),
+			}
+		}
+	}
+
+	fn on_frame(&mut self, frame: Frame) -> io::Result<Option<Event>> {
+		let (kind, payload) = match (frame.opcode, self.fragments.take()) {
+			(opcode::PING, fragments) => {
+				self.fragments = fragments;
+				self.write_frame(opcode::PONG, &frame.payload)?;
+				return Ok(None);
+			},
+			(opcode::PONG, fragments) => {
+				self.fragments = fragments;
+				return Ok(None);
+			},
+			(opcode::CLOSE, _) => {
+				let _ = self.write_frame(opcode::CLOSE, &frame.payload[..::std::cmp::min(2, frame.payload.len())]);
+				return Ok(Some(Event::Closed));
+			},
+			(opcode::CONTINUATION, Some((kind, mut payload))) => {
+				if payload.len() + frame.payload.len() > MAX_MESSAGE_SIZE {
+					return Err(protocol_error("Message too large"));
+				}
+				payload.extend(frame.payload);
+				(kind, payload)
+			},
+			(kind, None) if kind == opcode::TEXT || kind == opcode::BINARY => (kind, frame.payload),
+			_ => return Err(protocol_error("Unexpected frame")),
+		};
+
+		if !frame.fin {
+			self.fragments = Some((kind, payload));
+			return Ok(None);
+		}
+
+		Ok(Some(match kind {
+			opcode::BINARY => Event::Message(payload),
+			_ => Event::Text,
+		}))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_url, accept_key, check_handshake, encode_frame, decode_frame, Frame, Target};
+
+	#[test]
+	fn should_parse_wss_urls() {
+		let target = |host: &str, port, resource: &str| Ok(Target { host: host.into(), port: port, resource: resource.into() });
+		assert_eq!(parse_url("wss://node.example.com"), target("node.example.com", 443, "/"));
+		assert_eq!(parse_url("wss://node.example.com:8547/light"), target("node.example.com", 8547, "/light"));
+		assert_eq!(parse_url("wss://[::1]:8547"), target("::1", 8547, "/"));
+		assert_eq!(parse_url("wss://[::1]"), target("::1", 443, "/"));
+		assert!(parse_url("ws://node.example.com").is_err());
+		assert!(parse_url("wss://:8547").is_err());
+		assert!(parse_url("wss://node.example.com:port").is_err());
+	}
+
+	#[test]
+	fn should_validate_handshake() {
+		// example from RFC 6455.
+		assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+
+		let response = "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nsec-websocket-accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n";
+		assert_eq!(check_handshake(response, "dGhlIHNhbXBsZSBub25jZQ=="), Ok(()));
+		assert!(check_handshake(response, "AQIDBAUGBwgJCgsMDQ4PEC==").is_err());
+		assert!(check_handshake("HTTP/1.1 403 Forbidden\r\n\r\n", "dGhlIHNhbXBsZSBub25jZQ==").is_err());
+	}
+
+	#[test]
+	fn should_encode_masked_frames() {
+		// example from RFC 6455, with the text opcode replaced.
+		let frame = encode_frame(0x2, b"Hello", [0x37, 0xfa, 0x21, 0x3d]);
+		assert_eq!(frame, vec![0x82, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58]);
+
+		let frame = encode_frame(0x2, &[0u8; 256], [0; 4]);
+		assert_eq!(&frame[..4], &[0x82, 0xfe, 0x01, 0x00]);
+		assert_eq!(frame.len(), 4 + 4 + 256);
+	}
+
+	#[test]
+	fn should_decode_server_frames() {
+		let hello = [0x81, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f];
+		assert_eq!(decode_frame(&hello).unwrap(), Some((Frame { fin: true, opcode: 0x1, payload: b"Hello".to_vec() }, 7)));
+		assert_eq!(decode_frame(&hello[..4]).unwrap(), None);
+		assert_eq!(decode_frame(&[0x01, 0x03, 0x48, 0x65, 0x6c]).unwrap().map(|(frame, _)| frame.fin), Some(false));
+
+		let mut long = vec![0x82, 0x7e, 0x01, 0x00];
+		long.extend_from_slice(&[0u8; 256]);
+		assert_eq!(decode_frame(&long).unwrap().map(|(frame, len)| (frame.payload.len(), len)), Some((256, 260)));
+
+		// servers must not mask frames.
+		assert!(decode_frame(&[0x82, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f]).is_err());
+	}
+}
@@ -106,7 +106,8 @@ impl Client {
 		Ok(Arc::new(client))
 	}
 
-	fn with_limit(limit: Option<usize>) -> Result<Self, Error> {
+	/// Creates a client which downloads at most `limit` bytes per request, `None` for no limit.
+	pub fn with_limit(limit: Option<usize>) -> Result<Self, Error> {
 		Ok(Client {
 			client: RwLock::new((time::Instant::now(), Self::new_client()?)),
 			pool: CpuPool::new(4),
@@ -126,6 +127,26 @@ impl Client {
 		*self.client.write() = (time::Instant::now(), client.clone());
 		Ok(client)
 	}
+
+	/// Post a JSON body to the URL and get a future for the response.
+	pub fn post_json(&self, url: &str, body: String) -> CpuFuture<Response, Error> {
+		debug!(target: "fetch", "Posting to: {:?}", url);
+
+		match self.client() {
+			Ok(client) => {
+				self.pool.spawn(FetchTask {
+					url: url.into(),
+					body: Some(body),
+					client: client,
+					limit: self.limit,
+					abort: Default::default(),
+				})
+			},
+			Err(err) => {
+				self.pool.spawn(futures::future::err(err))
+			},
+		}
+	}
 }
 
 impl Fetch for Client {
@@ -151,6 +172,7 @@ impl Fetch for Client {
 			Ok(client) => {
 				self.pool.spawn(FetchTask {
 					url: url.into(),
+					body: None,
 					client: client,
 					limit: self.limit,
 					abort: abort,
@@ -165,6 +187,8 @@ impl Fetch for Client {
 
 struct FetchTask {
 	url: String,
+	// JSON body to post instead of getting the URL.
+	body: Option<String>,
 	client: Arc<reqwest::Client>,
 	limit: Option<usize>,
 	abort: Abort,
@@ -182,9 +206,15 @@ impl Future for FetchTask {
 		}
 
 		trace!(target: "fetch", "Starting fetch task: {:?}", self.url);
-		let result = self.client.get(&self.url)
-						  .header(reqwest::header::UserAgent("Parity Fetch".into()))
-						  .send()?;
+		let request = match self.body.take() {
+			Some(body) => self.client.post(&self.url)
+				.header(reqwest::header::ContentType::json())
+				.body(body),
+			None => self.client.get(&self.url),
+		};
+		let result = request
+			.header(reqwest::header::UserAgent("Parity Fetch".into()))
+			.send()?;
 
 		Ok(futures::Async::Ready(Response {
 			inner: ResponseInner::Response(result),
@@ -0,0 +1,96 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Token bucket rate limiter shared by all connections.
+
+use std::cmp::{min, max};
+use std::time::Instant;
+use parking_lot::Mutex;
+
+#[derive(Debug)]
+struct State {
+	/// Available bytes. Goes negative when concurrent connections overshoot the allowance.
+	tokens: i64,
+	last_refill: Instant,
+}
+
+/// Token bucket refilled at a constant rate and holding at most one second worth of tokens.
+#[derive(Debug)]
+pub struct TokenBucket {
+	/// Bytes per second.
+	rate: u64,
+	state: Mutex<State>,
+}
+
+impl TokenBucket {
+	/// Create a new full bucket for the given rate in bytes per second.
+	pub fn new(rate: u64) -> Self {
+		let rate = max(rate, 1);
+		TokenBucket {
+			rate: rate,
+			state: Mutex::new(State {
+				tokens: rate as i64,
+				last_refill: Instant::now(),
+			}),
+		}
+	}
+
+	/// Number of bytes that may be transferred now.
+	pub fn available(&self) -> usize {
+		self.available_at(Instant::now())
+	}
+
+	/// Account for transferred bytes.
+	pub fn consume(&self, size: usize) {
+		self.state.lock().tokens -= size as i64;
+	}
+
+	fn available_at(&self, now: Instant) -> usize {
+		let mut state = self.state.lock();
+		if now > state.last_refill {
+			let elapsed = now - state.last_refill;
+			let elapsed_ms = elapsed.as_secs() * 1000 + (elapsed.subsec_nanos() / 1_000_000) as u64;
+			let refill = min(elapsed_ms, 1000) * self.rate / 1000;
+			// keep the remainder for the next call unless the bucket is full anyway
+			if refill > 0 {
+				state.tokens = min(state.tokens + refill as i64, self.rate as i64);
+				state.last_refill = now;
+			}
+		}
+		max(state.tokens, 0) as usize
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use super::TokenBucket;
+
+	#[test]
+	fn should_refill_at_rate() {
+		let bucket = TokenBucket::new(1000);
+		let start = bucket.state.lock().last_refill;
+		assert_eq!(bucket.available_at(start), 1000);
+
+		bucket.consume(1500);
+		assert_eq!(bucket.available_at(start), 0);
+		assert_eq!(bucket.available_at(start + Duration::from_millis(400)), 0);
+		assert_eq!(bucket.available_at(start + Duration::from_millis(900)), 400);
+		// never holds more than a second worth of tokens
+		assert_eq!(bucket.available_at(start + Duration::from_secs(10)), 1000);
+		assert!(bucket.available() <= 1000);
+	}
+}
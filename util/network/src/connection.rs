@@ -15,6 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::sync::Arc;
+use std::cmp::min;
 use std::collections::VecDeque;
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
@@ -82,7 +83,12 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
 		}
 		let sock_ref = <Socket as Read>::by_ref(&mut self.socket);
 		loop {
-			let max = self.rec_size - self.rec_buf.len();
+			// The rest is read on the next throttle tick once the download limit allows.
+			let max = min(self.rec_size - self.rec_buf.len(), self.stats.recv_allowance());
+			if max == 0 {
+				trace!(target:"network", "{}: Read throttled", self.token);
+				return Ok(None);
+			}
 			match sock_ref.take(max as u64).try_read(unsafe { self.rec_buf.mut_bytes() }) {
 				Ok(Some(size)) if size != 0  => {
 					unsafe { self.rec_buf.advance(size); }
@@ -125,6 +131,12 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
 
 	/// Writable IO handler. Called when the socket is ready to send.
 	pub fn writable<Message>(&mut self, io: &IoContext<Message>) -> Result<WriteStatus, NetworkError> where Message: Send + Clone + Sync + 'static {
+		let allowance = self.stats.send_allowance();
+		if allowance == 0 {
+			// Registration is not updated so the socket does not fire again; the throttle tick resumes writing.
+			trace!(target:"network", "{}: Write throttled", self.token);
+			return Ok(WriteStatus::Ongoing);
+		}
 		{
 			let buf = match self.send_queue.front_mut() {
 				Some(buf) => buf,
@@ -137,7 +149,8 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
 				return Ok(WriteStatus::Complete)
 			}
 			let buf = buf as &mut Buf;
-			match self.socket.try_write(buf.bytes()) {
+			let len = min(buf.bytes().len(), allowance);
+			match self.socket.try_write(&buf.bytes()[..len]) {
 				Ok(Some(size)) if (pos + size) < send_size => {
 					buf.advance(size);
 					self.stats.inc_send(size);
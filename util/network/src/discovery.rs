@@ -30,7 +30,9 @@ use node_table::*;
 use error::NetworkError;
 use io::{StreamToken, IoContext};
 use ethkey::{Secret, KeyPair, sign, recover};
-use AllowIP;
+use enr::NodeRecord;
+use discv5::{Discv5, Incoming, Message, log_distance};
+use IpFilter;
 
 use PROTOCOL_VERSION;
 
@@ -46,9 +48,15 @@ const PACKET_PING: u8 = 1;
 const PACKET_PONG: u8 = 2;
 const PACKET_FIND_NODE: u8 = 3;
 const PACKET_NEIGHBOURS: u8 = 4;
+const PACKET_ENR_REQUEST: u8 = 5;
+const PACKET_ENR_RESPONSE: u8 = 6;
 
 const PING_TIMEOUT_MS: u64 = 300;
 const MAX_NODES_PING: usize = 32; // Max nodes to add/ping at once
+const MAX_NODE_RECORDS: usize = 4096; // Max node records kept
+const MAX_V5_DISTANCES: usize = 4; // Max distances served for a v5 FindNode
+const V5_RECORDS_PER_PACKET: usize = 3; // Node records sent in a single v5 Nodes message
+const V5_REQUEST_TIMEOUT_SEC: u64 = 10;
 
 #[derive(Clone, Debug)]
 pub struct NodeEntry {
@@ -99,7 +107,17 @@ pub struct Discovery {
 	send_queue: VecDeque<Datagramm>,
 	check_timestamps: bool,
 	adding_nodes: Vec<NodeEntry>,
-	allow_ips: AllowIP,
+	ip_filter: IpFilter,
+	/// Local node record.
+	record: Option<NodeRecord>,
+	/// Node records received from other nodes (EIP-868).
+	records: HashMap<NodeId, NodeRecord>,
+	/// Discovery v5 sessions and handshakes.
+	discv5: Discv5,
+	/// Nodes talking discovery v5.
+	v5_nodes: HashSet<NodeId>,
+	/// Pending v5 FindNode requests: node, requested distances and time of the request.
+	v5_requests: HashMap<Bytes, (NodeId, Vec<u16>, u64)>,
 }
 
 pub struct TableUpdates {
@@ -108,7 +126,7 @@ pub struct TableUpdates {
 }
 
 impl Discovery {
-	pub fn new(key: &KeyPair, listen: SocketAddr, public: NodeEndpoint, token: StreamToken, allow_ips: AllowIP) -> Discovery {
+	pub fn new(key: &KeyPair, listen: SocketAddr, public: NodeEndpoint, token: StreamToken, ip_filter: IpFilter) -> Discovery {
 		let socket = UdpSocket::bind(&listen).expect("Error binding UDP socket");
 		Discovery {
 			id: key.public().clone(),
@@ -124,16 +142,44 @@ impl Discovery {
 			send_queue: VecDeque::new(),
 			check_timestamps: true,
 			adding_nodes: Vec::new(),
-			allow_ips: allow_ips,
+			ip_filter: ip_filter,
+			record: None,
+			records: HashMap::new(),
+			discv5: Discv5::new(key.public(), key.secret()),
+			v5_nodes: HashSet::new(),
+			v5_requests: HashMap::new(),
 		}
 	}
 
+	/// Set the local node record. Its sequence number is advertised in ping and pong packets.
+	pub fn set_record(&mut self, record: NodeRecord) {
+		self.record = Some(record);
+	}
+
+	/// Latest known record of the node.
+	pub fn node_record(&self, id: &NodeId) -> Option<&NodeRecord> {
+		self.records.get(id)
+	}
+
 	/// Add a new node to discovery table. Pings the node.
 	pub fn add_node(&mut self, e: NodeEntry) {
 		if self.is_allowed(&e) {
-			let endpoint = e.endpoint.clone();
+			let entry = e.clone();
 			self.update_node(e);
-			self.ping(&endpoint);
+			self.ping_node(&entry);
+		}
+	}
+
+	/// Add a node known by its record. The node is contacted using discovery v5.
+	pub fn add_node_record(&mut self, record: NodeRecord) {
+		let entry = match (record.id(), record.endpoint()) {
+			(Some(id), Some(endpoint)) => NodeEntry { id: id, endpoint: endpoint },
+			_ => return,
+		};
+		if self.is_allowed(&entry) {
+			self.v5_nodes.insert(entry.id.clone());
+			self.insert_record(entry.id.clone(), record);
+			self.add_node(entry);
 		}
 	}
 
@@ -171,11 +217,11 @@ impl Discovery {
 				//ping least active node
 				let mut last = bucket.nodes.back_mut().expect("Last item is always present when len() > 0");
 				last.timeout = Some(time::precise_time_ns());
-				Some(last.address.endpoint.clone())
+				Some(last.address.clone())
 			} else { None }
 		};
-		if let Some(endpoint) = ping {
-			self.ping(&endpoint);
+		if let Some(entry) = ping {
+			self.ping_node(&entry);
 		}
 	}
 
@@ -213,8 +259,17 @@ impl Discovery {
 			let nearest = Discovery::nearest_node_entries(&self.discovery_id, &self.node_buckets).into_iter();
 			let nearest = nearest.filter(|x| !self.discovery_nodes.contains(&x.id)).take(ALPHA).collect::<Vec<_>>();
 			for r in nearest {
-				let rlp = encode_list(&(&[self.discovery_id.clone()][..]));
-				self.send_packet(PACKET_FIND_NODE, &r.endpoint.udp_address(), &rlp);
+				if self.v5_nodes.contains(&r.id) {
+					// ask for the nodes at the distance of the target from the queried node, and around it.
+					let distance = log_distance(&r.id.sha3(), &self.discovery_id.sha3());
+					let distances = vec![distance, distance + 1, distance.saturating_sub(1)].into_iter()
+						.filter(|d| *d > 0 && *d <= 256)
+						.collect();
+					self.send_v5_find_node(&r, distances);
+				} else {
+					let rlp = encode_list(&(&[self.discovery_id.clone()][..]));
+					self.send_packet(PACKET_FIND_NODE, &r.endpoint.udp_address(), &rlp);
+				}
 				self.discovery_nodes.insert(r.id.clone());
 				tried_count += 1;
 				trace!(target: "discovery", "Sent FindNode to {:?}", &r.endpoint);
@@ -249,19 +304,55 @@ impl Discovery {
 		self.public_endpoint.to_rlp_list(&mut rlp);
 		node.to_rlp_list(&mut rlp);
 		trace!(target: "discovery", "Sent Ping to {:?}", &node);
-		self.send_packet(PACKET_PING, &node.udp_address(), &rlp.drain());
+		let seq = self.record.as_ref().map(NodeRecord::seq);
+		self.send_packet_with_seq(PACKET_PING, &node.udp_address(), &rlp.drain(), seq);
+	}
+
+	/// Ping the node using the discovery protocol version it talks.
+	fn ping_node(&mut self, entry: &NodeEntry) {
+		if self.v5_nodes.contains(&entry.id) {
+			let ping = Message::Ping {
+				request_id: H64::random().to_vec(),
+				enr_seq: self.record.as_ref().map_or(0, NodeRecord::seq),
+			};
+			trace!(target: "discovery", "Sent v5 Ping to {:?}", &entry.endpoint);
+			self.send_v5(&entry.id, &entry.endpoint.udp_address(), &ping);
+		} else {
+			self.ping(&entry.endpoint);
+		}
+	}
+
+	fn send_v5(&mut self, node: &NodeId, address: &SocketAddr, message: &Message) {
+		let packet = self.discv5.encode_message(node, address, message);
+		self.send_to(packet, *address);
+	}
+
+	fn send_v5_find_node(&mut self, entry: &NodeEntry, distances: Vec<u16>) {
+		let request_id = H64::random().to_vec();
+		let find_node = Message::FindNode { request_id: request_id.clone(), distances: distances.clone() };
+		self.v5_requests.insert(request_id, (entry.id.clone(), distances, time::get_time().sec as u64));
+		trace!(target: "discovery", "Sent v5 FindNode to {:?}", &entry.endpoint);
+		self.send_v5(&entry.id, &entry.endpoint.udp_address(), &find_node);
 	}
 
 	fn send_packet(&mut self, packet_id: u8, address: &SocketAddr, payload: &[u8]) {
+		self.send_packet_with_seq(packet_id, address, payload, None)
+	}
+
+	/// Send a packet, appending the local record sequence number after the expiration timestamp (EIP-868).
+	fn send_packet_with_seq(&mut self, packet_id: u8, address: &SocketAddr, payload: &[u8], enr_seq: Option<u64>) {
 		let mut rlp = RlpStream::new();
 		rlp.append_raw(&[packet_id], 1);
 		let source = Rlp::new(payload);
-		rlp.begin_list(source.item_count() + 1);
+		rlp.begin_list(source.item_count() + 1 + enr_seq.map_or(0, |_| 1));
 		for i in 0 .. source.item_count() {
 			rlp.append_raw(source.at(i).as_raw(), 1);
 		}
 		let timestamp = time::get_time().sec as u32 + 60;
 		rlp.append(&timestamp);
+		if let Some(seq) = enr_seq {
+			rlp.append(&seq);
+		}
 
 		let bytes = rlp.drain();
 		let hash = bytes.as_ref().sha3();
@@ -363,14 +454,9 @@ impl Discovery {
 	}
 
 	fn on_packet(&mut self, packet: &[u8], from: SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
-		// validate packet
-		if packet.len() < 32 + 65 + 4 + 1 {
-			return Err(NetworkError::BadProtocol);
-		}
-
-		let hash_signed = (&packet[32..]).sha3();
-		if hash_signed[..] != packet[0..32] {
-			return Err(NetworkError::BadProtocol);
+		// packets without a valid v4 hash are tried as discovery v5 packets.
+		if packet.len() < 32 + 65 + 4 + 1 || (&packet[32..]).sha3()[..] != packet[0..32] {
+			return self.on_v5_packet(packet, from);
 		}
 
 		let signed = &packet[(32 + 65)..];
@@ -384,6 +470,8 @@ impl Discovery {
 			PACKET_PONG => self.on_pong(&rlp, &node_id, &from),
 			PACKET_FIND_NODE => self.on_find_node(&rlp, &node_id, &from),
 			PACKET_NEIGHBOURS => self.on_neighbours(&rlp, &node_id, &from),
+			PACKET_ENR_REQUEST => self.on_enr_request(&rlp, &H256::from_slice(&packet[0..32]), &from),
+			PACKET_ENR_RESPONSE => self.on_enr_response(&rlp, &node_id, &from),
 			_ => {
 				debug!("Unknown UDP packet: {}", packet_id);
 				Ok(None)
@@ -391,6 +479,176 @@ impl Discovery {
 		}
 	}
 
+	fn on_v5_packet(&mut self, packet: &[u8], from: SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
+		let incoming = {
+			let id_hash = &self.id_hash;
+			let buckets = &self.node_buckets;
+			let records = &self.records;
+			self.discv5.on_packet(packet, &from, self.record.as_ref(), |node_id| {
+				Discovery::find_public(id_hash, buckets, node_id)
+					.map(|public| { let seq = records.get(&public).map_or(0, NodeRecord::seq); (public, seq) })
+			})?
+		};
+		match incoming {
+			Some(Incoming::Reply(packet)) => {
+				self.send_to(packet, from);
+				Ok(None)
+			},
+			Some(Incoming::Message(node, message, record)) => {
+				if self.v5_nodes.len() < MAX_NODE_RECORDS {
+					self.v5_nodes.insert(node.clone());
+				}
+				if let Some(record) = record {
+					self.insert_record(node.clone(), record);
+				}
+				self.on_v5_message(node, message, from)
+			},
+			None => Ok(None),
+		}
+	}
+
+	/// Public key of a node in the table with the given v5 node identifier.
+	fn find_public(id_hash: &H256, buckets: &[NodeBucket], node_id: &H256) -> Option<NodeId> {
+		buckets.get(Discovery::distance(id_hash, node_id) as usize)
+			.and_then(|bucket| bucket.nodes.iter().find(|n| n.id_hash == *node_id))
+			.map(|n| n.address.id.clone())
+	}
+
+	fn on_v5_message(&mut self, node: NodeId, message: Message, from: SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
+		match message {
+			Message::Ping { request_id, enr_seq } => {
+				trace!(target: "discovery", "Got v5 Ping from {:?}", &from);
+				let pong = Message::Pong {
+					request_id: request_id,
+					enr_seq: self.record.as_ref().map_or(0, NodeRecord::seq),
+					address: from,
+				};
+				self.send_v5(&node, &from, &pong);
+				self.check_v5_record_seq(enr_seq, &node, &from);
+				// the TCP port is only known from the record.
+				let entry = self.records.get(&node).and_then(NodeRecord::endpoint).map(|endpoint| NodeEntry { id: node.clone(), endpoint: endpoint });
+				let mut added = HashMap::new();
+				if let Some(entry) = entry {
+					if entry.endpoint.is_valid() && self.is_allowed(&entry) {
+						self.update_node(entry.clone());
+						added.insert(node, entry);
+					}
+				}
+				Ok(Some(TableUpdates { added: added, removed: HashSet::new() }))
+			},
+			Message::Pong { enr_seq, .. } => {
+				trace!(target: "discovery", "Got v5 Pong from {:?}", &from);
+				self.clear_ping(&node);
+				self.check_v5_record_seq(enr_seq, &node, &from);
+				Ok(None)
+			},
+			Message::FindNode { request_id, distances } => {
+				trace!(target: "discovery", "Got v5 FindNode from {:?}", &from);
+				self.on_v5_find_node(&node, request_id, distances, &from);
+				Ok(None)
+			},
+			Message::Nodes { request_id, records, .. } => {
+				trace!(target: "discovery", "Got {} v5 Nodes from {:?}", records.len(), &from);
+				self.on_v5_nodes(&node, &request_id, records, &from)
+			},
+			Message::TalkRequest { request_id, .. } => {
+				// no talk protocols are supported, an empty response is required by the protocol.
+				self.send_v5(&node, &from, &Message::TalkResponse { request_id: request_id, response: Vec::new() });
+				Ok(None)
+			},
+			Message::TalkResponse { .. } => Ok(None),
+		}
+	}
+
+	/// Request the node record with a v5 FindNode for distance 0 if the node has a newer one.
+	fn check_v5_record_seq(&mut self, seq: u64, node: &NodeId, from: &SocketAddr) {
+		if self.records.get(node).map_or(true, |r| r.seq() < seq) {
+			let entry = NodeEntry { id: node.clone(), endpoint: NodeEndpoint { address: *from, udp_port: from.port() } };
+			self.send_v5_find_node(&entry, vec![0]);
+		}
+	}
+
+	fn on_v5_find_node(&mut self, node: &NodeId, request_id: Bytes, mut distances: Vec<u16>, from: &SocketAddr) {
+		distances.sort();
+		distances.dedup();
+		let mut records = Vec::new();
+		for distance in distances.into_iter().take(MAX_V5_DISTANCES) {
+			if distance == 0 {
+				records.extend(self.record.clone());
+				continue;
+			}
+			for bucket in &self.node_buckets {
+				let at_distance = bucket.nodes.iter()
+					.filter(|n| log_distance(&self.id_hash, &n.id_hash) == distance)
+					.filter_map(|n| self.records.get(&n.address.id).cloned());
+				records.extend(at_distance);
+			}
+		}
+		records.truncate(BUCKET_SIZE);
+
+		let chunks: Vec<Vec<NodeRecord>> = match records.is_empty() {
+			true => vec![Vec::new()],
+			false => records.chunks(V5_RECORDS_PER_PACKET).map(|c| c.to_vec()).collect(),
+		};
+		let total = chunks.len() as u8;
+		for chunk in chunks {
+			let nodes = Message::Nodes { request_id: request_id.clone(), total: total, records: chunk };
+			self.send_v5(node, from, &nodes);
+		}
+		trace!(target: "discovery", "Sent {} v5 Nodes to {:?}", records.len(), from);
+	}
+
+	fn on_v5_nodes(&mut self, node: &NodeId, request_id: &Bytes, records: Vec<NodeRecord>, from: &SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
+		let distances = match self.v5_requests.get(request_id) {
+			Some(&(ref requested, ref distances, _)) if requested == node => distances.clone(),
+			_ => {
+				debug!(target: "discovery", "Unexpected v5 Nodes from {:?}", from);
+				return Ok(None);
+			},
+		};
+		let node_hash = node.sha3();
+		let mut added = HashMap::new();
+		for record in records {
+			let id = match record.id() {
+				Some(ref id) if *id == self.id => continue,
+				Some(id) => id,
+				None => continue,
+			};
+			// only nodes at the requested distances are accepted.
+			if !distances.contains(&log_distance(&node_hash, &id.sha3())) {
+				debug!(target: "discovery", "v5 Nodes from {:?} contain a node at an unrequested distance", from);
+				continue;
+			}
+			let endpoint = record.endpoint();
+			if self.v5_nodes.len() < MAX_NODE_RECORDS {
+				self.v5_nodes.insert(id.clone());
+			}
+			self.insert_record(id.clone(), record);
+			let entry = match endpoint {
+				Some(endpoint) => NodeEntry { id: id.clone(), endpoint: endpoint },
+				None => continue,
+			};
+			if !entry.endpoint.is_valid() || !self.is_allowed(&entry) {
+				continue;
+			}
+			added.insert(id, entry.clone());
+			self.ping_node(&entry);
+			self.update_node(entry);
+		}
+		Ok(Some(TableUpdates { added: added, removed: HashSet::new() }))
+	}
+
+	/// Keep the record if it is newer than the known one.
+	fn insert_record(&mut self, node: NodeId, record: NodeRecord) {
+		if self.records.get(&node).map_or(false, |r| r.seq() >= record.seq()) {
+			return;
+		}
+		if self.records.len() >= MAX_NODE_RECORDS && !self.records.contains_key(&node) {
+			return;
+		}
+		self.records.insert(node, record);
+	}
+
 	fn check_timestamp(&self, timestamp: u64) -> Result<(), NetworkError> {
 		if self.check_timestamps && timestamp < time::get_time().sec as u64{
 			debug!(target: "discovery", "Expired packet");
@@ -400,7 +658,7 @@ impl Discovery {
 	}
 
 	fn is_allowed(&self, entry: &NodeEntry) -> bool {
-		entry.endpoint.is_allowed(self.allow_ips) && entry.id != self.id
+		entry.endpoint.is_allowed(&self.ip_filter) && entry.id != self.id
 	}
 
 	fn on_ping(&mut self, rlp: &UntrustedRlp, node: &NodeId, from: &SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
@@ -423,7 +681,9 @@ impl Discovery {
 		let mut response = RlpStream::new_list(2);
 		dest.to_rlp_list(&mut response);
 		response.append(&hash);
-		self.send_packet(PACKET_PONG, from, &response.drain());
+		let seq = self.record.as_ref().map(NodeRecord::seq);
+		self.send_packet_with_seq(PACKET_PONG, from, &response.drain(), seq);
+		self.check_record_seq(rlp.val_at(4).ok(), node, from);
 
 		Ok(Some(TableUpdates { added: added_map, removed: HashSet::new() }))
 	}
@@ -440,6 +700,7 @@ impl Discovery {
 			entry.endpoint.address = from.clone();
 		}
 		self.clear_ping(node);
+		self.check_record_seq(rlp.val_at(3).ok(), node, from);
 		let mut added_map = HashMap::new();
 		added_map.insert(node.clone(), entry);
 		Ok(None)
@@ -462,6 +723,46 @@ impl Discovery {
 		Ok(None)
 	}
 
+	/// Request the node record if the advertised sequence number is newer than the known one.
+	fn check_record_seq(&mut self, seq: Option<u64>, node: &NodeId, from: &SocketAddr) {
+		let seq = match seq {
+			Some(seq) => seq,
+			None => return,
+		};
+		if self.records.get(node).map_or(true, |r| r.seq() < seq) {
+			trace!(target: "discovery", "Sent ENRRequest to {:?}", from);
+			self.send_packet(PACKET_ENR_REQUEST, from, &RlpStream::new_list(0).out());
+		}
+	}
+
+	fn on_enr_request(&mut self, rlp: &UntrustedRlp, hash: &H256, from: &SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
+		trace!(target: "discovery", "Got ENRRequest from {:?}", &from);
+		let timestamp: u64 = rlp.val_at(0)?;
+		self.check_timestamp(timestamp)?;
+		let response = match self.record {
+			Some(ref record) => {
+				let mut response = RlpStream::new_list(2);
+				response.append(hash);
+				response.append(record);
+				response.out()
+			},
+			None => return Ok(None),
+		};
+		self.send_packet(PACKET_ENR_RESPONSE, from, &response);
+		Ok(None)
+	}
+
+	fn on_enr_response(&mut self, rlp: &UntrustedRlp, node: &NodeId, from: &SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
+		trace!(target: "discovery", "Got ENRResponse from {:?}", &from);
+		let record = NodeRecord::decode(&rlp.at(1)?)?;
+		if record.id().as_ref() != Some(node) {
+			debug!(target: "discovery", "Node record signed by another node from {:?}", from);
+			return Err(NetworkError::BadProtocol);
+		}
+		self.insert_record(node.clone(), record);
+		Ok(None)
+	}
+
 	fn prepare_neighbours_packets(nearest: &[NodeEntry]) -> Vec<Bytes> {
 		let limit = (MAX_DATAGRAM_SIZE - 109) / 90;
 		let chunks = nearest.chunks(limit);
@@ -526,6 +827,14 @@ impl Discovery {
 
 	pub fn round(&mut self) -> Option<TableUpdates> {
 		let removed = self.check_expired(false);
+		for id in &removed {
+			self.records.remove(id);
+			self.v5_nodes.remove(id);
+			self.discv5.remove_node(id);
+		}
+		self.discv5.expire();
+		let now = time::get_time().sec as u64;
+		self.v5_requests.retain(|_, &mut (_, _, sent)| now.saturating_sub(sent) < V5_REQUEST_TIMEOUT_SEC);
 		self.discover();
 		if !removed.is_empty() {
 			Some(TableUpdates { added: HashMap::new(), removed: removed })
@@ -561,7 +870,8 @@ mod tests {
 	use std::str::FromStr;
 	use rustc_serialize::hex::FromHex;
 	use ethkey::{Random, Generator};
-	use AllowIP;
+	use enr::NodeRecord;
+	use IpFilter;
 
 	#[test]
 	fn find_node() {
@@ -586,8 +896,8 @@ mod tests {
 		let key2 = Random.generate().unwrap();
 		let ep1 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40444").unwrap(), udp_port: 40444 };
 		let ep2 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40445").unwrap(), udp_port: 40445 };
-		let mut discovery1 = Discovery::new(&key1, ep1.address.clone(), ep1.clone(), 0, AllowIP::All);
-		let mut discovery2 = Discovery::new(&key2, ep2.address.clone(), ep2.clone(), 0, AllowIP::All);
+		let mut discovery1 = Discovery::new(&key1, ep1.address.clone(), ep1.clone(), 0, IpFilter::default());
+		let mut discovery2 = Discovery::new(&key2, ep2.address.clone(), ep2.clone(), 0, IpFilter::default());
 
 		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@127.0.0.1:7770").unwrap();
 		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@127.0.0.1:7771").unwrap();
@@ -615,11 +925,66 @@ mod tests {
 		assert_eq!(Discovery::nearest_node_entries(&NodeId::new(), &discovery2.node_buckets).len(), 3)
 	}
 
+	#[test]
+	fn exchanges_node_records() {
+		let key1 = Random.generate().unwrap();
+		let key2 = Random.generate().unwrap();
+		let ep1 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40448").unwrap(), udp_port: 40448 };
+		let ep2 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40449").unwrap(), udp_port: 40449 };
+		let mut discovery1 = Discovery::new(&key1, ep1.address.clone(), ep1.clone(), 0, IpFilter::default());
+		let mut discovery2 = Discovery::new(&key2, ep2.address.clone(), ep2.clone(), 0, IpFilter::default());
+		let record = NodeRecord::new(key2.secret(), key2.public(), 3, &ep2, &[("eth".into(), 63)], None).unwrap();
+		discovery2.set_record(record.clone());
+
+		discovery1.add_node(NodeEntry { id: key2.public().clone(), endpoint: ep2.clone() });
+		for _ in 0 .. 3 {
+			while let Some(datagramm) = discovery1.send_queue.pop_front() {
+				discovery2.on_packet(&datagramm.payload, ep1.address.clone()).ok();
+			}
+			while let Some(datagramm) = discovery2.send_queue.pop_front() {
+				discovery1.on_packet(&datagramm.payload, ep2.address.clone()).ok();
+			}
+		}
+		assert_eq!(discovery1.node_record(key2.public()), Some(&record));
+		assert_eq!(discovery2.node_record(key1.public()), None);
+	}
+
+	#[test]
+	fn discovers_over_v5() {
+		let key1 = Random.generate().unwrap();
+		let key2 = Random.generate().unwrap();
+		let ep1 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40452").unwrap(), udp_port: 40452 };
+		let ep2 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40453").unwrap(), udp_port: 40453 };
+		let mut discovery1 = Discovery::new(&key1, ep1.address.clone(), ep1.clone(), 0, IpFilter::default());
+		let mut discovery2 = Discovery::new(&key2, ep2.address.clone(), ep2.clone(), 0, IpFilter::default());
+		let record1 = NodeRecord::new(key1.secret(), key1.public(), 1, &ep1, &[], None).unwrap();
+		let old_record2 = NodeRecord::new(key2.secret(), key2.public(), 1, &ep2, &[], None).unwrap();
+		let record2 = NodeRecord::new(key2.secret(), key2.public(), 2, &ep2, &[], None).unwrap();
+		discovery1.set_record(record1.clone());
+		discovery2.set_record(record2.clone());
+
+		// node 1 only knows an outdated record of node 2.
+		discovery1.add_node_record(old_record2);
+		for _ in 0 .. 5 {
+			while let Some(datagramm) = discovery1.send_queue.pop_front() {
+				discovery2.on_packet(&datagramm.payload, ep1.address.clone()).unwrap();
+			}
+			while let Some(datagramm) = discovery2.send_queue.pop_front() {
+				discovery1.on_packet(&datagramm.payload, ep2.address.clone()).unwrap();
+			}
+		}
+		// records are exchanged in the handshake and with FindNode at distance 0.
+		assert_eq!(discovery2.node_record(key1.public()), Some(&record1));
+		assert_eq!(discovery1.node_record(key2.public()), Some(&record2));
+		assert_eq!(Discovery::nearest_node_entries(&NodeId::new(), &discovery1.node_buckets).len(), 1);
+		assert_eq!(Discovery::nearest_node_entries(&NodeId::new(), &discovery2.node_buckets).len(), 1);
+	}
+
 	#[test]
 	fn removes_expired() {
 		let key = Random.generate().unwrap();
 		let ep = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40446").unwrap(), udp_port: 40447 };
-		let mut discovery = Discovery::new(&key, ep.address.clone(), ep.clone(), 0, AllowIP::All);
+		let mut discovery = Discovery::new(&key, ep.address.clone(), ep.clone(), 0, IpFilter::default());
 		for _ in 0..1200 {
 			discovery.add_node(NodeEntry { id: NodeId::random(), endpoint: ep.clone() });
 		}
@@ -648,7 +1013,7 @@ mod tests {
 	fn packets() {
 		let key = Random.generate().unwrap();
 		let ep = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40447").unwrap(), udp_port: 40447 };
-		let mut discovery = Discovery::new(&key, ep.address.clone(), ep.clone(), 0, AllowIP::All);
+		let mut discovery = Discovery::new(&key, ep.address.clone(), ep.clone(), 0, IpFilter::default());
 		discovery.check_timestamps = false;
 		let from = SocketAddr::from_str("99.99.99.99:40445").unwrap();
 
@@ -0,0 +1,710 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Discovery v5.1 wire protocol: packet masking, encrypted sessions established with the
+//! WHOAREYOU handshake and the PING/PONG/FINDNODE/NODES/TALKREQ/TALKRESP messages.
+
+use std::collections::HashMap;
+use std::net::{SocketAddr, IpAddr, Ipv4Addr, Ipv6Addr};
+use rand::{self, Rng};
+use rcrypto::aes::{ctr, KeySize};
+use rcrypto::aes_gcm::AesGcm;
+use rcrypto::aead::{AeadEncryptor, AeadDecryptor};
+use rcrypto::digest::Digest;
+use rcrypto::hkdf::{hkdf_extract, hkdf_expand};
+use rcrypto::sha2::Sha256;
+use rcrypto::symmetriccipher::SynchronousStreamCipher;
+use ethkey::{Secret, Signature, Generator, Random, sign, recover};
+use ethkey::math::{public_mul_secret, public_from_compressed};
+use util::hash::*;
+use util::sha3::Hashable;
+use util::bytes::Bytes;
+use rlp::*;
+use node_table::NodeId;
+use enr::{NodeRecord, compress};
+use error::NetworkError;
+
+const PROTOCOL_ID: &'static [u8] = b"discv5";
+const VERSION: [u8; 2] = [0, 1];
+
+const FLAG_MESSAGE: u8 = 0;
+const FLAG_WHOAREYOU: u8 = 1;
+const FLAG_HANDSHAKE: u8 = 2;
+
+const MASKING_IV_SIZE: usize = 16;
+const STATIC_HEADER_SIZE: usize = 23;
+const WHOAREYOU_AUTHDATA_SIZE: usize = 24;
+const SIGNATURE_SIZE: usize = 64;
+const COMPRESSED_KEY_SIZE: usize = 33;
+const TAG_SIZE: usize = 16;
+const MIN_PACKET_SIZE: usize = MASKING_IV_SIZE + STATIC_HEADER_SIZE + WHOAREYOU_AUTHDATA_SIZE;
+
+const MESSAGE_PING: u8 = 1;
+const MESSAGE_PONG: u8 = 2;
+const MESSAGE_FIND_NODE: u8 = 3;
+const MESSAGE_NODES: u8 = 4;
+const MESSAGE_TALK_REQUEST: u8 = 5;
+const MESSAGE_TALK_RESPONSE: u8 = 6;
+
+const KEY_AGREEMENT_INFO: &'static [u8] = b"discovery v5 key agreement";
+const ID_PROOF_PREFIX: &'static [u8] = b"discovery v5 identity proof";
+
+const HANDSHAKE_TIMEOUT_SEC: u64 = 5;
+const MAX_SESSIONS: usize = 1024;
+const MAX_PENDING: usize = 1024;
+
+/// Node identifier in the v4 identity scheme: keccak256 of the public key.
+pub type NodeIdHash = H256;
+
+/// Discovery v5 message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+	Ping { request_id: Bytes, enr_seq: u64 },
+	Pong { request_id: Bytes, enr_seq: u64, address: SocketAddr },
+	FindNode { request_id: Bytes, distances: Vec<u16> },
+	Nodes { request_id: Bytes, total: u8, records: Vec<NodeRecord> },
+	TalkRequest { request_id: Bytes, protocol: Bytes, request: Bytes },
+	TalkResponse { request_id: Bytes, response: Bytes },
+}
+
+impl Message {
+	/// Message plaintext: type byte followed by the RLP list of fields.
+	pub fn encode(&self) -> Bytes {
+		let mut s = RlpStream::new();
+		let message_type = match *self {
+			Message::Ping { ref request_id, enr_seq } => {
+				s.begin_list(2).append(&&request_id[..]).append(&enr_seq);
+				MESSAGE_PING
+			},
+			Message::Pong { ref request_id, enr_seq, ref address } => {
+				s.begin_list(4).append(&&request_id[..]).append(&enr_seq);
+				match address.ip() {
+					IpAddr::V4(ip) => s.append(&&ip.octets()[..]),
+					IpAddr::V6(ip) => s.append(&&ip.octets()[..]),
+				};
+				s.append(&address.port());
+				MESSAGE_PONG
+			},
+			Message::FindNode { ref request_id, ref distances } => {
+				s.begin_list(2).append(&&request_id[..]);
+				s.begin_list(distances.len());
+				for distance in distances {
+					s.append(distance);
+				}
+				MESSAGE_FIND_NODE
+			},
+			Message::Nodes { ref request_id, total, ref records } => {
+				s.begin_list(3).append(&&request_id[..]).append(&total);
+				s.begin_list(records.len());
+				for record in records {
+					s.append(record);
+				}
+				MESSAGE_NODES
+			},
+			Message::TalkRequest { ref request_id, ref protocol, ref request } => {
+				s.begin_list(3).append(&&request_id[..]).append(&&protocol[..]).append(&&request[..]);
+				MESSAGE_TALK_REQUEST
+			},
+			Message::TalkResponse { ref request_id, ref response } => {
+				s.begin_list(2).append(&&request_id[..]).append(&&response[..]);
+				MESSAGE_TALK_RESPONSE
+			},
+		};
+		let mut message = vec![message_type];
+		message.extend(s.out());
+		message
+	}
+
+	/// Decode message plaintext.
+	pub fn decode(data: &[u8]) -> Result<Message, NetworkError> {
+		if data.is_empty() {
+			return Err(NetworkError::BadProtocol);
+		}
+		let rlp = UntrustedRlp::new(&data[1..]);
+		let request_id: Bytes = rlp.val_at(0)?;
+		if request_id.len() > 8 {
+			return Err(NetworkError::BadProtocol);
+		}
+		let message = match data[0] {
+			MESSAGE_PING => Message::Ping { request_id: request_id, enr_seq: rlp.val_at(1)? },
+			MESSAGE_PONG => {
+				let ip: Bytes = rlp.val_at(2)?;
+				let ip = match ip.len() {
+					4 => IpAddr::V4(Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3])),
+					16 => {
+						let mut octets = [0u8; 16];
+						octets.copy_from_slice(&ip);
+						IpAddr::V6(Ipv6Addr::from(octets))
+					},
+					_ => return Err(NetworkError::BadProtocol),
+				};
+				Message::Pong { request_id: request_id, enr_seq: rlp.val_at(1)?, address: SocketAddr::new(ip, rlp.val_at(3)?) }
+			},
+			MESSAGE_FIND_NODE => Message::FindNode { request_id: request_id, distances: rlp.list_at(1)? },
+			MESSAGE_NODES => Message::Nodes {
+				request_id: request_id,
+				total: rlp.val_at(1)?,
+				// invalid records are skipped, the rest of the response is still useful.
+				records: rlp.at(2)?.iter().filter_map(|r| NodeRecord::decode(&r).ok()).collect(),
+			},
+			MESSAGE_TALK_REQUEST => Message::TalkRequest { request_id: request_id, protocol: rlp.val_at(1)?, request: rlp.val_at(2)? },
+			MESSAGE_TALK_RESPONSE => Message::TalkResponse { request_id: request_id, response: rlp.val_at(1)? },
+			_ => return Err(NetworkError::BadProtocol),
+		};
+		Ok(message)
+	}
+}
+
+/// Result of processing an incoming packet.
+pub enum Incoming {
+	/// Authenticated message, with the node record sent during the handshake if any.
+	Message(NodeId, Message, Option<NodeRecord>),
+	/// Packet to be sent back to the sender.
+	Reply(Bytes),
+}
+
+struct Session {
+	public: NodeId,
+	write_key: [u8; 16],
+	read_key: [u8; 16],
+}
+
+/// WHOAREYOU sent to a node, waiting for its handshake.
+struct Challenge {
+	data: Bytes,
+	public: Option<NodeId>,
+	sent_at: u64,
+}
+
+/// Message sent to a node, kept to be resent in a handshake when the node replies with WHOAREYOU.
+struct Pending {
+	public: NodeId,
+	address: SocketAddr,
+	message: Bytes,
+	sent_at: u64,
+}
+
+/// Decoded packet with unmasked header.
+struct Packet<'a> {
+	masking_iv: &'a [u8],
+	header: Bytes,
+	message: &'a [u8],
+}
+
+impl<'a> Packet<'a> {
+	fn decode(local_id: &NodeIdHash, data: &'a [u8]) -> Result<Packet<'a>, NetworkError> {
+		if data.len() < MIN_PACKET_SIZE {
+			return Err(NetworkError::BadProtocol);
+		}
+		let masking_iv = &data[0..MASKING_IV_SIZE];
+		let mut cipher = ctr(KeySize::KeySize128, &local_id[0..16], masking_iv);
+		let mut header = vec![0u8; STATIC_HEADER_SIZE];
+		cipher.process(&data[MASKING_IV_SIZE..MASKING_IV_SIZE + STATIC_HEADER_SIZE], &mut header);
+		if &header[0..6] != PROTOCOL_ID || &header[6..8] != &VERSION[..] {
+			return Err(NetworkError::BadProtocol);
+		}
+		let authdata_size = (header[21] as usize) << 8 | header[22] as usize;
+		let start = MASKING_IV_SIZE + STATIC_HEADER_SIZE;
+		if data.len() < start + authdata_size {
+			return Err(NetworkError::BadProtocol);
+		}
+		let mut authdata = vec![0u8; authdata_size];
+		cipher.process(&data[start..start + authdata_size], &mut authdata);
+		header.extend(authdata);
+		Ok(Packet {
+			masking_iv: masking_iv,
+			header: header,
+			message: &data[start + authdata_size..],
+		})
+	}
+
+	fn flag(&self) -> u8 {
+		self.header[8]
+	}
+
+	fn nonce(&self) -> [u8; 12] {
+		let mut nonce = [0u8; 12];
+		nonce.copy_from_slice(&self.header[9..21]);
+		nonce
+	}
+
+	fn authdata(&self) -> &[u8] {
+		&self.header[STATIC_HEADER_SIZE..]
+	}
+
+	/// Masking IV followed by the unmasked header; authenticated data of the message and challenge data of WHOAREYOU.
+	fn iv_and_header(&self) -> Bytes {
+		let mut data = self.masking_iv.to_vec();
+		data.extend_from_slice(&self.header);
+		data
+	}
+}
+
+/// Discovery v5 protocol state: sessions with other nodes and handshakes in progress.
+pub struct Discv5 {
+	node_id: NodeIdHash,
+	secret: Secret,
+	sessions: HashMap<(NodeIdHash, SocketAddr), Session>,
+	challenges: HashMap<(NodeIdHash, SocketAddr), Challenge>,
+	pending: HashMap<[u8; 12], Pending>,
+}
+
+impl Discv5 {
+	pub fn new(public: &NodeId, secret: &Secret) -> Discv5 {
+		Discv5 {
+			node_id: public.sha3(),
+			secret: secret.clone(),
+			sessions: HashMap::new(),
+			challenges: HashMap::new(),
+			pending: HashMap::new(),
+		}
+	}
+
+	/// Encode a message packet to the node. Without a session the packet can't be decrypted by the node,
+	/// which then starts the handshake the message is resent in.
+	pub fn encode_message(&mut self, public: &NodeId, address: &SocketAddr, message: &Message) -> Bytes {
+		let node_id = public.sha3();
+		let nonce = random_nonce();
+		let masking_iv = random_masking_iv();
+		let plain = message.encode();
+		let header = static_header(FLAG_MESSAGE, &nonce, &self.node_id);
+		let ciphertext = match self.sessions.get(&(node_id, *address)) {
+			Some(session) => seal(&session.write_key, &nonce, &concat(&masking_iv, &header), &plain),
+			None => {
+				let mut random = vec![0u8; 20];
+				rand::thread_rng().fill_bytes(&mut random);
+				random
+			},
+		};
+		if self.pending.len() < MAX_PENDING {
+			self.pending.insert(nonce, Pending {
+				public: public.clone(),
+				address: *address,
+				message: plain,
+				sent_at: now(),
+			});
+		}
+		masked_packet(&node_id, &masking_iv, &header, &ciphertext)
+	}
+
+	/// Process an incoming packet. `lookup` returns the public key and the known record sequence number
+	/// of the node with given identifier.
+	pub fn on_packet<F>(&mut self, data: &[u8], from: &SocketAddr, local_record: Option<&NodeRecord>, lookup: F) -> Result<Option<Incoming>, NetworkError>
+		where F: Fn(&NodeIdHash) -> Option<(NodeId, u64)>
+	{
+		let packet = Packet::decode(&self.node_id, data)?;
+		match packet.flag() {
+			FLAG_MESSAGE => self.on_message(&packet, from, lookup),
+			FLAG_WHOAREYOU => self.on_whoareyou(&packet, from, local_record),
+			FLAG_HANDSHAKE => self.on_handshake(&packet, from),
+			_ => Err(NetworkError::BadProtocol),
+		}
+	}
+
+	fn on_message<F>(&mut self, packet: &Packet, from: &SocketAddr, lookup: F) -> Result<Option<Incoming>, NetworkError>
+		where F: Fn(&NodeIdHash) -> Option<(NodeId, u64)>
+	{
+		if packet.authdata().len() != 32 {
+			return Err(NetworkError::BadProtocol);
+		}
+		let src = H256::from_slice(packet.authdata());
+		let key = (src, *from);
+		let decrypted = self.sessions.get(&key).and_then(|session| {
+			open(&session.read_key, &packet.nonce(), &packet.iv_and_header(), packet.message).map(|plain| (session.public.clone(), plain))
+		});
+		if let Some((public, plain)) = decrypted {
+			return Ok(Some(Incoming::Message(public, Message::decode(&plain)?, None)));
+		}
+
+		// unknown session, challenge the node to do a handshake.
+		let tick = now();
+		if self.challenges.get(&key).map_or(false, |c| tick.saturating_sub(c.sent_at) < HANDSHAKE_TIMEOUT_SEC) {
+			return Ok(None);
+		}
+		let known = lookup(&src);
+		let mut authdata = [0u8; WHOAREYOU_AUTHDATA_SIZE];
+		rand::thread_rng().fill_bytes(&mut authdata[0..16]);
+		let seq = known.as_ref().map_or(0, |&(_, seq)| seq);
+		for i in 0..8 {
+			authdata[16 + i] = (seq >> (56 - 8 * i)) as u8;
+		}
+		let masking_iv = random_masking_iv();
+		let header = static_header(FLAG_WHOAREYOU, &packet.nonce(), &authdata);
+		if self.challenges.len() >= MAX_SESSIONS {
+			return Ok(None);
+		}
+		self.challenges.insert(key, Challenge {
+			data: concat(&masking_iv, &header),
+			public: known.map(|(public, _)| public),
+			sent_at: tick,
+		});
+		trace!(target: "discovery", "Sent WHOAREYOU to {:?}", from);
+		Ok(Some(Incoming::Reply(masked_packet(&src, &masking_iv, &header, &[]))))
+	}
+
+	fn on_whoareyou(&mut self, packet: &Packet, from: &SocketAddr, local_record: Option<&NodeRecord>) -> Result<Option<Incoming>, NetworkError> {
+		if packet.authdata().len() != WHOAREYOU_AUTHDATA_SIZE || !packet.message.is_empty() {
+			return Err(NetworkError::BadProtocol);
+		}
+		let pending = match self.pending.remove(&packet.nonce()) {
+			Some(ref pending) if pending.address != *from => return Err(NetworkError::BadProtocol),
+			Some(pending) => pending,
+			None => {
+				debug!(target: "discovery", "Unsolicited WHOAREYOU from {:?}", from);
+				return Ok(None);
+			},
+		};
+		let remote_seq = packet.authdata()[16..24].iter().fold(0u64, |seq, b| seq << 8 | *b as u64);
+		let challenge = packet.iv_and_header();
+		let remote_id = pending.public.sha3();
+
+		let ephemeral = Random.generate()?;
+		let ephemeral_public = compress(ephemeral.public());
+		let (initiator_key, recipient_key) = derive_keys(ephemeral.secret(), &pending.public, &challenge, &self.node_id, &remote_id)?;
+		let signature = sign(&self.secret, &id_proof_hash(&challenge, &ephemeral_public, &remote_id))?;
+
+		let mut authdata = self.node_id.to_vec();
+		authdata.push(SIGNATURE_SIZE as u8);
+		authdata.push(COMPRESSED_KEY_SIZE as u8);
+		authdata.extend_from_slice(&signature[0..SIGNATURE_SIZE]);
+		authdata.extend_from_slice(&ephemeral_public);
+		if let Some(record) = local_record {
+			if record.seq() > remote_seq {
+				authdata.extend(encode(record).to_vec());
+			}
+		}
+
+		let nonce = random_nonce();
+		let masking_iv = random_masking_iv();
+		let header = static_header(FLAG_HANDSHAKE, &nonce, &authdata);
+		let ciphertext = seal(&initiator_key, &nonce, &concat(&masking_iv, &header), &pending.message);
+		self.insert_session(remote_id, *from, Session {
+			public: pending.public,
+			write_key: initiator_key,
+			read_key: recipient_key,
+		});
+		trace!(target: "discovery", "Sent handshake to {:?}", from);
+		Ok(Some(Incoming::Reply(masked_packet(&remote_id, &masking_iv, &header, &ciphertext))))
+	}
+
+	fn on_handshake(&mut self, packet: &Packet, from: &SocketAddr) -> Result<Option<Incoming>, NetworkError> {
+		let authdata = packet.authdata();
+		if authdata.len() < 34 {
+			return Err(NetworkError::BadProtocol);
+		}
+		let src = H256::from_slice(&authdata[0..32]);
+		let (signature_size, key_size) = (authdata[32] as usize, authdata[33] as usize);
+		if signature_size != SIGNATURE_SIZE || key_size != COMPRESSED_KEY_SIZE || authdata.len() < 34 + signature_size + key_size {
+			return Err(NetworkError::BadProtocol);
+		}
+		let signature = &authdata[34..34 + signature_size];
+		let ephemeral_public = &authdata[34 + signature_size..34 + signature_size + key_size];
+		let record_data = &authdata[34 + signature_size + key_size..];
+
+		let challenge = match self.challenges.remove(&(src, *from)) {
+			Some(challenge) => challenge,
+			None => {
+				debug!(target: "discovery", "Unexpected handshake from {:?}", from);
+				return Ok(None);
+			},
+		};
+		let record = match record_data.is_empty() {
+			true => None,
+			false => Some(NodeRecord::decode(&UntrustedRlp::new(record_data))?),
+		};
+		let public = match record.as_ref().and_then(NodeRecord::id).or(challenge.public) {
+			Some(public) => public,
+			None => return Err(NetworkError::BadProtocol),
+		};
+		if public.sha3() != src || !verify_id_proof(&public, signature, &challenge.data, ephemeral_public, &self.node_id) {
+			return Err(NetworkError::Auth);
+		}
+
+		let (initiator_key, recipient_key) = derive_keys(&self.secret, &public_from_compressed(ephemeral_public)?, &challenge.data, &src, &self.node_id)?;
+		let plain = match open(&initiator_key, &packet.nonce(), &packet.iv_and_header(), packet.message) {
+			Some(plain) => plain,
+			None => return Err(NetworkError::Auth),
+		};
+		self.insert_session(src, *from, Session {
+			public: public.clone(),
+			write_key: recipient_key,
+			read_key: initiator_key,
+		});
+		Ok(Some(Incoming::Message(public, Message::decode(&plain)?, record)))
+	}
+
+	fn insert_session(&mut self, node_id: NodeIdHash, address: SocketAddr, session: Session) {
+		if self.sessions.len() >= MAX_SESSIONS && !self.sessions.contains_key(&(node_id, address)) {
+			// forget an arbitrary session, the node will just be asked to do a new handshake.
+			let evict = self.sessions.keys().next().cloned().expect("sessions are not empty; qed");
+			self.sessions.remove(&evict);
+		}
+		self.sessions.insert((node_id, address), session);
+	}
+
+	/// Forget sessions of the node.
+	pub fn remove_node(&mut self, public: &NodeId) {
+		let node_id = public.sha3();
+		self.sessions.retain(|&(id, _), _| id != node_id);
+	}
+
+	/// Drop handshakes which were not answered in time.
+	pub fn expire(&mut self) {
+		let tick = now();
+		self.challenges.retain(|_, c| tick.saturating_sub(c.sent_at) < HANDSHAKE_TIMEOUT_SEC);
+		self.pending.retain(|_, p| tick.saturating_sub(p.sent_at) < HANDSHAKE_TIMEOUT_SEC);
+	}
+}
+
+/// Logarithmic distance between two node identifiers.
+pub fn log_distance(a: &NodeIdHash, b: &NodeIdHash) -> u16 {
+	let d = *a ^ *b;
+	match d.iter().position(|byte| *byte != 0) {
+		Some(i) => ((32 - i) * 8) as u16 - d[i].leading_zeros() as u16,
+		None => 0,
+	}
+}
+
+fn now() -> u64 {
+	::time::get_time().sec as u64
+}
+
+fn random_nonce() -> [u8; 12] {
+	let mut nonce = [0u8; 12];
+	rand::thread_rng().fill_bytes(&mut nonce);
+	nonce
+}
+
+fn random_masking_iv() -> [u8; MASKING_IV_SIZE] {
+	let mut iv = [0u8; MASKING_IV_SIZE];
+	rand::thread_rng().fill_bytes(&mut iv);
+	iv
+}
+
+fn concat(a: &[u8], b: &[u8]) -> Bytes {
+	let mut data = a.to_vec();
+	data.extend_from_slice(b);
+	data
+}
+
+/// Unmasked header: static header followed by the authdata.
+fn static_header(flag: u8, nonce: &[u8; 12], authdata: &[u8]) -> Bytes {
+	let mut header = Vec::with_capacity(STATIC_HEADER_SIZE + authdata.len());
+	header.extend_from_slice(PROTOCOL_ID);
+	header.extend_from_slice(&VERSION);
+	header.push(flag);
+	header.extend_from_slice(nonce);
+	header.push((authdata.len() >> 8) as u8);
+	header.push(authdata.len() as u8);
+	header.extend_from_slice(authdata);
+	header
+}
+
+/// Packet with the header masked by the destination node identifier.
+fn masked_packet(dest: &NodeIdHash, masking_iv: &[u8], header: &[u8], ciphertext: &[u8]) -> Bytes {
+	let mut masked = vec![0u8; header.len()];
+	ctr(KeySize::KeySize128, &dest[0..16], masking_iv).process(header, &mut masked);
+	let mut packet = masking_iv.to_vec();
+	packet.extend(masked);
+	packet.extend_from_slice(ciphertext);
+	packet
+}
+
+fn seal(key: &[u8; 16], nonce: &[u8; 12], aad: &[u8], plain: &[u8]) -> Bytes {
+	let mut ciphertext = vec![0u8; plain.len() + TAG_SIZE];
+	let mut tag = [0u8; TAG_SIZE];
+	AesGcm::new(KeySize::KeySize128, key, nonce, aad).encrypt(plain, &mut ciphertext[0..plain.len()], &mut tag);
+	ciphertext[plain.len()..].copy_from_slice(&tag);
+	ciphertext
+}
+
+fn open(key: &[u8; 16], nonce: &[u8; 12], aad: &[u8], ciphertext: &[u8]) -> Option<Bytes> {
+	if ciphertext.len() < TAG_SIZE {
+		return None;
+	}
+	let len = ciphertext.len() - TAG_SIZE;
+	let mut plain = vec![0u8; len];
+	match AesGcm::new(KeySize::KeySize128, key, nonce, aad).decrypt(&ciphertext[0..len], &mut plain, &ciphertext[len..]) {
+		true => Some(plain),
+		false => None,
+	}
+}
+
+fn sha256(data: &[u8]) -> H256 {
+	let mut hasher = Sha256::new();
+	hasher.input(data);
+	let mut hash = H256::new();
+	hasher.result(&mut hash);
+	hash
+}
+
+/// Session keys `(initiator_key, recipient_key)` from the ECDH of `secret` and `public`.
+fn derive_keys(secret: &Secret, public: &NodeId, challenge: &[u8], initiator: &NodeIdHash, recipient: &NodeIdHash) -> Result<([u8; 16], [u8; 16]), NetworkError> {
+	let mut shared = public.clone();
+	public_mul_secret(&mut shared, secret)?;
+	let mut prk = [0u8; 32];
+	hkdf_extract(Sha256::new(), challenge, &compress(&shared), &mut prk);
+	let mut info = KEY_AGREEMENT_INFO.to_vec();
+	info.extend_from_slice(initiator);
+	info.extend_from_slice(recipient);
+	let mut keys = [0u8; 32];
+	hkdf_expand(Sha256::new(), &prk, &info, &mut keys);
+	let (mut initiator_key, mut recipient_key) = ([0u8; 16], [0u8; 16]);
+	initiator_key.copy_from_slice(&keys[0..16]);
+	recipient_key.copy_from_slice(&keys[16..32]);
+	Ok((initiator_key, recipient_key))
+}
+
+fn id_proof_hash(challenge: &[u8], ephemeral_public: &[u8], recipient: &NodeIdHash) -> H256 {
+	let mut input = ID_PROOF_PREFIX.to_vec();
+	input.extend_from_slice(challenge);
+	input.extend_from_slice(ephemeral_public);
+	input.extend_from_slice(recipient);
+	sha256(&input)
+}
+
+fn verify_id_proof(public: &NodeId, signature: &[u8], challenge: &[u8], ephemeral_public: &[u8], recipient: &NodeIdHash) -> bool {
+	let hash = id_proof_hash(challenge, ephemeral_public, recipient);
+	let r = H256::from_slice(&signature[0..32]);
+	let s = H256::from_slice(&signature[32..64]);
+	(0..2).filter_map(|v| recover(&Signature::from_rsv(&r, &s, v), &hash).ok()).any(|p| p == *public)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use std::net::SocketAddr;
+	use rustc_serialize::hex::FromHex;
+	use ethkey::{Secret, KeyPair, Random, Generator, sign};
+	use ethkey::math::public_from_compressed;
+	use util::hash::H256;
+	use node_table::NodeEndpoint;
+	use enr::NodeRecord;
+	use super::*;
+
+	fn h256(hex: &str) -> H256 {
+		H256::from_slice(&hex.from_hex().unwrap())
+	}
+
+	#[test]
+	fn decodes_ping_message_packet() {
+		// discv5.1 test vector "ping message packet (flag 0)".
+		let src = h256("aaaa8419e9f49d0083561b48287df592939a8d19947d8c0ef88f2a4856a69fbb");
+		let dest = h256("bbbb9d047f0488c0b5a93c1c3f2d8bafc7c8ff337024a55434a0d0555de64db9");
+		let nonce = [0xffu8; 12];
+		let key = [0u8; 16];
+		let expected = "00000000000000000000000000000000088b3d4342774649325f313964a39e55\
+			ea96c005ad52be8c7560413a7008f16c9e6d2f43bbea8814a546b7409ce783d3\
+			4c4f53245d08dab84102ed931f66d1492acb308fa1c6715b9d139b81acbdcc".from_hex().unwrap();
+		let ping = Message::Ping { request_id: vec![0, 0, 0, 1], enr_seq: 2 };
+
+		let masking_iv = [0u8; 16];
+		let header = static_header(FLAG_MESSAGE, &nonce, &src);
+		let ciphertext = seal(&key, &nonce, &concat(&masking_iv, &header), &ping.encode());
+		assert_eq!(masked_packet(&dest, &masking_iv, &header, &ciphertext), expected);
+
+		let packet = Packet::decode(&dest, &expected).unwrap();
+		assert_eq!(packet.flag(), FLAG_MESSAGE);
+		assert_eq!(packet.nonce(), nonce);
+		assert_eq!(packet.authdata(), &src[..]);
+		let plain = open(&key, &packet.nonce(), &packet.iv_and_header(), packet.message).unwrap();
+		assert_eq!(Message::decode(&plain).unwrap(), ping);
+		assert!(Packet::decode(&src, &expected).is_err());
+	}
+
+	#[test]
+	fn derives_session_keys() {
+		// discv5.1 test vectors "ECDH", "key derivation" and "ID nonce signing".
+		let ephemeral = Secret::from_str("fb757dc581730490a1d7a00deea65e9b1936924caaea8f44d476014856b68736").unwrap();
+		let dest = public_from_compressed(&"0317931e6e0840220642f230037d285d122bc59063221ef3226b1f403ddc69ca91".from_hex().unwrap()).unwrap();
+		let node_a = h256("aaaa8419e9f49d0083561b48287df592939a8d19947d8c0ef88f2a4856a69fbb");
+		let node_b = h256("bbbb9d047f0488c0b5a93c1c3f2d8bafc7c8ff337024a55434a0d0555de64db9");
+		let challenge = "000000000000000000000000000000006469736376350001010102030405060708090a0b0c\
+			00180102030405060708090a0b0c0d0e0f100000000000000000".from_hex().unwrap();
+
+		let (initiator_key, recipient_key) = derive_keys(&ephemeral, &dest, &challenge, &node_a, &node_b).unwrap();
+		assert_eq!(initiator_key.to_vec(), "dccc82d81bd610f4f76d3ebe97a40571".from_hex().unwrap());
+		assert_eq!(recipient_key.to_vec(), "ac74bb8773749920b0d3a8881c173ec5".from_hex().unwrap());
+
+		let static_key = KeyPair::from_secret(ephemeral).unwrap();
+		let ephemeral_public = "039961e4c2356d61bedb83052c115d311acb3a96f5777296dcf297351130266231".from_hex().unwrap();
+		let signature = "94852a1e2318c4e5e9d422c98eaf19d1d90d876b29cd06ca7cb7546d0fff7b48\
+			4fe86c09a064fe72bdbef73ba8e9c34df0cd2b53e9d65528c2c7f336d5dfc6e6".from_hex().unwrap();
+		assert!(verify_id_proof(static_key.public(), &signature, &challenge, &ephemeral_public, &node_b));
+		assert!(!verify_id_proof(static_key.public(), &signature, &challenge, &ephemeral_public, &node_a));
+
+		let own = sign(static_key.secret(), &id_proof_hash(&challenge, &ephemeral_public, &node_b)).unwrap();
+		assert!(verify_id_proof(static_key.public(), &own[0..64], &challenge, &ephemeral_public, &node_b));
+	}
+
+	#[test]
+	fn establishes_session_with_handshake() {
+		let key_a = Random.generate().unwrap();
+		let key_b = Random.generate().unwrap();
+		let addr_a = SocketAddr::from_str("127.0.0.1:40450").unwrap();
+		let addr_b = SocketAddr::from_str("127.0.0.1:40451").unwrap();
+		let endpoint_a = NodeEndpoint { address: addr_a, udp_port: 40450 };
+		let record_a = NodeRecord::new(key_a.secret(), key_a.public(), 5, &endpoint_a, &[], None).unwrap();
+		let mut a = Discv5::new(key_a.public(), key_a.secret());
+		let mut b = Discv5::new(key_b.public(), key_b.secret());
+		let unknown = |_: &H256| None;
+
+		// A does not have a session, B challenges it.
+		let ping = Message::Ping { request_id: vec![1], enr_seq: 5 };
+		let packet = a.encode_message(key_b.public(), &addr_b, &ping);
+		let whoareyou = match b.on_packet(&packet, &addr_a, None, &unknown).unwrap() {
+			Some(Incoming::Reply(packet)) => packet,
+			_ => panic!("expected WHOAREYOU"),
+		};
+		// A answers with the handshake, including its record B does not know.
+		let handshake = match a.on_packet(&whoareyou, &addr_b, Some(&record_a), &unknown).unwrap() {
+			Some(Incoming::Reply(packet)) => packet,
+			_ => panic!("expected handshake"),
+		};
+		match b.on_packet(&handshake, &addr_a, None, &unknown).unwrap() {
+			Some(Incoming::Message(public, message, record)) => {
+				assert_eq!(public, *key_a.public());
+				assert_eq!(message, ping);
+				assert_eq!(record, Some(record_a.clone()));
+			},
+			_ => panic!("expected message"),
+		}
+		// both sides can now exchange messages directly.
+		let pong = Message::Pong { request_id: vec![1], enr_seq: 0, address: addr_a };
+		let packet = b.encode_message(key_a.public(), &addr_a, &pong);
+		match a.on_packet(&packet, &addr_b, None, &unknown).unwrap() {
+			Some(Incoming::Message(public, message, None)) => {
+				assert_eq!(public, *key_b.public());
+				assert_eq!(message, pong);
+			},
+			_ => panic!("expected message"),
+		}
+
+		// a handshake replayed from another address is not accepted.
+		assert!(b.on_packet(&handshake, &addr_b, None, &unknown).unwrap().is_none());
+	}
+
+	#[test]
+	fn computes_log_distance() {
+		let a = H256::zero();
+		assert_eq!(log_distance(&a, &a), 0);
+		assert_eq!(log_distance(&a, &H256::from(1)), 1);
+		assert_eq!(log_distance(&a, &H256::from(0x80)), 8);
+		let mut far = H256::zero();
+		far[0] = 0x80;
+		assert_eq!(log_distance(&a, &far), 256);
+	}
+}
@@ -0,0 +1,279 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ethereum Node Records (EIP-778) using the "v4" identity scheme.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::{SocketAddr, SocketAddrV4, Ipv4Addr};
+use std::str::FromStr;
+use rustc_serialize::base64::{ToBase64, FromBase64, URL_SAFE};
+use ethkey::{Secret, Signature, sign, recover};
+use util::hash::*;
+use util::sha3::Hashable;
+use util::bytes::Bytes;
+use rlp::*;
+use node_table::{NodeId, NodeEndpoint};
+use error::NetworkError;
+
+/// Maximal size of an encoded record.
+pub const MAX_RECORD_SIZE: usize = 300;
+
+const ID_SCHEME: &'static str = "v4";
+
+/// Fork identifier (EIP-2124) advertised in the `eth` entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForkId {
+	/// CRC32 checksum of the genesis hash and passed fork blocks.
+	pub hash: u32,
+	/// Next upcoming fork block, zero if none is known.
+	pub next: u64,
+}
+
+/// Signed node record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeRecord {
+	seq: u64,
+	/// Keys with RLP-encoded values.
+	pairs: BTreeMap<String, Bytes>,
+	signature: Bytes,
+}
+
+impl NodeRecord {
+	/// Create and sign a record for the local node.
+	pub fn new(secret: &Secret, id: &NodeId, seq: u64, endpoint: &NodeEndpoint, capabilities: &[(String, u8)], fork_id: Option<ForkId>) -> Result<NodeRecord, NetworkError> {
+		let mut pairs = BTreeMap::new();
+		pairs.insert("id".to_owned(), encode(&ID_SCHEME).to_vec());
+		pairs.insert("secp256k1".to_owned(), encode(&(&compress(id)[..])).to_vec());
+		match endpoint.address {
+			SocketAddr::V4(ref a) => { pairs.insert("ip".to_owned(), encode(&(&a.ip().octets()[..])).to_vec()); },
+			SocketAddr::V6(ref a) => { pairs.insert("ip6".to_owned(), encode(&(&a.ip().octets()[..])).to_vec()); },
+		}
+		pairs.insert("tcp".to_owned(), encode(&endpoint.address.port()).to_vec());
+		pairs.insert("udp".to_owned(), encode(&endpoint.udp_port).to_vec());
+		if !capabilities.is_empty() {
+			let mut caps = RlpStream::new_list(capabilities.len());
+			for &(ref name, version) in capabilities {
+				caps.begin_list(2).append(name).append(&version);
+			}
+			pairs.insert("caps".to_owned(), caps.out());
+		}
+		if let Some(fork_id) = fork_id {
+			let mut eth = RlpStream::new_list(1);
+			eth.begin_list(2).append(&(&be_bytes(fork_id.hash)[..])).append(&fork_id.next);
+			pairs.insert("eth".to_owned(), eth.out());
+		}
+
+		let mut record = NodeRecord {
+			seq: seq,
+			pairs: pairs,
+			signature: Vec::new(),
+		};
+		let signature = sign(secret, &record.content().sha3())?;
+		record.signature = signature[0..64].to_vec();
+		Ok(record)
+	}
+
+	/// Sequence number, increased on every change of the record.
+	pub fn seq(&self) -> u64 {
+		self.seq
+	}
+
+	/// Public key of the node.
+	pub fn id(&self) -> Option<NodeId> {
+		self.value("secp256k1").and_then(|key| decompress_by_signature(&key, &self.signature, &self.content().sha3()))
+	}
+
+	/// Node endpoint.
+	pub fn endpoint(&self) -> Option<NodeEndpoint> {
+		let (ip, tcp) = match (self.value::<Bytes>("ip"), self.value::<u16>("tcp")) {
+			(Some(ref ip), Some(tcp)) if ip.len() == 4 => (Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3]), tcp),
+			_ => return None,
+		};
+		let udp = self.value("udp").unwrap_or(tcp);
+		Some(NodeEndpoint { address: SocketAddr::V4(SocketAddrV4::new(ip, tcp)), udp_port: udp })
+	}
+
+	/// Capabilities advertised by the node.
+	pub fn capabilities(&self) -> Option<Vec<(String, u8)>> {
+		self.pairs.get("caps").and_then(|raw| UntrustedRlp::new(raw).iter()
+			.map(|cap| Ok((cap.val_at(0)?, cap.val_at(1)?)))
+			.collect::<Result<Vec<_>, DecoderError>>()
+			.ok())
+	}
+
+	/// Fork identifier advertised by the node.
+	pub fn fork_id(&self) -> Option<ForkId> {
+		self.pairs.get("eth").and_then(|raw| {
+			let decode = || -> Result<(Bytes, u64), DecoderError> {
+				let rlp = UntrustedRlp::new(raw);
+				let entry = rlp.at(0)?;
+				Ok((entry.val_at(0)?, entry.val_at(1)?))
+			};
+			match decode() {
+				Ok((ref hash, next)) if hash.len() == 4 => Some(ForkId {
+					hash: (hash[0] as u32) << 24 | (hash[1] as u32) << 16 | (hash[2] as u32) << 8 | hash[3] as u32,
+					next: next,
+				}),
+				_ => None,
+			}
+		})
+	}
+
+	/// Decode value of the given key.
+	fn value<T: Decodable>(&self, key: &str) -> Option<T> {
+		self.pairs.get(key).and_then(|raw| UntrustedRlp::new(raw).as_val().ok())
+	}
+
+	/// Signed content: `[seq, k, v, ...]`.
+	fn content(&self) -> Bytes {
+		let mut s = RlpStream::new_list(1 + self.pairs.len() * 2);
+		s.append(&self.seq);
+		self.append_pairs(&mut s);
+		s.out()
+	}
+
+	fn append_pairs(&self, s: &mut RlpStream) {
+		for (key, value) in &self.pairs {
+			s.append(key);
+			s.append_raw(value, 1);
+		}
+	}
+
+	/// Decode and verify a record.
+	pub fn decode(rlp: &UntrustedRlp) -> Result<NodeRecord, NetworkError> {
+		if rlp.as_raw().len() > MAX_RECORD_SIZE {
+			return Err(NetworkError::BadProtocol);
+		}
+		let count = rlp.item_count()?;
+		if count < 2 || count % 2 != 0 {
+			return Err(NetworkError::BadProtocol);
+		}
+		let signature: Bytes = rlp.val_at(0)?;
+		let seq: u64 = rlp.val_at(1)?;
+		let mut pairs = BTreeMap::new();
+		let mut last_key: Option<String> = None;
+		for i in 1..count / 2 {
+			let key: String = rlp.val_at(i * 2)?;
+			// keys must be sorted and unique
+			if last_key.as_ref().map_or(false, |last| *last >= key) {
+				return Err(NetworkError::BadProtocol);
+			}
+			pairs.insert(key.clone(), rlp.at(i * 2 + 1)?.as_raw().to_vec());
+			last_key = Some(key);
+		}
+		let record = NodeRecord {
+			seq: seq,
+			pairs: pairs,
+			signature: signature,
+		};
+		if record.value::<String>("id").as_ref().map(String::as_str) != Some(ID_SCHEME) || record.id().is_none() {
+			return Err(NetworkError::BadProtocol);
+		}
+		Ok(record)
+	}
+}
+
+impl Encodable for NodeRecord {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(2 + self.pairs.len() * 2);
+		s.append(&self.signature);
+		s.append(&self.seq);
+		self.append_pairs(s);
+	}
+}
+
+impl fmt::Display for NodeRecord {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "enr:{}", encode(self).to_base64(URL_SAFE))
+	}
+}
+
+impl FromStr for NodeRecord {
+	type Err = NetworkError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if !s.starts_with("enr:") {
+			return Err(NetworkError::BadProtocol);
+		}
+		let bytes = s[4..].from_base64().map_err(|_| NetworkError::BadProtocol)?;
+		NodeRecord::decode(&UntrustedRlp::new(&bytes))
+	}
+}
+
+fn be_bytes(value: u32) -> [u8; 4] {
+	[(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]
+}
+
+/// Compressed form of a public key.
+pub fn compress(public: &NodeId) -> [u8; 33] {
+	let mut compressed = [0u8; 33];
+	compressed[0] = if public[63] & 1 == 0 { 2 } else { 3 };
+	compressed[1..].copy_from_slice(&public[0..32]);
+	compressed
+}
+
+/// Recover the full public key from its compressed form and a signature of `message` made with it.
+fn decompress_by_signature(compressed: &Bytes, signature: &Bytes, message: &H256) -> Option<NodeId> {
+	if compressed.len() != 33 || signature.len() != 64 {
+		return None;
+	}
+	let r = H256::from_slice(&signature[0..32]);
+	let s = H256::from_slice(&signature[32..64]);
+	(0..2).filter_map(|v| recover(&Signature::from_rsv(&r, &s, v), message).ok())
+		.find(|public| &compress(public)[..] == &compressed[..])
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use std::net::SocketAddr;
+	use ethkey::{Random, Generator};
+	use node_table::NodeEndpoint;
+	use super::{NodeRecord, ForkId};
+
+	#[test]
+	fn should_encode_and_verify_record() {
+		let key = Random.generate().unwrap();
+		let endpoint = NodeEndpoint { address: SocketAddr::from_str("22.99.55.44:30303").unwrap(), udp_port: 30301 };
+		let caps = vec![("eth".to_owned(), 63u8), ("par".to_owned(), 2u8)];
+		let fork_id = ForkId { hash: 0xfc64ec04, next: 1150000 };
+		let record = NodeRecord::new(key.secret(), key.public(), 7, &endpoint, &caps, Some(fork_id)).unwrap();
+
+		let decoded = NodeRecord::from_str(&format!("{}", record)).unwrap();
+		assert_eq!(decoded, record);
+		assert_eq!(decoded.seq(), 7);
+		assert_eq!(decoded.id().unwrap(), *key.public());
+		assert_eq!(decoded.endpoint().unwrap().address, endpoint.address);
+		assert_eq!(decoded.endpoint().unwrap().udp_port, 30301);
+		assert_eq!(decoded.capabilities().unwrap(), caps);
+		assert_eq!(decoded.fork_id(), Some(fork_id));
+	}
+
+	#[test]
+	fn should_reject_tampered_record() {
+		let key = Random.generate().unwrap();
+		let other = Random.generate().unwrap();
+		let endpoint = NodeEndpoint { address: SocketAddr::from_str("22.99.55.44:30303").unwrap(), udp_port: 30303 };
+		let mut record = NodeRecord::new(key.secret(), key.public(), 1, &endpoint, &[], None).unwrap();
+		record.seq = 2;
+		assert!(NodeRecord::from_str(&format!("{}", record)).is_err());
+
+		let mut record = NodeRecord::new(key.secret(), key.public(), 1, &endpoint, &[], None).unwrap();
+		record.signature = NodeRecord::new(other.secret(), other.public(), 1, &endpoint, &[], None).unwrap().signature;
+		assert!(NodeRecord::from_str(&format!("{}", record)).is_err());
+	}
+}
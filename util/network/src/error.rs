@@ -20,6 +20,7 @@ use util::UtilError;
 use std::fmt;
 use ethkey::Error as KeyError;
 use crypto::Error as CryptoError;
+use ip_utils::IpNetworkParseError;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum DisconnectReason
@@ -102,6 +103,8 @@ pub enum NetworkError {
 	Io(IoError),
 	/// Error concerning the network address parsing subsystem.
 	AddressParse(::std::net::AddrParseError),
+	/// Invalid network of the IP filter.
+	IpNetworkParse(IpNetworkParseError),
 	/// Error concerning the network address resolution subsystem.
 	AddressResolve(Option<::std::io::Error>),
 	/// Error concerning the Rust standard library's IO subsystem.
@@ -122,6 +125,7 @@ impl fmt::Display for NetworkError {
 			Disconnect(ref reason) => format!("Peer disconnected: {}", reason),
 			Io(ref err) => format!("Socket I/O error: {}", err),
 			AddressParse(ref err) => format!("{}", err),
+			IpNetworkParse(ref err) => format!("{}", err),
 			AddressResolve(Some(ref err)) => format!("{}", err),
 			AddressResolve(_) => "Failed to resolve network address.".into(),
 			StdIo(ref err) => format!("{}", err),
@@ -175,6 +179,12 @@ impl From<::std::net::AddrParseError> for NetworkError {
 	}
 }
 
+impl From<IpNetworkParseError> for NetworkError {
+	fn from(err: IpNetworkParseError) -> NetworkError {
+		NetworkError::IpNetworkParse(err)
+	}
+}
+
 #[test]
 fn test_errors() {
 	assert_eq!(DisconnectReason::ClientQuit, DisconnectReason::from_u8(8));
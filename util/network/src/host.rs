@@ -14,16 +14,19 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::net::{SocketAddr, SocketAddrV4, Ipv4Addr};
+use std::net::{SocketAddr, SocketAddrV4, Ipv4Addr, IpAddr};
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering as AtomicOrdering};
 use std::ops::*;
-use std::cmp::min;
+use std::cmp::{min, max};
 use std::path::{Path, PathBuf};
 use std::io::{Read, Write, ErrorKind};
 use std::fs;
+use std::net::TcpStream as StdTcpStream;
+use std::thread;
+use std::time::Duration;
 use ethkey::{KeyPair, Secret, Random, Generator};
 use mio::*;
 use mio::deprecated::{EventLoop};
@@ -35,11 +38,14 @@ use rlp::*;
 use session::{Session, SessionInfo, SessionData};
 use error::*;
 use io::*;
-use {NetworkProtocolHandler, NonReservedPeerMode, AllowIP, PROTOCOL_VERSION};
+use {NetworkProtocolHandler, NonReservedPeerMode, IpFilter, IpNetwork, PROTOCOL_VERSION};
 use node_table::*;
 use stats::NetworkStats;
 use discovery::{Discovery, TableUpdates, NodeEntry};
-use ip_utils::{map_external_address, select_public_address};
+use ip_utils::{map_external_address, select_public_address, SocketAddrExt};
+use nat_pmp;
+use enr::{NodeRecord, ForkId};
+use socks;
 use path::restrict_permissions_owner;
 use parking_lot::{Mutex, RwLock};
 
@@ -57,6 +63,8 @@ const DISCOVERY: usize = SYS_TIMER + 3;
 const DISCOVERY_REFRESH: usize = SYS_TIMER + 4;
 const DISCOVERY_ROUND: usize = SYS_TIMER + 5;
 const NODE_TABLE: usize = SYS_TIMER + 6;
+const NAT_RENEWAL: usize = SYS_TIMER + 7;
+const THROTTLE: usize = SYS_TIMER + 8;
 const FIRST_SESSION: usize = 0;
 const LAST_SESSION: usize = FIRST_SESSION + MAX_SESSIONS - 1;
 const USER_TIMER: usize = LAST_SESSION + 256;
@@ -67,6 +75,15 @@ const MAINTENANCE_TIMEOUT: u64 = 1000;
 const DISCOVERY_REFRESH_TIMEOUT: u64 = 60_000;
 const DISCOVERY_ROUND_TIMEOUT: u64 = 300;
 const NODE_TABLE_TIMEOUT: u64 = 300_000;
+const THROTTLE_TIMEOUT: u64 = 100;
+// Timeout for the SOCKS5 proxy handshake, in seconds.
+const PROXY_HANDSHAKE_TIMEOUT: u64 = 30;
+// Requested lifetime of NAT-PMP/PCP mappings, in seconds.
+const NAT_MAPPING_LIFETIME: u32 = 7200;
+// Shortest delay between NAT-PMP/PCP mapping renewals, in milliseconds.
+const NAT_MIN_RENEWAL_DELAY: u64 = 10_000;
+// Delay before retrying a failed NAT-PMP/PCP mapping renewal, in milliseconds.
+const NAT_RENEWAL_RETRY_DELAY: u64 = 60_000;
 
 #[derive(Debug, PartialEq, Clone)]
 /// Network service configuration
@@ -83,8 +100,14 @@ pub struct NetworkConfiguration {
 	pub udp_port: Option<u16>,
 	/// Enable NAT configuration
 	pub nat_enabled: bool,
+	/// Enable NAT-PMP/PCP port mapping, used when UPnP is disabled or fails
+	pub nat_pmp_enabled: bool,
 	/// Enable discovery
 	pub discovery_enabled: bool,
+	/// SOCKS5 proxy used for outbound connections
+	pub proxy: Option<SocketAddr>,
+	/// Fork identifier advertised in the node record
+	pub fork_id: Option<ForkId>,
 	/// List of initial node addresses
 	pub boot_nodes: Vec<String>,
 	/// Use provided node key instead of default
@@ -97,12 +120,22 @@ pub struct NetworkConfiguration {
 	pub max_handshakes: u32,
 	/// Reserved protocols. Peers with <key> protocol get additional <value> connection slots.
 	pub reserved_protocols: HashMap<ProtocolId, u32>,
+	/// Maximum number of peers sharing <key> protocol. Peers whose every shared protocol is at its limit are rejected.
+	pub protocol_limits: HashMap<ProtocolId, u32>,
 	/// List of reserved node addresses.
 	pub reserved_nodes: Vec<String>,
 	/// The non-reserved peer mode.
 	pub non_reserved_mode: NonReservedPeerMode,
-	/// IP filter
-	pub allow_ips: AllowIP,
+	/// IP filter for inbound and outbound connections
+	pub ip_filter: IpFilter,
+	/// Maximum number of peers from a single public IP address, zero for no limit
+	pub max_peers_per_ip: u32,
+	/// Maximum number of peers from a single public /24 (IPv4) or /64 (IPv6) subnet, zero for no limit
+	pub max_peers_per_subnet: u32,
+	/// Upload limit for all peer connections in bytes per second
+	pub max_upload: Option<u64>,
+	/// Download limit for all peer connections in bytes per second
+	pub max_download: Option<u64>,
 }
 
 impl Default for NetworkConfiguration {
@@ -121,14 +154,22 @@ impl NetworkConfiguration {
 			public_address: None,
 			udp_port: None,
 			nat_enabled: true,
+			nat_pmp_enabled: true,
 			discovery_enabled: true,
+			proxy: None,
+			fork_id: None,
 			boot_nodes: Vec::new(),
 			use_secret: None,
 			min_peers: 25,
 			max_peers: 50,
 			max_handshakes: 64,
 			reserved_protocols: HashMap::new(),
-			allow_ips: AllowIP::All,
+			protocol_limits: HashMap::new(),
+			ip_filter: IpFilter::default(),
+			max_peers_per_ip: 0,
+			max_peers_per_subnet: 0,
+			max_upload: None,
+			max_download: None,
 			reserved_nodes: Vec::new(),
 			non_reserved_mode: NonReservedPeerMode::Accept,
 		}
@@ -146,6 +187,7 @@ impl NetworkConfiguration {
 		let mut config = NetworkConfiguration::new();
 		config.listen_address = Some(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0)));
 		config.nat_enabled = false;
+		config.nat_pmp_enabled = false;
 		config
 	}
 }
@@ -186,6 +228,17 @@ pub enum NetworkIoMessage {
 	DisablePeer(PeerId),
 	/// Network has been started with the host as the given enode.
 	NetworkStarted(String),
+	/// Outbound connection through the proxy has been established.
+	ProxyConnected(NodeId),
+	/// NAT-PMP/PCP port mapping renewal has finished, `None` if it failed.
+	NatMappingRenewed(Option<PortMapping>),
+	/// Change limits for the number of connected peers.
+	PeerLimits {
+		/// Min number of connected peers to maintain.
+		min_peers: u32,
+		/// Max number of connected peers to maintain.
+		max_peers: u32,
+	},
 }
 
 /// Local (temporary) peer session ID.
@@ -296,6 +349,14 @@ impl<'s> NetworkContext<'s> {
 		Ok(())
 	}
 
+	/// Change limits for the number of connected peers.
+	pub fn set_peer_limits(&self, min_peers: u32, max_peers: u32) {
+		self.io.message(NetworkIoMessage::PeerLimits {
+			min_peers: min_peers,
+			max_peers: max_peers,
+		}).unwrap_or_else(|e| warn!("Error sending network IO message: {:?}", e));
+	}
+
 	/// Returns peer identification string
 	pub fn peer_client_version(&self, peer: PeerId) -> String {
 		self.resolve_session(peer).map_or("unknown".to_owned(), |s| s.lock().info.client_version.clone())
@@ -334,6 +395,10 @@ pub struct HostInfo {
 	pub local_endpoint: NodeEndpoint,
 	/// Public address + discovery port
 	pub public_endpoint: Option<NodeEndpoint>,
+	/// Port mapping created on the NAT gateway
+	pub port_mapping: Option<PortMapping>,
+	/// Signed record of the local node
+	pub node_record: Option<NodeRecord>,
 }
 
 impl HostInfo {
@@ -354,6 +419,30 @@ impl HostInfo {
 	}
 }
 
+/// NAT traversal method used to map the listening ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortMappingMethod {
+	/// Universal Plug and Play.
+	UPnP,
+	/// NAT Port Mapping Protocol.
+	NatPmp,
+	/// Port Control Protocol.
+	Pcp,
+}
+
+/// Port mapping created on the NAT gateway.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortMapping {
+	/// Method used to create the mapping.
+	pub method: PortMappingMethod,
+	/// External address and TCP port.
+	pub external_address: SocketAddr,
+	/// External UDP port.
+	pub udp_port: u16,
+	/// Mapping lifetime in seconds, renewed periodically. `None` for permanent mappings.
+	pub lifetime: Option<u32>,
+}
+
 type SharedSession = Arc<Mutex<Session>>;
 
 #[derive(Copy, Clone)]
@@ -368,6 +457,10 @@ pub struct Host {
 	tcp_listener: Mutex<TcpListener>,
 	sessions: Arc<RwLock<Slab<SharedSession>>>,
 	discovery: Mutex<Option<Discovery>>,
+	/// NAT-PMP/PCP gateway, shared with the mapping renewal thread.
+	nat_gateway: Arc<Mutex<Option<nat_pmp::Gateway>>>,
+	/// Outbound connections being established through the proxy.
+	proxy_connecting: Arc<Mutex<HashMap<NodeId, Option<StdTcpStream>>>>,
 	nodes: RwLock<NodeTable>,
 	handlers: RwLock<HashMap<ProtocolId, Arc<NetworkProtocolHandler>>>,
 	timers: RwLock<HashMap<TimerToken, ProtocolTimer>>,
@@ -421,8 +514,12 @@ impl Host {
 				capabilities: Vec::new(),
 				public_endpoint: None,
 				local_endpoint: local_endpoint,
+				port_mapping: None,
+				node_record: None,
 			}),
 			discovery: Mutex::new(None),
+			nat_gateway: Arc::new(Mutex::new(None)),
+			proxy_connecting: Arc::new(Mutex::new(HashMap::new())),
 			tcp_listener: Mutex::new(tcp_listener),
 			sessions: Arc::new(RwLock::new(Slab::new_starting_at(FIRST_SESSION, MAX_SESSIONS))),
 			nodes: RwLock::new(NodeTable::new(path)),
@@ -448,6 +545,18 @@ impl Host {
 	}
 
 	pub fn add_node(&mut self, id: &str) {
+		if id.starts_with("enr:") {
+			match NodeRecord::from_str(id).ok().and_then(|r| r.id().and_then(|id| r.endpoint().map(|ep| (id, ep, r.clone())))) {
+				None => { debug!(target: "network", "Could not add node record {}", id); },
+				Some((id, endpoint, record)) => {
+					self.nodes.write().add_node(Node::new(id, endpoint));
+					if let Some(ref mut discovery) = *self.discovery.lock() {
+						discovery.add_node_record(record);
+					}
+				}
+			}
+			return;
+		}
 		match Node::from_str(id) {
 			Err(e) => { debug!(target: "network", "Could not add node {}: {:?}", id, e); },
 			Ok(n) => {
@@ -526,6 +635,10 @@ impl Host {
 		format!("{}", Node::new(info.id().clone(), info.local_endpoint.clone()))
 	}
 
+	pub fn port_mapping(&self) -> Option<PortMapping> {
+		self.info.read().port_mapping.clone()
+	}
+
 	pub fn stop(&self, io: &IoContext<NetworkIoMessage>) -> Result<(), NetworkError> {
 		self.stopping.store(true, AtomicOrdering::Release);
 		let mut to_kill = Vec::new();
@@ -562,21 +675,38 @@ impl Host {
 		}
 		let local_endpoint = self.info.read().local_endpoint.clone();
 		let public_address = self.info.read().config.public_address.clone();
-		let allow_ips = self.info.read().config.allow_ips;
+		let ip_filter = self.info.read().config.ip_filter.clone();
 		let public_endpoint = match public_address {
 			None => {
 				let public_address = select_public_address(local_endpoint.address.port());
 				let public_endpoint = NodeEndpoint { address: public_address, udp_port: local_endpoint.udp_port };
-				if self.info.read().config.nat_enabled {
-					match map_external_address(&local_endpoint) {
-						Some(endpoint) => {
-							info!("NAT mapped to external address {}", endpoint.address);
+				let (nat_enabled, nat_pmp_enabled) = {
+					let info = self.info.read();
+					(info.config.nat_enabled, info.config.nat_pmp_enabled)
+				};
+				let upnp_endpoint = if nat_enabled { map_external_address(&local_endpoint) } else { None };
+				match upnp_endpoint {
+					Some(endpoint) => {
+						info!("NAT mapped to external address {}", endpoint.address);
+						self.info.write().port_mapping = Some(PortMapping {
+							method: PortMappingMethod::UPnP,
+							external_address: endpoint.address,
+							udp_port: endpoint.udp_port,
+							lifetime: None,
+						});
+						endpoint
+					},
+					None if nat_pmp_enabled => match map_nat_pmp(&self.nat_gateway, &local_endpoint, None) {
+						Some(mapping) => {
+							info!("NAT-PMP mapped to external address {}", mapping.external_address);
+							io.register_timer_once(NAT_RENEWAL, nat_renewal_delay(&mapping))?;
+							let endpoint = NodeEndpoint { address: mapping.external_address, udp_port: mapping.udp_port };
+							self.info.write().port_mapping = Some(mapping);
 							endpoint
 						},
 						None => public_endpoint
-					}
-				} else {
-					public_endpoint
+					},
+					None => public_endpoint
 				}
 			}
 			Some(addr) => NodeEndpoint { address: addr, udp_port: local_endpoint.udp_port }
@@ -594,23 +724,66 @@ impl Host {
 			if info.config.discovery_enabled && info.config.non_reserved_mode == NonReservedPeerMode::Accept {
 				let mut udp_addr = local_endpoint.address.clone();
 				udp_addr.set_port(local_endpoint.udp_port);
-				Some(Discovery::new(&info.keys, udp_addr, public_endpoint, DISCOVERY, allow_ips))
+				let boot_records: Vec<_> = info.config.boot_nodes.iter().filter_map(|n| NodeRecord::from_str(n).ok()).collect();
+				Some((Discovery::new(&info.keys, udp_addr, public_endpoint, DISCOVERY, ip_filter), boot_records))
 			} else { None }
 		};
 
-		if let Some(mut discovery) = discovery {
+		if let Some((mut discovery, boot_records)) = discovery {
 			discovery.init_node_list(self.nodes.read().unordered_entries());
 			discovery.add_node_list(self.nodes.read().unordered_entries());
+			// boot nodes given by their records talk discovery v5.
+			for record in boot_records {
+				discovery.add_node_record(record);
+			}
 			*self.discovery.lock() = Some(discovery);
 			io.register_stream(DISCOVERY)?;
 			io.register_timer(DISCOVERY_REFRESH, DISCOVERY_REFRESH_TIMEOUT)?;
 			io.register_timer(DISCOVERY_ROUND, DISCOVERY_ROUND_TIMEOUT)?;
 		}
+		self.update_node_record();
 		io.register_timer(NODE_TABLE, NODE_TABLE_TIMEOUT)?;
 		io.register_stream(TCP_ACCEPT)?;
 		Ok(())
 	}
 
+	/// Renew the NAT-PMP/PCP mapping in the background, the gateway may take seconds to answer.
+	/// `NatMappingRenewed` is sent once it is done.
+	fn renew_nat_pmp(&self, io: &IoContext<NetworkIoMessage>) {
+		let local_endpoint = self.info.read().local_endpoint.clone();
+		let previous = self.info.read().port_mapping.clone();
+		let gateway = self.nat_gateway.clone();
+		let channel = io.channel();
+		let spawned = thread::Builder::new().name("NAT-PMP renewal".into()).spawn(move || {
+			let mapping = map_nat_pmp(&gateway, &local_endpoint, previous.as_ref());
+			channel.send(NetworkIoMessage::NatMappingRenewed(mapping)).unwrap_or_else(|e| warn!("Error sending IO notification: {:?}", e));
+		});
+		if let Err(e) = spawned {
+			debug!(target: "network", "Error spawning NAT-PMP renewal thread: {:?}", e);
+			io.register_timer_once(NAT_RENEWAL, NAT_RENEWAL_RETRY_DELAY).unwrap_or_else(|e| debug!("Error registering NAT renewal timer: {:?}", e));
+		}
+	}
+
+	fn on_nat_pmp_renewed(&self, io: &IoContext<NetworkIoMessage>, mapping: Option<PortMapping>) {
+		let previous = self.info.read().public_endpoint.as_ref().map(|e| (e.address, e.udp_port));
+		let delay = match mapping {
+			Some(ref mapping) => {
+				if Some((mapping.external_address, mapping.udp_port)) != previous {
+					warn!(target: "network", "NAT-PMP mapping changed to {}, restart to advertise the new address", mapping.external_address);
+				} else {
+					trace!(target: "network", "NAT-PMP mapping renewed for {:?} seconds", mapping.lifetime);
+				}
+				nat_renewal_delay(mapping)
+			},
+			None => {
+				warn!(target: "network", "Failed to renew NAT-PMP port mapping");
+				NAT_RENEWAL_RETRY_DELAY
+			},
+		};
+		self.info.write().port_mapping = mapping;
+		io.register_timer_once(NAT_RENEWAL, delay).unwrap_or_else(|e| debug!("Error registering NAT renewal timer: {:?}", e));
+	}
+
 	fn maintain_network(&self, io: &IoContext<NetworkIoMessage>) {
 		self.keep_alive(io);
 		self.connect_peers(io);
@@ -625,7 +798,65 @@ impl Host {
 	}
 
 	fn connecting_to(&self, id: &NodeId) -> bool {
-		self.sessions.read().iter().any(|e| e.lock().id() == Some(id))
+		self.proxy_connecting.lock().contains_key(id) || self.sessions.read().iter().any(|e| e.lock().id() == Some(id))
+	}
+
+	/// Check if one more peer from the address would exceed the per-address or per-subnet limits.
+	/// Only public addresses are limited. The session `token` being checked is not counted.
+	fn ip_limit_reached(&self, ip: &IpAddr, token: Option<StreamToken>) -> bool {
+		let (per_ip, per_subnet) = {
+			let config = &self.info.read().config;
+			(config.max_peers_per_ip as usize, config.max_peers_per_subnet as usize)
+		};
+		if (per_ip == 0 && per_subnet == 0) || !ip.is_global_s() {
+			return false;
+		}
+		let prefix = match *ip {
+			IpAddr::V4(_) => 24,
+			IpAddr::V6(_) => 64,
+		};
+		let subnet = IpNetwork::new(*ip, prefix).expect("prefix is valid for the address family; qed");
+		let (mut same_ip, mut same_subnet) = (0, 0);
+		for e in self.sessions.read().iter() {
+			let s = e.lock();
+			if Some(s.token()) == token || !s.is_ready() {
+				continue;
+			}
+			if let Ok(address) = s.remote_addr() {
+				if address.ip() == *ip {
+					same_ip += 1;
+				}
+				if subnet.contains(&address.ip()) {
+					same_subnet += 1;
+				}
+			}
+		}
+		(per_ip != 0 && same_ip >= per_ip) || (per_subnet != 0 && same_subnet >= per_subnet)
+	}
+
+	/// Check if every protocol in `protocols` has reached its peer limit. The session `token` is not counted.
+	fn protocol_limits_reached(&self, protocols: &[ProtocolId], token: StreamToken) -> bool {
+		let limits: Vec<(ProtocolId, usize)> = {
+			let info = self.info.read();
+			let limits = &info.config.protocol_limits;
+			if protocols.is_empty() || protocols.iter().any(|p| !limits.contains_key(p)) {
+				return false;
+			}
+			protocols.iter().map(|p| (*p, limits[p] as usize)).collect()
+		};
+		let mut counts = vec![0; limits.len()];
+		for e in self.sessions.read().iter() {
+			let s = e.lock();
+			if s.token() == token || !s.is_ready() {
+				continue;
+			}
+			for (i, &(protocol, _)) in limits.iter().enumerate() {
+				if s.have_capability(protocol) {
+					counts[i] += 1;
+				}
+			}
+		}
+		limits.iter().zip(counts).all(|(&(_, limit), count)| count >= limit)
 	}
 
 	fn handshake_count(&self) -> usize {
@@ -649,14 +880,14 @@ impl Host {
 	}
 
 	fn connect_peers(&self, io: &IoContext<NetworkIoMessage>) {
-		let (min_peers, mut pin, max_handshakes, allow_ips, self_id) = {
+		let (min_peers, mut pin, max_handshakes, ip_filter, self_id) = {
 			let info = self.info.read();
 			if info.capabilities.is_empty() {
 				return;
 			}
 			let config = &info.config;
 
-			(config.min_peers, config.non_reserved_mode == NonReservedPeerMode::Deny, config.max_handshakes as usize, config.allow_ips, info.id().clone())
+			(config.min_peers, config.non_reserved_mode == NonReservedPeerMode::Deny, config.max_handshakes as usize, config.ip_filter.clone(), info.id().clone())
 		};
 
 		let session_count = self.session_count();
@@ -680,7 +911,8 @@ impl Host {
 		// iterate over all nodes, reserved ones coming first.
 		// if we are pinned to only reserved nodes, ignore all others.
 		let nodes = reserved_nodes.iter().cloned().chain(if !pin {
-			self.nodes.read().nodes(allow_ips)
+			let nodes = self.nodes.read().nodes(&ip_filter);
+			self.prioritize_by_record(nodes)
 		} else {
 			Vec::new()
 		});
@@ -689,14 +921,74 @@ impl Host {
 		let mut started: usize = 0;
 		for id in nodes.filter(|id| !self.have_session(id) && !self.connecting_to(id) && *id != self_id)
 			.take(min(max_handshakes_per_round, max_handshakes - handshake_count)) {
-			self.connect_peer(&id, io);
+			self.connect_peer(&id, reserved_nodes.contains(&id), io);
 			started += 1;
 		}
 		debug!(target: "network", "Connecting peers: {} sessions, {} pending, {} started", self.session_count(), self.handshake_count(), started);
 	}
 
+	/// Drop nodes whose record advertises none of our capabilities, then prefer nodes on the same fork.
+	fn prioritize_by_record(&self, mut nodes: Vec<NodeId>) -> Vec<NodeId> {
+		let discovery = self.discovery.lock();
+		let discovery = match *discovery {
+			Some(ref discovery) => discovery,
+			None => return nodes,
+		};
+		let info = self.info.read();
+		nodes.retain(|id| discovery.node_record(id).and_then(|r| r.capabilities()).map_or(true, |caps| {
+			caps.iter().any(|&(ref name, version)| info.capabilities.iter().any(|c| &c.protocol[..] == name.as_bytes() && c.version == version))
+		}));
+		// stable sort keeps the failure ordering within each group
+		nodes.sort_by_key(|id| match (info.config.fork_id, discovery.node_record(id).and_then(|r| r.fork_id())) {
+			(Some(ours), Some(theirs)) if ours.hash == theirs.hash => 0,
+			(Some(_), Some(_)) => 2,
+			_ => 1,
+		});
+		nodes
+	}
+
+	/// Change the advertised fork identifier, signing a new node record if it differs.
+	pub fn set_fork_id(&self, fork_id: ForkId) {
+		{
+			let mut info = self.info.write();
+			if info.config.fork_id == Some(fork_id) {
+				return;
+			}
+			info.config.fork_id = Some(fork_id);
+		}
+		self.update_node_record();
+	}
+
+	/// Sign a new record of the local node and advertise it through discovery.
+	fn update_node_record(&self) {
+		let record = {
+			let info = self.info.read();
+			let endpoint = match info.public_endpoint {
+				Some(ref endpoint) => endpoint.clone(),
+				None => return,
+			};
+			let capabilities: Vec<_> = info.capabilities.iter()
+				.map(|c| (String::from_utf8_lossy(&c.protocol).into_owned(), c.version))
+				.collect();
+			// records must survive restarts with increasing sequence numbers
+			let seq = max(info.node_record.as_ref().map_or(0, |r| r.seq() + 1), ::time::get_time().sec as u64);
+			match NodeRecord::new(info.secret(), info.id(), seq, &endpoint, &capabilities, info.config.fork_id) {
+				Ok(record) => record,
+				Err(e) => {
+					warn!(target: "network", "Error signing node record: {:?}", e);
+					return;
+				}
+			}
+		};
+		debug!(target: "network", "Node record: {}", record);
+		if let Some(ref mut discovery) = *self.discovery.lock() {
+			discovery.set_record(record.clone());
+		}
+		self.info.write().node_record = Some(record);
+	}
+
 	#[cfg_attr(feature="dev", allow(single_match))]
-	fn connect_peer(&self, id: &NodeId, io: &IoContext<NetworkIoMessage>) {
+	fn connect_peer(&self, id: &NodeId, reserved: bool, io: &IoContext<NetworkIoMessage>) {
 		if self.have_session(id) {
 			trace!(target: "network", "Aborted connect. Node already connected.");
 			return;
@@ -718,6 +1010,19 @@ impl Host {
 					return;
 				}
 			};
+			// blocked networks apply to reserved nodes as well
+			if self.info.read().config.ip_filter.is_blocked(&address.ip()) {
+				debug!(target: "network", "Connection to blocked address {:?} aborted", address);
+				return;
+			}
+			if !reserved && self.ip_limit_reached(&address.ip(), None) {
+				trace!(target: "network", "Connection to {:?} aborted, too many peers from the address", address);
+				return;
+			}
+			if let Some(proxy) = self.info.read().config.proxy {
+				self.connect_through_proxy(id, proxy, address, io);
+				return;
+			}
 			match TcpStream::connect(&address) {
 				Ok(socket) => {
 					trace!(target: "network", "Connecting to {:?}", address);
@@ -734,6 +1039,31 @@ impl Host {
 		}
 	}
 
+	/// Establish the connection through the proxy in the background. `ProxyConnected` is sent once it is ready.
+	fn connect_through_proxy(&self, id: &NodeId, proxy: SocketAddr, address: SocketAddr, io: &IoContext<NetworkIoMessage>) {
+		let id = id.clone();
+		let connecting = self.proxy_connecting.clone();
+		let channel = io.channel();
+		connecting.lock().insert(id, None);
+		trace!(target: "network", "Connecting to {:?} through proxy {:?}", address, proxy);
+		let spawned = thread::Builder::new().name("Proxy connect".into()).spawn(move || {
+			match socks::connect(&proxy, &address, Duration::from_secs(PROXY_HANDSHAKE_TIMEOUT)) {
+				Ok(stream) => {
+					connecting.lock().insert(id, Some(stream));
+					channel.send(NetworkIoMessage::ProxyConnected(id)).unwrap_or_else(|e| warn!("Error sending IO notification: {:?}", e));
+				},
+				Err(e) => {
+					debug!(target: "network", "Can't connect to address {:?} through proxy: {:?}", address, e);
+					connecting.lock().remove(&id);
+				},
+			}
+		});
+		if let Err(e) = spawned {
+			debug!(target: "network", "Error spawning proxy connection thread: {:?}", e);
+			self.proxy_connecting.lock().remove(&id);
+		}
+	}
+
 	#[cfg_attr(feature="dev", allow(block_in_if_condition_stmt))]
 	fn create_connection(&self, socket: TcpStream, id: Option<&NodeId>, io: &IoContext<NetworkIoMessage>) -> Result<(), NetworkError> {
 		let nonce = self.info.write().next_nonce();
@@ -762,7 +1092,14 @@ impl Host {
 		trace!(target: "network", "Accepting incoming connection");
 		loop {
 			let socket = match self.tcp_listener.lock().accept() {
-				Ok((sock, _addr)) => sock,
+				Ok((sock, addr)) => {
+					// reserved peers are exempt from the allowed networks, which is checked once they are identified.
+					if self.info.read().config.ip_filter.is_blocked(&addr.ip()) {
+						debug!(target: "network", "Rejected connection from blocked address {:?}", addr);
+						continue;
+					}
+					sock
+				},
 				Err(e) => {
 					if e.kind() != ErrorKind::WouldBlock {
 						debug!(target: "network", "Error accepting connection: {:?}", e);
@@ -790,6 +1127,20 @@ impl Host {
 		}
 	}
 
+	/// Continue reading and writing sessions stalled by the bandwidth limits.
+	fn resume_throttled(&self, io: &IoContext<NetworkIoMessage>) {
+		let tokens: Vec<StreamToken> = self.sessions.read().iter()
+			.filter_map(|s| {
+				let s = s.lock();
+				if s.expired() { None } else { Some(s.token()) }
+			})
+			.collect();
+		for token in tokens {
+			self.session_writable(token, io);
+			self.session_readable(token, io);
+		}
+	}
+
 	fn connection_closed(&self, token: TimerToken, io: &IoContext<NetworkIoMessage>) {
 		trace!(target: "network", "Connection closed: {}", token);
 		self.kill_connection(token, io, true);
@@ -837,6 +1188,17 @@ impl Host {
 
 							let id = s.id().expect("Ready session always has id").clone();
 
+							// Inbound peers outside of the allowed networks are accepted only if reserved.
+							if !s.info.originated && !self.reserved_nodes.read().contains(&id) {
+								let allowed = s.remote_addr().ok().map_or(false, |address| self.info.read().config.ip_filter.is_allowed(&address.ip()));
+								if !allowed {
+									debug!(target: "network", "Rejected connection from filtered address {:?}", s.remote_addr());
+									s.disconnect(io, DisconnectReason::UselessPeer);
+									kill = true;
+									break;
+								}
+							}
+
 							// Check for the session limit. session_counts accounts for the new session.
 							if reserved_only ||
 								(s.info.originated && session_count > min_peers) ||
@@ -861,6 +1223,7 @@ impl Host {
 									}
 								}
 							}
+							self.nodes.write().note_success(&id);
 							for (p, _) in self.handlers.read().iter() {
 								if s.have_capability(*p) {
 									ready_data.push(*p);
@@ -899,6 +1262,23 @@ impl Host {
 					self.kill_connection(token, io, false);
 					return;
 				}
+				let reserved = ready_id.as_ref().map_or(false, |id| self.reserved_nodes.read().contains(id));
+				if !reserved && self.protocol_limits_reached(&ready_data, token) {
+					trace!(target: "network", "Rejected connection, protocol slots are full: {}", token);
+					session.lock().disconnect(io, DisconnectReason::TooManyPeers);
+					self.kill_connection(token, io, false);
+					return;
+				}
+				let address = session.lock().remote_addr().ok();
+				if let Some(address) = address {
+					if !reserved && self.ip_limit_reached(&address.ip(), Some(token)) {
+						trace!(target: "network", "Rejected connection from {:?}, too many peers from the address: {}", address, token);
+						self.stats.inc_ip_limited();
+						session.lock().disconnect(io, DisconnectReason::TooManyPeers);
+						self.kill_connection(token, io, false);
+						return;
+					}
+				}
 				for p in ready_data {
 					self.stats.inc_sessions();
 					let reserved = self.reserved_nodes.read();
@@ -1004,6 +1384,9 @@ impl IoHandler<NetworkIoMessage> for Host {
 	/// Initialize networking
 	fn initialize(&self, io: &IoContext<NetworkIoMessage>) {
 		io.register_timer(IDLE, MAINTENANCE_TIMEOUT).expect("Error registering Network idle timer");
+		if self.stats.is_limited() {
+			io.register_timer(THROTTLE, THROTTLE_TIMEOUT).expect("Error registering Network throttle timer");
+		}
 		io.message(NetworkIoMessage::InitPublicInterface).unwrap_or_else(|e| warn!("Error sending IO notification: {:?}", e));
 		self.maintain_network(io)
 	}
@@ -1069,6 +1452,8 @@ impl IoHandler<NetworkIoMessage> for Host {
 				self.nodes.write().clear_useless();
 				self.nodes.write().save();
 			},
+			NAT_RENEWAL => self.renew_nat_pmp(io),
+			THROTTLE => self.resume_throttled(io),
 			_ => match self.timers.read().get(&token).cloned() {
 				Some(timer) => match self.handlers.read().get(&timer.protocol).cloned() {
 					None => { warn!(target: "network", "No handler found for protocol: {:?}", timer.protocol) },
@@ -1097,10 +1482,13 @@ impl IoHandler<NetworkIoMessage> for Host {
 				let reserved = self.reserved_nodes.read();
 				h.initialize(&NetworkContext::new(io, *protocol, None, self.sessions.clone(), &reserved));
 				self.handlers.write().insert(*protocol, h);
-				let mut info = self.info.write();
-				for v in versions {
-					info.capabilities.push(CapabilityInfo { protocol: *protocol, version: *v, packet_count: *packet_count });
+				{
+					let mut info = self.info.write();
+					for v in versions {
+						info.capabilities.push(CapabilityInfo { protocol: *protocol, version: *v, packet_count: *packet_count });
+					}
 				}
+				self.update_node_record();
 			},
 			NetworkIoMessage::AddTimer {
 				ref protocol,
@@ -1136,8 +1524,33 @@ impl IoHandler<NetworkIoMessage> for Host {
 				trace!(target: "network", "Disabling peer {}", peer);
 				self.kill_connection(*peer, io, false);
 			},
+			NetworkIoMessage::NatMappingRenewed(ref mapping) => self.on_nat_pmp_renewed(io, mapping.clone()),
+			NetworkIoMessage::ProxyConnected(ref id) => {
+				let stream = self.proxy_connecting.lock().remove(id).and_then(|s| s);
+				let socket = match stream.map(TcpStream::from_stream) {
+					Some(Ok(socket)) => socket,
+					Some(Err(e)) => {
+						debug!(target: "network", "Can't register proxy connection: {:?}", e);
+						return;
+					},
+					None => return,
+				};
+				if self.have_session(id) {
+					trace!(target: "network", "Dropped proxy connection. Node already connected.");
+					return;
+				}
+				if let Err(e) = self.create_connection(socket, Some(id), io) {
+					debug!(target: "network", "Can't create connection: {:?}", e);
+				}
+			},
 			NetworkIoMessage::InitPublicInterface =>
 				self.init_public_interface(io).unwrap_or_else(|e| warn!("Error initializing public interface: {:?}", e)),
+			NetworkIoMessage::PeerLimits { min_peers, max_peers } => {
+				trace!(target: "network", "Peer limits changed to {}-{}", min_peers, max_peers);
+				let mut info = self.info.write();
+				info.config.min_peers = min_peers;
+				info.config.max_peers = max_peers;
+			},
 			_ => {}	// ignore others.
 		}
 	}
@@ -1188,6 +1601,53 @@ impl IoHandler<NetworkIoMessage> for Host {
 	}
 }
 
+/// Map TCP and UDP ports using NAT-PMP or PCP, reusing the external ports of the `previous` mapping.
+/// Blocks while the gateway is retried, so it must not be called on the IO thread after start up.
+fn map_nat_pmp(gateway: &Mutex<Option<nat_pmp::Gateway>>, local_endpoint: &NodeEndpoint, previous: Option<&PortMapping>) -> Option<PortMapping> {
+	let local_addr = match local_endpoint.address {
+		SocketAddr::V4(ref addr) => addr.clone(),
+		_ => return None,
+	};
+	let mut gateway = gateway.lock();
+	if gateway.is_none() {
+		match nat_pmp::Gateway::find(local_addr.ip().clone()) {
+			Ok(g) => *gateway = Some(g),
+			Err(err) => {
+				debug!(target: "network", "NAT-PMP gateway error: {}", err);
+				return None;
+			}
+		}
+	}
+	let gateway = gateway.as_mut().expect("gateway is set above; qed");
+	let (tcp_port, udp_port) = match previous {
+		Some(mapping) => (mapping.external_address.port(), mapping.udp_port),
+		None => (local_addr.port(), local_endpoint.udp_port),
+	};
+	let mapping = gateway.map_port(nat_pmp::Protocol::Tcp, local_addr.port(), tcp_port, NAT_MAPPING_LIFETIME)
+		.and_then(|tcp| gateway.map_port(nat_pmp::Protocol::Udp, local_endpoint.udp_port, udp_port, NAT_MAPPING_LIFETIME).map(|udp| (tcp, udp)));
+	match mapping {
+		Ok((tcp, udp)) => Some(PortMapping {
+			method: match gateway.version() {
+				nat_pmp::Version::NatPmp => PortMappingMethod::NatPmp,
+				nat_pmp::Version::Pcp => PortMappingMethod::Pcp,
+			},
+			external_address: SocketAddr::V4(SocketAddrV4::new(tcp.external_ip, tcp.external_port)),
+			udp_port: udp.external_port,
+			lifetime: Some(min(tcp.lifetime, udp.lifetime)),
+		}),
+		Err(err) => {
+			debug!(target: "network", "NAT-PMP port mapping error: {}", err);
+			None
+		}
+	}
+}
+
+/// Milliseconds until the mapping should be renewed: half of the lifetime granted by the gateway.
+fn nat_renewal_delay(mapping: &PortMapping) -> u64 {
+	let lifetime = mapping.lifetime.unwrap_or(NAT_MAPPING_LIFETIME) as u64;
+	max(lifetime * 1000 / 2, NAT_MIN_RENEWAL_DELAY)
+}
+
 fn save_key(path: &Path, key: &Secret) {
 	let mut path_buf = PathBuf::from(path);
 	if let Err(e) = fs::create_dir_all(path_buf.as_path()) {
@@ -1248,6 +1708,20 @@ fn key_save_load() {
 	assert_eq!(key, r.unwrap());
 }
 
+#[test]
+fn nat_renewal_follows_granted_lifetime() {
+	let mut mapping = PortMapping {
+		method: PortMappingMethod::Pcp,
+		external_address: "1.2.3.4:30303".parse().unwrap(),
+		udp_port: 30303,
+		lifetime: Some(600),
+	};
+	assert_eq!(nat_renewal_delay(&mapping), 300_000);
+	mapping.lifetime = Some(4);
+	assert_eq!(nat_renewal_delay(&mapping), NAT_MIN_RENEWAL_DELAY);
+	mapping.lifetime = None;
+	assert_eq!(nat_renewal_delay(&mapping), NAT_MAPPING_LIFETIME as u64 * 500);
+}
 
 #[test]
 fn host_client_url() {
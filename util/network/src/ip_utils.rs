@@ -16,8 +16,10 @@
 
 // Based on original work by David Levy https://raw.githubusercontent.com/dlevy47/rust-interfaces
 
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, AddrParseError};
 use std::io;
+use std::fmt;
+use std::str::FromStr;
 use igd::{PortMappingProtocol, search_gateway_from_timeout};
 use std::time::Duration;
 use node_table::{NodeEndpoint};
@@ -72,6 +74,114 @@ impl SocketAddrExt for IpAddr {
 	}
 }
 
+/// IP network in CIDR notation, e.g. `10.0.0.0/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+	address: IpAddr,
+	prefix: u8,
+}
+
+impl IpNetwork {
+	/// Create a new network. Returns `None` if the prefix is longer than the address.
+	/// Networks of IPv4-mapped IPv6 addresses are stored as IPv4 networks.
+	pub fn new(address: IpAddr, prefix: u8) -> Option<IpNetwork> {
+		if prefix > max_prefix(&address) {
+			return None;
+		}
+		match to_canonical(&address) {
+			IpAddr::V4(v4) if address.is_ipv6() && prefix >= 96 => Some(IpNetwork { address: IpAddr::V4(v4), prefix: prefix - 96 }),
+			_ => Some(IpNetwork { address: address, prefix: prefix }),
+		}
+	}
+
+	/// Check if the address belongs to this network. Addresses of the other family never do,
+	/// except for IPv4-mapped IPv6 addresses which are matched as IPv4.
+	pub fn contains(&self, ip: &IpAddr) -> bool {
+		match (self.address, to_canonical(ip)) {
+			(IpAddr::V4(ref net), IpAddr::V4(ref ip)) => prefix_matches(&net.octets(), &ip.octets(), self.prefix),
+			(IpAddr::V6(ref net), IpAddr::V6(ref ip)) => prefix_matches(&net.octets(), &ip.octets(), self.prefix),
+			_ => false,
+		}
+	}
+}
+
+/// Convert an IPv4-mapped IPv6 address (`::ffff:a.b.c.d`) to IPv4, other addresses are returned unchanged.
+/// Dual-stack sockets report IPv4 peers this way.
+pub fn to_canonical(ip: &IpAddr) -> IpAddr {
+	match *ip {
+		IpAddr::V6(ref v6) if v6.segments()[..6] == [0, 0, 0, 0, 0, 0xffff] => {
+			let octets = v6.octets();
+			IpAddr::V4(Ipv4Addr::new(octets[12], octets[13], octets[14], octets[15]))
+		},
+		ip => ip,
+	}
+}
+
+fn max_prefix(address: &IpAddr) -> u8 {
+	match *address {
+		IpAddr::V4(_) => 32,
+		IpAddr::V6(_) => 128,
+	}
+}
+
+fn prefix_matches(network: &[u8], ip: &[u8], prefix: u8) -> bool {
+	let bytes = prefix as usize / 8;
+	let bits = prefix % 8;
+	if network[..bytes] != ip[..bytes] {
+		return false;
+	}
+	if bits == 0 {
+		return true;
+	}
+	let mask = 0xffu8 << (8 - bits);
+	network[bytes] & mask == ip[bytes] & mask
+}
+
+impl fmt::Display for IpNetwork {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}/{}", self.address, self.prefix)
+	}
+}
+
+/// Error parsing an IP network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpNetworkParseError {
+	/// Invalid address.
+	Address(AddrParseError),
+	/// Prefix is not a number or is longer than the address.
+	Prefix(String),
+}
+
+impl fmt::Display for IpNetworkParseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			IpNetworkParseError::Address(ref err) => write!(f, "{}", err),
+			IpNetworkParseError::Prefix(ref prefix) => write!(f, "invalid network prefix length {}", prefix),
+		}
+	}
+}
+
+impl From<AddrParseError> for IpNetworkParseError {
+	fn from(err: AddrParseError) -> Self {
+		IpNetworkParseError::Address(err)
+	}
+}
+
+impl FromStr for IpNetwork {
+	type Err = IpNetworkParseError;
+
+	/// Parse `address/prefix`. A missing prefix means a single address.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = s.splitn(2, '/');
+		let address: IpAddr = parts.next().unwrap_or("").parse()?;
+		let prefix = match parts.next() {
+			None => max_prefix(&address),
+			Some(prefix) => prefix.parse().map_err(|_| IpNetworkParseError::Prefix(prefix.into()))?,
+		};
+		IpNetwork::new(address, prefix).ok_or_else(|| IpNetworkParseError::Prefix(prefix.to_string()))
+	}
+}
+
 #[cfg(not(windows))]
 mod getinterfaces {
 	use std::{mem, io, ptr};
@@ -279,3 +389,44 @@ fn ipv6_properties() {
 	check("::", true,  false, true);
 	check("::1", false, true, false);
 }
+
+#[test]
+fn ip_network_contains() {
+	let net: IpNetwork = "10.1.0.0/16".parse().unwrap();
+	assert!(net.contains(&"10.1.255.3".parse().unwrap()));
+	assert!(!net.contains(&"10.2.0.1".parse().unwrap()));
+	assert!(!net.contains(&"::1".parse().unwrap()));
+
+	let net: IpNetwork = "192.168.0.128/25".parse().unwrap();
+	assert!(net.contains(&"192.168.0.200".parse().unwrap()));
+	assert!(!net.contains(&"192.168.0.100".parse().unwrap()));
+
+	let net: IpNetwork = "fc00::/7".parse().unwrap();
+	assert!(net.contains(&"fd12::1".parse().unwrap()));
+	assert!(!net.contains(&"fe80::1".parse().unwrap()));
+
+	let single: IpNetwork = "22.99.55.44".parse().unwrap();
+	assert_eq!(format!("{}", single), "22.99.55.44/32");
+	assert!(single.contains(&"22.99.55.44".parse().unwrap()));
+	assert!("0.0.0.0/0".parse::<IpNetwork>().unwrap().contains(&"1.2.3.4".parse().unwrap()));
+
+	assert_eq!("10.0.0.0/33".parse::<IpNetwork>(), Err(IpNetworkParseError::Prefix("33".into())));
+	assert_eq!("10.0.0.0/x".parse::<IpNetwork>(), Err(IpNetworkParseError::Prefix("x".into())));
+	assert!(match "10.0.0/8".parse::<IpNetwork>() { Err(IpNetworkParseError::Address(_)) => true, _ => false });
+}
+
+#[test]
+fn ip_network_ipv4_mapped() {
+	let net: IpNetwork = "10.1.0.0/16".parse().unwrap();
+	assert!(net.contains(&"::ffff:10.1.2.3".parse().unwrap()));
+	assert!(!net.contains(&"::ffff:10.2.0.1".parse().unwrap()));
+	assert!(!net.contains(&"::10.1.2.3".parse().unwrap()));
+
+	let mapped: IpNetwork = "::ffff:192.168.0.0/112".parse().unwrap();
+	assert_eq!(mapped, "192.168.0.0/16".parse().unwrap());
+	assert!(mapped.contains(&"192.168.5.5".parse().unwrap()));
+	assert!(mapped.contains(&"::ffff:192.168.5.5".parse().unwrap()));
+
+	assert_eq!(to_canonical(&"::ffff:1.2.3.4".parse().unwrap()), "1.2.3.4".parse::<IpAddr>().unwrap());
+	assert_eq!(to_canonical(&"::1".parse().unwrap()), "::1".parse::<IpAddr>().unwrap());
+}
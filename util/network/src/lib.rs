@@ -90,18 +90,28 @@ mod error;
 mod node_table;
 mod stats;
 mod ip_utils;
+mod nat_pmp;
+mod socks;
+mod enr;
+mod discv5;
+mod bandwidth;
 
 #[cfg(test)]
 mod tests;
 
-pub use host::{PeerId, PacketId, ProtocolId, NetworkContext, NetworkIoMessage, NetworkConfiguration};
+pub use host::{PeerId, PacketId, ProtocolId, NetworkContext, NetworkIoMessage, NetworkConfiguration, PortMapping, PortMappingMethod};
+pub use enr::{NodeRecord, ForkId};
 pub use service::NetworkService;
 pub use error::NetworkError;
-pub use stats::NetworkStats;
+pub use stats::{NetworkStats, Traffic};
 pub use session::SessionInfo;
 
 use io::TimerToken;
-pub use node_table::{is_valid_node_url, NodeId};
+pub use node_table::{is_valid_node_url, is_valid_boot_node_url, NodeId};
+pub use ip_utils::{IpNetwork, IpNetworkParseError};
+
+use std::net::IpAddr;
+use ip_utils::{SocketAddrExt, to_canonical};
 
 const PROTOCOL_VERSION: u32 = 4;
 
@@ -150,5 +160,54 @@ pub enum AllowIP {
 	Private,
 	/// Connect to public network only
 	Public,
+	/// Connect to explicitly allowed networks only
+	None,
+}
+
+impl AllowIP {
+	fn allows(&self, ip: &IpAddr) -> bool {
+		match *self {
+			AllowIP::All => true,
+			AllowIP::Private => !ip.is_global_s(),
+			AllowIP::Public => ip.is_global_s(),
+			AllowIP::None => false,
+		}
+	}
+}
+
+/// IP filter applied to both inbound and outbound connections.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IpFilter {
+	/// Predefined class of allowed addresses
+	pub predefined: AllowIP,
+	/// Networks allowed in addition to the predefined class
+	pub custom_allow: Vec<IpNetwork>,
+	/// Networks that are never allowed
+	pub custom_block: Vec<IpNetwork>,
+}
+
+impl Default for IpFilter {
+	fn default() -> Self {
+		IpFilter {
+			predefined: AllowIP::All,
+			custom_allow: Vec::new(),
+			custom_block: Vec::new(),
+		}
+	}
+}
+
+impl IpFilter {
+	/// Check if the address passes the filter. Blocked networks take precedence.
+	/// IPv4-mapped IPv6 addresses are checked as IPv4.
+	pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+		let ip = to_canonical(ip);
+		!self.is_blocked(&ip) && (self.predefined.allows(&ip) || self.custom_allow.iter().any(|net| net.contains(&ip)))
+	}
+
+	/// Check if the address belongs to a blocked network.
+	pub fn is_blocked(&self, ip: &IpAddr) -> bool {
+		let ip = to_canonical(ip);
+		self.custom_block.iter().any(|net| net.contains(&ip))
+	}
 }
 
@@ -0,0 +1,395 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! NAT-PMP (RFC 6886) and PCP (RFC 6887) port mapping client.
+//! PCP is tried first; gateways reporting an unsupported version are then asked using NAT-PMP.
+
+use std::fmt;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::Duration;
+use rand::{self, Rng};
+
+const GATEWAY_PORT: u16 = 5351;
+const NAT_PMP_VERSION: u8 = 0;
+const PCP_VERSION: u8 = 2;
+const OPCODE_EXTERNAL_ADDRESS: u8 = 0;
+const OPCODE_MAP_UDP: u8 = 1;
+const OPCODE_MAP_TCP: u8 = 2;
+const PCP_OPCODE_MAP: u8 = 1;
+const RESPONSE_BIT: u8 = 0x80;
+const RESULT_SUCCESS: u8 = 0;
+const PCP_UNSUPP_VERSION: u8 = 1;
+const PCP_PACKET_SIZE: usize = 60;
+const NAT_PMP_MAP_RESPONSE_SIZE: usize = 16;
+const NAT_PMP_ADDRESS_RESPONSE_SIZE: usize = 12;
+// Initial request timeout, doubled for each retry.
+const REQUEST_TIMEOUT_MS: u64 = 250;
+const REQUEST_RETRIES: u32 = 4;
+
+/// Transport protocol of a port mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+	/// TCP port.
+	Tcp,
+	/// UDP port.
+	Udp,
+}
+
+/// Port mapping protocol spoken by the gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+	/// NAT Port Mapping Protocol.
+	NatPmp,
+	/// Port Control Protocol.
+	Pcp,
+}
+
+/// Port mapping created on the gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mapping {
+	/// External gateway address.
+	pub external_ip: Ipv4Addr,
+	/// External port assigned by the gateway.
+	pub external_port: u16,
+	/// Mapping lifetime in seconds.
+	pub lifetime: u32,
+}
+
+/// Port mapping error.
+#[derive(Debug)]
+pub enum Error {
+	/// Default gateway could not be determined.
+	NoGateway,
+	/// Socket error or no response from the gateway.
+	Io(io::Error),
+	/// Gateway responded with an error result code.
+	Rejected(u16),
+	/// Gateway response could not be decoded.
+	Malformed,
+	/// Gateway does not support the protocol version.
+	UnsupportedVersion,
+}
+
+impl From<io::Error> for Error {
+	fn from(err: io::Error) -> Self {
+		Error::Io(err)
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::NoGateway => write!(f, "Default gateway not found"),
+			Error::Io(ref err) => write!(f, "{}", err),
+			Error::Rejected(code) => write!(f, "Request rejected with result code {}", code),
+			Error::Malformed => write!(f, "Malformed gateway response"),
+			Error::UnsupportedVersion => write!(f, "Unsupported protocol version"),
+		}
+	}
+}
+
+/// NAT gateway supporting NAT-PMP or PCP.
+pub struct Gateway {
+	address: Ipv4Addr,
+	local: Ipv4Addr,
+	version: Version,
+	/// PCP mapping nonce, kept for renewals.
+	nonce: [u8; 12],
+}
+
+impl Gateway {
+	/// Find the default gateway for given local address.
+	pub fn find(local: Ipv4Addr) -> Result<Gateway, Error> {
+		let address = default_gateway().or_else(|| guess_gateway(local)).ok_or(Error::NoGateway)?;
+		let mut nonce = [0u8; 12];
+		rand::thread_rng().fill_bytes(&mut nonce);
+		Ok(Gateway {
+			address: address,
+			local: local,
+			version: Version::Pcp,
+			nonce: nonce,
+		})
+	}
+
+	/// Protocol version used to talk to the gateway.
+	pub fn version(&self) -> Version {
+		self.version
+	}
+
+	/// Create or renew a mapping of the local port.
+	pub fn map_port(&mut self, protocol: Protocol, internal_port: u16, external_port: u16, lifetime: u32) -> Result<Mapping, Error> {
+		if self.version == Version::Pcp {
+			match self.pcp_map(protocol, internal_port, external_port, lifetime) {
+				Err(Error::UnsupportedVersion) => {
+					debug!(target: "network", "Gateway {} does not support PCP, falling back to NAT-PMP", self.address);
+					self.version = Version::NatPmp;
+				},
+				result => return result,
+			}
+		}
+		self.nat_pmp_map(protocol, internal_port, external_port, lifetime)
+	}
+
+	fn pcp_map(&self, protocol: Protocol, internal_port: u16, external_port: u16, lifetime: u32) -> Result<Mapping, Error> {
+		let request = pcp_map_request(self.local, &self.nonce, protocol, internal_port, external_port, lifetime);
+		let mut response = [0u8; 1100];
+		let len = self.send(&request, &mut response)?;
+		parse_pcp_map_response(&response[..len], &self.nonce)
+	}
+
+	fn nat_pmp_map(&self, protocol: Protocol, internal_port: u16, external_port: u16, lifetime: u32) -> Result<Mapping, Error> {
+		let mut response = [0u8; 1100];
+		let len = self.send(&[NAT_PMP_VERSION, OPCODE_EXTERNAL_ADDRESS], &mut response)?;
+		let external_ip = parse_nat_pmp_address_response(&response[..len])?;
+
+		let request = nat_pmp_map_request(protocol, internal_port, external_port, lifetime);
+		let len = self.send(&request, &mut response)?;
+		let (external_port, lifetime) = parse_nat_pmp_map_response(&response[..len], protocol)?;
+		Ok(Mapping {
+			external_ip: external_ip,
+			external_port: external_port,
+			lifetime: lifetime,
+		})
+	}
+
+	/// Send a request and wait for the response, retrying with increasing timeouts.
+	fn send(&self, request: &[u8], response: &mut [u8]) -> Result<usize, Error> {
+		let socket = UdpSocket::bind(SocketAddrV4::new(self.local, 0))?;
+		let gateway = SocketAddr::V4(SocketAddrV4::new(self.address, GATEWAY_PORT));
+		let mut timeout = REQUEST_TIMEOUT_MS;
+		for _ in 0..REQUEST_RETRIES {
+			socket.send_to(request, gateway)?;
+			socket.set_read_timeout(Some(Duration::from_millis(timeout)))?;
+			match socket.recv_from(response) {
+				Ok((len, ref from)) if *from == gateway => return Ok(len),
+				Ok(_) => {},
+				Err(ref e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {},
+				Err(e) => return Err(e.into()),
+			}
+			timeout *= 2;
+		}
+		Err(io::Error::new(io::ErrorKind::TimedOut, "No response from gateway").into())
+	}
+}
+
+/// Read the default IPv4 gateway from the kernel routing table.
+#[cfg(target_os = "linux")]
+fn default_gateway() -> Option<Ipv4Addr> {
+	use std::fs::File;
+	use std::io::Read;
+
+	let mut routes = String::new();
+	match File::open("/proc/net/route").and_then(|mut f| f.read_to_string(&mut routes)) {
+		Ok(_) => parse_route_table(&routes),
+		Err(_) => None,
+	}
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_gateway() -> Option<Ipv4Addr> {
+	None
+}
+
+/// Parse default gateway from `/proc/net/route` contents. Addresses are printed in host byte order.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_route_table(routes: &str) -> Option<Ipv4Addr> {
+	routes.lines().skip(1)
+		.map(|line| line.split_whitespace().collect::<Vec<_>>())
+		.find(|fields| fields.len() > 2 && fields[1] == "00000000")
+		.and_then(|fields| u32::from_str_radix(fields[2], 16).ok())
+		.map(|gateway| Ipv4Addr::from(u32::from_be(gateway)))
+}
+
+/// Most home routers use the first address of the local subnet.
+fn guess_gateway(local: Ipv4Addr) -> Option<Ipv4Addr> {
+	if !local.is_private() {
+		return None;
+	}
+	let octets = local.octets();
+	Some(Ipv4Addr::new(octets[0], octets[1], octets[2], 1))
+}
+
+fn nat_pmp_map_request(protocol: Protocol, internal_port: u16, external_port: u16, lifetime: u32) -> [u8; 12] {
+	let mut request = [0u8; 12];
+	request[0] = NAT_PMP_VERSION;
+	request[1] = match protocol {
+		Protocol::Udp => OPCODE_MAP_UDP,
+		Protocol::Tcp => OPCODE_MAP_TCP,
+	};
+	write_u16(&mut request[4..6], internal_port);
+	write_u16(&mut request[6..8], external_port);
+	write_u32(&mut request[8..12], lifetime);
+	request
+}
+
+fn parse_nat_pmp_address_response(response: &[u8]) -> Result<Ipv4Addr, Error> {
+	check_nat_pmp_response(response, OPCODE_EXTERNAL_ADDRESS, NAT_PMP_ADDRESS_RESPONSE_SIZE)?;
+	Ok(Ipv4Addr::new(response[8], response[9], response[10], response[11]))
+}
+
+fn parse_nat_pmp_map_response(response: &[u8], protocol: Protocol) -> Result<(u16, u32), Error> {
+	let opcode = match protocol {
+		Protocol::Udp => OPCODE_MAP_UDP,
+		Protocol::Tcp => OPCODE_MAP_TCP,
+	};
+	check_nat_pmp_response(response, opcode, NAT_PMP_MAP_RESPONSE_SIZE)?;
+	Ok((read_u16(&response[10..12]), read_u32(&response[12..16])))
+}
+
+fn check_nat_pmp_response(response: &[u8], opcode: u8, size: usize) -> Result<(), Error> {
+	if response.len() < 4 || response[0] != NAT_PMP_VERSION || response[1] != RESPONSE_BIT | opcode {
+		return Err(Error::Malformed);
+	}
+	match read_u16(&response[2..4]) {
+		0 if response.len() >= size => Ok(()),
+		0 => Err(Error::Malformed),
+		code => Err(Error::Rejected(code)),
+	}
+}
+
+fn pcp_map_request(client: Ipv4Addr, nonce: &[u8; 12], protocol: Protocol, internal_port: u16, external_port: u16, lifetime: u32) -> [u8; PCP_PACKET_SIZE] {
+	let mut request = [0u8; PCP_PACKET_SIZE];
+	request[0] = PCP_VERSION;
+	request[1] = PCP_OPCODE_MAP;
+	write_u32(&mut request[4..8], lifetime);
+	write_ipv4_mapped(&mut request[8..24], client);
+	request[24..36].copy_from_slice(nonce);
+	request[36] = match protocol {
+		Protocol::Tcp => 6,
+		Protocol::Udp => 17,
+	};
+	write_u16(&mut request[40..42], internal_port);
+	write_u16(&mut request[42..44], external_port);
+	write_ipv4_mapped(&mut request[44..60], Ipv4Addr::new(0, 0, 0, 0));
+	request
+}
+
+fn parse_pcp_map_response(response: &[u8], nonce: &[u8; 12]) -> Result<Mapping, Error> {
+	// NAT-PMP only gateways respond with version 0 and unsupported version result code.
+	if response.len() >= 4 && response[0] == NAT_PMP_VERSION {
+		return Err(Error::UnsupportedVersion);
+	}
+	if response.len() < 4 || response[0] != PCP_VERSION || response[1] != RESPONSE_BIT | PCP_OPCODE_MAP {
+		return Err(Error::Malformed);
+	}
+	match response[3] {
+		RESULT_SUCCESS => {},
+		PCP_UNSUPP_VERSION => return Err(Error::UnsupportedVersion),
+		code => return Err(Error::Rejected(code as u16)),
+	}
+	if response.len() < PCP_PACKET_SIZE || &response[24..36] != nonce {
+		return Err(Error::Malformed);
+	}
+	Ok(Mapping {
+		external_ip: Ipv4Addr::new(response[56], response[57], response[58], response[59]),
+		external_port: read_u16(&response[42..44]),
+		lifetime: read_u32(&response[4..8]),
+	})
+}
+
+fn write_ipv4_mapped(buf: &mut [u8], ip: Ipv4Addr) {
+	buf[10] = 0xff;
+	buf[11] = 0xff;
+	buf[12..16].copy_from_slice(&ip.octets());
+}
+
+fn write_u16(buf: &mut [u8], value: u16) {
+	buf[0] = (value >> 8) as u8;
+	buf[1] = value as u8;
+}
+
+fn write_u32(buf: &mut [u8], value: u32) {
+	buf[0] = (value >> 24) as u8;
+	buf[1] = (value >> 16) as u8;
+	buf[2] = (value >> 8) as u8;
+	buf[3] = value as u8;
+}
+
+fn read_u16(buf: &[u8]) -> u16 {
+	(buf[0] as u16) << 8 | buf[1] as u16
+}
+
+fn read_u32(buf: &[u8]) -> u32 {
+	(buf[0] as u32) << 24 | (buf[1] as u32) << 16 | (buf[2] as u32) << 8 | buf[3] as u32
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::Ipv4Addr;
+	use super::*;
+
+	#[test]
+	fn should_encode_nat_pmp_map_request() {
+		let request = nat_pmp_map_request(Protocol::Tcp, 30303, 30304, 7200);
+		assert_eq!(request, [0, 2, 0, 0, 0x76, 0x5f, 0x76, 0x60, 0, 0, 0x1c, 0x20]);
+	}
+
+	#[test]
+	fn should_parse_nat_pmp_responses() {
+		let address = [0, 128, 0, 0, 0, 0, 0, 1, 203, 0, 113, 7];
+		assert_eq!(parse_nat_pmp_address_response(&address).unwrap(), Ipv4Addr::new(203, 0, 113, 7));
+
+		let map = [0, 130, 0, 0, 0, 0, 0, 1, 0x76, 0x5f, 0x76, 0x61, 0, 0, 0x0e, 0x10];
+		assert_eq!(parse_nat_pmp_map_response(&map, Protocol::Tcp).unwrap(), (30305, 3600));
+		assert!(parse_nat_pmp_map_response(&map, Protocol::Udp).is_err());
+
+		let rejected = [0, 130, 0, 3, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0];
+		match parse_nat_pmp_map_response(&rejected, Protocol::Tcp) {
+			Err(Error::Rejected(3)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_encode_and_parse_pcp_map() {
+		let nonce = [7u8; 12];
+		let request = pcp_map_request(Ipv4Addr::new(192, 168, 1, 10), &nonce, Protocol::Udp, 30303, 30303, 7200);
+		assert_eq!(request[0], 2);
+		assert_eq!(request[1], 1);
+		assert_eq!(&request[18..24], &[0xff, 0xff, 192, 168, 1, 10]);
+		assert_eq!(request[36], 17);
+
+		let mut response = request;
+		response[1] = 0x81;
+		response[2] = 0;
+		response[3] = 0;
+		response[42] = 0x76;
+		response[43] = 0x61;
+		response[56..60].copy_from_slice(&[203, 0, 113, 7]);
+		assert_eq!(parse_pcp_map_response(&response, &nonce).unwrap(), Mapping {
+			external_ip: Ipv4Addr::new(203, 0, 113, 7),
+			external_port: 30305,
+			lifetime: 7200,
+		});
+		assert!(parse_pcp_map_response(&response, &[0u8; 12]).is_err());
+
+		match parse_pcp_map_response(&[0, 129, 0, 1, 0, 0, 0, 0], &nonce) {
+			Err(Error::UnsupportedVersion) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_parse_default_gateway() {
+		let routes = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+			eth0\t0001A8C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0\n\
+			eth0\t00000000\t0101A8C0\t0003\t0\t0\t0\t00000000\t0\t0\t0\n";
+		assert_eq!(parse_route_table(routes), Some(Ipv4Addr::new(192, 168, 1, 1)));
+		assert_eq!(guess_gateway(Ipv4Addr::new(10, 0, 0, 23)), Some(Ipv4Addr::new(10, 0, 0, 1)));
+		assert_eq!(guess_gateway(Ipv4Addr::new(8, 8, 8, 8)), None);
+	}
+}
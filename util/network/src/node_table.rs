@@ -28,9 +28,9 @@ use std::io::{Read, Write};
 use util::hash::*;
 use util::UtilError;
 use rlp::*;
-use time::Tm;
+use time::{self, Tm};
 use error::NetworkError;
-use AllowIP;
+use IpFilter;
 use discovery::{TableUpdates, NodeEntry};
 use ip_utils::*;
 pub use rustc_serialize::json::Json;
@@ -38,6 +38,11 @@ pub use rustc_serialize::json::Json;
 /// Node public key
 pub type NodeId = H512;
 
+/// Time in seconds a node is considered recently useful after the last established session.
+const RECENTLY_USEFUL_SECS: u64 = 24 * 60 * 60;
+/// Time in seconds a useless node is not connected to.
+const USELESS_NODE_BAN_SECS: u64 = 5 * 60;
+
 #[derive(Debug, Clone)]
 /// Node address info
 pub struct NodeEndpoint {
@@ -55,12 +60,8 @@ impl NodeEndpoint {
 		}
 	}
 
-	pub fn is_allowed(&self, filter: AllowIP) -> bool {
-		match filter {
-			AllowIP::All => true,
-			AllowIP::Private => !self.address.ip().is_global_s(),
-			AllowIP::Public => self.address.ip().is_global_s(),
-		}
+	pub fn is_allowed(&self, filter: &IpFilter) -> bool {
+		filter.is_allowed(&self.address.ip())
 	}
 
 	pub fn from_rlp(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
@@ -135,7 +136,11 @@ pub struct Node {
 	pub endpoint: NodeEndpoint,
 	pub peer_type: PeerType,
 	pub failures: u32,
+	/// Number of established sessions.
+	pub successes: u32,
 	pub last_attempted: Option<Tm>,
+	/// Unix time of the last established session.
+	pub last_contact: Option<u64>,
 }
 
 impl Node {
@@ -145,9 +150,16 @@ impl Node {
 			endpoint: endpoint,
 			peer_type: PeerType::Optional,
 			failures: 0,
+			successes: 0,
 			last_attempted: None,
+			last_contact: None,
 		}
 	}
+
+	/// Returns true if a session with the node was established recently.
+	pub fn is_recently_useful(&self, now: u64) -> bool {
+		self.last_contact.map_or(false, |t| t + RECENTLY_USEFUL_SECS > now)
+	}
 }
 
 impl Display for Node {
@@ -176,7 +188,9 @@ impl FromStr for Node {
 			endpoint: endpoint,
 			peer_type: PeerType::Optional,
 			last_attempted: None,
+			last_contact: None,
 			failures: 0,
+			successes: 0,
 		})
 	}
 }
@@ -197,31 +211,45 @@ impl Hash for Node {
 /// Node table backed by disk file.
 pub struct NodeTable {
 	nodes: HashMap<NodeId, Node>,
-	useless_nodes: HashSet<NodeId>,
+	/// Useless nodes with the unix time they were marked at.
+	useless_nodes: HashMap<NodeId, u64>,
 	path: Option<String>,
 }
 
+fn unix_time() -> u64 {
+	time::get_time().sec as u64
+}
+
 impl NodeTable {
 	pub fn new(path: Option<String>) -> NodeTable {
-		NodeTable {
-			path: path.clone(),
-			nodes: NodeTable::load(path),
-			useless_nodes: HashSet::new(),
-		}
+		let (nodes, useless_nodes) = NodeTable::load(path.clone());
+		let mut table = NodeTable {
+			path: path,
+			nodes: nodes,
+			useless_nodes: useless_nodes,
+		};
+		table.clear_useless();
+		table
 	}
 
 	/// Add a node to table
 	pub fn add_node(&mut self, mut node: Node) {
-		// preserve failure counter
-		let failures = self.nodes.get(&node.id).map_or(0, |n| n.failures);
-		node.failures = failures;
+		// preserve reputation
+		if let Some(n) = self.nodes.get(&node.id) {
+			node.failures = n.failures;
+			node.successes = n.successes;
+			node.last_contact = n.last_contact;
+		}
 		self.nodes.insert(node.id.clone(), node);
 	}
 
-	/// Returns node ids sorted by number of failures
-	pub fn nodes(&self, filter: AllowIP) -> Vec<NodeId> {
-		let mut refs: Vec<&Node> = self.nodes.values().filter(|n| !self.useless_nodes.contains(&n.id) && n.endpoint.is_allowed(filter)).collect();
-		refs.sort_by(|a, b| a.failures.cmp(&b.failures));
+	/// Returns node ids, recently useful nodes first, then sorted by number of failures
+	pub fn nodes(&self, filter: &IpFilter) -> Vec<NodeId> {
+		let now = unix_time();
+		let mut refs: Vec<&Node> = self.nodes.values().filter(|n| !self.useless_nodes.contains_key(&n.id) && n.endpoint.is_allowed(filter)).collect();
+		refs.sort_by(|a, b| b.is_recently_useful(now).cmp(&a.is_recently_useful(now))
+			.then_with(|| a.failures.cmp(&b.failures))
+			.then_with(|| b.last_contact.cmp(&a.last_contact)));
 		refs.iter().map(|n| n.id.clone()).collect()
 	}
 
@@ -256,14 +284,24 @@ impl NodeTable {
 		}
 	}
 
-	/// Mark as useless, no furter attempts to connect until next call to `clear_useless`.
+	/// Note an established session. Resets the failure counter.
+	pub fn note_success(&mut self, id: &NodeId) {
+		if let Some(node) = self.nodes.get_mut(id) {
+			node.successes += 1;
+			node.failures = 0;
+			node.last_contact = Some(unix_time());
+		}
+	}
+
+	/// Mark as useless, no furter attempts to connect until the ban expires in `clear_useless`.
 	pub fn mark_as_useless(&mut self, id: &NodeId) {
-		self.useless_nodes.insert(id.clone());
+		self.useless_nodes.insert(id.clone(), unix_time());
 	}
 
-	/// Atempt to connect to useless nodes again.
+	/// Atempt to connect to useless nodes again once their ban expires.
 	pub fn clear_useless(&mut self) {
-		self.useless_nodes.clear();
+		let now = unix_time();
+		self.useless_nodes.retain(|_, marked| *marked + USELESS_NODE_BAN_SECS > now);
 	}
 
 	/// Save the nodes.json file.
@@ -278,10 +316,19 @@ impl NodeTable {
 			let mut json = String::new();
 			json.push_str("{\n");
 			json.push_str("\"nodes\": [\n");
-			let node_ids = self.nodes(AllowIP::All);
+			let mut node_ids = self.nodes(&IpFilter::default());
+			// useless nodes are saved as well, so that the ban survives a restart
+			node_ids.extend(self.useless_nodes.keys().filter(|id| self.nodes.contains_key(id)).cloned());
 			for i in 0 .. node_ids.len() {
-				let node = self.nodes.get(&node_ids[i]).expect("self.nodes() only returns node IDs from self.nodes");
-				json.push_str(&format!("\t{{ \"url\": \"{}\", \"failures\": {} }}{}\n", node, node.failures, if i == node_ids.len() - 1 {""} else {","}))
+				let node = self.nodes.get(&node_ids[i]).expect("node_ids only contains node IDs from self.nodes");
+				let mut entry = format!("\t{{ \"url\": \"{}\", \"failures\": {}, \"successes\": {}", node, node.failures, node.successes);
+				if let Some(last_contact) = node.last_contact {
+					entry.push_str(&format!(", \"last_contact\": {}", last_contact));
+				}
+				if let Some(marked) = self.useless_nodes.get(&node.id) {
+					entry.push_str(&format!(", \"useless_since\": {}", marked));
+				}
+				json.push_str(&format!("{} }}{}\n", entry, if i == node_ids.len() - 1 {""} else {","}));
 			}
 			json.push_str("]\n");
 			json.push_str("}");
@@ -298,8 +345,9 @@ impl NodeTable {
 		}
 	}
 
-	fn load(path: Option<String>) -> HashMap<NodeId, Node> {
+	fn load(path: Option<String>) -> (HashMap<NodeId, Node>, HashMap<NodeId, u64>) {
 		let mut nodes: HashMap<NodeId, Node> = HashMap::new();
+		let mut useless_nodes: HashMap<NodeId, u64> = HashMap::new();
 		if let Some(path) = path {
 			let mut path_buf = PathBuf::from(path);
 			path_buf.push("nodes.json");
@@ -307,7 +355,7 @@ impl NodeTable {
 				Ok(file) => file,
 				Err(e) => {
 					debug!("Error opening node table file: {:?}", e);
-					return nodes;
+					return (nodes, useless_nodes);
 				}
 			};
 			let mut buf = String::new();
@@ -315,14 +363,14 @@ impl NodeTable {
 				Ok(_) => {},
 				Err(e) => {
 					warn!("Error reading node table file: {:?}", e);
-					return nodes;
+					return (nodes, useless_nodes);
 				}
 			}
 			let json = match Json::from_str(&buf) {
 				Ok(json) => json,
 				Err(e) => {
 					warn!("Error parsing node table file: {:?}", e);
-					return nodes;
+					return (nodes, useless_nodes);
 				}
 			};
 			if let Some(list) = json.as_object().and_then(|o| o.get("nodes")).and_then(|n| n.as_array()) {
@@ -332,13 +380,20 @@ impl NodeTable {
 							if let Some(failures) = n.get("failures").and_then(|f| f.as_u64()) {
 								node.failures = failures as u32;
 							}
+							if let Some(successes) = n.get("successes").and_then(|f| f.as_u64()) {
+								node.successes = successes as u32;
+							}
+							node.last_contact = n.get("last_contact").and_then(|t| t.as_u64());
+							if let Some(marked) = n.get("useless_since").and_then(|t| t.as_u64()) {
+								useless_nodes.insert(node.id.clone(), marked);
+							}
 							nodes.insert(node.id.clone(), node);
 						}
 					}
 				}
 			}
 		}
-		nodes
+		(nodes, useless_nodes)
 	}
 }
 
@@ -354,6 +409,15 @@ pub fn is_valid_node_url(url: &str) -> bool {
 	Node::from_str(url).is_ok()
 }
 
+/// Check if node url is valid for a boot node: an enode URL or a node record (`enr:`) with an endpoint.
+pub fn is_valid_boot_node_url(url: &str) -> bool {
+	use std::str::FromStr;
+	match url.starts_with("enr:") {
+		true => ::enr::NodeRecord::from_str(url).ok().and_then(|r| r.endpoint()).is_some(),
+		false => is_valid_node_url(url),
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -361,7 +425,7 @@ mod tests {
 	use util::H512;
 	use std::str::FromStr;
 	use devtools::*;
-	use AllowIP;
+	use {IpFilter, AllowIP};
 
 	#[test]
 	fn endpoint_parse() {
@@ -374,6 +438,32 @@ mod tests {
 		assert_eq!(SocketAddrV4::new(Ipv4Addr::new(123, 99, 55, 44), 7770), v4);
 	}
 
+	#[test]
+	fn endpoint_ip_filter() {
+		let public = NodeEndpoint::from_str("123.99.55.44:7770").unwrap();
+		let private = NodeEndpoint::from_str("10.0.0.1:7770").unwrap();
+		let other_private = NodeEndpoint::from_str("192.168.1.1:7770").unwrap();
+
+		assert!(public.is_allowed(&IpFilter::default()));
+		let filter = IpFilter {
+			predefined: AllowIP::None,
+			custom_allow: vec!["10.0.0.0/8".parse().unwrap()],
+			custom_block: Vec::new(),
+		};
+		assert!(!public.is_allowed(&filter));
+		assert!(private.is_allowed(&filter));
+		assert!(!other_private.is_allowed(&filter));
+
+		let filter = IpFilter {
+			predefined: AllowIP::Private,
+			custom_allow: vec!["123.99.0.0/16".parse().unwrap()],
+			custom_block: vec!["10.0.0.1".parse().unwrap()],
+		};
+		assert!(public.is_allowed(&filter));
+		assert!(!private.is_allowed(&filter));
+		assert!(other_private.is_allowed(&filter));
+	}
+
 	#[test]
 	fn node_parse() {
 		assert!(is_valid_node_url("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770"));
@@ -407,7 +497,7 @@ mod tests {
 		table.note_failure(&id1);
 		table.note_failure(&id2);
 
-		let r = table.nodes(AllowIP::All);
+		let r = table.nodes(&IpFilter::default());
 		assert_eq!(r[0][..], id3[..]);
 		assert_eq!(r[1][..], id2[..]);
 		assert_eq!(r[2][..], id1[..]);
@@ -429,9 +519,41 @@ mod tests {
 
 		{
 			let table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
-			let r = table.nodes(AllowIP::All);
+			let r = table.nodes(&IpFilter::default());
 			assert_eq!(r[0][..], id1[..]);
 			assert_eq!(r[1][..], id2[..]);
 		}
 	}
+
+	#[test]
+	fn table_reputation_save_load() {
+		let temp_path = RandomTempPath::create_dir();
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node3 = Node::from_str("enode://c979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = node1.id.clone();
+		let id2 = node2.id.clone();
+		let id3 = node3.id.clone();
+		{
+			let mut table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			table.add_node(node1);
+			table.add_node(node2);
+			table.add_node(node3);
+			table.note_failure(&id2);
+			table.note_success(&id2);
+			table.note_failure(&id2);
+			table.mark_as_useless(&id3);
+			assert_eq!(table.nodes(&IpFilter::default()), vec![id2.clone(), id1.clone()]);
+		}
+
+		{
+			let mut table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			// recently useful node comes first, useless node is still banned
+			assert_eq!(table.nodes(&IpFilter::default()), vec![id2.clone(), id1.clone()]);
+			let node = table.get_mut(&id2).unwrap();
+			assert_eq!(node.failures, 1);
+			assert_eq!(node.successes, 1);
+			assert!(node.last_contact.is_some());
+		}
+	}
 }
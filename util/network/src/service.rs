@@ -16,8 +16,9 @@
 
 use {NetworkProtocolHandler, NetworkConfiguration, NonReservedPeerMode};
 use error::NetworkError;
-use host::{Host, NetworkContext, NetworkIoMessage, PeerId, ProtocolId};
+use host::{Host, NetworkContext, NetworkIoMessage, PeerId, ProtocolId, PortMapping};
 use stats::NetworkStats;
+use enr::ForkId;
 use io::*;
 use parking_lot::RwLock;
 use std::sync::Arc;
@@ -59,7 +60,7 @@ impl NetworkService {
 		let io_service = IoService::<NetworkIoMessage>::start()?;
 		panic_handler.forward_from(&io_service);
 
-		let stats = Arc::new(NetworkStats::new());
+		let stats = Arc::new(NetworkStats::with_limits(config.max_upload, config.max_download));
 		let host_info = Host::client_version();
 		Ok(NetworkService {
 			io_service: io_service,
@@ -115,6 +116,12 @@ impl NetworkService {
 		host.as_ref().map(|h| h.local_url())
 	}
 
+	/// Returns the port mapping created on the NAT gateway, if any.
+	pub fn port_mapping(&self) -> Option<PortMapping> {
+		let host = self.host.read();
+		host.as_ref().and_then(|h| h.port_mapping())
+	}
+
 	/// Start network IO
 	pub fn start(&self) -> Result<(), NetworkError> {
 		let mut host = self.host.write();
@@ -176,6 +183,14 @@ impl NetworkService {
 		}
 	}
 
+	/// Set the fork identifier advertised in the node record.
+	pub fn set_fork_id(&self, fork_id: ForkId) {
+		let host = self.host.read();
+		if let Some(ref host) = *host {
+			host.set_fork_id(fork_id);
+		}
+	}
+
 	/// Executes action in the network context
 	pub fn with_context<F>(&self, protocol: ProtocolId, action: F) where F: FnOnce(&NetworkContext) {
 		let io = IoContext::new(self.io_service.channel(), 0);
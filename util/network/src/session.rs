@@ -31,7 +31,7 @@ use io::{IoContext, StreamToken};
 use error::{NetworkError, DisconnectReason};
 use host::*;
 use node_table::NodeId;
-use stats::NetworkStats;
+use stats::{NetworkStats, Traffic};
 use time;
 
 // Timeout must be less than (interval - 1).
@@ -61,6 +61,7 @@ pub struct Session {
 	state: State,
 	// Protocol states -- accumulates pending packets until signaled as ready.
 	protocol_states: HashMap<ProtocolId, ProtocolState>,
+	stats: Arc<NetworkStats>,
 }
 
 enum State {
@@ -107,6 +108,8 @@ pub struct SessionInfo {
 	pub remote_address: String,
 	/// Local endpoint address of the session
 	pub local_address: String,
+	/// Packet bytes transferred over the session
+	pub traffic: Traffic,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -177,7 +180,7 @@ impl Session {
 		nonce: &H256, stats: Arc<NetworkStats>, host: &HostInfo) -> Result<Session, NetworkError>
 		where Message: Send + Clone + Sync + 'static {
 		let originated = id.is_some();
-		let mut handshake = Handshake::new(token, id, socket, nonce, stats).expect("Can't create handshake");
+		let mut handshake = Handshake::new(token, id, socket, nonce, stats.clone()).expect("Can't create handshake");
 		let local_addr = handshake.connection.local_addr_str();
 		handshake.start(io, host, originated)?;
 		Ok(Session {
@@ -193,11 +196,13 @@ impl Session {
 				originated: originated,
 				remote_address: "Handshake".to_owned(),
 				local_address: local_addr,
+				traffic: Traffic::default(),
 			},
 			ping_time_ns: 0,
 			pong_time_ns: None,
 			expired: false,
 			protocol_states: HashMap::new(),
+			stats: stats,
 		})
 	}
 
@@ -277,6 +282,7 @@ impl Session {
 			}
 		}
 		if let Some(data) = packet_data {
+			self.info.traffic.recv += data.data.len() as u64;
 			return Ok(self.read_packet(io, data, host)?);
 		}
 		if create_session {
@@ -344,6 +350,7 @@ impl Session {
 			}
 		}
 		let pid = self.info.capabilities[i].id_offset + packet_id;
+		self.stats.inc_protocol_send(protocol, data.len());
 		let mut rlp = RlpStream::new();
 		rlp.append(&(pid as u32));
 		rlp.append_raw(data, 1);
@@ -435,6 +442,7 @@ impl Session {
 				// map to protocol
 				let protocol = self.info.capabilities[i].protocol;
 				let protocol_packet_id = packet_id - self.info.capabilities[i].id_offset;
+				self.stats.inc_protocol_recv(protocol, packet.data.len() - 1);
 
 				match *self.protocol_states.entry(protocol).or_insert_with(|| ProtocolState::Pending(Vec::new())) {
 					ProtocolState::Connected => {
@@ -564,7 +572,9 @@ impl Session {
 				warn!(target:"network", "Unexpected send request");
 			},
 			State::Session(ref mut s) => {
-				s.send_packet(io, &rlp.out())?
+				let data = rlp.out();
+				s.send_packet(io, &data)?;
+				self.info.traffic.send += data.len() as u64;
 			},
 		}
 		Ok(())
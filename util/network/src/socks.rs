@@ -0,0 +1,153 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! SOCKS5 (RFC 1928) client for outbound connections. Only unauthenticated `CONNECT` is supported.
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+const SOCKS_VERSION: u8 = 5;
+const METHOD_NO_AUTH: u8 = 0;
+const COMMAND_CONNECT: u8 = 1;
+const ADDRESS_IPV4: u8 = 1;
+const ADDRESS_DOMAIN: u8 = 3;
+const ADDRESS_IPV6: u8 = 4;
+const REPLY_SUCCEEDED: u8 = 0;
+
+fn error(msg: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, msg)
+}
+
+/// Connect to `target` through the SOCKS5 proxy. Returns the established (blocking) stream.
+pub fn connect(proxy: &SocketAddr, target: &SocketAddr, timeout: Duration) -> io::Result<TcpStream> {
+	let mut stream = TcpStream::connect(proxy)?;
+	stream.set_read_timeout(Some(timeout))?;
+	stream.set_write_timeout(Some(timeout))?;
+	handshake(&mut stream, target)?;
+	stream.set_read_timeout(None)?;
+	stream.set_write_timeout(None)?;
+	Ok(stream)
+}
+
+/// Negotiate a `CONNECT` to `target` over an established proxy connection.
+fn handshake<S: Read + Write>(stream: &mut S, target: &SocketAddr) -> io::Result<()> {
+	stream.write_all(&[SOCKS_VERSION, 1, METHOD_NO_AUTH])?;
+	let mut method = [0u8; 2];
+	stream.read_exact(&mut method)?;
+	if method[0] != SOCKS_VERSION {
+		return Err(error("Invalid SOCKS version"));
+	}
+	if method[1] != METHOD_NO_AUTH {
+		return Err(error("SOCKS proxy requires authentication"));
+	}
+
+	stream.write_all(&connect_request(target))?;
+	let mut reply = [0u8; 4];
+	stream.read_exact(&mut reply)?;
+	if reply[0] != SOCKS_VERSION {
+		return Err(error("Invalid SOCKS version"));
+	}
+	if reply[1] != REPLY_SUCCEEDED {
+		return Err(io::Error::new(io::ErrorKind::ConnectionRefused, format!("SOCKS proxy connect failed with reply {}", reply[1])));
+	}
+	// Skip the bound address, it is of no use for us.
+	let address_len = match reply[3] {
+		ADDRESS_IPV4 => 4,
+		ADDRESS_IPV6 => 16,
+		ADDRESS_DOMAIN => {
+			let mut len = [0u8; 1];
+			stream.read_exact(&mut len)?;
+			len[0] as usize
+		},
+		_ => return Err(error("Invalid SOCKS address type")),
+	};
+	let mut bound = vec![0u8; address_len + 2];
+	stream.read_exact(&mut bound)?;
+	Ok(())
+}
+
+fn connect_request(target: &SocketAddr) -> Vec<u8> {
+	let mut request = vec![SOCKS_VERSION, COMMAND_CONNECT, 0];
+	match *target {
+		SocketAddr::V4(ref addr) => {
+			request.push(ADDRESS_IPV4);
+			request.extend_from_slice(&addr.ip().octets());
+		},
+		SocketAddr::V6(ref addr) => {
+			request.push(ADDRESS_IPV6);
+			request.extend_from_slice(&addr.ip().octets());
+		},
+	}
+	request.push((target.port() >> 8) as u8);
+	request.push(target.port() as u8);
+	request
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::{self, Read, Write, Cursor};
+	use super::handshake;
+
+	/// Replays the proxy side of a connection and records what was written.
+	struct MockProxy {
+		input: Cursor<Vec<u8>>,
+		output: Vec<u8>,
+	}
+
+	impl Read for MockProxy {
+		fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+			self.input.read(buf)
+		}
+	}
+
+	impl Write for MockProxy {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.output.write(buf)
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn should_connect_through_proxy() {
+		let mut proxy = MockProxy {
+			input: Cursor::new(vec![5, 0, 5, 0, 0, 1, 127, 0, 0, 1, 0x23, 0x82]),
+			output: Vec::new(),
+		};
+		handshake(&mut proxy, &"22.99.55.44:30303".parse().unwrap()).unwrap();
+		assert_eq!(proxy.output, vec![5, 1, 0, 5, 1, 0, 1, 22, 99, 55, 44, 0x76, 0x5f]);
+	}
+
+	#[test]
+	fn should_fail_on_rejected_connect() {
+		let mut auth_required = MockProxy {
+			input: Cursor::new(vec![5, 0xff]),
+			output: Vec::new(),
+		};
+		assert!(handshake(&mut auth_required, &"22.99.55.44:30303".parse().unwrap()).is_err());
+
+		let mut refused = MockProxy {
+			input: Cursor::new(vec![5, 0, 5, 5, 0, 1, 0, 0, 0, 0, 0, 0]),
+			output: Vec::new(),
+		};
+		let err = handshake(&mut refused, &"[::1]:30303".parse().unwrap()).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+		assert_eq!(refused.output[6], 4);
+	}
+}
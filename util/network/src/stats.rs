@@ -15,7 +15,20 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Network Statistics
+use std::collections::HashMap;
 use std::sync::atomic::*;
+use parking_lot::RwLock;
+use bandwidth::TokenBucket;
+use host::ProtocolId;
+
+/// Bytes received and sent.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Traffic {
+	/// Bytes received
+	pub recv: u64,
+	/// Bytes sent
+	pub send: u64,
+}
 
 /// Network statistics structure
 #[derive(Default, Debug)]
@@ -26,6 +39,14 @@ pub struct NetworkStats {
 	send: AtomicUsize,
 	/// Total number of sessions created
 	sessions: AtomicUsize,
+	/// Number of sessions dropped by the per-address peer limits
+	ip_limited: AtomicUsize,
+	/// Upload rate limit
+	send_limit: Option<TokenBucket>,
+	/// Download rate limit
+	recv_limit: Option<TokenBucket>,
+	/// Packet payload bytes per subprotocol
+	protocols: RwLock<HashMap<ProtocolId, Traffic>>,
 }
 
 impl NetworkStats {
@@ -33,12 +54,48 @@ impl NetworkStats {
 	#[inline]
 	pub fn inc_recv(&self, size: usize) {
 		self.recv.fetch_add(size, Ordering::Relaxed);
+		if let Some(ref limit) = self.recv_limit {
+			limit.consume(size);
+		}
 	}
 
 	/// Increase bytes sent.
 	#[inline]
 	pub fn inc_send(&self, size: usize) {
 		self.send.fetch_add(size, Ordering::Relaxed);
+		if let Some(ref limit) = self.send_limit {
+			limit.consume(size);
+		}
+	}
+
+	/// Increase packet payload bytes received for the subprotocol.
+	pub fn inc_protocol_recv(&self, protocol: ProtocolId, size: usize) {
+		self.protocols.write().entry(protocol).or_insert_with(Traffic::default).recv += size as u64;
+	}
+
+	/// Increase packet payload bytes sent for the subprotocol.
+	pub fn inc_protocol_send(&self, protocol: ProtocolId, size: usize) {
+		self.protocols.write().entry(protocol).or_insert_with(Traffic::default).send += size as u64;
+	}
+
+	/// Get packet payload bytes transferred per subprotocol.
+	pub fn protocols(&self) -> HashMap<ProtocolId, Traffic> {
+		self.protocols.read().clone()
+	}
+
+	/// Number of bytes that may be received now under the download limit.
+	pub fn recv_allowance(&self) -> usize {
+		self.recv_limit.as_ref().map_or(usize::max_value(), TokenBucket::available)
+	}
+
+	/// Number of bytes that may be sent now under the upload limit.
+	pub fn send_allowance(&self) -> usize {
+		self.send_limit.as_ref().map_or(usize::max_value(), TokenBucket::available)
+	}
+
+	/// Check if any bandwidth limit is set.
+	pub fn is_limited(&self) -> bool {
+		self.send_limit.is_some() || self.recv_limit.is_some()
 	}
 
 	/// Increase number of sessions.
@@ -47,6 +104,12 @@ impl NetworkStats {
 		self.sessions.fetch_add(1, Ordering::Relaxed);
 	}
 
+	/// Increase number of sessions dropped by the per-address peer limits.
+	#[inline]
+	pub fn inc_ip_limited(&self) {
+		self.ip_limited.fetch_add(1, Ordering::Relaxed);
+	}
+
 	/// Get bytes sent.
 	#[inline]
 	pub fn send(&self) -> usize {
@@ -65,12 +128,27 @@ impl NetworkStats {
 		self.sessions.load(Ordering::Relaxed)
 	}
 
+	/// Get number of sessions dropped by the per-address peer limits.
+	#[inline]
+	pub fn ip_limited(&self) -> usize {
+		self.ip_limited.load(Ordering::Relaxed)
+	}
+
 	/// Create a new empty instance.
 	pub fn new() -> NetworkStats {
+		NetworkStats::with_limits(None, None)
+	}
+
+	/// Create a new empty instance with upload and download limits in bytes per second.
+	pub fn with_limits(send_limit: Option<u64>, recv_limit: Option<u64>) -> NetworkStats {
 		NetworkStats {
 			recv: AtomicUsize::new(0),
 			send: AtomicUsize::new(0),
 			sessions: AtomicUsize::new(0),
+			ip_limited: AtomicUsize::new(0),
+			send_limit: send_limit.map(TokenBucket::new),
+			recv_limit: recv_limit.map(TokenBucket::new),
+			protocols: RwLock::new(HashMap::new()),
 		}
 	}
 }
@@ -159,3 +159,19 @@ fn net_timeout() {
 		thread::sleep(Duration::from_millis(50));
 	}
 }
+
+#[test]
+fn ip_filter_checks_ipv4_mapped_addresses() {
+	let filter = IpFilter {
+		predefined: AllowIP::Private,
+		custom_allow: vec!["203.0.113.0/24".parse().unwrap()],
+		custom_block: vec!["10.1.0.0/16".parse().unwrap()],
+	};
+	let allowed = |ip: &str| filter.is_allowed(&ip.parse().unwrap());
+
+	assert!(allowed("::ffff:10.0.0.1"));
+	assert!(allowed("::ffff:203.0.113.7"));
+	assert!(!allowed("::ffff:8.8.8.8"));
+	assert!(!allowed("::ffff:10.1.0.1"));
+	assert!(filter.is_blocked(&"::ffff:10.1.0.1".parse().unwrap()));
+}
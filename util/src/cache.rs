@@ -19,7 +19,7 @@
 // TODO: push changes upstream in a clean way.
 
 use heapsize::HeapSizeOf;
-use lru_cache::LruCache;
+use lru_cache::{self, LruCache};
 
 use std::hash::Hash;
 
@@ -72,6 +72,11 @@ impl<K: Eq + Hash, V: HeapSizeOf> MemoryLruCache<K, V> {
 		self.inner.get_mut(key)
 	}
 
+	/// Iterate over all items, from least to most recently used.
+	pub fn iter(&self) -> lru_cache::Iter<K, V> {
+		self.inner.iter()
+	}
+
 	/// Currently-used size of values in bytes.
 	pub fn current_size(&self) -> usize {
 		self.cur_size
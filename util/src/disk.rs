@@ -0,0 +1,56 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Disk space utilities.
+
+use std::io;
+use std::path::Path;
+
+/// Space in bytes available to unprivileged users on the volume containing the path.
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> io::Result<u64> {
+	use std::ffi::CString;
+	use std::mem;
+	use std::os::unix::ffi::OsStrExt;
+	use libc;
+
+	let path = CString::new(path.as_os_str().as_bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+	unsafe {
+		let mut stat: libc::statvfs = mem::zeroed();
+		if libc::statvfs(path.as_ptr(), &mut stat) != 0 {
+			return Err(io::Error::last_os_error());
+		}
+		Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+	}
+}
+
+/// Space in bytes available to unprivileged users on the volume containing the path.
+#[cfg(not(unix))]
+pub fn available_space(_path: &Path) -> io::Result<u64> {
+	Err(io::Error::new(io::ErrorKind::Other, "Available disk space can't be determined on this platform"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	use std::env;
+	use super::available_space;
+
+	#[test]
+	fn should_return_available_space() {
+		assert!(available_space(&env::temp_dir()).is_ok());
+		assert!(available_space(&env::temp_dir().join("non-existent-dir")).is_err());
+	}
+}
@@ -119,6 +119,7 @@ pub mod bloom;
 pub mod standard;
 #[macro_use]
 pub mod common;
+pub mod disk;
 pub mod error;
 pub mod bytes;
 pub mod misc;
@@ -60,6 +60,14 @@ impl<T: Ord> Corpus<T> {
 	pub fn len(&self) -> usize {
 		self.0.len()
 	}
+
+	/// Get the element below which `percent` percent of the elements fall, if it exists.
+	pub fn percentile(&self, percent: usize) -> Option<&T> {
+		match self.0.len() {
+			0 => None,
+			len => self.0.get(::std::cmp::min(len - 1, len * percent / 100)),
+		}
+	}
 }
 
 impl<T: Ord + Copy + ::std::fmt::Display> Corpus<T>
@@ -121,7 +129,16 @@ impl<T: Ord + Copy + ::std::fmt::Display> Histogram<T>
 
 #[cfg(test)]
 mod tests {
-	use super::Histogram;
+	use super::{Corpus, Histogram};
+
+	#[test]
+	fn check_percentile() {
+		let corpus: Corpus<u64> = (1..101).rev().collect();
+		assert_eq!(corpus.percentile(50), Some(&51));
+		assert_eq!(corpus.percentile(99), Some(&100));
+		assert_eq!(corpus.percentile(100), Some(&100));
+		assert_eq!(Corpus::<u64>::from(vec![]).percentile(50), None);
+	}
 
 	#[test]
 	fn check_histogram() {